@@ -0,0 +1,8 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // gRPC messages mirror the REST API ones, so they are converted through their JSON form
+    tonic_build::configure()
+        .type_attribute(".", "#[derive(serde::Deserialize, serde::Serialize)]")
+        .type_attribute(".", "#[serde(default)]")
+        .compile_protos(&["proto/rln.proto"], &["proto"])?;
+    Ok(())
+}
@@ -0,0 +1,354 @@
+use amplify::s;
+use bitcoin::secp256k1::{PublicKey, Secp256k1, SecretKey};
+use bitcoin::Transaction;
+use hex::DisplayHex;
+use lightning::chain::chaininterface::{
+    BroadcasterInterface, ConfirmationTarget, FeeEstimator, FEERATE_FLOOR_SATS_PER_KW,
+};
+use lightning::rgb_utils::{
+    BITCOIN_NETWORK_FNAME, WALLET_ACCOUNT_XPUB_COLORED_FNAME, WALLET_ACCOUNT_XPUB_VANILLA_FNAME,
+    WALLET_MASTER_FINGERPRINT_FNAME,
+};
+use lightning::sign::KeysManager;
+use lightning::util::logger::{Logger, Record};
+use lightning::util::persist::{
+    KVStoreSync, MonitorUpdatingPersister, CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+    CHANNEL_MONITOR_PERSISTENCE_SECONDARY_NAMESPACE,
+};
+use rand::RngCore;
+use rgb_lib::{
+    wallet::{DatabaseType, Wallet as RgbLibWallet, WalletData},
+    AssetSchema, BitcoinNetwork, ConsignmentExt, ContractId, RgbTransfer,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::error::AppError;
+use crate::rgb::get_rgb_channel_info_optional;
+use crate::store::{stored_backend, NodeStore};
+use crate::utils::{get_current_timestamp, get_mnemonic_path, LDK_DIR};
+
+const AUDIT_MESSAGE_HEADER: &str = "rgb-lightning-node audit summary";
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AuditAsset {
+    pub(crate) asset_id: String,
+    pub(crate) settled: u64,
+    pub(crate) future: u64,
+    pub(crate) offchain_outbound: u64,
+    pub(crate) offchain_inbound: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AuditChannel {
+    pub(crate) channel_id: String,
+    pub(crate) funding_txid: String,
+    pub(crate) peer_pubkey: String,
+    pub(crate) latest_update_id: u64,
+    pub(crate) claimable_balance_sat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_local_amount: Option<u64>,
+    pub(crate) asset_remote_amount: Option<u64>,
+    pub(crate) consignment_verified: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AuditReport {
+    pub(crate) summary: AuditSummary,
+    pub(crate) auditor_pubkey: String,
+    pub(crate) message: String,
+    pub(crate) signature: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AuditSummary {
+    pub(crate) network: String,
+    pub(crate) master_fingerprint: String,
+    pub(crate) timestamp: u64,
+    pub(crate) onchain_btc_settled_sat: u64,
+    pub(crate) onchain_btc_future_sat: u64,
+    pub(crate) offchain_btc_claimable_sat: u64,
+    pub(crate) assets: Vec<AuditAsset>,
+    pub(crate) channels: Vec<AuditChannel>,
+    pub(crate) anomalies: Vec<String>,
+}
+
+/// Chain interface handed to the monitor persister, which only needs it to replay the pending
+/// monitor updates in memory. An audit never broadcasts anything.
+struct AuditChainInterface;
+
+impl BroadcasterInterface for AuditChainInterface {
+    fn broadcast_transactions(&self, txs: &[&Transaction]) {
+        tracing::warn!("audit mode, not broadcasting {} transaction(s)", txs.len());
+    }
+}
+
+impl FeeEstimator for AuditChainInterface {
+    fn get_est_sat_per_1000_weight(&self, _confirmation_target: ConfirmationTarget) -> u32 {
+        FEERATE_FLOOR_SATS_PER_KW
+    }
+}
+
+struct AuditLogger;
+
+impl Logger for AuditLogger {
+    fn log(&self, record: Record) {
+        tracing::debug!("{}", record.args);
+    }
+}
+
+fn read_wallet_file(storage_dir_path: &Path, fname: &str) -> Result<String, AppError> {
+    fs::read_to_string(storage_dir_path.join(fname))
+        .map(|s| s.trim().to_string())
+        .map_err(|_| {
+            AppError::FailedAudit(format!(
+                "missing {fname}, the node needs to be unlocked at least once"
+            ))
+        })
+}
+
+/// Audit the node storage directory without the node password, producing a summary signed with
+/// the auditor key.
+///
+/// The RGB wallet is opened watch-only from the stored account xpubs and is never taken online,
+/// channel monitors are read with a throwaway key seed, so no funds can be moved. Balances
+/// reflect the state of the storage directory at the time it was last written by the node.
+pub(crate) async fn audit_storage_dir(
+    storage_dir_path: &Path,
+    network: BitcoinNetwork,
+    auditor_key_path: &Path,
+) -> Result<AuditReport, AppError> {
+    let auditor_key = fs::read_to_string(auditor_key_path)?;
+    let auditor_key =
+        SecretKey::from_str(auditor_key.trim()).map_err(|_| AppError::InvalidAuditorKey)?;
+
+    if !get_mnemonic_path(storage_dir_path).exists() {
+        return Err(AppError::FailedAudit(s!(
+            "the node has not been initialized"
+        )));
+    }
+    let stored_network = read_wallet_file(storage_dir_path, BITCOIN_NETWORK_FNAME)?;
+    if BitcoinNetwork::from_str(&stored_network).ok() != Some(network) {
+        return Err(AppError::NetworkMismatch(stored_network, network));
+    }
+    let account_xpub_vanilla =
+        read_wallet_file(storage_dir_path, WALLET_ACCOUNT_XPUB_VANILLA_FNAME)?;
+    let account_xpub_colored =
+        read_wallet_file(storage_dir_path, WALLET_ACCOUNT_XPUB_COLORED_FNAME)?;
+    let master_fingerprint = read_wallet_file(storage_dir_path, WALLET_MASTER_FINGERPRINT_FNAME)?;
+
+    let mut anomalies = vec![];
+
+    // watch-only RGB wallet
+    let data_dir = storage_dir_path.to_string_lossy().to_string();
+    let wallet_data = WalletData {
+        data_dir,
+        bitcoin_network: network,
+        database_type: DatabaseType::Sqlite,
+        max_allocations_per_utxo: 1,
+        account_xpub_vanilla,
+        account_xpub_colored,
+        master_fingerprint: master_fingerprint.clone(),
+        mnemonic: None,
+        vanilla_keychain: None,
+        supported_schemas: vec![
+            AssetSchema::Nia,
+            AssetSchema::Cfa,
+            AssetSchema::Uda,
+            AssetSchema::Ifa,
+        ],
+    };
+    let mut rgb_wallet = tokio::task::spawn_blocking(move || RgbLibWallet::new(wallet_data))
+        .await
+        .unwrap()
+        .map_err(|e| AppError::FailedAudit(format!("cannot open the RGB wallet: {e}")))?;
+    let btc_balance = rgb_wallet
+        .get_btc_balance(None, true)
+        .map_err(|e| AppError::FailedAudit(format!("cannot get the BTC balance: {e}")))?;
+    let rgb_assets = rgb_wallet
+        .list_assets(vec![])
+        .map_err(|e| AppError::FailedAudit(format!("cannot list the RGB assets: {e}")))?;
+    let mut asset_ids: Vec<String> = vec![];
+    asset_ids.extend(
+        rgb_assets
+            .nia
+            .unwrap_or_default()
+            .into_iter()
+            .map(|a| a.asset_id),
+    );
+    asset_ids.extend(
+        rgb_assets
+            .uda
+            .unwrap_or_default()
+            .into_iter()
+            .map(|a| a.asset_id),
+    );
+    asset_ids.extend(
+        rgb_assets
+            .cfa
+            .unwrap_or_default()
+            .into_iter()
+            .map(|a| a.asset_id),
+    );
+
+    // channel monitors, with their pending updates applied
+    let ldk_data_dir = storage_dir_path.join(LDK_DIR);
+    let mut seed = [0; 32];
+    rand::thread_rng().fill_bytes(&mut seed);
+    let cur = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap();
+    let keys_manager = Arc::new(KeysManager::new(
+        &seed,
+        cur.as_secs(),
+        cur.subsec_nanos(),
+        true,
+        ldk_data_dir.clone(),
+    ));
+    let backend = stored_backend(&ldk_data_dir).unwrap_or_default();
+    let kv_store = Arc::new(NodeStore::new(&ldk_data_dir, backend)?);
+    let chain_interface = Arc::new(AuditChainInterface);
+    let persister = MonitorUpdatingPersister::new(
+        Arc::clone(&kv_store),
+        Arc::new(AuditLogger),
+        1000,
+        Arc::clone(&keys_manager),
+        Arc::clone(&keys_manager),
+        Arc::clone(&chain_interface),
+        Arc::clone(&chain_interface),
+    );
+    let monitor_keys = kv_store.list(
+        CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+        CHANNEL_MONITOR_PERSISTENCE_SECONDARY_NAMESPACE,
+    )?;
+
+    let mut channels = vec![];
+    let mut offchain_balances: HashMap<String, (u64, u64)> = HashMap::new();
+    for monitor_key in monitor_keys {
+        let monitor = match persister.read_channel_monitor_with_updates(&monitor_key) {
+            Ok((_, monitor)) => monitor,
+            Err(e) => {
+                anomalies.push(format!("channel monitor {monitor_key} cannot be read: {e}"));
+                continue;
+            }
+        };
+        let channel_id = monitor.channel_id();
+        let funding_txid = monitor.get_funding_txo().txid.to_string();
+        let mut channel = AuditChannel {
+            channel_id: channel_id.0.as_hex().to_string(),
+            funding_txid: funding_txid.clone(),
+            peer_pubkey: monitor.get_counterparty_node_id().to_string(),
+            latest_update_id: monitor.get_latest_update_id(),
+            claimable_balance_sat: monitor
+                .get_claimable_balances()
+                .iter()
+                .map(|b| b.claimable_amount_satoshis())
+                .sum::<u64>(),
+            asset_id: None,
+            asset_local_amount: None,
+            asset_remote_amount: None,
+            consignment_verified: None,
+        };
+
+        if let Some((rgb_info, _)) =
+            get_rgb_channel_info_optional(&channel_id, &ldk_data_dir, false)
+        {
+            let asset_id = rgb_info.contract_id.to_string();
+            if !asset_ids.contains(&asset_id) {
+                anomalies.push(format!(
+                    "channel {} uses asset {asset_id}, unknown to the wallet",
+                    channel.channel_id
+                ));
+            }
+
+            // the channel opening consignment is kept by the acceptor in the LDK directory and
+            // by the opener among the wallet's sent transfers
+            let mut consignment_path = ldk_data_dir.join(format!("consignment_{funding_txid}"));
+            if !consignment_path.exists() {
+                consignment_path = rgb_wallet.get_send_consignment_path(&asset_id, &funding_txid);
+            }
+            let consignment_verified = match RgbTransfer::load_file(consignment_path) {
+                Ok(consignment) if consignment.contract_id() == rgb_info.contract_id => true,
+                Ok(consignment) => {
+                    anomalies.push(format!(
+                        "channel {} consignment is for contract {}, expected {asset_id}",
+                        channel.channel_id,
+                        consignment.contract_id()
+                    ));
+                    false
+                }
+                Err(_) => {
+                    anomalies.push(format!(
+                        "channel {} consignment is missing or invalid",
+                        channel.channel_id
+                    ));
+                    false
+                }
+            };
+
+            offchain_balances
+                .entry(asset_id.clone())
+                .and_modify(|(offchain_outbound, offchain_inbound)| {
+                    *offchain_outbound += rgb_info.local_rgb_amount;
+                    *offchain_inbound += rgb_info.remote_rgb_amount;
+                })
+                .or_insert((rgb_info.local_rgb_amount, rgb_info.remote_rgb_amount));
+            channel.asset_id = Some(asset_id);
+            channel.asset_local_amount = Some(rgb_info.local_rgb_amount);
+            channel.asset_remote_amount = Some(rgb_info.remote_rgb_amount);
+            channel.consignment_verified = Some(consignment_verified);
+        }
+        channels.push(channel);
+    }
+    channels.sort_by(|a, b| a.channel_id.cmp(&b.channel_id));
+
+    let mut assets = vec![];
+    for asset_id in asset_ids {
+        let contract_id = ContractId::from_str(&asset_id)
+            .map_err(|_| AppError::FailedAudit(format!("invalid asset ID {asset_id}")))?;
+        let balance = rgb_wallet
+            .get_asset_balance(contract_id.to_string())
+            .map_err(|e| AppError::FailedAudit(format!("cannot get the asset balance: {e}")))?;
+        let (offchain_outbound, offchain_inbound) =
+            offchain_balances.get(&asset_id).copied().unwrap_or((0, 0));
+        assets.push(AuditAsset {
+            asset_id,
+            settled: balance.settled,
+            future: balance.future,
+            offchain_outbound,
+            offchain_inbound,
+        });
+    }
+
+    let summary = AuditSummary {
+        network: network.to_string(),
+        master_fingerprint,
+        timestamp: get_current_timestamp(),
+        onchain_btc_settled_sat: btc_balance.vanilla.settled + btc_balance.colored.settled,
+        onchain_btc_future_sat: btc_balance.vanilla.future + btc_balance.colored.future,
+        offchain_btc_claimable_sat: channels.iter().map(|c| c.claimable_balance_sat).sum(),
+        assets,
+        channels,
+        anomalies,
+    };
+
+    // the summary is signed with the auditor key, so it can be checked with any Lightning
+    // implementation supporting message verification (e.g. `lncli verifymessage`)
+    let message = format!(
+        "{AUDIT_MESSAGE_HEADER}\n{}",
+        serde_json::to_string(&summary).unwrap()
+    );
+    let signature = lightning::util::message_signing::sign(message.as_bytes(), &auditor_key);
+
+    Ok(AuditReport {
+        summary,
+        auditor_pubkey: PublicKey::from_secret_key(&Secp256k1::new(), &auditor_key).to_string(),
+        message,
+        signature,
+    })
+}
@@ -1,95 +1,277 @@
 use axum::{
     body::Body,
-    extract::State,
+    extract::{MatchedPath, State},
     http::{Request, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
 };
-use biscuit_auth::{macros::authorizer, Biscuit, PublicKey};
+use biscuit_auth::{
+    macros::{authorizer, biscuit},
+    Biscuit, KeyPair, PublicKey,
+};
+use lightning::impl_writeable_tlv_based;
+use lightning::util::hash_tables::HashMap as LdkHashMap;
+use lightning::util::persist::KVStoreSync;
+use lightning::util::ser::Writeable;
+use lightning_invoice::Bolt11Invoice;
+use serde::Deserialize;
 use std::{
     collections::HashSet,
     fs,
     io::{BufRead, BufReader, Write as IoWrite},
     path::PathBuf,
-    sync::Arc,
+    str::FromStr,
+    sync::{Arc, MutexGuard},
 };
 use tempfile::NamedTempFile;
 
 use crate::{
+    disk::TOKEN_SPENDING_FNAME,
     error::{APIError, AppError},
-    utils::{hex_str, hex_str_to_vec, AppState},
+    routes::{API_V2_PREFIX, API_VERSION_PREFIX},
+    utils::{get_current_timestamp, hex_str, hex_str_to_vec, AppState, UnlockedAppState},
 };
 
 const REVOKED_TOKENS_FILE: &str = "revoked_tokens.txt";
 
-const READ_ONLY_OPS: [&str; 23] = [
+const ROOT_PUBLIC_KEY_FILE: &str = "root_public_key.txt";
+
+const READ_ONLY_OPS: [&str; 48] = [
+    "/addressstats",
+    "/anchorreserve",
+    "/assetaudit",
     "/assetbalance",
     "/assetmetadata",
+    "/audit",
     "/btcbalance",
+    "/chainevents",
+    "/channels",
+    "/channelstatus",
     "/checkindexerurl",
     "/checkproxyendpoint",
     "/decodelninvoice",
     "/decodergbinvoice",
+    "/descriptors",
     "/estimatefee",
+    "/export/accounting",
+    "/feereport",
+    "/forwardinghistory",
     "/getassetmedia",
+    "/getchaninfo",
     "/getchannelid",
+    "/getnodeinfo",
+    "/getorder",
     "/getpayment",
     "/getswap",
+    "/invoicequotas",
     "/invoicestatus",
+    "/lease/orders",
     "/listassets",
     "/listchannels",
+    "/listescrows",
+    "/listoffers",
     "/listpayments",
     "/listpeers",
     "/listswaps",
+    "/listsweeps",
     "/listtransactions",
     "/listtransfers",
     "/listunspents",
     "/networkinfo",
     "/nodeinfo",
+    "/nwc/connections",
+    "/pendingbroadcasts",
+    "/phantomroutehints",
+    "/queryroutes",
+    "/torstatus",
+    "/verifymessage",
+];
+
+/// Operations moving funds that are charged to the spending caveats of a token
+const BUDGETED_OPS: [&str; 4] = ["/keysend", "/sendasset", "/sendbtc", "/sendpayment"];
+
+/// Operations moving funds whose amount cannot be charged to the spending caveats of a token,
+/// denied to tokens carrying any of them
+const UNBUDGETED_SPENDING_OPS: [&str; 26] = [
+    "/accelerateincoming",
+    "/approvebroadcast",
+    "/bumpclosefee",
+    "/bumpfee",
+    "/burnasset",
+    "/buyinbound",
+    "/closechannel",
+    "/createutxos",
+    "/exportbackup",
+    "/fundchannelcomplete",
+    "/fundpsbt",
+    "/makerexecute",
+    "/nwc/connect",
+    "/openchannel",
+    "/payoffer",
+    "/rebalance",
+    "/reissueasset",
+    "/retrysweep",
+    "/sendbtcmany",
+    "/sendtoroute",
+    "/sethedgingpolicy",
+    "/signpsbt",
+    "/swapin",
+    "/swapout",
+    "/sweepconfig",
+    "/taker",
 ];
 
+const BUDGETED_BODY_LIMIT: usize = 2 * 1024 * 1024;
+
+const SECONDS_IN_DAY: u64 = 86400;
+
+/// Revocation ID of the token authorizing a request, identifying the API credential
+#[derive(Clone, Debug)]
+pub(crate) struct TokenId(pub(crate) String);
+
+/// Caveats restricting what a token can spend, set as facts of its authority block
+#[derive(Default)]
+struct TokenCaveats {
+    max_payment_msat: Option<u64>,
+    daily_budget_msat: Option<u64>,
+    allowed_assets: Option<Vec<String>>,
+    allowed_endpoints: Option<Vec<String>>,
+}
+
+impl TokenCaveats {
+    fn limits_spending(&self) -> bool {
+        self.max_payment_msat.is_some()
+            || self.daily_budget_msat.is_some()
+            || self.allowed_assets.is_some()
+    }
+}
+
+/// Amount and asset of a budgeted operation, parsed from its request body
+#[derive(Deserialize)]
+struct BudgetedRequest {
+    invoice: Option<String>,
+    amt_msat: Option<u64>,
+    amount: Option<u64>,
+    asset_id: Option<String>,
+    dry_run: Option<bool>,
+}
+
+impl BudgetedRequest {
+    fn spending(&self, op: &str) -> (u64, Option<String>) {
+        match op {
+            "/sendpayment" => {
+                let invoice = self
+                    .invoice
+                    .as_deref()
+                    .and_then(|i| Bolt11Invoice::from_str(i).ok());
+                let amt_msat = self
+                    .amt_msat
+                    .or(invoice.as_ref().and_then(|i| i.amount_milli_satoshis()))
+                    .unwrap_or(0);
+                let asset_id = invoice
+                    .and_then(|i| i.rgb_contract_id())
+                    .map(|c| c.to_string());
+                (amt_msat, asset_id)
+            }
+            "/keysend" => (self.amt_msat.unwrap_or(0), self.asset_id.clone()),
+            "/sendbtc" => (self.amount.unwrap_or(0).saturating_mul(1000), None),
+            _ => (0, self.asset_id.clone()),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct TokenSpendingInfo {
+    pub(crate) day_start: u64,
+    pub(crate) spent_msat: u64,
+}
+
+impl_writeable_tlv_based!(TokenSpendingInfo, {
+    (0, day_start, required),
+    (2, spent_msat, required),
+});
+
+pub(crate) struct TokenSpendingMap {
+    pub(crate) tokens: LdkHashMap<String, TokenSpendingInfo>,
+}
+
+impl_writeable_tlv_based!(TokenSpendingMap, {
+    (0, tokens, required),
+});
+
 pub(crate) fn check_auth_args(
     disable_authentication: bool,
     root_public_key: Option<String>,
+    generate_root_key: bool,
 ) -> Result<Option<PublicKey>, AppError> {
-    match (disable_authentication, root_public_key.is_some()) {
-        (true, true) => {
+    match (
+        disable_authentication,
+        root_public_key.is_some(),
+        generate_root_key,
+    ) {
+        (true, true, _) => {
             tracing::error!("Authentication disabled but root key provided");
             return Err(AppError::InvalidAuthenticationArgs);
         }
-        (false, false) => {
+        (true, false, true) => {
+            tracing::error!("Authentication disabled but root key generation requested");
+            return Err(AppError::InvalidAuthenticationArgs);
+        }
+        (false, true, true) => {
+            tracing::error!("Root key both provided and requested to be generated");
+            return Err(AppError::InvalidAuthenticationArgs);
+        }
+        (false, false, false) => {
             tracing::error!("Authentication enabled but no root key provided");
             return Err(AppError::InvalidAuthenticationArgs);
         }
-        (true, false) => {
+        (true, false, false) => {
             tracing::info!("Authentication disabled");
         }
-        (false, true) => {
+        (false, true, false) => {
             tracing::info!("Authentication enabled");
         }
+        (false, false, true) => {
+            tracing::info!("Authentication enabled, with a root key generated on init");
+        }
     };
 
-    Ok(if let Some(root_key_hex) = &root_public_key {
-        let key_bytes = hex_str_to_vec(root_key_hex).ok_or(AppError::InvalidRootKey)?;
-        if key_bytes.len() != 32 {
-            return Err(AppError::InvalidRootKey);
-        }
-        let mut key_array = [0u8; 32];
-        key_array.copy_from_slice(&key_bytes);
-        let public_key = PublicKey::from_bytes(&key_array, biscuit_auth::Algorithm::Ed25519)
-            .map_err(|_| AppError::InvalidRootKey)?;
-        Some(public_key)
-    } else {
-        None
-    })
+    root_public_key
+        .map(|root_key_hex| parse_root_public_key(&root_key_hex))
+        .transpose()
+}
+
+fn parse_root_public_key(root_key_hex: &str) -> Result<PublicKey, AppError> {
+    let key_bytes = hex_str_to_vec(root_key_hex).ok_or(AppError::InvalidRootKey)?;
+    if key_bytes.len() != 32 {
+        return Err(AppError::InvalidRootKey);
+    }
+    let mut key_array = [0u8; 32];
+    key_array.copy_from_slice(&key_bytes);
+    PublicKey::from_bytes(&key_array, biscuit_auth::Algorithm::Ed25519)
+        .map_err(|_| AppError::InvalidRootKey)
 }
 
 pub(crate) async fn conditional_auth_middleware(
     State(app_state): State<Arc<AppState>>,
-    request: Request<Body>,
+    mut request: Request<Body>,
     next: Next,
 ) -> Result<Response, StatusCode> {
-    let Some(root_pubkey) = app_state.root_public_key else {
+    // LNURL-pay endpoints are queried by paying wallets, which carry no token, while the API
+    // spec is needed to generate clients
+    if is_operation_public(request.uri().path()) {
+        return Ok(next.run(request).await);
+    }
+
+    let root_public_key = *app_state.get_root_public_key();
+    let Some(root_pubkey) = root_public_key else {
+        if app_state.generate_root_key {
+            // no token can exist before the root key gets generated (or restored from a backup)
+            return match request.uri().path() {
+                "/init" | "/restore" => Ok(next.run(request).await),
+                _ => Err(StatusCode::UNAUTHORIZED),
+            };
+        }
         // if no root key is configured, skip authentication
         return Ok(next.run(request).await);
     };
@@ -112,52 +294,133 @@ pub(crate) async fn conditional_auth_middleware(
         return Err(StatusCode::UNAUTHORIZED);
     }
 
-    if is_token_expired(&token) {
+    // routes with path parameters are authorized on their base path (e.g. /assetaudit), the
+    // matched path including the URL prefix and the API version the APIs may be served under
+    let url_prefix = app_state.static_state.url_prefix.as_deref().unwrap_or("");
+    let op = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(request.uri().path(), |p| {
+            let path = p.as_str().strip_prefix(url_prefix).unwrap_or(p.as_str());
+            [API_VERSION_PREFIX, API_V2_PREFIX]
+                .into_iter()
+                .find_map(|prefix| path.strip_prefix(prefix).filter(|p| p.starts_with('/')))
+                .unwrap_or(path)
+        })
+        .split("/:")
+        .next()
+        .unwrap()
+        .to_string();
+
+    if fails_token_checks(&token, &op) {
         return Err(StatusCode::UNAUTHORIZED);
     }
 
-    if is_admin_role(&token) {
-        return Ok(next.run(request).await);
+    let caveats = token_caveats(&token).ok_or(StatusCode::UNAUTHORIZED)?;
+    if let Some(allowed_endpoints) = &caveats.allowed_endpoints {
+        if !allowed_endpoints.contains(&op) {
+            return Err(StatusCode::FORBIDDEN);
+        }
     }
 
-    let op = request.uri().path().to_string();
+    let permitted = if is_admin_role(&token, &op) {
+        true
+    } else if is_read_only_role(&token, &op) {
+        is_operation_readonly(&op)
+    } else if is_custom_role(&token, &op) {
+        is_operation_permitted(&token, &op)
+    } else {
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+    if !permitted {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let token_id = hex_str(&token.revocation_identifiers()[0]);
+    request.extensions_mut().insert(TokenId(token_id.clone()));
 
-    if is_read_only_role(&token) {
-        if is_operation_readonly(&op) {
-            return Ok(next.run(request).await);
-        } else {
-            return Err(StatusCode::FORBIDDEN);
-        }
+    if !caveats.limits_spending() {
+        return Ok(next.run(request).await);
+    }
+    if UNBUDGETED_SPENDING_OPS.contains(&op.as_str()) {
+        return Ok(APIError::TokenLimitExceeded(format!(
+            "{op} is not allowed to tokens with spending limits"
+        ))
+        .into_response());
+    }
+    if !BUDGETED_OPS.contains(&op.as_str()) {
+        return Ok(next.run(request).await);
     }
 
-    if is_custom_role(&token) {
-        if is_operation_permitted(&token, &op) {
-            return Ok(next.run(request).await);
-        } else {
-            return Err(StatusCode::FORBIDDEN);
+    let (parts, body) = request.into_parts();
+    let bytes = axum::body::to_bytes(body, BUDGETED_BODY_LIMIT)
+        .await
+        .map_err(|_| StatusCode::PAYLOAD_TOO_LARGE)?;
+    // a body that cannot be parsed will be rejected by the handler as well
+    let Ok(budgeted_request) = serde_json::from_slice::<BudgetedRequest>(&bytes) else {
+        return Ok(next
+            .run(Request::from_parts(parts, Body::from(bytes)))
+            .await);
+    };
+    let (amt_msat, asset_id) = budgeted_request.spending(&op);
+
+    if let Some(max_payment_msat) = caveats.max_payment_msat {
+        if amt_msat > max_payment_msat {
+            return Ok(APIError::TokenLimitExceeded(format!(
+                "payment of {amt_msat} msat exceeds the max of {max_payment_msat} msat"
+            ))
+            .into_response());
+        }
+    }
+    if let (Some(allowed_assets), Some(asset_id)) = (&caveats.allowed_assets, &asset_id) {
+        if !allowed_assets.contains(asset_id) {
+            return Ok(
+                APIError::TokenLimitExceeded(format!("asset {asset_id} is not allowed"))
+                    .into_response(),
+            );
         }
     }
 
-    Err(StatusCode::UNAUTHORIZED)
+    let request = Request::from_parts(parts, Body::from(bytes));
+    let Some(daily_budget_msat) = caveats.daily_budget_msat else {
+        return Ok(next.run(request).await);
+    };
+    // a locked node rejects the request by itself
+    let Some(unlocked_state) = app_state.get_unlocked_app_state().await.clone() else {
+        return Ok(next.run(request).await);
+    };
+    if let Err(e) = unlocked_state.reserve_token_budget(&token_id, amt_msat, daily_budget_msat) {
+        return Ok(e.into_response());
+    }
+    let response = next.run(request).await;
+    // a dry run spends nothing, it only goes through the same checks
+    if !response.status().is_success() || budgeted_request.dry_run.unwrap_or(false) {
+        unlocked_state.release_token_budget(&token_id, amt_msat);
+    }
+    Ok(response)
 }
 
-fn is_admin_role(token: &Biscuit) -> bool {
-    is_role(token, "admin")
+fn is_admin_role(token: &Biscuit, op: &str) -> bool {
+    is_role(token, "admin", op)
 }
 
-fn is_custom_role(token: &Biscuit) -> bool {
-    is_role(token, "custom")
+fn is_custom_role(token: &Biscuit, op: &str) -> bool {
+    is_role(token, "custom", op)
 }
 
-fn is_read_only_role(token: &Biscuit) -> bool {
-    is_role(token, "read-only")
+fn is_read_only_role(token: &Biscuit, op: &str) -> bool {
+    is_role(token, "read-only", op)
 }
 
-fn is_role(token: &Biscuit, role: &str) -> bool {
-    let res = authorizer!(r#"allow if role({role});"#)
-        .time()
-        .build(token)
-        .and_then(|mut authorizer| authorizer.authorize());
+fn is_role(token: &Biscuit, role: &str, op: &str) -> bool {
+    let res = authorizer!(
+        r#"
+            operation({op});
+            allow if role({role});
+        "#,
+    )
+    .time()
+    .build(token)
+    .and_then(|mut authorizer| authorizer.authorize());
     res.is_ok()
 }
 
@@ -174,19 +437,145 @@ fn is_operation_permitted(token: &Biscuit, op: &str) -> bool {
     res.is_ok()
 }
 
+fn is_operation_public(path: &str) -> bool {
+    path.starts_with("/.well-known/lnurlp/") || path.starts_with("/lnurlp/") || path == "/spec"
+}
+
 fn is_operation_readonly(operation: &str) -> bool {
     READ_ONLY_OPS.contains(&operation)
 }
 
-fn is_token_expired(token: &Biscuit) -> bool {
-    let res = authorizer!(r#"allow if true;"#)
-        .time()
-        .build(token)
-        .and_then(|mut authorizer| authorizer.authorize());
+/// Whether the token has expired or has been attenuated with checks the operation doesn't pass
+fn fails_token_checks(token: &Biscuit, op: &str) -> bool {
+    let res = authorizer!(
+        r#"
+            operation({op});
+            allow if true;
+        "#,
+    )
+    .time()
+    .build(token)
+    .and_then(|mut authorizer| authorizer.authorize());
     res.is_err()
 }
 
+/// Read the caveats of a token, which only count when set by its authority block
+fn token_caveats(token: &Biscuit) -> Option<TokenCaveats> {
+    let mut authorizer = authorizer!(r#"allow if true;"#).build(token).ok()?;
+    let amount = |facts: Vec<(i64,)>| facts.into_iter().map(|(n,)| n.max(0) as u64).min();
+    let strings = |facts: Vec<(String,)>| {
+        (!facts.is_empty()).then(|| facts.into_iter().map(|(s,)| s).collect::<Vec<_>>())
+    };
+    Some(TokenCaveats {
+        max_payment_msat: amount(authorizer.query("data($n) <- max_payment_msat($n)").ok()?),
+        daily_budget_msat: amount(authorizer.query("data($n) <- daily_budget_msat($n)").ok()?),
+        allowed_assets: strings(authorizer.query("data($a) <- allowed_asset($a)").ok()?),
+        allowed_endpoints: strings(authorizer.query("data($e) <- allowed_endpoint($e)").ok()?),
+    })
+}
+
+impl UnlockedAppState {
+    fn save_token_spending(&self, token_spending: MutexGuard<TokenSpendingMap>) {
+        self.kv_store
+            .write("", "", TOKEN_SPENDING_FNAME, token_spending.encode())
+            .unwrap();
+    }
+
+    /// Charge an amount to the daily budget of a token, failing if it would be exceeded
+    fn reserve_token_budget(
+        &self,
+        token_id: &str,
+        amt_msat: u64,
+        daily_budget_msat: u64,
+    ) -> Result<(), APIError> {
+        let mut token_spending = self.get_token_spending();
+        let now = get_current_timestamp();
+        let day_start = now - now % SECONDS_IN_DAY;
+        let spending =
+            token_spending
+                .tokens
+                .entry(token_id.to_string())
+                .or_insert(TokenSpendingInfo {
+                    day_start,
+                    spent_msat: 0,
+                });
+        if spending.day_start != day_start {
+            spending.day_start = day_start;
+            spending.spent_msat = 0;
+        }
+        let spent_msat = spending
+            .spent_msat
+            .checked_add(amt_msat)
+            .filter(|spent_msat| *spent_msat <= daily_budget_msat);
+        let Some(spent_msat) = spent_msat else {
+            return Err(APIError::TokenLimitExceeded(format!(
+                "payment of {amt_msat} msat exceeds the remaining daily budget of {} msat",
+                daily_budget_msat.saturating_sub(spending.spent_msat)
+            )));
+        };
+        spending.spent_msat = spent_msat;
+        self.save_token_spending(token_spending);
+        Ok(())
+    }
+
+    /// Give back to the daily budget of a token an amount that hasn't been spent
+    fn release_token_budget(&self, token_id: &str, amt_msat: u64) {
+        let mut token_spending = self.get_token_spending();
+        if let Some(spending) = token_spending.tokens.get_mut(token_id) {
+            spending.spent_msat = spending.spent_msat.saturating_sub(amt_msat);
+            self.save_token_spending(token_spending);
+        }
+    }
+}
+
 impl AppState {
+    /// Generate the root key, persisting only its public half, and mint the initial tokens
+    pub(crate) fn generate_root_key_and_tokens(&self) -> Result<(String, String), APIError> {
+        let root_keypair = KeyPair::new();
+        let mint = |role: &str| {
+            biscuit!(r#"role({role});"#)
+                .build(&root_keypair)
+                .and_then(|token| token.to_base64())
+                .map_err(|e| APIError::Unexpected(format!("failed to mint token: {e}")))
+        };
+        let admin_token = mint("admin")?;
+        let read_only_token = mint("read-only")?;
+
+        let root_public_key = root_keypair.public();
+        fs::write(
+            self.get_root_public_key_path(),
+            hex_str(&root_public_key.to_bytes()),
+        )?;
+        *self.get_root_public_key() = Some(root_public_key);
+        tracing::info!("Generated the root key for token authentication");
+
+        Ok((admin_token, read_only_token))
+    }
+
+    fn get_root_public_key_path(&self) -> PathBuf {
+        self.static_state
+            .storage_dir_path
+            .join(ROOT_PUBLIC_KEY_FILE)
+    }
+
+    /// Load the generated root public key, if the node has already been initialized
+    pub(crate) fn load_root_public_key(&self) -> Result<(), AppError> {
+        let path = self.get_root_public_key_path();
+        let root_key_hex = match fs::read_to_string(&path) {
+            Ok(k) => k,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                tracing::info!("No root key has been generated yet");
+                return Ok(());
+            }
+            Err(e) => {
+                tracing::error!("Failed to read root key file {}: {}", path.display(), e);
+                return Err(AppError::IO(e));
+            }
+        };
+        *self.get_root_public_key() = Some(parse_root_public_key(root_key_hex.trim())?);
+        Ok(())
+    }
+
     pub(crate) fn revoke_token(&self, token_to_revoke: &Biscuit) -> Result<(), APIError> {
         let revocation_ids = token_to_revoke.revocation_identifiers();
 
@@ -0,0 +1,516 @@
+use amplify::s;
+use bitcoin::hashes::hmac::{Hmac, HmacEngine};
+use bitcoin::hashes::{sha256, Hash, HashEngine};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::error::APIError;
+use crate::store::dir_checksums;
+use crate::utils::{hex_str, LOGS_DIR};
+
+/// File of every snapshot listing all the backed up files, with the snapshot holding their content
+pub(crate) const AUTO_BACKUP_MANIFEST_FNAME: &str = "manifest.json";
+/// Directory of the snapshots holding the channel backup files
+pub(crate) const AUTO_BACKUP_CHANNELS_DIR: &str = "channels";
+/// Directory of the snapshots holding the RGB wallet data
+pub(crate) const AUTO_BACKUP_RGB_DIR: &str = "rgb";
+const SNAPSHOT_PREFIX: &str = "snapshot_";
+const SNAPSHOT_FULL: &str = "full";
+const SNAPSHOT_INCREMENTAL: &str = "incremental";
+const S3_DEFAULT_REGION: &str = "us-east-1";
+const S3_SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+
+/// Where the automatic backups get uploaded
+#[derive(Clone)]
+pub(crate) enum AutoBackupTarget {
+    /// Local directory, which can be a mounted remote filesystem
+    Dir(PathBuf),
+    /// Directory on an SFTP server, reached with the sftp client of the system
+    Sftp {
+        destination: String,
+        port: Option<u16>,
+        dir: String,
+    },
+    /// Bucket of an S3-compatible endpoint
+    S3(S3Target),
+}
+
+#[derive(Clone)]
+pub(crate) struct S3Target {
+    /// URL of the bucket, including the key prefix
+    bucket_url: reqwest::Url,
+    prefix: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+}
+
+/// Parse the target of the automatic backups, which is either a local directory, an
+/// sftp://user@host[:port]/path URL or an
+/// s3://access_key_id:secret_access_key@host[:port]/bucket[/prefix][?region=region] one (s3+http
+/// for endpoints without TLS)
+pub(crate) fn parse_auto_backup_target(target: &str) -> Result<AutoBackupTarget, String> {
+    if !target.contains("://") {
+        return Ok(AutoBackupTarget::Dir(PathBuf::from(target)));
+    }
+    let parsed = reqwest::Url::parse(target).map_err(|e| format!("invalid URL: {e}"))?;
+    let host = parsed
+        .host_str()
+        .filter(|h| !h.is_empty())
+        .map(|h| h.to_string());
+    match parsed.scheme() {
+        "file" => Ok(AutoBackupTarget::Dir(PathBuf::from(percent_decode(
+            parsed.path(),
+        )?))),
+        "sftp" => {
+            let host = host.ok_or_else(|| s!("SFTP URL is missing the host"))?;
+            let destination = if parsed.username().is_empty() {
+                host
+            } else {
+                format!("{}@{host}", percent_decode(parsed.username())?)
+            };
+            Ok(AutoBackupTarget::Sftp {
+                destination,
+                port: parsed.port(),
+                dir: percent_decode(parsed.path().trim_start_matches('/'))?,
+            })
+        }
+        scheme @ ("s3" | "s3+http") => {
+            let host = host.ok_or_else(|| s!("S3 URL is missing the host"))?;
+            let access_key_id = percent_decode(parsed.username())?;
+            let secret_access_key = percent_decode(parsed.password().unwrap_or_default())?;
+            if access_key_id.is_empty() || secret_access_key.is_empty() {
+                return Err(s!("S3 URL is missing the credentials"));
+            }
+            let mut segments = parsed.path().trim_matches('/').splitn(2, '/');
+            let bucket = segments.next().unwrap_or_default();
+            if bucket.is_empty() {
+                return Err(s!("S3 URL is missing the bucket"));
+            }
+            let prefix = match segments.next() {
+                Some(prefix) if !prefix.is_empty() => format!("{prefix}/"),
+                _ => s!(""),
+            };
+            let region = parsed
+                .query_pairs()
+                .find(|(k, _)| k == "region")
+                .map(|(_, v)| v.to_string())
+                .unwrap_or_else(|| s!(S3_DEFAULT_REGION));
+            let http_scheme = if scheme == "s3" { "https" } else { "http" };
+            let port = parsed.port().map(|p| format!(":{p}")).unwrap_or_default();
+            let bucket_url =
+                reqwest::Url::parse(&format!("{http_scheme}://{host}{port}/{bucket}/{prefix}"))
+                    .map_err(|e| format!("invalid S3 URL: {e}"))?;
+            Ok(AutoBackupTarget::S3(S3Target {
+                bucket_url,
+                prefix: percent_decode(&prefix)?,
+                region,
+                access_key_id,
+                secret_access_key,
+            }))
+        }
+        scheme => Err(format!("unsupported auto backup URL scheme {scheme}")),
+    }
+}
+
+fn percent_decode(value: &str) -> Result<String, String> {
+    let mut decoded = Vec::with_capacity(value.len());
+    let mut bytes = value.bytes();
+    while let Some(b) = bytes.next() {
+        if b != b'%' {
+            decoded.push(b);
+            continue;
+        }
+        let hex = [bytes.next(), bytes.next()]
+            .into_iter()
+            .collect::<Option<Vec<u8>>>()
+            .and_then(|h| String::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(&h, 16).ok())
+            .ok_or_else(|| format!("invalid percent-encoding in {value}"))?;
+        decoded.push(hex);
+    }
+    String::from_utf8(decoded).map_err(|_| format!("invalid percent-encoding in {value}"))
+}
+
+/// Encoding of the AWS signature, leaving only the unreserved characters as they are
+fn uri_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// Values of the elements with the given tag in an XML response
+fn xml_values<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{tag}>");
+    let close = format!("</{tag}>");
+    xml.split(&open)
+        .skip(1)
+        .filter_map(|rest| rest.split_once(&close).map(|(value, _)| value))
+        .collect()
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut engine = HmacEngine::<sha256::Hash>::new(key);
+    engine.input(data);
+    Hmac::<sha256::Hash>::from_engine(engine).to_byte_array()
+}
+
+impl fmt::Display for AutoBackupTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AutoBackupTarget::Dir(dir) => write!(f, "{}", dir.display()),
+            AutoBackupTarget::Sftp {
+                destination,
+                port,
+                dir,
+            } => {
+                let port = port.map(|p| format!(":{p}")).unwrap_or_default();
+                write!(f, "sftp://{destination}{port}/{dir}")
+            }
+            AutoBackupTarget::S3(s3) => write!(f, "{}", s3.bucket_url),
+        }
+    }
+}
+
+impl AutoBackupTarget {
+    /// Make sure the target can be written, creating the local directory if needed
+    pub(crate) fn check(&self) -> Result<(), String> {
+        if let AutoBackupTarget::Dir(dir) = self {
+            fs::create_dir_all(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Location of the file with the given name on the target
+    pub(crate) fn location(&self, name: &str) -> String {
+        match self {
+            AutoBackupTarget::Dir(dir) => dir.join(name).to_string_lossy().to_string(),
+            _ => format!("{}/{name}", self.to_string().trim_end_matches('/')),
+        }
+    }
+
+    /// Upload the given file, which gets its final name only once complete
+    pub(crate) async fn upload(&self, path: &Path, name: &str) -> Result<(), APIError> {
+        match self {
+            AutoBackupTarget::Dir(dir) => {
+                let tmp_path = dir.join(format!("{name}.tmp"));
+                tokio::fs::copy(path, &tmp_path).await?;
+                tokio::fs::rename(&tmp_path, dir.join(name)).await?;
+            }
+            AutoBackupTarget::Sftp { dir, .. } => {
+                let remote_path = format!("{dir}/{name}");
+                self.run_sftp(&format!(
+                    "put \"{}\" \"{remote_path}.tmp\"\nrename \"{remote_path}.tmp\" \"{remote_path}\"\n",
+                    path.display()
+                ))
+                .await?;
+            }
+            AutoBackupTarget::S3(s3) => {
+                // a PUT is atomic, the object is only visible once completely uploaded
+                let body = tokio::fs::read(path).await?;
+                s3.request(reqwest::Method::PUT, s3.object_url(name)?, body)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Names of the files on the target
+    pub(crate) async fn list(&self) -> Result<Vec<String>, APIError> {
+        match self {
+            AutoBackupTarget::Dir(dir) => Ok(fs::read_dir(dir)?
+                .filter_map(|e| e.ok())
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .collect()),
+            AutoBackupTarget::Sftp { dir, .. } => {
+                let output = self.run_sftp(&format!("ls -1 \"{dir}\"\n")).await?;
+                Ok(output
+                    .lines()
+                    .filter(|l| !l.starts_with("sftp>"))
+                    .filter_map(|l| l.trim().rsplit('/').next())
+                    .filter(|n| !n.is_empty())
+                    .map(|n| n.to_string())
+                    .collect())
+            }
+            AutoBackupTarget::S3(s3) => s3.list().await,
+        }
+    }
+
+    /// Delete the file with the given name from the target
+    pub(crate) async fn delete(&self, name: &str) -> Result<(), APIError> {
+        match self {
+            AutoBackupTarget::Dir(dir) => tokio::fs::remove_file(dir.join(name)).await?,
+            AutoBackupTarget::Sftp { dir, .. } => {
+                self.run_sftp(&format!("rm \"{dir}/{name}\"\n")).await?;
+            }
+            AutoBackupTarget::S3(s3) => {
+                s3.request(reqwest::Method::DELETE, s3.object_url(name)?, vec![])
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Run the given batch of commands with the sftp client, which must be able to authenticate
+    /// without prompting (e.g. with a key of the SSH agent or the user configuration)
+    async fn run_sftp(&self, batch: &str) -> Result<String, APIError> {
+        let AutoBackupTarget::Sftp {
+            destination, port, ..
+        } = self
+        else {
+            unreachable!("only called for SFTP targets");
+        };
+        let mut command = Command::new("sftp");
+        command.args(["-b", "-", "-o", "BatchMode=yes"]);
+        if let Some(port) = port {
+            command.args(["-P", &port.to_string()]);
+        }
+        let mut child = command
+            .arg(destination)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| APIError::Unexpected(format!("Failed to start sftp: {e}")))?;
+        let mut stdin = child.stdin.take().expect("piped stdin");
+        stdin.write_all(batch.as_bytes()).await?;
+        drop(stdin);
+        let output = child.wait_with_output().await?;
+        if !output.status.success() {
+            return Err(APIError::Unexpected(format!(
+                "sftp exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+impl S3Target {
+    fn object_url(&self, name: &str) -> Result<reqwest::Url, APIError> {
+        self.bucket_url
+            .join(name)
+            .map_err(|e| APIError::Unexpected(format!("Invalid S3 object URL: {e}")))
+    }
+
+    async fn list(&self) -> Result<Vec<String>, APIError> {
+        let mut bucket_url = self.bucket_url.clone();
+        let bucket_path = format!("/{}/", bucket_url.path_segments().unwrap().next().unwrap());
+        bucket_url.set_path(&bucket_path);
+
+        let mut names = vec![];
+        let mut continuation_token = None;
+        loop {
+            // the canonical query string of the signature has its params sorted by name
+            let mut params = vec![];
+            if let Some(token) = &continuation_token {
+                params.push(("continuation-token", uri_encode(token)));
+            }
+            params.push(("list-type", s!("2")));
+            params.push(("prefix", uri_encode(&self.prefix)));
+            let query = params
+                .iter()
+                .map(|(k, v)| format!("{k}={v}"))
+                .collect::<Vec<_>>()
+                .join("&");
+            let mut url = bucket_url.clone();
+            url.set_query(Some(&query));
+            let response = self.request(reqwest::Method::GET, url, vec![]).await?;
+
+            names.extend(xml_values(&response, "Key").into_iter().filter_map(|key| {
+                key.strip_prefix(&self.prefix)
+                    .filter(|n| !n.contains('/'))
+                    .map(|n| n.to_string())
+            }));
+            let truncated = xml_values(&response, "IsTruncated").first() == Some(&"true");
+            match xml_values(&response, "NextContinuationToken").first() {
+                Some(token) if truncated => continuation_token = Some(token.to_string()),
+                _ => break,
+            }
+        }
+        Ok(names)
+    }
+
+    /// Send a request authenticated with the AWS signature version 4, returning the response body
+    async fn request(
+        &self,
+        method: reqwest::Method,
+        url: reqwest::Url,
+        body: Vec<u8>,
+    ) -> Result<String, APIError> {
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let date = &amz_date[..8];
+        let payload_hash = hex_str(sha256::Hash::hash(&body).as_byte_array());
+        let host = match url.port() {
+            Some(port) => format!("{}:{port}", url.host_str().unwrap_or_default()),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+        let canonical_request = format!(
+            "{method}\n{}\n{}\nhost:{host}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{amz_date}\n\n{S3_SIGNED_HEADERS}\n{payload_hash}",
+            url.path(),
+            url.query().unwrap_or_default(),
+        );
+        let scope = format!("{date}/{}/s3/aws4_request", self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            hex_str(sha256::Hash::hash(canonical_request.as_bytes()).as_byte_array())
+        );
+        let mut signing_key = hmac_sha256(
+            format!("AWS4{}", self.secret_access_key).as_bytes(),
+            date.as_bytes(),
+        );
+        for part in [self.region.as_bytes(), b"s3", b"aws4_request"] {
+            signing_key = hmac_sha256(&signing_key, part);
+        }
+        let signature = hex_str(&hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={S3_SIGNED_HEADERS}, Signature={signature}",
+            self.access_key_id
+        );
+
+        let response = reqwest::Client::new()
+            .request(method, url)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", amz_date)
+            .header(reqwest::header::AUTHORIZATION, authorization)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| APIError::Unexpected(format!("Failed to reach the S3 endpoint: {e}")))?;
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(APIError::Unexpected(format!(
+                "S3 endpoint responded with {status}: {text}"
+            )));
+        }
+        Ok(text)
+    }
+}
+
+/// Name of a snapshot uploaded to the target, holding all the backed up files if full or only
+/// the ones changed since the previous snapshot otherwise
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct SnapshotName {
+    pub(crate) timestamp: u64,
+    pub(crate) full: bool,
+}
+
+impl SnapshotName {
+    pub(crate) fn parse(name: &str) -> Option<Self> {
+        let (timestamp, kind) = name.strip_prefix(SNAPSHOT_PREFIX)?.split_once('_')?;
+        let full = match kind {
+            SNAPSHOT_FULL => true,
+            SNAPSHOT_INCREMENTAL => false,
+            _ => return None,
+        };
+        Some(Self {
+            timestamp: timestamp.parse().ok()?,
+            full,
+        })
+    }
+}
+
+impl fmt::Display for SnapshotName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = if self.full {
+            SNAPSHOT_FULL
+        } else {
+            SNAPSHOT_INCREMENTAL
+        };
+        write!(f, "{SNAPSHOT_PREFIX}{}_{kind}", self.timestamp)
+    }
+}
+
+/// Backed up files at the time of a snapshot, by path relative to the snapshot root
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub(crate) struct AutoBackupManifest {
+    pub(crate) files: BTreeMap<String, AutoBackupFile>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct AutoBackupFile {
+    pub(crate) sha256: String,
+    /// Name of the snapshot holding the content of the file
+    pub(crate) snapshot: String,
+}
+
+/// Copy a directory, skipping the log files
+pub(crate) fn copy_dir(source: &Path, target: &Path) -> Result<(), APIError> {
+    fs::create_dir_all(target)?;
+    for entry in fs::read_dir(source)? {
+        let path = entry?.path();
+        let file_name = path.file_name().expect("read_dir entry");
+        if path.is_dir() {
+            if file_name != LOGS_DIR {
+                copy_dir(&path, &target.join(file_name))?;
+            }
+        } else if !path.ends_with("log") {
+            fs::copy(&path, target.join(file_name))?;
+        }
+    }
+    Ok(())
+}
+
+/// Write to `snapshot_dir` the files of `data_dir` changed since the previous snapshot (all of
+/// them for a full one), along with the manifest of the snapshot, which is also returned. Nothing
+/// is written and None is returned if no file changed.
+pub(crate) fn prepare_snapshot(
+    data_dir: &Path,
+    snapshot_dir: &Path,
+    snapshot: &SnapshotName,
+    previous: Option<&AutoBackupManifest>,
+) -> Result<Option<AutoBackupManifest>, APIError> {
+    let mut manifest = AutoBackupManifest::default();
+    let mut changed = false;
+    for (relative_path, checksum) in dir_checksums(data_dir)? {
+        let name = relative_path
+            .iter()
+            .map(|c| c.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let sha256 = hex_str(checksum.as_byte_array());
+        let previous_file = previous
+            .and_then(|p| p.files.get(&name))
+            .filter(|f| f.sha256 == sha256);
+        let file = match previous_file {
+            Some(f) if !snapshot.full => f.clone(),
+            _ => {
+                changed |= previous_file.is_none();
+                let target_path = snapshot_dir.join(&relative_path);
+                fs::create_dir_all(target_path.parent().expect("inside snapshot dir"))?;
+                fs::copy(data_dir.join(&relative_path), target_path)?;
+                AutoBackupFile {
+                    sha256,
+                    snapshot: snapshot.to_string(),
+                }
+            }
+        };
+        manifest.files.insert(name, file);
+    }
+    // deleted files also make a new snapshot needed
+    let removed = previous.is_some_and(|p| p.files.keys().any(|k| !manifest.files.contains_key(k)));
+    if previous.is_some() && !changed && !removed {
+        return Ok(None);
+    }
+    fs::write(
+        snapshot_dir.join(AUTO_BACKUP_MANIFEST_FNAME),
+        serde_json::to_string_pretty(&manifest).unwrap(),
+    )?;
+    Ok(Some(manifest))
+}
@@ -7,6 +7,7 @@ use scrypt::Scrypt;
 use tempfile::TempDir;
 use typenum::consts::U32;
 use walkdir::WalkDir;
+use zeroize::Zeroize;
 use zip::write::SimpleFileOptions;
 
 use std::fs::{create_dir_all, read_to_string, remove_file, write, File};
@@ -36,6 +37,27 @@ struct CypherSecrets {
     nonce: [u8; BACKUP_NONCE_LENGTH],
 }
 
+/// Key derived from a password, so that several backups can be encrypted without hashing it
+/// again
+pub(crate) struct BackupKey {
+    key: GenericArray<u8, U32>,
+    salt: String,
+}
+
+impl Drop for BackupKey {
+    fn drop(&mut self) {
+        self.key.as_mut_slice().zeroize();
+    }
+}
+
+/// Derive a backup key from the provided password, with a randomly generated salt
+pub(crate) fn derive_backup_key(password: &str) -> Result<BackupKey, APIError> {
+    let salt = _random_alphanumeric(BACKUP_KEY_LENGTH);
+    tracing::debug!("using generated salt: {}", &salt);
+    let key = _get_cypher_key(password, &salt)?;
+    Ok(BackupKey { key, salt })
+}
+
 /// Create a backup of the wallet as a file with the provided name and encrypted with the
 /// provided password.
 ///
@@ -52,37 +74,45 @@ pub(crate) fn do_backup(
     if backup_file.exists() {
         Err(APIError::InvalidBackupPath)?;
     }
+    let backup_key = derive_backup_key(password)?;
+
+    encrypt_dir(wallet_dir, backup_file, &backup_key)?;
+
+    tracing::info!("backup completed");
+    Ok(())
+}
+
+/// Zip the provided directory and encrypt it with an already derived key, writing it to the
+/// given file in the backup format (an existing file gets overwritten)
+pub(crate) fn encrypt_dir(
+    dir: &Path,
+    backup_file: &Path,
+    backup_key: &BackupKey,
+) -> Result<(), APIError> {
     let tmp_base_path = _get_parent_path(backup_file)?;
     let files = _get_backup_paths(&tmp_base_path)?;
-    let salt: String = rand::thread_rng()
-        .sample_iter(&Alphanumeric)
-        .take(BACKUP_KEY_LENGTH)
-        .map(char::from)
-        .collect();
-    tracing::debug!("using generated salt: {}", &salt);
-    let nonce: String = rand::thread_rng()
-        .sample_iter(&Alphanumeric)
-        .take(BACKUP_NONCE_LENGTH)
-        .map(char::from)
-        .collect();
+    let nonce = _random_alphanumeric(BACKUP_NONCE_LENGTH);
     tracing::debug!("using generated nonce: {}", &nonce);
+    let cypher_secrets = CypherSecrets {
+        key: backup_key.key,
+        nonce: _get_nonce(&nonce)?,
+    };
 
-    // create zip archive of wallet data
-    tracing::debug!("\nzipping {:?} to {:?}", &wallet_dir, &files.zip);
-    _zip_dir(wallet_dir, &files.zip)?;
+    // create zip archive of the directory
+    tracing::debug!("\nzipping {:?} to {:?}", &dir, &files.zip);
+    _zip_dir(dir, &files.zip)?;
 
     // encrypt the backup file
     tracing::debug!("\nencrypting {:?} to {:?}", &files.zip, &files.encrypted);
-    _encrypt_file(&files.zip, &files.encrypted, password, &salt, &nonce)?;
+    _encrypt_file(&files.zip, &files.encrypted, &cypher_secrets)?;
 
     // add backup nonce + salt + version to final zip file
     write(files.nonce, nonce)?;
-    write(files.salt, salt)?;
+    write(files.salt, &backup_key.salt)?;
     write(files.version, BACKUP_VERSION.to_string())?;
     tracing::debug!("\nzipping {:?} to {:?}", &files.tempdir, &backup_file);
     _zip_dir(files.tempdir.path(), backup_file)?;
 
-    tracing::info!("backup completed");
     Ok(())
 }
 
@@ -246,11 +276,15 @@ fn _unzip(zip_path: &PathBuf, path_out: &Path) -> Result<(), APIError> {
     Ok(())
 }
 
-fn _get_cypher_secrets(
-    password: &str,
-    salt_str: &str,
-    nonce_str: &str,
-) -> Result<CypherSecrets, APIError> {
+fn _random_alphanumeric(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+fn _get_cypher_key(password: &str, salt_str: &str) -> Result<GenericArray<u8, U32>, APIError> {
     // hash password using scrypt with the provided salt
     let password_bytes = password.as_bytes();
     let salt = Salt::from_b64(salt_str)
@@ -264,13 +298,23 @@ fn _get_cypher_secrets(
     let hash = hash_output.as_bytes();
 
     // get key from password hash
-    let key = Key::clone_from_slice(&hash[..BACKUP_KEY_LENGTH]);
+    Ok(Key::clone_from_slice(&hash[..BACKUP_KEY_LENGTH]))
+}
 
-    // get nonce from provided str
+fn _get_nonce(nonce_str: &str) -> Result<[u8; BACKUP_NONCE_LENGTH], APIError> {
     let nonce_bytes = nonce_str.as_bytes();
-    let nonce: [u8; BACKUP_NONCE_LENGTH] = nonce_bytes[0..BACKUP_NONCE_LENGTH]
+    nonce_bytes[0..BACKUP_NONCE_LENGTH]
         .try_into()
-        .map_err(|e| APIError::Unexpected(format!("Failed to get nonce: {e}")))?;
+        .map_err(|e| APIError::Unexpected(format!("Failed to get nonce: {e}")))
+}
+
+fn _get_cypher_secrets(
+    password: &str,
+    salt_str: &str,
+    nonce_str: &str,
+) -> Result<CypherSecrets, APIError> {
+    let key = _get_cypher_key(password, salt_str)?;
+    let nonce = _get_nonce(nonce_str)?;
 
     Ok(CypherSecrets { key, nonce })
 }
@@ -278,12 +322,8 @@ fn _get_cypher_secrets(
 fn _encrypt_file(
     path_cleartext: &PathBuf,
     path_encrypted: &PathBuf,
-    password: &str,
-    salt_str: &str,
-    nonce_str: &str,
+    cypher_secrets: &CypherSecrets,
 ) -> Result<(), APIError> {
-    let cypher_secrets = _get_cypher_secrets(password, salt_str, nonce_str)?;
-
     // - XChacha20Poly1305 is fast, requires no special hardware and supports stream operation
     // - stream mode required as files to encrypt may be big, so avoiding a memory buffer
 
@@ -0,0 +1,722 @@
+use amplify::s;
+use anyhow::{anyhow, Context, Result};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde_json::{json, Map, Value};
+use std::path::PathBuf;
+
+const DEFAULT_NODE_URL: &str = "http://127.0.0.1:3001";
+const DEFAULT_INVOICE_EXPIRY_SEC: u32 = 3600;
+const DEFAULT_FEE_RATE: u64 = 7;
+const DEFAULT_MIN_CONFIRMATIONS: u8 = 1;
+
+/// Version prefix of the APIs called, the one returning paginated lists and structured errors
+const API_VERSION_PREFIX: &str = "/v2";
+
+/// Format of the command results printed to stdout
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    #[default]
+    Json,
+    Table,
+}
+
+/// Command line client for the rgb-lightning-node APIs
+#[derive(Parser)]
+#[command(name = "rln-cli", version, about, long_about = None)]
+struct Cli {
+    /// Base URL of the node APIs, including the URL prefix if the node uses one
+    #[arg(long, global = true, env = "RLN_CLI_NODE_URL", default_value = DEFAULT_NODE_URL)]
+    node_url: String,
+
+    /// Biscuit token to authenticate to the node with
+    #[arg(
+        long,
+        global = true,
+        env = "RLN_CLI_TOKEN",
+        hide_env_values = true,
+        conflicts_with = "token_file"
+    )]
+    token: Option<String>,
+
+    /// File containing the Biscuit token to authenticate to the node with
+    #[arg(long, global = true, env = "RLN_CLI_TOKEN_FILE")]
+    token_file: Option<PathBuf>,
+
+    /// PEM certificate to trust when connecting to the node over HTTPS (e.g. a self-signed one)
+    #[arg(long, global = true, env = "RLN_CLI_TLS_CA_CERT")]
+    tls_ca_cert: Option<PathBuf>,
+
+    /// Format of the printed results
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Json)]
+    output: OutputFormat,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// Pagination of the list commands
+#[derive(Args)]
+struct PageArgs {
+    /// Max number of items to return
+    #[arg(long)]
+    limit: Option<usize>,
+    /// The next_cursor of the previous page
+    #[arg(long)]
+    cursor: Option<String>,
+}
+
+impl PageArgs {
+    fn query(&self) -> Vec<(&'static str, String)> {
+        let mut query = vec![];
+        if let Some(limit) = self.limit {
+            query.push(("limit", limit.to_string()));
+        }
+        if let Some(cursor) = &self.cursor {
+            query.push(("cursor", cursor.clone()));
+        }
+        query
+    }
+}
+
+#[derive(Subcommand)]
+#[command(rename_all = "lower")]
+enum Command {
+    /// Initialize the node
+    Init {
+        #[arg(long, env = "RLN_CLI_PASSWORD", hide_env_values = true)]
+        password: String,
+        /// Mnemonic to restore, a new one is generated if not given
+        #[arg(long)]
+        mnemonic: Option<String>,
+        /// BIP39 passphrase of the LDK keys, requiring --ldk-keys-only
+        #[arg(long, env = "RLN_CLI_PASSPHRASE", hide_env_values = true)]
+        passphrase: Option<String>,
+        /// Account index of the LDK keys, requiring --ldk-keys-only
+        #[arg(long)]
+        account_index: Option<u32>,
+        /// Only derive the LDK keys with the passphrase and account index
+        #[arg(long)]
+        ldk_keys_only: bool,
+    },
+    /// Unlock the node
+    Unlock {
+        #[arg(long, env = "RLN_CLI_PASSWORD", hide_env_values = true)]
+        password: String,
+        #[arg(long)]
+        bitcoind_rpc_username: String,
+        #[arg(long, env = "RLN_CLI_BITCOIND_RPC_PASSWORD", hide_env_values = true)]
+        bitcoind_rpc_password: String,
+        #[arg(long)]
+        bitcoind_rpc_host: String,
+        #[arg(long)]
+        bitcoind_rpc_port: Option<u16>,
+        #[arg(long)]
+        indexer_url: Option<String>,
+        #[arg(long)]
+        proxy_endpoint: Option<String>,
+        /// Address to announce to the network, can be repeated
+        #[arg(long = "announce-address")]
+        announce_addresses: Vec<String>,
+        #[arg(long)]
+        announce_alias: Option<String>,
+    },
+    /// Lock the node
+    Lock,
+    /// Show the node info
+    NodeInfo,
+    /// Show the network info
+    NetworkInfo,
+    /// Get a new on-chain address
+    Address,
+    /// Show the BTC balance
+    BtcBalance {
+        #[arg(long)]
+        skip_sync: bool,
+    },
+    /// Create a LN invoice
+    LnInvoice {
+        #[arg(long)]
+        amt_msat: Option<u64>,
+        #[arg(long, default_value_t = DEFAULT_INVOICE_EXPIRY_SEC)]
+        expiry_sec: u32,
+        #[arg(long, requires = "asset_amount")]
+        asset_id: Option<String>,
+        #[arg(long, requires = "asset_id")]
+        asset_amount: Option<u64>,
+    },
+    /// Decode a LN invoice
+    DecodeLnInvoice { invoice: String },
+    /// Show the status of a LN invoice
+    InvoiceStatus { invoice: String },
+    /// Pay a LN invoice
+    SendPayment {
+        invoice: String,
+        /// Amount to pay, for invoices that don't specify one
+        #[arg(long)]
+        amt_msat: Option<u64>,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Send a spontaneous payment
+    Keysend {
+        dest_pubkey: String,
+        #[arg(long)]
+        amt_msat: u64,
+        #[arg(long, requires = "asset_amount")]
+        asset_id: Option<String>,
+        #[arg(long, requires = "asset_id")]
+        asset_amount: Option<u64>,
+    },
+    /// List the LN payments
+    ListPayments {
+        #[command(flatten)]
+        page: PageArgs,
+    },
+    /// Show a LN payment
+    GetPayment { payment_hash: String },
+    /// Connect to a peer (pubkey@host:port)
+    ConnectPeer { peer_pubkey_and_addr: String },
+    /// Disconnect from a peer
+    DisconnectPeer { peer_pubkey: String },
+    /// List the connected peers
+    ListPeers {
+        #[command(flatten)]
+        page: PageArgs,
+    },
+    /// Open a channel (pubkey[@host:port])
+    OpenChannel {
+        peer_pubkey_and_opt_addr: String,
+        #[arg(long)]
+        capacity_sat: u64,
+        #[arg(long, default_value_t = 0)]
+        push_msat: u64,
+        #[arg(long, requires = "asset_amount")]
+        asset_id: Option<String>,
+        #[arg(long, requires = "asset_id")]
+        asset_amount: Option<u64>,
+        /// Open a private (unannounced) channel
+        #[arg(long)]
+        private: bool,
+        /// Open a channel without anchor outputs
+        #[arg(long)]
+        no_anchors: bool,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Close a channel
+    CloseChannel {
+        channel_id: String,
+        peer_pubkey: String,
+        #[arg(long)]
+        force: bool,
+    },
+    /// List the channels
+    ListChannels {
+        #[command(flatten)]
+        page: PageArgs,
+    },
+    /// List the RGB assets
+    ListAssets {
+        #[command(flatten)]
+        page: PageArgs,
+    },
+    /// Show the balance of an RGB asset
+    AssetBalance { asset_id: String },
+    /// Issue an RGB NIA asset
+    IssueAssetNia {
+        #[arg(long)]
+        ticker: String,
+        #[arg(long)]
+        name: String,
+        #[arg(long, default_value_t = 0)]
+        precision: u8,
+        /// Amount to issue, can be repeated to issue to several allocations
+        #[arg(long = "amount", required = true)]
+        amounts: Vec<u64>,
+    },
+    /// Create an RGB invoice
+    RgbInvoice {
+        #[arg(long)]
+        asset_id: Option<String>,
+        #[arg(long)]
+        amount: Option<u64>,
+        /// Receive to a witness (instead of a blinded) UTXO
+        #[arg(long)]
+        witness: bool,
+        #[arg(long, default_value_t = DEFAULT_MIN_CONFIRMATIONS)]
+        min_confirmations: u8,
+    },
+    /// Send an RGB asset on-chain
+    SendAsset {
+        asset_id: String,
+        recipient_id: String,
+        #[arg(long)]
+        amount: u64,
+        /// Transport endpoint of the recipient, can be repeated
+        #[arg(long = "transport-endpoint", required = true)]
+        transport_endpoints: Vec<String>,
+        /// BTC amount to send along, required for witness recipients
+        #[arg(long)]
+        witness_amount_sat: Option<u64>,
+        #[arg(long, default_value_t = DEFAULT_FEE_RATE)]
+        fee_rate: u64,
+        #[arg(long, default_value_t = DEFAULT_MIN_CONFIRMATIONS)]
+        min_confirmations: u8,
+        #[arg(long)]
+        donation: bool,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// List the transfers of an RGB asset
+    ListTransfers {
+        asset_id: String,
+        #[command(flatten)]
+        page: PageArgs,
+    },
+    /// Call any API, with a POST if a JSON body is given and a GET otherwise
+    Call {
+        /// API path, without the version prefix (e.g. /listunspents)
+        path: String,
+        /// JSON request body
+        #[arg(long)]
+        data: Option<String>,
+    },
+}
+
+/// Request to send to the node, with the API path, the query of GET requests and the JSON body
+/// of POST requests
+struct ApiRequest {
+    path: String,
+    query: Vec<(&'static str, String)>,
+    body: Option<Value>,
+}
+
+impl ApiRequest {
+    fn get(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            query: vec![],
+            body: None,
+        }
+    }
+
+    fn get_page(path: &str, page: &PageArgs) -> Self {
+        Self {
+            query: page.query(),
+            ..Self::get(path)
+        }
+    }
+
+    fn post(path: &str, body: Value) -> Self {
+        Self {
+            path: path.to_string(),
+            query: vec![],
+            body: Some(body),
+        }
+    }
+}
+
+impl Command {
+    fn into_request(self) -> Result<ApiRequest> {
+        Ok(match self {
+            Command::Init {
+                password,
+                mnemonic,
+                passphrase,
+                account_index,
+                ldk_keys_only,
+            } => ApiRequest::post(
+                "/init",
+                json!({
+                    "password": password,
+                    "mnemonic": mnemonic,
+                    "passphrase": passphrase,
+                    "account_index": account_index,
+                    "ldk_keys_only": ldk_keys_only,
+                }),
+            ),
+            Command::Unlock {
+                password,
+                bitcoind_rpc_username,
+                bitcoind_rpc_password,
+                bitcoind_rpc_host,
+                bitcoind_rpc_port,
+                indexer_url,
+                proxy_endpoint,
+                announce_addresses,
+                announce_alias,
+            } => ApiRequest::post(
+                "/unlock",
+                json!({
+                    "password": password,
+                    "bitcoind_rpc_username": bitcoind_rpc_username,
+                    "bitcoind_rpc_password": bitcoind_rpc_password,
+                    "bitcoind_rpc_host": bitcoind_rpc_host,
+                    "bitcoind_rpc_port": bitcoind_rpc_port,
+                    "indexer_url": indexer_url,
+                    "proxy_endpoint": proxy_endpoint,
+                    "announce_addresses": announce_addresses,
+                    "announce_alias": announce_alias,
+                }),
+            ),
+            Command::Lock => ApiRequest::post("/lock", json!({})),
+            Command::NodeInfo => ApiRequest::get("/nodeinfo"),
+            Command::NetworkInfo => ApiRequest::get("/networkinfo"),
+            Command::Address => ApiRequest::post("/address", json!({})),
+            Command::BtcBalance { skip_sync } => {
+                ApiRequest::post("/btcbalance", json!({ "skip_sync": skip_sync }))
+            }
+            Command::LnInvoice {
+                amt_msat,
+                expiry_sec,
+                asset_id,
+                asset_amount,
+            } => ApiRequest::post(
+                "/lninvoice",
+                json!({
+                    "amt_msat": amt_msat,
+                    "expiry_sec": expiry_sec,
+                    "asset_id": asset_id,
+                    "asset_amount": asset_amount,
+                    "settle_after_expiry": false,
+                }),
+            ),
+            Command::DecodeLnInvoice { invoice } => {
+                ApiRequest::post("/decodelninvoice", json!({ "invoice": invoice }))
+            }
+            Command::InvoiceStatus { invoice } => {
+                ApiRequest::post("/invoicestatus", json!({ "invoice": invoice }))
+            }
+            Command::SendPayment {
+                invoice,
+                amt_msat,
+                dry_run,
+            } => ApiRequest::post(
+                "/sendpayment",
+                json!({ "invoice": invoice, "amt_msat": amt_msat, "dry_run": dry_run }),
+            ),
+            Command::Keysend {
+                dest_pubkey,
+                amt_msat,
+                asset_id,
+                asset_amount,
+            } => ApiRequest::post(
+                "/keysend",
+                json!({
+                    "dest_pubkey": dest_pubkey,
+                    "amt_msat": amt_msat,
+                    "asset_id": asset_id,
+                    "asset_amount": asset_amount,
+                }),
+            ),
+            Command::ListPayments { page } => ApiRequest::get_page("/listpayments", &page),
+            Command::GetPayment { payment_hash } => {
+                ApiRequest::post("/getpayment", json!({ "payment_hash": payment_hash }))
+            }
+            Command::ConnectPeer {
+                peer_pubkey_and_addr,
+            } => ApiRequest::post(
+                "/connectpeer",
+                json!({ "peer_pubkey_and_addr": peer_pubkey_and_addr }),
+            ),
+            Command::DisconnectPeer { peer_pubkey } => {
+                ApiRequest::post("/disconnectpeer", json!({ "peer_pubkey": peer_pubkey }))
+            }
+            Command::ListPeers { page } => ApiRequest::get_page("/listpeers", &page),
+            Command::OpenChannel {
+                peer_pubkey_and_opt_addr,
+                capacity_sat,
+                push_msat,
+                asset_id,
+                asset_amount,
+                private,
+                no_anchors,
+                dry_run,
+            } => ApiRequest::post(
+                "/openchannel",
+                json!({
+                    "peer_pubkey_and_opt_addr": peer_pubkey_and_opt_addr,
+                    "capacity_sat": capacity_sat,
+                    "push_msat": push_msat,
+                    "asset_id": asset_id,
+                    "asset_amount": asset_amount,
+                    "public": !private,
+                    "with_anchors": !no_anchors,
+                    "dry_run": dry_run,
+                }),
+            ),
+            Command::CloseChannel {
+                channel_id,
+                peer_pubkey,
+                force,
+            } => ApiRequest::post(
+                "/closechannel",
+                json!({ "channel_id": channel_id, "peer_pubkey": peer_pubkey, "force": force }),
+            ),
+            Command::ListChannels { page } => ApiRequest::get_page("/listchannels", &page),
+            Command::ListAssets { page } => ApiRequest::post(
+                "/listassets",
+                json!({
+                    "filter_asset_schemas": ["Nia", "Uda", "Cfa", "Ifa"],
+                    "limit": page.limit,
+                    "cursor": page.cursor,
+                }),
+            ),
+            Command::AssetBalance { asset_id } => {
+                ApiRequest::post("/assetbalance", json!({ "asset_id": asset_id }))
+            }
+            Command::IssueAssetNia {
+                ticker,
+                name,
+                precision,
+                amounts,
+            } => ApiRequest::post(
+                "/issueassetnia",
+                json!({
+                    "amounts": amounts,
+                    "ticker": ticker,
+                    "name": name,
+                    "precision": precision,
+                }),
+            ),
+            Command::RgbInvoice {
+                asset_id,
+                amount,
+                witness,
+                min_confirmations,
+            } => {
+                let assignment = amount.map(|value| json!({ "type": "Fungible", "value": value }));
+                ApiRequest::post(
+                    "/rgbinvoice",
+                    json!({
+                        "asset_id": asset_id,
+                        "assignment": assignment,
+                        "min_confirmations": min_confirmations,
+                        "witness": witness,
+                    }),
+                )
+            }
+            Command::SendAsset {
+                asset_id,
+                recipient_id,
+                amount,
+                transport_endpoints,
+                witness_amount_sat,
+                fee_rate,
+                min_confirmations,
+                donation,
+                dry_run,
+            } => {
+                let witness_data = witness_amount_sat
+                    .map(|amount_sat| json!({ "amount_sat": amount_sat, "blinding": null }));
+                ApiRequest::post(
+                    "/sendasset",
+                    json!({
+                        "asset_id": asset_id,
+                        "assignment": { "type": "Fungible", "value": amount },
+                        "recipient_id": recipient_id,
+                        "witness_data": witness_data,
+                        "donation": donation,
+                        "fee_rate": fee_rate,
+                        "min_confirmations": min_confirmations,
+                        "transport_endpoints": transport_endpoints,
+                        "skip_sync": false,
+                        "hold_for_approval": false,
+                        "dry_run": dry_run,
+                    }),
+                )
+            }
+            Command::ListTransfers { asset_id, page } => ApiRequest::post(
+                "/listtransfers",
+                json!({ "asset_id": asset_id, "limit": page.limit, "cursor": page.cursor }),
+            ),
+            Command::Call { path, data } => {
+                let path = format!("/{}", path.trim_start_matches('/'));
+                match data {
+                    Some(data) => {
+                        let body = serde_json::from_str(&data).context("invalid JSON body")?;
+                        ApiRequest::post(&path, body)
+                    }
+                    None => ApiRequest::get(&path),
+                }
+            }
+        })
+    }
+}
+
+fn read_token(cli: &Cli) -> Result<Option<String>> {
+    if let Some(token) = &cli.token {
+        return Ok(Some(token.trim().to_string()));
+    }
+    if let Some(token_file) = &cli.token_file {
+        let token = std::fs::read_to_string(token_file)
+            .with_context(|| format!("cannot read token file {}", token_file.display()))?;
+        return Ok(Some(token.trim().to_string()));
+    }
+    Ok(None)
+}
+
+fn build_http_client(tls_ca_cert: Option<&PathBuf>) -> Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder();
+    if let Some(path) = tls_ca_cert {
+        let pem = std::fs::read(path)
+            .with_context(|| format!("cannot read certificate {}", path.display()))?;
+        let cert = reqwest::Certificate::from_pem(&pem).context("invalid PEM certificate")?;
+        builder = builder.add_root_certificate(cert);
+    }
+    Ok(builder.build()?)
+}
+
+async fn send_request(
+    client: &reqwest::Client,
+    node_url: &str,
+    token: Option<&str>,
+    request: ApiRequest,
+) -> Result<Value> {
+    let url = format!(
+        "{}{API_VERSION_PREFIX}{}",
+        node_url.trim_end_matches('/'),
+        request.path
+    );
+    let mut builder = match &request.body {
+        Some(body) => client.post(&url).json(body),
+        None => client.get(&url).query(&request.query),
+    };
+    if let Some(token) = token {
+        builder = builder.bearer_auth(token);
+    }
+    let res = builder
+        .send()
+        .await
+        .with_context(|| format!("cannot reach the node at {url}"))?;
+    let status = res.status();
+    let text = res.text().await?;
+    if !status.is_success() {
+        let error = match serde_json::from_str::<Value>(&text) {
+            Ok(Value::Object(err)) if err.contains_key("message") => format!(
+                "{} ({}): {}",
+                err.get("code").and_then(Value::as_str).unwrap_or("Error"),
+                status.as_u16(),
+                err["message"].as_str().unwrap_or_default(),
+            ),
+            _ => format!("{status}: {text}"),
+        };
+        return Err(anyhow!(error));
+    }
+    if text.is_empty() {
+        return Ok(Value::Null);
+    }
+    Ok(serde_json::from_str(&text)?)
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => s!("-"),
+        Value::String(string) => string.clone(),
+        _ => value.to_string(),
+    }
+}
+
+fn render_table(header: &[String], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+    let format_row = |row: &[String]| {
+        row.iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{value:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    let mut lines = vec![format_row(header)];
+    lines.push(
+        widths
+            .iter()
+            .map(|w| "-".repeat(*w))
+            .collect::<Vec<_>>()
+            .join("  "),
+    );
+    lines.extend(rows.iter().map(|row| format_row(row)));
+    lines.join("\n")
+}
+
+/// Render a list of objects with a column per field, in order of first appearance
+fn render_list(items: &[Value]) -> String {
+    let mut columns: Vec<String> = vec![];
+    for item in items {
+        if let Value::Object(fields) = item {
+            for key in fields.keys() {
+                if !columns.contains(key) {
+                    columns.push(key.clone());
+                }
+            }
+        }
+    }
+    if columns.is_empty() {
+        return render_table(
+            &[s!("value")],
+            &items.iter().map(|i| vec![cell(i)]).collect::<Vec<_>>(),
+        );
+    }
+    let rows = items
+        .iter()
+        .map(|item| {
+            columns
+                .iter()
+                .map(|c| cell(item.get(c).unwrap_or(&Value::Null)))
+                .collect()
+        })
+        .collect::<Vec<_>>();
+    render_table(&columns, &rows)
+}
+
+/// Render an object as key/value rows
+fn render_object(fields: &Map<String, Value>) -> String {
+    let rows = fields
+        .iter()
+        .map(|(key, value)| vec![key.clone(), cell(value)])
+        .collect::<Vec<_>>();
+    render_table(&[s!("field"), s!("value")], &rows)
+}
+
+/// Render the response as a table: list responses (the page of `items` returned by the list APIs
+/// or a single field holding an array) get a row per item, other objects a row per field
+fn render_output(value: &Value) -> String {
+    match value {
+        Value::Array(items) => render_list(items),
+        Value::Object(fields) => match (fields.get("items"), fields.values().next()) {
+            (Some(Value::Array(items)), _) => render_list(items),
+            (_, Some(Value::Array(items))) if fields.len() == 1 => render_list(items),
+            _ => render_object(fields),
+        },
+        Value::Null => String::new(),
+        _ => cell(value),
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let token = read_token(&cli)?;
+    let client = build_http_client(cli.tls_ca_cert.as_ref())?;
+    let output = cli.output;
+    let request = cli.command.into_request()?;
+    let res = send_request(&client, &cli.node_url, token.as_deref(), request).await?;
+    match output {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&res)?),
+        OutputFormat::Table => println!("{}", render_output(&res)),
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = run(cli).await {
+        eprintln!("Error: {e:#}");
+        std::process::exit(1);
+    }
+}
@@ -1,7 +1,9 @@
 use base64::{engine::general_purpose, Engine as _};
-use bitcoin::blockdata::transaction::Transaction;
+use bitcoin::blockdata::block::Block;
+use bitcoin::blockdata::transaction::{OutPoint, Transaction, TxOut};
 use bitcoin::consensus::encode;
-use bitcoin::hash_types::BlockHash;
+use bitcoin::hash_types::{BlockHash, Txid};
+use bitcoin::{Amount, ScriptBuf};
 use lightning::chain::chaininterface::{BroadcasterInterface, ConfirmationTarget, FeeEstimator};
 use lightning::log_warn;
 use lightning::util::logger::Logger;
@@ -73,6 +75,24 @@ impl TryInto<MempoolMinFeeResponse> for JsonResponse {
     }
 }
 
+pub struct MempoolEntryResponse {
+    pub vsize: u64,
+    pub fee_sat: u64,
+}
+
+impl TryInto<MempoolEntryResponse> for JsonResponse {
+    type Error = std::io::Error;
+    fn try_into(self) -> std::io::Result<MempoolEntryResponse> {
+        let invalid = || std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid entry");
+        let vsize = self.0["vsize"].as_u64().ok_or_else(invalid)?;
+        let fee_btc = self.0["fees"]["base"].as_f64().ok_or_else(invalid)?;
+        Ok(MempoolEntryResponse {
+            vsize,
+            fee_sat: (fee_btc * 100_000_000.0).round() as u64,
+        })
+    }
+}
+
 pub struct BlockchainInfo {
     pub latest_height: usize,
     pub latest_blockhash: BlockHash,
@@ -91,6 +111,76 @@ impl TryInto<BlockchainInfo> for JsonResponse {
     }
 }
 
+pub struct BlockHashResponse(pub BlockHash);
+
+impl TryInto<BlockHashResponse> for JsonResponse {
+    type Error = std::io::Error;
+    fn try_into(self) -> std::io::Result<BlockHashResponse> {
+        let block_hash = self
+            .0
+            .as_str()
+            .and_then(|h| BlockHash::from_str(h).ok())
+            .ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid block hash")
+            })?;
+        Ok(BlockHashResponse(block_hash))
+    }
+}
+
+pub struct RawTransactionResponse(pub Transaction);
+
+impl TryInto<RawTransactionResponse> for JsonResponse {
+    type Error = std::io::Error;
+    fn try_into(self) -> std::io::Result<RawTransactionResponse> {
+        let tx = self
+            .0
+            .as_str()
+            .and_then(|h| encode::deserialize_hex(h).ok())
+            .ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid raw transaction")
+            })?;
+        Ok(RawTransactionResponse(tx))
+    }
+}
+
+/// Unspent transaction output in the UTXO set of the chain tip
+pub struct ChainTxOutResponse(pub Option<TxOut>);
+
+impl TryInto<ChainTxOutResponse> for JsonResponse {
+    type Error = std::io::Error;
+    fn try_into(self) -> std::io::Result<ChainTxOutResponse> {
+        if self.0.is_null() {
+            return Ok(ChainTxOutResponse(None));
+        }
+        let invalid = || std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid txout");
+        let value_btc = self.0["value"].as_f64().ok_or_else(invalid)?;
+        let script_pubkey = self.0["scriptPubKey"]["hex"]
+            .as_str()
+            .and_then(|h| ScriptBuf::from_hex(h).ok())
+            .ok_or_else(invalid)?;
+        Ok(ChainTxOutResponse(Some(TxOut {
+            value: Amount::from_sat((value_btc * 100_000_000.0).round() as u64),
+            script_pubkey,
+        })))
+    }
+}
+
+/// Confirmations of an unspent transaction output, if it exists and hasn't been spent
+pub struct TxOutResponse(pub Option<u32>);
+
+impl TryInto<TxOutResponse> for JsonResponse {
+    type Error = std::io::Error;
+    fn try_into(self) -> std::io::Result<TxOutResponse> {
+        if self.0.is_null() {
+            return Ok(TxOutResponse(None));
+        }
+        let confirmations = self.0["confirmations"]
+            .as_u64()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid txout"))?;
+        Ok(TxOutResponse(Some(confirmations as u32)))
+    }
+}
+
 pub struct FeeResponse {
     pub feerate_sat_per_kw: Option<u32>,
     pub errored: bool,
@@ -301,6 +391,108 @@ impl BitcoindClient {
             .await
             .unwrap()
     }
+
+    /// Get a transaction without requiring bitcoind's txindex, by providing the hash of the block
+    /// including it (derived from the given height) or looking it up in the mempool
+    pub(crate) async fn get_raw_transaction(
+        &self,
+        txid: &Txid,
+        height: Option<u32>,
+    ) -> Option<Transaction> {
+        let mut params = vec![
+            serde_json::json!(txid.to_string()),
+            serde_json::json!(false),
+        ];
+        if let Some(height) = height {
+            let block_hash = self
+                .bitcoind_rpc_client
+                .call_method::<BlockHashResponse>("getblockhash", &[serde_json::json!(height)])
+                .await
+                .ok()?;
+            params.push(serde_json::json!(block_hash.0.to_string()));
+        }
+        self.bitcoind_rpc_client
+            .call_method::<RawTransactionResponse>("getrawtransaction", &params)
+            .await
+            .ok()
+            .map(|r| r.0)
+    }
+
+    /// Get the block at the given height, or None if the chain is not that long yet
+    pub(crate) async fn get_block_at_height(&self, height: u32) -> Option<Block> {
+        let block_hash = self
+            .bitcoind_rpc_client
+            .call_method::<BlockHashResponse>("getblockhash", &[serde_json::json!(height)])
+            .await
+            .ok()?;
+        match self
+            .bitcoind_rpc_client
+            .get_block(&block_hash.0)
+            .await
+            .ok()?
+        {
+            BlockData::FullBlock(block) => Some(block),
+            BlockData::HeaderOnly(_) => None,
+        }
+    }
+
+    /// Get the mempool entry of a transaction, or None if it's not in the mempool
+    pub(crate) async fn get_mempool_entry(&self, txid: &Txid) -> Option<MempoolEntryResponse> {
+        self.bitcoind_rpc_client
+            .call_method::<MempoolEntryResponse>(
+                "getmempoolentry",
+                &[serde_json::json!(txid.to_string())],
+            )
+            .await
+            .ok()
+    }
+
+    /// Get an output spent by a transaction in the mempool, from its parent if unconfirmed or
+    /// from the UTXO set of the chain tip otherwise
+    pub(crate) async fn get_spent_txout(&self, outpoint: &OutPoint) -> Option<TxOut> {
+        if let Some(parent) = self.get_raw_transaction(&outpoint.txid, None).await {
+            return parent.output.get(outpoint.vout as usize).cloned();
+        }
+        let params = [
+            serde_json::json!(outpoint.txid.to_string()),
+            serde_json::json!(outpoint.vout),
+            serde_json::json!(false),
+        ];
+        self.bitcoind_rpc_client
+            .call_method::<ChainTxOutResponse>("gettxout", &params)
+            .await
+            .ok()
+            .and_then(|r| r.0)
+    }
+
+    /// Broadcast a transaction, failing with the bitcoind error if it's refused
+    pub(crate) async fn send_raw_transaction(&self, tx: &Transaction) -> std::io::Result<Txid> {
+        self.bitcoind_rpc_client
+            .call_method::<serde_json::Value>(
+                "sendrawtransaction",
+                &[serde_json::json!(encode::serialize_hex(tx))],
+            )
+            .await?;
+        Ok(tx.compute_txid())
+    }
+
+    /// Get the number of confirmations of an output (0 if it's still in the mempool), or None if
+    /// it doesn't exist or has been spent, also by a transaction in the mempool
+    pub(crate) async fn get_txout_confirmations(
+        &self,
+        txid: &Txid,
+        vout: u32,
+    ) -> std::io::Result<Option<u32>> {
+        let params = [
+            serde_json::json!(txid.to_string()),
+            serde_json::json!(vout),
+            serde_json::json!(true),
+        ];
+        self.bitcoind_rpc_client
+            .call_method::<TxOutResponse>("gettxout", &params)
+            .await
+            .map(|r| r.0)
+    }
 }
 
 impl FeeEstimator for BitcoindClient {
@@ -0,0 +1,255 @@
+use amplify::s;
+use bitcoin::absolute::LockTime;
+use bitcoin::psbt::Psbt;
+use bitcoin::transaction::Version;
+use bitcoin::{Address, Amount, OutPoint, Script, ScriptBuf, Sequence, Transaction, TxIn, TxOut};
+use rgb_lib::bdk_wallet::SignOptions;
+use std::cmp::Reverse;
+use std::str::FromStr;
+
+use crate::error::APIError;
+use crate::routes::{CoinSelection, CoinSelectionStrategy, DUST_LIMIT_MSAT};
+use crate::utils::UnlockedAppState;
+
+pub(crate) const TX_OVERHEAD_VSIZE: u64 = 11;
+const P2TR_INPUT_VSIZE: u64 = 58;
+const P2WPKH_INPUT_VSIZE: u64 = 68;
+
+/// A vanilla wallet UTXO that can be spent
+pub(crate) struct Candidate {
+    pub(crate) outpoint: OutPoint,
+    pub(crate) txout: TxOut,
+    pub(crate) confirmations: u32,
+    pub(crate) vsize: u64,
+}
+
+/// Build a PSBT signaling replaceability, spending the inputs to the outputs
+pub(crate) fn build_psbt(inputs: Vec<Candidate>, output: Vec<TxOut>) -> Psbt {
+    let unsigned_tx = Transaction {
+        version: Version::TWO,
+        lock_time: LockTime::ZERO,
+        input: inputs
+            .iter()
+            .map(|c| TxIn {
+                previous_output: c.outpoint,
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                ..Default::default()
+            })
+            .collect(),
+        output,
+    };
+    let mut psbt = Psbt::from_unsigned_tx(unsigned_tx).expect("valid unsigned TX");
+    for (psbt_input, candidate) in psbt.inputs.iter_mut().zip(inputs) {
+        psbt_input.witness_utxo = Some(candidate.txout);
+    }
+    psbt
+}
+
+pub(crate) fn input_vsize(script_pubkey: &Script) -> Option<u64> {
+    if script_pubkey.is_p2tr() {
+        Some(P2TR_INPUT_VSIZE)
+    } else if script_pubkey.is_p2wpkh() {
+        Some(P2WPKH_INPUT_VSIZE)
+    } else {
+        None
+    }
+}
+
+pub(crate) fn output_vsize(script_pubkey: &Script) -> u64 {
+    9 + script_pubkey.len() as u64
+}
+
+/// Pick candidates following the strategy until they cover the amount and the fee of the
+/// transaction, whose vsize without inputs is base_vsize
+fn select_coins(
+    mut candidates: Vec<Candidate>,
+    strategy: CoinSelectionStrategy,
+    amount_sat: u64,
+    fee_rate: u64,
+    base_vsize: u64,
+) -> Result<Vec<Candidate>, APIError> {
+    match strategy {
+        CoinSelectionStrategy::LargestFirst => candidates.sort_by_key(|c| Reverse(c.txout.value)),
+        CoinSelectionStrategy::OldestFirst => candidates.sort_by_key(|c| Reverse(c.confirmations)),
+        CoinSelectionStrategy::Privacy => {
+            // spending a single UTXO doesn't link any coins together
+            candidates.sort_by_key(|c| c.txout.value);
+            if let Some(pos) = candidates.iter().position(|c| {
+                c.txout.value.to_sat() >= amount_sat + (base_vsize + c.vsize) * fee_rate
+            }) {
+                return Ok(vec![candidates.swap_remove(pos)]);
+            }
+            candidates.reverse();
+        }
+    }
+
+    let mut selected = vec![];
+    let mut selected_sat = 0;
+    let mut vsize = base_vsize;
+    for candidate in candidates {
+        if selected_sat >= amount_sat + vsize * fee_rate {
+            break;
+        }
+        selected_sat += candidate.txout.value.to_sat();
+        vsize += candidate.vsize;
+        selected.push(candidate);
+    }
+    let needed_sat = amount_sat + vsize * fee_rate;
+    if selected_sat < needed_sat {
+        return Err(APIError::InsufficientFunds(needed_sat - selected_sat));
+    }
+    Ok(selected)
+}
+
+impl UnlockedAppState {
+    /// Build an unsigned PSBT paying the outputs, spending the vanilla UTXOs chosen as requested
+    /// instead of the ones the RGB wallet would pick
+    pub(crate) async fn build_psbt_with_coin_selection(
+        &self,
+        mut output: Vec<TxOut>,
+        fee_rate: u64,
+        coin_selection: &CoinSelection,
+        skip_sync: bool,
+    ) -> Result<Psbt, APIError> {
+        if coin_selection.outpoints.is_some() && coin_selection.excluded_outpoints.is_some() {
+            return Err(APIError::InvalidCoinSelection(s!(
+                "outpoints and excluded_outpoints cannot be both set"
+            )));
+        }
+        if fee_rate == 0 {
+            return Err(APIError::InvalidFeeRate(s!("must be at least 1 sat/vB")));
+        }
+        if output
+            .iter()
+            .any(|o| !o.script_pubkey.is_op_return() && o.value.to_sat() * 1000 < DUST_LIMIT_MSAT)
+        {
+            return Err(APIError::OutputBelowDustLimit);
+        }
+
+        let mut candidates = self.list_coin_candidates(skip_sync).await?;
+        let change_script = self.change_script()?;
+        let amount_sat = output.iter().map(|o| o.value.to_sat()).sum();
+        let base_vsize = TX_OVERHEAD_VSIZE
+            + output
+                .iter()
+                .map(|o| output_vsize(&o.script_pubkey))
+                .sum::<u64>()
+            + output_vsize(&change_script);
+        let selected = if let Some(outpoints) = &coin_selection.outpoints {
+            if outpoints.is_empty() {
+                return Err(APIError::InvalidCoinSelection(s!(
+                    "outpoints cannot be empty"
+                )));
+            }
+            let mut selected = vec![];
+            for outpoint in outpoints {
+                let pos = candidates
+                    .iter()
+                    .position(|c| c.outpoint.to_string() == *outpoint)
+                    .ok_or_else(|| {
+                        APIError::InvalidCoinSelection(format!(
+                            "{outpoint} is not a spendable vanilla UTXO"
+                        ))
+                    })?;
+                selected.push(candidates.swap_remove(pos));
+            }
+            selected
+        } else {
+            if let Some(excluded_outpoints) = &coin_selection.excluded_outpoints {
+                candidates.retain(|c| !excluded_outpoints.contains(&c.outpoint.to_string()));
+            }
+            select_coins(
+                candidates,
+                coin_selection.strategy,
+                amount_sat,
+                fee_rate,
+                base_vsize,
+            )?
+        };
+
+        let inputs_sat: u64 = selected.iter().map(|c| c.txout.value.to_sat()).sum();
+        let fee_sat = (base_vsize + selected.iter().map(|c| c.vsize).sum::<u64>()) * fee_rate;
+        if inputs_sat < amount_sat + fee_sat {
+            return Err(APIError::InsufficientFunds(
+                amount_sat + fee_sat - inputs_sat,
+            ));
+        }
+        let change_sat = inputs_sat - amount_sat - fee_sat;
+        if change_sat * 1000 >= DUST_LIMIT_MSAT {
+            output.push(TxOut {
+                value: Amount::from_sat(change_sat),
+                script_pubkey: change_script,
+            });
+        }
+        Ok(build_psbt(selected, output))
+    }
+
+    /// Build and sign a transaction paying the outputs, spending the vanilla UTXOs chosen as
+    /// requested
+    pub(crate) async fn send_btc_begin_with_coin_selection(
+        &self,
+        output: Vec<TxOut>,
+        fee_rate: u64,
+        coin_selection: &CoinSelection,
+        skip_sync: bool,
+    ) -> Result<String, APIError> {
+        let psbt = self
+            .build_psbt_with_coin_selection(output, fee_rate, coin_selection, skip_sync)
+            .await?;
+        self.sign_built_psbt(psbt)
+    }
+
+    /// Script of a new vanilla wallet address, to receive the change
+    pub(crate) fn change_script(&self) -> Result<ScriptBuf, APIError> {
+        Ok(Address::from_str(&self.rgb_wallet_wrapper.get_address()?)
+            .unwrap()
+            .assume_checked()
+            .script_pubkey())
+    }
+
+    /// List the vanilla UTXOs that can be spent, skipping the locked ones and the ones already
+    /// spent by a transaction in the mempool
+    pub(crate) async fn list_coin_candidates(
+        &self,
+        skip_sync: bool,
+    ) -> Result<Vec<Candidate>, APIError> {
+        let locked_utxos = self.locked_utxos();
+        let mut candidates = vec![];
+        for unspent in self.rgb_list_unspents_vanilla(skip_sync)? {
+            let outpoint = OutPoint::from_str(&unspent.outpoint.to_string()).unwrap();
+            if locked_utxos.contains_key(&outpoint.to_string()) {
+                continue;
+            }
+            let Some(vsize) = input_vsize(&unspent.txout.script_pubkey) else {
+                continue;
+            };
+            let Some(confirmations) = self
+                .bitcoind_client
+                .get_txout_confirmations(&outpoint.txid, outpoint.vout)
+                .await
+                .map_err(|e| APIError::FailedBitcoindConnection(e.to_string()))?
+            else {
+                continue;
+            };
+            candidates.push(Candidate {
+                outpoint,
+                txout: unspent.txout,
+                confirmations,
+                vsize,
+            });
+        }
+        Ok(candidates)
+    }
+
+    /// Sign a PSBT built by the node, whose inputs carry their witness UTXO
+    pub(crate) fn sign_built_psbt(&self, psbt: Psbt) -> Result<String, APIError> {
+        let sign_options = SignOptions {
+            trust_witness_utxo: true,
+            ..Default::default()
+        };
+        Ok(self
+            .rgb_wallet_wrapper
+            .get_rgb_wallet()
+            .sign_psbt(psbt.to_string(), Some(sign_options))?)
+    }
+}
@@ -0,0 +1,252 @@
+use amplify::s;
+use std::{fs, path::PathBuf, str::FromStr, sync::MutexGuard};
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+use crate::{
+    error::{APIError, AppError},
+    utils::AppState,
+};
+
+const CONFIG_FILE: &str = "config.json";
+
+pub(crate) const DEFAULT_LOG_LEVEL: &str = "info";
+
+/// Settings that can only be changed by restarting the node with the corresponding option
+const RESTART_REQUIRED_SETTINGS: [&str; 49] = [
+    "address_gap_limit",
+    "anchor_reserve_utxo_size_sat",
+    "anchor_reserve_utxos",
+    "announce_alias",
+    "announce_color",
+    "asset_htlc_min_msat",
+    "auto_backup_full_every",
+    "auto_backup_interval_sec",
+    "auto_backup_keep",
+    "auto_backup_target",
+    "cltv_expiry_delta",
+    "compact_storage_on_startup",
+    "cors_allowed_origins",
+    "daemon_listening_port",
+    "disable_authentication",
+    "generate_root_key",
+    "grpc_listening_port",
+    "hook_max_concurrency",
+    "hook_timeout_sec",
+    "ldk_peer_listening_port",
+    "max_media_upload_size_mb",
+    "max_total_cltv_expiry_delta",
+    "min_channel_depth",
+    "min_final_cltv_expiry_delta",
+    "network",
+    "phantom_secret_path",
+    "rgs_url",
+    "root_public_key",
+    "shutdown_drain_timeout_sec",
+    "swap_provider_url",
+    "tls",
+    "tls_cert_path",
+    "tls_client_ca_path",
+    "tls_key_path",
+    "tor_isolate_circuits",
+    "tor_max_inbound_connections",
+    "tor_max_inbound_per_minute",
+    "tor_no_clearnet_dns",
+    "tor_onion_authorized_clients",
+    "tor_onion_service_dir",
+    "tor_socks_addr",
+    "tor_socks_password",
+    "tor_socks_username",
+    "trust_forwarded_for",
+    "url_prefix",
+    "utxo_pool_min",
+    "utxo_pool_target",
+    "utxo_pool_utxo_size_sat",
+    "watch_only",
+];
+
+pub(crate) type LogLevelHandle = reload::Handle<EnvFilter, Registry>;
+
+/// Settings that can be reloaded from the config file without restarting the node
+#[derive(Clone, Debug)]
+pub(crate) struct RuntimeConfig {
+    pub(crate) gossip_bandwidth_cap: u64,
+    pub(crate) hook_command: Option<PathBuf>,
+    pub(crate) htlc_risk_threshold_sat: u64,
+    pub(crate) invoice_retention_sec: u64,
+    pub(crate) log_level: String,
+    pub(crate) max_invoices_per_minute: u32,
+    pub(crate) max_requests_per_minute: u32,
+    pub(crate) max_route_hints: u8,
+    pub(crate) max_token_invoices_per_minute: u32,
+    pub(crate) max_token_unpaid_invoices: u32,
+    pub(crate) max_unpaid_invoices: u32,
+    pub(crate) payment_retention_sec: u64,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ConfigReload {
+    pub(crate) applied: Vec<String>,
+    pub(crate) skipped: Vec<String>,
+}
+
+fn parse_setting<T: FromStr>(key: &str, value: &serde_json::Value) -> Result<T, String> {
+    let value_str = match value {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Number(n) => n.to_string(),
+        _ => return Err(format!("invalid value for {key}")),
+    };
+    value_str
+        .parse()
+        .map_err(|_| format!("invalid value for {key}"))
+}
+
+/// Parse a log level, either a global one (e.g. "debug") or comma-separated per-module
+/// directives (e.g. "info,rgb_lightning_node::ldk=trace")
+pub(crate) fn parse_log_level(log_level: &str) -> Result<EnvFilter, String> {
+    EnvFilter::builder()
+        .parse(log_level)
+        .map_err(|e| format!("invalid log level {log_level}: {e}"))
+}
+
+/// Parse the hook command, null removing it
+fn parse_hook_command(value: &serde_json::Value) -> Result<Option<PathBuf>, String> {
+    let hook_command = match value {
+        serde_json::Value::Null => return Ok(None),
+        serde_json::Value::String(s) => PathBuf::from(s),
+        _ => return Err(s!("invalid value for hook_command")),
+    };
+    if !hook_command.is_file() {
+        return Err(format!("{} is not a file", hook_command.display()));
+    }
+    Ok(Some(hook_command))
+}
+
+/// Parse the config file content on top of the current config, failing without changes if any
+/// setting is unknown or invalid
+fn parse_config(
+    content: &str,
+    current: &RuntimeConfig,
+) -> Result<(RuntimeConfig, ConfigReload), String> {
+    let settings: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(content).map_err(|e| e.to_string())?;
+
+    let mut config = current.clone();
+    let mut reload = ConfigReload::default();
+    for (key, value) in &settings {
+        match key.as_str() {
+            "gossip_bandwidth_cap" => config.gossip_bandwidth_cap = parse_setting(key, value)?,
+            "hook_command" => config.hook_command = parse_hook_command(value)?,
+            "htlc_risk_threshold_sat" => {
+                config.htlc_risk_threshold_sat = parse_setting(key, value)?
+            }
+            "invoice_retention_sec" => config.invoice_retention_sec = parse_setting(key, value)?,
+            "log_level" => {
+                let log_level: String = parse_setting(key, value)?;
+                parse_log_level(&log_level)?;
+                config.log_level = log_level
+            }
+            "max_invoices_per_minute" => {
+                config.max_invoices_per_minute = parse_setting(key, value)?
+            }
+            "max_requests_per_minute" => {
+                config.max_requests_per_minute = parse_setting(key, value)?
+            }
+            "max_route_hints" => config.max_route_hints = parse_setting(key, value)?,
+            "max_token_invoices_per_minute" => {
+                config.max_token_invoices_per_minute = parse_setting(key, value)?
+            }
+            "max_token_unpaid_invoices" => {
+                config.max_token_unpaid_invoices = parse_setting(key, value)?
+            }
+            "max_unpaid_invoices" => config.max_unpaid_invoices = parse_setting(key, value)?,
+            "payment_retention_sec" => config.payment_retention_sec = parse_setting(key, value)?,
+            _ if RESTART_REQUIRED_SETTINGS.contains(&key.as_str()) => {
+                reload.skipped.push(key.clone());
+                continue;
+            }
+            _ => return Err(format!("unknown setting {key}")),
+        }
+        reload.applied.push(key.clone());
+    }
+    Ok((config, reload))
+}
+
+impl AppState {
+    fn get_config_path(&self) -> PathBuf {
+        self.static_state.storage_dir_path.join(CONFIG_FILE)
+    }
+
+    pub(crate) fn get_runtime_config(&self) -> MutexGuard<'_, RuntimeConfig> {
+        self.runtime_config.lock().unwrap()
+    }
+
+    fn apply_config(&self, config: RuntimeConfig) {
+        if let Some(handle) = &self.log_level_handle {
+            let filter = parse_log_level(&config.log_level).expect("validated log level");
+            if let Err(e) = handle.reload(filter) {
+                tracing::error!("Failed to change log level: {e}");
+            }
+        }
+        *self.get_runtime_config() = config;
+    }
+
+    /// Apply the config file at startup, if present
+    pub(crate) fn load_config(&self) -> Result<(), AppError> {
+        let path = self.get_config_path();
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                tracing::info!("No config file found at {}", path.display());
+                return Ok(());
+            }
+            Err(e) => return Err(AppError::IO(e)),
+        };
+
+        let current = self.get_runtime_config().clone();
+        let (config, reload) = parse_config(&content, &current).map_err(AppError::InvalidConfig)?;
+        if !reload.skipped.is_empty() {
+            tracing::warn!(
+                "Config settings {:?} can only be set with the corresponding options",
+                reload.skipped
+            );
+        }
+        self.apply_config(config);
+        Ok(())
+    }
+
+    /// Re-read the config file and apply its settings, all of them or none
+    pub(crate) async fn reload_config(&self) -> Result<ConfigReload, APIError> {
+        let path = self.get_config_path();
+        let content = fs::read_to_string(&path).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                APIError::InvalidConfig(format!("no config file found at {}", path.display()))
+            } else {
+                APIError::IO(e)
+            }
+        })?;
+
+        let current = self.get_runtime_config().clone();
+        let (config, reload) = parse_config(&content, &current).map_err(APIError::InvalidConfig)?;
+
+        let gossip_bandwidth_cap = config.gossip_bandwidth_cap;
+        let hook_command = config.hook_command.clone();
+        self.apply_config(config);
+        // a locked node will pick up the new settings when unlocked
+        if let Some(unlocked_state) = self.get_unlocked_app_state().await.as_ref() {
+            unlocked_state
+                .gossip_handler
+                .set_global_cap(gossip_bandwidth_cap);
+            unlocked_state.hook_runner.set_command(hook_command);
+        }
+        Ok(reload)
+    }
+
+    /// Change the log level until the next config reload or restart
+    pub(crate) fn set_log_level(&self, log_level: String) -> Result<(), APIError> {
+        parse_log_level(&log_level).map_err(APIError::InvalidLogLevel)?;
+        let mut config = self.get_runtime_config().clone();
+        config.log_level = log_level;
+        self.apply_config(config);
+        Ok(())
+    }
+}
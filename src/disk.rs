@@ -1,38 +1,112 @@
 use bitcoin::secp256k1::PublicKey;
 use bitcoin::Network;
 use chrono::Utc;
+use lightning::ln::msgs::SocketAddress;
 use lightning::routing::scoring::{ProbabilisticScorer, ProbabilisticScoringDecayParameters};
 use lightning::util::hash_tables::new_hash_map;
 use lightning::util::logger::{Logger, Record};
+use lightning::util::persist::{
+    KVStoreSync, CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+    CHANNEL_MONITOR_PERSISTENCE_SECONDARY_NAMESPACE, NETWORK_GRAPH_PERSISTENCE_KEY,
+    SCORER_PERSISTENCE_KEY,
+};
 use lightning::util::ser::{Readable, ReadableArgs, Writer};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::net::SocketAddr;
+use std::io::{BufRead, BufReader, Cursor};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::Arc;
 
+use crate::auth::TokenSpendingMap;
 use crate::error::APIError;
+use crate::gossip::GossipLimitMap;
+use crate::hedging::{HedgeMap, HedgingPolicyMap};
 use crate::ldk::{
-    ChannelIdsMap, InboundPaymentInfoStorage, NetworkGraph, OutboundPaymentInfoStorage,
-    OutputSpenderTxes, SwapMap,
+    AmountBoundsMap, AnchorReserveMap, AssetPolicyMap, ChainSubscriptionMap, ChannelAcceptorPolicy,
+    ChannelDepthOverrides, ChannelEventMap, ChannelIdsMap, EscrowMap, FallbackAddressMap,
+    ForwardMap, HodlInvoiceMap, InboundPaymentInfoStorage, InterceptScopeMap, IssuedAddressMap,
+    JitChannelMap, JournalMap, LnurlPayConfig, LockedUtxoMap, NetworkGraph, NodeAnnouncementConfig,
+    OfferMap, OrderMap, OutboundPaymentInfoStorage, OutputSpenderTxes, PeerFilter,
+    PendingBroadcastMap, SettlementMap, SwapMap, SweepConfig,
 };
+use crate::lsps::{LeaseConfig, LeaseOrderMap, LspClientMap, LspConfig};
+use crate::nwc::NwcConnectionMap;
+use crate::peer_messages::AssetHtlcMinMap;
+use crate::store::NodeStore;
+use crate::submarine::SubmarineSwapMap;
+use crate::totp::TotpState;
 use crate::utils::{parse_peer_info, LOGS_DIR};
 
 pub(crate) const LDK_LOGS_FILE: &str = "logs.txt";
 
-pub(crate) const INBOUND_PAYMENTS_FNAME: &str = "inbound_payments";
-pub(crate) const OUTBOUND_PAYMENTS_FNAME: &str = "outbound_payments";
-
 pub(crate) const CHANNEL_PEER_DATA: &str = "channel_peer_data";
 
-pub(crate) const OUTPUT_SPENDER_TXES: &str = "output_spender_txes";
+pub(crate) const CHANNEL_MONITORS_DIR: &str = "monitors";
+
+pub(crate) const EMERGENCY_KIT_FNAME: &str = "emergency_kit";
+pub(crate) const EMERGENCY_KIT_CONTENTS_FNAME: &str = "emergency_kit.json";
+pub(crate) const EMERGENCY_KIT_INSTRUCTIONS_FNAME: &str = "INSTRUCTIONS.txt";
+
+pub(crate) const RESTORED_CHANNEL_PEERS_FNAME: &str = "restored_channel_peers";
 
-pub(crate) const CHANNEL_IDS_FNAME: &str = "channel_ids";
+/// Declares the keys of the node data persisted in the root namespace of the store, along with
+/// [`NODE_DATA_KEYS`] listing all of them, so that no key is left out of the storage encryption,
+/// the replication or the backend migration
+macro_rules! node_data_keys {
+    ($($name:ident = $key:literal;)*) => {
+        $(pub(crate) const $name: &str = $key;)*
 
-pub(crate) const MAKER_SWAPS_FNAME: &str = "maker_swaps";
-pub(crate) const TAKER_SWAPS_FNAME: &str = "taker_swaps";
+        /// Keys of all the node data persisted in the root namespace of the store
+        pub(crate) const NODE_DATA_KEYS: &[&str] = &[$($name),*];
+    };
+}
+
+node_data_keys! {
+    INBOUND_PAYMENTS_FNAME = "inbound_payments";
+    OUTBOUND_PAYMENTS_FNAME = "outbound_payments";
+    OUTPUT_SPENDER_TXES = "output_spender_txes";
+    CHANNEL_IDS_FNAME = "channel_ids";
+    CHANNEL_EVENTS_FNAME = "channel_events";
+    FORWARDS_FNAME = "forwards";
+    ASSET_HTLC_MINIMUMS_FNAME = "asset_htlc_minimums";
+    CHANNEL_ACCEPTOR_FNAME = "channel_acceptor";
+    PEER_FILTER_FNAME = "peer_filter";
+    CHANNEL_DEPTHS_FNAME = "channel_depths";
+    HODL_INVOICES_FNAME = "hodl_invoices";
+    ISSUED_ADDRESSES_FNAME = "issued_addresses";
+    JIT_CHANNELS_FNAME = "jit_channels";
+    AMOUNT_BOUNDS_FNAME = "amount_bounds";
+    FALLBACK_ADDRESSES_FNAME = "fallback_addresses";
+    ASSET_POLICIES_FNAME = "asset_policies";
+    LSP_CONFIG_FNAME = "lsp_config";
+    LNURL_PAY_FNAME = "lnurl_pay";
+    NODE_ANNOUNCEMENT_FNAME = "node_announcement";
+    LSP_CLIENTS_FNAME = "lsp_clients";
+    LEASE_CONFIG_FNAME = "lease_config";
+    LEASE_ORDERS_FNAME = "lease_orders";
+    NWC_CONNECTIONS_FNAME = "nwc_connections";
+    OFFERS_FNAME = "offers";
+    PENDING_BROADCASTS_FNAME = "pending_broadcasts";
+    LOCKED_UTXOS_FNAME = "locked_utxos";
+    ANCHOR_RESERVE_FNAME = "anchor_reserve";
+    ORDERS_FNAME = "orders";
+    ESCROWS_FNAME = "escrows";
+    SETTLEMENTS_FNAME = "settlements";
+    CHAIN_SUBSCRIPTIONS_FNAME = "chain_subscriptions";
+    INTERCEPT_SCOPES_FNAME = "intercept_scopes";
+    JOURNAL_FNAME = "journal";
+    GOSSIP_LIMITS_FNAME = "gossip_limits";
+    TOTP_FNAME = "totp";
+    TOKEN_SPENDING_FNAME = "token_spending";
+    HEDGES_FNAME = "hedges";
+    HEDGING_POLICIES_FNAME = "hedging_policies";
+    MAKER_SWAPS_FNAME = "maker_swaps";
+    SUBMARINE_SWAPS_FNAME = "submarine_swaps";
+    SWEEP_CONFIG_FNAME = "sweep_config";
+    TAKER_SWAPS_FNAME = "taker_swaps";
+}
 
 pub(crate) struct FilesystemLogger {
     data_dir: PathBuf,
@@ -76,7 +150,7 @@ impl Logger for FilesystemLogger {
 pub(crate) fn persist_channel_peer(
     path: &Path,
     pubkey: &PublicKey,
-    address: &SocketAddr,
+    address: &SocketAddress,
 ) -> Result<(), APIError> {
     let pubkey = pubkey.to_string();
     let peer_info = if path.exists() {
@@ -125,7 +199,7 @@ pub(crate) fn delete_channel_peer(path: &Path, pubkey: String) -> Result<(), API
 
 pub(crate) fn read_channel_peer_data(
     path: &Path,
-) -> Result<HashMap<PublicKey, SocketAddr>, APIError> {
+) -> Result<HashMap<PublicKey, SocketAddress>, APIError> {
     let mut peer_data = HashMap::new();
     if !path.exists() {
         return Ok(HashMap::new());
@@ -143,22 +217,99 @@ pub(crate) fn read_channel_peer_data(
     Ok(peer_data)
 }
 
-pub(crate) fn read_network(
+pub(crate) fn persist_restored_channel_peers(
     path: &Path,
+    peers: &HashSet<PublicKey>,
+) -> Result<(), APIError> {
+    let peers = peers
+        .iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut tmp_path = path.to_path_buf();
+    tmp_path.set_extension("ptmp");
+    fs::write(&tmp_path, peers.as_bytes())?;
+    fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+pub(crate) fn read_restored_channel_peers(path: &Path) -> HashSet<PublicKey> {
+    if let Ok(peers) = fs::read_to_string(path) {
+        return peers
+            .lines()
+            .filter_map(|line| PublicKey::from_str(line.trim()).ok())
+            .collect();
+    }
+    HashSet::new()
+}
+
+/// Copy the channel files of an unpacked static channel backup to the LDK data directory.
+///
+/// Monitors are written to the store the persister will load them from at the next unlock, while
+/// the peers of the restored channels are saved so they get reconnected. Returns the number of
+/// restored channels.
+pub(crate) fn restore_channel_files(
+    backup_dir: &Path,
+    ldk_data_dir: &Path,
+    kv_store: &NodeStore,
+) -> Result<usize, APIError> {
+    let backup_monitors_dir = backup_dir.join(CHANNEL_MONITORS_DIR);
+    if !backup_monitors_dir.is_dir() {
+        return Err(APIError::InvalidChannelBackup);
+    }
+
+    let mut num_channels = 0;
+    for entry in fs::read_dir(backup_monitors_dir)? {
+        let entry = entry?;
+        kv_store.write(
+            CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+            CHANNEL_MONITOR_PERSISTENCE_SECONDARY_NAMESPACE,
+            &entry.file_name().to_string_lossy(),
+            fs::read(entry.path())?,
+        )?;
+        num_channels += 1;
+    }
+
+    // RGB data of the channels
+    for entry in fs::read_dir(backup_dir)? {
+        let entry = entry?;
+        if entry.path().is_file() && entry.file_name() != CHANNEL_PEER_DATA {
+            fs::copy(entry.path(), ldk_data_dir.join(entry.file_name()))?;
+        }
+    }
+
+    let restored_peers_path = ldk_data_dir.join(RESTORED_CHANNEL_PEERS_FNAME);
+    let mut restored_peers = read_restored_channel_peers(&restored_peers_path);
+    let peer_data = read_channel_peer_data(&backup_dir.join(CHANNEL_PEER_DATA))?;
+    for (pubkey, address) in peer_data {
+        persist_channel_peer(&ldk_data_dir.join(CHANNEL_PEER_DATA), &pubkey, &address)?;
+        restored_peers.insert(pubkey);
+    }
+    persist_restored_channel_peers(&restored_peers_path, &restored_peers)?;
+
+    tracing::info!("restored {num_channels} channels from backup");
+    Ok(num_channels)
+}
+
+pub(crate) fn read_network(
+    kv_store: &NodeStore,
     network: Network,
     logger: Arc<FilesystemLogger>,
 ) -> NetworkGraph {
-    if let Ok(file) = File::open(path) {
-        if let Ok(graph) = NetworkGraph::read(&mut BufReader::new(file), logger.clone()) {
+    if let Ok(bytes) = kv_store.read("", "", NETWORK_GRAPH_PERSISTENCE_KEY) {
+        if let Ok(graph) = NetworkGraph::read(&mut Cursor::new(bytes), logger.clone()) {
             return graph;
         }
     }
     NetworkGraph::new(network, logger)
 }
 
-pub(crate) fn read_inbound_payment_info(path: &Path) -> InboundPaymentInfoStorage {
-    if let Ok(file) = File::open(path) {
-        if let Ok(info) = InboundPaymentInfoStorage::read(&mut BufReader::new(file)) {
+pub(crate) fn read_inbound_payment_info(
+    kv_store: &NodeStore,
+    key: &str,
+) -> InboundPaymentInfoStorage {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = InboundPaymentInfoStorage::read(&mut Cursor::new(bytes)) {
             return info;
         }
     }
@@ -167,9 +318,12 @@ pub(crate) fn read_inbound_payment_info(path: &Path) -> InboundPaymentInfoStorag
     }
 }
 
-pub(crate) fn read_outbound_payment_info(path: &Path) -> OutboundPaymentInfoStorage {
-    if let Ok(file) = File::open(path) {
-        if let Ok(info) = OutboundPaymentInfoStorage::read(&mut BufReader::new(file)) {
+pub(crate) fn read_outbound_payment_info(
+    kv_store: &NodeStore,
+    key: &str,
+) -> OutboundPaymentInfoStorage {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = OutboundPaymentInfoStorage::read(&mut Cursor::new(bytes)) {
             return info;
         }
     }
@@ -178,18 +332,18 @@ pub(crate) fn read_outbound_payment_info(path: &Path) -> OutboundPaymentInfoStor
     }
 }
 
-pub(crate) fn read_output_spender_txes(path: &Path) -> OutputSpenderTxes {
-    if let Ok(file) = File::open(path) {
-        if let Ok(info) = OutputSpenderTxes::read(&mut BufReader::new(file)) {
+pub(crate) fn read_output_spender_txes(kv_store: &NodeStore, key: &str) -> OutputSpenderTxes {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = OutputSpenderTxes::read(&mut Cursor::new(bytes)) {
             return info;
         }
     }
     new_hash_map()
 }
 
-pub(crate) fn read_swaps_info(path: &Path) -> SwapMap {
-    if let Ok(file) = File::open(path) {
-        if let Ok(info) = SwapMap::read(&mut BufReader::new(file)) {
+pub(crate) fn read_swaps_info(kv_store: &NodeStore, key: &str) -> SwapMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = SwapMap::read(&mut Cursor::new(bytes)) {
             return info;
         }
     }
@@ -199,23 +353,23 @@ pub(crate) fn read_swaps_info(path: &Path) -> SwapMap {
 }
 
 pub(crate) fn read_scorer(
-    path: &Path,
+    kv_store: &NodeStore,
     graph: Arc<NetworkGraph>,
     logger: Arc<FilesystemLogger>,
 ) -> ProbabilisticScorer<Arc<NetworkGraph>, Arc<FilesystemLogger>> {
     let params = ProbabilisticScoringDecayParameters::default();
-    if let Ok(file) = File::open(path) {
+    if let Ok(bytes) = kv_store.read("", "", SCORER_PERSISTENCE_KEY) {
         let args = (params, Arc::clone(&graph), Arc::clone(&logger));
-        if let Ok(scorer) = ProbabilisticScorer::read(&mut BufReader::new(file), args) {
+        if let Ok(scorer) = ProbabilisticScorer::read(&mut Cursor::new(bytes), args) {
             return scorer;
         }
     }
     ProbabilisticScorer::new(params, graph, logger)
 }
 
-pub(crate) fn read_channel_ids_info(path: &Path) -> ChannelIdsMap {
-    if let Ok(file) = File::open(path) {
-        if let Ok(info) = ChannelIdsMap::read(&mut BufReader::new(file)) {
+pub(crate) fn read_channel_ids_info(kv_store: &NodeStore, key: &str) -> ChannelIdsMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = ChannelIdsMap::read(&mut Cursor::new(bytes)) {
             return info;
         }
     }
@@ -223,3 +377,390 @@ pub(crate) fn read_channel_ids_info(path: &Path) -> ChannelIdsMap {
         channel_ids: new_hash_map(),
     }
 }
+
+pub(crate) fn read_hodl_invoices_info(kv_store: &NodeStore, key: &str) -> HodlInvoiceMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = HodlInvoiceMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    HodlInvoiceMap {
+        invoices: new_hash_map(),
+    }
+}
+
+pub(crate) fn read_jit_channels_info(kv_store: &NodeStore, key: &str) -> JitChannelMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = JitChannelMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    JitChannelMap {
+        channels: new_hash_map(),
+    }
+}
+
+pub(crate) fn read_amount_bounds_info(kv_store: &NodeStore, key: &str) -> AmountBoundsMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = AmountBoundsMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    AmountBoundsMap {
+        invoices: new_hash_map(),
+    }
+}
+
+pub(crate) fn read_fallback_addresses_info(kv_store: &NodeStore, key: &str) -> FallbackAddressMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = FallbackAddressMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    FallbackAddressMap {
+        invoices: new_hash_map(),
+    }
+}
+
+pub(crate) fn read_asset_policies_info(kv_store: &NodeStore, key: &str) -> AssetPolicyMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = AssetPolicyMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    AssetPolicyMap {
+        policies: new_hash_map(),
+    }
+}
+
+pub(crate) fn read_lsp_config_info(kv_store: &NodeStore, key: &str) -> LspConfig {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = LspConfig::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    LspConfig::default()
+}
+
+pub(crate) fn read_asset_htlc_minimums_info(kv_store: &NodeStore, key: &str) -> AssetHtlcMinMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = AssetHtlcMinMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    AssetHtlcMinMap {
+        channels: new_hash_map(),
+    }
+}
+
+pub(crate) fn read_lsp_clients_info(kv_store: &NodeStore, key: &str) -> LspClientMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = LspClientMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    LspClientMap {
+        clients: new_hash_map(),
+    }
+}
+
+pub(crate) fn read_lease_config_info(kv_store: &NodeStore, key: &str) -> LeaseConfig {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = LeaseConfig::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    LeaseConfig::default()
+}
+
+pub(crate) fn read_lease_orders_info(kv_store: &NodeStore, key: &str) -> LeaseOrderMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = LeaseOrderMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    LeaseOrderMap {
+        orders: new_hash_map(),
+    }
+}
+
+pub(crate) fn read_offers_info(kv_store: &NodeStore, key: &str) -> OfferMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = OfferMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    OfferMap {
+        offers: new_hash_map(),
+    }
+}
+
+pub(crate) fn read_channel_events_info(kv_store: &NodeStore, key: &str) -> ChannelEventMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = ChannelEventMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    ChannelEventMap {
+        channels: new_hash_map(),
+    }
+}
+
+pub(crate) fn read_forwards_info(kv_store: &NodeStore, key: &str) -> ForwardMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = ForwardMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    ForwardMap {
+        forwards: vec![],
+        totals: new_hash_map(),
+    }
+}
+
+pub(crate) fn read_channel_acceptor_info(kv_store: &NodeStore, key: &str) -> ChannelAcceptorPolicy {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = ChannelAcceptorPolicy::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    ChannelAcceptorPolicy::default()
+}
+
+pub(crate) fn read_channel_depths_info(kv_store: &NodeStore, key: &str) -> ChannelDepthOverrides {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = ChannelDepthOverrides::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    ChannelDepthOverrides::default()
+}
+
+pub(crate) fn read_lnurl_pay_info(kv_store: &NodeStore, key: &str) -> LnurlPayConfig {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = LnurlPayConfig::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    LnurlPayConfig::default()
+}
+
+pub(crate) fn read_node_announcement_info(
+    kv_store: &NodeStore,
+    key: &str,
+) -> NodeAnnouncementConfig {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = NodeAnnouncementConfig::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    NodeAnnouncementConfig::default()
+}
+
+pub(crate) fn read_nwc_connections_info(kv_store: &NodeStore, key: &str) -> NwcConnectionMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = NwcConnectionMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    NwcConnectionMap {
+        connections: new_hash_map(),
+    }
+}
+
+pub(crate) fn read_peer_filter_info(kv_store: &NodeStore, key: &str) -> PeerFilter {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = PeerFilter::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    PeerFilter::default()
+}
+
+pub(crate) fn read_sweep_config_info(kv_store: &NodeStore, key: &str) -> SweepConfig {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = SweepConfig::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    SweepConfig::default()
+}
+
+pub(crate) fn read_issued_addresses_info(kv_store: &NodeStore, key: &str) -> IssuedAddressMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = IssuedAddressMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    IssuedAddressMap {
+        addresses: new_hash_map(),
+    }
+}
+
+pub(crate) fn read_pending_broadcasts_info(kv_store: &NodeStore, key: &str) -> PendingBroadcastMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = PendingBroadcastMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    PendingBroadcastMap {
+        broadcasts: new_hash_map(),
+    }
+}
+
+pub(crate) fn read_locked_utxos_info(kv_store: &NodeStore, key: &str) -> LockedUtxoMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = LockedUtxoMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    LockedUtxoMap {
+        utxos: new_hash_map(),
+    }
+}
+
+pub(crate) fn read_anchor_reserve_info(kv_store: &NodeStore, key: &str) -> AnchorReserveMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = AnchorReserveMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    AnchorReserveMap {
+        utxos: new_hash_map(),
+        events: vec![],
+        depleted: false,
+    }
+}
+
+pub(crate) fn read_chain_subscriptions_info(
+    kv_store: &NodeStore,
+    key: &str,
+) -> ChainSubscriptionMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = ChainSubscriptionMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    ChainSubscriptionMap {
+        subscriptions: new_hash_map(),
+        last_scanned_height: 0,
+    }
+}
+
+pub(crate) fn read_intercept_scopes_info(kv_store: &NodeStore, key: &str) -> InterceptScopeMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = InterceptScopeMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    InterceptScopeMap {
+        scopes: new_hash_map(),
+    }
+}
+
+pub(crate) fn read_journal_info(kv_store: &NodeStore, key: &str) -> JournalMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = JournalMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    JournalMap { entries: vec![] }
+}
+
+pub(crate) fn read_gossip_limits_info(kv_store: &NodeStore, key: &str) -> GossipLimitMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = GossipLimitMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    GossipLimitMap {
+        limits: new_hash_map(),
+    }
+}
+
+pub(crate) fn read_totp_info(kv_store: &NodeStore, key: &str) -> TotpState {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = TotpState::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    TotpState { totp: None }
+}
+
+pub(crate) fn read_orders_info(kv_store: &NodeStore, key: &str) -> OrderMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = OrderMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    OrderMap {
+        orders: new_hash_map(),
+    }
+}
+
+pub(crate) fn read_escrows_info(kv_store: &NodeStore, key: &str) -> EscrowMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = EscrowMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    EscrowMap {
+        escrows: new_hash_map(),
+    }
+}
+
+pub(crate) fn read_settlements_info(kv_store: &NodeStore, key: &str) -> SettlementMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = SettlementMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    SettlementMap {
+        periods: new_hash_map(),
+        pending: new_hash_map(),
+    }
+}
+
+pub(crate) fn read_token_spending_info(kv_store: &NodeStore, key: &str) -> TokenSpendingMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = TokenSpendingMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    TokenSpendingMap {
+        tokens: new_hash_map(),
+    }
+}
+
+pub(crate) fn read_submarine_swaps_info(kv_store: &NodeStore, key: &str) -> SubmarineSwapMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = SubmarineSwapMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    SubmarineSwapMap {
+        swaps: new_hash_map(),
+    }
+}
+
+pub(crate) fn read_hedging_policies_info(kv_store: &NodeStore, key: &str) -> HedgingPolicyMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = HedgingPolicyMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    HedgingPolicyMap {
+        policies: new_hash_map(),
+    }
+}
+
+pub(crate) fn read_hedges_info(kv_store: &NodeStore, key: &str) -> HedgeMap {
+    if let Ok(bytes) = kv_store.read("", "", key) {
+        if let Ok(info) = HedgeMap::read(&mut Cursor::new(bytes)) {
+            return info;
+        }
+    }
+    HedgeMap {
+        hedges: new_hash_map(),
+    }
+}
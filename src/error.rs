@@ -1,13 +1,18 @@
 use amplify::s;
 use axum::{
-    extract::rejection::JsonRejection,
-    http::StatusCode,
+    body::{to_bytes, Body},
+    extract::rejection::{JsonRejection, QueryRejection},
+    http::{header, Request, StatusCode},
+    middleware::Next,
     response::{IntoResponse, Response},
     Json,
 };
 use rgb_lib::{BitcoinNetwork, Error as RgbLibError};
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 
+/// Body of the failed calls to the /v1 APIs (and to the unversioned ones), `name` being the
+/// machine-readable name of the error and `code` the HTTP status code
 #[derive(Debug, Deserialize, Serialize)]
 pub(crate) struct APIErrorResponse {
     pub(crate) error: String,
@@ -15,9 +20,47 @@ pub(crate) struct APIErrorResponse {
     pub(crate) name: String,
 }
 
+/// Body of the failed calls to the /v2 APIs, `code` being the stable machine-readable name of the
+/// error (e.g. `InvalidInvoice`) and `details` the structured data of the errors that carry some
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct APIErrorResponseV2 {
+    pub(crate) code: String,
+    pub(crate) message: String,
+    pub(crate) details: Option<Value>,
+}
+
+/// Turn the body of the failed calls into the one of the /v1 APIs, which keep the error shape they
+/// had before /v2 was introduced
+pub(crate) async fn v1_error_middleware(request: Request<Body>, next: Next) -> Response {
+    let response = next.run(request).await;
+    if response.status().is_success() {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    let Ok(error) = serde_json::from_slice::<APIErrorResponseV2>(&bytes) else {
+        // not an API error (e.g. a bare status returned by the auth middleware)
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    let body = serde_json::to_vec(&APIErrorResponse {
+        error: error.message,
+        code: parts.status.as_u16(),
+        name: error.code,
+    })
+    .unwrap();
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(body))
+}
+
 /// The error variants returned by APIs
 #[derive(Debug, thiserror::Error)]
 pub enum APIError {
+    #[error("Address gap limit ({0}) reached (hint: use some of the issued addresses)")]
+    AddressGapLimitReached(u32),
+
     #[error("Allocations already available")]
     AllocationsAlreadyAvailable,
 
@@ -30,27 +73,78 @@ pub enum APIError {
     #[error("Node has already been unlocked")]
     AlreadyUnlocked,
 
+    #[error("Asset {0} cannot be reissued, as it has no inflation rights")]
+    AssetNotInflatable(String),
+
     #[error("Authentication is disabled")]
     AuthenticationDisabled,
 
     #[error("Batch transfer not found")]
     BatchTransferNotFound,
 
+    #[error("Cannot accept channel: {0}")]
+    CannotAcceptChannel(String),
+
+    #[error("Cannot bump close fee: {0}")]
+    CannotBumpCloseFee(String),
+
+    #[error("Cannot bump fee: {0}")]
+    CannotBumpFee(String),
+
+    #[error("Cannot buy inbound channel: {0}")]
+    CannotBuyInbound(String),
+
     #[error("Cannot close channel")]
     CannotCloseChannel(String),
 
+    #[error("Cannot complete funding: {0}")]
+    CannotCompleteFunding(String),
+
     #[error("Cannot estimate fees")]
     CannotEstimateFees,
 
     #[error("Batch transfer cannot be set to failed status")]
     CannotFailBatchTransfer,
 
+    #[error("Cannot use LSP: {0}")]
+    CannotUseLsp(String),
+
+    #[error("Cannot reject channel: {0}")]
+    CannotRejectChannel(String),
+
+    #[error("Cannot resolve HTLC: {0}")]
+    CannotResolveHtlc(String),
+
+    #[error("Cannot restore channels: {0}")]
+    CannotRestoreChannels(String),
+
+    #[error("Cannot retry sweep: {0}")]
+    CannotRetrySweep(String),
+
+    #[error("Cannot retry transfer: {0}")]
+    CannotRetryTransfer(String),
+
+    #[error("Cannot sign PSBT: {0}")]
+    CannotSignPsbt(String),
+
     #[error("Cannot call other APIs while node is changing state")]
     ChangingState,
 
+    #[error("Channel funding is still in progress (hint: retry later)")]
+    ChannelFundingInProgress,
+
+    #[error("Channel has not been closed")]
+    ChannelNotClosed,
+
+    #[error("Clearnet DNS resolutions are not allowed: {0}")]
+    ClearnetDnsRequired(String),
+
     #[error("Another payment for this invoice is already in status {0}")]
     DuplicatePayment(String),
 
+    #[error("The escrow has already been resolved")]
+    EscrowAlreadyResolved,
+
     #[error("The swap offer has expired")]
     ExpiredSwapOffer,
 
@@ -63,6 +157,9 @@ pub enum APIError {
     #[error("Failed broadcast: {0}")]
     FailedBroadcast(String),
 
+    #[error("Failed to update channel policy: {0}")]
+    FailedChannelPolicyUpdate(String),
+
     #[error("Failed closing channel: {0}")]
     FailedClosingChannel(String),
 
@@ -75,21 +172,33 @@ pub enum APIError {
     #[error("Unable to create keys seed file {0}: {1}")]
     FailedKeysCreation(String, String),
 
+    #[error("Failed to create offer: {0}")]
+    FailedOfferCreation(String),
+
+    #[error("Failed LSP request: {0}")]
+    FailedLspRequest(String),
+
+    #[error("Failed maker request: {0}")]
+    FailedMakerRequest(String),
+
     #[error("Failed to open channel: {0}")]
     FailedOpenChannel(String),
 
     #[error("Failed payment: {0}")]
     FailedPayment(String),
 
-    #[error("Failed to connect to peer")]
-    FailedPeerConnection,
-
     #[error("Failed to disconnect to peer: {0}")]
     FailedPeerDisconnection(String),
 
     #[error("Failed to send onion message: {0}")]
     FailedSendingOnionMessage(String),
 
+    #[error("Failed swap provider request: {0}")]
+    FailedSwapProviderRequest(String),
+
+    #[error("Incompatible peer: {0}")]
+    IncompatiblePeer(String),
+
     #[error("For an RGB operation both asset_id and asset_amount must be set")]
     IncompleteRGBInfo,
 
@@ -114,6 +223,9 @@ pub enum APIError {
     #[error("Invalid announce alias: {0}")]
     InvalidAnnounceAlias(String),
 
+    #[error("Invalid announce color: {0}")]
+    InvalidAnnounceColor(String),
+
     #[error("Invalid asset ID: {0}")]
     InvalidAssetID(String),
 
@@ -126,51 +238,183 @@ pub enum APIError {
     #[error("Invalid backup path")]
     InvalidBackupPath,
 
+    #[error("Invalid backup upload: {0}")]
+    InvalidBackupUpload(String),
+
     #[error("Invalid Biscuit token")]
     InvalidBiscuitToken,
 
+    #[error("Invalid block height: {0}")]
+    InvalidBlockHeight(String),
+
+    #[error("Invalid broadcast schedule: {0}")]
+    InvalidBroadcastSchedule(String),
+
+    #[error("Invalid chain subscription: {0}")]
+    InvalidChainSubscription(String),
+
+    #[error("Invalid channel acceptor: {0}")]
+    InvalidChannelAcceptor(String),
+
+    #[error("Invalid channel backup")]
+    InvalidChannelBackup,
+
+    #[error("Invalid channel depths: {0}")]
+    InvalidChannelDepths(String),
+
     #[error("Invalid channel ID")]
     InvalidChannelID,
 
+    #[error("Invalid channel limits: {0}")]
+    InvalidChannelLimits(String),
+
+    #[error("Invalid channel policy: {0}")]
+    InvalidChannelPolicy(String),
+
+    #[error("Invalid channel type: {0}")]
+    InvalidChannelType(String),
+
+    #[error("Invalid close options: {0}")]
+    InvalidCloseOptions(String),
+
+    #[error("Invalid coin selection: {0}")]
+    InvalidCoinSelection(String),
+
+    #[error("Invalid config: {0}")]
+    InvalidConfig(String),
+
+    #[error("Invalid connect options: {0}")]
+    InvalidConnectOptions(String),
+
+    #[error("Invalid consignment: {0}")]
+    InvalidConsignment(String),
+
     #[error("Invalid details: {0}")]
     InvalidDetails(String),
 
+    #[error("Invalid escrow: {0}")]
+    InvalidEscrow(String),
+
     #[error("Trying to request fee estimation for an invalid block number")]
     InvalidEstimationBlocks,
 
+    #[error("Invalid external funding: {0}")]
+    InvalidExternalFunding(String),
+
+    #[error("Invalid fallback options: {0}")]
+    InvalidFallbackOptions(String),
+
     #[error("Invalid fee rate: {0}")]
     InvalidFeeRate(String),
 
+    #[error("Invalid funding PSBT: {0}")]
+    InvalidFundingPsbt(String),
+
+    #[error("Invalid hedging policy: {0}")]
+    InvalidHedgingPolicy(String),
+
+    #[error("Invalid HTLC resolution: {0}")]
+    InvalidHtlcResolution(String),
+
     #[error("Invalid indexer: {0}")]
     InvalidIndexer(String),
 
+    #[error("Invalid intercept scope: {0}")]
+    InvalidInterceptScope(String),
+
     #[error("Invalid invoice: {0}")]
     InvalidInvoice(String),
 
+    #[error("Invalid invoice batch: {0}")]
+    InvalidInvoiceBatch(String),
+
+    #[error("Invalid invoice status request: {0}")]
+    InvalidInvoiceStatusRequest(String),
+
+    #[error("Invalid journal range: {0}")]
+    InvalidJournalRange(String),
+
+    #[error("Invalid key derivation: {0}")]
+    InvalidKeyDerivation(String),
+
+    #[error("Invalid label: {0}")]
+    InvalidLabel(String),
+
+    #[error("Invalid lease config: {0}")]
+    InvalidLeaseConfig(String),
+
+    #[error("Invalid LNURL-pay config: {0}")]
+    InvalidLnurlPay(String),
+
+    #[error("Invalid log level: {0}")]
+    InvalidLogLevel(String),
+
+    #[error("Invalid LSP config: {0}")]
+    InvalidLspConfig(String),
+
+    #[error("Invalid LSP options: {0}")]
+    InvalidLspOptions(String),
+
     #[error("Invalid media digest")]
     InvalidMediaDigest,
 
+    #[error("Invalid mnemonic: {0}")]
+    InvalidMnemonic(String),
+
+    #[error("Invalid multisig funding: {0}")]
+    InvalidMultisigFunding(String),
+
     #[error("Invalid name: {0}")]
     InvalidName(String),
 
+    #[error("Invalid network: {0}")]
+    InvalidNetwork(String),
+
     #[error("Invalid node IDs: {0}")]
     InvalidNodeIds(String),
 
+    #[error("Invalid nonce: {0}")]
+    InvalidNonce(String),
+
+    #[error("Invalid NWC connection: {0}")]
+    InvalidNwcConnection(String),
+
     #[error("Invalid onion data: {0}")]
     InvalidOnionData(String),
 
+    #[error("Invalid order: {0}")]
+    InvalidOrder(String),
+
+    #[error("Invalid pagination: {0}")]
+    InvalidPagination(String),
+
     #[error("Invalid payment hash: {0}")]
     InvalidPaymentHash(String),
 
+    #[error("Invalid payment limits: {0}")]
+    InvalidPaymentLimits(String),
+
+    #[error("Invalid payment preimage")]
+    InvalidPaymentPreimage,
+
     #[error("Invalid payment secret")]
     InvalidPaymentSecret,
 
     #[error("Invalid password: {0}")]
     InvalidPassword(String),
 
+    #[error("Invalid peer filter: {0}")]
+    InvalidPeerFilter(String),
+
     #[error("Invalid peer info: {0}")]
     InvalidPeerInfo(String),
 
+    #[error("Invalid period: {0}")]
+    InvalidPeriod(String),
+
+    #[error("Invalid phantom options: {0}")]
+    InvalidPhantomOptions(String),
+
     #[error("Invalid precision: {0}")]
     InvalidPrecision(String),
 
@@ -180,9 +424,15 @@ pub enum APIError {
     #[error("Invalid proxy protocol version: {0}")]
     InvalidProxyProtocol(String),
 
+    #[error("Invalid PSBT: {0}")]
+    InvalidPsbt(String),
+
     #[error("Invalid pubkey")]
     InvalidPubkey,
 
+    #[error("Invalid rebalance: {0}")]
+    InvalidRebalance(String),
+
     #[error("The provided recipient data is invalid: {0}")]
     InvalidRecipientData(String),
 
@@ -192,6 +442,15 @@ pub enum APIError {
     #[error("The provided recipient ID is for a different network than the wallet's one")]
     InvalidRecipientNetwork,
 
+    #[error("Invalid recipients: {0}")]
+    InvalidRecipients(String),
+
+    #[error("Invalid route: {0}")]
+    InvalidRoute(String),
+
+    #[error("Invalid route hint: {0}")]
+    InvalidRouteHint(String),
+
     #[error("Invalid swap: {0}")]
     InvalidSwap(String),
 
@@ -213,12 +472,21 @@ pub enum APIError {
     #[error("IO error: {0}")]
     IO(#[from] std::io::Error),
 
+    #[error("Invoice is not claimable: {0}")]
+    InvoiceNotClaimable(String),
+
+    #[error("Invoice quota exceeded: {0}")]
+    InvoiceQuotaExceeded(String),
+
     #[error(transparent)]
     JsonExtractorRejection(#[from] JsonRejection),
 
     #[error("Node is locked (hint: call unlock)")]
     LockedNode,
 
+    #[error("UTXO {0} is locked (hint: call unlockutxo)")]
+    LockedUtxo(String),
+
     #[error("Media file is empty")]
     MediaFileEmpty,
 
@@ -234,6 +502,9 @@ pub enum APIError {
     #[error("Unable to find payment preimage, be sure you've provided the correct swap info")]
     MissingSwapPaymentPreimage,
 
+    #[error("A TOTP code is required for this operation")]
+    MissingTotpCode,
+
     #[error("Network error: {0}")]
     Network(String),
 
@@ -252,53 +523,164 @@ pub enum APIError {
     #[error("No valid transport endpoint found")]
     NoValidTransportEndpoint,
 
+    #[error("No view password has been set (hint: call setviewpassword)")]
+    NoViewPassword,
+
     #[error("Cannot perform this operation while an open channel operation is in progress")]
     OpenChannelInProgress,
 
     #[error("Output below the dust limit")]
     OutputBelowDustLimit,
 
+    #[error("Payment hash already used")]
+    PaymentHashAlreadyUsed,
+
     #[error("Payment not found: {0}")]
     PaymentNotFound(String),
 
+    #[error("Payment is not pending")]
+    PaymentNotPending,
+
+    #[error("Cannot resolve the peer address: {0}")]
+    PeerAddressUnresolved(String),
+
+    #[error("The peer closed the connection during the handshake")]
+    PeerConnectionRejected,
+
+    #[error("Connection to the peer timed out after {0}s")]
+    PeerConnectionTimeout(u64),
+
+    #[error("Handshake with the peer timed out after {0}s")]
+    PeerHandshakeTimeout(u64),
+
+    #[error("Peer is unreachable: {0}")]
+    PeerUnreachable(String),
+
+    #[error("No phantom secret configured (hint: start the node with --phantom-secret-path)")]
+    PhantomNotConfigured,
+
+    #[error(transparent)]
+    QueryExtractorRejection(#[from] QueryRejection),
+
+    #[error("Too many requests, retry in {0} seconds")]
+    RateLimited(u64),
+
     #[error("Recipient ID already used")]
     RecipientIDAlreadyUsed,
 
+    #[error("The node is shutting down")]
+    ShuttingDown,
+
     #[error("Swap not found: {0}")]
     SwapNotFound(String),
 
+    #[error("No swap provider configured (hint: start the node with --swap-provider-url)")]
+    SwapProviderNotConfigured,
+
     #[error("Temporary channel ID already used")]
     TemporaryChannelIdAlreadyUsed,
 
+    #[error("Token limit exceeded: {0}")]
+    TokenLimitExceeded(String),
+
+    #[error("TOTP is already enabled")]
+    TotpAlreadyEnabled,
+
+    #[error("TOTP has not been enrolled")]
+    TotpNotEnrolled,
+
+    #[error("Too many wrong TOTP codes, retry in {0} seconds")]
+    TotpRateLimited(u64),
+
+    #[error("Transfer not found: {0}")]
+    TransferNotFound(i32),
+
     #[error("Unexpected error: {0}")]
     Unexpected(String),
 
+    #[error("Unknown chain subscription")]
+    UnknownChainSubscription,
+
     #[error("Unknown channel ID")]
     UnknownChannelId,
 
+    #[error("Unknown consignment")]
+    UnknownConsignment,
+
     #[error("Unknown RGB contract ID")]
     UnknownContractId,
 
+    #[error("Unknown escrow")]
+    UnknownEscrow,
+
+    #[error("Unknown hedging policy")]
+    UnknownHedgingPolicy,
+
+    #[error("Unknown intercept scope")]
+    UnknownInterceptScope,
+
+    #[error("Unknown intercepted HTLC")]
+    UnknownInterceptedHtlc,
+
+    #[error("Unknown lightning address")]
+    UnknownLightningAddress,
+
     #[error("Unknown LN invoice")]
     UnknownLNInvoice,
 
+    #[error("Unknown node")]
+    UnknownNode,
+
+    #[error("Unknown NWC connection")]
+    UnknownNwcConnection,
+
+    #[error("Unknown order")]
+    UnknownOrder,
+
+    #[error("Unknown pending broadcast")]
+    UnknownPendingBroadcast,
+
+    #[error("Unknown sweep")]
+    UnknownSweep,
+
     #[error("Unknown temporary channel ID")]
     UnknownTemporaryChannelId,
 
+    #[error("Unknown transaction")]
+    UnknownTransaction,
+
+    #[error("Unknown UTXO")]
+    UnknownUtxo,
+
     #[error("Node is unlocked (hint: call lock)")]
     UnlockedNode,
 
+    #[error("Unsupported address type: {0}")]
+    UnsupportedAddressType(String),
+
     #[error("The provided backup has an unsupported version: {version}")]
     UnsupportedBackupVersion { version: String },
 
+    #[error("Dry run is not supported: {0}")]
+    UnsupportedDryRun(String),
+
     #[error("Layer 1 {0} is not supported")]
     UnsupportedLayer1(String),
 
+    #[error("RGB assets are not supported in BOLT12 offers yet")]
+    UnsupportedRgbOffer,
+
     #[error("Transport type is not supported")]
     UnsupportedTransportType,
 
+    #[error("UTXO is not locked")]
+    UtxoNotLocked,
+
     #[error("The provided password is incorrect")]
     WrongPassword,
+
+    #[error("The provided TOTP code is incorrect")]
+    WrongTotpCode,
 }
 
 impl APIError {
@@ -312,6 +694,29 @@ impl APIError {
             .unwrap()
             .to_string()
     }
+
+    fn details(&self) -> Option<Value> {
+        match self {
+            APIError::AddressGapLimitReached(gap_limit) => Some(json!({ "gap_limit": gap_limit })),
+            APIError::InsufficientCapacity(fees_sat) => {
+                Some(json!({ "commitment_fees_sat": fees_sat }))
+            }
+            APIError::InsufficientFunds(missing_sat) => Some(json!({ "missing_sat": missing_sat })),
+            APIError::NetworkMismatch(bitcoind_network, network) => Some(json!({
+                "bitcoind_network": bitcoind_network,
+                "network": network.to_string(),
+            })),
+            APIError::PeerConnectionTimeout(timeout_sec)
+            | APIError::PeerHandshakeTimeout(timeout_sec) => {
+                Some(json!({ "timeout_sec": timeout_sec }))
+            }
+            APIError::RateLimited(retry_after_sec) | APIError::TotpRateLimited(retry_after_sec) => {
+                Some(json!({ "retry_after_sec": retry_after_sec }))
+            }
+            APIError::TransferNotFound(idx) => Some(json!({ "transfer_idx": idx })),
+            _ => None,
+        }
+    }
 }
 
 impl From<RgbLibError> for APIError {
@@ -400,10 +805,17 @@ impl IntoResponse for APIError {
                 json_rejection.body_text(),
                 self.name(),
             ),
-            APIError::FailedClosingChannel(_)
+            APIError::QueryExtractorRejection(ref query_rejection) => (
+                query_rejection.status(),
+                query_rejection.body_text(),
+                self.name(),
+            ),
+            APIError::FailedChannelPolicyUpdate(_)
+            | APIError::FailedClosingChannel(_)
             | APIError::FailedInvoiceCreation(_)
             | APIError::FailedIssuingAsset(_)
             | APIError::FailedKeysCreation(_, _)
+            | APIError::FailedOfferCreation(_)
             | APIError::FailedOpenChannel(_)
             | APIError::FailedPayment(_)
             | APIError::FailedPeerDisconnection(_)
@@ -415,35 +827,86 @@ impl IntoResponse for APIError {
                 self.name(),
             ),
             APIError::AnchorsRequired
+            | APIError::ClearnetDnsRequired(_)
             | APIError::ExpiredSwapOffer
             | APIError::IncompleteRGBInfo
             | APIError::InvalidAddress(_)
             | APIError::InvalidAmount(_)
             | APIError::InvalidAnnounceAddresses(_)
             | APIError::InvalidAnnounceAlias(_)
+            | APIError::InvalidAnnounceColor(_)
             | APIError::InvalidAssetID(_)
             | APIError::InvalidAssignment
             | APIError::InvalidAttachments(_)
             | APIError::InvalidBackupPath
+            | APIError::InvalidBackupUpload(_)
             | APIError::InvalidBiscuitToken
+            | APIError::InvalidBlockHeight(_)
+            | APIError::InvalidBroadcastSchedule(_)
+            | APIError::InvalidChainSubscription(_)
+            | APIError::InvalidChannelAcceptor(_)
+            | APIError::InvalidChannelBackup
+            | APIError::InvalidChannelDepths(_)
             | APIError::InvalidChannelID
+            | APIError::InvalidChannelLimits(_)
+            | APIError::InvalidChannelPolicy(_)
+            | APIError::InvalidChannelType(_)
+            | APIError::InvalidCloseOptions(_)
+            | APIError::InvalidCoinSelection(_)
+            | APIError::InvalidConfig(_)
+            | APIError::InvalidConnectOptions(_)
+            | APIError::InvalidConsignment(_)
             | APIError::InvalidDetails(_)
+            | APIError::InvalidEscrow(_)
             | APIError::InvalidEstimationBlocks
+            | APIError::InvalidExternalFunding(_)
+            | APIError::InvalidFallbackOptions(_)
             | APIError::InvalidFeeRate(_)
+            | APIError::InvalidFundingPsbt(_)
+            | APIError::InvalidHedgingPolicy(_)
+            | APIError::InvalidHtlcResolution(_)
+            | APIError::InvalidInterceptScope(_)
             | APIError::InvalidInvoice(_)
+            | APIError::InvalidInvoiceBatch(_)
+            | APIError::InvalidInvoiceStatusRequest(_)
+            | APIError::InvalidJournalRange(_)
+            | APIError::InvalidKeyDerivation(_)
+            | APIError::InvalidLabel(_)
+            | APIError::InvalidLeaseConfig(_)
+            | APIError::InvalidLnurlPay(_)
+            | APIError::InvalidLogLevel(_)
+            | APIError::InvalidLspConfig(_)
+            | APIError::InvalidLspOptions(_)
             | APIError::InvalidMediaDigest
+            | APIError::InvalidMnemonic(_)
+            | APIError::InvalidMultisigFunding(_)
             | APIError::InvalidName(_)
+            | APIError::InvalidNetwork(_)
             | APIError::InvalidNodeIds(_)
+            | APIError::InvalidNonce(_)
+            | APIError::InvalidNwcConnection(_)
             | APIError::InvalidOnionData(_)
+            | APIError::InvalidOrder(_)
+            | APIError::InvalidPagination(_)
             | APIError::InvalidPassword(_)
             | APIError::InvalidPaymentHash(_)
+            | APIError::InvalidPaymentLimits(_)
+            | APIError::InvalidPaymentPreimage
             | APIError::InvalidPaymentSecret
+            | APIError::InvalidPeerFilter(_)
             | APIError::InvalidPeerInfo(_)
+            | APIError::InvalidPeriod(_)
+            | APIError::InvalidPhantomOptions(_)
             | APIError::InvalidPrecision(_)
+            | APIError::InvalidPsbt(_)
             | APIError::InvalidPubkey
+            | APIError::InvalidRebalance(_)
             | APIError::InvalidRecipientData(_)
             | APIError::InvalidRecipientID
             | APIError::InvalidRecipientNetwork
+            | APIError::InvalidRecipients(_)
+            | APIError::InvalidRoute(_)
+            | APIError::InvalidRouteHint(_)
             | APIError::InvalidSwap(_)
             | APIError::InvalidSwapString(_, _)
             | APIError::InvalidTicker(_)
@@ -454,52 +917,111 @@ impl IntoResponse for APIError {
             | APIError::MediaFileNotProvided
             | APIError::MissingSwapPaymentPreimage
             | APIError::OutputBelowDustLimit
-            | APIError::UnsupportedBackupVersion { .. } => {
+            | APIError::UnsupportedBackupVersion { .. }
+            | APIError::UnsupportedDryRun(_) => {
                 (StatusCode::BAD_REQUEST, self.to_string(), self.name())
             }
-            APIError::WrongPassword => (StatusCode::UNAUTHORIZED, self.to_string(), self.name()),
-            APIError::AllocationsAlreadyAvailable
+            APIError::MissingTotpCode | APIError::WrongPassword | APIError::WrongTotpCode => {
+                (StatusCode::UNAUTHORIZED, self.to_string(), self.name())
+            }
+            APIError::InvoiceQuotaExceeded(_)
+            | APIError::RateLimited(_)
+            | APIError::TotpRateLimited(_) => {
+                (StatusCode::TOO_MANY_REQUESTS, self.to_string(), self.name())
+            }
+            APIError::AddressGapLimitReached(_)
+            | APIError::AllocationsAlreadyAvailable
             | APIError::AlreadyInitialized
             | APIError::AlreadyUnlocked
+            | APIError::AssetNotInflatable(_)
             | APIError::AuthenticationDisabled
             | APIError::BatchTransferNotFound
+            | APIError::CannotAcceptChannel(_)
+            | APIError::CannotBumpCloseFee(_)
+            | APIError::CannotBumpFee(_)
+            | APIError::CannotBuyInbound(_)
             | APIError::CannotCloseChannel(_)
+            | APIError::CannotCompleteFunding(_)
             | APIError::CannotEstimateFees
             | APIError::CannotFailBatchTransfer
+            | APIError::CannotRejectChannel(_)
+            | APIError::CannotResolveHtlc(_)
+            | APIError::CannotRestoreChannels(_)
+            | APIError::CannotRetrySweep(_)
+            | APIError::CannotRetryTransfer(_)
+            | APIError::CannotSignPsbt(_)
+            | APIError::CannotUseLsp(_)
             | APIError::ChangingState
+            | APIError::ChannelFundingInProgress
+            | APIError::ChannelNotClosed
             | APIError::DuplicatePayment(_)
+            | APIError::EscrowAlreadyResolved
             | APIError::FailedBdkSync(_)
             | APIError::FailedBitcoindConnection(_)
             | APIError::FailedBroadcast(_)
-            | APIError::FailedPeerConnection
+            | APIError::FailedLspRequest(_)
+            | APIError::FailedMakerRequest(_)
+            | APIError::FailedSwapProviderRequest(_)
+            | APIError::IncompatiblePeer(_)
             | APIError::InsufficientAssets
             | APIError::InsufficientCapacity(_)
             | APIError::InsufficientFunds(_)
             | APIError::InvalidIndexer(_)
             | APIError::InvalidProxyEndpoint
             | APIError::InvalidProxyProtocol(_)
+            | APIError::InvoiceNotClaimable(_)
             | APIError::LockedNode
+            | APIError::LockedUtxo(_)
             | APIError::MaxFeeExceeded(_)
             | APIError::MinFeeNotMet(_)
             | APIError::NetworkMismatch(_, _)
             | APIError::NoAvailableUtxos
             | APIError::NoRoute
             | APIError::NotInitialized
+            | APIError::NoViewPassword
             | APIError::OpenChannelInProgress
+            | APIError::PaymentHashAlreadyUsed
             | APIError::PaymentNotFound(_)
+            | APIError::PaymentNotPending
+            | APIError::PeerAddressUnresolved(_)
+            | APIError::PeerConnectionRejected
+            | APIError::PeerConnectionTimeout(_)
+            | APIError::PeerHandshakeTimeout(_)
+            | APIError::PeerUnreachable(_)
+            | APIError::PhantomNotConfigured
             | APIError::RecipientIDAlreadyUsed
             | APIError::SwapNotFound(_)
+            | APIError::SwapProviderNotConfigured
             | APIError::TemporaryChannelIdAlreadyUsed
+            | APIError::TokenLimitExceeded(_)
+            | APIError::TotpAlreadyEnabled
+            | APIError::TotpNotEnrolled
+            | APIError::TransferNotFound(_)
+            | APIError::UnknownChainSubscription
             | APIError::UnknownChannelId
+            | APIError::UnknownConsignment
             | APIError::UnknownContractId
+            | APIError::UnknownEscrow
+            | APIError::UnknownHedgingPolicy
+            | APIError::UnknownInterceptScope
+            | APIError::UnknownInterceptedHtlc
+            | APIError::UnknownLightningAddress
             | APIError::UnknownLNInvoice
+            | APIError::UnknownNode
+            | APIError::UnknownNwcConnection
+            | APIError::UnknownOrder
+            | APIError::UnknownPendingBroadcast
+            | APIError::UnknownSweep
             | APIError::UnknownTemporaryChannelId
+            | APIError::UnknownTransaction
+            | APIError::UnknownUtxo
             | APIError::UnlockedNode
+            | APIError::UnsupportedAddressType(_)
             | APIError::UnsupportedLayer1(_)
-            | APIError::UnsupportedTransportType => {
-                (StatusCode::FORBIDDEN, self.to_string(), self.name())
-            }
-            APIError::Network(_) | APIError::NoValidTransportEndpoint => (
+            | APIError::UnsupportedRgbOffer
+            | APIError::UnsupportedTransportType
+            | APIError::UtxoNotLocked => (StatusCode::FORBIDDEN, self.to_string(), self.name()),
+            APIError::Network(_) | APIError::NoValidTransportEndpoint | APIError::ShuttingDown => (
                 StatusCode::SERVICE_UNAVAILABLE,
                 self.to_string(),
                 self.name(),
@@ -511,10 +1033,10 @@ impl IntoResponse for APIError {
         tracing::error!("APIError: {error}");
 
         let body = Json(
-            serde_json::to_value(APIErrorResponse {
-                error,
-                code: status.as_u16(),
-                name,
+            serde_json::to_value(APIErrorResponseV2 {
+                code: name,
+                message: error,
+                details: self.details(),
             })
             .unwrap(),
         );
@@ -526,18 +1048,88 @@ impl IntoResponse for APIError {
 /// The error variants returned by the app
 #[derive(Debug, thiserror::Error)]
 pub enum AppError {
+    #[error("Audit failed: {0}")]
+    FailedAudit(String),
+
+    #[error("Storage migration failed: {0}")]
+    FailedMigration(String),
+
+    #[error("Invalid announce alias: {0}")]
+    InvalidAnnounceAlias(String),
+
+    #[error("Invalid announce color: {0}")]
+    InvalidAnnounceColor(String),
+
+    #[error("The provided auditor key is invalid")]
+    InvalidAuditorKey,
+
     #[error("The provided authentication args are invalid")]
     InvalidAuthenticationArgs,
 
+    #[error("Invalid auto backup target: {0}")]
+    InvalidAutoBackupTarget(String),
+
+    #[error("Invalid CLTV configuration: {0}")]
+    InvalidCltvConfig(String),
+
+    #[error("Invalid config file: {0}")]
+    InvalidConfig(String),
+
+    #[error("Invalid CORS origin: {0}")]
+    InvalidCorsOrigin(String),
+
+    #[error("Invalid hook command: {0}")]
+    InvalidHookCommand(String),
+
+    #[error("Invalid log level: {0}")]
+    InvalidLogLevel(String),
+
+    #[error("Invalid notification configuration: {0}")]
+    InvalidNotificationConfig(String),
+
+    #[error("Invalid phantom secret: {0}")]
+    InvalidPhantomSecret(String),
+
     #[error("The revoked tokens file contains an invalid entry")]
     InvalidRevokedTokensFile,
 
+    #[error("Invalid RGS URL: {0}")]
+    InvalidRgsUrl(String),
+
+    #[cfg(feature = "simulation")]
+    #[error("Invalid simulation config: {0}")]
+    InvalidSimulationConfig(String),
+
     #[error("The provided root public key is invalid")]
     InvalidRootKey,
 
+    #[error("Invalid storage backend: {0}")]
+    InvalidStorageBackend(String),
+
+    #[error("Invalid storage directory: {0}")]
+    InvalidStorageDir(String),
+
+    #[error("Invalid storage encryption: {0}")]
+    InvalidStorageEncryption(String),
+
+    #[error("Invalid TLS configuration: {0}")]
+    InvalidTlsConfig(String),
+
+    #[error("Invalid Tor configuration: {0}")]
+    InvalidTorConfig(String),
+
+    #[error("Invalid URL prefix: {0}")]
+    InvalidUrlPrefix(String),
+
+    #[error("Invalid UTXO pool configuration: {0}")]
+    InvalidUtxoPoolConfig(String),
+
     #[error("IO error: {0}")]
     IO(#[from] std::io::Error),
 
+    #[error("The node has been initialized on {0}, it cannot be started on {1}")]
+    NetworkMismatch(String, BitcoinNetwork),
+
     #[error("Port {0} is unavailable")]
     UnavailablePort(u16),
 }
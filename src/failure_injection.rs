@@ -0,0 +1,61 @@
+use lightning::ln::channelmanager::FailureCode;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::routes::InjectedFailure;
+use crate::utils::UnlockedAppState;
+
+/// Failures armed by the integration tests of downstream apps, to reproduce the error paths
+/// deterministically
+#[derive(Default)]
+pub(crate) struct FailureInjector {
+    next_failure: Mutex<Option<InjectedFailure>>,
+    proxy_delay: Mutex<Option<Duration>>,
+}
+
+impl FailureInjector {
+    pub(crate) fn arm(&self, failure: Option<InjectedFailure>, proxy_delay: Option<Duration>) {
+        *self.next_failure.lock().unwrap() = failure;
+        *self.proxy_delay.lock().unwrap() = proxy_delay;
+    }
+
+    /// Disarm the armed failure if it's one of the given ones, returning it
+    fn take(&self, failures: &[InjectedFailure]) -> Option<InjectedFailure> {
+        let mut next_failure = self.next_failure.lock().unwrap();
+        match *next_failure {
+            Some(failure) if failures.contains(&failure) => next_failure.take(),
+            _ => None,
+        }
+    }
+}
+
+impl UnlockedAppState {
+    /// Whether the next outbound payment has been armed to find no route
+    pub(crate) fn take_injected_no_route(&self) -> bool {
+        self.failure_injector
+            .take(&[InjectedFailure::NoRoute])
+            .is_some()
+    }
+
+    /// Reason the next received payment has been armed to be failed back with
+    pub(crate) fn take_injected_receive_failure(&self) -> Option<FailureCode> {
+        self.failure_injector
+            .take(&[
+                InjectedFailure::TemporaryNodeFailure,
+                InjectedFailure::IncorrectPaymentDetails,
+            ])
+            .map(|failure| match failure {
+                InjectedFailure::TemporaryNodeFailure => FailureCode::TemporaryNodeFailure,
+                _ => FailureCode::IncorrectOrUnknownPaymentDetails,
+            })
+    }
+
+    /// Wait for the armed delay before a request to the RGB proxy
+    pub(crate) fn delay_proxy_request(&self) {
+        let proxy_delay = *self.failure_injector.proxy_delay.lock().unwrap();
+        if let Some(proxy_delay) = proxy_delay {
+            tracing::info!("Delaying RGB proxy request by {proxy_delay:?}");
+            std::thread::sleep(proxy_delay);
+        }
+    }
+}
@@ -0,0 +1,365 @@
+use amplify::{map, s};
+use bitcoin::psbt::Psbt;
+use bitcoin::{Amount, OutPoint, Script, Transaction, TxOut, Txid};
+use rgb_lib::{
+    bitcoin::psbt::Psbt as RgbLibPsbt,
+    utils::script_buf_from_recipient_id,
+    wallet::{
+        rust_only::{AssetColoringInfo, ColoringInfo},
+        TransportEndpoint,
+    },
+    Assignment, ContractId, TransferKind, TransferStatus,
+};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::bitcoind::MempoolEntryResponse;
+use crate::coin_selection::{build_psbt, input_vsize, output_vsize, Candidate, TX_OVERHEAD_VSIZE};
+use crate::error::APIError;
+use crate::rgb::assignment_amount;
+use crate::routes::{FeeBumpMethod, DUST_LIMIT_MSAT};
+use crate::utils::UnlockedAppState;
+
+/// Add the confirmed candidates, largest first, to the inputs until they cover the outputs and
+/// the fee required for the resulting vsize, returning the inputs and the change amount
+fn fund(
+    mut inputs: Vec<Candidate>,
+    mut candidates: Vec<Candidate>,
+    outputs_sat: u64,
+    outputs_vsize: u64,
+    change_script: &Script,
+    required_fee: impl Fn(u64) -> u64,
+) -> Result<(Vec<Candidate>, Option<u64>), APIError> {
+    candidates.retain(|c| c.confirmations > 0);
+    candidates.sort_by_key(|c| c.txout.value);
+    loop {
+        let inputs_sat: u64 = inputs.iter().map(|c| c.txout.value.to_sat()).sum();
+        let vsize = TX_OVERHEAD_VSIZE + outputs_vsize + inputs.iter().map(|c| c.vsize).sum::<u64>();
+        let needed_sat = outputs_sat + required_fee(vsize + output_vsize(change_script));
+        if inputs_sat >= needed_sat && (inputs_sat - needed_sat) * 1000 >= DUST_LIMIT_MSAT {
+            return Ok((inputs, Some(inputs_sat - needed_sat)));
+        }
+        // without outputs to pay the transaction needs the change one
+        let needed_sat = if outputs_vsize > 0 {
+            outputs_sat + required_fee(vsize)
+        } else {
+            needed_sat + DUST_LIMIT_MSAT / 1000
+        };
+        if outputs_vsize > 0 && inputs_sat >= needed_sat {
+            return Ok((inputs, None));
+        }
+        let Some(candidate) = candidates.pop() else {
+            return Err(APIError::InsufficientFunds(
+                needed_sat.saturating_sub(inputs_sat),
+            ));
+        };
+        inputs.push(candidate);
+    }
+}
+
+impl UnlockedAppState {
+    /// Make an unconfirmed wallet transaction confirm faster, at the given fee rate, returning
+    /// the txid of the transaction paying the higher fee and how the fee has been bumped
+    pub(crate) async fn bump_fee(
+        &self,
+        txid: Txid,
+        fee_rate: u64,
+        skip_sync: bool,
+    ) -> Result<(Txid, FeeBumpMethod), APIError> {
+        if fee_rate == 0 {
+            return Err(APIError::InvalidFeeRate(s!("must be at least 1 sat/vB")));
+        }
+        let wallet_tx = self
+            .rgb_list_transactions(skip_sync)?
+            .into_iter()
+            .find(|t| t.txid == txid.to_string())
+            .ok_or(APIError::UnknownTransaction)?;
+        if wallet_tx.confirmation_time.is_some() {
+            return Err(APIError::CannotBumpFee(s!(
+                "transaction is already confirmed"
+            )));
+        }
+        let (Some(tx), Some(entry)) = (
+            self.bitcoind_client.get_raw_transaction(&txid, None).await,
+            self.bitcoind_client.get_mempool_entry(&txid).await,
+        ) else {
+            return Err(APIError::CannotBumpFee(s!(
+                "transaction is not in the mempool"
+            )));
+        };
+        if fee_rate * entry.vsize <= entry.fee_sat {
+            return Err(APIError::CannotBumpFee(format!(
+                "fee rate must be higher than the current {:.1} sat/vB",
+                entry.fee_sat as f64 / entry.vsize as f64
+            )));
+        }
+
+        let candidates = self.list_coin_candidates(true).await?;
+        // RGB transfers reference the txid of their witness TX and channels the one of their
+        // funding TX, so they're bumped by a child TX instead of being replaced
+        let is_funding = self
+            .channel_manager
+            .list_channels()
+            .iter()
+            .any(|c| c.funding_txo.map(|o| o.txid) == Some(txid));
+        let (psbt, method) = if matches!(wallet_tx.transaction_type, rgb_lib::TransactionType::User)
+            && !is_funding
+        {
+            let psbt = self
+                .build_replacement(tx, entry, fee_rate, candidates)
+                .await?;
+            (psbt, FeeBumpMethod::Rbf)
+        } else {
+            let psbt = self.build_child(txid, entry, fee_rate, candidates)?;
+            (psbt, FeeBumpMethod::Cpfp)
+        };
+
+        let signed_psbt = self.sign_built_psbt(psbt)?;
+        let new_txid = self.rgb_send_btc_end(signed_psbt)?;
+        tracing::info!("Bumped the fee of TX {txid} with {method:?} TX {new_txid}");
+        Ok((Txid::from_str(&new_txid).unwrap(), method))
+    }
+
+    /// Make the witness TX of an incoming transfer confirm faster, at the given fee rate, by
+    /// spending its output to us in a child paying the fee for both, returning the txid of the
+    /// child and the fee it pays. Spending the output without a state transition would burn the
+    /// received assets, so the child moves them to a new output of ours
+    pub(crate) async fn accelerate_incoming(
+        &self,
+        idx: i32,
+        fee_rate: u64,
+        max_fee_sat: u64,
+    ) -> Result<(Txid, u64), APIError> {
+        if fee_rate == 0 {
+            return Err(APIError::InvalidFeeRate(s!("must be at least 1 sat/vB")));
+        }
+        let (asset_id, transfer) = self
+            .rgb_get_transfer(idx)?
+            .ok_or(APIError::TransferNotFound(idx))?;
+        if !matches!(transfer.kind, TransferKind::ReceiveWitness)
+            || !matches!(transfer.status, TransferStatus::WaitingConfirmations)
+        {
+            return Err(APIError::CannotBumpFee(s!(
+                "only incoming witness transfers waiting for confirmations can be accelerated"
+            )));
+        }
+        let (Some(txid), Some(receive_utxo)) = (transfer.txid, transfer.receive_utxo) else {
+            return Err(APIError::CannotBumpFee(s!(
+                "transfer has no witness transaction output to us"
+            )));
+        };
+        let txid = Txid::from_str(&txid).unwrap();
+        let outpoint = OutPoint::from_str(&receive_utxo.to_string()).unwrap();
+        let (Some(tx), Some(entry)) = (
+            self.bitcoind_client.get_raw_transaction(&txid, None).await,
+            self.bitcoind_client.get_mempool_entry(&txid).await,
+        ) else {
+            return Err(APIError::CannotBumpFee(s!(
+                "transaction is not in the mempool"
+            )));
+        };
+        if fee_rate * entry.vsize <= entry.fee_sat {
+            return Err(APIError::CannotBumpFee(format!(
+                "fee rate must be higher than the current {:.1} sat/vB",
+                entry.fee_sat as f64 / entry.vsize as f64
+            )));
+        }
+        let txout = tx.output[outpoint.vout as usize].clone();
+        let vsize = input_vsize(&txout.script_pubkey)
+            .ok_or_else(|| APIError::CannotBumpFee(format!("unsupported output {outpoint}")))?;
+        let received = Candidate {
+            outpoint,
+            txout,
+            confirmations: 0,
+            vsize,
+        };
+
+        // the received assets go to a new output, announced to ourselves through the proxy
+        let receive_data = self.rgb_witness_receive(
+            Some(asset_id.clone()),
+            Assignment::Any,
+            None,
+            vec![self.proxy_endpoint.clone()],
+            0,
+        )?;
+        let receive_script = script_buf_from_recipient_id(receive_data.recipient_id.clone())?
+            .expect("witness recipient");
+        let receive_sat = DUST_LIMIT_MSAT / 1000;
+        let candidates = self.list_coin_candidates(true).await?;
+        let change_script = self.change_script()?;
+        let (inputs, change_sat) = fund(
+            vec![received],
+            candidates,
+            receive_sat,
+            output_vsize(&receive_script),
+            &change_script,
+            |vsize| {
+                (fee_rate * (entry.vsize + vsize))
+                    .saturating_sub(entry.fee_sat)
+                    .max(vsize)
+            },
+        )?;
+        let inputs_sat: u64 = inputs.iter().map(|c| c.txout.value.to_sat()).sum();
+        let fee_sat = inputs_sat - receive_sat - change_sat.unwrap_or(0);
+        if fee_sat > max_fee_sat {
+            return Err(APIError::MaxFeeExceeded(format!(
+                "the child fee of {fee_sat} sats exceeds max_fee_sat"
+            )));
+        }
+        let mut output = vec![TxOut {
+            value: Amount::from_sat(receive_sat),
+            script_pubkey: receive_script,
+        }];
+        if let Some(change_sat) = change_sat {
+            output.push(TxOut {
+                value: Amount::from_sat(change_sat),
+                script_pubkey: change_script,
+            });
+        }
+        let psbt = build_psbt(inputs, output);
+
+        let contract_id = ContractId::from_str(&asset_id).unwrap();
+        let amount: u64 = transfer.assignments.iter().map(assignment_amount).sum();
+        let coloring_info = ColoringInfo {
+            asset_info_map: map![contract_id => AssetColoringInfo {
+                output_map: HashMap::from_iter([(0, amount)]),
+                static_blinding: None,
+            }],
+            static_blinding: None,
+            nonce: None,
+        };
+        let mut psbt = RgbLibPsbt::from_str(&psbt.to_string()).unwrap();
+        let consignments = self
+            .rgb_wallet_wrapper
+            .color_psbt_and_consume(&mut psbt, coloring_info)?;
+        let psbt = Psbt::from_str(&psbt.to_string()).unwrap();
+        let child_txid = psbt.unsigned_tx.compute_txid();
+        let signed_psbt = self.sign_built_psbt(psbt)?;
+
+        let proxy_url = TransportEndpoint::new(self.proxy_endpoint.clone())?.endpoint;
+        let consignment_dir = tempfile::tempdir()?;
+        for consignment in consignments {
+            let consignment_path = consignment_dir.path().join("consignment");
+            consignment
+                .save_file(&consignment_path)
+                .map_err(|e| APIError::Unexpected(format!("cannot save consignment: {e}")))?;
+            self.rgb_post_consignment(
+                &proxy_url,
+                receive_data.recipient_id.clone(),
+                &consignment_path,
+                child_txid.to_string(),
+                Some(0),
+            )?;
+        }
+        self.rgb_send_btc_end(signed_psbt)?;
+        tracing::info!(
+            "Accelerated incoming TX {txid} with CPFP TX {child_txid} paying {fee_sat} sats"
+        );
+        Ok((child_txid, fee_sat))
+    }
+
+    /// Build a child spending the wallet outputs of the parent, paying the fee for both
+    fn build_child(
+        &self,
+        parent_txid: Txid,
+        parent_entry: MempoolEntryResponse,
+        fee_rate: u64,
+        candidates: Vec<Candidate>,
+    ) -> Result<Psbt, APIError> {
+        let (parent_outputs, candidates): (Vec<_>, Vec<_>) = candidates
+            .into_iter()
+            .partition(|c| c.outpoint.txid == parent_txid);
+        if parent_outputs.is_empty() {
+            return Err(APIError::CannotBumpFee(s!(
+                "transaction has no unlocked vanilla output to spend"
+            )));
+        }
+
+        let change_script = self.change_script()?;
+        let (inputs, change_sat) =
+            fund(parent_outputs, candidates, 0, 0, &change_script, |vsize| {
+                (fee_rate * (parent_entry.vsize + vsize))
+                    .saturating_sub(parent_entry.fee_sat)
+                    .max(vsize)
+            })?;
+        let output = vec![TxOut {
+            value: Amount::from_sat(change_sat.expect("child has a change output")),
+            script_pubkey: change_script,
+        }];
+        Ok(build_psbt(inputs, output))
+    }
+
+    /// Build a transaction replacing the given one, spending the same inputs and paying the same
+    /// recipients, taking the higher fee from the change and adding inputs if needed
+    async fn build_replacement(
+        &self,
+        tx: Transaction,
+        entry: MempoolEntryResponse,
+        fee_rate: u64,
+        mut candidates: Vec<Candidate>,
+    ) -> Result<Psbt, APIError> {
+        let txid = tx.compute_txid();
+        // outputs of the transaction being replaced cannot be spent by the replacement
+        let wallet_vouts: Vec<u32> = candidates
+            .iter()
+            .filter(|c| c.outpoint.txid == txid)
+            .map(|c| c.outpoint.vout)
+            .collect();
+        candidates.retain(|c| c.outpoint.txid != txid);
+
+        let mut inputs = vec![];
+        for txin in &tx.input {
+            let txout = self
+                .bitcoind_client
+                .get_spent_txout(&txin.previous_output)
+                .await
+                .ok_or_else(|| {
+                    APIError::CannotBumpFee(format!("cannot find input {}", txin.previous_output))
+                })?;
+            let vsize = input_vsize(&txout.script_pubkey).ok_or_else(|| {
+                APIError::CannotBumpFee(format!("unsupported input {}", txin.previous_output))
+            })?;
+            inputs.push(Candidate {
+                outpoint: txin.previous_output,
+                txout,
+                confirmations: 0,
+                vsize,
+            });
+        }
+        // the change, if any, is the last wallet output
+        let change_vout = wallet_vouts.iter().max().copied();
+        let mut output: Vec<TxOut> = tx
+            .output
+            .iter()
+            .enumerate()
+            .filter(|(vout, _)| Some(*vout as u32) != change_vout)
+            .map(|(_, o)| o.clone())
+            .collect();
+        let change_script = match change_vout {
+            Some(vout) => tx.output[vout as usize].script_pubkey.clone(),
+            None => self.change_script()?,
+        };
+
+        let outputs_sat = output.iter().map(|o| o.value.to_sat()).sum();
+        let outputs_vsize = output.iter().map(|o| output_vsize(&o.script_pubkey)).sum();
+        // the replacement must pay for its own relay on top of the fee of the replaced TX
+        let (inputs, change_sat) = fund(
+            inputs,
+            candidates,
+            outputs_sat,
+            outputs_vsize,
+            &change_script,
+            |vsize| (fee_rate * vsize).max(entry.fee_sat + vsize),
+        )?;
+        if let Some(change_sat) = change_sat {
+            output.push(TxOut {
+                value: Amount::from_sat(change_sat),
+                script_pubkey: change_script,
+            });
+        }
+        let mut psbt = build_psbt(inputs, output);
+        psbt.unsigned_tx.version = tx.version;
+        psbt.unsigned_tx.lock_time = tx.lock_time;
+        Ok(psbt)
+    }
+}
@@ -0,0 +1,349 @@
+use amplify::s;
+use bitcoin::secp256k1::PublicKey;
+use lightning::impl_writeable_tlv_based;
+use lightning::ln::msgs::{
+    BaseMessageHandler, ChannelAnnouncement, ChannelUpdate, Init, LightningError, MessageSendEvent,
+    NodeAnnouncement, QueryChannelRange, QueryShortChannelIds, ReplyChannelRange,
+    ReplyShortChannelIdsEnd, RoutingMessageHandler,
+};
+use lightning::routing::gossip::{NodeId, P2PGossipSync};
+use lightning::types::features::{InitFeatures, NodeFeatures};
+use lightning::util::hash_tables::HashMap as LdkHashMap;
+use lightning::util::ser::Writeable;
+use lightning_rapid_gossip_sync::RapidGossipSync;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::disk::FilesystemLogger;
+use crate::error::AppError;
+use crate::ldk::{GossipVerifier, NetworkGraph};
+use crate::tor::{http_get_via_socks, CircuitGroup, TorProxy};
+use crate::utils::get_current_timestamp;
+
+const GOSSIP_WINDOW_SECS: u64 = 60;
+/// Interval between two downloads of the RGS snapshot
+pub(crate) const RGS_SYNC_INTERVAL: Duration = Duration::from_secs(3600);
+
+pub(crate) type LdkGossipSync =
+    P2PGossipSync<Arc<NetworkGraph>, Arc<GossipVerifier>, Arc<FilesystemLogger>>;
+
+pub(crate) type LdkRapidGossipSync = RapidGossipSync<Arc<NetworkGraph>, Arc<FilesystemLogger>>;
+
+/// Check the RGS server URL, which can only be reached over plain HTTP through the SOCKS proxy
+pub(crate) fn check_rgs_url(rgs_url: &str, tor_proxy: Option<&TorProxy>) -> Result<(), AppError> {
+    let url = reqwest::Url::parse(rgs_url).map_err(|e| AppError::InvalidRgsUrl(e.to_string()))?;
+    match (url.scheme(), tor_proxy) {
+        ("http", _) | ("https", None) => Ok(()),
+        ("https", Some(_)) => Err(AppError::InvalidRgsUrl(s!(
+            "snapshots are downloaded through the SOCKS proxy over plain HTTP, use the URL of an \
+             onion service"
+        ))),
+        (scheme, _) => Err(AppError::InvalidRgsUrl(format!(
+            "unsupported scheme {scheme}"
+        ))),
+    }
+}
+
+/// Download the RGS snapshot of the graph changes since the last sync and apply it, returning the
+/// timestamp of the new sync
+pub(crate) async fn sync_rapid_gossip(
+    rapid_gossip_sync: &LdkRapidGossipSync,
+    network_graph: &NetworkGraph,
+    rgs_url: &str,
+    tor_proxy: Option<&TorProxy>,
+) -> Result<u32, String> {
+    let last_sync_timestamp = network_graph
+        .get_last_rapid_gossip_sync_timestamp()
+        .unwrap_or(0);
+    let url = format!("{}/{last_sync_timestamp}", rgs_url.trim_end_matches('/'));
+    let snapshot = match tor_proxy {
+        Some(proxy) => {
+            let url = reqwest::Url::parse(&url).map_err(|e| e.to_string())?;
+            http_get_via_socks(proxy, &url, CircuitGroup::Gossip)
+                .await
+                .map_err(|e| e.to_string())?
+        }
+        None => reqwest::get(&url)
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| e.to_string())?
+            .bytes()
+            .await
+            .map_err(|e| e.to_string())?
+            .to_vec(),
+    };
+    rapid_gossip_sync
+        .update_network_graph(&snapshot)
+        .map_err(|e| format!("{e:?}"))
+}
+
+pub(crate) struct GossipLimitMap {
+    pub(crate) limits: LdkHashMap<PublicKey, u64>,
+}
+
+impl_writeable_tlv_based!(GossipLimitMap, {
+    (0, limits, required),
+});
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PeerGossipStats {
+    pub(crate) bytes_received: u64,
+    pub(crate) msgs_received: u64,
+    pub(crate) msgs_dropped: u64,
+    window_bytes: u64,
+}
+
+struct GossipBandwidth {
+    peers: HashMap<PublicKey, PeerGossipStats>,
+    limits: GossipLimitMap,
+    window_start: u64,
+    window_bytes: u64,
+}
+
+impl GossipBandwidth {
+    fn refresh_window(&mut self) {
+        let now = get_current_timestamp();
+        if now >= self.window_start + GOSSIP_WINDOW_SECS {
+            self.window_start = now;
+            self.window_bytes = 0;
+            self.peers.values_mut().for_each(|p| p.window_bytes = 0);
+        }
+    }
+}
+
+/// Routing message handler accounting the gossip received from each peer and dropping it once
+/// the peer limit or the global cap (in bytes per minute) has been reached, or always when the
+/// graph is synced through RGS
+pub(crate) struct GossipHandler {
+    gossip_sync: Arc<LdkGossipSync>,
+    p2p_sync: bool,
+    global_cap: AtomicU64,
+    bandwidth: Mutex<GossipBandwidth>,
+}
+
+impl GossipHandler {
+    pub(crate) fn new(
+        gossip_sync: Arc<LdkGossipSync>,
+        p2p_sync: bool,
+        global_cap: u64,
+        limits: GossipLimitMap,
+    ) -> Self {
+        Self {
+            gossip_sync,
+            p2p_sync,
+            global_cap: AtomicU64::new(global_cap),
+            bandwidth: Mutex::new(GossipBandwidth {
+                peers: HashMap::new(),
+                limits,
+                window_start: get_current_timestamp(),
+                window_bytes: 0,
+            }),
+        }
+    }
+
+    /// Account a gossip message received from a peer, returning false if it needs to be dropped
+    fn account_received(&self, their_node_id: Option<PublicKey>, len: usize) -> bool {
+        // messages without a peer have been generated by us
+        let Some(their_node_id) = their_node_id else {
+            return true;
+        };
+        let mut guard = self.bandwidth.lock().unwrap();
+        let bandwidth = &mut *guard;
+        bandwidth.refresh_window();
+        let limit = bandwidth.limits.limits.get(&their_node_id).copied();
+        let global_cap = self.global_cap.load(Ordering::Relaxed);
+        let over_cap = global_cap > 0 && bandwidth.window_bytes >= global_cap;
+        let stats = bandwidth.peers.entry(their_node_id).or_default();
+        stats.bytes_received += len as u64;
+        stats.msgs_received += 1;
+        if !self.p2p_sync || over_cap || limit.is_some_and(|l| stats.window_bytes >= l) {
+            stats.msgs_dropped += 1;
+            return false;
+        }
+        stats.window_bytes += len as u64;
+        bandwidth.window_bytes += len as u64;
+        true
+    }
+
+    /// Account a gossip message to be sent, returning false if the global cap has been reached
+    fn account_sent(&self, len: usize) -> bool {
+        let mut bandwidth = self.bandwidth.lock().unwrap();
+        bandwidth.refresh_window();
+        let global_cap = self.global_cap.load(Ordering::Relaxed);
+        if global_cap > 0 && bandwidth.window_bytes >= global_cap {
+            return false;
+        }
+        bandwidth.window_bytes += len as u64;
+        true
+    }
+
+    pub(crate) fn peer_stats(&self, peer: &PublicKey) -> (PeerGossipStats, Option<u64>) {
+        let bandwidth = self.bandwidth.lock().unwrap();
+        (
+            bandwidth.peers.get(peer).cloned().unwrap_or_default(),
+            bandwidth.limits.limits.get(peer).copied(),
+        )
+    }
+
+    pub(crate) fn set_global_cap(&self, global_cap: u64) {
+        self.global_cap.store(global_cap, Ordering::Relaxed);
+    }
+
+    /// Set (or remove, if None) the gossip limit of a peer, returning the encoded limits
+    pub(crate) fn set_peer_limit(&self, peer: PublicKey, limit: Option<u64>) -> Vec<u8> {
+        let mut bandwidth = self.bandwidth.lock().unwrap();
+        match limit {
+            Some(limit) => bandwidth.limits.limits.insert(peer, limit),
+            None => bandwidth.limits.limits.remove(&peer),
+        };
+        bandwidth.limits.encode()
+    }
+}
+
+impl BaseMessageHandler for GossipHandler {
+    fn get_and_clear_pending_msg_events(&self) -> Vec<MessageSendEvent> {
+        self.gossip_sync.get_and_clear_pending_msg_events()
+    }
+
+    fn peer_disconnected(&self, their_node_id: PublicKey) {
+        self.gossip_sync.peer_disconnected(their_node_id)
+    }
+
+    fn provided_node_features(&self) -> NodeFeatures {
+        self.gossip_sync.provided_node_features()
+    }
+
+    fn provided_init_features(&self, their_node_id: PublicKey) -> InitFeatures {
+        self.gossip_sync.provided_init_features(their_node_id)
+    }
+
+    fn peer_connected(
+        &self,
+        their_node_id: PublicKey,
+        init: &Init,
+        inbound: bool,
+    ) -> Result<(), ()> {
+        // without a gossip timestamp filter, peers supporting gossip queries send us no gossip
+        if !self.p2p_sync {
+            return Ok(());
+        }
+        self.gossip_sync
+            .peer_connected(their_node_id, init, inbound)
+    }
+}
+
+impl RoutingMessageHandler for GossipHandler {
+    fn handle_node_announcement(
+        &self,
+        their_node_id: Option<PublicKey>,
+        msg: &NodeAnnouncement,
+    ) -> Result<bool, LightningError> {
+        if !self.account_received(their_node_id, msg.serialized_length()) {
+            return Ok(false);
+        }
+        self.gossip_sync
+            .handle_node_announcement(their_node_id, msg)
+    }
+
+    fn handle_channel_announcement(
+        &self,
+        their_node_id: Option<PublicKey>,
+        msg: &ChannelAnnouncement,
+    ) -> Result<bool, LightningError> {
+        if !self.account_received(their_node_id, msg.serialized_length()) {
+            return Ok(false);
+        }
+        self.gossip_sync
+            .handle_channel_announcement(their_node_id, msg)
+    }
+
+    fn handle_channel_update(
+        &self,
+        their_node_id: Option<PublicKey>,
+        msg: &ChannelUpdate,
+    ) -> Result<Option<(NodeId, NodeId)>, LightningError> {
+        if !self.account_received(their_node_id, msg.serialized_length()) {
+            return Ok(None);
+        }
+        self.gossip_sync.handle_channel_update(their_node_id, msg)
+    }
+
+    fn get_next_channel_announcement(
+        &self,
+        starting_point: u64,
+    ) -> Option<(
+        ChannelAnnouncement,
+        Option<ChannelUpdate>,
+        Option<ChannelUpdate>,
+    )> {
+        let next = self
+            .gossip_sync
+            .get_next_channel_announcement(starting_point)?;
+        let len = next.0.serialized_length()
+            + next.1.as_ref().map_or(0, |u| u.serialized_length())
+            + next.2.as_ref().map_or(0, |u| u.serialized_length());
+        self.account_sent(len).then_some(next)
+    }
+
+    fn get_next_node_announcement(
+        &self,
+        starting_point: Option<&NodeId>,
+    ) -> Option<NodeAnnouncement> {
+        let next = self
+            .gossip_sync
+            .get_next_node_announcement(starting_point)?;
+        self.account_sent(next.serialized_length()).then_some(next)
+    }
+
+    fn handle_reply_channel_range(
+        &self,
+        their_node_id: PublicKey,
+        msg: ReplyChannelRange,
+    ) -> Result<(), LightningError> {
+        // replies to our own queries are always processed
+        self.account_received(Some(their_node_id), msg.serialized_length());
+        self.gossip_sync
+            .handle_reply_channel_range(their_node_id, msg)
+    }
+
+    fn handle_reply_short_channel_ids_end(
+        &self,
+        their_node_id: PublicKey,
+        msg: ReplyShortChannelIdsEnd,
+    ) -> Result<(), LightningError> {
+        self.account_received(Some(their_node_id), msg.serialized_length());
+        self.gossip_sync
+            .handle_reply_short_channel_ids_end(their_node_id, msg)
+    }
+
+    fn handle_query_channel_range(
+        &self,
+        their_node_id: PublicKey,
+        msg: QueryChannelRange,
+    ) -> Result<(), LightningError> {
+        // queries of throttled peers are left unanswered
+        if !self.account_received(Some(their_node_id), msg.serialized_length()) {
+            return Ok(());
+        }
+        self.gossip_sync
+            .handle_query_channel_range(their_node_id, msg)
+    }
+
+    fn handle_query_short_channel_ids(
+        &self,
+        their_node_id: PublicKey,
+        msg: QueryShortChannelIds,
+    ) -> Result<(), LightningError> {
+        if !self.account_received(Some(their_node_id), msg.serialized_length()) {
+            return Ok(());
+        }
+        self.gossip_sync
+            .handle_query_short_channel_ids(their_node_id, msg)
+    }
+
+    fn processing_queue_high(&self) -> bool {
+        self.gossip_sync.processing_queue_high()
+    }
+}
@@ -0,0 +1,220 @@
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+    Router,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use tonic::{metadata::MetadataValue, service::Routes, Code, Status};
+use tower::ServiceExt;
+
+use crate::error::APIErrorResponseV2;
+
+pub(crate) mod proto {
+    tonic::include_proto!("rln");
+}
+
+use proto::rgb_lightning_node_server::{RgbLightningNode, RgbLightningNodeServer};
+use proto::*;
+
+/// Metadata key of the failed calls holding the name of the API error (e.g. `InvalidInvoice`)
+pub(crate) const ERROR_NAME_METADATA_KEY: &str = "x-error-name";
+
+/// gRPC service forwarding each call to the REST API operation with the same name, so that both
+/// share validation, authentication and errors
+struct GrpcService {
+    rest_router: Router,
+}
+
+/// Router serving the gRPC service on top of the provided REST router
+pub(crate) fn grpc_router(rest_router: Router) -> Router {
+    Routes::new(RgbLightningNodeServer::new(GrpcService { rest_router })).into_axum_router()
+}
+
+fn status_code(status: StatusCode) -> Code {
+    match status {
+        StatusCode::BAD_REQUEST => Code::InvalidArgument,
+        StatusCode::UNAUTHORIZED => Code::Unauthenticated,
+        StatusCode::FORBIDDEN => Code::FailedPrecondition,
+        StatusCode::TOO_MANY_REQUESTS => Code::ResourceExhausted,
+        StatusCode::SERVICE_UNAVAILABLE => Code::Unavailable,
+        _ => Code::Internal,
+    }
+}
+
+impl GrpcService {
+    async fn forward<Req: Serialize, Res: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        request: tonic::Request<Req>,
+    ) -> Result<tonic::Response<Res>, Status> {
+        let payload = request.get_ref();
+        let (uri, body) = if method == Method::GET {
+            let query = serde_urlencoded::to_string(payload)
+                .map_err(|e| Status::invalid_argument(e.to_string()))?;
+            (format!("{path}?{query}"), Body::empty())
+        } else {
+            let body =
+                serde_json::to_vec(payload).map_err(|e| Status::invalid_argument(e.to_string()))?;
+            (path.to_string(), Body::from(body))
+        };
+        let mut builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json");
+        if let Some(auth) = request
+            .metadata()
+            .get(header::AUTHORIZATION.as_str())
+            .and_then(|v| v.to_str().ok())
+        {
+            builder = builder.header(header::AUTHORIZATION, auth);
+        }
+        let rest_request = builder
+            .body(body)
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        let response = self
+            .rest_router
+            .clone()
+            .oneshot(rest_request)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        if status.is_success() {
+            let res =
+                serde_json::from_slice(&bytes).map_err(|e| Status::internal(e.to_string()))?;
+            return Ok(tonic::Response::new(res));
+        }
+        // authentication failures come from the middleware, without a body
+        let Ok(error) = serde_json::from_slice::<APIErrorResponseV2>(&bytes) else {
+            let code = match status {
+                StatusCode::FORBIDDEN => Code::PermissionDenied,
+                _ => status_code(status),
+            };
+            return Err(Status::new(code, status.to_string()));
+        };
+        let mut grpc_status = Status::new(status_code(status), error.message);
+        if let Ok(name) = MetadataValue::try_from(error.code) {
+            grpc_status
+                .metadata_mut()
+                .insert(ERROR_NAME_METADATA_KEY, name);
+        }
+        Err(grpc_status)
+    }
+}
+
+type GrpcResult<T> = Result<tonic::Response<T>, Status>;
+
+#[tonic::async_trait]
+impl RgbLightningNode for GrpcService {
+    async fn init(&self, request: tonic::Request<InitRequest>) -> GrpcResult<InitResponse> {
+        self.forward(Method::POST, "/init", request).await
+    }
+
+    async fn unlock(&self, request: tonic::Request<UnlockRequest>) -> GrpcResult<Empty> {
+        self.forward(Method::POST, "/unlock", request).await
+    }
+
+    async fn lock(&self, request: tonic::Request<Empty>) -> GrpcResult<Empty> {
+        self.forward(Method::POST, "/lock", request).await
+    }
+
+    async fn node_info(&self, request: tonic::Request<Empty>) -> GrpcResult<NodeInfoResponse> {
+        self.forward(Method::GET, "/nodeinfo", request).await
+    }
+
+    async fn ln_invoice(
+        &self,
+        request: tonic::Request<LnInvoiceRequest>,
+    ) -> GrpcResult<LnInvoiceResponse> {
+        self.forward(Method::POST, "/lninvoice", request).await
+    }
+
+    async fn invoice_status(
+        &self,
+        request: tonic::Request<InvoiceStatusRequest>,
+    ) -> GrpcResult<InvoiceStatusResponse> {
+        self.forward(Method::POST, "/invoicestatus", request).await
+    }
+
+    async fn send_payment(
+        &self,
+        request: tonic::Request<SendPaymentRequest>,
+    ) -> GrpcResult<SendPaymentResponse> {
+        self.forward(Method::POST, "/sendpayment", request).await
+    }
+
+    async fn keysend(
+        &self,
+        request: tonic::Request<KeysendRequest>,
+    ) -> GrpcResult<KeysendResponse> {
+        self.forward(Method::POST, "/keysend", request).await
+    }
+
+    async fn list_payments(
+        &self,
+        request: tonic::Request<ListPaymentsRequest>,
+    ) -> GrpcResult<ListPaymentsResponse> {
+        self.forward(Method::GET, "/listpayments", request).await
+    }
+
+    async fn open_channel(
+        &self,
+        request: tonic::Request<OpenChannelRequest>,
+    ) -> GrpcResult<OpenChannelResponse> {
+        self.forward(Method::POST, "/openchannel", request).await
+    }
+
+    async fn list_channels(
+        &self,
+        request: tonic::Request<PageRequest>,
+    ) -> GrpcResult<ListChannelsResponse> {
+        self.forward(Method::GET, "/listchannels", request).await
+    }
+
+    async fn close_channel(
+        &self,
+        request: tonic::Request<CloseChannelRequest>,
+    ) -> GrpcResult<Empty> {
+        self.forward(Method::POST, "/closechannel", request).await
+    }
+
+    async fn issue_asset_nia(
+        &self,
+        request: tonic::Request<IssueAssetNiaRequest>,
+    ) -> GrpcResult<IssueAssetNiaResponse> {
+        self.forward(Method::POST, "/issueassetnia", request).await
+    }
+
+    async fn list_assets(
+        &self,
+        request: tonic::Request<ListAssetsRequest>,
+    ) -> GrpcResult<ListAssetsResponse> {
+        self.forward(Method::POST, "/listassets", request).await
+    }
+
+    async fn asset_balance(
+        &self,
+        request: tonic::Request<AssetBalanceRequest>,
+    ) -> GrpcResult<AssetBalanceResponse> {
+        self.forward(Method::POST, "/assetbalance", request).await
+    }
+
+    async fn rgb_invoice(
+        &self,
+        request: tonic::Request<RgbInvoiceRequest>,
+    ) -> GrpcResult<RgbInvoiceResponse> {
+        self.forward(Method::POST, "/rgbinvoice", request).await
+    }
+
+    async fn send_asset(
+        &self,
+        request: tonic::Request<SendAssetRequest>,
+    ) -> GrpcResult<SendAssetResponse> {
+        self.forward(Method::POST, "/sendasset", request).await
+    }
+}
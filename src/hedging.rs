@@ -0,0 +1,445 @@
+use amplify::s;
+use lightning::impl_writeable_tlv_based;
+use lightning::types::payment::PaymentHash;
+use lightning::util::hash_tables::HashMap as LdkHashMap;
+use lightning::util::persist::KVStoreSync;
+use lightning::util::ser::Writeable;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::str::FromStr;
+use std::sync::{Arc, MutexGuard};
+use std::time::Duration;
+
+use crate::disk::{HEDGES_FNAME, HEDGING_POLICIES_FNAME};
+use crate::error::{APIError, APIErrorResponse};
+use crate::ldk::BTC_POLICY_KEY;
+use crate::routes::{
+    taker_without_totp, Hedge, HedgeStatus, HedgingPolicyEntry, MakerExecuteRequest,
+    MakerInitRequest, MakerInitResponse, SwapStatus, TakerRequest,
+};
+use crate::utils::{get_current_timestamp, hex_str, hex_str_to_vec, AppState, UnlockedAppState};
+
+const MAKER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Validity of the swap offers requested to the maker, which are executed right away
+const HEDGE_SWAP_TIMEOUT_SEC: u32 = 600;
+
+/// Window of the daily conversion limit of a policy
+const HEDGING_DAY_SEC: u64 = 24 * 3600;
+
+/// Client of the maker API of the rgb-lightning-node acting as counterparty of the hedges
+pub(crate) struct MakerClient {
+    client: reqwest::Client,
+    url: String,
+    token: Option<String>,
+}
+
+impl MakerClient {
+    pub(crate) fn new(url: &str, token: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(MAKER_TIMEOUT)
+                .build()
+                .unwrap(),
+            url: url.trim_end_matches('/').to_string(),
+            token,
+        }
+    }
+
+    async fn post<B: Serialize, R: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<R, APIError> {
+        let mut request = self.client.post(format!("{}{path}", self.url)).json(body);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        let res = request
+            .send()
+            .await
+            .map_err(|e| APIError::FailedMakerRequest(e.to_string()))?;
+        if !res.status().is_success() {
+            let status = res.status();
+            let error = match res.json::<APIErrorResponse>().await {
+                Ok(e) => e.error,
+                Err(_) => status.to_string(),
+            };
+            return Err(APIError::FailedMakerRequest(error));
+        }
+        res.json::<R>()
+            .await
+            .map_err(|e| APIError::FailedMakerRequest(format!("invalid response: {e}")))
+    }
+
+    /// Ask the maker for a swap offer, the quantities being the ones of the policy rate
+    async fn maker_init(&self, request: &MakerInitRequest) -> Result<MakerInitResponse, APIError> {
+        self.post("/makerinit", request).await
+    }
+
+    /// Ask the maker to execute the swap, once we have accepted it as taker
+    async fn maker_execute(&self, request: &MakerExecuteRequest) -> Result<(), APIError> {
+        self.post::<_, serde_json::Value>("/makerexecute", request)
+            .await
+            .map(|_| ())
+    }
+}
+
+/// Conversion of the payments received in an asset (or in BTC) into a target asset (or BTC),
+/// through swaps taken from a maker node. The rate gives `rate_to` units of the target asset for
+/// each `rate_from` units received, BTC amounts being in msat.
+#[derive(Clone, Debug)]
+pub(crate) struct HedgingPolicy {
+    pub(crate) target_asset_id: Option<String>,
+    pub(crate) min_amount: u64,
+    pub(crate) max_amount: Option<u64>,
+    pub(crate) max_daily_amount: Option<u64>,
+    pub(crate) rate_from: u64,
+    pub(crate) rate_to: u64,
+    pub(crate) maker_url: String,
+    pub(crate) maker_token: Option<String>,
+    pub(crate) dry_run: bool,
+    pub(crate) updated_at: u64,
+}
+
+impl_writeable_tlv_based!(HedgingPolicy, {
+    (0, target_asset_id, option),
+    (2, min_amount, required),
+    (4, max_amount, option),
+    (6, max_daily_amount, option),
+    (8, rate_from, required),
+    (10, rate_to, required),
+    (12, maker_url, required),
+    (14, maker_token, option),
+    (16, dry_run, required),
+    (18, updated_at, required),
+});
+
+impl HedgingPolicy {
+    /// Quantity of the target asset to ask for the given received amount, rounding down
+    fn target_qty(&self, qty_from: u64) -> u64 {
+        (qty_from as u128 * self.rate_to as u128 / self.rate_from as u128)
+            .try_into()
+            .unwrap_or(u64::MAX)
+    }
+
+    pub(crate) fn to_entry(&self, asset_id: String) -> HedgingPolicyEntry {
+        HedgingPolicyEntry {
+            asset_id,
+            target_asset_id: self.target_asset_id.clone(),
+            min_amount: self.min_amount,
+            max_amount: self.max_amount,
+            max_daily_amount: self.max_daily_amount,
+            rate_from: self.rate_from,
+            rate_to: self.rate_to,
+            maker_url: self.maker_url.clone(),
+            dry_run: self.dry_run,
+            updated_at: self.updated_at,
+        }
+    }
+}
+
+pub(crate) struct HedgingPolicyMap {
+    pub(crate) policies: LdkHashMap<String, HedgingPolicy>,
+}
+
+impl_writeable_tlv_based!(HedgingPolicyMap, {
+    (0, policies, required),
+});
+
+/// Conversion triggered by a received payment, keyed by its payment hash
+#[derive(Clone, Debug)]
+pub(crate) struct HedgeInfo {
+    pub(crate) asset_id: Option<String>,
+    pub(crate) target_asset_id: Option<String>,
+    pub(crate) received_amount: u64,
+    pub(crate) qty_from: Option<u64>,
+    pub(crate) qty_to: Option<u64>,
+    pub(crate) status: HedgeStatus,
+    pub(crate) swap_payment_hash: Option<PaymentHash>,
+    pub(crate) created_at: u64,
+    pub(crate) updated_at: u64,
+    pub(crate) error: Option<String>,
+}
+
+impl_writeable_tlv_based!(HedgeInfo, {
+    (0, asset_id, option),
+    (2, target_asset_id, option),
+    (4, received_amount, required),
+    (6, qty_from, option),
+    (8, qty_to, option),
+    (10, status, required),
+    (12, swap_payment_hash, option),
+    (14, created_at, required),
+    (16, updated_at, required),
+    (18, error, option),
+});
+
+impl HedgeInfo {
+    pub(crate) fn to_hedge(&self, payment_hash: &PaymentHash) -> Hedge {
+        Hedge {
+            payment_hash: hex_str(&payment_hash.0),
+            asset_id: self.asset_id.clone(),
+            target_asset_id: self.target_asset_id.clone(),
+            received_amount: self.received_amount,
+            qty_from: self.qty_from,
+            qty_to: self.qty_to,
+            status: self.status,
+            swap_payment_hash: self.swap_payment_hash.map(|h| hex_str(&h.0)),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            error: self.error.clone(),
+        }
+    }
+
+    /// Whether the hedge counts towards the daily limit of its policy
+    fn uses_daily_amount(&self, since: u64) -> bool {
+        self.created_at >= since
+            && matches!(
+                self.status,
+                HedgeStatus::DryRun | HedgeStatus::Executing | HedgeStatus::Succeeded
+            )
+    }
+}
+
+pub(crate) struct HedgeMap {
+    pub(crate) hedges: LdkHashMap<PaymentHash, HedgeInfo>,
+}
+
+impl_writeable_tlv_based!(HedgeMap, {
+    (0, hedges, required),
+});
+
+impl UnlockedAppState {
+    pub(crate) fn set_hedging_policy(&self, key: &str, policy: Option<HedgingPolicy>) -> bool {
+        let mut hedging_policies = self.get_hedging_policies();
+        let found = match policy {
+            Some(policy) => hedging_policies
+                .policies
+                .insert(key.to_string(), policy)
+                .is_some(),
+            None => hedging_policies.policies.remove(key).is_some(),
+        };
+        self.kv_store
+            .write("", "", HEDGING_POLICIES_FNAME, hedging_policies.encode())
+            .unwrap();
+        found
+    }
+
+    pub(crate) fn hedging_policies(&self) -> LdkHashMap<String, HedgingPolicy> {
+        self.get_hedging_policies().policies.clone()
+    }
+
+    pub(crate) fn hedges(&self) -> LdkHashMap<PaymentHash, HedgeInfo> {
+        self.get_hedges().hedges.clone()
+    }
+
+    fn update_hedge(&self, payment_hash: &PaymentHash, update: impl FnOnce(&mut HedgeInfo)) {
+        let mut hedges = self.get_hedges();
+        if let Some(hedge) = hedges.hedges.get_mut(payment_hash) {
+            update(hedge);
+            hedge.updated_at = get_current_timestamp();
+        }
+        self.save_hedges(hedges);
+    }
+
+    fn save_hedges(&self, hedges: MutexGuard<HedgeMap>) {
+        self.kv_store
+            .write("", "", HEDGES_FNAME, hedges.encode())
+            .unwrap();
+    }
+
+    /// Queue the conversion of a received payment, if its asset has a hedging policy and the
+    /// received amount reaches the policy threshold
+    pub(crate) fn queue_hedge(
+        &self,
+        payment_hash: PaymentHash,
+        asset_id: Option<String>,
+        received_amount: u64,
+    ) {
+        let key = asset_id.as_deref().unwrap_or(BTC_POLICY_KEY);
+        let Some(policy) = self.get_hedging_policies().policies.get(key).cloned() else {
+            return;
+        };
+        if received_amount < policy.min_amount {
+            return;
+        }
+        let mut hedges = self.get_hedges();
+        if hedges.hedges.contains_key(&payment_hash) {
+            return;
+        }
+        let created_at = get_current_timestamp();
+        hedges.hedges.insert(
+            payment_hash,
+            HedgeInfo {
+                asset_id,
+                target_asset_id: policy.target_asset_id,
+                received_amount,
+                qty_from: None,
+                qty_to: None,
+                status: HedgeStatus::Pending,
+                swap_payment_hash: None,
+                created_at,
+                updated_at: created_at,
+                error: None,
+            },
+        );
+        self.save_hedges(hedges);
+        tracing::info!("Queued hedge of payment {payment_hash}");
+    }
+
+    fn finish_hedge(&self, payment_hash: &PaymentHash, status: HedgeStatus, error: Option<String>) {
+        match &error {
+            Some(e) => tracing::warn!("Hedge of payment {payment_hash} is {status:?}: {e}"),
+            None => tracing::info!("Hedge of payment {payment_hash} is {status:?}"),
+        }
+        self.update_hedge(payment_hash, |h| {
+            h.status = status;
+            h.error = error;
+        });
+    }
+}
+
+/// Take a swap from the maker, accepting it as taker before asking the maker to execute it
+async fn execute_hedge(
+    app_state: &Arc<AppState>,
+    unlocked_state: &UnlockedAppState,
+    policy: &HedgingPolicy,
+    hedge: &HedgeInfo,
+    qty_from: u64,
+    qty_to: u64,
+) -> Result<PaymentHash, APIError> {
+    let maker = MakerClient::new(&policy.maker_url, policy.maker_token.clone());
+    let offer = maker
+        .maker_init(&MakerInitRequest {
+            qty_from,
+            qty_to,
+            from_asset: hedge.asset_id.clone(),
+            to_asset: hedge.target_asset_id.clone(),
+            timeout_sec: HEDGE_SWAP_TIMEOUT_SEC,
+        })
+        .await?;
+    let swap_payment_hash = hex_str_to_vec(&offer.payment_hash)
+        .and_then(|h| h.try_into().ok())
+        .map(PaymentHash)
+        .ok_or_else(|| APIError::FailedMakerRequest(s!("invalid payment hash")))?;
+    taker_without_totp(
+        Arc::clone(app_state),
+        TakerRequest {
+            swapstring: offer.swapstring.clone(),
+            totp_code: None,
+        },
+    )
+    .await?;
+    maker
+        .maker_execute(&MakerExecuteRequest {
+            swapstring: offer.swapstring,
+            payment_secret: offer.payment_secret,
+            taker_pubkey: unlocked_state.channel_manager.get_our_node_id().to_string(),
+            totp_code: None,
+        })
+        .await?;
+    Ok(swap_payment_hash)
+}
+
+/// Execute the queued hedges within the limits of their policy (or just record them in dry-run
+/// mode) and follow the swaps being executed
+pub(crate) async fn process_hedges(app_state: &Arc<AppState>, unlocked_state: &UnlockedAppState) {
+    let policies = unlocked_state.hedging_policies();
+    let taker_swaps = unlocked_state.taker_swaps();
+    let since = get_current_timestamp().saturating_sub(HEDGING_DAY_SEC);
+    for (payment_hash, hedge) in unlocked_state.hedges() {
+        match hedge.status {
+            HedgeStatus::Pending => {
+                let key = hedge.asset_id.as_deref().unwrap_or(BTC_POLICY_KEY);
+                let Some(policy) = policies.get(key) else {
+                    unlocked_state.finish_hedge(
+                        &payment_hash,
+                        HedgeStatus::Skipped,
+                        Some(s!("hedging policy has been removed")),
+                    );
+                    continue;
+                };
+                let mut qty_from = policy
+                    .max_amount
+                    .map_or(hedge.received_amount, |m| hedge.received_amount.min(m));
+                if let Some(max_daily_amount) = policy.max_daily_amount {
+                    let used: u64 = unlocked_state
+                        .hedges()
+                        .values()
+                        .filter(|h| h.asset_id == hedge.asset_id && h.uses_daily_amount(since))
+                        .filter_map(|h| h.qty_from)
+                        .sum();
+                    qty_from = qty_from.min(max_daily_amount.saturating_sub(used));
+                }
+                let qty_to = policy.target_qty(qty_from);
+                if qty_from == 0 || qty_to == 0 {
+                    unlocked_state.finish_hedge(
+                        &payment_hash,
+                        HedgeStatus::Skipped,
+                        Some(s!("daily limit reached or amount too small to convert")),
+                    );
+                    continue;
+                }
+                unlocked_state.update_hedge(&payment_hash, |h| {
+                    h.target_asset_id = policy.target_asset_id.clone();
+                    h.qty_from = Some(qty_from);
+                    h.qty_to = Some(qty_to);
+                });
+                if policy.dry_run {
+                    tracing::info!(
+                        "Dry-run hedge of payment {payment_hash}: {qty_from} {key} for {qty_to} {}",
+                        policy.target_asset_id.as_deref().unwrap_or(BTC_POLICY_KEY)
+                    );
+                    unlocked_state.finish_hedge(&payment_hash, HedgeStatus::DryRun, None);
+                    continue;
+                }
+                match execute_hedge(app_state, unlocked_state, policy, &hedge, qty_from, qty_to)
+                    .await
+                {
+                    Ok(swap_payment_hash) => {
+                        tracing::info!(
+                            "Executing hedge of payment {payment_hash} with swap {swap_payment_hash}"
+                        );
+                        unlocked_state.update_hedge(&payment_hash, |h| {
+                            h.status = HedgeStatus::Executing;
+                            h.swap_payment_hash = Some(swap_payment_hash);
+                        });
+                    }
+                    Err(e) => unlocked_state.finish_hedge(
+                        &payment_hash,
+                        HedgeStatus::Failed,
+                        Some(e.to_string()),
+                    ),
+                }
+            }
+            HedgeStatus::Executing => {
+                let Some(swap) = hedge.swap_payment_hash.and_then(|h| taker_swaps.get(&h)) else {
+                    continue;
+                };
+                match swap.status {
+                    SwapStatus::Succeeded => {
+                        unlocked_state.finish_hedge(&payment_hash, HedgeStatus::Succeeded, None)
+                    }
+                    SwapStatus::Failed | SwapStatus::Expired => unlocked_state.finish_hedge(
+                        &payment_hash,
+                        HedgeStatus::Failed,
+                        Some(format!("swap is {:?}", swap.status)),
+                    ),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Parse the asset a hedging policy converts from or to, BTC being identified by its policy key
+pub(crate) fn parse_hedging_asset(asset_id: &str) -> Result<Option<String>, APIError> {
+    if asset_id == BTC_POLICY_KEY {
+        return Ok(None);
+    }
+    rgb_lib::ContractId::from_str(asset_id)
+        .map(|c| Some(c.to_string()))
+        .map_err(|_| APIError::InvalidAssetID(asset_id.to_string()))
+}
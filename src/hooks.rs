@@ -0,0 +1,173 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::sync::Semaphore;
+
+use crate::notify::NotificationPublisher;
+use crate::utils::get_current_timestamp;
+
+/// Directory, inside the storage one, used as the working directory of the hook command
+pub(crate) const HOOKS_DIR: &str = "hooks";
+/// The only environment variable passed to the hook command
+const HOOK_PATH_ENV: &str = "/usr/local/bin:/usr/bin:/bin";
+/// Max characters of the hook stderr reported in the logs
+const MAX_HOOK_STDERR_LEN: usize = 1000;
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub(crate) enum HookEvent {
+    AsyncPaymentExpired {
+        scope_id: String,
+        htlc_id: String,
+        payment_hash: String,
+    },
+    AsyncPaymentHeld {
+        scope_id: String,
+        htlc_id: String,
+        peer_pubkey: String,
+        payment_hash: String,
+        amt_msat: u64,
+        expires_at: u64,
+    },
+    BackupSaved {
+        path: String,
+        num_channels: usize,
+    },
+    HodlInvoiceExpiring {
+        payment_hash: String,
+        cltv_expiry: Option<u32>,
+        current_height: u32,
+        blocks_to_cancel: u32,
+    },
+    HtlcIntercepted {
+        scope_id: String,
+        htlc_id: String,
+        payment_hash: String,
+        amt_msat: u64,
+    },
+    InvoiceSettled {
+        payment_hash: String,
+        amt_msat: u64,
+        asset_id: Option<String>,
+        asset_amount: Option<u64>,
+    },
+    TransferSettled {
+        batch_transfer_idx: i32,
+    },
+}
+
+#[derive(Serialize)]
+struct HookPayload<'a> {
+    #[serde(flatten)]
+    event: &'a HookEvent,
+    timestamp: u64,
+}
+
+/// Runs the configured command on settlement, interception, async payment, HODL invoice deadline
+/// and automatic backup events, passing the event as JSON on its stdin. The command gets an empty environment (besides
+/// PATH) and is killed when it doesn't exit within the timeout. Events exceeding the concurrency limit wait for a running
+/// hook to finish. The command can be changed (or removed) by reloading the config. The same
+/// payload is also published on the configured message buses.
+pub(crate) struct HookRunner {
+    command: Mutex<Option<PathBuf>>,
+    working_dir: PathBuf,
+    timeout: Duration,
+    semaphore: Arc<Semaphore>,
+    publisher: Option<Arc<NotificationPublisher>>,
+}
+
+impl HookRunner {
+    pub(crate) fn new(
+        command: Option<PathBuf>,
+        working_dir: PathBuf,
+        timeout_sec: u64,
+        max_concurrency: u8,
+        publisher: Option<Arc<NotificationPublisher>>,
+    ) -> Self {
+        Self {
+            command: Mutex::new(command),
+            working_dir,
+            timeout: Duration::from_secs(timeout_sec),
+            semaphore: Arc::new(Semaphore::new(max_concurrency as usize)),
+            publisher,
+        }
+    }
+
+    pub(crate) fn set_command(&self, command: Option<PathBuf>) {
+        *self.command.lock().unwrap() = command;
+    }
+
+    /// Publish the given event and run the hook for it in the background, if a command is
+    /// configured
+    pub(crate) fn trigger(self: &Arc<Self>, event: HookEvent) {
+        let command = self.command.lock().unwrap().clone();
+        if command.is_none() && self.publisher.is_none() {
+            return;
+        }
+        let payload = serde_json::to_value(HookPayload {
+            event: &event,
+            timestamp: get_current_timestamp(),
+        })
+        .expect("valid hook payload");
+        let event_name = payload["event"]
+            .as_str()
+            .expect("tagged hook event")
+            .to_string();
+        let payload = serde_json::to_vec(&payload).expect("valid hook payload");
+        if let Some(publisher) = &self.publisher {
+            publisher.publish(&event_name, &payload);
+        }
+        let Some(command) = command else {
+            return;
+        };
+        let runner = Arc::clone(self);
+        tokio::spawn(async move {
+            let _permit = runner
+                .semaphore
+                .acquire()
+                .await
+                .expect("semaphore is never closed");
+            if let Err(e) = runner.run(&command, &payload).await {
+                tracing::error!("Hook for event {event:?} failed: {e}");
+            }
+        });
+    }
+
+    async fn run(&self, command: &Path, payload: &[u8]) -> Result<(), String> {
+        tokio::fs::create_dir_all(&self.working_dir)
+            .await
+            .map_err(|e| format!("cannot create {}: {e}", self.working_dir.display()))?;
+        let mut child = Command::new(command)
+            .env_clear()
+            .env("PATH", HOOK_PATH_ENV)
+            .current_dir(&self.working_dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("cannot start {}: {e}", command.display()))?;
+
+        let mut stdin = child.stdin.take().expect("piped stdin");
+        let output = tokio::time::timeout(self.timeout, async move {
+            // a hook may exit without reading its input
+            let _ = stdin.write_all(payload).await;
+            drop(stdin);
+            child.wait_with_output().await
+        })
+        .await
+        .map_err(|_| format!("timed out after {} seconds", self.timeout.as_secs()))?
+        .map_err(|e| e.to_string())?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stderr: String = stderr.chars().take(MAX_HOOK_STDERR_LEN).collect();
+            return Err(format!("exited with {}: {}", output.status, stderr.trim()));
+        }
+        Ok(())
+    }
+}
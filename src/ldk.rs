@@ -1,47 +1,64 @@
 use amplify::{map, s};
 use bitcoin::blockdata::locktime::absolute::LockTime;
+use bitcoin::consensus::encode;
+use bitcoin::hashes::{sha256, Hash as _};
 use bitcoin::psbt::{ExtractTxError, Psbt};
 use bitcoin::secp256k1::{All, PublicKey, Secp256k1};
+use bitcoin::transaction::Version;
 use bitcoin::{io, Amount, Network};
-use bitcoin::{BlockHash, TxOut};
+use bitcoin::{Address, Block, BlockHash, OutPoint as BtcOutPoint, Transaction, TxIn, TxOut, Txid};
 use bitcoin_bech32::WitnessProgram;
+use lightning::chain::chaininterface::BroadcasterInterface;
+use lightning::chain::transaction::OutPoint;
 use lightning::chain::{chainmonitor, ChannelMonitorUpdateStatus};
 use lightning::chain::{BestBlock, Filter};
-use lightning::events::bump_transaction::{BumpTransactionEventHandler, Wallet};
-use lightning::events::{Event, PaymentFailureReason, PaymentPurpose, ReplayEvent};
-use lightning::ln::channelmanager::{self, PaymentId, RecentPaymentDetails};
+use lightning::events::bump_transaction::{
+    BumpTransactionEvent, BumpTransactionEventHandler, Wallet,
+};
+use lightning::events::{
+    Event, HTLCHandlingFailureType, InboundChannelFunds, PaymentFailureReason, PaymentPurpose,
+    ReplayEvent,
+};
+use lightning::ln::channelmanager::{self, InterceptId, PaymentId, RecentPaymentDetails};
 use lightning::ln::channelmanager::{
-    ChainParameters, ChannelManagerReadArgs, SimpleArcChannelManager,
+    ChainParameters, ChannelManager as LdkChannelManager, ChannelManagerReadArgs,
 };
 use lightning::ln::msgs::SocketAddress;
 use lightning::ln::peer_handler::{
     IgnoringMessageHandler, MessageHandler, PeerManager as LdkPeerManager,
 };
 use lightning::ln::types::ChannelId;
+use lightning::offers::offer::OfferId;
 use lightning::onion_message::messenger::{
     DefaultMessageRouter, OnionMessenger as LdkOnionMessenger,
 };
 use lightning::rgb_utils::{
-    get_rgb_channel_info_pending, is_channel_rgb, parse_rgb_payment_info, read_rgb_transfer_info,
-    update_rgb_channel_amount, BITCOIN_NETWORK_FNAME, INDEXER_URL_FNAME, STATIC_BLINDING,
-    WALLET_ACCOUNT_XPUB_COLORED_FNAME, WALLET_ACCOUNT_XPUB_VANILLA_FNAME, WALLET_FINGERPRINT_FNAME,
-    WALLET_MASTER_FINGERPRINT_FNAME,
+    get_rgb_channel_info_pending, get_rgb_payment_info_path, is_channel_rgb,
+    parse_rgb_payment_info, read_rgb_transfer_info, update_rgb_channel_amount,
+    BITCOIN_NETWORK_FNAME, INDEXER_URL_FNAME, STATIC_BLINDING, WALLET_ACCOUNT_XPUB_COLORED_FNAME,
+    WALLET_ACCOUNT_XPUB_VANILLA_FNAME, WALLET_FINGERPRINT_FNAME, WALLET_MASTER_FINGERPRINT_FNAME,
 };
 use lightning::routing::gossip;
 use lightning::routing::gossip::{NodeId, P2PGossipSync};
-use lightning::routing::router::DefaultRouter;
+use lightning::routing::router::{DefaultRouter, Path as LnPath};
 use lightning::routing::scoring::{ProbabilisticScorer, ProbabilisticScoringFeeParameters};
 use lightning::sign::{
-    EntropySource, InMemorySigner, KeysManager, NodeSigner, OutputSpender,
-    SpendableOutputDescriptor,
+    EntropySource, InMemorySigner, KeysManager, NodeSigner, OutputSpender, PhantomKeysManager,
+    SignerProvider, SpendableOutputDescriptor,
 };
 use lightning::types::payment::{PaymentHash, PaymentPreimage};
-use lightning::util::config::UserConfig;
+use lightning::util::config::{
+    ChannelConfig, ChannelConfigOverrides, ChannelConfigUpdate, ChannelHandshakeConfig,
+    ChannelHandshakeConfigUpdate, ChannelHandshakeLimits, UserConfig,
+};
+use lightning::util::errors::APIError as LDKAPIError;
 use lightning::util::hash_tables::hash_map::Entry;
 use lightning::util::hash_tables::HashMap as LdkHashMap;
 use lightning::util::persist::{
-    KVStoreSync, MonitorUpdatingPersister, OUTPUT_SWEEPER_PERSISTENCE_KEY,
-    OUTPUT_SWEEPER_PERSISTENCE_PRIMARY_NAMESPACE, OUTPUT_SWEEPER_PERSISTENCE_SECONDARY_NAMESPACE,
+    KVStoreSync, MonitorUpdatingPersister, CHANNEL_MANAGER_PERSISTENCE_KEY,
+    CHANNEL_MANAGER_PERSISTENCE_PRIMARY_NAMESPACE, CHANNEL_MANAGER_PERSISTENCE_SECONDARY_NAMESPACE,
+    OUTPUT_SWEEPER_PERSISTENCE_KEY, OUTPUT_SWEEPER_PERSISTENCE_PRIMARY_NAMESPACE,
+    OUTPUT_SWEEPER_PERSISTENCE_SECONDARY_NAMESPACE,
 };
 use lightning::util::ser::{ReadableArgs, Writeable};
 use lightning::util::sweep as ldk_sweep;
@@ -55,7 +72,6 @@ use lightning_block_sync::UnboundedCache;
 use lightning_dns_resolver::OMDomainResolver;
 use lightning_invoice::PaymentSecret;
 use lightning_net_tokio::SocketDescriptor;
-use lightning_persister::fs_store::FilesystemStore;
 use rand::RngCore;
 use rgb_lib::{
     bdk_wallet::keys::{bip39::Mnemonic, DerivableKey, ExtendedKey},
@@ -78,9 +94,8 @@ use std::collections::HashMap;
 use std::convert::TryInto;
 use std::fs;
 use std::hash::{DefaultHasher, Hash, Hasher};
-use std::io::BufReader;
 use std::net::ToSocketAddrs;
-use std::net::{SocketAddr, TcpListener};
+use std::net::{IpAddr, SocketAddr, TcpListener};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -89,27 +104,125 @@ use std::time::{Duration, SystemTime};
 use time::OffsetDateTime;
 use tokio::runtime::Handle;
 use tokio::sync::watch::Sender;
+use tokio::sync::Mutex as TokioMutex;
 use tokio::task::JoinHandle;
+use zeroize::Zeroizing;
 
+use crate::auto_backup::{
+    copy_dir, prepare_snapshot, AutoBackupManifest, AutoBackupTarget, SnapshotName,
+    AUTO_BACKUP_CHANNELS_DIR, AUTO_BACKUP_RGB_DIR,
+};
+use crate::backup::{derive_backup_key, encrypt_dir, BackupKey};
 use crate::bitcoind::BitcoindClient;
 use crate::disk::{
-    self, FilesystemLogger, CHANNEL_IDS_FNAME, CHANNEL_PEER_DATA, INBOUND_PAYMENTS_FNAME,
-    MAKER_SWAPS_FNAME, OUTBOUND_PAYMENTS_FNAME, OUTPUT_SPENDER_TXES, TAKER_SWAPS_FNAME,
+    self, FilesystemLogger, AMOUNT_BOUNDS_FNAME, ANCHOR_RESERVE_FNAME, ASSET_HTLC_MINIMUMS_FNAME,
+    ASSET_POLICIES_FNAME, CHAIN_SUBSCRIPTIONS_FNAME, CHANNEL_ACCEPTOR_FNAME, CHANNEL_DEPTHS_FNAME,
+    CHANNEL_EVENTS_FNAME, CHANNEL_IDS_FNAME, CHANNEL_MONITORS_DIR, CHANNEL_PEER_DATA,
+    EMERGENCY_KIT_CONTENTS_FNAME, EMERGENCY_KIT_FNAME, EMERGENCY_KIT_INSTRUCTIONS_FNAME,
+    ESCROWS_FNAME, FALLBACK_ADDRESSES_FNAME, FORWARDS_FNAME, GOSSIP_LIMITS_FNAME, HEDGES_FNAME,
+    HEDGING_POLICIES_FNAME, HODL_INVOICES_FNAME, INBOUND_PAYMENTS_FNAME, INTERCEPT_SCOPES_FNAME,
+    ISSUED_ADDRESSES_FNAME, JIT_CHANNELS_FNAME, JOURNAL_FNAME, LEASE_CONFIG_FNAME,
+    LEASE_ORDERS_FNAME, LNURL_PAY_FNAME, LOCKED_UTXOS_FNAME, LSP_CLIENTS_FNAME, LSP_CONFIG_FNAME,
+    MAKER_SWAPS_FNAME, NODE_ANNOUNCEMENT_FNAME, NWC_CONNECTIONS_FNAME, OFFERS_FNAME, ORDERS_FNAME,
+    OUTBOUND_PAYMENTS_FNAME, OUTPUT_SPENDER_TXES, PEER_FILTER_FNAME, PENDING_BROADCASTS_FNAME,
+    RESTORED_CHANNEL_PEERS_FNAME, SETTLEMENTS_FNAME, SUBMARINE_SWAPS_FNAME, SWEEP_CONFIG_FNAME,
+    TAKER_SWAPS_FNAME, TOKEN_SPENDING_FNAME, TOTP_FNAME,
 };
 use crate::error::APIError;
-use crate::rgb::{check_rgb_proxy_endpoint, get_rgb_channel_info_optional, RgbLibWalletWrapper};
-use crate::routes::{HTLCStatus, SwapStatus, UnlockRequest, DUST_LIMIT_MSAT};
+#[cfg(feature = "failure-injection")]
+use crate::failure_injection::FailureInjector;
+use crate::gossip::{
+    sync_rapid_gossip, GossipHandler, LdkGossipSync, LdkRapidGossipSync, RGS_SYNC_INTERVAL,
+};
+use crate::hedging::process_hedges;
+use crate::hooks::{HookEvent, HookRunner, HOOKS_DIR};
+use crate::lease::process_lease_orders;
+use crate::lsps::{scid_to_string, LspsMessageHandler};
+use crate::nwc::run_nwc_service;
+use crate::peer_messages::{AssetHtlcMinHandler, PeerMessageHandler};
+use crate::rgb::{
+    check_rgb_proxy_endpoint, get_rgb_channel_info_optional, AnchorWalletSource,
+    RgbLibWalletWrapper,
+};
+use crate::routes::{
+    AnchorReserveEventKind, ChainEventKind, ChannelEventKind, CoinSelection, EmergencyKitChannel,
+    EmergencyKitContents, EscrowDecision, ForwardResolution, HTLCStatus, Htlc, HtlcDirection,
+    HtlcKind, InterceptScopeKind, JournalEventKind, OrderStatus, PendingBroadcastKind, SwapStatus,
+    SyncStage, UnlockRequest, DUST_LIMIT_MSAT, HTLC_MIN_MSAT,
+};
+use crate::store::{storage_cipher, NodeStore};
+use crate::submarine::SwapProviderClient;
 use crate::swap::SwapData;
+use crate::tor::{check_no_clearnet_dns, CircuitGroup};
+use crate::totp::{TotpEnrollment, TotpOperation, TotpVerifier};
 use crate::utils::{
-    check_port_is_available, connect_peer_if_necessary, do_connect_peer, get_current_timestamp,
-    hex_str, AppState, StaticState, UnlockedAppState, ELECTRUM_URL_MAINNET, ELECTRUM_URL_REGTEST,
-    ELECTRUM_URL_SIGNET, ELECTRUM_URL_TESTNET, ELECTRUM_URL_TESTNET4, PROXY_ENDPOINT_LOCAL,
-    PROXY_ENDPOINT_PUBLIC,
+    check_announce_alias, check_port_is_available, connect_peer_if_necessary, do_connect_peer,
+    get_current_timestamp, get_network_params, hex_str, read_ldk_key_derivation, AppState,
+    StaticState, UnlockedAppState, PEER_CONNECTION_TIMEOUT,
 };
 
 pub(crate) const FEE_RATE: u64 = 7;
 pub(crate) const UTXO_SIZE_SAT: u32 = 32000;
 pub(crate) const MIN_CHANNEL_CONFIRMATIONS: u8 = 6;
+/// Max confirmations that can be required on the funding TX of a channel
+pub(crate) const MAX_MIN_CHANNEL_DEPTH: u32 = 144;
+const MAX_CHANNEL_EVENTS: usize = 500;
+const MAX_ANCHOR_RESERVE_EVENTS: usize = 500;
+const MAX_CHAIN_EVENTS: usize = 500;
+/// Oldest forwards get pruned beyond this, per-channel totals are kept
+const MAX_FORWARDS: usize = 5000;
+pub(crate) const MAX_CHAIN_SUBSCRIPTIONS: usize = 1000;
+pub(crate) const MAX_INTERCEPT_SCOPES: usize = 1000;
+/// Time payments to an offline async recipient are held, well within the usual CLTV deltas
+pub(crate) const DEFAULT_ASYNC_HOLD_TIMEOUT_SEC: u64 = 3600;
+pub(crate) const MAX_ASYNC_HOLD_TIMEOUT_SEC: u64 = 6 * 3600;
+/// Oldest journal entries get pruned beyond this, the kept ones remain verifiable
+const MAX_JOURNAL_ENTRIES: usize = 5000;
+pub(crate) const MAX_JOURNAL_PROOF_ENTRIES: u64 = 1000;
+/// HTLCs this close to their CLTV deadline may soon cause a force-close
+pub(crate) const HTLC_RISK_WINDOW_BLOCKS: u32 = 36;
+/// Version, locktime, segwit marker and counters of a transaction
+const TX_OVERHEAD_VSIZE: u64 = 11;
+/// P2WSH 2-of-3 multisig input, with high-S signatures
+const MULTISIG_INPUT_VSIZE: u64 = 105;
+const P2WSH_OUTPUT_VSIZE: u64 = 43;
+/// Signed funding TXs of externally funded channels, broadcast once the peer has signed too
+pub(crate) const EXTERNAL_FUNDING_TX_PREFIX: &str = "external_funding_";
+/// Inbound channel requests not accepted within this time are discarded by LDK
+const PENDING_CHANNEL_TIMEOUT_SEC: u64 = 120;
+/// Time after their expiry invoices can still settle, e.g. held or RGB ones
+const SETTLEMENT_GRACE_SEC: u64 = 7 * 24 * 3600;
+/// Time after their expiry the fallback addresses of unpaid invoices are still watched, as the
+/// on-chain payments can confirm late
+const FALLBACK_WATCH_GRACE_SEC: u64 = 24 * 3600;
+/// Max number of transport endpoints an RGB invoice can carry
+const MAX_PROXY_ENDPOINTS: usize = 3;
+/// Max number of indexers, including the fallback ones
+const MAX_INDEXER_URLS: usize = 3;
+
+/// Path of the LDK seed on the default account, derived from the master key of the node mnemonic
+pub(crate) const LDK_SEED_DERIVATION_PATH: &str = "m/535'";
+
+const EMERGENCY_KIT_INSTRUCTIONS: &str = "\
+rgb-lightning-node emergency kit
+
+Use this kit only if the node is permanently lost: broadcasting an old commitment transaction \
+lets the counterparty take all the channel funds as a penalty. A restored node will close the \
+channels by itself.
+
+- emergency_kit.json lists the open channels, with their latest signed commitment transactions \
+(hex, empty for anchor channels, which need a fee-bumping child), and the node keys: the LDK seed \
+is derived at ldk_seed_derivation_path from the mnemonic master key (with the BIP39 passphrase \
+set on init, if any), while the swept funds go to the wallet accounts of the listed xpubs.
+- monitors/ contains the channel monitors, named after the funding outpoint, which an LDK-based \
+tool can load with a KeysManager built on the LDK seed to force-close the channels and build \
+the sweeping transactions once the commitment transactions confirm.
+- the other files hold the RGB data of the channels and the addresses of their peers.
+
+To force-close a channel without such tool, broadcast its commitment transactions, then wait for \
+the channel to_self_delay before sweeping the delayed output with the keys derived from the \
+LDK seed.
+";
 
 pub(crate) struct LdkBackgroundServices {
     stop_processing: Arc<AtomicBool>,
@@ -127,6 +240,10 @@ pub(crate) struct PaymentInfo {
     pub(crate) created_at: u64,
     pub(crate) updated_at: u64,
     pub(crate) payee_pubkey: PublicKey,
+    pub(crate) offer_id: Option<OfferId>,
+    pub(crate) parts: Vec<PaymentPartInfo>,
+    pub(crate) expires_at: Option<u64>,
+    pub(crate) settle_after_expiry: bool,
 }
 
 impl_writeable_tlv_based!(PaymentInfo, {
@@ -137,6 +254,54 @@ impl_writeable_tlv_based!(PaymentInfo, {
     (8, created_at, required),
     (10, updated_at, required),
     (12, payee_pubkey, required),
+    (14, offer_id, option),
+    (16, parts, optional_vec),
+    (18, expires_at, option),
+    (20, settle_after_expiry, (default_value, false)),
+});
+
+#[derive(Clone, Debug)]
+pub(crate) struct PaymentPartInfo {
+    pub(crate) amt_msat: u64,
+    pub(crate) fee_msat: u64,
+    pub(crate) first_hop_scid: Option<u64>,
+    pub(crate) hops: u8,
+    pub(crate) status: HTLCStatus,
+    pub(crate) failure: Option<String>,
+    pub(crate) updated_at: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) route: Vec<PaymentHopInfo>,
+}
+
+impl_writeable_tlv_based!(PaymentPartInfo, {
+    (0, amt_msat, required),
+    (2, fee_msat, required),
+    (4, first_hop_scid, option),
+    (6, hops, required),
+    (8, status, required),
+    (10, failure, option),
+    (12, updated_at, required),
+    (14, asset_id, option),
+    (16, asset_amount, option),
+    (18, route, optional_vec),
+});
+
+/// A hop of the route taken by a part of an outbound payment
+#[derive(Clone, Debug)]
+pub(crate) struct PaymentHopInfo {
+    pub(crate) pubkey: PublicKey,
+    pub(crate) short_channel_id: u64,
+    /// Fee charged by the node to forward over the next channel (0 for the recipient)
+    pub(crate) fee_msat: u64,
+    pub(crate) cltv_expiry_delta: u32,
+}
+
+impl_writeable_tlv_based!(PaymentHopInfo, {
+    (0, pubkey, required),
+    (2, short_channel_id, required),
+    (4, fee_msat, required),
+    (6, cltv_expiry_delta, required),
 });
 
 pub(crate) struct InboundPaymentInfoStorage {
@@ -171,256 +336,3572 @@ impl_writeable_tlv_based!(ChannelIdsMap, {
     (0, channel_ids, required),
 });
 
-impl UnlockedAppState {
-    pub(crate) fn add_maker_swap(&self, payment_hash: PaymentHash, swap: SwapData) {
-        let mut maker_swaps = self.get_maker_swaps();
-        maker_swaps.swaps.insert(payment_hash, swap);
-        self.save_maker_swaps(maker_swaps);
-    }
-
-    pub(crate) fn update_maker_swap_status(&self, payment_hash: &PaymentHash, status: SwapStatus) {
-        let mut maker_swaps = self.get_maker_swaps();
-        let maker_swap = maker_swaps.swaps.get_mut(payment_hash).unwrap();
-        match &status {
-            SwapStatus::Succeeded | SwapStatus::Failed | SwapStatus::Expired => {
-                maker_swap.completed_at = Some(get_current_timestamp())
-            }
-            SwapStatus::Pending => maker_swap.initiated_at = Some(get_current_timestamp()),
-            SwapStatus::Waiting => panic!("this doesn't make sense: swap starts in Waiting status"),
-        }
-        maker_swap.status = status;
-        self.save_maker_swaps(maker_swaps);
-    }
-
-    pub(crate) fn is_maker_swap(&self, payment_hash: &PaymentHash) -> bool {
-        self.maker_swaps().contains_key(payment_hash)
-    }
-
-    pub(crate) fn add_taker_swap(&self, payment_hash: PaymentHash, swap: SwapData) {
-        let mut taker_swaps = self.get_taker_swaps();
-        taker_swaps.swaps.insert(payment_hash, swap);
-        self.save_taker_swaps(taker_swaps);
-    }
-
-    pub(crate) fn update_taker_swap_status(&self, payment_hash: &PaymentHash, status: SwapStatus) {
-        let mut taker_swaps = self.get_taker_swaps();
-        let taker_swap = taker_swaps.swaps.get_mut(payment_hash).unwrap();
-        match &status {
-            SwapStatus::Succeeded | SwapStatus::Failed | SwapStatus::Expired => {
-                taker_swap.completed_at = Some(get_current_timestamp())
-            }
-            SwapStatus::Pending => taker_swap.initiated_at = Some(get_current_timestamp()),
-            SwapStatus::Waiting => panic!("this doesn't make sense: swap starts in Waiting status"),
-        }
-        taker_swap.status = status;
-        self.save_taker_swaps(taker_swaps);
-    }
+/// Policy applied to inbound channel requests. The requests passing it can be held for a manual
+/// decision, while the asset of RGB channels gets checked once the funding is received.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ChannelAcceptorPolicy {
+    pub(crate) min_channel_size_sat: Option<u64>,
+    pub(crate) max_channel_size_sat: Option<u64>,
+    pub(crate) require_anchors: bool,
+    pub(crate) max_channels_per_peer: Option<u16>,
+    pub(crate) reject_rgb_channels: bool,
+    pub(crate) allowed_assets: Vec<String>,
+    pub(crate) denied_assets: Vec<String>,
+    pub(crate) manual_approval: bool,
+}
 
-    pub(crate) fn is_taker_swap(&self, payment_hash: &PaymentHash) -> bool {
-        self.taker_swaps().contains_key(payment_hash)
-    }
+impl_writeable_tlv_based!(ChannelAcceptorPolicy, {
+    (0, min_channel_size_sat, option),
+    (2, max_channel_size_sat, option),
+    (4, require_anchors, required),
+    (6, max_channels_per_peer, option),
+    (8, reject_rgb_channels, required),
+    (10, allowed_assets, required_vec),
+    (12, denied_assets, required_vec),
+    (14, manual_approval, required),
+});
 
-    fn save_maker_swaps(&self, swaps: MutexGuard<SwapMap>) {
-        self.fs_store
-            .write("", "", MAKER_SWAPS_FNAME, swaps.encode())
-            .unwrap();
-    }
+/// Confirmations required on the funding TX of the channels with a peer, 0 trusting the peer with
+/// zero-conf channels
+#[derive(Clone, Debug)]
+pub(crate) struct PeerChannelDepth {
+    pub(crate) peer_pubkey: PublicKey,
+    pub(crate) min_depth: u32,
+}
 
-    fn save_taker_swaps(&self, swaps: MutexGuard<SwapMap>) {
-        self.fs_store
-            .write("", "", TAKER_SWAPS_FNAME, swaps.encode())
-            .unwrap();
-    }
+impl_writeable_tlv_based!(PeerChannelDepth, {
+    (0, peer_pubkey, required),
+    (2, min_depth, required),
+});
 
-    pub(crate) fn maker_swaps(&self) -> LdkHashMap<PaymentHash, SwapData> {
-        self.get_maker_swaps().swaps.clone()
-    }
+/// Per-peer overrides of the confirmations required on the funding TX of channels
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ChannelDepthOverrides {
+    pub(crate) peers: Vec<PeerChannelDepth>,
+}
 
-    pub(crate) fn taker_swaps(&self) -> LdkHashMap<PaymentHash, SwapData> {
-        self.get_taker_swaps().swaps.clone()
-    }
+impl_writeable_tlv_based!(ChannelDepthOverrides, {
+    (0, peers, required_vec),
+});
 
-    pub(crate) fn add_inbound_payment(&self, payment_hash: PaymentHash, payment_info: PaymentInfo) {
-        let mut inbound = self.get_inbound_payments();
-        inbound.payments.insert(payment_hash, payment_info);
-        self.save_inbound_payments(inbound);
+impl ChannelDepthOverrides {
+    /// Confirmations required on the funding TX of the channels with the peer
+    pub(crate) fn min_depth(&self, pubkey: &PublicKey, default_depth: u32) -> u32 {
+        self.peers
+            .iter()
+            .find(|p| &p.peer_pubkey == pubkey)
+            .map(|p| p.min_depth)
+            .unwrap_or(default_depth)
     }
+}
 
-    pub(crate) fn add_outbound_payment(
+impl ChannelAcceptorPolicy {
+    /// Reason to reject an inbound channel request, if any
+    fn check_request(
         &self,
-        payment_id: PaymentId,
-        payment_info: PaymentInfo,
-    ) -> Result<(), APIError> {
-        let mut outbound = self.get_outbound_payments();
-        if let Some(existing_payment) = outbound.payments.get(&payment_id) {
-            if !matches!(existing_payment.status, HTLCStatus::Failed) {
-                return Err(APIError::DuplicatePayment(
-                    existing_payment.status.to_string(),
+        capacity_sat: u64,
+        with_anchors: bool,
+        peer_channels: usize,
+    ) -> Option<String> {
+        if let Some(min_channel_size_sat) = self.min_channel_size_sat {
+            if capacity_sat < min_channel_size_sat {
+                return Some(format!(
+                    "channel size of {capacity_sat} sat is below the min of {min_channel_size_sat} sat"
                 ));
             }
         }
-        outbound.payments.insert(payment_id, payment_info);
-        self.save_outbound_payments(outbound);
-        Ok(())
-    }
-
-    fn fail_outbound_pending_payments(&self, recent_payments_payment_ids: Vec<PaymentId>) {
-        let mut outbound = self.get_outbound_payments();
-        let mut failed = false;
-        for (payment_id, payment_info) in outbound
-            .payments
-            .iter_mut()
-            .filter(|(_, i)| matches!(i.status, HTLCStatus::Pending))
-        {
-            if !recent_payments_payment_ids.contains(payment_id) {
-                payment_info.status = HTLCStatus::Failed;
-                payment_info.updated_at = get_current_timestamp();
-                failed = true;
+        if let Some(max_channel_size_sat) = self.max_channel_size_sat {
+            if capacity_sat > max_channel_size_sat {
+                return Some(format!(
+                    "channel size of {capacity_sat} sat is above the max of {max_channel_size_sat} sat"
+                ));
             }
         }
-        if failed {
-            self.save_outbound_payments(outbound);
+        if self.require_anchors && !with_anchors {
+            return Some(s!("anchor outputs are required"));
+        }
+        if let Some(max_channels_per_peer) = self.max_channels_per_peer {
+            if peer_channels >= max_channels_per_peer as usize {
+                return Some(format!(
+                    "the peer already has {peer_channels} channels, the max is {max_channels_per_peer}"
+                ));
+            }
         }
+        None
     }
 
-    pub(crate) fn inbound_payments(&self) -> LdkHashMap<PaymentHash, PaymentInfo> {
-        self.get_inbound_payments().payments.clone()
+    /// Reason to reject an inbound RGB channel of the given asset, if any
+    fn check_asset(&self, asset_id: &str) -> Option<String> {
+        if self.reject_rgb_channels {
+            return Some(s!("RGB channels are not accepted"));
+        }
+        if self.denied_assets.iter().any(|a| a == asset_id)
+            || (!self.allowed_assets.is_empty()
+                && !self.allowed_assets.iter().any(|a| a == asset_id))
+        {
+            return Some(format!("channels of asset {asset_id} are not accepted"));
+        }
+        None
     }
+}
 
-    pub(crate) fn outbound_payments(&self) -> LdkHashMap<PaymentId, PaymentInfo> {
-        self.get_outbound_payments().payments.clone()
-    }
+/// Name served as a lightning address (name@domain) through LNURL-pay
+#[derive(Clone, Debug)]
+pub(crate) struct LightningAddressInfo {
+    pub(crate) name: String,
+    pub(crate) description: String,
+    pub(crate) min_sendable_msat: u64,
+    pub(crate) max_sendable_msat: u64,
+    pub(crate) asset_id: Option<String>,
+}
 
-    fn save_inbound_payments(&self, inbound: MutexGuard<InboundPaymentInfoStorage>) {
-        self.fs_store
-            .write("", "", INBOUND_PAYMENTS_FNAME, inbound.encode())
-            .unwrap();
-    }
+impl_writeable_tlv_based!(LightningAddressInfo, {
+    (0, name, required),
+    (2, description, required),
+    (4, min_sendable_msat, required),
+    (6, max_sendable_msat, required),
+    (8, asset_id, option),
+});
 
-    fn save_outbound_payments(&self, outbound: MutexGuard<OutboundPaymentInfoStorage>) {
-        self.fs_store
-            .write("", "", OUTBOUND_PAYMENTS_FNAME, outbound.encode())
-            .unwrap();
-    }
+/// Identity in the node announcement, set by /setalias or else by the unlock request and options
+#[derive(Clone, Debug, Default)]
+pub(crate) struct NodeAnnouncementConfig {
+    pub(crate) alias: Option<String>,
+    pub(crate) color: Option<[u8; 3]>,
+}
 
-    fn upsert_inbound_payment(
-        &self,
-        payment_hash: PaymentHash,
-        status: HTLCStatus,
-        preimage: Option<PaymentPreimage>,
-        secret: Option<PaymentSecret>,
-        amt_msat: Option<u64>,
-        payee_pubkey: PublicKey,
-    ) {
-        let mut inbound = self.get_inbound_payments();
-        match inbound.payments.entry(payment_hash) {
-            Entry::Occupied(mut e) => {
-                let payment_info = e.get_mut();
-                payment_info.status = status;
-                payment_info.preimage = preimage;
-                payment_info.secret = secret;
-                payment_info.updated_at = get_current_timestamp();
-            }
-            Entry::Vacant(e) => {
-                let created_at = get_current_timestamp();
-                e.insert(PaymentInfo {
-                    preimage,
-                    secret,
-                    status,
-                    amt_msat,
-                    created_at,
-                    updated_at: created_at,
-                    payee_pubkey,
-                });
-            }
+impl_writeable_tlv_based!(NodeAnnouncementConfig, {
+    (0, alias, option),
+    (2, color, option),
+});
+
+impl NodeAnnouncementConfig {
+    fn alias_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0; 32];
+        if let Some(alias) = &self.alias {
+            bytes[..alias.len()].copy_from_slice(alias.as_bytes());
         }
-        self.save_inbound_payments(inbound);
+        bytes
     }
+}
 
-    pub(crate) fn update_outbound_payment(
-        &self,
-        payment_id: PaymentId,
-        status: HTLCStatus,
-        preimage: Option<PaymentPreimage>,
-    ) -> PaymentInfo {
-        let mut outbound = self.get_outbound_payments();
-        let payment_info = outbound.payments.get_mut(&payment_id).unwrap();
-        payment_info.status = status;
-        payment_info.preimage = preimage;
-        payment_info.updated_at = get_current_timestamp();
-        let payment = (*payment_info).clone();
-        self.save_outbound_payments(outbound);
-        payment
-    }
+#[derive(Clone, Debug, Default)]
+pub(crate) struct LnurlPayConfig {
+    pub(crate) domain: Option<String>,
+    pub(crate) addresses: Vec<LightningAddressInfo>,
+}
 
-    pub(crate) fn update_outbound_payment_status(&self, payment_id: PaymentId, status: HTLCStatus) {
-        let mut outbound = self.get_outbound_payments();
-        let payment_info = outbound.payments.get_mut(&payment_id).unwrap();
-        payment_info.status = status;
-        payment_info.updated_at = get_current_timestamp();
-        self.save_outbound_payments(outbound);
+impl_writeable_tlv_based!(LnurlPayConfig, {
+    (0, domain, option),
+    (2, addresses, required_vec),
+});
+
+impl LnurlPayConfig {
+    pub(crate) fn address(&self, name: &str) -> Option<&LightningAddressInfo> {
+        self.addresses.iter().find(|a| a.name == name)
     }
 
-    pub(crate) fn channel_ids(&self) -> LdkHashMap<ChannelId, ChannelId> {
-        self.get_channel_ids_map().channel_ids.clone()
+    /// LUD-06 metadata of an address, its hash is committed to by the invoices
+    pub(crate) fn metadata(&self, address: &LightningAddressInfo) -> String {
+        serde_json::json!([
+            ["text/plain", address.description],
+            [
+                "text/identifier",
+                format!(
+                    "{}@{}",
+                    address.name,
+                    self.domain.as_deref().unwrap_or_default()
+                )
+            ],
+        ])
+        .to_string()
     }
+}
 
-    pub(crate) fn add_channel_id(
-        &self,
-        former_temporary_channel_id: ChannelId,
-        channel_id: ChannelId,
-    ) {
-        let mut channel_ids_map = self.get_channel_ids_map();
-        channel_ids_map
-            .channel_ids
-            .insert(former_temporary_channel_id, channel_id);
-        self.save_channel_ids_map(channel_ids_map);
+/// Peers refused, or exclusively accepted, on inbound connections and channel requests. Addresses
+/// are IP or .onion hosts, matched against the connecting IP and the addresses the peer announced.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PeerFilter {
+    pub(crate) allowed_pubkeys: Vec<PublicKey>,
+    pub(crate) denied_pubkeys: Vec<PublicKey>,
+    pub(crate) allowed_addresses: Vec<String>,
+    pub(crate) denied_addresses: Vec<String>,
+}
+
+impl_writeable_tlv_based!(PeerFilter, {
+    (0, allowed_pubkeys, required_vec),
+    (2, denied_pubkeys, required_vec),
+    (4, allowed_addresses, required_vec),
+    (6, denied_addresses, required_vec),
+});
+
+impl PeerFilter {
+    /// Whether inbound connections from the IP get dropped before the handshake
+    pub(crate) fn is_ip_denied(&self, ip: IpAddr) -> bool {
+        self.denied_addresses
+            .contains(&ip.to_canonical().to_string())
     }
 
-    pub(crate) fn delete_channel_id(&self, channel_id: ChannelId) {
-        let mut channel_ids_map = self.get_channel_ids_map();
-        if let Some(temporary_channel_id) = channel_ids_map
-            .channel_ids
-            .clone()
-            .into_iter()
-            .find_map(|(tmp_chan_id, chan_id)| {
-                if chan_id == channel_id {
-                    Some(tmp_chan_id)
-                } else {
-                    None
-                }
-            })
+    /// Reason to refuse the peer, given the hosts of the addresses it announced, if any
+    pub(crate) fn check_peer(&self, pubkey: &PublicKey, hosts: &[String]) -> Option<String> {
+        if self.denied_pubkeys.contains(pubkey) {
+            return Some(format!("peer {pubkey} is denied"));
+        }
+        if let Some(host) = hosts.iter().find(|h| self.denied_addresses.contains(h)) {
+            return Some(format!("address {host} of peer {pubkey} is denied"));
+        }
+        let has_allow_list = !self.allowed_pubkeys.is_empty() || !self.allowed_addresses.is_empty();
+        if has_allow_list
+            && !self.allowed_pubkeys.contains(pubkey)
+            && !hosts.iter().any(|h| self.allowed_addresses.contains(h))
         {
-            channel_ids_map.channel_ids.remove(&temporary_channel_id);
-            self.save_channel_ids_map(channel_ids_map);
+            return Some(format!("peer {pubkey} is not allowed"));
         }
+        None
     }
+}
 
-    fn save_channel_ids_map(&self, channel_ids: MutexGuard<ChannelIdsMap>) {
-        self.fs_store
-            .write("", "", CHANNEL_IDS_FNAME, channel_ids.encode())
-            .unwrap();
-    }
+/// Overrides of the fee rate (in sat/vB) and of the destination of the bitcoins of the TXs
+/// sweeping the outputs of closed channels, applied to the sweeps built after they're set
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SweepConfig {
+    pub(crate) fee_rate: Option<u64>,
+    pub(crate) destination_address: Option<String>,
 }
 
-pub(crate) type ChainMonitor = chainmonitor::ChainMonitor<
-    InMemorySigner,
-    Arc<dyn Filter + Send + Sync>,
-    Arc<BitcoindClient>,
-    Arc<BitcoindClient>,
-    Arc<FilesystemLogger>,
-    Arc<
-        MonitorUpdatingPersister<
-            Arc<FilesystemStore>,
-            Arc<FilesystemLogger>,
-            Arc<KeysManager>,
-            Arc<KeysManager>,
-            Arc<BitcoindClient>,
-            Arc<BitcoindClient>,
-        >,
-    >,
-    Arc<KeysManager>,
->;
+impl_writeable_tlv_based!(SweepConfig, {
+    (0, fee_rate, option),
+    (2, destination_address, option),
+});
+
+/// Addresses announced by the node that can be connected to, onion ones being skipped unless
+/// peers are reached through the SOCKS proxy
+pub(crate) fn announced_peer_addrs(
+    network_graph: &NetworkGraph,
+    pubkey: &PublicKey,
+    with_onion: bool,
+) -> Vec<SocketAddress> {
+    let network_graph = network_graph.read_only();
+    let Some(announcement) = network_graph
+        .node(&NodeId::from_pubkey(pubkey))
+        .and_then(|n| n.announcement_info.as_ref())
+    else {
+        return vec![];
+    };
+    announcement
+        .addresses()
+        .iter()
+        .filter(|addr| match addr {
+            SocketAddress::OnionV2(_) => false,
+            SocketAddress::OnionV3 { .. } => with_onion,
+            _ => true,
+        })
+        .cloned()
+        .collect()
+}
+
+/// Hosts of the addresses announced by the node, as matched by the peer filter
+pub(crate) fn announced_hosts(network_graph: &NetworkGraph, pubkey: &PublicKey) -> Vec<String> {
+    let network_graph = network_graph.read_only();
+    let Some(announcement) = network_graph
+        .node(&NodeId::from_pubkey(pubkey))
+        .and_then(|n| n.announcement_info.as_ref())
+    else {
+        return vec![];
+    };
+    announcement
+        .addresses()
+        .iter()
+        .map(|addr| {
+            let addr = addr.to_string();
+            let host = addr
+                .rsplit_once(':')
+                .map_or(addr.as_str(), |(host, _)| host);
+            host.trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_lowercase()
+        })
+        .collect()
+}
+
+/// Inbound channel request held for a manual decision
+#[derive(Clone, Debug)]
+pub(crate) struct PendingChannelInfo {
+    pub(crate) counterparty_node_id: PublicKey,
+    pub(crate) capacity_sat: u64,
+    pub(crate) push_msat: u64,
+    pub(crate) public: bool,
+    pub(crate) with_anchors: bool,
+    pub(crate) received_at: u64,
+}
+
+pub(crate) type PendingChannelMap = HashMap<ChannelId, PendingChannelInfo>;
+
+#[derive(Clone, Debug)]
+pub(crate) struct AnchorReserveEventInfo {
+    pub(crate) timestamp: u64,
+    pub(crate) kind: AnchorReserveEventKind,
+    pub(crate) details: String,
+}
+
+impl_writeable_tlv_based!(AnchorReserveEventInfo, {
+    (0, timestamp, required),
+    (2, kind, required),
+    (4, details, required),
+});
+
+#[derive(Clone, Debug)]
+pub(crate) struct AnchorReserveUtxoInfo {
+    pub(crate) amount_sat: u64,
+    pub(crate) created_at: u64,
+}
+
+impl_writeable_tlv_based!(AnchorReserveUtxoInfo, {
+    (0, amount_sat, required),
+    (2, created_at, required),
+});
+
+pub(crate) struct AnchorReserveMap {
+    pub(crate) utxos: LdkHashMap<String, AnchorReserveUtxoInfo>,
+    pub(crate) events: Vec<AnchorReserveEventInfo>,
+    pub(crate) depleted: bool,
+}
+
+impl_writeable_tlv_based!(AnchorReserveMap, {
+    (0, utxos, required),
+    (2, events, required_vec),
+    (4, depleted, required),
+});
+
+#[derive(Clone, Debug)]
+pub(crate) struct ChainEventInfo {
+    pub(crate) timestamp: u64,
+    pub(crate) kind: ChainEventKind,
+    pub(crate) txid: Txid,
+    pub(crate) outpoint: Option<BtcOutPoint>,
+    pub(crate) amount_sat: Option<u64>,
+    pub(crate) block_height: u32,
+}
+
+impl_writeable_tlv_based!(ChainEventInfo, {
+    (0, timestamp, required),
+    (2, kind, required),
+    (4, txid, required),
+    (6, outpoint, option),
+    (8, amount_sat, option),
+    (10, block_height, required),
+});
+
+#[derive(Clone, Debug)]
+pub(crate) struct ChainSubscriptionInfo {
+    pub(crate) txid: Option<Txid>,
+    pub(crate) script: Option<ScriptBuf>,
+    pub(crate) start_height: u32,
+    pub(crate) created_at: u64,
+    pub(crate) watched_outpoints: Vec<BtcOutPoint>,
+    pub(crate) events: Vec<ChainEventInfo>,
+}
+
+impl_writeable_tlv_based!(ChainSubscriptionInfo, {
+    (0, txid, option),
+    (2, script, option),
+    (4, start_height, required),
+    (6, created_at, required),
+    (8, watched_outpoints, required_vec),
+    (10, events, required_vec),
+});
+
+pub(crate) struct ChainSubscriptionMap {
+    pub(crate) subscriptions: LdkHashMap<String, ChainSubscriptionInfo>,
+    pub(crate) last_scanned_height: u32,
+}
+
+impl_writeable_tlv_based!(ChainSubscriptionMap, {
+    (0, subscriptions, required),
+    (2, last_scanned_height, required),
+});
+
+/// Scope of the HTLCs held for a decision of the API client: forwards to the intercept SCID or
+/// receives for the payment hash. Forwards to an async recipient are instead held only while the
+/// peer is offline, up to the hold timeout.
+#[derive(Clone, Debug)]
+pub(crate) struct InterceptScopeInfo {
+    pub(crate) kind: InterceptScopeKind,
+    pub(crate) intercept_scid: Option<u64>,
+    /// Next hop of the forwards that get resumed
+    pub(crate) peer_pubkey: Option<PublicKey>,
+    pub(crate) payment_hash: Option<PaymentHash>,
+    pub(crate) hold_timeout_sec: Option<u64>,
+    pub(crate) created_at: u64,
+}
+
+impl_writeable_tlv_based!(InterceptScopeInfo, {
+    (0, kind, required),
+    (2, intercept_scid, option),
+    (4, peer_pubkey, option),
+    (6, payment_hash, option),
+    (8, created_at, required),
+    (10, hold_timeout_sec, option),
+});
+
+pub(crate) struct InterceptScopeMap {
+    pub(crate) scopes: LdkHashMap<String, InterceptScopeInfo>,
+}
+
+impl_writeable_tlv_based!(InterceptScopeMap, {
+    (0, scopes, required),
+});
+
+/// HTLC held by an intercept scope, LDK generates the events again on restart
+#[derive(Clone, Debug)]
+pub(crate) struct InterceptedHtlcInfo {
+    pub(crate) scope_id: String,
+    pub(crate) payment_hash: PaymentHash,
+    pub(crate) amt_msat: u64,
+    pub(crate) rgb_amount: Option<u64>,
+    pub(crate) held: HeldHtlc,
+    pub(crate) received_at: u64,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum HeldHtlc {
+    Forward {
+        intercept_id: InterceptId,
+        outbound_amt_msat: u64,
+        outbound_rgb_payment: Option<(ContractId, u64)>,
+    },
+    Receive {
+        payment_preimage: Option<PaymentPreimage>,
+        claim_deadline: Option<u32>,
+    },
+}
+
+pub(crate) type InterceptedHtlcMap = HashMap<String, InterceptedHtlcInfo>;
+
+#[derive(Clone, Debug)]
+pub(crate) struct ChannelEventInfo {
+    pub(crate) timestamp: u64,
+    pub(crate) kind: ChannelEventKind,
+    pub(crate) details: String,
+}
+
+impl_writeable_tlv_based!(ChannelEventInfo, {
+    (0, timestamp, required),
+    (2, kind, required),
+    (4, details, required),
+});
+
+/// An output of a closed channel handed to the sweeper
+#[derive(Clone, Debug)]
+pub(crate) struct CloseOutputInfo {
+    pub(crate) outpoint: BtcOutPoint,
+    pub(crate) amount_sat: u64,
+}
+
+impl_writeable_tlv_based!(CloseOutputInfo, {
+    (0, outpoint, required),
+    (2, amount_sat, required),
+});
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ChannelEventLog {
+    pub(crate) events: Vec<ChannelEventInfo>,
+    pub(crate) close_outputs: Vec<CloseOutputInfo>,
+}
+
+impl_writeable_tlv_based!(ChannelEventLog, {
+    (0, events, required_vec),
+    (2, close_outputs, optional_vec),
+});
+
+pub(crate) struct ChannelEventMap {
+    pub(crate) channels: LdkHashMap<ChannelId, ChannelEventLog>,
+}
+
+impl_writeable_tlv_based!(ChannelEventMap, {
+    (0, channels, required),
+});
+
+#[derive(Clone, Debug)]
+pub(crate) struct ForwardInfo {
+    pub(crate) timestamp: u64,
+    pub(crate) prev_channel_id: ChannelId,
+    pub(crate) next_channel_id: Option<ChannelId>,
+    pub(crate) payment_hash: Option<PaymentHash>,
+    pub(crate) amt_in_msat: Option<u64>,
+    pub(crate) amt_out_msat: Option<u64>,
+    pub(crate) fee_earned_msat: Option<u64>,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount_in: Option<u64>,
+    pub(crate) asset_amount_out: Option<u64>,
+    pub(crate) resolution: ForwardResolution,
+    pub(crate) failure_reason: Option<String>,
+}
+
+impl_writeable_tlv_based!(ForwardInfo, {
+    (0, timestamp, required),
+    (2, prev_channel_id, required),
+    (4, next_channel_id, option),
+    (6, payment_hash, option),
+    (8, amt_in_msat, option),
+    (10, amt_out_msat, option),
+    (12, fee_earned_msat, option),
+    (14, asset_id, option),
+    (16, asset_amount_in, option),
+    (18, asset_amount_out, option),
+    (20, resolution, required),
+    (22, failure_reason, option),
+});
+
+/// All-time forwarding totals of a channel, kept apart from the pruned history
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ForwardTotalsInfo {
+    pub(crate) forwards_in: u64,
+    pub(crate) forwards_out: u64,
+    pub(crate) failed_forwards: u64,
+    pub(crate) amt_in_msat: u64,
+    pub(crate) amt_out_msat: u64,
+    pub(crate) fee_earned_msat: u64,
+}
+
+impl_writeable_tlv_based!(ForwardTotalsInfo, {
+    (0, forwards_in, required),
+    (2, forwards_out, required),
+    (4, failed_forwards, required),
+    (6, amt_in_msat, required),
+    (8, amt_out_msat, required),
+    (10, fee_earned_msat, required),
+});
+
+pub(crate) struct ForwardMap {
+    pub(crate) forwards: Vec<ForwardInfo>,
+    pub(crate) totals: LdkHashMap<ChannelId, ForwardTotalsInfo>,
+}
+
+impl_writeable_tlv_based!(ForwardMap, {
+    (0, forwards, required_vec),
+    (2, totals, required),
+});
+
+/// Entry of the signed journal, chained to the previous one through its hash
+#[derive(Clone, Debug)]
+pub(crate) struct JournalEntryInfo {
+    pub(crate) seq: u64,
+    pub(crate) timestamp: u64,
+    pub(crate) kind: JournalEventKind,
+    pub(crate) details: String,
+    pub(crate) prev_hash: [u8; 32],
+    pub(crate) hash: [u8; 32],
+    /// Signature of the hex-encoded hash with the node key
+    pub(crate) signature: String,
+}
+
+impl_writeable_tlv_based!(JournalEntryInfo, {
+    (0, seq, required),
+    (2, timestamp, required),
+    (4, kind, required),
+    (6, details, required),
+    (8, prev_hash, required),
+    (10, hash, required),
+    (12, signature, required),
+});
+
+pub(crate) struct JournalMap {
+    pub(crate) entries: Vec<JournalEntryInfo>,
+}
+
+impl_writeable_tlv_based!(JournalMap, {
+    (0, entries, required_vec),
+});
+
+/// Hash of a journal entry, committing to the previous one
+pub(crate) fn journal_entry_hash(
+    prev_hash: &[u8; 32],
+    seq: u64,
+    timestamp: u64,
+    kind: JournalEventKind,
+    details: &str,
+) -> [u8; 32] {
+    let mut data = prev_hash.to_vec();
+    data.extend(seq.to_be_bytes());
+    data.extend(timestamp.to_be_bytes());
+    data.extend(format!("{kind:?}").as_bytes());
+    data.push(0);
+    data.extend(details.as_bytes());
+    sha256::Hash::hash(&data).to_byte_array()
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct HodlInvoiceInfo {
+    pub(crate) claimable_amt_msat: Option<u64>,
+    pub(crate) claim_deadline: Option<u32>,
+}
+
+impl_writeable_tlv_based!(HodlInvoiceInfo, {
+    (0, claimable_amt_msat, required),
+    (2, claim_deadline, required),
+});
+
+/// Block heights of a payment held by a HODL invoice
+pub(crate) struct HeldPaymentDeadline {
+    pub(crate) cltv_expiry: Option<u32>,
+    pub(crate) current_height: u32,
+    pub(crate) blocks_to_cancel: Option<u32>,
+}
+
+pub(crate) struct HodlInvoiceMap {
+    pub(crate) invoices: LdkHashMap<PaymentHash, HodlInvoiceInfo>,
+}
+
+impl_writeable_tlv_based!(HodlInvoiceMap, {
+    (0, invoices, required),
+});
+
+/// JIT channel bought from an LSP, to be opened when the payment of its invoice reaches the LSP
+#[derive(Clone, Debug)]
+pub(crate) struct JitChannelInfo {
+    pub(crate) lsp_pubkey: PublicKey,
+    pub(crate) scid: u64,
+    pub(crate) payment_size_msat: u64,
+    pub(crate) opening_fee_msat: u64,
+    pub(crate) created_at: u64,
+    pub(crate) expires_at: u64,
+}
+
+impl_writeable_tlv_based!(JitChannelInfo, {
+    (0, lsp_pubkey, required),
+    (2, scid, required),
+    (4, payment_size_msat, required),
+    (6, opening_fee_msat, required),
+    (8, created_at, required),
+    (10, expires_at, required),
+});
+
+pub(crate) struct JitChannelMap {
+    pub(crate) channels: LdkHashMap<PaymentHash, JitChannelInfo>,
+}
+
+impl_writeable_tlv_based!(JitChannelMap, {
+    (0, channels, required),
+});
+
+/// Amounts accepted by an invoice that lets the payer choose the amount
+#[derive(Clone, Debug)]
+pub(crate) struct AmountBoundsInfo {
+    pub(crate) min_amt_msat: Option<u64>,
+    pub(crate) max_amt_msat: Option<u64>,
+    pub(crate) expires_at: u64,
+}
+
+impl_writeable_tlv_based!(AmountBoundsInfo, {
+    (0, min_amt_msat, option),
+    (2, max_amt_msat, option),
+    (4, expires_at, required),
+});
+
+impl AmountBoundsInfo {
+    pub(crate) fn accepts(&self, amt_msat: u64) -> bool {
+        self.min_amt_msat.is_none_or(|min| amt_msat >= min)
+            && self.max_amt_msat.is_none_or(|max| amt_msat <= max)
+    }
+}
+
+pub(crate) struct AmountBoundsMap {
+    pub(crate) invoices: LdkHashMap<PaymentHash, AmountBoundsInfo>,
+}
+
+impl_writeable_tlv_based!(AmountBoundsMap, {
+    (0, invoices, required),
+});
+
+/// On-chain fallback address embedded in an invoice, with the TX that paid it once detected
+#[derive(Clone, Debug)]
+pub(crate) struct FallbackAddressInfo {
+    pub(crate) address: String,
+    pub(crate) amt_msat: u64,
+    pub(crate) created_at: u64,
+    pub(crate) expires_at: u64,
+    pub(crate) txid: Option<String>,
+}
+
+impl_writeable_tlv_based!(FallbackAddressInfo, {
+    (0, address, required),
+    (2, amt_msat, required),
+    (4, created_at, required),
+    (6, expires_at, required),
+    (8, txid, option),
+});
+
+pub(crate) struct FallbackAddressMap {
+    pub(crate) invoices: LdkHashMap<PaymentHash, FallbackAddressInfo>,
+}
+
+impl_writeable_tlv_based!(FallbackAddressMap, {
+    (0, invoices, required),
+});
+
+/// Key of the routing policy of the vanilla channels, the RGB ones using their asset ID
+pub(crate) const BTC_POLICY_KEY: &str = "btc";
+
+/// Routing policy of the channels of an RGB asset (or of the vanilla ones), the HTLC limits
+/// applying to the channels opened afterwards
+#[derive(Clone, Debug, Default)]
+pub(crate) struct AssetPolicy {
+    pub(crate) fee_base_msat: Option<u32>,
+    pub(crate) fee_proportional_millionths: Option<u32>,
+    pub(crate) cltv_expiry_delta: Option<u16>,
+    pub(crate) htlc_minimum_msat: Option<u64>,
+    pub(crate) max_htlc_value_in_flight_percent: Option<u8>,
+}
+
+impl_writeable_tlv_based!(AssetPolicy, {
+    (0, fee_base_msat, option),
+    (2, fee_proportional_millionths, option),
+    (4, cltv_expiry_delta, option),
+    (6, htlc_minimum_msat, option),
+    (8, max_htlc_value_in_flight_percent, option),
+});
+
+impl AssetPolicy {
+    /// Channel config changes of the policy, if any
+    pub(crate) fn config_update(&self) -> Option<ChannelConfigUpdate> {
+        if self.fee_base_msat.is_none()
+            && self.fee_proportional_millionths.is_none()
+            && self.cltv_expiry_delta.is_none()
+        {
+            return None;
+        }
+        Some(ChannelConfigUpdate {
+            forwarding_fee_base_msat: self.fee_base_msat,
+            forwarding_fee_proportional_millionths: self.fee_proportional_millionths,
+            cltv_expiry_delta: self.cltv_expiry_delta,
+            ..Default::default()
+        })
+    }
+
+    /// Set the fields of the given policy, keeping the other ones
+    fn merge(&mut self, policy: AssetPolicy) {
+        self.fee_base_msat = policy.fee_base_msat.or(self.fee_base_msat);
+        self.fee_proportional_millionths = policy
+            .fee_proportional_millionths
+            .or(self.fee_proportional_millionths);
+        self.cltv_expiry_delta = policy.cltv_expiry_delta.or(self.cltv_expiry_delta);
+        self.htlc_minimum_msat = policy.htlc_minimum_msat.or(self.htlc_minimum_msat);
+        self.max_htlc_value_in_flight_percent = policy
+            .max_htlc_value_in_flight_percent
+            .or(self.max_htlc_value_in_flight_percent);
+    }
+}
+
+pub(crate) struct AssetPolicyMap {
+    pub(crate) policies: LdkHashMap<String, AssetPolicy>,
+}
+
+impl_writeable_tlv_based!(AssetPolicyMap, {
+    (0, policies, required),
+});
+
+#[derive(Clone, Debug)]
+pub(crate) struct OfferInfo {
+    pub(crate) offer: String,
+    pub(crate) description: String,
+    pub(crate) amt_msat: Option<u64>,
+    pub(crate) created_at: u64,
+    pub(crate) expires_at: Option<u64>,
+}
+
+impl_writeable_tlv_based!(OfferInfo, {
+    (0, offer, required),
+    (2, description, required),
+    (4, amt_msat, required),
+    (6, created_at, required),
+    (8, expires_at, required),
+});
+
+pub(crate) struct OfferMap {
+    pub(crate) offers: LdkHashMap<OfferId, OfferInfo>,
+}
+
+impl_writeable_tlv_based!(OfferMap, {
+    (0, offers, required),
+});
+
+#[derive(Clone, Debug)]
+pub(crate) struct OrderItemInfo {
+    pub(crate) description: String,
+    pub(crate) amt_msat: Option<u64>,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+}
+
+impl_writeable_tlv_based!(OrderItemInfo, {
+    (0, description, required),
+    (2, amt_msat, option),
+    (4, asset_id, option),
+    (6, asset_amount, option),
+});
+
+#[derive(Clone, Debug)]
+pub(crate) struct OrderLegInfo {
+    pub(crate) invoice: String,
+    pub(crate) payment_hash: PaymentHash,
+    pub(crate) amt_msat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+}
+
+impl_writeable_tlv_based!(OrderLegInfo, {
+    (0, invoice, required),
+    (2, payment_hash, required),
+    (4, amt_msat, required),
+    (6, asset_id, option),
+    (8, asset_amount, option),
+});
+
+#[derive(Clone, Debug)]
+pub(crate) struct OrderInfo {
+    pub(crate) items: Vec<OrderItemInfo>,
+    pub(crate) legs: Vec<OrderLegInfo>,
+    pub(crate) status: OrderStatus,
+    pub(crate) created_at: u64,
+    pub(crate) expires_at: u64,
+}
+
+impl_writeable_tlv_based!(OrderInfo, {
+    (0, items, required_vec),
+    (2, legs, required_vec),
+    (4, status, required),
+    (6, created_at, required),
+    (8, expires_at, required),
+});
+
+pub(crate) struct OrderMap {
+    pub(crate) orders: LdkHashMap<String, OrderInfo>,
+}
+
+impl_writeable_tlv_based!(OrderMap, {
+    (0, orders, required),
+});
+
+/// HODL invoice whose payment is settled or refunded on the signed decision of an arbiter
+#[derive(Clone, Debug)]
+pub(crate) struct EscrowInfo {
+    pub(crate) payment_hash: PaymentHash,
+    pub(crate) arbiter_pubkey: PublicKey,
+    pub(crate) invoice: String,
+    pub(crate) description: Option<String>,
+    pub(crate) amt_msat: Option<u64>,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) created_at: u64,
+    pub(crate) expires_at: u64,
+    pub(crate) decision: Option<EscrowDecision>,
+    pub(crate) resolved_at: Option<u64>,
+}
+
+impl_writeable_tlv_based!(EscrowInfo, {
+    (0, payment_hash, required),
+    (2, arbiter_pubkey, required),
+    (4, invoice, required),
+    (6, description, option),
+    (8, amt_msat, option),
+    (10, asset_id, option),
+    (12, asset_amount, option),
+    (14, created_at, required),
+    (16, expires_at, required),
+    (18, decision, option),
+    (20, resolved_at, option),
+});
+
+pub(crate) struct EscrowMap {
+    pub(crate) escrows: LdkHashMap<String, EscrowInfo>,
+}
+
+impl_writeable_tlv_based!(EscrowMap, {
+    (0, escrows, required),
+});
+
+/// Invoice waiting to be settled, with the label its settlement gets grouped by
+#[derive(Clone, Debug)]
+pub(crate) struct PendingSettlementInfo {
+    pub(crate) label: Option<String>,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) recipient_id: Option<String>,
+    pub(crate) expires_at: u64,
+}
+
+impl_writeable_tlv_based!(PendingSettlementInfo, {
+    (0, label, option),
+    (2, asset_id, option),
+    (4, recipient_id, option),
+    (6, expires_at, required),
+});
+
+/// Settled invoices and received asset transfers sharing the same label and asset
+#[derive(Clone, Debug)]
+pub(crate) struct SettlementTotalInfo {
+    pub(crate) label: Option<String>,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) settled_invoices: u64,
+    pub(crate) amt_msat: u64,
+    pub(crate) received_transfers: u64,
+    pub(crate) asset_amount: u64,
+}
+
+impl_writeable_tlv_based!(SettlementTotalInfo, {
+    (0, label, option),
+    (2, asset_id, option),
+    (4, settled_invoices, required),
+    (6, amt_msat, required),
+    (8, received_transfers, required),
+    (10, asset_amount, required),
+});
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SettlementPeriodInfo {
+    pub(crate) totals: Vec<SettlementTotalInfo>,
+}
+
+impl_writeable_tlv_based!(SettlementPeriodInfo, {
+    (0, totals, required_vec),
+});
+
+/// Settlement rollups by period, kept up to date as settlements happen so reports don't need
+/// to go through the payment and transfer history. Pending invoices are keyed by payment hash
+/// (LN) or batch transfer index (RGB).
+pub(crate) struct SettlementMap {
+    pub(crate) periods: LdkHashMap<String, SettlementPeriodInfo>,
+    pub(crate) pending: LdkHashMap<String, PendingSettlementInfo>,
+}
+
+impl_writeable_tlv_based!(SettlementMap, {
+    (0, periods, required),
+    (2, pending, required),
+});
+
+/// Accounting period (UTC month, as YYYY-MM) of the given timestamp
+pub(crate) fn settlement_period(timestamp: u64) -> String {
+    let datetime = OffsetDateTime::from_unix_timestamp(timestamp as i64).unwrap();
+    format!("{:04}-{:02}", datetime.year(), datetime.month() as u8)
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct IssuedAddressInfo {
+    pub(crate) index: u32,
+    pub(crate) created_at: u64,
+    pub(crate) used: bool,
+}
+
+impl_writeable_tlv_based!(IssuedAddressInfo, {
+    (0, index, required),
+    (2, created_at, required),
+    (4, used, required),
+});
+
+pub(crate) struct IssuedAddressMap {
+    pub(crate) addresses: LdkHashMap<String, IssuedAddressInfo>,
+}
+
+impl_writeable_tlv_based!(IssuedAddressMap, {
+    (0, addresses, required),
+});
+
+#[derive(Clone, Debug)]
+pub(crate) struct PendingBroadcastInfo {
+    pub(crate) kind: PendingBroadcastKind,
+    pub(crate) signed_psbt: String,
+    pub(crate) created_at: u64,
+    pub(crate) broadcast_at: Option<u64>,
+    pub(crate) hold_for_approval: bool,
+    pub(crate) approved_at: Option<u64>,
+    pub(crate) error: Option<String>,
+}
+
+impl_writeable_tlv_based!(PendingBroadcastInfo, {
+    (0, kind, required),
+    (2, signed_psbt, required),
+    (4, created_at, required),
+    (6, broadcast_at, required),
+    (8, hold_for_approval, required),
+    (10, approved_at, required),
+    (12, error, required),
+});
+
+impl PendingBroadcastInfo {
+    /// Whether the transaction can be broadcast at the given time
+    pub(crate) fn is_due(&self, now: u64) -> bool {
+        (!self.hold_for_approval || self.approved_at.is_some())
+            && self.broadcast_at.is_none_or(|t| t <= now)
+    }
+}
+
+pub(crate) struct PendingBroadcastMap {
+    pub(crate) broadcasts: LdkHashMap<String, PendingBroadcastInfo>,
+}
+
+impl_writeable_tlv_based!(PendingBroadcastMap, {
+    (0, broadcasts, required),
+});
+
+#[derive(Clone, Debug)]
+pub(crate) struct LockedUtxoInfo {
+    pub(crate) label: Option<String>,
+    pub(crate) locked_at: u64,
+}
+
+impl_writeable_tlv_based!(LockedUtxoInfo, {
+    (0, label, required),
+    (2, locked_at, required),
+});
+
+pub(crate) struct LockedUtxoMap {
+    pub(crate) utxos: LdkHashMap<String, LockedUtxoInfo>,
+}
+
+impl_writeable_tlv_based!(LockedUtxoMap, {
+    (0, utxos, required),
+});
+
+impl IssuedAddressMap {
+    /// Number of issued addresses following the last one that has been used
+    pub(crate) fn gap(&self) -> u32 {
+        let last_used_index = self
+            .addresses
+            .values()
+            .filter(|a| a.used)
+            .map(|a| a.index)
+            .max();
+        self.addresses
+            .values()
+            .filter(|a| last_used_index.is_none_or(|i| a.index > i))
+            .count() as u32
+    }
+}
+
+impl UnlockedAppState {
+    pub(crate) fn add_maker_swap(&self, payment_hash: PaymentHash, swap: SwapData) {
+        let mut maker_swaps = self.get_maker_swaps();
+        maker_swaps.swaps.insert(payment_hash, swap);
+        self.save_maker_swaps(maker_swaps);
+    }
+
+    pub(crate) fn update_maker_swap_status(&self, payment_hash: &PaymentHash, status: SwapStatus) {
+        let mut maker_swaps = self.get_maker_swaps();
+        let maker_swap = maker_swaps.swaps.get_mut(payment_hash).unwrap();
+        match &status {
+            SwapStatus::Succeeded | SwapStatus::Failed | SwapStatus::Expired => {
+                maker_swap.completed_at = Some(get_current_timestamp())
+            }
+            SwapStatus::Pending => maker_swap.initiated_at = Some(get_current_timestamp()),
+            SwapStatus::Waiting => panic!("this doesn't make sense: swap starts in Waiting status"),
+        }
+        maker_swap.status = status;
+        self.save_maker_swaps(maker_swaps);
+    }
+
+    pub(crate) fn is_maker_swap(&self, payment_hash: &PaymentHash) -> bool {
+        self.maker_swaps().contains_key(payment_hash)
+    }
+
+    pub(crate) fn add_taker_swap(&self, payment_hash: PaymentHash, swap: SwapData) {
+        let mut taker_swaps = self.get_taker_swaps();
+        taker_swaps.swaps.insert(payment_hash, swap);
+        self.save_taker_swaps(taker_swaps);
+    }
+
+    pub(crate) fn update_taker_swap_status(&self, payment_hash: &PaymentHash, status: SwapStatus) {
+        let mut taker_swaps = self.get_taker_swaps();
+        let taker_swap = taker_swaps.swaps.get_mut(payment_hash).unwrap();
+        match &status {
+            SwapStatus::Succeeded | SwapStatus::Failed | SwapStatus::Expired => {
+                taker_swap.completed_at = Some(get_current_timestamp())
+            }
+            SwapStatus::Pending => taker_swap.initiated_at = Some(get_current_timestamp()),
+            SwapStatus::Waiting => panic!("this doesn't make sense: swap starts in Waiting status"),
+        }
+        taker_swap.status = status;
+        self.save_taker_swaps(taker_swaps);
+    }
+
+    pub(crate) fn is_taker_swap(&self, payment_hash: &PaymentHash) -> bool {
+        self.taker_swaps().contains_key(payment_hash)
+    }
+
+    fn save_maker_swaps(&self, swaps: MutexGuard<SwapMap>) {
+        self.kv_store
+            .write("", "", MAKER_SWAPS_FNAME, swaps.encode())
+            .unwrap();
+    }
+
+    fn save_taker_swaps(&self, swaps: MutexGuard<SwapMap>) {
+        self.kv_store
+            .write("", "", TAKER_SWAPS_FNAME, swaps.encode())
+            .unwrap();
+    }
+
+    pub(crate) fn maker_swaps(&self) -> LdkHashMap<PaymentHash, SwapData> {
+        self.get_maker_swaps().swaps.clone()
+    }
+
+    pub(crate) fn taker_swaps(&self) -> LdkHashMap<PaymentHash, SwapData> {
+        self.get_taker_swaps().swaps.clone()
+    }
+
+    pub(crate) fn add_inbound_payment(&self, payment_hash: PaymentHash, payment_info: PaymentInfo) {
+        let mut inbound = self.get_inbound_payments();
+        inbound.payments.insert(payment_hash, payment_info);
+        self.save_inbound_payments(inbound);
+    }
+
+    pub(crate) fn add_outbound_payment(
+        &self,
+        payment_id: PaymentId,
+        payment_info: PaymentInfo,
+    ) -> Result<(), APIError> {
+        let mut outbound = self.get_outbound_payments();
+        if let Some(existing_payment) = outbound.payments.get(&payment_id) {
+            if !matches!(existing_payment.status, HTLCStatus::Failed) {
+                return Err(APIError::DuplicatePayment(
+                    existing_payment.status.to_string(),
+                ));
+            }
+        }
+        outbound.payments.insert(payment_id, payment_info);
+        self.save_outbound_payments(outbound);
+        Ok(())
+    }
+
+    fn fail_outbound_pending_payments(&self, recent_payments_payment_ids: Vec<PaymentId>) {
+        let mut outbound = self.get_outbound_payments();
+        let mut failed = false;
+        for (payment_id, payment_info) in outbound
+            .payments
+            .iter_mut()
+            .filter(|(_, i)| matches!(i.status, HTLCStatus::Pending))
+        {
+            if !recent_payments_payment_ids.contains(payment_id) {
+                payment_info.status = HTLCStatus::Failed;
+                payment_info.updated_at = get_current_timestamp();
+                failed = true;
+            }
+        }
+        if failed {
+            self.save_outbound_payments(outbound);
+        }
+    }
+
+    pub(crate) fn inbound_payments(&self) -> LdkHashMap<PaymentHash, PaymentInfo> {
+        self.get_inbound_payments().payments.clone()
+    }
+
+    pub(crate) fn outbound_payments(&self) -> LdkHashMap<PaymentId, PaymentInfo> {
+        self.get_outbound_payments().payments.clone()
+    }
+
+    fn save_inbound_payments(&self, inbound: MutexGuard<InboundPaymentInfoStorage>) {
+        self.kv_store
+            .write("", "", INBOUND_PAYMENTS_FNAME, inbound.encode())
+            .unwrap();
+    }
+
+    fn save_outbound_payments(&self, outbound: MutexGuard<OutboundPaymentInfoStorage>) {
+        self.kv_store
+            .write("", "", OUTBOUND_PAYMENTS_FNAME, outbound.encode())
+            .unwrap();
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn upsert_inbound_payment(
+        &self,
+        payment_hash: PaymentHash,
+        status: HTLCStatus,
+        preimage: Option<PaymentPreimage>,
+        secret: Option<PaymentSecret>,
+        amt_msat: Option<u64>,
+        payee_pubkey: PublicKey,
+        offer_id: Option<OfferId>,
+    ) {
+        let mut inbound = self.get_inbound_payments();
+        match inbound.payments.entry(payment_hash) {
+            Entry::Occupied(mut e) => {
+                let payment_info = e.get_mut();
+                payment_info.status = status;
+                payment_info.preimage = preimage.or(payment_info.preimage);
+                payment_info.secret = secret;
+                payment_info.updated_at = get_current_timestamp();
+            }
+            Entry::Vacant(e) => {
+                let created_at = get_current_timestamp();
+                e.insert(PaymentInfo {
+                    preimage,
+                    secret,
+                    status,
+                    amt_msat,
+                    created_at,
+                    updated_at: created_at,
+                    payee_pubkey,
+                    offer_id,
+                    parts: vec![],
+                    expires_at: None,
+                    settle_after_expiry: false,
+                });
+            }
+        }
+        self.save_inbound_payments(inbound);
+    }
+
+    pub(crate) fn update_outbound_payment(
+        &self,
+        payment_id: PaymentId,
+        status: HTLCStatus,
+        preimage: Option<PaymentPreimage>,
+    ) -> PaymentInfo {
+        let mut outbound = self.get_outbound_payments();
+        let payment_info = outbound.payments.get_mut(&payment_id).unwrap();
+        payment_info.status = status;
+        payment_info.preimage = preimage;
+        payment_info.updated_at = get_current_timestamp();
+        let payment = (*payment_info).clone();
+        self.save_outbound_payments(outbound);
+        payment
+    }
+
+    /// Record the outcome of one of the paths of an outbound payment
+    pub(crate) fn add_outbound_payment_part(
+        &self,
+        payment_id: PaymentId,
+        path: &LnPath,
+        status: HTLCStatus,
+        failure: Option<String>,
+        rgb_payment: Option<(String, u64)>,
+    ) {
+        let mut outbound = self.get_outbound_payments();
+        if let Some(payment_info) = outbound.payments.get_mut(&payment_id) {
+            payment_info.parts.push(PaymentPartInfo {
+                amt_msat: path.final_value_msat(),
+                fee_msat: path.fee_msat(),
+                first_hop_scid: path.hops.first().map(|h| h.short_channel_id),
+                hops: path.hops.len() as u8,
+                status,
+                failure,
+                updated_at: get_current_timestamp(),
+                asset_id: rgb_payment.as_ref().map(|(asset_id, _)| asset_id.clone()),
+                asset_amount: rgb_payment.map(|(_, amount)| amount),
+                route: path
+                    .hops
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, h)| PaymentHopInfo {
+                        pubkey: h.pubkey,
+                        short_channel_id: h.short_channel_id,
+                        fee_msat: if idx + 1 < path.hops.len() {
+                            h.fee_msat
+                        } else {
+                            0
+                        },
+                        cltv_expiry_delta: h.cltv_expiry_delta,
+                    })
+                    .collect(),
+            });
+            self.save_outbound_payments(outbound);
+        }
+    }
+
+    /// Drop the unpaid invoices expired for longer than the retention, along with their RGB info
+    pub(crate) fn prune_expired_invoices(&self, retention_sec: u64, ldk_data_dir: &Path) -> usize {
+        let hodl_invoices = self.hodl_invoices();
+        let now = get_current_timestamp();
+        let mut inbound = self.get_inbound_payments();
+        let pruned: Vec<PaymentHash> = inbound
+            .payments
+            .iter()
+            .filter(|(payment_hash, p)| {
+                matches!(p.status, HTLCStatus::Pending)
+                    && !p.settle_after_expiry
+                    && !hodl_invoices.contains_key(payment_hash)
+                    && p.expires_at
+                        .is_some_and(|e| e.saturating_add(retention_sec) <= now)
+            })
+            .map(|(payment_hash, _)| *payment_hash)
+            .collect();
+        if pruned.is_empty() {
+            return 0;
+        }
+        for payment_hash in &pruned {
+            inbound.payments.remove(payment_hash);
+            let rgb_payment_info_path = get_rgb_payment_info_path(payment_hash, ldk_data_dir, true);
+            if rgb_payment_info_path.exists() {
+                let _ = fs::remove_file(rgb_payment_info_path);
+            }
+        }
+        self.save_inbound_payments(inbound);
+        pruned.len()
+    }
+
+    /// Drop the succeeded and failed payments resolved for longer than the retention, along with
+    /// their RGB info, returning the number of payments pruned and the bytes reclaimed
+    pub(crate) fn prune_resolved_payments(
+        &self,
+        retention_sec: u64,
+        ldk_data_dir: &Path,
+    ) -> (usize, u64) {
+        let now = get_current_timestamp();
+        let is_prunable = |p: &PaymentInfo| {
+            !matches!(p.status, HTLCStatus::Pending)
+                && p.updated_at.saturating_add(retention_sec) <= now
+        };
+        let remove_rgb_payment_info = |payment_hash: &PaymentHash, inbound: bool| {
+            let rgb_payment_info_path =
+                get_rgb_payment_info_path(payment_hash, ldk_data_dir, inbound);
+            let size = fs::metadata(&rgb_payment_info_path)
+                .map(|m| m.len())
+                .unwrap_or(0);
+            if size > 0 && fs::remove_file(rgb_payment_info_path).is_ok() {
+                size
+            } else {
+                0
+            }
+        };
+        let mut pruned = 0;
+        let mut reclaimed_bytes = 0;
+
+        {
+            let mut inbound = self.get_inbound_payments();
+            let size_before = inbound.encode().len() as u64;
+            let inbound_pruned: Vec<PaymentHash> = inbound
+                .payments
+                .iter()
+                .filter(|(_, p)| is_prunable(p))
+                .map(|(payment_hash, _)| *payment_hash)
+                .collect();
+            if !inbound_pruned.is_empty() {
+                for payment_hash in &inbound_pruned {
+                    inbound.payments.remove(payment_hash);
+                    reclaimed_bytes += remove_rgb_payment_info(payment_hash, true);
+                }
+                reclaimed_bytes += size_before.saturating_sub(inbound.encode().len() as u64);
+                pruned += inbound_pruned.len();
+                self.save_inbound_payments(inbound);
+            }
+        }
+
+        let mut outbound = self.get_outbound_payments();
+        let size_before = outbound.encode().len() as u64;
+        let outbound_pruned: Vec<PaymentId> = outbound
+            .payments
+            .iter()
+            .filter(|(_, p)| is_prunable(p))
+            .map(|(payment_id, _)| *payment_id)
+            .collect();
+        if !outbound_pruned.is_empty() {
+            for payment_id in &outbound_pruned {
+                outbound.payments.remove(payment_id);
+                // the RGB info of outbound payments is stored by payment hash, which is also the
+                // payment ID of the ones that can carry assets
+                reclaimed_bytes += remove_rgb_payment_info(&PaymentHash(payment_id.0), false);
+            }
+            reclaimed_bytes += size_before.saturating_sub(outbound.encode().len() as u64);
+            pruned += outbound_pruned.len();
+            self.save_outbound_payments(outbound);
+        }
+
+        (pruned, reclaimed_bytes)
+    }
+
+    pub(crate) fn update_inbound_payment_status(
+        &self,
+        payment_hash: &PaymentHash,
+        status: HTLCStatus,
+    ) {
+        let mut inbound = self.get_inbound_payments();
+        if let Some(payment_info) = inbound.payments.get_mut(payment_hash) {
+            payment_info.status = status;
+            payment_info.updated_at = get_current_timestamp();
+            self.save_inbound_payments(inbound);
+        }
+    }
+
+    pub(crate) fn update_outbound_payment_status(&self, payment_id: PaymentId, status: HTLCStatus) {
+        let mut outbound = self.get_outbound_payments();
+        let payment_info = outbound.payments.get_mut(&payment_id).unwrap();
+        payment_info.status = status;
+        payment_info.updated_at = get_current_timestamp();
+        self.save_outbound_payments(outbound);
+    }
+
+    pub(crate) fn channel_ids(&self) -> LdkHashMap<ChannelId, ChannelId> {
+        self.get_channel_ids_map().channel_ids.clone()
+    }
+
+    pub(crate) fn add_channel_id(
+        &self,
+        former_temporary_channel_id: ChannelId,
+        channel_id: ChannelId,
+    ) {
+        let mut channel_ids_map = self.get_channel_ids_map();
+        channel_ids_map
+            .channel_ids
+            .insert(former_temporary_channel_id, channel_id);
+        self.save_channel_ids_map(channel_ids_map);
+    }
+
+    pub(crate) fn delete_channel_id(&self, channel_id: ChannelId) {
+        let mut channel_ids_map = self.get_channel_ids_map();
+        if let Some(temporary_channel_id) = channel_ids_map
+            .channel_ids
+            .clone()
+            .into_iter()
+            .find_map(|(tmp_chan_id, chan_id)| {
+                if chan_id == channel_id {
+                    Some(tmp_chan_id)
+                } else {
+                    None
+                }
+            })
+        {
+            channel_ids_map.channel_ids.remove(&temporary_channel_id);
+            self.save_channel_ids_map(channel_ids_map);
+        }
+    }
+
+    fn save_channel_ids_map(&self, channel_ids: MutexGuard<ChannelIdsMap>) {
+        self.kv_store
+            .write("", "", CHANNEL_IDS_FNAME, channel_ids.encode())
+            .unwrap();
+    }
+
+    pub(crate) fn add_hodl_invoice(&self, payment_hash: PaymentHash) {
+        let mut hodl_invoices = self.get_hodl_invoices();
+        hodl_invoices.invoices.insert(
+            payment_hash,
+            HodlInvoiceInfo {
+                claimable_amt_msat: None,
+                claim_deadline: None,
+            },
+        );
+        self.save_hodl_invoices(hodl_invoices);
+    }
+
+    pub(crate) fn hodl_invoices(&self) -> LdkHashMap<PaymentHash, HodlInvoiceInfo> {
+        self.get_hodl_invoices().invoices.clone()
+    }
+
+    fn set_hodl_invoice_claimable(
+        &self,
+        payment_hash: &PaymentHash,
+        amt_msat: u64,
+        claim_deadline: Option<u32>,
+    ) {
+        let mut hodl_invoices = self.get_hodl_invoices();
+        if let Some(hodl_invoice) = hodl_invoices.invoices.get_mut(payment_hash) {
+            hodl_invoice.claimable_amt_msat = Some(amt_msat);
+            hodl_invoice.claim_deadline = claim_deadline;
+            self.save_hodl_invoices(hodl_invoices);
+        }
+    }
+
+    /// CLTV expiry of the HTLCs held for a HODL invoice and the blocks left before the payment
+    /// gets failed back, as it can't be claimed after the claim deadline without risking a force
+    /// close
+    pub(crate) fn held_payment_deadline(
+        &self,
+        payment_hash: &PaymentHash,
+        claim_deadline: Option<u32>,
+    ) -> HeldPaymentDeadline {
+        let current_height = self.channel_manager.current_best_block().height;
+        let cltv_expiry = self
+            .channel_manager
+            .list_channels()
+            .iter()
+            .flat_map(|c| c.pending_inbound_htlcs.iter())
+            .filter(|h| h.payment_hash == *payment_hash)
+            .map(|h| h.cltv_expiry)
+            .min();
+        HeldPaymentDeadline {
+            cltv_expiry,
+            current_height,
+            blocks_to_cancel: claim_deadline.map(|d| d.saturating_sub(current_height)),
+        }
+    }
+
+    /// Warn about the held HODL invoice payments close to their claim deadline
+    fn warn_expiring_hodl_invoices(&self) {
+        for (payment_hash, hodl_invoice) in self.hodl_invoices() {
+            if hodl_invoice.claimable_amt_msat.is_none() {
+                continue;
+            }
+            let deadline = self.held_payment_deadline(&payment_hash, hodl_invoice.claim_deadline);
+            let Some(blocks_to_cancel) = deadline.blocks_to_cancel else {
+                continue;
+            };
+            if blocks_to_cancel > HTLC_RISK_WINDOW_BLOCKS {
+                continue;
+            }
+            tracing::warn!(
+                "Held payment {payment_hash} needs to be settled within {blocks_to_cancel} blocks, \
+                otherwise it gets cancelled"
+            );
+            self.hook_runner.trigger(HookEvent::HodlInvoiceExpiring {
+                payment_hash: hex_str(&payment_hash.0),
+                cltv_expiry: deadline.cltv_expiry,
+                current_height: deadline.current_height,
+                blocks_to_cancel,
+            });
+        }
+    }
+
+    pub(crate) fn remove_hodl_invoice(&self, payment_hash: &PaymentHash) {
+        let mut hodl_invoices = self.get_hodl_invoices();
+        if hodl_invoices.invoices.remove(payment_hash).is_some() {
+            self.save_hodl_invoices(hodl_invoices);
+        }
+    }
+
+    /// Fail a payment outside of the accepted amounts, otherwise hold the payment of a HODL
+    /// invoice or claim it if its preimage is known
+    pub(crate) fn handle_claimable_payment(
+        &self,
+        payment_hash: &PaymentHash,
+        amt_msat: u64,
+        claim_deadline: Option<u32>,
+        payment_preimage: Option<PaymentPreimage>,
+    ) {
+        if let Some(amount_bounds) = self.amount_bounds(payment_hash) {
+            if !amount_bounds.accepts(amt_msat) {
+                tracing::info!(
+                    "EVENT: failing back payment with payment hash {} as {} millisatoshis are outside of the accepted amounts",
+                    payment_hash,
+                    amt_msat,
+                );
+                self.channel_manager.fail_htlc_backwards(payment_hash);
+                return;
+            }
+        }
+        if self.hodl_invoices().contains_key(payment_hash) {
+            tracing::info!(
+                "EVENT: holding payment with payment hash {} until it gets settled or cancelled",
+                payment_hash,
+            );
+            self.set_hodl_invoice_claimable(payment_hash, amt_msat, claim_deadline);
+            return;
+        }
+        // LDK checks the expiry against block times, which can lag behind by hours
+        let expired = self
+            .get_inbound_payments()
+            .payments
+            .get(payment_hash)
+            .is_some_and(|p| {
+                !p.settle_after_expiry && p.expires_at.is_some_and(|e| e <= get_current_timestamp())
+            });
+        if expired {
+            tracing::info!(
+                "EVENT: failing back payment with payment hash {} as its invoice has expired",
+                payment_hash,
+            );
+            self.channel_manager.fail_htlc_backwards(payment_hash);
+            return;
+        }
+        match payment_preimage {
+            Some(preimage) => self.channel_manager.claim_funds(preimage),
+            None => {
+                tracing::info!(
+                    "EVENT: failing back payment with payment hash {} as its preimage is unknown",
+                    payment_hash,
+                );
+                self.channel_manager.fail_htlc_backwards(payment_hash);
+            }
+        }
+    }
+
+    fn save_hodl_invoices(&self, hodl_invoices: MutexGuard<HodlInvoiceMap>) {
+        self.kv_store
+            .write("", "", HODL_INVOICES_FNAME, hodl_invoices.encode())
+            .unwrap();
+    }
+
+    /// Record a JIT channel, dropping the ones whose invoice has expired
+    pub(crate) fn add_jit_channel(&self, payment_hash: PaymentHash, jit_channel: JitChannelInfo) {
+        let mut jit_channels = self.get_jit_channels();
+        let now = get_current_timestamp();
+        jit_channels.channels.retain(|_, c| c.expires_at > now);
+        jit_channels.channels.insert(payment_hash, jit_channel);
+        self.save_jit_channels(jit_channels);
+    }
+
+    pub(crate) fn jit_channel(&self, payment_hash: &PaymentHash) -> Option<JitChannelInfo> {
+        self.get_jit_channels().channels.get(payment_hash).cloned()
+    }
+
+    /// Whether the peer is an LSP that should open a JIT channel for a pending invoice
+    fn is_jit_channel_lsp(&self, counterparty_node_id: &PublicKey) -> bool {
+        let now = get_current_timestamp();
+        self.get_jit_channels()
+            .channels
+            .values()
+            .any(|c| c.lsp_pubkey == *counterparty_node_id && c.expires_at > now)
+    }
+
+    fn remove_jit_channel(&self, payment_hash: &PaymentHash) {
+        let mut jit_channels = self.get_jit_channels();
+        if jit_channels.channels.remove(payment_hash).is_some() {
+            self.save_jit_channels(jit_channels);
+        }
+    }
+
+    fn save_jit_channels(&self, jit_channels: MutexGuard<JitChannelMap>) {
+        self.kv_store
+            .write("", "", JIT_CHANNELS_FNAME, jit_channels.encode())
+            .unwrap();
+    }
+
+    /// Update the routing policy of the channels of an asset, returning the resulting one
+    pub(crate) fn set_asset_policy(&self, key: &str, policy: AssetPolicy) -> AssetPolicy {
+        let mut asset_policies = self.get_asset_policies();
+        let asset_policy = asset_policies.policies.entry(key.to_string()).or_default();
+        asset_policy.merge(policy);
+        let asset_policy = asset_policy.clone();
+        self.kv_store
+            .write("", "", ASSET_POLICIES_FNAME, asset_policies.encode())
+            .unwrap();
+        asset_policy
+    }
+
+    pub(crate) fn asset_policy(&self, key: &str) -> Option<AssetPolicy> {
+        self.get_asset_policies().policies.get(key).cloned()
+    }
+
+    /// Key of the routing policy applying to a channel
+    pub(crate) fn channel_policy_key(&self, channel_id: &ChannelId, ldk_data_dir: &Path) -> String {
+        get_rgb_channel_info_optional(channel_id, ldk_data_dir, false)
+            .map(|(rgb_info, _)| rgb_info.contract_id.to_string())
+            .unwrap_or_else(|| BTC_POLICY_KEY.to_string())
+    }
+
+    /// Apply the routing policy of its asset to an inbound channel that just became ready
+    fn apply_asset_policy(
+        &self,
+        channel_id: &ChannelId,
+        counterparty_node_id: &PublicKey,
+        ldk_data_dir: &Path,
+    ) {
+        // outbound channels get the policy when opened, where it can be overridden
+        let outbound = self
+            .channel_manager
+            .list_channels()
+            .iter()
+            .any(|c| c.channel_id == *channel_id && c.is_outbound);
+        if outbound {
+            return;
+        }
+        let key = self.channel_policy_key(channel_id, ldk_data_dir);
+        let Some(config_update) = self.asset_policy(&key).and_then(|p| p.config_update()) else {
+            return;
+        };
+        match self.channel_manager.update_partial_channel_config(
+            counterparty_node_id,
+            &[*channel_id],
+            &config_update,
+        ) {
+            Ok(()) => tracing::info!("Applied the {key} routing policy to channel {channel_id}"),
+            Err(e) => tracing::error!(
+                "Failed to apply the {key} routing policy to channel {channel_id}: {e:?}"
+            ),
+        }
+    }
+
+    /// Record the amounts accepted by an invoice, dropping the ones of expired invoices
+    pub(crate) fn add_amount_bounds(
+        &self,
+        payment_hash: PaymentHash,
+        amount_bounds: AmountBoundsInfo,
+    ) {
+        let mut amount_bounds_map = self.get_amount_bounds();
+        let now = get_current_timestamp();
+        amount_bounds_map.invoices.retain(|_, b| b.expires_at > now);
+        amount_bounds_map
+            .invoices
+            .insert(payment_hash, amount_bounds);
+        self.kv_store
+            .write("", "", AMOUNT_BOUNDS_FNAME, amount_bounds_map.encode())
+            .unwrap();
+    }
+
+    pub(crate) fn amount_bounds(&self, payment_hash: &PaymentHash) -> Option<AmountBoundsInfo> {
+        self.get_amount_bounds().invoices.get(payment_hash).cloned()
+    }
+
+    pub(crate) fn add_fallback_address(
+        &self,
+        payment_hash: PaymentHash,
+        fallback_address: FallbackAddressInfo,
+    ) {
+        let mut fallback_addresses = self.get_fallback_addresses();
+        fallback_addresses
+            .invoices
+            .insert(payment_hash, fallback_address);
+        self.save_fallback_addresses(fallback_addresses);
+    }
+
+    /// TX that paid the invoice to its fallback address, if any
+    pub(crate) fn fallback_txid(&self, payment_hash: &PaymentHash) -> Option<String> {
+        self.get_fallback_addresses()
+            .invoices
+            .get(payment_hash)
+            .and_then(|f| f.txid.clone())
+    }
+
+    fn save_fallback_addresses(&self, fallback_addresses: MutexGuard<FallbackAddressMap>) {
+        self.kv_store
+            .write(
+                "",
+                "",
+                FALLBACK_ADDRESSES_FNAME,
+                fallback_addresses.encode(),
+            )
+            .unwrap();
+    }
+
+    /// Settle the pending invoices whose fallback address received at least their amount in a
+    /// confirmed wallet transaction, forgetting the unpaid ones some time after they expired
+    pub(crate) async fn check_fallback_payments(&self) -> Result<(), APIError> {
+        let now = get_current_timestamp();
+        let inbound_payments = self.inbound_payments();
+        let pending: Vec<(PaymentHash, FallbackAddressInfo, Vec<u8>)> = {
+            let mut fallback_addresses = self.get_fallback_addresses();
+            let num_invoices = fallback_addresses.invoices.len();
+            fallback_addresses
+                .invoices
+                .retain(|_, f| f.txid.is_some() || f.expires_at + FALLBACK_WATCH_GRACE_SEC > now);
+            let pending = fallback_addresses
+                .invoices
+                .iter()
+                .filter(|(payment_hash, f)| {
+                    f.txid.is_none()
+                        && inbound_payments
+                            .get(payment_hash)
+                            .is_some_and(|p| matches!(p.status, HTLCStatus::Pending))
+                })
+                .filter_map(|(payment_hash, f)| {
+                    let script = Address::from_str(&f.address)
+                        .ok()?
+                        .assume_checked()
+                        .script_pubkey()
+                        .to_bytes();
+                    Some((*payment_hash, f.clone(), script))
+                })
+                .collect();
+            if fallback_addresses.invoices.len() != num_invoices {
+                self.save_fallback_addresses(fallback_addresses);
+            }
+            pending
+        };
+        let Some(oldest_created_at) = pending.iter().map(|(_, f, _)| f.created_at).min() else {
+            return Ok(());
+        };
+
+        let mut paid: Vec<(PaymentHash, u64, String)> = vec![];
+        for tx in self.rgb_list_transactions(false)? {
+            if tx.received == 0 {
+                continue;
+            }
+            // only confirmed payments settle an invoice
+            let Some(confirmation_time) = &tx.confirmation_time else {
+                continue;
+            };
+            // block timestamps can be up to 2 hours in the future
+            if confirmation_time.timestamp + 7200 < oldest_created_at {
+                continue;
+            }
+            let Ok(txid) = Txid::from_str(&tx.txid) else {
+                continue;
+            };
+            let Some(raw_tx) = self
+                .bitcoind_client
+                .get_raw_transaction(&txid, Some(confirmation_time.height))
+                .await
+            else {
+                tracing::warn!("Cannot retrieve TX {txid} to check fallback payments");
+                continue;
+            };
+            for output in raw_tx.output {
+                if let Some((payment_hash, f, _)) =
+                    pending.iter().find(|(payment_hash, f, script)| {
+                        script == output.script_pubkey.as_bytes()
+                            && output.value.to_sat() >= f.amt_msat.div_ceil(1000)
+                            && !paid.iter().any(|(h, _, _)| h == payment_hash)
+                    })
+                {
+                    paid.push((*payment_hash, f.amt_msat, tx.txid.clone()));
+                }
+            }
+        }
+
+        for (payment_hash, amt_msat, txid) in paid {
+            tracing::info!("Invoice with payment hash {payment_hash} paid on-chain by TX {txid}");
+            let mut fallback_addresses = self.get_fallback_addresses();
+            if let Some(f) = fallback_addresses.invoices.get_mut(&payment_hash) {
+                f.txid = Some(txid.clone());
+            }
+            self.save_fallback_addresses(fallback_addresses);
+            self.update_inbound_payment_status(&payment_hash, HTLCStatus::Succeeded);
+            self.add_invoice_settlement(&payment_hash, amt_msat, None, None);
+            self.add_journal_entry(
+                JournalEventKind::InvoiceSettled,
+                format!("payment hash {payment_hash}, amt_msat {amt_msat}, fallback TX {txid}"),
+            );
+            self.hook_runner.trigger(HookEvent::InvoiceSettled {
+                payment_hash: hex_str(&payment_hash.0),
+                amt_msat,
+                asset_id: None,
+                asset_amount: None,
+            });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn set_channel_acceptor_policy(&self, policy: ChannelAcceptorPolicy) {
+        let mut channel_acceptor = self.get_channel_acceptor();
+        *channel_acceptor = policy;
+        self.kv_store
+            .write("", "", CHANNEL_ACCEPTOR_FNAME, channel_acceptor.encode())
+            .unwrap();
+    }
+
+    pub(crate) fn set_channel_depths(&self, overrides: ChannelDepthOverrides) {
+        let mut channel_depths = self.get_channel_depths();
+        *channel_depths = overrides;
+        self.kv_store
+            .write("", "", CHANNEL_DEPTHS_FNAME, channel_depths.encode())
+            .unwrap();
+    }
+
+    /// Accept an inbound channel requiring the confirmations set for the peer, as a zero-conf
+    /// channel if the peer is trusted
+    pub(crate) fn accept_inbound_channel_with_depth(
+        &self,
+        temporary_channel_id: &ChannelId,
+        counterparty_node_id: &PublicKey,
+        user_channel_id: u128,
+        default_depth: u32,
+    ) -> Result<(), LDKAPIError> {
+        let min_depth = self
+            .get_channel_depths()
+            .min_depth(counterparty_node_id, default_depth);
+        if min_depth == 0 {
+            return self
+                .channel_manager
+                .accept_inbound_channel_from_trusted_peer_0conf(
+                    temporary_channel_id,
+                    counterparty_node_id,
+                    user_channel_id,
+                    None,
+                );
+        }
+        let config_overrides = (min_depth != default_depth).then(|| ChannelConfigOverrides {
+            handshake_overrides: Some(ChannelHandshakeConfigUpdate {
+                minimum_depth: Some(min_depth),
+                ..Default::default()
+            }),
+            update_overrides: None,
+        });
+        self.channel_manager.accept_inbound_channel(
+            temporary_channel_id,
+            counterparty_node_id,
+            user_channel_id,
+            config_overrides,
+        )
+    }
+
+    /// Replace the peer filter, disconnecting the inbound peers it now refuses
+    pub(crate) fn set_peer_filter(&self, filter: PeerFilter) {
+        let mut peer_filter = self.get_peer_filter();
+        *peer_filter = filter;
+        self.kv_store
+            .write("", "", PEER_FILTER_FNAME, peer_filter.encode())
+            .unwrap();
+        for peer in self.peer_manager.list_peers() {
+            if !peer.is_inbound_connection {
+                continue;
+            }
+            let pubkey = peer.counterparty_node_id;
+            let hosts = announced_hosts(&self.network_graph, &pubkey);
+            if let Some(reason) = peer_filter.check_peer(&pubkey, &hosts) {
+                tracing::info!("Disconnecting inbound peer: {reason}");
+                self.peer_manager.disconnect_by_node_id(pubkey);
+            }
+        }
+    }
+
+    pub(crate) fn set_sweep_config(&self, config: SweepConfig) {
+        let mut sweep_config = self.get_sweep_config();
+        *sweep_config = config;
+        self.kv_store
+            .write("", "", SWEEP_CONFIG_FNAME, sweep_config.encode())
+            .unwrap();
+    }
+
+    /// Update the node announcement identity, broadcasting it right away
+    pub(crate) fn set_node_announcement(&self, config: NodeAnnouncementConfig) {
+        let mut node_announcement = self.get_node_announcement();
+        *node_announcement = config;
+        self.kv_store
+            .write("", "", NODE_ANNOUNCEMENT_FNAME, node_announcement.encode())
+            .unwrap();
+        drop(node_announcement);
+        self.broadcast_node_announcement();
+    }
+
+    /// Broadcast the node announcement, only possible (and needed) with some public channels.
+    /// Note that the announcement may not propagate until a channel has 6+ confirmations.
+    pub(crate) fn broadcast_node_announcement(&self) {
+        if !self
+            .channel_manager
+            .list_channels()
+            .iter()
+            .any(|chan| chan.is_announced)
+        {
+            return;
+        }
+        let node_announcement = self.get_node_announcement().clone();
+        self.peer_manager.broadcast_node_announcement(
+            node_announcement.color.unwrap_or_default(),
+            node_announcement.alias_bytes(),
+            self.announced_addresses.clone(),
+        );
+    }
+
+    pub(crate) fn set_lnurl_pay(&self, config: LnurlPayConfig) {
+        let mut lnurl_pay = self.get_lnurl_pay();
+        *lnurl_pay = config;
+        self.kv_store
+            .write("", "", LNURL_PAY_FNAME, lnurl_pay.encode())
+            .unwrap();
+    }
+
+    /// Inbound channel requests waiting for a decision, dropping the ones LDK has already
+    /// discarded for being unaccepted for too long
+    pub(crate) fn pending_channels(&self) -> PendingChannelMap {
+        let now = get_current_timestamp();
+        let mut pending_channels = self.get_pending_channels();
+        pending_channels.retain(|_, c| c.received_at + PENDING_CHANNEL_TIMEOUT_SEC > now);
+        pending_channels.clone()
+    }
+
+    pub(crate) fn accept_pending_channel(
+        &self,
+        temporary_channel_id: ChannelId,
+        default_depth: u32,
+    ) -> Result<(), APIError> {
+        let pending_channel = self
+            .get_pending_channels()
+            .remove(&temporary_channel_id)
+            .ok_or(APIError::UnknownTemporaryChannelId)?;
+        let counterparty_node_id = pending_channel.counterparty_node_id;
+        let user_channel_id = u128::from_be_bytes(
+            self.keys_manager.get_secure_random_bytes()[..16]
+                .try_into()
+                .unwrap(),
+        );
+        self.accept_inbound_channel_with_depth(
+            &temporary_channel_id,
+            &counterparty_node_id,
+            user_channel_id,
+            default_depth,
+        )
+        .map_err(|e| APIError::CannotAcceptChannel(format!("{e:?}")))?;
+        tracing::info!(
+            "Manually accepted inbound channel ({temporary_channel_id}) from {counterparty_node_id}"
+        );
+        self.add_channel_event(
+            temporary_channel_id,
+            ChannelEventKind::OpenReceived,
+            format!("manually accepted request from {counterparty_node_id}"),
+        );
+        Ok(())
+    }
+
+    pub(crate) fn reject_pending_channel(
+        &self,
+        temporary_channel_id: ChannelId,
+        reason: String,
+    ) -> Result<(), APIError> {
+        let pending_channel = self
+            .get_pending_channels()
+            .remove(&temporary_channel_id)
+            .ok_or(APIError::UnknownTemporaryChannelId)?;
+        self.reject_channel(
+            temporary_channel_id,
+            pending_channel.counterparty_node_id,
+            reason,
+        )
+    }
+
+    fn reject_channel(
+        &self,
+        channel_id: ChannelId,
+        counterparty_node_id: PublicKey,
+        reason: String,
+    ) -> Result<(), APIError> {
+        tracing::info!("Rejecting channel ({channel_id}) from {counterparty_node_id}: {reason}");
+        self.channel_manager
+            .force_close_broadcasting_latest_txn(&channel_id, &counterparty_node_id, reason.clone())
+            .map_err(|e| APIError::CannotRejectChannel(format!("{e:?}")))?;
+        self.add_channel_event(
+            channel_id,
+            ChannelEventKind::OpenReceived,
+            format!("rejected request from {counterparty_node_id}: {reason}"),
+        );
+        Ok(())
+    }
+
+    /// Open the JIT channel of the LSP client the intercepted HTLC is directed to, failing the
+    /// HTLC back if that's not possible. Returns false if the SCID doesn't belong to a client.
+    fn open_jit_channel(&self, scid: u64, intercept_id: InterceptId, amt_msat: u64) -> bool {
+        let fail = |reason: String| {
+            tracing::error!(
+                "ERROR: cannot open JIT channel {}: {reason}",
+                scid_to_string(scid)
+            );
+            self.lsps_handler.jit_channel_failed(scid);
+            self.channel_manager
+                .fail_intercepted_htlc(intercept_id)
+                .unwrap();
+            true
+        };
+        let jit_channel = match self
+            .lsps_handler
+            .jit_channel_htlc(scid, intercept_id, amt_msat)
+        {
+            None => return false,
+            Some(Ok(jit_channel)) => jit_channel,
+            Some(Err(e)) => return fail(e),
+        };
+
+        {
+            // RGB spends hold the lock until they complete, no channel can be opened meanwhile
+            let Ok(_rgb_spend_guard) = self.rgb_spend_lock.try_lock() else {
+                return fail(s!("an RGB spend is in progress"));
+            };
+            let mut rgb_send_lock = self.rgb_send_lock.lock().unwrap();
+            if *rgb_send_lock {
+                return fail(s!("another channel opening is in progress"));
+            }
+            *rgb_send_lock = true;
+            tracing::debug!("RGB send lock set to true");
+        }
+        let config = UserConfig {
+            channel_handshake_limits: ChannelHandshakeLimits {
+                their_to_self_delay: 2016,
+                ..Default::default()
+            },
+            channel_handshake_config: ChannelHandshakeConfig {
+                announce_for_forwarding: false,
+                our_htlc_minimum_msat: HTLC_MIN_MSAT,
+                negotiate_anchors_zero_fee_htlc_tx: true,
+                ..Default::default()
+            },
+            channel_config: ChannelConfig {
+                cltv_expiry_delta: self
+                    .channel_manager
+                    .get_current_config()
+                    .channel_config
+                    .cltv_expiry_delta,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        match self.channel_manager.create_channel(
+            jit_channel.client_pubkey,
+            jit_channel.capacity_sat,
+            0,
+            jit_channel.user_channel_id,
+            None,
+            Some(config),
+            None,
+        ) {
+            Ok(temporary_channel_id) => {
+                self.add_channel_event(
+                    temporary_channel_id,
+                    ChannelEventKind::OpenRequested,
+                    format!(
+                        "JIT channel {} of {} sat",
+                        scid_to_string(scid),
+                        jit_channel.capacity_sat
+                    ),
+                );
+                true
+            }
+            Err(e) => {
+                *self.rgb_send_lock.lock().unwrap() = false;
+                tracing::debug!("RGB send lock set to false (open channel failure: {e:?})");
+                fail(format!("{e:?}"))
+            }
+        }
+    }
+
+    /// Forward the HTLC held for a JIT channel that just got ready, net of the opening fee
+    fn forward_jit_channel_htlc(&self, user_channel_id: u128, channel_id: ChannelId) {
+        let Some((scid, intercept_id, client_pubkey, amt_msat)) = self
+            .lsps_handler
+            .jit_channel_ready(user_channel_id, channel_id)
+        else {
+            return;
+        };
+        let next_hop_scid = self
+            .channel_manager
+            .list_channels()
+            .into_iter()
+            .find(|c| c.channel_id == channel_id)
+            .and_then(|c| c.get_outbound_payment_scid());
+        let res = match next_hop_scid {
+            Some(next_hop_scid) => self
+                .channel_manager
+                .forward_intercepted_htlc(
+                    intercept_id,
+                    channelmanager::NextHopForward::ShortChannelId(next_hop_scid),
+                    client_pubkey,
+                    amt_msat,
+                    None,
+                )
+                .map_err(|e| format!("{e:?}")),
+            None => Err(s!("channel not found")),
+        };
+        match res {
+            Ok(()) => tracing::info!(
+                "Forwarded {amt_msat} msat through JIT channel {}",
+                scid_to_string(scid)
+            ),
+            Err(e) => {
+                tracing::error!(
+                    "ERROR: cannot forward through JIT channel {}: {e}",
+                    scid_to_string(scid)
+                );
+                self.lsps_handler.jit_channel_failed(scid);
+                let _ = self.channel_manager.fail_intercepted_htlc(intercept_id);
+            }
+        }
+    }
+
+    pub(crate) fn add_offer(&self, offer_id: OfferId, offer: OfferInfo) {
+        let mut offers = self.get_offers();
+        offers.offers.insert(offer_id, offer);
+        self.save_offers(offers);
+    }
+
+    pub(crate) fn offers(&self) -> LdkHashMap<OfferId, OfferInfo> {
+        self.get_offers().offers.clone()
+    }
+
+    fn save_offers(&self, offers: MutexGuard<OfferMap>) {
+        self.kv_store
+            .write("", "", OFFERS_FNAME, offers.encode())
+            .unwrap();
+    }
+
+    pub(crate) fn add_order(&self, order_id: String, order: OrderInfo) {
+        let mut orders = self.get_orders();
+        orders.orders.insert(order_id, order);
+        self.save_orders(orders);
+    }
+
+    pub(crate) fn orders(&self) -> LdkHashMap<String, OrderInfo> {
+        self.get_orders().orders.clone()
+    }
+
+    pub(crate) fn update_order_status(&self, order_id: &str, status: OrderStatus) {
+        let mut orders = self.get_orders();
+        if let Some(order) = orders.orders.get_mut(order_id) {
+            order.status = status;
+            self.save_orders(orders);
+        }
+    }
+
+    fn save_orders(&self, orders: MutexGuard<OrderMap>) {
+        self.kv_store
+            .write("", "", ORDERS_FNAME, orders.encode())
+            .unwrap();
+    }
+
+    pub(crate) fn add_escrow(&self, escrow_id: String, escrow: EscrowInfo) {
+        let mut escrows = self.get_escrows();
+        escrows.escrows.insert(escrow_id, escrow);
+        self.save_escrows(escrows);
+    }
+
+    pub(crate) fn escrows(&self) -> LdkHashMap<String, EscrowInfo> {
+        self.get_escrows().escrows.clone()
+    }
+
+    pub(crate) fn resolve_escrow(&self, escrow_id: &str, decision: EscrowDecision) {
+        let mut escrows = self.get_escrows();
+        if let Some(escrow) = escrows.escrows.get_mut(escrow_id) {
+            escrow.decision = Some(decision);
+            escrow.resolved_at = Some(get_current_timestamp());
+            self.save_escrows(escrows);
+        }
+    }
+
+    fn save_escrows(&self, escrows: MutexGuard<EscrowMap>) {
+        self.kv_store
+            .write("", "", ESCROWS_FNAME, escrows.encode())
+            .unwrap();
+    }
+
+    /// Track an invoice until it settles, dropping the ones expired long ago
+    pub(crate) fn add_pending_settlement(&self, key: String, pending: PendingSettlementInfo) {
+        let now = get_current_timestamp();
+        let mut settlements = self.get_settlements();
+        settlements
+            .pending
+            .retain(|_, p| p.expires_at.saturating_add(SETTLEMENT_GRACE_SEC) > now);
+        settlements.pending.insert(key, pending);
+        self.save_settlements(settlements);
+    }
+
+    pub(crate) fn pending_settlement(&self, key: &str) -> Option<PendingSettlementInfo> {
+        self.get_settlements().pending.get(key).cloned()
+    }
+
+    /// Add a settled invoice to the rollup of the current period
+    pub(crate) fn add_invoice_settlement(
+        &self,
+        payment_hash: &PaymentHash,
+        amt_msat: u64,
+        asset_id: Option<String>,
+        asset_amount: Option<u64>,
+    ) {
+        self.add_settlement(&hex_str(&payment_hash.0), asset_id, |total| {
+            total.settled_invoices += 1;
+            total.amt_msat += amt_msat;
+            total.asset_amount += asset_amount.unwrap_or(0);
+        });
+    }
+
+    /// Add a received asset transfer to the rollup of the current period
+    pub(crate) fn add_transfer_settlement(
+        &self,
+        batch_transfer_idx: i32,
+        asset_id: String,
+        asset_amount: u64,
+    ) {
+        self.add_settlement(&batch_transfer_idx.to_string(), Some(asset_id), |total| {
+            total.received_transfers += 1;
+            total.asset_amount += asset_amount;
+        });
+    }
+
+    fn add_settlement(
+        &self,
+        key: &str,
+        asset_id: Option<String>,
+        update: impl FnOnce(&mut SettlementTotalInfo),
+    ) {
+        let mut settlements = self.get_settlements();
+        let label = settlements.pending.remove(key).and_then(|p| p.label);
+        let period = settlement_period(get_current_timestamp());
+        let totals = &mut settlements.periods.entry(period).or_default().totals;
+        let idx = match totals
+            .iter()
+            .position(|t| t.label == label && t.asset_id == asset_id)
+        {
+            Some(idx) => idx,
+            None => {
+                totals.push(SettlementTotalInfo {
+                    label,
+                    asset_id,
+                    settled_invoices: 0,
+                    amt_msat: 0,
+                    received_transfers: 0,
+                    asset_amount: 0,
+                });
+                totals.len() - 1
+            }
+        };
+        update(&mut totals[idx]);
+        self.save_settlements(settlements);
+    }
+
+    pub(crate) fn settlements(&self, period: &str) -> Vec<SettlementTotalInfo> {
+        self.get_settlements()
+            .periods
+            .get(period)
+            .map(|p| p.totals.clone())
+            .unwrap_or_default()
+    }
+
+    fn save_settlements(&self, settlements: MutexGuard<SettlementMap>) {
+        self.kv_store
+            .write("", "", SETTLEMENTS_FNAME, settlements.encode())
+            .unwrap();
+    }
+
+    pub(crate) fn add_issued_address(&self, address: String) {
+        let mut issued_addresses = self.get_issued_addresses();
+        let index = issued_addresses.addresses.len() as u32;
+        issued_addresses.addresses.insert(
+            address,
+            IssuedAddressInfo {
+                index,
+                created_at: get_current_timestamp(),
+                used: false,
+            },
+        );
+        self.save_issued_addresses(issued_addresses);
+    }
+
+    /// Mark as used the issued addresses that received funds in a wallet transaction
+    pub(crate) async fn update_issued_addresses_usage(&self) -> Result<(), APIError> {
+        let unused: Vec<(String, IssuedAddressInfo)> = self
+            .get_issued_addresses()
+            .addresses
+            .iter()
+            .filter(|(_, info)| !info.used)
+            .map(|(address, info)| (address.clone(), info.clone()))
+            .collect();
+        let Some(oldest_created_at) = unused.iter().map(|(_, info)| info.created_at).min() else {
+            return Ok(());
+        };
+        let unused_scripts: Vec<(String, Vec<u8>)> = unused
+            .into_iter()
+            .filter_map(|(address, _)| {
+                let script = Address::from_str(&address)
+                    .ok()?
+                    .assume_checked()
+                    .script_pubkey()
+                    .to_bytes();
+                Some((address, script))
+            })
+            .collect();
+
+        let mut used = vec![];
+        for tx in self.rgb_list_transactions(false)? {
+            if tx.received == 0 {
+                continue;
+            }
+            // block timestamps can be up to 2 hours in the future
+            if let Some(ct) = &tx.confirmation_time {
+                if ct.timestamp + 7200 < oldest_created_at {
+                    continue;
+                }
+            }
+            let Ok(txid) = Txid::from_str(&tx.txid) else {
+                continue;
+            };
+            let height = tx.confirmation_time.map(|ct| ct.height);
+            let Some(raw_tx) = self
+                .bitcoind_client
+                .get_raw_transaction(&txid, height)
+                .await
+            else {
+                tracing::warn!("Cannot retrieve TX {txid} to check address usage");
+                continue;
+            };
+            for output in raw_tx.output {
+                if let Some((address, _)) = unused_scripts
+                    .iter()
+                    .find(|(_, script)| script == output.script_pubkey.as_bytes())
+                {
+                    used.push(address.clone());
+                }
+            }
+        }
+
+        if !used.is_empty() {
+            let mut issued_addresses = self.get_issued_addresses();
+            for address in used {
+                if let Some(info) = issued_addresses.addresses.get_mut(&address) {
+                    info.used = true;
+                }
+            }
+            self.save_issued_addresses(issued_addresses);
+        }
+        Ok(())
+    }
+
+    /// Issue a new wallet address, refusing to exceed the gap limit of unused addresses
+    pub(crate) async fn issue_address(&self, gap_limit: u32) -> Result<String, APIError> {
+        self.update_issued_addresses_usage().await?;
+        let gap = self.get_issued_addresses().gap();
+        if gap >= gap_limit {
+            return Err(APIError::AddressGapLimitReached(gap_limit));
+        }
+
+        let address = self.rgb_get_address()?;
+        self.add_issued_address(address.clone());
+        if gap + 1 > gap_limit / 2 {
+            tracing::warn!(
+                "{} issued addresses are unused (gap limit is {gap_limit})",
+                gap + 1
+            );
+        }
+        Ok(address)
+    }
+
+    fn save_issued_addresses(&self, issued_addresses: MutexGuard<IssuedAddressMap>) {
+        self.kv_store
+            .write("", "", ISSUED_ADDRESSES_FNAME, issued_addresses.encode())
+            .unwrap();
+    }
+
+    pub(crate) fn add_anchor_reserve_utxo(&self, outpoint: String, amount_sat: u64) {
+        let mut anchor_reserve = self.get_anchor_reserve();
+        anchor_reserve.utxos.insert(
+            outpoint,
+            AnchorReserveUtxoInfo {
+                amount_sat,
+                created_at: get_current_timestamp(),
+            },
+        );
+        self.save_anchor_reserve(anchor_reserve);
+    }
+
+    pub(crate) fn remove_anchor_reserve_utxo(&self, outpoint: &str) {
+        let mut anchor_reserve = self.get_anchor_reserve();
+        if anchor_reserve.utxos.remove(outpoint).is_some() {
+            self.save_anchor_reserve(anchor_reserve);
+        }
+    }
+
+    /// Record an event in the anchor reserve log, skipping repetitions of the last event
+    pub(crate) fn add_anchor_reserve_event(&self, kind: AnchorReserveEventKind, details: String) {
+        let mut anchor_reserve = self.get_anchor_reserve();
+        if anchor_reserve
+            .events
+            .last()
+            .is_some_and(|e| e.kind == kind && e.details == details)
+        {
+            return;
+        }
+        match kind {
+            AnchorReserveEventKind::Depleted => anchor_reserve.depleted = true,
+            AnchorReserveEventKind::Replenished => anchor_reserve.depleted = false,
+            _ => {}
+        }
+        anchor_reserve.events.push(AnchorReserveEventInfo {
+            timestamp: get_current_timestamp(),
+            kind,
+            details,
+        });
+        if anchor_reserve.events.len() > MAX_ANCHOR_RESERVE_EVENTS {
+            anchor_reserve.events.remove(0);
+        }
+        self.save_anchor_reserve(anchor_reserve);
+    }
+
+    fn save_anchor_reserve(&self, anchor_reserve: MutexGuard<AnchorReserveMap>) {
+        self.kv_store
+            .write("", "", ANCHOR_RESERVE_FNAME, anchor_reserve.encode())
+            .unwrap();
+    }
+
+    /// Add a chain subscription, returning false if the maximum number of subscriptions has
+    /// been reached
+    pub(crate) fn add_chain_subscription(
+        &self,
+        subscription_id: String,
+        info: ChainSubscriptionInfo,
+    ) -> bool {
+        let mut chain_subscriptions = self.get_chain_subscriptions();
+        if chain_subscriptions.subscriptions.len() >= MAX_CHAIN_SUBSCRIPTIONS {
+            return false;
+        }
+        chain_subscriptions
+            .subscriptions
+            .insert(subscription_id, info);
+        self.save_chain_subscriptions(chain_subscriptions);
+        true
+    }
+
+    pub(crate) fn chain_subscription(
+        &self,
+        subscription_id: &str,
+    ) -> Option<ChainSubscriptionInfo> {
+        self.get_chain_subscriptions()
+            .subscriptions
+            .get(subscription_id)
+            .cloned()
+    }
+
+    /// Height of the next block to be scanned for the chain subscriptions, skipping the blocks
+    /// mined before any of them was created
+    fn chain_scan_start_height(&self) -> Option<u32> {
+        let chain_subscriptions = self.get_chain_subscriptions();
+        let min_start_height = chain_subscriptions
+            .subscriptions
+            .values()
+            .map(|s| s.start_height)
+            .min()?;
+        Some(min_start_height.max(chain_subscriptions.last_scanned_height + 1))
+    }
+
+    /// Record the events of the given block for the chain subscriptions
+    fn scan_chain_block(&self, block: &Block, height: u32) {
+        let now = get_current_timestamp();
+        let mut chain_subscriptions = self.get_chain_subscriptions();
+        for subscription in chain_subscriptions.subscriptions.values_mut() {
+            if height < subscription.start_height {
+                continue;
+            }
+            for tx in &block.txdata {
+                let txid = tx.compute_txid();
+                let mut events = vec![];
+                for input in &tx.input {
+                    if let Some(pos) = subscription
+                        .watched_outpoints
+                        .iter()
+                        .position(|o| *o == input.previous_output)
+                    {
+                        subscription.watched_outpoints.remove(pos);
+                        events.push((ChainEventKind::Spent, Some(input.previous_output), None));
+                    }
+                }
+                if subscription.txid == Some(txid) {
+                    subscription
+                        .watched_outpoints
+                        .extend((0..tx.output.len() as u32).map(|vout| BtcOutPoint { txid, vout }));
+                    events.push((ChainEventKind::Confirmed, None, None));
+                }
+                if let Some(script) = &subscription.script {
+                    for (vout, output) in tx.output.iter().enumerate() {
+                        if output.script_pubkey == *script {
+                            let outpoint = BtcOutPoint {
+                                txid,
+                                vout: vout as u32,
+                            };
+                            subscription.watched_outpoints.push(outpoint);
+                            events.push((
+                                ChainEventKind::Received,
+                                Some(outpoint),
+                                Some(output.value.to_sat()),
+                            ));
+                        }
+                    }
+                }
+                for (kind, outpoint, amount_sat) in events {
+                    subscription.events.push(ChainEventInfo {
+                        timestamp: now,
+                        kind,
+                        txid,
+                        outpoint,
+                        amount_sat,
+                        block_height: height,
+                    });
+                    if subscription.events.len() > MAX_CHAIN_EVENTS {
+                        subscription.events.remove(0);
+                    }
+                }
+            }
+        }
+        chain_subscriptions.last_scanned_height = height;
+        self.save_chain_subscriptions(chain_subscriptions);
+    }
+
+    fn save_chain_subscriptions(&self, chain_subscriptions: MutexGuard<ChainSubscriptionMap>) {
+        self.kv_store
+            .write(
+                "",
+                "",
+                CHAIN_SUBSCRIPTIONS_FNAME,
+                chain_subscriptions.encode(),
+            )
+            .unwrap();
+    }
+
+    /// Add an intercept scope, returning false if the maximum number of scopes has been reached
+    pub(crate) fn add_intercept_scope(&self, scope_id: String, info: InterceptScopeInfo) -> bool {
+        let mut intercept_scopes = self.get_intercept_scopes();
+        if intercept_scopes.scopes.len() >= MAX_INTERCEPT_SCOPES {
+            return false;
+        }
+        intercept_scopes.scopes.insert(scope_id, info);
+        self.save_intercept_scopes(intercept_scopes);
+        true
+    }
+
+    pub(crate) fn intercept_scope(&self, scope_id: &str) -> Option<InterceptScopeInfo> {
+        self.get_intercept_scopes().scopes.get(scope_id).cloned()
+    }
+
+    fn find_intercept_scope(
+        &self,
+        matches: impl Fn(&InterceptScopeInfo) -> bool,
+    ) -> Option<String> {
+        self.get_intercept_scopes()
+            .scopes
+            .iter()
+            .find(|(_, s)| matches(s))
+            .map(|(id, _)| id.clone())
+    }
+
+    /// Hold the HTLC until the API client resolves it, notifying the hook
+    fn hold_intercepted_htlc(&self, htlc_id: String, info: InterceptedHtlcInfo) {
+        tracing::info!(
+            "Holding HTLC {htlc_id} with payment hash {} of intercept scope {}",
+            info.payment_hash,
+            info.scope_id
+        );
+        self.hook_runner.trigger(HookEvent::HtlcIntercepted {
+            scope_id: info.scope_id.clone(),
+            htlc_id: htlc_id.clone(),
+            payment_hash: hex_str(&info.payment_hash.0),
+            amt_msat: info.amt_msat,
+        });
+        self.get_intercepted_htlcs().insert(htlc_id, info);
+    }
+
+    /// Forward the payment to the async recipient right away when it's online, otherwise hold it
+    /// until the peer reconnects, notifying the hook so that the recipient can be woken up
+    fn hold_async_payment(
+        &self,
+        htlc_id: String,
+        info: InterceptedHtlcInfo,
+        scope: &InterceptScopeInfo,
+        ldk_data_dir: &Path,
+    ) {
+        let HeldHtlc::Forward {
+            intercept_id,
+            outbound_amt_msat,
+            outbound_rgb_payment,
+        } = info.held.clone()
+        else {
+            return;
+        };
+        let peer_pubkey = scope
+            .peer_pubkey
+            .expect("async recipient scopes have a peer");
+        if self.peer_manager.peer_by_node_id(&peer_pubkey).is_some()
+            && self
+                .forward_held_htlc(
+                    &info.scope_id,
+                    intercept_id,
+                    outbound_amt_msat,
+                    outbound_rgb_payment,
+                    ldk_data_dir,
+                )
+                .is_ok()
+        {
+            tracing::info!(
+                "Forwarded payment {} to online async recipient {peer_pubkey}",
+                info.payment_hash
+            );
+            return;
+        }
+        let expires_at = info.received_at + scope.hold_timeout_sec.unwrap_or_default();
+        tracing::info!(
+            "Holding payment {} for offline async recipient {peer_pubkey} until {expires_at}",
+            info.payment_hash
+        );
+        self.hook_runner.trigger(HookEvent::AsyncPaymentHeld {
+            scope_id: info.scope_id.clone(),
+            htlc_id: htlc_id.clone(),
+            peer_pubkey: peer_pubkey.to_string(),
+            payment_hash: hex_str(&info.payment_hash.0),
+            amt_msat: info.amt_msat,
+            expires_at,
+        });
+        self.get_intercepted_htlcs().insert(htlc_id, info);
+    }
+
+    /// Forward the held HTLC to the scope peer, through a usable channel of the same asset
+    pub(crate) fn forward_held_htlc(
+        &self,
+        scope_id: &str,
+        intercept_id: InterceptId,
+        outbound_amt_msat: u64,
+        outbound_rgb_payment: Option<(ContractId, u64)>,
+        ldk_data_dir: &Path,
+    ) -> Result<(), APIError> {
+        let peer_pubkey = self
+            .intercept_scope(scope_id)
+            .and_then(|s| s.peer_pubkey)
+            .ok_or(APIError::UnknownInterceptScope)?;
+        let contract_id = outbound_rgb_payment.map(|(c, _)| c);
+        let Some(channel) = self
+            .channel_manager
+            .list_usable_channels()
+            .into_iter()
+            .find(|c| {
+                c.counterparty.node_id == peer_pubkey
+                    && get_rgb_channel_info_optional(&c.channel_id, ldk_data_dir, true)
+                        .map(|(info, _)| info.contract_id)
+                        == contract_id
+            })
+        else {
+            return Err(APIError::CannotResolveHtlc(s!(
+                "no usable channel with the next hop"
+            )));
+        };
+        self.channel_manager
+            .forward_intercepted_htlc(
+                intercept_id,
+                channelmanager::NextHopForward::ShortChannelId(channel.short_channel_id.unwrap()),
+                peer_pubkey,
+                outbound_amt_msat,
+                outbound_rgb_payment,
+            )
+            .map_err(|e| APIError::CannotResolveHtlc(format!("{e:?}")))
+    }
+
+    /// Release the payments held for the async recipients that came back online, failing back
+    /// the ones held for longer than the scope hold timeout
+    fn release_async_payments(&self, ldk_data_dir: &Path) {
+        let now = get_current_timestamp();
+        let held: Vec<(String, InterceptedHtlcInfo, InterceptScopeInfo)> = self
+            .get_intercepted_htlcs()
+            .iter()
+            .filter_map(|(id, h)| {
+                self.intercept_scope(&h.scope_id)
+                    .filter(|s| s.kind == InterceptScopeKind::AsyncRecipient)
+                    .map(|s| (id.clone(), h.clone(), s))
+            })
+            .collect();
+        for (htlc_id, htlc, scope) in held {
+            let HeldHtlc::Forward {
+                intercept_id,
+                outbound_amt_msat,
+                outbound_rgb_payment,
+            } = htlc.held
+            else {
+                continue;
+            };
+            let peer_pubkey = scope
+                .peer_pubkey
+                .expect("async recipient scopes have a peer");
+            if now >= htlc.received_at + scope.hold_timeout_sec.unwrap_or_default() {
+                if self.take_intercepted_htlc(&htlc_id).is_none() {
+                    continue;
+                }
+                if let Err(e) = self.channel_manager.fail_intercepted_htlc(intercept_id) {
+                    tracing::error!("Failed to fail back expired async payment {htlc_id}: {e:?}");
+                }
+                tracing::info!(
+                    "Async recipient {peer_pubkey} didn't come back online for payment {}",
+                    htlc.payment_hash
+                );
+                self.hook_runner.trigger(HookEvent::AsyncPaymentExpired {
+                    scope_id: htlc.scope_id,
+                    htlc_id,
+                    payment_hash: hex_str(&htlc.payment_hash.0),
+                });
+                continue;
+            }
+            // channels become usable once reestablished with the reconnected peer
+            if self.peer_manager.peer_by_node_id(&peer_pubkey).is_none() {
+                continue;
+            }
+            if self
+                .forward_held_htlc(
+                    &htlc.scope_id,
+                    intercept_id,
+                    outbound_amt_msat,
+                    outbound_rgb_payment,
+                    ldk_data_dir,
+                )
+                .is_ok()
+            {
+                self.take_intercepted_htlc(&htlc_id);
+                tracing::info!(
+                    "Released payment {} to async recipient {peer_pubkey}",
+                    htlc.payment_hash
+                );
+            }
+        }
+    }
+
+    pub(crate) fn intercepted_htlcs(&self, scope_id: &str) -> Vec<(String, InterceptedHtlcInfo)> {
+        self.get_intercepted_htlcs()
+            .iter()
+            .filter(|(_, h)| h.scope_id == scope_id)
+            .map(|(id, h)| (id.clone(), h.clone()))
+            .collect()
+    }
+
+    pub(crate) fn take_intercepted_htlc(&self, htlc_id: &str) -> Option<InterceptedHtlcInfo> {
+        self.get_intercepted_htlcs().remove(htlc_id)
+    }
+
+    fn save_intercept_scopes(&self, intercept_scopes: MutexGuard<InterceptScopeMap>) {
+        self.kv_store
+            .write("", "", INTERCEPT_SCOPES_FNAME, intercept_scopes.encode())
+            .unwrap();
+    }
+
+    /// Require the TOTP code, when enrolled and needed by the operation
+    pub(crate) fn check_totp(
+        &self,
+        totp_code: Option<&str>,
+        operation: TotpOperation,
+    ) -> Result<(), APIError> {
+        if let Some(totp) = self.totp_verifier.check(totp_code, operation)? {
+            self.save_totp(totp);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn enroll_totp(
+        &self,
+        payment_threshold_msat: u64,
+    ) -> Result<TotpEnrollment, APIError> {
+        let account = self.channel_manager.get_our_node_id().to_string();
+        let (enrollment, totp) = self
+            .totp_verifier
+            .enroll(payment_threshold_msat, &account)?;
+        self.save_totp(totp);
+        Ok(enrollment)
+    }
+
+    pub(crate) fn confirm_totp(&self, code: &str) -> Result<(), APIError> {
+        let totp = self.totp_verifier.confirm(code)?;
+        self.save_totp(totp);
+        Ok(())
+    }
+
+    pub(crate) fn disable_totp(&self, code: &str) -> Result<(), APIError> {
+        let totp = self.totp_verifier.disable(code)?;
+        self.save_totp(totp);
+        Ok(())
+    }
+
+    fn save_totp(&self, totp: Vec<u8>) {
+        self.kv_store.write("", "", TOTP_FNAME, totp).unwrap();
+    }
+
+    pub(crate) fn set_gossip_limit(&self, peer: PublicKey, limit: Option<u64>) {
+        let limits = self.gossip_handler.set_peer_limit(peer, limit);
+        self.kv_store
+            .write("", "", GOSSIP_LIMITS_FNAME, limits)
+            .unwrap();
+    }
+
+    pub(crate) fn add_probe(
+        &self,
+        payment_id: PaymentId,
+        sender: tokio::sync::oneshot::Sender<ProbeOutcome>,
+    ) {
+        self.get_probes().insert(payment_id, Some(sender));
+    }
+
+    /// Report the outcome of a probe, returning false if the payment is not a probe
+    pub(crate) fn report_probe_outcome(
+        &self,
+        payment_id: PaymentId,
+        outcome: ProbeOutcome,
+    ) -> bool {
+        let mut probes = self.get_probes();
+        let Some(sender) = probes.get_mut(&payment_id) else {
+            return false;
+        };
+        if let Some(sender) = sender.take() {
+            let _ = sender.send(outcome);
+        }
+        true
+    }
+
+    pub(crate) fn remove_probe(&self, payment_id: PaymentId) {
+        self.get_probes().remove(&payment_id);
+    }
+
+    pub(crate) fn record_probe_result(&self, node: PublicKey, reachable: bool) {
+        let mut probe_stats = self.get_probe_stats();
+        let stats = probe_stats.entry(node).or_default();
+        stats.attempts += 1;
+        if reachable {
+            stats.successes += 1;
+        }
+    }
+
+    /// List the pending HTLCs of all channels, sorted by the blocks left to their CLTV deadline
+    pub(crate) fn list_htlcs(&self, ldk_data_dir: &Path) -> Vec<Htlc> {
+        let current_height = self.channel_manager.current_best_block().height;
+        let inbound_payments = self.inbound_payments();
+        let outbound_payments = self.outbound_payments();
+        let hodl_invoices = self.hodl_invoices();
+        let is_swap = |payment_hash: &PaymentHash| {
+            self.is_maker_swap(payment_hash) || self.is_taker_swap(payment_hash)
+        };
+
+        let mut htlcs = vec![];
+        for chan_info in self.channel_manager.list_channels() {
+            let inbound = chan_info.pending_inbound_htlcs.iter().map(|h| {
+                let kind = if is_swap(&h.payment_hash) {
+                    HtlcKind::Swap
+                } else if inbound_payments.contains_key(&h.payment_hash)
+                    || hodl_invoices.contains_key(&h.payment_hash)
+                {
+                    HtlcKind::Invoice
+                } else {
+                    HtlcKind::Forward
+                };
+                (
+                    HtlcDirection::Inbound,
+                    kind,
+                    h.payment_hash,
+                    h.amount_msat,
+                    h.cltv_expiry,
+                )
+            });
+            let outbound = chan_info.pending_outbound_htlcs.iter().map(|h| {
+                let kind = if is_swap(&h.payment_hash) {
+                    HtlcKind::Swap
+                } else if outbound_payments.contains_key(&PaymentId(h.payment_hash.0)) {
+                    HtlcKind::Payment
+                } else {
+                    HtlcKind::Forward
+                };
+                (
+                    HtlcDirection::Outbound,
+                    kind,
+                    h.payment_hash,
+                    h.amount_msat,
+                    h.cltv_expiry,
+                )
+            });
+            for (direction, kind, payment_hash, amt_msat, cltv_expiry) in inbound.chain(outbound) {
+                let rgb_payment_info_path = get_rgb_payment_info_path(
+                    &payment_hash,
+                    ldk_data_dir,
+                    direction == HtlcDirection::Inbound,
+                );
+                let (asset_id, asset_amount) = if rgb_payment_info_path.exists() {
+                    let info = parse_rgb_payment_info(&rgb_payment_info_path);
+                    (Some(info.contract_id.to_string()), Some(info.amount))
+                } else {
+                    (None, None)
+                };
+                let blocks_to_deadline = cltv_expiry.saturating_sub(current_height);
+                htlcs.push(Htlc {
+                    channel_id: hex_str(&chan_info.channel_id.0),
+                    peer_pubkey: chan_info.counterparty.node_id.to_string(),
+                    direction,
+                    kind,
+                    payment_hash: hex_str(&payment_hash.0),
+                    amt_msat,
+                    asset_id,
+                    asset_amount,
+                    cltv_expiry,
+                    blocks_to_deadline,
+                    at_risk: blocks_to_deadline <= HTLC_RISK_WINDOW_BLOCKS,
+                });
+            }
+        }
+        htlcs.sort_by_key(|h| h.cltv_expiry);
+        htlcs
+    }
+
+    /// Write the current monitors of the open channels, along with their RGB data and peer
+    /// addresses, to the given directory. Returns the number of backed up channels.
+    pub(crate) fn write_channel_backup_files(
+        &self,
+        ldk_data_dir: &Path,
+        backup_dir: &Path,
+    ) -> Result<usize, APIError> {
+        let monitors_dir = backup_dir.join(CHANNEL_MONITORS_DIR);
+        fs::create_dir_all(&monitors_dir)?;
+        let peer_data = disk::read_channel_peer_data(&ldk_data_dir.join(CHANNEL_PEER_DATA))?;
+        let ldk_files = fs::read_dir(ldk_data_dir)?
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_file())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+
+        let mut num_channels = 0;
+        for chan_info in self.channel_manager.list_channels() {
+            let Some(funding_txo) = chan_info.funding_txo else {
+                continue;
+            };
+            let Ok(monitor) = self.chain_monitor.get_monitor(chan_info.channel_id) else {
+                continue;
+            };
+            fs::write(
+                monitors_dir.join(format!("{}_{}", funding_txo.txid, funding_txo.index)),
+                monitor.encode(),
+            )?;
+
+            let channel_id = hex_str(&chan_info.channel_id.0);
+            let funding_txid = funding_txo.txid.to_string();
+            for fname in ldk_files
+                .iter()
+                .filter(|f| f.contains(&channel_id) || f.contains(&funding_txid))
+            {
+                fs::copy(ldk_data_dir.join(fname), backup_dir.join(fname))?;
+            }
+
+            let node_id = chan_info.counterparty.node_id;
+            if let Some(address) = peer_data.get(&node_id) {
+                disk::persist_channel_peer(&backup_dir.join(CHANNEL_PEER_DATA), &node_id, address)?;
+            }
+            num_channels += 1;
+        }
+        Ok(num_channels)
+    }
+
+    /// Write the emergency kit again if the state of any open channel changed since the latest one
+    /// has been written. The kit is encrypted in the backup format and includes the channel
+    /// backup files, the latest signed commitment transactions and the keys needed to sweep the
+    /// funds, so that the channels can be closed by an external tool if the node gets lost.
+    pub(crate) fn refresh_emergency_kit(&self, static_state: &StaticState) -> Result<(), APIError> {
+        let mut emergency_kit = self.get_emergency_kit();
+        let update_ids = self
+            .channel_manager
+            .list_channels()
+            .iter()
+            .filter_map(|c| {
+                let monitor = self.chain_monitor.get_monitor(c.channel_id).ok()?;
+                Some((c.channel_id, monitor.get_latest_update_id()))
+            })
+            .collect::<Vec<_>>();
+        let kit_path = static_state.ldk_data_dir.join(EMERGENCY_KIT_FNAME);
+        if emergency_kit.update_ids.as_ref() == Some(&update_ids) && kit_path.exists() {
+            return Ok(());
+        }
+
+        let kit_dir = tempfile::tempdir_in(&static_state.storage_dir_path)?;
+        let num_channels =
+            self.write_channel_backup_files(&static_state.ldk_data_dir, kit_dir.path())?;
+        let contents = self.emergency_kit_contents(static_state)?;
+        fs::write(
+            kit_dir.path().join(EMERGENCY_KIT_CONTENTS_FNAME),
+            serde_json::to_string_pretty(&contents).unwrap(),
+        )?;
+        fs::write(
+            kit_dir.path().join(EMERGENCY_KIT_INSTRUCTIONS_FNAME),
+            EMERGENCY_KIT_INSTRUCTIONS,
+        )?;
+
+        // replace the previous kit only once the new one is complete
+        let tmp_kit_path = kit_path.with_extension("tmp");
+        encrypt_dir(kit_dir.path(), &tmp_kit_path, &emergency_kit.key)?;
+        fs::rename(&tmp_kit_path, &kit_path)?;
+
+        emergency_kit.update_ids = Some(update_ids);
+        emergency_kit.updated_at = contents.created_at;
+        emergency_kit.num_channels = num_channels;
+        tracing::info!("Refreshed the emergency kit with {num_channels} channels");
+        Ok(())
+    }
+
+    /// Write to the given file an encrypted snapshot of the channel backup files, the emergency
+    /// kit and the RGB wallet data, holding only the files changed since the previous snapshot
+    /// unless it's a full one. Returns the manifest of the snapshot and the number of backed up
+    /// channels, or None if nothing changed.
+    pub(crate) fn write_auto_backup_snapshot(
+        &self,
+        static_state: &StaticState,
+        snapshot: &SnapshotName,
+        snapshot_path: &Path,
+        previous: Option<&AutoBackupManifest>,
+    ) -> Result<Option<(AutoBackupManifest, usize)>, APIError> {
+        let data_dir = tempfile::tempdir_in(&static_state.storage_dir_path)?;
+        let num_channels = self.write_channel_backup_files(
+            &static_state.ldk_data_dir,
+            &data_dir.path().join(AUTO_BACKUP_CHANNELS_DIR),
+        )?;
+        let kit_path = static_state.ldk_data_dir.join(EMERGENCY_KIT_FNAME);
+        if kit_path.exists() {
+            fs::copy(&kit_path, data_dir.path().join(EMERGENCY_KIT_FNAME))?;
+        }
+        {
+            // the RGB wallet is kept locked, so that its database is copied in a consistent state
+            let rgb_wallet = self.rgb_wallet_wrapper.get_rgb_wallet();
+            copy_dir(
+                &rgb_wallet.get_wallet_dir(),
+                &data_dir.path().join(AUTO_BACKUP_RGB_DIR),
+            )?;
+        }
+
+        let snapshot_dir = tempfile::tempdir_in(&static_state.storage_dir_path)?;
+        let Some(manifest) =
+            prepare_snapshot(data_dir.path(), snapshot_dir.path(), snapshot, previous)?
+        else {
+            return Ok(None);
+        };
+        encrypt_dir(
+            snapshot_dir.path(),
+            snapshot_path,
+            &self.get_emergency_kit().key,
+        )?;
+        Ok(Some((manifest, num_channels)))
+    }
+
+    fn emergency_kit_contents(
+        &self,
+        static_state: &StaticState,
+    ) -> Result<EmergencyKitContents, APIError> {
+        let read_wallet_file = |fname: &str| -> Result<String, APIError> {
+            Ok(
+                fs::read_to_string(static_state.storage_dir_path.join(fname))?
+                    .trim()
+                    .to_string(),
+            )
+        };
+
+        // monitors are read again from disk, so that the signed commitment transactions come
+        // from a copy that is not tracked by the chain monitor
+        let persister = MonitorUpdatingPersister::new(
+            Arc::clone(&self.kv_store),
+            Arc::clone(&static_state.logger),
+            1000,
+            Arc::clone(&self.keys_manager),
+            Arc::clone(&self.keys_manager),
+            Arc::clone(&self.bitcoind_client),
+            Arc::clone(&self.bitcoind_client),
+        );
+        let mut channels = vec![];
+        for chan_info in self.channel_manager.list_channels() {
+            let Some(funding_txo) = chan_info.funding_txo else {
+                continue;
+            };
+            let monitor_key = format!("{}_{}", funding_txo.txid, funding_txo.index);
+            let (_, monitor) = persister
+                .read_channel_monitor_with_updates(&monitor_key)
+                .map_err(|e| {
+                    APIError::Unexpected(format!("Failed to read monitor {monitor_key}: {e}"))
+                })?;
+            let collector = Arc::new(TxCollector::default());
+            monitor.broadcast_latest_holder_commitment_txn(
+                &collector,
+                &self.bitcoind_client,
+                &static_state.logger,
+            );
+            let commitment_txs = collector
+                .txs
+                .lock()
+                .unwrap()
+                .iter()
+                .map(encode::serialize_hex)
+                .collect();
+
+            channels.push(EmergencyKitChannel {
+                channel_id: hex_str(&chan_info.channel_id.0),
+                peer_pubkey: chan_info.counterparty.node_id.to_string(),
+                funding_txid: funding_txo.txid.to_string(),
+                funding_vout: funding_txo.index as u32,
+                capacity_sat: chan_info.channel_value_satoshis,
+                asset_id: get_rgb_channel_info_optional(
+                    &chan_info.channel_id,
+                    &static_state.ldk_data_dir,
+                    false,
+                )
+                .map(|(rgb_info, _)| rgb_info.contract_id.to_string()),
+                latest_update_id: monitor.get_latest_update_id(),
+                commitment_txs,
+            });
+        }
+
+        Ok(EmergencyKitContents {
+            created_at: get_current_timestamp(),
+            network: static_state.network.to_string(),
+            node_pubkey: self.channel_manager.get_our_node_id().to_string(),
+            ldk_seed_derivation_path: self.ldk_seed_derivation_path.clone(),
+            master_fingerprint: read_wallet_file(WALLET_MASTER_FINGERPRINT_FNAME)?,
+            account_xpub_vanilla: read_wallet_file(WALLET_ACCOUNT_XPUB_VANILLA_FNAME)?,
+            account_xpub_colored: read_wallet_file(WALLET_ACCOUNT_XPUB_COLORED_FNAME)?,
+            channels,
+        })
+    }
+
+    /// Build the funding PSBT of a channel funded by a multisig treasury, to be signed externally
+    /// and then returned with the /fundchannelcomplete API
+    pub(crate) fn create_multisig_funding_psbt(
+        &self,
+        temporary_channel_id: ChannelId,
+        output_script: ScriptBuf,
+    ) {
+        let mut multisig_fundings = self.get_multisig_fundings();
+        let Some(info) = multisig_fundings.get_mut(&temporary_channel_id) else {
+            return;
+        };
+
+        let mut output = vec![TxOut {
+            value: Amount::from_sat(info.capacity_sat),
+            script_pubkey: output_script,
+        }];
+        let change_sat = info
+            .inputs_amount_sat()
+            .saturating_sub(info.capacity_sat + info.fee_sat());
+        if change_sat * 1000 >= DUST_LIMIT_MSAT {
+            output.push(TxOut {
+                value: Amount::from_sat(change_sat),
+                script_pubkey: info.change_script.clone(),
+            });
+        }
+        let unsigned_tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: info
+                .inputs
+                .iter()
+                .map(|i| TxIn {
+                    previous_output: i.outpoint,
+                    ..Default::default()
+                })
+                .collect(),
+            output,
+        };
+        let txid = unsigned_tx.compute_txid();
+        let mut psbt = Psbt::from_unsigned_tx(unsigned_tx).expect("valid unsigned TX");
+        for (psbt_input, input) in psbt.inputs.iter_mut().zip(info.inputs.iter()) {
+            psbt_input.witness_utxo = Some(input.txout.clone());
+            psbt_input.witness_script = Some(input.witness_script.clone());
+        }
+        info.unsigned_psbt = Some(psbt);
+        drop(multisig_fundings);
+
+        tracing::info!("Created multisig funding PSBT {txid} for channel {temporary_channel_id}");
+        self.add_channel_event(
+            temporary_channel_id,
+            ChannelEventKind::FundingPsbtCreated,
+            format!("funding PSBT {txid} waiting for the treasury signatures"),
+        );
+    }
+
+    /// Drop a channel open funded by a multisig treasury, closing the unfunded channel
+    pub(crate) fn abort_multisig_funding(&self, temporary_channel_id: ChannelId, reason: &str) {
+        let Some(info) = self.get_multisig_fundings().remove(&temporary_channel_id) else {
+            return;
+        };
+        tracing::info!("Aborting multisig funding of channel {temporary_channel_id}: {reason}");
+        let _ = self.channel_manager.force_close_broadcasting_latest_txn(
+            &temporary_channel_id,
+            &info.counterparty_node_id,
+            format!("Funding aborted: {reason}"),
+        );
+        self.add_channel_event(
+            temporary_channel_id,
+            ChannelEventKind::FundingFailed,
+            format!("multisig funding aborted: {reason}"),
+        );
+    }
+
+    /// Drop a channel open funded by an external PSBT, closing the unfunded channel
+    pub(crate) fn abort_external_funding(&self, temporary_channel_id: ChannelId, reason: &str) {
+        let Some(info) = self.get_external_fundings().remove(&temporary_channel_id) else {
+            return;
+        };
+        tracing::info!("Aborting external funding of channel {temporary_channel_id}: {reason}");
+        let _ = self.channel_manager.force_close_broadcasting_latest_txn(
+            &temporary_channel_id,
+            &info.counterparty_node_id,
+            format!("Funding aborted: {reason}"),
+        );
+        self.add_channel_event(
+            temporary_channel_id,
+            ChannelEventKind::FundingFailed,
+            format!("external funding aborted: {reason}"),
+        );
+    }
+
+    pub(crate) fn set_force_close_feerate(&self, channel_id: ChannelId, feerate_sat_per_kw: u32) {
+        self.get_force_close_feerates()
+            .insert(channel_id, feerate_sat_per_kw);
+    }
+
+    /// Raise the target feerate of a bump event to the one requested when force-closing
+    fn apply_force_close_feerate(&self, event: &mut BumpTransactionEvent) {
+        let (channel_id, target_feerate) = match event {
+            BumpTransactionEvent::ChannelClose {
+                channel_id,
+                package_target_feerate_sat_per_1000_weight,
+                ..
+            } => (channel_id, package_target_feerate_sat_per_1000_weight),
+            BumpTransactionEvent::HTLCResolution {
+                channel_id,
+                target_feerate_sat_per_1000_weight,
+                ..
+            } => (channel_id, target_feerate_sat_per_1000_weight),
+        };
+        if let Some(feerate) = self.get_force_close_feerates().get(channel_id) {
+            *target_feerate = (*target_feerate).max(*feerate);
+        }
+    }
+
+    /// Record an event in the channel log, skipping repetitions of the last event
+    pub(crate) fn add_channel_event(
+        &self,
+        channel_id: ChannelId,
+        kind: ChannelEventKind,
+        details: String,
+    ) {
+        let mut channel_events = self.get_channel_events();
+        let log = channel_events.channels.entry(channel_id).or_default();
+        if log
+            .events
+            .last()
+            .is_some_and(|e| e.kind == kind && e.details == details)
+        {
+            return;
+        }
+        log.events.push(ChannelEventInfo {
+            timestamp: get_current_timestamp(),
+            kind,
+            details,
+        });
+        if log.events.len() > MAX_CHANNEL_EVENTS {
+            log.events.remove(0);
+        }
+        self.save_channel_events(channel_events);
+    }
+
+    /// Record a resolved HTLC forward and update the totals of the channels involved.
+    /// Settled forwards count towards both channels, with the fee going to the outbound one,
+    /// failed forwards only count towards the inbound channel.
+    pub(crate) fn add_forward(&self, forward: ForwardInfo) {
+        let mut forwards = self.get_forwards();
+        let prev_totals = forwards.totals.entry(forward.prev_channel_id).or_default();
+        match forward.resolution {
+            ForwardResolution::Settled => {
+                prev_totals.forwards_in += 1;
+                prev_totals.amt_in_msat += forward.amt_in_msat.unwrap_or(0);
+                if let Some(next_channel_id) = forward.next_channel_id {
+                    let next_totals = forwards.totals.entry(next_channel_id).or_default();
+                    next_totals.forwards_out += 1;
+                    next_totals.amt_out_msat += forward.amt_out_msat.unwrap_or(0);
+                    next_totals.fee_earned_msat += forward.fee_earned_msat.unwrap_or(0);
+                }
+            }
+            ForwardResolution::Failed => prev_totals.failed_forwards += 1,
+        }
+        forwards.forwards.push(forward);
+        if forwards.forwards.len() > MAX_FORWARDS {
+            forwards.forwards.remove(0);
+        }
+        self.save_forwards(forwards);
+    }
+
+    fn save_forwards(&self, forwards: MutexGuard<ForwardMap>) {
+        self.kv_store
+            .write("", "", FORWARDS_FNAME, forwards.encode())
+            .unwrap();
+    }
+
+    /// Append an entry to the journal, chaining it to the last one and signing its hash
+    pub(crate) fn add_journal_entry(&self, kind: JournalEventKind, details: String) {
+        let mut journal = self.get_journal();
+        let (seq, prev_hash) = journal
+            .entries
+            .last()
+            .map(|e| (e.seq + 1, e.hash))
+            .unwrap_or((0, [0; 32]));
+        let timestamp = get_current_timestamp();
+        let hash = journal_entry_hash(&prev_hash, seq, timestamp, kind, &details);
+        let signature = lightning::util::message_signing::sign(
+            hex_str(&hash).as_bytes(),
+            &self.keys_manager.get_node_secret_key(),
+        );
+        journal.entries.push(JournalEntryInfo {
+            seq,
+            timestamp,
+            kind,
+            details,
+            prev_hash,
+            hash,
+            signature,
+        });
+        if journal.entries.len() > MAX_JOURNAL_ENTRIES {
+            journal.entries.remove(0);
+        }
+        self.save_journal(journal);
+    }
+
+    /// Journal entries with sequence numbers in the given inclusive range
+    pub(crate) fn journal_entries(
+        &self,
+        start: u64,
+        end: u64,
+    ) -> Result<Vec<JournalEntryInfo>, APIError> {
+        let journal = self.get_journal();
+        let (Some(first), Some(last)) = (journal.entries.first(), journal.entries.last()) else {
+            return Err(APIError::InvalidJournalRange(s!("the journal is empty")));
+        };
+        if start < first.seq {
+            return Err(APIError::InvalidJournalRange(format!(
+                "entries before {} have been pruned",
+                first.seq
+            )));
+        }
+        if end > last.seq {
+            return Err(APIError::InvalidJournalRange(format!(
+                "the last entry is {}",
+                last.seq
+            )));
+        }
+        let offset = (start - first.seq) as usize;
+        Ok(journal.entries[offset..=offset + (end - start) as usize].to_vec())
+    }
+
+    fn save_journal(&self, journal: MutexGuard<JournalMap>) {
+        self.kv_store
+            .write("", "", JOURNAL_FNAME, journal.encode())
+            .unwrap();
+    }
+
+    pub(crate) fn channel_events(&self, channel_id: &ChannelId) -> Option<Vec<ChannelEventInfo>> {
+        self.get_channel_events()
+            .channels
+            .get(channel_id)
+            .map(|log| log.events.clone())
+    }
+
+    /// Record the outputs of a closed channel handed to the sweeper, which forgets about them
+    /// once their sweep is deeply confirmed
+    pub(crate) fn add_close_outputs(
+        &self,
+        channel_id: ChannelId,
+        descriptors: &[SpendableOutputDescriptor],
+    ) {
+        let mut channel_events = self.get_channel_events();
+        let log = channel_events.channels.entry(channel_id).or_default();
+        for descriptor in descriptors {
+            let (outpoint, txout) = spendable_output(descriptor);
+            if log.close_outputs.iter().any(|o| o.outpoint == outpoint) {
+                continue;
+            }
+            log.close_outputs.push(CloseOutputInfo {
+                outpoint,
+                amount_sat: txout.value.to_sat(),
+            });
+        }
+        self.save_channel_events(channel_events);
+    }
+
+    pub(crate) fn close_outputs(&self, channel_id: &ChannelId) -> Vec<CloseOutputInfo> {
+        self.get_channel_events()
+            .channels
+            .get(channel_id)
+            .map(|log| log.close_outputs.clone())
+            .unwrap_or_default()
+    }
+
+    /// Move the events recorded with a temporary channel ID to the final one
+    pub(crate) fn move_channel_events(
+        &self,
+        temporary_channel_id: ChannelId,
+        channel_id: ChannelId,
+    ) {
+        let mut channel_events = self.get_channel_events();
+        let Some(mut log) = channel_events.channels.remove(&temporary_channel_id) else {
+            return;
+        };
+        if let Some(existing) = channel_events.channels.remove(&channel_id) {
+            log.events.extend(existing.events);
+            log.close_outputs.extend(existing.close_outputs);
+        }
+        channel_events.channels.insert(channel_id, log);
+        self.save_channel_events(channel_events);
+    }
+
+    fn save_channel_events(&self, channel_events: MutexGuard<ChannelEventMap>) {
+        self.kv_store
+            .write("", "", CHANNEL_EVENTS_FNAME, channel_events.encode())
+            .unwrap();
+    }
+
+    pub(crate) fn add_pending_broadcast(&self, txid: String, info: PendingBroadcastInfo) {
+        let mut pending_broadcasts = self.get_pending_broadcasts();
+        pending_broadcasts.broadcasts.insert(txid, info);
+        self.save_pending_broadcasts(pending_broadcasts);
+    }
+
+    pub(crate) fn pending_broadcasts(&self) -> LdkHashMap<String, PendingBroadcastInfo> {
+        self.get_pending_broadcasts().broadcasts.clone()
+    }
+
+    pub(crate) fn approve_pending_broadcast(&self, txid: &str) -> Option<PendingBroadcastInfo> {
+        let mut pending_broadcasts = self.get_pending_broadcasts();
+        let info = pending_broadcasts.broadcasts.get_mut(txid)?;
+        info.approved_at = Some(get_current_timestamp());
+        info.error = None;
+        let info = info.clone();
+        self.save_pending_broadcasts(pending_broadcasts);
+        Some(info)
+    }
+
+    pub(crate) fn remove_pending_broadcast(&self, txid: &str) {
+        let mut pending_broadcasts = self.get_pending_broadcasts();
+        pending_broadcasts.broadcasts.remove(txid);
+        self.save_pending_broadcasts(pending_broadcasts);
+    }
+
+    pub(crate) fn set_pending_broadcast_error(&self, txid: &str, error: String) {
+        let mut pending_broadcasts = self.get_pending_broadcasts();
+        if let Some(info) = pending_broadcasts.broadcasts.get_mut(txid) {
+            info.error = Some(error);
+        }
+        self.save_pending_broadcasts(pending_broadcasts);
+    }
+
+    fn save_pending_broadcasts(&self, pending_broadcasts: MutexGuard<PendingBroadcastMap>) {
+        self.kv_store
+            .write(
+                "",
+                "",
+                PENDING_BROADCASTS_FNAME,
+                pending_broadcasts.encode(),
+            )
+            .unwrap();
+    }
+
+    /// Make sure the given PSBT doesn't spend any locked UTXO
+    pub(crate) fn check_locked_utxos(&self, psbt: &str) -> Result<(), APIError> {
+        let psbt = Psbt::from_str(psbt).map_err(|e| APIError::Unexpected(e.to_string()))?;
+        let locked_utxos = self.get_locked_utxos();
+        match psbt
+            .unsigned_tx
+            .input
+            .iter()
+            .map(|i| i.previous_output.to_string())
+            .find(|o| locked_utxos.utxos.contains_key(o))
+        {
+            Some(outpoint) => Err(APIError::LockedUtxo(outpoint)),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn lock_utxo(&self, outpoint: String, info: LockedUtxoInfo) {
+        let mut locked_utxos = self.get_locked_utxos();
+        locked_utxos.utxos.insert(outpoint, info);
+        self.save_locked_utxos(locked_utxos);
+    }
+
+    pub(crate) fn locked_utxos(&self) -> LdkHashMap<String, LockedUtxoInfo> {
+        self.get_locked_utxos().utxos.clone()
+    }
+
+    /// Unlock a UTXO, returning false if it wasn't locked
+    pub(crate) fn unlock_utxo(&self, outpoint: &str) -> bool {
+        let mut locked_utxos = self.get_locked_utxos();
+        if locked_utxos.utxos.remove(outpoint).is_none() {
+            return false;
+        }
+        self.save_locked_utxos(locked_utxos);
+        true
+    }
+
+    fn save_locked_utxos(&self, locked_utxos: MutexGuard<LockedUtxoMap>) {
+        self.kv_store
+            .write("", "", LOCKED_UTXOS_FNAME, locked_utxos.encode())
+            .unwrap();
+    }
+}
+
+pub(crate) type ChainMonitor = chainmonitor::ChainMonitor<
+    InMemorySigner,
+    Arc<dyn Filter + Send + Sync>,
+    Arc<BitcoindClient>,
+    Arc<BitcoindClient>,
+    Arc<FilesystemLogger>,
+    Arc<
+        MonitorUpdatingPersister<
+            Arc<NodeStore>,
+            Arc<FilesystemLogger>,
+            Arc<PhantomKeysManager>,
+            Arc<PhantomKeysManager>,
+            Arc<BitcoindClient>,
+            Arc<BitcoindClient>,
+        >,
+    >,
+    Arc<PhantomKeysManager>,
+>;
 
 pub(crate) type GossipVerifier = lightning_block_sync::gossip::GossipVerifier<
     TokioSpawner,
@@ -431,11 +3912,11 @@ pub(crate) type GossipVerifier = lightning_block_sync::gossip::GossipVerifier<
 pub(crate) type PeerManager = LdkPeerManager<
     SocketDescriptor,
     Arc<ChannelManager>,
-    Arc<P2PGossipSync<Arc<NetworkGraph>, Arc<GossipVerifier>, Arc<FilesystemLogger>>>,
+    Arc<GossipHandler>,
     Arc<OnionMessenger>,
     Arc<FilesystemLogger>,
-    IgnoringMessageHandler,
-    Arc<KeysManager>,
+    Arc<PeerMessageHandler>,
+    Arc<PhantomKeysManager>,
     Arc<ChainMonitor>,
 >;
 
@@ -444,23 +3925,35 @@ pub(crate) type Scorer = ProbabilisticScorer<Arc<NetworkGraph>, Arc<FilesystemLo
 pub(crate) type Router = DefaultRouter<
     Arc<NetworkGraph>,
     Arc<FilesystemLogger>,
-    Arc<KeysManager>,
+    Arc<PhantomKeysManager>,
     Arc<RwLock<Scorer>>,
     ProbabilisticScoringFeeParameters,
     Scorer,
 >;
 
-pub(crate) type ChannelManager =
-    SimpleArcChannelManager<ChainMonitor, BitcoindClient, BitcoindClient, FilesystemLogger>;
+pub(crate) type MessageRouter =
+    DefaultMessageRouter<Arc<NetworkGraph>, Arc<FilesystemLogger>, Arc<PhantomKeysManager>>;
+
+pub(crate) type ChannelManager = LdkChannelManager<
+    Arc<ChainMonitor>,
+    Arc<BitcoindClient>,
+    Arc<PhantomKeysManager>,
+    Arc<PhantomKeysManager>,
+    Arc<PhantomKeysManager>,
+    Arc<BitcoindClient>,
+    Arc<Router>,
+    Arc<MessageRouter>,
+    Arc<FilesystemLogger>,
+>;
 
 pub(crate) type NetworkGraph = gossip::NetworkGraph<Arc<FilesystemLogger>>;
 
 pub(crate) type OnionMessenger = LdkOnionMessenger<
-    Arc<KeysManager>,
-    Arc<KeysManager>,
+    Arc<PhantomKeysManager>,
+    Arc<PhantomKeysManager>,
     Arc<FilesystemLogger>,
     Arc<ChannelManager>,
-    Arc<DefaultMessageRouter<Arc<NetworkGraph>, Arc<FilesystemLogger>, Arc<KeysManager>>>,
+    Arc<MessageRouter>,
     Arc<ChannelManager>,
     Arc<ChannelManager>,
     Arc<OMDomainResolver<Arc<ChannelManager>>>,
@@ -469,19 +3962,120 @@ pub(crate) type OnionMessenger = LdkOnionMessenger<
 
 pub(crate) type BumpTxEventHandler = BumpTransactionEventHandler<
     Arc<BitcoindClient>,
-    Arc<Wallet<Arc<RgbLibWalletWrapper>, Arc<FilesystemLogger>>>,
-    Arc<KeysManager>,
+    Arc<Wallet<Arc<AnchorWalletSource>, Arc<FilesystemLogger>>>,
+    Arc<PhantomKeysManager>,
     Arc<FilesystemLogger>,
 >;
 
 pub(crate) type OutputSpenderTxes = LdkHashMap<u64, bitcoin::Transaction>;
 
+#[derive(Debug)]
+pub(crate) struct ProbeOutcome {
+    pub(crate) reachable: bool,
+    pub(crate) short_channel_id: Option<u64>,
+    pub(crate) failure: String,
+}
+
+/// In-flight probes, with the channel used to report their outcome
+pub(crate) type ProbeMap = HashMap<PaymentId, Option<tokio::sync::oneshot::Sender<ProbeOutcome>>>;
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ProbeStats {
+    pub(crate) attempts: u32,
+    pub(crate) successes: u32,
+}
+
+/// Outcome of the probes sent to each destination since the node has been unlocked
+pub(crate) type ProbeStatsMap = HashMap<PublicKey, ProbeStats>;
+
+/// Feerates (in sat/KW) requested for the transactions of force-closed channels
+pub(crate) type ForceCloseFeerateMap = HashMap<ChannelId, u32>;
+
+#[derive(Clone, Debug)]
+pub(crate) struct MultisigFundingInput {
+    pub(crate) outpoint: BtcOutPoint,
+    pub(crate) txout: TxOut,
+    pub(crate) witness_script: ScriptBuf,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct MultisigFundingInfo {
+    pub(crate) counterparty_node_id: PublicKey,
+    pub(crate) capacity_sat: u64,
+    pub(crate) inputs: Vec<MultisigFundingInput>,
+    pub(crate) change_script: ScriptBuf,
+    pub(crate) fee_rate: u64,
+    pub(crate) unsigned_psbt: Option<Psbt>,
+    pub(crate) created_at: u64,
+    pub(crate) expires_at: u64,
+}
+
+impl MultisigFundingInfo {
+    pub(crate) fn inputs_amount_sat(&self) -> u64 {
+        self.inputs.iter().map(|i| i.txout.value.to_sat()).sum()
+    }
+
+    /// Fee of the funding transaction, sized for the worst case of its inputs signatures
+    pub(crate) fn fee_sat(&self) -> u64 {
+        let vsize = TX_OVERHEAD_VSIZE
+            + self.inputs.len() as u64 * MULTISIG_INPUT_VSIZE
+            + P2WSH_OUTPUT_VSIZE
+            + 9
+            + self.change_script.len() as u64;
+        vsize * self.fee_rate
+    }
+}
+
+/// Channel opens funded by an external multisig treasury, waiting for its signatures. They are
+/// kept in memory only, as LDK drops unfunded channels on restart.
+pub(crate) type MultisigFundingMap = HashMap<ChannelId, MultisigFundingInfo>;
+
+pub(crate) type FundingCoinSelectionMap = HashMap<ChannelId, CoinSelection>;
+
+#[derive(Clone, Debug)]
+pub(crate) struct ExternalFundingInfo {
+    pub(crate) counterparty_node_id: PublicKey,
+    pub(crate) capacity_sat: u64,
+    pub(crate) funding_script: Option<ScriptBuf>,
+    pub(crate) created_at: u64,
+    pub(crate) expires_at: u64,
+}
+
+/// Channel opens funded by a PSBT built and signed outside of the node, waiting for it. They are
+/// kept in memory only, as LDK drops unfunded channels on restart.
+pub(crate) type ExternalFundingMap = HashMap<ChannelId, ExternalFundingInfo>;
+
+/// Key encrypting the emergency kit, derived from the password the node has been unlocked with,
+/// along with the monitor update IDs of the channels included in the latest kit written
+pub(crate) struct EmergencyKitState {
+    pub(crate) key: BackupKey,
+    pub(crate) update_ids: Option<Vec<(ChannelId, u64)>>,
+    pub(crate) updated_at: u64,
+    pub(crate) num_channels: usize,
+}
+
+/// Collects the transactions a channel monitor would broadcast, without sending them
+#[derive(Default)]
+struct TxCollector {
+    txs: Mutex<Vec<Transaction>>,
+}
+
+impl BroadcasterInterface for TxCollector {
+    fn broadcast_transactions(&self, txs: &[&Transaction]) {
+        self.txs
+            .lock()
+            .unwrap()
+            .extend(txs.iter().map(|tx| (*tx).clone()));
+    }
+}
+
 pub(crate) struct RgbOutputSpender {
     static_state: Arc<StaticState>,
     rgb_wallet_wrapper: Arc<RgbLibWalletWrapper>,
     keys_manager: Arc<KeysManager>,
-    fs_store: Arc<FilesystemStore>,
+    kv_store: Arc<NodeStore>,
     txes: Arc<Mutex<OutputSpenderTxes>>,
+    sweep_config: Arc<Mutex<SweepConfig>>,
     proxy_endpoint: String,
 }
 
@@ -490,11 +4084,55 @@ pub(crate) type OutputSweeper = ldk_sweep::OutputSweeper<
     Arc<RgbLibWalletWrapper>,
     Arc<BitcoindClient>,
     Arc<dyn Filter + Send + Sync>,
-    Arc<FilesystemStore>,
+    Arc<NodeStore>,
     Arc<FilesystemLogger>,
     Arc<RgbOutputSpender>,
 >;
 
+/// Outpoint and output of a descriptor the sweeper can spend
+pub(crate) fn spendable_output(descriptor: &SpendableOutputDescriptor) -> (BtcOutPoint, &TxOut) {
+    match descriptor {
+        SpendableOutputDescriptor::StaticPaymentOutput(d) => {
+            (d.outpoint.into_bitcoin_outpoint(), &d.output)
+        }
+        SpendableOutputDescriptor::DelayedPaymentOutput(d) => {
+            (d.outpoint.into_bitcoin_outpoint(), &d.output)
+        }
+        SpendableOutputDescriptor::StaticOutput {
+            outpoint, output, ..
+        } => (outpoint.into_bitcoin_outpoint(), output),
+    }
+}
+
+/// Asset and amount allocated to our outputs of a channel closing TX, if any
+pub(crate) fn close_output_allocation(
+    ldk_data_dir: &Path,
+    txid: &Txid,
+) -> Option<(ContractId, u64)> {
+    let transfer_info_path = ldk_data_dir.join(format!("{txid}_transfer_info"));
+    if !transfer_info_path.exists() {
+        return None;
+    }
+    let transfer_info = read_rgb_transfer_info(&transfer_info_path);
+    if transfer_info.rgb_amount == 0 {
+        return None;
+    }
+    Some((transfer_info.contract_id, transfer_info.rgb_amount))
+}
+
+/// Asset ID and amount of an outbound RGB payment, if any
+fn _outbound_rgb_payment(ldk_data_dir: &Path, payment_hash: &PaymentHash) -> Option<(String, u64)> {
+    let rgb_payment_info_path = get_rgb_payment_info_path(payment_hash, ldk_data_dir, false);
+    if !rgb_payment_info_path.exists() {
+        return None;
+    }
+    let rgb_payment_info = parse_rgb_payment_info(&rgb_payment_info_path);
+    Some((
+        rgb_payment_info.contract_id.to_string(),
+        rgb_payment_info.amount,
+    ))
+}
+
 fn _update_rgb_channel_amount(ldk_data_dir: &Path, payment_hash: &PaymentHash, receiver: bool) {
     let payment_hash_str = hex_str(&payment_hash.0);
     for entry in fs::read_dir(ldk_data_dir).unwrap() {
@@ -534,6 +4172,7 @@ async fn handle_ldk_events(
             counterparty_node_id,
             channel_value_satoshis,
             output_script,
+            user_channel_id,
             ..
         } => {
             let addr = WitnessProgram::from_scriptpubkey(
@@ -550,11 +4189,82 @@ async fn handle_ldk_events(
             .expect("Lightning funding tx should always be to a SegWit output");
             let script_buf = ScriptBuf::from_bytes(addr.to_scriptpubkey());
 
+            // the acceptor picks the confirmations required on the funding TX, refuse fewer
+            // than the ones set for the peer (JIT channels sold to clients are zero-conf)
+            let min_depth = unlocked_state
+                .get_channel_depths()
+                .min_depth(&counterparty_node_id, static_state.min_channel_depth);
+            let required_depth = unlocked_state
+                .channel_manager
+                .list_channels_with_counterparty(&counterparty_node_id)
+                .into_iter()
+                .find(|c| c.channel_id == temporary_channel_id)
+                .and_then(|c| c.confirmations_required);
+            let is_jit_channel = unlocked_state.lsps_handler.is_jit_channel(user_channel_id);
+            if let Some(required_depth) =
+                required_depth.filter(|d| *d < min_depth && !is_jit_channel)
+            {
+                let reason =
+                    format!("peer requires {required_depth} confirmations, less than {min_depth}");
+                tracing::error!("Cannot fund channel {temporary_channel_id}: {reason}");
+                if unlocked_state
+                    .get_multisig_fundings()
+                    .contains_key(&temporary_channel_id)
+                {
+                    unlocked_state.abort_multisig_funding(temporary_channel_id, &reason);
+                } else if unlocked_state
+                    .get_external_fundings()
+                    .contains_key(&temporary_channel_id)
+                {
+                    unlocked_state.abort_external_funding(temporary_channel_id, &reason);
+                } else {
+                    let _ = unlocked_state
+                        .channel_manager
+                        .force_close_broadcasting_latest_txn(
+                            &temporary_channel_id,
+                            &counterparty_node_id,
+                            format!("Funding refused: {reason}"),
+                        );
+                    *unlocked_state.rgb_send_lock.lock().unwrap() = false;
+                    unlocked_state.add_channel_event(
+                        temporary_channel_id,
+                        ChannelEventKind::FundingFailed,
+                        reason,
+                    );
+                }
+                return Ok(());
+            }
+
+            // the funding TX of channels funded by a multisig treasury is signed externally
+            if unlocked_state
+                .get_multisig_fundings()
+                .contains_key(&temporary_channel_id)
+            {
+                unlocked_state.create_multisig_funding_psbt(temporary_channel_id, output_script);
+                return Ok(());
+            }
+            // the funding TX of channels funded externally is built and signed by the caller
+            if let Some(info) = unlocked_state
+                .get_external_fundings()
+                .get_mut(&temporary_channel_id)
+            {
+                info.funding_script = Some(output_script);
+                unlocked_state.add_channel_event(
+                    temporary_channel_id,
+                    ChannelEventKind::FundingOutputReady,
+                    format!(
+                        "waiting for a PSBT paying {channel_value_satoshis} sats to {}",
+                        addr.to_address()
+                    ),
+                );
+                return Ok(());
+            }
+
             let is_colored = is_channel_rgb(
                 &temporary_channel_id,
                 &PathBuf::from(&static_state.ldk_data_dir),
             );
-            let (unsigned_psbt, asset_id) = if is_colored {
+            let (signed_psbt, asset_id) = if is_colored {
                 let (rgb_info, _) = get_rgb_channel_info_pending(
                     &temporary_channel_id,
                     &PathBuf::from(&static_state.ldk_data_dir),
@@ -563,9 +4273,10 @@ async fn handle_ldk_events(
                 let channel_rgb_amount: u64 = rgb_info.local_rgb_amount;
                 let asset_id = rgb_info.contract_id.to_string();
                 let assignment = match rgb_info.schema {
-                    AssetSchema::Nia | AssetSchema::Cfa => Assignment::Fungible(channel_rgb_amount),
+                    AssetSchema::Nia | AssetSchema::Cfa | AssetSchema::Ifa => {
+                        Assignment::Fungible(channel_rgb_amount)
+                    }
                     AssetSchema::Uda => Assignment::NonFungible,
-                    AssetSchema::Ifa => todo!(),
                 };
 
                 let recipient_id = recipient_id_from_script_buf(script_buf, static_state.network);
@@ -589,15 +4300,52 @@ async fn handle_ldk_events(
                 })
                 .await
                 .unwrap();
-                (unsigned_psbt, Some(asset_id))
+                let signed_psbt = unlocked_state.rgb_sign_psbt(unsigned_psbt).unwrap();
+                (signed_psbt, Some(asset_id))
             } else {
-                let unsigned_psbt = unlocked_state
-                    .rgb_send_btc_begin(addr.to_address(), channel_value_satoshis, FEE_RATE)
-                    .unwrap();
-                (unsigned_psbt, None)
+                let coin_selection = unlocked_state
+                    .get_funding_coin_selections()
+                    .remove(&temporary_channel_id);
+                // coin selection can fail here if it would spend a locked UTXO
+                let res = if let Some(coin_selection) = coin_selection {
+                    unlocked_state
+                        .send_btc_begin_with_coin_selection(
+                            vec![TxOut {
+                                value: Amount::from_sat(channel_value_satoshis),
+                                script_pubkey: script_buf,
+                            }],
+                            FEE_RATE,
+                            &coin_selection,
+                            false,
+                        )
+                        .await
+                } else {
+                    unlocked_state
+                        .rgb_send_btc_begin(addr.to_address(), channel_value_satoshis, FEE_RATE)
+                        .and_then(|p| unlocked_state.rgb_sign_psbt(p).map_err(APIError::from))
+                };
+                match res {
+                    Ok(signed_psbt) => (signed_psbt, None),
+                    Err(e) => {
+                        tracing::error!("Cannot fund channel {temporary_channel_id}: {e}");
+                        let _ = unlocked_state
+                            .channel_manager
+                            .force_close_broadcasting_latest_txn(
+                                &temporary_channel_id,
+                                &counterparty_node_id,
+                                format!("Funding failed: {e}"),
+                            );
+                        *unlocked_state.rgb_send_lock.lock().unwrap() = false;
+                        unlocked_state.add_channel_event(
+                            temporary_channel_id,
+                            ChannelEventKind::FundingFailed,
+                            e.to_string(),
+                        );
+                        return Ok(());
+                    }
+                }
             };
 
-            let signed_psbt = unlocked_state.rgb_sign_psbt(unsigned_psbt).unwrap();
             let psbt = Psbt::from_str(&signed_psbt).unwrap();
 
             let funding_tx = psbt.clone().extract_tx().unwrap();
@@ -629,12 +4377,13 @@ async fn handle_ldk_events(
                     .unwrap()
                     .endpoint;
                 let unlocked_state_copy = unlocked_state.clone();
+                let witness_id = funding_txid.clone();
                 let res = tokio::task::spawn_blocking(move || {
                     unlocked_state_copy.rgb_post_consignment(
                         &proxy_url,
-                        funding_txid.clone(),
+                        witness_id.clone(),
                         &consignment_path,
-                        funding_txid,
+                        witness_id,
                         None,
                     )
                 })
@@ -645,10 +4394,22 @@ async fn handle_ldk_events(
                     tracing::error!("cannot post consignment: {e}");
                     return Err(ReplayEvent());
                 }
+                unlocked_state.add_channel_event(
+                    temporary_channel_id,
+                    ChannelEventKind::ConsignmentSent,
+                    format!("consignment of funding TX {funding_txid} sent to the proxy"),
+                );
             }
 
             let channel_manager_copy = unlocked_state.channel_manager.clone();
 
+            let funding_txid = funding_tx.compute_txid();
+            let funding_output_index = funding_tx
+                .output
+                .iter()
+                .position(|o| o.script_pubkey == output_script)
+                .expect("funding TX should pay to the funding script");
+
             // Give the funding transaction back to LDK for opening the channel.
             if channel_manager_copy
                 .funding_transaction_generated(
@@ -661,19 +4422,64 @@ async fn handle_ldk_events(
                 tracing::error!(
                         "ERROR: Channel went away before we could fund it. The peer disconnected or refused the channel.");
                 *unlocked_state.rgb_send_lock.lock().unwrap() = false;
+                unlocked_state.add_channel_event(
+                    temporary_channel_id,
+                    ChannelEventKind::FundingFailed,
+                    s!("the peer disconnected or refused the channel before funding"),
+                );
+            } else {
+                // make the final channel ID immediately available, without waiting for the
+                // ChannelPending event
+                let channel_id = ChannelId::v1_from_funding_outpoint(OutPoint {
+                    txid: funding_txid,
+                    index: funding_output_index as u16,
+                });
+                unlocked_state.add_channel_id(temporary_channel_id, channel_id);
+                unlocked_state.move_channel_events(temporary_channel_id, channel_id);
+                unlocked_state.add_channel_event(
+                    channel_id,
+                    ChannelEventKind::FundingGenerated,
+                    format!("funding TX {funding_txid}:{funding_output_index}"),
+                );
             }
         }
-        Event::FundingTxBroadcastSafe { .. } => {
-            // We don't use the manual broadcasting feature, so this event should never be seen.
+        Event::FundingTxBroadcastSafe {
+            channel_id,
+            funding_txo,
+            ..
+        } => {
+            // only externally funded channels are broadcast manually, once the peer has signed
+            let tx_path = static_state
+                .ldk_data_dir
+                .join(format!("{EXTERNAL_FUNDING_TX_PREFIX}{}", funding_txo.txid));
+            let Ok(tx_hex) = fs::read_to_string(&tx_path) else {
+                tracing::error!(
+                    "Missing funding TX {} of channel {channel_id}",
+                    funding_txo.txid
+                );
+                return Ok(());
+            };
+            let funding_tx: Transaction =
+                encode::deserialize_hex(&tx_hex).expect("valid funding TX");
+            unlocked_state
+                .bitcoind_client
+                .broadcast_transactions(&[&funding_tx]);
+            fs::remove_file(tx_path).unwrap();
+            tracing::info!("Broadcast external funding TX {}", funding_txo.txid);
+            unlocked_state.add_channel_event(
+                channel_id,
+                ChannelEventKind::FundingBroadcast,
+                format!("funding TX {} broadcast", funding_txo.txid),
+            );
         }
         Event::PaymentClaimable {
             payment_hash,
             purpose,
             amount_msat,
             receiver_node_id: _,
-            claim_deadline: _,
+            claim_deadline,
             onion_fields: _,
-            counterparty_skimmed_fee_msat: _,
+            counterparty_skimmed_fee_msat,
             receiving_channel_ids: _,
             payment_id: _,
         } => {
@@ -682,6 +4488,46 @@ async fn handle_ldk_events(
                 payment_hash,
                 amount_msat,
             );
+            #[cfg(feature = "failure-injection")]
+            if let Some(failure_code) = unlocked_state.take_injected_receive_failure() {
+                tracing::info!(
+                    "EVENT: failing back payment with payment hash {} with injected {:?}",
+                    payment_hash,
+                    failure_code,
+                );
+                unlocked_state
+                    .channel_manager
+                    .fail_htlc_backwards_with_reason(&payment_hash, failure_code);
+                return Ok(());
+            }
+            if let Some(txid) = unlocked_state.fallback_txid(&payment_hash) {
+                tracing::info!(
+                    "EVENT: failing back payment with payment hash {} as the invoice has been paid on-chain by TX {}",
+                    payment_hash,
+                    txid,
+                );
+                unlocked_state
+                    .channel_manager
+                    .fail_htlc_backwards(&payment_hash);
+                return Ok(());
+            }
+            if counterparty_skimmed_fee_msat > 0 {
+                let max_skimmed_fee_msat = unlocked_state
+                    .jit_channel(&payment_hash)
+                    .map(|c| c.opening_fee_msat)
+                    .unwrap_or(0);
+                if counterparty_skimmed_fee_msat > max_skimmed_fee_msat {
+                    tracing::info!(
+                        "EVENT: failing back payment with payment hash {} as {} millisatoshis have been skimmed",
+                        payment_hash,
+                        counterparty_skimmed_fee_msat,
+                    );
+                    unlocked_state
+                        .channel_manager
+                        .fail_htlc_backwards(&payment_hash);
+                    return Ok(());
+                }
+            }
             let payment_preimage = match purpose {
                 PaymentPurpose::Bolt11InvoicePayment {
                     payment_preimage, ..
@@ -694,9 +4540,31 @@ async fn handle_ldk_events(
                 } => payment_preimage,
                 PaymentPurpose::SpontaneousPayment(preimage) => Some(preimage),
             };
-            unlocked_state
-                .channel_manager
-                .claim_funds(payment_preimage.unwrap());
+            if let Some(scope_id) =
+                unlocked_state.find_intercept_scope(|s| s.payment_hash == Some(payment_hash))
+            {
+                unlocked_state.hold_intercepted_htlc(
+                    hex_str(&payment_hash.0),
+                    InterceptedHtlcInfo {
+                        scope_id,
+                        payment_hash,
+                        amt_msat: amount_msat,
+                        rgb_amount: None,
+                        held: HeldHtlc::Receive {
+                            payment_preimage,
+                            claim_deadline,
+                        },
+                        received_at: get_current_timestamp(),
+                    },
+                );
+                return Ok(());
+            }
+            unlocked_state.handle_claimable_payment(
+                &payment_hash,
+                amount_msat,
+                claim_deadline,
+                payment_preimage,
+            );
         }
         Event::PaymentClaimed {
             payment_hash,
@@ -713,23 +4581,27 @@ async fn handle_ldk_events(
                 payment_hash,
                 amount_msat,
             );
-            let (payment_preimage, payment_secret) = match purpose {
+            let (payment_preimage, payment_secret, offer_id) = match purpose {
                 PaymentPurpose::Bolt11InvoicePayment {
                     payment_preimage,
                     payment_secret,
                     ..
-                } => (payment_preimage, Some(payment_secret)),
+                } => (payment_preimage, Some(payment_secret), None),
                 PaymentPurpose::Bolt12OfferPayment {
                     payment_preimage,
                     payment_secret,
-                    ..
-                } => (payment_preimage, Some(payment_secret)),
+                    payment_context,
+                } => (
+                    payment_preimage,
+                    Some(payment_secret),
+                    Some(payment_context.offer_id),
+                ),
                 PaymentPurpose::Bolt12RefundPayment {
                     payment_preimage,
                     payment_secret,
                     ..
-                } => (payment_preimage, Some(payment_secret)),
-                PaymentPurpose::SpontaneousPayment(preimage) => (Some(preimage), None),
+                } => (payment_preimage, Some(payment_secret), None),
+                PaymentPurpose::SpontaneousPayment(preimage) => (Some(preimage), None, None),
             };
 
             // check if already claimed
@@ -753,6 +4625,7 @@ async fn handle_ldk_events(
             }
 
             _update_rgb_channel_amount(&static_state.ldk_data_dir, &payment_hash, true);
+            unlocked_state.remove_jit_channel(&payment_hash);
             if is_maker_swap {
                 unlocked_state.update_maker_swap_status(&payment_hash, SwapStatus::Succeeded);
             } else {
@@ -763,7 +4636,42 @@ async fn handle_ldk_events(
                     payment_secret,
                     Some(amount_msat),
                     receiver_node_id.unwrap(),
+                    offer_id,
                 );
+                // keysend payments don't settle an invoice
+                if payment_secret.is_some() {
+                    let rgb_payment_info_path =
+                        get_rgb_payment_info_path(&payment_hash, &static_state.ldk_data_dir, true);
+                    let (asset_id, asset_amount) = if rgb_payment_info_path.exists() {
+                        let info = parse_rgb_payment_info(&rgb_payment_info_path);
+                        (Some(info.contract_id.to_string()), Some(info.amount))
+                    } else {
+                        (None, None)
+                    };
+                    unlocked_state.add_invoice_settlement(
+                        &payment_hash,
+                        amount_msat,
+                        asset_id.clone(),
+                        asset_amount,
+                    );
+                    unlocked_state.add_journal_entry(
+                        JournalEventKind::InvoiceSettled,
+                        format!("payment hash {payment_hash}, amt_msat {amount_msat}"),
+                    );
+                    unlocked_state.queue_hedge(
+                        payment_hash,
+                        asset_id.clone(),
+                        asset_amount.unwrap_or(amount_msat),
+                    );
+                    unlocked_state
+                        .hook_runner
+                        .trigger(HookEvent::InvoiceSettled {
+                            payment_hash: hex_str(&payment_hash.0),
+                            amt_msat: amount_msat,
+                            asset_id,
+                            asset_amount,
+                        });
+                }
             }
         }
         Event::PaymentSent {
@@ -788,6 +4696,13 @@ async fn handle_ldk_events(
                     HTLCStatus::Succeeded,
                     Some(payment_preimage),
                 );
+                unlocked_state.add_journal_entry(
+                    JournalEventKind::PaymentSucceeded,
+                    format!(
+                        "payment hash {payment_hash}, amt_msat {}",
+                        payment.amt_msat.map_or(s!("any"), |a| a.to_string())
+                    ),
+                );
                 tracing::info!(
                     "EVENT: successfully sent payment of {:?} millisatoshis{} from \
                             payment hash {} with preimage {}",
@@ -805,18 +4720,101 @@ async fn handle_ldk_events(
         Event::OpenChannelRequest {
             ref temporary_channel_id,
             ref counterparty_node_id,
+            funding_satoshis,
+            channel_negotiation_type,
+            ref channel_type,
+            is_announced,
             ..
         } => {
+            let is_jit_channel = unlocked_state.is_jit_channel_lsp(counterparty_node_id);
+            // JIT channels have been bought by us, so they skip the acceptor
+            if !is_jit_channel {
+                let hosts = announced_hosts(&unlocked_state.network_graph, counterparty_node_id);
+                let peer_rejection = unlocked_state
+                    .get_peer_filter()
+                    .check_peer(counterparty_node_id, &hosts);
+                let policy = unlocked_state.get_channel_acceptor().clone();
+                let peer_channels = unlocked_state
+                    .channel_manager
+                    .list_channels_with_counterparty(counterparty_node_id)
+                    .len()
+                    + unlocked_state
+                        .pending_channels()
+                        .values()
+                        .filter(|c| c.counterparty_node_id == *counterparty_node_id)
+                        .count();
+                let with_anchors = channel_type.supports_anchors_zero_fee_htlc_tx();
+                if let Some(reason) = peer_rejection
+                    .or_else(|| policy.check_request(funding_satoshis, with_anchors, peer_channels))
+                {
+                    if let Err(e) = unlocked_state.reject_channel(
+                        *temporary_channel_id,
+                        *counterparty_node_id,
+                        reason,
+                    ) {
+                        tracing::error!("EVENT: {e}");
+                    }
+                    return Ok(());
+                }
+                if policy.manual_approval {
+                    let push_msat = match channel_negotiation_type {
+                        InboundChannelFunds::PushMsat(push_msat) => push_msat,
+                        _ => 0,
+                    };
+                    unlocked_state.get_pending_channels().insert(
+                        *temporary_channel_id,
+                        PendingChannelInfo {
+                            counterparty_node_id: *counterparty_node_id,
+                            capacity_sat: funding_satoshis,
+                            push_msat,
+                            public: is_announced,
+                            with_anchors,
+                            received_at: get_current_timestamp(),
+                        },
+                    );
+                    tracing::info!(
+                        "EVENT: Holding inbound channel ({}) from {} for a decision",
+                        temporary_channel_id,
+                        hex_str(&counterparty_node_id.serialize()),
+                    );
+                    unlocked_state.add_channel_event(
+                        *temporary_channel_id,
+                        ChannelEventKind::OpenReceived,
+                        format!("request from {counterparty_node_id} waiting for a decision"),
+                    );
+                    return Ok(());
+                }
+            }
+
             let mut random_bytes = [0u8; 16];
             random_bytes
                 .copy_from_slice(&unlocked_state.keys_manager.get_secure_random_bytes()[..16]);
             let user_channel_id = u128::from_be_bytes(random_bytes);
-            let res = unlocked_state.channel_manager.accept_inbound_channel(
-                temporary_channel_id,
-                counterparty_node_id,
-                user_channel_id,
-                None,
-            );
+            let res = if is_jit_channel {
+                // the LSP deducts its opening fee from the HTLC forwarded over the JIT channel
+                let config_overrides = ChannelConfigOverrides {
+                    handshake_overrides: None,
+                    update_overrides: Some(ChannelConfigUpdate {
+                        accept_underpaying_htlcs: Some(true),
+                        ..Default::default()
+                    }),
+                };
+                unlocked_state
+                    .channel_manager
+                    .accept_inbound_channel_from_trusted_peer_0conf(
+                        temporary_channel_id,
+                        counterparty_node_id,
+                        user_channel_id,
+                        Some(config_overrides),
+                    )
+            } else {
+                unlocked_state.accept_inbound_channel_with_depth(
+                    temporary_channel_id,
+                    counterparty_node_id,
+                    user_channel_id,
+                    static_state.min_channel_depth,
+                )
+            };
 
             if let Err(e) = res {
                 tracing::error!(
@@ -825,16 +4823,73 @@ async fn handle_ldk_events(
                     hex_str(&counterparty_node_id.serialize()),
                     e,
                 );
+                unlocked_state.add_channel_event(
+                    *temporary_channel_id,
+                    ChannelEventKind::OpenReceived,
+                    format!("rejected request from {counterparty_node_id}: {e:?}"),
+                );
             } else {
                 tracing::info!(
                     "EVENT: Accepted inbound channel ({}) from {}",
                     temporary_channel_id,
                     hex_str(&counterparty_node_id.serialize()),
                 );
+                unlocked_state.add_channel_event(
+                    *temporary_channel_id,
+                    ChannelEventKind::OpenReceived,
+                    format!("accepted request from {counterparty_node_id}"),
+                );
+            }
+        }
+        Event::PaymentPathSuccessful {
+            payment_id,
+            payment_hash,
+            path,
+            ..
+        } => {
+            let rgb_payment =
+                payment_hash.and_then(|h| _outbound_rgb_payment(&static_state.ldk_data_dir, &h));
+            unlocked_state.add_outbound_payment_part(
+                payment_id,
+                &path,
+                HTLCStatus::Succeeded,
+                None,
+                rgb_payment,
+            );
+        }
+        Event::PaymentPathFailed {
+            payment_id,
+            payment_hash,
+            payment_failed_permanently,
+            path,
+            failure,
+            short_channel_id,
+            ..
+        } => {
+            if let Some(payment_id) = payment_id {
+                // a probe rejected by the recipient has found enough liquidity to reach it
+                let outcome = ProbeOutcome {
+                    reachable: payment_failed_permanently,
+                    short_channel_id,
+                    failure: format!("{failure:?}"),
+                };
+                if unlocked_state.report_probe_outcome(payment_id, outcome) {
+                    return Ok(());
+                }
+                let rgb_payment = _outbound_rgb_payment(&static_state.ldk_data_dir, &payment_hash);
+                let failure = match short_channel_id {
+                    Some(scid) => format!("{failure:?} at channel {scid}"),
+                    None => format!("{failure:?}"),
+                };
+                unlocked_state.add_outbound_payment_part(
+                    payment_id,
+                    &path,
+                    HTLCStatus::Failed,
+                    Some(failure),
+                    rgb_payment,
+                );
             }
         }
-        Event::PaymentPathSuccessful { .. } => {}
-        Event::PaymentPathFailed { .. } => {}
         Event::ProbeSuccessful { .. } => {}
         Event::ProbeFailed { .. } => {}
         Event::PaymentFailed {
@@ -843,6 +4898,18 @@ async fn handle_ldk_events(
             payment_id,
             ..
         } => {
+            let outcome = ProbeOutcome {
+                reachable: false,
+                short_channel_id: None,
+                failure: reason
+                    .as_ref()
+                    .map(|r| format!("{r:?}"))
+                    .unwrap_or_default(),
+            };
+            if unlocked_state.report_probe_outcome(payment_id, outcome) {
+                unlocked_state.remove_probe(payment_id);
+                return Ok(());
+            }
             if let Some(hash) = payment_hash {
                 tracing::error!(
                     "EVENT: Failed to send payment to payment ID {}, payment hash {}: {:?}",
@@ -858,6 +4925,10 @@ async fn handle_ldk_events(
                     unlocked_state.update_maker_swap_status(&hash, SwapStatus::Failed);
                 } else {
                     unlocked_state.update_outbound_payment_status(payment_id, HTLCStatus::Failed);
+                    unlocked_state.add_journal_entry(
+                        JournalEventKind::PaymentFailed,
+                        format!("payment hash {hash}"),
+                    );
                 }
             } else {
                 tracing::error!(
@@ -916,6 +4987,28 @@ async fn handle_ldk_events(
                 unlocked_state.update_taker_swap_status(&payment_hash, SwapStatus::Succeeded);
             }
 
+            unlocked_state.add_forward(ForwardInfo {
+                timestamp: get_current_timestamp(),
+                prev_channel_id: prev_channel_id.unwrap(),
+                next_channel_id,
+                payment_hash: Some(payment_hash),
+                amt_in_msat: outbound_amount_forwarded_msat
+                    .zip(total_fee_earned_msat)
+                    .map(|(amt, fee)| amt + fee),
+                amt_out_msat: outbound_amount_forwarded_msat,
+                fee_earned_msat: total_fee_earned_msat,
+                asset_id: get_rgb_channel_info_optional(
+                    &prev_channel_id.unwrap(),
+                    &static_state.ldk_data_dir,
+                    false,
+                )
+                .map(|(rgb_info, _)| rgb_info.contract_id.to_string()),
+                asset_amount_in: inbound_amount_forwarded_rgb,
+                asset_amount_out: outbound_amount_forwarded_rgb,
+                resolution: ForwardResolution::Settled,
+                failure_reason: None,
+            });
+
             let read_only_network_graph = unlocked_state.network_graph.read_only();
             let nodes = read_only_network_graph.nodes();
             let channels = unlocked_state.channel_manager.list_channels();
@@ -982,13 +5075,49 @@ async fn handle_ldk_events(
                 );
             }
         }
-        Event::HTLCHandlingFailed { .. } => {}
+        Event::HTLCHandlingFailed {
+            prev_channel_id,
+            failure_type,
+            failure_reason,
+        } => {
+            let next_channel_id = match failure_type {
+                HTLCHandlingFailureType::Forward { channel_id, .. } => Some(channel_id),
+                HTLCHandlingFailureType::InvalidForward { .. }
+                | HTLCHandlingFailureType::UnknownNextHop { .. } => None,
+                _ => return Ok(()),
+            };
+            tracing::info!(
+                "EVENT: Failed to forward HTLC from channel {prev_channel_id}: {failure_reason:?}"
+            );
+            unlocked_state.add_forward(ForwardInfo {
+                timestamp: get_current_timestamp(),
+                prev_channel_id,
+                next_channel_id,
+                payment_hash: None,
+                amt_in_msat: None,
+                amt_out_msat: None,
+                fee_earned_msat: None,
+                asset_id: get_rgb_channel_info_optional(
+                    &prev_channel_id,
+                    &static_state.ldk_data_dir,
+                    false,
+                )
+                .map(|(rgb_info, _)| rgb_info.contract_id.to_string()),
+                asset_amount_in: None,
+                asset_amount_out: None,
+                resolution: ForwardResolution::Failed,
+                failure_reason: failure_reason.map(|r| format!("{r:?}")),
+            });
+        }
         Event::SpendableOutputs {
             outputs,
             channel_id,
         } => {
             tracing::info!("EVENT: tracking {} spendable outputs", outputs.len(),);
 
+            if let Some(channel_id) = channel_id {
+                unlocked_state.add_close_outputs(channel_id, &outputs);
+            }
             unlocked_state
                 .output_sweeper
                 .track_spendable_outputs(outputs, channel_id, false, None)
@@ -1009,6 +5138,18 @@ async fn handle_ldk_events(
             );
 
             unlocked_state.add_channel_id(former_temporary_channel_id.unwrap(), channel_id);
+            unlocked_state
+                .asset_htlc_min_handler
+                .announce(channel_id, counterparty_node_id);
+            unlocked_state.move_channel_events(former_temporary_channel_id.unwrap(), channel_id);
+            unlocked_state.add_channel_event(
+                channel_id,
+                ChannelEventKind::Pending,
+                format!(
+                    "awaiting confirmation of funding TX {}:{}",
+                    funding_txo.txid, funding_txo.index
+                ),
+            );
 
             let funding_txid = funding_txo.txid.to_string();
             let psbt_path = static_state
@@ -1040,6 +5181,11 @@ async fn handle_ldk_events(
                 })?;
 
                 *unlocked_state.rgb_send_lock.lock().unwrap() = false;
+                unlocked_state.add_channel_event(
+                    channel_id,
+                    ChannelEventKind::FundingBroadcast,
+                    format!("funding TX {funding_txid} broadcast"),
+                );
             } else {
                 // acceptor
                 let consignment_path = static_state
@@ -1051,6 +5197,17 @@ async fn handle_ldk_events(
                 }
                 let consignment =
                     RgbTransfer::load_file(consignment_path).expect("successful consignment load");
+                // the asset is only known once the funding is received
+                let asset_id = consignment.contract_id().to_string();
+                let rejection = unlocked_state.get_channel_acceptor().check_asset(&asset_id);
+                if let Some(reason) = rejection {
+                    if let Err(e) =
+                        unlocked_state.reject_channel(channel_id, counterparty_node_id, reason)
+                    {
+                        tracing::error!("EVENT: {e}");
+                    }
+                    return Ok(());
+                }
 
                 match unlocked_state.rgb_save_new_asset(consignment, funding_txid) {
                     Ok(_) => {}
@@ -1061,7 +5218,7 @@ async fn handle_ldk_events(
         }
         Event::ChannelReady {
             ref channel_id,
-            user_channel_id: _,
+            user_channel_id,
             ref counterparty_node_id,
             funding_txo: _,
             channel_type: _,
@@ -1071,6 +5228,21 @@ async fn handle_ldk_events(
                 channel_id,
                 hex_str(&counterparty_node_id.serialize()),
             );
+            unlocked_state.add_channel_event(
+                *channel_id,
+                ChannelEventKind::Ready,
+                s!("funding locked in"),
+            );
+            unlocked_state.add_journal_entry(
+                JournalEventKind::ChannelOpened,
+                format!("channel {channel_id} with peer {counterparty_node_id}"),
+            );
+            unlocked_state.apply_asset_policy(
+                channel_id,
+                counterparty_node_id,
+                &static_state.ldk_data_dir,
+            );
+            unlocked_state.forward_jit_channel_htlc(user_channel_id, *channel_id);
 
             tokio::task::spawn_blocking(move || {
                 unlocked_state.rgb_refresh(false).unwrap();
@@ -1082,7 +5254,7 @@ async fn handle_ldk_events(
         Event::ChannelClosed {
             channel_id,
             reason,
-            user_channel_id: _,
+            user_channel_id,
             counterparty_node_id,
             channel_capacity_sats: _,
             channel_funding_txo: _,
@@ -1096,6 +5268,31 @@ async fn handle_ldk_events(
                     .unwrap_or("".to_owned()),
                 reason
             );
+            unlocked_state.add_channel_event(
+                channel_id,
+                ChannelEventKind::Closed,
+                reason.to_string(),
+            );
+            unlocked_state.add_journal_entry(
+                JournalEventKind::ChannelClosed,
+                format!("channel {channel_id}: {reason}"),
+            );
+            unlocked_state.get_multisig_fundings().remove(&channel_id);
+            unlocked_state.get_external_fundings().remove(&channel_id);
+            unlocked_state
+                .get_funding_coin_selections()
+                .remove(&channel_id);
+            unlocked_state
+                .asset_htlc_min_handler
+                .channel_closed(&channel_id);
+            if let Some(intercept_id) = unlocked_state
+                .lsps_handler
+                .jit_channel_closed(user_channel_id)
+            {
+                let _ = unlocked_state
+                    .channel_manager
+                    .fail_intercepted_htlc(intercept_id);
+            }
 
             unlocked_state.delete_channel_id(channel_id);
         }
@@ -1108,6 +5305,11 @@ async fn handle_ldk_events(
             );
 
             *unlocked_state.rgb_send_lock.lock().unwrap() = false;
+            unlocked_state.add_channel_event(
+                channel_id,
+                ChannelEventKind::FundingFailed,
+                s!("funding TX discarded"),
+            );
 
             unlocked_state.delete_channel_id(channel_id);
         }
@@ -1123,7 +5325,45 @@ async fn handle_ldk_events(
             prev_outbound_scid_alias,
         } => {
             if !is_swap {
-                tracing::warn!("Intercepted an HTLC that's not related to a swap");
+                if unlocked_state.open_jit_channel(
+                    requested_next_hop_scid,
+                    intercept_id,
+                    expected_outbound_amount_msat,
+                ) {
+                    return Ok(());
+                }
+                if let Some(scope_id) = unlocked_state
+                    .find_intercept_scope(|s| s.intercept_scid == Some(requested_next_hop_scid))
+                {
+                    let scope = unlocked_state.intercept_scope(&scope_id).unwrap();
+                    let htlc_id = hex_str(&intercept_id.0);
+                    let info = InterceptedHtlcInfo {
+                        scope_id,
+                        payment_hash,
+                        amt_msat: inbound_amount_msat,
+                        rgb_amount: inbound_rgb_amount,
+                        held: HeldHtlc::Forward {
+                            intercept_id,
+                            outbound_amt_msat: expected_outbound_amount_msat,
+                            outbound_rgb_payment: expected_outbound_rgb_payment,
+                        },
+                        received_at: get_current_timestamp(),
+                    };
+                    if scope.kind == InterceptScopeKind::AsyncRecipient {
+                        unlocked_state.hold_async_payment(
+                            htlc_id,
+                            info,
+                            &scope,
+                            &static_state.ldk_data_dir,
+                        );
+                    } else {
+                        unlocked_state.hold_intercepted_htlc(htlc_id, info);
+                    }
+                    return Ok(());
+                }
+                tracing::warn!(
+                    "Intercepted an HTLC that's not related to a swap, JIT channel or scope"
+                );
                 unlocked_state
                     .channel_manager
                     .fail_intercepted_htlc(intercept_id)
@@ -1246,22 +5486,29 @@ async fn handle_ldk_events(
             // We don't use the onion message interception feature, so we have no use for this
             // event.
         }
-        Event::BumpTransaction(event) => {
+        Event::BumpTransaction(mut event) => {
+            unlocked_state.apply_force_close_feerate(&mut event);
             unlocked_state
                 .bump_tx_event_handler
                 .handle_event(&event)
                 .await
         }
         Event::ConnectionNeeded { node_id, addresses } => {
+            let tor_proxy = static_state.tor_proxy.clone();
             tokio::spawn(async move {
                 for address in addresses {
-                    if let Ok(sockaddrs) = address.to_socket_addrs() {
-                        for addr in sockaddrs {
-                            let pm = Arc::clone(&unlocked_state.peer_manager);
-                            if connect_peer_if_necessary(node_id, addr, pm).await.is_ok() {
-                                return;
-                            }
-                        }
+                    let pm = Arc::clone(&unlocked_state.peer_manager);
+                    if connect_peer_if_necessary(
+                        node_id,
+                        &address,
+                        pm,
+                        tor_proxy.as_ref(),
+                        CircuitGroup::Gossip,
+                    )
+                    .await
+                    .is_ok()
+                    {
+                        return;
                     }
                 }
             });
@@ -1303,6 +5550,15 @@ impl OutputSpender for RgbOutputSpender {
             return Ok(tx.clone());
         }
 
+        let sweep_config = self.sweep_config.lock().unwrap().clone();
+        let change_destination_script = match sweep_config.destination_address {
+            Some(address) => Address::from_str(&address)
+                .unwrap()
+                .assume_checked()
+                .script_pubkey(),
+            None => change_destination_script,
+        };
+
         let mut vout = 0;
         let mut vanilla_descriptor = true;
 
@@ -1319,17 +5575,11 @@ impl OutputSpender for RgbOutputSpender {
             let txid = outpoint.txid;
             let txid_str = txid.to_string();
 
-            let transfer_info_path = self
-                .static_state
-                .ldk_data_dir
-                .join(format!("{txid_str}_transfer_info"));
-            if !transfer_info_path.exists() {
+            let Some((contract_id, amt_rgb)) =
+                close_output_allocation(&self.static_state.ldk_data_dir, &txid)
+            else {
                 continue;
             };
-            let transfer_info = read_rgb_transfer_info(&transfer_info_path);
-            if transfer_info.rgb_amount == 0 {
-                continue;
-            }
 
             vanilla_descriptor = false;
 
@@ -1348,8 +5598,6 @@ impl OutputSpender for RgbOutputSpender {
                 return Err(());
             }
 
-            let contract_id = transfer_info.contract_id;
-
             let mut new_asset = false;
             let recipient_id = if let Some((_, _, recipient_id)) = asset_info.get(&contract_id) {
                 recipient_id.clone()
@@ -1375,8 +5623,6 @@ impl OutputSpender for RgbOutputSpender {
                 receive_data.recipient_id
             };
 
-            let amt_rgb = transfer_info.rgb_amount;
-
             asset_info
                 .entry(contract_id)
                 .and_modify(|(_, a, _)| {
@@ -1394,13 +5640,17 @@ impl OutputSpender for RgbOutputSpender {
                 descriptors.as_ref(),
                 txouts,
                 change_destination_script,
-                feerate_sat_per_1000_weight,
+                sweep_config
+                    .fee_rate
+                    .map(|r| r as u32 * 250)
+                    .unwrap_or(feerate_sat_per_1000_weight),
                 locktime,
                 secp_ctx,
             );
         }
 
-        let feerate_sat_per_1000_weight = FEE_RATE as u32 * 250; // 1 sat/vB = 250 sat/kw
+        let fee_rate = sweep_config.fee_rate.unwrap_or(FEE_RATE);
+        let feerate_sat_per_1000_weight = fee_rate as u32 * 250; // 1 sat/vB = 250 sat/kw
         let (psbt, _expected_max_weight) =
             SpendableOutputDescriptor::create_spendable_outputs_psbt(
                 secp_ctx,
@@ -1489,7 +5739,7 @@ impl OutputSpender for RgbOutputSpender {
         }
 
         txes.insert(descriptors_hash, spending_tx.clone());
-        self.fs_store
+        self.kv_store
             .write("", "", OUTPUT_SPENDER_TXES, txes.encode())
             .unwrap();
 
@@ -1497,6 +5747,209 @@ impl OutputSpender for RgbOutputSpender {
     }
 }
 
+/// Scan the blocks mined since the last scan for the chain subscriptions
+async fn scan_chain_subscriptions(unlocked_state: Arc<UnlockedAppState>) {
+    let Some(mut height) = unlocked_state.chain_scan_start_height() else {
+        return;
+    };
+    while let Some(block) = unlocked_state
+        .bitcoind_client
+        .get_block_at_height(height)
+        .await
+    {
+        unlocked_state.scan_chain_block(&block, height);
+        height += 1;
+    }
+}
+
+/// Top up the free colorable UTXOs when they drop below the pool threshold, returning how many
+/// UTXOs have been created
+async fn maintain_utxo_pool(
+    unlocked_state: Arc<UnlockedAppState>,
+    target_utxos: u8,
+    min_utxos: u8,
+    utxo_size_sat: u32,
+) -> Result<u8, APIError> {
+    tokio::task::spawn_blocking(move || {
+        let locked_utxos = unlocked_state.locked_utxos();
+        let free_utxos = unlocked_state
+            .rgb_list_unspents(false)?
+            .iter()
+            .filter(|u| {
+                u.utxo.colorable
+                    && u.rgb_allocations.is_empty()
+                    && !locked_utxos.contains_key(&u.utxo.outpoint.to_string())
+            })
+            .count();
+        if free_utxos >= min_utxos as usize {
+            return Ok(0);
+        }
+        let missing = target_utxos.saturating_sub(free_utxos as u8);
+        Ok(unlocked_state.rgb_create_utxos(false, missing, utxo_size_sat, FEE_RATE, true)?)
+    })
+    .await
+    .unwrap()
+}
+
+/// Drop the spent anchor reserve UTXOs and create the missing ones
+async fn maintain_anchor_reserve(
+    unlocked_state: Arc<UnlockedAppState>,
+    network: Network,
+    target_utxos: u8,
+    utxo_size_sat: u64,
+) {
+    let reserve_outpoints: Vec<String> = unlocked_state
+        .get_anchor_reserve()
+        .utxos
+        .keys()
+        .cloned()
+        .collect();
+    for outpoint in reserve_outpoints {
+        let Ok(o) = bitcoin::OutPoint::from_str(&outpoint) else {
+            continue;
+        };
+        // skip the check if bitcoind cannot be reached, the UTXO could still be unspent
+        if let Ok(None) = unlocked_state
+            .bitcoind_client
+            .get_txout_confirmations(&o.txid, o.vout)
+            .await
+        {
+            unlocked_state.remove_anchor_reserve_utxo(&outpoint);
+            unlocked_state.add_anchor_reserve_event(AnchorReserveEventKind::Spent, outpoint);
+        }
+    }
+
+    let reserve_script = unlocked_state
+        .keys_manager
+        .get_destination_script([0; 32])
+        .unwrap();
+    let reserve_address = Address::from_script(&reserve_script, network)
+        .unwrap()
+        .to_string();
+    let missing =
+        (target_utxos as usize).saturating_sub(unlocked_state.get_anchor_reserve().utxos.len());
+    for _ in 0..missing {
+        let state = Arc::clone(&unlocked_state);
+        let address = reserve_address.clone();
+        let script = reserve_script.clone();
+        let res = tokio::task::spawn_blocking(move || -> Result<String, String> {
+            let unsigned_psbt = state
+                .rgb_send_btc_begin(address, utxo_size_sat, FEE_RATE)
+                .map_err(|e| e.to_string())?;
+            let vout = Psbt::from_str(&unsigned_psbt)
+                .map_err(|e| e.to_string())?
+                .unsigned_tx
+                .output
+                .iter()
+                .position(|o| o.script_pubkey == script)
+                .ok_or_else(|| s!("reserve output not found"))?;
+            let signed_psbt = state
+                .rgb_sign_psbt(unsigned_psbt)
+                .map_err(|e| e.to_string())?;
+            let txid = state
+                .rgb_send_btc_end(signed_psbt)
+                .map_err(|e| e.to_string())?;
+            Ok(format!("{txid}:{vout}"))
+        })
+        .await
+        .unwrap();
+        match res {
+            Ok(outpoint) => {
+                unlocked_state.add_anchor_reserve_utxo(outpoint.clone(), utxo_size_sat);
+                unlocked_state.add_anchor_reserve_event(AnchorReserveEventKind::Created, outpoint);
+            }
+            Err(e) => {
+                // avoid flooding the log while the reserve is depleted
+                if !unlocked_state.get_anchor_reserve().depleted {
+                    unlocked_state
+                        .add_anchor_reserve_event(AnchorReserveEventKind::CreationFailed, e);
+                }
+                break;
+            }
+        }
+    }
+
+    let (reserve_len, depleted) = {
+        let anchor_reserve = unlocked_state.get_anchor_reserve();
+        (anchor_reserve.utxos.len(), anchor_reserve.depleted)
+    };
+    if reserve_len == 0 && !depleted {
+        tracing::warn!("Anchor reserve is depleted, anchor channels may fail to bump fees");
+        unlocked_state.add_anchor_reserve_event(AnchorReserveEventKind::Depleted, s!(""));
+    } else if depleted && reserve_len >= target_utxos as usize {
+        unlocked_state.add_anchor_reserve_event(
+            AnchorReserveEventKind::Replenished,
+            format!("{reserve_len} UTXOs available"),
+        );
+    }
+}
+
+/// Upload a new snapshot to the automatic backup target if the node data changed since the
+/// previous one, then delete the snapshots older than the full ones to keep. Returns the manifest
+/// of the uploaded snapshot, if any.
+async fn save_auto_backup(
+    unlocked_state: Arc<UnlockedAppState>,
+    static_state: Arc<StaticState>,
+    target: &AutoBackupTarget,
+    previous: Option<AutoBackupManifest>,
+    full: bool,
+) -> Result<Option<AutoBackupManifest>, APIError> {
+    let snapshot = SnapshotName {
+        timestamp: get_current_timestamp(),
+        full,
+    };
+    let snapshot_name = snapshot.to_string();
+    let staging_dir = tempfile::tempdir_in(&static_state.storage_dir_path)?;
+    let snapshot_path = staging_dir.path().join(&snapshot_name);
+    let keep = static_state.auto_backup_keep as usize;
+    let hook_runner = Arc::clone(&unlocked_state.hook_runner);
+    let written = {
+        let snapshot_path = snapshot_path.clone();
+        tokio::task::spawn_blocking(move || {
+            unlocked_state.write_auto_backup_snapshot(
+                &static_state,
+                &snapshot,
+                &snapshot_path,
+                previous.as_ref(),
+            )
+        })
+        .await
+        .unwrap()?
+    };
+    let Some((manifest, num_channels)) = written else {
+        return Ok(None);
+    };
+    target.upload(&snapshot_path, &snapshot_name).await?;
+
+    let mut snapshots = target
+        .list()
+        .await?
+        .iter()
+        .filter_map(|name| SnapshotName::parse(name))
+        .collect::<Vec<_>>();
+    snapshots.sort_unstable();
+    let full_timestamps = snapshots
+        .iter()
+        .filter(|s| s.full)
+        .map(|s| s.timestamp)
+        .collect::<Vec<_>>();
+    if full_timestamps.len() > keep {
+        // incremental snapshots are only needed along with the full one they follow
+        let oldest_kept = full_timestamps[full_timestamps.len() - keep];
+        for old_snapshot in snapshots.iter().filter(|s| s.timestamp < oldest_kept) {
+            target.delete(&old_snapshot.to_string()).await?;
+        }
+    }
+
+    let location = target.location(&snapshot_name);
+    tracing::info!("Saved automatic backup {location} with {num_channels} channels");
+    hook_runner.trigger(HookEvent::BackupSaved {
+        path: location,
+        num_channels,
+    });
+    Ok(Some(manifest))
+}
+
 pub(crate) async fn start_ldk(
     app_state: Arc<AppState>,
     mnemonic: Mnemonic,
@@ -1509,12 +5962,44 @@ pub(crate) async fn start_ldk(
     let logger = static_state.logger.clone();
     let bitcoin_network = static_state.network;
     let network: Network = bitcoin_network.into();
+    let network_params = get_network_params(bitcoin_network);
     let ldk_peer_listening_port = static_state.ldk_peer_listening_port;
 
+    // Without going through Tor, only endpoints that need no DNS resolution can be used when the
+    // node has been asked not to leak DNS queries
+    if static_state
+        .tor_proxy
+        .as_ref()
+        .is_some_and(|p| p.no_clearnet_dns)
+    {
+        let endpoints = [
+            unlock_request.bitcoind_rpc_host.as_str(),
+            unlock_request
+                .indexer_url
+                .as_deref()
+                .unwrap_or(network_params.indexer_url),
+            unlock_request
+                .proxy_endpoint
+                .as_deref()
+                .unwrap_or(network_params.proxy_endpoint),
+        ];
+        let fallback_endpoints = unlock_request
+            .fallback_indexer_urls
+            .iter()
+            .chain(unlock_request.fallback_proxy_endpoints.iter())
+            .flatten()
+            .map(|e| e.as_str());
+        for endpoint in endpoints.into_iter().chain(fallback_endpoints) {
+            check_no_clearnet_dns(endpoint).map_err(APIError::ClearnetDnsRequired)?;
+        }
+    }
+
     // Initialize our bitcoind client.
     let bitcoind_client = match BitcoindClient::new(
         unlock_request.bitcoind_rpc_host.clone(),
-        unlock_request.bitcoind_rpc_port,
+        unlock_request
+            .bitcoind_rpc_port
+            .unwrap_or(network_params.bitcoind_rpc_port),
         unlock_request.bitcoind_rpc_username.clone(),
         unlock_request.bitcoind_rpc_password.clone(),
         tokio::runtime::Handle::current(),
@@ -1530,52 +6015,85 @@ pub(crate) async fn start_ldk(
 
     // Check that the bitcoind we've connected to is running the network we expect
     let bitcoind_chain = bitcoind_client.get_blockchain_info().await.chain;
-    if bitcoind_chain
-        != match bitcoin_network {
-            BitcoinNetwork::Mainnet => "main",
-            BitcoinNetwork::Testnet => "test",
-            BitcoinNetwork::Testnet4 => "testnet4",
-            BitcoinNetwork::Regtest => "regtest",
-            BitcoinNetwork::Signet => "signet",
-        }
-    {
+    if bitcoind_chain != network_params.bitcoind_chain {
         return Err(APIError::NetworkMismatch(bitcoind_chain, bitcoin_network));
     }
 
     // RGB setup
+    let has_fallback_indexers = unlock_request
+        .fallback_indexer_urls
+        .as_ref()
+        .is_some_and(|u| !u.is_empty());
     let indexer_url = if let Some(indexer_url) = &unlock_request.indexer_url {
-        let indexer_protocol = check_indexer_url(indexer_url, bitcoin_network)?;
-        tracing::info!(
-            "Connected to an indexer with the {} protocol",
-            indexer_protocol
-        );
+        match check_indexer_url(indexer_url, bitcoin_network) {
+            Ok(indexer_protocol) => tracing::info!(
+                "Connected to an indexer with the {} protocol",
+                indexer_protocol
+            ),
+            // the first fallback indexer that answers will be used instead
+            Err(e) if has_fallback_indexers => {
+                tracing::warn!("Indexer {indexer_url} is unusable: {e}")
+            }
+            Err(e) => return Err(e.into()),
+        }
         indexer_url
     } else {
         tracing::info!("Using the default indexer");
-        match bitcoin_network {
-            BitcoinNetwork::Regtest => ELECTRUM_URL_REGTEST,
-            BitcoinNetwork::Signet => ELECTRUM_URL_SIGNET,
-            BitcoinNetwork::Testnet => ELECTRUM_URL_TESTNET,
-            BitcoinNetwork::Testnet4 => ELECTRUM_URL_TESTNET4,
-            BitcoinNetwork::Mainnet => ELECTRUM_URL_MAINNET,
-        }
+        network_params.indexer_url
     };
+    let mut indexer_urls = vec![indexer_url.to_string()];
+    for fallback_indexer_url in unlock_request.fallback_indexer_urls.iter().flatten() {
+        if indexer_urls.contains(fallback_indexer_url) {
+            continue;
+        }
+        indexer_urls.push(fallback_indexer_url.clone());
+    }
+    if indexer_urls.len() > MAX_INDEXER_URLS {
+        return Err(APIError::InvalidIndexer(format!(
+            "at most {MAX_INDEXER_URLS} indexers are supported"
+        )));
+    }
+    // a fallback indexer being down is what it's there for, so it doesn't prevent unlocking
+    for fallback_indexer_url in &indexer_urls[1..] {
+        if let Err(e) = check_indexer_url(fallback_indexer_url, bitcoin_network) {
+            tracing::warn!("Fallback indexer {fallback_indexer_url} is unusable: {e}");
+        }
+    }
+    if indexer_urls.len() > 1 {
+        tracing::info!("Using {} fallback indexers", indexer_urls.len() - 1);
+    }
     let proxy_endpoint = if let Some(proxy_endpoint) = &unlock_request.proxy_endpoint {
         check_rgb_proxy_endpoint(proxy_endpoint).await?;
         tracing::info!("Using a custom proxy");
         proxy_endpoint
     } else {
         tracing::info!("Using the default proxy");
-        match bitcoin_network {
-            BitcoinNetwork::Signet
-            | BitcoinNetwork::Testnet
-            | BitcoinNetwork::Testnet4
-            | BitcoinNetwork::Mainnet => PROXY_ENDPOINT_PUBLIC,
-            BitcoinNetwork::Regtest => PROXY_ENDPOINT_LOCAL,
-        }
+        network_params.proxy_endpoint
     };
+    let mut proxy_endpoints = vec![proxy_endpoint.to_string()];
+    for fallback_proxy_endpoint in unlock_request.fallback_proxy_endpoints.iter().flatten() {
+        if proxy_endpoints.contains(fallback_proxy_endpoint) {
+            continue;
+        }
+        TransportEndpoint::new(fallback_proxy_endpoint.clone())
+            .map_err(|_| APIError::InvalidProxyEndpoint)?;
+        proxy_endpoints.push(fallback_proxy_endpoint.clone());
+    }
+    if proxy_endpoints.len() > MAX_PROXY_ENDPOINTS {
+        return Err(APIError::InvalidTransportEndpoints(format!(
+            "at most {MAX_PROXY_ENDPOINTS} proxy endpoints are supported"
+        )));
+    }
+    // a fallback proxy being down is what it's there for, so it doesn't prevent unlocking
+    for fallback_proxy_endpoint in &proxy_endpoints[1..] {
+        if let Err(e) = check_rgb_proxy_endpoint(fallback_proxy_endpoint).await {
+            tracing::warn!("Fallback proxy {fallback_proxy_endpoint} is unreachable: {e}");
+        }
+    }
+    if proxy_endpoints.len() > 1 {
+        tracing::info!("Using {} fallback proxies", proxy_endpoints.len() - 1);
+    }
     let storage_dir_path = app_state.static_state.storage_dir_path.clone();
-    fs::write(storage_dir_path.join(INDEXER_URL_FNAME), indexer_url).expect("able to write");
     fs::write(
         storage_dir_path.join(BITCOIN_NETWORK_FNAME),
         bitcoin_network.to_string(),
@@ -1594,21 +6112,50 @@ pub(crate) async fn start_ldk(
     // Initialize the KeysManager
     // The key seed that we use to derive the node privkey (that corresponds to the node pubkey) and
     // other secret key material.
-    let xkey: ExtendedKey = mnemonic
-        .clone()
+    // The passphrase and account index set on init only apply to it, as rgb-lib derives the
+    // wallet keys from the bare mnemonic
+    let ldk_key_derivation = read_ldk_key_derivation(&static_state.storage_dir_path, &mnemonic)?;
+    let xkey: ExtendedKey = (
+        mnemonic.clone(),
+        Some(ldk_key_derivation.passphrase.to_string()),
+    )
         .into_extended_key()
         .expect("a valid key should have been provided");
-    let master_xprv = &xkey
+    let mut master_xprv = xkey
         .into_xprv(network)
         .expect("should be possible to get an extended private key");
-    let xprv: Xpriv = master_xprv
-        .derive_priv(&Secp256k1_30::new(), &ChildNumber::Hardened { index: 535 })
+    let mut ldk_seed_path = vec![ChildNumber::Hardened { index: 535 }];
+    if ldk_key_derivation.account_index != 0 {
+        ldk_seed_path.push(
+            ChildNumber::from_hardened_idx(ldk_key_derivation.account_index)
+                .expect("valid LDK account index"),
+        );
+    }
+    let mut xprv: Xpriv = master_xprv
+        .derive_priv(&Secp256k1_30::new(), &ldk_seed_path)
         .unwrap();
-    let ldk_seed: [u8; 32] = xprv.private_key.secret_bytes();
+    // local copies of the key material are wiped when dropped, the extended keys right away
+    let ldk_seed = Zeroizing::new(xprv.private_key.secret_bytes());
+    master_xprv.private_key.non_secure_erase();
+    xprv.private_key.non_secure_erase();
     let cur = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap();
-    let keys_manager = Arc::new(KeysManager::new(
+    // nodes sharing the phantom secret can all settle phantom invoices, the others get a phantom
+    // node of their own
+    let cross_node_seed = Zeroizing::new(static_state.phantom_secret.unwrap_or_else(|| {
+        sha256::Hash::hash(&[&ldk_seed[..], b"phantom"].concat()).to_byte_array()
+    }));
+    let keys_manager = Arc::new(PhantomKeysManager::new(
+        &ldk_seed,
+        cur.as_secs(),
+        cur.subsec_nanos(),
+        &cross_node_seed,
+        true,
+        ldk_data_dir_path.clone(),
+    ));
+    // only signs the outputs swept to the wallet, which need the inner KeysManager
+    let wallet_keys_manager = Arc::new(KeysManager::new(
         &ldk_seed,
         cur.as_secs(),
         cur.subsec_nanos(),
@@ -1617,9 +6164,19 @@ pub(crate) async fn start_ldk(
     ));
 
     // Initialize Persistence
-    let fs_store = Arc::new(FilesystemStore::new(ldk_data_dir.clone()));
+    let kv_store = Arc::new(
+        NodeStore::open(
+            &ldk_data_dir,
+            static_state.storage_backend,
+            static_state.remote_storage_url.as_deref(),
+            static_state
+                .encrypt_storage
+                .then(|| storage_cipher(&mnemonic)),
+        )
+        .await?,
+    );
     let persister = Arc::new(MonitorUpdatingPersister::new(
-        Arc::clone(&fs_store),
+        Arc::clone(&kv_store),
         Arc::clone(&logger),
         1000,
         Arc::clone(&keys_manager),
@@ -1648,16 +6205,10 @@ pub(crate) async fn start_ldk(
         .expect("Failed to fetch best block header and best block");
 
     // Initialize routing ProbabilisticScorer
-    let network_graph_path = ldk_data_dir.join("network_graph");
-    let network_graph = Arc::new(disk::read_network(
-        &network_graph_path,
-        network,
-        logger.clone(),
-    ));
+    let network_graph = Arc::new(disk::read_network(&kv_store, network, logger.clone()));
 
-    let scorer_path = ldk_data_dir.join("scorer");
     let scorer = Arc::new(RwLock::new(disk::read_scorer(
-        &scorer_path,
+        &kv_store,
         Arc::clone(&network_graph),
         Arc::clone(&logger),
     )));
@@ -1685,9 +6236,17 @@ pub(crate) async fn start_ldk(
         .channel_handshake_config
         .negotiate_anchors_zero_fee_htlc_tx = true;
     user_config.manually_accept_inbound_channels = true;
+    // HTLCs to JIT channels sold as an LSP are held until the channel gets opened
+    user_config.accept_intercept_htlcs = true;
+    user_config.channel_config.cltv_expiry_delta = static_state.cltv_expiry_delta;
+    user_config.channel_handshake_config.minimum_depth = static_state.min_channel_depth;
     let mut restarting_node = true;
     let (channel_manager_blockhash, channel_manager) = {
-        if let Ok(f) = fs::File::open(ldk_data_dir.join("manager")) {
+        if let Ok(bytes) = kv_store.read(
+            CHANNEL_MANAGER_PERSISTENCE_PRIMARY_NAMESPACE,
+            CHANNEL_MANAGER_PERSISTENCE_SECONDARY_NAMESPACE,
+            CHANNEL_MANAGER_PERSISTENCE_KEY,
+        ) {
             let mut channel_monitor_references = Vec::new();
             for (_, channel_monitor) in channelmonitors.iter() {
                 channel_monitor_references.push(channel_monitor);
@@ -1706,7 +6265,7 @@ pub(crate) async fn start_ldk(
                 channel_monitor_references,
                 ldk_data_dir_path.clone(),
             );
-            <(BlockHash, ChannelManager)>::read(&mut BufReader::new(f), read_args).unwrap()
+            <(BlockHash, ChannelManager)>::read(&mut io::Cursor::new(bytes), read_args).unwrap()
         } else {
             // We're starting a fresh node.
             restarting_node = false;
@@ -1737,7 +6296,7 @@ pub(crate) async fn start_ldk(
     };
 
     // Prepare the RGB wallet
-    let mnemonic_str = mnemonic.to_string();
+    let mnemonic_str = Zeroizing::new(mnemonic.to_string());
     let (_, account_xpub_vanilla, _) =
         get_account_data(bitcoin_network, &mnemonic_str, false).unwrap();
     let (_, account_xpub_colored, master_fingerprint) =
@@ -1758,13 +6317,35 @@ pub(crate) async fn start_ldk(
             master_fingerprint: master_fingerprint.to_string(),
             mnemonic: Some(mnemonic.to_string()),
             vanilla_keychain: None,
-            supported_schemas: vec![AssetSchema::Nia, AssetSchema::Cfa, AssetSchema::Uda],
+            supported_schemas: vec![
+                AssetSchema::Nia,
+                AssetSchema::Cfa,
+                AssetSchema::Uda,
+                AssetSchema::Ifa,
+            ],
         })
         .expect("valid rgb-lib wallet")
     })
     .await
     .unwrap();
-    let rgb_online = rgb_wallet.go_online(false, indexer_url.to_string())?;
+    app_state.update_sync_stage(SyncStage::SyncingWallet);
+    // go online with the first indexer that answers, by priority
+    let mut indexer_urls_left = indexer_urls.iter();
+    let rgb_online = loop {
+        let indexer_url = indexer_urls_left.next().expect("at least one indexer");
+        match rgb_wallet.go_online(false, indexer_url.clone()) {
+            Ok(online) => break online,
+            Err(e) if indexer_urls_left.len() > 0 => {
+                tracing::warn!("Cannot go online with indexer {indexer_url}: {e}")
+            }
+            Err(e) => return Err(e.into()),
+        }
+    };
+    fs::write(
+        static_state.storage_dir_path.join(INDEXER_URL_FNAME),
+        &rgb_online.indexer_url,
+    )
+    .expect("able to write");
     fs::write(
         static_state.storage_dir_path.join(WALLET_FINGERPRINT_FNAME),
         account_xpub_colored.fingerprint().to_string(),
@@ -1795,21 +6376,28 @@ pub(crate) async fn start_ldk(
     let rgb_wallet_wrapper = Arc::new(RgbLibWalletWrapper::new(
         Arc::new(Mutex::new(rgb_wallet)),
         rgb_online.clone(),
+        indexer_urls,
     ));
 
     // Initialize the OutputSweeper.
     let txes = Arc::new(Mutex::new(disk::read_output_spender_txes(
-        &ldk_data_dir.join(OUTPUT_SPENDER_TXES),
+        &kv_store,
+        OUTPUT_SPENDER_TXES,
+    )));
+    let sweep_config = Arc::new(Mutex::new(disk::read_sweep_config_info(
+        &kv_store,
+        SWEEP_CONFIG_FNAME,
     )));
     let rgb_output_spender = Arc::new(RgbOutputSpender {
         static_state: static_state.clone(),
         rgb_wallet_wrapper: rgb_wallet_wrapper.clone(),
-        keys_manager: keys_manager.clone(),
-        fs_store: fs_store.clone(),
+        keys_manager: Arc::clone(&wallet_keys_manager),
+        kv_store: kv_store.clone(),
         txes,
+        sweep_config: Arc::clone(&sweep_config),
         proxy_endpoint: proxy_endpoint.to_string(),
     });
-    let (sweeper_best_block, output_sweeper) = match fs_store.read(
+    let (sweeper_best_block, output_sweeper) = match kv_store.read(
         OUTPUT_SWEEPER_PERSISTENCE_PRIMARY_NAMESPACE,
         OUTPUT_SWEEPER_PERSISTENCE_SECONDARY_NAMESPACE,
         OUTPUT_SWEEPER_PERSISTENCE_KEY,
@@ -1822,7 +6410,7 @@ pub(crate) async fn start_ldk(
                 None,
                 rgb_output_spender,
                 rgb_wallet_wrapper.clone(),
-                fs_store.clone(),
+                kv_store.clone(),
                 logger.clone(),
             );
             (channel_manager.current_best_block(), sweeper)
@@ -1834,7 +6422,7 @@ pub(crate) async fn start_ldk(
                 None,
                 rgb_output_spender.clone(),
                 rgb_wallet_wrapper.clone(),
-                fs_store.clone(),
+                kv_store.clone(),
                 logger.clone(),
             );
             let mut reader = io::Cursor::new(&mut bytes);
@@ -1844,10 +6432,12 @@ pub(crate) async fn start_ldk(
         Err(e) => panic!("Failed to read OutputSweeper with {e}"),
     };
 
+    app_state.update_sync_stage(SyncStage::SyncingChain);
     // Sync ChannelMonitors, ChannelManager and OutputSweeper to chain tip
     let mut chain_listener_channel_monitors = Vec::new();
     let mut cache = UnboundedCache::new();
-    let chain_tip = if restarting_node {
+    // monitors restored from a static channel backup need to be synced even on a fresh node
+    let chain_tip = if restarting_node || !channelmonitors.is_empty() {
         let mut chain_listeners = vec![
             (
                 channel_manager_blockhash,
@@ -1907,6 +6497,8 @@ pub(crate) async fn start_ldk(
         polled_chain_tip
     };
 
+    app_state.update_sync_stage(SyncStage::StartingServices);
+
     // Give ChannelMonitors to ChainMonitor
     for (_, (channel_monitor, _, _, _), _) in chain_listener_channel_monitors {
         let channel_id = channel_monitor.channel_id();
@@ -1923,6 +6515,22 @@ pub(crate) async fn start_ldk(
         Arc::clone(&logger),
     ));
 
+    // When an RGS server is configured the graph is synced from its snapshots instead of P2P
+    let rapid_gossip_sync = static_state.rgs_url.as_ref().map(|_| {
+        Arc::new(LdkRapidGossipSync::new(
+            Arc::clone(&network_graph),
+            Arc::clone(&logger),
+        ))
+    });
+
+    // Account and throttle the gossip received from peers
+    let gossip_handler = Arc::new(GossipHandler::new(
+        Arc::clone(&gossip_sync),
+        rapid_gossip_sync.is_none(),
+        app_state.get_runtime_config().gossip_bandwidth_cap,
+        disk::read_gossip_limits_info(&kv_store, GOSSIP_LIMITS_FNAME),
+    ));
+
     // Initialize an OMDomainResolver as a service to other nodes.
     // As a service to other LDK users, using an `OMDomainResolver` allows others to resolve BIP
     // 353 Human Readable Names for others, providing them DNSSEC proofs over lightning onion
@@ -1953,11 +6561,49 @@ pub(crate) async fn start_ldk(
         .unwrap()
         .as_secs();
     rand::thread_rng().fill_bytes(&mut ephemeral_bytes);
+    let lsp_promise_key = sha256::Hash::hash(
+        &[
+            &keys_manager.get_node_secret_key().secret_bytes()[..],
+            b"lsps2-promise",
+        ]
+        .concat(),
+    )
+    .to_byte_array();
+    let lsps_handler = Arc::new(LspsMessageHandler::new(
+        Arc::clone(&channel_manager),
+        Arc::clone(&kv_store),
+        lsp_promise_key,
+        static_state.cltv_expiry_delta,
+        disk::read_lsp_config_info(&kv_store, LSP_CONFIG_FNAME),
+        disk::read_lsp_clients_info(&kv_store, LSP_CLIENTS_FNAME),
+        disk::read_lease_config_info(&kv_store, LEASE_CONFIG_FNAME),
+        disk::read_lease_orders_info(&kv_store, LEASE_ORDERS_FNAME),
+    ));
+    let peer_filter = Arc::new(Mutex::new(disk::read_peer_filter_info(
+        &kv_store,
+        PEER_FILTER_FNAME,
+    )));
+    let asset_htlc_min_handler = Arc::new(AssetHtlcMinHandler::new(
+        Arc::clone(&channel_manager),
+        Arc::clone(&kv_store),
+        disk::read_asset_htlc_minimums_info(&kv_store, ASSET_HTLC_MINIMUMS_FNAME),
+        static_state.asset_htlc_min_msat,
+    ));
+    let totp_verifier = Arc::new(TotpVerifier::new(disk::read_totp_info(
+        &kv_store, TOTP_FNAME,
+    )));
+    let peer_connected_at = Arc::new(Mutex::new(HashMap::new()));
     let lightning_msg_handler = MessageHandler {
         chan_handler: channel_manager.clone(),
-        route_handler: gossip_sync.clone(),
+        route_handler: gossip_handler.clone(),
         onion_message_handler: onion_messenger.clone(),
-        custom_message_handler: IgnoringMessageHandler {},
+        custom_message_handler: Arc::new(PeerMessageHandler {
+            asset_htlc_min_handler: Arc::clone(&asset_htlc_min_handler),
+            lsps_handler: Arc::clone(&lsps_handler),
+            network_graph: Arc::clone(&network_graph),
+            peer_filter: Arc::clone(&peer_filter),
+            peer_connected_at: Arc::clone(&peer_connected_at),
+        }),
         send_only_message_handler: Arc::clone(&chain_monitor),
     };
     let peer_manager: Arc<PeerManager> = Arc::new(PeerManager::new(
@@ -1984,6 +6630,8 @@ pub(crate) async fn start_ldk(
     let listening_port = ldk_peer_listening_port;
     let stop_processing = Arc::new(AtomicBool::new(false));
     let stop_listen = Arc::clone(&stop_processing);
+    let peer_filter_listener = Arc::clone(&peer_filter);
+    let tor_manager_listener = Arc::clone(&app_state.tor_connection_manager);
     tokio::spawn(async move {
         let listener = tokio::net::TcpListener::bind(format!("[::]:{listening_port}"))
             .await
@@ -1994,12 +6642,27 @@ pub(crate) async fn start_ldk(
             if stop_listen.load(Ordering::Acquire) {
                 return;
             }
+            let mut onion_permit = None;
+            if let Ok(addr) = tcp_stream.peer_addr() {
+                if peer_filter_listener.lock().unwrap().is_ip_denied(addr.ip()) {
+                    tracing::info!("Dropping inbound connection from denied address {addr}");
+                    continue;
+                }
+                // the local Tor daemon forwards the onion service connections from loopback
+                if addr.ip().to_canonical().is_loopback() {
+                    match tor_manager_listener.admit_inbound() {
+                        Some(permit) => onion_permit = Some(permit),
+                        None => continue,
+                    }
+                }
+            }
             tokio::spawn(async move {
                 lightning_net_tokio::setup_inbound(
                     peer_mgr.clone(),
                     tcp_stream.into_std().unwrap(),
                 )
                 .await;
+                drop(onion_permit);
             });
         }
     });
@@ -2029,36 +6692,224 @@ pub(crate) async fn start_ldk(
         }
     });
 
-    let inbound_payments = Arc::new(Mutex::new(disk::read_inbound_payment_info(
-        &ldk_data_dir.join(INBOUND_PAYMENTS_FNAME),
+    let inbound_payments = Arc::new(Mutex::new(disk::read_inbound_payment_info(
+        &kv_store,
+        INBOUND_PAYMENTS_FNAME,
+    )));
+    let outbound_payments = Arc::new(Mutex::new(disk::read_outbound_payment_info(
+        &kv_store,
+        OUTBOUND_PAYMENTS_FNAME,
+    )));
+
+    // Read anchor reserve info
+    let anchor_reserve = Arc::new(Mutex::new(disk::read_anchor_reserve_info(
+        &kv_store,
+        ANCHOR_RESERVE_FNAME,
+    )));
+
+    let anchor_wallet_source = Arc::new(AnchorWalletSource {
+        rgb_wallet_wrapper: rgb_wallet_wrapper.clone(),
+        keys_manager: wallet_keys_manager,
+        bitcoind_client: Arc::clone(&bitcoind_client),
+        anchor_reserve: Arc::clone(&anchor_reserve),
+    });
+    let bump_tx_event_handler = Arc::new(BumpTransactionEventHandler::new(
+        Arc::clone(&broadcaster),
+        Arc::new(Wallet::new(anchor_wallet_source, Arc::clone(&logger))),
+        Arc::clone(&keys_manager),
+        Arc::clone(&logger),
+    ));
+
+    // Read swaps info
+    let maker_swaps = Arc::new(Mutex::new(disk::read_swaps_info(
+        &kv_store,
+        MAKER_SWAPS_FNAME,
+    )));
+    let taker_swaps = Arc::new(Mutex::new(disk::read_swaps_info(
+        &kv_store,
+        TAKER_SWAPS_FNAME,
+    )));
+
+    // Read channel IDs info
+    let channel_ids_map = Arc::new(Mutex::new(disk::read_channel_ids_info(
+        &kv_store,
+        CHANNEL_IDS_FNAME,
+    )));
+
+    // Read HODL invoices info
+    let hodl_invoices = Arc::new(Mutex::new(disk::read_hodl_invoices_info(
+        &kv_store,
+        HODL_INVOICES_FNAME,
+    )));
+
+    // Read JIT channels info
+    let settlements = Arc::new(Mutex::new(disk::read_settlements_info(
+        &kv_store,
+        SETTLEMENTS_FNAME,
+    )));
+    let submarine_swaps = Arc::new(Mutex::new(disk::read_submarine_swaps_info(
+        &kv_store,
+        SUBMARINE_SWAPS_FNAME,
+    )));
+    let hedging_policies = Arc::new(Mutex::new(disk::read_hedging_policies_info(
+        &kv_store,
+        HEDGING_POLICIES_FNAME,
+    )));
+    let hedges = Arc::new(Mutex::new(disk::read_hedges_info(&kv_store, HEDGES_FNAME)));
+    let channel_acceptor = Arc::new(Mutex::new(disk::read_channel_acceptor_info(
+        &kv_store,
+        CHANNEL_ACCEPTOR_FNAME,
+    )));
+    let channel_depths = Arc::new(Mutex::new(disk::read_channel_depths_info(
+        &kv_store,
+        CHANNEL_DEPTHS_FNAME,
+    )));
+    let jit_channels = Arc::new(Mutex::new(disk::read_jit_channels_info(
+        &kv_store,
+        JIT_CHANNELS_FNAME,
+    )));
+    let amount_bounds = Arc::new(Mutex::new(disk::read_amount_bounds_info(
+        &kv_store,
+        AMOUNT_BOUNDS_FNAME,
+    )));
+    let fallback_addresses = Arc::new(Mutex::new(disk::read_fallback_addresses_info(
+        &kv_store,
+        FALLBACK_ADDRESSES_FNAME,
+    )));
+    let asset_policies = Arc::new(Mutex::new(disk::read_asset_policies_info(
+        &kv_store,
+        ASSET_POLICIES_FNAME,
+    )));
+
+    // Read LNURL-pay info
+    let lnurl_pay = Arc::new(Mutex::new(disk::read_lnurl_pay_info(
+        &kv_store,
+        LNURL_PAY_FNAME,
+    )));
+
+    // Read NWC connections info
+    let nwc_connections = Arc::new(Mutex::new(disk::read_nwc_connections_info(
+        &kv_store,
+        NWC_CONNECTIONS_FNAME,
     )));
-    let outbound_payments = Arc::new(Mutex::new(disk::read_outbound_payment_info(
-        &ldk_data_dir.join(OUTBOUND_PAYMENTS_FNAME),
+
+    // Read token spending info
+    let token_spending = Arc::new(Mutex::new(disk::read_token_spending_info(
+        &kv_store,
+        TOKEN_SPENDING_FNAME,
     )));
 
-    let bump_tx_event_handler = Arc::new(BumpTransactionEventHandler::new(
-        Arc::clone(&broadcaster),
-        Arc::new(Wallet::new(rgb_wallet_wrapper.clone(), Arc::clone(&logger))),
-        Arc::clone(&keys_manager),
-        Arc::clone(&logger),
-    ));
+    // Read offers info
+    let offers = Arc::new(Mutex::new(disk::read_offers_info(&kv_store, OFFERS_FNAME)));
 
-    // Persist ChannelManager and NetworkGraph
-    let persister = Arc::new(FilesystemStore::new(ldk_data_dir_path.clone()));
+    // Read chain subscriptions info
+    let chain_subscriptions = Arc::new(Mutex::new(disk::read_chain_subscriptions_info(
+        &kv_store,
+        CHAIN_SUBSCRIPTIONS_FNAME,
+    )));
 
-    // Read swaps info
-    let maker_swaps = Arc::new(Mutex::new(disk::read_swaps_info(
-        &ldk_data_dir.join(MAKER_SWAPS_FNAME),
+    // Read intercept scopes info
+    let intercept_scopes = Arc::new(Mutex::new(disk::read_intercept_scopes_info(
+        &kv_store,
+        INTERCEPT_SCOPES_FNAME,
     )));
-    let taker_swaps = Arc::new(Mutex::new(disk::read_swaps_info(
-        &ldk_data_dir.join(TAKER_SWAPS_FNAME),
+
+    // Read journal info
+    let journal = Arc::new(Mutex::new(disk::read_journal_info(
+        &kv_store,
+        JOURNAL_FNAME,
     )));
 
-    // Read channel IDs info
-    let channel_ids_map = Arc::new(Mutex::new(disk::read_channel_ids_info(
-        &ldk_data_dir.join(CHANNEL_IDS_FNAME),
+    // Derive the emergency kit key from the unlock password
+    let password = unlock_request.password.clone();
+    let emergency_kit_key = tokio::task::spawn_blocking(move || derive_backup_key(&password))
+        .await
+        .unwrap()?;
+    let emergency_kit = Arc::new(Mutex::new(EmergencyKitState {
+        key: emergency_kit_key,
+        update_ids: None,
+        updated_at: 0,
+        num_channels: 0,
+    }));
+
+    // Read orders info
+    let orders = Arc::new(Mutex::new(disk::read_orders_info(&kv_store, ORDERS_FNAME)));
+
+    // Read escrows info
+    let escrows = Arc::new(Mutex::new(disk::read_escrows_info(
+        &kv_store,
+        ESCROWS_FNAME,
+    )));
+
+    // Read issued addresses info
+    let issued_addresses = Arc::new(Mutex::new(disk::read_issued_addresses_info(
+        &kv_store,
+        ISSUED_ADDRESSES_FNAME,
+    )));
+
+    // Read channel events info
+    let channel_events = Arc::new(Mutex::new(disk::read_channel_events_info(
+        &kv_store,
+        CHANNEL_EVENTS_FNAME,
+    )));
+
+    // Read forwards info
+    let forwards = Arc::new(Mutex::new(disk::read_forwards_info(
+        &kv_store,
+        FORWARDS_FNAME,
+    )));
+
+    // Read pending broadcasts info
+    let pending_broadcasts = Arc::new(Mutex::new(disk::read_pending_broadcasts_info(
+        &kv_store,
+        PENDING_BROADCASTS_FNAME,
+    )));
+
+    // Read locked UTXOs info
+    let locked_utxos = Arc::new(Mutex::new(disk::read_locked_utxos_info(
+        &kv_store,
+        LOCKED_UTXOS_FNAME,
     )));
 
+    // Parse node announcement data
+    let mut ldk_announced_listen_addr = Vec::new();
+    for addr in unlock_request.announce_addresses {
+        match SocketAddress::from_str(&addr) {
+            Ok(sa) => {
+                ldk_announced_listen_addr.push(sa);
+            }
+            Err(_) => {
+                return Err(APIError::InvalidAnnounceAddresses(format!(
+                    "failed to parse address '{addr}'"
+                )))
+            }
+        }
+    }
+    // The identity set at runtime, if any, prevails over the unlock request and the options
+    let mut node_announcement =
+        disk::read_node_announcement_info(&kv_store, NODE_ANNOUNCEMENT_FNAME);
+    if node_announcement.alias.is_none() {
+        node_announcement.alias = unlock_request
+            .announce_alias
+            .or_else(|| static_state.announce_alias.clone());
+    }
+    if let Some(alias) = &node_announcement.alias {
+        check_announce_alias(alias).map_err(APIError::InvalidAnnounceAlias)?;
+    }
+    if node_announcement.color.is_none() {
+        node_announcement.color = static_state.announce_color;
+    }
+    let node_announcement = Arc::new(Mutex::new(node_announcement));
+
+    // Run the configured command and publish the notifications on settlement events
+    let hook_runner = Arc::new(HookRunner::new(
+        app_state.get_runtime_config().hook_command.clone(),
+        static_state.storage_dir_path.join(HOOKS_DIR),
+        static_state.hook_timeout_sec,
+        static_state.hook_max_concurrency,
+        app_state.notification_publisher.clone(),
+    ));
+
     let unlocked_state = Arc::new(UnlockedAppState {
         channel_manager: Arc::clone(&channel_manager),
         inbound_payments,
@@ -2068,7 +6919,7 @@ pub(crate) async fn start_ldk(
         onion_messenger: onion_messenger.clone(),
         outbound_payments,
         peer_manager: Arc::clone(&peer_manager),
-        fs_store: Arc::clone(&fs_store),
+        kv_store: Arc::clone(&kv_store),
         bump_tx_event_handler,
         rgb_wallet_wrapper,
         maker_swaps,
@@ -2076,8 +6927,59 @@ pub(crate) async fn start_ldk(
         router: Arc::clone(&router),
         output_sweeper: Arc::clone(&output_sweeper),
         rgb_send_lock: Arc::new(Mutex::new(false)),
+        rgb_spend_lock: Arc::new(TokioMutex::new(())),
         channel_ids_map,
+        channel_events,
+        forwards,
+        hodl_invoices,
+        jit_channels,
+        amount_bounds,
+        fallback_addresses,
+        asset_policies,
+        lnurl_pay,
+        node_announcement,
+        nwc_connections,
+        token_spending,
+        channel_acceptor,
+        channel_depths,
+        peer_filter,
+        sweep_config,
+        pending_channels: Arc::new(Mutex::new(HashMap::new())),
+        offers,
+        orders,
+        escrows,
+        settlements,
+        submarine_swaps,
+        hedging_policies,
+        hedges,
+        issued_addresses,
+        pending_broadcasts,
+        locked_utxos,
+        anchor_reserve,
+        chain_subscriptions,
+        intercept_scopes,
+        intercepted_htlcs: Arc::new(Mutex::new(HashMap::new())),
+        journal,
+        gossip_handler,
+        lsps_handler,
+        asset_htlc_min_handler,
+        peer_connected_at,
+        totp_verifier,
+        hook_runner,
+        probes: Arc::new(Mutex::new(HashMap::new())),
+        probe_stats: Arc::new(Mutex::new(HashMap::new())),
+        force_close_feerates: Arc::new(Mutex::new(HashMap::new())),
+        multisig_fundings: Arc::new(Mutex::new(HashMap::new())),
+        external_fundings: Arc::new(Mutex::new(HashMap::new())),
+        funding_coin_selections: Arc::new(Mutex::new(HashMap::new())),
+        emergency_kit,
+        #[cfg(feature = "failure-injection")]
+        failure_injector: Arc::new(FailureInjector::default()),
+        bitcoind_client: Arc::clone(&bitcoind_client),
+        announced_addresses: ldk_announced_listen_addr,
         proxy_endpoint: proxy_endpoint.to_string(),
+        proxy_endpoints,
+        ldk_seed_derivation_path: ldk_key_derivation.seed_derivation_path(),
     });
 
     let recent_payments_payment_ids = channel_manager
@@ -2102,14 +7004,24 @@ pub(crate) async fn start_ldk(
     };
 
     // Background Processing
+    let bp_gossip_sync: GossipSync<
+        Arc<LdkGossipSync>,
+        Arc<LdkRapidGossipSync>,
+        Arc<NetworkGraph>,
+        Arc<GossipVerifier>,
+        Arc<FilesystemLogger>,
+    > = match &rapid_gossip_sync {
+        Some(rgs) => GossipSync::Rapid(Arc::clone(rgs)),
+        None => GossipSync::P2P(Arc::clone(&gossip_sync)),
+    };
     let (bp_exit, bp_exit_check) = tokio::sync::watch::channel(());
     let background_processor = tokio::spawn(process_events_async(
-        persister,
+        kv_store,
         event_handler,
         chain_monitor.clone(),
         channel_manager.clone(),
         Some(onion_messenger),
-        GossipSync::p2p(gossip_sync),
+        bp_gossip_sync,
         peer_manager.clone(),
         NO_LIQUIDITY_MANAGER,
         Some(Arc::clone(&output_sweeper)),
@@ -2134,34 +7046,101 @@ pub(crate) async fn start_ldk(
         },
     ));
 
-    // Regularly reconnect to channel peers.
+    // Regularly reconnect to channel peers, including the ones of channels restored from a static
+    // channel backup, which are expected to force-close them once reconnected. Peers whose address
+    // is unknown (e.g. the ones that opened a channel to us) are reached at the addresses they
+    // announce, which are then remembered for the next restarts.
+    let connect_state = Arc::clone(&unlocked_state);
     let connect_cm = Arc::clone(&channel_manager);
     let connect_pm = Arc::clone(&peer_manager);
+    let connect_graph = Arc::clone(&network_graph);
     let peer_data_path = ldk_data_dir.join(CHANNEL_PEER_DATA);
+    let restored_peers_path = ldk_data_dir.join(RESTORED_CHANNEL_PEERS_FNAME);
     let stop_connect = Arc::clone(&stop_processing);
+    let connect_tor_proxy = static_state.tor_proxy.clone();
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(Duration::from_secs(1));
         interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
         loop {
             interval.tick().await;
+            let mut restored_peers = disk::read_restored_channel_peers(&restored_peers_path);
             match disk::read_channel_peer_data(&peer_data_path) {
                 Ok(info) => {
                     for node_id in connect_cm
                         .list_channels()
                         .iter()
                         .map(|chan| chan.counterparty.node_id)
+                        .chain(restored_peers.clone())
                         .filter(|id| connect_pm.peer_by_node_id(id).is_none())
                     {
                         if stop_connect.load(Ordering::Acquire) {
                             return;
                         }
-                        for (pubkey, peer_addr) in info.iter() {
-                            if *pubkey == node_id {
-                                let _ =
-                                    do_connect_peer(*pubkey, *peer_addr, Arc::clone(&connect_pm))
-                                        .await;
+                        // the last known address comes first, then the ones the peer announces
+                        let mut peer_addrs: Vec<SocketAddress> =
+                            info.get(&node_id).cloned().into_iter().collect();
+                        for addr in announced_peer_addrs(
+                            &connect_graph,
+                            &node_id,
+                            connect_tor_proxy.is_some(),
+                        ) {
+                            if !peer_addrs.contains(&addr) {
+                                peer_addrs.push(addr);
+                            }
+                        }
+                        let Some(first_addr) = peer_addrs.first().cloned() else {
+                            continue;
+                        };
+                        let mut peer_addr = first_addr;
+                        let mut res = Ok(());
+                        for addr in peer_addrs {
+                            res = do_connect_peer(
+                                node_id,
+                                &addr,
+                                Arc::clone(&connect_pm),
+                                connect_tor_proxy.as_ref(),
+                                CircuitGroup::Payments,
+                                PEER_CONNECTION_TIMEOUT,
+                            )
+                            .await;
+                            peer_addr = addr;
+                            if res.is_ok() {
+                                break;
                             }
                         }
+                        if res.is_ok() && info.get(&node_id) != Some(&peer_addr) {
+                            if let Err(e) =
+                                disk::persist_channel_peer(&peer_data_path, &node_id, &peer_addr)
+                            {
+                                tracing::error!("Failed to persist peer address: {e}");
+                            }
+                        }
+                        if res.is_ok() && restored_peers.remove(&node_id) {
+                            tracing::info!("Reconnected to restored channel peer {node_id}");
+                            if let Err(e) = disk::persist_restored_channel_peers(
+                                &restored_peers_path,
+                                &restored_peers,
+                            ) {
+                                tracing::error!("Failed to persist restored peers: {e}");
+                            }
+                        }
+                        let (kind, details) = match res {
+                            Ok(()) => (
+                                ChannelEventKind::Reconnected,
+                                format!("reconnected to {peer_addr}"),
+                            ),
+                            Err(e) => (
+                                ChannelEventKind::ReconnectFailed,
+                                format!("cannot reconnect to {peer_addr}: {e}"),
+                            ),
+                        };
+                        for chan in connect_cm
+                            .list_channels()
+                            .iter()
+                            .filter(|c| c.counterparty.node_id == node_id)
+                        {
+                            connect_state.add_channel_event(chan.channel_id, kind, details.clone());
+                        }
                     }
                 }
                 Err(e) => tracing::error!(
@@ -2172,36 +7151,467 @@ pub(crate) async fn start_ldk(
         }
     });
 
-    // Regularly broadcast our node_announcement. This is only required (or possible) if we have
-    // some public channels.
-    let mut ldk_announced_listen_addr = Vec::new();
-    for addr in unlock_request.announce_addresses {
-        match SocketAddress::from_str(&addr) {
-            Ok(sa) => {
-                ldk_announced_listen_addr.push(sa);
+    // Regularly broadcast the deferred transactions that are due.
+    let broadcast_state = Arc::clone(&unlocked_state);
+    let stop_broadcast = Arc::clone(&stop_processing);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(5));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if stop_broadcast.load(Ordering::Acquire) {
+                return;
             }
-            Err(_) => {
-                return Err(APIError::InvalidAnnounceAddresses(format!(
-                    "failed to parse address '{addr}'"
-                )))
+            // don't interfere with an RGB channel opening
+            if *broadcast_state.rgb_send_lock.lock().unwrap() {
+                continue;
+            }
+            let now = get_current_timestamp();
+            let due_txids: Vec<String> = broadcast_state
+                .pending_broadcasts()
+                .into_iter()
+                .filter(|(_, info)| info.error.is_none() && info.is_due(now))
+                .map(|(txid, _)| txid)
+                .collect();
+            for txid in due_txids {
+                let state = Arc::clone(&broadcast_state);
+                let _ = tokio::task::spawn_blocking(move || state.broadcast_pending(&txid)).await;
+            }
+        }
+    });
+
+    // Regularly settle, expire or complete the pending orders.
+    let orders_state = Arc::clone(&unlocked_state);
+    let stop_orders = Arc::clone(&stop_processing);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(5));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if stop_orders.load(Ordering::Acquire) {
+                return;
             }
+            let state = Arc::clone(&orders_state);
+            let _ = tokio::task::spawn_blocking(move || state.process_orders()).await;
         }
+    });
+
+    // Regularly claim, refund or complete the submarine swaps.
+    if let Some(swap_provider_url) = static_state.swap_provider_url.clone() {
+        let submarine_swaps_state = Arc::clone(&unlocked_state);
+        let stop_submarine_swaps = Arc::clone(&stop_processing);
+        let network = Network::from(static_state.network);
+        tokio::spawn(async move {
+            let provider = SwapProviderClient::new(&swap_provider_url);
+            let mut interval = tokio::time::interval(Duration::from_secs(10));
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                if stop_submarine_swaps.load(Ordering::Acquire) {
+                    return;
+                }
+                submarine_swaps_state
+                    .process_submarine_swaps(&provider, network)
+                    .await;
+            }
+        });
     }
-    let ldk_announced_node_name = match unlock_request.announce_alias {
-        Some(s) => {
-            if s.len() > 32 {
-                return Err(APIError::InvalidAnnounceAlias(s!(
-                    "cannot be longer than 32 bytes"
-                )));
+
+    // Serve the NWC connections through their relays.
+    tokio::spawn(run_nwc_service(
+        Arc::clone(&app_state),
+        Arc::clone(&unlocked_state),
+        Arc::clone(&stop_processing),
+    ));
+
+    // Regularly release the payments held for the async recipients that came back online.
+    let async_payments_state = Arc::clone(&unlocked_state);
+    let async_payments_ldk_data_dir = ldk_data_dir.clone();
+    let stop_async_payments = Arc::clone(&stop_processing);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(2));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if stop_async_payments.load(Ordering::Acquire) {
+                return;
             }
-            let mut bytes = [0; 32];
-            bytes[..s.len()].copy_from_slice(s.as_bytes());
-            bytes
+            async_payments_state.release_async_payments(&async_payments_ldk_data_dir);
         }
-        None => [0; 32],
-    };
-    let peer_man = Arc::clone(&peer_manager);
-    let chan_man = Arc::clone(&channel_manager);
+    });
+
+    // Regularly answer the inbound channel leases bought from us and open the paid ones.
+    let lease_state = Arc::clone(&unlocked_state);
+    let lease_app_state = Arc::clone(&app_state);
+    let stop_lease = Arc::clone(&stop_processing);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(2));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if stop_lease.load(Ordering::Acquire) {
+                return;
+            }
+            process_lease_orders(&lease_app_state, &lease_state).await;
+        }
+    });
+
+    // Regularly convert the received payments according to the hedging policies and follow the
+    // swaps being executed.
+    let hedging_state = Arc::clone(&unlocked_state);
+    let hedging_app_state = Arc::clone(&app_state);
+    let stop_hedging = Arc::clone(&stop_processing);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(2));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if stop_hedging.load(Ordering::Acquire) {
+                return;
+            }
+            process_hedges(&hedging_app_state, &hedging_state).await;
+        }
+    });
+
+    // Regularly scan the new blocks for the chain subscriptions.
+    let chain_subscriptions_state = Arc::clone(&unlocked_state);
+    let stop_chain_subscriptions = Arc::clone(&stop_processing);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(5));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if stop_chain_subscriptions.load(Ordering::Acquire) {
+                return;
+            }
+            scan_chain_subscriptions(Arc::clone(&chain_subscriptions_state)).await;
+        }
+    });
+
+    // Regularly check the value of the HTLCs close to their deadline, warning when it's too high.
+    let htlc_risk_state = Arc::clone(&unlocked_state);
+    let htlc_risk_app_state = Arc::clone(&app_state);
+    let stop_htlc_risk = Arc::clone(&stop_processing);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(60));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if stop_htlc_risk.load(Ordering::Acquire) {
+                return;
+            }
+            let threshold_sat = htlc_risk_app_state
+                .get_runtime_config()
+                .htlc_risk_threshold_sat;
+            if threshold_sat == 0 {
+                continue;
+            }
+            let htlcs = htlc_risk_state.list_htlcs(&htlc_risk_app_state.static_state.ldk_data_dir);
+            let at_risk = htlcs.iter().filter(|h| h.at_risk);
+            let at_risk_amt_msat = at_risk.clone().map(|h| h.amt_msat).sum::<u64>();
+            if at_risk_amt_msat > threshold_sat * 1000 {
+                tracing::warn!(
+                    "{} HTLCs worth {at_risk_amt_msat} msats are within {HTLC_RISK_WINDOW_BLOCKS} \
+                    blocks of their deadline (threshold {threshold_sat} sats), channels may be \
+                    force-closed",
+                    at_risk.count()
+                );
+            }
+        }
+    });
+
+    // Regularly sync the network graph from the RGS server, through the gossip Tor circuit when a
+    // SOCKS proxy is configured.
+    if let (Some(rgs), Some(rgs_url)) = (rapid_gossip_sync, static_state.rgs_url.clone()) {
+        let rgs_graph = Arc::clone(&network_graph);
+        let rgs_tor_proxy = static_state.tor_proxy.clone();
+        let stop_rgs = Arc::clone(&stop_processing);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(RGS_SYNC_INTERVAL);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                if stop_rgs.load(Ordering::Acquire) {
+                    return;
+                }
+                match sync_rapid_gossip(&rgs, &rgs_graph, &rgs_url, rgs_tor_proxy.as_ref()).await {
+                    Ok(timestamp) => {
+                        tracing::info!("Synced network graph from RGS up to {timestamp}")
+                    }
+                    Err(e) => tracing::warn!("Failed to sync network graph from RGS: {e}"),
+                }
+            }
+        });
+    }
+
+    // Regularly prune the unpaid invoices that expired longer ago than the retention.
+    let invoice_pruning_state = Arc::clone(&unlocked_state);
+    let invoice_pruning_app_state = Arc::clone(&app_state);
+    let stop_invoice_pruning = Arc::clone(&stop_processing);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(60));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if stop_invoice_pruning.load(Ordering::Acquire) {
+                return;
+            }
+            let retention_sec = invoice_pruning_app_state
+                .get_runtime_config()
+                .invoice_retention_sec;
+            if retention_sec == 0 {
+                continue;
+            }
+            let pruned = invoice_pruning_state.prune_expired_invoices(
+                retention_sec,
+                &invoice_pruning_app_state.static_state.ldk_data_dir,
+            );
+            if pruned > 0 {
+                tracing::info!("Pruned {pruned} expired invoices");
+            }
+        }
+    });
+
+    // Warn about the held HODL invoice payments close to their claim deadline on each new block.
+    let hodl_deadline_state = Arc::clone(&unlocked_state);
+    let stop_hodl_deadline = Arc::clone(&stop_processing);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(10));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut last_height = 0;
+        loop {
+            interval.tick().await;
+            if stop_hodl_deadline.load(Ordering::Acquire) {
+                return;
+            }
+            let current_height = hodl_deadline_state
+                .channel_manager
+                .current_best_block()
+                .height;
+            if current_height == last_height {
+                continue;
+            }
+            last_height = current_height;
+            hodl_deadline_state.warn_expiring_hodl_invoices();
+        }
+    });
+
+    // Check the fallback addresses of the pending invoices for on-chain payments on each new block.
+    let fallback_state = Arc::clone(&unlocked_state);
+    let stop_fallback = Arc::clone(&stop_processing);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(10));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut last_height = 0;
+        loop {
+            interval.tick().await;
+            if stop_fallback.load(Ordering::Acquire) {
+                return;
+            }
+            let current_height = fallback_state.channel_manager.current_best_block().height;
+            if current_height == last_height {
+                continue;
+            }
+            last_height = current_height;
+            if let Err(e) = fallback_state.check_fallback_payments().await {
+                tracing::warn!("Failed to check the invoice fallback addresses: {e}");
+            }
+        }
+    });
+
+    // Regularly abort the multisig and external fundings whose signatures have not been provided
+    // in time.
+    let multisig_funding_state = Arc::clone(&unlocked_state);
+    let stop_multisig_funding = Arc::clone(&stop_processing);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(10));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if stop_multisig_funding.load(Ordering::Acquire) {
+                return;
+            }
+            let now = get_current_timestamp();
+            let expired = multisig_funding_state
+                .get_multisig_fundings()
+                .iter()
+                .filter(|(_, info)| info.expires_at <= now)
+                .map(|(temporary_channel_id, _)| *temporary_channel_id)
+                .collect::<Vec<_>>();
+            for temporary_channel_id in expired {
+                multisig_funding_state.abort_multisig_funding(temporary_channel_id, "timed out");
+            }
+            let expired = multisig_funding_state
+                .get_external_fundings()
+                .iter()
+                .filter(|(_, info)| info.expires_at <= now)
+                .map(|(temporary_channel_id, _)| *temporary_channel_id)
+                .collect::<Vec<_>>();
+            for temporary_channel_id in expired {
+                multisig_funding_state.abort_external_funding(temporary_channel_id, "timed out");
+            }
+        }
+    });
+
+    // Regularly write the emergency kit again when the state of a channel changes.
+    let emergency_kit_state = Arc::clone(&unlocked_state);
+    let emergency_kit_app_state = Arc::clone(&app_state);
+    let stop_emergency_kit = Arc::clone(&stop_processing);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(10));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if stop_emergency_kit.load(Ordering::Acquire) {
+                return;
+            }
+            let state = Arc::clone(&emergency_kit_state);
+            let app_state = Arc::clone(&emergency_kit_app_state);
+            let res = tokio::task::spawn_blocking(move || {
+                state.refresh_emergency_kit(&app_state.static_state)
+            })
+            .await
+            .unwrap();
+            if let Err(e) = res {
+                tracing::error!("Failed to refresh the emergency kit: {e}");
+            }
+        }
+    });
+
+    // Regularly upload a snapshot of the node data to the automatic backup target.
+    if let Some(auto_backup_target) = static_state.auto_backup_target.clone() {
+        let auto_backup_state = Arc::clone(&unlocked_state);
+        let auto_backup_static_state = Arc::clone(static_state);
+        let auto_backup_interval_sec = static_state.auto_backup_interval_sec;
+        let auto_backup_full_every = static_state.auto_backup_full_every;
+        let stop_auto_backup = Arc::clone(&stop_processing);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(auto_backup_interval_sec));
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            // the first snapshot after unlocking is a full one
+            let mut previous: Option<AutoBackupManifest> = None;
+            let mut num_incremental = 0;
+            loop {
+                interval.tick().await;
+                if stop_auto_backup.load(Ordering::Acquire) {
+                    return;
+                }
+                let full = previous.is_none() || num_incremental + 1 >= auto_backup_full_every;
+                let res = save_auto_backup(
+                    Arc::clone(&auto_backup_state),
+                    Arc::clone(&auto_backup_static_state),
+                    &auto_backup_target,
+                    previous.clone(),
+                    full,
+                )
+                .await;
+                match res {
+                    Ok(Some(manifest)) => {
+                        num_incremental = if full { 0 } else { num_incremental + 1 };
+                        previous = Some(manifest);
+                    }
+                    Ok(None) => {}
+                    Err(e) => tracing::error!("Failed to save the automatic backup: {e}"),
+                }
+            }
+        });
+    }
+
+    // Regularly check the anchor reserve, replacing the spent UTXOs.
+    let anchor_reserve_utxos = static_state.anchor_reserve_utxos;
+    if anchor_reserve_utxos > 0 {
+        let anchor_reserve_state = Arc::clone(&unlocked_state);
+        let anchor_reserve_utxo_size_sat = static_state.anchor_reserve_utxo_size_sat;
+        let stop_anchor_reserve = Arc::clone(&stop_processing);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(30));
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                if stop_anchor_reserve.load(Ordering::Acquire) {
+                    return;
+                }
+                // don't interfere with an RGB channel opening
+                if *anchor_reserve_state.rgb_send_lock.lock().unwrap() {
+                    continue;
+                }
+                maintain_anchor_reserve(
+                    Arc::clone(&anchor_reserve_state),
+                    network,
+                    anchor_reserve_utxos,
+                    anchor_reserve_utxo_size_sat,
+                )
+                .await;
+            }
+        });
+    }
+
+    // Regularly check the free colorable UTXOs, topping up the pool when needed (watch-only nodes
+    // don't spend on their own).
+    let utxo_pool_target = static_state.utxo_pool_target;
+    if utxo_pool_target > 0 && !static_state.watch_only {
+        let utxo_pool_state = Arc::clone(&unlocked_state);
+        let utxo_pool_min = static_state.utxo_pool_min;
+        let utxo_pool_utxo_size_sat = static_state.utxo_pool_utxo_size_sat;
+        let stop_utxo_pool = Arc::clone(&stop_processing);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(30));
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            // avoid flooding the log while the wallet lacks the funds to top up the pool
+            let mut failing = false;
+            loop {
+                interval.tick().await;
+                if stop_utxo_pool.load(Ordering::Acquire) {
+                    return;
+                }
+                // don't interfere with an RGB channel opening
+                if *utxo_pool_state.rgb_send_lock.lock().unwrap() {
+                    continue;
+                }
+                match maintain_utxo_pool(
+                    Arc::clone(&utxo_pool_state),
+                    utxo_pool_target,
+                    utxo_pool_min,
+                    utxo_pool_utxo_size_sat,
+                )
+                .await
+                {
+                    Ok(created) => {
+                        if created > 0 {
+                            tracing::info!("Created {created} UTXOs to top up the UTXO pool");
+                        }
+                        failing = false;
+                    }
+                    Err(e) => {
+                        if !failing {
+                            tracing::warn!("Failed to top up the UTXO pool: {e}");
+                        }
+                        failing = true;
+                    }
+                }
+            }
+        });
+    }
+
+    // Compact the storage once the node is up, if requested at startup.
+    if static_state.compact_storage_on_startup {
+        let compaction_state = Arc::clone(&unlocked_state);
+        let compaction_app_state = Arc::clone(&app_state);
+        tokio::task::spawn_blocking(move || {
+            let payment_retention_sec = compaction_app_state
+                .get_runtime_config()
+                .payment_retention_sec;
+            if let Err(e) = compaction_state
+                .compact_storage(&compaction_app_state.static_state, payment_retention_sec)
+            {
+                tracing::error!("Failed to compact the storage: {e}");
+            }
+        });
+    }
+
+    // Regularly broadcast our node_announcement. This is only required (or possible) if we have
+    // some public channels.
+    let node_announcement_state = Arc::clone(&unlocked_state);
     tokio::spawn(async move {
         // First wait a minute until we have some peers and maybe have opened a channel.
         tokio::time::sleep(Duration::from_secs(60)).await;
@@ -2210,20 +7620,7 @@ pub(crate) async fn start_ldk(
         let mut interval = tokio::time::interval(Duration::from_secs(3600));
         loop {
             interval.tick().await;
-            // Don't bother trying to announce if we don't have any public channls, though our
-            // peers should drop such an announcement anyway. Note that announcement may not
-            // propagate until we have a channel with 6+ confirmations.
-            if chan_man
-                .list_channels()
-                .iter()
-                .any(|chan| chan.is_announced)
-            {
-                peer_man.broadcast_node_announcement(
-                    [0; 3],
-                    ldk_announced_node_name,
-                    ldk_announced_listen_addr.clone(),
-                );
-            }
+            node_announcement_state.broadcast_node_announcement();
         }
     });
 
@@ -2270,6 +7667,43 @@ impl AppState {
     }
 }
 
+/// Wait, up to the configured timeout, for the pending HTLCs to be resolved and for the RGB
+/// transfer being sent (if any) to complete, refusing new payments meanwhile
+pub(crate) async fn drain_before_shutdown(app_state: Arc<AppState>) {
+    let timeout_sec = app_state.static_state.shutdown_drain_timeout_sec;
+    if timeout_sec == 0 {
+        return;
+    }
+    let Some(unlocked_state) = app_state.get_unlocked_app_state().await.clone() else {
+        return;
+    };
+    *app_state.get_draining() = true;
+    tracing::info!("Draining in-flight payments before shutting down (timeout {timeout_sec}s)");
+
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let num_htlcs = unlocked_state
+            .channel_manager
+            .list_channels()
+            .iter()
+            .map(|c| c.pending_inbound_htlcs.len() + c.pending_outbound_htlcs.len())
+            .sum::<usize>();
+        let sending_rgb = *unlocked_state.rgb_send_lock.lock().unwrap();
+        if num_htlcs == 0 && !sending_rgb {
+            tracing::info!("No in-flight payments left");
+            return;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > timeout_sec as f32 {
+            tracing::warn!(
+                "Drain timeout reached with {num_htlcs} pending HTLCs (RGB send in progress: \
+                {sending_rgb})"
+            );
+            return;
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
 pub(crate) async fn stop_ldk(app_state: Arc<AppState>) {
     tracing::info!("Stopping LDK");
 
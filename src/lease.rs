@@ -0,0 +1,195 @@
+use axum::{extract::State, Json};
+use axum_extra::extract::WithRejection;
+use bitcoin::hashes::Hash;
+use lightning::sign::EntropySource;
+use lightning::types::payment::PaymentHash;
+use lightning_invoice::Bolt11Invoice;
+use serde_json::json;
+use std::marker::PhantomData;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::error::APIError;
+use crate::lsps::{LeaseOrderInfo, LeaseRequest, LSPS0_INTERNAL_ERROR};
+use crate::routes::{
+    ln_invoice, open_channel_without_totp, HTLCStatus, LNInvoiceRequest, LeaseOrderStatus,
+    OpenChannelRequest,
+};
+use crate::utils::{get_current_timestamp, hex_str, AppState, UnlockedAppState};
+
+/// Create the fee invoice of a lease bought by a client, recording the order
+async fn create_lease_order(
+    app_state: &Arc<AppState>,
+    unlocked_state: &UnlockedAppState,
+    request: &LeaseRequest,
+) -> Result<(String, String), APIError> {
+    let expiry_sec = unlocked_state.lsps_handler.lease_config().order_expiry_sec;
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(request.fee_sat * 1000),
+        min_amt_msat: None,
+        max_amt_msat: None,
+        expiry_sec,
+        asset_id: None,
+        asset_amount: None,
+        route_hints: None,
+        lsp: None,
+        phantom: None,
+        label: None,
+        settle_after_expiry: false,
+        fallback_address_type: None,
+    };
+    let Json(res) = ln_invoice(
+        State(Arc::clone(app_state)),
+        None,
+        WithRejection(Json(payload), PhantomData),
+    )
+    .await?;
+    let invoice = Bolt11Invoice::from_str(&res.invoice).unwrap();
+
+    let order_id = hex_str(&unlocked_state.keys_manager.get_secure_random_bytes());
+    let created_at = get_current_timestamp();
+    unlocked_state.lsps_handler.add_lease_order(
+        order_id.clone(),
+        LeaseOrderInfo {
+            client_pubkey: request.client_pubkey,
+            capacity_sat: request.capacity_sat,
+            asset_id: request.asset_id.clone(),
+            asset_amount: request.asset_amount,
+            fee_sat: request.fee_sat,
+            lease_duration_blocks: request.lease_duration_blocks,
+            payment_hash: PaymentHash(invoice.payment_hash().to_byte_array()),
+            status: LeaseOrderStatus::AwaitingPayment,
+            temporary_channel_id: None,
+            expires_at: created_at + expiry_sec as u64,
+            created_at,
+            updated_at: created_at,
+        },
+    );
+    tracing::info!(
+        "Created lease order {order_id} of {} sat for {}",
+        request.capacity_sat,
+        request.client_pubkey
+    );
+    Ok((order_id, res.invoice))
+}
+
+/// Open the channel of a paid lease order, which stays paid if the opening has to be retried
+async fn open_lease_channel(
+    app_state: &Arc<AppState>,
+    unlocked_state: &UnlockedAppState,
+    order_id: &str,
+    order: &LeaseOrderInfo,
+) {
+    // the client connected to buy, wait for it to come back online
+    if unlocked_state
+        .peer_manager
+        .peer_by_node_id(&order.client_pubkey)
+        .is_none()
+    {
+        return;
+    }
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_opt_addr: order.client_pubkey.to_string(),
+        capacity_sat: order.capacity_sat,
+        push_msat: 0,
+        asset_amount: order.asset_amount,
+        asset_id: order.asset_id.clone(),
+        public: false,
+        with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        temporary_channel_id: None,
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
+    };
+    match open_channel_without_totp(Arc::clone(app_state), payload).await {
+        Ok(Json(res)) => {
+            tracing::info!(
+                "Opened channel {} for lease order {order_id}",
+                res.temporary_channel_id
+            );
+            unlocked_state
+                .lsps_handler
+                .update_lease_order(order_id, |o| {
+                    o.status = LeaseOrderStatus::ChannelOpened;
+                    o.temporary_channel_id = Some(res.temporary_channel_id);
+                });
+        }
+        Err(APIError::OpenChannelInProgress) => {}
+        Err(e) => {
+            tracing::error!(
+                "ERROR: cannot open the channel of paid lease order {order_id}, the fee of {} \
+                sat should be refunded: {e}",
+                order.fee_sat
+            );
+            unlocked_state
+                .lsps_handler
+                .update_lease_order(order_id, |o| o.status = LeaseOrderStatus::Failed);
+        }
+    }
+}
+
+/// Answer the leases bought by clients with their fee invoice, then follow the orders: expire
+/// the unpaid ones and open the channels whose fee has been paid
+pub(crate) async fn process_lease_orders(
+    app_state: &Arc<AppState>,
+    unlocked_state: &UnlockedAppState,
+) {
+    let requests = unlocked_state.lsps_handler.take_lease_requests();
+    if !requests.is_empty() {
+        for request in requests {
+            let res = match create_lease_order(app_state, unlocked_state, &request).await {
+                Ok((order_id, invoice)) => Ok(json!({
+                    "order_id": order_id,
+                    "fee_sat": request.fee_sat,
+                    "invoice": invoice,
+                    "lease_duration_blocks": request.lease_duration_blocks,
+                })),
+                Err(e) => {
+                    tracing::error!("ERROR: cannot create lease order: {e}");
+                    Err((
+                        LSPS0_INTERNAL_ERROR,
+                        format!("cannot create the fee invoice: {e}"),
+                    ))
+                }
+            };
+            unlocked_state
+                .lsps_handler
+                .respond(request.client_pubkey, &request.request_id, res);
+        }
+        unlocked_state.peer_manager.process_events();
+    }
+
+    let now = get_current_timestamp();
+    let inbound_payments = unlocked_state.inbound_payments();
+    for (order_id, order) in unlocked_state.lsps_handler.lease_orders() {
+        match order.status {
+            LeaseOrderStatus::AwaitingPayment => {
+                let status = inbound_payments.get(&order.payment_hash).map(|p| p.status);
+                if status == Some(HTLCStatus::Succeeded) {
+                    tracing::info!("Lease order {order_id} has been paid");
+                    unlocked_state
+                        .lsps_handler
+                        .update_lease_order(&order_id, |o| o.status = LeaseOrderStatus::Paid);
+                } else if status == Some(HTLCStatus::Failed) || now >= order.expires_at {
+                    tracing::info!("Lease order {order_id} has expired");
+                    unlocked_state
+                        .lsps_handler
+                        .update_lease_order(&order_id, |o| o.status = LeaseOrderStatus::Expired);
+                }
+            }
+            LeaseOrderStatus::Paid => {
+                open_lease_channel(app_state, unlocked_state, &order_id, &order).await
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,625 @@
+mod args;
+mod audit;
+mod auth;
+mod auto_backup;
+mod backup;
+mod bitcoind;
+mod coin_selection;
+mod config;
+mod disk;
+mod error;
+#[cfg(feature = "failure-injection")]
+mod failure_injection;
+mod fee_bump;
+mod gossip;
+mod grpc;
+mod hedging;
+mod hooks;
+mod ldk;
+mod lease;
+mod lsps;
+mod maintenance;
+mod notify;
+mod nwc;
+mod peer_messages;
+mod proxy;
+mod quota;
+mod rgb;
+mod routes;
+#[cfg(feature = "simulation")]
+mod simulation;
+mod store;
+mod submarine;
+mod swap;
+#[cfg(any(test, feature = "testing"))]
+pub mod testkit;
+mod tls;
+mod tor;
+mod totp;
+mod utils;
+
+#[cfg(test)]
+mod test;
+
+use anyhow::Result;
+use axum::{
+    extract::DefaultBodyLimit,
+    http::Request,
+    middleware,
+    response::Response,
+    routing::{get, post},
+    Router,
+};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tokio::signal;
+use tower_http::limit::RequestBodyLimitLayer;
+use tower_http::trace::TraceLayer;
+use tracing::Span;
+use tracing_subscriber::{
+    filter,
+    fmt::{
+        format::{DefaultFields, Writer},
+        FormatFields,
+    },
+    prelude::*,
+    reload,
+};
+
+use crate::args::{LogFormat, UserArgs};
+use crate::audit::audit_storage_dir;
+use crate::auth::conditional_auth_middleware;
+use crate::config::parse_log_level;
+use crate::error::{v1_error_middleware, AppError};
+use crate::ldk::{drain_before_shutdown, stop_ldk};
+use crate::proxy::{client_ip_middleware, cors_layer, ClientIp};
+#[cfg(feature = "failure-injection")]
+use crate::routes::inject_failure;
+use crate::routes::{
+    abandon_payment, accelerate_incoming, accept_channel, address, address_stats, anchor_reserve,
+    approve_broadcast, asset_audit, asset_balance, asset_metadata, audit, backup, backup_channels,
+    balance_sheet, broadcast_psbt, btc_balance, bump_close_fee, bump_fee, burn_asset, buy_inbound,
+    cancel_invoice, cancel_invoices, chain_events, chain_subscriptions, change_password,
+    channel_acceptor, channel_depths, channel_events, channel_status, check_indexer_url,
+    check_proxy_endpoint, close_channel, close_detail, compact_storage, confirm_totp, connect_peer,
+    create_order, create_utxos, decode_ln_invoice, decode_rgb_invoice, descriptors, disable_totp,
+    disconnect_peer, emergency_kit, enroll_totp, escrow_create, escrow_resolve, estimate_fee,
+    export_accounting, export_backup, export_consignment, fail_transfer, fail_transfers,
+    fee_report, forwarding_history, fund_channel_abort, fund_channel_complete, fund_psbt,
+    get_asset_media, get_chan_info, get_channel_id, get_node_info, get_order, get_payment,
+    get_swap, hedging_policies, hodl_invoice, htlcs, import_backup, import_consignment, init,
+    intercept_scopes, intercepted_htlcs, invoice_quotas, invoice_status, issue_asset_cfa,
+    issue_asset_ifa, issue_asset_nia, issue_asset_uda, journal_proof, keysend, lease_config,
+    lease_orders, lease_rates, list_assets, list_assets_v1, list_channels, list_channels_v1,
+    list_escrows, list_hedges, list_offers, list_payments, list_payments_v1, list_peers,
+    list_peers_v1, list_submarine_swaps, list_swaps, list_sweeps, list_transactions,
+    list_transfers, list_transfers_v1, list_unspents, ln_invoice, lnurl_pay, lnurl_pay_callback,
+    lnurl_pay_metadata, lock, lock_utxo, log_level, lsp_clients, lsp_config, lsp_fee_menu,
+    maker_execute, maker_init, network_info, node_info, nwc_connect, nwc_connections, nwc_revoke,
+    offer, open_channel, ownership_proof, pay_offer, peer_compat, peer_filter, peer_suggestions,
+    pending_broadcasts, pending_channels, pending_fundings, phantom_route_hints, post_asset_media,
+    probe_payment, query_routes, rebalance, refresh_transfers, reissue_asset, reject_channel,
+    reload_config, remove_hedging_policy, resolve_htlc, restore, restore_channels, retry_sweep,
+    retry_transfer, revoke_token, rgb_invoice, send_asset, send_btc, send_btc_many,
+    send_onion_message, send_payment, send_to_route, set_alias, set_hedging_policy,
+    set_view_password, settle_invoice, settle_invoices, settlements, shutdown, sign_message,
+    sign_psbt, spec, swap_in, swap_out, sweep_config, sync, sync_status, taker, throttle_gossip,
+    tor_status, transfer_detail, unlock, unlock_utxo, unlock_view, update_channel_acceptor,
+    update_channel_depths, update_channel_policy, update_lease_config, update_lnurl_pay,
+    update_log_level, update_lsp_config, update_peer_filter, update_sweep_config, verify_message,
+    API_V2_PREFIX, API_VERSION_PREFIX,
+};
+use crate::store::{migrate_storage, migrate_storage_dir};
+use crate::utils::{start_daemon, AppState, LOGS_DIR};
+
+/// Run the node with the startup arguments of the command line
+pub async fn run() -> Result<()> {
+    let mut args = args::parse_startup_args()?;
+
+    // audits only read the storage directory, so they run before any logger writes to it
+    if let Some(auditor_key_path) = &args.auditor_key_path {
+        let report =
+            audit_storage_dir(&args.storage_dir_path, args.network, auditor_key_path).await?;
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        return Ok(());
+    }
+
+    if let Some(target_dir_path) = args.migrate_storage_dir.take() {
+        let migrated = migrate_storage_dir(&args.storage_dir_path, &target_dir_path)?;
+        println!("Migrated {migrated} files to {}", target_dir_path.display());
+        if args.migrate_storage_to.is_none() {
+            return Ok(());
+        }
+        args.storage_dir_path = target_dir_path;
+    }
+
+    if let Some(target_backend) = args.migrate_storage_to {
+        let migrated = migrate_storage(&args.storage_dir_path, target_backend)?;
+        println!("Migrated {migrated} entries to the {target_backend} storage backend");
+        return Ok(());
+    }
+
+    #[cfg(feature = "simulation")]
+    if let Some(simulation_config_path) = &args.simulation_config_path {
+        let config = simulation::read_simulation_config(simulation_config_path)?;
+        let report = simulation::run_simulation(config)?;
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        return Ok(());
+    }
+
+    // stdout logger, its level can be changed at runtime
+    let stdout_log = match args.log_format {
+        LogFormat::Text => tracing_subscriber::fmt::layer()
+            .fmt_fields(TypedFields::default())
+            .boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer().json().boxed(),
+    };
+    let stdout_filter = parse_log_level(&args.log_level).expect("validated log level");
+    let (stdout_filter, log_level_handle) = reload::Layer::new(stdout_filter);
+    args.log_level_handle = Some(log_level_handle);
+
+    // file logger
+    let log_dir = args.storage_dir_path.join(LOGS_DIR);
+    let file_appender = tracing_appender::rolling::daily(&log_dir, "rln.log");
+    let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
+    let file_log = tracing_subscriber::fmt::layer()
+        .with_file(true)
+        .with_line_number(true)
+        .with_target(true)
+        .with_thread_ids(true)
+        .with_thread_names(true)
+        .with_writer(non_blocking);
+    let file_log = match args.log_format {
+        LogFormat::Text => file_log.boxed(),
+        LogFormat::Json => file_log.json().boxed(),
+    };
+
+    tracing_subscriber::registry()
+        .with(stdout_log.with_filter(stdout_filter))
+        .with(file_log.with_filter(filter::LevelFilter::DEBUG))
+        .init();
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], args.daemon_listening_port));
+    let tls_config = args.tls_config.clone();
+
+    let (router, app_state) = app(args).await?;
+
+    tracing::info!("Listening on {}", addr);
+    let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
+    #[cfg(unix)]
+    tokio::spawn(reload_config_on_hangup(app_state.clone()));
+    tls::serve(listener, router, tls_config, shutdown_signal(app_state))
+        .await
+        .unwrap();
+
+    Ok(())
+}
+
+pub(crate) async fn app(args: UserArgs) -> Result<(Router, Arc<AppState>), AppError> {
+    let app_state = start_daemon(&args).await?;
+
+    if args.watch_only {
+        tracing::info!("Serving the watch-only APIs");
+    }
+    let router = api_router(&args, app_state.clone(), true);
+    let v2_router = api_router(&args, app_state.clone(), false);
+
+    let grpc_router = grpc::grpc_router(v2_router.clone());
+    // the unversioned paths keep being served for existing clients, with the /v1 behavior
+    let router = Router::new()
+        .nest(API_VERSION_PREFIX, router.clone())
+        .nest(API_V2_PREFIX, v2_router)
+        .merge(router);
+    // gRPC methods keep their standard paths, only the REST APIs move under the prefix
+    let router = match &args.url_prefix {
+        Some(url_prefix) => Router::new().nest(url_prefix, router),
+        None => router,
+    };
+    let router = if let Some(grpc_listening_port) = args.grpc_listening_port {
+        let grpc_addr = SocketAddr::from(([0, 0, 0, 0], grpc_listening_port));
+        let grpc_listener = tokio::net::TcpListener::bind(&grpc_addr).await?;
+        tracing::info!("gRPC server listening on {}", grpc_addr);
+        let cancel_token = app_state.cancel_token.clone();
+        let tls_config = args.tls_config.clone();
+        tokio::spawn(async move {
+            tls::serve(grpc_listener, grpc_router, tls_config, async move {
+                cancel_token.cancelled().await
+            })
+            .await
+            .unwrap();
+        });
+        router
+    } else {
+        router.merge(grpc_router)
+    };
+
+    Ok((router, app_state))
+}
+
+/// Router of the APIs served under a version prefix, /v1 (the one also served unversioned) and /v2
+/// only differing in the shape of the list responses and of the errors
+fn api_router(args: &UserArgs, app_state: Arc<AppState>, v1: bool) -> Router {
+    // a watch-only node has no route to the APIs that can move funds
+    let router = if args.watch_only {
+        watch_only_router()
+    } else {
+        full_router(args)
+    }
+    .merge(list_router(v1));
+    // the failure injection API is only compiled in to test the error paths
+    #[cfg(feature = "failure-injection")]
+    let router = if args.watch_only {
+        router
+    } else {
+        router.route("/testing/injectfailure", post(inject_failure))
+    };
+    let router = router
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(|request: &Request<_>| {
+                    let span = tracing::info_span!(
+                        "request",
+                        status_code = tracing::field::Empty,
+                        uri = tracing::field::display(request.uri()),
+                        request_id = tracing::field::display(uuid::Uuid::new_v4()),
+                        client_ip = tracing::field::Empty,
+                    );
+                    if let Some(ClientIp(client_ip)) = request.extensions().get::<ClientIp>() {
+                        span.record("client_ip", tracing::field::display(client_ip));
+                    }
+                    span
+                })
+                .on_request(|_request: &Request<_>, _span: &Span| {
+                    tracing::info!("STARTED");
+                })
+                .on_response(|response: &Response, latency: Duration, span: &Span| {
+                    span.record("status_code", tracing::field::display(response.status()));
+                    tracing::info!("ENDED in {:?}", latency);
+                }),
+        )
+        .layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            conditional_auth_middleware,
+        ))
+        .layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            client_ip_middleware,
+        ));
+    let router = if v1 {
+        router.layer(middleware::from_fn(v1_error_middleware))
+    } else {
+        router
+    };
+    router
+        .layer(cors_layer(args.cors_allowed_origins.clone()))
+        .with_state(app_state)
+}
+
+/// Router of all the APIs, served unless the node runs in watch-only mode
+fn full_router(args: &UserArgs) -> Router<Arc<AppState>> {
+    Router::new()
+        .route(
+            "/postassetmedia",
+            post(post_asset_media).layer(RequestBodyLimitLayer::new(
+                args.max_media_upload_size_mb as usize * 1024 * 1024,
+            )),
+        )
+        .route("/importbackup", post(import_backup))
+        .route("/importconsignment", post(import_consignment))
+        // all routes before this will have the default body limit disabled
+        .layer(DefaultBodyLimit::disable())
+        .route("/.well-known/lnurlp/:name", get(lnurl_pay_metadata))
+        .route("/abandonpayment", post(abandon_payment))
+        .route("/accelerateincoming", post(accelerate_incoming))
+        .route("/acceptchannel", post(accept_channel))
+        .route("/address", post(address))
+        .route("/addressstats", get(address_stats))
+        .route("/anchorreserve", get(anchor_reserve))
+        .route("/approvebroadcast/:txid", post(approve_broadcast))
+        .route("/assetaudit/:asset_id", get(asset_audit))
+        .route("/assetbalance", post(asset_balance))
+        .route("/assetmetadata", post(asset_metadata))
+        .route("/audit", get(audit))
+        .route("/backup", post(backup))
+        .route("/backupchannels", post(backup_channels))
+        .route("/balancesheet", post(balance_sheet))
+        .route("/broadcastpsbt", post(broadcast_psbt))
+        .route("/btcbalance", post(btc_balance))
+        .route("/bumpclosefee", post(bump_close_fee))
+        .route("/bumpfee", post(bump_fee))
+        .route("/burnasset", post(burn_asset))
+        .route("/buyinbound", post(buy_inbound))
+        .route("/cancelinvoice", post(cancel_invoice))
+        .route("/cancelinvoices", post(cancel_invoices))
+        .route("/chainevents/:subscription_id", get(chain_events))
+        .route("/chainsubscriptions", post(chain_subscriptions))
+        .route("/changepassword", post(change_password))
+        .route(
+            "/channelacceptor",
+            get(channel_acceptor).post(update_channel_acceptor),
+        )
+        .route(
+            "/channeldepths",
+            get(channel_depths).post(update_channel_depths),
+        )
+        .route("/channels/:channel_id/events", get(channel_events))
+        .route("/channelstatus", get(channel_status))
+        .route("/checkindexerurl", post(check_indexer_url))
+        .route("/checkproxyendpoint", post(check_proxy_endpoint))
+        .route("/closechannel", post(close_channel))
+        .route("/closedetail", post(close_detail))
+        .route("/confirmtotp", post(confirm_totp))
+        .route("/connectpeer", post(connect_peer))
+        .route("/createutxos", post(create_utxos))
+        .route("/decodelninvoice", post(decode_ln_invoice))
+        .route("/decodergbinvoice", post(decode_rgb_invoice))
+        .route("/descriptors", get(descriptors))
+        .route("/disabletotp", post(disable_totp))
+        .route("/disconnectpeer", post(disconnect_peer))
+        .route("/emergencykit", get(emergency_kit))
+        .route("/enrolltotp", post(enroll_totp))
+        .route("/escrowcreate", post(escrow_create))
+        .route("/escrowresolve", post(escrow_resolve))
+        .route("/estimatefee", post(estimate_fee))
+        .route("/export/accounting", get(export_accounting))
+        .route("/exportbackup", post(export_backup))
+        .route("/exportconsignment", post(export_consignment))
+        .route("/failtransfer", post(fail_transfer))
+        .route("/failtransfers", post(fail_transfers))
+        .route("/feereport", get(fee_report))
+        .route("/forwardinghistory", get(forwarding_history))
+        .route("/fundchannelabort", post(fund_channel_abort))
+        .route("/fundchannelcomplete", post(fund_channel_complete))
+        .route("/fundpsbt", post(fund_psbt))
+        .route("/getassetmedia", post(get_asset_media))
+        .route("/getchaninfo", get(get_chan_info))
+        .route("/getchannelid", post(get_channel_id))
+        .route("/getnodeinfo", get(get_node_info))
+        .route("/getorder", post(get_order))
+        .route("/getpayment", post(get_payment))
+        .route("/getswap", post(get_swap))
+        .route("/hedgingpolicies", get(hedging_policies))
+        .route("/hodlinvoice", post(hodl_invoice))
+        .route("/htlcs", get(htlcs))
+        .route("/init", post(init))
+        .route("/interceptedhtlcs/:scope_id", get(intercepted_htlcs))
+        .route("/interceptscopes", post(intercept_scopes))
+        .route("/invoicequotas", get(invoice_quotas))
+        .route("/invoicestatus", post(invoice_status))
+        .route("/issueassetcfa", post(issue_asset_cfa))
+        .route("/issueassetifa", post(issue_asset_ifa))
+        .route("/issueassetnia", post(issue_asset_nia))
+        .route("/issueassetuda", post(issue_asset_uda))
+        .route("/journalproof/:range", get(journal_proof))
+        .route("/keysend", post(keysend))
+        .route("/lease/config", get(lease_config).post(update_lease_config))
+        .route("/lease/orders", get(lease_orders))
+        .route("/leaserates", post(lease_rates))
+        .route("/listescrows", get(list_escrows))
+        .route("/listhedges", get(list_hedges))
+        .route("/listoffers", get(list_offers))
+        .route("/listsubmarineswaps", get(list_submarine_swaps))
+        .route("/listswaps", get(list_swaps))
+        .route("/listsweeps", get(list_sweeps))
+        .route("/listtransactions", post(list_transactions))
+        .route("/listunspents", post(list_unspents))
+        .route("/lninvoice", post(ln_invoice))
+        .route("/lnurlp/:name/callback", get(lnurl_pay_callback))
+        .route("/lnurlpay", get(lnurl_pay).post(update_lnurl_pay))
+        .route("/lock", post(lock))
+        .route("/lockutxo", post(lock_utxo))
+        .route("/loglevel", get(log_level).post(update_log_level))
+        .route("/lsp/clients", get(lsp_clients))
+        .route("/lsp/config", get(lsp_config).post(update_lsp_config))
+        .route("/lspfeemenu", post(lsp_fee_menu))
+        .route("/maintenance/compact", post(compact_storage))
+        .route("/makerexecute", post(maker_execute))
+        .route("/makerinit", post(maker_init))
+        .route("/networkinfo", get(network_info))
+        .route("/nodeinfo", get(node_info))
+        .route("/nwc/connect", post(nwc_connect))
+        .route("/nwc/connections", get(nwc_connections))
+        .route("/nwc/revoke", post(nwc_revoke))
+        .route("/offer", post(offer))
+        .route("/openchannel", post(open_channel))
+        .route("/orders", post(create_order))
+        .route("/ownershipproof", get(ownership_proof))
+        .route("/payoffer", post(pay_offer))
+        .route("/peercompat", get(peer_compat))
+        .route("/peerfilter", get(peer_filter).post(update_peer_filter))
+        .route("/peersuggestions", get(peer_suggestions))
+        .route("/pendingbroadcasts", get(pending_broadcasts))
+        .route("/pendingchannels", get(pending_channels))
+        .route("/pendingfundings", get(pending_fundings))
+        .route("/phantomroutehints", get(phantom_route_hints))
+        .route("/probepayment", post(probe_payment))
+        .route("/queryroutes", post(query_routes))
+        .route("/rebalance", post(rebalance))
+        .route("/refreshtransfers", post(refresh_transfers))
+        .route("/reissueasset", post(reissue_asset))
+        .route("/rejectchannel", post(reject_channel))
+        .route("/reloadconfig", post(reload_config))
+        .route("/removehedgingpolicy", post(remove_hedging_policy))
+        .route("/resolvehtlc", post(resolve_htlc))
+        .route("/restore", post(restore))
+        .route("/restorechannels", post(restore_channels))
+        .route("/retrysweep", post(retry_sweep))
+        .route("/retrytransfer", post(retry_transfer))
+        .route("/revoketoken", post(revoke_token))
+        .route("/rgbinvoice", post(rgb_invoice))
+        .route("/sendasset", post(send_asset))
+        .route("/sendbtc", post(send_btc))
+        .route("/sendbtcmany", post(send_btc_many))
+        .route("/sendonionmessage", post(send_onion_message))
+        .route("/sendpayment", post(send_payment))
+        .route("/sendtoroute", post(send_to_route))
+        .route("/setalias", post(set_alias))
+        .route("/sethedgingpolicy", post(set_hedging_policy))
+        .route("/settleinvoice", post(settle_invoice))
+        .route("/settleinvoices", post(settle_invoices))
+        .route("/settlements", get(settlements))
+        .route("/setviewpassword", post(set_view_password))
+        .route("/shutdown", post(shutdown))
+        .route("/signmessage", post(sign_message))
+        .route("/signpsbt", post(sign_psbt))
+        .route("/spec", get(spec))
+        .route("/swapin", post(swap_in))
+        .route("/swapout", post(swap_out))
+        .route("/sweepconfig", get(sweep_config).post(update_sweep_config))
+        .route("/sync", post(sync))
+        .route("/syncstatus", get(sync_status))
+        .route("/taker", post(taker))
+        .route("/throttlegossip", post(throttle_gossip))
+        .route("/torstatus", get(tor_status))
+        .route("/transferdetail", post(transfer_detail))
+        .route("/unlock", post(unlock))
+        .route("/unlockutxo", post(unlock_utxo))
+        .route("/updatechannelpolicy", post(update_channel_policy))
+        .route("/verifymessage", post(verify_message))
+}
+
+/// Router of the APIs reporting balances, channels, payments and RGB assets, for dashboards and
+/// accountants that must not be able to move funds
+fn watch_only_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/addressstats", get(address_stats))
+        .route("/assetaudit/:asset_id", get(asset_audit))
+        .route("/assetbalance", post(asset_balance))
+        .route("/assetmetadata", post(asset_metadata))
+        .route("/audit", get(audit))
+        .route("/balancesheet", post(balance_sheet))
+        .route("/btcbalance", post(btc_balance))
+        .route("/channels/:channel_id/events", get(channel_events))
+        .route("/channelstatus", get(channel_status))
+        .route("/closedetail", post(close_detail))
+        .route("/decodelninvoice", post(decode_ln_invoice))
+        .route("/decodergbinvoice", post(decode_rgb_invoice))
+        .route("/descriptors", get(descriptors))
+        .route("/export/accounting", get(export_accounting))
+        .route("/feereport", get(fee_report))
+        .route("/forwardinghistory", get(forwarding_history))
+        .route("/getassetmedia", post(get_asset_media))
+        .route("/getchaninfo", get(get_chan_info))
+        .route("/getchannelid", post(get_channel_id))
+        .route("/getpayment", post(get_payment))
+        .route("/htlcs", get(htlcs))
+        .route("/invoicestatus", post(invoice_status))
+        .route("/listsweeps", get(list_sweeps))
+        .route("/listtransactions", post(list_transactions))
+        .route("/listunspents", post(list_unspents))
+        .route("/lock", post(lock))
+        .route("/networkinfo", get(network_info))
+        .route("/nodeinfo", get(node_info))
+        .route("/pendingchannels", get(pending_channels))
+        .route("/settlements", get(settlements))
+        .route("/shutdown", post(shutdown))
+        .route("/spec", get(spec))
+        .route("/syncstatus", get(sync_status))
+        .route("/unlockview", post(unlock_view))
+        .route("/verifymessage", post(verify_message))
+}
+
+/// Router of the list APIs, which /v2 paginates with cursors and the other versions return whole
+fn list_router(v1: bool) -> Router<Arc<AppState>> {
+    if v1 {
+        Router::new()
+            .route("/listassets", post(list_assets_v1))
+            .route("/listchannels", get(list_channels_v1))
+            .route("/listpayments", get(list_payments_v1))
+            .route("/listpeers", get(list_peers_v1))
+            .route("/listtransfers", post(list_transfers_v1))
+    } else {
+        Router::new()
+            .route("/listassets", post(list_assets))
+            .route("/listchannels", get(list_channels))
+            .route("/listpayments", get(list_payments))
+            .route("/listpeers", get(list_peers))
+            .route("/listtransfers", post(list_transfers))
+    }
+}
+
+impl AppState {
+    fn wait_state_change(&self) -> bool {
+        let _unlocked_state = self.get_unlocked_app_state();
+        let mut changing_state = self.get_changing_state();
+        if !*changing_state {
+            *changing_state = true;
+            return true;
+        }
+        false
+    }
+}
+
+/// Re-read the config file each time the process receives a SIGHUP
+#[cfg(unix)]
+async fn reload_config_on_hangup(app_state: Arc<AppState>) {
+    let mut hangup = signal::unix::signal(signal::unix::SignalKind::hangup())
+        .expect("failed to install signal handler");
+    while hangup.recv().await.is_some() {
+        match app_state.reload_config().await {
+            Ok(reload) => tracing::info!(
+                "Reloaded config on SIGHUP, applied {:?}, skipped {:?}",
+                reload.applied,
+                reload.skipped
+            ),
+            Err(e) => tracing::error!("Failed to reload config on SIGHUP: {e}"),
+        }
+    }
+}
+
+/// Tokio signal handler that will wait for a user to press CTRL+C.
+async fn shutdown_signal(app_state: Arc<AppState>) {
+    let cancel_token = app_state.cancel_token.clone();
+
+    let ctrl_c = async {
+        signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("failed to install signal handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+        _ = cancel_token.cancelled() => {},
+    }
+
+    tracing::info!("Received a shutdown signal");
+
+    drain_before_shutdown(app_state.clone()).await;
+
+    let app_state_copy = app_state.clone();
+    loop {
+        {
+            if app_state_copy.wait_state_change() {
+                break;
+            }
+        }
+        tracing::info!("Will shutdown after change state is complete");
+        tokio::time::sleep(Duration::from_millis(300)).await;
+    }
+    stop_ldk(app_state.clone()).await;
+}
+
+// workaround for https://github.com/tokio-rs/tracing/issues/1372
+#[derive(Default)]
+struct TypedFields(DefaultFields);
+
+impl<'writer> FormatFields<'writer> for TypedFields {
+    fn format_fields<R: tracing_subscriber::field::RecordFields>(
+        &self,
+        writer: Writer<'writer>,
+        fields: R,
+    ) -> std::fmt::Result {
+        self.0.format_fields(writer, fields)
+    }
+}
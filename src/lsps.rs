@@ -0,0 +1,1053 @@
+use amplify::s;
+use bitcoin::hashes::hmac::{Hmac, HmacEngine};
+use bitcoin::hashes::{sha256, Hash, HashEngine};
+use bitcoin::secp256k1::PublicKey;
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+use lightning::impl_writeable_tlv_based;
+use lightning::io::{self, Read};
+use lightning::ln::channelmanager::InterceptId;
+use lightning::ln::msgs::{DecodeError, Init, LightningError};
+use lightning::ln::peer_handler::CustomMessageHandler;
+use lightning::ln::types::ChannelId;
+use lightning::ln::wire::{CustomMessageReader, Type};
+use lightning::types::features::{InitFeatures, NodeFeatures};
+use lightning::types::payment::PaymentHash;
+use lightning::util::hash_tables::HashMap as LdkHashMap;
+use lightning::util::persist::KVStoreSync;
+use lightning::util::ser::{LengthLimitedRead, Writeable, Writer};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+use crate::disk::{LEASE_CONFIG_FNAME, LEASE_ORDERS_FNAME, LSP_CLIENTS_FNAME, LSP_CONFIG_FNAME};
+use crate::error::APIError;
+use crate::ldk::{ChannelManager, PeerManager};
+use crate::routes::{LeaseOrderStatus, LspClientStatus};
+use crate::store::NodeStore;
+use crate::utils::{get_current_timestamp, hex_str};
+
+/// BOLT8 message type reserved to the LSPS0 transport
+pub(crate) const LSPS_MESSAGE_TYPE: u16 = 37913;
+const LSPS_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const LSPS2_GET_INFO_METHOD: &str = "lsps2.get_info";
+const LSPS2_BUY_METHOD: &str = "lsps2.buy";
+const LEASE_GET_RATES_METHOD: &str = "lease.get_rates";
+const LEASE_BUY_METHOD: &str = "lease.buy";
+const LSPS0_METHOD_NOT_FOUND: i32 = -32601;
+const LSPS0_INVALID_PARAMS: i32 = -32602;
+pub(crate) const LSPS0_INTERNAL_ERROR: i32 = -32603;
+const LSPS2_INVALID_OPENING_FEE_PARAMS: i32 = 201;
+const LSPS2_PAYMENT_SIZE_TOO_SMALL: i32 = 202;
+const LSPS2_PAYMENT_SIZE_TOO_LARGE: i32 = 203;
+/// Lifetime of JIT channels and max delay of the client, as promised in the fee parameters
+const LSP_MIN_LIFETIME_BLOCKS: u32 = 1008;
+const LSP_MAX_CLIENT_TO_SELF_DELAY: u32 = 2016;
+/// Clients that bought a JIT channel which never got a payment are dropped after this time
+const LSP_CLIENT_EXPIRY_SEC: u64 = 7 * 24 * 3600;
+
+/// LSPS0 message, a JSON-RPC 2.0 object sent as the whole message payload
+#[derive(Clone, Debug)]
+pub(crate) struct LspsMessage {
+    payload: String,
+}
+
+impl Type for LspsMessage {
+    fn type_id(&self) -> u16 {
+        LSPS_MESSAGE_TYPE
+    }
+}
+
+impl Writeable for LspsMessage {
+    fn write<W: Writer>(&self, w: &mut W) -> Result<(), io::Error> {
+        w.write_all(self.payload.as_bytes())
+    }
+}
+
+/// Serialize msat amounts as strings, as LSPS0 requires for values that may not fit a JSON number
+mod msat_string {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Fees and limits of a JIT channel, as offered by the LSP and bound by its promise
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct OpeningFeeParams {
+    #[serde(with = "msat_string")]
+    pub(crate) min_fee_msat: u64,
+    pub(crate) proportional: u32,
+    pub(crate) valid_until: String,
+    pub(crate) min_lifetime: u32,
+    pub(crate) max_client_to_self_delay: u32,
+    #[serde(with = "msat_string")]
+    pub(crate) min_payment_size_msat: u64,
+    #[serde(with = "msat_string")]
+    pub(crate) max_payment_size_msat: u64,
+    pub(crate) promise: String,
+}
+
+impl OpeningFeeParams {
+    /// Fee charged by the LSP for a JIT channel receiving the given payment, None on overflow
+    pub(crate) fn opening_fee_msat(&self, payment_size_msat: u64) -> Option<u64> {
+        let proportional_fee = (payment_size_msat as u128 * self.proportional as u128)
+            .div_ceil(1_000_000)
+            .try_into()
+            .ok()?;
+        Some(self.min_fee_msat.max(proportional_fee))
+    }
+
+    pub(crate) fn is_valid_for(&self, payment_size_msat: u64, now: DateTime<Utc>) -> bool {
+        let not_expired = DateTime::parse_from_rfc3339(&self.valid_until)
+            .is_ok_and(|valid_until| valid_until > now);
+        not_expired
+            && payment_size_msat >= self.min_payment_size_msat
+            && payment_size_msat <= self.max_payment_size_msat
+            && self
+                .opening_fee_msat(payment_size_msat)
+                .is_some_and(|fee| fee < payment_size_msat)
+    }
+}
+
+/// Pick the cheapest fee parameters of the menu that can be used for the given payment
+pub(crate) fn cheapest_opening_fee_params(
+    menu: Vec<OpeningFeeParams>,
+    payment_size_msat: u64,
+) -> Option<(OpeningFeeParams, u64)> {
+    let now = Utc::now();
+    menu.into_iter()
+        .filter(|p| p.is_valid_for(payment_size_msat, now))
+        .filter_map(|p| {
+            let fee_msat = p.opening_fee_msat(payment_size_msat)?;
+            Some((p, fee_msat))
+        })
+        .min_by_key(|(_, fee_msat)| *fee_msat)
+}
+
+#[derive(Deserialize)]
+struct GetInfoResult {
+    opening_fee_params_menu: Vec<OpeningFeeParams>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct BuyResult {
+    pub(crate) jit_channel_scid: String,
+    pub(crate) lsp_cltv_expiry_delta: u16,
+}
+
+impl BuyResult {
+    /// Parse the SCID, given in the BLOCKxTXxOUTPUT format
+    pub(crate) fn scid(&self) -> Option<u64> {
+        let mut parts = self.jit_channel_scid.split('x');
+        let block: u64 = parts.next()?.parse().ok()?;
+        let tx_index: u64 = parts.next()?.parse().ok()?;
+        let output_index: u64 = parts.next()?.parse().ok()?;
+        if parts.next().is_some()
+            || block >= 1 << 24
+            || tx_index >= 1 << 24
+            || output_index >= 1 << 16
+        {
+            return None;
+        }
+        Some(block << 40 | tx_index << 16 | output_index)
+    }
+}
+
+/// Format an SCID in the BLOCKxTXxOUTPUT format
+pub(crate) fn scid_to_string(scid: u64) -> String {
+    format!(
+        "{}x{}x{}",
+        scid >> 40,
+        (scid >> 16) & 0xffffff,
+        scid & 0xffff
+    )
+}
+
+#[derive(Deserialize)]
+struct BuyParams {
+    opening_fee_params: OpeningFeeParams,
+    #[serde(with = "msat_string")]
+    payment_size_msat: u64,
+}
+
+/// Fees and limits applied when acting as an LSP
+#[derive(Clone, Debug)]
+pub(crate) struct LspConfig {
+    pub(crate) enabled: bool,
+    pub(crate) min_fee_msat: u64,
+    pub(crate) fee_proportional_millionths: u32,
+    pub(crate) min_payment_size_msat: u64,
+    pub(crate) max_payment_size_msat: u64,
+    pub(crate) min_channel_size_sat: u64,
+    pub(crate) fee_params_validity_sec: u32,
+}
+
+impl_writeable_tlv_based!(LspConfig, {
+    (0, enabled, required),
+    (2, min_fee_msat, required),
+    (4, fee_proportional_millionths, required),
+    (6, min_payment_size_msat, required),
+    (8, max_payment_size_msat, required),
+    (10, min_channel_size_sat, required),
+    (12, fee_params_validity_sec, required),
+});
+
+impl Default for LspConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_fee_msat: 1_000_000,
+            fee_proportional_millionths: 5_000,
+            min_payment_size_msat: 10_000_000,
+            max_payment_size_msat: 1_000_000_000,
+            min_channel_size_sat: 100_000,
+            fee_params_validity_sec: 3600,
+        }
+    }
+}
+
+/// Client that bought a JIT channel from us
+#[derive(Clone, Debug)]
+pub(crate) struct LspClientInfo {
+    pub(crate) client_pubkey: PublicKey,
+    pub(crate) payment_size_msat: u64,
+    pub(crate) opening_fee_msat: u64,
+    pub(crate) status: LspClientStatus,
+    pub(crate) intercept_id: Option<InterceptId>,
+    pub(crate) htlc_amount_msat: Option<u64>,
+    pub(crate) user_channel_id: Option<u128>,
+    pub(crate) channel_id: Option<ChannelId>,
+    pub(crate) created_at: u64,
+    pub(crate) updated_at: u64,
+}
+
+impl_writeable_tlv_based!(LspClientInfo, {
+    (0, client_pubkey, required),
+    (2, payment_size_msat, required),
+    (4, opening_fee_msat, required),
+    (6, status, required),
+    (8, intercept_id, option),
+    (10, htlc_amount_msat, option),
+    (12, user_channel_id, option),
+    (14, channel_id, option),
+    (16, created_at, required),
+    (18, updated_at, required),
+});
+
+/// LSP clients, by the intercept SCID of their JIT channel
+pub(crate) struct LspClientMap {
+    pub(crate) clients: LdkHashMap<u64, LspClientInfo>,
+}
+
+impl_writeable_tlv_based!(LspClientMap, {
+    (0, clients, required),
+});
+
+/// JIT channel to be opened to a client, following the interception of its payment
+pub(crate) struct JitChannelOpen {
+    pub(crate) client_pubkey: PublicKey,
+    pub(crate) capacity_sat: u64,
+    pub(crate) user_channel_id: u128,
+}
+
+/// Rates at which inbound channels are leased, as advertised to buyers
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct LeaseRates {
+    pub(crate) min_channel_size_sat: u64,
+    pub(crate) max_channel_size_sat: u64,
+    pub(crate) fee_base_sat: u64,
+    pub(crate) fee_proportional_millionths: u32,
+    pub(crate) lease_duration_blocks: u32,
+}
+
+impl LeaseRates {
+    /// Fee for leasing a channel of the given capacity
+    pub(crate) fn fee_sat(&self, capacity_sat: u64) -> u64 {
+        let proportional_sat = (capacity_sat as u128 * self.fee_proportional_millionths as u128)
+            .div_ceil(1_000_000) as u64;
+        self.fee_base_sat.saturating_add(proportional_sat)
+    }
+}
+
+#[derive(Deserialize)]
+struct LeaseBuyParams {
+    capacity_sat: u64,
+    asset_id: Option<String>,
+    asset_amount: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct LeaseBuyResult {
+    pub(crate) order_id: String,
+    pub(crate) fee_sat: u64,
+    pub(crate) invoice: String,
+    pub(crate) lease_duration_blocks: u32,
+}
+
+/// Rates and limits applied when selling inbound channels
+#[derive(Clone, Debug)]
+pub(crate) struct LeaseConfig {
+    pub(crate) enabled: bool,
+    pub(crate) min_channel_size_sat: u64,
+    pub(crate) max_channel_size_sat: u64,
+    pub(crate) fee_base_sat: u64,
+    pub(crate) fee_proportional_millionths: u32,
+    pub(crate) lease_duration_blocks: u32,
+    pub(crate) order_expiry_sec: u32,
+}
+
+impl_writeable_tlv_based!(LeaseConfig, {
+    (0, enabled, required),
+    (2, min_channel_size_sat, required),
+    (4, max_channel_size_sat, required),
+    (6, fee_base_sat, required),
+    (8, fee_proportional_millionths, required),
+    (10, lease_duration_blocks, required),
+    (12, order_expiry_sec, required),
+});
+
+impl LeaseConfig {
+    pub(crate) fn rates(&self) -> LeaseRates {
+        LeaseRates {
+            min_channel_size_sat: self.min_channel_size_sat,
+            max_channel_size_sat: self.max_channel_size_sat,
+            fee_base_sat: self.fee_base_sat,
+            fee_proportional_millionths: self.fee_proportional_millionths,
+            lease_duration_blocks: self.lease_duration_blocks,
+        }
+    }
+}
+
+impl Default for LeaseConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_channel_size_sat: 100_000,
+            max_channel_size_sat: 10_000_000,
+            fee_base_sat: 1_000,
+            fee_proportional_millionths: 10_000,
+            lease_duration_blocks: 4032,
+            order_expiry_sec: 3600,
+        }
+    }
+}
+
+/// Inbound channel sold to a buyer, opened once the lease fee has been paid
+#[derive(Clone, Debug)]
+pub(crate) struct LeaseOrderInfo {
+    pub(crate) client_pubkey: PublicKey,
+    pub(crate) capacity_sat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) fee_sat: u64,
+    pub(crate) lease_duration_blocks: u32,
+    pub(crate) payment_hash: PaymentHash,
+    pub(crate) status: LeaseOrderStatus,
+    pub(crate) temporary_channel_id: Option<String>,
+    pub(crate) expires_at: u64,
+    pub(crate) created_at: u64,
+    pub(crate) updated_at: u64,
+}
+
+impl_writeable_tlv_based!(LeaseOrderInfo, {
+    (0, client_pubkey, required),
+    (2, capacity_sat, required),
+    (4, asset_id, option),
+    (6, asset_amount, option),
+    (8, fee_sat, required),
+    (10, lease_duration_blocks, required),
+    (12, payment_hash, required),
+    (14, status, required),
+    (16, temporary_channel_id, option),
+    (18, expires_at, required),
+    (20, created_at, required),
+    (22, updated_at, required),
+});
+
+/// Lease orders, by order ID
+pub(crate) struct LeaseOrderMap {
+    pub(crate) orders: LdkHashMap<String, LeaseOrderInfo>,
+}
+
+impl_writeable_tlv_based!(LeaseOrderMap, {
+    (0, orders, required),
+});
+
+/// Lease bought by a client, waiting for the fee invoice to be created before being answered
+pub(crate) struct LeaseRequest {
+    pub(crate) request_id: String,
+    pub(crate) client_pubkey: PublicKey,
+    pub(crate) capacity_sat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) fee_sat: u64,
+    pub(crate) lease_duration_blocks: u32,
+}
+
+/// State of the node acting as an LSP
+struct LspServer {
+    channel_manager: Arc<ChannelManager>,
+    kv_store: Arc<NodeStore>,
+    promise_key: [u8; 32],
+    cltv_expiry_delta: u16,
+    config: Mutex<LspConfig>,
+    clients: Mutex<LspClientMap>,
+    lease_config: Mutex<LeaseConfig>,
+    lease_orders: Mutex<LeaseOrderMap>,
+    lease_requests: Mutex<Vec<LeaseRequest>>,
+}
+
+type PendingRequest = oneshot::Sender<Result<Value, String>>;
+
+/// LSPS0 endpoint, exchanging JSON-RPC messages as custom messages. As a client it sends requests
+/// to LSPs, as an LSP it answers LSPS2 requests and tracks the JIT channels sold to clients. The
+/// same transport carries the lease requests for inbound channels.
+pub(crate) struct LspsMessageHandler {
+    pending_messages: Mutex<Vec<(PublicKey, LspsMessage)>>,
+    pending_requests: Mutex<HashMap<String, (PublicKey, PendingRequest)>>,
+    server: LspServer,
+}
+
+impl LspsMessageHandler {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        channel_manager: Arc<ChannelManager>,
+        kv_store: Arc<NodeStore>,
+        promise_key: [u8; 32],
+        cltv_expiry_delta: u16,
+        config: LspConfig,
+        clients: LspClientMap,
+        lease_config: LeaseConfig,
+        lease_orders: LeaseOrderMap,
+    ) -> Self {
+        Self {
+            pending_messages: Mutex::new(vec![]),
+            pending_requests: Mutex::new(HashMap::new()),
+            server: LspServer {
+                channel_manager,
+                kv_store,
+                promise_key,
+                cltv_expiry_delta,
+                config: Mutex::new(config),
+                clients: Mutex::new(clients),
+                lease_config: Mutex::new(lease_config),
+                lease_orders: Mutex::new(lease_orders),
+                lease_requests: Mutex::new(vec![]),
+            },
+        }
+    }
+
+    pub(crate) fn lsp_config(&self) -> LspConfig {
+        self.server.config.lock().unwrap().clone()
+    }
+
+    pub(crate) fn set_lsp_config(&self, config: LspConfig) {
+        let mut current_config = self.server.config.lock().unwrap();
+        *current_config = config;
+        self.server
+            .kv_store
+            .write("", "", LSP_CONFIG_FNAME, current_config.encode())
+            .unwrap();
+    }
+
+    pub(crate) fn lsp_clients(&self) -> LdkHashMap<u64, LspClientInfo> {
+        self.server.clients.lock().unwrap().clients.clone()
+    }
+
+    fn save_lsp_clients(&self, clients: &LspClientMap) {
+        self.server
+            .kv_store
+            .write("", "", LSP_CLIENTS_FNAME, clients.encode())
+            .unwrap();
+    }
+
+    /// Update the status of the client and return its info
+    fn update_lsp_client(
+        &self,
+        clients: &mut LspClientMap,
+        scid: u64,
+        update: impl FnOnce(&mut LspClientInfo),
+    ) -> LspClientInfo {
+        let client = clients.clients.get_mut(&scid).unwrap();
+        update(client);
+        client.updated_at = get_current_timestamp();
+        let client = client.clone();
+        self.save_lsp_clients(clients);
+        client
+    }
+
+    pub(crate) fn lease_config(&self) -> LeaseConfig {
+        self.server.lease_config.lock().unwrap().clone()
+    }
+
+    pub(crate) fn set_lease_config(&self, config: LeaseConfig) {
+        let mut current_config = self.server.lease_config.lock().unwrap();
+        *current_config = config;
+        self.server
+            .kv_store
+            .write("", "", LEASE_CONFIG_FNAME, current_config.encode())
+            .unwrap();
+    }
+
+    pub(crate) fn lease_orders(&self) -> LdkHashMap<String, LeaseOrderInfo> {
+        self.server.lease_orders.lock().unwrap().orders.clone()
+    }
+
+    fn save_lease_orders(&self, orders: &LeaseOrderMap) {
+        self.server
+            .kv_store
+            .write("", "", LEASE_ORDERS_FNAME, orders.encode())
+            .unwrap();
+    }
+
+    pub(crate) fn add_lease_order(&self, order_id: String, order: LeaseOrderInfo) {
+        let mut orders = self.server.lease_orders.lock().unwrap();
+        orders.orders.insert(order_id, order);
+        self.save_lease_orders(&orders);
+    }
+
+    /// Update the lease order, if it exists
+    pub(crate) fn update_lease_order(
+        &self,
+        order_id: &str,
+        update: impl FnOnce(&mut LeaseOrderInfo),
+    ) {
+        let mut orders = self.server.lease_orders.lock().unwrap();
+        if let Some(order) = orders.orders.get_mut(order_id) {
+            update(order);
+            order.updated_at = get_current_timestamp();
+            self.save_lease_orders(&orders);
+        }
+    }
+
+    /// Take the leases bought since the last call, which still need a fee invoice
+    pub(crate) fn take_lease_requests(&self) -> Vec<LeaseRequest> {
+        std::mem::take(&mut *self.server.lease_requests.lock().unwrap())
+    }
+
+    /// Queue the answer to a request of a client, to be sent when processing the peer events
+    pub(crate) fn respond(
+        &self,
+        client_pubkey: PublicKey,
+        id: &str,
+        res: Result<Value, (i32, String)>,
+    ) {
+        let payload = match res {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message },
+            }),
+        };
+        self.pending_messages.lock().unwrap().push((
+            client_pubkey,
+            LspsMessage {
+                payload: payload.to_string(),
+            },
+        ));
+    }
+
+    /// Promise binding the fee parameters, so that clients cannot alter them when buying
+    fn promise(&self, params: &OpeningFeeParams) -> String {
+        let mut engine = HmacEngine::<sha256::Hash>::new(&self.server.promise_key);
+        engine.input(
+            format!(
+                "{}|{}|{}|{}|{}|{}|{}",
+                params.min_fee_msat,
+                params.proportional,
+                params.valid_until,
+                params.min_lifetime,
+                params.max_client_to_self_delay,
+                params.min_payment_size_msat,
+                params.max_payment_size_msat
+            )
+            .as_bytes(),
+        );
+        hex_str(&Hmac::<sha256::Hash>::from_engine(engine).to_byte_array())
+    }
+
+    fn opening_fee_params(&self, config: &LspConfig) -> OpeningFeeParams {
+        let valid_until = Utc::now() + TimeDelta::seconds(config.fee_params_validity_sec as i64);
+        let mut params = OpeningFeeParams {
+            min_fee_msat: config.min_fee_msat,
+            proportional: config.fee_proportional_millionths,
+            valid_until: valid_until.to_rfc3339_opts(SecondsFormat::Millis, true),
+            min_lifetime: LSP_MIN_LIFETIME_BLOCKS,
+            max_client_to_self_delay: LSP_MAX_CLIENT_TO_SELF_DELAY,
+            min_payment_size_msat: config.min_payment_size_msat,
+            max_payment_size_msat: config.max_payment_size_msat,
+            promise: String::new(),
+        };
+        params.promise = self.promise(&params);
+        params
+    }
+
+    fn handle_buy(&self, client_pubkey: PublicKey, params: &Value) -> Result<Value, (i32, String)> {
+        let params: BuyParams = serde_json::from_value(params.clone())
+            .map_err(|e| (LSPS0_INVALID_PARAMS, format!("invalid params: {e}")))?;
+        let fee_params = params.opening_fee_params;
+        let payment_size_msat = params.payment_size_msat;
+        let not_expired = DateTime::parse_from_rfc3339(&fee_params.valid_until)
+            .is_ok_and(|valid_until| valid_until > Utc::now());
+        if fee_params.promise != self.promise(&fee_params) || !not_expired {
+            return Err((
+                LSPS2_INVALID_OPENING_FEE_PARAMS,
+                s!("invalid or expired opening_fee_params"),
+            ));
+        }
+        if payment_size_msat > fee_params.max_payment_size_msat {
+            return Err((LSPS2_PAYMENT_SIZE_TOO_LARGE, s!("payment_size_too_large")));
+        }
+        let opening_fee_msat = fee_params
+            .opening_fee_msat(payment_size_msat)
+            .filter(|fee| *fee < payment_size_msat)
+            .filter(|_| payment_size_msat >= fee_params.min_payment_size_msat)
+            .ok_or_else(|| (LSPS2_PAYMENT_SIZE_TOO_SMALL, s!("payment_size_too_small")))?;
+
+        let scid = self.server.channel_manager.get_intercept_scid();
+        let now = get_current_timestamp();
+        let mut clients = self.server.clients.lock().unwrap();
+        clients.clients.retain(|_, c| {
+            c.status != LspClientStatus::Waiting || c.created_at + LSP_CLIENT_EXPIRY_SEC > now
+        });
+        clients.clients.insert(
+            scid,
+            LspClientInfo {
+                client_pubkey,
+                payment_size_msat,
+                opening_fee_msat,
+                status: LspClientStatus::Waiting,
+                intercept_id: None,
+                htlc_amount_msat: None,
+                user_channel_id: None,
+                channel_id: None,
+                created_at: now,
+                updated_at: now,
+            },
+        );
+        self.save_lsp_clients(&clients);
+        tracing::info!(
+            "Sold JIT channel {} to {client_pubkey}",
+            scid_to_string(scid)
+        );
+
+        Ok(json!({
+            "jit_channel_scid": scid_to_string(scid),
+            "lsp_cltv_expiry_delta": self.server.cltv_expiry_delta,
+            "client_trusts_lsp": false,
+        }))
+    }
+
+    /// Validate the lease bought by a client, queueing it until its fee invoice is ready
+    fn handle_lease_buy(
+        &self,
+        client_pubkey: PublicKey,
+        id: &str,
+        config: &LeaseConfig,
+        params: &Value,
+    ) -> Result<(), (i32, String)> {
+        let params: LeaseBuyParams = serde_json::from_value(params.clone())
+            .map_err(|e| (LSPS0_INVALID_PARAMS, format!("invalid params: {e}")))?;
+        if params.capacity_sat < config.min_channel_size_sat
+            || params.capacity_sat > config.max_channel_size_sat
+        {
+            return Err((
+                LSPS0_INVALID_PARAMS,
+                format!(
+                    "capacity_sat must be between {} and {}",
+                    config.min_channel_size_sat, config.max_channel_size_sat
+                ),
+            ));
+        }
+        match (&params.asset_id, params.asset_amount) {
+            (None, None) => {}
+            (Some(_), Some(asset_amount)) if asset_amount > 0 => {}
+            _ => {
+                return Err((
+                    LSPS0_INVALID_PARAMS,
+                    s!("asset_id and a positive asset_amount must be provided together"),
+                ))
+            }
+        }
+
+        let rates = config.rates();
+        self.server
+            .lease_requests
+            .lock()
+            .unwrap()
+            .push(LeaseRequest {
+                request_id: id.to_string(),
+                client_pubkey,
+                capacity_sat: params.capacity_sat,
+                asset_id: params.asset_id,
+                asset_amount: params.asset_amount,
+                fee_sat: rates.fee_sat(params.capacity_sat),
+                lease_duration_blocks: rates.lease_duration_blocks,
+            });
+        Ok(())
+    }
+
+    /// Answer an LSPS request of a client, if LSP or lease mode is enabled. None if the answer is
+    /// deferred.
+    fn handle_request(
+        &self,
+        client_pubkey: PublicKey,
+        id: &str,
+        method: &str,
+        params: &Value,
+    ) -> Result<Option<Value>, (i32, String)> {
+        let config = self.lsp_config();
+        let lease_config = self.lease_config();
+        match method {
+            LSPS2_GET_INFO_METHOD if config.enabled => Ok(Some(json!({
+                "opening_fee_params_menu": [self.opening_fee_params(&config)],
+            }))),
+            LSPS2_BUY_METHOD if config.enabled => self.handle_buy(client_pubkey, params).map(Some),
+            LEASE_GET_RATES_METHOD if lease_config.enabled => Ok(Some(json!(lease_config.rates()))),
+            LEASE_BUY_METHOD if lease_config.enabled => self
+                .handle_lease_buy(client_pubkey, id, &lease_config, params)
+                .map(|_| None),
+            _ => Err((LSPS0_METHOD_NOT_FOUND, s!("method not found"))),
+        }
+    }
+
+    /// Register the HTLC intercepted for a JIT channel, returning the channel to be opened to the
+    /// client. None if the SCID doesn't belong to a client waiting for its payment.
+    pub(crate) fn jit_channel_htlc(
+        &self,
+        scid: u64,
+        intercept_id: InterceptId,
+        amt_msat: u64,
+    ) -> Option<Result<JitChannelOpen, String>> {
+        let mut clients = self.server.clients.lock().unwrap();
+        let client = clients.clients.get(&scid)?;
+        if client.status != LspClientStatus::Waiting {
+            return None;
+        }
+        if amt_msat < client.payment_size_msat {
+            let payment_size_msat = client.payment_size_msat;
+            self.update_lsp_client(&mut clients, scid, |c| c.status = LspClientStatus::Failed);
+            return Some(Err(format!(
+                "HTLC of {amt_msat} msat is lower than the payment size of {payment_size_msat} msat"
+            )));
+        }
+
+        let min_channel_size_sat = self.lsp_config().min_channel_size_sat;
+        // leave the client some inbound liquidity besides the payment
+        let capacity_sat = min_channel_size_sat.max(2 * amt_msat.div_ceil(1000));
+        let user_channel_id = rand::random::<u128>();
+        let client = self.update_lsp_client(&mut clients, scid, |c| {
+            c.status = LspClientStatus::Opening;
+            c.intercept_id = Some(intercept_id);
+            c.htlc_amount_msat = Some(amt_msat);
+            c.user_channel_id = Some(user_channel_id);
+        });
+        Some(Ok(JitChannelOpen {
+            client_pubkey: client.client_pubkey,
+            capacity_sat,
+            user_channel_id,
+        }))
+    }
+
+    pub(crate) fn jit_channel_failed(&self, scid: u64) {
+        let mut clients = self.server.clients.lock().unwrap();
+        if clients.clients.contains_key(&scid) {
+            self.update_lsp_client(&mut clients, scid, |c| c.status = LspClientStatus::Failed);
+        }
+    }
+
+    fn jit_channel_scid(clients: &LspClientMap, user_channel_id: u128) -> Option<u64> {
+        clients
+            .clients
+            .iter()
+            .find(|(_, c)| {
+                c.status == LspClientStatus::Opening && c.user_channel_id == Some(user_channel_id)
+            })
+            .map(|(scid, _)| *scid)
+    }
+
+    /// Whether the channel being opened is a JIT channel sold to a client
+    pub(crate) fn is_jit_channel(&self, user_channel_id: u128) -> bool {
+        let clients = self.server.clients.lock().unwrap();
+        Self::jit_channel_scid(&clients, user_channel_id).is_some()
+    }
+
+    /// Mark the JIT channel as ready, returning its SCID, the intercepted HTLC and the amount to
+    /// be forwarded, net of the opening fee
+    pub(crate) fn jit_channel_ready(
+        &self,
+        user_channel_id: u128,
+        channel_id: ChannelId,
+    ) -> Option<(u64, InterceptId, PublicKey, u64)> {
+        let mut clients = self.server.clients.lock().unwrap();
+        let scid = Self::jit_channel_scid(&clients, user_channel_id)?;
+        let client = self.update_lsp_client(&mut clients, scid, |c| {
+            c.status = LspClientStatus::Forwarded;
+            c.channel_id = Some(channel_id);
+        });
+        let amt_msat = client.htlc_amount_msat.unwrap() - client.opening_fee_msat;
+        Some((
+            scid,
+            client.intercept_id.unwrap(),
+            client.client_pubkey,
+            amt_msat,
+        ))
+    }
+
+    /// Mark as failed the JIT channel that closed before getting ready, returning the intercepted
+    /// HTLC to be failed back
+    pub(crate) fn jit_channel_closed(&self, user_channel_id: u128) -> Option<InterceptId> {
+        let mut clients = self.server.clients.lock().unwrap();
+        let scid = Self::jit_channel_scid(&clients, user_channel_id)?;
+        let client =
+            self.update_lsp_client(&mut clients, scid, |c| c.status = LspClientStatus::Failed);
+        client.intercept_id
+    }
+
+    async fn request(
+        &self,
+        peer_manager: &PeerManager,
+        lsp_pubkey: PublicKey,
+        method: &str,
+        params: Value,
+    ) -> Result<Value, APIError> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let (sender, receiver) = oneshot::channel();
+        self.pending_requests
+            .lock()
+            .unwrap()
+            .insert(id.clone(), (lsp_pubkey, sender));
+        let payload = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+        self.pending_messages.lock().unwrap().push((
+            lsp_pubkey,
+            LspsMessage {
+                payload: payload.to_string(),
+            },
+        ));
+        peer_manager.process_events();
+
+        let res = tokio::time::timeout(LSPS_REQUEST_TIMEOUT, receiver).await;
+        self.pending_requests.lock().unwrap().remove(&id);
+        match res {
+            Ok(Ok(Ok(result))) => Ok(result),
+            Ok(Ok(Err(e))) => Err(APIError::FailedLspRequest(format!("{method}: {e}"))),
+            Ok(Err(_)) => Err(APIError::FailedLspRequest(format!(
+                "{method}: the LSP disconnected"
+            ))),
+            Err(_) => Err(APIError::FailedLspRequest(format!(
+                "{method}: no response from the LSP"
+            ))),
+        }
+    }
+
+    /// Get the JIT channel fee menu offered by the LSP
+    pub(crate) async fn lsps2_get_info(
+        &self,
+        peer_manager: &PeerManager,
+        lsp_pubkey: PublicKey,
+        token: Option<String>,
+    ) -> Result<Vec<OpeningFeeParams>, APIError> {
+        let params = match token {
+            Some(token) => json!({ "token": token }),
+            None => json!({}),
+        };
+        let result = self
+            .request(peer_manager, lsp_pubkey, LSPS2_GET_INFO_METHOD, params)
+            .await?;
+        let result: GetInfoResult = serde_json::from_value(result).map_err(|e| {
+            APIError::FailedLspRequest(format!("{LSPS2_GET_INFO_METHOD}: invalid response: {e}"))
+        })?;
+        Ok(result.opening_fee_params_menu)
+    }
+
+    /// Buy a JIT channel that will be opened when a payment of the given size reaches the LSP
+    pub(crate) async fn lsps2_buy(
+        &self,
+        peer_manager: &PeerManager,
+        lsp_pubkey: PublicKey,
+        opening_fee_params: &OpeningFeeParams,
+        payment_size_msat: u64,
+    ) -> Result<BuyResult, APIError> {
+        let result = self
+            .request(
+                peer_manager,
+                lsp_pubkey,
+                LSPS2_BUY_METHOD,
+                json!({
+                    "opening_fee_params": opening_fee_params,
+                    "payment_size_msat": payment_size_msat.to_string(),
+                }),
+            )
+            .await?;
+        serde_json::from_value(result).map_err(|e| {
+            APIError::FailedLspRequest(format!("{LSPS2_BUY_METHOD}: invalid response: {e}"))
+        })
+    }
+
+    /// Get the rates at which the seller leases inbound channels
+    pub(crate) async fn lease_get_rates(
+        &self,
+        peer_manager: &PeerManager,
+        seller_pubkey: PublicKey,
+    ) -> Result<LeaseRates, APIError> {
+        let result = self
+            .request(
+                peer_manager,
+                seller_pubkey,
+                LEASE_GET_RATES_METHOD,
+                json!({}),
+            )
+            .await?;
+        serde_json::from_value(result).map_err(|e| {
+            APIError::FailedLspRequest(format!("{LEASE_GET_RATES_METHOD}: invalid response: {e}"))
+        })
+    }
+
+    /// Buy an inbound channel, that the seller opens once the returned fee invoice gets paid
+    pub(crate) async fn lease_buy(
+        &self,
+        peer_manager: &PeerManager,
+        seller_pubkey: PublicKey,
+        capacity_sat: u64,
+        asset_id: Option<String>,
+        asset_amount: Option<u64>,
+    ) -> Result<LeaseBuyResult, APIError> {
+        let result = self
+            .request(
+                peer_manager,
+                seller_pubkey,
+                LEASE_BUY_METHOD,
+                json!({
+                    "capacity_sat": capacity_sat,
+                    "asset_id": asset_id,
+                    "asset_amount": asset_amount,
+                }),
+            )
+            .await?;
+        serde_json::from_value(result).map_err(|e| {
+            APIError::FailedLspRequest(format!("{LEASE_BUY_METHOD}: invalid response: {e}"))
+        })
+    }
+}
+
+impl CustomMessageReader for LspsMessageHandler {
+    type CustomMessage = LspsMessage;
+
+    fn read<R: LengthLimitedRead>(
+        &self,
+        message_type: u16,
+        buffer: &mut R,
+    ) -> Result<Option<LspsMessage>, DecodeError> {
+        if message_type != LSPS_MESSAGE_TYPE {
+            return Ok(None);
+        }
+        let mut payload = vec![];
+        let mut chunk = [0; 1024];
+        loop {
+            let len = buffer.read(&mut chunk)?;
+            if len == 0 {
+                break;
+            }
+            payload.extend_from_slice(&chunk[..len]);
+        }
+        let payload = String::from_utf8(payload).map_err(|_| DecodeError::InvalidValue)?;
+        Ok(Some(LspsMessage { payload }))
+    }
+}
+
+impl CustomMessageHandler for LspsMessageHandler {
+    fn handle_custom_message(
+        &self,
+        msg: LspsMessage,
+        sender_node_id: PublicKey,
+    ) -> Result<(), LightningError> {
+        let Ok(message) = serde_json::from_str::<Value>(&msg.payload) else {
+            tracing::warn!("Ignoring invalid LSPS message from {sender_node_id}");
+            return Ok(());
+        };
+        let Some(id) = message.get("id").and_then(|id| id.as_str()) else {
+            tracing::debug!("Ignoring LSPS message without ID from {sender_node_id}");
+            return Ok(());
+        };
+        if let Some(method) = message.get("method").and_then(|m| m.as_str()) {
+            let params = message.get("params").cloned().unwrap_or(json!({}));
+            match self.handle_request(sender_node_id, id, method, &params) {
+                Ok(Some(result)) => self.respond(sender_node_id, id, Ok(result)),
+                Ok(None) => {}
+                Err(e) => self.respond(sender_node_id, id, Err(e)),
+            }
+            return Ok(());
+        }
+        let mut pending_requests = self.pending_requests.lock().unwrap();
+        match pending_requests.get(id) {
+            Some((lsp_pubkey, _)) if *lsp_pubkey == sender_node_id => {}
+            _ => {
+                tracing::debug!("Ignoring unexpected LSPS message {id} from {sender_node_id}");
+                return Ok(());
+            }
+        }
+        let (_, sender) = pending_requests.remove(id).unwrap();
+        let res = if let Some(error) = message.get("error") {
+            Err(error
+                .get("message")
+                .and_then(|m| m.as_str())
+                .map(|m| m.to_string())
+                .unwrap_or_else(|| error.to_string()))
+        } else {
+            Ok(message.get("result").cloned().unwrap_or(Value::Null))
+        };
+        let _ = sender.send(res);
+        Ok(())
+    }
+
+    fn get_and_clear_pending_msg(&self) -> Vec<(PublicKey, LspsMessage)> {
+        std::mem::take(&mut *self.pending_messages.lock().unwrap())
+    }
+
+    fn peer_disconnected(&self, their_node_id: PublicKey) {
+        // dropping the senders makes the requests fail without waiting for the timeout
+        self.pending_requests
+            .lock()
+            .unwrap()
+            .retain(|_, (lsp_pubkey, _)| *lsp_pubkey != their_node_id);
+        // the buyer would not get the fee invoice
+        self.server
+            .lease_requests
+            .lock()
+            .unwrap()
+            .retain(|r| r.client_pubkey != their_node_id);
+    }
+
+    fn peer_connected(
+        &self,
+        _their_node_id: PublicKey,
+        _msg: &Init,
+        _inbound: bool,
+    ) -> Result<(), ()> {
+        Ok(())
+    }
+
+    fn provided_node_features(&self) -> NodeFeatures {
+        NodeFeatures::empty()
+    }
+
+    fn provided_init_features(&self, _their_node_id: PublicKey) -> InitFeatures {
+        InitFeatures::empty()
+    }
+}
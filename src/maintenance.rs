@@ -0,0 +1,170 @@
+use bitcoin::Txid;
+use lightning::chain::transaction::OutPoint;
+use lightning::ln::types::ChannelId;
+use lightning::util::persist::{
+    KVStoreSync, MonitorUpdatingPersister, ARCHIVED_CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+    CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE, CHANNEL_MONITOR_PERSISTENCE_SECONDARY_NAMESPACE,
+    CHANNEL_MONITOR_UPDATE_PERSISTENCE_PRIMARY_NAMESPACE,
+};
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::error::APIError;
+use crate::utils::{StaticState, UnlockedAppState};
+
+/// Outcome of a storage compaction
+#[derive(Debug, Default)]
+pub(crate) struct CompactionSummary {
+    pub(crate) pruned_payments: usize,
+    pub(crate) removed_monitors: usize,
+    pub(crate) removed_monitor_updates: usize,
+    pub(crate) deleted_failed_transfers: bool,
+    pub(crate) reclaimed_bytes: u64,
+}
+
+/// Parse the key of a monitor, stored by funding outpoint as {txid}_{index}
+fn monitor_funding_outpoint(monitor_key: &str) -> Option<OutPoint> {
+    let (txid, index) = monitor_key.split_once('_')?;
+    Some(OutPoint {
+        txid: Txid::from_str(txid).ok()?,
+        index: index.parse().ok()?,
+    })
+}
+
+impl UnlockedAppState {
+    /// Number and total size of the pending monitor updates
+    fn monitor_updates_size(&self) -> Result<(usize, u64), APIError> {
+        let mut count = 0;
+        let mut size = 0;
+        for monitor_key in self.kv_store.list(
+            CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+            CHANNEL_MONITOR_PERSISTENCE_SECONDARY_NAMESPACE,
+        )? {
+            for key in self.kv_store.list(
+                CHANNEL_MONITOR_UPDATE_PERSISTENCE_PRIMARY_NAMESPACE,
+                &monitor_key,
+            )? {
+                if let Ok(value) = self.kv_store.read(
+                    CHANNEL_MONITOR_UPDATE_PERSISTENCE_PRIMARY_NAMESPACE,
+                    &monitor_key,
+                    &key,
+                ) {
+                    count += 1;
+                    size += value.len() as u64;
+                }
+            }
+        }
+        Ok((count, size))
+    }
+
+    /// Drop the monitor updates already applied to their monitor, returning the number removed
+    /// and the bytes reclaimed
+    fn remove_stale_monitor_updates(
+        &self,
+        static_state: &StaticState,
+    ) -> Result<(usize, u64), APIError> {
+        let (count_before, size_before) = self.monitor_updates_size()?;
+        let persister = MonitorUpdatingPersister::new(
+            Arc::clone(&self.kv_store),
+            Arc::clone(&static_state.logger),
+            1000,
+            Arc::clone(&self.keys_manager),
+            Arc::clone(&self.keys_manager),
+            Arc::clone(&self.bitcoind_client),
+            Arc::clone(&self.bitcoind_client),
+        );
+        persister.cleanup_stale_updates(false).map_err(|e| {
+            APIError::Unexpected(format!("Failed to clean up the monitor updates: {e}"))
+        })?;
+        let (count_after, size_after) = self.monitor_updates_size()?;
+        Ok((
+            count_before.saturating_sub(count_after),
+            size_before.saturating_sub(size_after),
+        ))
+    }
+
+    /// Archive the monitors of the fully resolved channels, then delete the archived ones whose
+    /// outputs are no longer tracked by the sweeper, returning the number deleted and the bytes
+    /// reclaimed
+    fn remove_resolved_monitors(&self) -> Result<(usize, u64), APIError> {
+        self.chain_monitor.archive_fully_resolved_channel_monitors();
+
+        let sweeping_channels: HashSet<ChannelId> = self
+            .output_sweeper
+            .tracked_spendable_outputs()
+            .into_iter()
+            .filter_map(|o| o.channel_id)
+            .collect();
+        let mut removed = 0;
+        let mut reclaimed_bytes = 0;
+        for monitor_key in self.kv_store.list(
+            ARCHIVED_CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+            CHANNEL_MONITOR_PERSISTENCE_SECONDARY_NAMESPACE,
+        )? {
+            let Some(funding_outpoint) = monitor_funding_outpoint(&monitor_key) else {
+                continue;
+            };
+            if sweeping_channels.contains(&ChannelId::v1_from_funding_outpoint(funding_outpoint)) {
+                continue;
+            }
+            let size = self
+                .kv_store
+                .read(
+                    ARCHIVED_CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+                    CHANNEL_MONITOR_PERSISTENCE_SECONDARY_NAMESPACE,
+                    &monitor_key,
+                )
+                .map(|v| v.len() as u64)
+                .unwrap_or(0);
+            self.kv_store.remove(
+                ARCHIVED_CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+                CHANNEL_MONITOR_PERSISTENCE_SECONDARY_NAMESPACE,
+                &monitor_key,
+                false,
+            )?;
+            tracing::info!("Removed the monitor {monitor_key} of a resolved channel");
+            removed += 1;
+            reclaimed_bytes += size;
+        }
+        Ok((removed, reclaimed_bytes))
+    }
+
+    /// Prune the payments resolved for longer than the retention (0 to keep them), delete the
+    /// monitors of the closed channels whose funds have been swept and the failed RGB transfers,
+    /// then vacuum the RGB database
+    pub(crate) fn compact_storage(
+        &self,
+        static_state: &StaticState,
+        payment_retention_sec: u64,
+    ) -> Result<CompactionSummary, APIError> {
+        let mut summary = CompactionSummary::default();
+
+        if payment_retention_sec > 0 {
+            let (pruned, reclaimed_bytes) =
+                self.prune_resolved_payments(payment_retention_sec, &static_state.ldk_data_dir);
+            summary.pruned_payments = pruned;
+            summary.reclaimed_bytes += reclaimed_bytes;
+        }
+
+        let (removed, reclaimed_bytes) = self.remove_resolved_monitors()?;
+        summary.removed_monitors = removed;
+        summary.reclaimed_bytes += reclaimed_bytes;
+        let (removed, reclaimed_bytes) = self.remove_stale_monitor_updates(static_state)?;
+        summary.removed_monitor_updates = removed;
+        summary.reclaimed_bytes += reclaimed_bytes;
+
+        summary.deleted_failed_transfers = self.rgb_delete_transfers(None, false)?;
+        summary.reclaimed_bytes += self.rgb_vacuum_database()?;
+
+        tracing::info!(
+            "Storage compacted: {} payments pruned, {} monitors and {} monitor updates removed, \
+            {} bytes reclaimed",
+            summary.pruned_payments,
+            summary.removed_monitors,
+            summary.removed_monitor_updates,
+            summary.reclaimed_bytes
+        );
+        Ok(summary)
+    }
+}
@@ -0,0 +1,143 @@
+use amplify::s;
+use rumqttc::{AsyncClient, ConnectionError, MqttOptions, QoS, Transport};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use zeromq::{PubSocket, Socket, SocketSend, ZmqMessage};
+
+use crate::error::AppError;
+
+/// Max number of events waiting to be sent, further events get dropped
+const NOTIFICATION_QUEUE_SIZE: usize = 1000;
+/// Time to wait before reconnecting to the MQTT broker after a connection error
+const MQTT_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+const MQTT_KEEP_ALIVE: Duration = Duration::from_secs(30);
+const MQTT_DEFAULT_PORT: u16 = 1883;
+const MQTTS_DEFAULT_PORT: u16 = 8883;
+
+/// Check the ZMQ endpoint to bind the pub socket to, which must be a TCP or IPC one
+pub(crate) fn check_zmq_endpoint(endpoint: &str) -> Result<(), String> {
+    if !endpoint.starts_with("tcp://") && !endpoint.starts_with("ipc://") {
+        return Err(format!(
+            "ZMQ endpoint {endpoint} must start with tcp:// or ipc://"
+        ));
+    }
+    Ok(())
+}
+
+/// Parse the MQTT broker URL (mqtt://[user:password@]host[:port] or mqtts://...) into the
+/// client options
+pub(crate) fn parse_mqtt_url(url: &str) -> Result<MqttOptions, String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("invalid MQTT URL: {e}"))?;
+    let tls = match parsed.scheme() {
+        "mqtt" => false,
+        "mqtts" => true,
+        scheme => return Err(format!("unsupported MQTT URL scheme {scheme}")),
+    };
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| s!("MQTT URL is missing the host"))?;
+    let default_port = if tls {
+        MQTTS_DEFAULT_PORT
+    } else {
+        MQTT_DEFAULT_PORT
+    };
+    let client_id = format!("rgb-lightning-node-{}", uuid::Uuid::new_v4());
+    let mut options = MqttOptions::new(client_id, host, parsed.port().unwrap_or(default_port));
+    options.set_keep_alive(MQTT_KEEP_ALIVE);
+    if !parsed.username().is_empty() {
+        options.set_credentials(parsed.username(), parsed.password().unwrap_or_default());
+    }
+    if tls {
+        options.set_transport(Transport::tls_with_default_config());
+    }
+    Ok(options)
+}
+
+/// Publishes the hook events on a ZMQ pub socket, as a multipart message made of the event name
+/// (so that subscribers can filter by topic) and the JSON payload, and/or to an MQTT broker, on
+/// the <topic prefix>/<event name> topic. Delivery is best-effort: events are dropped when the
+/// queue is full or the broker cannot be reached.
+pub(crate) struct NotificationPublisher {
+    zmq_sender: Option<mpsc::Sender<(String, Vec<u8>)>>,
+    mqtt: Option<(AsyncClient, String)>,
+}
+
+impl NotificationPublisher {
+    pub(crate) async fn start(
+        zmq_endpoint: Option<&str>,
+        mqtt_url: Option<&str>,
+        mqtt_topic_prefix: &str,
+    ) -> Result<Self, AppError> {
+        let zmq_sender = if let Some(endpoint) = zmq_endpoint {
+            let mut socket = PubSocket::new();
+            socket
+                .bind(endpoint)
+                .await
+                .map_err(|e| AppError::InvalidNotificationConfig(format!("ZMQ bind: {e}")))?;
+            tracing::info!("Publishing notifications on ZMQ endpoint {endpoint}");
+            let (sender, mut receiver) =
+                mpsc::channel::<(String, Vec<u8>)>(NOTIFICATION_QUEUE_SIZE);
+            tokio::spawn(async move {
+                while let Some((event, payload)) = receiver.recv().await {
+                    let mut msg = ZmqMessage::from(event);
+                    msg.push_back(payload.into());
+                    if let Err(e) = socket.send(msg).await {
+                        tracing::error!("Failed to publish ZMQ notification: {e}");
+                    }
+                }
+            });
+            Some(sender)
+        } else {
+            None
+        };
+
+        let mqtt = if let Some(url) = mqtt_url {
+            let options = parse_mqtt_url(url).map_err(AppError::InvalidNotificationConfig)?;
+            tracing::info!(
+                "Publishing notifications to MQTT broker {}:{}",
+                options.broker_address().0,
+                options.broker_address().1
+            );
+            let (client, mut eventloop) = AsyncClient::new(options, NOTIFICATION_QUEUE_SIZE);
+            tokio::spawn(async move {
+                loop {
+                    match eventloop.poll().await {
+                        Ok(_) => {}
+                        Err(ConnectionError::RequestsDone) => break,
+                        Err(e) => {
+                            tracing::warn!("MQTT connection error: {e}");
+                            tokio::time::sleep(MQTT_RECONNECT_DELAY).await;
+                        }
+                    }
+                }
+            });
+            Some((client, mqtt_topic_prefix.trim_end_matches('/').to_string()))
+        } else {
+            None
+        };
+
+        Ok(Self { zmq_sender, mqtt })
+    }
+
+    /// Queue the event for publishing, without waiting for it to be delivered
+    pub(crate) fn publish(&self, event: &str, payload: &[u8]) {
+        if let Some(sender) = &self.zmq_sender {
+            if sender
+                .try_send((event.to_string(), payload.to_vec()))
+                .is_err()
+            {
+                tracing::warn!("Dropping ZMQ notification for event {event}: queue is full");
+            }
+        }
+        if let Some((client, topic_prefix)) = &self.mqtt {
+            if let Err(e) = client.try_publish(
+                format!("{topic_prefix}/{event}"),
+                QoS::AtLeastOnce,
+                false,
+                payload.to_vec(),
+            ) {
+                tracing::warn!("Dropping MQTT notification for event {event}: {e}");
+            }
+        }
+    }
+}
@@ -0,0 +1,784 @@
+use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use amplify::s;
+use axum::{extract::State, Json};
+use axum_extra::extract::WithRejection;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::key::{Keypair, Parity};
+use bitcoin::secp256k1::{ecdh, schnorr, Message, Secp256k1, SecretKey, XOnlyPublicKey};
+use futures::{SinkExt, StreamExt};
+use lightning::impl_writeable_tlv_based;
+use lightning::ln::channelmanager::PaymentId;
+use lightning::sign::EntropySource;
+use lightning::util::hash_tables::HashMap as LdkHashMap;
+use lightning::util::persist::KVStoreSync;
+use lightning::util::ser::Writeable;
+use lightning_invoice::Bolt11Invoice;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, MutexGuard};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+use crate::disk::NWC_CONNECTIONS_FNAME;
+use crate::error::APIError;
+use crate::routes::{
+    ln_invoice, send_payment, HTLCStatus, LNInvoiceRequest, NwcBudgetRenewal, NwcConnection,
+    SendPaymentRequest,
+};
+use crate::utils::{get_current_timestamp, hex_str, hex_str_to_vec, AppState, UnlockedAppState};
+
+type Aes256CbcEnc = cbc::Encryptor<aes::Aes256>;
+type Aes256CbcDec = cbc::Decryptor<aes::Aes256>;
+
+/// Kind of the (replaceable) event advertising the methods supported by a wallet service
+const NWC_INFO_KIND: u32 = 13194;
+const NWC_REQUEST_KIND: u32 = 23194;
+const NWC_RESPONSE_KIND: u32 = 23195;
+
+const NWC_METHODS: [&str; 3] = ["get_balance", "make_invoice", "pay_invoice"];
+
+const DEFAULT_NWC_INVOICE_EXPIRY_SEC: u32 = 3600;
+
+/// Time to wait for a payment to succeed or fail before answering it's still pending
+const NWC_PAYMENT_WAIT_TIMEOUT: Duration = Duration::from_secs(120);
+
+const RELAY_RECONNECT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Number of handled request IDs remembered per relay connection, to skip the ones sent twice
+const MAX_SEEN_REQUESTS: usize = 10000;
+
+/// Nostr event, as defined by NIP-01
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct NostrEvent {
+    pub(crate) id: String,
+    pub(crate) pubkey: String,
+    pub(crate) created_at: u64,
+    pub(crate) kind: u32,
+    pub(crate) tags: Vec<Vec<String>>,
+    pub(crate) content: String,
+    pub(crate) sig: String,
+}
+
+impl NostrEvent {
+    pub(crate) fn new(
+        keypair: &Keypair,
+        kind: u32,
+        tags: Vec<Vec<String>>,
+        content: String,
+    ) -> Self {
+        let pubkey = hex_str(&keypair.x_only_public_key().0.serialize());
+        let created_at = get_current_timestamp();
+        let id = Self::compute_id(&pubkey, created_at, kind, &tags, &content);
+        let sig =
+            Secp256k1::signing_only().sign_schnorr_no_aux_rand(&Message::from_digest(id), keypair);
+        Self {
+            id: hex_str(&id),
+            pubkey,
+            created_at,
+            kind,
+            tags,
+            content,
+            sig: hex_str(&sig.serialize()),
+        }
+    }
+
+    fn compute_id(
+        pubkey: &str,
+        created_at: u64,
+        kind: u32,
+        tags: &[Vec<String>],
+        content: &str,
+    ) -> [u8; 32] {
+        let serialized =
+            serde_json::json!([0, pubkey, created_at, kind, tags, content]).to_string();
+        sha256::Hash::hash(serialized.as_bytes()).to_byte_array()
+    }
+
+    /// Whether the ID matches the content and is signed by the author
+    pub(crate) fn verify(&self) -> bool {
+        let id = Self::compute_id(
+            &self.pubkey,
+            self.created_at,
+            self.kind,
+            &self.tags,
+            &self.content,
+        );
+        if hex_str(&id) != self.id {
+            return false;
+        }
+        let (Ok(pubkey), Some(Ok(sig))) = (
+            XOnlyPublicKey::from_str(&self.pubkey),
+            hex_str_to_vec(&self.sig).map(|s| schnorr::Signature::from_slice(&s)),
+        ) else {
+            return false;
+        };
+        Secp256k1::verification_only()
+            .verify_schnorr(&sig, &Message::from_digest(id), &pubkey)
+            .is_ok()
+    }
+
+    pub(crate) fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|t| t.first().is_some_and(|n| n == name))
+            .and_then(|t| t.get(1))
+            .map(|v| v.as_str())
+    }
+}
+
+fn nip04_shared_secret(secret_key: &SecretKey, pubkey: &str) -> Result<[u8; 32], String> {
+    let pubkey = XOnlyPublicKey::from_str(pubkey)
+        .map_err(|e| format!("invalid pubkey: {e}"))?
+        .public_key(Parity::Even);
+    let point = ecdh::shared_secret_point(&pubkey, secret_key);
+    let mut shared_secret = [0u8; 32];
+    shared_secret.copy_from_slice(&point[..32]);
+    Ok(shared_secret)
+}
+
+/// Encrypt a message to a pubkey as defined by NIP-04
+pub(crate) fn nip04_encrypt(
+    secret_key: &SecretKey,
+    pubkey: &str,
+    plaintext: &str,
+) -> Result<String, String> {
+    let shared_secret = nip04_shared_secret(secret_key, pubkey)?;
+    let iv: [u8; 16] = rand::random();
+    let ciphertext = Aes256CbcEnc::new(&shared_secret.into(), &iv.into())
+        .encrypt_padded_vec_mut::<Pkcs7>(plaintext.as_bytes());
+    Ok(format!(
+        "{}?iv={}",
+        BASE64.encode(ciphertext),
+        BASE64.encode(iv)
+    ))
+}
+
+/// Decrypt a message from a pubkey as defined by NIP-04
+pub(crate) fn nip04_decrypt(
+    secret_key: &SecretKey,
+    pubkey: &str,
+    content: &str,
+) -> Result<String, String> {
+    let (ciphertext, iv) = content
+        .split_once("?iv=")
+        .ok_or(s!("missing initialization vector"))?;
+    let ciphertext = BASE64.decode(ciphertext).map_err(|e| e.to_string())?;
+    let iv: [u8; 16] = BASE64
+        .decode(iv)
+        .map_err(|e| e.to_string())?
+        .try_into()
+        .map_err(|_| s!("invalid initialization vector"))?;
+    let shared_secret = nip04_shared_secret(secret_key, pubkey)?;
+    let plaintext = Aes256CbcDec::new(&shared_secret.into(), &iv.into())
+        .decrypt_padded_vec_mut::<Pkcs7>(&ciphertext)
+        .map_err(|e| e.to_string())?;
+    String::from_utf8(plaintext).map_err(|e| e.to_string())
+}
+
+/// NWC connection, identified by the pubkey of the app using it. The wallet service answers its
+/// requests with a key dedicated to the connection.
+#[derive(Clone, Debug)]
+pub(crate) struct NwcConnectionInfo {
+    pub(crate) name: String,
+    pub(crate) relay: String,
+    pub(crate) service_secret: SecretKey,
+    pub(crate) budget_msat: Option<u64>,
+    pub(crate) budget_renewal: NwcBudgetRenewal,
+    pub(crate) spent_msat: u64,
+    pub(crate) budget_period_start: u64,
+    pub(crate) created_at: u64,
+    pub(crate) last_used_at: Option<u64>,
+}
+
+impl_writeable_tlv_based!(NwcConnectionInfo, {
+    (0, name, required),
+    (2, relay, required),
+    (4, service_secret, required),
+    (6, budget_msat, option),
+    (8, budget_renewal, required),
+    (10, spent_msat, required),
+    (12, budget_period_start, required),
+    (14, created_at, required),
+    (16, last_used_at, option),
+});
+
+impl NwcConnectionInfo {
+    pub(crate) fn service_keypair(&self) -> Keypair {
+        Keypair::from_secret_key(&Secp256k1::signing_only(), &self.service_secret)
+    }
+
+    pub(crate) fn service_pubkey(&self) -> String {
+        hex_str(&self.service_keypair().x_only_public_key().0.serialize())
+    }
+
+    pub(crate) fn to_connection(&self, connection_id: &str) -> NwcConnection {
+        NwcConnection {
+            connection_id: connection_id.to_string(),
+            name: self.name.clone(),
+            relay: self.relay.clone(),
+            wallet_pubkey: self.service_pubkey(),
+            budget_msat: self.budget_msat,
+            budget_renewal: self.budget_renewal,
+            spent_msat: self.spent_msat,
+            created_at: self.created_at,
+            last_used_at: self.last_used_at,
+        }
+    }
+
+    /// Reset the spent amount once the budget period has elapsed
+    fn renew_budget(&mut self, now: u64) {
+        let period_secs = match self.budget_renewal {
+            NwcBudgetRenewal::Never => return,
+            NwcBudgetRenewal::Daily => 86400,
+            NwcBudgetRenewal::Weekly => 7 * 86400,
+            NwcBudgetRenewal::Monthly => 30 * 86400,
+        };
+        if now >= self.budget_period_start + period_secs {
+            let elapsed_periods = (now - self.budget_period_start) / period_secs;
+            self.budget_period_start += elapsed_periods * period_secs;
+            self.spent_msat = 0;
+        }
+    }
+}
+
+pub(crate) struct NwcConnectionMap {
+    pub(crate) connections: LdkHashMap<String, NwcConnectionInfo>,
+}
+
+impl_writeable_tlv_based!(NwcConnectionMap, {
+    (0, connections, required),
+});
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct NwcError {
+    pub(crate) code: String,
+    pub(crate) message: String,
+}
+
+impl NwcError {
+    fn new(code: &str, message: impl ToString) -> Self {
+        Self {
+            code: code.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<APIError> for NwcError {
+    fn from(e: APIError) -> Self {
+        let code = match e {
+            APIError::InsufficientCapacity(_) | APIError::InsufficientFunds(_) => {
+                "INSUFFICIENT_BALANCE"
+            }
+            APIError::ChangingState | APIError::LockedNode | APIError::ShuttingDown => "INTERNAL",
+            _ => "OTHER",
+        };
+        Self::new(code, e)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct NwcRequest {
+    pub(crate) method: String,
+    #[serde(default)]
+    pub(crate) params: Value,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct NwcResponse {
+    pub(crate) result_type: String,
+    pub(crate) error: Option<NwcError>,
+    pub(crate) result: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct MakeInvoiceParams {
+    amount: u64,
+    expiry: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct PayInvoiceParams {
+    invoice: String,
+    amount: Option<u64>,
+}
+
+impl UnlockedAppState {
+    pub(crate) fn add_nwc_connection(&self, connection_id: String, connection: NwcConnectionInfo) {
+        let mut nwc_connections = self.get_nwc_connections();
+        nwc_connections
+            .connections
+            .insert(connection_id, connection);
+        self.save_nwc_connections(nwc_connections);
+    }
+
+    /// Generate the keys of a new NWC connection, returning the service secret and the client one
+    pub(crate) fn new_nwc_keys(&self) -> (SecretKey, SecretKey) {
+        let new_key = || loop {
+            if let Ok(key) = SecretKey::from_slice(&self.keys_manager.get_secure_random_bytes()) {
+                return key;
+            }
+        };
+        (new_key(), new_key())
+    }
+
+    pub(crate) fn remove_nwc_connection(&self, connection_id: &str) -> bool {
+        let mut nwc_connections = self.get_nwc_connections();
+        let removed = nwc_connections.connections.remove(connection_id).is_some();
+        if removed {
+            self.save_nwc_connections(nwc_connections);
+        }
+        removed
+    }
+
+    fn save_nwc_connections(&self, nwc_connections: MutexGuard<NwcConnectionMap>) {
+        self.kv_store
+            .write("", "", NWC_CONNECTIONS_FNAME, nwc_connections.encode())
+            .unwrap();
+    }
+
+    /// Charge an amount to the budget of a connection, failing if it would be exceeded
+    fn reserve_nwc_budget(&self, connection_id: &str, amt_msat: u64) -> Result<(), NwcError> {
+        let mut nwc_connections = self.get_nwc_connections();
+        let Some(connection) = nwc_connections.connections.get_mut(connection_id) else {
+            return Err(NwcError::new("UNAUTHORIZED", "connection has been revoked"));
+        };
+        let now = get_current_timestamp();
+        connection.renew_budget(now);
+        let budget_msat = connection.budget_msat;
+        let spent_msat = connection
+            .spent_msat
+            .checked_add(amt_msat)
+            .filter(|spent_msat| !budget_msat.is_some_and(|b| *spent_msat > b));
+        let Some(spent_msat) = spent_msat else {
+            return Err(NwcError::new(
+                "QUOTA_EXCEEDED",
+                format!(
+                    "payment of {amt_msat} msat exceeds the remaining budget of {} msat",
+                    budget_msat
+                        .unwrap_or(u64::MAX)
+                        .saturating_sub(connection.spent_msat)
+                ),
+            ));
+        };
+        connection.spent_msat = spent_msat;
+        connection.last_used_at = Some(now);
+        self.save_nwc_connections(nwc_connections);
+        Ok(())
+    }
+
+    /// Give back to the budget of a connection an amount that hasn't been spent
+    fn release_nwc_budget(&self, connection_id: &str, amt_msat: u64) {
+        let mut nwc_connections = self.get_nwc_connections();
+        if let Some(connection) = nwc_connections.connections.get_mut(connection_id) {
+            connection.spent_msat = connection.spent_msat.saturating_sub(amt_msat);
+            self.save_nwc_connections(nwc_connections);
+        }
+    }
+
+    /// Charge the routing fees of a payment, once known, even if they exceed the budget
+    fn charge_nwc_fees(&self, connection_id: &str, fee_msat: u64) {
+        let mut nwc_connections = self.get_nwc_connections();
+        if let Some(connection) = nwc_connections.connections.get_mut(connection_id) {
+            connection.spent_msat = connection.spent_msat.saturating_add(fee_msat);
+            self.save_nwc_connections(nwc_connections);
+        }
+    }
+
+    fn touch_nwc_connection(&self, connection_id: &str) {
+        let mut nwc_connections = self.get_nwc_connections();
+        if let Some(connection) = nwc_connections.connections.get_mut(connection_id) {
+            connection.last_used_at = Some(get_current_timestamp());
+            self.save_nwc_connections(nwc_connections);
+        }
+    }
+
+    /// Connections served through a relay, by service pubkey
+    fn nwc_relay_connections(&self, relay: &str) -> HashMap<String, (String, Keypair)> {
+        self.get_nwc_connections()
+            .connections
+            .iter()
+            .filter(|(_, c)| c.relay == relay)
+            .map(|(id, c)| (c.service_pubkey(), (id.clone(), c.service_keypair())))
+            .collect()
+    }
+
+    fn nwc_relays(&self) -> HashSet<String> {
+        self.get_nwc_connections()
+            .connections
+            .values()
+            .map(|c| c.relay.clone())
+            .collect()
+    }
+}
+
+fn get_balance(unlocked_state: &UnlockedAppState) -> Result<Value, NwcError> {
+    let balance: u64 = unlocked_state
+        .channel_manager
+        .list_channels()
+        .iter()
+        .map(|c| c.outbound_capacity_msat)
+        .sum();
+    Ok(serde_json::json!({ "balance": balance }))
+}
+
+async fn make_invoice(app_state: Arc<AppState>, params: Value) -> Result<Value, NwcError> {
+    let params: MakeInvoiceParams = serde_json::from_value(params)
+        .map_err(|e| NwcError::new("OTHER", format!("invalid params: {e}")))?;
+    let expiry_sec = params.expiry.unwrap_or(DEFAULT_NWC_INVOICE_EXPIRY_SEC);
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(params.amount),
+        min_amt_msat: None,
+        max_amt_msat: None,
+        expiry_sec,
+        asset_id: None,
+        asset_amount: None,
+        route_hints: None,
+        lsp: None,
+        phantom: None,
+        label: None,
+        settle_after_expiry: false,
+        fallback_address_type: None,
+    };
+    let Json(res) = ln_invoice(
+        State(app_state),
+        None,
+        WithRejection(Json(payload), PhantomData),
+    )
+    .await?;
+    let invoice = Bolt11Invoice::from_str(&res.invoice).unwrap();
+    let created_at = invoice.duration_since_epoch().as_secs();
+    Ok(serde_json::json!({
+        "type": "incoming",
+        "invoice": res.invoice,
+        "payment_hash": hex_str(invoice.payment_hash().as_byte_array()),
+        "amount": params.amount,
+        "created_at": created_at,
+        "expires_at": created_at + expiry_sec as u64,
+    }))
+}
+
+async fn pay_invoice(
+    app_state: Arc<AppState>,
+    unlocked_state: &UnlockedAppState,
+    connection_id: &str,
+    params: Value,
+) -> Result<Value, NwcError> {
+    let params: PayInvoiceParams = serde_json::from_value(params)
+        .map_err(|e| NwcError::new("OTHER", format!("invalid params: {e}")))?;
+    let invoice = Bolt11Invoice::from_str(&params.invoice)
+        .map_err(|e| NwcError::new("OTHER", format!("invalid invoice: {e}")))?;
+    // budgets are in msat, so RGB payments can't be accounted for
+    if invoice.rgb_contract_id().is_some() {
+        return Err(NwcError::new(
+            "RESTRICTED",
+            "payments of RGB assets are not allowed",
+        ));
+    }
+    let Some(amt_msat) = invoice.amount_milli_satoshis().or(params.amount) else {
+        return Err(NwcError::new("OTHER", "an amount is required"));
+    };
+
+    unlocked_state.reserve_nwc_budget(connection_id, amt_msat)?;
+    let payload = SendPaymentRequest {
+        invoice: params.invoice,
+        amt_msat: params.amount,
+        timeout_sec: None,
+        max_retries: None,
+        max_fee_msat: None,
+        max_fee_ppm: None,
+        max_cltv_expiry_delta: None,
+        max_parts: None,
+        max_shard_amt_msat: None,
+        totp_code: None,
+        dry_run: false,
+    };
+    if let Err(e) = send_payment(State(app_state), WithRejection(Json(payload), PhantomData)).await
+    {
+        unlocked_state.release_nwc_budget(connection_id, amt_msat);
+        return Err(e.into());
+    }
+
+    let payment_id = PaymentId(invoice.payment_hash().to_byte_array());
+    let started = tokio::time::Instant::now();
+    loop {
+        let payment = unlocked_state
+            .get_outbound_payments()
+            .payments
+            .get(&payment_id)
+            .cloned();
+        match payment.as_ref().map(|p| p.status) {
+            Some(HTLCStatus::Succeeded) => {
+                let payment = payment.unwrap();
+                let fees_paid: u64 = payment
+                    .parts
+                    .iter()
+                    .filter(|p| p.status == HTLCStatus::Succeeded)
+                    .map(|p| p.fee_msat)
+                    .sum();
+                unlocked_state.charge_nwc_fees(connection_id, fees_paid);
+                return Ok(serde_json::json!({
+                    "preimage": payment.preimage.map(|p| hex_str(&p.0)),
+                    "fees_paid": fees_paid,
+                }));
+            }
+            Some(HTLCStatus::Failed) => {
+                unlocked_state.release_nwc_budget(connection_id, amt_msat);
+                return Err(NwcError::new("PAYMENT_FAILED", "the payment has failed"));
+            }
+            _ => {}
+        }
+        if started.elapsed() > NWC_PAYMENT_WAIT_TIMEOUT {
+            // the amount stays charged, as the payment may still succeed
+            return Err(NwcError::new("INTERNAL", "the payment is still pending"));
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+/// Execute a request, returning the encrypted response event (if the request can be answered)
+async fn handle_nwc_request(
+    app_state: Arc<AppState>,
+    unlocked_state: Arc<UnlockedAppState>,
+    connection_id: String,
+    keypair: Keypair,
+    event: NostrEvent,
+) -> Option<NostrEvent> {
+    if event
+        .tag("expiration")
+        .and_then(|e| e.parse::<u64>().ok())
+        .is_some_and(|e| e < get_current_timestamp())
+    {
+        tracing::debug!("Ignoring expired NWC request {}", event.id);
+        return None;
+    }
+    let secret_key = keypair.secret_key();
+    let request = match nip04_decrypt(&secret_key, &event.pubkey, &event.content)
+        .and_then(|r| serde_json::from_str::<NwcRequest>(&r).map_err(|e| e.to_string()))
+    {
+        Ok(r) => r,
+        Err(e) => {
+            tracing::warn!("Invalid NWC request {}: {e}", event.id);
+            return None;
+        }
+    };
+    tracing::info!("NWC request {} for method {}", event.id, request.method);
+
+    let result = match request.method.as_str() {
+        "get_balance" => {
+            unlocked_state.touch_nwc_connection(&connection_id);
+            get_balance(&unlocked_state)
+        }
+        "make_invoice" => {
+            unlocked_state.touch_nwc_connection(&connection_id);
+            make_invoice(app_state, request.params).await
+        }
+        "pay_invoice" => {
+            pay_invoice(app_state, &unlocked_state, &connection_id, request.params).await
+        }
+        method => Err(NwcError::new(
+            "NOT_IMPLEMENTED",
+            format!("method {method} is not supported"),
+        )),
+    };
+    let response = match result {
+        Ok(result) => NwcResponse {
+            result_type: request.method,
+            error: None,
+            result: Some(result),
+        },
+        Err(error) => NwcResponse {
+            result_type: request.method,
+            error: Some(error),
+            result: None,
+        },
+    };
+    let content = nip04_encrypt(
+        &secret_key,
+        &event.pubkey,
+        &serde_json::to_string(&response).unwrap(),
+    )
+    .ok()?;
+    Some(NostrEvent::new(
+        &keypair,
+        NWC_RESPONSE_KIND,
+        vec![vec![s!("p"), event.pubkey], vec![s!("e"), event.id]],
+        content,
+    ))
+}
+
+/// Serve the NWC connections of a relay until none is left or the node gets locked, returning an
+/// error if the connection to the relay is lost
+async fn serve_relay(
+    app_state: &Arc<AppState>,
+    unlocked_state: &Arc<UnlockedAppState>,
+    relay: &str,
+    stop_processing: &Arc<AtomicBool>,
+) -> Result<(), String> {
+    let (ws_stream, _) = tokio_tungstenite::connect_async(relay)
+        .await
+        .map_err(|e| e.to_string())?;
+    tracing::info!("Connected to NWC relay {relay}");
+    let (mut sink, mut stream) = ws_stream.split();
+    let (response_tx, mut response_rx) = mpsc::unbounded_channel::<NostrEvent>();
+    let since = get_current_timestamp();
+    let mut subscribed: Vec<String> = vec![];
+    let mut seen_requests: HashSet<String> = HashSet::new();
+    let mut interval = tokio::time::interval(Duration::from_secs(5));
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                let connections = unlocked_state.nwc_relay_connections(relay);
+                if stop_processing.load(Ordering::Acquire) || connections.is_empty() {
+                    let _ = sink.close().await;
+                    return Ok(());
+                }
+                let mut pubkeys: Vec<String> = connections.keys().cloned().collect();
+                pubkeys.sort();
+                if pubkeys != subscribed {
+                    for (pubkey, (_, keypair)) in &connections {
+                        if subscribed.contains(pubkey) {
+                            continue;
+                        }
+                        let info = NostrEvent::new(
+                            keypair,
+                            NWC_INFO_KIND,
+                            vec![],
+                            NWC_METHODS.join(" "),
+                        );
+                        let msg = serde_json::json!(["EVENT", info]).to_string();
+                        sink.send(WsMessage::Text(msg)).await.map_err(|e| e.to_string())?;
+                    }
+                    // a REQ with the same subscription ID replaces the previous one
+                    let filter = serde_json::json!({
+                        "kinds": [NWC_REQUEST_KIND],
+                        "#p": pubkeys,
+                        "since": since,
+                    });
+                    let msg = serde_json::json!(["REQ", "nwc", filter]).to_string();
+                    sink.send(WsMessage::Text(msg)).await.map_err(|e| e.to_string())?;
+                    subscribed = pubkeys;
+                }
+            }
+            Some(response) = response_rx.recv() => {
+                let msg = serde_json::json!(["EVENT", response]).to_string();
+                sink.send(WsMessage::Text(msg)).await.map_err(|e| e.to_string())?;
+            }
+            msg = stream.next() => {
+                let text = match msg {
+                    None => return Err(s!("connection closed")),
+                    Some(Err(e)) => return Err(e.to_string()),
+                    Some(Ok(WsMessage::Text(text))) => text,
+                    Some(Ok(_)) => continue,
+                };
+                let Ok(msg) = serde_json::from_str::<Vec<Value>>(&text) else {
+                    continue;
+                };
+                match msg.first().and_then(|m| m.as_str()) {
+                    Some("EVENT") => {}
+                    Some("NOTICE") => {
+                        tracing::debug!("NWC relay {relay} notice: {:?}", msg.get(1));
+                        continue;
+                    }
+                    Some("OK") if msg.get(2).and_then(|v| v.as_bool()) == Some(false) => {
+                        tracing::warn!("NWC relay {relay} rejected event: {:?}", msg.get(3));
+                        continue;
+                    }
+                    _ => continue,
+                }
+                let Some(Ok(event)) = msg.get(2).map(|e| {
+                    serde_json::from_value::<NostrEvent>(e.clone())
+                }) else {
+                    continue;
+                };
+                if event.kind != NWC_REQUEST_KIND || !event.verify() {
+                    continue;
+                }
+                if !seen_requests.insert(event.id.clone()) {
+                    continue;
+                }
+                if seen_requests.len() > MAX_SEEN_REQUESTS {
+                    seen_requests.clear();
+                }
+                // the request must come from the app of the connection of the tagged service key
+                let Some((connection_id, keypair)) = event
+                    .tag("p")
+                    .and_then(|p| unlocked_state.nwc_relay_connections(relay).remove(p))
+                else {
+                    continue;
+                };
+                if connection_id != event.pubkey {
+                    continue;
+                }
+                let app_state = Arc::clone(app_state);
+                let unlocked_state = Arc::clone(unlocked_state);
+                let response_tx = response_tx.clone();
+                tokio::spawn(async move {
+                    if let Some(response) = handle_nwc_request(
+                        app_state,
+                        unlocked_state,
+                        connection_id,
+                        keypair,
+                        event,
+                    )
+                    .await
+                    {
+                        let _ = response_tx.send(response);
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// Keep a connection open to each relay used by the NWC connections, until the node gets locked
+pub(crate) async fn run_nwc_service(
+    app_state: Arc<AppState>,
+    unlocked_state: Arc<UnlockedAppState>,
+    stop_processing: Arc<AtomicBool>,
+) {
+    let mut relay_tasks: HashMap<String, JoinHandle<()>> = HashMap::new();
+    let mut interval = tokio::time::interval(Duration::from_secs(5));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        if stop_processing.load(Ordering::Acquire) {
+            return;
+        }
+        relay_tasks.retain(|_, task| !task.is_finished());
+        for relay in unlocked_state.nwc_relays() {
+            if relay_tasks.contains_key(&relay) {
+                continue;
+            }
+            let app_state = Arc::clone(&app_state);
+            let unlocked_state = Arc::clone(&unlocked_state);
+            let stop_processing = Arc::clone(&stop_processing);
+            let task_relay = relay.clone();
+            let task = tokio::spawn(async move {
+                loop {
+                    let res =
+                        serve_relay(&app_state, &unlocked_state, &task_relay, &stop_processing)
+                            .await;
+                    match res {
+                        Ok(()) => return,
+                        Err(e) => tracing::warn!("NWC relay {task_relay} failed: {e}"),
+                    }
+                    tokio::time::sleep(RELAY_RECONNECT_INTERVAL).await;
+                    if stop_processing.load(Ordering::Acquire)
+                        || !unlocked_state.nwc_relays().contains(&task_relay)
+                    {
+                        return;
+                    }
+                }
+            });
+            relay_tasks.insert(relay, task);
+        }
+    }
+}
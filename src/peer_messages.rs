@@ -0,0 +1,373 @@
+use bitcoin::secp256k1::PublicKey;
+use lightning::impl_writeable_tlv_based;
+use lightning::io;
+use lightning::ln::msgs::{DecodeError, Init, LightningError};
+use lightning::ln::peer_handler::CustomMessageHandler;
+use lightning::ln::types::ChannelId;
+use lightning::ln::wire::{CustomMessageReader, Type};
+use lightning::types::features::{InitFeatures, NodeFeatures};
+use lightning::util::hash_tables::HashMap as LdkHashMap;
+use lightning::util::persist::KVStoreSync;
+use lightning::util::ser::{LengthLimitedRead, Readable, Writeable, Writer};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use crate::disk::ASSET_HTLC_MINIMUMS_FNAME;
+use crate::ldk::{announced_hosts, ChannelManager, NetworkGraph, PeerFilter};
+use crate::lsps::{LspsMessage, LspsMessageHandler};
+use crate::routes::HTLC_MIN_MSAT;
+use crate::store::NodeStore;
+use crate::utils::get_current_timestamp;
+
+/// BOLT8 message type announcing the asset HTLC minimum of a channel (odd, so that peers not
+/// supporting it ignore the message)
+pub(crate) const ASSET_HTLC_MIN_MESSAGE_TYPE: u16 = 37915;
+
+/// Custom feature bit advertising support for RGB channels (odd, so that peers not knowing it
+/// still accept the connection)
+pub(crate) const RGB_FEATURE_BIT: usize = 1337;
+
+/// Whether the peer advertises support for RGB channels, as either an optional or a required
+/// feature
+pub(crate) fn supports_rgb(features: &InitFeatures) -> bool {
+    let flags = features.le_flags();
+    [RGB_FEATURE_BIT - 1, RGB_FEATURE_BIT].iter().any(|bit| {
+        flags
+            .get(bit / 8)
+            .is_some_and(|b| b & (1 << (bit % 8)) != 0)
+    })
+}
+
+/// Minimum msat amount the sender accepts in the asset HTLCs of the channel
+#[derive(Clone, Debug)]
+pub(crate) struct AssetHtlcMinMessage {
+    channel_id: ChannelId,
+    htlc_min_msat: u64,
+}
+
+impl Type for AssetHtlcMinMessage {
+    fn type_id(&self) -> u16 {
+        ASSET_HTLC_MIN_MESSAGE_TYPE
+    }
+}
+
+impl Writeable for AssetHtlcMinMessage {
+    fn write<W: Writer>(&self, w: &mut W) -> Result<(), io::Error> {
+        self.channel_id.write(w)?;
+        self.htlc_min_msat.write(w)
+    }
+}
+
+/// Asset HTLC minimums of a channel, as announced by each side
+#[derive(Clone, Debug)]
+pub(crate) struct AssetHtlcMinInfo {
+    pub(crate) counterparty_node_id: PublicKey,
+    pub(crate) local_msat: u64,
+    pub(crate) remote_msat: Option<u64>,
+}
+
+impl_writeable_tlv_based!(AssetHtlcMinInfo, {
+    (0, counterparty_node_id, required),
+    (2, local_msat, required),
+    (4, remote_msat, option),
+});
+
+impl AssetHtlcMinInfo {
+    /// Minimum accepted by both sides, assuming the legacy constant until the peer announces its
+    /// own
+    pub(crate) fn agreed_msat(&self) -> u64 {
+        self.local_msat
+            .max(self.remote_msat.unwrap_or(HTLC_MIN_MSAT))
+    }
+}
+
+pub(crate) struct AssetHtlcMinMap {
+    pub(crate) channels: LdkHashMap<ChannelId, AssetHtlcMinInfo>,
+}
+
+impl_writeable_tlv_based!(AssetHtlcMinMap, {
+    (0, channels, required),
+});
+
+/// Negotiation of the msat amount carried by asset HTLCs. Each side announces its minimum when
+/// the channel gets opened (and again on reconnection, until the peer's one is known or after
+/// ours has been changed), then asset HTLCs need to satisfy the higher of the two.
+pub(crate) struct AssetHtlcMinHandler {
+    channel_manager: Arc<ChannelManager>,
+    kv_store: Arc<NodeStore>,
+    local_msat: u64,
+    pending_messages: Mutex<Vec<(PublicKey, AssetHtlcMinMessage)>>,
+    minimums: Mutex<AssetHtlcMinMap>,
+    /// Channels whose peer hasn't been told about our new minimum yet
+    outdated_channels: Mutex<HashSet<ChannelId>>,
+}
+
+impl AssetHtlcMinHandler {
+    pub(crate) fn new(
+        channel_manager: Arc<ChannelManager>,
+        kv_store: Arc<NodeStore>,
+        mut minimums: AssetHtlcMinMap,
+        local_msat: u64,
+    ) -> Self {
+        // a minimum changed since the last run gets announced again on reconnection
+        let mut outdated_channels = HashSet::new();
+        for (channel_id, info) in minimums.channels.iter_mut() {
+            if info.local_msat != local_msat {
+                info.local_msat = local_msat;
+                outdated_channels.insert(*channel_id);
+            }
+        }
+        let handler = Self {
+            channel_manager,
+            kv_store,
+            local_msat,
+            pending_messages: Mutex::new(vec![]),
+            minimums: Mutex::new(minimums),
+            outdated_channels: Mutex::new(outdated_channels),
+        };
+        if !handler.outdated_channels.lock().unwrap().is_empty() {
+            handler.save_minimums(&handler.minimums.lock().unwrap());
+        }
+        handler
+    }
+
+    fn save_minimums(&self, minimums: &AssetHtlcMinMap) {
+        self.kv_store
+            .write("", "", ASSET_HTLC_MINIMUMS_FNAME, minimums.encode())
+            .unwrap();
+    }
+
+    fn queue_announcement(&self, counterparty_node_id: PublicKey, channel_id: ChannelId) {
+        self.pending_messages.lock().unwrap().push((
+            counterparty_node_id,
+            AssetHtlcMinMessage {
+                channel_id,
+                htlc_min_msat: self.local_msat,
+            },
+        ));
+    }
+
+    /// Announce our minimum for a newly opened channel
+    pub(crate) fn announce(&self, channel_id: ChannelId, counterparty_node_id: PublicKey) {
+        let mut minimums = self.minimums.lock().unwrap();
+        let info = minimums
+            .channels
+            .entry(channel_id)
+            .or_insert(AssetHtlcMinInfo {
+                counterparty_node_id,
+                local_msat: self.local_msat,
+                remote_msat: None,
+            });
+        if info.counterparty_node_id != counterparty_node_id {
+            info.counterparty_node_id = counterparty_node_id;
+            info.remote_msat = None;
+        }
+        info.local_msat = self.local_msat;
+        self.save_minimums(&minimums);
+        self.queue_announcement(counterparty_node_id, channel_id);
+    }
+
+    /// Asset HTLC minimum agreed for the channel, None if it has never been announced
+    pub(crate) fn agreed_msat(&self, channel_id: &ChannelId) -> Option<u64> {
+        self.minimums
+            .lock()
+            .unwrap()
+            .channels
+            .get(channel_id)
+            .map(|info| info.agreed_msat())
+    }
+
+    pub(crate) fn channel_closed(&self, channel_id: &ChannelId) {
+        let mut minimums = self.minimums.lock().unwrap();
+        if minimums.channels.remove(channel_id).is_some() {
+            self.save_minimums(&minimums);
+        }
+    }
+
+    fn handle_announcement(&self, msg: AssetHtlcMinMessage, sender_node_id: PublicKey) {
+        let channel_id = msg.channel_id;
+        let known_channel = self
+            .channel_manager
+            .list_channels_with_counterparty(&sender_node_id)
+            .iter()
+            .any(|c| c.channel_id == channel_id);
+        if !known_channel {
+            tracing::debug!(
+                "Ignoring asset HTLC minimum for unknown channel {channel_id} from {sender_node_id}"
+            );
+            return;
+        }
+        let mut minimums = self.minimums.lock().unwrap();
+        let info = minimums
+            .channels
+            .entry(channel_id)
+            .or_insert(AssetHtlcMinInfo {
+                counterparty_node_id: sender_node_id,
+                local_msat: self.local_msat,
+                remote_msat: None,
+            });
+        // an unchanged announcement means the peer is still missing ours
+        if info.remote_msat == Some(msg.htlc_min_msat) {
+            drop(minimums);
+            self.queue_announcement(sender_node_id, channel_id);
+            return;
+        }
+        info.remote_msat = Some(msg.htlc_min_msat);
+        tracing::info!(
+            "Agreed asset HTLC minimum of {} msat for channel {channel_id}",
+            info.agreed_msat()
+        );
+        self.save_minimums(&minimums);
+    }
+
+    fn peer_connected(&self, their_node_id: PublicKey) {
+        let minimums = self.minimums.lock().unwrap();
+        let mut outdated_channels = self.outdated_channels.lock().unwrap();
+        for (channel_id, _) in minimums.channels.iter().filter(|(channel_id, info)| {
+            info.counterparty_node_id == their_node_id
+                && (info.remote_msat.is_none() || outdated_channels.contains(channel_id))
+        }) {
+            outdated_channels.remove(channel_id);
+            self.queue_announcement(their_node_id, *channel_id);
+        }
+    }
+}
+
+/// Custom message exchanged with peers
+#[derive(Clone, Debug)]
+pub(crate) enum PeerMessage {
+    AssetHtlcMin(AssetHtlcMinMessage),
+    Lsps(LspsMessage),
+}
+
+impl Type for PeerMessage {
+    fn type_id(&self) -> u16 {
+        match self {
+            PeerMessage::AssetHtlcMin(msg) => msg.type_id(),
+            PeerMessage::Lsps(msg) => msg.type_id(),
+        }
+    }
+}
+
+impl Writeable for PeerMessage {
+    fn write<W: Writer>(&self, w: &mut W) -> Result<(), io::Error> {
+        match self {
+            PeerMessage::AssetHtlcMin(msg) => msg.write(w),
+            PeerMessage::Lsps(msg) => msg.write(w),
+        }
+    }
+}
+
+/// Dispatcher of custom messages to the handler of their type, also refusing the inbound peers
+/// not passing the peer filter once their pubkey is known and tracking when peers connected
+pub(crate) struct PeerMessageHandler {
+    pub(crate) asset_htlc_min_handler: Arc<AssetHtlcMinHandler>,
+    pub(crate) lsps_handler: Arc<LspsMessageHandler>,
+    pub(crate) network_graph: Arc<NetworkGraph>,
+    pub(crate) peer_filter: Arc<Mutex<PeerFilter>>,
+    pub(crate) peer_connected_at: Arc<Mutex<HashMap<PublicKey, u64>>>,
+}
+
+impl CustomMessageReader for PeerMessageHandler {
+    type CustomMessage = PeerMessage;
+
+    fn read<R: LengthLimitedRead>(
+        &self,
+        message_type: u16,
+        buffer: &mut R,
+    ) -> Result<Option<PeerMessage>, DecodeError> {
+        if message_type == ASSET_HTLC_MIN_MESSAGE_TYPE {
+            return Ok(Some(PeerMessage::AssetHtlcMin(AssetHtlcMinMessage {
+                channel_id: Readable::read(buffer)?,
+                htlc_min_msat: Readable::read(buffer)?,
+            })));
+        }
+        Ok(self
+            .lsps_handler
+            .read(message_type, buffer)?
+            .map(PeerMessage::Lsps))
+    }
+}
+
+impl CustomMessageHandler for PeerMessageHandler {
+    fn handle_custom_message(
+        &self,
+        msg: PeerMessage,
+        sender_node_id: PublicKey,
+    ) -> Result<(), LightningError> {
+        match msg {
+            PeerMessage::AssetHtlcMin(msg) => {
+                self.asset_htlc_min_handler
+                    .handle_announcement(msg, sender_node_id);
+                Ok(())
+            }
+            PeerMessage::Lsps(msg) => self.lsps_handler.handle_custom_message(msg, sender_node_id),
+        }
+    }
+
+    fn get_and_clear_pending_msg(&self) -> Vec<(PublicKey, PeerMessage)> {
+        let asset_htlc_min_messages =
+            std::mem::take(&mut *self.asset_htlc_min_handler.pending_messages.lock().unwrap());
+        asset_htlc_min_messages
+            .into_iter()
+            .map(|(pk, msg)| (pk, PeerMessage::AssetHtlcMin(msg)))
+            .chain(
+                self.lsps_handler
+                    .get_and_clear_pending_msg()
+                    .into_iter()
+                    .map(|(pk, msg)| (pk, PeerMessage::Lsps(msg))),
+            )
+            .collect()
+    }
+
+    fn peer_disconnected(&self, their_node_id: PublicKey) {
+        self.peer_connected_at
+            .lock()
+            .unwrap()
+            .remove(&their_node_id);
+        self.lsps_handler.peer_disconnected(their_node_id);
+    }
+
+    fn peer_connected(
+        &self,
+        their_node_id: PublicKey,
+        msg: &Init,
+        inbound: bool,
+    ) -> Result<(), ()> {
+        if inbound {
+            let hosts = announced_hosts(&self.network_graph, &their_node_id);
+            if let Some(reason) = self
+                .peer_filter
+                .lock()
+                .unwrap()
+                .check_peer(&their_node_id, &hosts)
+            {
+                tracing::info!("Refusing inbound connection: {reason}");
+                return Err(());
+            }
+        }
+        self.peer_connected_at
+            .lock()
+            .unwrap()
+            .insert(their_node_id, get_current_timestamp());
+        self.asset_htlc_min_handler.peer_connected(their_node_id);
+        self.lsps_handler
+            .peer_connected(their_node_id, msg, inbound)
+    }
+
+    fn provided_node_features(&self) -> NodeFeatures {
+        let mut features = NodeFeatures::empty();
+        features
+            .set_optional_custom_bit(RGB_FEATURE_BIT)
+            .expect("RGB feature bit is in the custom range");
+        features
+    }
+
+    fn provided_init_features(&self, _their_node_id: PublicKey) -> InitFeatures {
+        let mut features = InitFeatures::empty();
+        features
+            .set_optional_custom_bit(RGB_FEATURE_BIT)
+            .expect("RGB feature bit is in the custom range");
+        features
+    }
+}
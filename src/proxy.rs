@@ -0,0 +1,163 @@
+use amplify::s;
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, State},
+    http::{header, HeaderValue, Method, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, MutexGuard};
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::error::{APIError, AppError};
+use crate::utils::{get_current_timestamp, AppState};
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+const CORS_MAX_AGE_SEC: u64 = 3600;
+
+const RATE_LIMIT_WINDOW_SEC: u64 = 60;
+
+/// Address of the client sending a request, as reported by the reverse proxy if trusted
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ClientIp(pub(crate) IpAddr);
+
+/// Requests received from a client in the current rate limiting window
+pub(crate) struct RequestWindow {
+    start: u64,
+    count: u32,
+}
+
+pub(crate) type RequestWindowMap = HashMap<IpAddr, RequestWindow>;
+
+/// Parse the comma-separated origins allowed to call the APIs from a browser
+pub(crate) fn check_cors_args(
+    cors_allowed_origins: Option<String>,
+) -> Result<Option<Vec<HeaderValue>>, AppError> {
+    let Some(cors_allowed_origins) = cors_allowed_origins else {
+        return Ok(None);
+    };
+    let mut origins = vec![];
+    for origin in cors_allowed_origins.split(',').map(str::trim) {
+        if origin == "*" {
+            return Err(AppError::InvalidCorsOrigin(s!(
+                "* is not allowed (hint: omit the option to allow any origin)"
+            )));
+        }
+        if !(origin.starts_with("http://") || origin.starts_with("https://"))
+            || origin.ends_with('/')
+        {
+            return Err(AppError::InvalidCorsOrigin(format!(
+                "{origin} is not a scheme://host[:port] origin"
+            )));
+        }
+        let origin = HeaderValue::from_str(origin)
+            .map_err(|_| AppError::InvalidCorsOrigin(origin.to_string()))?;
+        origins.push(origin);
+    }
+    tracing::info!("Allowing CORS requests from {} origins", origins.len());
+    Ok(Some(origins))
+}
+
+/// Check the path prefix the APIs are served under
+pub(crate) fn check_url_prefix(url_prefix: Option<String>) -> Result<Option<String>, AppError> {
+    let Some(url_prefix) = url_prefix else {
+        return Ok(None);
+    };
+    if !url_prefix.starts_with('/') || url_prefix.ends_with('/') {
+        return Err(AppError::InvalidUrlPrefix(s!(
+            "it needs to start and not end with / (e.g. /api/v1)"
+        )));
+    }
+    let valid_chars = url_prefix
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "/-._~".contains(c));
+    if !valid_chars || url_prefix.contains("//") {
+        return Err(AppError::InvalidUrlPrefix(format!(
+            "{url_prefix} is not a valid path"
+        )));
+    }
+    tracing::info!("Serving the APIs under {url_prefix}");
+    Ok(Some(url_prefix))
+}
+
+/// Allow the configured origins to call the APIs, or any origin if none has been configured
+pub(crate) fn cors_layer(cors_allowed_origins: Option<Vec<HeaderValue>>) -> CorsLayer {
+    let Some(origins) = cors_allowed_origins else {
+        return CorsLayer::permissive();
+    };
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_methods([Method::GET, Method::POST])
+        .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE])
+        .max_age(Duration::from_secs(CORS_MAX_AGE_SEC))
+}
+
+/// Get the client address from the last X-Forwarded-For entry, added by the proxy in front of
+/// the node
+fn forwarded_for(request: &Request<Body>) -> Option<IpAddr> {
+    request
+        .headers()
+        .get_all(X_FORWARDED_FOR)
+        .iter()
+        .last()
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.rsplit(',').next())
+        .and_then(|ip| ip.trim().parse().ok())
+}
+
+/// Record the client address of the request, rate limiting each client if configured
+pub(crate) async fn client_ip_middleware(
+    State(app_state): State<Arc<AppState>>,
+    mut request: Request<Body>,
+    next: Next,
+) -> Response {
+    let peer_ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|c| c.0.ip());
+    let forwarded_ip = if app_state.static_state.trust_forwarded_for {
+        forwarded_for(&request)
+    } else {
+        None
+    };
+    if let Some(client_ip) = forwarded_ip.or(peer_ip) {
+        if let Err(e) = app_state.count_request(client_ip) {
+            tracing::warn!("Rate limited requests from {client_ip}");
+            return e.into_response();
+        }
+        request.extensions_mut().insert(ClientIp(client_ip));
+    }
+    next.run(request).await
+}
+
+impl AppState {
+    fn get_request_windows(&self) -> MutexGuard<'_, RequestWindowMap> {
+        self.request_windows.lock().unwrap()
+    }
+
+    /// Count a request of a client, failing if it exceeds the max requests per minute
+    fn count_request(&self, client_ip: IpAddr) -> Result<(), APIError> {
+        let max_requests_per_minute = self.get_runtime_config().max_requests_per_minute;
+        if max_requests_per_minute == 0 {
+            return Ok(());
+        }
+        let now = get_current_timestamp();
+        let mut request_windows = self.get_request_windows();
+        request_windows.retain(|_, w| now < w.start + RATE_LIMIT_WINDOW_SEC);
+        let window = request_windows.entry(client_ip).or_insert(RequestWindow {
+            start: now,
+            count: 0,
+        });
+        if window.count >= max_requests_per_minute {
+            return Err(APIError::RateLimited(
+                window.start + RATE_LIMIT_WINDOW_SEC - now,
+            ));
+        }
+        window.count += 1;
+        Ok(())
+    }
+}
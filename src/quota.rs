@@ -0,0 +1,189 @@
+use lightning::types::payment::PaymentHash;
+use std::collections::{HashMap, HashSet};
+use std::sync::MutexGuard;
+
+use crate::error::APIError;
+use crate::routes::{HTLCStatus, InvoiceQuotaUsage, TokenInvoiceQuotaUsage};
+use crate::utils::{get_current_timestamp, AppState, UnlockedAppState};
+
+const INVOICE_QUOTA_WINDOW_SEC: u64 = 60;
+
+/// Invoices issued in the current window
+#[derive(Default)]
+struct IssuanceWindow {
+    start: u64,
+    count: u32,
+}
+
+impl IssuanceWindow {
+    fn count(&mut self, now: u64) -> u32 {
+        if now >= self.start + INVOICE_QUOTA_WINDOW_SEC {
+            self.start = now;
+            self.count = 0;
+        }
+        self.count
+    }
+}
+
+/// Invoice issuance of a credential, or of the node as a whole
+#[derive(Default)]
+struct IssuanceStats {
+    window: IssuanceWindow,
+    issued: u64,
+    rejected: u64,
+}
+
+impl IssuanceStats {
+    /// Check the per-minute and unpaid quotas (0 for no limit), returning why the invoice is
+    /// refused
+    fn check(
+        &mut self,
+        scope: &str,
+        max_per_minute: u32,
+        unpaid: usize,
+        max_unpaid: u32,
+        now: u64,
+    ) -> Option<String> {
+        if max_per_minute > 0 && self.window.count(now) >= max_per_minute {
+            let retry_after_sec = self.window.start + INVOICE_QUOTA_WINDOW_SEC - now;
+            return Some(format!(
+                "{scope} reached the max of {max_per_minute} invoices per minute, retry in {retry_after_sec} seconds"
+            ));
+        }
+        if max_unpaid > 0 && unpaid >= max_unpaid as usize {
+            return Some(format!(
+                "{scope} reached the max of {max_unpaid} unpaid invoices"
+            ));
+        }
+        None
+    }
+
+    fn usage(&mut self, unpaid: usize, now: u64) -> InvoiceQuotaUsage {
+        InvoiceQuotaUsage {
+            issued_last_minute: self.window.count(now),
+            unpaid,
+            issued: self.issued,
+            rejected: self.rejected,
+        }
+    }
+}
+
+/// Invoice issuance tracked to enforce the quotas, since the node has been started
+#[derive(Default)]
+pub(crate) struct InvoiceQuotas {
+    global: IssuanceStats,
+    tokens: HashMap<String, IssuanceStats>,
+    /// Credential each unpaid invoice has been issued to
+    token_invoices: HashMap<PaymentHash, String>,
+}
+
+impl InvoiceQuotas {
+    fn token_unpaid(&self, token_id: &str) -> usize {
+        self.token_invoices
+            .values()
+            .filter(|t| *t == token_id)
+            .count()
+    }
+}
+
+/// Invoices that can still be paid
+fn unpaid_invoices(unlocked_state: &UnlockedAppState) -> HashSet<PaymentHash> {
+    let now = get_current_timestamp();
+    unlocked_state
+        .get_inbound_payments()
+        .payments
+        .iter()
+        .filter(|(_, p)| {
+            matches!(p.status, HTLCStatus::Pending) && p.expires_at.is_none_or(|e| e > now)
+        })
+        .map(|(payment_hash, _)| *payment_hash)
+        .collect()
+}
+
+impl AppState {
+    fn get_invoice_quotas(&self) -> MutexGuard<'_, InvoiceQuotas> {
+        self.invoice_quotas.lock().unwrap()
+    }
+
+    /// Check the invoice issuance quotas of the node and of the credential (if any), counting
+    /// the invoice in the current windows when accepted
+    pub(crate) fn check_invoice_quotas(
+        &self,
+        unlocked_state: &UnlockedAppState,
+        token_id: Option<&str>,
+    ) -> Result<(), APIError> {
+        let config = self.get_runtime_config().clone();
+        let unpaid = unpaid_invoices(unlocked_state);
+        let now = get_current_timestamp();
+
+        let mut quotas = self.get_invoice_quotas();
+        quotas.token_invoices.retain(|h, _| unpaid.contains(h));
+        let token_unpaid = token_id.map(|t| quotas.token_unpaid(t)).unwrap_or(0);
+
+        let mut refusal = quotas.global.check(
+            "node",
+            config.max_invoices_per_minute,
+            unpaid.len(),
+            config.max_unpaid_invoices,
+            now,
+        );
+        if let Some(token_id) = token_id {
+            let token_stats = quotas.tokens.entry(token_id.to_string()).or_default();
+            if let Some(reason) = token_stats.check(
+                "token",
+                config.max_token_invoices_per_minute,
+                token_unpaid,
+                config.max_token_unpaid_invoices,
+                now,
+            ) {
+                token_stats.rejected += 1;
+                refusal = refusal.or(Some(reason));
+            } else if refusal.is_none() {
+                token_stats.window.count += 1;
+            }
+        }
+        if let Some(reason) = refusal {
+            quotas.global.rejected += 1;
+            tracing::warn!("Refused invoice: {reason}");
+            return Err(APIError::InvoiceQuotaExceeded(reason));
+        }
+        quotas.global.window.count += 1;
+        Ok(())
+    }
+
+    /// Record an invoice issued after passing the quotas
+    pub(crate) fn record_invoice(&self, payment_hash: PaymentHash, token_id: Option<String>) {
+        let mut quotas = self.get_invoice_quotas();
+        quotas.global.issued += 1;
+        if let Some(token_id) = token_id {
+            quotas.tokens.entry(token_id.clone()).or_default().issued += 1;
+            quotas.token_invoices.insert(payment_hash, token_id);
+        }
+    }
+
+    /// Invoice issuance of the node and of each credential that issued invoices
+    pub(crate) fn invoice_quota_usage(
+        &self,
+        unlocked_state: &UnlockedAppState,
+    ) -> (InvoiceQuotaUsage, Vec<TokenInvoiceQuotaUsage>) {
+        let unpaid = unpaid_invoices(unlocked_state);
+        let now = get_current_timestamp();
+
+        let mut quotas = self.get_invoice_quotas();
+        quotas.token_invoices.retain(|h, _| unpaid.contains(h));
+        let global = quotas.global.usage(unpaid.len(), now);
+        let token_ids: Vec<String> = quotas.tokens.keys().cloned().collect();
+        let mut tokens = vec![];
+        for token_id in token_ids {
+            let token_unpaid = quotas.token_unpaid(&token_id);
+            let usage = quotas
+                .tokens
+                .get_mut(&token_id)
+                .unwrap()
+                .usage(token_unpaid, now);
+            tokens.push(TokenInvoiceQuotaUsage { token_id, usage });
+        }
+        tokens.sort_by(|a, b| a.token_id.cmp(&b.token_id));
+        (global, tokens)
+    }
+}
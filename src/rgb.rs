@@ -3,34 +3,47 @@ use bitcoin::blockdata::script::ScriptBuf;
 use bitcoin::hashes::Hash;
 use bitcoin::key::XOnlyPublicKey;
 use bitcoin::psbt::Psbt;
-use bitcoin::{Address, Network, OutPoint, Transaction, TxOut, WPubkeyHash};
+use bitcoin::secp256k1::Secp256k1;
+use bitcoin::{Address, Amount, Network, OutPoint, Transaction, TxOut, WPubkeyHash};
 use hex::DisplayHex;
+use lightning::chain::transaction::OutPoint as LdkOutPoint;
 use lightning::events::bump_transaction::{Utxo, WalletSource};
 use lightning::ln::types::ChannelId;
 use lightning::rgb_utils::{
-    get_rgb_channel_info_path, is_channel_rgb, parse_rgb_channel_info, RgbInfo,
+    get_rgb_channel_info_path, is_channel_rgb, parse_rgb_channel_info, RgbInfo, INDEXER_URL_FNAME,
+};
+use lightning::sign::{
+    ChangeDestinationSource, KeysManager, SignerProvider, SpendableOutputDescriptor,
 };
-use lightning::sign::ChangeDestinationSource;
 use lightning::util::async_poll::AsyncResult;
 use rgb_lib::{
-    bdk_wallet::SignOptions,
+    bdk_wallet::{LocalOutput, SignOptions},
     bitcoin::psbt::Psbt as BitcoinPsbt,
     wallet::{
         rust_only::{check_proxy_url, ColoringInfo},
-        AssetCFA, AssetNIA, AssetUDA, Assets, Balance, BtcBalance, Metadata, Online,
+        AssetCFA, AssetIFA, AssetNIA, AssetUDA, Assets, Balance, BtcBalance, Metadata, Online,
         OperationResult, ReceiveData, Recipient, RefreshResult, Transaction as RgbLibTransaction,
         Transfer, TransportEndpoint, Unspent, WalletData,
     },
     AssetSchema, Assignment, BitcoinNetwork, ContractId, Error as RgbLibError, RgbTransfer,
-    RgbTransport, RgbTxid, UpdateRes, Wallet as RgbLibWallet, WitnessOrd,
+    RgbTransport, RgbTxid, TransferKind, TransferStatus, UpdateRes, Wallet as RgbLibWallet,
+    WitnessOrd,
 };
+use rusqlite::Connection;
 use std::collections::HashMap;
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::{Arc, Mutex, MutexGuard};
 
+use crate::bitcoind::BitcoindClient;
+use crate::hooks::HookEvent;
+use crate::ldk::AnchorReserveMap;
 use crate::{error::APIError, utils::UnlockedAppState};
 
+/// Name of the rgb-lib SQLite database, inside the wallet directory
+const RGB_LIB_DB_FNAME: &str = "rgb_lib_db";
+
 impl UnlockedAppState {
     pub(crate) fn rgb_blind_receive(
         &self,
@@ -61,6 +74,15 @@ impl UnlockedAppState {
             .create_utxos(up_to, num, size, fee_rate, skip_sync)
     }
 
+    pub(crate) fn rgb_delete_transfers(
+        &self,
+        batch_transfer_idx: Option<i32>,
+        no_asset_only: bool,
+    ) -> Result<bool, RgbLibError> {
+        self.rgb_wallet_wrapper
+            .delete_transfers(batch_transfer_idx, no_asset_only)
+    }
+
     pub(crate) fn rgb_fail_transfers(
         &self,
         batch_transfer_idx: Option<i32>,
@@ -126,6 +148,36 @@ impl UnlockedAppState {
             .issue_asset_cfa(name, details, precision, amounts, file_path)
     }
 
+    pub(crate) fn rgb_inflate(
+        &self,
+        asset_id: String,
+        inflation_amounts: Vec<u64>,
+        fee_rate: u64,
+        min_confirmations: u8,
+    ) -> Result<OperationResult, APIError> {
+        let unsigned_psbt = self.rgb_wallet_wrapper.inflate_begin(
+            asset_id,
+            inflation_amounts,
+            fee_rate,
+            min_confirmations,
+        )?;
+        self.check_locked_utxos(&unsigned_psbt)?;
+        let signed_psbt = self.rgb_sign_psbt(unsigned_psbt)?;
+        Ok(self.rgb_wallet_wrapper.inflate_end(signed_psbt)?)
+    }
+
+    pub(crate) fn rgb_issue_asset_ifa(
+        &self,
+        ticker: String,
+        name: String,
+        precision: u8,
+        amounts: Vec<u64>,
+        inflation_amounts: Vec<u64>,
+    ) -> Result<AssetIFA, RgbLibError> {
+        self.rgb_wallet_wrapper
+            .issue_asset_ifa(ticker, name, precision, amounts, inflation_amounts)
+    }
+
     pub(crate) fn rgb_issue_asset_nia(
         &self,
         ticker: String,
@@ -163,6 +215,55 @@ impl UnlockedAppState {
         self.rgb_wallet_wrapper.list_assets(filter_asset_schemas)
     }
 
+    /// Look up a transfer by its index among all the assets, returning it with its asset ID.
+    /// Transfer indexes are unique across assets, so the first match is the only one.
+    pub(crate) fn rgb_get_transfer(
+        &self,
+        idx: i32,
+    ) -> Result<Option<(String, Transfer)>, RgbLibError> {
+        for asset_id in self.rgb_list_asset_ids()? {
+            if let Some(transfer) = self
+                .rgb_list_transfers(asset_id.clone())?
+                .into_iter()
+                .find(|t| t.idx == idx)
+            {
+                return Ok(Some((asset_id, transfer)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// IDs of all the assets known to the wallet, regardless of their schema
+    pub(crate) fn rgb_list_asset_ids(&self) -> Result<Vec<String>, RgbLibError> {
+        let assets = self.rgb_list_assets(vec![
+            AssetSchema::Nia,
+            AssetSchema::Uda,
+            AssetSchema::Cfa,
+            AssetSchema::Ifa,
+        ])?;
+        let nia = assets
+            .nia
+            .unwrap_or_default()
+            .into_iter()
+            .map(|a| a.asset_id);
+        let uda = assets
+            .uda
+            .unwrap_or_default()
+            .into_iter()
+            .map(|a| a.asset_id);
+        let cfa = assets
+            .cfa
+            .unwrap_or_default()
+            .into_iter()
+            .map(|a| a.asset_id);
+        let ifa = assets
+            .ifa
+            .unwrap_or_default()
+            .into_iter()
+            .map(|a| a.asset_id);
+        Ok(nia.chain(uda).chain(cfa).chain(ifa).collect())
+    }
+
     pub(crate) fn rgb_list_transactions(
         &self,
         skip_sync: bool,
@@ -181,6 +282,13 @@ impl UnlockedAppState {
         self.rgb_wallet_wrapper.list_unspents(skip_sync)
     }
 
+    pub(crate) fn rgb_list_unspents_vanilla(
+        &self,
+        skip_sync: bool,
+    ) -> Result<Vec<LocalOutput>, RgbLibError> {
+        self.rgb_wallet_wrapper.list_unspents_vanilla(skip_sync)
+    }
+
     pub(crate) fn rgb_post_consignment<P: AsRef<Path>>(
         &self,
         proxy_url: &str,
@@ -189,6 +297,8 @@ impl UnlockedAppState {
         txid: String,
         vout: Option<u32>,
     ) -> Result<(), RgbLibError> {
+        #[cfg(feature = "failure-injection")]
+        self.delay_proxy_request();
         self.rgb_wallet_wrapper.post_consignment(
             proxy_url,
             recipient_id,
@@ -199,7 +309,52 @@ impl UnlockedAppState {
     }
 
     pub(crate) fn rgb_refresh(&self, skip_sync: bool) -> Result<RefreshResult, RgbLibError> {
-        self.rgb_wallet_wrapper.refresh(skip_sync)
+        #[cfg(feature = "failure-injection")]
+        self.delay_proxy_request();
+        let refresh_result = self.rgb_wallet_wrapper.refresh(skip_sync)?;
+        for (batch_transfer_idx, refreshed) in &refresh_result {
+            if !matches!(refreshed.updated_status, Some(TransferStatus::Settled)) {
+                continue;
+            }
+            if let Err(e) = self.add_received_transfer_settlement(*batch_transfer_idx) {
+                tracing::error!("Failed to record settlement of batch {batch_transfer_idx}: {e}");
+            }
+            self.hook_runner.trigger(HookEvent::TransferSettled {
+                batch_transfer_idx: *batch_transfer_idx,
+            });
+        }
+        Ok(refresh_result)
+    }
+
+    /// Add the transfer received by the given batch to the settlement rollups. Only receives
+    /// created with an RGB invoice are tracked, when the invoice doesn't specify the asset the
+    /// transfer is looked up among all the assets.
+    fn add_received_transfer_settlement(&self, batch_transfer_idx: i32) -> Result<(), RgbLibError> {
+        let Some(pending) = self.pending_settlement(&batch_transfer_idx.to_string()) else {
+            return Ok(());
+        };
+        let asset_ids = if let Some(asset_id) = pending.asset_id {
+            vec![asset_id]
+        } else {
+            self.rgb_list_asset_ids()?
+        };
+        for asset_id in asset_ids {
+            let received = self
+                .rgb_list_transfers(asset_id.clone())?
+                .into_iter()
+                .find(|t| {
+                    matches!(
+                        t.kind,
+                        TransferKind::ReceiveBlind | TransferKind::ReceiveWitness
+                    ) && t.recipient_id == pending.recipient_id
+                });
+            if let Some(transfer) = received {
+                let amount = transfer.assignments.iter().map(assignment_amount).sum();
+                self.add_transfer_settlement(batch_transfer_idx, asset_id, amount);
+                break;
+            }
+        }
+        Ok(())
     }
 
     pub(crate) fn rgb_save_new_asset(
@@ -218,14 +373,13 @@ impl UnlockedAppState {
         fee_rate: u64,
         min_confirmations: u8,
         skip_sync: bool,
-    ) -> Result<OperationResult, RgbLibError> {
-        self.rgb_wallet_wrapper.send(
-            recipient_map,
-            donation,
-            fee_rate,
-            min_confirmations,
-            skip_sync,
-        )
+    ) -> Result<OperationResult, APIError> {
+        let unsigned_psbt =
+            self.rgb_send_begin(recipient_map, donation, fee_rate, min_confirmations)?;
+        let signed_psbt = self.rgb_sign_psbt(unsigned_psbt)?;
+        #[cfg(feature = "failure-injection")]
+        self.delay_proxy_request();
+        Ok(self.rgb_wallet_wrapper.send_end(signed_psbt, skip_sync)?)
     }
 
     pub(crate) fn rgb_send_begin(
@@ -234,9 +388,15 @@ impl UnlockedAppState {
         donation: bool,
         fee_rate: u64,
         min_confirmations: u8,
-    ) -> Result<String, RgbLibError> {
-        self.rgb_wallet_wrapper
-            .send_begin(recipient_map, donation, fee_rate, min_confirmations)
+    ) -> Result<String, APIError> {
+        let unsigned_psbt = self.rgb_wallet_wrapper.send_begin(
+            recipient_map,
+            donation,
+            fee_rate,
+            min_confirmations,
+        )?;
+        self.check_locked_utxos(&unsigned_psbt)?;
+        Ok(unsigned_psbt)
     }
 
     pub(crate) fn rgb_send_btc(
@@ -245,9 +405,15 @@ impl UnlockedAppState {
         amount: u64,
         fee_rate: u64,
         skip_sync: bool,
-    ) -> Result<String, RgbLibError> {
-        self.rgb_wallet_wrapper
-            .send_btc(address, amount, fee_rate, skip_sync)
+    ) -> Result<String, APIError> {
+        let unsigned_psbt = self
+            .rgb_wallet_wrapper
+            .send_btc_begin(address, amount, fee_rate, skip_sync)?;
+        self.check_locked_utxos(&unsigned_psbt)?;
+        let signed_psbt = self.rgb_sign_psbt(unsigned_psbt)?;
+        Ok(self
+            .rgb_wallet_wrapper
+            .send_btc_end(signed_psbt, skip_sync)?)
     }
 
     pub(crate) fn rgb_send_btc_begin(
@@ -255,17 +421,22 @@ impl UnlockedAppState {
         address: String,
         amount: u64,
         fee_rate: u64,
-    ) -> Result<String, RgbLibError> {
-        self.rgb_wallet_wrapper
-            .send_btc_begin(address, amount, fee_rate)
+    ) -> Result<String, APIError> {
+        let unsigned_psbt = self
+            .rgb_wallet_wrapper
+            .send_btc_begin(address, amount, fee_rate, false)?;
+        self.check_locked_utxos(&unsigned_psbt)?;
+        Ok(unsigned_psbt)
     }
 
     pub(crate) fn rgb_send_btc_end(&self, signed_psbt: String) -> Result<String, RgbLibError> {
-        self.rgb_wallet_wrapper.send_btc_end(signed_psbt)
+        self.rgb_wallet_wrapper.send_btc_end(signed_psbt, false)
     }
 
     pub(crate) fn rgb_send_end(&self, signed_psbt: String) -> Result<OperationResult, RgbLibError> {
-        self.rgb_wallet_wrapper.send_end(signed_psbt)
+        #[cfg(feature = "failure-injection")]
+        self.delay_proxy_request();
+        self.rgb_wallet_wrapper.send_end(signed_psbt, false)
     }
 
     pub(crate) fn rgb_sign_psbt(&self, unsigned_psbt: String) -> Result<String, RgbLibError> {
@@ -285,6 +456,10 @@ impl UnlockedAppState {
             .upsert_witness(witness_id, witness_ord)
     }
 
+    pub(crate) fn rgb_vacuum_database(&self) -> Result<u64, APIError> {
+        self.rgb_wallet_wrapper.vacuum_database()
+    }
+
     pub(crate) fn rgb_witness_receive(
         &self,
         asset_id: Option<String>,
@@ -305,18 +480,89 @@ impl UnlockedAppState {
 
 pub(crate) struct RgbLibWalletWrapper {
     pub(crate) wallet: Arc<Mutex<RgbLibWallet>>,
-    pub(crate) online: Online,
+    online: Mutex<Online>,
+    /// Indexers to go online with, in order of priority
+    indexer_urls: Vec<String>,
 }
 
 impl RgbLibWalletWrapper {
-    pub(crate) fn new(wallet: Arc<Mutex<RgbLibWallet>>, online: Online) -> Self {
-        RgbLibWalletWrapper { wallet, online }
+    pub(crate) fn new(
+        wallet: Arc<Mutex<RgbLibWallet>>,
+        online: Online,
+        indexer_urls: Vec<String>,
+    ) -> Self {
+        RgbLibWalletWrapper {
+            wallet,
+            online: Mutex::new(online),
+            indexer_urls,
+        }
     }
 
     pub(crate) fn get_rgb_wallet(&self) -> MutexGuard<'_, RgbLibWallet> {
         self.wallet.lock().unwrap()
     }
 
+    pub(crate) fn active_indexer_url(&self) -> String {
+        self.online().indexer_url
+    }
+
+    /// Go online with the next reachable indexer after the one that failed, returning false if
+    /// none is available
+    fn fail_over_indexer(&self, failed_online: &Online) -> bool {
+        let mut wallet = self.get_rgb_wallet();
+        let mut online = self.online.lock().unwrap();
+        // another operation may have already switched indexer
+        if online.indexer_url != failed_online.indexer_url {
+            return true;
+        }
+        let failed_idx = self
+            .indexer_urls
+            .iter()
+            .position(|u| *u == failed_online.indexer_url)
+            .unwrap_or_default();
+        for offset in 1..self.indexer_urls.len() {
+            let indexer_url = &self.indexer_urls[(failed_idx + offset) % self.indexer_urls.len()];
+            match wallet.go_online(true, indexer_url.clone()) {
+                Ok(new_online) => {
+                    tracing::warn!(
+                        "Indexer {} failed, switched to {indexer_url}",
+                        failed_online.indexer_url
+                    );
+                    // LDK reads the indexer to use from the storage directory
+                    let data_dir = wallet.get_wallet_data().data_dir;
+                    fs::write(Path::new(&data_dir).join(INDEXER_URL_FNAME), indexer_url)
+                        .expect("able to write");
+                    *online = new_online;
+                    return true;
+                }
+                Err(e) => tracing::warn!("Cannot fail over to indexer {indexer_url}: {e}"),
+            }
+        }
+        false
+    }
+
+    fn online(&self) -> Online {
+        self.online.lock().unwrap().clone()
+    }
+
+    /// Run an operation using the indexer, retrying it once with the next indexer by priority if
+    /// the active one doesn't answer
+    fn with_indexer<T>(
+        &self,
+        op: impl Fn(&mut RgbLibWallet, Online) -> Result<T, RgbLibError>,
+    ) -> Result<T, RgbLibError> {
+        let online = self.online();
+        let res = op(&mut self.get_rgb_wallet(), online.clone());
+        match res {
+            Err(RgbLibError::Indexer { .. } | RgbLibError::FailedBdkSync { .. })
+                if self.fail_over_indexer(&online) =>
+            {
+                op(&mut self.get_rgb_wallet(), self.online())
+            }
+            res => res,
+        }
+    }
+
     pub(crate) fn bitcoin_network(&self) -> BitcoinNetwork {
         self.get_rgb_wallet().get_wallet_data().bitcoin_network
     }
@@ -355,14 +601,18 @@ impl RgbLibWalletWrapper {
         fee_rate: u64,
         skip_sync: bool,
     ) -> Result<u8, RgbLibError> {
-        self.get_rgb_wallet().create_utxos(
-            self.online.clone(),
-            up_to,
-            Some(num),
-            Some(size),
-            fee_rate,
-            skip_sync,
-        )
+        self.with_indexer(|wallet, online| {
+            wallet.create_utxos(online, up_to, Some(num), Some(size), fee_rate, skip_sync)
+        })
+    }
+
+    pub(crate) fn delete_transfers(
+        &self,
+        batch_transfer_idx: Option<i32>,
+        no_asset_only: bool,
+    ) -> Result<bool, RgbLibError> {
+        self.get_rgb_wallet()
+            .delete_transfers(batch_transfer_idx, no_asset_only)
     }
 
     pub(crate) fn fail_transfers(
@@ -371,12 +621,9 @@ impl RgbLibWalletWrapper {
         no_asset_only: bool,
         skip_sync: bool,
     ) -> Result<bool, RgbLibError> {
-        self.get_rgb_wallet().fail_transfers(
-            self.online.clone(),
-            batch_transfer_idx,
-            no_asset_only,
-            skip_sync,
-        )
+        self.with_indexer(|wallet, online| {
+            wallet.fail_transfers(online, batch_transfer_idx, no_asset_only, skip_sync)
+        })
     }
 
     pub(crate) fn get_address(&self) -> Result<String, RgbLibError> {
@@ -400,17 +647,13 @@ impl RgbLibWalletWrapper {
     }
 
     pub(crate) fn get_btc_balance(&self, skip_sync: bool) -> Result<BtcBalance, RgbLibError> {
-        let online = if skip_sync {
-            None
-        } else {
-            Some(self.online.clone())
-        };
-        self.get_rgb_wallet().get_btc_balance(online, skip_sync)
+        self.with_indexer(|wallet, online| {
+            wallet.get_btc_balance((!skip_sync).then_some(online), skip_sync)
+        })
     }
 
     pub(crate) fn get_fee_estimation(&self, blocks: u16) -> Result<f64, RgbLibError> {
-        self.get_rgb_wallet()
-            .get_fee_estimation(self.online.clone(), blocks)
+        self.with_indexer(|wallet, online| wallet.get_fee_estimation(online, blocks))
     }
 
     pub(crate) fn get_media_dir(&self) -> PathBuf {
@@ -430,6 +673,28 @@ impl RgbLibWalletWrapper {
         self.get_rgb_wallet().get_wallet_data()
     }
 
+    pub(crate) fn inflate_begin(
+        &self,
+        asset_id: String,
+        inflation_amounts: Vec<u64>,
+        fee_rate: u64,
+        min_confirmations: u8,
+    ) -> Result<String, RgbLibError> {
+        self.with_indexer(|wallet, online| {
+            wallet.inflate_begin(
+                online,
+                asset_id.clone(),
+                inflation_amounts.clone(),
+                fee_rate,
+                min_confirmations,
+            )
+        })
+    }
+
+    pub(crate) fn inflate_end(&self, signed_psbt: String) -> Result<OperationResult, RgbLibError> {
+        self.with_indexer(|wallet, online| wallet.inflate_end(online, signed_psbt.clone()))
+    }
+
     pub(crate) fn issue_asset_cfa(
         &self,
         name: String,
@@ -442,6 +707,26 @@ impl RgbLibWalletWrapper {
             .issue_asset_cfa(name, details, precision, amounts, file_path)
     }
 
+    pub(crate) fn issue_asset_ifa(
+        &self,
+        ticker: String,
+        name: String,
+        precision: u8,
+        amounts: Vec<u64>,
+        inflation_amounts: Vec<u64>,
+    ) -> Result<AssetIFA, RgbLibError> {
+        // no replace rights nor reject list, as the node only manages the supply of its assets
+        self.get_rgb_wallet().issue_asset_ifa(
+            ticker,
+            name,
+            precision,
+            amounts,
+            inflation_amounts,
+            0,
+            None,
+        )
+    }
+
     pub(crate) fn issue_asset_nia(
         &self,
         ticker: String,
@@ -483,12 +768,9 @@ impl RgbLibWalletWrapper {
         &self,
         skip_sync: bool,
     ) -> Result<Vec<RgbLibTransaction>, RgbLibError> {
-        let online = if skip_sync {
-            None
-        } else {
-            Some(self.online.clone())
-        };
-        self.get_rgb_wallet().list_transactions(online, skip_sync)
+        self.with_indexer(|wallet, online| {
+            wallet.list_transactions((!skip_sync).then_some(online), skip_sync)
+        })
     }
 
     pub(crate) fn list_transfers(&self, asset_id: String) -> Result<Vec<Transfer>, RgbLibError> {
@@ -496,13 +778,16 @@ impl RgbLibWalletWrapper {
     }
 
     pub(crate) fn list_unspents(&self, skip_sync: bool) -> Result<Vec<Unspent>, RgbLibError> {
-        let online = if skip_sync {
-            None
-        } else {
-            Some(self.online.clone())
-        };
-        self.get_rgb_wallet()
-            .list_unspents(online, false, skip_sync)
+        self.with_indexer(|wallet, online| {
+            wallet.list_unspents((!skip_sync).then_some(online), false, skip_sync)
+        })
+    }
+
+    pub(crate) fn list_unspents_vanilla(
+        &self,
+        skip_sync: bool,
+    ) -> Result<Vec<LocalOutput>, RgbLibError> {
+        self.with_indexer(|wallet, online| wallet.list_unspents_vanilla(online, 0, skip_sync))
     }
 
     pub(crate) fn post_consignment<P: AsRef<Path>>(
@@ -523,8 +808,7 @@ impl RgbLibWalletWrapper {
     }
 
     pub(crate) fn refresh(&self, skip_sync: bool) -> Result<RefreshResult, RgbLibError> {
-        self.get_rgb_wallet()
-            .refresh(self.online.clone(), None, vec![], skip_sync)
+        self.with_indexer(|wallet, online| wallet.refresh(online, None, vec![], skip_sync))
     }
 
     pub(crate) fn save_new_asset(
@@ -536,24 +820,6 @@ impl RgbLibWalletWrapper {
             .save_new_asset(consignment, offchain_txid)
     }
 
-    pub(crate) fn send(
-        &self,
-        recipient_map: HashMap<String, Vec<Recipient>>,
-        donation: bool,
-        fee_rate: u64,
-        min_confirmations: u8,
-        skip_sync: bool,
-    ) -> Result<OperationResult, RgbLibError> {
-        self.get_rgb_wallet().send(
-            self.online.clone(),
-            recipient_map,
-            donation,
-            fee_rate,
-            min_confirmations,
-            skip_sync,
-        )
-    }
-
     pub(crate) fn send_begin(
         &self,
         recipient_map: HashMap<String, Vec<Recipient>>,
@@ -561,44 +827,45 @@ impl RgbLibWalletWrapper {
         fee_rate: u64,
         min_confirmations: u8,
     ) -> Result<String, RgbLibError> {
-        self.get_rgb_wallet().send_begin(
-            self.online.clone(),
-            recipient_map,
-            donation,
-            fee_rate,
-            min_confirmations,
-        )
+        self.with_indexer(|wallet, online| {
+            wallet.send_begin(
+                online,
+                recipient_map.clone(),
+                donation,
+                fee_rate,
+                min_confirmations,
+            )
+        })
     }
 
-    pub(crate) fn send_btc(
+    pub(crate) fn send_btc_begin(
         &self,
         address: String,
         amount: u64,
         fee_rate: u64,
         skip_sync: bool,
     ) -> Result<String, RgbLibError> {
-        self.get_rgb_wallet()
-            .send_btc(self.online.clone(), address, amount, fee_rate, skip_sync)
+        self.with_indexer(|wallet, online| {
+            wallet.send_btc_begin(online, address.clone(), amount, fee_rate, skip_sync)
+        })
     }
 
-    pub(crate) fn send_btc_begin(
+    pub(crate) fn send_btc_end(
         &self,
-        address: String,
-        amount: u64,
-        fee_rate: u64,
+        signed_psbt: String,
+        skip_sync: bool,
     ) -> Result<String, RgbLibError> {
-        self.get_rgb_wallet()
-            .send_btc_begin(self.online.clone(), address, amount, fee_rate, false)
-    }
-
-    pub(crate) fn send_btc_end(&self, signed_psbt: String) -> Result<String, RgbLibError> {
-        self.get_rgb_wallet()
-            .send_btc_end(self.online.clone(), signed_psbt, false)
+        self.with_indexer(|wallet, online| {
+            wallet.send_btc_end(online, signed_psbt.clone(), skip_sync)
+        })
     }
 
-    pub(crate) fn send_end(&self, signed_psbt: String) -> Result<OperationResult, RgbLibError> {
-        self.get_rgb_wallet()
-            .send_end(self.online.clone(), signed_psbt, false)
+    pub(crate) fn send_end(
+        &self,
+        signed_psbt: String,
+        skip_sync: bool,
+    ) -> Result<OperationResult, RgbLibError> {
+        self.with_indexer(|wallet, online| wallet.send_end(online, signed_psbt.clone(), skip_sync))
     }
 
     pub(crate) fn sign_psbt(&self, unsigned_psbt: String) -> Result<String, RgbLibError> {
@@ -606,7 +873,7 @@ impl RgbLibWalletWrapper {
     }
 
     pub(crate) fn sync(&self) -> Result<(), RgbLibError> {
-        self.get_rgb_wallet().sync(self.online.clone())
+        self.with_indexer(|wallet, online| wallet.sync(online))
     }
 
     pub(crate) fn update_witnesses(
@@ -627,6 +894,24 @@ impl RgbLibWalletWrapper {
             .upsert_witness(witness_id, witness_ord)
     }
 
+    /// Rebuild the RGB database file to drop its free pages, returning the bytes reclaimed
+    ///
+    /// The wallet is kept locked meanwhile, so that no operation writes to the database
+    pub(crate) fn vacuum_database(&self) -> Result<u64, APIError> {
+        let wallet = self.get_rgb_wallet();
+        let wallet_data = wallet.get_wallet_data();
+        let db_path = Path::new(&wallet_data.data_dir)
+            .join(&wallet_data.master_fingerprint)
+            .join(RGB_LIB_DB_FNAME);
+        let size_before = fs::metadata(&db_path)?.len();
+        Connection::open(&db_path)
+            .and_then(|conn| conn.execute_batch("VACUUM"))
+            .map_err(|e| APIError::Unexpected(format!("Failed to vacuum the RGB database: {e}")))?;
+        let size_after = fs::metadata(&db_path)?.len();
+        drop(wallet);
+        Ok(size_before.saturating_sub(size_after))
+    }
+
     pub(crate) fn witness_receive(
         &self,
         asset_id: Option<String>,
@@ -661,8 +946,7 @@ impl WalletSource for RgbLibWalletWrapper {
         Box::pin(async move {
             let network =
                 Network::from_str(&self.bitcoin_network().to_string().to_lowercase()).unwrap();
-            let mut wallet = self.wallet.lock().unwrap();
-            Ok(wallet.list_unspents_vanilla(self.online.clone(), 1, false).unwrap().iter().filter_map(|u| {
+            Ok(self.with_indexer(|wallet, online| wallet.list_unspents_vanilla(online, 1, false)).unwrap().iter().filter_map(|u| {
             let script = u.txout.script_pubkey.clone().into_boxed_script();
             let address = Address::from_script(&script, network).unwrap();
             let outpoint = OutPoint::from_str(&u.outpoint.to_string()).unwrap();
@@ -723,6 +1007,117 @@ impl WalletSource for RgbLibWalletWrapper {
     }
 }
 
+/// Wallet source for anchor fee bumping, adding the reserve UTXOs (held by the LDK keys manager
+/// to keep them out of rgb-lib coin selection) to the vanilla UTXOs of the RGB wallet
+pub(crate) struct AnchorWalletSource {
+    pub(crate) rgb_wallet_wrapper: Arc<RgbLibWalletWrapper>,
+    pub(crate) keys_manager: Arc<KeysManager>,
+    pub(crate) bitcoind_client: Arc<BitcoindClient>,
+    pub(crate) anchor_reserve: Arc<Mutex<AnchorReserveMap>>,
+}
+
+impl AnchorWalletSource {
+    fn reserve_script(&self) -> ScriptBuf {
+        self.keys_manager.get_destination_script([0; 32]).unwrap()
+    }
+
+    fn reserve_utxos(&self) -> Vec<(OutPoint, u64)> {
+        self.anchor_reserve
+            .lock()
+            .unwrap()
+            .utxos
+            .iter()
+            .filter_map(|(o, i)| OutPoint::from_str(o).ok().map(|o| (o, i.amount_sat)))
+            .collect()
+    }
+}
+
+impl WalletSource for AnchorWalletSource {
+    fn list_confirmed_utxos<'a>(&'a self) -> AsyncResult<'a, Vec<Utxo>, ()> {
+        Box::pin(async move {
+            let mut utxos = WalletSource::list_confirmed_utxos(&*self.rgb_wallet_wrapper).await?;
+            let reserve_script = self.reserve_script();
+            let wpkh = WPubkeyHash::from_slice(&reserve_script.as_bytes()[2..]).map_err(|_| ())?;
+            for (outpoint, amount_sat) in self.reserve_utxos() {
+                match self
+                    .bitcoind_client
+                    .get_txout_confirmations(&outpoint.txid, outpoint.vout)
+                    .await
+                {
+                    Ok(Some(confirmations)) if confirmations > 0 => utxos.push(
+                        Utxo::new_v0_p2wpkh(outpoint, Amount::from_sat(amount_sat), &wpkh),
+                    ),
+                    _ => {}
+                }
+            }
+            Ok(utxos)
+        })
+    }
+
+    fn get_change_script<'a>(&'a self) -> AsyncResult<'a, ScriptBuf, ()> {
+        WalletSource::get_change_script(&*self.rgb_wallet_wrapper)
+    }
+
+    fn sign_psbt<'a>(&'a self, tx: Psbt) -> AsyncResult<'a, Transaction, ()> {
+        Box::pin(async move {
+            let reserve_script = self.reserve_script();
+            let reserve_descriptors: Vec<SpendableOutputDescriptor> = tx
+                .unsigned_tx
+                .input
+                .iter()
+                .zip(tx.inputs.iter())
+                .filter_map(|(txin, psbt_in)| {
+                    let output = psbt_in.witness_utxo.clone()?;
+                    (output.script_pubkey == reserve_script).then(|| {
+                        SpendableOutputDescriptor::StaticOutput {
+                            outpoint: LdkOutPoint {
+                                txid: txin.previous_output.txid,
+                                index: txin.previous_output.vout as u16,
+                            },
+                            output,
+                            channel_keys_id: None,
+                        }
+                    })
+                })
+                .collect();
+            if reserve_descriptors.is_empty() {
+                return WalletSource::sign_psbt(&*self.rgb_wallet_wrapper, tx).await;
+            }
+
+            let mut psbt = tx;
+            if reserve_descriptors.len() < psbt.inputs.len() {
+                let sign_options = SignOptions {
+                    trust_witness_utxo: true,
+                    ..Default::default()
+                };
+                let signed = self
+                    .rgb_wallet_wrapper
+                    .wallet
+                    .lock()
+                    .unwrap()
+                    .sign_psbt(psbt.to_string(), Some(sign_options))
+                    .map_err(|_| ())?;
+                psbt = Psbt::from_str(&signed).map_err(|_| ())?;
+            }
+            let descriptors: Vec<&SpendableOutputDescriptor> = reserve_descriptors.iter().collect();
+            let psbt = self.keys_manager.sign_spendable_outputs_psbt(
+                &descriptors,
+                psbt,
+                &Secp256k1::new(),
+            )?;
+            psbt.extract_tx().map_err(|_| ())
+        })
+    }
+}
+
+pub(crate) fn assignment_amount(assignment: &Assignment) -> u64 {
+    match assignment {
+        Assignment::Fungible(amt) => *amt,
+        Assignment::NonFungible => 1,
+        _ => 0,
+    }
+}
+
 pub(crate) async fn check_rgb_proxy_endpoint(proxy_endpoint: &str) -> Result<(), APIError> {
     let rgb_transport =
         RgbTransport::from_str(proxy_endpoint).map_err(|_| APIError::InvalidProxyEndpoint)?;
@@ -1,26 +1,49 @@
 use amplify::{map, s, Display};
 use axum::{
-    extract::{Multipart, State},
-    Json,
+    body::Body,
+    extract::{Multipart, Path as AxumPath, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Extension, Json,
 };
 use axum_extra::extract::WithRejection;
 use biscuit_auth::Biscuit;
+use bitcoin::consensus::encode::serialize_hex;
+use bitcoin::constants::ChainHash;
 use bitcoin::hashes::sha256::{self, Hash as Sha256};
 use bitcoin::hashes::Hash;
-use bitcoin::secp256k1::PublicKey;
-use bitcoin::{Network, ScriptBuf};
+use bitcoin::opcodes::all::{OP_CHECKMULTISIG, OP_PUSHNUM_2, OP_PUSHNUM_3, OP_RETURN};
+use bitcoin::psbt::Psbt;
+use bitcoin::script::Instruction;
+use bitcoin::secp256k1::{PublicKey, Secp256k1, SecretKey};
+use bitcoin::{Address, Amount, Network, OutPoint, Script, ScriptBuf, TxOut, Txid, Witness};
 use hex::DisplayHex;
-use lightning::ln::{channelmanager::OptionalOfferPaymentParams, types::ChannelId};
+use lightning::ln::{
+    channelmanager::{OptionalOfferPaymentParams, PhantomRouteHints, MIN_CLTV_EXPIRY_DELTA},
+    invoice_utils::create_phantom_invoice,
+    msgs::SocketAddress,
+    script::ShutdownScript,
+    types::ChannelId,
+};
 use lightning::offers::offer::{self, Offer};
 use lightning::onion_message::messenger::Destination;
 use lightning::rgb_utils::{
-    get_rgb_channel_info_path, get_rgb_payment_info_path, parse_rgb_channel_info,
-    parse_rgb_payment_info, STATIC_BLINDING,
+    get_rgb_channel_info_path, get_rgb_payment_info_path, is_channel_rgb, parse_rgb_channel_info,
+    parse_rgb_payment_info, BITCOIN_NETWORK_FNAME, STATIC_BLINDING,
+    WALLET_ACCOUNT_XPUB_COLORED_FNAME, WALLET_ACCOUNT_XPUB_VANILLA_FNAME,
 };
 use lightning::routing::gossip::RoutingFees;
-use lightning::routing::router::{Path as LnPath, Route, RouteHint, RouteHintHop};
-use lightning::sign::EntropySource;
-use lightning::util::config::ChannelConfig;
+use lightning::routing::router::{
+    build_route_from_hops, Path as LnPath, Route, RouteHint, RouteHintHop, Router as _,
+};
+use lightning::sign::{EntropySource, SpendableOutputDescriptor};
+use lightning::util::config::{ChannelConfig, ChannelConfigUpdate};
+use lightning::util::persist::{
+    KVStoreSync, ARCHIVED_CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+    CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE, CHANNEL_MONITOR_PERSISTENCE_SECONDARY_NAMESPACE,
+};
+use lightning::util::ser::{Readable, Writeable};
+use lightning::util::sweep::OutputSpendStatus;
 use lightning::{chain::channelmonitor::Balance, impl_writeable_tlv_based_enum};
 use lightning::{
     ln::channel_state::ChannelShutdownState, onion_message::messenger::MessageSendInstructions,
@@ -34,68 +57,105 @@ use lightning::{
     ln::channelmanager::{PaymentId, RecipientOnionFields, Retry},
     rgb_utils::{write_rgb_channel_info, write_rgb_payment_info_file, RgbInfo},
     routing::{
-        gossip::NodeId,
+        gossip::{ChannelUpdateInfo, NodeId},
         router::{PaymentParameters, RouteParameters},
     },
     util::config::{ChannelHandshakeConfig, ChannelHandshakeLimits, UserConfig},
     util::{errors::APIError as LDKAPIError, IS_SWAP_SCID},
 };
-use lightning_invoice::{Bolt11Invoice, PaymentSecret};
+use lightning_invoice::{
+    Bolt11Invoice, Bolt11InvoiceDescription, Bolt11InvoiceDescriptionRef, Currency, Fallback,
+    InvoiceBuilder, PaymentSecret,
+};
 use regex::Regex;
 use rgb_lib::{
+    bdk_wallet::keys::bip39::Mnemonic,
+    bdk_wallet::miniscript::{Descriptor, DescriptorPublicKey},
+    bitcoin::bip32::ChildNumber,
     generate_keys,
-    utils::recipient_id_from_script_buf,
+    utils::{recipient_id_from_script_buf, script_buf_from_recipient_id},
     wallet::{
         rust_only::{
             check_indexer_url as rgb_lib_check_indexer_url,
             IndexerProtocol as RgbLibIndexerProtocol,
         },
-        AssetCFA as RgbLibAssetCFA, AssetNIA as RgbLibAssetNIA, AssetUDA as RgbLibAssetUDA,
-        Balance as RgbLibBalance, EmbeddedMedia as RgbLibEmbeddedMedia, Invoice as RgbLibInvoice,
-        Media as RgbLibMedia, ProofOfReserves as RgbLibProofOfReserves, Recipient, RecipientInfo,
-        RecipientType as RgbLibRecipientType, Token as RgbLibToken, TokenLight as RgbLibTokenLight,
+        AssetCFA as RgbLibAssetCFA, AssetIFA as RgbLibAssetIFA, AssetNIA as RgbLibAssetNIA,
+        AssetUDA as RgbLibAssetUDA, Balance as RgbLibBalance, EmbeddedMedia as RgbLibEmbeddedMedia,
+        Invoice as RgbLibInvoice, Media as RgbLibMedia, ProofOfReserves as RgbLibProofOfReserves,
+        Recipient, RecipientInfo, RecipientType as RgbLibRecipientType, Token as RgbLibToken,
+        TokenLight as RgbLibTokenLight, Transfer as RgbLibTransfer,
         WitnessData as RgbLibWitnessData,
     },
     AssetSchema as RgbLibAssetSchema, Assignment as RgbLibAssignment,
-    BitcoinNetwork as RgbLibNetwork, ContractId, RgbTransport,
+    BitcoinNetwork as RgbLibNetwork, ConsignmentExt, ContractId, RgbTransfer, RgbTransport,
 };
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
-    net::ToSocketAddrs,
+    collections::{HashMap, HashSet},
+    marker::PhantomData,
+    net::IpAddr,
     path::{Path, PathBuf},
     str::FromStr,
     sync::Arc,
-    time::Duration,
+    time::{Duration, Instant},
 };
 use tokio::{
     fs::File,
     io::{AsyncReadExt, AsyncWriteExt, BufReader},
-    sync::MutexGuard as TokioMutexGuard,
+    sync::RwLockReadGuard as TokioRwLockReadGuard,
 };
-
-use crate::ldk::{start_ldk, stop_ldk, LdkBackgroundServices, MIN_CHANNEL_CONFIRMATIONS};
+use tokio_util::io::ReaderStream;
+use zeroize::Zeroizing;
+
+use crate::auth::TokenId;
+use crate::hedging::{parse_hedging_asset, HedgingPolicy};
+use crate::ldk::{
+    close_output_allocation, spendable_output, start_ldk, stop_ldk, AmountBoundsInfo, AssetPolicy,
+    ChainSubscriptionInfo, ChannelAcceptorPolicy, ChannelDepthOverrides, CloseOutputInfo,
+    EscrowInfo, ExternalFundingInfo, FallbackAddressInfo, HeldHtlc, InterceptScopeInfo,
+    JitChannelInfo, LdkBackgroundServices, LightningAddressInfo, LnurlPayConfig, LockedUtxoInfo,
+    MultisigFundingInfo, MultisigFundingInput, PeerChannelDepth, PeerFilter, ProbeOutcome,
+    SweepConfig, BTC_POLICY_KEY, DEFAULT_ASYNC_HOLD_TIMEOUT_SEC, EXTERNAL_FUNDING_TX_PREFIX,
+    MAX_ASYNC_HOLD_TIMEOUT_SEC, MAX_CHAIN_SUBSCRIPTIONS, MAX_INTERCEPT_SCOPES,
+    MAX_JOURNAL_PROOF_ENTRIES, MAX_MIN_CHANNEL_DEPTH, MIN_CHANNEL_CONFIRMATIONS,
+};
+use crate::lsps::{cheapest_opening_fee_params, scid_to_string, LeaseConfig, LspConfig};
+use crate::nwc::NwcConnectionInfo;
+use crate::peer_messages::supports_rgb;
+use crate::store::{storage_cipher, NodeStore};
+use crate::submarine::{check_swap_output, SubmarineSwapInfo, SwapProviderClient};
 use crate::swap::{SwapData, SwapInfo, SwapString};
+use crate::tor::CircuitGroup;
+use crate::totp::TotpOperation;
 use crate::utils::{
-    check_already_initialized, check_channel_id, check_password_strength, check_password_validity,
-    encrypt_and_save_mnemonic, get_max_local_rgb_amount, get_mnemonic_path, get_route, hex_str,
-    hex_str_to_compressed_pubkey, hex_str_to_vec, UnlockedAppState, UserOnionMessageContents,
+    check_already_initialized, check_announce_alias, check_channel_id, check_password_strength,
+    check_password_validity, check_payment_hash, check_payment_preimage,
+    check_view_password_validity, encrypt_and_save_mnemonic, get_max_local_rgb_amount,
+    get_mnemonic_path, get_route, get_view_mnemonic_path, hex_str, hex_str_to_compressed_pubkey,
+    hex_str_to_vec, parse_announce_color, save_ldk_key_derivation, LdkKeyDerivation,
+    UnlockedAppState, UserOnionMessageContents,
 };
 use crate::{
     backup::{do_backup, restore_backup},
-    rgb::{check_rgb_proxy_endpoint, get_rgb_channel_info_optional},
+    rgb::{assignment_amount, check_rgb_proxy_endpoint, get_rgb_channel_info_optional},
 };
 use crate::{
-    disk::{self, CHANNEL_PEER_DATA},
+    disk::{self, CHANNEL_PEER_DATA, EMERGENCY_KIT_FNAME, OUTPUT_SPENDER_TXES},
     error::APIError,
-    ldk::{PaymentInfo, FEE_RATE, UTXO_SIZE_SAT},
+    ldk::{
+        settlement_period, OfferInfo, OrderInfo, OrderItemInfo, OrderLegInfo, PaymentInfo,
+        PaymentPartInfo, PendingBroadcastInfo, PendingSettlementInfo, FEE_RATE, UTXO_SIZE_SAT,
+    },
     utils::{
-        connect_peer_if_necessary, get_current_timestamp, no_cancel, parse_peer_info, AppState,
+        connect_peer_if_necessary, connect_peer_with_retries, get_current_timestamp, no_cancel,
+        parse_peer_info, AppState, PEER_CONNECTION_TIMEOUT,
     },
 };
 
 const UTXO_NUM: u8 = 4;
 
+const BACKUP_EXPORT_FNAME: &str = "rln_backup";
+
 pub(crate) const HTLC_MIN_MSAT: u64 = 3000000;
 pub(crate) const MAX_SWAP_FEE_MSAT: u64 = HTLC_MIN_MSAT;
 
@@ -104,15 +164,206 @@ const OPENCHANNEL_MIN_SAT: u64 = 5506;
 const OPENCHANNEL_MAX_SAT: u64 = 16777215;
 const OPENCHANNEL_MIN_RGB_AMT: u64 = 1;
 
+const MAX_ACCEPTED_HTLCS: u16 = 483;
+const MAX_CHANNEL_RESERVE_PROPORTIONAL_MILLIONTHS: u32 = 1_000_000;
+
+const MIN_CLOSE_FEE_RATE: u64 = 1;
+const MIN_SWEEP_FEE_RATE: u64 = 1;
+
+const MAX_PEER_CONNECTION_TIMEOUT_SEC: u64 = 300;
+const MAX_PEER_CONNECTION_RETRIES: u8 = 10;
+
+const MIN_FUNDING_FEE_RATE: u64 = 1;
+const DEFAULT_FUNDING_TIMEOUT_SEC: u64 = 1800;
+// LDK drops unfunded channels after about an hour
+const MAX_FUNDING_TIMEOUT_SEC: u64 = 3600;
+
 pub const DUST_LIMIT_MSAT: u64 = 546000;
 
 const INVOICE_MIN_MSAT: u64 = HTLC_MIN_MSAT;
 
+const MAX_INVOICES_BATCH_SIZE: usize = 1000;
+
+const MAX_PAGE_SIZE: usize = 1000;
+
+const MAX_FORWARDS_PAGE_SIZE: usize = 1000;
+
+const LNURL_INVOICE_EXPIRY_SEC: u32 = 600;
+const MAX_LIGHTNING_ADDRESSES: usize = 100;
+
+const DEFAULT_PAYMENT_RETRY_TIMEOUT_SEC: u64 = 10;
+const MAX_PAYMENT_TIMEOUT_SEC: u64 = 3600;
+const MAX_PAYMENT_PARTS: u8 = 16;
+
+const DEFAULT_QUERY_ROUTES: u8 = 3;
+const MAX_QUERY_ROUTES: u8 = 10;
+
+const PROBE_TIMEOUT_SEC: u64 = 60;
+
+const REBALANCE_INVOICE_EXPIRY_SEC: u32 = 3600;
+
+const SUBMARINE_SWAP_INVOICE_EXPIRY_SEC: u32 = 86400;
+
+const MAX_ORDER_ITEMS: usize = 100;
+
+/// Path prefix of the first API version, the APIs being also served without it
+pub(crate) const API_VERSION_PREFIX: &str = "/v1";
+
+/// Path prefix of the API version returning paginated lists and errors with structured details
+pub(crate) const API_V2_PREFIX: &str = "/v2";
+
+pub(crate) const OPENAPI_SPEC: &str = include_str!("../openapi.yaml");
+
+const DEFAULT_PEER_SUGGESTIONS: usize = 10;
+const MAX_PEER_SUGGESTIONS: usize = 100;
+
+const MAX_NONCE_LEN: usize = 128;
+
+const AUDIT_SNAPSHOT_MESSAGE_HEADER: &str = "rgb-lightning-node audit snapshot";
+
+const MAX_LABEL_LEN: usize = 64;
+
 pub(crate) const DEFAULT_FINAL_CLTV_EXPIRY_DELTA: u32 = 14;
 
+/// Keychain of the account xpubs the RGB wallet derives its addresses from
+const WALLET_KEYCHAIN: u8 = 0;
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AbandonPaymentRequest {
+    pub(crate) payment_hash: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AccelerateIncomingRequest {
+    pub(crate) idx: i32,
+    pub(crate) fee_rate: u64,
+    pub(crate) max_fee_sat: u64,
+    pub(crate) totp_code: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AccelerateIncomingResponse {
+    pub(crate) txid: String,
+    pub(crate) fee_sat: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AccountingEntry {
+    pub(crate) id: String,
+    pub(crate) timestamp: u64,
+    pub(crate) kind: AccountingEntryKind,
+    pub(crate) amount_msat: i64,
+    pub(crate) fee_msat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<i64>,
+    pub(crate) reference: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum AccountingEntryKind {
+    OnchainTx,
+    ChannelOpen,
+    ChannelClose,
+    PaymentSent,
+    PaymentReceived,
+    Forward,
+    AssetTransfer,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AcceptChannelRequest {
+    pub(crate) temporary_channel_id: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AddressRequest {
+    pub(crate) address_type: Option<AddressType>,
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct AddressResponse {
     pub(crate) address: String,
+    pub(crate) address_type: AddressType,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AddressStatsResponse {
+    pub(crate) issued: u32,
+    pub(crate) used: u32,
+    pub(crate) gap: u32,
+    pub(crate) gap_limit: u32,
+    pub(crate) unused_addresses: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum AddressType {
+    P2tr,
+    P2wpkh,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AnchorReserveEvent {
+    pub(crate) timestamp: u64,
+    pub(crate) kind: AnchorReserveEventKind,
+    pub(crate) details: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum AnchorReserveEventKind {
+    Created,
+    CreationFailed,
+    Spent,
+    Depleted,
+    Replenished,
+}
+
+impl_writeable_tlv_based_enum!(AnchorReserveEventKind,
+    (0, Created) => {},
+    (1, CreationFailed) => {},
+    (2, Spent) => {},
+    (3, Depleted) => {},
+    (4, Replenished) => {},
+);
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AnchorReserveResponse {
+    pub(crate) target_utxos: u8,
+    pub(crate) utxo_size_sat: u64,
+    pub(crate) depleted: bool,
+    pub(crate) utxos: Vec<AnchorReserveUtxo>,
+    pub(crate) events: Vec<AnchorReserveEvent>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AnchorReserveUtxo {
+    pub(crate) outpoint: String,
+    pub(crate) amount_sat: u64,
+    pub(crate) confirmations: Option<u32>,
+    pub(crate) created_at: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ApproveBroadcastRequest {
+    pub(crate) totp_code: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ApproveBroadcastResponse {
+    pub(crate) broadcast: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AssetAuditResponse {
+    pub(crate) asset_id: String,
+    pub(crate) asset_schema: AssetSchema,
+    pub(crate) issued_supply: u64,
+    pub(crate) issued_locally: u64,
+    pub(crate) received: u64,
+    pub(crate) sent: u64,
+    pub(crate) held_onchain: u64,
+    pub(crate) held_offchain: u64,
+    pub(crate) burned: u64,
+    pub(crate) anomalies: Vec<String>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -160,6 +411,16 @@ pub(crate) struct AssetMetadataResponse {
     pub(crate) token: Option<Token>,
 }
 
+/// Asset of any schema, tagged with its `asset_schema`
+#[derive(Deserialize, Serialize)]
+#[serde(tag = "asset_schema")]
+pub(crate) enum Asset {
+    Nia(AssetNIA),
+    Uda(AssetUDA),
+    Cfa(AssetCFA),
+    Ifa(AssetIFA),
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct AssetCFA {
     pub(crate) asset_id: String,
@@ -189,6 +450,41 @@ impl From<RgbLibAssetCFA> for AssetCFA {
     }
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AssetIFA {
+    pub(crate) asset_id: String,
+    pub(crate) ticker: String,
+    pub(crate) name: String,
+    pub(crate) details: Option<String>,
+    pub(crate) precision: u8,
+    pub(crate) initial_supply: u64,
+    pub(crate) max_supply: u64,
+    pub(crate) known_circulating_supply: u64,
+    pub(crate) timestamp: i64,
+    pub(crate) added_at: i64,
+    pub(crate) balance: AssetBalanceResponse,
+    pub(crate) media: Option<Media>,
+}
+
+impl From<RgbLibAssetIFA> for AssetIFA {
+    fn from(value: RgbLibAssetIFA) -> Self {
+        Self {
+            asset_id: value.asset_id,
+            ticker: value.ticker,
+            name: value.name,
+            details: value.details,
+            precision: value.precision,
+            initial_supply: value.initial_supply,
+            max_supply: value.max_supply,
+            known_circulating_supply: value.known_circulating_supply,
+            timestamp: value.timestamp,
+            added_at: value.added_at,
+            balance: value.balance.into(),
+            media: value.media.map(|m| m.into()),
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct AssetNIA {
     pub(crate) asset_id: String,
@@ -220,11 +516,12 @@ impl From<RgbLibAssetNIA> for AssetNIA {
     }
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, PartialEq, Serialize)]
 pub(crate) enum AssetSchema {
     Nia,
     Uda,
     Cfa,
+    Ifa,
 }
 
 impl From<AssetSchema> for RgbLibAssetSchema {
@@ -233,6 +530,7 @@ impl From<AssetSchema> for RgbLibAssetSchema {
             AssetSchema::Nia => Self::Nia,
             AssetSchema::Uda => Self::Uda,
             AssetSchema::Cfa => Self::Cfa,
+            AssetSchema::Ifa => Self::Ifa,
         }
     }
 }
@@ -243,11 +541,20 @@ impl From<RgbLibAssetSchema> for AssetSchema {
             RgbLibAssetSchema::Nia => Self::Nia,
             RgbLibAssetSchema::Uda => Self::Uda,
             RgbLibAssetSchema::Cfa => Self::Cfa,
-            RgbLibAssetSchema::Ifa => todo!(),
+            RgbLibAssetSchema::Ifa => Self::Ifa,
         }
     }
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AssetRecipient {
+    pub(crate) asset_id: String,
+    pub(crate) assignment: Assignment,
+    pub(crate) recipient_id: String,
+    pub(crate) witness_data: Option<WitnessData>,
+    pub(crate) transport_endpoints: Vec<String>,
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct AssetUDA {
     pub(crate) asset_id: String,
@@ -311,12 +618,121 @@ impl From<Assignment> for RgbLibAssignment {
     }
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AuditRequest {
+    pub(crate) block_height: Option<u32>,
+    pub(crate) nonce: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AuditResponse {
+    pub(crate) snapshot: AuditSnapshot,
+    pub(crate) message: String,
+    pub(crate) signature: String,
+}
+
+/// Holdings of the node at a block height, signed as a whole in its JSON serialization
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AuditSnapshot {
+    pub(crate) pubkey: String,
+    pub(crate) block_height: u32,
+    pub(crate) block_hash: String,
+    pub(crate) timestamp: u64,
+    pub(crate) nonce: Option<String>,
+    pub(crate) onchain_vanilla_sat: u64,
+    pub(crate) onchain_colored_sat: u64,
+    pub(crate) channels: Vec<AuditSnapshotChannel>,
+    pub(crate) assets: Vec<AuditSnapshotAsset>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AuditSnapshotAsset {
+    pub(crate) asset_id: String,
+    pub(crate) settled: u64,
+    pub(crate) offchain_outbound: u64,
+    pub(crate) offchain_inbound: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct AuditSnapshotChannel {
+    pub(crate) channel_id: String,
+    pub(crate) peer_pubkey: String,
+    pub(crate) funding_txid: Option<String>,
+    pub(crate) ready: bool,
+    pub(crate) capacity_sat: u64,
+    pub(crate) claimable_balance_sat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_local_amount: Option<u64>,
+    pub(crate) asset_remote_amount: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BackupChannelsRequest {
+    pub(crate) backup_path: String,
+    pub(crate) password: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BackupChannelsResponse {
+    pub(crate) num_channels: usize,
+}
+
 #[derive(Deserialize, Serialize)]
 pub(crate) struct BackupRequest {
     pub(crate) backup_path: String,
     pub(crate) password: String,
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BalanceSheetAsset {
+    pub(crate) asset_id: String,
+    pub(crate) ticker: Option<String>,
+    pub(crate) settled: u64,
+    pub(crate) future: u64,
+    pub(crate) spendable: u64,
+    pub(crate) offchain_outbound: u64,
+    pub(crate) offchain_inbound: u64,
+    pub(crate) pending_close: u64,
+    pub(crate) pending_sweep: u64,
+    pub(crate) htlc_inbound: u64,
+    pub(crate) htlc_outbound: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BalanceSheetChannels {
+    pub(crate) local_sat: u64,
+    pub(crate) remote_sat: u64,
+    pub(crate) pending_open_sat: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BalanceSheetHtlcs {
+    pub(crate) inbound_msat: u64,
+    pub(crate) outbound_msat: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BalanceSheetOnchain {
+    pub(crate) confirmed_sat: u64,
+    pub(crate) unconfirmed_sat: i64,
+    pub(crate) spendable_sat: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BalanceSheetRequest {
+    pub(crate) skip_sync: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BalanceSheetResponse {
+    pub(crate) onchain: BalanceSheetOnchain,
+    pub(crate) channels: BalanceSheetChannels,
+    pub(crate) pending_close_sat: u64,
+    pub(crate) pending_sweep_sat: u64,
+    pub(crate) htlcs: BalanceSheetHtlcs,
+    pub(crate) assets: Vec<BalanceSheetAsset>,
+}
+
 #[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub(crate) enum BitcoinNetwork {
     Mainnet,
@@ -357,6 +773,26 @@ pub(crate) struct BlockTime {
     pub(crate) timestamp: u64,
 }
 
+#[derive(Deserialize, Serialize)]
+pub(crate) struct Bolt12Offer {
+    pub(crate) offer_id: String,
+    pub(crate) offer: String,
+    pub(crate) description: String,
+    pub(crate) amt_msat: Option<u64>,
+    pub(crate) created_at: u64,
+    pub(crate) expires_at: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BroadcastPsbtRequest {
+    pub(crate) psbt: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BroadcastPsbtResponse {
+    pub(crate) txid: String,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub(crate) struct BtcBalance {
     pub(crate) settled: u64,
@@ -375,1523 +811,11876 @@ pub(crate) struct BtcBalanceResponse {
     pub(crate) colored: BtcBalance,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub(crate) struct ChangePasswordRequest {
-    pub(crate) old_password: String,
-    pub(crate) new_password: String,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BtcRecipient {
+    pub(crate) address: String,
+    pub(crate) amount: u64,
 }
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
-pub(crate) struct Channel {
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BumpCloseFeeRequest {
     pub(crate) channel_id: String,
-    pub(crate) funding_txid: Option<String>,
-    pub(crate) peer_pubkey: String,
-    pub(crate) peer_alias: Option<String>,
-    pub(crate) short_channel_id: Option<u64>,
-    pub(crate) status: ChannelStatus,
-    pub(crate) ready: bool,
-    pub(crate) capacity_sat: u64,
-    pub(crate) local_balance_sat: u64,
-    pub(crate) outbound_balance_msat: u64,
-    pub(crate) inbound_balance_msat: u64,
-    pub(crate) next_outbound_htlc_limit_msat: u64,
-    pub(crate) next_outbound_htlc_minimum_msat: u64,
-    pub(crate) is_usable: bool,
-    pub(crate) public: bool,
-    pub(crate) asset_id: Option<String>,
-    pub(crate) asset_local_amount: Option<u64>,
-    pub(crate) asset_remote_amount: Option<u64>,
-}
-
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
-pub(crate) enum ChannelStatus {
-    #[default]
-    Opening,
-    Opened,
-    Closing,
+    pub(crate) confirmation_target: u16,
+    pub(crate) totp_code: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub(crate) struct CheckIndexerUrlRequest {
-    pub(crate) indexer_url: String,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BumpCloseFeeResponse {
+    pub(crate) fee_rate_sat_per_kw: u32,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub(crate) struct CheckIndexerUrlResponse {
-    pub(crate) indexer_protocol: IndexerProtocol,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BumpFeeRequest {
+    pub(crate) txid: String,
+    pub(crate) fee_rate: u64,
+    pub(crate) skip_sync: bool,
+    pub(crate) totp_code: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub(crate) struct CheckProxyEndpointRequest {
-    pub(crate) proxy_endpoint: String,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BumpFeeResponse {
+    pub(crate) txid: String,
+    pub(crate) method: FeeBumpMethod,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct CloseChannelRequest {
-    pub(crate) channel_id: String,
-    pub(crate) peer_pubkey: String,
-    pub(crate) force: bool,
-}
-
-#[derive(Deserialize, Serialize)]
-pub(crate) struct ConnectPeerRequest {
-    pub(crate) peer_pubkey_and_addr: String,
-}
-
-#[derive(Deserialize, Serialize)]
-pub(crate) struct CreateUtxosRequest {
-    pub(crate) up_to: bool,
-    pub(crate) num: Option<u8>,
-    pub(crate) size: Option<u32>,
+pub(crate) struct BurnAssetRequest {
+    pub(crate) asset_id: String,
+    pub(crate) amount: u64,
     pub(crate) fee_rate: u64,
+    pub(crate) min_confirmations: u8,
     pub(crate) skip_sync: bool,
+    pub(crate) totp_code: Option<String>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct DecodeLNInvoiceRequest {
-    pub(crate) invoice: String,
+pub(crate) struct BurnAssetResponse {
+    pub(crate) txid: String,
+    pub(crate) witness_script: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct DecodeLNInvoiceResponse {
-    pub(crate) amt_msat: Option<u64>,
-    pub(crate) expiry_sec: u64,
-    pub(crate) timestamp: u64,
+pub(crate) struct BuyInboundRequest {
+    pub(crate) peer_pubkey_and_opt_addr: String,
+    pub(crate) capacity_sat: u64,
     pub(crate) asset_id: Option<String>,
     pub(crate) asset_amount: Option<u64>,
+    pub(crate) max_fee_sat: u64,
+    pub(crate) totp_code: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct BuyInboundResponse {
+    pub(crate) order_id: String,
+    pub(crate) fee_sat: u64,
+    pub(crate) lease_duration_blocks: u32,
     pub(crate) payment_hash: String,
-    pub(crate) payment_secret: String,
-    pub(crate) payee_pubkey: Option<String>,
-    pub(crate) network: BitcoinNetwork,
+    pub(crate) payment_status: HTLCStatus,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct DecodeRGBInvoiceRequest {
-    pub(crate) invoice: String,
+pub(crate) struct CancelInvoiceRequest {
+    pub(crate) payment_hash: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct DecodeRGBInvoiceResponse {
-    pub(crate) recipient_id: String,
-    pub(crate) recipient_type: RecipientType,
-    pub(crate) asset_schema: Option<AssetSchema>,
-    pub(crate) asset_id: Option<String>,
-    pub(crate) assignment: Assignment,
-    pub(crate) network: BitcoinNetwork,
-    pub(crate) expiration_timestamp: Option<i64>,
-    pub(crate) transport_endpoints: Vec<String>,
+pub(crate) struct CancelInvoicesRequest {
+    pub(crate) payment_hashes: Vec<String>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct DisconnectPeerRequest {
-    pub(crate) peer_pubkey: String,
+pub(crate) struct CancelInvoicesResponse {
+    pub(crate) results: Vec<InvoiceResult>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct EmbeddedMedia {
-    pub(crate) mime: String,
-    pub(crate) data: Vec<u8>,
+pub(crate) struct ChainEvent {
+    pub(crate) timestamp: u64,
+    pub(crate) kind: ChainEventKind,
+    pub(crate) txid: String,
+    pub(crate) outpoint: Option<String>,
+    pub(crate) amount_sat: Option<u64>,
+    pub(crate) block_height: u32,
 }
 
-impl From<RgbLibEmbeddedMedia> for EmbeddedMedia {
-    fn from(value: RgbLibEmbeddedMedia) -> Self {
-        Self {
-            mime: value.mime,
-            data: value.data,
-        }
-    }
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum ChainEventKind {
+    Confirmed,
+    Received,
+    Spent,
 }
 
-#[derive(Deserialize, Serialize)]
-pub(crate) struct EmptyResponse {}
+impl_writeable_tlv_based_enum!(ChainEventKind,
+    (0, Confirmed) => {},
+    (1, Received) => {},
+    (2, Spent) => {},
+);
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct EstimateFeeRequest {
-    pub(crate) blocks: u16,
+pub(crate) struct ChainEventsResponse {
+    pub(crate) subscription_id: String,
+    pub(crate) events: Vec<ChainEvent>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct EstimateFeeResponse {
-    pub(crate) fee_rate: f64,
+pub(crate) struct ChainSubscriptionsRequest {
+    pub(crate) txid: Option<String>,
+    pub(crate) script: Option<String>,
+    pub(crate) address: Option<String>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct FailTransfersRequest {
-    pub(crate) batch_transfer_idx: Option<i32>,
-    pub(crate) no_asset_only: bool,
-    pub(crate) skip_sync: bool,
+pub(crate) struct ChainSubscriptionsResponse {
+    pub(crate) subscription_id: String,
+    pub(crate) start_height: u32,
 }
 
-#[derive(Deserialize, Serialize)]
-pub(crate) struct FailTransfersResponse {
-    pub(crate) transfers_changed: bool,
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct ChangePasswordRequest {
+    pub(crate) old_password: String,
+    pub(crate) new_password: String,
 }
 
-#[derive(Deserialize, Serialize)]
-pub(crate) struct GetAssetMediaRequest {
-    pub(crate) digest: String,
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Channel {
+    pub channel_id: String,
+    pub funding_txid: Option<String>,
+    pub peer_pubkey: String,
+    pub peer_alias: Option<String>,
+    pub short_channel_id: Option<u64>,
+    pub status: ChannelStatus,
+    pub ready: bool,
+    pub capacity_sat: u64,
+    pub local_balance_sat: u64,
+    pub outbound_balance_msat: u64,
+    pub inbound_balance_msat: u64,
+    pub next_outbound_htlc_limit_msat: u64,
+    pub next_outbound_htlc_minimum_msat: u64,
+    pub is_usable: bool,
+    pub public: bool,
+    pub with_anchors: bool,
+    pub scid_alias: bool,
+    pub inbound_htlc_minimum_msat: Option<u64>,
+    pub inbound_htlc_maximum_msat: Option<u64>,
+    pub local_reserve_sat: Option<u64>,
+    pub remote_reserve_sat: u64,
+    pub asset_id: Option<String>,
+    pub asset_local_amount: Option<u64>,
+    pub asset_remote_amount: Option<u64>,
+    pub asset_htlc_min_msat: Option<u64>,
+    pub fee_base_msat: Option<u32>,
+    pub fee_proportional_millionths: Option<u32>,
+    pub cltv_expiry_delta: Option<u16>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct GetAssetMediaResponse {
-    pub(crate) bytes_hex: String,
+pub(crate) struct ChannelAcceptorRequest {
+    pub(crate) min_channel_size_sat: Option<u64>,
+    pub(crate) max_channel_size_sat: Option<u64>,
+    pub(crate) require_anchors: bool,
+    pub(crate) max_channels_per_peer: Option<u16>,
+    pub(crate) reject_rgb_channels: bool,
+    pub(crate) allowed_assets: Vec<String>,
+    pub(crate) denied_assets: Vec<String>,
+    pub(crate) manual_approval: bool,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct GetChannelIdRequest {
-    pub(crate) temporary_channel_id: String,
+pub(crate) struct ChannelAcceptorResponse {
+    pub(crate) min_channel_size_sat: Option<u64>,
+    pub(crate) max_channel_size_sat: Option<u64>,
+    pub(crate) require_anchors: bool,
+    pub(crate) max_channels_per_peer: Option<u16>,
+    pub(crate) reject_rgb_channels: bool,
+    pub(crate) allowed_assets: Vec<String>,
+    pub(crate) denied_assets: Vec<String>,
+    pub(crate) manual_approval: bool,
+}
+
+impl From<ChannelAcceptorPolicy> for ChannelAcceptorResponse {
+    fn from(policy: ChannelAcceptorPolicy) -> Self {
+        Self {
+            min_channel_size_sat: policy.min_channel_size_sat,
+            max_channel_size_sat: policy.max_channel_size_sat,
+            require_anchors: policy.require_anchors,
+            max_channels_per_peer: policy.max_channels_per_peer,
+            reject_rgb_channels: policy.reject_rgb_channels,
+            allowed_assets: policy.allowed_assets,
+            denied_assets: policy.denied_assets,
+            manual_approval: policy.manual_approval,
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct GetChannelIdResponse {
-    pub(crate) channel_id: String,
+pub(crate) struct ChannelDepthOverride {
+    pub(crate) peer_pubkey: String,
+    pub(crate) min_depth: u32,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct GetPaymentRequest {
-    pub(crate) payment_hash: String,
+pub(crate) struct ChannelDepthsRequest {
+    pub(crate) peers: Vec<ChannelDepthOverride>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct GetPaymentResponse {
-    pub(crate) payment: Payment,
+pub(crate) struct ChannelDepthsResponse {
+    pub(crate) min_depth: u32,
+    pub(crate) peers: Vec<ChannelDepthOverride>,
 }
 
-#[derive(Deserialize, Serialize)]
-pub(crate) struct GetSwapRequest {
-    pub(crate) payment_hash: String,
-    pub(crate) taker: bool,
+impl ChannelDepthsResponse {
+    fn new(overrides: &ChannelDepthOverrides, min_depth: u32) -> Self {
+        Self {
+            min_depth,
+            peers: overrides
+                .peers
+                .iter()
+                .map(|p| ChannelDepthOverride {
+                    peer_pubkey: p.peer_pubkey.to_string(),
+                    min_depth: p.min_depth,
+                })
+                .collect(),
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct GetSwapResponse {
-    pub(crate) swap: Swap,
+pub(crate) struct ChannelEvent {
+    pub(crate) timestamp: u64,
+    pub(crate) kind: ChannelEventKind,
+    pub(crate) details: String,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize, Display)]
-#[display(inner)]
-pub(crate) enum HTLCStatus {
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum ChannelEventKind {
+    OpenRequested,
+    OpenReceived,
+    FundingGenerated,
+    FundingFailed,
     Pending,
-    Succeeded,
-    Failed,
-}
-
-impl_writeable_tlv_based_enum!(HTLCStatus,
-    (0, Pending) => {},
-    (1, Succeeded) => {},
-    (2, Failed) => {},
+    Ready,
+    Reconnected,
+    ReconnectFailed,
+    CloseRequested,
+    Closed,
+    CloseFeeBumped,
+    FundingPsbtCreated,
+    FundingOutputReady,
+    ConsignmentSent,
+    FundingBroadcast,
+}
+
+impl_writeable_tlv_based_enum!(ChannelEventKind,
+    (0, OpenRequested) => {},
+    (1, OpenReceived) => {},
+    (2, FundingGenerated) => {},
+    (3, FundingFailed) => {},
+    (4, Pending) => {},
+    (5, Ready) => {},
+    (6, Reconnected) => {},
+    (7, ReconnectFailed) => {},
+    (8, CloseRequested) => {},
+    (9, Closed) => {},
+    (10, CloseFeeBumped) => {},
+    (11, FundingPsbtCreated) => {},
+    (12, FundingOutputReady) => {},
+    (13, ConsignmentSent) => {},
+    (14, FundingBroadcast) => {},
 );
 
-#[derive(Debug, Deserialize, Serialize)]
-pub(crate) enum IndexerProtocol {
-    Electrum,
-    Esplora,
-}
-
-impl From<RgbLibIndexerProtocol> for IndexerProtocol {
-    fn from(x: RgbLibIndexerProtocol) -> Self {
-        match x {
-            RgbLibIndexerProtocol::Electrum => Self::Electrum,
-            RgbLibIndexerProtocol::Esplora => Self::Esplora,
-        }
-    }
-}
-
 #[derive(Deserialize, Serialize)]
-pub(crate) struct InitRequest {
-    pub(crate) password: String,
+pub(crate) struct ChannelEventsResponse {
+    pub(crate) channel_id: String,
+    pub(crate) events: Vec<ChannelEvent>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct InitResponse {
-    pub(crate) mnemonic: String,
+pub(crate) struct ChannelFeeReport {
+    pub(crate) channel_id: String,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) forwards_in: u64,
+    pub(crate) forwards_out: u64,
+    pub(crate) failed_forwards: u64,
+    pub(crate) amt_in_msat: u64,
+    pub(crate) amt_out_msat: u64,
+    pub(crate) fee_earned_msat: u64,
+}
+
+/// Progress of a channel being opened, RGB channels going through the consignment stages
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum ChannelOpenStage {
+    /// Waiting for the peer to accept the channel and for the funding TX to be built
+    Negotiating,
+    /// Funding consignment sent to the proxy, waiting for the peer to validate it
+    ConsignmentSent,
+    /// Funding consignment validated and funding TX signed by the acceptor
+    ConsignmentAcked,
+    /// Funding TX broadcast, waiting for its confirmations
+    FundingBroadcast,
+    Ready,
+    Failed,
+    Closed,
 }
 
-#[derive(Clone, Copy, Deserialize, Serialize)]
-pub(crate) enum InvoiceStatus {
-    Pending,
-    Succeeded,
-    Failed,
-    Expired,
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum ChannelOperation {
+    Funding,
+    Close,
 }
 
-#[derive(Deserialize, Serialize)]
-pub(crate) struct InvoiceStatusRequest {
-    pub(crate) invoice: String,
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub enum ChannelStatus {
+    #[default]
+    Opening,
+    Opened,
+    Closing,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct InvoiceStatusResponse {
-    pub(crate) status: InvoiceStatus,
+pub(crate) struct ChannelStatusRequest {
+    pub(crate) temporary_channel_id: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct IssueAssetCFARequest {
-    pub(crate) amounts: Vec<u64>,
-    pub(crate) name: String,
+pub(crate) struct ChannelStatusResponse {
+    pub(crate) temporary_channel_id: String,
+    pub(crate) channel_id: Option<String>,
+    pub(crate) stage: ChannelOpenStage,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) funding_txid: Option<String>,
+    pub(crate) confirmations: Option<u32>,
+    pub(crate) required_confirmations: Option<u32>,
     pub(crate) details: Option<String>,
-    pub(crate) precision: u8,
-    pub(crate) file_digest: Option<String>,
+    pub(crate) updated_at: Option<u64>,
 }
 
-#[derive(Deserialize, Serialize)]
-pub(crate) struct IssueAssetCFAResponse {
-    pub(crate) asset: AssetCFA,
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct CheckIndexerUrlRequest {
+    pub(crate) indexer_url: String,
 }
 
-#[derive(Deserialize, Serialize)]
-pub(crate) struct IssueAssetNIARequest {
-    pub(crate) amounts: Vec<u64>,
-    pub(crate) ticker: String,
-    pub(crate) name: String,
-    pub(crate) precision: u8,
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct CheckIndexerUrlResponse {
+    pub(crate) indexer_protocol: IndexerProtocol,
 }
 
-#[derive(Deserialize, Serialize)]
-pub(crate) struct IssueAssetNIAResponse {
-    pub(crate) asset: AssetNIA,
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct CheckProxyEndpointRequest {
+    pub(crate) proxy_endpoint: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct IssueAssetUDARequest {
-    pub(crate) ticker: String,
-    pub(crate) name: String,
-    pub(crate) details: Option<String>,
-    pub(crate) precision: u8,
-    pub(crate) media_file_digest: Option<String>,
-    pub(crate) attachments_file_digests: Vec<String>,
+pub(crate) struct CloseChannelRequest {
+    pub(crate) channel_id: String,
+    pub(crate) peer_pubkey: String,
+    pub(crate) force: bool,
+    pub(crate) close_address: Option<String>,
+    pub(crate) fee_rate: Option<u64>,
+    pub(crate) confirmation_target: Option<u16>,
+    pub(crate) totp_code: Option<String>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct IssueAssetUDAResponse {
-    pub(crate) asset: AssetUDA,
+pub(crate) struct CloseClaim {
+    pub(crate) kind: CloseClaimKind,
+    pub(crate) amount_sat: u64,
+    pub(crate) height: Option<u32>,
 }
 
-#[derive(Deserialize, Serialize)]
-pub(crate) struct KeysendRequest {
-    pub(crate) dest_pubkey: String,
-    pub(crate) amt_msat: u64,
-    pub(crate) asset_id: Option<String>,
-    pub(crate) asset_amount: Option<u64>,
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) enum CloseClaimKind {
+    AwaitingCloseConfirmation,
+    AwaitingConfirmations,
+    Contentious,
+    HtlcTimeout,
+    HtlcPreimage,
+    RevokedOutput,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct KeysendResponse {
-    pub(crate) payment_hash: String,
-    pub(crate) payment_preimage: String,
-    pub(crate) status: HTLCStatus,
+pub(crate) struct CloseDetailRequest {
+    pub(crate) channel_id: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct ListAssetsRequest {
-    pub(crate) filter_asset_schemas: Vec<AssetSchema>,
+pub(crate) struct CloseDetailResponse {
+    pub(crate) channel_id: String,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) pending_claims: Vec<CloseClaim>,
+    pub(crate) outputs: Vec<CloseOutput>,
+    pub(crate) recovered: bool,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct ListAssetsResponse {
-    pub(crate) nia: Option<Vec<AssetNIA>>,
-    pub(crate) uda: Option<Vec<AssetUDA>>,
-    pub(crate) cfa: Option<Vec<AssetCFA>>,
+pub(crate) struct CloseOutput {
+    pub(crate) outpoint: String,
+    pub(crate) amount_sat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) status: CloseOutputStatus,
+    pub(crate) spendable_height: Option<u32>,
+    pub(crate) confirmation_height: Option<u32>,
+    pub(crate) sweep_txid: Option<String>,
 }
 
-#[derive(Deserialize, Serialize)]
-pub(crate) struct ListChannelsResponse {
-    pub(crate) channels: Vec<Channel>,
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) enum CloseOutputStatus {
+    PendingBroadcast,
+    PendingConfirmation,
+    Confirming,
+    Swept,
 }
 
-#[derive(Deserialize, Serialize)]
-pub(crate) struct ListPaymentsResponse {
-    pub(crate) payments: Vec<Payment>,
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct CoinSelection {
+    pub(crate) strategy: CoinSelectionStrategy,
+    pub(crate) outpoints: Option<Vec<String>>,
+    pub(crate) excluded_outpoints: Option<Vec<String>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum CoinSelectionStrategy {
+    LargestFirst,
+    OldestFirst,
+    Privacy,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct ListPeersResponse {
-    pub(crate) peers: Vec<Peer>,
+pub(crate) struct CompactStorageRequest {
+    pub(crate) payment_retention_sec: Option<u64>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub(crate) struct ListSwapsResponse {
-    pub(crate) maker: Vec<Swap>,
-    pub(crate) taker: Vec<Swap>,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct CompactStorageResponse {
+    pub(crate) pruned_payments: u64,
+    pub(crate) removed_monitors: u64,
+    pub(crate) removed_monitor_updates: u64,
+    pub(crate) deleted_failed_transfers: bool,
+    pub(crate) reclaimed_bytes: u64,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct ListTransactionsRequest {
-    pub(crate) skip_sync: bool,
+pub(crate) struct ConfirmTotpRequest {
+    pub(crate) code: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct ListTransactionsResponse {
-    pub(crate) transactions: Vec<Transaction>,
+pub(crate) struct ConnectPeerRequest {
+    pub(crate) peer_pubkey_and_addr: String,
+    pub(crate) timeout_sec: Option<u64>,
+    pub(crate) retries: Option<u8>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct ListTransfersRequest {
-    pub(crate) asset_id: String,
+pub(crate) struct CreateOrderRequest {
+    pub(crate) items: Vec<OrderItem>,
+    pub(crate) expiry_sec: u32,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct ListTransfersResponse {
-    pub(crate) transfers: Vec<Transfer>,
+pub(crate) struct CreateOrderResponse {
+    pub(crate) order: Order,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct ListUnspentsRequest {
+pub(crate) struct CreateUtxosRequest {
+    pub(crate) up_to: bool,
+    pub(crate) num: Option<u8>,
+    pub(crate) size: Option<u32>,
+    pub(crate) fee_rate: u64,
     pub(crate) skip_sync: bool,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct ListUnspentsResponse {
-    pub(crate) unspents: Vec<Unspent>,
+pub(crate) struct DecodeLNInvoiceRequest {
+    pub(crate) invoice: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct LNInvoiceRequest {
+pub(crate) struct DecodeLNInvoiceResponse {
     pub(crate) amt_msat: Option<u64>,
-    pub(crate) expiry_sec: u32,
+    pub(crate) expiry_sec: u64,
+    pub(crate) timestamp: u64,
     pub(crate) asset_id: Option<String>,
     pub(crate) asset_amount: Option<u64>,
+    pub(crate) payment_hash: String,
+    pub(crate) payment_secret: String,
+    pub(crate) payee_pubkey: Option<String>,
+    pub(crate) network: BitcoinNetwork,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct LNInvoiceResponse {
+pub(crate) struct DecodeRGBInvoiceRequest {
     pub(crate) invoice: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct MakerExecuteRequest {
-    pub(crate) swapstring: String,
-    pub(crate) payment_secret: String,
-    pub(crate) taker_pubkey: String,
+pub(crate) struct DecodeRGBInvoiceResponse {
+    pub(crate) recipient_id: String,
+    pub(crate) recipient_type: RecipientType,
+    pub(crate) asset_schema: Option<AssetSchema>,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) assignment: Assignment,
+    pub(crate) network: BitcoinNetwork,
+    pub(crate) expiration_timestamp: Option<i64>,
+    pub(crate) transport_endpoints: Vec<String>,
 }
 
-// "from" and "to" are seen from the taker's perspective, so:
-// - "from" is what the taker will send and the maker will receive
-// - "to" is what the taker will receive and the maker will send
-// qty_from and qty_to are in msat when the asset is BTC
 #[derive(Deserialize, Serialize)]
-pub(crate) struct MakerInitRequest {
-    pub(crate) qty_from: u64,
-    pub(crate) qty_to: u64,
-    pub(crate) from_asset: Option<String>,
-    pub(crate) to_asset: Option<String>,
-    pub(crate) timeout_sec: u32,
+pub(crate) struct DescriptorsResponse {
+    pub(crate) vanilla: String,
+    pub(crate) colored: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct MakerInitResponse {
-    pub(crate) payment_hash: String,
-    pub(crate) payment_secret: String,
-    pub(crate) swapstring: String,
+pub(crate) struct DisableTotpRequest {
+    pub(crate) code: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct Media {
-    pub(crate) file_path: String,
-    pub(crate) digest: String,
+pub(crate) struct DisconnectPeerRequest {
+    pub(crate) peer_pubkey: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct DryRunInput {
+    pub(crate) outpoint: String,
+    pub(crate) btc_amount: u64,
+    pub(crate) rgb_allocations: Vec<RgbAllocation>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct DryRunResult {
+    pub(crate) fee_sat: Option<u64>,
+    pub(crate) inputs: Vec<DryRunInput>,
+    pub(crate) routes: Vec<QueriedRoute>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct EmbeddedMedia {
     pub(crate) mime: String,
+    pub(crate) data: Vec<u8>,
 }
 
-impl From<RgbLibMedia> for Media {
-    fn from(value: RgbLibMedia) -> Self {
+impl From<RgbLibEmbeddedMedia> for EmbeddedMedia {
+    fn from(value: RgbLibEmbeddedMedia) -> Self {
         Self {
-            file_path: value.file_path,
-            digest: value.digest,
             mime: value.mime,
+            data: value.data,
         }
     }
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct NetworkInfoResponse {
-    pub(crate) network: BitcoinNetwork,
-    pub(crate) height: u32,
+pub(crate) struct EmergencyKitChannel {
+    pub(crate) channel_id: String,
+    pub(crate) peer_pubkey: String,
+    pub(crate) funding_txid: String,
+    pub(crate) funding_vout: u32,
+    pub(crate) capacity_sat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) latest_update_id: u64,
+    pub(crate) commitment_txs: Vec<String>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct NodeInfoResponse {
-    pub(crate) pubkey: String,
-    pub(crate) num_channels: usize,
-    pub(crate) num_usable_channels: usize,
-    pub(crate) local_balance_sat: u64,
-    pub(crate) eventual_close_fees_sat: u64,
-    pub(crate) pending_outbound_payments_sat: u64,
-    pub(crate) num_peers: usize,
+pub(crate) struct EmergencyKitContents {
+    pub(crate) created_at: u64,
+    pub(crate) network: String,
+    pub(crate) node_pubkey: String,
+    pub(crate) ldk_seed_derivation_path: String,
+    pub(crate) master_fingerprint: String,
     pub(crate) account_xpub_vanilla: String,
     pub(crate) account_xpub_colored: String,
-    pub(crate) max_media_upload_size_mb: u16,
-    pub(crate) rgb_htlc_min_msat: u64,
-    pub(crate) rgb_channel_capacity_min_sat: u64,
-    pub(crate) channel_capacity_min_sat: u64,
-    pub(crate) channel_capacity_max_sat: u64,
-    pub(crate) channel_asset_min_amount: u64,
-    pub(crate) channel_asset_max_amount: u64,
-    pub(crate) network_nodes: usize,
-    pub(crate) network_channels: usize,
+    pub(crate) channels: Vec<EmergencyKitChannel>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct OpenChannelRequest {
-    pub(crate) peer_pubkey_and_opt_addr: String,
-    pub(crate) capacity_sat: u64,
-    pub(crate) push_msat: u64,
-    pub(crate) asset_amount: Option<u64>,
-    pub(crate) asset_id: Option<String>,
-    pub(crate) public: bool,
-    pub(crate) with_anchors: bool,
-    pub(crate) fee_base_msat: Option<u32>,
-    pub(crate) fee_proportional_millionths: Option<u32>,
-    pub(crate) temporary_channel_id: Option<String>,
+pub(crate) struct EmergencyKitResponse {
+    pub(crate) kit_hex: String,
+    pub(crate) updated_at: u64,
+    pub(crate) num_channels: usize,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct OpenChannelResponse {
-    pub(crate) temporary_channel_id: String,
+pub(crate) struct EmptyResponse {}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct EnrollTotpRequest {
+    pub(crate) payment_threshold_msat: u64,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub(crate) struct Payment {
+#[derive(Deserialize, Serialize)]
+pub(crate) struct EnrollTotpResponse {
+    pub(crate) secret: String,
+    pub(crate) uri: String,
+    pub(crate) recovery_codes: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct Escrow {
+    pub(crate) escrow_id: String,
+    pub(crate) payment_hash: String,
+    pub(crate) arbiter_pubkey: String,
+    pub(crate) invoice: String,
+    pub(crate) description: Option<String>,
     pub(crate) amt_msat: Option<u64>,
-    pub(crate) asset_amount: Option<u64>,
     pub(crate) asset_id: Option<String>,
-    pub(crate) payment_hash: String,
-    pub(crate) inbound: bool,
-    pub(crate) status: HTLCStatus,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) status: EscrowStatus,
     pub(crate) created_at: u64,
-    pub(crate) updated_at: u64,
-    pub(crate) payee_pubkey: String,
+    pub(crate) expires_at: u64,
+    pub(crate) resolved_at: Option<u64>,
+    pub(crate) settle_message: String,
+    pub(crate) refund_message: String,
 }
 
-#[derive(Clone, Deserialize, Serialize)]
-pub(crate) struct Peer {
-    pub(crate) pubkey: String,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct EscrowCreateRequest {
+    pub(crate) payment_hash: String,
+    pub(crate) arbiter_pubkey: String,
+    pub(crate) description: Option<String>,
+    pub(crate) amt_msat: Option<u64>,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) expiry_sec: u32,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct PostAssetMediaResponse {
-    pub(crate) digest: String,
+pub(crate) struct EscrowCreateResponse {
+    pub(crate) escrow: Escrow,
 }
 
-#[derive(Deserialize, Serialize)]
-pub(crate) struct ProofOfReserves {
-    pub(crate) utxo: String,
-    pub(crate) proof: Vec<u8>,
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum EscrowDecision {
+    Settle,
+    Refund,
 }
 
-impl From<RgbLibProofOfReserves> for ProofOfReserves {
-    fn from(value: RgbLibProofOfReserves) -> Self {
-        Self {
-            utxo: value.utxo.to_string(),
-            proof: value.proof,
-        }
-    }
+impl_writeable_tlv_based_enum!(EscrowDecision,
+    (0, Settle) => {},
+    (1, Refund) => {},
+);
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct EscrowResolveRequest {
+    pub(crate) escrow_id: String,
+    pub(crate) decision: EscrowDecision,
+    pub(crate) payment_preimage: Option<String>,
+    pub(crate) signature: String,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
-pub(crate) enum RecipientType {
-    Blind,
-    Witness,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct EscrowResolveResponse {
+    pub(crate) escrow: Escrow,
 }
 
-impl From<RgbLibRecipientType> for RecipientType {
-    fn from(value: RgbLibRecipientType) -> Self {
-        match value {
-            RgbLibRecipientType::Blind => Self::Blind,
-            RgbLibRecipientType::Witness => Self::Witness,
-        }
-    }
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum EscrowStatus {
+    AwaitingPayment,
+    Funded,
+    Settled,
+    Refunded,
+    Expired,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct RefreshRequest {
-    pub(crate) skip_sync: bool,
+pub(crate) struct EstimateFeeRequest {
+    pub(crate) blocks: u16,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct RestoreRequest {
-    pub(crate) backup_path: String,
-    pub(crate) password: String,
+pub(crate) struct EstimateFeeResponse {
+    pub(crate) fee_rate: f64,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub(crate) struct RevokeTokenRequest {
-    pub(crate) token: String,
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct ExportAccountingRequest {
+    pub(crate) start_time: Option<u64>,
+    pub(crate) end_time: Option<u64>,
+    pub(crate) format: Option<ExportFormat>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct RgbAllocation {
-    pub(crate) asset_id: Option<String>,
-    pub(crate) assignment: Assignment,
-    pub(crate) settled: bool,
+pub(crate) struct ExportAccountingResponse {
+    pub(crate) entries: Vec<AccountingEntry>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct RgbInvoiceRequest {
-    pub(crate) asset_id: Option<String>,
-    pub(crate) assignment: Option<Assignment>,
-    pub(crate) duration_seconds: Option<u32>,
-    pub(crate) min_confirmations: u8,
-    pub(crate) witness: bool,
+pub(crate) struct ExportBackupRequest {
+    pub(crate) password: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct RgbInvoiceResponse {
-    pub(crate) recipient_id: String,
-    pub(crate) invoice: String,
-    pub(crate) expiration_timestamp: Option<i64>,
-    pub(crate) batch_transfer_idx: i32,
+pub(crate) struct ExportConsignmentRequest {
+    pub(crate) asset_id: String,
+    pub(crate) txid: String,
 }
 
-#[derive(Deserialize, Serialize)]
-pub(crate) struct SendAssetRequest {
-    pub(crate) asset_id: String,
-    pub(crate) assignment: Assignment,
-    pub(crate) recipient_id: String,
-    pub(crate) witness_data: Option<WitnessData>,
-    pub(crate) donation: bool,
-    pub(crate) fee_rate: u64,
-    pub(crate) min_confirmations: u8,
-    pub(crate) transport_endpoints: Vec<String>,
-    pub(crate) skip_sync: bool,
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub(crate) enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
 }
 
-#[derive(Deserialize, Serialize)]
-pub(crate) struct SendAssetResponse {
-    pub(crate) txid: String,
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct ExternalFunding {
+    pub(crate) timeout_sec: Option<u64>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct SendBtcRequest {
-    pub(crate) amount: u64,
-    pub(crate) address: String,
-    pub(crate) fee_rate: u64,
+pub(crate) struct FailTransferRequest {
+    pub(crate) idx: i32,
     pub(crate) skip_sync: bool,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct SendBtcResponse {
-    pub(crate) txid: String,
+pub(crate) struct FailTransfersRequest {
+    pub(crate) batch_transfer_idx: Option<i32>,
+    pub(crate) no_asset_only: bool,
+    pub(crate) skip_sync: bool,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct SendOnionMessageRequest {
-    pub(crate) node_ids: Vec<String>,
-    pub(crate) tlv_type: u64,
-    pub(crate) data: String,
+pub(crate) struct FailTransfersResponse {
+    pub(crate) transfers_changed: bool,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) enum FeeBumpMethod {
+    Cpfp,
+    Rbf,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct SendPaymentRequest {
-    pub(crate) invoice: String,
-    pub(crate) amt_msat: Option<u64>,
+pub(crate) struct FeeReportResponse {
+    pub(crate) channels: Vec<ChannelFeeReport>,
+    pub(crate) day_fee_msat: u64,
+    pub(crate) week_fee_msat: u64,
+    pub(crate) month_fee_msat: u64,
+    pub(crate) total_fee_msat: u64,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct SendPaymentResponse {
-    pub(crate) payment_id: String,
+pub(crate) struct Forward {
+    pub(crate) timestamp: u64,
+    pub(crate) prev_channel_id: String,
+    pub(crate) next_channel_id: Option<String>,
     pub(crate) payment_hash: Option<String>,
-    pub(crate) payment_secret: Option<String>,
-    pub(crate) status: HTLCStatus,
+    pub(crate) amt_in_msat: Option<u64>,
+    pub(crate) amt_out_msat: Option<u64>,
+    pub(crate) fee_earned_msat: Option<u64>,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount_in: Option<u64>,
+    pub(crate) asset_amount_out: Option<u64>,
+    pub(crate) resolution: ForwardResolution,
+    pub(crate) failure_reason: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum ForwardResolution {
+    Settled,
+    Failed,
+}
+
+impl_writeable_tlv_based_enum!(ForwardResolution,
+    (0, Settled) => {},
+    (1, Failed) => {},
+);
+
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct ForwardingHistoryRequest {
+    pub(crate) start_time: Option<u64>,
+    pub(crate) end_time: Option<u64>,
+    pub(crate) limit: Option<usize>,
+    pub(crate) offset: Option<usize>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct SignMessageRequest {
-    pub(crate) message: String,
+pub(crate) struct ForwardingHistoryResponse {
+    pub(crate) forwards: Vec<Forward>,
+    pub(crate) total: usize,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct SignMessageResponse {
-    pub(crate) signed_message: String,
+pub(crate) struct FundChannelAbortRequest {
+    pub(crate) temporary_channel_id: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
-pub(crate) struct Swap {
-    pub(crate) qty_from: u64,
-    pub(crate) qty_to: u64,
-    pub(crate) from_asset: Option<String>,
-    pub(crate) to_asset: Option<String>,
-    pub(crate) payment_hash: String,
-    pub(crate) status: SwapStatus,
-    pub(crate) requested_at: u64,
-    pub(crate) initiated_at: Option<u64>,
-    pub(crate) expires_at: u64,
-    pub(crate) completed_at: Option<u64>,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct FundChannelCompleteRequest {
+    pub(crate) temporary_channel_id: String,
+    pub(crate) signed_psbt: String,
+    pub(crate) totp_code: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
-pub(crate) enum SwapStatus {
-    Waiting,
-    Pending,
-    Succeeded,
-    Expired,
-    Failed,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct FundChannelCompleteResponse {
+    pub(crate) channel_id: String,
+    pub(crate) funding_txid: String,
 }
 
-impl_writeable_tlv_based_enum!(SwapStatus,
-    (0, Waiting) => {},
-    (1, Pending) => {},
-    (2, Succeeded) => {},
-    (3, Expired) => {},
-    (4, Failed) => {},
-);
-
 #[derive(Deserialize, Serialize)]
-pub(crate) struct TakerRequest {
-    pub(crate) swapstring: String,
+pub(crate) struct FundPsbtRequest {
+    pub(crate) recipients: Vec<BtcRecipient>,
+    pub(crate) opret_commitment: bool,
+    pub(crate) fee_rate: u64,
+    pub(crate) coin_selection: Option<CoinSelection>,
+    pub(crate) skip_sync: bool,
+    pub(crate) totp_code: Option<String>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct Token {
-    pub(crate) index: u32,
-    pub(crate) ticker: Option<String>,
-    pub(crate) name: Option<String>,
-    pub(crate) details: Option<String>,
-    pub(crate) embedded_media: Option<EmbeddedMedia>,
-    pub(crate) media: Option<Media>,
-    pub(crate) attachments: HashMap<u8, Media>,
-    pub(crate) reserves: Option<ProofOfReserves>,
+pub(crate) struct FundPsbtResponse {
+    pub(crate) psbt: String,
 }
 
-impl From<RgbLibToken> for Token {
-    fn from(value: RgbLibToken) -> Self {
-        Self {
-            index: value.index,
-            ticker: value.ticker,
-            name: value.name,
-            details: value.details,
-            embedded_media: value.embedded_media.map(|em| em.into()),
-            media: value.media.map(|m| m.into()),
-            attachments: value
-                .attachments
-                .into_iter()
-                .map(|(k, v)| (k, v.into()))
-                .collect(),
-            reserves: value.reserves.map(|r| r.into()),
-        }
-    }
+#[derive(Deserialize, Serialize)]
+pub(crate) struct GetAssetMediaRequest {
+    pub(crate) digest: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct TokenLight {
-    pub(crate) index: u32,
-    pub(crate) ticker: Option<String>,
-    pub(crate) name: Option<String>,
-    pub(crate) details: Option<String>,
-    pub(crate) embedded_media: bool,
-    pub(crate) media: Option<Media>,
-    pub(crate) attachments: HashMap<u8, Media>,
-    pub(crate) reserves: bool,
+pub(crate) struct GetAssetMediaResponse {
+    pub(crate) bytes_hex: String,
 }
 
-impl From<RgbLibTokenLight> for TokenLight {
-    fn from(value: RgbLibTokenLight) -> Self {
-        Self {
-            index: value.index,
-            ticker: value.ticker,
-            name: value.name,
-            details: value.details,
-            embedded_media: value.embedded_media,
-            media: value.media.map(|m| m.into()),
-            attachments: value
-                .attachments
-                .into_iter()
-                .map(|(k, v)| (k, v.into()))
-                .collect(),
-            reserves: value.reserves,
-        }
-    }
+#[derive(Deserialize, Serialize)]
+pub(crate) struct GetChanInfoRequest {
+    pub(crate) chan_id: u64,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub(crate) struct Transaction {
-    pub(crate) transaction_type: TransactionType,
-    pub(crate) txid: String,
-    pub(crate) received: u64,
-    pub(crate) sent: u64,
-    pub(crate) fee: u64,
-    pub(crate) confirmation_time: Option<BlockTime>,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct GetChanInfoResponse {
+    pub(crate) chan_id: u64,
+    pub(crate) node1_pubkey: String,
+    pub(crate) node2_pubkey: String,
+    pub(crate) capacity_sat: Option<u64>,
+    pub(crate) node1_policy: Option<GraphChannelPolicy>,
+    pub(crate) node2_policy: Option<GraphChannelPolicy>,
 }
 
-#[derive(Debug, PartialEq, Deserialize, Serialize)]
-pub(crate) enum TransactionType {
-    RgbSend,
-    Drain,
-    CreateUtxos,
-    User,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct GetChannelIdRequest {
+    pub(crate) temporary_channel_id: String,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub(crate) struct Transfer {
-    pub(crate) idx: i32,
-    pub(crate) created_at: i64,
-    pub(crate) updated_at: i64,
-    pub(crate) status: TransferStatus,
-    pub(crate) requested_assignment: Option<Assignment>,
-    pub(crate) assignments: Vec<Assignment>,
-    pub(crate) kind: TransferKind,
-    pub(crate) txid: Option<String>,
-    pub(crate) recipient_id: Option<String>,
-    pub(crate) receive_utxo: Option<String>,
-    pub(crate) change_utxo: Option<String>,
-    pub(crate) expiration: Option<i64>,
-    pub(crate) transport_endpoints: Vec<TransferTransportEndpoint>,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct GetChannelIdResponse {
+    pub(crate) channel_id: String,
 }
 
-#[derive(Debug, PartialEq, Deserialize, Serialize)]
-pub(crate) enum TransferKind {
-    Issuance,
-    ReceiveBlind,
-    ReceiveWitness,
-    Send,
-    Inflation,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct GetNodeInfoRequest {
+    pub(crate) pubkey: String,
 }
 
-#[derive(Debug, PartialEq, Deserialize, Serialize)]
-pub(crate) enum TransferStatus {
-    WaitingCounterparty,
-    WaitingConfirmations,
-    Settled,
-    Failed,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct GetNodeInfoResponse {
+    pub(crate) pubkey: String,
+    pub(crate) alias: Option<String>,
+    pub(crate) color: Option<String>,
+    pub(crate) addresses: Vec<String>,
+    pub(crate) last_update: Option<u32>,
+    pub(crate) num_channels: usize,
+    pub(crate) total_capacity_sat: u64,
+    pub(crate) chan_ids: Vec<u64>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub(crate) struct TransferTransportEndpoint {
-    pub(crate) endpoint: String,
-    pub(crate) transport_type: TransportType,
-    pub(crate) used: bool,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct GetOrderRequest {
+    pub(crate) order_id: String,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub(crate) enum TransportType {
-    JsonRpc,
+#[derive(Deserialize, Serialize)]
+pub(crate) struct GetOrderResponse {
+    pub(crate) order: Order,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct UnlockRequest {
-    pub(crate) password: String,
-    pub(crate) bitcoind_rpc_username: String,
-    pub(crate) bitcoind_rpc_password: String,
-    pub(crate) bitcoind_rpc_host: String,
-    pub(crate) bitcoind_rpc_port: u16,
-    pub(crate) indexer_url: Option<String>,
-    pub(crate) proxy_endpoint: Option<String>,
-    pub(crate) announce_addresses: Vec<String>,
-    pub(crate) announce_alias: Option<String>,
+pub(crate) struct GetPaymentRequest {
+    pub(crate) payment_hash: String,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct Unspent {
-    pub(crate) utxo: Utxo,
-    pub(crate) rgb_allocations: Vec<RgbAllocation>,
+pub(crate) struct GetPaymentResponse {
+    pub(crate) payment: Payment,
+    pub(crate) preimage: Option<String>,
+    pub(crate) resolved_at: Option<u64>,
+    pub(crate) parts: Vec<PaymentPart>,
+    pub(crate) fees: Vec<PaymentFee>,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct Utxo {
-    pub(crate) outpoint: String,
-    pub(crate) btc_amount: u64,
-    pub(crate) colorable: bool,
+pub(crate) struct GetSwapRequest {
+    pub(crate) payment_hash: String,
+    pub(crate) taker: bool,
 }
 
 #[derive(Deserialize, Serialize)]
-pub(crate) struct WitnessData {
-    pub(crate) amount_sat: u64,
-    pub(crate) blinding: Option<u64>,
+pub(crate) struct GetSwapResponse {
+    pub(crate) swap: Swap,
 }
 
-impl From<WitnessData> for RgbLibWitnessData {
-    fn from(value: WitnessData) -> Self {
-        Self {
-            amount_sat: value.amount_sat,
-            blinding: value.blinding,
-        }
-    }
+#[derive(Deserialize, Serialize)]
+pub(crate) struct GraphChannelPolicy {
+    pub(crate) enabled: bool,
+    pub(crate) fee_base_msat: u32,
+    pub(crate) fee_proportional_millionths: u32,
+    pub(crate) cltv_expiry_delta: u16,
+    pub(crate) htlc_minimum_msat: u64,
+    pub(crate) htlc_maximum_msat: u64,
+    pub(crate) last_update: u32,
 }
 
-impl AppState {
-    fn check_changing_state(&self) -> Result<(), APIError> {
-        if *self.get_changing_state() {
-            return Err(APIError::ChangingState);
-        }
-        Ok(())
-    }
-
-    async fn check_locked(
-        &self,
-    ) -> Result<TokioMutexGuard<'_, Option<Arc<UnlockedAppState>>>, APIError> {
-        self.check_changing_state()?;
-        let unlocked_app_state = self.get_unlocked_app_state().await;
-        if unlocked_app_state.is_some() {
-            Err(APIError::UnlockedNode)
-        } else {
-            Ok(unlocked_app_state)
-        }
-    }
-
-    async fn check_unlocked(
-        &self,
-    ) -> Result<TokioMutexGuard<'_, Option<Arc<UnlockedAppState>>>, APIError> {
-        self.check_changing_state()?;
-        let unlocked_app_state = self.get_unlocked_app_state().await;
-        if unlocked_app_state.is_none() {
-            Err(APIError::LockedNode)
-        } else {
-            Ok(unlocked_app_state)
-        }
-    }
-
-    fn update_changing_state(&self, updated: bool) {
-        let mut changing_state = self.get_changing_state();
-        *changing_state = updated;
-    }
-
-    fn update_ldk_background_services(&self, updated: Option<LdkBackgroundServices>) {
-        let mut ldk_background_services = self.get_ldk_background_services();
-        *ldk_background_services = updated;
-    }
+#[derive(Deserialize, Serialize)]
+pub(crate) struct Hedge {
+    pub(crate) payment_hash: String,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) target_asset_id: Option<String>,
+    pub(crate) received_amount: u64,
+    pub(crate) qty_from: Option<u64>,
+    pub(crate) qty_to: Option<u64>,
+    pub(crate) status: HedgeStatus,
+    pub(crate) swap_payment_hash: Option<String>,
+    pub(crate) created_at: u64,
+    pub(crate) updated_at: u64,
+    pub(crate) error: Option<String>,
+}
 
-    async fn update_unlocked_app_state(&self, updated: Option<Arc<UnlockedAppState>>) {
-        let mut unlocked_app_state = self.get_unlocked_app_state().await;
-        *unlocked_app_state = updated;
-    }
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum HedgeStatus {
+    Pending,
+    DryRun,
+    Executing,
+    Succeeded,
+    Skipped,
+    Failed,
 }
 
-pub(crate) async fn address(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<AddressResponse>, APIError> {
-    let guard = state.check_unlocked().await?;
-    let unlocked_state = guard.as_ref().unwrap();
+impl_writeable_tlv_based_enum!(HedgeStatus,
+    (0, Pending) => {},
+    (1, DryRun) => {},
+    (2, Executing) => {},
+    (3, Succeeded) => {},
+    (4, Skipped) => {},
+    (5, Failed) => {},
+);
 
-    let address = unlocked_state.rgb_get_address()?;
+#[derive(Deserialize, Serialize)]
+pub(crate) struct HedgingPoliciesResponse {
+    pub(crate) policies: Vec<HedgingPolicyEntry>,
+}
 
-    Ok(Json(AddressResponse { address }))
+#[derive(Deserialize, Serialize)]
+pub(crate) struct HedgingPolicyEntry {
+    pub(crate) asset_id: String,
+    pub(crate) target_asset_id: Option<String>,
+    pub(crate) min_amount: u64,
+    pub(crate) max_amount: Option<u64>,
+    pub(crate) max_daily_amount: Option<u64>,
+    pub(crate) rate_from: u64,
+    pub(crate) rate_to: u64,
+    pub(crate) maker_url: String,
+    pub(crate) dry_run: bool,
+    pub(crate) updated_at: u64,
 }
 
-pub(crate) async fn asset_balance(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<AssetBalanceRequest>, APIError>,
-) -> Result<Json<AssetBalanceResponse>, APIError> {
-    let guard = state.check_unlocked().await?;
-    let unlocked_state = guard.as_ref().unwrap();
-
-    let contract_id = ContractId::from_str(&payload.asset_id)
-        .map_err(|_| APIError::InvalidAssetID(payload.asset_id))?;
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize, Display)]
+#[display(inner)]
+pub(crate) enum HTLCStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
 
-    let balance = unlocked_state.rgb_get_asset_balance(contract_id)?;
+impl_writeable_tlv_based_enum!(HTLCStatus,
+    (0, Pending) => {},
+    (1, Succeeded) => {},
+    (2, Failed) => {},
+);
 
-    let mut offchain_outbound = 0;
-    let mut offchain_inbound = 0;
-    for chan_info in unlocked_state.channel_manager.list_channels() {
-        let info_file_path = get_rgb_channel_info_path(
-            &chan_info.channel_id.0.as_hex().to_string(),
-            &state.static_state.ldk_data_dir,
-            false,
-        );
-        if !info_file_path.exists() {
-            continue;
-        }
-        let rgb_info = parse_rgb_channel_info(&info_file_path);
-        if rgb_info.contract_id == contract_id {
-            offchain_outbound += rgb_info.local_rgb_amount;
-            offchain_inbound += rgb_info.remote_rgb_amount;
-        }
-    }
+#[derive(Deserialize, Serialize)]
+pub(crate) struct HodlInvoiceRequest {
+    pub(crate) amt_msat: Option<u64>,
+    pub(crate) min_amt_msat: Option<u64>,
+    pub(crate) max_amt_msat: Option<u64>,
+    pub(crate) expiry_sec: u32,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) payment_hash: Option<String>,
+    pub(crate) reveal_preimage: bool,
+    pub(crate) route_hints: Option<Vec<InvoiceRouteHint>>,
+}
 
-    Ok(Json(AssetBalanceResponse {
-        settled: balance.settled,
-        future: balance.future,
-        spendable: balance.spendable,
-        offchain_outbound,
-        offchain_inbound,
-    }))
+#[derive(Deserialize, Serialize)]
+pub(crate) struct HodlInvoiceResponse {
+    pub(crate) invoice: String,
+    pub(crate) payment_hash: String,
+    pub(crate) payment_preimage: Option<String>,
 }
 
-pub(crate) async fn asset_metadata(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<AssetMetadataRequest>, APIError>,
-) -> Result<Json<AssetMetadataResponse>, APIError> {
-    let contract_id = ContractId::from_str(&payload.asset_id)
-        .map_err(|_| APIError::InvalidAssetID(payload.asset_id))?;
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct Htlc {
+    pub(crate) channel_id: String,
+    pub(crate) peer_pubkey: String,
+    pub(crate) direction: HtlcDirection,
+    pub(crate) kind: HtlcKind,
+    pub(crate) payment_hash: String,
+    pub(crate) amt_msat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) cltv_expiry: u32,
+    pub(crate) blocks_to_deadline: u32,
+    pub(crate) at_risk: bool,
+}
 
-    let metadata = state
-        .check_unlocked()
-        .await?
-        .clone()
-        .unwrap()
-        .rgb_get_asset_metadata(contract_id)?;
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum HtlcDirection {
+    Inbound,
+    Outbound,
+}
 
-    Ok(Json(AssetMetadataResponse {
-        asset_schema: metadata.asset_schema.into(),
-        initial_supply: metadata.initial_supply,
-        max_supply: metadata.max_supply,
-        known_circulating_supply: metadata.known_circulating_supply,
-        timestamp: metadata.timestamp,
-        name: metadata.name,
-        precision: metadata.precision,
-        ticker: metadata.ticker,
-        details: metadata.details,
-        token: metadata.token.map(|t| t.into()),
-    }))
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum HtlcKind {
+    Invoice,
+    Payment,
+    Swap,
+    Forward,
 }
 
-pub(crate) async fn backup(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<BackupRequest>, APIError>,
-) -> Result<Json<EmptyResponse>, APIError> {
-    no_cancel(async move {
-        let _guard = state.check_locked().await?;
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum HtlcResolution {
+    Settle,
+    Fail,
+    Resume,
+}
 
-        let _mnemonic =
-            check_password_validity(&payload.password, &state.static_state.storage_dir_path)?;
+#[derive(Deserialize, Serialize)]
+pub(crate) struct HtlcsResponse {
+    pub(crate) htlcs: Vec<Htlc>,
+    pub(crate) current_height: u32,
+    pub(crate) at_risk_amt_msat: u64,
+    pub(crate) risk_threshold_sat: u64,
+    pub(crate) risk_threshold_exceeded: bool,
+}
 
-        do_backup(
-            &state.static_state.storage_dir_path,
-            Path::new(&payload.backup_path),
-            &payload.password,
-        )?;
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) enum IndexerProtocol {
+    Electrum,
+    Esplora,
+}
 
-        Ok(Json(EmptyResponse {}))
-    })
-    .await
+impl From<RgbLibIndexerProtocol> for IndexerProtocol {
+    fn from(x: RgbLibIndexerProtocol) -> Self {
+        match x {
+            RgbLibIndexerProtocol::Electrum => Self::Electrum,
+            RgbLibIndexerProtocol::Esplora => Self::Esplora,
+        }
+    }
 }
 
-pub(crate) async fn btc_balance(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<BtcBalanceRequest>, APIError>,
-) -> Result<Json<BtcBalanceResponse>, APIError> {
-    let guard = state.check_unlocked().await?;
-    let unlocked_state = guard.as_ref().unwrap();
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ImportConsignmentResponse {
+    pub(crate) asset_id: String,
+}
 
-    let btc_balance = unlocked_state.rgb_get_btc_balance(payload.skip_sync)?;
+#[derive(Deserialize, Serialize)]
+pub(crate) struct InitRequest {
+    pub(crate) password: String,
+    pub(crate) network: Option<BitcoinNetwork>,
+    pub(crate) mnemonic: Option<String>,
+    pub(crate) passphrase: Option<String>,
+    pub(crate) account_index: Option<u32>,
+    #[serde(default)]
+    pub(crate) ldk_keys_only: bool,
+}
 
-    let vanilla = BtcBalance {
-        settled: btc_balance.vanilla.settled,
-        future: btc_balance.vanilla.future,
-        spendable: btc_balance.vanilla.spendable,
-    };
+#[derive(Deserialize, Serialize)]
+pub(crate) struct InitResponse {
+    pub(crate) mnemonic: String,
+    pub(crate) admin_token: Option<String>,
+    pub(crate) read_only_token: Option<String>,
+}
 
-    let colored = BtcBalance {
-        settled: btc_balance.colored.settled,
-        future: btc_balance.colored.future,
-        spendable: btc_balance.colored.spendable,
-    };
+#[cfg(feature = "failure-injection")]
+#[derive(Deserialize, Serialize)]
+pub(crate) struct InjectFailureRequest {
+    pub(crate) failure: Option<InjectedFailure>,
+    pub(crate) proxy_delay_ms: Option<u64>,
+}
+
+/// Failure forced on the next payment sent or received by the node
+#[cfg(feature = "failure-injection")]
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum InjectedFailure {
+    /// The next outbound payment finds no route
+    NoRoute,
+    /// The next payment received is failed back with a temporary_node_failure
+    TemporaryNodeFailure,
+    /// The next payment received is failed back with incorrect_or_unknown_payment_details
+    IncorrectPaymentDetails,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum InterceptScopeKind {
+    AsyncRecipient,
+    Forward,
+    Receive,
+}
+
+impl_writeable_tlv_based_enum!(InterceptScopeKind,
+    (0, Forward) => {},
+    (1, Receive) => {},
+    (2, AsyncRecipient) => {},
+);
 
-    Ok(Json(BtcBalanceResponse { vanilla, colored }))
+#[derive(Deserialize, Serialize)]
+pub(crate) struct InterceptScopesRequest {
+    pub(crate) kind: InterceptScopeKind,
+    pub(crate) peer_pubkey: Option<String>,
+    pub(crate) payment_hash: Option<String>,
+    pub(crate) hold_timeout_sec: Option<u64>,
 }
 
-pub(crate) async fn change_password(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<ChangePasswordRequest>, APIError>,
-) -> Result<Json<EmptyResponse>, APIError> {
-    no_cancel(async move {
-        let _guard = state.check_locked().await?;
+#[derive(Deserialize, Serialize)]
+pub(crate) struct InterceptScopesResponse {
+    pub(crate) scope_id: String,
+    pub(crate) intercept_scid: Option<String>,
+}
 
-        check_password_strength(payload.new_password.clone())?;
+#[derive(Deserialize, Serialize)]
+pub(crate) struct InterceptedHtlc {
+    pub(crate) htlc_id: String,
+    pub(crate) kind: InterceptScopeKind,
+    pub(crate) payment_hash: String,
+    pub(crate) amt_msat: u64,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) outbound_amt_msat: Option<u64>,
+    pub(crate) received_at: u64,
+}
 
-        let mnemonic =
-            check_password_validity(&payload.old_password, &state.static_state.storage_dir_path)?;
+#[derive(Deserialize, Serialize)]
+pub(crate) struct InterceptedHtlcsResponse {
+    pub(crate) scope_id: String,
+    pub(crate) htlcs: Vec<InterceptedHtlc>,
+}
 
-        encrypt_and_save_mnemonic(
-            payload.new_password,
-            mnemonic.to_string(),
-            &get_mnemonic_path(&state.static_state.storage_dir_path),
-        )?;
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum InvoiceStatus {
+    Pending,
+    Held,
+    Succeeded,
+    Failed,
+    Expired,
+}
 
-        Ok(Json(EmptyResponse {}))
-    })
-    .await
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct InvoiceResult {
+    pub(crate) payment_hash: String,
+    pub(crate) error: Option<String>,
 }
 
-pub(crate) async fn check_indexer_url(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<CheckIndexerUrlRequest>, APIError>,
-) -> Result<Json<CheckIndexerUrlResponse>, APIError> {
-    let indexer_protocol =
-        rgb_lib_check_indexer_url(&payload.indexer_url, state.static_state.network)?.into();
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct InvoiceLsp {
+    pub(crate) peer_pubkey_and_opt_addr: String,
+    pub(crate) token: Option<String>,
+    pub(crate) max_fee_msat: Option<u64>,
+}
 
-    Ok(Json(CheckIndexerUrlResponse { indexer_protocol }))
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct InvoicePhantom {
+    pub(crate) route_hints: Vec<String>,
 }
 
-pub(crate) async fn check_proxy_endpoint(
-    WithRejection(Json(payload), _): WithRejection<Json<CheckProxyEndpointRequest>, APIError>,
-) -> Result<Json<EmptyResponse>, APIError> {
-    check_rgb_proxy_endpoint(&payload.proxy_endpoint).await?;
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct InvoiceRouteHint {
+    pub(crate) hops: Vec<InvoiceRouteHintHop>,
+}
 
-    Ok(Json(EmptyResponse {}))
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct InvoiceRouteHintHop {
+    pub(crate) src_node_id: String,
+    pub(crate) short_channel_id: u64,
+    pub(crate) fee_base_msat: u32,
+    pub(crate) fee_proportional_millionths: u32,
+    pub(crate) cltv_expiry_delta: u16,
+    pub(crate) htlc_minimum_msat: Option<u64>,
+    pub(crate) htlc_maximum_msat: Option<u64>,
 }
 
-pub(crate) async fn close_channel(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<CloseChannelRequest>, APIError>,
-) -> Result<Json<EmptyResponse>, APIError> {
-    no_cancel(async move {
-        let guard = state.check_unlocked().await?;
-        let unlocked_state = guard.as_ref().unwrap();
+#[derive(Deserialize, Serialize)]
+pub(crate) struct InvoiceQuotaUsage {
+    pub(crate) issued_last_minute: u32,
+    pub(crate) unpaid: usize,
+    pub(crate) issued: u64,
+    pub(crate) rejected: u64,
+}
 
-        let channel_id_vec = hex_str_to_vec(&payload.channel_id);
-        if channel_id_vec.is_none() || channel_id_vec.as_ref().unwrap().len() != 32 {
-            return Err(APIError::InvalidChannelID);
-        }
-        let requested_cid = ChannelId(channel_id_vec.unwrap().try_into().unwrap());
+#[derive(Deserialize, Serialize)]
+pub(crate) struct InvoiceQuotasResponse {
+    pub(crate) max_invoices_per_minute: u32,
+    pub(crate) max_unpaid_invoices: u32,
+    pub(crate) max_token_invoices_per_minute: u32,
+    pub(crate) max_token_unpaid_invoices: u32,
+    pub(crate) node: InvoiceQuotaUsage,
+    pub(crate) tokens: Vec<TokenInvoiceQuotaUsage>,
+}
 
-        let peer_pubkey_vec = match hex_str_to_vec(&payload.peer_pubkey) {
-            Some(peer_pubkey_vec) => peer_pubkey_vec,
-            None => return Err(APIError::InvalidPubkey),
-        };
-        let peer_pubkey = match PublicKey::from_slice(&peer_pubkey_vec) {
-            Ok(peer_pubkey) => peer_pubkey,
-            Err(_) => return Err(APIError::InvalidPubkey),
-        };
+#[derive(Deserialize, Serialize)]
+pub(crate) struct InvoiceStatusRequest {
+    pub(crate) invoice: Option<String>,
+    pub(crate) payment_hash: Option<String>,
+}
 
-        if let Some(chan_details) = unlocked_state
-            .channel_manager
-            .list_channels()
-            .iter()
-            .find(|c| c.channel_id == requested_cid)
-        {
-            match chan_details.channel_shutdown_state {
-                Some(ChannelShutdownState::NotShuttingDown) => {}
-                _ => {
-                    return Err(APIError::CannotCloseChannel(s!(
-                        "Channel is already being closed"
-                    )))
-                }
-            }
-        } else {
-            return Err(APIError::UnknownChannelId);
-        }
+#[derive(Deserialize, Serialize)]
+pub(crate) struct InvoiceStatusResponse {
+    pub(crate) status: InvoiceStatus,
+    pub(crate) amt_msat: Option<u64>,
+    pub(crate) expires_at: Option<u64>,
+    pub(crate) settled_at: Option<u64>,
+    pub(crate) cltv_expiry: Option<u32>,
+    pub(crate) current_height: Option<u32>,
+    pub(crate) blocks_to_cancel: Option<u32>,
+    pub(crate) fallback_txid: Option<String>,
+}
 
-        if payload.force {
-            match unlocked_state
-                .channel_manager
-                .force_close_broadcasting_latest_txn(
-                    &requested_cid,
-                    &peer_pubkey,
-                    "Manually force-closed".to_string(),
-                ) {
-                Ok(()) => tracing::info!("EVENT: initiating channel force-close"),
-                Err(e) => match e {
-                    LDKAPIError::APIMisuseError { err } => {
-                        return Err(APIError::FailedClosingChannel(err))
-                    }
-                    _ => return Err(APIError::CannotCloseChannel(format!("{e:?}"))),
-                },
-            }
-        } else {
-            match unlocked_state
-                .channel_manager
-                .close_channel(&requested_cid, &peer_pubkey)
-            {
-                Ok(()) => tracing::info!("EVENT: initiating channel close"),
-                Err(e) => match e {
-                    LDKAPIError::APIMisuseError { err } => {
-                        return Err(APIError::FailedClosingChannel(err))
-                    }
-                    _ => return Err(APIError::CannotCloseChannel(format!("{e:?}"))),
-                },
-            }
-        }
-
-        Ok(Json(EmptyResponse {}))
-    })
-    .await
+#[derive(Deserialize, Serialize)]
+pub(crate) struct IssueAssetCFARequest {
+    pub(crate) amounts: Vec<u64>,
+    pub(crate) name: String,
+    pub(crate) details: Option<String>,
+    pub(crate) precision: u8,
+    pub(crate) file_digest: Option<String>,
 }
 
-pub(crate) async fn connect_peer(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<ConnectPeerRequest>, APIError>,
-) -> Result<Json<EmptyResponse>, APIError> {
-    no_cancel(async move {
-        let guard = state.check_unlocked().await?;
-        let unlocked_state = guard.as_ref().unwrap();
+#[derive(Deserialize, Serialize)]
+pub(crate) struct IssueAssetCFAResponse {
+    pub(crate) asset: AssetCFA,
+}
 
-        let (peer_pubkey, peer_addr) = parse_peer_info(payload.peer_pubkey_and_addr.to_string())?;
+#[derive(Deserialize, Serialize)]
+pub(crate) struct IssueAssetIFARequest {
+    pub(crate) amounts: Vec<u64>,
+    pub(crate) inflation_amounts: Vec<u64>,
+    pub(crate) ticker: String,
+    pub(crate) name: String,
+    pub(crate) precision: u8,
+}
 
-        if let Some(peer_addr) = peer_addr {
-            connect_peer_if_necessary(peer_pubkey, peer_addr, unlocked_state.peer_manager.clone())
-                .await?;
-            disk::persist_channel_peer(
-                &state.static_state.ldk_data_dir.join(CHANNEL_PEER_DATA),
-                &peer_pubkey,
-                &peer_addr,
-            )?;
-        } else {
-            return Err(APIError::InvalidPeerInfo(s!(
-                "incorrectly formatted peer info. Should be formatted as: `pubkey@host:port`"
-            )));
-        }
+#[derive(Deserialize, Serialize)]
+pub(crate) struct IssueAssetIFAResponse {
+    pub(crate) asset: AssetIFA,
+}
 
-        Ok(Json(EmptyResponse {}))
-    })
-    .await
+#[derive(Deserialize, Serialize)]
+pub(crate) struct IssueAssetNIARequest {
+    pub(crate) amounts: Vec<u64>,
+    pub(crate) ticker: String,
+    pub(crate) name: String,
+    pub(crate) precision: u8,
 }
 
-pub(crate) async fn create_utxos(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<CreateUtxosRequest>, APIError>,
-) -> Result<Json<EmptyResponse>, APIError> {
-    no_cancel(async move {
-        let guard = state.check_unlocked().await?;
-        let unlocked_state = guard.as_ref().unwrap();
+#[derive(Deserialize, Serialize)]
+pub(crate) struct IssueAssetNIAResponse {
+    pub(crate) asset: AssetNIA,
+}
 
-        unlocked_state.rgb_create_utxos(
-            payload.up_to,
-            payload.num.unwrap_or(UTXO_NUM),
-            payload.size.unwrap_or(UTXO_SIZE_SAT),
-            payload.fee_rate,
-            payload.skip_sync,
-        )?;
-        tracing::debug!("UTXO creation complete");
+#[derive(Deserialize, Serialize)]
+pub(crate) struct IssueAssetUDARequest {
+    pub(crate) ticker: String,
+    pub(crate) name: String,
+    pub(crate) details: Option<String>,
+    pub(crate) precision: u8,
+    pub(crate) media_file_digest: Option<String>,
+    pub(crate) attachments_file_digests: Vec<String>,
+}
 
-        Ok(Json(EmptyResponse {}))
-    })
-    .await
+#[derive(Deserialize, Serialize)]
+pub(crate) struct IssueAssetUDAResponse {
+    pub(crate) asset: AssetUDA,
 }
 
-pub(crate) async fn decode_ln_invoice(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<DecodeLNInvoiceRequest>, APIError>,
-) -> Result<Json<DecodeLNInvoiceResponse>, APIError> {
-    let _guard = state.get_unlocked_app_state();
+#[derive(Deserialize, Serialize)]
+pub(crate) struct JournalEntry {
+    pub(crate) seq: u64,
+    pub(crate) timestamp: u64,
+    pub(crate) kind: JournalEventKind,
+    pub(crate) details: String,
+    pub(crate) prev_hash: String,
+    pub(crate) hash: String,
+    pub(crate) signature: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum JournalEventKind {
+    InvoiceCreated,
+    InvoiceCancelled,
+    InvoiceSettled,
+    PaymentSucceeded,
+    PaymentFailed,
+    ChannelOpened,
+    ChannelClosed,
+}
+
+impl_writeable_tlv_based_enum!(JournalEventKind,
+    (0, InvoiceCreated) => {},
+    (1, InvoiceCancelled) => {},
+    (2, InvoiceSettled) => {},
+    (3, PaymentSucceeded) => {},
+    (4, PaymentFailed) => {},
+    (5, ChannelOpened) => {},
+    (6, ChannelClosed) => {},
+);
 
-    let invoice = match Bolt11Invoice::from_str(&payload.invoice) {
-        Err(e) => return Err(APIError::InvalidInvoice(e.to_string())),
-        Ok(v) => v,
-    };
+#[derive(Deserialize, Serialize)]
+pub(crate) struct JournalProofResponse {
+    pub(crate) pubkey: String,
+    pub(crate) entries: Vec<JournalEntry>,
+}
 
-    Ok(Json(DecodeLNInvoiceResponse {
-        amt_msat: invoice.amount_milli_satoshis(),
-        expiry_sec: invoice.expiry_time().as_secs(),
-        timestamp: invoice.duration_since_epoch().as_secs(),
-        asset_id: invoice.rgb_contract_id().map(|c| c.to_string()),
-        asset_amount: invoice.rgb_amount(),
-        payment_hash: hex_str(&invoice.payment_hash().to_byte_array()),
-        payment_secret: hex_str(&invoice.payment_secret().0),
-        payee_pubkey: invoice.payee_pub_key().map(|p| p.to_string()),
-        network: invoice.network().into(),
-    }))
+#[derive(Deserialize, Serialize)]
+pub(crate) struct KeysendRequest {
+    pub(crate) dest_pubkey: String,
+    pub(crate) amt_msat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) totp_code: Option<String>,
 }
 
-pub(crate) async fn decode_rgb_invoice(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<DecodeRGBInvoiceRequest>, APIError>,
-) -> Result<Json<DecodeRGBInvoiceResponse>, APIError> {
-    let _guard = state.get_unlocked_app_state();
+#[derive(Deserialize, Serialize)]
+pub(crate) struct KeysendResponse {
+    pub(crate) payment_hash: String,
+    pub(crate) payment_preimage: String,
+    pub(crate) status: HTLCStatus,
+}
 
-    let invoice_data = RgbLibInvoice::new(payload.invoice)?.invoice_data();
-    let recipient_info = RecipientInfo::new(invoice_data.recipient_id.clone())?;
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LeaseConfigRequest {
+    pub(crate) enabled: bool,
+    pub(crate) min_channel_size_sat: u64,
+    pub(crate) max_channel_size_sat: u64,
+    pub(crate) fee_base_sat: u64,
+    pub(crate) fee_proportional_millionths: u32,
+    pub(crate) lease_duration_blocks: u32,
+    pub(crate) order_expiry_sec: u32,
+}
 
-    Ok(Json(DecodeRGBInvoiceResponse {
-        recipient_id: invoice_data.recipient_id,
-        recipient_type: recipient_info.recipient_type.into(),
-        asset_schema: invoice_data.asset_schema.map(|s| s.into()),
-        asset_id: invoice_data.asset_id,
-        assignment: invoice_data.assignment.into(),
-        network: invoice_data.network.into(),
-        expiration_timestamp: invoice_data.expiration_timestamp,
-        transport_endpoints: invoice_data.transport_endpoints,
-    }))
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LeaseConfigResponse {
+    pub(crate) enabled: bool,
+    pub(crate) min_channel_size_sat: u64,
+    pub(crate) max_channel_size_sat: u64,
+    pub(crate) fee_base_sat: u64,
+    pub(crate) fee_proportional_millionths: u32,
+    pub(crate) lease_duration_blocks: u32,
+    pub(crate) order_expiry_sec: u32,
+}
+
+impl From<LeaseConfig> for LeaseConfigResponse {
+    fn from(config: LeaseConfig) -> Self {
+        Self {
+            enabled: config.enabled,
+            min_channel_size_sat: config.min_channel_size_sat,
+            max_channel_size_sat: config.max_channel_size_sat,
+            fee_base_sat: config.fee_base_sat,
+            fee_proportional_millionths: config.fee_proportional_millionths,
+            lease_duration_blocks: config.lease_duration_blocks,
+            order_expiry_sec: config.order_expiry_sec,
+        }
+    }
 }
 
-pub(crate) async fn disconnect_peer(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<DisconnectPeerRequest>, APIError>,
-) -> Result<Json<EmptyResponse>, APIError> {
-    no_cancel(async move {
-        let guard = state.check_unlocked().await?;
-        let unlocked_state = guard.as_ref().unwrap();
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LeaseOrder {
+    pub(crate) order_id: String,
+    pub(crate) client_pubkey: String,
+    pub(crate) capacity_sat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) fee_sat: u64,
+    pub(crate) lease_duration_blocks: u32,
+    pub(crate) payment_hash: String,
+    pub(crate) status: LeaseOrderStatus,
+    pub(crate) temporary_channel_id: Option<String>,
+    pub(crate) expires_at: u64,
+    pub(crate) created_at: u64,
+    pub(crate) updated_at: u64,
+}
 
-        let peer_pubkey = match PublicKey::from_str(&payload.peer_pubkey) {
-            Ok(pubkey) => pubkey,
-            Err(_e) => return Err(APIError::InvalidPubkey),
-        };
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum LeaseOrderStatus {
+    AwaitingPayment,
+    Paid,
+    ChannelOpened,
+    Expired,
+    Failed,
+}
 
-        //check for open channels with peer
-        for channel in unlocked_state.channel_manager.list_channels() {
-            if channel.counterparty.node_id == peer_pubkey {
-                return Err(APIError::FailedPeerDisconnection(s!(
-                    "node has an active channel with this peer, close any channels first"
-                )));
-            }
-        }
+impl_writeable_tlv_based_enum!(LeaseOrderStatus,
+    (0, AwaitingPayment) => {},
+    (1, Paid) => {},
+    (2, ChannelOpened) => {},
+    (3, Expired) => {},
+    (4, Failed) => {},
+);
 
-        disk::delete_channel_peer(
-            &state.static_state.ldk_data_dir.join(CHANNEL_PEER_DATA),
-            payload.peer_pubkey,
-        )?;
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LeaseOrdersResponse {
+    pub(crate) orders: Vec<LeaseOrder>,
+}
 
-        //check the pubkey matches a valid connected peer
-        if unlocked_state
-            .peer_manager
-            .peer_by_node_id(&peer_pubkey)
-            .is_none()
-        {
-            return Err(APIError::FailedPeerDisconnection(format!(
-                "Could not find peer {peer_pubkey}"
-            )));
-        }
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LeaseRatesRequest {
+    pub(crate) peer_pubkey_and_opt_addr: String,
+}
 
-        unlocked_state
-            .peer_manager
-            .disconnect_by_node_id(peer_pubkey);
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LeaseRatesResponse {
+    pub(crate) min_channel_size_sat: u64,
+    pub(crate) max_channel_size_sat: u64,
+    pub(crate) fee_base_sat: u64,
+    pub(crate) fee_proportional_millionths: u32,
+    pub(crate) lease_duration_blocks: u32,
+}
 
-        Ok(Json(EmptyResponse {}))
-    })
-    .await
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LightningAddress {
+    pub(crate) name: String,
+    pub(crate) description: String,
+    pub(crate) min_sendable_msat: u64,
+    pub(crate) max_sendable_msat: u64,
+    pub(crate) asset_id: Option<String>,
 }
 
-pub(crate) async fn estimate_fee(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<EstimateFeeRequest>, APIError>,
-) -> Result<Json<EstimateFeeResponse>, APIError> {
-    let fee_rate = state
-        .check_unlocked()
-        .await?
-        .clone()
-        .unwrap()
-        .rgb_get_fee_estimation(payload.blocks)?;
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct ListAssetsRequest {
+    pub(crate) filter_asset_schemas: Vec<AssetSchema>,
+    pub(crate) limit: Option<usize>,
+    pub(crate) cursor: Option<String>,
+}
 
-    Ok(Json(EstimateFeeResponse { fee_rate }))
+/// Assets returned by the /v1 API, grouped by schema, the ones of the schemas not requested being
+/// null
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListAssetsResponse {
+    pub(crate) nia: Option<Vec<AssetNIA>>,
+    pub(crate) uda: Option<Vec<AssetUDA>>,
+    pub(crate) cfa: Option<Vec<AssetCFA>>,
+    pub(crate) ifa: Option<Vec<AssetIFA>>,
 }
 
-pub(crate) async fn fail_transfers(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<FailTransfersRequest>, APIError>,
-) -> Result<Json<FailTransfersResponse>, APIError> {
-    no_cancel(async move {
-        let guard = state.check_unlocked().await?;
-        let unlocked_state = guard.as_ref().unwrap();
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListChannelsResponse {
+    pub(crate) channels: Vec<Channel>,
+}
 
-        let unlocked_state_copy = unlocked_state.clone();
-        let transfers_changed = tokio::task::spawn_blocking(move || {
-            unlocked_state_copy.rgb_fail_transfers(
-                payload.batch_transfer_idx,
-                payload.no_asset_only,
-                payload.skip_sync,
-            )
-        })
-        .await
-        .unwrap()?;
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListEscrowsResponse {
+    pub(crate) escrows: Vec<Escrow>,
+}
 
-        Ok(Json(FailTransfersResponse { transfers_changed }))
-    })
-    .await
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListHedgesResponse {
+    pub(crate) hedges: Vec<Hedge>,
 }
 
-pub(crate) async fn get_asset_media(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<GetAssetMediaRequest>, APIError>,
-) -> Result<Json<GetAssetMediaResponse>, APIError> {
-    let file_path = state
-        .check_unlocked()
-        .await?
-        .clone()
-        .unwrap()
-        .rgb_get_media_dir()
-        .join(payload.digest.to_lowercase());
-    if !file_path.exists() {
-        return Err(APIError::InvalidMediaDigest);
-    }
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListOffersResponse {
+    pub(crate) offers: Vec<Bolt12Offer>,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct ListPaymentsRequest {
+    pub(crate) limit: Option<usize>,
+    pub(crate) cursor: Option<String>,
+    /// Number of matching payments to skip, only accepted by the /v1 API
+    pub(crate) offset: Option<usize>,
+    pub(crate) inbound: Option<bool>,
+    pub(crate) status: Option<HTLCStatus>,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) created_from: Option<u64>,
+    pub(crate) created_to: Option<u64>,
+    pub(crate) payment_hash_prefix: Option<String>,
+}
 
-    let mut buf_reader = BufReader::new(File::open(file_path).await?);
-    let mut file_bytes = Vec::new();
-    buf_reader.read_to_end(&mut file_bytes).await?;
-    let bytes_hex = hex_str(&file_bytes);
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListPaymentsResponse {
+    pub(crate) payments: Vec<Payment>,
+    pub(crate) total: usize,
+}
 
-    Ok(Json(GetAssetMediaResponse { bytes_hex }))
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListPeersResponse {
+    pub(crate) peers: Vec<Peer>,
 }
 
-pub(crate) async fn get_channel_id(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<GetChannelIdRequest>, APIError>,
-) -> Result<Json<GetChannelIdResponse>, APIError> {
-    let tmp_chan_id = check_channel_id(&payload.temporary_channel_id)?;
-    let channel_ids = state.check_unlocked().await?.clone().unwrap().channel_ids();
-    let channel_id = if let Some(channel_id) = channel_ids.get(&tmp_chan_id) {
-        channel_id.0.as_hex().to_string()
-    } else {
-        return Err(APIError::UnknownTemporaryChannelId);
-    };
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListSubmarineSwapsResponse {
+    pub(crate) swaps: Vec<SubmarineSwap>,
+}
 
-    Ok(Json(GetChannelIdResponse { channel_id }))
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct ListSwapsResponse {
+    pub(crate) maker: Vec<Swap>,
+    pub(crate) taker: Vec<Swap>,
 }
 
-pub(crate) async fn init(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<InitRequest>, APIError>,
-) -> Result<Json<InitResponse>, APIError> {
-    no_cancel(async move {
-        let _unlocked_state = state.check_locked().await?;
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListSweepsResponse {
+    pub(crate) sweeps: Vec<Sweep>,
+}
 
-        check_password_strength(payload.password.clone())?;
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListTransactionsRequest {
+    pub(crate) skip_sync: bool,
+}
 
-        let mnemonic_path = get_mnemonic_path(&state.static_state.storage_dir_path);
-        check_already_initialized(&mnemonic_path)?;
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListTransactionsResponse {
+    pub(crate) transactions: Vec<Transaction>,
+}
 
-        let keys = generate_keys(state.static_state.network);
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct ListTransfersRequest {
+    pub(crate) asset_id: String,
+    pub(crate) limit: Option<usize>,
+    pub(crate) cursor: Option<String>,
+    /// Number of matching transfers to skip, only accepted by the /v1 API
+    pub(crate) offset: Option<usize>,
+    pub(crate) inbound: Option<bool>,
+    pub(crate) status: Option<TransferStatus>,
+    pub(crate) created_from: Option<i64>,
+    pub(crate) created_to: Option<i64>,
+}
 
-        let mnemonic = keys.mnemonic;
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListTransfersResponse {
+    pub(crate) transfers: Vec<Transfer>,
+    pub(crate) total: usize,
+}
 
-        encrypt_and_save_mnemonic(payload.password, mnemonic.clone(), &mnemonic_path)?;
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListUnspentsRequest {
+    pub(crate) skip_sync: bool,
+}
 
-        Ok(Json(InitResponse { mnemonic }))
-    })
-    .await
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ListUnspentsResponse {
+    pub(crate) unspents: Vec<Unspent>,
 }
 
-pub(crate) async fn invoice_status(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<InvoiceStatusRequest>, APIError>,
-) -> Result<Json<InvoiceStatusResponse>, APIError> {
-    let guard = state.check_unlocked().await?;
-    let unlocked_state = guard.as_ref().unwrap();
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct LNInvoiceRequest {
+    pub(crate) amt_msat: Option<u64>,
+    pub(crate) min_amt_msat: Option<u64>,
+    pub(crate) max_amt_msat: Option<u64>,
+    pub(crate) expiry_sec: u32,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) route_hints: Option<Vec<InvoiceRouteHint>>,
+    pub(crate) lsp: Option<InvoiceLsp>,
+    pub(crate) phantom: Option<InvoicePhantom>,
+    pub(crate) label: Option<String>,
+    #[serde(default)]
+    pub(crate) settle_after_expiry: bool,
+    pub(crate) fallback_address_type: Option<AddressType>,
+}
 
-    let invoice = match Bolt11Invoice::from_str(&payload.invoice) {
-        Err(e) => return Err(APIError::InvalidInvoice(e.to_string())),
-        Ok(v) => v,
-    };
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LNInvoiceResponse {
+    pub(crate) invoice: String,
+}
 
-    let payment_hash = PaymentHash(invoice.payment_hash().to_byte_array());
-    let status = match unlocked_state.inbound_payments().get(&payment_hash) {
-        Some(v) => match v.status {
-            HTLCStatus::Pending if invoice.is_expired() => InvoiceStatus::Expired,
-            HTLCStatus::Pending => InvoiceStatus::Pending,
-            HTLCStatus::Succeeded => InvoiceStatus::Succeeded,
-            HTLCStatus::Failed => InvoiceStatus::Failed,
-        },
-        None => return Err(APIError::UnknownLNInvoice),
-    };
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LnurlErrorResponse {
+    pub(crate) status: String,
+    pub(crate) reason: String,
+}
 
-    Ok(Json(InvoiceStatusResponse { status }))
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LnurlPayCallbackRequest {
+    pub(crate) amount: u64,
+    pub(crate) asset_amount: Option<u64>,
 }
 
-pub(crate) async fn issue_asset_cfa(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<IssueAssetCFARequest>, APIError>,
-) -> Result<Json<IssueAssetCFAResponse>, APIError> {
-    no_cancel(async move {
-        let guard = state.check_unlocked().await?;
-        let unlocked_state = guard.as_ref().unwrap();
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LnurlPayCallbackResponse {
+    pub(crate) pr: String,
+    pub(crate) routes: Vec<String>,
+}
 
-        if *unlocked_state.rgb_send_lock.lock().unwrap() {
-            return Err(APIError::OpenChannelInProgress);
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct LnurlPayMetadataResponse {
+    pub(crate) callback: String,
+    pub(crate) max_sendable: u64,
+    pub(crate) min_sendable: u64,
+    pub(crate) metadata: String,
+    pub(crate) tag: String,
+    pub(crate) asset_id: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LnurlPayRequest {
+    pub(crate) domain: String,
+    pub(crate) addresses: Vec<LightningAddress>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LnurlPayResponse {
+    pub(crate) domain: Option<String>,
+    pub(crate) addresses: Vec<LightningAddress>,
+}
+
+impl From<LnurlPayConfig> for LnurlPayResponse {
+    fn from(config: LnurlPayConfig) -> Self {
+        Self {
+            domain: config.domain,
+            addresses: config
+                .addresses
+                .into_iter()
+                .map(|a| LightningAddress {
+                    name: a.name,
+                    description: a.description,
+                    min_sendable_msat: a.min_sendable_msat,
+                    max_sendable_msat: a.max_sendable_msat,
+                    asset_id: a.asset_id,
+                })
+                .collect(),
         }
+    }
+}
 
-        let file_path = payload.file_digest.map(|d: String| {
-            unlocked_state
-                .rgb_get_media_dir()
-                .join(d.to_lowercase())
-                .to_string_lossy()
-                .to_string()
-        });
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LockUtxoRequest {
+    pub(crate) outpoint: String,
+    pub(crate) label: Option<String>,
+}
 
-        let asset = unlocked_state.rgb_issue_asset_cfa(
-            payload.name,
-            payload.details,
-            payload.precision,
-            payload.amounts,
-            file_path,
-        )?;
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LogLevelRequest {
+    pub(crate) log_level: String,
+}
 
-        Ok(Json(IssueAssetCFAResponse {
-            asset: asset.into(),
-        }))
-    })
-    .await
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LogLevelResponse {
+    pub(crate) log_level: String,
 }
 
-pub(crate) async fn issue_asset_nia(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<IssueAssetNIARequest>, APIError>,
-) -> Result<Json<IssueAssetNIAResponse>, APIError> {
-    no_cancel(async move {
-        let guard = state.check_unlocked().await?;
-        let unlocked_state = guard.as_ref().unwrap();
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LspClient {
+    pub(crate) jit_channel_scid: String,
+    pub(crate) client_pubkey: String,
+    pub(crate) payment_size_msat: u64,
+    pub(crate) opening_fee_msat: u64,
+    pub(crate) status: LspClientStatus,
+    pub(crate) channel_id: Option<String>,
+    pub(crate) created_at: u64,
+    pub(crate) updated_at: u64,
+}
 
-        if *unlocked_state.rgb_send_lock.lock().unwrap() {
-            return Err(APIError::OpenChannelInProgress);
-        }
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum LspClientStatus {
+    Waiting,
+    Opening,
+    Forwarded,
+    Failed,
+}
 
-        let asset = unlocked_state.rgb_issue_asset_nia(
-            payload.ticker,
-            payload.name,
-            payload.precision,
-            payload.amounts,
-        )?;
+impl_writeable_tlv_based_enum!(LspClientStatus,
+    (0, Waiting) => {},
+    (1, Opening) => {},
+    (2, Forwarded) => {},
+    (3, Failed) => {},
+);
 
-        Ok(Json(IssueAssetNIAResponse {
-            asset: asset.into(),
-        }))
-    })
-    .await
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LspClientsResponse {
+    pub(crate) clients: Vec<LspClient>,
 }
 
-pub(crate) async fn issue_asset_uda(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<IssueAssetUDARequest>, APIError>,
-) -> Result<Json<IssueAssetUDAResponse>, APIError> {
-    no_cancel(async move {
-        let guard = state.check_unlocked().await?;
-        let unlocked_state = guard.as_ref().unwrap();
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LspConfigRequest {
+    pub(crate) enabled: bool,
+    pub(crate) min_fee_msat: u64,
+    pub(crate) fee_proportional_millionths: u32,
+    pub(crate) min_payment_size_msat: u64,
+    pub(crate) max_payment_size_msat: u64,
+    pub(crate) min_channel_size_sat: u64,
+    pub(crate) fee_params_validity_sec: u32,
+}
 
-        if *unlocked_state.rgb_send_lock.lock().unwrap() {
-            return Err(APIError::OpenChannelInProgress);
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LspConfigResponse {
+    pub(crate) enabled: bool,
+    pub(crate) min_fee_msat: u64,
+    pub(crate) fee_proportional_millionths: u32,
+    pub(crate) min_payment_size_msat: u64,
+    pub(crate) max_payment_size_msat: u64,
+    pub(crate) min_channel_size_sat: u64,
+    pub(crate) fee_params_validity_sec: u32,
+}
+
+impl From<LspConfig> for LspConfigResponse {
+    fn from(config: LspConfig) -> Self {
+        Self {
+            enabled: config.enabled,
+            min_fee_msat: config.min_fee_msat,
+            fee_proportional_millionths: config.fee_proportional_millionths,
+            min_payment_size_msat: config.min_payment_size_msat,
+            max_payment_size_msat: config.max_payment_size_msat,
+            min_channel_size_sat: config.min_channel_size_sat,
+            fee_params_validity_sec: config.fee_params_validity_sec,
         }
+    }
+}
 
-        let rgb_media_dir = unlocked_state.rgb_get_media_dir();
-        let get_string_path = |d: String| {
-            rgb_media_dir
-                .join(d.to_lowercase())
-                .to_string_lossy()
-                .to_string()
-        };
-        let media_file_path = payload.media_file_digest.map(get_string_path);
-        let attachments_file_paths = payload
-            .attachments_file_digests
-            .into_iter()
-            .map(get_string_path)
-            .collect();
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LspFeeMenuRequest {
+    pub(crate) peer_pubkey_and_opt_addr: String,
+    pub(crate) token: Option<String>,
+}
 
-        let asset = unlocked_state.rgb_issue_asset_uda(
-            payload.ticker,
-            payload.name,
-            payload.details,
-            payload.precision,
-            media_file_path,
-            attachments_file_paths,
-        )?;
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LspFeeMenuResponse {
+    pub(crate) fee_menu: Vec<LspFeeParams>,
+}
 
-        Ok(Json(IssueAssetUDAResponse {
-            asset: asset.into(),
-        }))
-    })
-    .await
+#[derive(Deserialize, Serialize)]
+pub(crate) struct LspFeeParams {
+    pub(crate) min_fee_msat: u64,
+    pub(crate) proportional: u32,
+    pub(crate) valid_until: String,
+    pub(crate) min_lifetime: u32,
+    pub(crate) max_client_to_self_delay: u32,
+    pub(crate) min_payment_size_msat: u64,
+    pub(crate) max_payment_size_msat: u64,
 }
 
-pub(crate) async fn keysend(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<KeysendRequest>, APIError>,
-) -> Result<Json<KeysendResponse>, APIError> {
+#[derive(Deserialize, Serialize)]
+pub(crate) struct MakerExecuteRequest {
+    pub(crate) swapstring: String,
+    pub(crate) payment_secret: String,
+    pub(crate) taker_pubkey: String,
+    pub(crate) totp_code: Option<String>,
+}
+
+// "from" and "to" are seen from the taker's perspective, so:
+// - "from" is what the taker will send and the maker will receive
+// - "to" is what the taker will receive and the maker will send
+// qty_from and qty_to are in msat when the asset is BTC
+#[derive(Deserialize, Serialize)]
+pub(crate) struct MakerInitRequest {
+    pub(crate) qty_from: u64,
+    pub(crate) qty_to: u64,
+    pub(crate) from_asset: Option<String>,
+    pub(crate) to_asset: Option<String>,
+    pub(crate) timeout_sec: u32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct MakerInitResponse {
+    pub(crate) payment_hash: String,
+    pub(crate) payment_secret: String,
+    pub(crate) swapstring: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct Media {
+    pub(crate) file_path: String,
+    pub(crate) digest: String,
+    pub(crate) mime: String,
+}
+
+impl From<RgbLibMedia> for Media {
+    fn from(value: RgbLibMedia) -> Self {
+        Self {
+            file_path: value.file_path,
+            digest: value.digest,
+            mime: value.mime,
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct MultisigFunding {
+    pub(crate) inputs: Vec<MultisigInput>,
+    pub(crate) change_address: String,
+    pub(crate) fee_rate: u64,
+    pub(crate) timeout_sec: Option<u64>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct MultisigInput {
+    pub(crate) outpoint: String,
+    pub(crate) amount_sat: u64,
+    pub(crate) witness_script: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct NetworkInfoResponse {
+    pub(crate) network: BitcoinNetwork,
+    pub(crate) height: u32,
+    pub(crate) indexer_url: String,
+    pub(crate) synced_to_chain: bool,
+    pub(crate) num_nodes: usize,
+    pub(crate) num_channels: usize,
+    pub(crate) num_channels_with_updates: usize,
+    pub(crate) total_capacity_sat: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct NodeInfoResponse {
+    pub(crate) pubkey: String,
+    pub(crate) network: BitcoinNetwork,
+    pub(crate) num_channels: usize,
+    pub(crate) num_usable_channels: usize,
+    pub(crate) local_balance_sat: u64,
+    pub(crate) eventual_close_fees_sat: u64,
+    pub(crate) pending_outbound_payments_sat: u64,
+    pub(crate) num_peers: usize,
+    pub(crate) account_xpub_vanilla: String,
+    pub(crate) account_xpub_colored: String,
+    pub(crate) max_media_upload_size_mb: u16,
+    pub(crate) rgb_htlc_min_msat: u64,
+    pub(crate) rgb_channel_capacity_min_sat: u64,
+    pub(crate) channel_capacity_min_sat: u64,
+    pub(crate) channel_capacity_max_sat: u64,
+    pub(crate) channel_asset_min_amount: u64,
+    pub(crate) channel_asset_max_amount: u64,
+    pub(crate) network_nodes: usize,
+    pub(crate) network_channels: usize,
+    pub(crate) alias: Option<String>,
+    pub(crate) color: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub(crate) enum NwcBudgetRenewal {
+    #[default]
+    Never,
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl_writeable_tlv_based_enum!(NwcBudgetRenewal,
+    (0, Never) => {},
+    (1, Daily) => {},
+    (2, Weekly) => {},
+    (3, Monthly) => {},
+);
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct NwcConnectRequest {
+    pub(crate) name: String,
+    pub(crate) relay: String,
+    pub(crate) budget_msat: Option<u64>,
+    pub(crate) budget_renewal: Option<NwcBudgetRenewal>,
+    pub(crate) totp_code: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct NwcConnectResponse {
+    pub(crate) connection_id: String,
+    pub(crate) uri: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct NwcConnection {
+    pub(crate) connection_id: String,
+    pub(crate) name: String,
+    pub(crate) relay: String,
+    pub(crate) wallet_pubkey: String,
+    pub(crate) budget_msat: Option<u64>,
+    pub(crate) budget_renewal: NwcBudgetRenewal,
+    pub(crate) spent_msat: u64,
+    pub(crate) created_at: u64,
+    pub(crate) last_used_at: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct NwcConnectionsResponse {
+    pub(crate) connections: Vec<NwcConnection>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct NwcRevokeRequest {
+    pub(crate) connection_id: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct OfferRequest {
+    pub(crate) amt_msat: Option<u64>,
+    pub(crate) description: String,
+    pub(crate) expiry_sec: Option<u32>,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct OfferResponse {
+    pub(crate) offer: String,
+    pub(crate) offer_id: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct OnionServiceStatus {
+    pub(crate) client_authorization: bool,
+    pub(crate) authorized_clients: u16,
+    pub(crate) max_inbound_connections: u16,
+    pub(crate) max_inbound_per_minute: u16,
+    pub(crate) active_inbound_connections: u16,
+    pub(crate) accepted_inbound_connections: u64,
+    pub(crate) rejected_max_connections: u64,
+    pub(crate) rejected_throttled: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct OpenChannelRequest {
+    pub(crate) peer_pubkey_and_opt_addr: String,
+    pub(crate) capacity_sat: u64,
+    pub(crate) push_msat: u64,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) public: bool,
+    pub(crate) with_anchors: bool,
+    pub(crate) scid_alias: Option<bool>,
+    pub(crate) htlc_minimum_msat: Option<u64>,
+    pub(crate) max_htlc_value_in_flight_percent: Option<u8>,
+    pub(crate) max_accepted_htlcs: Option<u16>,
+    pub(crate) channel_reserve_proportional_millionths: Option<u32>,
+    pub(crate) fee_base_msat: Option<u32>,
+    pub(crate) fee_proportional_millionths: Option<u32>,
+    pub(crate) temporary_channel_id: Option<String>,
+    pub(crate) multisig_funding: Option<MultisigFunding>,
+    pub(crate) external_funding: Option<ExternalFunding>,
+    pub(crate) coin_selection: Option<CoinSelection>,
+    pub(crate) totp_code: Option<String>,
+    pub(crate) dry_run: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct OpenChannelResponse {
+    pub(crate) temporary_channel_id: String,
+    pub(crate) dry_run: Option<DryRunResult>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct Order {
+    pub(crate) order_id: String,
+    pub(crate) items: Vec<OrderItem>,
+    pub(crate) legs: Vec<OrderLeg>,
+    pub(crate) status: OrderStatus,
+    pub(crate) created_at: u64,
+    pub(crate) expires_at: u64,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct OrderItem {
+    pub(crate) description: String,
+    pub(crate) amt_msat: Option<u64>,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct OrderLeg {
+    pub(crate) invoice: String,
+    pub(crate) payment_hash: String,
+    pub(crate) amt_msat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) status: HTLCStatus,
+    pub(crate) received: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum OrderStatus {
+    Pending,
+    Paid,
+    Expired,
+    Failed,
+}
+
+impl_writeable_tlv_based_enum!(OrderStatus,
+    (0, Pending) => {},
+    (1, Paid) => {},
+    (2, Expired) => {},
+    (3, Failed) => {},
+);
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct OwnershipProofRequest {
+    pub(crate) nonce: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct OwnershipProofResponse {
+    pub(crate) pubkey: String,
+    pub(crate) addresses: Vec<String>,
+    pub(crate) nonce: String,
+    pub(crate) timestamp: u64,
+    pub(crate) message: String,
+    pub(crate) signature: String,
+}
+
+/// Page of the items returned by a list API, `next_cursor` being set when more items follow and
+/// `total` counting all the items matching the request
+#[derive(Deserialize, Serialize)]
+pub(crate) struct Page<T> {
+    pub(crate) items: Vec<T>,
+    pub(crate) next_cursor: Option<String>,
+    pub(crate) total: usize,
+}
+
+impl<T> Page<T> {
+    /// Build the page of the given items starting at the offset returned by [`page_offset`]
+    fn new(items: Vec<T>, offset: usize, limit: Option<usize>) -> Self {
+        let total = items.len();
+        let items: Vec<T> = items
+            .into_iter()
+            .skip(offset)
+            .take(limit.unwrap_or(usize::MAX))
+            .collect();
+        let end = offset.saturating_add(items.len());
+        Self {
+            items,
+            next_cursor: (end < total).then(|| end.to_string()),
+            total,
+        }
+    }
+}
+
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct PageRequest {
+    pub(crate) limit: Option<usize>,
+    pub(crate) cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct Payment {
+    pub(crate) amt_msat: Option<u64>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) payment_hash: String,
+    pub(crate) inbound: bool,
+    pub(crate) status: HTLCStatus,
+    pub(crate) created_at: u64,
+    pub(crate) updated_at: u64,
+    pub(crate) payee_pubkey: String,
+    pub(crate) offer_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct PaymentFee {
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) amt_msat: u64,
+    pub(crate) fee_msat: u64,
+    pub(crate) num_parts: u8,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PaymentPart {
+    pub(crate) amt_msat: u64,
+    pub(crate) fee_msat: u64,
+    pub(crate) first_hop_scid: Option<u64>,
+    pub(crate) hops: u8,
+    pub(crate) status: HTLCStatus,
+    pub(crate) failure: Option<String>,
+    pub(crate) updated_at: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) route: Vec<PaymentHop>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PaymentHop {
+    pub(crate) pubkey: String,
+    pub(crate) short_channel_id: u64,
+    pub(crate) fee_msat: u64,
+    pub(crate) cltv_expiry_delta: u32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PayOfferRequest {
+    pub(crate) offer: String,
+    pub(crate) amt_msat: Option<u64>,
+    pub(crate) payer_note: Option<String>,
+    pub(crate) totp_code: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PayOfferResponse {
+    pub(crate) payment_id: String,
+    pub(crate) status: HTLCStatus,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub(crate) struct Peer {
+    pub(crate) pubkey: String,
+    pub(crate) address: Option<String>,
+    pub(crate) transport: PeerTransport,
+    pub(crate) inbound: bool,
+    pub(crate) connected_at: Option<u64>,
+    pub(crate) uptime_sec: Option<u64>,
+    pub(crate) init_features: String,
+    pub(crate) num_channels: usize,
+    pub(crate) gossip_bytes_received: u64,
+    pub(crate) gossip_msgs_received: u64,
+    pub(crate) gossip_msgs_dropped: u64,
+    pub(crate) gossip_limit_bytes_per_min: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PeerCompatRequest {
+    pub(crate) pubkey: String,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) with_anchors: Option<bool>,
+    pub(crate) scid_alias: Option<bool>,
+    pub(crate) public: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PeerCompatResponse {
+    pub(crate) pubkey: String,
+    pub(crate) features: PeerFeatures,
+    pub(crate) init_features: String,
+    pub(crate) open_compatible: bool,
+    pub(crate) issues: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PeerFeatures {
+    pub(crate) anchors: bool,
+    pub(crate) rgb: bool,
+    pub(crate) scid_alias: bool,
+    pub(crate) dual_funding: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PeerFilterRequest {
+    pub(crate) allowed_pubkeys: Vec<String>,
+    pub(crate) denied_pubkeys: Vec<String>,
+    pub(crate) allowed_addresses: Vec<String>,
+    pub(crate) denied_addresses: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PeerFilterResponse {
+    pub(crate) allowed_pubkeys: Vec<String>,
+    pub(crate) denied_pubkeys: Vec<String>,
+    pub(crate) allowed_addresses: Vec<String>,
+    pub(crate) denied_addresses: Vec<String>,
+}
+
+impl From<PeerFilter> for PeerFilterResponse {
+    fn from(filter: PeerFilter) -> Self {
+        Self {
+            allowed_pubkeys: filter
+                .allowed_pubkeys
+                .iter()
+                .map(|pk| pk.to_string())
+                .collect(),
+            denied_pubkeys: filter
+                .denied_pubkeys
+                .iter()
+                .map(|pk| pk.to_string())
+                .collect(),
+            allowed_addresses: filter.allowed_addresses,
+            denied_addresses: filter.denied_addresses,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PeerSuggestion {
+    pub(crate) pubkey: String,
+    pub(crate) alias: Option<String>,
+    pub(crate) score: f64,
+    pub(crate) num_channels: usize,
+    pub(crate) total_capacity_sat: u64,
+    pub(crate) avg_fee_base_msat: u64,
+    pub(crate) avg_fee_proportional_millionths: u64,
+    pub(crate) probe_success_rate: Option<f64>,
+    pub(crate) supports_assets: bool,
+    pub(crate) has_channel: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub(crate) enum PeerSuggestionGoal {
+    RoutingRevenue,
+    #[default]
+    PaymentReliability,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct PeerSuggestionsRequest {
+    pub(crate) goal: Option<PeerSuggestionGoal>,
+    pub(crate) limit: Option<usize>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PeerSuggestionsResponse {
+    pub(crate) suggestions: Vec<PeerSuggestion>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum PeerTransport {
+    Clearnet,
+    Tor,
+    OnionServiceInbound,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PendingBroadcast {
+    pub(crate) txid: String,
+    pub(crate) kind: PendingBroadcastKind,
+    pub(crate) created_at: u64,
+    pub(crate) broadcast_at: Option<u64>,
+    pub(crate) hold_for_approval: bool,
+    pub(crate) approved_at: Option<u64>,
+    pub(crate) error: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum PendingBroadcastKind {
+    Btc,
+    Asset,
+}
+
+impl_writeable_tlv_based_enum!(PendingBroadcastKind,
+    (0, Btc) => {},
+    (1, Asset) => {},
+);
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PendingBroadcastsResponse {
+    pub(crate) broadcasts: Vec<PendingBroadcast>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PendingChannel {
+    pub(crate) temporary_channel_id: String,
+    pub(crate) peer_pubkey: String,
+    pub(crate) capacity_sat: u64,
+    pub(crate) push_msat: u64,
+    pub(crate) public: bool,
+    pub(crate) with_anchors: bool,
+    pub(crate) received_at: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PendingChannelsResponse {
+    pub(crate) channels: Vec<PendingChannel>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PendingExternalFunding {
+    pub(crate) temporary_channel_id: String,
+    pub(crate) peer_pubkey: String,
+    pub(crate) capacity_sat: u64,
+    pub(crate) funding_address: Option<String>,
+    pub(crate) created_at: u64,
+    pub(crate) expires_at: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PendingFunding {
+    pub(crate) temporary_channel_id: String,
+    pub(crate) peer_pubkey: String,
+    pub(crate) capacity_sat: u64,
+    pub(crate) fee_sat: u64,
+    pub(crate) unsigned_psbt: Option<String>,
+    pub(crate) created_at: u64,
+    pub(crate) expires_at: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PendingFundingsResponse {
+    pub(crate) fundings: Vec<PendingFunding>,
+    pub(crate) external_fundings: Vec<PendingExternalFunding>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PhantomRouteHintsResponse {
+    pub(crate) route_hints: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct PostAssetMediaResponse {
+    pub(crate) digest: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ProbePaymentRequest {
+    pub(crate) dest_pubkey: String,
+    pub(crate) amt_msat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ProbePaymentResponse {
+    pub(crate) reachable: bool,
+    pub(crate) hops: Vec<QueriedRouteHop>,
+    pub(crate) total_fee_msat: u64,
+    pub(crate) total_cltv_expiry_delta: u32,
+    pub(crate) latency_ms: u64,
+    pub(crate) failed_short_channel_id: Option<u64>,
+    pub(crate) failure: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ProofOfReserves {
+    pub(crate) utxo: String,
+    pub(crate) proof: Vec<u8>,
+}
+
+impl From<RgbLibProofOfReserves> for ProofOfReserves {
+    fn from(value: RgbLibProofOfReserves) -> Self {
+        Self {
+            utxo: value.utxo.to_string(),
+            proof: value.proof,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub(crate) enum RecipientType {
+    Blind,
+    Witness,
+}
+
+impl From<RgbLibRecipientType> for RecipientType {
+    fn from(value: RgbLibRecipientType) -> Self {
+        match value {
+            RgbLibRecipientType::Blind => Self::Blind,
+            RgbLibRecipientType::Witness => Self::Witness,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct QueriedRoute {
+    pub(crate) hops: Vec<QueriedRouteHop>,
+    pub(crate) total_amt_msat: u64,
+    pub(crate) total_fee_msat: u64,
+    pub(crate) total_cltv_expiry_delta: u32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct QueriedRouteHop {
+    pub(crate) pubkey: String,
+    pub(crate) short_channel_id: u64,
+    pub(crate) fee_msat: u64,
+    pub(crate) cltv_expiry_delta: u32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct QueryRoutesRequest {
+    pub(crate) dest_pubkey: String,
+    pub(crate) amt_msat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) max_fee_msat: Option<u64>,
+    pub(crate) max_cltv_expiry_delta: Option<u32>,
+    pub(crate) max_routes: Option<u8>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct QueryRoutesResponse {
+    pub(crate) routes: Vec<QueriedRoute>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RebalanceRequest {
+    pub(crate) amt_msat: u64,
+    pub(crate) outgoing_channel_id: String,
+    pub(crate) incoming_channel_id: String,
+    pub(crate) max_fee_msat: u64,
+    pub(crate) totp_code: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RebalanceResponse {
+    pub(crate) payment_id: String,
+    pub(crate) payment_hash: String,
+    pub(crate) status: HTLCStatus,
+    pub(crate) total_fee_msat: u64,
+    pub(crate) hops: Vec<QueriedRouteHop>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RefreshRequest {
+    pub(crate) skip_sync: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ReissueAssetRequest {
+    pub(crate) asset_id: String,
+    pub(crate) amounts: Vec<u64>,
+    pub(crate) fee_rate: u64,
+    pub(crate) min_confirmations: u8,
+    pub(crate) totp_code: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ReissueAssetResponse {
+    pub(crate) txid: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RejectChannelRequest {
+    pub(crate) temporary_channel_id: String,
+    pub(crate) reason: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ReloadConfigResponse {
+    pub(crate) applied: Vec<String>,
+    pub(crate) skipped: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RemoveHedgingPolicyRequest {
+    pub(crate) asset_id: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ResolveHtlcRequest {
+    pub(crate) htlc_id: String,
+    pub(crate) resolution: HtlcResolution,
+    pub(crate) payment_preimage: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RestoreChannelsRequest {
+    pub(crate) backup_path: String,
+    pub(crate) password: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RestoreChannelsResponse {
+    pub(crate) num_channels: usize,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RestoreRequest {
+    pub(crate) backup_path: String,
+    pub(crate) password: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RetrySweepRequest {
+    pub(crate) outpoint: String,
+    pub(crate) totp_code: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RetryTransferRequest {
+    pub(crate) idx: i32,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct RevokeTokenRequest {
+    pub(crate) token: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RgbAllocation {
+    pub(crate) asset_id: Option<String>,
+    pub(crate) assignment: Assignment,
+    pub(crate) settled: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RgbInvoiceRequest {
+    pub(crate) asset_id: Option<String>,
+    pub(crate) assignment: Option<Assignment>,
+    pub(crate) duration_seconds: Option<u32>,
+    pub(crate) min_confirmations: u8,
+    pub(crate) witness: bool,
+    pub(crate) label: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct RgbInvoiceResponse {
+    pub(crate) recipient_id: String,
+    pub(crate) invoice: String,
+    pub(crate) expiration_timestamp: Option<i64>,
+    pub(crate) batch_transfer_idx: i32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SendAssetRequest {
+    pub(crate) asset_id: String,
+    pub(crate) assignment: Assignment,
+    pub(crate) recipient_id: String,
+    pub(crate) witness_data: Option<WitnessData>,
+    pub(crate) donation: bool,
+    pub(crate) fee_rate: u64,
+    pub(crate) min_confirmations: u8,
+    pub(crate) transport_endpoints: Vec<String>,
+    pub(crate) skip_sync: bool,
+    pub(crate) broadcast_at: Option<u64>,
+    pub(crate) hold_for_approval: bool,
+    pub(crate) totp_code: Option<String>,
+    pub(crate) dry_run: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SendAssetResponse {
+    pub(crate) txid: String,
+    pub(crate) dry_run: Option<DryRunResult>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SendBtcManyRequest {
+    pub(crate) recipients: Vec<BtcRecipient>,
+    pub(crate) asset_recipients: Vec<AssetRecipient>,
+    pub(crate) fee_rate: u64,
+    pub(crate) donation: bool,
+    pub(crate) min_confirmations: u8,
+    pub(crate) skip_sync: bool,
+    pub(crate) coin_selection: Option<CoinSelection>,
+    pub(crate) totp_code: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SendBtcManyResponse {
+    pub(crate) txid: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SendBtcRequest {
+    pub(crate) amount: u64,
+    pub(crate) address: String,
+    pub(crate) fee_rate: u64,
+    pub(crate) skip_sync: bool,
+    pub(crate) broadcast_at: Option<u64>,
+    pub(crate) hold_for_approval: bool,
+    pub(crate) coin_selection: Option<CoinSelection>,
+    pub(crate) totp_code: Option<String>,
+    pub(crate) dry_run: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SendBtcResponse {
+    pub(crate) txid: String,
+    pub(crate) dry_run: Option<DryRunResult>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SendOnionMessageRequest {
+    pub(crate) node_ids: Vec<String>,
+    pub(crate) tlv_type: u64,
+    pub(crate) data: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SendPaymentRequest {
+    pub(crate) invoice: String,
+    pub(crate) amt_msat: Option<u64>,
+    pub(crate) timeout_sec: Option<u64>,
+    pub(crate) max_retries: Option<u32>,
+    pub(crate) max_fee_msat: Option<u64>,
+    pub(crate) max_fee_ppm: Option<u32>,
+    pub(crate) max_cltv_expiry_delta: Option<u32>,
+    pub(crate) max_parts: Option<u8>,
+    pub(crate) max_shard_amt_msat: Option<u64>,
+    pub(crate) totp_code: Option<String>,
+    pub(crate) dry_run: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SendPaymentResponse {
+    pub(crate) payment_id: String,
+    pub(crate) payment_hash: Option<String>,
+    pub(crate) payment_secret: Option<String>,
+    pub(crate) status: HTLCStatus,
+    pub(crate) dry_run: Option<DryRunResult>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SendToRouteRequest {
+    pub(crate) invoice: String,
+    pub(crate) amt_msat: Option<u64>,
+    pub(crate) hops: Vec<String>,
+    pub(crate) totp_code: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SetAliasRequest {
+    pub(crate) alias: Option<String>,
+    pub(crate) color: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SetHedgingPolicyRequest {
+    pub(crate) asset_id: String,
+    pub(crate) target_asset_id: String,
+    pub(crate) min_amount: u64,
+    pub(crate) max_amount: Option<u64>,
+    pub(crate) max_daily_amount: Option<u64>,
+    pub(crate) rate_from: u64,
+    pub(crate) rate_to: u64,
+    pub(crate) maker_url: String,
+    pub(crate) maker_token: Option<String>,
+    pub(crate) totp_code: Option<String>,
+    pub(crate) dry_run: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SetViewPasswordRequest {
+    pub(crate) password: String,
+    pub(crate) view_password: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SettleInvoiceRequest {
+    pub(crate) payment_hash: String,
+    pub(crate) payment_preimage: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SettleInvoicesRequest {
+    pub(crate) invoices: Vec<SettleInvoiceRequest>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SettleInvoicesResponse {
+    pub(crate) results: Vec<InvoiceResult>,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+pub(crate) struct SettlementsRequest {
+    pub(crate) period: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SettlementsResponse {
+    pub(crate) period: String,
+    pub(crate) totals: Vec<SettlementTotal>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SettlementTotal {
+    pub(crate) label: Option<String>,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) settled_invoices: u64,
+    pub(crate) amt_msat: u64,
+    pub(crate) received_transfers: u64,
+    pub(crate) asset_amount: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SignMessageRequest {
+    pub(crate) message: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SignMessageResponse {
+    pub(crate) signed_message: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SignPsbtRequest {
+    pub(crate) psbt: String,
+    pub(crate) totp_code: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SignPsbtResponse {
+    pub(crate) psbt: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SubmarineSwap {
+    pub(crate) id: String,
+    pub(crate) kind: SubmarineSwapKind,
+    pub(crate) status: SubmarineSwapStatus,
+    pub(crate) amt_sat: u64,
+    pub(crate) onchain_amt_sat: u64,
+    pub(crate) payment_hash: String,
+    pub(crate) timeout_block_height: u32,
+    pub(crate) lockup_address: String,
+    pub(crate) lockup_txid: Option<String>,
+    pub(crate) sweep_address: String,
+    pub(crate) sweep_txid: Option<String>,
+    pub(crate) created_at: u64,
+    pub(crate) updated_at: u64,
+    pub(crate) error: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum SubmarineSwapKind {
+    In,
+    Out,
+}
+
+impl_writeable_tlv_based_enum!(SubmarineSwapKind,
+    (0, In) => {},
+    (1, Out) => {},
+);
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum SubmarineSwapStatus {
+    Pending,
+    Claimed,
+    Succeeded,
+    Refunded,
+    Failed,
+}
+
+impl_writeable_tlv_based_enum!(SubmarineSwapStatus,
+    (0, Pending) => {},
+    (1, Claimed) => {},
+    (2, Succeeded) => {},
+    (3, Refunded) => {},
+    (4, Failed) => {},
+);
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub(crate) struct Swap {
+    pub(crate) qty_from: u64,
+    pub(crate) qty_to: u64,
+    pub(crate) from_asset: Option<String>,
+    pub(crate) to_asset: Option<String>,
+    pub(crate) payment_hash: String,
+    pub(crate) status: SwapStatus,
+    pub(crate) requested_at: u64,
+    pub(crate) initiated_at: Option<u64>,
+    pub(crate) expires_at: u64,
+    pub(crate) completed_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub(crate) enum SwapStatus {
+    Waiting,
+    Pending,
+    Succeeded,
+    Expired,
+    Failed,
+}
+
+impl_writeable_tlv_based_enum!(SwapStatus,
+    (0, Waiting) => {},
+    (1, Pending) => {},
+    (2, Succeeded) => {},
+    (3, Expired) => {},
+    (4, Failed) => {},
+);
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SwapInRequest {
+    pub(crate) amt_sat: u64,
+    pub(crate) fee_rate: u64,
+    pub(crate) max_fee_sat: Option<u64>,
+    pub(crate) skip_sync: bool,
+    pub(crate) totp_code: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SwapInResponse {
+    pub(crate) swap: SubmarineSwap,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SwapOutRequest {
+    pub(crate) amt_sat: u64,
+    pub(crate) address: Option<String>,
+    pub(crate) max_fee_sat: Option<u64>,
+    pub(crate) totp_code: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SwapOutResponse {
+    pub(crate) swap: SubmarineSwap,
+}
+
+/// Output of a closed channel still to be swept to the wallet, outputs still locked in the
+/// channel monitor having no outpoint yet
+#[derive(Deserialize, Serialize)]
+pub(crate) struct Sweep {
+    pub(crate) channel_id: Option<String>,
+    pub(crate) outpoint: Option<String>,
+    pub(crate) amount_sat: u64,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) asset_amount: Option<u64>,
+    pub(crate) status: SweepStatus,
+    pub(crate) spendable_height: Option<u32>,
+    pub(crate) sweep_txid: Option<String>,
+    pub(crate) broadcast_height: Option<u32>,
+    pub(crate) confirmation_height: Option<u32>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SweepConfigRequest {
+    pub(crate) fee_rate: Option<u64>,
+    pub(crate) destination_address: Option<String>,
+    pub(crate) totp_code: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SweepConfigResponse {
+    pub(crate) fee_rate: Option<u64>,
+    pub(crate) destination_address: Option<String>,
+}
+
+impl From<SweepConfig> for SweepConfigResponse {
+    fn from(config: SweepConfig) -> Self {
+        Self {
+            fee_rate: config.fee_rate,
+            destination_address: config.destination_address,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) enum SweepStatus {
+    Timelocked,
+    PendingBroadcast,
+    FailedBroadcast,
+    PendingConfirmation,
+    Confirming,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub(crate) enum SyncStage {
+    #[default]
+    Locked,
+    Unlocking,
+    SyncingWallet,
+    SyncingChain,
+    StartingServices,
+    Ready,
+    Failed,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct SyncStatusResponse {
+    pub(crate) stage: SyncStage,
+    pub(crate) error: Option<String>,
+    pub(crate) started_at: Option<u64>,
+    pub(crate) updated_at: Option<u64>,
+    pub(crate) height: Option<u32>,
+    pub(crate) chain_tip_lag: Option<u32>,
+    pub(crate) graph_num_nodes: Option<usize>,
+    pub(crate) graph_num_channels: Option<usize>,
+    pub(crate) graph_num_channels_with_updates: Option<usize>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct TakerRequest {
+    pub(crate) swapstring: String,
+    pub(crate) totp_code: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct ThrottleGossipRequest {
+    pub(crate) peer_pubkey: String,
+    pub(crate) limit_bytes_per_min: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct Token {
+    pub(crate) index: u32,
+    pub(crate) ticker: Option<String>,
+    pub(crate) name: Option<String>,
+    pub(crate) details: Option<String>,
+    pub(crate) embedded_media: Option<EmbeddedMedia>,
+    pub(crate) media: Option<Media>,
+    pub(crate) attachments: HashMap<u8, Media>,
+    pub(crate) reserves: Option<ProofOfReserves>,
+}
+
+impl From<RgbLibToken> for Token {
+    fn from(value: RgbLibToken) -> Self {
+        Self {
+            index: value.index,
+            ticker: value.ticker,
+            name: value.name,
+            details: value.details,
+            embedded_media: value.embedded_media.map(|em| em.into()),
+            media: value.media.map(|m| m.into()),
+            attachments: value
+                .attachments
+                .into_iter()
+                .map(|(k, v)| (k, v.into()))
+                .collect(),
+            reserves: value.reserves.map(|r| r.into()),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct TokenInvoiceQuotaUsage {
+    pub(crate) token_id: String,
+    pub(crate) usage: InvoiceQuotaUsage,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct TokenLight {
+    pub(crate) index: u32,
+    pub(crate) ticker: Option<String>,
+    pub(crate) name: Option<String>,
+    pub(crate) details: Option<String>,
+    pub(crate) embedded_media: bool,
+    pub(crate) media: Option<Media>,
+    pub(crate) attachments: HashMap<u8, Media>,
+    pub(crate) reserves: bool,
+}
+
+impl From<RgbLibTokenLight> for TokenLight {
+    fn from(value: RgbLibTokenLight) -> Self {
+        Self {
+            index: value.index,
+            ticker: value.ticker,
+            name: value.name,
+            details: value.details,
+            embedded_media: value.embedded_media,
+            media: value.media.map(|m| m.into()),
+            attachments: value
+                .attachments
+                .into_iter()
+                .map(|(k, v)| (k, v.into()))
+                .collect(),
+            reserves: value.reserves,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct TorStatusResponse {
+    pub(crate) socks_proxy: Option<String>,
+    pub(crate) socks_authentication: bool,
+    pub(crate) no_clearnet_dns: bool,
+    pub(crate) isolate_circuits: bool,
+    pub(crate) onion_service: OnionServiceStatus,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct Transaction {
+    pub(crate) transaction_type: TransactionType,
+    pub(crate) txid: String,
+    pub(crate) received: u64,
+    pub(crate) sent: u64,
+    pub(crate) fee: u64,
+    pub(crate) confirmation_time: Option<BlockTime>,
+    pub(crate) confirmations: u32,
+    pub(crate) direction: TransactionDirection,
+    pub(crate) rgb_commitment: bool,
+    pub(crate) asset_ids: Vec<String>,
+    pub(crate) channel_operation: Option<ChannelOperation>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum TransactionDirection {
+    Incoming,
+    Outgoing,
+    SelfTransfer,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum TransactionType {
+    RgbSend,
+    Drain,
+    CreateUtxos,
+    User,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct Transfer {
+    pub(crate) idx: i32,
+    pub(crate) created_at: i64,
+    pub(crate) updated_at: i64,
+    pub(crate) status: TransferStatus,
+    pub(crate) requested_assignment: Option<Assignment>,
+    pub(crate) assignments: Vec<Assignment>,
+    pub(crate) kind: TransferKind,
+    pub(crate) txid: Option<String>,
+    pub(crate) recipient_id: Option<String>,
+    pub(crate) receive_utxo: Option<String>,
+    pub(crate) change_utxo: Option<String>,
+    pub(crate) expiration: Option<i64>,
+    pub(crate) transport_endpoints: Vec<TransferTransportEndpoint>,
+}
+
+impl From<RgbLibTransfer> for Transfer {
+    fn from(value: RgbLibTransfer) -> Self {
+        Self {
+            idx: value.idx,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            status: match value.status {
+                rgb_lib::TransferStatus::WaitingCounterparty => TransferStatus::WaitingCounterparty,
+                rgb_lib::TransferStatus::WaitingConfirmations => {
+                    TransferStatus::WaitingConfirmations
+                }
+                rgb_lib::TransferStatus::Settled => TransferStatus::Settled,
+                rgb_lib::TransferStatus::Failed => TransferStatus::Failed,
+            },
+            requested_assignment: value.requested_assignment.map(|a| a.into()),
+            assignments: value.assignments.into_iter().map(|a| a.into()).collect(),
+            kind: match value.kind {
+                rgb_lib::TransferKind::Issuance => TransferKind::Issuance,
+                rgb_lib::TransferKind::ReceiveBlind => TransferKind::ReceiveBlind,
+                rgb_lib::TransferKind::ReceiveWitness => TransferKind::ReceiveWitness,
+                rgb_lib::TransferKind::Send => TransferKind::Send,
+                rgb_lib::TransferKind::Inflation => TransferKind::Inflation,
+            },
+            txid: value.txid,
+            recipient_id: value.recipient_id,
+            receive_utxo: value.receive_utxo.map(|u| u.to_string()),
+            change_utxo: value.change_utxo.map(|u| u.to_string()),
+            expiration: value.expiration,
+            transport_endpoints: value
+                .transport_endpoints
+                .iter()
+                .map(|tte| TransferTransportEndpoint {
+                    endpoint: tte.endpoint.clone(),
+                    transport_type: match tte.transport_type {
+                        rgb_lib::TransportType::JsonRpc => TransportType::JsonRpc,
+                    },
+                    used: tte.used,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct TransferDetailRequest {
+    pub(crate) idx: i32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct TransferDetailResponse {
+    pub(crate) asset_id: String,
+    pub(crate) transfer: Transfer,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum TransferKind {
+    Issuance,
+    ReceiveBlind,
+    ReceiveWitness,
+    Send,
+    Inflation,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) enum TransferStatus {
+    WaitingCounterparty,
+    WaitingConfirmations,
+    Settled,
+    Failed,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct TransferTransportEndpoint {
+    pub(crate) endpoint: String,
+    pub(crate) transport_type: TransportType,
+    pub(crate) used: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) enum TransportType {
+    JsonRpc,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct UnlockRequest {
+    /// Wiped from memory once the request is dropped
+    pub(crate) password: Zeroizing<String>,
+    pub(crate) bitcoind_rpc_username: String,
+    pub(crate) bitcoind_rpc_password: String,
+    pub(crate) bitcoind_rpc_host: String,
+    pub(crate) bitcoind_rpc_port: Option<u16>,
+    pub(crate) indexer_url: Option<String>,
+    pub(crate) fallback_indexer_urls: Option<Vec<String>>,
+    pub(crate) proxy_endpoint: Option<String>,
+    pub(crate) fallback_proxy_endpoints: Option<Vec<String>>,
+    pub(crate) announce_addresses: Vec<String>,
+    pub(crate) announce_alias: Option<String>,
+    pub(crate) background: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct UnlockUtxoRequest {
+    pub(crate) outpoint: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct Unspent {
+    pub(crate) utxo: Utxo,
+    pub(crate) rgb_allocations: Vec<RgbAllocation>,
+    pub(crate) confirmations: u32,
+    pub(crate) locked: bool,
+    pub(crate) label: Option<String>,
+    pub(crate) reserved_for_funding: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct UpdateChannelPolicyRequest {
+    pub(crate) channel_id: Option<String>,
+    pub(crate) asset_id: Option<String>,
+    pub(crate) fee_base_msat: Option<u32>,
+    pub(crate) fee_proportional_millionths: Option<u32>,
+    pub(crate) cltv_expiry_delta: Option<u16>,
+    pub(crate) htlc_minimum_msat: Option<u64>,
+    pub(crate) max_htlc_value_in_flight_percent: Option<u8>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct UpdateChannelPolicyResponse {
+    pub(crate) channel_ids: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct Utxo {
+    pub(crate) outpoint: String,
+    pub(crate) btc_amount: u64,
+    pub(crate) colorable: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct VerifyMessageRequest {
+    pub(crate) message: String,
+    pub(crate) signature: String,
+    pub(crate) pubkey: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct VerifyMessageResponse {
+    pub(crate) valid: bool,
+    pub(crate) pubkey: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub(crate) struct WitnessData {
+    pub(crate) amount_sat: u64,
+    pub(crate) blinding: Option<u64>,
+}
+
+impl From<WitnessData> for RgbLibWitnessData {
+    fn from(value: WitnessData) -> Self {
+        Self {
+            amount_sat: value.amount_sat,
+            blinding: value.blinding,
+        }
+    }
+}
+
+impl AppState {
+    /// Refuse new payments while the node drains in-flight HTLCs before shutting down
+    fn check_draining(&self) -> Result<(), APIError> {
+        if *self.get_draining() {
+            return Err(APIError::ShuttingDown);
+        }
+        Ok(())
+    }
+
+    fn check_changing_state(&self) -> Result<(), APIError> {
+        if *self.get_changing_state() {
+            return Err(APIError::ChangingState);
+        }
+        Ok(())
+    }
+
+    async fn check_locked(
+        &self,
+    ) -> Result<TokioRwLockReadGuard<'_, Option<Arc<UnlockedAppState>>>, APIError> {
+        self.check_changing_state()?;
+        let unlocked_app_state = self.get_unlocked_app_state().await;
+        if unlocked_app_state.is_some() {
+            Err(APIError::UnlockedNode)
+        } else {
+            Ok(unlocked_app_state)
+        }
+    }
+
+    async fn check_unlocked(
+        &self,
+    ) -> Result<TokioRwLockReadGuard<'_, Option<Arc<UnlockedAppState>>>, APIError> {
+        self.check_changing_state()?;
+        let unlocked_app_state = self.get_unlocked_app_state().await;
+        if unlocked_app_state.is_none() {
+            Err(APIError::LockedNode)
+        } else {
+            Ok(unlocked_app_state)
+        }
+    }
+
+    fn swap_provider(&self) -> Result<SwapProviderClient, APIError> {
+        self.static_state
+            .swap_provider_url
+            .as_deref()
+            .map(SwapProviderClient::new)
+            .ok_or(APIError::SwapProviderNotConfigured)
+    }
+
+    fn update_changing_state(&self, updated: bool) {
+        let mut changing_state = self.get_changing_state();
+        *changing_state = updated;
+    }
+
+    fn update_ldk_background_services(&self, updated: Option<LdkBackgroundServices>) {
+        let mut ldk_background_services = self.get_ldk_background_services();
+        *ldk_background_services = updated;
+    }
+
+    async fn update_unlocked_app_state(&self, updated: Option<Arc<UnlockedAppState>>) {
+        let mut unlocked_app_state = self.unlocked_app_state.write().await;
+        *unlocked_app_state = updated;
+    }
+}
+
+impl UnlockedAppState {
+    /// Map the txids of the known channel funding and closing transactions to their operation
+    ///
+    /// Fundings are taken from the channel monitors, including the archived ones, while closes
+    /// are the sweeps of the channel outputs together with the transactions they spend
+    fn channel_operations(&self) -> Result<HashMap<String, ChannelOperation>, APIError> {
+        let mut operations = HashMap::new();
+        for primary_namespace in [
+            CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+            ARCHIVED_CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+        ] {
+            for monitor_key in self.kv_store.list(
+                primary_namespace,
+                CHANNEL_MONITOR_PERSISTENCE_SECONDARY_NAMESPACE,
+            )? {
+                // monitors are stored by funding outpoint, as {txid}_{index}
+                if let Some((txid, _)) = monitor_key.split_once('_') {
+                    operations.insert(txid.to_string(), ChannelOperation::Funding);
+                }
+            }
+        }
+
+        let mut closes = vec![];
+        for sweep_tx in disk::read_output_spender_txes(&self.kv_store, OUTPUT_SPENDER_TXES).values()
+        {
+            closes.push(sweep_tx.compute_txid());
+            closes.extend(sweep_tx.input.iter().map(|i| i.previous_output.txid));
+        }
+        for output in self.output_sweeper.tracked_spendable_outputs() {
+            closes.push(match &output.descriptor {
+                SpendableOutputDescriptor::StaticPaymentOutput(d) => d.outpoint.txid,
+                SpendableOutputDescriptor::DelayedPaymentOutput(d) => d.outpoint.txid,
+                SpendableOutputDescriptor::StaticOutput { outpoint, .. } => outpoint.txid,
+            });
+            match &output.status {
+                OutputSpendStatus::PendingFirstConfirmation {
+                    latest_spending_tx, ..
+                }
+                | OutputSpendStatus::PendingThresholdConfirmations {
+                    latest_spending_tx, ..
+                } => closes.push(latest_spending_tx.compute_txid()),
+                OutputSpendStatus::PendingInitialBroadcast { .. } => {}
+            }
+        }
+        for txid in closes {
+            operations
+                .entry(txid.to_string())
+                .or_insert(ChannelOperation::Close);
+        }
+
+        Ok(operations)
+    }
+
+    /// Add route hints and an optional on-chain fallback address to an LDK-created invoice,
+    /// re-signing it
+    ///
+    /// Explicit hints replace any existing ones, otherwise hints for private channels are added
+    /// (if any) and the invoice is left untouched when there's nothing to add
+    fn add_invoice_route_hints(
+        &self,
+        invoice: Bolt11Invoice,
+        route_hints: Option<Vec<RouteHint>>,
+        fallback_address: Option<&str>,
+        contract_id: Option<ContractId>,
+        max_route_hints: u8,
+        ldk_data_dir: &Path,
+    ) -> Result<Bolt11Invoice, APIError> {
+        let route_hints = match route_hints {
+            Some(route_hints) => route_hints,
+            None => {
+                let route_hints =
+                    self.private_route_hints(contract_id, max_route_hints, ldk_data_dir);
+                if route_hints.is_empty() && fallback_address.is_none() {
+                    return Ok(invoice);
+                }
+                route_hints
+            }
+        };
+
+        let description = match invoice.description() {
+            Bolt11InvoiceDescriptionRef::Direct(d) => Bolt11InvoiceDescription::Direct(d.clone()),
+            Bolt11InvoiceDescriptionRef::Hash(h) => Bolt11InvoiceDescription::Hash(h.clone()),
+        };
+        let mut builder = InvoiceBuilder::new(invoice.currency())
+            .invoice_description(description)
+            .payment_hash(*invoice.payment_hash())
+            .payment_secret(*invoice.payment_secret())
+            .duration_since_epoch(invoice.duration_since_epoch())
+            .min_final_cltv_expiry_delta(invoice.min_final_cltv_expiry_delta())
+            .expiry_time(invoice.expiry_time())
+            .basic_mpp();
+        if let Some(amt_msat) = invoice.amount_milli_satoshis() {
+            builder = builder.amount_milli_satoshis(amt_msat);
+        }
+        if let Some(contract_id) = invoice.rgb_contract_id() {
+            builder = builder.rgb_contract_id(contract_id);
+        }
+        if let Some(rgb_amount) = invoice.rgb_amount() {
+            builder = builder.rgb_amount(rgb_amount);
+        }
+        for route_hint in route_hints {
+            builder = builder.private_route(route_hint);
+        }
+        if let Some(address) = fallback_address {
+            let witness_program = Address::from_str(address)
+                .map_err(|e| APIError::FailedInvoiceCreation(e.to_string()))?
+                .assume_checked()
+                .witness_program()
+                .ok_or_else(|| {
+                    APIError::FailedInvoiceCreation(s!("fallback address is not segwit"))
+                })?;
+            builder = builder.fallback(Fallback::SegWitProgram {
+                version: witness_program.version(),
+                program: witness_program.program().as_bytes().to_vec(),
+            });
+        }
+
+        let node_secret = self.keys_manager.get_node_secret_key();
+        builder
+            .build_signed(|hash| Secp256k1::new().sign_ecdsa_recoverable(hash, &node_secret))
+            .map_err(|e| APIError::FailedInvoiceCreation(e.to_string()))
+    }
+
+    /// Check the msat amount of an asset payment against the asset HTLC minimums agreed for the
+    /// usable channels of the asset
+    fn check_asset_htlc_min(
+        &self,
+        contract_id: ContractId,
+        amt_msat: u64,
+        ldk_data_dir: &Path,
+    ) -> Result<(), APIError> {
+        let min_msat = self
+            .channel_manager
+            .list_usable_channels()
+            .iter()
+            .filter(|c| {
+                get_rgb_channel_info_optional(&c.channel_id, ldk_data_dir, false)
+                    .is_some_and(|(info, _)| info.contract_id == contract_id)
+            })
+            .map(|c| {
+                self.asset_htlc_min_handler
+                    .agreed_msat(&c.channel_id)
+                    .unwrap_or(HTLC_MIN_MSAT)
+            })
+            .min();
+        if let Some(min_msat) = min_msat {
+            if amt_msat < min_msat {
+                return Err(APIError::InvalidAmount(format!(
+                    "amt_msat cannot be less than {min_msat}, the asset HTLC minimum agreed with the peers"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Broadcast a deferred transaction, keeping it as pending with the error in case of failure
+    pub(crate) fn broadcast_pending(&self, txid: &str) -> Result<(), APIError> {
+        let Some(info) = self.pending_broadcasts().get(txid).cloned() else {
+            return Err(APIError::UnknownPendingBroadcast);
+        };
+
+        let res = match info.kind {
+            PendingBroadcastKind::Btc => self.rgb_send_btc_end(info.signed_psbt).map(|_| ()),
+            PendingBroadcastKind::Asset => self.rgb_send_end(info.signed_psbt).map(|_| ()),
+        };
+        match res {
+            Ok(()) => {
+                self.remove_pending_broadcast(txid);
+                tracing::info!("Broadcast deferred TX {txid}");
+                Ok(())
+            }
+            Err(e) => {
+                let e = APIError::from(e);
+                tracing::error!("Failed to broadcast deferred TX {txid}: {e}");
+                self.set_pending_broadcast_error(txid, e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    fn escrow_response(&self, escrow_id: String, escrow: EscrowInfo) -> Escrow {
+        let payment_hash = hex_str(&escrow.payment_hash.0);
+        let status = match escrow.decision {
+            Some(EscrowDecision::Settle) => EscrowStatus::Settled,
+            Some(EscrowDecision::Refund) => EscrowStatus::Refunded,
+            None => {
+                let payment_status = self
+                    .inbound_payments()
+                    .get(&escrow.payment_hash)
+                    .map(|p| p.status);
+                let funded = self
+                    .hodl_invoices()
+                    .get(&escrow.payment_hash)
+                    .is_some_and(|h| h.claimable_amt_msat.is_some());
+                match payment_status {
+                    Some(HTLCStatus::Succeeded) => EscrowStatus::Settled,
+                    _ if funded => EscrowStatus::Funded,
+                    Some(HTLCStatus::Pending) if escrow.expires_at > get_current_timestamp() => {
+                        EscrowStatus::AwaitingPayment
+                    }
+                    // the held payment got cancelled before a decision (e.g. close to its CLTV
+                    // deadline) or the invoice expired unpaid
+                    _ => EscrowStatus::Expired,
+                }
+            }
+        };
+        Escrow {
+            settle_message: escrow_decision_message(
+                &escrow_id,
+                &payment_hash,
+                EscrowDecision::Settle,
+            ),
+            refund_message: escrow_decision_message(
+                &escrow_id,
+                &payment_hash,
+                EscrowDecision::Refund,
+            ),
+            escrow_id,
+            payment_hash,
+            arbiter_pubkey: escrow.arbiter_pubkey.to_string(),
+            invoice: escrow.invoice,
+            description: escrow.description,
+            amt_msat: escrow.amt_msat,
+            asset_id: escrow.asset_id,
+            asset_amount: escrow.asset_amount,
+            status,
+            created_at: escrow.created_at,
+            expires_at: escrow.expires_at,
+            resolved_at: escrow.resolved_at,
+        }
+    }
+
+    fn order_response(&self, order_id: String, order: OrderInfo) -> Order {
+        let hodl_invoices = self.hodl_invoices();
+        let inbound_payments = self.inbound_payments();
+        Order {
+            order_id,
+            items: order
+                .items
+                .into_iter()
+                .map(|i| OrderItem {
+                    description: i.description,
+                    amt_msat: i.amt_msat,
+                    asset_id: i.asset_id,
+                    asset_amount: i.asset_amount,
+                })
+                .collect(),
+            legs: order
+                .legs
+                .into_iter()
+                .map(|l| {
+                    let status = inbound_payments
+                        .get(&l.payment_hash)
+                        .map(|p| p.status)
+                        .unwrap_or(HTLCStatus::Failed);
+                    let received = status == HTLCStatus::Succeeded
+                        || hodl_invoices
+                            .get(&l.payment_hash)
+                            .is_some_and(|h| h.claimable_amt_msat.is_some());
+                    OrderLeg {
+                        invoice: l.invoice,
+                        payment_hash: hex_str(&l.payment_hash.0),
+                        amt_msat: l.amt_msat,
+                        asset_id: l.asset_id,
+                        asset_amount: l.asset_amount,
+                        status,
+                        received,
+                    }
+                })
+                .collect(),
+            status: order.status,
+            created_at: order.created_at,
+            expires_at: order.expires_at,
+        }
+    }
+
+    /// Create an invoice whose payment is held until it gets settled or cancelled
+    fn create_hodl_invoice(
+        &self,
+        invoice_params: Bolt11InvoiceParameters,
+        payment_preimage: Option<PaymentPreimage>,
+        route_hints: Option<Vec<RouteHint>>,
+        max_route_hints: u8,
+        ldk_data_dir: &Path,
+    ) -> Result<Bolt11Invoice, APIError> {
+        let payment_hash = invoice_params.payment_hash.unwrap();
+        if self.inbound_payments().contains_key(&payment_hash) {
+            return Err(APIError::PaymentHashAlreadyUsed);
+        }
+
+        let amt_msat = invoice_params.amount_msats;
+        let contract_id = invoice_params.contract_id;
+        let invoice = match self.channel_manager.create_bolt11_invoice(invoice_params) {
+            Ok(inv) => inv,
+            Err(e) => return Err(APIError::FailedInvoiceCreation(e.to_string())),
+        };
+        let invoice = self.add_invoice_route_hints(
+            invoice,
+            route_hints,
+            None,
+            contract_id,
+            max_route_hints,
+            ldk_data_dir,
+        )?;
+
+        let created_at = get_current_timestamp();
+        self.add_hodl_invoice(payment_hash);
+        self.add_inbound_payment(
+            payment_hash,
+            PaymentInfo {
+                preimage: payment_preimage,
+                secret: Some(*invoice.payment_secret()),
+                status: HTLCStatus::Pending,
+                amt_msat,
+                created_at,
+                updated_at: created_at,
+                payee_pubkey: self.channel_manager.get_our_node_id(),
+                offer_id: None,
+                parts: vec![],
+                expires_at: invoice.expires_at().map(|e| e.as_secs()),
+                settle_after_expiry: false,
+            },
+        );
+        self.add_journal_entry(
+            JournalEventKind::InvoiceCreated,
+            format!(
+                "payment hash {payment_hash}, amt_msat {}, hodl",
+                amt_msat.map_or(s!("any"), |a| a.to_string())
+            ),
+        );
+
+        Ok(invoice)
+    }
+
+    fn cancel_hodl_invoice(&self, payment_hash: &str) -> Result<(), APIError> {
+        let payment_hash = check_payment_hash(payment_hash)?;
+        if !self.hodl_invoices().contains_key(&payment_hash) {
+            return Err(APIError::UnknownLNInvoice);
+        }
+
+        self.channel_manager.fail_htlc_backwards(&payment_hash);
+        self.remove_hodl_invoice(&payment_hash);
+        self.update_inbound_payment_status(&payment_hash, HTLCStatus::Failed);
+        self.add_journal_entry(
+            JournalEventKind::InvoiceCancelled,
+            format!("payment hash {payment_hash}"),
+        );
+
+        tracing::info!("Cancelled HODL invoice with payment hash {payment_hash}");
+        Ok(())
+    }
+
+    fn defer_broadcast(
+        &self,
+        kind: PendingBroadcastKind,
+        signed_psbt: String,
+        broadcast_at: Option<u64>,
+        hold_for_approval: bool,
+    ) -> Result<String, APIError> {
+        let txid = Psbt::from_str(&signed_psbt)
+            .map_err(|e| APIError::Unexpected(e.to_string()))?
+            .extract_tx()
+            .map_err(|e| APIError::Unexpected(e.to_string()))?
+            .compute_txid()
+            .to_string();
+
+        self.add_pending_broadcast(
+            txid.clone(),
+            PendingBroadcastInfo {
+                kind,
+                signed_psbt,
+                created_at: get_current_timestamp(),
+                broadcast_at,
+                hold_for_approval,
+                approved_at: None,
+                error: None,
+            },
+        );
+
+        tracing::info!("Deferred broadcast of TX {txid}");
+        Ok(txid)
+    }
+
+    fn pay_offer(
+        &self,
+        offer: &Offer,
+        amt_msat: Option<u64>,
+        payer_note: Option<String>,
+        limits: &PaymentLimits,
+    ) -> Result<(PaymentId, HTLCStatus), APIError> {
+        let payment_id = PaymentId(self.keys_manager.get_secure_random_bytes());
+
+        let offer_amt_msat = match (offer.amount(), amt_msat) {
+            (Some(offer::Amount::Bitcoin { amount_msats }), _) => amount_msats,
+            (_, Some(amt)) => amt,
+            (amt, _) => {
+                return Err(APIError::InvalidAmount(format!(
+                    "cannot process non-Bitcoin-denominated offer value {amt:?}"
+                )));
+            }
+        };
+        if amt_msat.is_some() && amt_msat != Some(offer_amt_msat) {
+            return Err(APIError::InvalidAmount(format!(
+                "amount didn't match offer of {offer_amt_msat}msat"
+            )));
+        }
+
+        // TODO: add and check RGB amount after enabling RGB support for offers
+
+        let payee_pubkey = offer
+            .issuer_signing_pubkey()
+            .ok_or(APIError::InvalidInvoice(s!("missing signing pubkey")))?;
+        let created_at = get_current_timestamp();
+        self.add_outbound_payment(
+            payment_id,
+            PaymentInfo {
+                preimage: None,
+                secret: None,
+                status: HTLCStatus::Pending,
+                amt_msat: Some(offer_amt_msat),
+                created_at,
+                updated_at: created_at,
+                payee_pubkey,
+                offer_id: Some(offer.id()),
+                parts: vec![],
+                expires_at: None,
+                settle_after_expiry: false,
+            },
+        )?;
+
+        let params = OptionalOfferPaymentParams {
+            payer_note,
+            retry_strategy: limits.retry_strategy(),
+            route_params_config: limits.route_params_config(offer_amt_msat),
+        };
+        let status = match self.channel_manager.pay_for_offer(
+            offer,
+            Some(offer_amt_msat),
+            payment_id,
+            params,
+        ) {
+            Ok(()) => {
+                tracing::info!(
+                    "EVENT: initiated sending {offer_amt_msat} msats to offer {}",
+                    hex_str(&offer.id().0)
+                );
+                HTLCStatus::Pending
+            }
+            Err(e) => {
+                tracing::error!("ERROR: failed to pay: {:?}", e);
+                self.update_outbound_payment_status(payment_id, HTLCStatus::Failed);
+                HTLCStatus::Failed
+            }
+        };
+        Ok((payment_id, status))
+    }
+
+    /// Stop retrying an outbound payment, returning whether it was still pending
+    fn abandon_pending_payment(&self, payment_id: PaymentId, reason: &str) -> bool {
+        if self
+            .outbound_payments()
+            .get(&payment_id)
+            .is_some_and(|p| matches!(p.status, HTLCStatus::Pending))
+        {
+            tracing::info!("Abandoning payment {} {reason}", hex_str(&payment_id.0));
+            self.channel_manager.abandon_payment(payment_id);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Build a multi-part route for the invoice, splitting the amount in equal parts that are
+    /// routed one after the other, accounting for the liquidity used by the previous ones
+    fn multi_part_route(
+        &self,
+        invoice: &Bolt11Invoice,
+        amt_msat: u64,
+        num_parts: u64,
+        limits: &PaymentLimits,
+    ) -> Result<Route, String> {
+        let our_node_id = self.channel_manager.get_our_node_id();
+        let first_hops = self.channel_manager.list_usable_channels();
+        let first_hops = first_hops.iter().collect::<Vec<_>>();
+        let mut inflight_htlcs = self.channel_manager.compute_inflight_htlcs();
+        let max_total_routing_fee_msat = limits
+            .route_params_config(amt_msat)
+            .max_total_routing_fee_msat;
+
+        let mut paths = vec![];
+        let mut fee_msat = 0;
+        for i in 0..num_parts {
+            let part_amt_msat = amt_msat / num_parts + u64::from(i < amt_msat % num_parts);
+            let mut payment_params = PaymentParameters::from_bolt11_invoice(invoice);
+            payment_params.max_total_cltv_expiry_delta = limits.max_total_cltv_expiry_delta;
+            payment_params.max_path_count = 1;
+            let route = self
+                .router
+                .find_route(
+                    &our_node_id,
+                    &RouteParameters {
+                        payment_params,
+                        final_value_msat: part_amt_msat,
+                        max_total_routing_fee_msat: None,
+                        rgb_payment: None,
+                    },
+                    Some(&first_hops),
+                    inflight_htlcs.clone(),
+                )
+                .map_err(|e| format!("cannot route part {} of {num_parts}: {}", i + 1, e.err))?;
+            for path in route.paths {
+                inflight_htlcs.process_path(&path, our_node_id);
+                fee_msat += path.fee_msat();
+                paths.push(path);
+            }
+        }
+        if max_total_routing_fee_msat.is_some_and(|max_fee_msat| fee_msat > max_fee_msat) {
+            return Err(format!("routing fees of {fee_msat}msat exceed the limit"));
+        }
+
+        let mut payment_params = PaymentParameters::from_bolt11_invoice(invoice);
+        payment_params.max_total_cltv_expiry_delta = limits.max_total_cltv_expiry_delta;
+        payment_params.max_path_count = num_parts as u8;
+        Ok(Route {
+            paths,
+            route_params: Some(RouteParameters {
+                payment_params,
+                final_value_msat: amt_msat,
+                max_total_routing_fee_msat,
+                rgb_payment: None,
+            }),
+        })
+    }
+
+    /// Describe the TX a dry run would broadcast: its fee and the UTXOs it spends, with the RGB
+    /// allocations they carry
+    fn dry_run_tx(&self, psbt: &str) -> Result<(String, DryRunResult), APIError> {
+        let psbt = Psbt::from_str(psbt)
+            .map_err(|e| APIError::Unexpected(format!("invalid dry run PSBT: {e}")))?;
+        let unspents = self.rgb_list_unspents(true)?;
+        let inputs = psbt
+            .unsigned_tx
+            .input
+            .iter()
+            .zip(&psbt.inputs)
+            .map(|(txin, input)| {
+                let outpoint = txin.previous_output.to_string();
+                let rgb_allocations = unspents
+                    .iter()
+                    .find(|u| u.utxo.outpoint.to_string() == outpoint)
+                    .map(|u| {
+                        u.rgb_allocations
+                            .iter()
+                            .map(|a| RgbAllocation {
+                                asset_id: a.asset_id.clone(),
+                                assignment: a.assignment.clone().into(),
+                                settled: a.settled,
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                DryRunInput {
+                    outpoint,
+                    btc_amount: input.witness_utxo.as_ref().map_or(0, |o| o.value.to_sat()),
+                    rgb_allocations,
+                }
+            })
+            .collect();
+
+        Ok((
+            psbt.unsigned_tx.compute_txid().to_string(),
+            DryRunResult {
+                fee_sat: psbt.fee().ok().map(|f| f.to_sat()),
+                inputs,
+                routes: vec![],
+            },
+        ))
+    }
+
+    /// Find the routes a payment would be sent over, without sending it
+    fn dry_run_payment(
+        &self,
+        invoice: &Bolt11Invoice,
+        amt_msat: u64,
+        rgb_payment: Option<(ContractId, u64)>,
+        num_parts: u64,
+        limits: &PaymentLimits,
+    ) -> Result<DryRunResult, APIError> {
+        let route = if limits.max_shard_amt_msat.is_some() {
+            self.multi_part_route(invoice, amt_msat, num_parts, limits)
+                .map_err(|e| {
+                    tracing::info!("Dry run payment found no route: {e}");
+                    APIError::NoRoute
+                })?
+        } else {
+            let config = limits.route_params_config(amt_msat);
+            let mut payment_params = PaymentParameters::from_bolt11_invoice(invoice);
+            payment_params.max_total_cltv_expiry_delta = config.max_total_cltv_expiry_delta;
+            payment_params.max_path_count = config.max_path_count;
+            let mut route_params = RouteParameters::from_payment_params_and_value(
+                payment_params,
+                amt_msat,
+                rgb_payment,
+            );
+            route_params.max_total_routing_fee_msat = config.max_total_routing_fee_msat;
+            let first_hops = self.channel_manager.list_usable_channels();
+            self.router
+                .find_route(
+                    &self.channel_manager.get_our_node_id(),
+                    &route_params,
+                    Some(&first_hops.iter().collect::<Vec<_>>()),
+                    self.channel_manager.compute_inflight_htlcs(),
+                )
+                .map_err(|_| APIError::NoRoute)?
+        };
+
+        Ok(DryRunResult {
+            fee_sat: None,
+            inputs: vec![],
+            routes: route.paths.iter().map(queried_route).collect(),
+        })
+    }
+
+    /// Build route hints for private channels, only when the node has no announced channel
+    ///
+    /// For RGB invoices only channels of the requested asset are considered, preferring the ones
+    /// with the highest inbound capacity
+    fn private_route_hints(
+        &self,
+        contract_id: Option<ContractId>,
+        max_route_hints: u8,
+        ldk_data_dir: &Path,
+    ) -> Vec<RouteHint> {
+        let usable_channels = self.channel_manager.list_usable_channels();
+        if max_route_hints == 0 || usable_channels.iter().any(|c| c.is_announced) {
+            return vec![];
+        }
+
+        let mut channels: Vec<_> = usable_channels
+            .into_iter()
+            .filter(|c| c.counterparty.forwarding_info.is_some())
+            .filter(|c| c.get_inbound_payment_scid().is_some())
+            .filter(|c| match contract_id {
+                Some(contract_id) => matches!(
+                    get_rgb_channel_info_optional(&c.channel_id, ldk_data_dir, false),
+                    Some((rgb_info, _)) if rgb_info.contract_id == contract_id
+                ),
+                None => true,
+            })
+            .collect();
+        channels.sort_by(|a, b| b.inbound_capacity_msat.cmp(&a.inbound_capacity_msat));
+
+        channels
+            .into_iter()
+            .take(max_route_hints as usize)
+            .map(|c| {
+                let config = c.counterparty.forwarding_info.as_ref().unwrap();
+                RouteHint(vec![RouteHintHop {
+                    src_node_id: c.counterparty.node_id,
+                    short_channel_id: c.get_inbound_payment_scid().unwrap(),
+                    cltv_expiry_delta: config.cltv_expiry_delta,
+                    htlc_maximum_msat: c.inbound_htlc_maximum_msat,
+                    htlc_minimum_msat: c.inbound_htlc_minimum_msat,
+                    fees: RoutingFees {
+                        base_msat: config.fee_base_msat,
+                        proportional_millionths: config.fee_proportional_millionths,
+                    },
+                    htlc_maximum_rgb: None,
+                }])
+            })
+            .collect()
+    }
+
+    fn resolve_intercepted_htlc(
+        &self,
+        htlc_id: &str,
+        resolution: HtlcResolution,
+        payment_preimage: Option<&str>,
+        ldk_data_dir: &Path,
+    ) -> Result<(), APIError> {
+        let htlc = self
+            .get_intercepted_htlcs()
+            .get(htlc_id)
+            .cloned()
+            .ok_or(APIError::UnknownInterceptedHtlc)?;
+        if payment_preimage.is_some() && resolution != HtlcResolution::Settle {
+            return Err(APIError::InvalidHtlcResolution(s!(
+                "a payment preimage can only be provided to settle"
+            )));
+        }
+
+        match (htlc.held, resolution) {
+            (HeldHtlc::Forward { .. }, HtlcResolution::Settle) => {
+                return Err(APIError::InvalidHtlcResolution(s!(
+                    "forwarded HTLCs can only be resumed or failed"
+                )));
+            }
+            (HeldHtlc::Forward { intercept_id, .. }, HtlcResolution::Fail) => {
+                self.channel_manager
+                    .fail_intercepted_htlc(intercept_id)
+                    .map_err(|e| APIError::CannotResolveHtlc(format!("{e:?}")))?;
+            }
+            (
+                HeldHtlc::Forward {
+                    intercept_id,
+                    outbound_amt_msat,
+                    outbound_rgb_payment,
+                },
+                HtlcResolution::Resume,
+            ) => {
+                self.forward_held_htlc(
+                    &htlc.scope_id,
+                    intercept_id,
+                    outbound_amt_msat,
+                    outbound_rgb_payment,
+                    ldk_data_dir,
+                )?;
+            }
+            (HeldHtlc::Receive { .. }, HtlcResolution::Settle) => {
+                let payment_preimage =
+                    check_payment_preimage(payment_preimage.ok_or_else(|| {
+                        APIError::InvalidHtlcResolution(s!("settling needs the payment preimage"))
+                    })?)?;
+                if Sha256::hash(&payment_preimage.0[..]).to_byte_array() != htlc.payment_hash.0 {
+                    return Err(APIError::InvalidPaymentPreimage);
+                }
+                self.channel_manager.claim_funds(payment_preimage);
+                self.remove_hodl_invoice(&htlc.payment_hash);
+            }
+            (HeldHtlc::Receive { .. }, HtlcResolution::Fail) => {
+                self.channel_manager.fail_htlc_backwards(&htlc.payment_hash);
+            }
+            (
+                HeldHtlc::Receive {
+                    payment_preimage,
+                    claim_deadline,
+                },
+                HtlcResolution::Resume,
+            ) => {
+                self.handle_claimable_payment(
+                    &htlc.payment_hash,
+                    htlc.amt_msat,
+                    claim_deadline,
+                    payment_preimage,
+                );
+            }
+        }
+        self.take_intercepted_htlc(htlc_id);
+
+        tracing::info!("Resolved intercepted HTLC {htlc_id} with {resolution:?}");
+        Ok(())
+    }
+
+    fn settle_hodl_invoice(
+        &self,
+        payment_hash: &str,
+        payment_preimage: &str,
+    ) -> Result<(), APIError> {
+        let payment_hash = check_payment_hash(payment_hash)?;
+        let payment_preimage = check_payment_preimage(payment_preimage)?;
+        if Sha256::hash(&payment_preimage.0[..]).to_byte_array() != payment_hash.0 {
+            return Err(APIError::InvalidPaymentPreimage);
+        }
+
+        let hodl_invoice = match self.hodl_invoices().get(&payment_hash) {
+            Some(h) => h.clone(),
+            None => return Err(APIError::UnknownLNInvoice),
+        };
+        if hodl_invoice.claimable_amt_msat.is_none() {
+            return Err(APIError::InvoiceNotClaimable(s!(
+                "no payment has been received yet"
+            )));
+        }
+
+        self.channel_manager.claim_funds(payment_preimage);
+        self.remove_hodl_invoice(&payment_hash);
+
+        tracing::info!("Settled HODL invoice with payment hash {payment_hash}");
+        Ok(())
+    }
+
+    /// Settle the orders whose legs have all been received, cancel the held legs of the expired
+    /// or failed ones and mark as paid the orders whose legs have all been settled
+    pub(crate) fn process_orders(&self) {
+        let now = get_current_timestamp();
+        let hodl_invoices = self.hodl_invoices();
+        let inbound_payments = self.inbound_payments();
+        for (order_id, order) in self.orders() {
+            if order.status != OrderStatus::Pending {
+                continue;
+            }
+            let leg_statuses: Vec<Option<HTLCStatus>> = order
+                .legs
+                .iter()
+                .map(|l| inbound_payments.get(&l.payment_hash).map(|p| p.status))
+                .collect();
+            if leg_statuses
+                .iter()
+                .all(|s| *s == Some(HTLCStatus::Succeeded))
+            {
+                tracing::info!("Order {order_id} has been paid");
+                self.update_order_status(&order_id, OrderStatus::Paid);
+                continue;
+            }
+            let held_legs: Vec<&OrderLegInfo> = order
+                .legs
+                .iter()
+                .filter(|l| hodl_invoices.contains_key(&l.payment_hash))
+                .collect();
+            if leg_statuses.contains(&Some(HTLCStatus::Failed)) {
+                for leg in held_legs {
+                    let _ = self.cancel_hodl_invoice(&hex_str(&leg.payment_hash.0));
+                }
+                tracing::info!("Order {order_id} has failed");
+                self.update_order_status(&order_id, OrderStatus::Failed);
+                continue;
+            }
+            // legs are settled all together, wait for the pending claims to complete
+            if held_legs.len() < order.legs.len() {
+                continue;
+            }
+            let all_received = order.legs.iter().all(|l| {
+                hodl_invoices
+                    .get(&l.payment_hash)
+                    .is_some_and(|h| h.claimable_amt_msat.is_some())
+            });
+            if all_received {
+                for leg in held_legs {
+                    let payment_hash = hex_str(&leg.payment_hash.0);
+                    let Some(preimage) = inbound_payments
+                        .get(&leg.payment_hash)
+                        .and_then(|p| p.preimage)
+                    else {
+                        continue;
+                    };
+                    if let Err(e) = self.settle_hodl_invoice(&payment_hash, &hex_str(&preimage.0)) {
+                        tracing::error!(
+                            "Failed to settle leg {payment_hash} of order {order_id}: {e}"
+                        );
+                    }
+                }
+            } else if now >= order.expires_at {
+                // legs received so far are given back to the payers
+                for leg in held_legs {
+                    let _ = self.cancel_hodl_invoice(&hex_str(&leg.payment_hash.0));
+                }
+                tracing::info!("Order {order_id} has expired");
+                self.update_order_status(&order_id, OrderStatus::Expired);
+            }
+        }
+    }
+}
+
+/// Retry strategy and routing limits of an outbound payment
+#[derive(Clone, Copy)]
+struct PaymentLimits {
+    timeout: Option<Duration>,
+    max_retries: Option<u32>,
+    max_fee_msat: Option<u64>,
+    max_fee_ppm: Option<u32>,
+    max_total_cltv_expiry_delta: u32,
+    max_parts: Option<u8>,
+    max_shard_amt_msat: Option<u64>,
+}
+
+impl PaymentLimits {
+    fn new(max_total_cltv_expiry_delta: u32) -> Self {
+        Self {
+            timeout: None,
+            max_retries: None,
+            max_fee_msat: None,
+            max_fee_ppm: None,
+            max_total_cltv_expiry_delta,
+            max_parts: None,
+            max_shard_amt_msat: None,
+        }
+    }
+
+    fn retry_strategy(&self) -> Retry {
+        match (self.max_retries, self.timeout) {
+            (Some(max_retries), _) => Retry::Attempts(max_retries),
+            (None, Some(timeout)) => Retry::Timeout(timeout),
+            (None, None) => Retry::Timeout(Duration::from_secs(DEFAULT_PAYMENT_RETRY_TIMEOUT_SEC)),
+        }
+    }
+
+    fn route_params_config(&self, amt_msat: u64) -> RouteParametersConfig {
+        let max_fee_ppm_msat = self
+            .max_fee_ppm
+            .map(|ppm| (amt_msat as u128 * ppm as u128 / 1_000_000) as u64);
+        let max_total_routing_fee_msat = match (self.max_fee_msat, max_fee_ppm_msat) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        let mut config = RouteParametersConfig {
+            max_total_routing_fee_msat,
+            max_total_cltv_expiry_delta: self.max_total_cltv_expiry_delta,
+            ..Default::default()
+        };
+        if let Some(max_parts) = self.max_parts {
+            config.max_path_count = max_parts;
+        }
+        config
+    }
+
+    /// Number of parts a payment of the given amount needs to be split in
+    fn num_parts(&self, amt_msat: u64) -> Result<u64, APIError> {
+        let max_parts = self.max_parts.unwrap_or(MAX_PAYMENT_PARTS) as u64;
+        let Some(max_shard_amt_msat) = self.max_shard_amt_msat else {
+            return Ok(1);
+        };
+        let num_parts = amt_msat.div_ceil(max_shard_amt_msat);
+        if num_parts > max_parts {
+            return Err(APIError::InvalidPaymentLimits(format!(
+                "{amt_msat}msat cannot be split in {max_parts} parts of at most {max_shard_amt_msat}msat"
+            )));
+        }
+        Ok(num_parts)
+    }
+}
+
+fn check_payment_limits(
+    payload: &SendPaymentRequest,
+    node_max_total_cltv_expiry_delta: u32,
+) -> Result<PaymentLimits, APIError> {
+    let mut limits = PaymentLimits::new(node_max_total_cltv_expiry_delta);
+    if let Some(timeout_sec) = payload.timeout_sec {
+        if timeout_sec == 0 || timeout_sec > MAX_PAYMENT_TIMEOUT_SEC {
+            return Err(APIError::InvalidPaymentLimits(format!(
+                "timeout_sec must be between 1 and {MAX_PAYMENT_TIMEOUT_SEC}"
+            )));
+        }
+        limits.timeout = Some(Duration::from_secs(timeout_sec));
+    }
+    if let Some(max_fee_ppm) = payload.max_fee_ppm {
+        if max_fee_ppm > 1_000_000 {
+            return Err(APIError::InvalidPaymentLimits(s!(
+                "max_fee_ppm cannot be more than 1000000"
+            )));
+        }
+    }
+    if let Some(max_cltv_expiry_delta) = payload.max_cltv_expiry_delta {
+        if max_cltv_expiry_delta > node_max_total_cltv_expiry_delta {
+            return Err(APIError::InvalidPaymentLimits(format!(
+                "max_cltv_expiry_delta cannot be more than {node_max_total_cltv_expiry_delta}"
+            )));
+        }
+        limits.max_total_cltv_expiry_delta = max_cltv_expiry_delta;
+    }
+    if let Some(max_parts) = payload.max_parts {
+        if max_parts == 0 || max_parts > MAX_PAYMENT_PARTS {
+            return Err(APIError::InvalidPaymentLimits(format!(
+                "max_parts must be between 1 and {MAX_PAYMENT_PARTS}"
+            )));
+        }
+    }
+    if payload.max_shard_amt_msat == Some(0) {
+        return Err(APIError::InvalidPaymentLimits(s!(
+            "max_shard_amt_msat must be positive"
+        )));
+    }
+    limits.max_retries = payload.max_retries;
+    limits.max_fee_msat = payload.max_fee_msat;
+    limits.max_fee_ppm = payload.max_fee_ppm;
+    limits.max_parts = payload.max_parts;
+    limits.max_shard_amt_msat = payload.max_shard_amt_msat;
+    Ok(limits)
+}
+
+/// Time a payment has reached its final status, if it has
+fn payment_resolved_at(payment_info: &PaymentInfo) -> Option<u64> {
+    matches!(
+        payment_info.status,
+        HTLCStatus::Succeeded | HTLCStatus::Failed
+    )
+    .then_some(payment_info.updated_at)
+}
+
+/// Aggregate the routing fees of the delivered parts of a payment by asset
+///
+/// Fees are always paid in msat, also when the parts carry an RGB asset
+fn payment_fees(parts: &[PaymentPartInfo]) -> Vec<PaymentFee> {
+    let mut fees: Vec<PaymentFee> = vec![];
+    for part in parts
+        .iter()
+        .filter(|p| matches!(p.status, HTLCStatus::Succeeded))
+    {
+        let idx = match fees.iter().position(|f| f.asset_id == part.asset_id) {
+            Some(idx) => idx,
+            None => {
+                fees.push(PaymentFee {
+                    asset_id: part.asset_id.clone(),
+                    asset_amount: part.asset_id.as_ref().map(|_| 0),
+                    amt_msat: 0,
+                    fee_msat: 0,
+                    num_parts: 0,
+                });
+                fees.len() - 1
+            }
+        };
+        let fee = &mut fees[idx];
+        fee.asset_amount = fee.asset_amount.map(|a| a + part.asset_amount.unwrap_or(0));
+        fee.amt_msat += part.amt_msat;
+        fee.fee_msat += part.fee_msat;
+        fee.num_parts += 1;
+    }
+    fees
+}
+
+fn check_address_network(address: &str, network: RgbLibNetwork) -> Result<(), APIError> {
+    let address =
+        Address::from_str(address).map_err(|e| APIError::InvalidAddress(e.to_string()))?;
+    if !address.is_valid_for_network(network.into()) {
+        return Err(APIError::InvalidNetwork(format!(
+            "address is not valid on {network}"
+        )));
+    }
+    Ok(())
+}
+
+/// Check the options of a channel open funded by an external PSBT
+fn check_external_funding(
+    external_funding: ExternalFunding,
+    counterparty_node_id: PublicKey,
+    capacity_sat: u64,
+) -> Result<ExternalFundingInfo, APIError> {
+    let timeout_sec = external_funding
+        .timeout_sec
+        .unwrap_or(DEFAULT_FUNDING_TIMEOUT_SEC);
+    if timeout_sec == 0 || timeout_sec > MAX_FUNDING_TIMEOUT_SEC {
+        return Err(APIError::InvalidExternalFunding(format!(
+            "timeout_sec must be between 1 and {MAX_FUNDING_TIMEOUT_SEC}"
+        )));
+    }
+    let created_at = get_current_timestamp();
+    Ok(ExternalFundingInfo {
+        counterparty_node_id,
+        capacity_sat,
+        funding_script: None,
+        created_at,
+        expires_at: created_at + timeout_sec,
+    })
+}
+
+/// Check the options of a channel open funded by a multisig treasury
+fn check_multisig_funding(
+    multisig_funding: MultisigFunding,
+    counterparty_node_id: PublicKey,
+    capacity_sat: u64,
+    network: RgbLibNetwork,
+) -> Result<MultisigFundingInfo, APIError> {
+    if multisig_funding.inputs.is_empty() {
+        return Err(APIError::InvalidMultisigFunding(s!(
+            "at least one input is required"
+        )));
+    }
+    let mut inputs = vec![];
+    for input in multisig_funding.inputs {
+        let outpoint = OutPoint::from_str(&input.outpoint).map_err(|_| {
+            APIError::InvalidMultisigFunding(format!("invalid outpoint {}", input.outpoint))
+        })?;
+        let witness_script = ScriptBuf::from_hex(&input.witness_script)
+            .ok()
+            .filter(|s| get_two_of_three_multisig_pubkeys(s).is_some())
+            .ok_or_else(|| {
+                APIError::InvalidMultisigFunding(format!(
+                    "witness script of {outpoint} is not a 2-of-3 multisig"
+                ))
+            })?;
+        inputs.push(MultisigFundingInput {
+            outpoint,
+            txout: TxOut {
+                value: Amount::from_sat(input.amount_sat),
+                script_pubkey: ScriptBuf::new_p2wsh(&witness_script.wscript_hash()),
+            },
+            witness_script,
+        });
+    }
+
+    check_address_network(&multisig_funding.change_address, network)?;
+    let change_script = Address::from_str(&multisig_funding.change_address)
+        .unwrap()
+        .assume_checked()
+        .script_pubkey();
+
+    if multisig_funding.fee_rate < MIN_FUNDING_FEE_RATE {
+        return Err(APIError::InvalidFeeRate(format!(
+            "fee_rate cannot be less than {MIN_FUNDING_FEE_RATE}"
+        )));
+    }
+
+    let timeout_sec = multisig_funding
+        .timeout_sec
+        .unwrap_or(DEFAULT_FUNDING_TIMEOUT_SEC);
+    if timeout_sec == 0 || timeout_sec > MAX_FUNDING_TIMEOUT_SEC {
+        return Err(APIError::InvalidMultisigFunding(format!(
+            "timeout_sec must be between 1 and {MAX_FUNDING_TIMEOUT_SEC}"
+        )));
+    }
+
+    let created_at = get_current_timestamp();
+    let info = MultisigFundingInfo {
+        counterparty_node_id,
+        capacity_sat,
+        inputs,
+        change_script,
+        fee_rate: multisig_funding.fee_rate,
+        unsigned_psbt: None,
+        created_at,
+        expires_at: created_at + timeout_sec,
+    };
+    let required_sat = capacity_sat + info.fee_sat();
+    if info.inputs_amount_sat() < required_sat {
+        return Err(APIError::InvalidMultisigFunding(format!(
+            "inputs amount ({} sats) is lower than the capacity plus fees ({required_sat} sats)",
+            info.inputs_amount_sat()
+        )));
+    }
+    Ok(info)
+}
+
+/// Estimate the feerate (in sat/KW) needed to confirm a transaction within the given blocks
+fn estimate_feerate_sat_per_kw(
+    unlocked_state: &UnlockedAppState,
+    blocks: u16,
+) -> Result<u32, APIError> {
+    let fee_rate = unlocked_state.rgb_get_fee_estimation(blocks)?;
+    Ok((fee_rate * 250.0).ceil() as u32)
+}
+
+/// Add the final witness to the inputs of a funding PSBT signed by the multisig treasury, unless
+/// the signer already finalized them
+fn finalize_multisig_psbt(psbt: &mut Psbt) -> Result<(), APIError> {
+    for (i, input) in psbt.inputs.iter_mut().enumerate() {
+        if input.final_script_witness.is_some() {
+            continue;
+        }
+        let pubkeys = input
+            .witness_script
+            .as_ref()
+            .and_then(|s| get_two_of_three_multisig_pubkeys(s))
+            .ok_or_else(|| {
+                APIError::InvalidFundingPsbt(format!("input {i} is not a 2-of-3 multisig"))
+            })?;
+        // signatures must follow the order of the keys in the script
+        let sigs = pubkeys
+            .iter()
+            .filter_map(|pk| input.partial_sigs.get(pk))
+            .take(2)
+            .map(|sig| sig.to_vec())
+            .collect::<Vec<_>>();
+        if sigs.len() < 2 {
+            return Err(APIError::InvalidFundingPsbt(format!(
+                "input {i} has {} of the 2 required signatures",
+                sigs.len()
+            )));
+        }
+        let mut witness = Witness::new();
+        // dummy element consumed by OP_CHECKMULTISIG
+        witness.push(Vec::<u8>::new());
+        for sig in sigs {
+            witness.push(sig);
+        }
+        witness.push(input.witness_script.as_ref().unwrap().as_bytes());
+        input.final_script_witness = Some(witness);
+        input.partial_sigs.clear();
+    }
+    Ok(())
+}
+
+/// Get the keys of a 2-of-3 multisig witness script, in script order
+fn get_two_of_three_multisig_pubkeys(script: &Script) -> Option<Vec<bitcoin::PublicKey>> {
+    let instructions = script.instructions().collect::<Result<Vec<_>, _>>().ok()?;
+    let [Instruction::Op(m), Instruction::PushBytes(k1), Instruction::PushBytes(k2), Instruction::PushBytes(k3), Instruction::Op(n), Instruction::Op(op)] =
+        instructions.as_slice()
+    else {
+        return None;
+    };
+    if *m != OP_PUSHNUM_2 || *n != OP_PUSHNUM_3 || *op != OP_CHECKMULTISIG {
+        return None;
+    }
+    [k1, k2, k3]
+        .iter()
+        .map(|k| bitcoin::PublicKey::from_slice(k.as_bytes()).ok())
+        .collect()
+}
+
+/// Score each suggestion between 0 and 1, combining its centrality in the network graph, its
+/// fee policy, the success rate of the probes sent to it and its support for RGB assets
+fn score_peer_suggestions(suggestions: &mut [PeerSuggestion], goal: PeerSuggestionGoal) {
+    let max_channels = suggestions
+        .iter()
+        .map(|s| s.num_channels)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let max_capacity = suggestions
+        .iter()
+        .map(|s| s.total_capacity_sat)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let max_ppm = suggestions
+        .iter()
+        .map(|s| s.avg_fee_proportional_millionths)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    for suggestion in suggestions {
+        let centrality = 0.5 * suggestion.num_channels as f64 / max_channels as f64
+            + 0.5 * suggestion.total_capacity_sat as f64 / max_capacity as f64;
+        let fee_level = suggestion.avg_fee_proportional_millionths as f64 / max_ppm as f64;
+        // nodes that have never been probed are considered as likely to be up as not
+        let uptime = suggestion.probe_success_rate.unwrap_or(0.5);
+        let assets = if suggestion.supports_assets { 1.0 } else { 0.0 };
+        suggestion.score = match goal {
+            // well connected nodes charging higher fees signal demand for the liquidity
+            PeerSuggestionGoal::RoutingRevenue => {
+                0.5 * centrality + 0.3 * fee_level + 0.1 * uptime + 0.1 * assets
+            }
+            PeerSuggestionGoal::PaymentReliability => {
+                0.4 * centrality + 0.2 * (1.0 - fee_level) + 0.3 * uptime + 0.1 * assets
+            }
+        };
+    }
+}
+
+fn check_broadcast_schedule(broadcast_at: Option<u64>) -> Result<(), APIError> {
+    if let Some(broadcast_at) = broadcast_at {
+        if broadcast_at <= get_current_timestamp() {
+            return Err(APIError::InvalidBroadcastSchedule(s!(
+                "broadcast_at must be in the future"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Check the requested address type, as the RGB wallet derives its vanilla addresses from a
+/// BIP86 account
+fn check_address_type(address_type: AddressType) -> Result<(), APIError> {
+    if address_type != AddressType::P2tr {
+        return Err(APIError::UnsupportedAddressType(format!(
+            "{address_type:?}, the wallet only derives P2tr addresses"
+        )));
+    }
+    Ok(())
+}
+
+/// Check the amounts accepted by an invoice, which can only be bounded when the payer chooses
+/// the amount
+fn check_amount_bounds(
+    amt_msat: Option<u64>,
+    min_amt_msat: Option<u64>,
+    max_amt_msat: Option<u64>,
+) -> Result<(), APIError> {
+    if min_amt_msat.is_none() && max_amt_msat.is_none() {
+        return Ok(());
+    }
+    if amt_msat.is_some() {
+        return Err(APIError::InvalidAmount(s!(
+            "min_amt_msat and max_amt_msat cannot be provided along with amt_msat"
+        )));
+    }
+    if max_amt_msat == Some(0) {
+        return Err(APIError::InvalidAmount(s!(
+            "max_amt_msat must be greater than 0"
+        )));
+    }
+    if let (Some(min_amt_msat), Some(max_amt_msat)) = (min_amt_msat, max_amt_msat) {
+        if min_amt_msat > max_amt_msat {
+            return Err(APIError::InvalidAmount(format!(
+                "min_amt_msat of {min_amt_msat} cannot be more than max_amt_msat of {max_amt_msat}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Amount to be paid for an invoice and the RGB asset it transfers, if any
+fn check_invoice_amounts(
+    invoice: &Bolt11Invoice,
+    amt_msat: Option<u64>,
+) -> Result<(u64, Option<(ContractId, u64)>), APIError> {
+    let zero_amt_invoice =
+        invoice.amount_milli_satoshis().is_none() || invoice.amount_milli_satoshis() == Some(0);
+
+    let amt_msat = if zero_amt_invoice {
+        match amt_msat {
+            Some(0) => {
+                return Err(APIError::InvalidAmount(s!(
+                    "amount for the given 0-value invoice must be greater than 0"
+                )))
+            }
+            Some(amt_msat) => amt_msat,
+            None => {
+                return Err(APIError::InvalidAmount(s!(
+                    "need an amount for the given 0-value invoice"
+                )))
+            }
+        }
+    } else {
+        if amt_msat.is_some() && invoice.amount_milli_satoshis() != amt_msat {
+            return Err(APIError::InvalidAmount(format!(
+                "amount didn't match invoice value of {}msat",
+                invoice.amount_milli_satoshis().unwrap_or(0)
+            )));
+        }
+        invoice.amount_milli_satoshis().unwrap_or(0)
+    };
+
+    let rgb_payment = match (invoice.rgb_contract_id(), invoice.rgb_amount()) {
+        (Some(rgb_contract_id), Some(rgb_amount)) => {
+            if amt_msat < INVOICE_MIN_MSAT {
+                return Err(APIError::InvalidAmount(format!(
+                    "msat amount in invoice sending an RGB asset cannot be less than {INVOICE_MIN_MSAT}"
+                )));
+            }
+            Some((rgb_contract_id, rgb_amount))
+        }
+        (None, None) => None,
+        (Some(_), None) => {
+            return Err(APIError::InvalidInvoice(s!(
+                "invoice has an RGB contract ID but not an RGB amount"
+            )))
+        }
+        (None, Some(_)) => {
+            return Err(APIError::InvalidInvoice(s!(
+                "invoice has an RGB amount but not an RGB contract ID"
+            )))
+        }
+    };
+
+    Ok((amt_msat, rgb_payment))
+}
+
+fn check_label(label: String) -> Result<String, APIError> {
+    if label.trim().is_empty() {
+        return Err(APIError::InvalidLabel(s!("label cannot be empty")));
+    }
+    if label.len() > MAX_LABEL_LEN {
+        return Err(APIError::InvalidLabel(format!(
+            "label cannot be longer than {MAX_LABEL_LEN} bytes"
+        )));
+    }
+    Ok(label)
+}
+
+fn check_nonce(nonce: &str) -> Result<String, APIError> {
+    let nonce = nonce.trim().to_string();
+    if nonce.is_empty() {
+        return Err(APIError::InvalidNonce(s!("cannot be empty")));
+    }
+    if nonce.len() > MAX_NONCE_LEN {
+        return Err(APIError::InvalidNonce(format!(
+            "cannot be longer than {MAX_NONCE_LEN} bytes"
+        )));
+    }
+    if nonce.contains(['\n', '\r']) {
+        return Err(APIError::InvalidNonce(s!("cannot contain line breaks")));
+    }
+    Ok(nonce)
+}
+
+/// Witness script of the P2WSH outputs assets are burned to, which can never be spent
+fn burn_witness_script() -> ScriptBuf {
+    ScriptBuf::builder().push_opcode(OP_RETURN).into_script()
+}
+
+/// Outputs of closed channels not swept yet, from the balances still timelocked in the channel
+/// monitors to the sweeps waiting for enough confirmations to be forgotten by the sweeper
+async fn get_sweeps(unlocked_state: &UnlockedAppState, ldk_data_dir: &Path) -> Vec<Sweep> {
+    let mut sweeps = vec![];
+
+    let open_channels = unlocked_state.channel_manager.list_channels();
+    for channel_id in unlocked_state.chain_monitor.list_monitors() {
+        if open_channels.iter().any(|c| c.channel_id == channel_id) {
+            continue;
+        }
+        let Ok(monitor) = unlocked_state.chain_monitor.get_monitor(channel_id) else {
+            continue;
+        };
+        let asset_id = get_rgb_channel_info_optional(&channel_id, ldk_data_dir, false)
+            .map(|(rgb_info, _)| rgb_info.contract_id.to_string());
+        for balance in monitor.get_claimable_balances() {
+            let Balance::ClaimableAwaitingConfirmations {
+                amount_satoshis,
+                confirmation_height,
+                ..
+            } = balance
+            else {
+                continue;
+            };
+            sweeps.push(Sweep {
+                channel_id: Some(channel_id.0.as_hex().to_string()),
+                outpoint: None,
+                amount_sat: amount_satoshis,
+                asset_id: asset_id.clone(),
+                asset_amount: None,
+                status: SweepStatus::Timelocked,
+                spendable_height: Some(confirmation_height),
+                sweep_txid: None,
+                broadcast_height: None,
+                confirmation_height: None,
+            });
+        }
+    }
+
+    let current_height = unlocked_state.channel_manager.current_best_block().height;
+    for output in unlocked_state.output_sweeper.tracked_spendable_outputs() {
+        let (outpoint, txout) = spendable_output(&output.descriptor);
+        let (asset_id, asset_amount) = match close_output_allocation(ldk_data_dir, &outpoint.txid) {
+            Some((contract_id, amount)) => (Some(contract_id.to_string()), Some(amount)),
+            None => (None, None),
+        };
+        let mut sweep = Sweep {
+            channel_id: output.channel_id.map(|c| c.0.as_hex().to_string()),
+            outpoint: Some(outpoint.to_string()),
+            amount_sat: txout.value.to_sat(),
+            asset_id,
+            asset_amount,
+            status: SweepStatus::PendingBroadcast,
+            spendable_height: None,
+            sweep_txid: None,
+            broadcast_height: None,
+            confirmation_height: None,
+        };
+        match &output.status {
+            OutputSpendStatus::PendingInitialBroadcast {
+                delayed_until_height,
+            } => {
+                if delayed_until_height.is_some_and(|h| h > current_height) {
+                    sweep.status = SweepStatus::Timelocked;
+                }
+                sweep.spendable_height = *delayed_until_height;
+            }
+            OutputSpendStatus::PendingFirstConfirmation {
+                latest_broadcast_height,
+                latest_spending_tx,
+                ..
+            } => {
+                // a sweep that is neither confirmed nor in the mempool has been refused
+                let txid = latest_spending_tx.compute_txid();
+                sweep.status = match unlocked_state
+                    .bitcoind_client
+                    .get_mempool_entry(&txid)
+                    .await
+                {
+                    Some(_) => SweepStatus::PendingConfirmation,
+                    None => SweepStatus::FailedBroadcast,
+                };
+                sweep.sweep_txid = Some(txid.to_string());
+                sweep.broadcast_height = Some(*latest_broadcast_height);
+            }
+            OutputSpendStatus::PendingThresholdConfirmations {
+                latest_broadcast_height,
+                latest_spending_tx,
+                confirmation_height,
+                ..
+            } => {
+                sweep.status = SweepStatus::Confirming;
+                sweep.sweep_txid = Some(latest_spending_tx.compute_txid().to_string());
+                sweep.broadcast_height = Some(*latest_broadcast_height);
+                sweep.confirmation_height = Some(*confirmation_height);
+            }
+        }
+        sweeps.push(sweep);
+    }
+
+    sweeps
+}
+
+/// Get the path of the uploaded media with the given digest, which needs to be a SHA256 hex
+/// digest of an existing media file
+fn get_media_path(unlocked_state: &UnlockedAppState, digest: &str) -> Result<PathBuf, APIError> {
+    let digest = digest.to_lowercase();
+    if sha256::Hash::from_str(&digest).is_err() {
+        return Err(APIError::InvalidMediaDigest);
+    }
+    let file_path = unlocked_state.rgb_get_media_dir().join(digest);
+    if !file_path.is_file() {
+        return Err(APIError::InvalidMediaDigest);
+    }
+    Ok(file_path)
+}
+
+/// Check the period is a month in the YYYY-MM format
+fn check_period(period: &str) -> Result<(), APIError> {
+    let valid = match period.split_once('-') {
+        Some((year, month)) => {
+            year.len() == 4
+                && month.len() == 2
+                && year.parse::<u16>().is_ok()
+                && month.parse::<u8>().is_ok_and(|m| (1..=12).contains(&m))
+        }
+        None => false,
+    };
+    if !valid {
+        return Err(APIError::InvalidPeriod(format!(
+            "{period} is not a month in the YYYY-MM format"
+        )));
+    }
+    Ok(())
+}
+
+/// Check the pagination args of a list API and return the offset of the requested page, cursors
+/// being opaque to clients, which only get them from the `next_cursor` of the previous page
+fn page_offset(limit: Option<usize>, cursor: Option<&str>) -> Result<usize, APIError> {
+    if let Some(limit) = limit {
+        if limit == 0 || limit > MAX_PAGE_SIZE {
+            return Err(APIError::InvalidPagination(format!(
+                "limit must be between 1 and {MAX_PAGE_SIZE}"
+            )));
+        }
+    }
+    match cursor {
+        Some(cursor) => cursor
+            .parse()
+            .map_err(|_| APIError::InvalidPagination(format!("invalid cursor {cursor}"))),
+        None => Ok(0),
+    }
+}
+
+fn check_invoice_network(invoice: &Bolt11Invoice, network: RgbLibNetwork) -> Result<(), APIError> {
+    if invoice.currency() != Currency::from(Network::from(network)) {
+        return Err(APIError::InvalidNetwork(format!(
+            "invoice is not valid on {network}"
+        )));
+    }
+    Ok(())
+}
+
+fn check_offer_network(offer: &Offer, network: RgbLibNetwork) -> Result<(), APIError> {
+    if !offer.supports_chain(ChainHash::using_genesis_block(Network::from(network))) {
+        return Err(APIError::InvalidNetwork(format!(
+            "offer is not valid on {network}"
+        )));
+    }
+    Ok(())
+}
+
+/// Connect to the LSP, using its known address when none is provided
+async fn connect_lsp(
+    state: &Arc<AppState>,
+    unlocked_state: &UnlockedAppState,
+    peer_pubkey_and_opt_addr: String,
+) -> Result<PublicKey, APIError> {
+    let (lsp_pubkey, mut lsp_addr) = parse_peer_info(peer_pubkey_and_opt_addr)?;
+    let peer_data_path = state.static_state.ldk_data_dir.join(CHANNEL_PEER_DATA);
+    if lsp_addr.is_none() {
+        if unlocked_state
+            .peer_manager
+            .peer_by_node_id(&lsp_pubkey)
+            .is_some()
+        {
+            return Ok(lsp_pubkey);
+        }
+        let peer_info = disk::read_channel_peer_data(&peer_data_path)?;
+        lsp_addr = peer_info
+            .into_iter()
+            .find(|(pubkey, _)| *pubkey == lsp_pubkey)
+            .map(|(_, addr)| addr);
+    }
+    let Some(lsp_addr) = lsp_addr else {
+        return Err(APIError::InvalidPeerInfo(s!(
+            "cannot find the address for the provided pubkey"
+        )));
+    };
+    connect_peer_if_necessary(
+        lsp_pubkey,
+        &lsp_addr,
+        unlocked_state.peer_manager.clone(),
+        state.static_state.tor_proxy.as_ref(),
+        CircuitGroup::Payments,
+    )
+    .await?;
+    // keep reconnecting to the LSP, which needs us online to open the JIT channel
+    disk::persist_channel_peer(&peer_data_path, &lsp_pubkey, &lsp_addr)?;
+    Ok(lsp_pubkey)
+}
+
+fn parse_phantom_route_hints(route_hints: Vec<String>) -> Result<Vec<PhantomRouteHints>, APIError> {
+    route_hints
+        .into_iter()
+        .map(|route_hints| {
+            hex_str_to_vec(&route_hints)
+                .and_then(|bytes| PhantomRouteHints::read(&mut &bytes[..]).ok())
+                .ok_or_else(|| {
+                    APIError::InvalidPhantomOptions(format!("invalid route hints {route_hints}"))
+                })
+        })
+        .collect()
+}
+
+fn parse_route_hints(route_hints: Vec<InvoiceRouteHint>) -> Result<Vec<RouteHint>, APIError> {
+    route_hints
+        .into_iter()
+        .map(|route_hint| {
+            if route_hint.hops.is_empty() {
+                return Err(APIError::InvalidRouteHint(s!(
+                    "a route hint needs at least a hop"
+                )));
+            }
+            let hops = route_hint
+                .hops
+                .into_iter()
+                .map(|hop| {
+                    let src_node_id =
+                        hex_str_to_compressed_pubkey(&hop.src_node_id).ok_or_else(|| {
+                            APIError::InvalidRouteHint(format!(
+                                "invalid pubkey {}",
+                                hop.src_node_id
+                            ))
+                        })?;
+                    Ok(RouteHintHop {
+                        src_node_id,
+                        short_channel_id: hop.short_channel_id,
+                        fees: RoutingFees {
+                            base_msat: hop.fee_base_msat,
+                            proportional_millionths: hop.fee_proportional_millionths,
+                        },
+                        cltv_expiry_delta: hop.cltv_expiry_delta,
+                        htlc_minimum_msat: hop.htlc_minimum_msat,
+                        htlc_maximum_msat: hop.htlc_maximum_msat,
+                        htlc_maximum_rgb: None,
+                    })
+                })
+                .collect::<Result<Vec<_>, APIError>>()?;
+            Ok(RouteHint(hops))
+        })
+        .collect()
+}
+
+async fn process_invoices_batch<F>(
+    unlocked_state: Arc<UnlockedAppState>,
+    items: Vec<(String, Option<String>)>,
+    process: F,
+) -> Result<Vec<InvoiceResult>, APIError>
+where
+    F: Fn(&UnlockedAppState, &str, Option<&str>) -> Result<(), APIError> + Copy + Send + 'static,
+{
+    if items.is_empty() {
+        return Err(APIError::InvalidInvoiceBatch(s!(
+            "no invoice has been provided"
+        )));
+    }
+    if items.len() > MAX_INVOICES_BATCH_SIZE {
+        return Err(APIError::InvalidInvoiceBatch(format!(
+            "cannot process more than {MAX_INVOICES_BATCH_SIZE} invoices at once"
+        )));
+    }
+
+    let tasks = items.into_iter().map(|(payment_hash, payment_preimage)| {
+        let unlocked_state = unlocked_state.clone();
+        tokio::task::spawn_blocking(move || {
+            let error = process(&unlocked_state, &payment_hash, payment_preimage.as_deref())
+                .err()
+                .map(|e| e.to_string());
+            InvoiceResult {
+                payment_hash,
+                error,
+            }
+        })
+    });
+
+    let mut results = vec![];
+    for res in futures::future::join_all(tasks).await {
+        results.push(res.map_err(|e| APIError::Unexpected(e.to_string()))?);
+    }
+    Ok(results)
+}
+
+pub(crate) async fn abandon_payment(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<AbandonPaymentRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let payment_hash = check_payment_hash(&payload.payment_hash)?;
+        let payment_id = PaymentId(payment_hash.0);
+        if !unlocked_state.outbound_payments().contains_key(&payment_id) {
+            return Err(APIError::PaymentNotFound(payload.payment_hash));
+        }
+        // HTLCs already in flight can still succeed, the payment fails once they all resolve
+        if !unlocked_state.abandon_pending_payment(payment_id, "on request") {
+            return Err(APIError::PaymentNotPending);
+        }
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn accelerate_incoming(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<AccelerateIncomingRequest>, APIError>,
+) -> Result<Json<AccelerateIncomingResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let _rgb_spend_guard = unlocked_state.rgb_spend_lock.lock().await;
+        if *unlocked_state.rgb_send_lock.lock().unwrap() {
+            return Err(APIError::OpenChannelInProgress);
+        }
+        unlocked_state.check_totp(payload.totp_code.as_deref(), TotpOperation::Spend)?;
+
+        let (txid, fee_sat) = unlocked_state
+            .accelerate_incoming(payload.idx, payload.fee_rate, payload.max_fee_sat)
+            .await?;
+
+        Ok(Json(AccelerateIncomingResponse {
+            txid: txid.to_string(),
+            fee_sat,
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn accept_channel(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<AcceptChannelRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let temporary_channel_id = check_channel_id(&payload.temporary_channel_id)?;
+        unlocked_state
+            .accept_pending_channel(temporary_channel_id, state.static_state.min_channel_depth)?;
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn address(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Query(payload), _): WithRejection<Query<AddressRequest>, APIError>,
+) -> Result<Json<AddressResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let address_type = payload.address_type.unwrap_or(AddressType::P2tr);
+    check_address_type(address_type)?;
+
+    let address = unlocked_state
+        .issue_address(state.static_state.address_gap_limit)
+        .await?;
+
+    Ok(Json(AddressResponse {
+        address,
+        address_type,
+    }))
+}
+
+pub(crate) async fn address_stats(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<AddressStatsResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    unlocked_state.update_issued_addresses_usage().await?;
+    let issued_addresses = unlocked_state.get_issued_addresses();
+    let mut unused_addresses: Vec<(u32, String)> = issued_addresses
+        .addresses
+        .iter()
+        .filter(|(_, info)| !info.used)
+        .map(|(address, info)| (info.index, address.clone()))
+        .collect();
+    unused_addresses.sort();
+
+    Ok(Json(AddressStatsResponse {
+        issued: issued_addresses.addresses.len() as u32,
+        used: issued_addresses.addresses.len() as u32 - unused_addresses.len() as u32,
+        gap: issued_addresses.gap(),
+        gap_limit: state.static_state.address_gap_limit,
+        unused_addresses: unused_addresses.into_iter().map(|(_, a)| a).collect(),
+    }))
+}
+
+pub(crate) async fn anchor_reserve(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<AnchorReserveResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let (reserve_utxos, events, depleted) = {
+        let anchor_reserve = unlocked_state.get_anchor_reserve();
+        (
+            anchor_reserve.utxos.clone(),
+            anchor_reserve.events.clone(),
+            anchor_reserve.depleted,
+        )
+    };
+    let mut utxos = vec![];
+    for (outpoint, info) in reserve_utxos {
+        let confirmations = match OutPoint::from_str(&outpoint) {
+            Ok(o) => unlocked_state
+                .bitcoind_client
+                .get_txout_confirmations(&o.txid, o.vout)
+                .await
+                .ok()
+                .flatten(),
+            Err(_) => None,
+        };
+        utxos.push(AnchorReserveUtxo {
+            outpoint,
+            amount_sat: info.amount_sat,
+            confirmations,
+            created_at: info.created_at,
+        });
+    }
+    utxos.sort_by_key(|u| u.created_at);
+
+    Ok(Json(AnchorReserveResponse {
+        target_utxos: state.static_state.anchor_reserve_utxos,
+        utxo_size_sat: state.static_state.anchor_reserve_utxo_size_sat,
+        depleted,
+        utxos,
+        events: events
+            .into_iter()
+            .map(|e| AnchorReserveEvent {
+                timestamp: e.timestamp,
+                kind: e.kind,
+                details: e.details,
+            })
+            .collect(),
+    }))
+}
+
+pub(crate) async fn approve_broadcast(
+    State(state): State<Arc<AppState>>,
+    WithRejection(AxumPath(txid), _): WithRejection<AxumPath<String>, APIError>,
+    WithRejection(Json(payload), _): WithRejection<Json<ApproveBroadcastRequest>, APIError>,
+) -> Result<Json<ApproveBroadcastResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        unlocked_state.check_totp(payload.totp_code.as_deref(), TotpOperation::Spend)?;
+
+        let info = unlocked_state
+            .approve_pending_broadcast(&txid)
+            .ok_or(APIError::UnknownPendingBroadcast)?;
+
+        // when an RGB channel opening is in progress the broadcast is left to the scheduler
+        let broadcast =
+            info.is_due(get_current_timestamp()) && !*unlocked_state.rgb_send_lock.lock().unwrap();
+        if broadcast {
+            let unlocked_state_copy = unlocked_state.clone();
+            tokio::task::spawn_blocking(move || unlocked_state_copy.broadcast_pending(&txid))
+                .await
+                .unwrap()?;
+        }
+
+        Ok(Json(ApproveBroadcastResponse { broadcast }))
+    })
+    .await
+}
+
+pub(crate) async fn asset_audit(
+    State(state): State<Arc<AppState>>,
+    WithRejection(AxumPath(asset_id), _): WithRejection<AxumPath<String>, APIError>,
+) -> Result<Json<AssetAuditResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let contract_id =
+        ContractId::from_str(&asset_id).map_err(|_| APIError::InvalidAssetID(asset_id.clone()))?;
+
+    let metadata = unlocked_state.rgb_get_asset_metadata(contract_id)?;
+    let mut anomalies = vec![];
+
+    // offchain amounts, keeping track of channel funding outpoints to exclude channel opening
+    // transfers from the sent amount and to detect allocations counted twice
+    let mut held_offchain = 0;
+    let mut funding_outpoints = HashMap::new();
+    for chan_info in unlocked_state.channel_manager.list_channels() {
+        let Some((rgb_info, _)) = get_rgb_channel_info_optional(
+            &chan_info.channel_id,
+            &state.static_state.ldk_data_dir,
+            false,
+        ) else {
+            continue;
+        };
+        if rgb_info.contract_id != contract_id {
+            continue;
+        }
+        held_offchain += rgb_info.local_rgb_amount;
+        if let Some(funding_txo) = chan_info.funding_txo {
+            funding_outpoints.insert(
+                format!("{}:{}", funding_txo.txid, funding_txo.index),
+                chan_info.channel_id,
+            );
+        }
+    }
+
+    let burn_script_pubkey = ScriptBuf::new_p2wsh(&burn_witness_script().wscript_hash());
+    let mut issued_locally = 0;
+    let mut received = 0;
+    let mut sent = 0;
+    for transfer in unlocked_state.rgb_list_transfers(asset_id.clone())? {
+        if !matches!(transfer.status, rgb_lib::TransferStatus::Settled) {
+            continue;
+        }
+        match transfer.kind {
+            rgb_lib::TransferKind::Issuance | rgb_lib::TransferKind::Inflation => {
+                issued_locally += transfer
+                    .assignments
+                    .iter()
+                    .map(assignment_amount)
+                    .sum::<u64>()
+            }
+            rgb_lib::TransferKind::ReceiveBlind | rgb_lib::TransferKind::ReceiveWitness => {
+                received += transfer
+                    .assignments
+                    .iter()
+                    .map(assignment_amount)
+                    .sum::<u64>()
+            }
+            rgb_lib::TransferKind::Send => {
+                let is_channel_funding = transfer.txid.as_ref().is_some_and(|txid| {
+                    funding_outpoints
+                        .keys()
+                        .any(|o| o.starts_with(&format!("{txid}:")))
+                });
+                let is_burn = transfer.recipient_id.as_ref().is_some_and(|r| {
+                    script_buf_from_recipient_id(r.clone())
+                        .is_ok_and(|s| s.as_ref() == Some(&burn_script_pubkey))
+                });
+                if !is_channel_funding && !is_burn {
+                    sent += transfer
+                        .requested_assignment
+                        .as_ref()
+                        .map(assignment_amount)
+                        .unwrap_or(0);
+                }
+            }
+        }
+    }
+
+    let mut held_onchain = 0;
+    let mut allocated_utxos = HashMap::new();
+    for unspent in unlocked_state.rgb_list_unspents(true)? {
+        let outpoint = unspent.utxo.outpoint.to_string();
+        for allocation in unspent
+            .rgb_allocations
+            .iter()
+            .filter(|a| a.settled && a.asset_id.as_ref() == Some(&asset_id))
+        {
+            held_onchain += assignment_amount(&allocation.assignment);
+            *allocated_utxos.entry(outpoint.clone()).or_insert(0) += 1;
+        }
+    }
+    for outpoint in allocated_utxos.keys() {
+        if let Some(channel_id) = funding_outpoints.get(outpoint) {
+            anomalies.push(format!(
+                "UTXO {outpoint} is both an on-chain allocation and the funding of channel {channel_id}"
+            ));
+        }
+    }
+    if matches!(metadata.asset_schema, RgbLibAssetSchema::Uda) && allocated_utxos.len() > 1 {
+        anomalies.push(format!(
+            "non-fungible token allocated to {} UTXOs",
+            allocated_utxos.len()
+        ));
+    }
+
+    let held = held_onchain + held_offchain;
+    if held > metadata.known_circulating_supply {
+        anomalies.push(format!(
+            "held amount {held} exceeds the issued supply {}",
+            metadata.known_circulating_supply
+        ));
+    }
+
+    // what entered the wallet and is neither held nor sent has been destroyed (e.g. with
+    // /burnasset or by spending a colored UTXO without a transfer)
+    let burned = (issued_locally + received).saturating_sub(held + sent);
+
+    Ok(Json(AssetAuditResponse {
+        asset_id,
+        asset_schema: metadata.asset_schema.into(),
+        issued_supply: metadata.known_circulating_supply,
+        issued_locally,
+        received,
+        sent,
+        held_onchain,
+        held_offchain,
+        burned,
+        anomalies,
+    }))
+}
+
+pub(crate) async fn asset_balance(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<AssetBalanceRequest>, APIError>,
+) -> Result<Json<AssetBalanceResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let contract_id = ContractId::from_str(&payload.asset_id)
+        .map_err(|_| APIError::InvalidAssetID(payload.asset_id))?;
+
+    let balance = unlocked_state.rgb_get_asset_balance(contract_id)?;
+
+    let mut offchain_outbound = 0;
+    let mut offchain_inbound = 0;
+    for chan_info in unlocked_state.channel_manager.list_channels() {
+        let info_file_path = get_rgb_channel_info_path(
+            &chan_info.channel_id.0.as_hex().to_string(),
+            &state.static_state.ldk_data_dir,
+            false,
+        );
+        if !info_file_path.exists() {
+            continue;
+        }
+        let rgb_info = parse_rgb_channel_info(&info_file_path);
+        if rgb_info.contract_id == contract_id {
+            offchain_outbound += rgb_info.local_rgb_amount;
+            offchain_inbound += rgb_info.remote_rgb_amount;
+        }
+    }
+
+    Ok(Json(AssetBalanceResponse {
+        settled: balance.settled,
+        future: balance.future,
+        spendable: balance.spendable,
+        offchain_outbound,
+        offchain_inbound,
+    }))
+}
+
+pub(crate) async fn asset_metadata(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<AssetMetadataRequest>, APIError>,
+) -> Result<Json<AssetMetadataResponse>, APIError> {
+    let contract_id = ContractId::from_str(&payload.asset_id)
+        .map_err(|_| APIError::InvalidAssetID(payload.asset_id))?;
+
+    let metadata = state
+        .check_unlocked()
+        .await?
+        .clone()
+        .unwrap()
+        .rgb_get_asset_metadata(contract_id)?;
+
+    Ok(Json(AssetMetadataResponse {
+        asset_schema: metadata.asset_schema.into(),
+        initial_supply: metadata.initial_supply,
+        max_supply: metadata.max_supply,
+        known_circulating_supply: metadata.known_circulating_supply,
+        timestamp: metadata.timestamp,
+        name: metadata.name,
+        precision: metadata.precision,
+        ticker: metadata.ticker,
+        details: metadata.details,
+        token: metadata.token.map(|t| t.into()),
+    }))
+}
+
+pub(crate) async fn audit(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Query(payload), _): WithRejection<Query<AuditRequest>, APIError>,
+) -> Result<Json<AuditResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let nonce = payload.nonce.as_deref().map(check_nonce).transpose()?;
+    let best_block = unlocked_state.channel_manager.current_best_block();
+    if let Some(block_height) = payload.block_height {
+        if block_height != best_block.height {
+            return Err(APIError::InvalidBlockHeight(format!(
+                "requested {block_height} but the node is at height {}",
+                best_block.height
+            )));
+        }
+    }
+
+    let btc_balance = unlocked_state.rgb_get_btc_balance(false)?;
+
+    let mut channels = vec![];
+    let mut offchain_balances: HashMap<String, (u64, u64)> = HashMap::new();
+    for chan_info in unlocked_state.channel_manager.list_channels() {
+        let mut channel = AuditSnapshotChannel {
+            channel_id: chan_info.channel_id.0.as_hex().to_string(),
+            peer_pubkey: chan_info.counterparty.node_id.to_string(),
+            funding_txid: chan_info.funding_txo.map(|o| o.txid.to_string()),
+            ready: chan_info.is_channel_ready,
+            capacity_sat: chan_info.channel_value_satoshis,
+            claimable_balance_sat: 0,
+            asset_id: None,
+            asset_local_amount: None,
+            asset_remote_amount: None,
+        };
+        if let Ok(chan_monitor) = unlocked_state
+            .chain_monitor
+            .get_monitor(chan_info.channel_id)
+        {
+            channel.claimable_balance_sat = chan_monitor
+                .get_claimable_balances()
+                .iter()
+                .map(|b| b.claimable_amount_satoshis())
+                .sum::<u64>();
+        }
+        let info_file_path = get_rgb_channel_info_path(
+            &chan_info.channel_id.0.as_hex().to_string(),
+            &state.static_state.ldk_data_dir,
+            false,
+        );
+        if info_file_path.exists() {
+            let rgb_info = parse_rgb_channel_info(&info_file_path);
+            let asset_id = rgb_info.contract_id.to_string();
+            let (outbound, inbound) = offchain_balances.entry(asset_id.clone()).or_default();
+            *outbound += rgb_info.local_rgb_amount;
+            *inbound += rgb_info.remote_rgb_amount;
+            channel.asset_id = Some(asset_id);
+            channel.asset_local_amount = Some(rgb_info.local_rgb_amount);
+            channel.asset_remote_amount = Some(rgb_info.remote_rgb_amount);
+        }
+        channels.push(channel);
+    }
+    channels.sort_by(|a, b| a.channel_id.cmp(&b.channel_id));
+
+    let rgb_assets = unlocked_state.rgb_list_assets(
+        [
+            AssetSchema::Nia,
+            AssetSchema::Uda,
+            AssetSchema::Cfa,
+            AssetSchema::Ifa,
+        ]
+        .into_iter()
+        .map(|s| s.into())
+        .collect(),
+    )?;
+    let settled_balances = rgb_assets
+        .nia
+        .unwrap_or_default()
+        .into_iter()
+        .map(|a| (a.asset_id, a.balance.settled))
+        .chain(
+            rgb_assets
+                .uda
+                .unwrap_or_default()
+                .into_iter()
+                .map(|a| (a.asset_id, a.balance.settled)),
+        )
+        .chain(
+            rgb_assets
+                .cfa
+                .unwrap_or_default()
+                .into_iter()
+                .map(|a| (a.asset_id, a.balance.settled)),
+        )
+        .chain(
+            rgb_assets
+                .ifa
+                .unwrap_or_default()
+                .into_iter()
+                .map(|a| (a.asset_id, a.balance.settled)),
+        );
+    let mut assets: Vec<AuditSnapshotAsset> = settled_balances
+        .map(|(asset_id, settled)| {
+            let (offchain_outbound, offchain_inbound) =
+                offchain_balances.remove(&asset_id).unwrap_or_default();
+            AuditSnapshotAsset {
+                asset_id,
+                settled,
+                offchain_outbound,
+                offchain_inbound,
+            }
+        })
+        .collect();
+    // assets only known through a channel are reported too
+    assets.extend(offchain_balances.into_iter().map(
+        |(asset_id, (offchain_outbound, offchain_inbound))| AuditSnapshotAsset {
+            asset_id,
+            settled: 0,
+            offchain_outbound,
+            offchain_inbound,
+        },
+    ));
+    assets.sort_by(|a, b| a.asset_id.cmp(&b.asset_id));
+
+    let snapshot = AuditSnapshot {
+        pubkey: unlocked_state.channel_manager.get_our_node_id().to_string(),
+        block_height: best_block.height,
+        block_hash: best_block.block_hash.to_string(),
+        timestamp: get_current_timestamp(),
+        nonce,
+        onchain_vanilla_sat: btc_balance.vanilla.settled,
+        onchain_colored_sat: btc_balance.colored.settled,
+        channels,
+        assets,
+    };
+
+    // the snapshot is signed with the node key, so it can be checked with /verifymessage or any
+    // Lightning implementation supporting message verification (e.g. `lncli verifymessage`)
+    let message = format!(
+        "{AUDIT_SNAPSHOT_MESSAGE_HEADER}\n{}",
+        serde_json::to_string(&snapshot).unwrap()
+    );
+    let signature = lightning::util::message_signing::sign(
+        message.as_bytes(),
+        &unlocked_state.keys_manager.get_node_secret_key(),
+    );
+
+    Ok(Json(AuditResponse {
+        snapshot,
+        message,
+        signature,
+    }))
+}
+
+pub(crate) async fn backup(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<BackupRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let _guard = state.check_locked().await?;
+
+        let _mnemonic =
+            check_password_validity(&payload.password, &state.static_state.storage_dir_path)?;
+
+        do_backup(
+            &state.static_state.storage_dir_path,
+            Path::new(&payload.backup_path),
+            &payload.password,
+        )?;
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn backup_channels(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<BackupChannelsRequest>, APIError>,
+) -> Result<Json<BackupChannelsResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let _mnemonic =
+            check_password_validity(&payload.password, &state.static_state.storage_dir_path)?;
+
+        let backup_dir = tempfile::tempdir_in(&state.static_state.storage_dir_path)?;
+        let num_channels = unlocked_state
+            .write_channel_backup_files(&state.static_state.ldk_data_dir, backup_dir.path())?;
+
+        do_backup(
+            backup_dir.path(),
+            Path::new(&payload.backup_path),
+            &payload.password,
+        )?;
+        tracing::info!("Backed up {num_channels} channels");
+
+        Ok(Json(BackupChannelsResponse { num_channels }))
+    })
+    .await
+}
+
+pub(crate) async fn balance_sheet(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<BalanceSheetRequest>, APIError>,
+) -> Result<Json<BalanceSheetResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+    let ldk_data_dir = &state.static_state.ldk_data_dir;
+
+    let btc_balance = unlocked_state.rgb_get_btc_balance(payload.skip_sync)?;
+    let confirmed_sat = btc_balance.vanilla.settled + btc_balance.colored.settled;
+    let future_sat = btc_balance.vanilla.future + btc_balance.colored.future;
+    let onchain = BalanceSheetOnchain {
+        confirmed_sat,
+        unconfirmed_sat: future_sat as i64 - confirmed_sat as i64,
+        spendable_sat: btc_balance.vanilla.spendable + btc_balance.colored.spendable,
+    };
+
+    let mut assets: Vec<BalanceSheetAsset> = vec![];
+    for asset_id in unlocked_state.rgb_list_asset_ids()? {
+        let contract_id = ContractId::from_str(&asset_id).unwrap();
+        let balance = unlocked_state.rgb_get_asset_balance(contract_id)?;
+        let ticker = unlocked_state.rgb_get_asset_metadata(contract_id)?.ticker;
+        assets.push(BalanceSheetAsset {
+            asset_id,
+            ticker,
+            settled: balance.settled,
+            future: balance.future,
+            spendable: balance.spendable,
+            offchain_outbound: 0,
+            offchain_inbound: 0,
+            pending_close: 0,
+            pending_sweep: 0,
+            htlc_inbound: 0,
+            htlc_outbound: 0,
+        });
+    }
+    let asset_entry = |assets: &mut Vec<BalanceSheetAsset>, asset_id: String| {
+        assets.iter_mut().position(|a| a.asset_id == asset_id)
+    };
+
+    // the close balances of open channels are what they hold, the ones of the others are pending
+    let open_channels = unlocked_state.channel_manager.list_channels();
+    let mut channels = BalanceSheetChannels {
+        local_sat: 0,
+        remote_sat: 0,
+        pending_open_sat: 0,
+    };
+    for chan_info in &open_channels {
+        let local_sat = unlocked_state
+            .chain_monitor
+            .get_monitor(chan_info.channel_id)
+            .map(|monitor| {
+                monitor
+                    .get_claimable_balances()
+                    .iter()
+                    .filter(|b| matches!(b, Balance::ClaimableOnChannelClose { .. }))
+                    .map(|b| b.claimable_amount_satoshis())
+                    .sum::<u64>()
+            })
+            .unwrap_or(0);
+        if chan_info.is_channel_ready {
+            channels.local_sat += local_sat;
+            channels.remote_sat += chan_info.inbound_capacity_msat / 1000;
+        } else {
+            channels.pending_open_sat += local_sat;
+        }
+        let info_file_path = get_rgb_channel_info_path(
+            &chan_info.channel_id.0.as_hex().to_string(),
+            ldk_data_dir,
+            false,
+        );
+        if info_file_path.exists() {
+            let rgb_info = parse_rgb_channel_info(&info_file_path);
+            if let Some(idx) = asset_entry(&mut assets, rgb_info.contract_id.to_string()) {
+                assets[idx].offchain_outbound += rgb_info.local_rgb_amount;
+                assets[idx].offchain_inbound += rgb_info.remote_rgb_amount;
+            }
+        }
+    }
+
+    let mut pending_close_sat = 0;
+    for channel_id in unlocked_state.chain_monitor.list_monitors() {
+        if open_channels.iter().any(|c| c.channel_id == channel_id) {
+            continue;
+        }
+        let Ok(monitor) = unlocked_state.chain_monitor.get_monitor(channel_id) else {
+            continue;
+        };
+        let claimable_sat = monitor
+            .get_claimable_balances()
+            .iter()
+            .map(|b| b.claimable_amount_satoshis())
+            .sum::<u64>();
+        if claimable_sat == 0 {
+            continue;
+        }
+        pending_close_sat += claimable_sat;
+        let info_file_path =
+            get_rgb_channel_info_path(&channel_id.0.as_hex().to_string(), ldk_data_dir, false);
+        if info_file_path.exists() {
+            let rgb_info = parse_rgb_channel_info(&info_file_path);
+            if let Some(idx) = asset_entry(&mut assets, rgb_info.contract_id.to_string()) {
+                assets[idx].pending_close += rgb_info.local_rgb_amount;
+            }
+        }
+    }
+
+    // once broadcast, sweeps are part of the on-chain balance
+    let mut pending_sweep_sat = 0;
+    for output in unlocked_state.output_sweeper.tracked_spendable_outputs() {
+        if !matches!(
+            output.status,
+            OutputSpendStatus::PendingInitialBroadcast { .. }
+        ) {
+            continue;
+        }
+        let (outpoint, txout) = spendable_output(&output.descriptor);
+        pending_sweep_sat += txout.value.to_sat();
+        if let Some((contract_id, amount)) = close_output_allocation(ldk_data_dir, &outpoint.txid) {
+            if let Some(idx) = asset_entry(&mut assets, contract_id.to_string()) {
+                assets[idx].pending_sweep += amount;
+            }
+        }
+    }
+
+    let mut htlcs = BalanceSheetHtlcs {
+        inbound_msat: 0,
+        outbound_msat: 0,
+    };
+    for htlc in unlocked_state.list_htlcs(ldk_data_dir) {
+        let inbound = htlc.direction == HtlcDirection::Inbound;
+        if inbound {
+            htlcs.inbound_msat += htlc.amt_msat;
+        } else {
+            htlcs.outbound_msat += htlc.amt_msat;
+        }
+        if let (Some(asset_id), Some(asset_amount)) = (htlc.asset_id, htlc.asset_amount) {
+            if let Some(idx) = asset_entry(&mut assets, asset_id) {
+                if inbound {
+                    assets[idx].htlc_inbound += asset_amount;
+                } else {
+                    assets[idx].htlc_outbound += asset_amount;
+                }
+            }
+        }
+    }
+
+    Ok(Json(BalanceSheetResponse {
+        onchain,
+        channels,
+        pending_close_sat,
+        pending_sweep_sat,
+        htlcs,
+        assets,
+    }))
+}
+
+pub(crate) async fn broadcast_psbt(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<BroadcastPsbtRequest>, APIError>,
+) -> Result<Json<BroadcastPsbtResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let psbt =
+            Psbt::from_str(&payload.psbt).map_err(|e| APIError::InvalidPsbt(e.to_string()))?;
+        if let Some(idx) = psbt
+            .inputs
+            .iter()
+            .position(|i| i.final_script_witness.is_none() && i.final_script_sig.is_none())
+        {
+            return Err(APIError::InvalidPsbt(format!(
+                "input {idx} is not finalized"
+            )));
+        }
+        let tx = psbt
+            .extract_tx()
+            .map_err(|e| APIError::InvalidPsbt(e.to_string()))?;
+        let txid = unlocked_state
+            .bitcoind_client
+            .send_raw_transaction(&tx)
+            .await
+            .map_err(|e| APIError::FailedBroadcast(e.to_string()))?;
+        tracing::info!("Broadcast PSBT TX {txid}");
+
+        Ok(Json(BroadcastPsbtResponse {
+            txid: txid.to_string(),
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn btc_balance(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<BtcBalanceRequest>, APIError>,
+) -> Result<Json<BtcBalanceResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let btc_balance = unlocked_state.rgb_get_btc_balance(payload.skip_sync)?;
+
+    let vanilla = BtcBalance {
+        settled: btc_balance.vanilla.settled,
+        future: btc_balance.vanilla.future,
+        spendable: btc_balance.vanilla.spendable,
+    };
+
+    let colored = BtcBalance {
+        settled: btc_balance.colored.settled,
+        future: btc_balance.colored.future,
+        spendable: btc_balance.colored.spendable,
+    };
+
+    Ok(Json(BtcBalanceResponse { vanilla, colored }))
+}
+
+pub(crate) async fn bump_close_fee(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<BumpCloseFeeRequest>, APIError>,
+) -> Result<Json<BumpCloseFeeResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let channel_id = check_channel_id(&payload.channel_id)?;
+        unlocked_state.check_totp(payload.totp_code.as_deref(), TotpOperation::Spend)?;
+
+        if unlocked_state
+            .channel_manager
+            .list_channels()
+            .iter()
+            .any(|c| c.channel_id == channel_id)
+        {
+            return Err(APIError::CannotBumpCloseFee(s!(
+                "channel has not been force-closed"
+            )));
+        }
+        let claimable_sat = match unlocked_state.chain_monitor.get_monitor(channel_id) {
+            Ok(monitor) => monitor
+                .get_claimable_balances()
+                .iter()
+                .map(|b| b.claimable_amount_satoshis())
+                .sum::<u64>(),
+            Err(_) => return Err(APIError::UnknownChannelId),
+        };
+        if claimable_sat == 0 {
+            return Err(APIError::CannotBumpCloseFee(s!(
+                "channel has no pending claims"
+            )));
+        }
+
+        let fee_rate_sat_per_kw =
+            estimate_feerate_sat_per_kw(unlocked_state, payload.confirmation_target)?;
+        unlocked_state.set_force_close_feerate(channel_id, fee_rate_sat_per_kw);
+        unlocked_state.add_channel_event(
+            channel_id,
+            ChannelEventKind::CloseFeeBumped,
+            format!("targeting {fee_rate_sat_per_kw} sat/KW"),
+        );
+
+        // regenerate the bump events of pending claims, which will now use the new feerate
+        unlocked_state.chain_monitor.rebroadcast_pending_claims();
+        tracing::info!("Bumping close fee of channel {channel_id} to {fee_rate_sat_per_kw} sat/KW");
+
+        Ok(Json(BumpCloseFeeResponse {
+            fee_rate_sat_per_kw,
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn bump_fee(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<BumpFeeRequest>, APIError>,
+) -> Result<Json<BumpFeeResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let txid = Txid::from_str(&payload.txid).map_err(|_| APIError::UnknownTransaction)?;
+        unlocked_state.check_totp(payload.totp_code.as_deref(), TotpOperation::Spend)?;
+
+        let (txid, method) = unlocked_state
+            .bump_fee(txid, payload.fee_rate, payload.skip_sync)
+            .await?;
+
+        Ok(Json(BumpFeeResponse {
+            txid: txid.to_string(),
+            method,
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn burn_asset(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<BurnAssetRequest>, APIError>,
+) -> Result<Json<BurnAssetResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        state.check_draining()?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        // held until the burn completes, so that no channel open can start meanwhile
+        let _rgb_spend_guard = unlocked_state.rgb_spend_lock.lock().await;
+        if *unlocked_state.rgb_send_lock.lock().unwrap() {
+            return Err(APIError::OpenChannelInProgress);
+        }
+
+        let contract_id = ContractId::from_str(&payload.asset_id)
+            .map_err(|_| APIError::InvalidAssetID(payload.asset_id.clone()))?;
+        let assignment = match unlocked_state
+            .rgb_get_asset_metadata(contract_id)?
+            .asset_schema
+        {
+            RgbLibAssetSchema::Uda if payload.amount != 1 => {
+                return Err(APIError::InvalidAmount(s!(
+                    "amount must be 1 for non-fungible assets"
+                )))
+            }
+            RgbLibAssetSchema::Uda => Assignment::NonFungible,
+            _ if payload.amount == 0 => {
+                return Err(APIError::InvalidAmount(s!("amount must be greater than 0")))
+            }
+            _ => Assignment::Fungible(payload.amount),
+        };
+        unlocked_state.check_totp(payload.totp_code.as_deref(), TotpOperation::Spend)?;
+
+        // the assets are sent to an output nobody can spend, as anyone given the witness script
+        // can verify, and the consignment is posted to the proxy like for any other witness send
+        let witness_script = burn_witness_script();
+        let recipient_id = recipient_id_from_script_buf(
+            ScriptBuf::new_p2wsh(&witness_script.wscript_hash()),
+            state.static_state.network,
+        );
+        let recipient_map = map! {
+            contract_id.to_string() => vec![Recipient {
+                recipient_id,
+                witness_data: Some(RgbLibWitnessData {
+                    amount_sat: DUST_LIMIT_MSAT / 1000,
+                    blinding: None,
+                }),
+                assignment: assignment.into(),
+                transport_endpoints: vec![unlocked_state.proxy_endpoint.clone()],
+            }]
+        };
+
+        let unlocked_state_copy = unlocked_state.clone();
+        let txid = tokio::task::spawn_blocking(move || {
+            unlocked_state_copy.rgb_send(
+                recipient_map,
+                true,
+                payload.fee_rate,
+                payload.min_confirmations,
+                payload.skip_sync,
+            )
+        })
+        .await
+        .unwrap()?
+        .txid;
+        tracing::info!(
+            "Burned {} of asset {contract_id} in TX {txid}",
+            payload.amount
+        );
+
+        Ok(Json(BurnAssetResponse {
+            txid,
+            witness_script: witness_script.to_hex_string(),
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn buy_inbound(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<BuyInboundRequest>, APIError>,
+) -> Result<Json<BuyInboundResponse>, APIError> {
+    no_cancel(async move {
+        let (lease, payment_hash) = {
+            let guard = state.check_unlocked().await?;
+            state.check_draining()?;
+            let unlocked_state = guard.as_ref().unwrap();
+
+            match (&payload.asset_id, payload.asset_amount) {
+                (Some(asset_id), Some(_)) => {
+                    ContractId::from_str(asset_id)
+                        .map_err(|_| APIError::InvalidAssetID(asset_id.clone()))?;
+                }
+                (None, None) => {}
+                _ => return Err(APIError::IncompleteRGBInfo),
+            }
+            unlocked_state.check_totp(payload.totp_code.as_deref(), TotpOperation::Spend)?;
+
+            let seller_pubkey =
+                connect_lsp(&state, unlocked_state, payload.peer_pubkey_and_opt_addr).await?;
+            let lease = unlocked_state
+                .lsps_handler
+                .lease_buy(
+                    &unlocked_state.peer_manager,
+                    seller_pubkey,
+                    payload.capacity_sat,
+                    payload.asset_id,
+                    payload.asset_amount,
+                )
+                .await?;
+
+            // the fee actually charged is the invoice amount
+            let invoice = Bolt11Invoice::from_str(&lease.invoice)
+                .map_err(|e| APIError::FailedLspRequest(format!("invalid fee invoice: {e}")))?;
+            if invoice.get_payee_pub_key() != seller_pubkey {
+                return Err(APIError::FailedLspRequest(s!(
+                    "the fee invoice is not payable to the seller"
+                )));
+            }
+            let fee_sat = invoice
+                .amount_milli_satoshis()
+                .unwrap_or(u64::MAX)
+                .div_ceil(1000);
+            if fee_sat > payload.max_fee_sat {
+                return Err(APIError::CannotBuyInbound(format!(
+                    "lease fee of {fee_sat} sat exceeds the max of {} sat",
+                    payload.max_fee_sat
+                )));
+            }
+            (lease, hex_str(invoice.payment_hash().as_byte_array()))
+        };
+
+        let send_payload = SendPaymentRequest {
+            invoice: lease.invoice,
+            amt_msat: None,
+            timeout_sec: None,
+            max_retries: None,
+            max_fee_msat: None,
+            max_fee_ppm: None,
+            max_cltv_expiry_delta: None,
+            max_parts: None,
+            max_shard_amt_msat: None,
+            totp_code: None,
+            dry_run: false,
+        };
+        let Json(payment) =
+            send_payment(State(state), WithRejection(Json(send_payload), PhantomData)).await?;
+        tracing::info!(
+            "Bought inbound lease {} for {} sat",
+            lease.order_id,
+            lease.fee_sat
+        );
+
+        Ok(Json(BuyInboundResponse {
+            order_id: lease.order_id,
+            fee_sat: lease.fee_sat,
+            lease_duration_blocks: lease.lease_duration_blocks,
+            payment_hash,
+            payment_status: payment.status,
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn cancel_invoice(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CancelInvoiceRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        unlocked_state.cancel_hodl_invoice(&payload.payment_hash)?;
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn cancel_invoices(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CancelInvoicesRequest>, APIError>,
+) -> Result<Json<CancelInvoicesResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap().clone();
+
+        let items = payload
+            .payment_hashes
+            .into_iter()
+            .map(|payment_hash| (payment_hash, None))
+            .collect();
+        let results = process_invoices_batch(unlocked_state, items, |u, payment_hash, _| {
+            u.cancel_hodl_invoice(payment_hash)
+        })
+        .await?;
+
+        Ok(Json(CancelInvoicesResponse { results }))
+    })
+    .await
+}
+
+pub(crate) async fn chain_events(
+    State(state): State<Arc<AppState>>,
+    WithRejection(AxumPath(subscription_id), _): WithRejection<AxumPath<String>, APIError>,
+) -> Result<Json<ChainEventsResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let events = unlocked_state
+        .chain_subscription(&subscription_id)
+        .ok_or(APIError::UnknownChainSubscription)?
+        .events
+        .into_iter()
+        .map(|e| ChainEvent {
+            timestamp: e.timestamp,
+            kind: e.kind,
+            txid: e.txid.to_string(),
+            outpoint: e.outpoint.map(|o| o.to_string()),
+            amount_sat: e.amount_sat,
+            block_height: e.block_height,
+        })
+        .collect();
+
+    Ok(Json(ChainEventsResponse {
+        subscription_id,
+        events,
+    }))
+}
+
+pub(crate) async fn chain_subscriptions(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ChainSubscriptionsRequest>, APIError>,
+) -> Result<Json<ChainSubscriptionsResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let (txid, script) = match (payload.txid, payload.script, payload.address) {
+            (Some(txid), None, None) => {
+                let txid = Txid::from_str(&txid).map_err(|_| {
+                    APIError::InvalidChainSubscription(format!("invalid txid {txid}"))
+                })?;
+                (Some(txid), None)
+            }
+            (None, Some(script), None) => {
+                let script = hex_str_to_vec(&script).ok_or_else(|| {
+                    APIError::InvalidChainSubscription(format!("invalid script {script}"))
+                })?;
+                (None, Some(ScriptBuf::from_bytes(script)))
+            }
+            (None, None, Some(address)) => {
+                check_address_network(&address, state.static_state.network)?;
+                let script = Address::from_str(&address)
+                    .unwrap()
+                    .assume_checked()
+                    .script_pubkey();
+                (None, Some(script))
+            }
+            _ => {
+                return Err(APIError::InvalidChainSubscription(s!(
+                    "exactly one of txid, script and address must be provided"
+                )))
+            }
+        };
+
+        // only blocks mined after the subscription are scanned
+        let start_height = unlocked_state
+            .bitcoind_client
+            .get_blockchain_info()
+            .await
+            .latest_height as u32
+            + 1;
+        let subscription_id = hex_str(&unlocked_state.keys_manager.get_secure_random_bytes());
+        let added = unlocked_state.add_chain_subscription(
+            subscription_id.clone(),
+            ChainSubscriptionInfo {
+                txid,
+                script,
+                start_height,
+                created_at: get_current_timestamp(),
+                watched_outpoints: vec![],
+                events: vec![],
+            },
+        );
+        if !added {
+            return Err(APIError::InvalidChainSubscription(format!(
+                "cannot have more than {MAX_CHAIN_SUBSCRIPTIONS} subscriptions"
+            )));
+        }
+        tracing::info!("Added chain subscription {subscription_id}");
+
+        Ok(Json(ChainSubscriptionsResponse {
+            subscription_id,
+            start_height,
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn change_password(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ChangePasswordRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let _guard = state.check_locked().await?;
+
+        check_password_strength(payload.new_password.clone())?;
+
+        let mnemonic =
+            check_password_validity(&payload.old_password, &state.static_state.storage_dir_path)?;
+
+        encrypt_and_save_mnemonic(
+            payload.new_password,
+            mnemonic.to_string(),
+            &get_mnemonic_path(&state.static_state.storage_dir_path),
+        )?;
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn channel_acceptor(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ChannelAcceptorResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    Ok(Json(unlocked_state.get_channel_acceptor().clone().into()))
+}
+
+pub(crate) async fn channel_depths(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ChannelDepthsResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    Ok(Json(ChannelDepthsResponse::new(
+        &unlocked_state.get_channel_depths(),
+        state.static_state.min_channel_depth,
+    )))
+}
+
+pub(crate) async fn channel_events(
+    State(state): State<Arc<AppState>>,
+    WithRejection(AxumPath(channel_id), _): WithRejection<AxumPath<String>, APIError>,
+) -> Result<Json<ChannelEventsResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let mut requested_cid = check_channel_id(&channel_id)?;
+    if let Some(final_cid) = unlocked_state.channel_ids().get(&requested_cid) {
+        requested_cid = *final_cid;
+    }
+
+    let events = unlocked_state
+        .channel_events(&requested_cid)
+        .ok_or(APIError::UnknownChannelId)?
+        .into_iter()
+        .map(|e| ChannelEvent {
+            timestamp: e.timestamp,
+            kind: e.kind,
+            details: e.details,
+        })
+        .collect();
+
+    Ok(Json(ChannelEventsResponse {
+        channel_id: requested_cid.0.as_hex().to_string(),
+        events,
+    }))
+}
+
+pub(crate) async fn channel_status(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Query(payload), _): WithRejection<Query<ChannelStatusRequest>, APIError>,
+) -> Result<Json<ChannelStatusResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let tmp_chan_id = check_channel_id(&payload.temporary_channel_id)?;
+    let final_chan_id = unlocked_state.channel_ids().get(&tmp_chan_id).copied();
+    let chan_id = final_chan_id.unwrap_or(tmp_chan_id);
+    let events = unlocked_state.channel_events(&chan_id).unwrap_or_default();
+    let chan_info = unlocked_state
+        .channel_manager
+        .list_channels()
+        .into_iter()
+        .find(|c| c.channel_id == chan_id);
+    if events.is_empty() && chan_info.is_none() {
+        return Err(APIError::UnknownTemporaryChannelId);
+    }
+
+    // the RGB info of the channel moves from the temporary to the final channel ID on funding
+    let asset_id = [chan_id, tmp_chan_id]
+        .iter()
+        .map(|id| {
+            get_rgb_channel_info_path(
+                &id.0.as_hex().to_string(),
+                &state.static_state.ldk_data_dir,
+                false,
+            )
+        })
+        .find(|path| path.exists())
+        .map(|path| parse_rgb_channel_info(&path).contract_id.to_string());
+
+    let funding_txid = chan_info
+        .as_ref()
+        .and_then(|c| c.funding_txo)
+        .map(|txo| txo.txid.to_string());
+    let confirmations = chan_info
+        .as_ref()
+        .filter(|c| c.funding_txo.is_some())
+        .map(|c| c.confirmations.unwrap_or(0));
+    let has_event = |kind| events.iter().any(|e| e.kind == kind);
+    let stage = if has_event(ChannelEventKind::FundingFailed) {
+        ChannelOpenStage::Failed
+    } else if has_event(ChannelEventKind::Closed) {
+        ChannelOpenStage::Closed
+    } else if has_event(ChannelEventKind::Ready)
+        || chan_info.as_ref().is_some_and(|c| c.is_channel_ready)
+    {
+        ChannelOpenStage::Ready
+    } else if has_event(ChannelEventKind::FundingBroadcast) || confirmations.unwrap_or(0) > 0 {
+        ChannelOpenStage::FundingBroadcast
+    } else if has_event(ChannelEventKind::Pending) {
+        // the acceptor signs the funding of RGB channels once it has validated the consignment,
+        // while the funding TX of vanilla ones is broadcast as soon as it's signed
+        if asset_id.is_some() {
+            ChannelOpenStage::ConsignmentAcked
+        } else {
+            ChannelOpenStage::FundingBroadcast
+        }
+    } else if has_event(ChannelEventKind::ConsignmentSent) {
+        ChannelOpenStage::ConsignmentSent
+    } else {
+        ChannelOpenStage::Negotiating
+    };
+    let last_event = events.last();
+
+    Ok(Json(ChannelStatusResponse {
+        temporary_channel_id: tmp_chan_id.0.as_hex().to_string(),
+        channel_id: final_chan_id.map(|id| id.0.as_hex().to_string()),
+        stage,
+        asset_id,
+        funding_txid,
+        confirmations,
+        required_confirmations: chan_info.and_then(|c| c.confirmations_required),
+        details: last_event.map(|e| e.details.clone()),
+        updated_at: last_event.map(|e| e.timestamp),
+    }))
+}
+
+pub(crate) async fn check_indexer_url(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CheckIndexerUrlRequest>, APIError>,
+) -> Result<Json<CheckIndexerUrlResponse>, APIError> {
+    let indexer_protocol =
+        rgb_lib_check_indexer_url(&payload.indexer_url, state.static_state.network)?.into();
+
+    Ok(Json(CheckIndexerUrlResponse { indexer_protocol }))
+}
+
+pub(crate) async fn check_proxy_endpoint(
+    WithRejection(Json(payload), _): WithRejection<Json<CheckProxyEndpointRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    check_rgb_proxy_endpoint(&payload.proxy_endpoint).await?;
+
+    Ok(Json(EmptyResponse {}))
+}
+
+pub(crate) async fn close_channel(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CloseChannelRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let channel_id_vec = hex_str_to_vec(&payload.channel_id);
+        if channel_id_vec.is_none() || channel_id_vec.as_ref().unwrap().len() != 32 {
+            return Err(APIError::InvalidChannelID);
+        }
+        let requested_cid = ChannelId(channel_id_vec.unwrap().try_into().unwrap());
+
+        let peer_pubkey_vec = match hex_str_to_vec(&payload.peer_pubkey) {
+            Some(peer_pubkey_vec) => peer_pubkey_vec,
+            None => return Err(APIError::InvalidPubkey),
+        };
+        let peer_pubkey = match PublicKey::from_slice(&peer_pubkey_vec) {
+            Ok(peer_pubkey) => peer_pubkey,
+            Err(_) => return Err(APIError::InvalidPubkey),
+        };
+
+        if let Some(chan_details) = unlocked_state
+            .channel_manager
+            .list_channels()
+            .iter()
+            .find(|c| c.channel_id == requested_cid)
+        {
+            match chan_details.channel_shutdown_state {
+                Some(ChannelShutdownState::NotShuttingDown) => {}
+                _ => {
+                    return Err(APIError::CannotCloseChannel(s!(
+                        "Channel is already being closed"
+                    )))
+                }
+            }
+        } else {
+            return Err(APIError::UnknownChannelId);
+        }
+
+        unlocked_state.check_totp(payload.totp_code.as_deref(), TotpOperation::Spend)?;
+
+        if payload.force {
+            if payload.close_address.is_some() || payload.fee_rate.is_some() {
+                return Err(APIError::InvalidCloseOptions(s!(
+                    "close_address and fee_rate are only supported by cooperative closes"
+                )));
+            }
+        } else if payload.confirmation_target.is_some() {
+            return Err(APIError::InvalidCloseOptions(s!(
+                "confirmation_target is only supported by force closes"
+            )));
+        }
+
+        let shutdown_script = if let Some(close_address) = &payload.close_address {
+            check_address_network(close_address, state.static_state.network)?;
+            let script_pubkey = Address::from_str(close_address)
+                .unwrap()
+                .assume_checked()
+                .script_pubkey();
+            Some(ShutdownScript::try_from(script_pubkey).map_err(|_| {
+                APIError::InvalidAddress(s!("address type is not supported for closing"))
+            })?)
+        } else {
+            None
+        };
+        let target_feerate_sat_per_kw = payload
+            .fee_rate
+            .map(|fee_rate| {
+                if fee_rate < MIN_CLOSE_FEE_RATE {
+                    return Err(APIError::InvalidFeeRate(format!(
+                        "fee_rate cannot be less than {MIN_CLOSE_FEE_RATE}"
+                    )));
+                }
+                u32::try_from(fee_rate * 250)
+                    .map_err(|_| APIError::InvalidFeeRate(s!("fee_rate is too high")))
+            })
+            .transpose()?;
+        let force_close_feerate_sat_per_kw = payload
+            .confirmation_target
+            .map(|blocks| estimate_feerate_sat_per_kw(unlocked_state, blocks))
+            .transpose()?;
+
+        unlocked_state.add_channel_event(
+            requested_cid,
+            ChannelEventKind::CloseRequested,
+            if payload.force {
+                s!("force-close requested")
+            } else {
+                s!("cooperative close requested")
+            },
+        );
+
+        if payload.force {
+            if let Some(feerate_sat_per_kw) = force_close_feerate_sat_per_kw {
+                unlocked_state.set_force_close_feerate(requested_cid, feerate_sat_per_kw);
+            }
+            match unlocked_state
+                .channel_manager
+                .force_close_broadcasting_latest_txn(
+                    &requested_cid,
+                    &peer_pubkey,
+                    "Manually force-closed".to_string(),
+                ) {
+                Ok(()) => tracing::info!("EVENT: initiating channel force-close"),
+                Err(e) => match e {
+                    LDKAPIError::APIMisuseError { err } => {
+                        return Err(APIError::FailedClosingChannel(err))
+                    }
+                    _ => return Err(APIError::CannotCloseChannel(format!("{e:?}"))),
+                },
+            }
+        } else {
+            match unlocked_state
+                .channel_manager
+                .close_channel_with_feerate_and_script(
+                    &requested_cid,
+                    &peer_pubkey,
+                    target_feerate_sat_per_kw,
+                    shutdown_script,
+                ) {
+                Ok(()) => tracing::info!("EVENT: initiating channel close"),
+                Err(e) => match e {
+                    LDKAPIError::APIMisuseError { err } => {
+                        return Err(APIError::FailedClosingChannel(err))
+                    }
+                    _ => return Err(APIError::CannotCloseChannel(format!("{e:?}"))),
+                },
+            }
+        }
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn close_detail(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CloseDetailRequest>, APIError>,
+) -> Result<Json<CloseDetailResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let mut channel_id = check_channel_id(&payload.channel_id)?;
+    if let Some(final_cid) = unlocked_state.channel_ids().get(&channel_id) {
+        channel_id = *final_cid;
+    }
+    if unlocked_state
+        .channel_manager
+        .list_channels()
+        .iter()
+        .any(|c| c.channel_id == channel_id)
+    {
+        return Err(APIError::ChannelNotClosed);
+    }
+
+    let balances = match unlocked_state.chain_monitor.get_monitor(channel_id) {
+        Ok(monitor) => Some(monitor.get_claimable_balances()),
+        Err(_) => None,
+    };
+    let mut close_outputs = unlocked_state.close_outputs(&channel_id);
+    let tracked_outputs: Vec<_> = unlocked_state
+        .output_sweeper
+        .tracked_spendable_outputs()
+        .into_iter()
+        .filter(|o| o.channel_id == Some(channel_id))
+        .collect();
+    // outputs tracked before their recording was introduced are only known to the sweeper
+    for output in &tracked_outputs {
+        let (outpoint, txout) = spendable_output(&output.descriptor);
+        if !close_outputs.iter().any(|o| o.outpoint == outpoint) {
+            close_outputs.push(CloseOutputInfo {
+                outpoint,
+                amount_sat: txout.value.to_sat(),
+            });
+        }
+    }
+    if balances.is_none() && close_outputs.is_empty() {
+        return Err(APIError::UnknownChannelId);
+    }
+
+    let pending_claims: Vec<CloseClaim> = balances
+        .unwrap_or_default()
+        .iter()
+        .map(|b| {
+            let (kind, height) = match b {
+                Balance::ClaimableOnChannelClose { .. } => {
+                    (CloseClaimKind::AwaitingCloseConfirmation, None)
+                }
+                Balance::ClaimableAwaitingConfirmations {
+                    confirmation_height,
+                    ..
+                } => (
+                    CloseClaimKind::AwaitingConfirmations,
+                    Some(*confirmation_height),
+                ),
+                Balance::ContentiousClaimable { timeout_height, .. } => {
+                    (CloseClaimKind::Contentious, Some(*timeout_height))
+                }
+                Balance::MaybeTimeoutClaimableHTLC {
+                    claimable_height, ..
+                } => (CloseClaimKind::HtlcTimeout, Some(*claimable_height)),
+                Balance::MaybePreimageClaimableHTLC { expiry_height, .. } => {
+                    (CloseClaimKind::HtlcPreimage, Some(*expiry_height))
+                }
+                Balance::CounterpartyRevokedOutputClaimable { .. } => {
+                    (CloseClaimKind::RevokedOutput, None)
+                }
+            };
+            CloseClaim {
+                kind,
+                amount_sat: b.claimable_amount_satoshis(),
+                height,
+            }
+        })
+        .collect();
+
+    // outputs the sweeper has forgotten have been swept, RGB sweeps being kept on disk
+    let sweep_txes = disk::read_output_spender_txes(&unlocked_state.kv_store, OUTPUT_SPENDER_TXES);
+    let ldk_data_dir = &state.static_state.ldk_data_dir;
+    let outputs: Vec<CloseOutput> = close_outputs
+        .into_iter()
+        .map(|close_output| {
+            let outpoint = close_output.outpoint;
+            let (asset_id, asset_amount) =
+                match close_output_allocation(ldk_data_dir, &outpoint.txid) {
+                    Some((contract_id, amount)) => (Some(contract_id.to_string()), Some(amount)),
+                    None => (None, None),
+                };
+            let tracked = tracked_outputs
+                .iter()
+                .find(|o| spendable_output(&o.descriptor).0 == outpoint);
+            let (status, spendable_height, confirmation_height, sweep_txid) = match tracked
+                .map(|o| &o.status)
+            {
+                Some(OutputSpendStatus::PendingInitialBroadcast {
+                    delayed_until_height,
+                }) => (
+                    CloseOutputStatus::PendingBroadcast,
+                    *delayed_until_height,
+                    None,
+                    None,
+                ),
+                Some(OutputSpendStatus::PendingFirstConfirmation {
+                    latest_spending_tx, ..
+                }) => (
+                    CloseOutputStatus::PendingConfirmation,
+                    None,
+                    None,
+                    Some(latest_spending_tx.compute_txid()),
+                ),
+                Some(OutputSpendStatus::PendingThresholdConfirmations {
+                    latest_spending_tx,
+                    confirmation_height,
+                    ..
+                }) => (
+                    CloseOutputStatus::Confirming,
+                    None,
+                    Some(*confirmation_height),
+                    Some(latest_spending_tx.compute_txid()),
+                ),
+                None => (
+                    CloseOutputStatus::Swept,
+                    None,
+                    None,
+                    sweep_txes
+                        .values()
+                        .find(|tx| tx.input.iter().any(|i| i.previous_output == outpoint))
+                        .map(|tx| tx.compute_txid()),
+                ),
+            };
+            CloseOutput {
+                outpoint: outpoint.to_string(),
+                amount_sat: close_output.amount_sat,
+                asset_id,
+                asset_amount,
+                status,
+                spendable_height,
+                confirmation_height,
+                sweep_txid: sweep_txid.map(|t| t.to_string()),
+            }
+        })
+        .collect();
+
+    let recovered =
+        pending_claims.is_empty() && outputs.iter().all(|o| o.status == CloseOutputStatus::Swept);
+
+    Ok(Json(CloseDetailResponse {
+        channel_id: channel_id.0.as_hex().to_string(),
+        asset_id: get_rgb_channel_info_optional(&channel_id, ldk_data_dir, false)
+            .map(|(rgb_info, _)| rgb_info.contract_id.to_string()),
+        pending_claims,
+        outputs,
+        recovered,
+    }))
+}
+
+pub(crate) async fn compact_storage(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CompactStorageRequest>, APIError>,
+) -> Result<Json<CompactStorageResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let payment_retention_sec = payload
+            .payment_retention_sec
+            .unwrap_or_else(|| state.get_runtime_config().payment_retention_sec);
+        let summary = unlocked_state.compact_storage(&state.static_state, payment_retention_sec)?;
+
+        Ok(Json(CompactStorageResponse {
+            pruned_payments: summary.pruned_payments as u64,
+            removed_monitors: summary.removed_monitors as u64,
+            removed_monitor_updates: summary.removed_monitor_updates as u64,
+            deleted_failed_transfers: summary.deleted_failed_transfers,
+            reclaimed_bytes: summary.reclaimed_bytes,
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn confirm_totp(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ConfirmTotpRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        unlocked_state.confirm_totp(&payload.code)?;
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn connect_peer(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ConnectPeerRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let timeout = match payload.timeout_sec {
+            Some(timeout_sec)
+                if timeout_sec == 0 || timeout_sec > MAX_PEER_CONNECTION_TIMEOUT_SEC =>
+            {
+                return Err(APIError::InvalidConnectOptions(format!(
+                    "timeout_sec must be between 1 and {MAX_PEER_CONNECTION_TIMEOUT_SEC}"
+                )));
+            }
+            Some(timeout_sec) => Duration::from_secs(timeout_sec),
+            None => PEER_CONNECTION_TIMEOUT,
+        };
+        let retries = payload.retries.unwrap_or(0);
+        if retries > MAX_PEER_CONNECTION_RETRIES {
+            return Err(APIError::InvalidConnectOptions(format!(
+                "retries cannot be more than {MAX_PEER_CONNECTION_RETRIES}"
+            )));
+        }
+
+        let (peer_pubkey, peer_addr) = parse_peer_info(payload.peer_pubkey_and_addr.to_string())?;
+
+        if let Some(peer_addr) = peer_addr {
+            // peers we have channels with keep using the circuits of the payments
+            let circuit_group = if unlocked_state
+                .channel_manager
+                .list_channels_with_counterparty(&peer_pubkey)
+                .is_empty()
+            {
+                CircuitGroup::Gossip
+            } else {
+                CircuitGroup::Payments
+            };
+            connect_peer_with_retries(
+                peer_pubkey,
+                &peer_addr,
+                unlocked_state.peer_manager.clone(),
+                state.static_state.tor_proxy.as_ref(),
+                circuit_group,
+                timeout,
+                retries,
+            )
+            .await?;
+            disk::persist_channel_peer(
+                &state.static_state.ldk_data_dir.join(CHANNEL_PEER_DATA),
+                &peer_pubkey,
+                &peer_addr,
+            )?;
+        } else {
+            return Err(APIError::InvalidPeerInfo(s!(
+                "incorrectly formatted peer info. Should be formatted as: `pubkey@host:port`"
+            )));
+        }
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn create_order(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CreateOrderRequest>, APIError>,
+) -> Result<Json<CreateOrderResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        if payload.items.is_empty() || payload.items.len() > MAX_ORDER_ITEMS {
+            return Err(APIError::InvalidOrder(format!(
+                "the number of items must be between 1 and {MAX_ORDER_ITEMS}"
+            )));
+        }
+
+        // items are paid with a single invoice for the sat total and one invoice per asset
+        let mut btc_total_msat = 0;
+        let mut asset_totals: Vec<(String, ContractId, u64)> = vec![];
+        for item in &payload.items {
+            match (item.amt_msat, &item.asset_id, item.asset_amount) {
+                (Some(amt_msat), None, None) if amt_msat > 0 => btc_total_msat += amt_msat,
+                (None, Some(asset_id), Some(asset_amount)) if asset_amount > 0 => {
+                    let contract_id = ContractId::from_str(asset_id)
+                        .map_err(|_| APIError::InvalidAssetID(asset_id.clone()))?;
+                    match asset_totals.iter_mut().find(|(a, _, _)| a == asset_id) {
+                        Some((_, _, total)) => *total += asset_amount,
+                        None => asset_totals.push((asset_id.clone(), contract_id, asset_amount)),
+                    }
+                }
+                _ => {
+                    return Err(APIError::InvalidOrder(s!(
+                        "each item needs either a positive amt_msat or an asset_id with a positive asset_amount"
+                    )))
+                }
+            }
+        }
+
+        let mut legs_params = vec![];
+        if btc_total_msat > 0 {
+            legs_params.push((btc_total_msat, None));
+        }
+        legs_params.extend(
+            asset_totals
+                .into_iter()
+                .map(|(asset_id, contract_id, amount)| {
+                    (
+                        state.static_state.asset_htlc_min_msat,
+                        Some((asset_id, contract_id, amount)),
+                    )
+                }),
+        );
+
+        // legs are held until all of them have been received
+        let mut legs = vec![];
+        for (amt_msat, asset) in legs_params {
+            let payment_preimage =
+                PaymentPreimage(unlocked_state.keys_manager.get_secure_random_bytes());
+            let payment_hash = PaymentHash(Sha256::hash(&payment_preimage.0[..]).to_byte_array());
+            let invoice_params = Bolt11InvoiceParameters {
+                amount_msats: Some(amt_msat),
+                invoice_expiry_delta_secs: Some(payload.expiry_sec),
+                min_final_cltv_expiry_delta: Some(state.static_state.min_final_cltv_expiry_delta),
+                payment_hash: Some(payment_hash),
+                contract_id: asset.as_ref().map(|(_, c, _)| *c),
+                asset_amount: asset.as_ref().map(|(_, _, a)| *a),
+                ..Default::default()
+            };
+            let invoice = unlocked_state.create_hodl_invoice(
+                invoice_params,
+                Some(payment_preimage),
+                None,
+                state.get_runtime_config().max_route_hints,
+                &state.static_state.ldk_data_dir,
+            )?;
+            legs.push(OrderLegInfo {
+                invoice: invoice.to_string(),
+                payment_hash,
+                amt_msat,
+                asset_id: asset.as_ref().map(|(a, _, _)| a.clone()),
+                asset_amount: asset.map(|(_, _, a)| a),
+            });
+        }
+
+        let order_id = hex_str(&unlocked_state.keys_manager.get_secure_random_bytes());
+        let created_at = get_current_timestamp();
+        let order = OrderInfo {
+            items: payload
+                .items
+                .into_iter()
+                .map(|i| OrderItemInfo {
+                    description: i.description,
+                    amt_msat: i.amt_msat,
+                    asset_id: i.asset_id,
+                    asset_amount: i.asset_amount,
+                })
+                .collect(),
+            legs,
+            status: OrderStatus::Pending,
+            created_at,
+            expires_at: created_at + payload.expiry_sec as u64,
+        };
+        unlocked_state.add_order(order_id.clone(), order.clone());
+        tracing::info!("Created order {order_id}");
+
+        Ok(Json(CreateOrderResponse {
+            order: unlocked_state.order_response(order_id, order),
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn create_utxos(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CreateUtxosRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        unlocked_state.rgb_create_utxos(
+            payload.up_to,
+            payload.num.unwrap_or(UTXO_NUM),
+            payload.size.unwrap_or(UTXO_SIZE_SAT),
+            payload.fee_rate,
+            payload.skip_sync,
+        )?;
+        tracing::debug!("UTXO creation complete");
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn decode_ln_invoice(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<DecodeLNInvoiceRequest>, APIError>,
+) -> Result<Json<DecodeLNInvoiceResponse>, APIError> {
+    let _guard = state.get_unlocked_app_state();
+
+    let invoice = match Bolt11Invoice::from_str(&payload.invoice) {
+        Err(e) => return Err(APIError::InvalidInvoice(e.to_string())),
+        Ok(v) => v,
+    };
+
+    Ok(Json(DecodeLNInvoiceResponse {
+        amt_msat: invoice.amount_milli_satoshis(),
+        expiry_sec: invoice.expiry_time().as_secs(),
+        timestamp: invoice.duration_since_epoch().as_secs(),
+        asset_id: invoice.rgb_contract_id().map(|c| c.to_string()),
+        asset_amount: invoice.rgb_amount(),
+        payment_hash: hex_str(&invoice.payment_hash().to_byte_array()),
+        payment_secret: hex_str(&invoice.payment_secret().0),
+        payee_pubkey: invoice.payee_pub_key().map(|p| p.to_string()),
+        network: invoice.network().into(),
+    }))
+}
+
+pub(crate) async fn decode_rgb_invoice(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<DecodeRGBInvoiceRequest>, APIError>,
+) -> Result<Json<DecodeRGBInvoiceResponse>, APIError> {
+    let _guard = state.get_unlocked_app_state();
+
+    let invoice_data = RgbLibInvoice::new(payload.invoice)?.invoice_data();
+    let recipient_info = RecipientInfo::new(invoice_data.recipient_id.clone())?;
+
+    Ok(Json(DecodeRGBInvoiceResponse {
+        recipient_id: invoice_data.recipient_id,
+        recipient_type: recipient_info.recipient_type.into(),
+        asset_schema: invoice_data.asset_schema.map(|s| s.into()),
+        asset_id: invoice_data.asset_id,
+        assignment: invoice_data.assignment.into(),
+        network: invoice_data.network.into(),
+        expiration_timestamp: invoice_data.expiration_timestamp,
+        transport_endpoints: invoice_data.transport_endpoints,
+    }))
+}
+
+pub(crate) async fn descriptors(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<DescriptorsResponse>, APIError> {
+    state.check_unlocked().await?;
+
+    let descriptor = |fname: &str| -> Result<String, APIError> {
+        let account_xpub =
+            std::fs::read_to_string(state.static_state.storage_dir_path.join(fname))?;
+        let descriptor = format!("tr({}/{WALLET_KEYCHAIN}/*)", account_xpub.trim());
+        // parsing adds the checksum external tools expect
+        Ok(Descriptor::<DescriptorPublicKey>::from_str(&descriptor)
+            .map_err(|e| APIError::Unexpected(e.to_string()))?
+            .to_string())
+    };
+
+    Ok(Json(DescriptorsResponse {
+        vanilla: descriptor(WALLET_ACCOUNT_XPUB_VANILLA_FNAME)?,
+        colored: descriptor(WALLET_ACCOUNT_XPUB_COLORED_FNAME)?,
+    }))
+}
+
+pub(crate) async fn disable_totp(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<DisableTotpRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        unlocked_state.disable_totp(&payload.code)?;
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn disconnect_peer(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<DisconnectPeerRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let peer_pubkey = match PublicKey::from_str(&payload.peer_pubkey) {
+            Ok(pubkey) => pubkey,
+            Err(_e) => return Err(APIError::InvalidPubkey),
+        };
+
+        //check for open channels with peer
+        for channel in unlocked_state.channel_manager.list_channels() {
+            if channel.counterparty.node_id == peer_pubkey {
+                return Err(APIError::FailedPeerDisconnection(s!(
+                    "node has an active channel with this peer, close any channels first"
+                )));
+            }
+        }
+
+        disk::delete_channel_peer(
+            &state.static_state.ldk_data_dir.join(CHANNEL_PEER_DATA),
+            payload.peer_pubkey,
+        )?;
+
+        //check the pubkey matches a valid connected peer
+        if unlocked_state
+            .peer_manager
+            .peer_by_node_id(&peer_pubkey)
+            .is_none()
+        {
+            return Err(APIError::FailedPeerDisconnection(format!(
+                "Could not find peer {peer_pubkey}"
+            )));
+        }
+
+        unlocked_state
+            .peer_manager
+            .disconnect_by_node_id(peer_pubkey);
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn emergency_kit(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<EmergencyKitResponse>, APIError> {
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+
+    // make sure the kit reflects the current state of the channels
+    let refresh_state = Arc::clone(&unlocked_state);
+    let static_state = Arc::clone(&state.static_state);
+    tokio::task::spawn_blocking(move || refresh_state.refresh_emergency_kit(&static_state))
+        .await
+        .unwrap()?;
+
+    let emergency_kit = unlocked_state.get_emergency_kit();
+    let kit = std::fs::read(state.static_state.ldk_data_dir.join(EMERGENCY_KIT_FNAME))?;
+
+    Ok(Json(EmergencyKitResponse {
+        kit_hex: hex_str(&kit),
+        updated_at: emergency_kit.updated_at,
+        num_channels: emergency_kit.num_channels,
+    }))
+}
+
+pub(crate) async fn enroll_totp(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<EnrollTotpRequest>, APIError>,
+) -> Result<Json<EnrollTotpResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let enrollment = unlocked_state.enroll_totp(payload.payment_threshold_msat)?;
+
+        Ok(Json(EnrollTotpResponse {
+            secret: enrollment.secret,
+            uri: enrollment.uri,
+            recovery_codes: enrollment.recovery_codes,
+        }))
+    })
+    .await
+}
+
+/// Statement the arbiter of an escrow signs (with the Lightning message signing scheme) to settle
+/// or refund its payment
+fn escrow_decision_message(
+    escrow_id: &str,
+    payment_hash: &str,
+    decision: EscrowDecision,
+) -> String {
+    let decision = match decision {
+        EscrowDecision::Settle => "settle",
+        EscrowDecision::Refund => "refund",
+    };
+    format!(
+        "rgb-lightning-node escrow decision\nescrow_id: {escrow_id}\npayment_hash: {payment_hash}\ndecision: {decision}"
+    )
+}
+
+pub(crate) async fn escrow_create(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<EscrowCreateRequest>, APIError>,
+) -> Result<Json<EscrowCreateResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        state.check_draining()?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let payment_hash = check_payment_hash(&payload.payment_hash)?;
+        let arbiter_pubkey = PublicKey::from_str(&payload.arbiter_pubkey)
+            .map_err(|_| APIError::InvalidEscrow(s!("invalid arbiter_pubkey")))?;
+        if arbiter_pubkey == unlocked_state.channel_manager.get_our_node_id() {
+            return Err(APIError::InvalidEscrow(s!(
+                "the arbiter cannot be the node itself"
+            )));
+        }
+
+        let contract_id = if let Some(asset_id) = &payload.asset_id {
+            Some(
+                ContractId::from_str(asset_id)
+                    .map_err(|_| APIError::InvalidAssetID(asset_id.clone()))?,
+            )
+        } else {
+            None
+        };
+        let asset_htlc_min_msat = state.static_state.asset_htlc_min_msat;
+        if contract_id.is_some() && payload.amt_msat.unwrap_or(0) < asset_htlc_min_msat {
+            return Err(APIError::InvalidAmount(format!(
+                "amt_msat cannot be less than {asset_htlc_min_msat} when transferring an RGB asset"
+            )));
+        }
+
+        // the arbiter holds the preimage, which only gets revealed when releasing the payment
+        let mut invoice_params = Bolt11InvoiceParameters {
+            amount_msats: payload.amt_msat,
+            invoice_expiry_delta_secs: Some(payload.expiry_sec),
+            min_final_cltv_expiry_delta: Some(state.static_state.min_final_cltv_expiry_delta),
+            payment_hash: Some(payment_hash),
+            contract_id,
+            asset_amount: payload.asset_amount,
+            ..Default::default()
+        };
+        if let Some(description) = &payload.description {
+            let description = lightning_invoice::Description::new(description.clone())
+                .map_err(|e| APIError::InvalidEscrow(format!("invalid description: {e}")))?;
+            invoice_params.description = Bolt11InvoiceDescription::Direct(description);
+        }
+        let invoice = unlocked_state.create_hodl_invoice(
+            invoice_params,
+            None,
+            None,
+            state.get_runtime_config().max_route_hints,
+            &state.static_state.ldk_data_dir,
+        )?;
+
+        let escrow_id = hex_str(&unlocked_state.keys_manager.get_secure_random_bytes());
+        let created_at = get_current_timestamp();
+        let escrow = EscrowInfo {
+            payment_hash,
+            arbiter_pubkey,
+            invoice: invoice.to_string(),
+            description: payload.description,
+            amt_msat: payload.amt_msat,
+            asset_id: payload.asset_id,
+            asset_amount: payload.asset_amount,
+            created_at,
+            expires_at: created_at + payload.expiry_sec as u64,
+            decision: None,
+            resolved_at: None,
+        };
+        unlocked_state.add_escrow(escrow_id.clone(), escrow.clone());
+        tracing::info!("Created escrow {escrow_id} with arbiter {arbiter_pubkey}");
+
+        Ok(Json(EscrowCreateResponse {
+            escrow: unlocked_state.escrow_response(escrow_id, escrow),
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn escrow_resolve(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<EscrowResolveRequest>, APIError>,
+) -> Result<Json<EscrowResolveResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let escrow = unlocked_state
+            .escrows()
+            .remove(&payload.escrow_id)
+            .ok_or(APIError::UnknownEscrow)?;
+        if escrow.decision.is_some() {
+            return Err(APIError::EscrowAlreadyResolved);
+        }
+
+        let payment_hash = hex_str(&escrow.payment_hash.0);
+        let message = escrow_decision_message(&payload.escrow_id, &payment_hash, payload.decision);
+        if !lightning::util::message_signing::verify(
+            message.as_bytes(),
+            &payload.signature,
+            &escrow.arbiter_pubkey,
+        ) {
+            return Err(APIError::InvalidEscrow(s!(
+                "the decision is not signed by the arbiter"
+            )));
+        }
+
+        match payload.decision {
+            EscrowDecision::Settle => {
+                let payment_preimage = payload.payment_preimage.ok_or_else(|| {
+                    APIError::InvalidEscrow(s!("payment_preimage is needed to settle"))
+                })?;
+                unlocked_state.settle_hodl_invoice(&payment_hash, &payment_preimage)?;
+            }
+            EscrowDecision::Refund => unlocked_state.cancel_hodl_invoice(&payment_hash)?,
+        }
+        unlocked_state.resolve_escrow(&payload.escrow_id, payload.decision);
+        tracing::info!(
+            "Resolved escrow {} with decision {:?}",
+            payload.escrow_id,
+            payload.decision
+        );
+
+        let escrow = unlocked_state
+            .escrows()
+            .remove(&payload.escrow_id)
+            .expect("escrow exists");
+        Ok(Json(EscrowResolveResponse {
+            escrow: unlocked_state.escrow_response(payload.escrow_id, escrow),
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn estimate_fee(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<EstimateFeeRequest>, APIError>,
+) -> Result<Json<EstimateFeeResponse>, APIError> {
+    let fee_rate = state
+        .check_unlocked()
+        .await?
+        .clone()
+        .unwrap()
+        .rgb_get_fee_estimation(payload.blocks)?;
+
+    Ok(Json(EstimateFeeResponse { fee_rate }))
+}
+
+pub(crate) async fn export_accounting(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Query(payload), _): WithRejection<Query<ExportAccountingRequest>, APIError>,
+) -> Result<Response, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    if let (Some(start_time), Some(end_time)) = (payload.start_time, payload.end_time) {
+        if start_time > end_time {
+            return Err(APIError::InvalidPagination(s!(
+                "start_time cannot be after end_time"
+            )));
+        }
+    }
+
+    // amounts are the signed change of the node balance, fees included
+    let mut entries = vec![];
+
+    let channel_operations = unlocked_state.channel_operations()?;
+    for tx in unlocked_state.rgb_list_transactions(false)? {
+        let Some(confirmation_time) = tx.confirmation_time else {
+            continue;
+        };
+        let kind = match channel_operations.get(&tx.txid) {
+            Some(ChannelOperation::Funding) => AccountingEntryKind::ChannelOpen,
+            Some(ChannelOperation::Close) => AccountingEntryKind::ChannelClose,
+            None => AccountingEntryKind::OnchainTx,
+        };
+        entries.push(AccountingEntry {
+            id: format!("onchain:{}", tx.txid),
+            timestamp: confirmation_time.timestamp,
+            kind,
+            amount_msat: (tx.received as i64 - tx.sent as i64) * 1000,
+            fee_msat: if tx.sent > 0 { tx.fee * 1000 } else { 0 },
+            asset_id: None,
+            asset_amount: None,
+            reference: tx.txid,
+        });
+    }
+
+    let ldk_data_dir = &state.static_state.ldk_data_dir;
+    let rgb_payment = |payment_hash: &PaymentHash, inbound: bool| {
+        let path = get_rgb_payment_info_path(payment_hash, ldk_data_dir, inbound);
+        if path.exists() {
+            let info = parse_rgb_payment_info(&path);
+            (Some(info.contract_id.to_string()), Some(info.amount as i64))
+        } else {
+            (None, None)
+        }
+    };
+    for (payment_hash, payment_info) in unlocked_state.inbound_payments() {
+        if payment_info.status != HTLCStatus::Succeeded {
+            continue;
+        }
+        let (asset_id, asset_amount) = rgb_payment(&payment_hash, true);
+        entries.push(AccountingEntry {
+            id: format!("payment_received:{}", hex_str(&payment_hash.0)),
+            timestamp: payment_info.updated_at,
+            kind: AccountingEntryKind::PaymentReceived,
+            amount_msat: payment_info.amt_msat.unwrap_or(0) as i64,
+            fee_msat: 0,
+            asset_id,
+            asset_amount,
+            reference: hex_str(&payment_hash.0),
+        });
+    }
+    for (payment_id, payment_info) in unlocked_state.outbound_payments() {
+        if payment_info.status != HTLCStatus::Succeeded {
+            continue;
+        }
+        let payment_hash = PaymentHash(payment_id.0);
+        let (asset_id, asset_amount) = rgb_payment(&payment_hash, false);
+        let fee_msat: u64 = payment_info
+            .parts
+            .iter()
+            .filter(|p| p.status == HTLCStatus::Succeeded)
+            .map(|p| p.fee_msat)
+            .sum();
+        entries.push(AccountingEntry {
+            id: format!("payment_sent:{}", hex_str(&payment_hash.0)),
+            timestamp: payment_info.updated_at,
+            kind: AccountingEntryKind::PaymentSent,
+            amount_msat: -((payment_info.amt_msat.unwrap_or(0) + fee_msat) as i64),
+            fee_msat,
+            asset_id,
+            asset_amount: asset_amount.map(|a| -a),
+            reference: hex_str(&payment_hash.0),
+        });
+    }
+
+    // a forward only leaves the earned fee, numbered to tell apart parts of the same payment
+    let mut forward_counts: HashMap<String, usize> = HashMap::new();
+    for forward in unlocked_state.get_forwards().forwards.iter() {
+        if forward.resolution != ForwardResolution::Settled {
+            continue;
+        }
+        let reference = forward
+            .payment_hash
+            .map(|h| hex_str(&h.0))
+            .unwrap_or_default();
+        let count = forward_counts.entry(reference.clone()).or_default();
+        entries.push(AccountingEntry {
+            id: format!("forward:{reference}:{count}"),
+            timestamp: forward.timestamp,
+            kind: AccountingEntryKind::Forward,
+            amount_msat: forward.fee_earned_msat.unwrap_or(0) as i64,
+            fee_msat: 0,
+            asset_id: None,
+            asset_amount: None,
+            reference,
+        });
+        *count += 1;
+    }
+
+    for asset_id in unlocked_state.rgb_list_asset_ids()? {
+        for transfer in unlocked_state.rgb_list_transfers(asset_id.clone())? {
+            if !matches!(transfer.status, rgb_lib::TransferStatus::Settled) {
+                continue;
+            }
+            let asset_amount = if matches!(transfer.kind, rgb_lib::TransferKind::Send) {
+                -(transfer
+                    .requested_assignment
+                    .as_ref()
+                    .map(assignment_amount)
+                    .unwrap_or(0) as i64)
+            } else {
+                transfer
+                    .assignments
+                    .iter()
+                    .map(assignment_amount)
+                    .sum::<u64>() as i64
+            };
+            entries.push(AccountingEntry {
+                id: format!("rgb_transfer:{}", transfer.idx),
+                timestamp: transfer.updated_at as u64,
+                kind: AccountingEntryKind::AssetTransfer,
+                amount_msat: 0,
+                fee_msat: 0,
+                asset_id: Some(asset_id.clone()),
+                asset_amount: Some(asset_amount),
+                reference: transfer.txid.unwrap_or_default(),
+            });
+        }
+    }
+
+    entries.retain(|e| {
+        payload.start_time.is_none_or(|start| e.timestamp >= start)
+            && payload.end_time.is_none_or(|end| e.timestamp <= end)
+    });
+    entries.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then_with(|| a.id.cmp(&b.id)));
+
+    if payload.format.unwrap_or_default() == ExportFormat::Csv {
+        let mut csv =
+            s!("id,timestamp,kind,amount_msat,fee_msat,asset_id,asset_amount,reference\n");
+        for e in entries {
+            csv.push_str(&format!(
+                "{},{},{:?},{},{},{},{},{}\n",
+                e.id,
+                e.timestamp,
+                e.kind,
+                e.amount_msat,
+                e.fee_msat,
+                e.asset_id.unwrap_or_default(),
+                e.asset_amount.map(|a| a.to_string()).unwrap_or_default(),
+                e.reference,
+            ));
+        }
+        return Ok(Response::builder()
+            .header(header::CONTENT_TYPE, "text/csv")
+            .body(Body::from(csv))
+            .unwrap());
+    }
+
+    Ok(Json(ExportAccountingResponse { entries }).into_response())
+}
+
+pub(crate) async fn export_backup(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ExportBackupRequest>, APIError>,
+) -> Result<Response, APIError> {
+    no_cancel(async move {
+        let _guard = state.check_locked().await?;
+
+        let _mnemonic =
+            check_password_validity(&payload.password, &state.static_state.storage_dir_path)?;
+
+        // the backup is built outside of the storage directory, which gets backed up
+        let backup_dir = tempfile::tempdir()?;
+        let backup_file = backup_dir.path().join(BACKUP_EXPORT_FNAME);
+        do_backup(
+            &state.static_state.storage_dir_path,
+            &backup_file,
+            &payload.password,
+        )?;
+        let file = File::open(&backup_file).await?;
+        // the open file can still be streamed once the temporary directory gets removed
+        drop(backup_dir);
+
+        Ok((
+            [
+                (header::CONTENT_TYPE, "application/octet-stream"),
+                (
+                    header::CONTENT_DISPOSITION,
+                    "attachment; filename=\"rln_backup\"",
+                ),
+            ],
+            Body::from_stream(ReaderStream::new(file)),
+        )
+            .into_response())
+    })
+    .await
+}
+
+pub(crate) async fn export_consignment(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ExportConsignmentRequest>, APIError>,
+) -> Result<Response, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let contract_id = ContractId::from_str(&payload.asset_id)
+        .map_err(|_| APIError::InvalidAssetID(payload.asset_id.clone()))?;
+    let txid = Txid::from_str(&payload.txid).map_err(|_| APIError::UnknownConsignment)?;
+    let consignment_path =
+        unlocked_state.rgb_get_send_consignment_path(&contract_id.to_string(), &txid.to_string());
+    if !consignment_path.is_file() {
+        return Err(APIError::UnknownConsignment);
+    }
+    let file = File::open(&consignment_path).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, s!("application/octet-stream")),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"consignment_{txid}\""),
+            ),
+        ],
+        Body::from_stream(ReaderStream::new(file)),
+    )
+        .into_response())
+}
+
+pub(crate) async fn fail_transfer(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<FailTransferRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let Some((_, transfer)) = unlocked_state.rgb_get_transfer(payload.idx)? else {
+            return Err(APIError::TransferNotFound(payload.idx));
+        };
+        if !matches!(
+            transfer.status,
+            rgb_lib::TransferStatus::WaitingCounterparty
+        ) {
+            return Err(APIError::CannotFailBatchTransfer);
+        }
+
+        // the whole batch is failed, releasing the UTXOs allocated to it
+        let unlocked_state_copy = unlocked_state.clone();
+        tokio::task::spawn_blocking(move || {
+            unlocked_state_copy.rgb_fail_transfers(
+                Some(transfer.batch_transfer_idx),
+                false,
+                payload.skip_sync,
+            )
+        })
+        .await
+        .unwrap()?;
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn fail_transfers(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<FailTransfersRequest>, APIError>,
+) -> Result<Json<FailTransfersResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let unlocked_state_copy = unlocked_state.clone();
+        let transfers_changed = tokio::task::spawn_blocking(move || {
+            unlocked_state_copy.rgb_fail_transfers(
+                payload.batch_transfer_idx,
+                payload.no_asset_only,
+                payload.skip_sync,
+            )
+        })
+        .await
+        .unwrap()?;
+
+        Ok(Json(FailTransfersResponse { transfers_changed }))
+    })
+    .await
+}
+
+pub(crate) async fn fee_report(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<FeeReportResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let forwards = unlocked_state.get_forwards();
+
+    let now = get_current_timestamp();
+    let fees_since = |secs: u64| -> u64 {
+        forwards
+            .forwards
+            .iter()
+            .filter(|f| f.timestamp >= now.saturating_sub(secs))
+            .filter_map(|f| f.fee_earned_msat)
+            .sum()
+    };
+    let day_fee_msat = fees_since(24 * 3600);
+    let week_fee_msat = fees_since(7 * 24 * 3600);
+    let month_fee_msat = fees_since(30 * 24 * 3600);
+
+    let mut channels: Vec<ChannelFeeReport> = forwards
+        .totals
+        .iter()
+        .map(|(channel_id, totals)| ChannelFeeReport {
+            channel_id: channel_id.0.as_hex().to_string(),
+            asset_id: get_rgb_channel_info_optional(
+                channel_id,
+                &state.static_state.ldk_data_dir,
+                false,
+            )
+            .map(|(rgb_info, _)| rgb_info.contract_id.to_string()),
+            forwards_in: totals.forwards_in,
+            forwards_out: totals.forwards_out,
+            failed_forwards: totals.failed_forwards,
+            amt_in_msat: totals.amt_in_msat,
+            amt_out_msat: totals.amt_out_msat,
+            fee_earned_msat: totals.fee_earned_msat,
+        })
+        .collect();
+    channels.sort_by(|a, b| {
+        b.fee_earned_msat
+            .cmp(&a.fee_earned_msat)
+            .then_with(|| a.channel_id.cmp(&b.channel_id))
+    });
+    let total_fee_msat = channels.iter().map(|c| c.fee_earned_msat).sum();
+
+    Ok(Json(FeeReportResponse {
+        channels,
+        day_fee_msat,
+        week_fee_msat,
+        month_fee_msat,
+        total_fee_msat,
+    }))
+}
+
+pub(crate) async fn forwarding_history(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Query(payload), _): WithRejection<Query<ForwardingHistoryRequest>, APIError>,
+) -> Result<Json<ForwardingHistoryResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    if let Some(limit) = payload.limit {
+        if limit == 0 || limit > MAX_FORWARDS_PAGE_SIZE {
+            return Err(APIError::InvalidPagination(format!(
+                "limit must be between 1 and {MAX_FORWARDS_PAGE_SIZE}"
+            )));
+        }
+    }
+    if let (Some(start_time), Some(end_time)) = (payload.start_time, payload.end_time) {
+        if start_time > end_time {
+            return Err(APIError::InvalidPagination(s!(
+                "start_time cannot be after end_time"
+            )));
+        }
+    }
+
+    let forwards = unlocked_state.get_forwards();
+    let matching: Vec<_> = forwards
+        .forwards
+        .iter()
+        .filter(|f| {
+            payload.start_time.is_none_or(|start| f.timestamp >= start)
+                && payload.end_time.is_none_or(|end| f.timestamp <= end)
+        })
+        .collect();
+
+    let total = matching.len();
+    // oldest first, as they were recorded
+    let forwards = matching
+        .into_iter()
+        .skip(payload.offset.unwrap_or(0))
+        .take(payload.limit.unwrap_or(usize::MAX))
+        .map(|f| Forward {
+            timestamp: f.timestamp,
+            prev_channel_id: f.prev_channel_id.0.as_hex().to_string(),
+            next_channel_id: f.next_channel_id.map(|c| c.0.as_hex().to_string()),
+            payment_hash: f.payment_hash.map(|h| hex_str(&h.0)),
+            amt_in_msat: f.amt_in_msat,
+            amt_out_msat: f.amt_out_msat,
+            fee_earned_msat: f.fee_earned_msat,
+            asset_id: f.asset_id.clone(),
+            asset_amount_in: f.asset_amount_in,
+            asset_amount_out: f.asset_amount_out,
+            resolution: f.resolution,
+            failure_reason: f.failure_reason.clone(),
+        })
+        .collect();
+
+    Ok(Json(ForwardingHistoryResponse { forwards, total }))
+}
+
+/// Give LDK the funding TX of an externally funded channel, checking it pays the funding output.
+/// The TX is kept to be broadcast once the peer has signed the commitment TX.
+fn complete_external_funding(
+    unlocked_state: &UnlockedAppState,
+    ldk_data_dir: &Path,
+    temporary_channel_id: ChannelId,
+    signed_psbt: &str,
+) -> Result<FundChannelCompleteResponse, APIError> {
+    let mut external_fundings = unlocked_state.get_external_fundings();
+    let Some(info) = external_fundings.get(&temporary_channel_id) else {
+        return Err(APIError::UnknownTemporaryChannelId);
+    };
+    let Some(funding_script) = &info.funding_script else {
+        return Err(APIError::CannotCompleteFunding(s!(
+            "the funding output is not known yet"
+        )));
+    };
+
+    let psbt =
+        Psbt::from_str(signed_psbt).map_err(|e| APIError::InvalidFundingPsbt(e.to_string()))?;
+    if let Some(i) = psbt
+        .inputs
+        .iter()
+        .position(|i| i.final_script_witness.is_none())
+    {
+        return Err(APIError::InvalidFundingPsbt(format!(
+            "input {i} is not finalized"
+        )));
+    }
+    let funding_outputs: Vec<usize> = psbt
+        .unsigned_tx
+        .output
+        .iter()
+        .enumerate()
+        .filter(|(_, o)| o.script_pubkey == *funding_script)
+        .map(|(i, _)| i)
+        .collect();
+    let [funding_output_index] = funding_outputs[..] else {
+        return Err(APIError::InvalidFundingPsbt(s!(
+            "it must pay the funding output exactly once"
+        )));
+    };
+    if psbt.unsigned_tx.output[funding_output_index].value.to_sat() != info.capacity_sat {
+        return Err(APIError::InvalidFundingPsbt(format!(
+            "the funding output must pay {} sats",
+            info.capacity_sat
+        )));
+    }
+    let funding_tx = psbt.extract_tx_unchecked_fee_rate();
+    let funding_txid = funding_tx.compute_txid();
+
+    let counterparty_node_id = info.counterparty_node_id;
+    external_fundings.remove(&temporary_channel_id);
+    drop(external_fundings);
+
+    let tx_path = ldk_data_dir.join(format!("{EXTERNAL_FUNDING_TX_PREFIX}{funding_txid}"));
+    std::fs::write(&tx_path, serialize_hex(&funding_tx))?;
+    unlocked_state
+        .channel_manager
+        .funding_transaction_generated(temporary_channel_id, counterparty_node_id, funding_tx)
+        .map_err(|e| {
+            let _ = std::fs::remove_file(&tx_path);
+            unlocked_state.add_channel_event(
+                temporary_channel_id,
+                ChannelEventKind::FundingFailed,
+                s!("the peer disconnected or refused the channel before funding"),
+            );
+            APIError::FailedOpenChannel(format!("{e:?}"))
+        })?;
+    tracing::info!("Funding TXID: {funding_txid}");
+
+    let channel_id =
+        ChannelId::v1_from_funding_txid(funding_txid.as_byte_array(), funding_output_index as u16);
+    unlocked_state.add_channel_id(temporary_channel_id, channel_id);
+    unlocked_state.move_channel_events(temporary_channel_id, channel_id);
+    unlocked_state.add_channel_event(
+        channel_id,
+        ChannelEventKind::FundingGenerated,
+        format!("funding TX {funding_txid}:{funding_output_index} signed externally"),
+    );
+
+    Ok(FundChannelCompleteResponse {
+        channel_id: channel_id.0.as_hex().to_string(),
+        funding_txid: funding_txid.to_string(),
+    })
+}
+
+pub(crate) async fn fund_channel_abort(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<FundChannelAbortRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let temporary_channel_id = check_channel_id(&payload.temporary_channel_id)?;
+        if unlocked_state
+            .get_multisig_fundings()
+            .contains_key(&temporary_channel_id)
+        {
+            unlocked_state.abort_multisig_funding(temporary_channel_id, "aborted by the user");
+        } else if unlocked_state
+            .get_external_fundings()
+            .contains_key(&temporary_channel_id)
+        {
+            unlocked_state.abort_external_funding(temporary_channel_id, "aborted by the user");
+        } else {
+            return Err(APIError::UnknownTemporaryChannelId);
+        }
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn fund_channel_complete(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<FundChannelCompleteRequest>, APIError>,
+) -> Result<Json<FundChannelCompleteResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let temporary_channel_id = check_channel_id(&payload.temporary_channel_id)?;
+        unlocked_state.check_totp(payload.totp_code.as_deref(), TotpOperation::Spend)?;
+
+        if unlocked_state
+            .get_external_fundings()
+            .contains_key(&temporary_channel_id)
+        {
+            return Ok(Json(complete_external_funding(
+                unlocked_state,
+                &state.static_state.ldk_data_dir,
+                temporary_channel_id,
+                &payload.signed_psbt,
+            )?));
+        }
+
+        let mut multisig_fundings = unlocked_state.get_multisig_fundings();
+        let Some(info) = multisig_fundings.get(&temporary_channel_id) else {
+            return Err(APIError::UnknownTemporaryChannelId);
+        };
+        let Some(unsigned_psbt) = &info.unsigned_psbt else {
+            return Err(APIError::CannotCompleteFunding(s!(
+                "the funding PSBT has not been created yet"
+            )));
+        };
+
+        let mut psbt = Psbt::from_str(&payload.signed_psbt)
+            .map_err(|e| APIError::InvalidFundingPsbt(e.to_string()))?;
+        if psbt.unsigned_tx.compute_txid() != unsigned_psbt.unsigned_tx.compute_txid() {
+            return Err(APIError::InvalidFundingPsbt(s!(
+                "it doesn't spend and pay the same as the unsigned funding PSBT"
+            )));
+        }
+        // some signers strip the input scripts, which are needed to finalize the PSBT
+        for (input, unsigned_input) in psbt.inputs.iter_mut().zip(unsigned_psbt.inputs.iter()) {
+            if input.witness_script.is_none() {
+                input.witness_script = unsigned_input.witness_script.clone();
+            }
+        }
+        finalize_multisig_psbt(&mut psbt)?;
+        let funding_tx = psbt.extract_tx_unchecked_fee_rate();
+        let funding_txid = funding_tx.compute_txid();
+
+        let counterparty_node_id = info.counterparty_node_id;
+        multisig_fundings.remove(&temporary_channel_id);
+        drop(multisig_fundings);
+
+        unlocked_state
+            .channel_manager
+            .funding_transaction_generated(temporary_channel_id, counterparty_node_id, funding_tx)
+            .map_err(|e| {
+                unlocked_state.add_channel_event(
+                    temporary_channel_id,
+                    ChannelEventKind::FundingFailed,
+                    s!("the peer disconnected or refused the channel before funding"),
+                );
+                APIError::FailedOpenChannel(format!("{e:?}"))
+            })?;
+        tracing::info!("Funding TXID: {funding_txid}");
+
+        // the funding output is always the first one of the funding PSBT
+        let channel_id = ChannelId::v1_from_funding_txid(funding_txid.as_byte_array(), 0);
+        unlocked_state.add_channel_id(temporary_channel_id, channel_id);
+        unlocked_state.move_channel_events(temporary_channel_id, channel_id);
+        unlocked_state.add_channel_event(
+            channel_id,
+            ChannelEventKind::FundingGenerated,
+            format!("funding TX {funding_txid}:0 signed by the multisig treasury"),
+        );
+
+        Ok(Json(FundChannelCompleteResponse {
+            channel_id: channel_id.0.as_hex().to_string(),
+            funding_txid: funding_txid.to_string(),
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn fund_psbt(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<FundPsbtRequest>, APIError>,
+) -> Result<Json<FundPsbtResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let mut output = vec![];
+    if payload.opret_commitment {
+        // placeholder of the commitment size, to be filled by the caller before signing
+        output.push(TxOut {
+            value: Amount::ZERO,
+            script_pubkey: ScriptBuf::new_op_return([0u8; 32]),
+        });
+    }
+    for recipient in payload.recipients {
+        check_address_network(&recipient.address, state.static_state.network)?;
+        output.push(TxOut {
+            value: Amount::from_sat(recipient.amount),
+            script_pubkey: Address::from_str(&recipient.address)
+                .unwrap()
+                .assume_checked()
+                .script_pubkey(),
+        });
+    }
+    if output.is_empty() {
+        return Err(APIError::InvalidRecipients(s!("no recipient provided")));
+    }
+    unlocked_state.check_totp(payload.totp_code.as_deref(), TotpOperation::Spend)?;
+    let coin_selection = payload.coin_selection.unwrap_or(CoinSelection {
+        strategy: CoinSelectionStrategy::LargestFirst,
+        outpoints: None,
+        excluded_outpoints: None,
+    });
+    let psbt = unlocked_state
+        .build_psbt_with_coin_selection(
+            output,
+            payload.fee_rate,
+            &coin_selection,
+            payload.skip_sync,
+        )
+        .await?;
+
+    Ok(Json(FundPsbtResponse {
+        psbt: psbt.to_string(),
+    }))
+}
+
+pub(crate) async fn get_asset_media(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<GetAssetMediaRequest>, APIError>,
+) -> Result<Json<GetAssetMediaResponse>, APIError> {
+    let file_path = get_media_path(
+        state.check_unlocked().await?.as_ref().unwrap(),
+        &payload.digest,
+    )?;
+
+    let mut buf_reader = BufReader::new(File::open(file_path).await?);
+    let mut file_bytes = Vec::new();
+    buf_reader.read_to_end(&mut file_bytes).await?;
+    let bytes_hex = hex_str(&file_bytes);
+
+    Ok(Json(GetAssetMediaResponse { bytes_hex }))
+}
+
+pub(crate) async fn get_chan_info(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Query(payload), _): WithRejection<Query<GetChanInfoRequest>, APIError>,
+) -> Result<Json<GetChanInfoResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let graph = unlocked_state.network_graph.read_only();
+    let channel = graph
+        .channel(payload.chan_id)
+        .ok_or(APIError::UnknownChannelId)?;
+    let policy = |update: &Option<ChannelUpdateInfo>| {
+        update.as_ref().map(|u| GraphChannelPolicy {
+            enabled: u.enabled,
+            fee_base_msat: u.fees.base_msat,
+            fee_proportional_millionths: u.fees.proportional_millionths,
+            cltv_expiry_delta: u.cltv_expiry_delta,
+            htlc_minimum_msat: u.htlc_minimum_msat,
+            htlc_maximum_msat: u.htlc_maximum_msat,
+            last_update: u.last_update,
+        })
+    };
+
+    Ok(Json(GetChanInfoResponse {
+        chan_id: payload.chan_id,
+        node1_pubkey: channel.node_one.to_string(),
+        node2_pubkey: channel.node_two.to_string(),
+        capacity_sat: channel.capacity_sats,
+        node1_policy: policy(&channel.one_to_two),
+        node2_policy: policy(&channel.two_to_one),
+    }))
+}
+
+pub(crate) async fn get_channel_id(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<GetChannelIdRequest>, APIError>,
+) -> Result<Json<GetChannelIdResponse>, APIError> {
+    let tmp_chan_id = check_channel_id(&payload.temporary_channel_id)?;
+    let unlocked_state = state.check_unlocked().await?.clone().unwrap();
+    let channel_ids = unlocked_state.channel_ids();
+    let channel_id = if let Some(channel_id) = channel_ids.get(&tmp_chan_id) {
+        channel_id.0.as_hex().to_string()
+    } else if unlocked_state
+        .channel_manager
+        .list_channels()
+        .iter()
+        .any(|c| c.channel_id == tmp_chan_id)
+    {
+        return Err(APIError::ChannelFundingInProgress);
+    } else {
+        return Err(APIError::UnknownTemporaryChannelId);
+    };
+
+    Ok(Json(GetChannelIdResponse { channel_id }))
+}
+
+pub(crate) async fn get_node_info(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Query(payload), _): WithRejection<Query<GetNodeInfoRequest>, APIError>,
+) -> Result<Json<GetNodeInfoResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let pubkey = PublicKey::from_str(&payload.pubkey).map_err(|_| APIError::InvalidPubkey)?;
+    let graph = unlocked_state.network_graph.read_only();
+    let node = graph
+        .node(&NodeId::from_pubkey(&pubkey))
+        .ok_or(APIError::UnknownNode)?;
+    let announcement = node.announcement_info.as_ref();
+    let total_capacity_sat = node
+        .channels
+        .iter()
+        .filter_map(|scid| graph.channel(*scid))
+        .map(|c| c.capacity_sats.unwrap_or(0))
+        .sum();
+
+    Ok(Json(GetNodeInfoResponse {
+        pubkey: pubkey.to_string(),
+        alias: announcement.map(|a| a.alias().to_string()),
+        color: announcement.map(|a| a.rgb().as_hex().to_string()),
+        addresses: announcement
+            .map(|a| a.addresses().iter().map(|a| a.to_string()).collect())
+            .unwrap_or_default(),
+        last_update: announcement.map(|a| a.last_update()),
+        num_channels: node.channels.len(),
+        total_capacity_sat,
+        chan_ids: node.channels.clone(),
+    }))
+}
+
+pub(crate) async fn hedging_policies(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<HedgingPoliciesResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let mut policies: Vec<HedgingPolicyEntry> = unlocked_state
+        .hedging_policies()
+        .into_iter()
+        .map(|(asset_id, policy)| policy.to_entry(asset_id))
+        .collect();
+    policies.sort_by(|a, b| a.asset_id.cmp(&b.asset_id));
+
+    Ok(Json(HedgingPoliciesResponse { policies }))
+}
+
+pub(crate) async fn hodl_invoice(
+    State(state): State<Arc<AppState>>,
+    token_id: Option<Extension<TokenId>>,
+    WithRejection(Json(payload), _): WithRejection<Json<HodlInvoiceRequest>, APIError>,
+) -> Result<Json<HodlInvoiceResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        state.check_draining()?;
+        let unlocked_state = guard.as_ref().unwrap();
+        let token_id = token_id.map(|Extension(TokenId(id))| id);
+
+        let contract_id = if let Some(asset_id) = payload.asset_id {
+            Some(ContractId::from_str(&asset_id).map_err(|_| APIError::InvalidAssetID(asset_id))?)
+        } else {
+            None
+        };
+
+        let asset_htlc_min_msat = state.static_state.asset_htlc_min_msat;
+        if contract_id.is_some() && payload.amt_msat.unwrap_or(0) < asset_htlc_min_msat {
+            return Err(APIError::InvalidAmount(format!(
+                "amt_msat cannot be less than {asset_htlc_min_msat} when transferring an RGB asset"
+            )));
+        }
+        check_amount_bounds(payload.amt_msat, payload.min_amt_msat, payload.max_amt_msat)?;
+
+        let (payment_hash, payment_preimage) = if let Some(payment_hash) = payload.payment_hash {
+            (check_payment_hash(&payment_hash)?, None)
+        } else {
+            let payment_preimage =
+                PaymentPreimage(unlocked_state.keys_manager.get_secure_random_bytes());
+            let payment_hash = PaymentHash(Sha256::hash(&payment_preimage.0[..]).to_byte_array());
+            (payment_hash, Some(payment_preimage))
+        };
+
+        let route_hints = payload.route_hints.map(parse_route_hints).transpose()?;
+
+        state.check_invoice_quotas(unlocked_state, token_id.as_deref())?;
+        let invoice_params = Bolt11InvoiceParameters {
+            amount_msats: payload.amt_msat,
+            invoice_expiry_delta_secs: Some(payload.expiry_sec),
+            min_final_cltv_expiry_delta: Some(state.static_state.min_final_cltv_expiry_delta),
+            payment_hash: Some(payment_hash),
+            contract_id,
+            asset_amount: payload.asset_amount,
+            ..Default::default()
+        };
+        let invoice = unlocked_state.create_hodl_invoice(
+            invoice_params,
+            payment_preimage,
+            route_hints,
+            state.get_runtime_config().max_route_hints,
+            &state.static_state.ldk_data_dir,
+        )?;
+        state.record_invoice(payment_hash, token_id);
+        if payload.min_amt_msat.is_some() || payload.max_amt_msat.is_some() {
+            unlocked_state.add_amount_bounds(
+                payment_hash,
+                AmountBoundsInfo {
+                    min_amt_msat: payload.min_amt_msat,
+                    max_amt_msat: payload.max_amt_msat,
+                    expires_at: get_current_timestamp() + payload.expiry_sec as u64,
+                },
+            );
+        }
+
+        let payment_preimage = if payload.reveal_preimage {
+            payment_preimage.map(|p| hex_str(&p.0))
+        } else {
+            None
+        };
+
+        Ok(Json(HodlInvoiceResponse {
+            invoice: invoice.to_string(),
+            payment_hash: hex_str(&payment_hash.0),
+            payment_preimage,
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn htlcs(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<HtlcsResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let htlcs = unlocked_state.list_htlcs(&state.static_state.ldk_data_dir);
+    let at_risk_amt_msat = htlcs.iter().filter(|h| h.at_risk).map(|h| h.amt_msat).sum();
+    let risk_threshold_sat = state.get_runtime_config().htlc_risk_threshold_sat;
+
+    Ok(Json(HtlcsResponse {
+        htlcs,
+        current_height: unlocked_state.channel_manager.current_best_block().height,
+        at_risk_amt_msat,
+        risk_threshold_sat,
+        risk_threshold_exceeded: risk_threshold_sat > 0
+            && at_risk_amt_msat > risk_threshold_sat * 1000,
+    }))
+}
+
+pub(crate) async fn import_backup(
+    State(state): State<Arc<AppState>>,
+    mut multipart: Multipart,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let _unlocked_state = state.check_locked().await?;
+
+        let mnemonic_path = get_mnemonic_path(&state.static_state.storage_dir_path);
+        check_already_initialized(&mnemonic_path)?;
+
+        // the uploaded backup is written to disk as it's received
+        let backup_dir = tempfile::tempdir()?;
+        let backup_file = backup_dir.path().join(BACKUP_EXPORT_FNAME);
+        let mut password = None;
+        let mut file_provided = false;
+        while let Some(mut field) = multipart
+            .next_field()
+            .await
+            .map_err(|e| APIError::InvalidBackupUpload(e.to_string()))?
+        {
+            match field.name() {
+                Some("password") => {
+                    password = Some(
+                        field
+                            .text()
+                            .await
+                            .map_err(|e| APIError::InvalidBackupUpload(e.to_string()))?,
+                    )
+                }
+                Some("file") => {
+                    let mut file = File::create(&backup_file).await?;
+                    while let Some(chunk) = field
+                        .chunk()
+                        .await
+                        .map_err(|e| APIError::InvalidBackupUpload(e.to_string()))?
+                    {
+                        file.write_all(&chunk).await?;
+                    }
+                    file.flush().await?;
+                    file_provided = true;
+                }
+                _ => {
+                    return Err(APIError::InvalidBackupUpload(s!(
+                        "only the file and password fields are expected"
+                    )))
+                }
+            }
+        }
+        let Some(password) = password else {
+            return Err(APIError::InvalidBackupUpload(s!("password not provided")));
+        };
+        if !file_provided {
+            return Err(APIError::InvalidBackupUpload(s!("file not provided")));
+        }
+
+        restore_from_backup(&state, &backup_file, &password)?;
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn import_consignment(
+    State(state): State<Arc<AppState>>,
+    mut multipart: Multipart,
+) -> Result<Json<ImportConsignmentResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let mut consignment_bytes = None;
+        let mut txid = None;
+        while let Some(field) = multipart
+            .next_field()
+            .await
+            .map_err(|e| APIError::InvalidConsignment(e.to_string()))?
+        {
+            match field.name() {
+                Some("file") => {
+                    consignment_bytes = Some(
+                        field
+                            .bytes()
+                            .await
+                            .map_err(|e| APIError::InvalidConsignment(e.to_string()))?,
+                    )
+                }
+                Some("txid") => {
+                    txid = Some(
+                        field
+                            .text()
+                            .await
+                            .map_err(|e| APIError::InvalidConsignment(e.to_string()))?,
+                    )
+                }
+                _ => {
+                    return Err(APIError::InvalidConsignment(s!(
+                        "only the file and txid fields are expected"
+                    )))
+                }
+            }
+        }
+        let Some(consignment_bytes) = consignment_bytes else {
+            return Err(APIError::InvalidConsignment(s!("file not provided")));
+        };
+        let Some(txid) = txid else {
+            return Err(APIError::InvalidConsignment(s!("txid not provided")));
+        };
+        let txid = Txid::from_str(&txid)
+            .map_err(|_| APIError::InvalidConsignment(format!("invalid txid {txid}")))?;
+
+        let consignment_dir = tempfile::tempdir()?;
+        let consignment_path = consignment_dir.path().join("consignment");
+        tokio::fs::write(&consignment_path, &consignment_bytes).await?;
+        let consignment = RgbTransfer::load_file(&consignment_path)
+            .map_err(|_| APIError::InvalidConsignment(s!("cannot be decoded")))?;
+        let asset_id = consignment.contract_id().to_string();
+
+        // the consignment gets validated against the given witness transaction
+        match unlocked_state.rgb_save_new_asset(consignment, txid.to_string()) {
+            Ok(()) => tracing::info!("Imported asset {asset_id} from a consignment"),
+            Err(e) if e.to_string().contains("UNIQUE constraint failed") => {
+                tracing::info!("Asset {asset_id} is already known")
+            }
+            Err(e) => return Err(APIError::InvalidConsignment(e.to_string())),
+        }
+
+        Ok(Json(ImportConsignmentResponse { asset_id }))
+    })
+    .await
+}
+
+pub(crate) async fn init(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<InitRequest>, APIError>,
+) -> Result<Json<InitResponse>, APIError> {
+    no_cancel(async move {
+        let _unlocked_state = state.check_locked().await?;
+
+        check_password_strength(payload.password.clone())?;
+
+        let network = state.static_state.network;
+        if let Some(requested) = payload.network {
+            if requested != BitcoinNetwork::from(network) {
+                return Err(APIError::InvalidNetwork(format!(
+                    "the node has been started on {network}"
+                )));
+            }
+        }
+
+        let mnemonic_path = get_mnemonic_path(&state.static_state.storage_dir_path);
+        check_already_initialized(&mnemonic_path)?;
+
+        // an existing mnemonic can be imported instead of generating a new one
+        let mnemonic = if let Some(mnemonic) = payload.mnemonic {
+            Mnemonic::from_str(mnemonic.trim())
+                .map_err(|e| APIError::InvalidMnemonic(e.to_string()))?
+                .to_string()
+        } else {
+            generate_keys(network).mnemonic
+        };
+
+        let ldk_key_derivation = LdkKeyDerivation {
+            passphrase: Zeroizing::new(payload.passphrase.unwrap_or_default()),
+            account_index: payload.account_index.unwrap_or_default(),
+        };
+        if ldk_key_derivation != LdkKeyDerivation::default() {
+            // rgb-lib derives the wallet keys from the bare mnemonic on fixed accounts, so they
+            // would not follow the requested derivation
+            if !payload.ldk_keys_only {
+                return Err(APIError::InvalidKeyDerivation(s!(
+                    "rgb-lib does not support BIP39 passphrases and account indexes, set \
+                     ldk_keys_only to only derive the LDK keys with them"
+                )));
+            }
+            ChildNumber::from_hardened_idx(ldk_key_derivation.account_index).map_err(|_| {
+                APIError::InvalidKeyDerivation(s!("the account index must be below 2^31"))
+            })?;
+        }
+
+        encrypt_and_save_mnemonic(payload.password, mnemonic.clone(), &mnemonic_path)?;
+        save_ldk_key_derivation(
+            &state.static_state.storage_dir_path,
+            &mnemonic,
+            &ldk_key_derivation,
+        )?;
+        // a view password left by a previous wallet would unlock it in watch-only mode
+        let view_mnemonic_path = get_view_mnemonic_path(&state.static_state.storage_dir_path);
+        if view_mnemonic_path.exists() {
+            std::fs::remove_file(view_mnemonic_path)?;
+        }
+        std::fs::write(
+            state
+                .static_state
+                .storage_dir_path
+                .join(BITCOIN_NETWORK_FNAME),
+            network.to_string(),
+        )?;
+
+        let (admin_token, read_only_token) = if state.generate_root_key {
+            let (admin_token, read_only_token) = state.generate_root_key_and_tokens()?;
+            (Some(admin_token), Some(read_only_token))
+        } else {
+            (None, None)
+        };
+
+        Ok(Json(InitResponse {
+            mnemonic,
+            admin_token,
+            read_only_token,
+        }))
+    })
+    .await
+}
+
+#[cfg(feature = "failure-injection")]
+pub(crate) async fn inject_failure(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<InjectFailureRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let proxy_delay = payload
+        .proxy_delay_ms
+        .filter(|ms| *ms > 0)
+        .map(Duration::from_millis);
+    tracing::info!(
+        "Armed failure {:?} with RGB proxy delay {proxy_delay:?}",
+        payload.failure
+    );
+    unlocked_state
+        .failure_injector
+        .arm(payload.failure, proxy_delay);
+
+    Ok(Json(EmptyResponse {}))
+}
+
+pub(crate) async fn intercept_scopes(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<InterceptScopesRequest>, APIError>,
+) -> Result<Json<InterceptScopesResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        if payload.hold_timeout_sec.is_some() && payload.kind != InterceptScopeKind::AsyncRecipient
+        {
+            return Err(APIError::InvalidInterceptScope(s!(
+                "only async recipient scopes have a hold timeout"
+            )));
+        }
+        let hold_timeout_sec = match payload.kind {
+            InterceptScopeKind::AsyncRecipient => {
+                let hold_timeout_sec = payload
+                    .hold_timeout_sec
+                    .unwrap_or(DEFAULT_ASYNC_HOLD_TIMEOUT_SEC);
+                if hold_timeout_sec == 0 || hold_timeout_sec > MAX_ASYNC_HOLD_TIMEOUT_SEC {
+                    return Err(APIError::InvalidInterceptScope(format!(
+                        "hold timeout must be between 1 and {MAX_ASYNC_HOLD_TIMEOUT_SEC} seconds"
+                    )));
+                }
+                Some(hold_timeout_sec)
+            }
+            InterceptScopeKind::Forward | InterceptScopeKind::Receive => None,
+        };
+
+        let (intercept_scid, peer_pubkey, payment_hash) =
+            match (payload.kind, payload.peer_pubkey, payload.payment_hash) {
+                (
+                    InterceptScopeKind::AsyncRecipient | InterceptScopeKind::Forward,
+                    Some(peer_pubkey),
+                    None,
+                ) => {
+                    let peer_pubkey = hex_str_to_compressed_pubkey(&peer_pubkey)
+                        .ok_or(APIError::InvalidPubkey)?;
+                    let intercept_scid = unlocked_state.channel_manager.get_intercept_scid();
+                    (Some(intercept_scid), Some(peer_pubkey), None)
+                }
+                (InterceptScopeKind::Receive, None, Some(payment_hash)) => {
+                    (None, None, Some(check_payment_hash(&payment_hash)?))
+                }
+                (InterceptScopeKind::AsyncRecipient, _, _) => {
+                    return Err(APIError::InvalidInterceptScope(s!(
+                        "async recipient scopes need only the peer_pubkey"
+                    )))
+                }
+                (InterceptScopeKind::Forward, _, _) => {
+                    return Err(APIError::InvalidInterceptScope(s!(
+                        "forward scopes need only the peer_pubkey"
+                    )))
+                }
+                (InterceptScopeKind::Receive, _, _) => {
+                    return Err(APIError::InvalidInterceptScope(s!(
+                        "receive scopes need only the payment_hash"
+                    )))
+                }
+            };
+
+        let scope_id = hex_str(&unlocked_state.keys_manager.get_secure_random_bytes());
+        let added = unlocked_state.add_intercept_scope(
+            scope_id.clone(),
+            InterceptScopeInfo {
+                kind: payload.kind,
+                intercept_scid,
+                peer_pubkey,
+                payment_hash,
+                hold_timeout_sec,
+                created_at: get_current_timestamp(),
+            },
+        );
+        if !added {
+            return Err(APIError::InvalidInterceptScope(format!(
+                "cannot have more than {MAX_INTERCEPT_SCOPES} scopes"
+            )));
+        }
+        tracing::info!("Added intercept scope {scope_id}");
+
+        Ok(Json(InterceptScopesResponse {
+            scope_id,
+            intercept_scid: intercept_scid.map(scid_to_string),
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn intercepted_htlcs(
+    State(state): State<Arc<AppState>>,
+    WithRejection(AxumPath(scope_id), _): WithRejection<AxumPath<String>, APIError>,
+) -> Result<Json<InterceptedHtlcsResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let scope = unlocked_state
+        .intercept_scope(&scope_id)
+        .ok_or(APIError::UnknownInterceptScope)?;
+    let mut htlcs: Vec<InterceptedHtlc> = unlocked_state
+        .intercepted_htlcs(&scope_id)
+        .into_iter()
+        .map(|(htlc_id, h)| InterceptedHtlc {
+            htlc_id,
+            kind: scope.kind,
+            payment_hash: hex_str(&h.payment_hash.0),
+            amt_msat: h.amt_msat,
+            asset_amount: h.rgb_amount,
+            outbound_amt_msat: match h.held {
+                HeldHtlc::Forward {
+                    outbound_amt_msat, ..
+                } => Some(outbound_amt_msat),
+                HeldHtlc::Receive { .. } => None,
+            },
+            received_at: h.received_at,
+        })
+        .collect();
+    htlcs.sort_by_key(|h| h.received_at);
+
+    Ok(Json(InterceptedHtlcsResponse { scope_id, htlcs }))
+}
+
+pub(crate) async fn invoice_quotas(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<InvoiceQuotasResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let (node, tokens) = state.invoice_quota_usage(unlocked_state);
+    let config = state.get_runtime_config().clone();
+
+    Ok(Json(InvoiceQuotasResponse {
+        max_invoices_per_minute: config.max_invoices_per_minute,
+        max_unpaid_invoices: config.max_unpaid_invoices,
+        max_token_invoices_per_minute: config.max_token_invoices_per_minute,
+        max_token_unpaid_invoices: config.max_token_unpaid_invoices,
+        node,
+        tokens,
+    }))
+}
+
+pub(crate) async fn invoice_status(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<InvoiceStatusRequest>, APIError>,
+) -> Result<Json<InvoiceStatusResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let (payment_hash, invoice_expires_at) = match (payload.invoice, payload.payment_hash) {
+        (Some(invoice), None) => {
+            let invoice = match Bolt11Invoice::from_str(&invoice) {
+                Err(e) => return Err(APIError::InvalidInvoice(e.to_string())),
+                Ok(v) => v,
+            };
+            check_invoice_network(&invoice, state.static_state.network)?;
+            (
+                PaymentHash(invoice.payment_hash().to_byte_array()),
+                invoice.expires_at().map(|e| e.as_secs()),
+            )
+        }
+        (None, Some(payment_hash)) => (check_payment_hash(&payment_hash)?, None),
+        _ => {
+            return Err(APIError::InvalidInvoiceStatusRequest(s!(
+                "exactly one of invoice and payment_hash must be provided"
+            )))
+        }
+    };
+
+    let payment_info = match unlocked_state.inbound_payments().get(&payment_hash) {
+        Some(v) => v.clone(),
+        None => return Err(APIError::UnknownLNInvoice),
+    };
+    // the expiry of invoices created before it was recorded is only known from the invoice
+    let expires_at = payment_info.expires_at.or(invoice_expires_at);
+    let hodl_invoice = unlocked_state.hodl_invoices().get(&payment_hash).cloned();
+    let status = match payment_info.status {
+        HTLCStatus::Pending
+            if hodl_invoice
+                .as_ref()
+                .is_some_and(|h| h.claimable_amt_msat.is_some()) =>
+        {
+            InvoiceStatus::Held
+        }
+        HTLCStatus::Pending if expires_at.is_some_and(|e| e <= get_current_timestamp()) => {
+            InvoiceStatus::Expired
+        }
+        HTLCStatus::Pending => InvoiceStatus::Pending,
+        HTLCStatus::Succeeded => InvoiceStatus::Succeeded,
+        HTLCStatus::Failed => InvoiceStatus::Failed,
+    };
+    let settled_at = match status {
+        InvoiceStatus::Succeeded => Some(payment_info.updated_at),
+        _ => None,
+    };
+    // held payments need to be settled before LDK fails them back to avoid a force close
+    let held_deadline = match (status, hodl_invoice) {
+        (InvoiceStatus::Held, Some(hodl_invoice)) => {
+            Some(unlocked_state.held_payment_deadline(&payment_hash, hodl_invoice.claim_deadline))
+        }
+        _ => None,
+    };
+
+    Ok(Json(InvoiceStatusResponse {
+        status,
+        amt_msat: payment_info.amt_msat,
+        expires_at,
+        settled_at,
+        cltv_expiry: held_deadline.as_ref().and_then(|d| d.cltv_expiry),
+        current_height: held_deadline.as_ref().map(|d| d.current_height),
+        blocks_to_cancel: held_deadline.and_then(|d| d.blocks_to_cancel),
+        fallback_txid: unlocked_state.fallback_txid(&payment_hash),
+    }))
+}
+
+pub(crate) async fn issue_asset_cfa(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<IssueAssetCFARequest>, APIError>,
+) -> Result<Json<IssueAssetCFAResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let _rgb_spend_guard = unlocked_state.rgb_spend_lock.lock().await;
+        if *unlocked_state.rgb_send_lock.lock().unwrap() {
+            return Err(APIError::OpenChannelInProgress);
+        }
+
+        let file_path = payload
+            .file_digest
+            .map(|d| get_media_path(unlocked_state, &d))
+            .transpose()?
+            .map(|p| p.to_string_lossy().to_string());
+
+        let asset = unlocked_state.rgb_issue_asset_cfa(
+            payload.name,
+            payload.details,
+            payload.precision,
+            payload.amounts,
+            file_path,
+        )?;
+
+        Ok(Json(IssueAssetCFAResponse {
+            asset: asset.into(),
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn issue_asset_ifa(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<IssueAssetIFARequest>, APIError>,
+) -> Result<Json<IssueAssetIFAResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let _rgb_spend_guard = unlocked_state.rgb_spend_lock.lock().await;
+        if *unlocked_state.rgb_send_lock.lock().unwrap() {
+            return Err(APIError::OpenChannelInProgress);
+        }
+
+        let asset = unlocked_state.rgb_issue_asset_ifa(
+            payload.ticker,
+            payload.name,
+            payload.precision,
+            payload.amounts,
+            payload.inflation_amounts,
+        )?;
+
+        Ok(Json(IssueAssetIFAResponse {
+            asset: asset.into(),
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn issue_asset_nia(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<IssueAssetNIARequest>, APIError>,
+) -> Result<Json<IssueAssetNIAResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let _rgb_spend_guard = unlocked_state.rgb_spend_lock.lock().await;
+        if *unlocked_state.rgb_send_lock.lock().unwrap() {
+            return Err(APIError::OpenChannelInProgress);
+        }
+
+        let asset = unlocked_state.rgb_issue_asset_nia(
+            payload.ticker,
+            payload.name,
+            payload.precision,
+            payload.amounts,
+        )?;
+
+        Ok(Json(IssueAssetNIAResponse {
+            asset: asset.into(),
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn issue_asset_uda(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<IssueAssetUDARequest>, APIError>,
+) -> Result<Json<IssueAssetUDAResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let _rgb_spend_guard = unlocked_state.rgb_spend_lock.lock().await;
+        if *unlocked_state.rgb_send_lock.lock().unwrap() {
+            return Err(APIError::OpenChannelInProgress);
+        }
+
+        let get_string_path =
+            |d: String| get_media_path(unlocked_state, &d).map(|p| p.to_string_lossy().to_string());
+        let media_file_path = payload.media_file_digest.map(get_string_path).transpose()?;
+        let attachments_file_paths = payload
+            .attachments_file_digests
+            .into_iter()
+            .map(get_string_path)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let asset = unlocked_state.rgb_issue_asset_uda(
+            payload.ticker,
+            payload.name,
+            payload.details,
+            payload.precision,
+            media_file_path,
+            attachments_file_paths,
+        )?;
+
+        Ok(Json(IssueAssetUDAResponse {
+            asset: asset.into(),
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn journal_proof(
+    State(state): State<Arc<AppState>>,
+    WithRejection(AxumPath(range), _): WithRejection<AxumPath<String>, APIError>,
+) -> Result<Json<JournalProofResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let (start, end) = range
+        .split_once('-')
+        .and_then(|(s, e)| Some((s.parse::<u64>().ok()?, e.parse::<u64>().ok()?)))
+        .ok_or_else(|| APIError::InvalidJournalRange(s!("expected START-END")))?;
+    if start > end {
+        return Err(APIError::InvalidJournalRange(s!(
+            "start cannot be greater than end"
+        )));
+    }
+    if end - start >= MAX_JOURNAL_PROOF_ENTRIES {
+        return Err(APIError::InvalidJournalRange(format!(
+            "cannot export more than {MAX_JOURNAL_PROOF_ENTRIES} entries"
+        )));
+    }
+
+    let entries = unlocked_state
+        .journal_entries(start, end)?
+        .into_iter()
+        .map(|e| JournalEntry {
+            seq: e.seq,
+            timestamp: e.timestamp,
+            kind: e.kind,
+            details: e.details,
+            prev_hash: hex_str(&e.prev_hash),
+            hash: hex_str(&e.hash),
+            signature: e.signature,
+        })
+        .collect();
+
+    Ok(Json(JournalProofResponse {
+        pubkey: unlocked_state.channel_manager.get_our_node_id().to_string(),
+        entries,
+    }))
+}
+
+pub(crate) async fn keysend(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<KeysendRequest>, APIError>,
+) -> Result<Json<KeysendResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        state.check_draining()?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let dest_pubkey = match hex_str_to_compressed_pubkey(&payload.dest_pubkey) {
+            Some(pk) => pk,
+            None => return Err(APIError::InvalidPubkey),
+        };
+
+        let amt_msat = payload.amt_msat;
+        if amt_msat < HTLC_MIN_MSAT {
+            return Err(APIError::InvalidAmount(format!(
+                "amt_msat cannot be less than {HTLC_MIN_MSAT}"
+            )));
+        }
+
+        let payment_preimage =
+            PaymentPreimage(unlocked_state.keys_manager.get_secure_random_bytes());
+        let payment_hash_inner = Sha256::hash(&payment_preimage.0[..]).to_byte_array();
+        let payment_id = PaymentId(payment_hash_inner);
+        let payment_hash = PaymentHash(payment_hash_inner);
+
+        let rgb_payment = match (payload.asset_id, payload.asset_amount) {
+            (Some(asset_id), Some(rgb_amount)) => {
+                let contract_id = ContractId::from_str(&asset_id)
+                    .map_err(|_| APIError::InvalidAssetID(asset_id))?;
+                unlocked_state.check_asset_htlc_min(
+                    contract_id,
+                    amt_msat,
+                    &state.static_state.ldk_data_dir,
+                )?;
+                Some((contract_id, rgb_amount))
+            }
+            (None, None) => None,
+            _ => {
+                return Err(APIError::IncompleteRGBInfo);
+            }
+        };
+        // payments of RGB assets always need the code, as their value isn't in msat
+        let totp_operation = if rgb_payment.is_some() {
+            TotpOperation::Spend
+        } else {
+            TotpOperation::Payment(amt_msat)
+        };
+        unlocked_state.check_totp(payload.totp_code.as_deref(), totp_operation)?;
+
+        let mut payment_params = PaymentParameters::for_keysend(dest_pubkey, 40, false);
+        payment_params.max_total_cltv_expiry_delta = state.static_state.max_total_cltv_expiry_delta;
+        // no route fits a null CLTV budget, so the route search fails as on a real network
+        #[cfg(feature = "failure-injection")]
+        if unlocked_state.take_injected_no_route() {
+            payment_params.max_total_cltv_expiry_delta = 0;
+        }
+        let route_params =
+            RouteParameters::from_payment_params_and_value(payment_params, amt_msat, rgb_payment);
+        let created_at = get_current_timestamp();
+        unlocked_state.add_outbound_payment(
+            payment_id,
+            PaymentInfo {
+                preimage: None,
+                secret: None,
+                status: HTLCStatus::Pending,
+                amt_msat: Some(amt_msat),
+                created_at,
+                updated_at: created_at,
+                payee_pubkey: dest_pubkey,
+                offer_id: None,
+                parts: vec![],
+                expires_at: None,
+                settle_after_expiry: false,
+            },
+        )?;
+        if let Some((contract_id, rgb_amount)) = rgb_payment {
+            write_rgb_payment_info_file(
+                &PathBuf::from(&state.static_state.ldk_data_dir),
+                &payment_hash,
+                contract_id,
+                rgb_amount,
+                false,
+                false,
+            );
+        }
+
+        let status = match unlocked_state.channel_manager.send_spontaneous_payment(
+            Some(payment_preimage),
+            RecipientOnionFields::spontaneous_empty(),
+            payment_id,
+            route_params,
+            Retry::Timeout(Duration::from_secs(10)),
+        ) {
+            Ok(_payment_hash) => {
+                tracing::info!(
+                    "EVENT: initiated sending {} msats to {}",
+                    amt_msat,
+                    dest_pubkey
+                );
+                HTLCStatus::Pending
+            }
+            Err(e) => {
+                tracing::error!("ERROR: failed to send payment: {:?}", e);
+                unlocked_state.update_outbound_payment_status(payment_id, HTLCStatus::Failed);
+                HTLCStatus::Failed
+            }
+        };
+
+        Ok(Json(KeysendResponse {
+            payment_hash: hex_str(&payment_hash.0),
+            payment_preimage: hex_str(&payment_preimage.0),
+            status,
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn lease_config(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<LeaseConfigResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    Ok(Json(unlocked_state.lsps_handler.lease_config().into()))
+}
+
+pub(crate) async fn lease_orders(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<LeaseOrdersResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let mut orders: Vec<LeaseOrder> = unlocked_state
+        .lsps_handler
+        .lease_orders()
+        .into_iter()
+        .map(|(order_id, order)| LeaseOrder {
+            order_id,
+            client_pubkey: order.client_pubkey.to_string(),
+            capacity_sat: order.capacity_sat,
+            asset_id: order.asset_id,
+            asset_amount: order.asset_amount,
+            fee_sat: order.fee_sat,
+            lease_duration_blocks: order.lease_duration_blocks,
+            payment_hash: hex_str(&order.payment_hash.0),
+            status: order.status,
+            temporary_channel_id: order.temporary_channel_id,
+            expires_at: order.expires_at,
+            created_at: order.created_at,
+            updated_at: order.updated_at,
+        })
+        .collect();
+    orders.sort_by_key(|o| o.created_at);
+
+    Ok(Json(LeaseOrdersResponse { orders }))
+}
+
+pub(crate) async fn lease_rates(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<LeaseRatesRequest>, APIError>,
+) -> Result<Json<LeaseRatesResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let seller_pubkey =
+            connect_lsp(&state, unlocked_state, payload.peer_pubkey_and_opt_addr).await?;
+        let rates = unlocked_state
+            .lsps_handler
+            .lease_get_rates(&unlocked_state.peer_manager, seller_pubkey)
+            .await?;
+
+        Ok(Json(LeaseRatesResponse {
+            min_channel_size_sat: rates.min_channel_size_sat,
+            max_channel_size_sat: rates.max_channel_size_sat,
+            fee_base_sat: rates.fee_base_sat,
+            fee_proportional_millionths: rates.fee_proportional_millionths,
+            lease_duration_blocks: rates.lease_duration_blocks,
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn list_assets(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ListAssetsRequest>, APIError>,
+) -> Result<Json<Page<Asset>>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let offset = page_offset(payload.limit, payload.cursor.as_deref())?;
+
+    let rgb_assets = unlocked_state.rgb_list_assets(
+        payload
+            .filter_asset_schemas
+            .into_iter()
+            .map(|s| s.into())
+            .collect(),
+    )?;
+
+    let mut offchain_balances = HashMap::new();
+    for chan_info in unlocked_state.channel_manager.list_channels() {
+        let info_file_path = get_rgb_channel_info_path(
+            &chan_info.channel_id.0.as_hex().to_string(),
+            &state.static_state.ldk_data_dir,
+            false,
+        );
+        if !info_file_path.exists() {
+            continue;
+        }
+        let rgb_info = parse_rgb_channel_info(&info_file_path);
+        offchain_balances
+            .entry(rgb_info.contract_id.to_string())
+            .and_modify(|(offchain_outbound, offchain_inbound)| {
+                *offchain_outbound += rgb_info.local_rgb_amount;
+                *offchain_inbound += rgb_info.remote_rgb_amount;
+            })
+            .or_insert((rgb_info.local_rgb_amount, rgb_info.remote_rgb_amount));
+    }
+
+    let offchain_balance = |asset_id: &str| *offchain_balances.get(asset_id).unwrap_or(&(0, 0));
+    let mut assets = vec![];
+    for asset in rgb_assets.nia.unwrap_or_default() {
+        let mut asset: AssetNIA = asset.into();
+        (
+            asset.balance.offchain_outbound,
+            asset.balance.offchain_inbound,
+        ) = offchain_balance(&asset.asset_id);
+        assets.push(Asset::Nia(asset));
+    }
+    for asset in rgb_assets.uda.unwrap_or_default() {
+        let mut asset: AssetUDA = asset.into();
+        (
+            asset.balance.offchain_outbound,
+            asset.balance.offchain_inbound,
+        ) = offchain_balance(&asset.asset_id);
+        assets.push(Asset::Uda(asset));
+    }
+    for asset in rgb_assets.cfa.unwrap_or_default() {
+        let mut asset: AssetCFA = asset.into();
+        (
+            asset.balance.offchain_outbound,
+            asset.balance.offchain_inbound,
+        ) = offchain_balance(&asset.asset_id);
+        assets.push(Asset::Cfa(asset));
+    }
+    for asset in rgb_assets.ifa.unwrap_or_default() {
+        let mut asset: AssetIFA = asset.into();
+        (
+            asset.balance.offchain_outbound,
+            asset.balance.offchain_inbound,
+        ) = offchain_balance(&asset.asset_id);
+        assets.push(Asset::Ifa(asset));
+    }
+
+    Ok(Json(Page::new(assets, offset, payload.limit)))
+}
+
+/// The /v1 /listassets, returning all the assets grouped by schema
+pub(crate) async fn list_assets_v1(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ListAssetsRequest>, APIError>,
+) -> Result<Json<ListAssetsResponse>, APIError> {
+    // without a filter all the schemas are returned, each one as an empty list if it has no assets
+    let schemas = &payload.filter_asset_schemas;
+    let requested = |schema| schemas.is_empty() || schemas.contains(&schema);
+    let mut assets = ListAssetsResponse {
+        nia: requested(AssetSchema::Nia).then(Vec::new),
+        uda: requested(AssetSchema::Uda).then(Vec::new),
+        cfa: requested(AssetSchema::Cfa).then(Vec::new),
+        ifa: requested(AssetSchema::Ifa).then(Vec::new),
+    };
+    let payload = ListAssetsRequest {
+        filter_asset_schemas: payload.filter_asset_schemas,
+        limit: None,
+        cursor: None,
+    };
+    let page = list_assets(State(state), WithRejection(Json(payload), PhantomData)).await?;
+    for asset in page.0.items {
+        match asset {
+            Asset::Nia(asset) => assets.nia.get_or_insert_with(Vec::new).push(asset),
+            Asset::Uda(asset) => assets.uda.get_or_insert_with(Vec::new).push(asset),
+            Asset::Cfa(asset) => assets.cfa.get_or_insert_with(Vec::new).push(asset),
+            Asset::Ifa(asset) => assets.ifa.get_or_insert_with(Vec::new).push(asset),
+        }
+    }
+
+    Ok(Json(assets))
+}
+
+pub(crate) async fn list_channels(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Query(payload), _): WithRejection<Query<PageRequest>, APIError>,
+) -> Result<Json<Page<Channel>>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let offset = page_offset(payload.limit, payload.cursor.as_deref())?;
+
+    let mut channels = vec![];
+    for chan_info in unlocked_state.channel_manager.list_channels() {
+        let status = match chan_info.channel_shutdown_state.unwrap() {
+            ChannelShutdownState::NotShuttingDown => {
+                if chan_info.is_channel_ready {
+                    ChannelStatus::Opened
+                } else {
+                    ChannelStatus::Opening
+                }
+            }
+            _ => ChannelStatus::Closing,
+        };
+        let mut channel = Channel {
+            channel_id: chan_info.channel_id.0.as_hex().to_string(),
+            peer_pubkey: hex_str(&chan_info.counterparty.node_id.serialize()),
+            status,
+            ready: chan_info.is_channel_ready,
+            capacity_sat: chan_info.channel_value_satoshis,
+            outbound_balance_msat: chan_info.outbound_capacity_msat,
+            inbound_balance_msat: chan_info.inbound_capacity_msat,
+            next_outbound_htlc_limit_msat: chan_info.next_outbound_htlc_limit_msat,
+            next_outbound_htlc_minimum_msat: chan_info.next_outbound_htlc_minimum_msat,
+            is_usable: chan_info.is_usable,
+            public: chan_info.is_announced,
+            with_anchors: chan_info
+                .channel_type
+                .as_ref()
+                .is_some_and(|t| t.supports_anchors_zero_fee_htlc_tx()),
+            scid_alias: chan_info
+                .channel_type
+                .as_ref()
+                .is_some_and(|t| t.supports_scid_privacy()),
+            inbound_htlc_minimum_msat: chan_info.inbound_htlc_minimum_msat,
+            inbound_htlc_maximum_msat: chan_info.inbound_htlc_maximum_msat,
+            local_reserve_sat: chan_info.unspendable_punishment_reserve,
+            remote_reserve_sat: chan_info.counterparty.unspendable_punishment_reserve,
+            fee_base_msat: chan_info.config.map(|c| c.forwarding_fee_base_msat),
+            fee_proportional_millionths: chan_info
+                .config
+                .map(|c| c.forwarding_fee_proportional_millionths),
+            cltv_expiry_delta: chan_info.config.map(|c| c.cltv_expiry_delta),
+            ..Default::default()
+        };
+
+        if let Some(funding_txo) = chan_info.funding_txo {
+            channel.funding_txid = Some(funding_txo.txid.to_string());
+            if let Ok(chan_monitor) = unlocked_state
+                .chain_monitor
+                .get_monitor(chan_info.channel_id)
+            {
+                channel.local_balance_sat = chan_monitor
+                    .get_claimable_balances()
+                    .iter()
+                    .map(|b| b.claimable_amount_satoshis())
+                    .sum::<u64>();
+            }
+        }
+
+        if let Some(node_info) = unlocked_state
+            .network_graph
+            .read_only()
+            .nodes()
+            .get(&NodeId::from_pubkey(&chan_info.counterparty.node_id))
+        {
+            if let Some(announcement) = &node_info.announcement_info {
+                channel.peer_alias = Some(announcement.alias().to_string());
+            }
+        }
+
+        if let Some(id) = chan_info.short_channel_id {
+            channel.short_channel_id = Some(id);
+        }
+
+        let info_file_path = get_rgb_channel_info_path(
+            &chan_info.channel_id.0.as_hex().to_string(),
+            &state.static_state.ldk_data_dir,
+            false,
+        );
+        if info_file_path.exists() {
+            let rgb_info = parse_rgb_channel_info(&info_file_path);
+            channel.asset_id = Some(rgb_info.contract_id.to_string());
+            channel.asset_local_amount = Some(rgb_info.local_rgb_amount);
+            channel.asset_remote_amount = Some(rgb_info.remote_rgb_amount);
+            channel.asset_htlc_min_msat = Some(
+                unlocked_state
+                    .asset_htlc_min_handler
+                    .agreed_msat(&chan_info.channel_id)
+                    .unwrap_or(HTLC_MIN_MSAT),
+            );
+        };
+
+        channels.push(channel);
+    }
+    // the channel manager has no stable order, pages need one so they don't overlap
+    channels.sort_by(|a, b| a.channel_id.cmp(&b.channel_id));
+
+    Ok(Json(Page::new(channels, offset, payload.limit)))
+}
+
+/// The /v1 /listchannels, returning all the channels
+pub(crate) async fn list_channels_v1(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListChannelsResponse>, APIError> {
+    let page = list_channels(
+        State(state),
+        WithRejection(Query(PageRequest::default()), PhantomData),
+    )
+    .await?;
+
+    Ok(Json(ListChannelsResponse {
+        channels: page.0.items,
+    }))
+}
+
+pub(crate) async fn list_escrows(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListEscrowsResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let mut escrows: Vec<Escrow> = unlocked_state
+        .escrows()
+        .into_iter()
+        .map(|(escrow_id, escrow)| unlocked_state.escrow_response(escrow_id, escrow))
+        .collect();
+    escrows.sort_by_key(|e| e.created_at);
+
+    Ok(Json(ListEscrowsResponse { escrows }))
+}
+
+pub(crate) async fn list_hedges(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListHedgesResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let mut hedges: Vec<Hedge> = unlocked_state
+        .hedges()
+        .iter()
+        .map(|(payment_hash, hedge)| hedge.to_hedge(payment_hash))
+        .collect();
+    hedges.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+    Ok(Json(ListHedgesResponse { hedges }))
+}
+
+pub(crate) async fn list_offers(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListOffersResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let mut offers: Vec<Bolt12Offer> = unlocked_state
+        .offers()
+        .into_iter()
+        .map(|(offer_id, info)| Bolt12Offer {
+            offer_id: hex_str(&offer_id.0),
+            offer: info.offer,
+            description: info.description,
+            amt_msat: info.amt_msat,
+            created_at: info.created_at,
+            expires_at: info.expires_at,
+        })
+        .collect();
+    offers.sort_by_key(|o| o.created_at);
+
+    Ok(Json(ListOffersResponse { offers }))
+}
+
+pub(crate) async fn list_payments(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Query(payload), _): WithRejection<Query<ListPaymentsRequest>, APIError>,
+) -> Result<Json<Page<Payment>>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    if payload.offset.is_some() {
+        return Err(APIError::InvalidPagination(s!(
+            "offset is only accepted by the /v1 API, use cursor"
+        )));
+    }
+    let offset = page_offset(payload.limit, payload.cursor.as_deref())?;
+    let payment_hash_prefix = match payload.payment_hash_prefix {
+        Some(prefix) if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_hexdigit()) => {
+            return Err(APIError::InvalidPaymentHash(prefix));
+        }
+        Some(prefix) => Some(prefix.to_lowercase()),
+        None => None,
+    };
+
+    let inbound_payments = unlocked_state.inbound_payments();
+    let outbound_payments = unlocked_state.outbound_payments();
+    let mut payments = vec![];
+
+    for (payment_hash, payment_info) in &inbound_payments {
+        let rgb_payment_info_path_inbound =
+            get_rgb_payment_info_path(payment_hash, &state.static_state.ldk_data_dir, true);
+
+        let (asset_amount, asset_id) = if rgb_payment_info_path_inbound.exists() {
+            let info = parse_rgb_payment_info(&rgb_payment_info_path_inbound);
+            (Some(info.amount), Some(info.contract_id.to_string()))
+        } else {
+            (None, None)
+        };
+
+        payments.push(Payment {
+            amt_msat: payment_info.amt_msat,
+            asset_amount,
+            asset_id,
+            payment_hash: hex_str(&payment_hash.0),
+            inbound: true,
+            status: payment_info.status,
+            created_at: payment_info.created_at,
+            updated_at: payment_info.updated_at,
+            payee_pubkey: payment_info.payee_pubkey.to_string(),
+            offer_id: payment_info.offer_id.map(|o| hex_str(&o.0)),
+        });
+    }
+
+    for (payment_id, payment_info) in &outbound_payments {
+        let payment_hash = &PaymentHash(payment_id.0);
+
+        let rgb_payment_info_path_outbound =
+            get_rgb_payment_info_path(payment_hash, &state.static_state.ldk_data_dir, false);
+
+        let (asset_amount, asset_id) = if rgb_payment_info_path_outbound.exists() {
+            let info = parse_rgb_payment_info(&rgb_payment_info_path_outbound);
+            (Some(info.amount), Some(info.contract_id.to_string()))
+        } else {
+            (None, None)
+        };
+
+        payments.push(Payment {
+            amt_msat: payment_info.amt_msat,
+            asset_amount,
+            asset_id,
+            payment_hash: hex_str(&payment_hash.0),
+            inbound: false,
+            status: payment_info.status,
+            created_at: payment_info.created_at,
+            updated_at: payment_info.updated_at,
+            payee_pubkey: payment_info.payee_pubkey.to_string(),
+            offer_id: payment_info.offer_id.map(|o| hex_str(&o.0)),
+        });
+    }
+
+    payments.retain(|p| {
+        payload.inbound.is_none_or(|inbound| p.inbound == inbound)
+            && payload.status.is_none_or(|status| p.status == status)
+            && payload
+                .asset_id
+                .as_ref()
+                .is_none_or(|asset_id| p.asset_id.as_ref() == Some(asset_id))
+            && payload.created_from.is_none_or(|from| p.created_at >= from)
+            && payload.created_to.is_none_or(|to| p.created_at <= to)
+            && payment_hash_prefix
+                .as_ref()
+                .is_none_or(|prefix| p.payment_hash.starts_with(prefix))
+    });
+    // newest first, with a stable order so pages don't overlap
+    payments.sort_by(|a, b| {
+        b.created_at
+            .cmp(&a.created_at)
+            .then_with(|| a.payment_hash.cmp(&b.payment_hash))
+            .then_with(|| a.inbound.cmp(&b.inbound))
+    });
+
+    Ok(Json(Page::new(payments, offset, payload.limit)))
+}
+
+/// The /v1 /listpayments, paginated by `offset` instead of cursors
+pub(crate) async fn list_payments_v1(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Query(payload), _): WithRejection<Query<ListPaymentsRequest>, APIError>,
+) -> Result<Json<ListPaymentsResponse>, APIError> {
+    let payload = ListPaymentsRequest {
+        cursor: payload.offset.map(|offset| offset.to_string()),
+        offset: None,
+        ..payload
+    };
+    let page = list_payments(State(state), WithRejection(Query(payload), PhantomData)).await?;
+
+    Ok(Json(ListPaymentsResponse {
+        payments: page.0.items,
+        total: page.0.total,
+    }))
+}
+
+pub(crate) async fn get_order(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<GetOrderRequest>, APIError>,
+) -> Result<Json<GetOrderResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let order = unlocked_state
+        .orders()
+        .remove(&payload.order_id)
+        .ok_or(APIError::UnknownOrder)?;
+
+    Ok(Json(GetOrderResponse {
+        order: unlocked_state.order_response(payload.order_id, order),
+    }))
+}
+
+pub(crate) async fn get_payment(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<GetPaymentRequest>, APIError>,
+) -> Result<Json<GetPaymentResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let payment_hash_vec = hex_str_to_vec(&payload.payment_hash);
+    if payment_hash_vec.is_none() || payment_hash_vec.as_ref().unwrap().len() != 32 {
+        return Err(APIError::InvalidPaymentHash(payload.payment_hash));
+    }
+    let requested_ph = PaymentHash(payment_hash_vec.unwrap().try_into().unwrap());
+
+    let inbound_payments = unlocked_state.inbound_payments();
+    let outbound_payments = unlocked_state.outbound_payments();
+
+    for (payment_hash, payment_info) in &inbound_payments {
+        if payment_hash == &requested_ph {
+            let rgb_payment_info_path_inbound =
+                get_rgb_payment_info_path(payment_hash, &state.static_state.ldk_data_dir, true);
+
+            let (asset_amount, asset_id) = if rgb_payment_info_path_inbound.exists() {
+                let info = parse_rgb_payment_info(&rgb_payment_info_path_inbound);
+                (Some(info.amount), Some(info.contract_id.to_string()))
+            } else {
+                (None, None)
+            };
+
+            return Ok(Json(GetPaymentResponse {
+                payment: Payment {
+                    amt_msat: payment_info.amt_msat,
+                    asset_amount,
+                    asset_id,
+                    payment_hash: hex_str(&payment_hash.0),
+                    inbound: true,
+                    status: payment_info.status,
+                    created_at: payment_info.created_at,
+                    updated_at: payment_info.updated_at,
+                    payee_pubkey: payment_info.payee_pubkey.to_string(),
+                    offer_id: payment_info.offer_id.map(|o| hex_str(&o.0)),
+                },
+                preimage: payment_info.preimage.map(|p| hex_str(&p.0)),
+                resolved_at: payment_resolved_at(payment_info),
+                parts: vec![],
+                fees: vec![],
+            }));
+        }
+    }
+
+    for (payment_id, payment_info) in &outbound_payments {
+        let payment_hash = &PaymentHash(payment_id.0);
+        if payment_hash == &requested_ph {
+            let rgb_payment_info_path_outbound =
+                get_rgb_payment_info_path(payment_hash, &state.static_state.ldk_data_dir, false);
+
+            let (asset_amount, asset_id) = if rgb_payment_info_path_outbound.exists() {
+                let info = parse_rgb_payment_info(&rgb_payment_info_path_outbound);
+                (Some(info.amount), Some(info.contract_id.to_string()))
+            } else {
+                (None, None)
+            };
+
+            return Ok(Json(GetPaymentResponse {
+                payment: Payment {
+                    amt_msat: payment_info.amt_msat,
+                    asset_amount,
+                    asset_id,
+                    payment_hash: hex_str(&payment_hash.0),
+                    inbound: false,
+                    status: payment_info.status,
+                    created_at: payment_info.created_at,
+                    updated_at: payment_info.updated_at,
+                    payee_pubkey: payment_info.payee_pubkey.to_string(),
+                    offer_id: payment_info.offer_id.map(|o| hex_str(&o.0)),
+                },
+                preimage: payment_info.preimage.map(|p| hex_str(&p.0)),
+                resolved_at: payment_resolved_at(payment_info),
+                parts: payment_info
+                    .parts
+                    .iter()
+                    .map(|p| PaymentPart {
+                        amt_msat: p.amt_msat,
+                        fee_msat: p.fee_msat,
+                        first_hop_scid: p.first_hop_scid,
+                        hops: p.hops,
+                        status: p.status,
+                        failure: p.failure.clone(),
+                        updated_at: p.updated_at,
+                        asset_id: p.asset_id.clone(),
+                        asset_amount: p.asset_amount,
+                        route: p
+                            .route
+                            .iter()
+                            .map(|h| PaymentHop {
+                                pubkey: h.pubkey.to_string(),
+                                short_channel_id: h.short_channel_id,
+                                fee_msat: h.fee_msat,
+                                cltv_expiry_delta: h.cltv_expiry_delta,
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+                fees: payment_fees(&payment_info.parts),
+            }));
+        }
+    }
+
+    Err(APIError::PaymentNotFound(payload.payment_hash))
+}
+
+pub(crate) async fn list_peers(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Query(payload), _): WithRejection<Query<PageRequest>, APIError>,
+) -> Result<Json<Page<Peer>>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let offset = page_offset(payload.limit, payload.cursor.as_deref())?;
+
+    let now = get_current_timestamp();
+    let mut peers = vec![];
+    for peer_details in unlocked_state.peer_manager.list_peers() {
+        let counterparty = peer_details.counterparty_node_id;
+        let (gossip_stats, gossip_limit) = unlocked_state.gossip_handler.peer_stats(&counterparty);
+        // onion service connections are forwarded by the local Tor daemon, so they come from
+        // loopback (as do the ones of peers running on the same host), while outbound ones made
+        // through the SOCKS proxy report the address of the proxy
+        let transport = match &peer_details.socket_address {
+            Some(SocketAddress::OnionV2(_) | SocketAddress::OnionV3 { .. }) => PeerTransport::Tor,
+            Some(SocketAddress::TcpIpV4 { addr, .. })
+                if peer_details.is_inbound_connection && addr[0] == 127 =>
+            {
+                PeerTransport::OnionServiceInbound
+            }
+            Some(SocketAddress::TcpIpV6 { addr, .. })
+                if peer_details.is_inbound_connection
+                    && std::net::Ipv6Addr::from(*addr).is_loopback() =>
+            {
+                PeerTransport::OnionServiceInbound
+            }
+            _ if !peer_details.is_inbound_connection && state.static_state.tor_proxy.is_some() => {
+                PeerTransport::Tor
+            }
+            _ => PeerTransport::Clearnet,
+        };
+        let connected_at = unlocked_state
+            .peer_connected_at
+            .lock()
+            .unwrap()
+            .get(&counterparty)
+            .copied();
+        let mut features = peer_details.init_features.le_flags().to_vec();
+        features.reverse();
+        peers.push(Peer {
+            pubkey: counterparty.to_string(),
+            address: peer_details.socket_address.as_ref().map(|a| a.to_string()),
+            transport,
+            inbound: peer_details.is_inbound_connection,
+            connected_at,
+            uptime_sec: connected_at.map(|t| now.saturating_sub(t)),
+            init_features: hex_str(&features),
+            num_channels: unlocked_state
+                .channel_manager
+                .list_channels_with_counterparty(&counterparty)
+                .len(),
+            gossip_bytes_received: gossip_stats.bytes_received,
+            gossip_msgs_received: gossip_stats.msgs_received,
+            gossip_msgs_dropped: gossip_stats.msgs_dropped,
+            gossip_limit_bytes_per_min: gossip_limit,
+        })
+    }
+
+    peers.sort_by(|a, b| a.pubkey.cmp(&b.pubkey));
+
+    Ok(Json(Page::new(peers, offset, payload.limit)))
+}
+
+/// The /v1 /listpeers, returning all the peers
+pub(crate) async fn list_peers_v1(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListPeersResponse>, APIError> {
+    let page = list_peers(
+        State(state),
+        WithRejection(Query(PageRequest::default()), PhantomData),
+    )
+    .await?;
+
+    Ok(Json(ListPeersResponse {
+        peers: page.0.items,
+    }))
+}
+
+pub(crate) async fn list_submarine_swaps(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListSubmarineSwapsResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let mut swaps: Vec<SubmarineSwap> = unlocked_state
+        .submarine_swaps()
+        .into_iter()
+        .map(|(id, swap)| swap.to_submarine_swap(id))
+        .collect();
+    swaps.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+    Ok(Json(ListSubmarineSwapsResponse { swaps }))
+}
+
+pub(crate) async fn list_swaps(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListSwapsResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let map_swap = |payment_hash: &PaymentHash, swap_data: &SwapData, taker: bool| {
+        let mut status = swap_data.status.clone();
+        if status == SwapStatus::Waiting && get_current_timestamp() > swap_data.swap_info.expiry {
+            status = SwapStatus::Expired;
+        } else if status == SwapStatus::Pending
+            && get_current_timestamp() > swap_data.initiated_at.unwrap() + 86400
+        {
+            status = SwapStatus::Failed;
+        }
+        if status != swap_data.status {
+            if taker {
+                unlocked_state.update_taker_swap_status(payment_hash, status.clone());
+            } else {
+                unlocked_state.update_maker_swap_status(payment_hash, status.clone());
+            }
+        }
+        Swap {
+            payment_hash: payment_hash.to_string(),
+            qty_from: swap_data.swap_info.qty_from,
+            qty_to: swap_data.swap_info.qty_to,
+            from_asset: swap_data.swap_info.from_asset.map(|c| c.to_string()),
+            to_asset: swap_data.swap_info.to_asset.map(|c| c.to_string()),
+            status,
+            requested_at: swap_data.requested_at,
+            initiated_at: swap_data.initiated_at,
+            expires_at: swap_data.swap_info.expiry,
+            completed_at: swap_data.completed_at,
+        }
+    };
+
+    let taker_swaps = unlocked_state.taker_swaps();
+    let maker_swaps = unlocked_state.maker_swaps();
+
+    Ok(Json(ListSwapsResponse {
+        taker: taker_swaps
+            .iter()
+            .map(|(ph, sd)| map_swap(ph, sd, true))
+            .collect(),
+        maker: maker_swaps
+            .iter()
+            .map(|(ph, sd)| map_swap(ph, sd, false))
+            .collect(),
+    }))
+}
+
+pub(crate) async fn get_swap(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<GetSwapRequest>, APIError>,
+) -> Result<Json<GetSwapResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let payment_hash_vec = hex_str_to_vec(&payload.payment_hash);
+    if payment_hash_vec.is_none() || payment_hash_vec.as_ref().unwrap().len() != 32 {
+        return Err(APIError::InvalidPaymentHash(payload.payment_hash));
+    }
+    let requested_ph = PaymentHash(payment_hash_vec.unwrap().try_into().unwrap());
+
+    let map_swap = |payment_hash: &PaymentHash, swap_data: &SwapData, taker: bool| {
+        let mut status = swap_data.status.clone();
+        if status == SwapStatus::Waiting && get_current_timestamp() > swap_data.swap_info.expiry {
+            status = SwapStatus::Expired;
+        } else if status == SwapStatus::Pending
+            && get_current_timestamp() > swap_data.initiated_at.unwrap() + 86400
+        {
+            status = SwapStatus::Failed;
+        }
+        if status != swap_data.status {
+            if taker {
+                unlocked_state.update_taker_swap_status(payment_hash, status.clone());
+            } else {
+                unlocked_state.update_maker_swap_status(payment_hash, status.clone());
+            }
+        }
+        Swap {
+            payment_hash: payment_hash.to_string(),
+            qty_from: swap_data.swap_info.qty_from,
+            qty_to: swap_data.swap_info.qty_to,
+            from_asset: swap_data.swap_info.from_asset.map(|c| c.to_string()),
+            to_asset: swap_data.swap_info.to_asset.map(|c| c.to_string()),
+            status,
+            requested_at: swap_data.requested_at,
+            initiated_at: swap_data.initiated_at,
+            expires_at: swap_data.swap_info.expiry,
+            completed_at: swap_data.completed_at,
+        }
+    };
+
+    if payload.taker {
+        let taker_swaps = unlocked_state.taker_swaps();
+        if let Some(sd) = taker_swaps.get(&requested_ph) {
+            return Ok(Json(GetSwapResponse {
+                swap: map_swap(&requested_ph, sd, true),
+            }));
+        }
+    } else {
+        let maker_swaps = unlocked_state.maker_swaps();
+        if let Some(sd) = maker_swaps.get(&requested_ph) {
+            return Ok(Json(GetSwapResponse {
+                swap: map_swap(&requested_ph, sd, false),
+            }));
+        }
+    }
+
+    Err(APIError::SwapNotFound(payload.payment_hash))
+}
+
+pub(crate) async fn list_sweeps(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ListSweepsResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let sweeps = get_sweeps(unlocked_state, &state.static_state.ldk_data_dir).await;
+
+    Ok(Json(ListSweepsResponse { sweeps }))
+}
+
+pub(crate) async fn list_transactions(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ListTransactionsRequest>, APIError>,
+) -> Result<Json<ListTransactionsResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let wallet_txs = unlocked_state.rgb_list_transactions(payload.skip_sync)?;
+
+    let mut tx_assets: HashMap<String, Vec<String>> = HashMap::new();
+    for asset_id in unlocked_state.rgb_list_asset_ids()? {
+        for transfer in unlocked_state.rgb_list_transfers(asset_id.clone())? {
+            let Some(txid) = transfer.txid else {
+                continue;
+            };
+            let asset_ids = tx_assets.entry(txid).or_default();
+            if !asset_ids.contains(&asset_id) {
+                asset_ids.push(asset_id.clone());
+            }
+        }
+    }
+    let channel_operations = unlocked_state.channel_operations()?;
+    let tip_height = unlocked_state.channel_manager.current_best_block().height;
+
+    let mut transactions = vec![];
+    for tx in wallet_txs {
+        let asset_ids = tx_assets.remove(&tx.txid).unwrap_or_default();
+        let transaction_type = match tx.transaction_type {
+            rgb_lib::TransactionType::RgbSend => TransactionType::RgbSend,
+            rgb_lib::TransactionType::Drain => TransactionType::Drain,
+            rgb_lib::TransactionType::CreateUtxos => TransactionType::CreateUtxos,
+            rgb_lib::TransactionType::User => TransactionType::User,
+        };
+        // the wallet spends its own inputs, so only the fee leaves it when the outputs come back
+        let direction = if tx.sent == 0 || tx.received > tx.sent {
+            TransactionDirection::Incoming
+        } else if tx.sent - tx.received <= tx.fee {
+            TransactionDirection::SelfTransfer
+        } else {
+            TransactionDirection::Outgoing
+        };
+        transactions.push(Transaction {
+            rgb_commitment: transaction_type == TransactionType::RgbSend || !asset_ids.is_empty(),
+            transaction_type,
+            channel_operation: channel_operations.get(&tx.txid).copied(),
+            txid: tx.txid,
+            received: tx.received,
+            sent: tx.sent,
+            fee: tx.fee,
+            confirmations: tx
+                .confirmation_time
+                .as_ref()
+                .map_or(0, |ct| (tip_height + 1).saturating_sub(ct.height)),
+            confirmation_time: tx.confirmation_time.map(|ct| BlockTime {
+                height: ct.height,
+                timestamp: ct.timestamp,
+            }),
+            direction,
+            asset_ids,
+        })
+    }
+
+    Ok(Json(ListTransactionsResponse { transactions }))
+}
+
+pub(crate) async fn list_transfers(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ListTransfersRequest>, APIError>,
+) -> Result<Json<Page<Transfer>>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    if payload.offset.is_some() {
+        return Err(APIError::InvalidPagination(s!(
+            "offset is only accepted by the /v1 API, use cursor"
+        )));
+    }
+    let offset = page_offset(payload.limit, payload.cursor.as_deref())?;
+
+    let mut transfers: Vec<Transfer> = unlocked_state
+        .rgb_list_transfers(payload.asset_id)?
+        .into_iter()
+        .map(|t| t.into())
+        .collect();
+
+    // everything but sends brings assets to the node
+    transfers.retain(|t| {
+        payload
+            .inbound
+            .is_none_or(|inbound| (t.kind != TransferKind::Send) == inbound)
+            && payload.status.is_none_or(|status| t.status == status)
+            && payload.created_from.is_none_or(|from| t.created_at >= from)
+            && payload.created_to.is_none_or(|to| t.created_at <= to)
+    });
+    // newest first, with a stable order so pages don't overlap
+    transfers.sort_by(|a, b| {
+        b.created_at
+            .cmp(&a.created_at)
+            .then_with(|| b.idx.cmp(&a.idx))
+    });
+
+    Ok(Json(Page::new(transfers, offset, payload.limit)))
+}
+
+/// The /v1 /listtransfers, paginated by `offset` instead of cursors
+pub(crate) async fn list_transfers_v1(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ListTransfersRequest>, APIError>,
+) -> Result<Json<ListTransfersResponse>, APIError> {
+    let payload = ListTransfersRequest {
+        cursor: payload.offset.map(|offset| offset.to_string()),
+        offset: None,
+        ..payload
+    };
+    let page = list_transfers(State(state), WithRejection(Json(payload), PhantomData)).await?;
+
+    Ok(Json(ListTransfersResponse {
+        transfers: page.0.items,
+        total: page.0.total,
+    }))
+}
+
+pub(crate) async fn list_unspents(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ListUnspentsRequest>, APIError>,
+) -> Result<Json<ListUnspentsResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    // inputs of the funding TXs of outbound channels that haven't been broadcast yet
+    let mut reserved_for_funding = HashSet::new();
+    for channel in unlocked_state.channel_manager.list_channels() {
+        let Some(funding_txo) = channel.funding_txo else {
+            continue;
+        };
+        if !channel.is_outbound || channel.is_channel_ready {
+            continue;
+        }
+        let psbt_path = state
+            .static_state
+            .ldk_data_dir
+            .join(format!("psbt_{}", funding_txo.txid));
+        if let Some(psbt) = std::fs::read_to_string(psbt_path)
+            .ok()
+            .and_then(|p| Psbt::from_str(&p).ok())
+        {
+            reserved_for_funding.extend(
+                psbt.unsigned_tx
+                    .input
+                    .iter()
+                    .map(|i| i.previous_output.to_string()),
+            );
+        }
+    }
+
+    let locked_utxos = unlocked_state.locked_utxos();
+    let mut unspents = vec![];
+    for unspent in unlocked_state.rgb_list_unspents(payload.skip_sync)? {
+        let outpoint = unspent.utxo.outpoint.to_string();
+        let confirmations = unlocked_state
+            .bitcoind_client
+            .get_txout_confirmations(
+                &Txid::from_str(&unspent.utxo.outpoint.txid).unwrap(),
+                unspent.utxo.outpoint.vout,
+            )
+            .await
+            .map_err(|e| APIError::FailedBitcoindConnection(e.to_string()))?
+            .unwrap_or(0);
+        let locked_utxo = locked_utxos.get(&outpoint);
+        unspents.push(Unspent {
+            utxo: Utxo {
+                outpoint: outpoint.clone(),
+                btc_amount: unspent.utxo.btc_amount,
+                colorable: unspent.utxo.colorable,
+            },
+            rgb_allocations: unspent
+                .rgb_allocations
+                .iter()
+                .map(|a| RgbAllocation {
+                    asset_id: a.asset_id.clone(),
+                    assignment: a.assignment.clone().into(),
+                    settled: a.settled,
+                })
+                .collect(),
+            confirmations,
+            locked: locked_utxo.is_some(),
+            label: locked_utxo.and_then(|u| u.label.clone()),
+            reserved_for_funding: reserved_for_funding.contains(&outpoint),
+        })
+    }
+    Ok(Json(ListUnspentsResponse { unspents }))
+}
+
+pub(crate) async fn ln_invoice(
+    State(state): State<Arc<AppState>>,
+    token_id: Option<Extension<TokenId>>,
+    WithRejection(Json(payload), _): WithRejection<Json<LNInvoiceRequest>, APIError>,
+) -> Result<Json<LNInvoiceResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        state.check_draining()?;
+        let unlocked_state = guard.as_ref().unwrap();
+        let token_id = token_id.map(|Extension(TokenId(id))| id);
+
+        let contract_id = if let Some(asset_id) = payload.asset_id {
+            Some(ContractId::from_str(&asset_id).map_err(|_| APIError::InvalidAssetID(asset_id))?)
+        } else {
+            None
+        };
+
+        let asset_htlc_min_msat = state.static_state.asset_htlc_min_msat;
+        if contract_id.is_some() && payload.amt_msat.unwrap_or(0) < asset_htlc_min_msat {
+            return Err(APIError::InvalidAmount(format!(
+                "amt_msat cannot be less than {asset_htlc_min_msat} when transferring an RGB asset"
+            )));
+        }
+        check_amount_bounds(payload.amt_msat, payload.min_amt_msat, payload.max_amt_msat)?;
+
+        if let Some(lsp) = &payload.lsp {
+            if contract_id.is_some() {
+                return Err(APIError::InvalidLspOptions(s!(
+                    "JIT channels cannot receive RGB assets"
+                )));
+            }
+            if payload.amt_msat.is_none() {
+                return Err(APIError::InvalidLspOptions(s!(
+                    "amt_msat is required to receive through a JIT channel"
+                )));
+            }
+            if payload.route_hints.is_some() {
+                return Err(APIError::InvalidLspOptions(s!(
+                    "route_hints cannot be provided along with an LSP"
+                )));
+            }
+            if lsp.max_fee_msat == Some(0) {
+                return Err(APIError::InvalidLspOptions(s!(
+                    "max_fee_msat must be greater than 0"
+                )));
+            }
+        }
+
+        if payload.phantom.is_some() {
+            if state.static_state.phantom_secret.is_none() {
+                return Err(APIError::PhantomNotConfigured);
+            }
+            if contract_id.is_some() {
+                return Err(APIError::InvalidPhantomOptions(s!(
+                    "phantom invoices cannot receive RGB assets"
+                )));
+            }
+            if payload.lsp.is_some() || payload.route_hints.is_some() {
+                return Err(APIError::InvalidPhantomOptions(s!(
+                    "route_hints and lsp cannot be provided along with phantom"
+                )));
+            }
+            if payload.min_amt_msat.is_some() || payload.max_amt_msat.is_some() {
+                return Err(APIError::InvalidPhantomOptions(s!(
+                    "min_amt_msat and max_amt_msat cannot be provided along with phantom"
+                )));
+            }
+        }
+
+        if let Some(address_type) = payload.fallback_address_type {
+            if contract_id.is_some() {
+                return Err(APIError::InvalidFallbackOptions(s!(
+                    "invoices receiving RGB assets cannot have an on-chain fallback"
+                )));
+            }
+            if payload.amt_msat.is_none() {
+                return Err(APIError::InvalidFallbackOptions(s!(
+                    "amt_msat is required to add an on-chain fallback"
+                )));
+            }
+            if payload.phantom.is_some() {
+                return Err(APIError::InvalidFallbackOptions(s!(
+                    "phantom invoices cannot have an on-chain fallback"
+                )));
+            }
+            check_address_type(address_type)?;
+        }
+
+        let label = payload.label.map(check_label).transpose()?;
+        let mut route_hints = payload.route_hints.map(parse_route_hints).transpose()?;
+        let phantom_route_hints = payload
+            .phantom
+            .map(|p| parse_phantom_route_hints(p.route_hints))
+            .transpose()?;
+
+        state.check_invoice_quotas(unlocked_state, token_id.as_deref())?;
+
+        let mut jit_channel = None;
+        if let Some(lsp) = payload.lsp {
+            let payment_size_msat = payload.amt_msat.unwrap();
+            let lsp_pubkey =
+                connect_lsp(&state, unlocked_state, lsp.peer_pubkey_and_opt_addr).await?;
+            let fee_menu = unlocked_state
+                .lsps_handler
+                .lsps2_get_info(&unlocked_state.peer_manager, lsp_pubkey, lsp.token)
+                .await?;
+            let (opening_fee_params, opening_fee_msat) =
+                cheapest_opening_fee_params(fee_menu, payment_size_msat).ok_or_else(|| {
+                    APIError::CannotUseLsp(format!(
+                        "no fee parameters offered for a payment of {payment_size_msat} msat"
+                    ))
+                })?;
+            if let Some(max_fee_msat) = lsp.max_fee_msat {
+                if opening_fee_msat > max_fee_msat {
+                    return Err(APIError::CannotUseLsp(format!(
+                        "opening fee of {opening_fee_msat} msat exceeds the max of {max_fee_msat} msat"
+                    )));
+                }
+            }
+            let buy_result = unlocked_state
+                .lsps_handler
+                .lsps2_buy(
+                    &unlocked_state.peer_manager,
+                    lsp_pubkey,
+                    &opening_fee_params,
+                    payment_size_msat,
+                )
+                .await?;
+            let scid = buy_result.scid().ok_or_else(|| {
+                APIError::FailedLspRequest(format!(
+                    "invalid JIT channel SCID {}",
+                    buy_result.jit_channel_scid
+                ))
+            })?;
+            route_hints = Some(vec![RouteHint(vec![RouteHintHop {
+                src_node_id: lsp_pubkey,
+                short_channel_id: scid,
+                fees: RoutingFees {
+                    base_msat: 0,
+                    proportional_millionths: 0,
+                },
+                cltv_expiry_delta: buy_result.lsp_cltv_expiry_delta,
+                htlc_minimum_msat: None,
+                htlc_maximum_msat: None,
+                htlc_maximum_rgb: None,
+            }])]);
+            jit_channel = Some((lsp_pubkey, scid, payment_size_msat, opening_fee_msat));
+        }
+
+        let fallback_address = if payload.fallback_address_type.is_some() {
+            Some(
+                unlocked_state
+                    .issue_address(state.static_state.address_gap_limit)
+                    .await?,
+            )
+        } else {
+            None
+        };
+
+        let invoice = if let Some(mut phantom_route_hints) = phantom_route_hints {
+            // any node of the cluster can settle the payment, through its own channels
+            phantom_route_hints.push(unlocked_state.channel_manager.get_phantom_route_hints());
+            create_phantom_invoice(
+                payload.amt_msat,
+                None,
+                String::new(),
+                payload.expiry_sec,
+                phantom_route_hints,
+                Arc::clone(&unlocked_state.keys_manager),
+                Arc::clone(&unlocked_state.keys_manager),
+                Arc::clone(&state.static_state.logger),
+                Currency::from(Network::from(state.static_state.network)),
+                Some(state.static_state.min_final_cltv_expiry_delta),
+                Duration::from_secs(get_current_timestamp()),
+            )
+            .map_err(|e| APIError::FailedInvoiceCreation(e.to_string()))?
+        } else {
+            let invoice_params = Bolt11InvoiceParameters {
+                amount_msats: payload.amt_msat,
+                invoice_expiry_delta_secs: Some(payload.expiry_sec),
+                min_final_cltv_expiry_delta: Some(state.static_state.min_final_cltv_expiry_delta),
+                contract_id,
+                asset_amount: payload.asset_amount,
+                ..Default::default()
+            };
+
+            let invoice = match unlocked_state
+                .channel_manager
+                .create_bolt11_invoice(invoice_params)
+            {
+                Ok(inv) => inv,
+                Err(e) => return Err(APIError::FailedInvoiceCreation(e.to_string())),
+            };
+            unlocked_state.add_invoice_route_hints(
+                invoice,
+                route_hints,
+                fallback_address.as_deref(),
+                contract_id,
+                state.get_runtime_config().max_route_hints,
+                &state.static_state.ldk_data_dir,
+            )?
+        };
+
+        let payment_hash = PaymentHash((*invoice.payment_hash()).to_byte_array());
+        state.record_invoice(payment_hash, token_id);
+        let created_at = get_current_timestamp();
+        if let Some((lsp_pubkey, scid, payment_size_msat, opening_fee_msat)) = jit_channel {
+            unlocked_state.add_jit_channel(
+                payment_hash,
+                JitChannelInfo {
+                    lsp_pubkey,
+                    scid,
+                    payment_size_msat,
+                    opening_fee_msat,
+                    created_at,
+                    expires_at: created_at + payload.expiry_sec as u64,
+                },
+            );
+        }
+        if payload.min_amt_msat.is_some() || payload.max_amt_msat.is_some() {
+            unlocked_state.add_amount_bounds(
+                payment_hash,
+                AmountBoundsInfo {
+                    min_amt_msat: payload.min_amt_msat,
+                    max_amt_msat: payload.max_amt_msat,
+                    expires_at: created_at + payload.expiry_sec as u64,
+                },
+            );
+        }
+        if let Some(address) = fallback_address {
+            unlocked_state.add_fallback_address(
+                payment_hash,
+                FallbackAddressInfo {
+                    address,
+                    amt_msat: payload.amt_msat.unwrap(),
+                    created_at,
+                    expires_at: created_at + payload.expiry_sec as u64,
+                    txid: None,
+                },
+            );
+        }
+        unlocked_state.add_inbound_payment(
+            payment_hash,
+            PaymentInfo {
+                preimage: None,
+                secret: Some(*invoice.payment_secret()),
+                status: HTLCStatus::Pending,
+                amt_msat: payload.amt_msat,
+                created_at,
+                updated_at: created_at,
+                payee_pubkey: unlocked_state.channel_manager.get_our_node_id(),
+                offer_id: None,
+                parts: vec![],
+                expires_at: invoice.expires_at().map(|e| e.as_secs()),
+                settle_after_expiry: payload.settle_after_expiry,
+            },
+        );
+        unlocked_state.add_journal_entry(
+            JournalEventKind::InvoiceCreated,
+            format!(
+                "payment hash {payment_hash}, amt_msat {}",
+                payload.amt_msat.map_or(s!("any"), |a| a.to_string())
+            ),
+        );
+        if label.is_some() {
+            unlocked_state.add_pending_settlement(
+                hex_str(&payment_hash.0),
+                PendingSettlementInfo {
+                    label,
+                    asset_id: None,
+                    recipient_id: None,
+                    expires_at: created_at + payload.expiry_sec as u64,
+                },
+            );
+        }
+
+        Ok(Json(LNInvoiceResponse {
+            invoice: invoice.to_string(),
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn lnurl_pay(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<LnurlPayResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    Ok(Json(unlocked_state.get_lnurl_pay().clone().into()))
+}
+
+pub(crate) async fn lnurl_pay_callback(
+    State(state): State<Arc<AppState>>,
+    WithRejection(AxumPath(name), _): WithRejection<AxumPath<String>, APIError>,
+    WithRejection(Query(payload), _): WithRejection<Query<LnurlPayCallbackRequest>, APIError>,
+) -> Response {
+    lnurl_response(
+        no_cancel(async move {
+            let guard = state.check_unlocked().await?;
+            state.check_draining()?;
+            let unlocked_state = guard.as_ref().unwrap();
+
+            let (address, metadata) = {
+                let lnurl_pay = unlocked_state.get_lnurl_pay();
+                let address = lnurl_pay
+                    .address(&name)
+                    .filter(|_| lnurl_pay.domain.is_some())
+                    .ok_or(APIError::UnknownLightningAddress)?
+                    .clone();
+                let metadata = lnurl_pay.metadata(&address);
+                (address, metadata)
+            };
+
+            if payload.amount < address.min_sendable_msat
+                || payload.amount > address.max_sendable_msat
+            {
+                return Err(APIError::InvalidAmount(format!(
+                    "amount must be between {} and {} msat",
+                    address.min_sendable_msat, address.max_sendable_msat
+                )));
+            }
+            // asset IDs are validated when the address is configured
+            let contract_id = match (&address.asset_id, payload.asset_amount) {
+                (Some(asset_id), Some(asset_amount)) if asset_amount > 0 => {
+                    Some(ContractId::from_str(asset_id).unwrap())
+                }
+                (Some(_), _) => {
+                    return Err(APIError::InvalidAmount(s!(
+                        "a positive asset_amount is required by this address"
+                    )))
+                }
+                (None, Some(_)) => {
+                    return Err(APIError::InvalidAmount(s!(
+                        "this address doesn't receive RGB assets"
+                    )))
+                }
+                (None, None) => None,
+            };
+
+            // anyone can request these invoices, so only the node quotas apply
+            state.check_invoice_quotas(unlocked_state, None)?;
+            let invoice_params = Bolt11InvoiceParameters {
+                amount_msats: Some(payload.amount),
+                description: Bolt11InvoiceDescription::Hash(lightning_invoice::Sha256(
+                    Sha256::hash(metadata.as_bytes()),
+                )),
+                invoice_expiry_delta_secs: Some(LNURL_INVOICE_EXPIRY_SEC),
+                min_final_cltv_expiry_delta: Some(state.static_state.min_final_cltv_expiry_delta),
+                contract_id,
+                asset_amount: payload.asset_amount,
+                ..Default::default()
+            };
+            let invoice = unlocked_state
+                .channel_manager
+                .create_bolt11_invoice(invoice_params)
+                .map_err(|e| APIError::FailedInvoiceCreation(e.to_string()))?;
+            let invoice = unlocked_state.add_invoice_route_hints(
+                invoice,
+                None,
+                None,
+                contract_id,
+                state.get_runtime_config().max_route_hints,
+                &state.static_state.ldk_data_dir,
+            )?;
+
+            let payment_hash = PaymentHash((*invoice.payment_hash()).to_byte_array());
+            state.record_invoice(payment_hash, None);
+            let created_at = get_current_timestamp();
+            unlocked_state.add_inbound_payment(
+                payment_hash,
+                PaymentInfo {
+                    preimage: None,
+                    secret: Some(*invoice.payment_secret()),
+                    status: HTLCStatus::Pending,
+                    amt_msat: Some(payload.amount),
+                    created_at,
+                    updated_at: created_at,
+                    payee_pubkey: unlocked_state.channel_manager.get_our_node_id(),
+                    offer_id: None,
+                    parts: vec![],
+                    expires_at: invoice.expires_at().map(|e| e.as_secs()),
+                    settle_after_expiry: false,
+                },
+            );
+            unlocked_state.add_journal_entry(
+                JournalEventKind::InvoiceCreated,
+                format!(
+                    "payment hash {payment_hash}, amt_msat {}, lightning address {name}",
+                    payload.amount
+                ),
+            );
+
+            Ok(LnurlPayCallbackResponse {
+                pr: invoice.to_string(),
+                routes: vec![],
+            })
+        })
+        .await,
+    )
+}
+
+pub(crate) async fn lnurl_pay_metadata(
+    State(state): State<Arc<AppState>>,
+    WithRejection(AxumPath(name), _): WithRejection<AxumPath<String>, APIError>,
+) -> Response {
+    lnurl_response(
+        async {
+            let guard = state.check_unlocked().await?;
+            let unlocked_state = guard.as_ref().unwrap();
+
+            let lnurl_pay = unlocked_state.get_lnurl_pay();
+            let (Some(domain), Some(address)) = (&lnurl_pay.domain, lnurl_pay.address(&name))
+            else {
+                return Err(APIError::UnknownLightningAddress);
+            };
+            // LUD-17: onion services are served over plain HTTP
+            let scheme = if domain.ends_with(".onion") {
+                "http"
+            } else {
+                "https"
+            };
+
+            Ok(LnurlPayMetadataResponse {
+                callback: format!(
+                    "{scheme}://{domain}{}/lnurlp/{name}/callback",
+                    state.static_state.url_prefix.as_deref().unwrap_or("")
+                ),
+                max_sendable: address.max_sendable_msat,
+                min_sendable: address.min_sendable_msat,
+                metadata: lnurl_pay.metadata(address),
+                tag: s!("payRequest"),
+                asset_id: address.asset_id.clone(),
+            })
+        }
+        .await,
+    )
+}
+
+/// Reply to an LNURL request, reporting errors in the LUD-06 format wallets expect
+fn lnurl_response<T: Serialize>(result: Result<T, APIError>) -> Response {
+    match result {
+        Ok(res) => Json(res).into_response(),
+        Err(e) => {
+            let reason = e.to_string();
+            let status = e.into_response().status();
+            (
+                status,
+                Json(LnurlErrorResponse {
+                    status: s!("ERROR"),
+                    reason,
+                }),
+            )
+                .into_response()
+        }
+    }
+}
+
+pub(crate) async fn lsp_clients(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<LspClientsResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let mut clients: Vec<LspClient> = unlocked_state
+        .lsps_handler
+        .lsp_clients()
+        .into_iter()
+        .map(|(scid, client)| LspClient {
+            jit_channel_scid: scid_to_string(scid),
+            client_pubkey: client.client_pubkey.to_string(),
+            payment_size_msat: client.payment_size_msat,
+            opening_fee_msat: client.opening_fee_msat,
+            status: client.status,
+            channel_id: client.channel_id.map(|id| id.0.as_hex().to_string()),
+            created_at: client.created_at,
+            updated_at: client.updated_at,
+        })
+        .collect();
+    clients.sort_by_key(|c| c.created_at);
+
+    Ok(Json(LspClientsResponse { clients }))
+}
+
+pub(crate) async fn lsp_config(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<LspConfigResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    Ok(Json(unlocked_state.lsps_handler.lsp_config().into()))
+}
+
+pub(crate) async fn lsp_fee_menu(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<LspFeeMenuRequest>, APIError>,
+) -> Result<Json<LspFeeMenuResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let lsp_pubkey =
+            connect_lsp(&state, unlocked_state, payload.peer_pubkey_and_opt_addr).await?;
+        let fee_menu = unlocked_state
+            .lsps_handler
+            .lsps2_get_info(&unlocked_state.peer_manager, lsp_pubkey, payload.token)
+            .await?
+            .into_iter()
+            .map(|p| LspFeeParams {
+                min_fee_msat: p.min_fee_msat,
+                proportional: p.proportional,
+                valid_until: p.valid_until,
+                min_lifetime: p.min_lifetime,
+                max_client_to_self_delay: p.max_client_to_self_delay,
+                min_payment_size_msat: p.min_payment_size_msat,
+                max_payment_size_msat: p.max_payment_size_msat,
+            })
+            .collect();
+
+        Ok(Json(LspFeeMenuResponse { fee_menu }))
+    })
+    .await
+}
+
+pub(crate) async fn lock(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    tracing::info!("Lock started");
+    no_cancel(async move {
+        match state.check_unlocked().await {
+            Ok(unlocked_state) => {
+                state.update_changing_state(true);
+                drop(unlocked_state);
+            }
+            Err(e) => {
+                state.update_changing_state(false);
+                return Err(e);
+            }
+        }
+
+        // requests hold the unlocked state until they complete, so the exclusive access waits for
+        // the ones in progress, while new ones are refused as the state is changing
+        drop(state.unlocked_app_state.write().await);
+
+        tracing::debug!("Stopping LDK...");
+        stop_ldk(state.clone()).await;
+        tracing::debug!("LDK stopped");
+
+        state.update_unlocked_app_state(None).await;
+
+        state.update_ldk_background_services(None);
+
+        state.update_sync_stage(SyncStage::Locked);
+
+        state.update_changing_state(false);
+
+        tracing::info!("Lock completed");
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn lock_utxo(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<LockUtxoRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let label = payload.label.map(check_label).transpose()?;
+        // both colored and vanilla UTXOs can be locked
+        let is_wallet_utxo = unlocked_state
+            .rgb_list_unspents(true)?
+            .iter()
+            .any(|u| u.utxo.outpoint.to_string() == payload.outpoint)
+            || unlocked_state
+                .rgb_list_unspents_vanilla(true)?
+                .iter()
+                .any(|u| u.outpoint.to_string() == payload.outpoint);
+        if !is_wallet_utxo {
+            return Err(APIError::UnknownUtxo);
+        }
+
+        unlocked_state.lock_utxo(
+            payload.outpoint,
+            LockedUtxoInfo {
+                label,
+                locked_at: get_current_timestamp(),
+            },
+        );
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn log_level(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<LogLevelResponse>, APIError> {
+    Ok(Json(LogLevelResponse {
+        log_level: state.get_runtime_config().log_level.clone(),
+    }))
+}
+
+pub(crate) async fn maker_execute(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<MakerExecuteRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        state.check_draining()?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let swapstring = SwapString::from_str(&payload.swapstring)
+            .map_err(|e| APIError::InvalidSwapString(payload.swapstring.clone(), e.to_string()))?;
+        let payment_secret = hex_str_to_vec(&payload.payment_secret)
+            .and_then(|data| data.try_into().ok())
+            .map(PaymentSecret)
+            .ok_or(APIError::InvalidPaymentSecret)?;
+        let taker_pk =
+            PublicKey::from_str(&payload.taker_pubkey).map_err(|_| APIError::InvalidPubkey)?;
+        unlocked_state.check_totp(payload.totp_code.as_deref(), TotpOperation::Spend)?;
+
+        if get_current_timestamp() > swapstring.swap_info.expiry {
+            unlocked_state.update_maker_swap_status(&swapstring.payment_hash, SwapStatus::Expired);
+            return Err(APIError::ExpiredSwapOffer);
+        }
+
+        let payment_preimage = unlocked_state
+            .channel_manager
+            .get_payment_preimage(swapstring.payment_hash, payment_secret)
+            .map_err(|_| APIError::MissingSwapPaymentPreimage)?;
+
+        let swap_info = swapstring.swap_info;
+
+        let receive_hints = unlocked_state
+            .channel_manager
+            .list_usable_channels()
+            .iter()
+            .filter(|details| {
+                match get_rgb_channel_info_optional(
+                    &details.channel_id,
+                    &state.static_state.ldk_data_dir,
+                    false,
+                ) {
+                    _ if swap_info.is_from_btc() => true,
+                    Some((rgb_info, _)) if Some(rgb_info.contract_id) == swap_info.from_asset => {
+                        true
+                    }
+                    _ => false,
+                }
+            })
+            .map(|details| {
+                let config = details.counterparty.forwarding_info.as_ref().unwrap();
+                RouteHint(vec![RouteHintHop {
+                    src_node_id: details.counterparty.node_id,
+                    short_channel_id: details.short_channel_id.unwrap(),
+                    cltv_expiry_delta: config.cltv_expiry_delta,
+                    htlc_maximum_msat: None,
+                    htlc_minimum_msat: None,
+                    fees: RoutingFees {
+                        base_msat: config.fee_base_msat,
+                        proportional_millionths: config.fee_proportional_millionths,
+                    },
+                    htlc_maximum_rgb: None,
+                }])
+            })
+            .collect();
+
+        let rgb_payment = swap_info
+            .to_asset
+            .map(|to_asset| (to_asset, swap_info.qty_to));
+        let first_leg = get_route(
+            &unlocked_state.channel_manager,
+            &unlocked_state.router,
+            unlocked_state.channel_manager.get_our_node_id(),
+            taker_pk,
+            if swap_info.is_to_btc() {
+                Some(swap_info.qty_to + HTLC_MIN_MSAT)
+            } else {
+                Some(HTLC_MIN_MSAT)
+            },
+            rgb_payment,
+            vec![],
+            state.static_state.max_total_cltv_expiry_delta,
+        );
+
+        let rgb_payment = swap_info
+            .from_asset
+            .map(|from_asset| (from_asset, swap_info.qty_from));
+        let second_leg = get_route(
+            &unlocked_state.channel_manager,
+            &unlocked_state.router,
+            taker_pk,
+            unlocked_state.channel_manager.get_our_node_id(),
+            if swap_info.is_to_btc() || swap_info.is_asset_asset() {
+                Some(HTLC_MIN_MSAT)
+            } else {
+                Some(swap_info.qty_from + HTLC_MIN_MSAT)
+            },
+            rgb_payment,
+            receive_hints,
+            state.static_state.max_total_cltv_expiry_delta,
+        );
+
+        let (mut first_leg, mut second_leg) = match (first_leg, second_leg) {
+            (Some(f), Some(s)) => (f, s),
+            _ => {
+                return Err(APIError::NoRoute);
+            }
+        };
+
+        // Set swap flag
+        second_leg.paths[0].hops[0].short_channel_id |= IS_SWAP_SCID;
+
+        // Generally in the last hop the fee_amount is set to the payment amount, so we need to
+        // override it with fee = 0
+        first_leg.paths[0]
+            .hops
+            .last_mut()
+            .expect("Path not to be empty")
+            .fee_msat = 0;
+
+        let fullpaths = first_leg.paths[0]
+            .hops
+            .clone()
+            .into_iter()
+            .map(|mut hop| {
+                if swap_info.is_to_asset() {
+                    hop.rgb_payment = Some((swap_info.to_asset.unwrap(), swap_info.qty_to));
+                }
+                hop
+            })
+            .chain(second_leg.paths[0].hops.clone().into_iter().map(|mut hop| {
+                if swap_info.is_from_asset() {
+                    hop.rgb_payment = Some((swap_info.from_asset.unwrap(), swap_info.qty_from));
+                }
+                hop
+            }))
+            .collect::<Vec<_>>();
+
+        // Skip last fee because it's equal to the payment amount
+        let total_fee = fullpaths
+            .iter()
+            .rev()
+            .skip(1)
+            .map(|hop| hop.fee_msat)
+            .sum::<u64>();
+
+        if total_fee >= MAX_SWAP_FEE_MSAT {
+            return Err(APIError::FailedPayment(format!(
+                "Fee too high: {total_fee}"
+            )));
+        }
+
+        let route = Route {
+            paths: vec![LnPath {
+                hops: fullpaths,
+                blinded_tail: None,
+            }],
+            route_params: Some(RouteParameters {
+                payment_params: PaymentParameters::for_keysend(
+                    unlocked_state.channel_manager.get_our_node_id(),
+                    DEFAULT_FINAL_CLTV_EXPIRY_DELTA,
+                    false,
+                ),
+                // This value is not used anywhere, it's set by the router
+                // when creating a route, but here we are creating it manually
+                // by composing a pre-existing list of hops
+                final_value_msat: 0,
+                max_total_routing_fee_msat: None,
+                // This value is not used anywhere, same as final_value_msat
+                rgb_payment: None,
+            }),
+        };
+
+        if swap_info.is_to_asset() {
+            write_rgb_payment_info_file(
+                &state.static_state.ldk_data_dir,
+                &swapstring.payment_hash,
+                swap_info.to_asset.unwrap(),
+                swap_info.qty_to,
+                true,
+                false,
+            );
+        }
+
+        unlocked_state.update_maker_swap_status(&swapstring.payment_hash, SwapStatus::Pending);
+
+        let payment_hash: PaymentHash = payment_preimage.into();
+        let (_status, err) = match unlocked_state
+            .channel_manager
+            .send_spontaneous_payment_with_route(
+                route,
+                payment_hash,
+                payment_preimage,
+                RecipientOnionFields::spontaneous_empty(),
+                PaymentId(swapstring.payment_hash.0),
+            ) {
+            Ok(()) => {
+                tracing::debug!("EVENT: initiated swap");
+                (HTLCStatus::Pending, None)
+            }
+            Err(e) => {
+                tracing::warn!("ERROR: failed to send payment: {:?}", e);
+                (HTLCStatus::Failed, Some(e))
+            }
+        };
+
+        match err {
+            None => Ok(Json(EmptyResponse {})),
+            Some(e) => {
+                unlocked_state
+                    .update_maker_swap_status(&swapstring.payment_hash, SwapStatus::Failed);
+                Err(APIError::FailedPayment(format!("{e:?}")))
+            }
+        }
+    })
+    .await
+}
+
+pub(crate) async fn maker_init(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<MakerInitRequest>, APIError>,
+) -> Result<Json<MakerInitResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let from_asset = match &payload.from_asset {
+            None => None,
+            Some(asset) => Some(
+                ContractId::from_str(asset).map_err(|_| APIError::InvalidAssetID(asset.clone()))?,
+            ),
+        };
+
+        let to_asset = match &payload.to_asset {
+            None => None,
+            Some(asset) => Some(
+                ContractId::from_str(asset).map_err(|_| APIError::InvalidAssetID(asset.clone()))?,
+            ),
+        };
+
+        // prevent BTC-to-BTC swaps
+        if from_asset.is_none() && to_asset.is_none() {
+            return Err(APIError::InvalidSwap(s!("cannot swap BTC for BTC")));
+        }
+
+        // prevent swaps of same assets
+        if from_asset == to_asset {
+            return Err(APIError::InvalidSwap(s!("cannot swap the same asset")));
+        }
+
+        let qty_from = payload.qty_from;
+        let qty_to = payload.qty_to;
+
+        let expiry = get_current_timestamp() + payload.timeout_sec as u64;
+        let swap_info = SwapInfo {
+            from_asset,
+            to_asset,
+            qty_from,
+            qty_to,
+            expiry,
+        };
+        let swap_data = SwapData::create_from_swap_info(&swap_info);
+
+        // Check that we have enough assets to send
+        if let Some(to_asset) = to_asset {
+            let max_balance = get_max_local_rgb_amount(
+                to_asset,
+                &state.static_state.ldk_data_dir,
+                unlocked_state.channel_manager.list_channels().iter(),
+            );
+            if swap_info.qty_to > max_balance {
+                return Err(APIError::InsufficientAssets);
+            }
+        }
+
+        let (payment_hash, payment_secret) = unlocked_state
+            .channel_manager
+            .create_inbound_payment(Some(DUST_LIMIT_MSAT), payload.timeout_sec, None)
+            .unwrap();
+        unlocked_state.add_maker_swap(payment_hash, swap_data);
+
+        let swapstring = SwapString::from_swap_info(&swap_info, payment_hash).to_string();
+
+        let payment_secret = payment_secret.0.as_hex().to_string();
+        let payment_hash = payment_hash.0.as_hex().to_string();
+        Ok(Json(MakerInitResponse {
+            payment_hash,
+            payment_secret,
+            swapstring,
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn network_info(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<NetworkInfoResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let best_block = unlocked_state.channel_manager.current_best_block();
+    let chain_height = unlocked_state
+        .bitcoind_client
+        .get_blockchain_info()
+        .await
+        .latest_height;
+
+    let graph = unlocked_state.network_graph.read_only();
+    let channels = graph.channels();
+    // channels are announced before their policies, so the ones still missing updates tell how
+    // far the gossip sync has got
+    let num_channels_with_updates = channels
+        .unordered_iter()
+        .filter(|(_, c)| c.one_to_two.is_some() || c.two_to_one.is_some())
+        .count();
+    let total_capacity_sat = channels
+        .unordered_iter()
+        .map(|(_, c)| c.capacity_sats.unwrap_or(0))
+        .sum();
+
+    Ok(Json(NetworkInfoResponse {
+        network: state.static_state.network.into(),
+        height: best_block.height,
+        indexer_url: unlocked_state.rgb_wallet_wrapper.active_indexer_url(),
+        synced_to_chain: best_block.height as usize >= chain_height,
+        num_nodes: graph.nodes().len(),
+        num_channels: channels.len(),
+        num_channels_with_updates,
+        total_capacity_sat,
+    }))
+}
+
+pub(crate) async fn node_info(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<NodeInfoResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let chans = unlocked_state.channel_manager.list_channels();
+
+    let balances = unlocked_state.chain_monitor.get_claimable_balances(&[]);
+    let local_balance_sat = balances
+        .iter()
+        .map(|b| b.claimable_amount_satoshis())
+        .sum::<u64>();
+
+    let close_fees_map = |b| match b {
+        &Balance::ClaimableOnChannelClose {
+            ref balance_candidates,
+            confirmed_balance_candidate_index,
+            ..
+        } => balance_candidates[confirmed_balance_candidate_index].transaction_fee_satoshis,
+        _ => 0,
+    };
+    let eventual_close_fees_sat = balances.iter().map(close_fees_map).sum::<u64>();
+
+    let pending_payments_map = |b| match b {
+        &Balance::MaybeTimeoutClaimableHTLC {
+            amount_satoshis,
+            outbound_payment,
+            ..
+        } => {
+            if outbound_payment {
+                amount_satoshis
+            } else {
+                0
+            }
+        }
+        _ => 0,
+    };
+    let pending_outbound_payments_sat = balances.iter().map(pending_payments_map).sum::<u64>();
+
+    let graph_lock = unlocked_state.network_graph.read_only();
+    let network_nodes = graph_lock.nodes().len();
+    let network_channels = graph_lock.channels().len();
+
+    let node_announcement = unlocked_state.get_node_announcement().clone();
+
+    Ok(Json(NodeInfoResponse {
+        pubkey: unlocked_state.channel_manager.get_our_node_id().to_string(),
+        network: state.static_state.network.into(),
+        num_channels: chans.len(),
+        num_usable_channels: chans.iter().filter(|c| c.is_usable).count(),
+        local_balance_sat,
+        eventual_close_fees_sat,
+        pending_outbound_payments_sat,
+        num_peers: unlocked_state.peer_manager.list_peers().len(),
+        account_xpub_vanilla: unlocked_state.rgb_get_wallet_data().account_xpub_vanilla,
+        account_xpub_colored: unlocked_state.rgb_get_wallet_data().account_xpub_colored,
+        max_media_upload_size_mb: state.static_state.max_media_upload_size_mb,
+        rgb_htlc_min_msat: HTLC_MIN_MSAT,
+        rgb_channel_capacity_min_sat: OPENRGBCHANNEL_MIN_SAT,
+        channel_capacity_min_sat: OPENCHANNEL_MIN_SAT,
+        channel_capacity_max_sat: OPENCHANNEL_MAX_SAT,
+        channel_asset_min_amount: OPENCHANNEL_MIN_RGB_AMT,
+        channel_asset_max_amount: u64::MAX,
+        network_nodes,
+        network_channels,
+        alias: node_announcement.alias,
+        color: node_announcement.color.map(|c| hex_str(&c)),
+    }))
+}
+
+pub(crate) async fn nwc_connect(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<NwcConnectRequest>, APIError>,
+) -> Result<Json<NwcConnectResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        unlocked_state.check_totp(payload.totp_code.as_deref(), TotpOperation::Spend)?;
+
+        if payload.name.trim().is_empty() {
+            return Err(APIError::InvalidNwcConnection(s!("name cannot be empty")));
+        }
+        let relay = payload.relay.trim().to_string();
+        if !(relay.starts_with("wss://") || relay.starts_with("ws://"))
+            || relay.chars().any(char::is_whitespace)
+        {
+            return Err(APIError::InvalidNwcConnection(format!(
+                "invalid relay {relay}, expected a ws:// or wss:// URL"
+            )));
+        }
+        if payload.budget_msat.is_none() && payload.budget_renewal.is_some() {
+            return Err(APIError::InvalidNwcConnection(s!(
+                "budget_renewal requires a budget_msat"
+            )));
+        }
+
+        let (service_secret, client_secret) = unlocked_state.new_nwc_keys();
+        let secp = Secp256k1::signing_only();
+        let connection_id = hex_str(&client_secret.x_only_public_key(&secp).0.serialize());
+        let now = get_current_timestamp();
+        let connection = NwcConnectionInfo {
+            name: payload.name,
+            relay: relay.clone(),
+            service_secret,
+            budget_msat: payload.budget_msat,
+            budget_renewal: payload.budget_renewal.unwrap_or_default(),
+            spent_msat: 0,
+            budget_period_start: now,
+            created_at: now,
+            last_used_at: None,
+        };
+        let wallet_pubkey = connection.service_pubkey();
+        unlocked_state.add_nwc_connection(connection_id.clone(), connection);
+
+        let query = serde_urlencoded::to_string([
+            ("relay", relay),
+            ("secret", hex_str(&client_secret.secret_bytes())),
+        ])
+        .unwrap();
+        Ok(Json(NwcConnectResponse {
+            connection_id,
+            uri: format!("nostr+walletconnect://{wallet_pubkey}?{query}"),
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn nwc_connections(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<NwcConnectionsResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let mut connections: Vec<NwcConnection> = unlocked_state
+        .get_nwc_connections()
+        .connections
+        .iter()
+        .map(|(id, c)| c.to_connection(id))
+        .collect();
+    connections.sort_by_key(|c| c.created_at);
+
+    Ok(Json(NwcConnectionsResponse { connections }))
+}
+
+pub(crate) async fn nwc_revoke(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<NwcRevokeRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        if !unlocked_state.remove_nwc_connection(&payload.connection_id) {
+            return Err(APIError::UnknownNwcConnection);
+        }
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+pub(crate) async fn offer(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<OfferRequest>, APIError>,
+) -> Result<Json<OfferResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        if let Some(asset_id) = payload.asset_id {
+            ContractId::from_str(&asset_id).map_err(|_| APIError::InvalidAssetID(asset_id))?;
+            return Err(APIError::UnsupportedRgbOffer);
+        }
+        if payload.asset_amount.is_some() {
+            return Err(APIError::IncompleteRGBInfo);
+        }
+
+        let created_at = get_current_timestamp();
+        let expires_at = payload.expiry_sec.map(|e| created_at + e as u64);
+
+        let mut builder = unlocked_state
+            .channel_manager
+            .create_offer_builder()
+            .map_err(|e| APIError::FailedOfferCreation(format!("{e:?}")))?
+            .description(payload.description.clone());
+        if let Some(amt_msat) = payload.amt_msat {
+            builder = builder.amount_msats(amt_msat);
+        }
+        if let Some(expires_at) = expires_at {
+            builder = builder.absolute_expiry(Duration::from_secs(expires_at));
+        }
+        let offer = builder
+            .build()
+            .map_err(|e| APIError::FailedOfferCreation(format!("{e:?}")))?;
+
+        unlocked_state.add_offer(
+            offer.id(),
+            OfferInfo {
+                offer: offer.to_string(),
+                description: payload.description,
+                amt_msat: payload.amt_msat,
+                created_at,
+                expires_at,
+            },
+        );
+
+        Ok(Json(OfferResponse {
+            offer: offer.to_string(),
+            offer_id: hex_str(&offer.id().0),
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn open_channel(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<OpenChannelRequest>, APIError>,
+) -> Result<Json<OpenChannelResponse>, APIError> {
+    // the funding is spent on-chain and push_msat is given away, whatever the amounts
+    if !payload.dry_run {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+        unlocked_state.check_totp(payload.totp_code.as_deref(), TotpOperation::Spend)?;
+    }
+    open_channel_without_totp(state, payload).await
+}
+
+/// Open a channel without requiring the TOTP code, for the opens started by the node itself
+pub(crate) async fn open_channel_without_totp(
+    state: Arc<AppState>,
+    payload: OpenChannelRequest,
+) -> Result<Json<OpenChannelResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        state.check_draining()?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        // held until the RGB send lock gets set, so that no other open or RGB spend can start
+        // in between
+        let _rgb_spend_guard = unlocked_state.rgb_spend_lock.lock().await;
+        if *unlocked_state.rgb_send_lock.lock().unwrap() {
+            return Err(APIError::OpenChannelInProgress);
+        }
+
+        let temporary_channel_id = if let Some(tmp_chan_id_str) = payload.temporary_channel_id {
+            let tmp_chan_id = check_channel_id(&tmp_chan_id_str)?;
+            if unlocked_state.channel_ids().contains_key(&tmp_chan_id) {
+                return Err(APIError::TemporaryChannelIdAlreadyUsed);
+            }
+            Some(tmp_chan_id)
+        } else {
+            None
+        };
+
+        let colored_info = match (payload.asset_id, payload.asset_amount) {
+            (Some(_), Some(amt)) if amt < OPENCHANNEL_MIN_RGB_AMT => {
+                return Err(APIError::InvalidAmount(format!(
+                    "Channel RGB amount must be equal to or higher than {OPENCHANNEL_MIN_RGB_AMT}"
+                )));
+            }
+            (Some(asset), Some(amt)) => {
+                let asset =
+                    ContractId::from_str(&asset).map_err(|_| APIError::InvalidAssetID(asset))?;
+                Some((asset, amt))
+            }
+            (None, None) => None,
+            _ => {
+                return Err(APIError::IncompleteRGBInfo);
+            }
+        };
+
+        if colored_info.is_some() && payload.capacity_sat < OPENRGBCHANNEL_MIN_SAT {
+            return Err(APIError::InvalidAmount(format!(
+                "RGB channel amount must be equal to or higher than {OPENRGBCHANNEL_MIN_SAT} sats"
+            )));
+        } else if payload.capacity_sat < OPENCHANNEL_MIN_SAT {
+            return Err(APIError::InvalidAmount(format!(
+                "Channel amount must be equal to or higher than {OPENCHANNEL_MIN_SAT} sats"
+            )));
+        }
+        if payload.capacity_sat > OPENCHANNEL_MAX_SAT {
+            return Err(APIError::InvalidAmount(format!(
+                "Channel amount must be equal to or less than {OPENCHANNEL_MAX_SAT} sats"
+            )));
+        }
+
+        if payload.push_msat > payload.capacity_sat * 1000 {
+            return Err(APIError::InvalidAmount(s!(
+                "Channel push amount cannot be higher than the capacity"
+            )));
+        }
+
+        if colored_info.is_some() && !payload.with_anchors {
+            return Err(APIError::AnchorsRequired);
+        }
+
+        let scid_alias = payload.scid_alias.unwrap_or(false);
+        if scid_alias && payload.public {
+            return Err(APIError::InvalidChannelType(s!(
+                "scid_alias is only supported by private channels"
+            )));
+        }
+
+        // the routing policy of the channel asset fills in the fields left unset
+        let policy_key = colored_info.map_or(s!(BTC_POLICY_KEY), |(c, _)| c.to_string());
+        let asset_policy = unlocked_state
+            .asset_policy(&policy_key)
+            .unwrap_or_default();
+        // RGB channels refuse the HTLCs below the asset HTLC minimum, forwarded ones included
+        let min_htlc_minimum_msat = if colored_info.is_some() {
+            state.static_state.asset_htlc_min_msat
+        } else {
+            HTLC_MIN_MSAT
+        };
+        let htlc_minimum_msat = payload
+            .htlc_minimum_msat
+            .or(asset_policy.htlc_minimum_msat)
+            .unwrap_or(min_htlc_minimum_msat);
+        let max_htlc_value_in_flight_percent = payload
+            .max_htlc_value_in_flight_percent
+            .or(asset_policy.max_htlc_value_in_flight_percent);
+        if colored_info.is_some() && htlc_minimum_msat < min_htlc_minimum_msat {
+            return Err(APIError::InvalidChannelLimits(format!(
+                "htlc_minimum_msat of RGB channels cannot be less than {min_htlc_minimum_msat}"
+            )));
+        }
+        if htlc_minimum_msat == 0 || htlc_minimum_msat >= payload.capacity_sat * 1000 {
+            return Err(APIError::InvalidChannelLimits(s!(
+                "htlc_minimum_msat must be positive and lower than the capacity"
+            )));
+        }
+        if let Some(percent) = max_htlc_value_in_flight_percent {
+            if percent == 0 || percent > 100 {
+                return Err(APIError::InvalidChannelLimits(s!(
+                    "max_htlc_value_in_flight_percent must be between 1 and 100"
+                )));
+            }
+        }
+        if let Some(max_accepted_htlcs) = payload.max_accepted_htlcs {
+            if max_accepted_htlcs == 0 || max_accepted_htlcs > MAX_ACCEPTED_HTLCS {
+                return Err(APIError::InvalidChannelLimits(format!(
+                    "max_accepted_htlcs must be between 1 and {MAX_ACCEPTED_HTLCS}"
+                )));
+            }
+        }
+        if let Some(reserve) = payload.channel_reserve_proportional_millionths {
+            if reserve > MAX_CHANNEL_RESERVE_PROPORTIONAL_MILLIONTHS {
+                return Err(APIError::InvalidChannelLimits(format!(
+                    "channel_reserve_proportional_millionths cannot be more than {MAX_CHANNEL_RESERVE_PROPORTIONAL_MILLIONTHS}"
+                )));
+            }
+        }
+
+        let (peer_pubkey, mut peer_addr) =
+            parse_peer_info(payload.peer_pubkey_and_opt_addr.to_string())?;
+
+        let multisig_funding = if let Some(multisig_funding) = payload.multisig_funding {
+            if colored_info.is_some() {
+                return Err(APIError::InvalidMultisigFunding(s!(
+                    "RGB channels cannot be funded by a multisig treasury"
+                )));
+            }
+            Some(check_multisig_funding(
+                multisig_funding,
+                peer_pubkey,
+                payload.capacity_sat,
+                state.static_state.network,
+            )?)
+        } else {
+            None
+        };
+
+        let external_funding = if let Some(external_funding) = payload.external_funding {
+            if colored_info.is_some() {
+                return Err(APIError::InvalidExternalFunding(s!(
+                    "RGB channels cannot be funded by an external PSBT"
+                )));
+            }
+            if multisig_funding.is_some() {
+                return Err(APIError::InvalidExternalFunding(s!(
+                    "cannot be used along with multisig_funding"
+                )));
+            }
+            Some(check_external_funding(
+                external_funding,
+                peer_pubkey,
+                payload.capacity_sat,
+            )?)
+        } else {
+            None
+        };
+
+        let mut funding_psbt = None;
+        if let Some(coin_selection) = &payload.coin_selection {
+            if colored_info.is_some() || multisig_funding.is_some() || external_funding.is_some() {
+                return Err(APIError::InvalidCoinSelection(s!(
+                    "only supported for vanilla channels funded by the node wallet"
+                )));
+            }
+            // build the funding TX to a placeholder script to make sure the selection works
+            let mut fake_p2wsh: [u8; 34] = [0; 34];
+            fake_p2wsh[1] = 32;
+            let psbt = unlocked_state
+                .send_btc_begin_with_coin_selection(
+                    vec![TxOut {
+                        value: Amount::from_sat(payload.capacity_sat),
+                        script_pubkey: ScriptBuf::from_bytes(fake_p2wsh.to_vec()),
+                    }],
+                    FEE_RATE,
+                    coin_selection,
+                    false,
+                )
+                .await?;
+            funding_psbt = Some(psbt);
+        }
+
+        let mut channel_config = ChannelConfig {
+            cltv_expiry_delta: asset_policy
+                .cltv_expiry_delta
+                .unwrap_or(state.static_state.cltv_expiry_delta),
+            ..Default::default()
+        };
+        if let Some(fee_base_msat) = payload.fee_base_msat.or(asset_policy.fee_base_msat) {
+            channel_config.forwarding_fee_base_msat = fee_base_msat;
+        }
+        if let Some(fee_proportional_millionths) = payload
+            .fee_proportional_millionths
+            .or(asset_policy.fee_proportional_millionths)
+        {
+            channel_config.forwarding_fee_proportional_millionths = fee_proportional_millionths;
+        }
+        let mut handshake_config = ChannelHandshakeConfig {
+            announce_for_forwarding: payload.public,
+            our_htlc_minimum_msat: htlc_minimum_msat,
+            minimum_depth: unlocked_state
+                .get_channel_depths()
+                .min_depth(&peer_pubkey, state.static_state.min_channel_depth),
+            negotiate_anchors_zero_fee_htlc_tx: payload.with_anchors,
+            negotiate_scid_privacy: scid_alias,
+            ..Default::default()
+        };
+        if let Some(percent) = max_htlc_value_in_flight_percent {
+            handshake_config.max_inbound_htlc_value_in_flight_percent_of_channel = percent;
+        }
+        if let Some(max_accepted_htlcs) = payload.max_accepted_htlcs {
+            handshake_config.our_max_accepted_htlcs = max_accepted_htlcs;
+        }
+        if let Some(reserve) = payload.channel_reserve_proportional_millionths {
+            handshake_config.their_channel_reserve_proportional_millionths = reserve;
+        }
+        let config = UserConfig {
+            channel_handshake_limits: ChannelHandshakeLimits {
+                // lnd's max to_self_delay is 2016, so we want to be compatible.
+                their_to_self_delay: 2016,
+                ..Default::default()
+            },
+            channel_handshake_config: handshake_config,
+            channel_config,
+            // the caller holds the signed funding TX as well, so it's only broadcast once the
+            // peer has signed the commitment TX, making it safe
+            manually_broadcast_outbound_channels: external_funding.is_some(),
+            ..Default::default()
+        };
+
+        // fail before reserving the funding UTXOs when the connected peer can't open RGB channels
+        if colored_info.is_some() {
+            if let Some(peer) = unlocked_state.peer_manager.peer_by_node_id(&peer_pubkey) {
+                if !supports_rgb(&peer.init_features) {
+                    return Err(APIError::IncompatiblePeer(s!(
+                        "peer does not support RGB channels"
+                    )));
+                }
+            }
+        }
+
+        let consignment_endpoint = if let Some((contract_id, asset_amount)) = &colored_info {
+            let balance = unlocked_state.rgb_get_asset_balance(*contract_id)?;
+            let spendable_rgb_amount = balance.spendable;
+
+            if *asset_amount > spendable_rgb_amount {
+                return Err(APIError::InsufficientAssets);
+            }
+
+            Some(RgbTransport::from_str(&unlocked_state.proxy_endpoint).unwrap())
+        } else {
+            None
+        };
+
+        let schema = if let Some((contract_id, asset_amount)) = &colored_info {
+            let mut fake_p2wsh: [u8; 34] = [0; 34];
+            fake_p2wsh[1] = 32;
+            let script_buf = ScriptBuf::from_bytes(fake_p2wsh.to_vec());
+            let recipient_id = recipient_id_from_script_buf(script_buf, state.static_state.network);
+            let asset_id = contract_id.to_string();
+            let schema = unlocked_state
+                .rgb_get_asset_metadata(*contract_id)?
+                .asset_schema;
+            let assignment = match schema {
+                RgbLibAssetSchema::Nia | RgbLibAssetSchema::Cfa | RgbLibAssetSchema::Ifa => {
+                    Assignment::Fungible(*asset_amount)
+                }
+                RgbLibAssetSchema::Uda => Assignment::NonFungible,
+            };
+
+            let recipient_map = map! {
+                asset_id => vec![Recipient {
+                    recipient_id,
+                    witness_data: Some(RgbLibWitnessData {
+                        amount_sat: payload.capacity_sat,
+                        blinding: Some(STATIC_BLINDING + 1),
+                    }),
+                    assignment: assignment.into(),
+                    transport_endpoints: vec![unlocked_state.proxy_endpoint.clone()]
+            }]};
+
+            let unlocked_state_copy = unlocked_state.clone();
+            let psbt = tokio::task::spawn_blocking(move || {
+                unlocked_state_copy.rgb_send_begin(
+                    recipient_map,
+                    true,
+                    FEE_RATE,
+                    MIN_CHANNEL_CONFIRMATIONS,
+                )
+            })
+            .await
+            .unwrap()?;
+            funding_psbt = Some(psbt);
+            Some(schema)
+        } else {
+            None
+        };
+
+        let peer_data_path = state.static_state.ldk_data_dir.join(CHANNEL_PEER_DATA);
+        if peer_addr.is_none() {
+            // connections through the SOCKS proxy report the address of the proxy
+            if state.static_state.tor_proxy.is_none() {
+                if let Some(peer) = unlocked_state.peer_manager.peer_by_node_id(&peer_pubkey) {
+                    peer_addr = peer.socket_address;
+                }
+            }
+        }
+        if peer_addr.is_none() {
+            let peer_info = disk::read_channel_peer_data(&peer_data_path)?;
+            for (pubkey, addr) in peer_info.into_iter() {
+                if pubkey == peer_pubkey {
+                    peer_addr = Some(addr);
+                    break;
+                }
+            }
+        }
+        let Some(peer_addr) = peer_addr else {
+            return Err(APIError::InvalidPeerInfo(s!(
+                "cannot find the address for the provided pubkey"
+            )));
+        };
+
+        if payload.dry_run {
+            let dry_run = match funding_psbt {
+                Some(psbt) => unlocked_state.dry_run_tx(&psbt)?.1,
+                // a multisig treasury or an external PSBT funds the channel, the wallet has no part
+                None if multisig_funding.is_some() || external_funding.is_some() => DryRunResult {
+                    fee_sat: None,
+                    inputs: vec![],
+                    routes: vec![],
+                },
+                None => {
+                    let mut fake_p2wsh: [u8; 34] = [0; 34];
+                    fake_p2wsh[1] = 32;
+                    let address = Address::from_script(
+                        &ScriptBuf::from_bytes(fake_p2wsh.to_vec()),
+                        Network::from(state.static_state.network),
+                    )
+                    .expect("funding script is a SegWit output");
+                    let psbt = unlocked_state.rgb_send_btc_begin(
+                        address.to_string(),
+                        payload.capacity_sat,
+                        FEE_RATE,
+                    )?;
+                    unlocked_state.dry_run_tx(&psbt)?.1
+                }
+            };
+            let temporary_channel_id = temporary_channel_id.unwrap_or_else(|| {
+                ChannelId::temporary_from_entropy_source(&unlocked_state.keys_manager)
+            });
+            return Ok(Json(OpenChannelResponse {
+                temporary_channel_id: temporary_channel_id.0.as_hex().to_string(),
+                dry_run: Some(dry_run),
+            }));
+        }
+
+        connect_peer_if_necessary(
+            peer_pubkey,
+            &peer_addr,
+            unlocked_state.peer_manager.clone(),
+            state.static_state.tor_proxy.as_ref(),
+            CircuitGroup::Payments,
+        )
+        .await?;
+        disk::persist_channel_peer(&peer_data_path, &peer_pubkey, &peer_addr)?;
+
+        // channels funded by a multisig treasury or an external PSBT don't use the RGB wallet, so
+        // they're registered before creating the channel, to be recognized when the funding is
+        // requested
+        let uses_rgb_wallet = multisig_funding.is_none() && external_funding.is_none();
+        let temporary_channel_id = if let Some(multisig_funding) = multisig_funding {
+            let temporary_channel_id = temporary_channel_id.unwrap_or_else(|| {
+                ChannelId::temporary_from_entropy_source(&unlocked_state.keys_manager)
+            });
+            unlocked_state
+                .get_multisig_fundings()
+                .insert(temporary_channel_id, multisig_funding);
+            Some(temporary_channel_id)
+        } else if let Some(external_funding) = external_funding {
+            let temporary_channel_id = temporary_channel_id.unwrap_or_else(|| {
+                ChannelId::temporary_from_entropy_source(&unlocked_state.keys_manager)
+            });
+            unlocked_state
+                .get_external_fundings()
+                .insert(temporary_channel_id, external_funding);
+            Some(temporary_channel_id)
+        } else if let Some(coin_selection) = payload.coin_selection {
+            // the funding TX spends the selected coins, so the selection is kept until the
+            // funding is requested
+            let temporary_channel_id = temporary_channel_id.unwrap_or_else(|| {
+                ChannelId::temporary_from_entropy_source(&unlocked_state.keys_manager)
+            });
+            unlocked_state
+                .get_funding_coin_selections()
+                .insert(temporary_channel_id, coin_selection);
+            *unlocked_state.rgb_send_lock.lock().unwrap() = true;
+            tracing::debug!("RGB send lock set to true");
+            Some(temporary_channel_id)
+        } else {
+            *unlocked_state.rgb_send_lock.lock().unwrap() = true;
+            tracing::debug!("RGB send lock set to true");
+            temporary_channel_id
+        };
+
+        let temporary_channel_id = unlocked_state
+            .channel_manager
+            .create_channel(
+                peer_pubkey,
+                payload.capacity_sat,
+                payload.push_msat,
+                0,
+                temporary_channel_id,
+                Some(config),
+                consignment_endpoint,
+            )
+            .map_err(|e| {
+                if uses_rgb_wallet {
+                    *unlocked_state.rgb_send_lock.lock().unwrap() = false;
+                    tracing::debug!("RGB send lock set to false (open channel failure: {e:?})");
+                    if let Some(temporary_channel_id) = temporary_channel_id {
+                        unlocked_state
+                            .get_funding_coin_selections()
+                            .remove(&temporary_channel_id);
+                    }
+                } else if let Some(temporary_channel_id) = temporary_channel_id {
+                    unlocked_state
+                        .get_multisig_fundings()
+                        .remove(&temporary_channel_id);
+                    unlocked_state
+                        .get_external_fundings()
+                        .remove(&temporary_channel_id);
+                }
+                match e {
+                    LDKAPIError::APIMisuseError { err }
+                        if err.contains("fee for initial commitment transaction") =>
+                    {
+                        let mut commitment_tx_fee = 0;
+                        let re =
+                            Regex::new(r"fee for initial commitment transaction fee of (\d+).")
+                                .unwrap();
+                        if let Some(captures) = re.captures(&err) {
+                            if let Some(fee_str) = captures.get(1) {
+                                commitment_tx_fee = fee_str.as_str().parse().unwrap();
+                            }
+                        }
+                        APIError::InsufficientCapacity(commitment_tx_fee)
+                    }
+                    _ => APIError::FailedOpenChannel(format!("{e:?}")),
+                }
+            })?;
+        unlocked_state.add_channel_event(
+            temporary_channel_id,
+            ChannelEventKind::OpenRequested,
+            format!(
+                "requested {} sat channel to {peer_pubkey}",
+                payload.capacity_sat
+            ),
+        );
+        let temporary_channel_id = temporary_channel_id.0.as_hex().to_string();
+        tracing::info!("EVENT: initiated channel with peer {}", peer_pubkey);
+
+        if let Some((contract_id, asset_amount)) = &colored_info {
+            let rgb_info = RgbInfo {
+                contract_id: *contract_id,
+                schema: schema.unwrap(),
+                local_rgb_amount: *asset_amount,
+                remote_rgb_amount: 0,
+            };
+            write_rgb_channel_info(
+                &get_rgb_channel_info_path(
+                    &temporary_channel_id,
+                    &state.static_state.ldk_data_dir,
+                    true,
+                ),
+                &rgb_info,
+            );
+            write_rgb_channel_info(
+                &get_rgb_channel_info_path(
+                    &temporary_channel_id,
+                    &state.static_state.ldk_data_dir,
+                    false,
+                ),
+                &rgb_info,
+            );
+        }
+
+        Ok(Json(OpenChannelResponse {
+            temporary_channel_id,
+            dry_run: None,
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn ownership_proof(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Query(payload), _): WithRejection<Query<OwnershipProofRequest>, APIError>,
+) -> Result<Json<OwnershipProofResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let nonce = check_nonce(&payload.nonce)?;
+
+    let pubkey = unlocked_state.channel_manager.get_our_node_id().to_string();
+    let addresses: Vec<String> = unlocked_state
+        .announced_addresses
+        .iter()
+        .map(|a| a.to_string())
+        .collect();
+    let timestamp = get_current_timestamp();
+
+    // the statement is signed with the node key, so it can be checked with any Lightning
+    // implementation supporting message verification (e.g. `lncli verifymessage`)
+    let message = format!(
+        "rgb-lightning-node ownership proof\npubkey: {pubkey}\naddresses: {}\nnonce: {nonce}\ntimestamp: {timestamp}",
+        addresses.join(",")
+    );
+    let signature = lightning::util::message_signing::sign(
+        message.as_bytes(),
+        &unlocked_state.keys_manager.get_node_secret_key(),
+    );
+
+    Ok(Json(OwnershipProofResponse {
+        pubkey,
+        addresses,
+        nonce,
+        timestamp,
+        message,
+        signature,
+    }))
+}
+
+pub(crate) async fn pay_offer(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<PayOfferRequest>, APIError>,
+) -> Result<Json<PayOfferResponse>, APIError> {
     no_cancel(async move {
         let guard = state.check_unlocked().await?;
+        state.check_draining()?;
         let unlocked_state = guard.as_ref().unwrap();
 
-        let dest_pubkey = match hex_str_to_compressed_pubkey(&payload.dest_pubkey) {
-            Some(pk) => pk,
-            None => return Err(APIError::InvalidPubkey),
+        let offer = Offer::from_str(&payload.offer)
+            .map_err(|e| APIError::InvalidInvoice(format!("{e:?}")))?;
+        check_offer_network(&offer, state.static_state.network)?;
+        let totp_operation = match (offer.amount(), payload.amt_msat) {
+            (Some(offer::Amount::Bitcoin { amount_msats }), _) => {
+                TotpOperation::Payment(amount_msats)
+            }
+            (_, Some(amt_msat)) => TotpOperation::Payment(amt_msat),
+            _ => TotpOperation::Spend,
         };
+        unlocked_state.check_totp(payload.totp_code.as_deref(), totp_operation)?;
 
-        let amt_msat = payload.amt_msat;
-        if amt_msat < HTLC_MIN_MSAT {
-            return Err(APIError::InvalidAmount(format!(
-                "amt_msat cannot be less than {HTLC_MIN_MSAT}"
+        let (payment_id, status) = unlocked_state.pay_offer(
+            &offer,
+            payload.amt_msat,
+            payload.payer_note,
+            &PaymentLimits::new(state.static_state.max_total_cltv_expiry_delta),
+        )?;
+
+        Ok(Json(PayOfferResponse {
+            payment_id: hex_str(&payment_id.0),
+            status,
+        }))
+    })
+    .await
+}
+
+pub(crate) async fn peer_compat(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Query(payload), _): WithRejection<Query<PeerCompatRequest>, APIError>,
+) -> Result<Json<PeerCompatResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let (peer_pubkey, peer_addr) = parse_peer_info(payload.pubkey)?;
+    let asset_id = payload
+        .asset_id
+        .map(|a| ContractId::from_str(&a).map_err(|_| APIError::InvalidAssetID(a)))
+        .transpose()?;
+    let with_anchors = payload.with_anchors.unwrap_or(true);
+    let scid_alias = payload.scid_alias.unwrap_or(false);
+    let public = payload.public.unwrap_or(false);
+
+    if unlocked_state
+        .peer_manager
+        .peer_by_node_id(&peer_pubkey)
+        .is_none()
+    {
+        let peer_addr = match peer_addr {
+            Some(addr) => Some(addr),
+            None => {
+                let peer_data_path = state.static_state.ldk_data_dir.join(CHANNEL_PEER_DATA);
+                disk::read_channel_peer_data(&peer_data_path)?
+                    .into_iter()
+                    .find(|(pubkey, _)| *pubkey == peer_pubkey)
+                    .map(|(_, addr)| addr)
+            }
+        };
+        let Some(peer_addr) = peer_addr else {
+            return Err(APIError::InvalidPeerInfo(s!(
+                "cannot find the address for the provided pubkey"
             )));
+        };
+        connect_peer_if_necessary(
+            peer_pubkey,
+            &peer_addr,
+            unlocked_state.peer_manager.clone(),
+            state.static_state.tor_proxy.as_ref(),
+            CircuitGroup::Payments,
+        )
+        .await?;
+    }
+    let peer_details = unlocked_state
+        .peer_manager
+        .peer_by_node_id(&peer_pubkey)
+        .ok_or_else(|| APIError::PeerUnreachable(s!("peer disconnected")))?;
+    let init_features = peer_details.init_features;
+
+    let features = PeerFeatures {
+        anchors: init_features.supports_anchors_zero_fee_htlc_tx(),
+        rgb: supports_rgb(&init_features),
+        scid_alias: init_features.supports_scid_privacy(),
+        dual_funding: init_features.supports_dual_fund(),
+    };
+
+    // same checks done by /openchannel, plus the ones the peer would fail during the negotiation
+    let mut issues = vec![];
+    if asset_id.is_some() && !with_anchors {
+        issues.push(s!("RGB channels require anchors"));
+    }
+    if asset_id.is_some() && !features.rgb {
+        issues.push(s!("peer does not support RGB channels"));
+    }
+    if let Some(contract_id) = asset_id {
+        if unlocked_state.rgb_get_asset_metadata(contract_id).is_err() {
+            issues.push(format!("asset {contract_id} is unknown"));
         }
+    }
+    if with_anchors && !features.anchors {
+        issues.push(s!("peer does not support anchor outputs"));
+    }
+    if scid_alias && public {
+        issues.push(s!("scid_alias is only supported by private channels"));
+    }
+    if scid_alias && !features.scid_alias {
+        issues.push(s!("peer does not support option_scid_alias"));
+    }
 
-        let payment_preimage =
-            PaymentPreimage(unlocked_state.keys_manager.get_secure_random_bytes());
-        let payment_hash_inner = Sha256::hash(&payment_preimage.0[..]).to_byte_array();
-        let payment_id = PaymentId(payment_hash_inner);
-        let payment_hash = PaymentHash(payment_hash_inner);
+    let mut flags = init_features.le_flags().to_vec();
+    flags.reverse();
+
+    Ok(Json(PeerCompatResponse {
+        pubkey: peer_pubkey.to_string(),
+        features,
+        init_features: hex_str(&flags),
+        open_compatible: issues.is_empty(),
+        issues,
+    }))
+}
+
+pub(crate) async fn peer_filter(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<PeerFilterResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    Ok(Json(unlocked_state.get_peer_filter().clone().into()))
+}
+
+pub(crate) async fn peer_suggestions(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Query(payload), _): WithRejection<Query<PeerSuggestionsRequest>, APIError>,
+) -> Result<Json<PeerSuggestionsResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let goal = payload.goal.unwrap_or_default();
+    let limit = payload.limit.unwrap_or(DEFAULT_PEER_SUGGESTIONS);
+    if limit == 0 || limit > MAX_PEER_SUGGESTIONS {
+        return Err(APIError::InvalidPagination(format!(
+            "limit must be between 1 and {MAX_PEER_SUGGESTIONS}"
+        )));
+    }
+
+    let our_node_id = NodeId::from_pubkey(&unlocked_state.channel_manager.get_our_node_id());
+    let mut channel_peers = HashSet::new();
+    let mut asset_peers = HashSet::new();
+    for chan_info in unlocked_state.channel_manager.list_channels() {
+        let node_id = NodeId::from_pubkey(&chan_info.counterparty.node_id);
+        channel_peers.insert(node_id);
+        if is_channel_rgb(
+            &chan_info.channel_id,
+            &PathBuf::from(&state.static_state.ldk_data_dir),
+        ) {
+            asset_peers.insert(node_id);
+        }
+    }
+    let probe_stats = unlocked_state.get_probe_stats().clone();
+
+    let graph = unlocked_state.network_graph.read_only();
+    let mut suggestions = vec![];
+    for (node_id, node_info) in graph.nodes().unordered_iter() {
+        if *node_id == our_node_id || node_info.channels.is_empty() {
+            continue;
+        }
+        let Ok(pubkey) = node_id.as_pubkey() else {
+            continue;
+        };
+        let mut total_capacity_sat = 0;
+        let mut fees = vec![];
+        for scid in &node_info.channels {
+            let Some(channel) = graph.channel(*scid) else {
+                continue;
+            };
+            total_capacity_sat += channel.capacity_sats.unwrap_or(0);
+            // the fees charged by the node are the ones of the direction it forwards to
+            let update = if channel.node_one == *node_id {
+                &channel.one_to_two
+            } else {
+                &channel.two_to_one
+            };
+            if let Some(update) = update.as_ref().filter(|u| u.enabled) {
+                fees.push(update.fees);
+            }
+        }
+        let num_fees = fees.len().max(1) as u64;
+        let probe_success_rate = probe_stats
+            .get(&pubkey)
+            .filter(|s| s.attempts > 0)
+            .map(|s| s.successes as f64 / s.attempts as f64);
+        suggestions.push(PeerSuggestion {
+            pubkey: pubkey.to_string(),
+            alias: node_info
+                .announcement_info
+                .as_ref()
+                .map(|a| a.alias().to_string()),
+            score: 0.0,
+            num_channels: node_info.channels.len(),
+            total_capacity_sat,
+            avg_fee_base_msat: fees.iter().map(|f| f.base_msat as u64).sum::<u64>() / num_fees,
+            avg_fee_proportional_millionths: fees
+                .iter()
+                .map(|f| f.proportional_millionths as u64)
+                .sum::<u64>()
+                / num_fees,
+            probe_success_rate,
+            supports_assets: asset_peers.contains(node_id),
+            has_channel: channel_peers.contains(node_id),
+        });
+    }
+    drop(graph);
+
+    score_peer_suggestions(&mut suggestions, goal);
+    suggestions.sort_by(|a, b| b.score.total_cmp(&a.score));
+    suggestions.truncate(limit);
+
+    Ok(Json(PeerSuggestionsResponse { suggestions }))
+}
+
+pub(crate) async fn pending_broadcasts(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<PendingBroadcastsResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let mut broadcasts: Vec<PendingBroadcast> = unlocked_state
+        .pending_broadcasts()
+        .into_iter()
+        .map(|(txid, info)| PendingBroadcast {
+            txid,
+            kind: info.kind,
+            created_at: info.created_at,
+            broadcast_at: info.broadcast_at,
+            hold_for_approval: info.hold_for_approval,
+            approved_at: info.approved_at,
+            error: info.error,
+        })
+        .collect();
+    broadcasts.sort_by_key(|b| b.created_at);
+
+    Ok(Json(PendingBroadcastsResponse { broadcasts }))
+}
+
+pub(crate) async fn pending_channels(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<PendingChannelsResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let mut channels: Vec<PendingChannel> = unlocked_state
+        .pending_channels()
+        .into_iter()
+        .map(|(temporary_channel_id, info)| PendingChannel {
+            temporary_channel_id: temporary_channel_id.0.as_hex().to_string(),
+            peer_pubkey: info.counterparty_node_id.to_string(),
+            capacity_sat: info.capacity_sat,
+            push_msat: info.push_msat,
+            public: info.public,
+            with_anchors: info.with_anchors,
+            received_at: info.received_at,
+        })
+        .collect();
+    channels.sort_by_key(|c| c.received_at);
+
+    Ok(Json(PendingChannelsResponse { channels }))
+}
+
+pub(crate) async fn pending_fundings(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<PendingFundingsResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let mut fundings: Vec<PendingFunding> = unlocked_state
+        .get_multisig_fundings()
+        .iter()
+        .map(|(temporary_channel_id, info)| PendingFunding {
+            temporary_channel_id: temporary_channel_id.0.as_hex().to_string(),
+            peer_pubkey: info.counterparty_node_id.to_string(),
+            capacity_sat: info.capacity_sat,
+            fee_sat: info.fee_sat(),
+            unsigned_psbt: info.unsigned_psbt.as_ref().map(|p| p.to_string()),
+            created_at: info.created_at,
+            expires_at: info.expires_at,
+        })
+        .collect();
+    fundings.sort_by_key(|f| f.created_at);
+
+    let network: Network = state.static_state.network.into();
+    let mut external_fundings: Vec<PendingExternalFunding> = unlocked_state
+        .get_external_fundings()
+        .iter()
+        .map(|(temporary_channel_id, info)| PendingExternalFunding {
+            temporary_channel_id: temporary_channel_id.0.as_hex().to_string(),
+            peer_pubkey: info.counterparty_node_id.to_string(),
+            capacity_sat: info.capacity_sat,
+            funding_address: info.funding_script.as_ref().map(|script| {
+                Address::from_script(script, network)
+                    .expect("funding script is a SegWit output")
+                    .to_string()
+            }),
+            created_at: info.created_at,
+            expires_at: info.expires_at,
+        })
+        .collect();
+    external_fundings.sort_by_key(|f| f.created_at);
+
+    Ok(Json(PendingFundingsResponse {
+        fundings,
+        external_fundings,
+    }))
+}
+
+pub(crate) async fn phantom_route_hints(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<PhantomRouteHintsResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    if state.static_state.phantom_secret.is_none() {
+        return Err(APIError::PhantomNotConfigured);
+    }
+
+    let route_hints = unlocked_state.channel_manager.get_phantom_route_hints();
+
+    Ok(Json(PhantomRouteHintsResponse {
+        route_hints: hex_str(&route_hints.encode()),
+    }))
+}
+
+pub(crate) async fn post_asset_media(
+    State(state): State<Arc<AppState>>,
+    mut multipart: Multipart,
+) -> Result<Json<PostAssetMediaResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let digest = if let Some(field) = multipart
+            .next_field()
+            .await
+            .map_err(|_| APIError::MediaFileNotProvided)?
+        {
+            let file_bytes = field
+                .bytes()
+                .await
+                .map_err(|e| APIError::Unexpected(format!("Failed to read bytes: {e}")))?;
+            if file_bytes.is_empty() {
+                return Err(APIError::MediaFileEmpty);
+            }
+            let file_hash: sha256::Hash = Hash::hash(&file_bytes[..]);
+            let digest = file_hash.to_string();
+
+            let file_path = unlocked_state.rgb_get_media_dir().join(&digest);
+            let mut write = true;
+            if file_path.exists() {
+                let mut buf_reader = BufReader::new(File::open(&file_path).await?);
+                let mut existing_file_bytes = Vec::new();
+                buf_reader.read_to_end(&mut existing_file_bytes).await?;
+                if file_bytes != existing_file_bytes {
+                    tokio::fs::remove_file(&file_path).await?;
+                } else {
+                    write = false;
+                }
+            }
+            if write {
+                let mut file = File::create(&file_path).await?;
+                file.write_all(&file_bytes).await?;
+            }
+            digest
+        } else {
+            return Err(APIError::MediaFileNotProvided);
+        };
+
+        Ok(Json(PostAssetMediaResponse { digest }))
+    })
+    .await
+}
+
+pub(crate) async fn probe_payment(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ProbePaymentRequest>, APIError>,
+) -> Result<Json<ProbePaymentResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let dest_pubkey = match hex_str_to_compressed_pubkey(&payload.dest_pubkey) {
+            Some(pk) => pk,
+            None => return Err(APIError::InvalidPubkey),
+        };
 
         let rgb_payment = match (payload.asset_id, payload.asset_amount) {
             (Some(asset_id), Some(rgb_amount)) => {
@@ -1905,24 +12694,50 @@ pub(crate) async fn keysend(
             }
         };
 
+        let mut payment_params =
+            PaymentParameters::from_node_id(dest_pubkey, DEFAULT_FINAL_CLTV_EXPIRY_DELTA);
+        payment_params.max_total_cltv_expiry_delta = state.static_state.max_total_cltv_expiry_delta;
+        payment_params.max_path_count = 1;
         let route_params = RouteParameters::from_payment_params_and_value(
-            PaymentParameters::for_keysend(dest_pubkey, 40, false),
-            amt_msat,
+            payment_params,
+            payload.amt_msat,
             rgb_payment,
         );
-        let created_at = get_current_timestamp();
-        unlocked_state.add_outbound_payment(
-            payment_id,
-            PaymentInfo {
-                preimage: None,
-                secret: None,
-                status: HTLCStatus::Pending,
-                amt_msat: Some(amt_msat),
-                created_at,
-                updated_at: created_at,
-                payee_pubkey: dest_pubkey,
-            },
-        )?;
+        let first_hops = unlocked_state.channel_manager.list_usable_channels();
+        let first_hops = first_hops.iter().collect::<Vec<_>>();
+        let mut route = unlocked_state
+            .router
+            .find_route(
+                &unlocked_state.channel_manager.get_our_node_id(),
+                &route_params,
+                Some(&first_hops),
+                unlocked_state.channel_manager.compute_inflight_htlcs(),
+            )
+            .map_err(|_| APIError::NoRoute)?;
+        if rgb_payment.is_some() {
+            for hop in route.paths.iter_mut().flat_map(|p| p.hops.iter_mut()) {
+                hop.rgb_payment = rgb_payment;
+            }
+        }
+        let path = route.paths.first().ok_or(APIError::NoRoute)?;
+        let total_fee_msat = path.fee_msat();
+        let total_cltv_expiry_delta = path.hops.iter().map(|h| h.cltv_expiry_delta).sum();
+        let hops = path
+            .hops
+            .iter()
+            .map(|h| QueriedRouteHop {
+                pubkey: h.pubkey.to_string(),
+                short_channel_id: h.short_channel_id,
+                fee_msat: h.fee_msat,
+                cltv_expiry_delta: h.cltv_expiry_delta,
+            })
+            .collect();
+
+        // the recipient cannot know the preimage of a random hash, so the probe can never be
+        // claimed and is failed back once it reaches it
+        let payment_hash = PaymentHash(unlocked_state.keys_manager.get_secure_random_bytes());
+        let payment_id = PaymentId(payment_hash.0);
+        let payment_secret = PaymentSecret(unlocked_state.keys_manager.get_secure_random_bytes());
         if let Some((contract_id, rgb_amount)) = rgb_payment {
             write_rgb_payment_info_file(
                 &PathBuf::from(&state.static_state.ldk_data_dir),
@@ -1934,1856 +12749,2805 @@ pub(crate) async fn keysend(
             );
         }
 
-        let status = match unlocked_state.channel_manager.send_spontaneous_payment(
-            Some(payment_preimage),
-            RecipientOnionFields::spontaneous_empty(),
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        unlocked_state.add_probe(payment_id, sender);
+        let started_at = Instant::now();
+        let outcome = match unlocked_state.channel_manager.send_payment_with_route(
+            route,
+            payment_hash,
+            RecipientOnionFields::secret_only(payment_secret),
             payment_id,
-            route_params,
-            Retry::Timeout(Duration::from_secs(10)),
         ) {
-            Ok(_payment_hash) => {
+            Ok(()) => {
                 tracing::info!(
-                    "EVENT: initiated sending {} msats to {}",
-                    amt_msat,
-                    dest_pubkey
+                    "EVENT: initiated probing {} msats to {dest_pubkey}",
+                    payload.amt_msat
                 );
-                HTLCStatus::Pending
-            }
-            Err(e) => {
-                tracing::error!("ERROR: failed to send payment: {:?}", e);
-                unlocked_state.update_outbound_payment_status(payment_id, HTLCStatus::Failed);
-                HTLCStatus::Failed
+                match tokio::time::timeout(Duration::from_secs(PROBE_TIMEOUT_SEC), receiver).await {
+                    Ok(Ok(outcome)) => outcome,
+                    _ => {
+                        unlocked_state.channel_manager.abandon_payment(payment_id);
+                        ProbeOutcome {
+                            reachable: false,
+                            short_channel_id: None,
+                            failure: format!("timed out after {PROBE_TIMEOUT_SEC} seconds"),
+                        }
+                    }
+                }
             }
+            Err(e) => ProbeOutcome {
+                reachable: false,
+                short_channel_id: None,
+                failure: format!("{e:?}"),
+            },
         };
-
-        Ok(Json(KeysendResponse {
-            payment_hash: hex_str(&payment_hash.0),
-            payment_preimage: hex_str(&payment_preimage.0),
-            status,
+        let latency_ms = started_at.elapsed().as_millis() as u64;
+        unlocked_state.record_probe_result(dest_pubkey, outcome.reachable);
+
+        Ok(Json(ProbePaymentResponse {
+            reachable: outcome.reachable,
+            hops,
+            total_fee_msat,
+            total_cltv_expiry_delta,
+            latency_ms,
+            failed_short_channel_id: if outcome.reachable {
+                None
+            } else {
+                outcome.short_channel_id
+            },
+            failure: (!outcome.reachable).then_some(outcome.failure),
         }))
     })
     .await
 }
 
-pub(crate) async fn list_assets(
-    State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<ListAssetsRequest>, APIError>,
-) -> Result<Json<ListAssetsResponse>, APIError> {
-    let guard = state.check_unlocked().await?;
-    let unlocked_state = guard.as_ref().unwrap();
-
-    let rgb_assets = unlocked_state.rgb_list_assets(
-        payload
-            .filter_asset_schemas
-            .into_iter()
-            .map(|s| s.into())
-            .collect(),
-    )?;
-
-    let mut offchain_balances = HashMap::new();
-    for chan_info in unlocked_state.channel_manager.list_channels() {
-        let info_file_path = get_rgb_channel_info_path(
-            &chan_info.channel_id.0.as_hex().to_string(),
-            &state.static_state.ldk_data_dir,
-            false,
-        );
-        if !info_file_path.exists() {
-            continue;
-        }
-        let rgb_info = parse_rgb_channel_info(&info_file_path);
-        offchain_balances
-            .entry(rgb_info.contract_id.to_string())
-            .and_modify(|(offchain_outbound, offchain_inbound)| {
-                *offchain_outbound += rgb_info.local_rgb_amount;
-                *offchain_inbound += rgb_info.remote_rgb_amount;
+fn queried_route(path: &LnPath) -> QueriedRoute {
+    QueriedRoute {
+        total_amt_msat: path.final_value_msat() + path.fee_msat(),
+        total_fee_msat: path.fee_msat(),
+        total_cltv_expiry_delta: path.hops.iter().map(|h| h.cltv_expiry_delta).sum(),
+        hops: path
+            .hops
+            .iter()
+            .map(|h| QueriedRouteHop {
+                pubkey: h.pubkey.to_string(),
+                short_channel_id: h.short_channel_id,
+                fee_msat: h.fee_msat,
+                cltv_expiry_delta: h.cltv_expiry_delta,
             })
-            .or_insert((rgb_info.local_rgb_amount, rgb_info.remote_rgb_amount));
+            .collect(),
     }
-
-    let nia = rgb_assets.nia.map(|assets| {
-        assets
-            .into_iter()
-            .map(|a| {
-                let mut asset: AssetNIA = a.into();
-                (
-                    asset.balance.offchain_outbound,
-                    asset.balance.offchain_inbound,
-                ) = *offchain_balances.get(&asset.asset_id).unwrap_or(&(0, 0));
-                asset
-            })
-            .collect()
-    });
-    let uda = rgb_assets.uda.map(|assets| {
-        assets
-            .into_iter()
-            .map(|a| {
-                let mut asset: AssetUDA = a.into();
-                (
-                    asset.balance.offchain_outbound,
-                    asset.balance.offchain_inbound,
-                ) = *offchain_balances.get(&asset.asset_id).unwrap_or(&(0, 0));
-                asset
-            })
-            .collect()
-    });
-    let cfa = rgb_assets.cfa.map(|assets| {
-        assets
-            .into_iter()
-            .map(|a| {
-                let mut asset: AssetCFA = a.into();
-                (
-                    asset.balance.offchain_outbound,
-                    asset.balance.offchain_inbound,
-                ) = *offchain_balances.get(&asset.asset_id).unwrap_or(&(0, 0));
-                asset
-            })
-            .collect()
-    });
-
-    Ok(Json(ListAssetsResponse { nia, uda, cfa }))
 }
 
-pub(crate) async fn list_channels(
+pub(crate) async fn query_routes(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<ListChannelsResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<QueryRoutesRequest>, APIError>,
+) -> Result<Json<QueryRoutesResponse>, APIError> {
     let guard = state.check_unlocked().await?;
     let unlocked_state = guard.as_ref().unwrap();
 
-    let mut channels = vec![];
-    for chan_info in unlocked_state.channel_manager.list_channels() {
-        let status = match chan_info.channel_shutdown_state.unwrap() {
-            ChannelShutdownState::NotShuttingDown => {
-                if chan_info.is_channel_ready {
-                    ChannelStatus::Opened
-                } else {
-                    ChannelStatus::Opening
-                }
-            }
-            _ => ChannelStatus::Closing,
-        };
-        let mut channel = Channel {
-            channel_id: chan_info.channel_id.0.as_hex().to_string(),
-            peer_pubkey: hex_str(&chan_info.counterparty.node_id.serialize()),
-            status,
-            ready: chan_info.is_channel_ready,
-            capacity_sat: chan_info.channel_value_satoshis,
-            outbound_balance_msat: chan_info.outbound_capacity_msat,
-            inbound_balance_msat: chan_info.inbound_capacity_msat,
-            next_outbound_htlc_limit_msat: chan_info.next_outbound_htlc_limit_msat,
-            next_outbound_htlc_minimum_msat: chan_info.next_outbound_htlc_minimum_msat,
-            is_usable: chan_info.is_usable,
-            public: chan_info.is_announced,
-            ..Default::default()
-        };
+    let dest_pubkey = match hex_str_to_compressed_pubkey(&payload.dest_pubkey) {
+        Some(pk) => pk,
+        None => return Err(APIError::InvalidPubkey),
+    };
 
-        if let Some(funding_txo) = chan_info.funding_txo {
-            channel.funding_txid = Some(funding_txo.txid.to_string());
-            if let Ok(chan_monitor) = unlocked_state
-                .chain_monitor
-                .get_monitor(chan_info.channel_id)
-            {
-                channel.local_balance_sat = chan_monitor
-                    .get_claimable_balances()
-                    .iter()
-                    .map(|b| b.claimable_amount_satoshis())
-                    .sum::<u64>();
-            }
+    let rgb_payment = match (payload.asset_id, payload.asset_amount) {
+        (Some(asset_id), Some(rgb_amount)) => {
+            let contract_id =
+                ContractId::from_str(&asset_id).map_err(|_| APIError::InvalidAssetID(asset_id))?;
+            Some((contract_id, rgb_amount))
         }
-
-        if let Some(node_info) = unlocked_state
-            .network_graph
-            .read_only()
-            .nodes()
-            .get(&NodeId::from_pubkey(&chan_info.counterparty.node_id))
-        {
-            if let Some(announcement) = &node_info.announcement_info {
-                channel.peer_alias = Some(announcement.alias().to_string());
-            }
+        (None, None) => None,
+        _ => {
+            return Err(APIError::IncompleteRGBInfo);
         }
+    };
 
-        if let Some(id) = chan_info.short_channel_id {
-            channel.short_channel_id = Some(id);
+    let max_routes = payload.max_routes.unwrap_or(DEFAULT_QUERY_ROUTES);
+    if max_routes == 0 || max_routes > MAX_QUERY_ROUTES {
+        return Err(APIError::InvalidPaymentLimits(format!(
+            "max_routes must be between 1 and {MAX_QUERY_ROUTES}"
+        )));
+    }
+    let node_max_total_cltv_expiry_delta = state.static_state.max_total_cltv_expiry_delta;
+    let max_total_cltv_expiry_delta = match payload.max_cltv_expiry_delta {
+        Some(max_cltv) if max_cltv > node_max_total_cltv_expiry_delta => {
+            return Err(APIError::InvalidPaymentLimits(format!(
+                "max_cltv_expiry_delta cannot be more than {node_max_total_cltv_expiry_delta}"
+            )));
         }
+        Some(max_cltv) => max_cltv,
+        None => node_max_total_cltv_expiry_delta,
+    };
 
-        let info_file_path = get_rgb_channel_info_path(
-            &chan_info.channel_id.0.as_hex().to_string(),
-            &state.static_state.ldk_data_dir,
-            false,
+    let our_node_id = unlocked_state.channel_manager.get_our_node_id();
+    let first_hops = unlocked_state.channel_manager.list_usable_channels();
+    let first_hops = first_hops.iter().collect::<Vec<_>>();
+
+    // each new candidate avoids the channels used by the previous ones
+    let mut routes = vec![];
+    let mut used_channels = vec![];
+    for _ in 0..max_routes {
+        let mut payment_params =
+            PaymentParameters::from_node_id(dest_pubkey, DEFAULT_FINAL_CLTV_EXPIRY_DELTA);
+        payment_params.max_total_cltv_expiry_delta = max_total_cltv_expiry_delta;
+        payment_params.max_path_count = 1;
+        payment_params.previously_failed_channels = used_channels.clone();
+        let mut route_params = RouteParameters::from_payment_params_and_value(
+            payment_params,
+            payload.amt_msat,
+            rgb_payment,
         );
-        if info_file_path.exists() {
-            let rgb_info = parse_rgb_channel_info(&info_file_path);
-            channel.asset_id = Some(rgb_info.contract_id.to_string());
-            channel.asset_local_amount = Some(rgb_info.local_rgb_amount);
-            channel.asset_remote_amount = Some(rgb_info.remote_rgb_amount);
+        if payload.max_fee_msat.is_some() {
+            route_params.max_total_routing_fee_msat = payload.max_fee_msat;
+        }
+        let Ok(route) = unlocked_state.router.find_route(
+            &our_node_id,
+            &route_params,
+            Some(&first_hops),
+            unlocked_state.channel_manager.compute_inflight_htlcs(),
+        ) else {
+            break;
+        };
+        let Some(path) = route.paths.into_iter().next() else {
+            break;
         };
+        used_channels.extend(path.hops.iter().map(|h| h.short_channel_id));
+        routes.push(queried_route(&path));
+    }
 
-        channels.push(channel);
+    if routes.is_empty() {
+        return Err(APIError::NoRoute);
     }
 
-    Ok(Json(ListChannelsResponse { channels }))
+    Ok(Json(QueryRoutesResponse { routes }))
 }
 
-pub(crate) async fn list_payments(
+pub(crate) async fn rebalance(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<ListPaymentsResponse>, APIError> {
-    let guard = state.check_unlocked().await?;
-    let unlocked_state = guard.as_ref().unwrap();
-
-    let inbound_payments = unlocked_state.inbound_payments();
-    let outbound_payments = unlocked_state.outbound_payments();
-    let mut payments = vec![];
+    WithRejection(Json(payload), _): WithRejection<Json<RebalanceRequest>, APIError>,
+) -> Result<Json<RebalanceResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        state.check_draining()?;
+        let unlocked_state = guard.as_ref().unwrap();
 
-    for (payment_hash, payment_info) in &inbound_payments {
-        let rgb_payment_info_path_inbound =
-            get_rgb_payment_info_path(payment_hash, &state.static_state.ldk_data_dir, true);
+        unlocked_state.check_totp(payload.totp_code.as_deref(), TotpOperation::Spend)?;
 
-        let (asset_amount, asset_id) = if rgb_payment_info_path_inbound.exists() {
-            let info = parse_rgb_payment_info(&rgb_payment_info_path_inbound);
-            (Some(info.amount), Some(info.contract_id.to_string()))
-        } else {
-            (None, None)
+        let outgoing_cid = check_channel_id(&payload.outgoing_channel_id)?;
+        let incoming_cid = check_channel_id(&payload.incoming_channel_id)?;
+        if outgoing_cid == incoming_cid {
+            return Err(APIError::InvalidRebalance(s!(
+                "the outgoing and incoming channels need to be different"
+            )));
+        }
+        let usable_channels = unlocked_state.channel_manager.list_usable_channels();
+        let find_channel = |channel_id| {
+            usable_channels
+                .iter()
+                .find(|c| c.channel_id == channel_id)
+                .ok_or(APIError::UnknownChannelId)
         };
+        let outgoing = find_channel(outgoing_cid)?;
+        let incoming = find_channel(incoming_cid)?;
+        let ldk_data_dir = &state.static_state.ldk_data_dir;
+        if [outgoing, incoming]
+            .iter()
+            .any(|c| get_rgb_channel_info_optional(&c.channel_id, ldk_data_dir, false).is_some())
+        {
+            return Err(APIError::InvalidRebalance(s!(
+                "RGB channels cannot be rebalanced"
+            )));
+        }
+        if outgoing.next_outbound_htlc_limit_msat < payload.amt_msat {
+            return Err(APIError::InvalidRebalance(s!(
+                "not enough outbound liquidity on the outgoing channel"
+            )));
+        }
+        if incoming.inbound_capacity_msat < payload.amt_msat {
+            return Err(APIError::InvalidRebalance(s!(
+                "not enough inbound liquidity on the incoming channel"
+            )));
+        }
+        let (Some(forwarding_info), Some(incoming_scid)) = (
+            incoming.counterparty.forwarding_info.as_ref(),
+            incoming.get_inbound_payment_scid(),
+        ) else {
+            return Err(APIError::InvalidRebalance(s!(
+                "the incoming channel cannot be used to receive yet"
+            )));
+        };
+        let incoming_fee_msat = forwarding_info.fee_base_msat as u64
+            + payload.amt_msat * forwarding_info.fee_proportional_millionths as u64 / 1_000_000;
+        if incoming_fee_msat > payload.max_fee_msat {
+            return Err(APIError::InvalidRebalance(format!(
+                "the incoming channel fee of {incoming_fee_msat} msat exceeds max_fee_msat"
+            )));
+        }
 
-        payments.push(Payment {
-            amt_msat: payment_info.amt_msat,
-            asset_amount,
-            asset_id,
-            payment_hash: hex_str(&payment_hash.0),
-            inbound: true,
-            status: payment_info.status,
-            created_at: payment_info.created_at,
-            updated_at: payment_info.updated_at,
-            payee_pubkey: payment_info.payee_pubkey.to_string(),
-        });
-    }
-
-    for (payment_id, payment_info) in &outbound_payments {
-        let payment_hash = &PaymentHash(payment_id.0);
+        // the payment goes out through the outgoing channel and comes back from the incoming
+        // channel peer, paying an invoice of our own
+        let invoice_params = Bolt11InvoiceParameters {
+            amount_msats: Some(payload.amt_msat),
+            invoice_expiry_delta_secs: Some(REBALANCE_INVOICE_EXPIRY_SEC),
+            min_final_cltv_expiry_delta: Some(state.static_state.min_final_cltv_expiry_delta),
+            ..Default::default()
+        };
+        let invoice = unlocked_state
+            .channel_manager
+            .create_bolt11_invoice(invoice_params)
+            .map_err(|e| APIError::FailedInvoiceCreation(e.to_string()))?;
+        let final_cltv_expiry_delta = invoice.min_final_cltv_expiry_delta() as u32;
+
+        let mut payment_params =
+            PaymentParameters::from_node_id(incoming.counterparty.node_id, final_cltv_expiry_delta);
+        payment_params.max_total_cltv_expiry_delta = state.static_state.max_total_cltv_expiry_delta;
+        payment_params.max_path_count = 1;
+        let mut route_params = RouteParameters::from_payment_params_and_value(
+            payment_params,
+            payload.amt_msat + incoming_fee_msat,
+            None,
+        );
+        route_params.max_total_routing_fee_msat = Some(payload.max_fee_msat - incoming_fee_msat);
+        let mut route = unlocked_state
+            .router
+            .find_route(
+                &unlocked_state.channel_manager.get_our_node_id(),
+                &route_params,
+                Some(&[outgoing]),
+                unlocked_state.channel_manager.compute_inflight_htlcs(),
+            )
+            .map_err(|_| APIError::NoRoute)?;
+        let path = route.paths.first_mut().ok_or(APIError::NoRoute)?;
+        let mut last_hop = path.hops.last().unwrap().clone();
+        let peer_hop = path.hops.last_mut().unwrap();
+        peer_hop.fee_msat = incoming_fee_msat;
+        peer_hop.cltv_expiry_delta = forwarding_info.cltv_expiry_delta as u32;
+        last_hop.pubkey = unlocked_state.channel_manager.get_our_node_id();
+        last_hop.node_features = unlocked_state.channel_manager.node_features();
+        last_hop.short_channel_id = incoming_scid;
+        last_hop.channel_features = unlocked_state.channel_manager.channel_features();
+        last_hop.fee_msat = payload.amt_msat;
+        last_hop.cltv_expiry_delta = final_cltv_expiry_delta;
+        last_hop.maybe_announced_channel = incoming.is_announced;
+        path.hops.push(last_hop);
+        let total_fee_msat = path.fee_msat();
+        let hops = path
+            .hops
+            .iter()
+            .map(|h| QueriedRouteHop {
+                pubkey: h.pubkey.to_string(),
+                short_channel_id: h.short_channel_id,
+                fee_msat: h.fee_msat,
+                cltv_expiry_delta: h.cltv_expiry_delta,
+            })
+            .collect();
+        // let the route parameters be derived from the circular route
+        route.route_params = None;
 
-        let rgb_payment_info_path_outbound =
-            get_rgb_payment_info_path(payment_hash, &state.static_state.ldk_data_dir, false);
+        let payment_hash = PaymentHash(invoice.payment_hash().to_byte_array());
+        let payment_id = PaymentId(payment_hash.0);
+        let payment_secret = *invoice.payment_secret();
+        let our_node_id = unlocked_state.channel_manager.get_our_node_id();
+        let created_at = get_current_timestamp();
+        let payment_info = PaymentInfo {
+            preimage: None,
+            secret: Some(payment_secret),
+            status: HTLCStatus::Pending,
+            amt_msat: Some(payload.amt_msat),
+            created_at,
+            updated_at: created_at,
+            payee_pubkey: our_node_id,
+            offer_id: None,
+            parts: vec![],
+            expires_at: invoice.expires_at().map(|e| e.as_secs()),
+            settle_after_expiry: false,
+        };
+        unlocked_state.add_inbound_payment(payment_hash, payment_info.clone());
+        unlocked_state.add_outbound_payment(payment_id, payment_info)?;
 
-        let (asset_amount, asset_id) = if rgb_payment_info_path_outbound.exists() {
-            let info = parse_rgb_payment_info(&rgb_payment_info_path_outbound);
-            (Some(info.amount), Some(info.contract_id.to_string()))
-        } else {
-            (None, None)
+        let status = match unlocked_state.channel_manager.send_payment_with_route(
+            route,
+            payment_hash,
+            RecipientOnionFields::secret_only(payment_secret),
+            payment_id,
+        ) {
+            Ok(()) => {
+                tracing::info!(
+                    "EVENT: initiated rebalancing {} msats from channel {outgoing_cid} to \
+                     channel {incoming_cid}",
+                    payload.amt_msat
+                );
+                HTLCStatus::Pending
+            }
+            Err(e) => {
+                tracing::error!("ERROR: failed to send rebalance payment: {:?}", e);
+                unlocked_state.update_outbound_payment_status(payment_id, HTLCStatus::Failed);
+                HTLCStatus::Failed
+            }
         };
 
-        payments.push(Payment {
-            amt_msat: payment_info.amt_msat,
-            asset_amount,
-            asset_id,
+        Ok(Json(RebalanceResponse {
+            payment_id: hex_str(&payment_id.0),
             payment_hash: hex_str(&payment_hash.0),
-            inbound: false,
-            status: payment_info.status,
-            created_at: payment_info.created_at,
-            updated_at: payment_info.updated_at,
-            payee_pubkey: payment_info.payee_pubkey.to_string(),
-        });
-    }
-
-    Ok(Json(ListPaymentsResponse { payments }))
+            status,
+            total_fee_msat,
+            hops,
+        }))
+    })
+    .await
 }
 
-pub(crate) async fn get_payment(
+pub(crate) async fn refresh_transfers(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<GetPaymentRequest>, APIError>,
-) -> Result<Json<GetPaymentResponse>, APIError> {
-    let guard = state.check_unlocked().await?;
-    let unlocked_state = guard.as_ref().unwrap();
+    WithRejection(Json(payload), _): WithRejection<Json<RefreshRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+        let unlocked_state_copy = unlocked_state.clone();
 
-    let payment_hash_vec = hex_str_to_vec(&payload.payment_hash);
-    if payment_hash_vec.is_none() || payment_hash_vec.as_ref().unwrap().len() != 32 {
-        return Err(APIError::InvalidPaymentHash(payload.payment_hash));
-    }
-    let requested_ph = PaymentHash(payment_hash_vec.unwrap().try_into().unwrap());
+        tokio::task::spawn_blocking(move || unlocked_state_copy.rgb_refresh(payload.skip_sync))
+            .await
+            .unwrap()?;
 
-    let inbound_payments = unlocked_state.inbound_payments();
-    let outbound_payments = unlocked_state.outbound_payments();
+        tracing::info!("Refresh complete");
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
 
-    for (payment_hash, payment_info) in &inbound_payments {
-        if payment_hash == &requested_ph {
-            let rgb_payment_info_path_inbound =
-                get_rgb_payment_info_path(payment_hash, &state.static_state.ldk_data_dir, true);
+pub(crate) async fn reissue_asset(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ReissueAssetRequest>, APIError>,
+) -> Result<Json<ReissueAssetResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        state.check_draining()?;
+        let unlocked_state = guard.as_ref().unwrap();
 
-            let (asset_amount, asset_id) = if rgb_payment_info_path_inbound.exists() {
-                let info = parse_rgb_payment_info(&rgb_payment_info_path_inbound);
-                (Some(info.amount), Some(info.contract_id.to_string()))
-            } else {
-                (None, None)
-            };
+        let _rgb_spend_guard = unlocked_state.rgb_spend_lock.lock().await;
+        if *unlocked_state.rgb_send_lock.lock().unwrap() {
+            return Err(APIError::OpenChannelInProgress);
+        }
+        unlocked_state.check_totp(payload.totp_code.as_deref(), TotpOperation::Spend)?;
 
-            return Ok(Json(GetPaymentResponse {
-                payment: Payment {
-                    amt_msat: payment_info.amt_msat,
-                    asset_amount,
-                    asset_id,
-                    payment_hash: hex_str(&payment_hash.0),
-                    inbound: true,
-                    status: payment_info.status,
-                    created_at: payment_info.created_at,
-                    updated_at: payment_info.updated_at,
-                    payee_pubkey: payment_info.payee_pubkey.to_string(),
-                },
-            }));
+        let contract_id = ContractId::from_str(&payload.asset_id)
+            .map_err(|_| APIError::InvalidAssetID(payload.asset_id.clone()))?;
+        let metadata = unlocked_state.rgb_get_asset_metadata(contract_id)?;
+        if !matches!(metadata.asset_schema, RgbLibAssetSchema::Ifa) {
+            return Err(APIError::AssetNotInflatable(payload.asset_id));
+        }
+        if payload.amounts.is_empty() || payload.amounts.contains(&0) {
+            return Err(APIError::InvalidAmount(s!(
+                "at least one amount is needed and amounts must be greater than 0"
+            )));
+        }
+        let amount = payload
+            .amounts
+            .iter()
+            .try_fold(metadata.known_circulating_supply, |acc, a| {
+                acc.checked_add(*a)
+            });
+        if amount.is_none_or(|a| a > metadata.max_supply) {
+            return Err(APIError::InvalidAmount(format!(
+                "the reissued amount would exceed the max supply of {}",
+                metadata.max_supply
+            )));
         }
-    }
 
-    for (payment_id, payment_info) in &outbound_payments {
-        let payment_hash = &PaymentHash(payment_id.0);
-        if payment_hash == &requested_ph {
-            let rgb_payment_info_path_outbound =
-                get_rgb_payment_info_path(payment_hash, &state.static_state.ldk_data_dir, false);
+        let unlocked_state_copy = unlocked_state.clone();
+        let txid = tokio::task::spawn_blocking(move || {
+            unlocked_state_copy.rgb_inflate(
+                contract_id.to_string(),
+                payload.amounts,
+                payload.fee_rate,
+                payload.min_confirmations,
+            )
+        })
+        .await
+        .unwrap()?
+        .txid;
+        tracing::info!("Reissued asset {contract_id} in TX {txid}");
 
-            let (asset_amount, asset_id) = if rgb_payment_info_path_outbound.exists() {
-                let info = parse_rgb_payment_info(&rgb_payment_info_path_outbound);
-                (Some(info.amount), Some(info.contract_id.to_string()))
-            } else {
-                (None, None)
-            };
+        Ok(Json(ReissueAssetResponse { txid }))
+    })
+    .await
+}
 
-            return Ok(Json(GetPaymentResponse {
-                payment: Payment {
-                    amt_msat: payment_info.amt_msat,
-                    asset_amount,
-                    asset_id,
-                    payment_hash: hex_str(&payment_hash.0),
-                    inbound: false,
-                    status: payment_info.status,
-                    created_at: payment_info.created_at,
-                    updated_at: payment_info.updated_at,
-                    payee_pubkey: payment_info.payee_pubkey.to_string(),
-                },
-            }));
-        }
-    }
+pub(crate) async fn reject_channel(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<RejectChannelRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
 
-    Err(APIError::PaymentNotFound(payload.payment_hash))
+        let temporary_channel_id = check_channel_id(&payload.temporary_channel_id)?;
+        let reason = payload
+            .reason
+            .unwrap_or_else(|| s!("channel rejected by the node operator"));
+        unlocked_state.reject_pending_channel(temporary_channel_id, reason)?;
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
 }
 
-pub(crate) async fn list_peers(
+pub(crate) async fn reload_config(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<ListPeersResponse>, APIError> {
-    let guard = state.check_unlocked().await?;
-    let unlocked_state = guard.as_ref().unwrap();
+) -> Result<Json<ReloadConfigResponse>, APIError> {
+    no_cancel(async move {
+        state.check_changing_state()?;
 
-    let mut peers = vec![];
-    for peer_details in unlocked_state.peer_manager.list_peers() {
-        peers.push(Peer {
-            pubkey: peer_details.counterparty_node_id.to_string(),
-        })
-    }
+        let reload = state.reload_config().await?;
+        tracing::info!(
+            "Config reloaded, applied {:?}, skipped {:?}",
+            reload.applied,
+            reload.skipped
+        );
 
-    Ok(Json(ListPeersResponse { peers }))
+        Ok(Json(ReloadConfigResponse {
+            applied: reload.applied,
+            skipped: reload.skipped,
+        }))
+    })
+    .await
 }
 
-pub(crate) async fn list_swaps(
+pub(crate) async fn remove_hedging_policy(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<ListSwapsResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<RemoveHedgingPolicyRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
     let guard = state.check_unlocked().await?;
     let unlocked_state = guard.as_ref().unwrap();
 
-    let map_swap = |payment_hash: &PaymentHash, swap_data: &SwapData, taker: bool| {
-        let mut status = swap_data.status.clone();
-        if status == SwapStatus::Waiting && get_current_timestamp() > swap_data.swap_info.expiry {
-            status = SwapStatus::Expired;
-        } else if status == SwapStatus::Pending
-            && get_current_timestamp() > swap_data.initiated_at.unwrap() + 86400
-        {
-            status = SwapStatus::Failed;
-        }
-        if status != swap_data.status {
-            if taker {
-                unlocked_state.update_taker_swap_status(payment_hash, status.clone());
-            } else {
-                unlocked_state.update_maker_swap_status(payment_hash, status.clone());
-            }
-        }
-        Swap {
-            payment_hash: payment_hash.to_string(),
-            qty_from: swap_data.swap_info.qty_from,
-            qty_to: swap_data.swap_info.qty_to,
-            from_asset: swap_data.swap_info.from_asset.map(|c| c.to_string()),
-            to_asset: swap_data.swap_info.to_asset.map(|c| c.to_string()),
-            status,
-            requested_at: swap_data.requested_at,
-            initiated_at: swap_data.initiated_at,
-            expires_at: swap_data.swap_info.expiry,
-            completed_at: swap_data.completed_at,
-        }
-    };
+    let key = parse_hedging_asset(&payload.asset_id)?.unwrap_or(s!(BTC_POLICY_KEY));
+    if !unlocked_state.set_hedging_policy(&key, None) {
+        return Err(APIError::UnknownHedgingPolicy);
+    }
+    tracing::info!("Removed the {key} hedging policy");
 
-    let taker_swaps = unlocked_state.taker_swaps();
-    let maker_swaps = unlocked_state.maker_swaps();
+    Ok(Json(EmptyResponse {}))
+}
 
-    Ok(Json(ListSwapsResponse {
-        taker: taker_swaps
-            .iter()
-            .map(|(ph, sd)| map_swap(ph, sd, true))
-            .collect(),
-        maker: maker_swaps
-            .iter()
-            .map(|(ph, sd)| map_swap(ph, sd, false))
-            .collect(),
-    }))
+pub(crate) async fn resolve_htlc(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<ResolveHtlcRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        unlocked_state.resolve_intercepted_htlc(
+            &payload.htlc_id,
+            payload.resolution,
+            payload.payment_preimage.as_deref(),
+            &state.static_state.ldk_data_dir,
+        )?;
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
 }
 
-pub(crate) async fn get_swap(
+pub(crate) async fn restore(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<GetSwapRequest>, APIError>,
-) -> Result<Json<GetSwapResponse>, APIError> {
-    let guard = state.check_unlocked().await?;
-    let unlocked_state = guard.as_ref().unwrap();
+    WithRejection(Json(payload), _): WithRejection<Json<RestoreRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let _unlocked_state = state.check_locked().await?;
 
-    let payment_hash_vec = hex_str_to_vec(&payload.payment_hash);
-    if payment_hash_vec.is_none() || payment_hash_vec.as_ref().unwrap().len() != 32 {
-        return Err(APIError::InvalidPaymentHash(payload.payment_hash));
-    }
-    let requested_ph = PaymentHash(payment_hash_vec.unwrap().try_into().unwrap());
+        let mnemonic_path = get_mnemonic_path(&state.static_state.storage_dir_path);
+        check_already_initialized(&mnemonic_path)?;
 
-    let map_swap = |payment_hash: &PaymentHash, swap_data: &SwapData, taker: bool| {
-        let mut status = swap_data.status.clone();
-        if status == SwapStatus::Waiting && get_current_timestamp() > swap_data.swap_info.expiry {
-            status = SwapStatus::Expired;
-        } else if status == SwapStatus::Pending
-            && get_current_timestamp() > swap_data.initiated_at.unwrap() + 86400
-        {
-            status = SwapStatus::Failed;
-        }
-        if status != swap_data.status {
-            if taker {
-                unlocked_state.update_taker_swap_status(payment_hash, status.clone());
-            } else {
-                unlocked_state.update_maker_swap_status(payment_hash, status.clone());
-            }
-        }
-        Swap {
-            payment_hash: payment_hash.to_string(),
-            qty_from: swap_data.swap_info.qty_from,
-            qty_to: swap_data.swap_info.qty_to,
-            from_asset: swap_data.swap_info.from_asset.map(|c| c.to_string()),
-            to_asset: swap_data.swap_info.to_asset.map(|c| c.to_string()),
-            status,
-            requested_at: swap_data.requested_at,
-            initiated_at: swap_data.initiated_at,
-            expires_at: swap_data.swap_info.expiry,
-            completed_at: swap_data.completed_at,
-        }
-    };
+        restore_from_backup(&state, Path::new(&payload.backup_path), &payload.password)?;
+
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+/// Restore the storage directory of an uninitialized node from a backup file
+fn restore_from_backup(
+    state: &AppState,
+    backup_path: &Path,
+    password: &str,
+) -> Result<(), APIError> {
+    restore_backup(backup_path, password, &state.static_state.storage_dir_path)?;
+
+    let _mnemonic = check_password_validity(password, &state.static_state.storage_dir_path)?;
+
+    if state.generate_root_key {
+        state
+            .load_root_public_key()
+            .map_err(|e| APIError::Unexpected(format!("failed to load root key: {e}")))?;
+    }
+    Ok(())
+}
 
-    if payload.taker {
-        let taker_swaps = unlocked_state.taker_swaps();
-        if let Some(sd) = taker_swaps.get(&requested_ph) {
-            return Ok(Json(GetSwapResponse {
-                swap: map_swap(&requested_ph, sd, true),
-            }));
-        }
-    } else {
-        let maker_swaps = unlocked_state.maker_swaps();
-        if let Some(sd) = maker_swaps.get(&requested_ph) {
-            return Ok(Json(GetSwapResponse {
-                swap: map_swap(&requested_ph, sd, false),
-            }));
+pub(crate) async fn restore_channels(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<RestoreChannelsRequest>, APIError>,
+) -> Result<Json<RestoreChannelsResponse>, APIError> {
+    no_cancel(async move {
+        let _unlocked_state = state.check_locked().await?;
+
+        let mnemonic =
+            check_password_validity(&payload.password, &state.static_state.storage_dir_path)?;
+
+        let ldk_data_dir = &state.static_state.ldk_data_dir;
+        let kv_store = NodeStore::open(
+            ldk_data_dir,
+            state.static_state.storage_backend,
+            state.static_state.remote_storage_url.as_deref(),
+            state
+                .static_state
+                .encrypt_storage
+                .then(|| storage_cipher(&mnemonic)),
+        )
+        .await?;
+        if !kv_store
+            .list(
+                CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+                CHANNEL_MONITOR_PERSISTENCE_SECONDARY_NAMESPACE,
+            )?
+            .is_empty()
+        {
+            return Err(APIError::CannotRestoreChannels(s!(
+                "the node already has channels"
+            )));
         }
-    }
 
-    Err(APIError::SwapNotFound(payload.payment_hash))
+        let backup_dir = tempfile::tempdir_in(&state.static_state.storage_dir_path)?;
+        restore_backup(
+            Path::new(&payload.backup_path),
+            &payload.password,
+            backup_dir.path(),
+        )?;
+        let num_channels = disk::restore_channel_files(backup_dir.path(), ldk_data_dir, &kv_store)?;
+
+        Ok(Json(RestoreChannelsResponse { num_channels }))
+    })
+    .await
 }
 
-pub(crate) async fn list_transactions(
+pub(crate) async fn retry_sweep(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<ListTransactionsRequest>, APIError>,
-) -> Result<Json<ListTransactionsResponse>, APIError> {
-    let guard = state.check_unlocked().await?;
-    let unlocked_state = guard.as_ref().unwrap();
+    WithRejection(Json(payload), _): WithRejection<Json<RetrySweepRequest>, APIError>,
+) -> Result<Json<Sweep>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
 
-    let mut transactions = vec![];
-    for tx in unlocked_state.rgb_list_transactions(payload.skip_sync)? {
-        transactions.push(Transaction {
-            transaction_type: match tx.transaction_type {
-                rgb_lib::TransactionType::RgbSend => TransactionType::RgbSend,
-                rgb_lib::TransactionType::Drain => TransactionType::Drain,
-                rgb_lib::TransactionType::CreateUtxos => TransactionType::CreateUtxos,
-                rgb_lib::TransactionType::User => TransactionType::User,
-            },
-            txid: tx.txid,
-            received: tx.received,
-            sent: tx.sent,
-            fee: tx.fee,
-            confirmation_time: tx.confirmation_time.map(|ct| BlockTime {
-                height: ct.height,
-                timestamp: ct.timestamp,
-            }),
-        })
-    }
+        unlocked_state.check_totp(payload.totp_code.as_deref(), TotpOperation::Spend)?;
 
-    Ok(Json(ListTransactionsResponse { transactions }))
+        let output = unlocked_state
+            .output_sweeper
+            .tracked_spendable_outputs()
+            .into_iter()
+            .find(|o| spendable_output(&o.descriptor).0.to_string() == payload.outpoint)
+            .ok_or(APIError::UnknownSweep)?;
+
+        match output.status {
+            OutputSpendStatus::PendingInitialBroadcast {
+                delayed_until_height,
+            } => {
+                let current_height = unlocked_state.channel_manager.current_best_block().height;
+                if let Some(height) = delayed_until_height.filter(|h| *h > current_height) {
+                    return Err(APIError::CannotRetrySweep(format!(
+                        "output is timelocked until height {height}"
+                    )));
+                }
+                // the sweeper only tries to build the sweeps once per block
+                unlocked_state
+                    .output_sweeper
+                    .regenerate_and_broadcast_spend_if_necessary()
+                    .await
+                    .map_err(|_| APIError::CannotRetrySweep(s!("failed to build the sweep TX")))?;
+            }
+            OutputSpendStatus::PendingFirstConfirmation {
+                latest_spending_tx, ..
+            } => {
+                let txid = unlocked_state
+                    .bitcoind_client
+                    .send_raw_transaction(&latest_spending_tx)
+                    .await
+                    .map_err(|e| APIError::FailedBroadcast(e.to_string()))?;
+                tracing::info!("Rebroadcast sweep TX {txid}");
+            }
+            OutputSpendStatus::PendingThresholdConfirmations { .. } => {
+                return Err(APIError::CannotRetrySweep(s!("sweep is already confirmed")));
+            }
+        }
+
+        get_sweeps(unlocked_state, &state.static_state.ldk_data_dir)
+            .await
+            .into_iter()
+            .find(|s| s.outpoint.as_ref() == Some(&payload.outpoint))
+            .map(Json)
+            .ok_or(APIError::UnknownSweep)
+    })
+    .await
 }
 
-pub(crate) async fn list_transfers(
+pub(crate) async fn retry_transfer(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<ListTransfersRequest>, APIError>,
-) -> Result<Json<ListTransfersResponse>, APIError> {
-    let guard = state.check_unlocked().await?;
-    let unlocked_state = guard.as_ref().unwrap();
+    WithRejection(Json(payload), _): WithRejection<Json<RetryTransferRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
 
-    let mut transfers = vec![];
-    for transfer in unlocked_state.rgb_list_transfers(payload.asset_id)? {
-        transfers.push(Transfer {
-            idx: transfer.idx,
-            created_at: transfer.created_at,
-            updated_at: transfer.updated_at,
-            status: match transfer.status {
-                rgb_lib::TransferStatus::WaitingCounterparty => TransferStatus::WaitingCounterparty,
-                rgb_lib::TransferStatus::WaitingConfirmations => {
-                    TransferStatus::WaitingConfirmations
+        let Some((asset_id, transfer)) = unlocked_state.rgb_get_transfer(payload.idx)? else {
+            return Err(APIError::TransferNotFound(payload.idx));
+        };
+        if !matches!(transfer.kind, rgb_lib::TransferKind::Send)
+            || !matches!(
+                transfer.status,
+                rgb_lib::TransferStatus::WaitingCounterparty
+            )
+        {
+            return Err(APIError::CannotRetryTransfer(s!(
+                "only sends waiting for the counterparty can be retried"
+            )));
+        }
+        let (Some(txid), Some(recipient_id)) = (transfer.txid, transfer.recipient_id) else {
+            return Err(APIError::CannotRetryTransfer(s!(
+                "the transfer has no TXID or recipient ID"
+            )));
+        };
+        let consignment_path = unlocked_state.rgb_get_send_consignment_path(&asset_id, &txid);
+        if !consignment_path.exists() {
+            return Err(APIError::CannotRetryTransfer(s!("consignment not found")));
+        }
+
+        // witness recipients are identified by the TX output paying them
+        let vout = match RecipientInfo::new(recipient_id.clone())?.recipient_type {
+            RgbLibRecipientType::Blind => None,
+            RgbLibRecipientType::Witness => {
+                let script_pubkey = script_buf_from_recipient_id(recipient_id.clone())?
+                    .ok_or(APIError::InvalidRecipientID)?;
+                let tx = unlocked_state
+                    .bitcoind_client
+                    .get_raw_transaction(&Txid::from_str(&txid).unwrap(), None)
+                    .await
+                    .ok_or_else(|| {
+                        APIError::CannotRetryTransfer(s!("the transfer TX is not in the mempool"))
+                    })?;
+                let vout = tx
+                    .output
+                    .iter()
+                    .position(|o| o.script_pubkey == script_pubkey)
+                    .ok_or_else(|| {
+                        APIError::CannotRetryTransfer(s!(
+                            "the transfer TX doesn't pay the recipient"
+                        ))
+                    })?;
+                Some(vout as u32)
+            }
+        };
+
+        let mut last_error = None;
+        for transport_endpoint in transfer.transport_endpoints {
+            let endpoint = transport_endpoint.endpoint;
+            let unlocked_state_copy = unlocked_state.clone();
+            let recipient_id = recipient_id.clone();
+            let consignment_path = consignment_path.clone();
+            let txid = txid.clone();
+            let endpoint_copy = endpoint.clone();
+            let res = tokio::task::spawn_blocking(move || {
+                unlocked_state_copy.rgb_post_consignment(
+                    &endpoint_copy,
+                    recipient_id,
+                    consignment_path,
+                    txid,
+                    vout,
+                )
+            })
+            .await
+            .unwrap();
+            match res {
+                Ok(()) => {
+                    tracing::info!(
+                        "Posted consignment of transfer {} to {endpoint}",
+                        payload.idx
+                    );
+                    return Ok(Json(EmptyResponse {}));
                 }
-                rgb_lib::TransferStatus::Settled => TransferStatus::Settled,
-                rgb_lib::TransferStatus::Failed => TransferStatus::Failed,
-            },
-            requested_assignment: transfer.requested_assignment.map(|a| a.into()),
-            assignments: transfer.assignments.into_iter().map(|a| a.into()).collect(),
-            kind: match transfer.kind {
-                rgb_lib::TransferKind::Issuance => TransferKind::Issuance,
-                rgb_lib::TransferKind::ReceiveBlind => TransferKind::ReceiveBlind,
-                rgb_lib::TransferKind::ReceiveWitness => TransferKind::ReceiveWitness,
-                rgb_lib::TransferKind::Send => TransferKind::Send,
-                rgb_lib::TransferKind::Inflation => TransferKind::Inflation,
-            },
-            txid: transfer.txid,
-            recipient_id: transfer.recipient_id,
-            receive_utxo: transfer.receive_utxo.map(|u| u.to_string()),
-            change_utxo: transfer.change_utxo.map(|u| u.to_string()),
-            expiration: transfer.expiration,
-            transport_endpoints: transfer
-                .transport_endpoints
-                .iter()
-                .map(|tte| TransferTransportEndpoint {
-                    endpoint: tte.endpoint.clone(),
-                    transport_type: match tte.transport_type {
-                        rgb_lib::TransportType::JsonRpc => TransportType::JsonRpc,
-                    },
-                    used: tte.used,
-                })
-                .collect(),
-        })
-    }
-    Ok(Json(ListTransfersResponse { transfers }))
+                Err(e) => {
+                    tracing::warn!("Cannot post consignment to {endpoint}: {e}");
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.map_or(APIError::NoValidTransportEndpoint, |e| e.into()))
+    })
+    .await
 }
 
-pub(crate) async fn list_unspents(
+pub(crate) async fn revoke_token(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<ListUnspentsRequest>, APIError>,
-) -> Result<Json<ListUnspentsResponse>, APIError> {
-    let guard = state.check_unlocked().await?;
-    let unlocked_state = guard.as_ref().unwrap();
+    WithRejection(Json(payload), _): WithRejection<Json<RevokeTokenRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    let root_public_key = *state.get_root_public_key();
+    let Some(root_pubkey) = root_public_key else {
+        return Err(APIError::AuthenticationDisabled);
+    };
 
-    let mut unspents = vec![];
-    for unspent in unlocked_state.rgb_list_unspents(payload.skip_sync)? {
-        unspents.push(Unspent {
-            utxo: Utxo {
-                outpoint: unspent.utxo.outpoint.to_string(),
-                btc_amount: unspent.utxo.btc_amount,
-                colorable: unspent.utxo.colorable,
-            },
-            rgb_allocations: unspent
-                .rgb_allocations
-                .iter()
-                .map(|a| RgbAllocation {
-                    asset_id: a.asset_id.clone(),
-                    assignment: a.assignment.clone().into(),
-                    settled: a.settled,
-                })
-                .collect(),
-        })
-    }
-    Ok(Json(ListUnspentsResponse { unspents }))
+    let token_to_revoke = Biscuit::from_base64(&payload.token, root_pubkey)
+        .map_err(|_| APIError::InvalidBiscuitToken)?;
+    state.revoke_token(&token_to_revoke)?;
+
+    Ok(Json(EmptyResponse {}))
 }
 
-pub(crate) async fn ln_invoice(
+pub(crate) async fn rgb_invoice(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<LNInvoiceRequest>, APIError>,
-) -> Result<Json<LNInvoiceResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<RgbInvoiceRequest>, APIError>,
+) -> Result<Json<RgbInvoiceResponse>, APIError> {
     no_cancel(async move {
         let guard = state.check_unlocked().await?;
         let unlocked_state = guard.as_ref().unwrap();
 
-        let contract_id = if let Some(asset_id) = payload.asset_id {
-            Some(ContractId::from_str(&asset_id).map_err(|_| APIError::InvalidAssetID(asset_id))?)
-        } else {
-            None
-        };
-
-        if contract_id.is_some() && payload.amt_msat.unwrap_or(0) < INVOICE_MIN_MSAT {
-            return Err(APIError::InvalidAmount(format!(
-                "amt_msat cannot be less than {INVOICE_MIN_MSAT} when transferring an RGB asset"
-            )));
+        let _rgb_spend_guard = unlocked_state.rgb_spend_lock.lock().await;
+        if *unlocked_state.rgb_send_lock.lock().unwrap() {
+            return Err(APIError::OpenChannelInProgress);
         }
 
-        let invoice_params = Bolt11InvoiceParameters {
-            amount_msats: payload.amt_msat,
-            invoice_expiry_delta_secs: Some(payload.expiry_sec),
-            contract_id,
-            asset_amount: payload.asset_amount,
-            ..Default::default()
-        };
+        let label = payload.label.map(check_label).transpose()?;
+        let assignment = payload.assignment.unwrap_or(Assignment::Any).into();
 
-        let invoice = match unlocked_state
-            .channel_manager
-            .create_bolt11_invoice(invoice_params)
-        {
-            Ok(inv) => inv,
-            Err(e) => return Err(APIError::FailedInvoiceCreation(e.to_string())),
+        let receive_data = if payload.witness {
+            unlocked_state.rgb_witness_receive(
+                payload.asset_id.clone(),
+                assignment,
+                payload.duration_seconds,
+                unlocked_state.proxy_endpoints.clone(),
+                payload.min_confirmations,
+            )?
+        } else {
+            unlocked_state.rgb_blind_receive(
+                payload.asset_id.clone(),
+                assignment,
+                payload.duration_seconds,
+                unlocked_state.proxy_endpoints.clone(),
+                payload.min_confirmations,
+            )?
         };
-
-        let payment_hash = PaymentHash((*invoice.payment_hash()).to_byte_array());
-        let created_at = get_current_timestamp();
-        unlocked_state.add_inbound_payment(
-            payment_hash,
-            PaymentInfo {
-                preimage: None,
-                secret: Some(*invoice.payment_secret()),
-                status: HTLCStatus::Pending,
-                amt_msat: payload.amt_msat,
-                created_at,
-                updated_at: created_at,
-                payee_pubkey: unlocked_state.channel_manager.get_our_node_id(),
+        unlocked_state.add_pending_settlement(
+            receive_data.batch_transfer_idx.to_string(),
+            PendingSettlementInfo {
+                label,
+                asset_id: payload.asset_id,
+                recipient_id: Some(receive_data.recipient_id.clone()),
+                expires_at: receive_data
+                    .expiration_timestamp
+                    .map(|t| t as u64)
+                    .unwrap_or(u64::MAX),
             },
         );
 
-        Ok(Json(LNInvoiceResponse {
-            invoice: invoice.to_string(),
+        Ok(Json(RgbInvoiceResponse {
+            recipient_id: receive_data.recipient_id,
+            invoice: receive_data.invoice,
+            expiration_timestamp: receive_data.expiration_timestamp,
+            batch_transfer_idx: receive_data.batch_transfer_idx,
         }))
     })
     .await
 }
 
-pub(crate) async fn lock(
+pub(crate) async fn send_asset(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<EmptyResponse>, APIError> {
-    tracing::info!("Lock started");
+    WithRejection(Json(payload), _): WithRejection<Json<SendAssetRequest>, APIError>,
+) -> Result<Json<SendAssetResponse>, APIError> {
     no_cancel(async move {
-        match state.check_unlocked().await {
-            Ok(unlocked_state) => {
-                state.update_changing_state(true);
-                drop(unlocked_state);
-            }
-            Err(e) => {
-                state.update_changing_state(false);
-                return Err(e);
-            }
+        let guard = state.check_unlocked().await?;
+        state.check_draining()?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        // held until the send completes, so that no channel open can start meanwhile
+        let _rgb_spend_guard = unlocked_state.rgb_spend_lock.lock().await;
+        if *unlocked_state.rgb_send_lock.lock().unwrap() {
+            return Err(APIError::OpenChannelInProgress);
         }
 
-        tracing::debug!("Stopping LDK...");
-        stop_ldk(state.clone()).await;
-        tracing::debug!("LDK stopped");
+        check_broadcast_schedule(payload.broadcast_at)?;
 
-        state.update_unlocked_app_state(None).await;
+        RecipientInfo::new(payload.recipient_id.clone())?;
+        if !payload.dry_run {
+            unlocked_state.check_totp(payload.totp_code.as_deref(), TotpOperation::Spend)?;
+        }
+        let recipient_map = map! {
+            payload.asset_id => vec![Recipient {
+                recipient_id: payload.recipient_id,
+                witness_data: payload.witness_data.map(|w| w.into()),
+                assignment: payload.assignment.into(),
+                transport_endpoints: payload.transport_endpoints,
+            }]
+        };
 
-        state.update_ldk_background_services(None);
+        if payload.dry_run {
+            // the transfer is only saved when its TX gets broadcast, so nothing is left behind
+            let unlocked_state = unlocked_state.clone();
+            let (txid, dry_run) = tokio::task::spawn_blocking(move || {
+                let unsigned_psbt = unlocked_state.rgb_send_begin(
+                    recipient_map,
+                    payload.donation,
+                    payload.fee_rate,
+                    payload.min_confirmations,
+                )?;
+                unlocked_state.dry_run_tx(&unsigned_psbt)
+            })
+            .await
+            .unwrap()?;
+            return Ok(Json(SendAssetResponse {
+                txid,
+                dry_run: Some(dry_run),
+            }));
+        }
 
-        state.update_changing_state(false);
+        let unlocked_state_copy = unlocked_state.clone();
+        let txid = if payload.hold_for_approval || payload.broadcast_at.is_some() {
+            tokio::task::spawn_blocking(move || {
+                let unsigned_psbt = unlocked_state_copy.rgb_send_begin(
+                    recipient_map,
+                    payload.donation,
+                    payload.fee_rate,
+                    payload.min_confirmations,
+                )?;
+                let signed_psbt = unlocked_state_copy.rgb_sign_psbt(unsigned_psbt)?;
+                unlocked_state_copy.defer_broadcast(
+                    PendingBroadcastKind::Asset,
+                    signed_psbt,
+                    payload.broadcast_at,
+                    payload.hold_for_approval,
+                )
+            })
+            .await
+            .unwrap()?
+        } else {
+            tokio::task::spawn_blocking(move || {
+                unlocked_state_copy.rgb_send(
+                    recipient_map,
+                    payload.donation,
+                    payload.fee_rate,
+                    payload.min_confirmations,
+                    payload.skip_sync,
+                )
+            })
+            .await
+            .unwrap()?
+            .txid
+        };
 
-        tracing::info!("Lock completed");
-        Ok(Json(EmptyResponse {}))
+        Ok(Json(SendAssetResponse {
+            txid,
+            dry_run: None,
+        }))
     })
     .await
 }
 
-pub(crate) async fn maker_execute(
+pub(crate) async fn send_btc(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<MakerExecuteRequest>, APIError>,
-) -> Result<Json<EmptyResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<SendBtcRequest>, APIError>,
+) -> Result<Json<SendBtcResponse>, APIError> {
     no_cancel(async move {
         let guard = state.check_unlocked().await?;
         let unlocked_state = guard.as_ref().unwrap();
 
-        let swapstring = SwapString::from_str(&payload.swapstring)
-            .map_err(|e| APIError::InvalidSwapString(payload.swapstring.clone(), e.to_string()))?;
-        let payment_secret = hex_str_to_vec(&payload.payment_secret)
-            .and_then(|data| data.try_into().ok())
-            .map(PaymentSecret)
-            .ok_or(APIError::InvalidPaymentSecret)?;
-        let taker_pk =
-            PublicKey::from_str(&payload.taker_pubkey).map_err(|_| APIError::InvalidPubkey)?;
-
-        if get_current_timestamp() > swapstring.swap_info.expiry {
-            unlocked_state.update_maker_swap_status(&swapstring.payment_hash, SwapStatus::Expired);
-            return Err(APIError::ExpiredSwapOffer);
+        check_broadcast_schedule(payload.broadcast_at)?;
+        check_address_network(&payload.address, state.static_state.network)?;
+        if !payload.dry_run {
+            unlocked_state.check_totp(payload.totp_code.as_deref(), TotpOperation::Spend)?;
         }
 
-        let payment_preimage = unlocked_state
-            .channel_manager
-            .get_payment_preimage(swapstring.payment_hash, payment_secret)
-            .map_err(|_| APIError::MissingSwapPaymentPreimage)?;
-
-        let swap_info = swapstring.swap_info;
-
-        let receive_hints = unlocked_state
-            .channel_manager
-            .list_usable_channels()
-            .iter()
-            .filter(|details| {
-                match get_rgb_channel_info_optional(
-                    &details.channel_id,
-                    &state.static_state.ldk_data_dir,
-                    false,
-                ) {
-                    _ if swap_info.is_from_btc() => true,
-                    Some((rgb_info, _)) if Some(rgb_info.contract_id) == swap_info.from_asset => {
-                        true
-                    }
-                    _ => false,
-                }
-            })
-            .map(|details| {
-                let config = details.counterparty.forwarding_info.as_ref().unwrap();
-                RouteHint(vec![RouteHintHop {
-                    src_node_id: details.counterparty.node_id,
-                    short_channel_id: details.short_channel_id.unwrap(),
-                    cltv_expiry_delta: config.cltv_expiry_delta,
-                    htlc_maximum_msat: None,
-                    htlc_minimum_msat: None,
-                    fees: RoutingFees {
-                        base_msat: config.fee_base_msat,
-                        proportional_millionths: config.fee_proportional_millionths,
-                    },
-                    htlc_maximum_rgb: None,
-                }])
-            })
-            .collect();
-
-        let rgb_payment = swap_info
-            .to_asset
-            .map(|to_asset| (to_asset, swap_info.qty_to));
-        let first_leg = get_route(
-            &unlocked_state.channel_manager,
-            &unlocked_state.router,
-            unlocked_state.channel_manager.get_our_node_id(),
-            taker_pk,
-            if swap_info.is_to_btc() {
-                Some(swap_info.qty_to + HTLC_MIN_MSAT)
-            } else {
-                Some(HTLC_MIN_MSAT)
-            },
-            rgb_payment,
-            vec![],
-        );
-
-        let rgb_payment = swap_info
-            .from_asset
-            .map(|from_asset| (from_asset, swap_info.qty_from));
-        let second_leg = get_route(
-            &unlocked_state.channel_manager,
-            &unlocked_state.router,
-            taker_pk,
-            unlocked_state.channel_manager.get_our_node_id(),
-            if swap_info.is_to_btc() || swap_info.is_asset_asset() {
-                Some(HTLC_MIN_MSAT)
+        let txid = if let Some(coin_selection) = payload.coin_selection {
+            let script_pubkey = Address::from_str(&payload.address)
+                .unwrap()
+                .assume_checked()
+                .script_pubkey();
+            let signed_psbt = unlocked_state
+                .send_btc_begin_with_coin_selection(
+                    vec![TxOut {
+                        value: Amount::from_sat(payload.amount),
+                        script_pubkey,
+                    }],
+                    payload.fee_rate,
+                    &coin_selection,
+                    payload.skip_sync,
+                )
+                .await?;
+            if payload.dry_run {
+                let (txid, dry_run) = unlocked_state.dry_run_tx(&signed_psbt)?;
+                return Ok(Json(SendBtcResponse {
+                    txid,
+                    dry_run: Some(dry_run),
+                }));
+            }
+            if payload.hold_for_approval || payload.broadcast_at.is_some() {
+                unlocked_state.defer_broadcast(
+                    PendingBroadcastKind::Btc,
+                    signed_psbt,
+                    payload.broadcast_at,
+                    payload.hold_for_approval,
+                )?
             } else {
-                Some(swap_info.qty_from + HTLC_MIN_MSAT)
-            },
-            rgb_payment,
-            receive_hints,
-        );
-
-        let (mut first_leg, mut second_leg) = match (first_leg, second_leg) {
-            (Some(f), Some(s)) => (f, s),
-            _ => {
-                return Err(APIError::NoRoute);
+                unlocked_state.rgb_send_btc_end(signed_psbt)?
             }
+        } else if payload.dry_run || payload.hold_for_approval || payload.broadcast_at.is_some() {
+            if !payload.skip_sync {
+                unlocked_state.rgb_sync()?;
+            }
+            let unsigned_psbt = unlocked_state.rgb_send_btc_begin(
+                payload.address,
+                payload.amount,
+                payload.fee_rate,
+            )?;
+            if payload.dry_run {
+                let (txid, dry_run) = unlocked_state.dry_run_tx(&unsigned_psbt)?;
+                return Ok(Json(SendBtcResponse {
+                    txid,
+                    dry_run: Some(dry_run),
+                }));
+            }
+            let signed_psbt = unlocked_state.rgb_sign_psbt(unsigned_psbt)?;
+            unlocked_state.defer_broadcast(
+                PendingBroadcastKind::Btc,
+                signed_psbt,
+                payload.broadcast_at,
+                payload.hold_for_approval,
+            )?
+        } else {
+            unlocked_state.rgb_send_btc(
+                payload.address,
+                payload.amount,
+                payload.fee_rate,
+                payload.skip_sync,
+            )?
         };
 
-        // Set swap flag
-        second_leg.paths[0].hops[0].short_channel_id |= IS_SWAP_SCID;
-
-        // Generally in the last hop the fee_amount is set to the payment amount, so we need to
-        // override it with fee = 0
-        first_leg.paths[0]
-            .hops
-            .last_mut()
-            .expect("Path not to be empty")
-            .fee_msat = 0;
-
-        let fullpaths = first_leg.paths[0]
-            .hops
-            .clone()
-            .into_iter()
-            .map(|mut hop| {
-                if swap_info.is_to_asset() {
-                    hop.rgb_payment = Some((swap_info.to_asset.unwrap(), swap_info.qty_to));
-                }
-                hop
-            })
-            .chain(second_leg.paths[0].hops.clone().into_iter().map(|mut hop| {
-                if swap_info.is_from_asset() {
-                    hop.rgb_payment = Some((swap_info.from_asset.unwrap(), swap_info.qty_from));
-                }
-                hop
-            }))
-            .collect::<Vec<_>>();
+        Ok(Json(SendBtcResponse {
+            txid,
+            dry_run: None,
+        }))
+    })
+    .await
+}
 
-        // Skip last fee because it's equal to the payment amount
-        let total_fee = fullpaths
-            .iter()
-            .rev()
-            .skip(1)
-            .map(|hop| hop.fee_msat)
-            .sum::<u64>();
+pub(crate) async fn send_btc_many(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<SendBtcManyRequest>, APIError>,
+) -> Result<Json<SendBtcManyResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
 
-        if total_fee >= MAX_SWAP_FEE_MSAT {
-            return Err(APIError::FailedPayment(format!(
-                "Fee too high: {total_fee}"
-            )));
+        if payload.recipients.is_empty() && payload.asset_recipients.is_empty() {
+            return Err(APIError::InvalidRecipients(s!("no recipient provided")));
         }
-
-        let route = Route {
-            paths: vec![LnPath {
-                hops: fullpaths,
-                blinded_tail: None,
-            }],
-            route_params: Some(RouteParameters {
-                payment_params: PaymentParameters::for_keysend(
-                    unlocked_state.channel_manager.get_our_node_id(),
-                    DEFAULT_FINAL_CLTV_EXPIRY_DELTA,
-                    false,
-                ),
-                // This value is not used anywhere, it's set by the router
-                // when creating a route, but here we are creating it manually
-                // by composing a pre-existing list of hops
-                final_value_msat: 0,
-                max_total_routing_fee_msat: None,
-                // This value is not used anywhere, same as final_value_msat
-                rgb_payment: None,
-            }),
-        };
-
-        if swap_info.is_to_asset() {
-            write_rgb_payment_info_file(
-                &state.static_state.ldk_data_dir,
-                &swapstring.payment_hash,
-                swap_info.to_asset.unwrap(),
-                swap_info.qty_to,
-                true,
-                false,
-            );
+        // consignments reference the txid of the TX built by rgb-lib, which cannot be changed to
+        // pay additional outputs
+        if !payload.recipients.is_empty() && !payload.asset_recipients.is_empty() {
+            return Err(APIError::InvalidRecipients(s!(
+                "BTC and asset recipients cannot be paid in the same transaction"
+            )));
         }
-
-        unlocked_state.update_maker_swap_status(&swapstring.payment_hash, SwapStatus::Pending);
-
-        let payment_hash: PaymentHash = payment_preimage.into();
-        let (_status, err) = match unlocked_state
-            .channel_manager
-            .send_spontaneous_payment_with_route(
-                route,
-                payment_hash,
-                payment_preimage,
-                RecipientOnionFields::spontaneous_empty(),
-                PaymentId(swapstring.payment_hash.0),
-            ) {
-            Ok(()) => {
-                tracing::debug!("EVENT: initiated swap");
-                (HTLCStatus::Pending, None)
+        unlocked_state.check_totp(payload.totp_code.as_deref(), TotpOperation::Spend)?;
+
+        let txid = if payload.asset_recipients.is_empty() {
+            let mut output = vec![];
+            for recipient in payload.recipients {
+                check_address_network(&recipient.address, state.static_state.network)?;
+                output.push(TxOut {
+                    value: Amount::from_sat(recipient.amount),
+                    script_pubkey: Address::from_str(&recipient.address)
+                        .unwrap()
+                        .assume_checked()
+                        .script_pubkey(),
+                });
             }
-            Err(e) => {
-                tracing::warn!("ERROR: failed to send payment: {:?}", e);
-                (HTLCStatus::Failed, Some(e))
+            let coin_selection = payload.coin_selection.unwrap_or(CoinSelection {
+                strategy: CoinSelectionStrategy::LargestFirst,
+                outpoints: None,
+                excluded_outpoints: None,
+            });
+            let signed_psbt = unlocked_state
+                .send_btc_begin_with_coin_selection(
+                    output,
+                    payload.fee_rate,
+                    &coin_selection,
+                    payload.skip_sync,
+                )
+                .await?;
+            unlocked_state.rgb_send_btc_end(signed_psbt)?
+        } else {
+            state.check_draining()?;
+            let _rgb_spend_guard = unlocked_state.rgb_spend_lock.lock().await;
+            if *unlocked_state.rgb_send_lock.lock().unwrap() {
+                return Err(APIError::OpenChannelInProgress);
+            }
+            if payload.coin_selection.is_some() {
+                return Err(APIError::InvalidCoinSelection(s!(
+                    "only supported for BTC recipients"
+                )));
             }
-        };
 
-        match err {
-            None => Ok(Json(EmptyResponse {})),
-            Some(e) => {
-                unlocked_state
-                    .update_maker_swap_status(&swapstring.payment_hash, SwapStatus::Failed);
-                Err(APIError::FailedPayment(format!("{e:?}")))
+            let mut recipient_map: HashMap<String, Vec<Recipient>> = HashMap::new();
+            for recipient in payload.asset_recipients {
+                RecipientInfo::new(recipient.recipient_id.clone())?;
+                recipient_map
+                    .entry(recipient.asset_id)
+                    .or_default()
+                    .push(Recipient {
+                        recipient_id: recipient.recipient_id,
+                        witness_data: recipient.witness_data.map(|w| w.into()),
+                        assignment: recipient.assignment.into(),
+                        transport_endpoints: recipient.transport_endpoints,
+                    });
             }
-        }
+            let unlocked_state_copy = unlocked_state.clone();
+            tokio::task::spawn_blocking(move || {
+                unlocked_state_copy.rgb_send(
+                    recipient_map,
+                    payload.donation,
+                    payload.fee_rate,
+                    payload.min_confirmations,
+                    payload.skip_sync,
+                )
+            })
+            .await
+            .unwrap()?
+            .txid
+        };
+
+        Ok(Json(SendBtcManyResponse { txid }))
     })
     .await
 }
 
-pub(crate) async fn maker_init(
+pub(crate) async fn send_onion_message(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<MakerInitRequest>, APIError>,
-) -> Result<Json<MakerInitResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<SendOnionMessageRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
     no_cancel(async move {
         let guard = state.check_unlocked().await?;
         let unlocked_state = guard.as_ref().unwrap();
 
-        let from_asset = match &payload.from_asset {
-            None => None,
-            Some(asset) => Some(
-                ContractId::from_str(asset).map_err(|_| APIError::InvalidAssetID(asset.clone()))?,
-            ),
-        };
-
-        let to_asset = match &payload.to_asset {
-            None => None,
-            Some(asset) => Some(
-                ContractId::from_str(asset).map_err(|_| APIError::InvalidAssetID(asset.clone()))?,
-            ),
-        };
-
-        // prevent BTC-to-BTC swaps
-        if from_asset.is_none() && to_asset.is_none() {
-            return Err(APIError::InvalidSwap(s!("cannot swap BTC for BTC")));
+        if payload.node_ids.is_empty() {
+            return Err(APIError::InvalidNodeIds(s!(
+                "sendonionmessage requires at least one node id for the path"
+            )));
         }
 
-        // prevent swaps of same assets
-        if from_asset == to_asset {
-            return Err(APIError::InvalidSwap(s!("cannot swap the same asset")));
+        let mut intermediate_nodes = Vec::new();
+        for pk_str in payload.node_ids {
+            let node_pubkey_vec = match hex_str_to_vec(&pk_str) {
+                Some(peer_pubkey_vec) => peer_pubkey_vec,
+                None => {
+                    return Err(APIError::InvalidNodeIds(format!(
+                        "Couldn't parse peer_pubkey '{pk_str}'"
+                    )))
+                }
+            };
+            let node_pubkey = match PublicKey::from_slice(&node_pubkey_vec) {
+                Ok(peer_pubkey) => peer_pubkey,
+                Err(_) => {
+                    return Err(APIError::InvalidNodeIds(format!(
+                        "Couldn't parse peer_pubkey '{pk_str}'"
+                    )))
+                }
+            };
+            intermediate_nodes.push(node_pubkey);
         }
 
-        let qty_from = payload.qty_from;
-        let qty_to = payload.qty_to;
+        if payload.tlv_type < 64 {
+            return Err(APIError::InvalidTlvType(s!(
+                "need an integral message type above 64"
+            )));
+        }
 
-        let expiry = get_current_timestamp() + payload.timeout_sec as u64;
-        let swap_info = SwapInfo {
-            from_asset,
-            to_asset,
-            qty_from,
-            qty_to,
-            expiry,
-        };
-        let swap_data = SwapData::create_from_swap_info(&swap_info);
+        let data = hex_str_to_vec(&payload.data)
+            .ok_or(APIError::InvalidOnionData(s!("need a hex data string")))?;
 
-        // Check that we have enough assets to send
-        if let Some(to_asset) = to_asset {
-            let max_balance = get_max_local_rgb_amount(
-                to_asset,
-                &state.static_state.ldk_data_dir,
-                unlocked_state.channel_manager.list_channels().iter(),
-            );
-            if swap_info.qty_to > max_balance {
-                return Err(APIError::InsufficientAssets);
-            }
-        }
+        let destination = Destination::Node(intermediate_nodes.pop().unwrap());
+        let message_send_instructions = MessageSendInstructions::WithoutReplyPath { destination };
 
-        let (payment_hash, payment_secret) = unlocked_state
-            .channel_manager
-            .create_inbound_payment(Some(DUST_LIMIT_MSAT), payload.timeout_sec, None)
-            .unwrap();
-        unlocked_state.add_maker_swap(payment_hash, swap_data);
+        unlocked_state
+            .onion_messenger
+            .send_onion_message(
+                UserOnionMessageContents {
+                    tlv_type: payload.tlv_type,
+                    data,
+                },
+                message_send_instructions,
+            )
+            .map_err(|e| APIError::FailedSendingOnionMessage(format!("{e:?}")))?;
 
-        let swapstring = SwapString::from_swap_info(&swap_info, payment_hash).to_string();
+        tracing::info!("SUCCESS: forwarded onion message to first hop");
 
-        let payment_secret = payment_secret.0.as_hex().to_string();
-        let payment_hash = payment_hash.0.as_hex().to_string();
-        Ok(Json(MakerInitResponse {
-            payment_hash,
-            payment_secret,
-            swapstring,
-        }))
+        Ok(Json(EmptyResponse {}))
     })
     .await
 }
 
-pub(crate) async fn network_info(
+pub(crate) async fn send_payment(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<NetworkInfoResponse>, APIError> {
-    let guard = state.check_unlocked().await?;
-    let unlocked_state = guard.as_ref().unwrap();
-
-    let best_block = unlocked_state.channel_manager.current_best_block();
+    WithRejection(Json(payload), _): WithRejection<Json<SendPaymentRequest>, APIError>,
+) -> Result<Json<SendPaymentResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        state.check_draining()?;
+        let unlocked_state = guard.as_ref().unwrap();
 
-    Ok(Json(NetworkInfoResponse {
-        network: state.static_state.network.into(),
-        height: best_block.height,
-    }))
-}
+        let limits =
+            check_payment_limits(&payload, state.static_state.max_total_cltv_expiry_delta)?;
 
-pub(crate) async fn node_info(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<NodeInfoResponse>, APIError> {
-    let guard = state.check_unlocked().await?;
-    let unlocked_state = guard.as_ref().unwrap();
+        let mut status = HTLCStatus::Pending;
+        let created_at = get_current_timestamp();
 
-    let chans = unlocked_state.channel_manager.list_channels();
+        let (payment_id, payment_hash, payment_secret) = if let Ok(offer) =
+            Offer::from_str(&payload.invoice)
+        {
+            check_offer_network(&offer, state.static_state.network)?;
+            if payload.dry_run {
+                return Err(APIError::UnsupportedDryRun(s!(
+                    "offers are paid with an invoice requested to the recipient"
+                )));
+            }
+            let totp_operation = match (offer.amount(), payload.amt_msat) {
+                (Some(offer::Amount::Bitcoin { amount_msats }), _) => {
+                    TotpOperation::Payment(amount_msats)
+                }
+                (_, Some(amt_msat)) => TotpOperation::Payment(amt_msat),
+                _ => TotpOperation::Spend,
+            };
+            unlocked_state.check_totp(payload.totp_code.as_deref(), totp_operation)?;
+            let (payment_id, offer_status) =
+                unlocked_state.pay_offer(&offer, payload.amt_msat, None, &limits)?;
+            status = offer_status;
+            (payment_id, None, None)
+        } else {
+            let invoice = match Bolt11Invoice::from_str(&payload.invoice) {
+                Err(e) => return Err(APIError::InvalidInvoice(e.to_string())),
+                Ok(v) => v,
+            };
+            check_invoice_network(&invoice, state.static_state.network)?;
 
-    let balances = unlocked_state.chain_monitor.get_claimable_balances(&[]);
-    let local_balance_sat = balances
-        .iter()
-        .map(|b| b.claimable_amount_satoshis())
-        .sum::<u64>();
+            let payment_id = PaymentId((*invoice.payment_hash()).to_byte_array());
+            let payment_secret = Some(*invoice.payment_secret());
+            let (amt_msat, rgb_payment) = check_invoice_amounts(&invoice, payload.amt_msat)?;
+            // payments of RGB assets always need the code, as their value isn't in msat
+            let totp_operation = if rgb_payment.is_some() {
+                TotpOperation::Spend
+            } else {
+                TotpOperation::Payment(amt_msat)
+            };
+            if !payload.dry_run {
+                unlocked_state.check_totp(payload.totp_code.as_deref(), totp_operation)?;
+            }
+            if let Some((contract_id, _)) = rgb_payment {
+                unlocked_state.check_asset_htlc_min(
+                    contract_id,
+                    amt_msat,
+                    &state.static_state.ldk_data_dir,
+                )?;
+            }
 
-    let close_fees_map = |b| match b {
-        &Balance::ClaimableOnChannelClose {
-            ref balance_candidates,
-            confirmed_balance_candidate_index,
-            ..
-        } => balance_candidates[confirmed_balance_candidate_index].transaction_fee_satoshis,
-        _ => 0,
-    };
-    let eventual_close_fees_sat = balances.iter().map(close_fees_map).sum::<u64>();
+            let num_parts = limits.num_parts(amt_msat)?;
+            if rgb_payment.is_some() && (num_parts > 1 || limits.max_parts.is_some_and(|p| p > 1)) {
+                return Err(APIError::InvalidPaymentLimits(s!(
+                    "multi-part payments are not supported for RGB assets"
+                )));
+            }
+            if num_parts > 1 && !invoice.features().is_some_and(|f| f.supports_basic_mpp()) {
+                return Err(APIError::InvalidPaymentLimits(s!(
+                    "the invoice does not support multi-part payments"
+                )));
+            }
 
-    let pending_payments_map = |b| match b {
-        &Balance::MaybeTimeoutClaimableHTLC {
-            amount_satoshis,
-            outbound_payment,
-            ..
-        } => {
-            if outbound_payment {
-                amount_satoshis
-            } else {
-                0
+            if payload.dry_run {
+                if let Some(existing_payment) = unlocked_state
+                    .get_outbound_payments()
+                    .payments
+                    .get(&payment_id)
+                {
+                    if !matches!(existing_payment.status, HTLCStatus::Failed) {
+                        return Err(APIError::DuplicatePayment(
+                            existing_payment.status.to_string(),
+                        ));
+                    }
+                }
+                let dry_run = unlocked_state.dry_run_payment(
+                    &invoice,
+                    amt_msat,
+                    rgb_payment,
+                    num_parts,
+                    &limits,
+                )?;
+                return Ok(Json(SendPaymentResponse {
+                    payment_id: hex_str(&payment_id.0),
+                    payment_hash: Some(hex_str(&invoice.payment_hash().to_byte_array())),
+                    payment_secret: payment_secret.map(|s| hex_str(&s.0)),
+                    status,
+                    dry_run: Some(dry_run),
+                }));
             }
-        }
-        _ => 0,
-    };
-    let pending_outbound_payments_sat = balances.iter().map(pending_payments_map).sum::<u64>();
 
-    let graph_lock = unlocked_state.network_graph.read_only();
-    let network_nodes = graph_lock.nodes().len();
-    let network_channels = graph_lock.channels().len();
+            let secret = payment_secret;
+            unlocked_state.add_outbound_payment(
+                payment_id,
+                PaymentInfo {
+                    preimage: None,
+                    secret,
+                    status,
+                    amt_msat: invoice.amount_milli_satoshis(),
+                    created_at,
+                    updated_at: created_at,
+                    payee_pubkey: invoice.get_payee_pub_key(),
+                    offer_id: None,
+                    parts: vec![],
+                    expires_at: None,
+                    settle_after_expiry: false,
+                },
+            )?;
+            let payment_hash = PaymentHash(invoice.payment_hash().to_byte_array());
+            if let Some((contract_id, rgb_amount)) = rgb_payment {
+                write_rgb_payment_info_file(
+                    &PathBuf::from(&state.static_state.ldk_data_dir),
+                    &payment_hash,
+                    contract_id,
+                    rgb_amount,
+                    false,
+                    false,
+                );
+            }
 
-    Ok(Json(NodeInfoResponse {
-        pubkey: unlocked_state.channel_manager.get_our_node_id().to_string(),
-        num_channels: chans.len(),
-        num_usable_channels: chans.iter().filter(|c| c.is_usable).count(),
-        local_balance_sat,
-        eventual_close_fees_sat,
-        pending_outbound_payments_sat,
-        num_peers: unlocked_state.peer_manager.list_peers().len(),
-        account_xpub_vanilla: unlocked_state.rgb_get_wallet_data().account_xpub_vanilla,
-        account_xpub_colored: unlocked_state.rgb_get_wallet_data().account_xpub_colored,
-        max_media_upload_size_mb: state.static_state.max_media_upload_size_mb,
-        rgb_htlc_min_msat: HTLC_MIN_MSAT,
-        rgb_channel_capacity_min_sat: OPENRGBCHANNEL_MIN_SAT,
-        channel_capacity_min_sat: OPENCHANNEL_MIN_SAT,
-        channel_capacity_max_sat: OPENCHANNEL_MAX_SAT,
-        channel_asset_min_amount: OPENCHANNEL_MIN_RGB_AMT,
-        channel_asset_max_amount: u64::MAX,
-        network_nodes,
-        network_channels,
-    }))
+            // no route fits a null CLTV budget, so the route search fails as on a real network
+            #[cfg(feature = "failure-injection")]
+            let limits = if unlocked_state.take_injected_no_route() {
+                PaymentLimits {
+                    max_total_cltv_expiry_delta: 0,
+                    ..limits
+                }
+            } else {
+                limits
+            };
+            let send_res = if limits.max_shard_amt_msat.is_some() {
+                // parts are planned here, so they are sent without retrying on other routes
+                unlocked_state
+                    .multi_part_route(&invoice, amt_msat, num_parts, &limits)
+                    .and_then(|route| {
+                        unlocked_state
+                            .channel_manager
+                            .send_payment_with_route(
+                                route,
+                                payment_hash,
+                                RecipientOnionFields::secret_only(*invoice.payment_secret()),
+                                payment_id,
+                            )
+                            .map_err(|e| format!("{e:?}"))
+                    })
+            } else {
+                unlocked_state
+                    .channel_manager
+                    .pay_for_bolt11_invoice(
+                        &invoice,
+                        payment_id,
+                        Some(amt_msat),
+                        limits.route_params_config(amt_msat),
+                        limits.retry_strategy(),
+                    )
+                    .map_err(|e| format!("{e:?}"))
+            };
+            match send_res {
+                Ok(_) => {
+                    let payee_pubkey = invoice.recover_payee_pub_key();
+                    let amt_msat = invoice.amount_milli_satoshis().unwrap();
+                    tracing::info!(
+                        "EVENT: initiated sending {} msats to {}",
+                        amt_msat,
+                        payee_pubkey
+                    );
+                }
+                Err(e) => {
+                    tracing::error!("ERROR: failed to send payment: {:?}", e);
+                    status = HTLCStatus::Failed;
+                    unlocked_state.update_outbound_payment_status(payment_id, status);
+                }
+            };
+
+            (payment_id, Some(payment_hash), secret)
+        };
+
+        if let (Some(timeout), HTLCStatus::Pending) = (limits.timeout, status) {
+            let unlocked_state = Arc::clone(unlocked_state);
+            tokio::spawn(async move {
+                tokio::time::sleep(timeout).await;
+                unlocked_state.abandon_pending_payment(payment_id, "after its timeout");
+            });
+        }
+
+        Ok(Json(SendPaymentResponse {
+            payment_id: hex_str(&payment_id.0),
+            payment_hash: payment_hash.map(|h| hex_str(&h.0)),
+            payment_secret: payment_secret.map(|s| hex_str(&s.0)),
+            status,
+            dry_run: None,
+        }))
+    })
+    .await
 }
 
-pub(crate) async fn open_channel(
+pub(crate) async fn send_to_route(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<OpenChannelRequest>, APIError>,
-) -> Result<Json<OpenChannelResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<SendToRouteRequest>, APIError>,
+) -> Result<Json<SendPaymentResponse>, APIError> {
     no_cancel(async move {
         let guard = state.check_unlocked().await?;
+        state.check_draining()?;
         let unlocked_state = guard.as_ref().unwrap();
 
-        if *unlocked_state.rgb_send_lock.lock().unwrap() {
-            return Err(APIError::OpenChannelInProgress);
-        }
-
-        let temporary_channel_id = if let Some(tmp_chan_id_str) = payload.temporary_channel_id {
-            let tmp_chan_id = check_channel_id(&tmp_chan_id_str)?;
-            if unlocked_state.channel_ids().contains_key(&tmp_chan_id) {
-                return Err(APIError::TemporaryChannelIdAlreadyUsed);
-            }
-            Some(tmp_chan_id)
-        } else {
-            None
+        let invoice = match Bolt11Invoice::from_str(&payload.invoice) {
+            Err(e) => return Err(APIError::InvalidInvoice(e.to_string())),
+            Ok(v) => v,
         };
-
-        let colored_info = match (payload.asset_id, payload.asset_amount) {
-            (Some(_), Some(amt)) if amt < OPENCHANNEL_MIN_RGB_AMT => {
-                return Err(APIError::InvalidAmount(format!(
-                    "Channel RGB amount must be equal to or higher than {OPENCHANNEL_MIN_RGB_AMT}"
-                )));
-            }
-            (Some(asset), Some(amt)) => {
-                let asset =
-                    ContractId::from_str(&asset).map_err(|_| APIError::InvalidAssetID(asset))?;
-                Some((asset, amt))
-            }
-            (None, None) => None,
-            _ => {
-                return Err(APIError::IncompleteRGBInfo);
-            }
+        check_invoice_network(&invoice, state.static_state.network)?;
+        let (amt_msat, rgb_payment) = check_invoice_amounts(&invoice, payload.amt_msat)?;
+        let totp_operation = if rgb_payment.is_some() {
+            TotpOperation::Spend
+        } else {
+            TotpOperation::Payment(amt_msat)
         };
-
-        if colored_info.is_some() && payload.capacity_sat < OPENRGBCHANNEL_MIN_SAT {
-            return Err(APIError::InvalidAmount(format!(
-                "RGB channel amount must be equal to or higher than {OPENRGBCHANNEL_MIN_SAT} sats"
-            )));
-        } else if payload.capacity_sat < OPENCHANNEL_MIN_SAT {
-            return Err(APIError::InvalidAmount(format!(
-                "Channel amount must be equal to or higher than {OPENCHANNEL_MIN_SAT} sats"
-            )));
-        }
-        if payload.capacity_sat > OPENCHANNEL_MAX_SAT {
-            return Err(APIError::InvalidAmount(format!(
-                "Channel amount must be equal to or less than {OPENCHANNEL_MAX_SAT} sats"
-            )));
+        unlocked_state.check_totp(payload.totp_code.as_deref(), totp_operation)?;
+        if let Some((contract_id, _)) = rgb_payment {
+            unlocked_state.check_asset_htlc_min(
+                contract_id,
+                amt_msat,
+                &state.static_state.ldk_data_dir,
+            )?;
         }
 
-        if payload.push_msat > payload.capacity_sat * 1000 {
-            return Err(APIError::InvalidAmount(s!(
-                "Channel push amount cannot be higher than the capacity"
+        let hops = payload
+            .hops
+            .iter()
+            .map(|h| hex_str_to_compressed_pubkey(h).ok_or(APIError::InvalidPubkey))
+            .collect::<Result<Vec<_>, _>>()?;
+        let payee_pubkey = invoice.recover_payee_pub_key();
+        if hops.last() != Some(&payee_pubkey) {
+            return Err(APIError::InvalidRoute(s!(
+                "the last hop needs to be the invoice payee"
             )));
         }
 
-        if colored_info.is_some() && !payload.with_anchors {
-            return Err(APIError::AnchorsRequired);
-        }
-
-        let (peer_pubkey, mut peer_addr) =
-            parse_peer_info(payload.peer_pubkey_and_opt_addr.to_string())?;
-
-        let peer_data_path = state.static_state.ldk_data_dir.join(CHANNEL_PEER_DATA);
-        if peer_addr.is_none() {
-            if let Some(peer) = unlocked_state.peer_manager.peer_by_node_id(&peer_pubkey) {
-                if let Some(socket_address) = peer.socket_address {
-                    if let Ok(mut socket_addrs) = socket_address.to_socket_addrs() {
-                        // assuming there's only one IP address
-                        peer_addr = socket_addrs.next();
-                    }
-                }
-            }
-        }
-        if peer_addr.is_none() {
-            let peer_info = disk::read_channel_peer_data(&peer_data_path)?;
-            for (pubkey, addr) in peer_info.into_iter() {
-                if pubkey == peer_pubkey {
-                    peer_addr = Some(addr);
-                    break;
-                }
+        let mut payment_params = PaymentParameters::from_bolt11_invoice(&invoice);
+        payment_params.max_total_cltv_expiry_delta = state.static_state.max_total_cltv_expiry_delta;
+        let route_params =
+            RouteParameters::from_payment_params_and_value(payment_params, amt_msat, rgb_payment);
+        let mut route = build_route_from_hops(
+            &unlocked_state.channel_manager.get_our_node_id(),
+            &hops,
+            &route_params,
+            &unlocked_state.network_graph,
+            Arc::clone(&state.static_state.logger),
+            &unlocked_state.keys_manager.get_secure_random_bytes(),
+        )
+        .map_err(|e| APIError::InvalidRoute(e.err))?;
+        if rgb_payment.is_some() {
+            for hop in route.paths.iter_mut().flat_map(|p| p.hops.iter_mut()) {
+                hop.rgb_payment = rgb_payment;
             }
         }
-        if let Some(peer_addr) = peer_addr {
-            connect_peer_if_necessary(peer_pubkey, peer_addr, unlocked_state.peer_manager.clone())
-                .await?;
-            disk::persist_channel_peer(&peer_data_path, &peer_pubkey, &peer_addr)?;
-        } else {
-            return Err(APIError::InvalidPeerInfo(s!(
-                "cannot find the address for the provided pubkey"
-            )));
-        }
 
-        let mut channel_config = ChannelConfig::default();
-        if let Some(fee_base_msat) = payload.fee_base_msat {
-            channel_config.forwarding_fee_base_msat = fee_base_msat;
-        }
-        if let Some(fee_proportional_millionths) = payload.fee_proportional_millionths {
-            channel_config.forwarding_fee_proportional_millionths = fee_proportional_millionths;
-        }
-        let config = UserConfig {
-            channel_handshake_limits: ChannelHandshakeLimits {
-                // lnd's max to_self_delay is 2016, so we want to be compatible.
-                their_to_self_delay: 2016,
-                ..Default::default()
-            },
-            channel_handshake_config: ChannelHandshakeConfig {
-                announce_for_forwarding: payload.public,
-                our_htlc_minimum_msat: HTLC_MIN_MSAT,
-                minimum_depth: MIN_CHANNEL_CONFIRMATIONS as u32,
-                negotiate_anchors_zero_fee_htlc_tx: payload.with_anchors,
-                ..Default::default()
+        let payment_id = PaymentId((*invoice.payment_hash()).to_byte_array());
+        let payment_hash = PaymentHash(invoice.payment_hash().to_byte_array());
+        let payment_secret = *invoice.payment_secret();
+        let created_at = get_current_timestamp();
+        unlocked_state.add_outbound_payment(
+            payment_id,
+            PaymentInfo {
+                preimage: None,
+                secret: Some(payment_secret),
+                status: HTLCStatus::Pending,
+                amt_msat: invoice.amount_milli_satoshis(),
+                created_at,
+                updated_at: created_at,
+                payee_pubkey: invoice.get_payee_pub_key(),
+                offer_id: None,
+                parts: vec![],
+                expires_at: None,
+                settle_after_expiry: false,
             },
-            channel_config,
-            ..Default::default()
-        };
-
-        let consignment_endpoint = if let Some((contract_id, asset_amount)) = &colored_info {
-            let balance = unlocked_state.rgb_get_asset_balance(*contract_id)?;
-            let spendable_rgb_amount = balance.spendable;
-
-            if *asset_amount > spendable_rgb_amount {
-                return Err(APIError::InsufficientAssets);
-            }
-
-            Some(RgbTransport::from_str(&unlocked_state.proxy_endpoint).unwrap())
-        } else {
-            None
-        };
-
-        let schema = if let Some((contract_id, asset_amount)) = &colored_info {
-            let mut fake_p2wsh: [u8; 34] = [0; 34];
-            fake_p2wsh[1] = 32;
-            let script_buf = ScriptBuf::from_bytes(fake_p2wsh.to_vec());
-            let recipient_id = recipient_id_from_script_buf(script_buf, state.static_state.network);
-            let asset_id = contract_id.to_string();
-            let schema = unlocked_state
-                .rgb_get_asset_metadata(*contract_id)?
-                .asset_schema;
-            let assignment = match schema {
-                RgbLibAssetSchema::Nia | RgbLibAssetSchema::Cfa => {
-                    Assignment::Fungible(*asset_amount)
-                }
-                RgbLibAssetSchema::Uda => Assignment::NonFungible,
-                RgbLibAssetSchema::Ifa => todo!(),
-            };
-
-            let recipient_map = map! {
-                asset_id => vec![Recipient {
-                    recipient_id,
-                    witness_data: Some(RgbLibWitnessData {
-                        amount_sat: payload.capacity_sat,
-                        blinding: Some(STATIC_BLINDING + 1),
-                    }),
-                    assignment: assignment.into(),
-                    transport_endpoints: vec![unlocked_state.proxy_endpoint.clone()]
-            }]};
-
-            let unlocked_state_copy = unlocked_state.clone();
-            tokio::task::spawn_blocking(move || {
-                unlocked_state_copy.rgb_send_begin(
-                    recipient_map,
-                    true,
-                    FEE_RATE,
-                    MIN_CHANNEL_CONFIRMATIONS,
-                )
-            })
-            .await
-            .unwrap()?;
-            Some(schema)
-        } else {
-            None
-        };
-
-        *unlocked_state.rgb_send_lock.lock().unwrap() = true;
-        tracing::debug!("RGB send lock set to true");
-
-        let temporary_channel_id = unlocked_state
-            .channel_manager
-            .create_channel(
-                peer_pubkey,
-                payload.capacity_sat,
-                payload.push_msat,
-                0,
-                temporary_channel_id,
-                Some(config),
-                consignment_endpoint,
-            )
-            .map_err(|e| {
-                *unlocked_state.rgb_send_lock.lock().unwrap() = false;
-                tracing::debug!("RGB send lock set to false (open channel failure: {e:?})");
-                match e {
-                    LDKAPIError::APIMisuseError { err }
-                        if err.contains("fee for initial commitment transaction") =>
-                    {
-                        let mut commitment_tx_fee = 0;
-                        let re =
-                            Regex::new(r"fee for initial commitment transaction fee of (\d+).")
-                                .unwrap();
-                        if let Some(captures) = re.captures(&err) {
-                            if let Some(fee_str) = captures.get(1) {
-                                commitment_tx_fee = fee_str.as_str().parse().unwrap();
-                            }
-                        }
-                        APIError::InsufficientCapacity(commitment_tx_fee)
-                    }
-                    _ => APIError::FailedOpenChannel(format!("{e:?}")),
-                }
-            })?;
-        let temporary_channel_id = temporary_channel_id.0.as_hex().to_string();
-        tracing::info!("EVENT: initiated channel with peer {}", peer_pubkey);
-
-        if let Some((contract_id, asset_amount)) = &colored_info {
-            let rgb_info = RgbInfo {
-                contract_id: *contract_id,
-                schema: schema.unwrap(),
-                local_rgb_amount: *asset_amount,
-                remote_rgb_amount: 0,
-            };
-            write_rgb_channel_info(
-                &get_rgb_channel_info_path(
-                    &temporary_channel_id,
-                    &state.static_state.ldk_data_dir,
-                    true,
-                ),
-                &rgb_info,
-            );
-            write_rgb_channel_info(
-                &get_rgb_channel_info_path(
-                    &temporary_channel_id,
-                    &state.static_state.ldk_data_dir,
-                    false,
-                ),
-                &rgb_info,
+        )?;
+        if let Some((contract_id, rgb_amount)) = rgb_payment {
+            write_rgb_payment_info_file(
+                &PathBuf::from(&state.static_state.ldk_data_dir),
+                &payment_hash,
+                contract_id,
+                rgb_amount,
+                false,
+                false,
             );
         }
 
-        Ok(Json(OpenChannelResponse {
-            temporary_channel_id,
+        let status = match unlocked_state.channel_manager.send_payment_with_route(
+            route,
+            payment_hash,
+            RecipientOnionFields::secret_only(payment_secret),
+            payment_id,
+        ) {
+            Ok(()) => {
+                tracing::info!(
+                    "EVENT: initiated sending {amt_msat} msats to {payee_pubkey} over a given route"
+                );
+                HTLCStatus::Pending
+            }
+            Err(e) => {
+                tracing::error!("ERROR: failed to send payment: {:?}", e);
+                unlocked_state.update_outbound_payment_status(payment_id, HTLCStatus::Failed);
+                HTLCStatus::Failed
+            }
+        };
+
+        Ok(Json(SendPaymentResponse {
+            payment_id: hex_str(&payment_id.0),
+            payment_hash: Some(hex_str(&payment_hash.0)),
+            payment_secret: Some(hex_str(&payment_secret.0)),
+            status,
+            dry_run: None,
         }))
     })
     .await
 }
 
-pub(crate) async fn post_asset_media(
+pub(crate) async fn set_alias(
     State(state): State<Arc<AppState>>,
-    mut multipart: Multipart,
-) -> Result<Json<PostAssetMediaResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<SetAliasRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let mut node_announcement = unlocked_state.get_node_announcement().clone();
+    if let Some(alias) = payload.alias {
+        check_announce_alias(&alias).map_err(APIError::InvalidAnnounceAlias)?;
+        node_announcement.alias = Some(alias);
+    }
+    if let Some(color) = payload.color {
+        let color = parse_announce_color(&color).ok_or_else(|| {
+            APIError::InvalidAnnounceColor(s!("expected 3 hex-encoded bytes (e.g. 3399ff)"))
+        })?;
+        node_announcement.color = Some(color);
+    }
+    tracing::info!(
+        "Set node alias to {:?} and color to {:?}",
+        node_announcement.alias,
+        node_announcement.color.map(|c| hex_str(&c))
+    );
+    unlocked_state.set_node_announcement(node_announcement);
+
+    Ok(Json(EmptyResponse {}))
+}
+
+pub(crate) async fn set_hedging_policy(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<SetHedgingPolicyRequest>, APIError>,
+) -> Result<Json<HedgingPolicyEntry>, APIError> {
     no_cancel(async move {
         let guard = state.check_unlocked().await?;
         let unlocked_state = guard.as_ref().unwrap();
 
-        let digest = if let Some(field) = multipart
-            .next_field()
-            .await
-            .map_err(|_| APIError::MediaFileNotProvided)?
-        {
-            let file_bytes = field
-                .bytes()
-                .await
-                .map_err(|e| APIError::Unexpected(format!("Failed to read bytes: {e}")))?;
-            if file_bytes.is_empty() {
-                return Err(APIError::MediaFileEmpty);
+        let asset_id = parse_hedging_asset(&payload.asset_id)?;
+        let target_asset_id = parse_hedging_asset(&payload.target_asset_id)?;
+        if asset_id == target_asset_id {
+            return Err(APIError::InvalidHedgingPolicy(s!(
+                "asset_id and target_asset_id need to be different"
+            )));
+        }
+        if payload.rate_from == 0 || payload.rate_to == 0 {
+            return Err(APIError::InvalidHedgingPolicy(s!(
+                "rate_from and rate_to must be greater than 0"
+            )));
+        }
+        if payload.min_amount == 0 {
+            return Err(APIError::InvalidHedgingPolicy(s!(
+                "min_amount must be greater than 0"
+            )));
+        }
+        if payload.max_amount.is_some_and(|m| m < payload.min_amount) {
+            return Err(APIError::InvalidHedgingPolicy(s!(
+                "max_amount cannot be less than min_amount"
+            )));
+        }
+        if payload.max_daily_amount == Some(0) {
+            return Err(APIError::InvalidHedgingPolicy(s!(
+                "max_daily_amount must be greater than 0"
+            )));
+        }
+        match reqwest::Url::parse(&payload.maker_url) {
+            Ok(url) if ["http", "https"].contains(&url.scheme()) => {}
+            _ => {
+                return Err(APIError::InvalidHedgingPolicy(s!(
+                    "maker_url must be an HTTP(S) URL"
+                )))
             }
-            let file_hash: sha256::Hash = Hash::hash(&file_bytes[..]);
-            let digest = file_hash.to_string();
+        }
+        // a dry-run policy only logs the swaps it would take
+        if !payload.dry_run {
+            unlocked_state.check_totp(payload.totp_code.as_deref(), TotpOperation::Spend)?;
+        }
 
-            let file_path = unlocked_state.rgb_get_media_dir().join(&digest);
-            let mut write = true;
-            if file_path.exists() {
-                let mut buf_reader = BufReader::new(File::open(&file_path).await?);
-                let mut existing_file_bytes = Vec::new();
-                buf_reader.read_to_end(&mut existing_file_bytes).await?;
-                if file_bytes != existing_file_bytes {
-                    tokio::fs::remove_file(&file_path).await?;
-                } else {
-                    write = false;
-                }
-            }
-            if write {
-                let mut file = File::create(&file_path).await?;
-                file.write_all(&file_bytes).await?;
+        let key = asset_id.unwrap_or(s!(BTC_POLICY_KEY));
+        let policy = HedgingPolicy {
+            target_asset_id,
+            min_amount: payload.min_amount,
+            max_amount: payload.max_amount,
+            max_daily_amount: payload.max_daily_amount,
+            rate_from: payload.rate_from,
+            rate_to: payload.rate_to,
+            maker_url: payload.maker_url,
+            maker_token: payload.maker_token,
+            dry_run: payload.dry_run,
+            updated_at: get_current_timestamp(),
+        };
+        let entry = policy.to_entry(key.clone());
+        unlocked_state.set_hedging_policy(&key, Some(policy));
+        tracing::info!(
+            "Set the {key} hedging policy towards {}{}",
+            payload.target_asset_id,
+            if payload.dry_run { " (dry-run)" } else { "" }
+        );
+
+        Ok(Json(entry))
+    })
+    .await
+}
+
+pub(crate) async fn set_view_password(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<SetViewPasswordRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let storage_dir_path = &state.static_state.storage_dir_path;
+        let mnemonic = check_password_validity(&payload.password, storage_dir_path)?;
+
+        let view_mnemonic_path = get_view_mnemonic_path(storage_dir_path);
+        let Some(view_password) = payload.view_password else {
+            if view_mnemonic_path.exists() {
+                std::fs::remove_file(&view_mnemonic_path)?;
+                tracing::info!("Removed the view password");
             }
-            digest
-        } else {
-            return Err(APIError::MediaFileNotProvided);
+            return Ok(Json(EmptyResponse {}));
         };
+        check_password_strength(view_password.clone())?;
+        if view_password == payload.password {
+            return Err(APIError::InvalidPassword(s!(
+                "the view password must differ from the node one"
+            )));
+        }
 
-        Ok(Json(PostAssetMediaResponse { digest }))
+        encrypt_and_save_mnemonic(view_password, mnemonic.to_string(), &view_mnemonic_path)?;
+        tracing::info!("Set the view password");
+
+        Ok(Json(EmptyResponse {}))
     })
     .await
 }
 
-pub(crate) async fn refresh_transfers(
+pub(crate) async fn settle_invoice(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<RefreshRequest>, APIError>,
+    WithRejection(Json(payload), _): WithRejection<Json<SettleInvoiceRequest>, APIError>,
 ) -> Result<Json<EmptyResponse>, APIError> {
     no_cancel(async move {
         let guard = state.check_unlocked().await?;
         let unlocked_state = guard.as_ref().unwrap();
-        let unlocked_state_copy = unlocked_state.clone();
 
-        tokio::task::spawn_blocking(move || unlocked_state_copy.rgb_refresh(payload.skip_sync))
-            .await
-            .unwrap()?;
+        unlocked_state.settle_hodl_invoice(&payload.payment_hash, &payload.payment_preimage)?;
 
-        tracing::info!("Refresh complete");
         Ok(Json(EmptyResponse {}))
     })
     .await
 }
 
-pub(crate) async fn restore(
+pub(crate) async fn settle_invoices(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<RestoreRequest>, APIError>,
-) -> Result<Json<EmptyResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<SettleInvoicesRequest>, APIError>,
+) -> Result<Json<SettleInvoicesResponse>, APIError> {
     no_cancel(async move {
-        let _unlocked_state = state.check_locked().await?;
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap().clone();
 
-        let mnemonic_path = get_mnemonic_path(&state.static_state.storage_dir_path);
-        check_already_initialized(&mnemonic_path)?;
+        let items = payload
+            .invoices
+            .into_iter()
+            .map(|i| (i.payment_hash, Some(i.payment_preimage)))
+            .collect();
+        let results = process_invoices_batch(
+            unlocked_state,
+            items,
+            |u, payment_hash, payment_preimage| {
+                u.settle_hodl_invoice(payment_hash, payment_preimage.unwrap_or_default())
+            },
+        )
+        .await?;
 
-        restore_backup(
-            Path::new(&payload.backup_path),
-            &payload.password,
-            &state.static_state.storage_dir_path,
-        )?;
+        Ok(Json(SettleInvoicesResponse { results }))
+    })
+    .await
+}
 
-        let _mnemonic =
-            check_password_validity(&payload.password, &state.static_state.storage_dir_path)?;
+pub(crate) async fn settlements(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Query(payload), _): WithRejection<Query<SettlementsRequest>, APIError>,
+) -> Result<Json<SettlementsResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let period = match payload.period {
+        Some(period) => {
+            check_period(&period)?;
+            period
+        }
+        None => settlement_period(get_current_timestamp()),
+    };
+
+    let mut totals: Vec<SettlementTotal> = unlocked_state
+        .settlements(&period)
+        .into_iter()
+        .map(|t| SettlementTotal {
+            label: t.label,
+            asset_id: t.asset_id,
+            settled_invoices: t.settled_invoices,
+            amt_msat: t.amt_msat,
+            received_transfers: t.received_transfers,
+            asset_amount: t.asset_amount,
+        })
+        .collect();
+    totals.sort_by(|a, b| (&a.label, &a.asset_id).cmp(&(&b.label, &b.asset_id)));
+
+    Ok(Json(SettlementsResponse { period, totals }))
+}
+
+pub(crate) async fn shutdown(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let _unlocked_app_state = state.get_unlocked_app_state();
+        state.check_changing_state()?;
 
+        state.cancel_token.cancel();
         Ok(Json(EmptyResponse {}))
     })
     .await
 }
 
-pub(crate) async fn revoke_token(
+pub(crate) async fn sign_psbt(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<RevokeTokenRequest>, APIError>,
-) -> Result<Json<EmptyResponse>, APIError> {
-    let Some(root_pubkey) = state.root_public_key else {
-        return Err(APIError::AuthenticationDisabled);
-    };
+    WithRejection(Json(payload), _): WithRejection<Json<SignPsbtRequest>, APIError>,
+) -> Result<Json<SignPsbtResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
 
-    let token_to_revoke = Biscuit::from_base64(&payload.token, root_pubkey)
-        .map_err(|_| APIError::InvalidBiscuitToken)?;
-    state.revoke_token(&token_to_revoke)?;
+        let psbt =
+            Psbt::from_str(&payload.psbt).map_err(|e| APIError::InvalidPsbt(e.to_string()))?;
+        unlocked_state.check_totp(payload.totp_code.as_deref(), TotpOperation::Spend)?;
+        unlocked_state.check_locked_utxos(&payload.psbt)?;
 
-    Ok(Json(EmptyResponse {}))
+        // spending RGB allocations without a commitment moving them would burn them
+        let carries_commitment = psbt
+            .unsigned_tx
+            .output
+            .iter()
+            .any(|o| o.script_pubkey.is_op_return())
+            || psbt.outputs.iter().any(|o| !o.proprietary.is_empty());
+        if !carries_commitment {
+            let colored_outpoints: HashSet<String> = unlocked_state
+                .rgb_list_unspents(false)?
+                .into_iter()
+                .filter(|u| !u.rgb_allocations.is_empty())
+                .map(|u| u.utxo.outpoint.to_string())
+                .collect();
+            if let Some(outpoint) = psbt
+                .unsigned_tx
+                .input
+                .iter()
+                .map(|i| i.previous_output.to_string())
+                .find(|o| colored_outpoints.contains(o))
+            {
+                return Err(APIError::CannotSignPsbt(format!(
+                    "input {outpoint} holds RGB allocations but the PSBT carries no RGB commitment"
+                )));
+            }
+        }
+
+        let psbt = unlocked_state.sign_built_psbt(psbt)?;
+
+        Ok(Json(SignPsbtResponse { psbt }))
+    })
+    .await
 }
 
-pub(crate) async fn rgb_invoice(
+pub(crate) async fn sign_message(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<RgbInvoiceRequest>, APIError>,
-) -> Result<Json<RgbInvoiceResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<SignMessageRequest>, APIError>,
+) -> Result<Json<SignMessageResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    let message = payload.message.trim();
+    let signed_message = lightning::util::message_signing::sign(
+        &message.as_bytes()[message.len()..],
+        &unlocked_state.keys_manager.get_node_secret_key(),
+    );
+
+    Ok(Json(SignMessageResponse { signed_message }))
+}
+
+pub(crate) async fn spec() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "application/yaml")], OPENAPI_SPEC)
+}
+
+pub(crate) async fn swap_in(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<SwapInRequest>, APIError>,
+) -> Result<Json<SwapInResponse>, APIError> {
     no_cancel(async move {
         let guard = state.check_unlocked().await?;
+        state.check_draining()?;
         let unlocked_state = guard.as_ref().unwrap();
 
-        if *unlocked_state.rgb_send_lock.lock().unwrap() {
-            return Err(APIError::OpenChannelInProgress);
+        let provider = state.swap_provider()?;
+        if payload.amt_sat == 0 {
+            return Err(APIError::InvalidAmount(s!(
+                "amt_sat must be greater than 0"
+            )));
         }
+        unlocked_state.check_totp(payload.totp_code.as_deref(), TotpOperation::Spend)?;
+        let network = Network::from(state.static_state.network);
 
-        let assignment = payload.assignment.unwrap_or(Assignment::Any).into();
-
-        let receive_data = if payload.witness {
-            unlocked_state.rgb_witness_receive(
-                payload.asset_id,
-                assignment,
-                payload.duration_seconds,
-                vec![unlocked_state.proxy_endpoint.clone()],
-                payload.min_confirmations,
-            )?
-        } else {
-            unlocked_state.rgb_blind_receive(
-                payload.asset_id,
-                assignment,
-                payload.duration_seconds,
-                vec![unlocked_state.proxy_endpoint.clone()],
-                payload.min_confirmations,
-            )?
+        // the provider pays this invoice once our lockup output can be claimed with its preimage
+        let invoice_params = Bolt11InvoiceParameters {
+            amount_msats: Some(payload.amt_sat * 1000),
+            invoice_expiry_delta_secs: Some(SUBMARINE_SWAP_INVOICE_EXPIRY_SEC),
+            min_final_cltv_expiry_delta: Some(state.static_state.min_final_cltv_expiry_delta),
+            ..Default::default()
+        };
+        let invoice = unlocked_state
+            .channel_manager
+            .create_bolt11_invoice(invoice_params)
+            .map_err(|e| APIError::FailedInvoiceCreation(e.to_string()))?;
+        let payment_hash = PaymentHash(invoice.payment_hash().to_byte_array());
+
+        let secret_key =
+            SecretKey::from_slice(&unlocked_state.keys_manager.get_secure_random_bytes()).unwrap();
+        let refund_pubkey = secret_key.public_key(&Secp256k1::signing_only());
+        let provider_swap = provider
+            .create_submarine_swap(&invoice.to_string(), &refund_pubkey)
+            .await?;
+        let fee_sat = provider_swap
+            .expected_amount
+            .saturating_sub(payload.amt_sat);
+        if let Some(max_fee_sat) = payload.max_fee_sat {
+            if fee_sat > max_fee_sat {
+                return Err(APIError::MaxFeeExceeded(format!(
+                    "the swap fee of {fee_sat} sats exceeds max_fee_sat"
+                )));
+            }
+        }
+        let provider_pubkey = PublicKey::from_str(&provider_swap.claim_public_key)
+            .map_err(|e| APIError::FailedSwapProviderRequest(e.to_string()))?;
+        let created_at = get_current_timestamp();
+        let mut swap = SubmarineSwapInfo {
+            kind: SubmarineSwapKind::In,
+            status: SubmarineSwapStatus::Pending,
+            amt_sat: payload.amt_sat,
+            onchain_amt_sat: provider_swap.expected_amount,
+            payment_hash,
+            preimage: None,
+            secret_key,
+            provider_pubkey,
+            timeout_block_height: provider_swap.timeout_block_height,
+            lockup_address: provider_swap.address,
+            lockup_txid: None,
+            lockup_vout: None,
+            sweep_address: unlocked_state.rgb_get_address()?,
+            sweep_txid: None,
+            created_at,
+            updated_at: created_at,
+            error: None,
         };
+        check_swap_output(&provider_swap.swap_tree, &swap, network)?;
 
-        Ok(Json(RgbInvoiceResponse {
-            recipient_id: receive_data.recipient_id,
-            invoice: receive_data.invoice,
-            expiration_timestamp: receive_data.expiration_timestamp,
-            batch_transfer_idx: receive_data.batch_transfer_idx,
+        unlocked_state.add_inbound_payment(
+            payment_hash,
+            PaymentInfo {
+                preimage: None,
+                secret: Some(*invoice.payment_secret()),
+                status: HTLCStatus::Pending,
+                amt_msat: Some(payload.amt_sat * 1000),
+                created_at,
+                updated_at: created_at,
+                payee_pubkey: unlocked_state.channel_manager.get_our_node_id(),
+                offer_id: None,
+                parts: vec![],
+                expires_at: invoice.expires_at().map(|e| e.as_secs()),
+                settle_after_expiry: false,
+            },
+        );
+        let lockup_txid = unlocked_state.rgb_send_btc(
+            swap.lockup_address.clone(),
+            swap.onchain_amt_sat,
+            payload.fee_rate,
+            payload.skip_sync,
+        )?;
+        let lockup_script = Address::from_str(&swap.lockup_address)
+            .unwrap()
+            .assume_checked()
+            .script_pubkey();
+        swap.lockup_vout = unlocked_state
+            .bitcoind_client
+            .get_raw_transaction(&Txid::from_str(&lockup_txid).unwrap(), None)
+            .await
+            .and_then(|tx| {
+                tx.output
+                    .iter()
+                    .position(|o| o.script_pubkey == lockup_script)
+            })
+            .map(|vout| vout as u32);
+        swap.lockup_txid = Some(lockup_txid);
+        unlocked_state.add_submarine_swap(provider_swap.id.clone(), swap.clone());
+        tracing::info!(
+            "Started submarine swap {} locking {} sats",
+            provider_swap.id,
+            swap.onchain_amt_sat
+        );
+
+        Ok(Json(SwapInResponse {
+            swap: swap.to_submarine_swap(provider_swap.id),
         }))
     })
     .await
 }
 
-pub(crate) async fn send_asset(
+pub(crate) async fn swap_out(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<SendAssetRequest>, APIError>,
-) -> Result<Json<SendAssetResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<SwapOutRequest>, APIError>,
+) -> Result<Json<SwapOutResponse>, APIError> {
     no_cancel(async move {
         let guard = state.check_unlocked().await?;
+        state.check_draining()?;
         let unlocked_state = guard.as_ref().unwrap();
 
-        if *unlocked_state.rgb_send_lock.lock().unwrap() {
-            return Err(APIError::OpenChannelInProgress);
+        let provider = state.swap_provider()?;
+        if payload.amt_sat == 0 {
+            return Err(APIError::InvalidAmount(s!(
+                "amt_sat must be greater than 0"
+            )));
         }
-
-        RecipientInfo::new(payload.recipient_id.clone())?;
-        let recipient_map = map! {
-            payload.asset_id => vec![Recipient {
-                recipient_id: payload.recipient_id,
-                witness_data: payload.witness_data.map(|w| w.into()),
-                assignment: payload.assignment.into(),
-                transport_endpoints: payload.transport_endpoints,
-            }]
+        if let Some(address) = &payload.address {
+            check_address_network(address, state.static_state.network)?;
+        }
+        let amt_msat = payload.amt_sat * 1000;
+        unlocked_state.check_totp(
+            payload.totp_code.as_deref(),
+            TotpOperation::Payment(amt_msat),
+        )?;
+        let network = Network::from(state.static_state.network);
+
+        let preimage = PaymentPreimage(unlocked_state.keys_manager.get_secure_random_bytes());
+        let payment_hash = PaymentHash(Sha256::hash(&preimage.0).to_byte_array());
+        let secret_key =
+            SecretKey::from_slice(&unlocked_state.keys_manager.get_secure_random_bytes()).unwrap();
+        let claim_pubkey = secret_key.public_key(&Secp256k1::signing_only());
+        let provider_swap = provider
+            .create_reverse_swap(&payment_hash, &claim_pubkey, payload.amt_sat)
+            .await?;
+
+        let invoice = Bolt11Invoice::from_str(&provider_swap.invoice)
+            .map_err(|e| APIError::FailedSwapProviderRequest(format!("invalid invoice: {e}")))?;
+        check_invoice_network(&invoice, state.static_state.network)?;
+        if invoice.payment_hash().to_byte_array() != payment_hash.0
+            || invoice.amount_milli_satoshis() != Some(amt_msat)
+        {
+            return Err(APIError::FailedSwapProviderRequest(s!(
+                "the invoice doesn't match the swap"
+            )));
+        }
+        let fee_sat = payload.amt_sat.saturating_sub(provider_swap.onchain_amount);
+        if let Some(max_fee_sat) = payload.max_fee_sat {
+            if fee_sat > max_fee_sat {
+                return Err(APIError::MaxFeeExceeded(format!(
+                    "the swap fee of {fee_sat} sats exceeds max_fee_sat"
+                )));
+            }
+        }
+        let provider_pubkey = PublicKey::from_str(&provider_swap.refund_public_key)
+            .map_err(|e| APIError::FailedSwapProviderRequest(e.to_string()))?;
+        let sweep_address = match payload.address {
+            Some(address) => address,
+            None => unlocked_state.rgb_get_address()?,
+        };
+        let created_at = get_current_timestamp();
+        let swap = SubmarineSwapInfo {
+            kind: SubmarineSwapKind::Out,
+            status: SubmarineSwapStatus::Pending,
+            amt_sat: payload.amt_sat,
+            onchain_amt_sat: provider_swap.onchain_amount,
+            payment_hash,
+            preimage: Some(preimage),
+            secret_key,
+            provider_pubkey,
+            timeout_block_height: provider_swap.timeout_block_height,
+            lockup_address: provider_swap.lockup_address,
+            lockup_txid: None,
+            lockup_vout: None,
+            sweep_address,
+            sweep_txid: None,
+            created_at,
+            updated_at: created_at,
+            error: None,
         };
+        check_swap_output(&provider_swap.swap_tree, &swap, network)?;
+        // the swap is saved before paying so the claim survives a restart
+        unlocked_state.add_submarine_swap(provider_swap.id.clone(), swap.clone());
 
-        let unlocked_state_copy = unlocked_state.clone();
-        let send_result = tokio::task::spawn_blocking(move || {
-            unlocked_state_copy.rgb_send(
-                recipient_map,
-                payload.donation,
-                payload.fee_rate,
-                payload.min_confirmations,
-                payload.skip_sync,
-            )
-        })
-        .await
-        .unwrap()?;
+        let payment_id = PaymentId(payment_hash.0);
+        unlocked_state.add_outbound_payment(
+            payment_id,
+            PaymentInfo {
+                preimage: None,
+                secret: Some(*invoice.payment_secret()),
+                status: HTLCStatus::Pending,
+                amt_msat: Some(amt_msat),
+                created_at,
+                updated_at: created_at,
+                payee_pubkey: invoice.get_payee_pub_key(),
+                offer_id: None,
+                parts: vec![],
+                expires_at: None,
+                settle_after_expiry: false,
+            },
+        )?;
+        let limits = PaymentLimits::new(state.static_state.max_total_cltv_expiry_delta);
+        match unlocked_state.channel_manager.pay_for_bolt11_invoice(
+            &invoice,
+            payment_id,
+            None,
+            limits.route_params_config(amt_msat),
+            limits.retry_strategy(),
+        ) {
+            Ok(()) => tracing::info!(
+                "Started submarine swap {} paying {amt_msat} msats",
+                provider_swap.id
+            ),
+            Err(e) => {
+                tracing::error!("ERROR: failed to send submarine swap payment: {:?}", e);
+                unlocked_state.update_outbound_payment_status(payment_id, HTLCStatus::Failed);
+            }
+        }
 
-        Ok(Json(SendAssetResponse {
-            txid: send_result.txid,
+        Ok(Json(SwapOutResponse {
+            swap: swap.to_submarine_swap(provider_swap.id),
         }))
     })
     .await
 }
 
-pub(crate) async fn send_btc(
+pub(crate) async fn sweep_config(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<SendBtcRequest>, APIError>,
-) -> Result<Json<SendBtcResponse>, APIError> {
+) -> Result<Json<SweepConfigResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    Ok(Json(unlocked_state.get_sweep_config().clone().into()))
+}
+
+pub(crate) async fn sync(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<EmptyResponse>, APIError> {
     no_cancel(async move {
         let guard = state.check_unlocked().await?;
         let unlocked_state = guard.as_ref().unwrap();
 
-        let txid = unlocked_state.rgb_send_btc(
-            payload.address,
-            payload.amount,
-            payload.fee_rate,
-            payload.skip_sync,
-        )?;
+        unlocked_state.rgb_sync()?;
 
-        Ok(Json(SendBtcResponse { txid }))
+        Ok(Json(EmptyResponse {}))
     })
     .await
 }
 
-pub(crate) async fn send_onion_message(
+pub(crate) async fn sync_status(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<SendOnionMessageRequest>, APIError>,
+) -> Result<Json<SyncStatusResponse>, APIError> {
+    let progress = state.get_sync_progress().clone();
+    let mut response = SyncStatusResponse {
+        stage: progress.stage,
+        error: progress.error,
+        started_at: progress.started_at,
+        updated_at: progress.updated_at,
+        height: None,
+        chain_tip_lag: None,
+        graph_num_nodes: None,
+        graph_num_channels: None,
+        graph_num_channels_with_updates: None,
+    };
+    if progress.stage != SyncStage::Ready {
+        return Ok(Json(response));
+    }
+    let Some(unlocked_state) = state.get_unlocked_app_state().await.clone() else {
+        return Ok(Json(response));
+    };
+
+    let best_block = unlocked_state.channel_manager.current_best_block();
+    let chain_height = unlocked_state
+        .bitcoind_client
+        .get_blockchain_info()
+        .await
+        .latest_height;
+    response.height = Some(best_block.height);
+    response.chain_tip_lag = Some((chain_height as u32).saturating_sub(best_block.height));
+
+    let graph = unlocked_state.network_graph.read_only();
+    let channels = graph.channels();
+    response.graph_num_nodes = Some(graph.nodes().len());
+    response.graph_num_channels = Some(channels.len());
+    response.graph_num_channels_with_updates = Some(
+        channels
+            .unordered_iter()
+            .filter(|(_, c)| c.one_to_two.is_some() || c.two_to_one.is_some())
+            .count(),
+    );
+
+    Ok(Json(response))
+}
+
+pub(crate) async fn taker(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<TakerRequest>, APIError>,
 ) -> Result<Json<EmptyResponse>, APIError> {
-    no_cancel(async move {
+    {
         let guard = state.check_unlocked().await?;
         let unlocked_state = guard.as_ref().unwrap();
+        unlocked_state.check_totp(payload.totp_code.as_deref(), TotpOperation::Spend)?;
+    }
+    taker_without_totp(state, payload).await
+}
 
-        if payload.node_ids.is_empty() {
-            return Err(APIError::InvalidNodeIds(s!(
-                "sendonionmessage requires at least one node id for the path"
-            )));
-        }
+/// Accept a swap as taker without requiring the TOTP code, for the swaps started by the node itself
+pub(crate) async fn taker_without_totp(
+    state: Arc<AppState>,
+    payload: TakerRequest,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+        let swapstring = SwapString::from_str(&payload.swapstring)
+            .map_err(|e| APIError::InvalidSwapString(payload.swapstring.clone(), e.to_string()))?;
 
-        let mut intermediate_nodes = Vec::new();
-        for pk_str in payload.node_ids {
-            let node_pubkey_vec = match hex_str_to_vec(&pk_str) {
-                Some(peer_pubkey_vec) => peer_pubkey_vec,
-                None => {
-                    return Err(APIError::InvalidNodeIds(format!(
-                        "Couldn't parse peer_pubkey '{pk_str}'"
-                    )))
-                }
-            };
-            let node_pubkey = match PublicKey::from_slice(&node_pubkey_vec) {
-                Ok(peer_pubkey) => peer_pubkey,
-                Err(_) => {
-                    return Err(APIError::InvalidNodeIds(format!(
-                        "Couldn't parse peer_pubkey '{pk_str}'"
-                    )))
-                }
-            };
-            intermediate_nodes.push(node_pubkey);
+        if get_current_timestamp() > swapstring.swap_info.expiry {
+            return Err(APIError::ExpiredSwapOffer);
         }
 
-        if payload.tlv_type < 64 {
-            return Err(APIError::InvalidTlvType(s!(
-                "need an integral message type above 64"
-            )));
+        // We are selling assets, check if we have enough
+        if let Some(from_asset) = swapstring.swap_info.from_asset {
+            let max_balance = get_max_local_rgb_amount(
+                from_asset,
+                &state.static_state.ldk_data_dir,
+                unlocked_state.channel_manager.list_channels().iter(),
+            );
+            if swapstring.swap_info.qty_from > max_balance {
+                return Err(APIError::InsufficientAssets);
+            }
         }
 
-        let data = hex_str_to_vec(&payload.data)
-            .ok_or(APIError::InvalidOnionData(s!("need a hex data string")))?;
-
-        let destination = Destination::Node(intermediate_nodes.pop().unwrap());
-        let message_send_instructions = MessageSendInstructions::WithoutReplyPath { destination };
-
-        unlocked_state
-            .onion_messenger
-            .send_onion_message(
-                UserOnionMessageContents {
-                    tlv_type: payload.tlv_type,
-                    data,
-                },
-                message_send_instructions,
-            )
-            .map_err(|e| APIError::FailedSendingOnionMessage(format!("{e:?}")))?;
-
-        tracing::info!("SUCCESS: forwarded onion message to first hop");
+        let swap_data = SwapData::create_from_swap_info(&swapstring.swap_info);
+        unlocked_state.add_taker_swap(swapstring.payment_hash, swap_data);
 
         Ok(Json(EmptyResponse {}))
     })
     .await
 }
 
-pub(crate) async fn send_payment(
+pub(crate) async fn throttle_gossip(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<SendPaymentRequest>, APIError>,
-) -> Result<Json<SendPaymentResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<ThrottleGossipRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
     no_cancel(async move {
         let guard = state.check_unlocked().await?;
         let unlocked_state = guard.as_ref().unwrap();
 
-        let mut status = HTLCStatus::Pending;
-        let created_at = get_current_timestamp();
+        let peer_pubkey =
+            PublicKey::from_str(&payload.peer_pubkey).map_err(|_| APIError::InvalidPubkey)?;
 
-        let (payment_id, payment_hash, payment_secret) = if let Ok(offer) = Offer::from_str(&payload.invoice) {
-            let random_bytes = unlocked_state.keys_manager.get_secure_random_bytes();
-            let payment_id = PaymentId(random_bytes);
+        unlocked_state.set_gossip_limit(peer_pubkey, payload.limit_bytes_per_min);
+        tracing::info!(
+            "Set gossip limit of peer {peer_pubkey} to {:?} bytes per minute",
+            payload.limit_bytes_per_min
+        );
 
-            let amt_msat = match (offer.amount(), payload.amt_msat) {
-                (Some(offer::Amount::Bitcoin { amount_msats }), _) => amount_msats,
-                (_, Some(amt)) => amt,
-                (amt, _) => {
-                    return Err(APIError::InvalidAmount(format!(
-                        "cannot process non-Bitcoin-denominated offer value {amt:?}"
-                    )));
-                },
-            };
-            if payload.amt_msat.is_some() && payload.amt_msat != Some(amt_msat) {
-                return Err(APIError::InvalidAmount(format!(
-                    "amount didn't match offer of {amt_msat}msat"
-                )));
+        Ok(Json(EmptyResponse {}))
+    })
+    .await
+}
+
+/// Start LDK and make the node unlocked, reporting the outcome in the sync progress
+async fn complete_unlock(
+    state: Arc<AppState>,
+    mnemonic: Mnemonic,
+    payload: UnlockRequest,
+) -> Result<(), APIError> {
+    tracing::debug!("Starting LDK...");
+    let (new_ldk_background_services, new_unlocked_app_state) =
+        match start_ldk(state.clone(), mnemonic, payload).await {
+            Ok((nlbs, nuap)) => (nlbs, nuap),
+            Err(e) => {
+                tracing::error!("Unlock failed: {e}");
+                state.fail_sync_progress(&e);
+                state.update_changing_state(false);
+                return Err(e);
             }
+        };
+    tracing::debug!("LDK started");
 
-            // TODO: add and check RGB amount after enabling RGB support for offers
+    state
+        .update_unlocked_app_state(Some(new_unlocked_app_state))
+        .await;
 
-            let secret = None;
+    state.update_ldk_background_services(Some(new_ldk_background_services));
 
-            unlocked_state.add_outbound_payment(
-                payment_id,
-                PaymentInfo {
-                    preimage: None,
-                    secret,
-                    status,
-                    amt_msat: Some(amt_msat),
-                    created_at,
-                    updated_at: created_at,
-                    payee_pubkey: offer.issuer_signing_pubkey().ok_or(APIError::InvalidInvoice(s!("missing signing pubkey")))?,
-                },
-            )?;
+    state.update_sync_stage(SyncStage::Ready);
 
-            let params = OptionalOfferPaymentParams {
-                retry_strategy: Retry::Timeout(Duration::from_secs(10)),
-                ..Default::default()
-            };
-            let pay = unlocked_state.channel_manager
-                .pay_for_offer(&offer, Some(amt_msat), payment_id, params);
-            if pay.is_err() {
-                tracing::error!("ERROR: failed to pay: {:?}", pay);
-                unlocked_state.update_outbound_payment_status(payment_id, HTLCStatus::Failed);
-                status = HTLCStatus::Failed;
-                unlocked_state.update_outbound_payment_status(payment_id, status);
-            }
-            (payment_id, None, secret)
-        } else {
-            let invoice = match Bolt11Invoice::from_str(&payload.invoice) {
-                Err(e) => return Err(APIError::InvalidInvoice(e.to_string())),
-                Ok(v) => v,
-            };
+    state.update_changing_state(false);
 
-            let payment_id = PaymentId((*invoice.payment_hash()).to_byte_array());
-            let payment_secret = Some(*invoice.payment_secret());
-            let zero_amt_invoice =
-                invoice.amount_milli_satoshis().is_none() || invoice.amount_milli_satoshis() == Some(0);
+    tracing::info!("Unlock completed");
+    Ok(())
+}
 
-            let amt_msat = if zero_amt_invoice {
-                if let Some(amt_msat) = payload.amt_msat {
-                    amt_msat
-                } else {
-                    return Err(APIError::InvalidAmount(s!(
-                        "need an amount for the given 0-value invoice"
-                    )));
-                }
-            } else {
-                if payload.amt_msat.is_some() && invoice.amount_milli_satoshis() != payload.amt_msat
-                {
-                    return Err(APIError::InvalidAmount(format!(
-                        "amount didn't match invoice value of {}msat", invoice.amount_milli_satoshis().unwrap_or(0)
-                    )));
-                }
-                invoice.amount_milli_satoshis().unwrap_or(0)
-            };
+pub(crate) async fn tor_status(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<TorStatusResponse>, APIError> {
+    let tor_proxy = state.static_state.tor_proxy.as_ref();
+    let manager = &state.tor_connection_manager;
+    let inbound = manager.inbound_stats();
+    Ok(Json(TorStatusResponse {
+        socks_proxy: tor_proxy.map(|p| p.addr.clone()),
+        socks_authentication: tor_proxy.is_some_and(|p| p.credentials.is_some()),
+        no_clearnet_dns: tor_proxy.is_some_and(|p| p.no_clearnet_dns),
+        isolate_circuits: tor_proxy.is_some_and(|p| p.isolate_circuits),
+        onion_service: OnionServiceStatus {
+            client_authorization: !manager.config.authorized_clients.is_empty(),
+            authorized_clients: manager.config.authorized_clients.len() as u16,
+            max_inbound_connections: manager.config.max_inbound_connections,
+            max_inbound_per_minute: manager.config.max_inbound_per_minute,
+            active_inbound_connections: inbound.active,
+            accepted_inbound_connections: inbound.accepted,
+            rejected_max_connections: inbound.rejected_max_connections,
+            rejected_throttled: inbound.rejected_throttled,
+        },
+    }))
+}
 
-            let rgb_payment = match (invoice.rgb_contract_id(), invoice.rgb_amount()) {
-                (Some(rgb_contract_id), Some(rgb_amount)) => {
-                    if amt_msat < INVOICE_MIN_MSAT {
-                        return Err(APIError::InvalidAmount(format!(
-                            "msat amount in invoice sending an RGB asset cannot be less than {INVOICE_MIN_MSAT}"
-                        )));
-                    }
-                    Some((rgb_contract_id, rgb_amount))
-                },
-                (None, None) => None,
-                (Some(_), None) => {
-                    return Err(APIError::InvalidInvoice(s!(
-                        "invoice has an RGB contract ID but not an RGB amount"
-                    )))
-                }
-                (None, Some(_)) => {
-                    return Err(APIError::InvalidInvoice(s!(
-                        "invoice has an RGB amount but not an RGB contract ID"
-                    )))
-                }
-            };
+pub(crate) async fn unlock(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<UnlockRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    tracing::info!("Unlock started");
+    unlock_with(state, payload, check_password_validity).await
+}
 
-            let secret = payment_secret;
-            unlocked_state.add_outbound_payment(
-                payment_id,
-                PaymentInfo {
-                    preimage: None,
-                    secret,
-                    status,
-                    amt_msat: invoice.amount_milli_satoshis(),
-                    created_at,
-                    updated_at: created_at,
-                    payee_pubkey: invoice.get_payee_pub_key(),
-                },
-            )?;
-            let payment_hash = PaymentHash(invoice.payment_hash().to_byte_array());
-            if let Some((contract_id, rgb_amount)) = rgb_payment {
-                write_rgb_payment_info_file(
-                    &PathBuf::from(&state.static_state.ldk_data_dir),
-                    &payment_hash,
-                    contract_id,
-                    rgb_amount,
-                    false,
-                    false,
-                );
-            }
+/// Unlock a watch-only node with the view password, the node password being never accepted
+pub(crate) async fn unlock_view(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<UnlockRequest>, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    tracing::info!("Watch-only unlock started");
+    unlock_with(state, payload, check_view_password_validity).await
+}
 
-            match unlocked_state.channel_manager.pay_for_bolt11_invoice(
-                &invoice,
-                payment_id,
-                Some(amt_msat),
-                RouteParametersConfig::default(),
-                Retry::Timeout(Duration::from_secs(10)),
-            ) {
-                Ok(_) => {
-                    let payee_pubkey = invoice.recover_payee_pub_key();
-                    let amt_msat = invoice.amount_milli_satoshis().unwrap();
-                    tracing::info!(
-                        "EVENT: initiated sending {} msats to {}",
-                        amt_msat,
-                        payee_pubkey
-                    );
-                },
-                Err(e) => {
-                    tracing::error!("ERROR: failed to send payment: {:?}", e);
-                    status = HTLCStatus::Failed;
-                    unlocked_state.update_outbound_payment_status(payment_id, status);
-                },
-            };
+async fn unlock_with(
+    state: Arc<AppState>,
+    payload: UnlockRequest,
+    check_password: fn(&str, &Path) -> Result<Mnemonic, APIError>,
+) -> Result<Json<EmptyResponse>, APIError> {
+    no_cancel(async move {
+        match state.check_locked().await {
+            Ok(unlocked_state) => {
+                state.update_changing_state(true);
+                drop(unlocked_state);
+            }
+            Err(e) => {
+                return Err(match e {
+                    APIError::UnlockedNode => APIError::AlreadyUnlocked,
+                    _ => e,
+                });
+            }
+        }
 
-            (payment_id, Some(payment_hash), secret)
+        let mnemonic = match check_password(&payload.password, &state.static_state.storage_dir_path)
+        {
+            Ok(mnemonic) => mnemonic,
+            Err(e) => {
+                state.update_changing_state(false);
+                return Err(e);
+            }
         };
 
-        Ok(Json(SendPaymentResponse {
-            payment_id: hex_str(&payment_id.0),
-            payment_hash: payment_hash.map(|h| hex_str(&h.0)),
-            payment_secret: payment_secret.map(|s| hex_str(&s.0)),
-            status,
-        }))
+        state.start_sync_progress();
+        if payload.background == Some(true) {
+            let state = state.clone();
+            tokio::spawn(async move {
+                let _ = complete_unlock(state, mnemonic, payload).await;
+            });
+            tracing::info!("Unlock continuing in the background");
+            return Ok(Json(EmptyResponse {}));
+        }
+        complete_unlock(state, mnemonic, payload).await?;
+
+        Ok(Json(EmptyResponse {}))
     })
     .await
 }
 
-pub(crate) async fn shutdown(
+pub(crate) async fn unlock_utxo(
     State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<UnlockUtxoRequest>, APIError>,
 ) -> Result<Json<EmptyResponse>, APIError> {
     no_cancel(async move {
-        let _unlocked_app_state = state.get_unlocked_app_state();
-        state.check_changing_state()?;
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        if !unlocked_state.unlock_utxo(&payload.outpoint) {
+            return Err(APIError::UtxoNotLocked);
+        }
 
-        state.cancel_token.cancel();
         Ok(Json(EmptyResponse {}))
     })
     .await
 }
 
-pub(crate) async fn sign_message(
+pub(crate) async fn transfer_detail(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<SignMessageRequest>, APIError>,
-) -> Result<Json<SignMessageResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<TransferDetailRequest>, APIError>,
+) -> Result<Json<TransferDetailResponse>, APIError> {
     let guard = state.check_unlocked().await?;
     let unlocked_state = guard.as_ref().unwrap();
 
-    let message = payload.message.trim();
-    let signed_message = lightning::util::message_signing::sign(
-        &message.as_bytes()[message.len()..],
-        &unlocked_state.keys_manager.get_node_secret_key(),
-    );
+    let Some((asset_id, transfer)) = unlocked_state.rgb_get_transfer(payload.idx)? else {
+        return Err(APIError::TransferNotFound(payload.idx));
+    };
 
-    Ok(Json(SignMessageResponse { signed_message }))
+    Ok(Json(TransferDetailResponse {
+        asset_id,
+        transfer: transfer.into(),
+    }))
 }
 
-pub(crate) async fn sync(
+pub(crate) async fn update_channel_acceptor(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<EmptyResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<ChannelAcceptorRequest>, APIError>,
+) -> Result<Json<ChannelAcceptorResponse>, APIError> {
     no_cancel(async move {
         let guard = state.check_unlocked().await?;
         let unlocked_state = guard.as_ref().unwrap();
 
-        unlocked_state.rgb_sync()?;
+        if let (Some(min), Some(max)) = (payload.min_channel_size_sat, payload.max_channel_size_sat)
+        {
+            if min > max {
+                return Err(APIError::InvalidChannelAcceptor(s!(
+                    "min_channel_size_sat cannot exceed max_channel_size_sat"
+                )));
+            }
+        }
+        if payload.max_channels_per_peer == Some(0) {
+            return Err(APIError::InvalidChannelAcceptor(s!(
+                "max_channels_per_peer must be greater than 0"
+            )));
+        }
+        for asset_id in payload.allowed_assets.iter().chain(&payload.denied_assets) {
+            ContractId::from_str(asset_id)
+                .map_err(|_| APIError::InvalidAssetID(asset_id.clone()))?;
+        }
+        if payload
+            .allowed_assets
+            .iter()
+            .any(|a| payload.denied_assets.contains(a))
+        {
+            return Err(APIError::InvalidChannelAcceptor(s!(
+                "an asset cannot be both allowed and denied"
+            )));
+        }
 
-        Ok(Json(EmptyResponse {}))
+        let policy = ChannelAcceptorPolicy {
+            min_channel_size_sat: payload.min_channel_size_sat,
+            max_channel_size_sat: payload.max_channel_size_sat,
+            require_anchors: payload.require_anchors,
+            max_channels_per_peer: payload.max_channels_per_peer,
+            reject_rgb_channels: payload.reject_rgb_channels,
+            allowed_assets: payload.allowed_assets,
+            denied_assets: payload.denied_assets,
+            manual_approval: payload.manual_approval,
+        };
+        unlocked_state.set_channel_acceptor_policy(policy.clone());
+
+        Ok(Json(policy.into()))
     })
     .await
 }
 
-pub(crate) async fn taker(
+pub(crate) async fn update_channel_depths(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<TakerRequest>, APIError>,
-) -> Result<Json<EmptyResponse>, APIError> {
+    WithRejection(Json(payload), _): WithRejection<Json<ChannelDepthsRequest>, APIError>,
+) -> Result<Json<ChannelDepthsResponse>, APIError> {
     no_cancel(async move {
         let guard = state.check_unlocked().await?;
         let unlocked_state = guard.as_ref().unwrap();
-        let swapstring = SwapString::from_str(&payload.swapstring)
-            .map_err(|e| APIError::InvalidSwapString(payload.swapstring.clone(), e.to_string()))?;
 
-        if get_current_timestamp() > swapstring.swap_info.expiry {
-            return Err(APIError::ExpiredSwapOffer);
+        let mut overrides = ChannelDepthOverrides::default();
+        for peer in payload.peers {
+            let peer_pubkey = PublicKey::from_str(&peer.peer_pubkey).map_err(|_| {
+                APIError::InvalidChannelDepths(format!("invalid pubkey {}", peer.peer_pubkey))
+            })?;
+            if overrides.peers.iter().any(|p| p.peer_pubkey == peer_pubkey) {
+                return Err(APIError::InvalidChannelDepths(format!(
+                    "duplicate pubkey {peer_pubkey}"
+                )));
+            }
+            if peer.min_depth > MAX_MIN_CHANNEL_DEPTH {
+                return Err(APIError::InvalidChannelDepths(format!(
+                    "min_depth cannot be more than {MAX_MIN_CHANNEL_DEPTH}"
+                )));
+            }
+            overrides.peers.push(PeerChannelDepth {
+                peer_pubkey,
+                min_depth: peer.min_depth,
+            });
         }
+        let response = ChannelDepthsResponse::new(&overrides, state.static_state.min_channel_depth);
+        unlocked_state.set_channel_depths(overrides);
 
-        // We are selling assets, check if we have enough
-        if let Some(from_asset) = swapstring.swap_info.from_asset {
-            let max_balance = get_max_local_rgb_amount(
-                from_asset,
-                &state.static_state.ldk_data_dir,
-                unlocked_state.channel_manager.list_channels().iter(),
-            );
-            if swapstring.swap_info.qty_from > max_balance {
-                return Err(APIError::InsufficientAssets);
+        Ok(Json(response))
+    })
+    .await
+}
+
+pub(crate) async fn update_channel_policy(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<UpdateChannelPolicyRequest>, APIError>,
+) -> Result<Json<UpdateChannelPolicyResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let policy = AssetPolicy {
+            fee_base_msat: payload.fee_base_msat,
+            fee_proportional_millionths: payload.fee_proportional_millionths,
+            cltv_expiry_delta: payload.cltv_expiry_delta,
+            htlc_minimum_msat: payload.htlc_minimum_msat,
+            max_htlc_value_in_flight_percent: payload.max_htlc_value_in_flight_percent,
+        };
+        let has_htlc_limits =
+            policy.htlc_minimum_msat.is_some() || policy.max_htlc_value_in_flight_percent.is_some();
+        if policy.config_update().is_none() && !has_htlc_limits {
+            return Err(APIError::InvalidChannelPolicy(s!(
+                "at least one policy field needs to be set"
+            )));
+        }
+        if let Some(cltv_expiry_delta) = payload.cltv_expiry_delta {
+            if cltv_expiry_delta < MIN_CLTV_EXPIRY_DELTA {
+                return Err(APIError::InvalidChannelPolicy(format!(
+                    "cltv_expiry_delta cannot be less than {MIN_CLTV_EXPIRY_DELTA}"
+                )));
             }
         }
+        if payload.channel_id.is_some() && payload.asset_id.is_some() {
+            return Err(APIError::InvalidChannelPolicy(s!(
+                "channel_id and asset_id cannot be provided together"
+            )));
+        }
 
-        let swap_data = SwapData::create_from_swap_info(&swapstring.swap_info);
-        unlocked_state.add_taker_swap(swapstring.payment_hash, swap_data);
+        // the policy of an asset also applies to its channels opened afterwards
+        let policy_key = match payload.asset_id {
+            Some(asset_id) if asset_id == BTC_POLICY_KEY => Some(asset_id),
+            Some(asset_id) => Some(
+                ContractId::from_str(&asset_id)
+                    .map_err(|_| APIError::InvalidAssetID(asset_id))?
+                    .to_string(),
+            ),
+            None if has_htlc_limits => {
+                return Err(APIError::InvalidChannelPolicy(s!(
+                    "HTLC limits can only be set for an asset_id, as they apply to the channels opened afterwards"
+                )))
+            }
+            None => None,
+        };
+        if let Some(percent) = policy.max_htlc_value_in_flight_percent {
+            if percent == 0 || percent > 100 {
+                return Err(APIError::InvalidChannelPolicy(s!(
+                    "max_htlc_value_in_flight_percent must be between 1 and 100"
+                )));
+            }
+        }
+        if let Some(htlc_minimum_msat) = policy.htlc_minimum_msat {
+            let min_msat = match policy_key.as_deref() {
+                Some(BTC_POLICY_KEY) => 1,
+                _ => HTLC_MIN_MSAT,
+            };
+            if htlc_minimum_msat < min_msat {
+                return Err(APIError::InvalidChannelPolicy(format!(
+                    "htlc_minimum_msat cannot be less than {min_msat}"
+                )));
+            }
+        }
 
-        Ok(Json(EmptyResponse {}))
+        let requested_cid = payload
+            .channel_id
+            .as_deref()
+            .map(check_channel_id)
+            .transpose()?;
+
+        // channels need to be updated separately for each counterparty
+        let mut channels_by_peer: HashMap<PublicKey, Vec<ChannelId>> = HashMap::new();
+        for chan_info in unlocked_state.channel_manager.list_channels() {
+            if requested_cid.is_some_and(|cid| cid != chan_info.channel_id) {
+                continue;
+            }
+            if policy_key.as_ref().is_some_and(|key| {
+                *key != unlocked_state
+                    .channel_policy_key(&chan_info.channel_id, &state.static_state.ldk_data_dir)
+            }) {
+                continue;
+            }
+            channels_by_peer
+                .entry(chan_info.counterparty.node_id)
+                .or_default()
+                .push(chan_info.channel_id);
+        }
+        if requested_cid.is_some() && channels_by_peer.is_empty() {
+            return Err(APIError::UnknownChannelId);
+        }
+
+        let config_update = policy.config_update();
+        if let Some(policy_key) = policy_key {
+            let policy = unlocked_state.set_asset_policy(&policy_key, policy);
+            tracing::info!("Updated the {policy_key} routing policy: {policy:?}");
+        }
+        let Some(config_update) = config_update else {
+            return Ok(Json(UpdateChannelPolicyResponse {
+                channel_ids: vec![],
+            }));
+        };
+        let mut channel_ids = vec![];
+        for (counterparty, ids) in channels_by_peer {
+            // LDK takes care of broadcasting the new channel_update for public channels
+            unlocked_state
+                .channel_manager
+                .update_partial_channel_config(&counterparty, &ids, &config_update)
+                .map_err(|e| APIError::FailedChannelPolicyUpdate(format!("{e:?}")))?;
+            channel_ids.extend(ids.iter().map(|id| id.0.as_hex().to_string()));
+        }
+        tracing::info!("Updated policy of channels {channel_ids:?}");
+
+        Ok(Json(UpdateChannelPolicyResponse { channel_ids }))
     })
     .await
 }
 
-pub(crate) async fn unlock(
+pub(crate) async fn update_lease_config(
     State(state): State<Arc<AppState>>,
-    WithRejection(Json(payload), _): WithRejection<Json<UnlockRequest>, APIError>,
-) -> Result<Json<EmptyResponse>, APIError> {
-    tracing::info!("Unlock started");
+    WithRejection(Json(payload), _): WithRejection<Json<LeaseConfigRequest>, APIError>,
+) -> Result<Json<LeaseConfigResponse>, APIError> {
     no_cancel(async move {
-        match state.check_locked().await {
-            Ok(unlocked_state) => {
-                state.update_changing_state(true);
-                drop(unlocked_state);
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        if payload.min_channel_size_sat < OPENCHANNEL_MIN_SAT
+            || payload.max_channel_size_sat > OPENCHANNEL_MAX_SAT
+        {
+            return Err(APIError::InvalidLeaseConfig(format!(
+                "channel sizes must be between {OPENCHANNEL_MIN_SAT} and {OPENCHANNEL_MAX_SAT}"
+            )));
+        }
+        if payload.min_channel_size_sat > payload.max_channel_size_sat {
+            return Err(APIError::InvalidLeaseConfig(s!(
+                "min_channel_size_sat cannot exceed max_channel_size_sat"
+            )));
+        }
+        if payload.fee_base_sat == 0 && payload.fee_proportional_millionths == 0 {
+            return Err(APIError::InvalidLeaseConfig(s!(
+                "fee_base_sat and fee_proportional_millionths cannot both be 0"
+            )));
+        }
+        if payload.lease_duration_blocks == 0 {
+            return Err(APIError::InvalidLeaseConfig(s!(
+                "lease_duration_blocks must be greater than 0"
+            )));
+        }
+        if payload.order_expiry_sec == 0 {
+            return Err(APIError::InvalidLeaseConfig(s!(
+                "order_expiry_sec must be greater than 0"
+            )));
+        }
+
+        let config = LeaseConfig {
+            enabled: payload.enabled,
+            min_channel_size_sat: payload.min_channel_size_sat,
+            max_channel_size_sat: payload.max_channel_size_sat,
+            fee_base_sat: payload.fee_base_sat,
+            fee_proportional_millionths: payload.fee_proportional_millionths,
+            lease_duration_blocks: payload.lease_duration_blocks,
+            order_expiry_sec: payload.order_expiry_sec,
+        };
+        unlocked_state.lsps_handler.set_lease_config(config.clone());
+
+        Ok(Json(config.into()))
+    })
+    .await
+}
+
+pub(crate) async fn update_log_level(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<LogLevelRequest>, APIError>,
+) -> Result<Json<LogLevelResponse>, APIError> {
+    state.set_log_level(payload.log_level)?;
+    let log_level = state.get_runtime_config().log_level.clone();
+    tracing::info!("Log level changed to {log_level}");
+
+    Ok(Json(LogLevelResponse { log_level }))
+}
+
+pub(crate) async fn update_lnurl_pay(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<LnurlPayRequest>, APIError>,
+) -> Result<Json<LnurlPayResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let domain = payload.domain.trim().to_lowercase();
+        if domain.is_empty()
+            || !domain
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':'))
+        {
+            return Err(APIError::InvalidLnurlPay(format!(
+                "invalid domain {}, expected a host with an optional port",
+                payload.domain
+            )));
+        }
+        if payload.addresses.len() > MAX_LIGHTNING_ADDRESSES {
+            return Err(APIError::InvalidLnurlPay(format!(
+                "cannot serve more than {MAX_LIGHTNING_ADDRESSES} addresses"
+            )));
+        }
+
+        let mut addresses: Vec<LightningAddressInfo> = vec![];
+        for address in payload.addresses {
+            // LUD-16 restricts the name to these characters
+            if address.name.is_empty()
+                || !address.name.chars().all(|c| {
+                    c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '_' | '.')
+                })
+            {
+                return Err(APIError::InvalidLnurlPay(format!(
+                    "invalid name {}, only a-z, 0-9, -, _ and . are allowed",
+                    address.name
+                )));
             }
-            Err(e) => {
-                return Err(match e {
-                    APIError::UnlockedNode => APIError::AlreadyUnlocked,
-                    _ => e,
-                });
+            if addresses.iter().any(|a| a.name == address.name) {
+                return Err(APIError::InvalidLnurlPay(format!(
+                    "duplicate name {}",
+                    address.name
+                )));
+            }
+            if address.min_sendable_msat == 0
+                || address.min_sendable_msat > address.max_sendable_msat
+            {
+                return Err(APIError::InvalidLnurlPay(format!(
+                    "min_sendable_msat of {} must be positive and not more than max_sendable_msat",
+                    address.name
+                )));
+            }
+            if let Some(asset_id) = &address.asset_id {
+                ContractId::from_str(asset_id)
+                    .map_err(|_| APIError::InvalidAssetID(asset_id.clone()))?;
+                let asset_htlc_min_msat = state.static_state.asset_htlc_min_msat;
+                if address.min_sendable_msat < asset_htlc_min_msat {
+                    return Err(APIError::InvalidLnurlPay(format!(
+                        "min_sendable_msat of {} cannot be less than {asset_htlc_min_msat} when receiving an RGB asset",
+                        address.name
+                    )));
+                }
             }
+            addresses.push(LightningAddressInfo {
+                name: address.name,
+                description: address.description,
+                min_sendable_msat: address.min_sendable_msat,
+                max_sendable_msat: address.max_sendable_msat,
+                asset_id: address.asset_id,
+            });
         }
 
-        let mnemonic = match check_password_validity(
-            &payload.password,
-            &state.static_state.storage_dir_path,
-        ) {
-            Ok(mnemonic) => mnemonic,
-            Err(e) => {
-                state.update_changing_state(false);
-                return Err(e);
-            }
+        let config = LnurlPayConfig {
+            domain: Some(domain),
+            addresses,
         };
+        unlocked_state.set_lnurl_pay(config.clone());
 
-        tracing::debug!("Starting LDK...");
-        let (new_ldk_background_services, new_unlocked_app_state) =
-            match start_ldk(state.clone(), mnemonic, payload).await {
-                Ok((nlbs, nuap)) => (nlbs, nuap),
-                Err(e) => {
-                    state.update_changing_state(false);
-                    return Err(e);
-                }
-            };
-        tracing::debug!("LDK started");
+        Ok(Json(config.into()))
+    })
+    .await
+}
 
-        state
-            .update_unlocked_app_state(Some(new_unlocked_app_state))
-            .await;
+pub(crate) async fn update_lsp_config(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<LspConfigRequest>, APIError>,
+) -> Result<Json<LspConfigResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        if payload.min_payment_size_msat > payload.max_payment_size_msat {
+            return Err(APIError::InvalidLspConfig(s!(
+                "min_payment_size_msat cannot exceed max_payment_size_msat"
+            )));
+        }
+        if payload.min_fee_msat >= payload.max_payment_size_msat {
+            return Err(APIError::InvalidLspConfig(s!(
+                "min_fee_msat must be lower than max_payment_size_msat"
+            )));
+        }
+        if payload.fee_params_validity_sec == 0 {
+            return Err(APIError::InvalidLspConfig(s!(
+                "fee_params_validity_sec must be greater than 0"
+            )));
+        }
+        if payload.min_channel_size_sat == 0 {
+            return Err(APIError::InvalidLspConfig(s!(
+                "min_channel_size_sat must be greater than 0"
+            )));
+        }
 
-        state.update_ldk_background_services(Some(new_ldk_background_services));
+        let config = LspConfig {
+            enabled: payload.enabled,
+            min_fee_msat: payload.min_fee_msat,
+            fee_proportional_millionths: payload.fee_proportional_millionths,
+            min_payment_size_msat: payload.min_payment_size_msat,
+            max_payment_size_msat: payload.max_payment_size_msat,
+            min_channel_size_sat: payload.min_channel_size_sat,
+            fee_params_validity_sec: payload.fee_params_validity_sec,
+        };
+        unlocked_state.lsps_handler.set_lsp_config(config.clone());
 
-        state.update_changing_state(false);
+        Ok(Json(config.into()))
+    })
+    .await
+}
 
-        tracing::info!("Unlock completed");
-        Ok(Json(EmptyResponse {}))
+pub(crate) async fn update_peer_filter(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<PeerFilterRequest>, APIError>,
+) -> Result<Json<PeerFilterResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        let parse_pubkeys = |pubkeys: Vec<String>| {
+            pubkeys
+                .into_iter()
+                .map(|pk| {
+                    PublicKey::from_str(&pk)
+                        .map_err(|_| APIError::InvalidPeerFilter(format!("invalid pubkey {pk}")))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let parse_addresses = |addresses: Vec<String>| {
+            addresses
+                .into_iter()
+                .map(|addr| {
+                    let host = addr.to_lowercase();
+                    if let Ok(ip) = IpAddr::from_str(&host) {
+                        return Ok(ip.to_canonical().to_string());
+                    }
+                    if host.ends_with(".onion") && host.len() > ".onion".len() {
+                        return Ok(host);
+                    }
+                    Err(APIError::InvalidPeerFilter(format!(
+                        "invalid address {addr}, expected an IP or .onion host"
+                    )))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let filter = PeerFilter {
+            allowed_pubkeys: parse_pubkeys(payload.allowed_pubkeys)?,
+            denied_pubkeys: parse_pubkeys(payload.denied_pubkeys)?,
+            allowed_addresses: parse_addresses(payload.allowed_addresses)?,
+            denied_addresses: parse_addresses(payload.denied_addresses)?,
+        };
+        if filter
+            .allowed_pubkeys
+            .iter()
+            .any(|pk| filter.denied_pubkeys.contains(pk))
+        {
+            return Err(APIError::InvalidPeerFilter(s!(
+                "a pubkey cannot be both allowed and denied"
+            )));
+        }
+        if filter
+            .allowed_addresses
+            .iter()
+            .any(|a| filter.denied_addresses.contains(a))
+        {
+            return Err(APIError::InvalidPeerFilter(s!(
+                "an address cannot be both allowed and denied"
+            )));
+        }
+
+        unlocked_state.set_peer_filter(filter.clone());
+
+        Ok(Json(filter.into()))
+    })
+    .await
+}
+
+pub(crate) async fn update_sweep_config(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<SweepConfigRequest>, APIError>,
+) -> Result<Json<SweepConfigResponse>, APIError> {
+    no_cancel(async move {
+        let guard = state.check_unlocked().await?;
+        let unlocked_state = guard.as_ref().unwrap();
+
+        unlocked_state.check_totp(payload.totp_code.as_deref(), TotpOperation::Spend)?;
+
+        if let Some(fee_rate) = payload.fee_rate {
+            if fee_rate < MIN_SWEEP_FEE_RATE {
+                return Err(APIError::InvalidFeeRate(format!(
+                    "fee_rate cannot be less than {MIN_SWEEP_FEE_RATE}"
+                )));
+            }
+            if u32::try_from(fee_rate * 250).is_err() {
+                return Err(APIError::InvalidFeeRate(s!("fee_rate is too high")));
+            }
+        }
+        if let Some(address) = &payload.destination_address {
+            check_address_network(address, state.static_state.network)?;
+        }
+
+        let config = SweepConfig {
+            fee_rate: payload.fee_rate,
+            destination_address: payload.destination_address,
+        };
+        unlocked_state.set_sweep_config(config.clone());
+
+        Ok(Json(config.into()))
     })
     .await
 }
+
+pub(crate) async fn verify_message(
+    State(state): State<Arc<AppState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<VerifyMessageRequest>, APIError>,
+) -> Result<Json<VerifyMessageResponse>, APIError> {
+    let guard = state.check_unlocked().await?;
+    let unlocked_state = guard.as_ref().unwrap();
+
+    // the signature is checked against the node key unless another pubkey is provided
+    let expected_pubkey = match payload.pubkey {
+        Some(pubkey) => PublicKey::from_str(&pubkey).map_err(|_| APIError::InvalidPubkey)?,
+        None => unlocked_state.channel_manager.get_our_node_id(),
+    };
+
+    let recovered_pubkey = lightning::util::message_signing::recover_pk(
+        payload.message.as_bytes(),
+        &payload.signature,
+    )
+    .ok();
+
+    Ok(Json(VerifyMessageResponse {
+        valid: recovered_pubkey == Some(expected_pubkey),
+        pubkey: recovered_pubkey.map(|pk| pk.to_string()),
+    }))
+}
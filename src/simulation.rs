@@ -0,0 +1,441 @@
+use amplify::s;
+use bitcoin::constants::ChainHash;
+use bitcoin::secp256k1::{PublicKey, Secp256k1, SecretKey};
+use bitcoin::Network;
+use lightning::ln::msgs::UnsignedChannelUpdate;
+use lightning::routing::gossip::NetworkGraph;
+use lightning::routing::router::{find_route, Path, PaymentParameters, RouteParameters};
+use lightning::routing::scoring::{
+    ProbabilisticScorer, ProbabilisticScoringDecayParameters, ProbabilisticScoringFeeParameters,
+    ScoreUpdate,
+};
+use lightning::types::features::ChannelFeatures;
+use lightning::util::logger::{Logger, Record};
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use rgb_lib::ContractId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path as FsPath;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::error::AppError;
+use crate::routes::{DEFAULT_FINAL_CLTV_EXPIRY_DELTA, HTLC_MIN_MSAT};
+use crate::utils::get_current_timestamp;
+
+/// Asset moved by the simulated payments, only its ID matters to the router
+const SIMULATION_ASSET_ID: &str = "rgb:CJkb4YZw-jRiz2sk-~PARPio-wtVYI1c-XAEYCqO-wTfvRZ8";
+const SIMULATION_FEE_BASE_MSAT: u32 = 1000;
+const SIMULATION_FEE_PROPORTIONAL_MILLIONTHS: u32 = 100;
+const SIMULATION_CLTV_EXPIRY_DELTA: u16 = 40;
+
+/// Network and workload of a simulation, as read from its JSON config file
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub(crate) struct SimulationConfig {
+    pub(crate) nodes: u16,
+    pub(crate) channels_per_node: u8,
+    pub(crate) channel_capacity_sat: u64,
+    pub(crate) asset_amount_per_channel: u64,
+    pub(crate) payments: u32,
+    pub(crate) payment_amt_msat: u64,
+    pub(crate) payment_asset_amount: Option<u64>,
+    pub(crate) max_attempts: u8,
+    pub(crate) hop_latency_ms: u64,
+    pub(crate) seed: u64,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            nodes: 20,
+            channels_per_node: 3,
+            channel_capacity_sat: 1_000_000,
+            asset_amount_per_channel: 1000,
+            payments: 500,
+            payment_amt_msat: HTLC_MIN_MSAT,
+            payment_asset_amount: Some(10),
+            max_attempts: 5,
+            hop_latency_ms: 50,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct LatencyDistribution {
+    pub(crate) p50: u64,
+    pub(crate) p90: u64,
+    pub(crate) p99: u64,
+    pub(crate) max: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct SimulationReport {
+    pub(crate) nodes: u16,
+    pub(crate) channels: usize,
+    pub(crate) payments: u32,
+    pub(crate) succeeded: u32,
+    pub(crate) failed: u32,
+    pub(crate) no_route: u32,
+    pub(crate) success_rate: f64,
+    pub(crate) avg_attempts: f64,
+    /// Simulated time from the first HTLC to the settlement of the succeeded payments
+    pub(crate) latency_ms: LatencyDistribution,
+    /// Time actually spent looking for routes, for each attempt
+    pub(crate) route_finding_us: LatencyDistribution,
+}
+
+struct SimulationLogger;
+
+impl Logger for SimulationLogger {
+    fn log(&self, record: Record) {
+        tracing::trace!("{}", record.args);
+    }
+}
+
+/// Channel between two virtual nodes, with the BTC and asset balances each side can send
+struct SimChannel {
+    scid: u64,
+    nodes: [usize; 2],
+    balances_msat: [u64; 2],
+    asset_balances: [u64; 2],
+}
+
+impl SimChannel {
+    fn side(&self, node: usize) -> usize {
+        if self.nodes[0] == node {
+            0
+        } else {
+            1
+        }
+    }
+}
+
+/// Payment outcome, with the simulated latency and number of attempts
+enum PaymentOutcome {
+    Succeeded(u64, u8),
+    Failed(u8),
+    NoRoute(u8),
+}
+
+fn latency_distribution(mut values: Vec<u64>) -> LatencyDistribution {
+    values.sort_unstable();
+    let percentile = |p: usize| {
+        if values.is_empty() {
+            return 0;
+        }
+        values[(values.len() - 1) * p / 100]
+    };
+    LatencyDistribution {
+        p50: percentile(50),
+        p90: percentile(90),
+        p99: percentile(99),
+        max: values.last().copied().unwrap_or(0),
+    }
+}
+
+fn check_simulation_config(config: &SimulationConfig) -> Result<(), AppError> {
+    if config.nodes < 2 {
+        return Err(AppError::InvalidSimulationConfig(s!(
+            "at least 2 nodes are required"
+        )));
+    }
+    if config.channels_per_node == 0 || config.channels_per_node as u16 >= config.nodes {
+        return Err(AppError::InvalidSimulationConfig(s!(
+            "channels_per_node must be between 1 and the number of nodes minus 1"
+        )));
+    }
+    if config.max_attempts == 0 {
+        return Err(AppError::InvalidSimulationConfig(s!(
+            "max_attempts must be greater than 0"
+        )));
+    }
+    if config.payment_amt_msat == 0 || config.payment_amt_msat > config.channel_capacity_sat * 1000
+    {
+        return Err(AppError::InvalidSimulationConfig(s!(
+            "payment_amt_msat must be between 1 and the channel capacity"
+        )));
+    }
+    if config.payment_asset_amount == Some(0) {
+        return Err(AppError::InvalidSimulationConfig(s!(
+            "payment_asset_amount must be greater than 0"
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) fn read_simulation_config(path: &FsPath) -> Result<SimulationConfig, AppError> {
+    let content = fs::read_to_string(path)?;
+    serde_json::from_str(&content).map_err(|e| AppError::InvalidSimulationConfig(e.to_string()))
+}
+
+type SimNetworkGraph = NetworkGraph<Arc<SimulationLogger>>;
+
+/// In-process network of virtual nodes, routing payments with the node router and settling them
+/// against simulated channel balances
+struct Simulation {
+    config: SimulationConfig,
+    rng: StdRng,
+    logger: Arc<SimulationLogger>,
+    pubkeys: Vec<PublicKey>,
+    channels: Vec<SimChannel>,
+    network_graph: Arc<SimNetworkGraph>,
+    scorer: ProbabilisticScorer<Arc<SimNetworkGraph>, Arc<SimulationLogger>>,
+}
+
+impl Simulation {
+    fn new(config: SimulationConfig) -> Self {
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let secp = Secp256k1::new();
+        let pubkeys: Vec<PublicKey> = (0..config.nodes)
+            .map(|_| {
+                let mut secret = [0u8; 32];
+                rng.fill_bytes(&mut secret);
+                let secret_key = SecretKey::from_slice(&secret).unwrap();
+                PublicKey::from_secret_key(&secp, &secret_key)
+            })
+            .collect();
+
+        // a ring keeps the network connected, random channels are added on top of it
+        let nodes = config.nodes as usize;
+        let mut pairs = HashSet::new();
+        for node in 0..nodes {
+            pairs.insert((node.min((node + 1) % nodes), node.max((node + 1) % nodes)));
+        }
+        for node in 0..nodes {
+            for _ in 1..config.channels_per_node {
+                let peer = rng.gen_range(0..nodes);
+                if peer != node {
+                    pairs.insert((node.min(peer), node.max(peer)));
+                }
+            }
+        }
+        let mut pairs: Vec<(usize, usize)> = pairs.into_iter().collect();
+        pairs.sort_unstable();
+
+        let capacity_msat = config.channel_capacity_sat * 1000;
+        let channels: Vec<SimChannel> = pairs
+            .into_iter()
+            .enumerate()
+            .map(|(i, (a, b))| SimChannel {
+                scid: (i as u64 + 1) << 40,
+                nodes: [a, b],
+                balances_msat: [capacity_msat / 2, capacity_msat - capacity_msat / 2],
+                asset_balances: [
+                    config.asset_amount_per_channel / 2,
+                    config.asset_amount_per_channel - config.asset_amount_per_channel / 2,
+                ],
+            })
+            .collect();
+
+        let logger = Arc::new(SimulationLogger);
+        let network_graph = Arc::new(NetworkGraph::new(Network::Regtest, Arc::clone(&logger)));
+        let timestamp = get_current_timestamp();
+        for channel in &channels {
+            let (pubkey_a, pubkey_b) = (pubkeys[channel.nodes[0]], pubkeys[channel.nodes[1]]);
+            // announcements list the nodes in lexicographic order
+            let (node_1, node_2) = if pubkey_a.serialize() < pubkey_b.serialize() {
+                (pubkey_a, pubkey_b)
+            } else {
+                (pubkey_b, pubkey_a)
+            };
+            network_graph
+                .add_channel_from_partial_announcement(
+                    channel.scid,
+                    timestamp,
+                    ChannelFeatures::empty(),
+                    node_1,
+                    node_2,
+                )
+                .unwrap();
+            for direction in 0..2 {
+                network_graph
+                    .update_channel_unsigned(&UnsignedChannelUpdate {
+                        chain_hash: ChainHash::using_genesis_block(Network::Regtest),
+                        short_channel_id: channel.scid,
+                        timestamp: timestamp as u32,
+                        message_flags: 1,
+                        channel_flags: direction,
+                        cltv_expiry_delta: SIMULATION_CLTV_EXPIRY_DELTA,
+                        htlc_minimum_msat: 1,
+                        htlc_maximum_msat: capacity_msat,
+                        fee_base_msat: SIMULATION_FEE_BASE_MSAT,
+                        fee_proportional_millionths: SIMULATION_FEE_PROPORTIONAL_MILLIONTHS,
+                        excess_data: vec![],
+                    })
+                    .unwrap();
+            }
+        }
+        let scorer = ProbabilisticScorer::new(
+            ProbabilisticScoringDecayParameters::default(),
+            Arc::clone(&network_graph),
+            Arc::clone(&logger),
+        );
+
+        Self {
+            config,
+            rng,
+            logger,
+            pubkeys,
+            channels,
+            network_graph,
+            scorer,
+        }
+    }
+
+    fn hop_latency_ms(&mut self) -> u64 {
+        // +/- 50% of jitter around the configured latency
+        let hop_latency_ms = self.config.hop_latency_ms;
+        hop_latency_ms / 2 + self.rng.gen_range(0..=hop_latency_ms)
+    }
+
+    /// Settle the path against the channel balances, returning the index of the first hop that
+    /// cannot forward the payment, if any
+    fn settle_path(&mut self, sender: usize, path: &Path, asset_amount: u64) -> Option<usize> {
+        let mut channel_idxs = vec![];
+        let mut node = sender;
+        for (i, hop) in path.hops.iter().enumerate() {
+            let channel_idx = self
+                .channels
+                .iter()
+                .position(|c| c.scid == hop.short_channel_id)
+                .unwrap();
+            let channel = &self.channels[channel_idx];
+            let side = channel.side(node);
+            // each hop forwards the final amount plus the fees of the next hops
+            let amt_msat: u64 = path.hops[i..].iter().map(|h| h.fee_msat).sum();
+            if channel.balances_msat[side] < amt_msat || channel.asset_balances[side] < asset_amount
+            {
+                return Some(i);
+            }
+            channel_idxs.push((channel_idx, side, amt_msat));
+            node = channel.nodes[1 - side];
+        }
+        for (channel_idx, side, amt_msat) in channel_idxs {
+            let channel = &mut self.channels[channel_idx];
+            channel.balances_msat[side] -= amt_msat;
+            channel.balances_msat[1 - side] += amt_msat;
+            channel.asset_balances[side] -= asset_amount;
+            channel.asset_balances[1 - side] += asset_amount;
+        }
+        None
+    }
+
+    fn pay(
+        &mut self,
+        sender: usize,
+        recipient: usize,
+        contract_id: ContractId,
+        route_finding_us: &mut Vec<u64>,
+    ) -> PaymentOutcome {
+        let rgb_payment = self
+            .config
+            .payment_asset_amount
+            .map(|amount| (contract_id, amount));
+        let asset_amount = self.config.payment_asset_amount.unwrap_or(0);
+        let mut failed_channels = vec![];
+        let mut latency_ms = 0;
+        for attempt in 1..=self.config.max_attempts {
+            let mut payment_params = PaymentParameters::from_node_id(
+                self.pubkeys[recipient],
+                DEFAULT_FINAL_CLTV_EXPIRY_DELTA,
+            );
+            payment_params.max_path_count = 1;
+            payment_params.previously_failed_channels = failed_channels.clone();
+            let route_params = RouteParameters::from_payment_params_and_value(
+                payment_params,
+                self.config.payment_amt_msat,
+                rgb_payment,
+            );
+            let mut random_seed_bytes = [0u8; 32];
+            self.rng.fill_bytes(&mut random_seed_bytes);
+            let started = Instant::now();
+            let route = find_route(
+                &self.pubkeys[sender],
+                &route_params,
+                &self.network_graph.read_only(),
+                None,
+                Arc::clone(&self.logger),
+                &self.scorer,
+                &ProbabilisticScoringFeeParameters::default(),
+                &random_seed_bytes,
+            );
+            route_finding_us.push(started.elapsed().as_micros() as u64);
+            let Some(path) = route.ok().and_then(|r| r.paths.into_iter().next()) else {
+                return PaymentOutcome::NoRoute(attempt);
+            };
+
+            let now = Duration::from_secs(get_current_timestamp());
+            match self.settle_path(sender, &path, asset_amount) {
+                None => {
+                    // HTLCs get added hop by hop, then the preimage goes back the same way
+                    for _ in 0..path.hops.len() * 2 {
+                        latency_ms += self.hop_latency_ms();
+                    }
+                    self.scorer.payment_path_successful(&path, now);
+                    return PaymentOutcome::Succeeded(latency_ms, attempt);
+                }
+                Some(failed_hop) => {
+                    for _ in 0..(failed_hop + 1) * 2 {
+                        latency_ms += self.hop_latency_ms();
+                    }
+                    let scid = path.hops[failed_hop].short_channel_id;
+                    self.scorer.payment_path_failed(&path, scid, now);
+                    failed_channels.push(scid);
+                }
+            }
+        }
+        PaymentOutcome::Failed(self.config.max_attempts)
+    }
+}
+
+/// Run the payment workload on a simulated network of asset channels
+pub(crate) fn run_simulation(config: SimulationConfig) -> Result<SimulationReport, AppError> {
+    check_simulation_config(&config)?;
+    let contract_id = ContractId::from_str(SIMULATION_ASSET_ID).unwrap();
+    let mut simulation = Simulation::new(config.clone());
+
+    let mut latencies_ms = vec![];
+    let mut route_finding_us = vec![];
+    let (mut succeeded, mut failed, mut no_route, mut attempts) = (0, 0, 0, 0u64);
+    for _ in 0..config.payments {
+        let sender = simulation.rng.gen_range(0..config.nodes as usize);
+        let mut recipient = simulation.rng.gen_range(0..config.nodes as usize - 1);
+        if recipient >= sender {
+            recipient += 1;
+        }
+        match simulation.pay(sender, recipient, contract_id, &mut route_finding_us) {
+            PaymentOutcome::Succeeded(latency_ms, payment_attempts) => {
+                succeeded += 1;
+                attempts += payment_attempts as u64;
+                latencies_ms.push(latency_ms);
+            }
+            PaymentOutcome::Failed(payment_attempts) => {
+                failed += 1;
+                attempts += payment_attempts as u64;
+            }
+            PaymentOutcome::NoRoute(payment_attempts) => {
+                failed += 1;
+                no_route += 1;
+                attempts += payment_attempts as u64;
+            }
+        }
+    }
+
+    Ok(SimulationReport {
+        nodes: config.nodes,
+        channels: simulation.network_graph.read_only().channels().len(),
+        payments: config.payments,
+        succeeded,
+        failed,
+        no_route,
+        success_rate: succeeded as f64 / config.payments.max(1) as f64,
+        avg_attempts: attempts as f64 / config.payments.max(1) as f64,
+        latency_ms: latency_distribution(latencies_ms),
+        route_finding_us: latency_distribution(route_finding_us),
+    })
+}
@@ -0,0 +1,907 @@
+use amplify::s;
+use bitcoin::hashes::hmac::{Hmac, HmacEngine};
+use bitcoin::hashes::{sha256, Hash, HashEngine};
+use chacha20poly1305::aead::{Aead, Payload};
+use chacha20poly1305::{Key, KeyInit, XChaCha20Poly1305, XNonce};
+use lightning::util::async_poll::AsyncResult;
+use lightning::util::persist::{
+    KVStore, KVStoreSync, ARCHIVED_CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+    CHANNEL_MANAGER_PERSISTENCE_KEY, CHANNEL_MANAGER_PERSISTENCE_PRIMARY_NAMESPACE,
+    CHANNEL_MANAGER_PERSISTENCE_SECONDARY_NAMESPACE, CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+    CHANNEL_MONITOR_PERSISTENCE_SECONDARY_NAMESPACE,
+    CHANNEL_MONITOR_UPDATE_PERSISTENCE_PRIMARY_NAMESPACE, NETWORK_GRAPH_PERSISTENCE_KEY,
+    OUTPUT_SWEEPER_PERSISTENCE_KEY, SCORER_PERSISTENCE_KEY,
+};
+use lightning_persister::fs_store::FilesystemStore;
+use native_tls::TlsConnector;
+use postgres_native_tls::MakeTlsConnector;
+use rand::RngCore;
+use rgb_lib::bdk_wallet::keys::bip39::Mnemonic;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::BTreeMap;
+use std::fs;
+use std::future::Future;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tokio::runtime::Handle;
+use zeroize::Zeroizing;
+
+use crate::args::StorageBackend;
+use crate::disk::NODE_DATA_KEYS;
+use crate::error::AppError;
+use crate::utils::LDK_DIR;
+
+pub(crate) const SQLITE_STORE_FNAME: &str = "node.sqlite";
+
+/// Prefix of the values encrypted with the storage cipher
+pub(crate) const ENCRYPTED_VALUE_MAGIC: &[u8] = b"RLNENC\x00\x01";
+const ENCRYPTION_NONCE_LENGTH: usize = 24;
+const STORAGE_KEY_TAG: &[u8] = b"rgb-lightning-node storage encryption";
+/// Key of the entry written once all the node data has been encrypted, after which values in clear
+/// are rejected
+const STORAGE_ENCRYPTED_KEY: &str = "storage_encrypted";
+
+/// Keys of the root namespace written by LDK, which on the filesystem is shared with the files
+/// written by the RGB extensions of LDK, the node data keys being listed in [`NODE_DATA_KEYS`]
+const LDK_ROOT_KEYS: [&str; 5] = [
+    CHANNEL_MANAGER_PERSISTENCE_KEY,
+    NETWORK_GRAPH_PERSISTENCE_KEY,
+    OUTPUT_SWEEPER_PERSISTENCE_KEY,
+    SCORER_PERSISTENCE_KEY,
+    STORAGE_ENCRYPTED_KEY,
+];
+
+fn sqlite_error(e: rusqlite::Error) -> io::Error {
+    io::Error::other(e)
+}
+
+/// Key-value store keeping all the entries in a single SQLite database in WAL mode
+pub(crate) struct SqliteStore {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    fn new(db_path: &Path) -> io::Result<Self> {
+        let connection = Connection::open(db_path).map_err(sqlite_error)?;
+        connection
+            .pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))
+            .map_err(sqlite_error)?;
+        // a write must survive a crash once it returns, as LDK relies on it for channel monitors
+        connection
+            .pragma_update(None, "synchronous", "FULL")
+            .map_err(sqlite_error)?;
+        connection
+            .execute(
+                "CREATE TABLE IF NOT EXISTS kv_store (
+                    primary_namespace TEXT NOT NULL,
+                    secondary_namespace TEXT NOT NULL,
+                    key TEXT NOT NULL,
+                    value BLOB NOT NULL,
+                    PRIMARY KEY (primary_namespace, secondary_namespace, key)
+                )",
+                [],
+            )
+            .map_err(sqlite_error)?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+impl KVStoreSync for SqliteStore {
+    fn read(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+    ) -> io::Result<Vec<u8>> {
+        self.connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT value FROM kv_store
+                 WHERE primary_namespace = ?1 AND secondary_namespace = ?2 AND key = ?3",
+                params![primary_namespace, secondary_namespace, key],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(sqlite_error)?
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("{primary_namespace}/{secondary_namespace}/{key} not found"),
+                )
+            })
+    }
+
+    fn write(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+        buf: Vec<u8>,
+    ) -> io::Result<()> {
+        self.connection
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT OR REPLACE INTO kv_store
+                 (primary_namespace, secondary_namespace, key, value) VALUES (?1, ?2, ?3, ?4)",
+                params![primary_namespace, secondary_namespace, key, buf],
+            )
+            .map_err(sqlite_error)?;
+        Ok(())
+    }
+
+    fn remove(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+        _lazy: bool,
+    ) -> io::Result<()> {
+        self.connection
+            .lock()
+            .unwrap()
+            .execute(
+                "DELETE FROM kv_store
+                 WHERE primary_namespace = ?1 AND secondary_namespace = ?2 AND key = ?3",
+                params![primary_namespace, secondary_namespace, key],
+            )
+            .map_err(sqlite_error)?;
+        Ok(())
+    }
+
+    fn list(&self, primary_namespace: &str, secondary_namespace: &str) -> io::Result<Vec<String>> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection
+            .prepare(
+                "SELECT key FROM kv_store WHERE primary_namespace = ?1 AND secondary_namespace = ?2",
+            )
+            .map_err(sqlite_error)?;
+        let keys = statement
+            .query_map(params![primary_namespace, secondary_namespace], |row| {
+                row.get(0)
+            })
+            .map_err(sqlite_error)?
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(sqlite_error)?;
+        Ok(keys)
+    }
+}
+
+/// Store on the local disk, with the backend chosen at startup
+pub(crate) enum LocalStore {
+    Filesystem(FilesystemStore),
+    Sqlite(SqliteStore),
+}
+
+impl LocalStore {
+    pub(crate) fn new(ldk_data_dir: &Path, backend: StorageBackend) -> io::Result<Self> {
+        Ok(match backend {
+            StorageBackend::Filesystem => {
+                Self::Filesystem(FilesystemStore::new(ldk_data_dir.to_path_buf()))
+            }
+            StorageBackend::Sqlite => {
+                Self::Sqlite(SqliteStore::new(&ldk_data_dir.join(SQLITE_STORE_FNAME))?)
+            }
+        })
+    }
+}
+
+impl KVStoreSync for LocalStore {
+    fn read(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+    ) -> io::Result<Vec<u8>> {
+        match self {
+            Self::Filesystem(store) => {
+                KVStoreSync::read(store, primary_namespace, secondary_namespace, key)
+            }
+            Self::Sqlite(store) => store.read(primary_namespace, secondary_namespace, key),
+        }
+    }
+
+    fn write(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+        buf: Vec<u8>,
+    ) -> io::Result<()> {
+        match self {
+            Self::Filesystem(store) => {
+                KVStoreSync::write(store, primary_namespace, secondary_namespace, key, buf)
+            }
+            Self::Sqlite(store) => store.write(primary_namespace, secondary_namespace, key, buf),
+        }
+    }
+
+    fn remove(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+        lazy: bool,
+    ) -> io::Result<()> {
+        match self {
+            Self::Filesystem(store) => {
+                KVStoreSync::remove(store, primary_namespace, secondary_namespace, key, lazy)
+            }
+            Self::Sqlite(store) => store.remove(primary_namespace, secondary_namespace, key, lazy),
+        }
+    }
+
+    fn list(&self, primary_namespace: &str, secondary_namespace: &str) -> io::Result<Vec<String>> {
+        match self {
+            Self::Filesystem(store) => {
+                KVStoreSync::list(store, primary_namespace, secondary_namespace)
+            }
+            Self::Sqlite(store) => store.list(primary_namespace, secondary_namespace),
+        }
+    }
+}
+
+/// Key-value store in a remote Postgres database
+pub(crate) struct PostgresStore {
+    client: tokio_postgres::Client,
+    runtime: Handle,
+}
+
+fn postgres_error(e: tokio_postgres::Error) -> io::Error {
+    io::Error::other(format!("remote storage: {e}"))
+}
+
+impl PostgresStore {
+    async fn connect(url: &str) -> io::Result<Self> {
+        let tls_connector = TlsConnector::new().map_err(io::Error::other)?;
+        let (client, connection) =
+            tokio_postgres::connect(url, MakeTlsConnector::new(tls_connector))
+                .await
+                .map_err(postgres_error)?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                tracing::error!("Remote storage connection closed: {e}");
+            }
+        });
+        client
+            .execute(
+                "CREATE TABLE IF NOT EXISTS kv_store (
+                    primary_namespace TEXT NOT NULL,
+                    secondary_namespace TEXT NOT NULL,
+                    key TEXT NOT NULL,
+                    value BYTEA NOT NULL,
+                    PRIMARY KEY (primary_namespace, secondary_namespace, key)
+                )",
+                &[],
+            )
+            .await
+            .map_err(postgres_error)?;
+        Ok(Self {
+            client,
+            runtime: Handle::current(),
+        })
+    }
+
+    /// All the entries of the store, as namespaces, key and value
+    async fn entries(&self) -> io::Result<Vec<(String, String, String, Vec<u8>)>> {
+        let rows = self
+            .client
+            .query(
+                "SELECT primary_namespace, secondary_namespace, key, value FROM kv_store",
+                &[],
+            )
+            .await
+            .map_err(postgres_error)?;
+        Ok(rows
+            .iter()
+            .map(|row| (row.get(0), row.get(1), row.get(2), row.get(3)))
+            .collect())
+    }
+
+    async fn write(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+        buf: &[u8],
+    ) -> io::Result<()> {
+        self.client
+            .execute(
+                "INSERT INTO kv_store (primary_namespace, secondary_namespace, key, value)
+                 VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (primary_namespace, secondary_namespace, key)
+                 DO UPDATE SET value = EXCLUDED.value",
+                &[&primary_namespace, &secondary_namespace, &key, &buf],
+            )
+            .await
+            .map_err(postgres_error)?;
+        Ok(())
+    }
+
+    async fn remove(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+    ) -> io::Result<()> {
+        self.client
+            .execute(
+                "DELETE FROM kv_store
+                 WHERE primary_namespace = $1 AND secondary_namespace = $2 AND key = $3",
+                &[&primary_namespace, &secondary_namespace, &key],
+            )
+            .await
+            .map_err(postgres_error)?;
+        Ok(())
+    }
+
+    /// Wait for a query from synchronous code running on the runtime
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        tokio::task::block_in_place(|| self.runtime.block_on(future))
+    }
+}
+
+/// Cipher encrypting the node data, with a key derived from the wallet seed so that it's only
+/// available once the node has been unlocked
+pub(crate) fn storage_cipher(mnemonic: &Mnemonic) -> XChaCha20Poly1305 {
+    let seed = Zeroizing::new(mnemonic.to_seed(""));
+    let mut engine = HmacEngine::<sha256::Hash>::new(STORAGE_KEY_TAG);
+    engine.input(&seed[..]);
+    let key = Zeroizing::new(Hmac::from_engine(engine).to_byte_array());
+    XChaCha20Poly1305::new(Key::from_slice(&key[..]))
+}
+
+/// Whether the value has been encrypted with a storage cipher
+fn is_encrypted(value: &[u8]) -> bool {
+    value.starts_with(ENCRYPTED_VALUE_MAGIC)
+}
+
+/// Encrypt a value, binding it to its namespaces and key so it can't be moved to another entry
+fn encrypt_value(cipher: &XChaCha20Poly1305, aad: &str, value: &[u8]) -> io::Result<Vec<u8>> {
+    let mut nonce = [0; ENCRYPTION_NONCE_LENGTH];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let ciphertext = cipher
+        .encrypt(
+            XNonce::from_slice(&nonce),
+            Payload {
+                msg: value,
+                aad: aad.as_bytes(),
+            },
+        )
+        .map_err(|_| io::Error::other(format!("failed to encrypt {aad}")))?;
+    let mut encrypted =
+        Vec::with_capacity(ENCRYPTED_VALUE_MAGIC.len() + nonce.len() + ciphertext.len());
+    encrypted.extend_from_slice(ENCRYPTED_VALUE_MAGIC);
+    encrypted.extend_from_slice(&nonce);
+    encrypted.extend_from_slice(&ciphertext);
+    Ok(encrypted)
+}
+
+/// Decrypt a value, returning the ones in clear as they are only when there's no cipher, as with one
+/// all the node data gets encrypted before being read
+fn decrypt_value(
+    cipher: Option<&XChaCha20Poly1305>,
+    aad: &str,
+    value: Vec<u8>,
+) -> io::Result<Vec<u8>> {
+    let Some(encrypted) = value.strip_prefix(ENCRYPTED_VALUE_MAGIC) else {
+        if cipher.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{aad} is not encrypted"),
+            ));
+        }
+        return Ok(value);
+    };
+    let Some(cipher) = cipher else {
+        return Err(io::Error::other(format!(
+            "{aad} is encrypted, the node must be started with --encrypt-storage"
+        )));
+    };
+    if encrypted.len() < ENCRYPTION_NONCE_LENGTH {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{aad} is truncated"),
+        ));
+    }
+    let (nonce, ciphertext) = encrypted.split_at(ENCRYPTION_NONCE_LENGTH);
+    cipher
+        .decrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: aad.as_bytes(),
+            },
+        )
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("cannot decrypt {aad}")))
+}
+
+fn entry_aad(primary_namespace: &str, secondary_namespace: &str, key: &str) -> String {
+    format!("{primary_namespace}/{secondary_namespace}/{key}")
+}
+
+/// Store of the channel state and of the node data
+///
+/// Reads are served by the local store, while writes are applied to the remote one, if any,
+/// before the local one, so the remote copy is never behind the local data. With a cipher, values
+/// are encrypted before reaching either store.
+pub(crate) struct NodeStore {
+    local: LocalStore,
+    remote: Option<PostgresStore>,
+    cipher: Option<XChaCha20Poly1305>,
+}
+
+impl NodeStore {
+    /// Open the local store alone, without decrypting the data
+    pub(crate) fn new(ldk_data_dir: &Path, backend: StorageBackend) -> io::Result<Self> {
+        Ok(Self {
+            local: LocalStore::new(ldk_data_dir, backend)?,
+            remote: None,
+            cipher: None,
+        })
+    }
+
+    /// Open the local store, replicating it to the remote one if configured
+    ///
+    /// A node without local data gets it restored from the remote store, while the first time a
+    /// remote store is used it receives a copy of the local data. With a cipher, the entries
+    /// stored before encryption was enabled get encrypted.
+    pub(crate) async fn open(
+        ldk_data_dir: &Path,
+        backend: StorageBackend,
+        remote_storage_url: Option<&str>,
+        cipher: Option<XChaCha20Poly1305>,
+    ) -> io::Result<Self> {
+        let has_local_data = stored_backend(ldk_data_dir).is_some();
+        let mut store = Self::new(ldk_data_dir, backend)?;
+        if let Some(remote_storage_url) = remote_storage_url {
+            let remote = PostgresStore::connect(remote_storage_url).await?;
+            let remote_entries = remote.entries().await?;
+            if !has_local_data {
+                let num_entries = remote_entries.len();
+                for (primary_namespace, secondary_namespace, key, value) in remote_entries {
+                    store
+                        .local
+                        .write(&primary_namespace, &secondary_namespace, &key, value)?;
+                }
+                if num_entries > 0 {
+                    tracing::info!("Restored {num_entries} entries from the remote storage");
+                }
+            } else if remote_entries.is_empty() {
+                let entries = node_entries(&store.local)?;
+                for (primary_namespace, secondary_namespace, key, value) in &entries {
+                    remote
+                        .write(primary_namespace, secondary_namespace, key, value)
+                        .await?;
+                }
+                tracing::info!("Copied {} entries to the remote storage", entries.len());
+            }
+            store.remote = Some(remote);
+        }
+
+        // the marker can only be read back with the cipher, so it can't be forged
+        store.cipher = cipher;
+        if store.cipher.is_some()
+            && KVStoreSync::read(&store, "", "", STORAGE_ENCRYPTED_KEY).is_err()
+        {
+            let mut num_encrypted = 0;
+            for (primary_namespace, secondary_namespace, key, value) in node_entries(&store.local)?
+            {
+                if !is_encrypted(&value) {
+                    KVStoreSync::write(
+                        &store,
+                        &primary_namespace,
+                        &secondary_namespace,
+                        &key,
+                        value,
+                    )?;
+                    num_encrypted += 1;
+                }
+            }
+            if num_encrypted > 0 {
+                tracing::info!("Encrypted {num_encrypted} entries of the node data");
+            }
+            KVStoreSync::write(&store, "", "", STORAGE_ENCRYPTED_KEY, vec![])?;
+        }
+        Ok(store)
+    }
+
+    /// The filesystem store, when values reach it unchanged and are not replicated
+    fn plain_filesystem_store(&self) -> Option<&FilesystemStore> {
+        match (&self.local, &self.remote, &self.cipher) {
+            (LocalStore::Filesystem(store), None, None) => Some(store),
+            _ => None,
+        }
+    }
+}
+
+impl KVStoreSync for NodeStore {
+    fn read(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+    ) -> io::Result<Vec<u8>> {
+        let value = self
+            .local
+            .read(primary_namespace, secondary_namespace, key)?;
+        decrypt_value(
+            self.cipher.as_ref(),
+            &entry_aad(primary_namespace, secondary_namespace, key),
+            value,
+        )
+    }
+
+    fn write(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+        buf: Vec<u8>,
+    ) -> io::Result<()> {
+        let buf = match &self.cipher {
+            Some(cipher) => encrypt_value(
+                cipher,
+                &entry_aad(primary_namespace, secondary_namespace, key),
+                &buf,
+            )?,
+            None => buf,
+        };
+        if let Some(remote) = &self.remote {
+            remote.block_on(remote.write(primary_namespace, secondary_namespace, key, &buf))?;
+        }
+        self.local
+            .write(primary_namespace, secondary_namespace, key, buf)
+    }
+
+    fn remove(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+        lazy: bool,
+    ) -> io::Result<()> {
+        if let Some(remote) = &self.remote {
+            remote.block_on(remote.remove(primary_namespace, secondary_namespace, key))?;
+        }
+        self.local
+            .remove(primary_namespace, secondary_namespace, key, lazy)
+    }
+
+    fn list(&self, primary_namespace: &str, secondary_namespace: &str) -> io::Result<Vec<String>> {
+        self.local.list(primary_namespace, secondary_namespace)
+    }
+}
+
+// besides the plain filesystem store, operations complete before returning, which also
+// preserves the write ordering
+impl KVStore for NodeStore {
+    fn read(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+    ) -> AsyncResult<'static, Vec<u8>, io::Error> {
+        match (&self.local, &self.cipher) {
+            (LocalStore::Filesystem(store), None) => {
+                KVStore::read(store, primary_namespace, secondary_namespace, key)
+            }
+            _ => {
+                let res = KVStoreSync::read(self, primary_namespace, secondary_namespace, key);
+                Box::pin(async move { res })
+            }
+        }
+    }
+
+    fn write(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+        buf: Vec<u8>,
+    ) -> AsyncResult<'static, (), io::Error> {
+        if let Some(store) = self.plain_filesystem_store() {
+            return KVStore::write(store, primary_namespace, secondary_namespace, key, buf);
+        }
+        let res = KVStoreSync::write(self, primary_namespace, secondary_namespace, key, buf);
+        Box::pin(async move { res })
+    }
+
+    fn remove(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+        lazy: bool,
+    ) -> AsyncResult<'static, (), io::Error> {
+        if let Some(store) = self.plain_filesystem_store() {
+            return KVStore::remove(store, primary_namespace, secondary_namespace, key, lazy);
+        }
+        let res = KVStoreSync::remove(self, primary_namespace, secondary_namespace, key, lazy);
+        Box::pin(async move { res })
+    }
+
+    fn list(
+        &self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+    ) -> AsyncResult<'static, Vec<String>, io::Error> {
+        match &self.local {
+            LocalStore::Filesystem(store) => {
+                KVStore::list(store, primary_namespace, secondary_namespace)
+            }
+            LocalStore::Sqlite(store) => {
+                let res = store.list(primary_namespace, secondary_namespace);
+                Box::pin(async move { res })
+            }
+        }
+    }
+}
+
+/// All the node data entries of the store, as namespaces, key and value
+fn node_entries(store: &LocalStore) -> io::Result<Vec<(String, String, String, Vec<u8>)>> {
+    let mut keys = vec![];
+    for key in LDK_ROOT_KEYS.iter().chain(NODE_DATA_KEYS) {
+        keys.push((
+            CHANNEL_MANAGER_PERSISTENCE_PRIMARY_NAMESPACE.to_string(),
+            CHANNEL_MANAGER_PERSISTENCE_SECONDARY_NAMESPACE.to_string(),
+            key.to_string(),
+        ));
+    }
+    for primary_namespace in [
+        CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+        ARCHIVED_CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+    ] {
+        for key in store.list(
+            primary_namespace,
+            CHANNEL_MONITOR_PERSISTENCE_SECONDARY_NAMESPACE,
+        )? {
+            keys.push((
+                primary_namespace.to_string(),
+                CHANNEL_MONITOR_PERSISTENCE_SECONDARY_NAMESPACE.to_string(),
+                key,
+            ));
+        }
+    }
+    for monitor_key in store.list(
+        CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+        CHANNEL_MONITOR_PERSISTENCE_SECONDARY_NAMESPACE,
+    )? {
+        for key in store.list(
+            CHANNEL_MONITOR_UPDATE_PERSISTENCE_PRIMARY_NAMESPACE,
+            &monitor_key,
+        )? {
+            keys.push((
+                CHANNEL_MONITOR_UPDATE_PERSISTENCE_PRIMARY_NAMESPACE.to_string(),
+                monitor_key.clone(),
+                key,
+            ));
+        }
+    }
+
+    let mut entries = vec![];
+    for (primary_namespace, secondary_namespace, key) in keys {
+        let value = match store.read(&primary_namespace, &secondary_namespace, &key) {
+            Ok(value) => value,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        entries.push((primary_namespace, secondary_namespace, key, value));
+    }
+    Ok(entries)
+}
+
+/// Remove a SQLite database along with its WAL files
+fn remove_sqlite_files(db_path: &Path) -> io::Result<()> {
+    for suffix in ["", "-wal", "-shm"] {
+        let mut path = db_path.as_os_str().to_owned();
+        path.push(suffix);
+        if Path::new(&path).exists() {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Backend holding the data of an initialized node, if any
+pub(crate) fn stored_backend(ldk_data_dir: &Path) -> Option<StorageBackend> {
+    if ldk_data_dir.join(SQLITE_STORE_FNAME).exists() {
+        Some(StorageBackend::Sqlite)
+    } else if ldk_data_dir.join(CHANNEL_MANAGER_PERSISTENCE_KEY).exists() {
+        Some(StorageBackend::Filesystem)
+    } else {
+        None
+    }
+}
+
+/// Make sure the node doesn't start from an empty store while its data is kept by another backend
+pub(crate) fn check_storage_backend(
+    storage_dir_path: &Path,
+    backend: StorageBackend,
+) -> Result<(), AppError> {
+    match stored_backend(&storage_dir_path.join(LDK_DIR)) {
+        Some(stored) if stored != backend => Err(AppError::InvalidStorageBackend(format!(
+            "the node data is stored with the {stored} backend, start the node with it or migrate \
+             the data with --migrate-storage-to {backend}"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Make sure a node whose data has been encrypted isn't started without decrypting it
+pub(crate) fn check_storage_encryption(
+    storage_dir_path: &Path,
+    backend: StorageBackend,
+    encrypt_storage: bool,
+) -> Result<(), AppError> {
+    let ldk_data_dir = storage_dir_path.join(LDK_DIR);
+    if encrypt_storage || stored_backend(&ldk_data_dir) != Some(backend) {
+        return Ok(());
+    }
+    let store = LocalStore::new(&ldk_data_dir, backend)?;
+    let encrypted = [STORAGE_ENCRYPTED_KEY, CHANNEL_MANAGER_PERSISTENCE_KEY]
+        .into_iter()
+        .any(|key| {
+            store
+                .read("", "", key)
+                .is_ok_and(|value| is_encrypted(&value))
+        });
+    if encrypted {
+        return Err(AppError::InvalidStorageEncryption(s!(
+            "the node data is encrypted, start the node with --encrypt-storage"
+        )));
+    }
+    Ok(())
+}
+
+/// Move the node data to the target backend, returning the number of migrated entries
+///
+/// The entries are removed from the source backend only after all of them have been copied, so
+/// an interrupted migration can be run again.
+pub(crate) fn migrate_storage(
+    storage_dir_path: &Path,
+    target: StorageBackend,
+) -> Result<usize, AppError> {
+    let ldk_data_dir = storage_dir_path.join(LDK_DIR);
+    let source = match stored_backend(&ldk_data_dir) {
+        None => {
+            return Err(AppError::InvalidStorageBackend(s!(
+                "the node has no data to migrate"
+            )))
+        }
+        Some(source) if source == target => {
+            return Err(AppError::InvalidStorageBackend(format!(
+                "the node data is already stored with the {target} backend"
+            )))
+        }
+        Some(source) => source,
+    };
+    let source_store = LocalStore::new(&ldk_data_dir, source)?;
+    // the database gets its final name once complete, so it's never mistaken for the node data
+    let migrating_db_path = ldk_data_dir.join(format!("{SQLITE_STORE_FNAME}.migrating"));
+    remove_sqlite_files(&migrating_db_path)?;
+    let target_store = match target {
+        StorageBackend::Filesystem => LocalStore::new(&ldk_data_dir, target)?,
+        StorageBackend::Sqlite => LocalStore::Sqlite(SqliteStore::new(&migrating_db_path)?),
+    };
+
+    let mut entries = node_entries(&source_store)?;
+    for (primary_namespace, secondary_namespace, key, value) in &entries {
+        target_store.write(primary_namespace, secondary_namespace, key, value.clone())?;
+    }
+
+    // the source is only touched once every entry can be read back unchanged from the target
+    let mut copied_entries = node_entries(&target_store)?;
+    entries.sort();
+    copied_entries.sort();
+    if copied_entries != entries {
+        drop(target_store);
+        if matches!(target, StorageBackend::Sqlite) {
+            remove_sqlite_files(&migrating_db_path)?;
+        }
+        return Err(AppError::FailedMigration(format!(
+            "the {target} backend doesn't hold the same entries as the {source} one"
+        )));
+    }
+
+    drop(target_store);
+    match source_store {
+        LocalStore::Filesystem(_) => {
+            fs::rename(&migrating_db_path, ldk_data_dir.join(SQLITE_STORE_FNAME))?;
+            for (primary_namespace, secondary_namespace, key, _) in &entries {
+                source_store.remove(primary_namespace, secondary_namespace, key, false)?;
+            }
+        }
+        LocalStore::Sqlite(_) => {
+            drop(source_store);
+            remove_sqlite_files(&ldk_data_dir.join(SQLITE_STORE_FNAME))?;
+        }
+    }
+    tracing::info!(
+        "Migrated {} entries from the {source} to the {target} storage backend",
+        entries.len()
+    );
+    Ok(entries.len())
+}
+
+/// SHA256 of all the files in the directory, by path relative to it
+pub(crate) fn dir_checksums(dir: &Path) -> io::Result<BTreeMap<PathBuf, sha256::Hash>> {
+    let mut checksums = BTreeMap::new();
+    let mut pending_dirs = vec![dir.to_path_buf()];
+    while let Some(current_dir) = pending_dirs.pop() {
+        for entry in fs::read_dir(&current_dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending_dirs.push(path);
+                continue;
+            }
+            let relative_path = path.strip_prefix(dir).expect("inside dir").to_path_buf();
+            checksums.insert(relative_path, sha256::Hash::hash(&fs::read(&path)?));
+        }
+    }
+    Ok(checksums)
+}
+
+/// Move the whole node to a new storage directory, returning the number of migrated files
+///
+/// Every file is checked against its checksum, taken before the copy, both in the new directory
+/// and in the old one, which is removed only if they all match. A failed migration leaves the
+/// node in the old directory.
+pub(crate) fn migrate_storage_dir(
+    storage_dir_path: &Path,
+    target_dir_path: &Path,
+) -> Result<usize, AppError> {
+    let backend = stored_backend(&storage_dir_path.join(LDK_DIR))
+        .ok_or_else(|| AppError::InvalidStorageDir(s!("the node has no data to migrate")))?;
+    if target_dir_path.exists() && fs::read_dir(target_dir_path)?.next().is_some() {
+        return Err(AppError::InvalidStorageDir(format!(
+            "{} is not empty",
+            target_dir_path.display()
+        )));
+    }
+    if target_dir_path.starts_with(storage_dir_path) {
+        return Err(AppError::InvalidStorageDir(s!(
+            "the new directory can't be inside the current one"
+        )));
+    }
+
+    let checksums = dir_checksums(storage_dir_path)?;
+    for relative_path in checksums.keys() {
+        let target_path = target_dir_path.join(relative_path);
+        if let Some(parent) = target_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(storage_dir_path.join(relative_path), &target_path)?;
+        fs::File::open(&target_path)?.sync_all()?;
+    }
+
+    let mismatch = if dir_checksums(target_dir_path)? != checksums {
+        Some("the copied files don't match the original ones")
+    } else if dir_checksums(storage_dir_path)? != checksums {
+        Some("the node data changed during the migration, make sure the node is not running")
+    } else if LocalStore::new(&target_dir_path.join(LDK_DIR), backend)
+        .and_then(|store| node_entries(&store))
+        .is_err()
+    {
+        Some("the node data can't be read from the new directory")
+    } else {
+        None
+    };
+    if let Some(mismatch) = mismatch {
+        fs::remove_dir_all(target_dir_path)?;
+        return Err(AppError::FailedMigration(s!(mismatch)));
+    }
+
+    fs::remove_dir_all(storage_dir_path)?;
+    tracing::info!(
+        "Migrated {} files from {} to {}",
+        checksums.len(),
+        storage_dir_path.display(),
+        target_dir_path.display()
+    );
+    Ok(checksums.len())
+}
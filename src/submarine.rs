@@ -0,0 +1,657 @@
+use amplify::s;
+use bitcoin::absolute::LockTime;
+use bitcoin::hashes::{ripemd160, sha256, Hash, HashEngine};
+use bitcoin::key::Keypair;
+use bitcoin::opcodes::all::{
+    OP_CHECKSIG, OP_CHECKSIGVERIFY, OP_CLTV, OP_EQUALVERIFY, OP_HASH160, OP_SIZE,
+};
+use bitcoin::script::Builder;
+use bitcoin::secp256k1::{Message, PublicKey, Scalar, Secp256k1, SecretKey, XOnlyPublicKey};
+use bitcoin::sighash::{Prevouts, SighashCache};
+use bitcoin::taproot::{LeafVersion, TapLeafHash, TaprootBuilder, TaprootSpendInfo};
+use bitcoin::transaction::Version;
+use bitcoin::{
+    Address, Amount, Network, OutPoint, ScriptBuf, Sequence, TapSighashType, Transaction, TxIn,
+    TxOut, Txid, Witness,
+};
+use lightning::chain::chaininterface::{BroadcasterInterface, ConfirmationTarget, FeeEstimator};
+use lightning::impl_writeable_tlv_based;
+use lightning::ln::channelmanager::PaymentId;
+use lightning::types::payment::{PaymentHash, PaymentPreimage};
+use lightning::util::hash_tables::HashMap as LdkHashMap;
+use lightning::util::persist::KVStoreSync;
+use lightning::util::ser::Writeable;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::MutexGuard;
+use std::time::Duration;
+
+use crate::disk::SUBMARINE_SWAPS_FNAME;
+use crate::error::APIError;
+use crate::routes::{HTLCStatus, SubmarineSwap, SubmarineSwapKind, SubmarineSwapStatus};
+use crate::utils::{get_current_timestamp, hex_str, UnlockedAppState};
+
+const SWAP_PROVIDER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Weight of the transactions sweeping a lockup output (1 script-path input, 1 output), rounded up
+const SWEEP_TX_WEIGHT: u64 = 600;
+
+/// Confirmations of the lockup of a swap out required before revealing the preimage to claim it
+const LOCKUP_MIN_CONFIRMATIONS: u32 = 1;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SwapTreeLeaf {
+    pub(crate) version: u8,
+    pub(crate) output: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SwapTree {
+    pub(crate) claim_leaf: SwapTreeLeaf,
+    pub(crate) refund_leaf: SwapTreeLeaf,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProviderSubmarineSwap {
+    pub(crate) id: String,
+    pub(crate) address: String,
+    pub(crate) swap_tree: SwapTree,
+    pub(crate) claim_public_key: String,
+    pub(crate) timeout_block_height: u32,
+    pub(crate) expected_amount: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProviderReverseSwap {
+    pub(crate) id: String,
+    pub(crate) invoice: String,
+    pub(crate) swap_tree: SwapTree,
+    pub(crate) lockup_address: String,
+    pub(crate) refund_public_key: String,
+    pub(crate) timeout_block_height: u32,
+    pub(crate) onchain_amount: u64,
+}
+
+#[derive(Deserialize)]
+struct ProviderTransaction {
+    hex: String,
+}
+
+#[derive(Deserialize)]
+struct ProviderError {
+    error: String,
+}
+
+/// Client of the v2 REST API of a Boltz-compatible swap provider
+pub(crate) struct SwapProviderClient {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl SwapProviderClient {
+    pub(crate) fn new(url: &str) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(SWAP_PROVIDER_TIMEOUT)
+                .build()
+                .unwrap(),
+            url: url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    async fn parse_response<R: DeserializeOwned>(
+        res: Result<reqwest::Response, reqwest::Error>,
+    ) -> Result<R, APIError> {
+        let res = res.map_err(|e| APIError::FailedSwapProviderRequest(e.to_string()))?;
+        if !res.status().is_success() {
+            let status = res.status();
+            let error = match res.json::<ProviderError>().await {
+                Ok(e) => e.error,
+                Err(_) => status.to_string(),
+            };
+            return Err(APIError::FailedSwapProviderRequest(error));
+        }
+        res.json::<R>()
+            .await
+            .map_err(|e| APIError::FailedSwapProviderRequest(format!("invalid response: {e}")))
+    }
+
+    async fn get<R: DeserializeOwned>(&self, path: &str) -> Result<R, APIError> {
+        let res = self.client.get(format!("{}{path}", self.url)).send().await;
+        Self::parse_response(res).await
+    }
+
+    async fn post<B: Serialize, R: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<R, APIError> {
+        let res = self
+            .client
+            .post(format!("{}{path}", self.url))
+            .json(body)
+            .send()
+            .await;
+        Self::parse_response(res).await
+    }
+
+    /// Ask the provider to pay the invoice once we lock the expected amount on-chain
+    pub(crate) async fn create_submarine_swap(
+        &self,
+        invoice: &str,
+        refund_pubkey: &PublicKey,
+    ) -> Result<ProviderSubmarineSwap, APIError> {
+        let body = serde_json::json!({
+            "from": "BTC",
+            "to": "BTC",
+            "invoice": invoice,
+            "refundPublicKey": refund_pubkey.to_string(),
+        });
+        self.post("/v2/swap/submarine", &body).await
+    }
+
+    /// Ask the provider for an invoice that, once paid, has it lock funds on-chain we can claim
+    /// with the preimage
+    pub(crate) async fn create_reverse_swap(
+        &self,
+        payment_hash: &PaymentHash,
+        claim_pubkey: &PublicKey,
+        amt_sat: u64,
+    ) -> Result<ProviderReverseSwap, APIError> {
+        let body = serde_json::json!({
+            "from": "BTC",
+            "to": "BTC",
+            "preimageHash": hex_str(&payment_hash.0),
+            "claimPublicKey": claim_pubkey.to_string(),
+            "invoiceAmount": amt_sat,
+        });
+        self.post("/v2/swap/reverse", &body).await
+    }
+
+    /// Get the transaction the provider locked the funds of a reverse swap with
+    pub(crate) async fn reverse_lockup_transaction(
+        &self,
+        swap_id: &str,
+    ) -> Result<Transaction, APIError> {
+        let tx: ProviderTransaction = self
+            .get(&format!("/v2/swap/reverse/{swap_id}/transaction"))
+            .await?;
+        bitcoin::consensus::encode::deserialize_hex(&tx.hex)
+            .map_err(|e| APIError::FailedSwapProviderRequest(format!("invalid lockup TX: {e}")))
+    }
+}
+
+fn tagged_hash(tag: &str, data: &[&[u8]]) -> [u8; 32] {
+    let tag_hash = sha256::Hash::hash(tag.as_bytes());
+    let mut engine = sha256::Hash::engine();
+    engine.input(tag_hash.as_ref());
+    engine.input(tag_hash.as_ref());
+    for d in data {
+        engine.input(d);
+    }
+    sha256::Hash::from_engine(engine).to_byte_array()
+}
+
+/// Aggregate the keys as MuSig2 (BIP327) does, keeping the given order, to get the internal key
+/// of the lockup output
+fn aggregate_keys(keys: &[PublicKey]) -> Result<XOnlyPublicKey, String> {
+    let secp = Secp256k1::verification_only();
+    let serialized: Vec<[u8; 33]> = keys.iter().map(|k| k.serialize()).collect();
+    let list_hash = tagged_hash(
+        "KeyAgg list",
+        &serialized.iter().map(|k| &k[..]).collect::<Vec<_>>(),
+    );
+    // the coefficient of the first key different from the first one is 1
+    let second_key = keys.iter().find(|k| **k != keys[0]);
+    let tweaked_keys = keys
+        .iter()
+        .zip(&serialized)
+        .map(|(key, ser)| {
+            if Some(key) == second_key {
+                return Ok(*key);
+            }
+            let coefficient = tagged_hash("KeyAgg coefficient", &[&list_hash, ser]);
+            let scalar =
+                Scalar::from_be_bytes(coefficient).map_err(|_| s!("invalid key coefficient"))?;
+            key.mul_tweak(&secp, &scalar).map_err(|e| e.to_string())
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let aggregated = PublicKey::combine_keys(&tweaked_keys.iter().collect::<Vec<_>>())
+        .map_err(|e| e.to_string())?;
+    Ok(aggregated.x_only_public_key().0)
+}
+
+/// Leaf spendable by the claim key revealing the preimage of the payment hash, also checking the
+/// preimage size for reverse swaps
+fn claim_script(payment_hash: &PaymentHash, claim_key: XOnlyPublicKey, reverse: bool) -> ScriptBuf {
+    let hash = ripemd160::Hash::hash(&payment_hash.0).to_byte_array();
+    let mut builder = Builder::new();
+    if reverse {
+        builder = builder
+            .push_opcode(OP_SIZE)
+            .push_int(32)
+            .push_opcode(OP_EQUALVERIFY);
+    }
+    builder
+        .push_opcode(OP_HASH160)
+        .push_slice(hash)
+        .push_opcode(OP_EQUALVERIFY)
+        .push_x_only_key(&claim_key)
+        .push_opcode(OP_CHECKSIG)
+        .into_script()
+}
+
+/// Leaf spendable by the refund key after the timeout
+fn refund_script(refund_key: XOnlyPublicKey, timeout_block_height: u32) -> ScriptBuf {
+    Builder::new()
+        .push_x_only_key(&refund_key)
+        .push_opcode(OP_CHECKSIGVERIFY)
+        .push_int(timeout_block_height as i64)
+        .push_opcode(OP_CLTV)
+        .into_script()
+}
+
+fn swap_spend_info(
+    claim_script: ScriptBuf,
+    refund_script: ScriptBuf,
+    provider_pubkey: &PublicKey,
+    our_pubkey: &PublicKey,
+) -> Result<TaprootSpendInfo, String> {
+    let internal_key = aggregate_keys(&[*provider_pubkey, *our_pubkey])?;
+    TaprootBuilder::new()
+        .add_leaf(1, claim_script)
+        .and_then(|b| b.add_leaf(1, refund_script))
+        .map_err(|e| e.to_string())?
+        .finalize(&Secp256k1::verification_only(), internal_key)
+        .map_err(|_| s!("incomplete swap tree"))
+}
+
+/// Check the swap tree and the lockup address returned by the provider match the ones built from
+/// the terms of the swap, so the lockup output can be swept without the provider
+pub(crate) fn check_swap_output(
+    swap_tree: &SwapTree,
+    swap: &SubmarineSwapInfo,
+    network: Network,
+) -> Result<(), APIError> {
+    let (claim_script, refund_script) = swap.scripts();
+    let leaf_version = LeafVersion::TapScript.to_consensus();
+    if swap_tree.claim_leaf.version != leaf_version
+        || swap_tree.refund_leaf.version != leaf_version
+        || swap_tree.claim_leaf.output != hex_str(claim_script.as_bytes())
+        || swap_tree.refund_leaf.output != hex_str(refund_script.as_bytes())
+    {
+        return Err(APIError::FailedSwapProviderRequest(s!(
+            "unexpected swap tree"
+        )));
+    }
+    let lockup_script = swap
+        .lockup_script(network)
+        .map_err(APIError::FailedSwapProviderRequest)?;
+    let spend_info = swap
+        .spend_info()
+        .map_err(APIError::FailedSwapProviderRequest)?;
+    let expected_script = ScriptBuf::new_p2tr_tweaked(spend_info.output_key());
+    if lockup_script != expected_script {
+        return Err(APIError::FailedSwapProviderRequest(s!(
+            "lockup address doesn't match the swap tree"
+        )));
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct SubmarineSwapInfo {
+    pub(crate) kind: SubmarineSwapKind,
+    pub(crate) status: SubmarineSwapStatus,
+    pub(crate) amt_sat: u64,
+    pub(crate) onchain_amt_sat: u64,
+    pub(crate) payment_hash: PaymentHash,
+    pub(crate) preimage: Option<PaymentPreimage>,
+    pub(crate) secret_key: SecretKey,
+    pub(crate) provider_pubkey: PublicKey,
+    pub(crate) timeout_block_height: u32,
+    pub(crate) lockup_address: String,
+    pub(crate) lockup_txid: Option<String>,
+    pub(crate) lockup_vout: Option<u32>,
+    pub(crate) sweep_address: String,
+    pub(crate) sweep_txid: Option<String>,
+    pub(crate) created_at: u64,
+    pub(crate) updated_at: u64,
+    pub(crate) error: Option<String>,
+}
+
+impl_writeable_tlv_based!(SubmarineSwapInfo, {
+    (0, kind, required),
+    (2, status, required),
+    (4, amt_sat, required),
+    (6, onchain_amt_sat, required),
+    (8, payment_hash, required),
+    (10, preimage, option),
+    (12, secret_key, required),
+    (14, provider_pubkey, required),
+    (16, timeout_block_height, required),
+    (18, lockup_address, required),
+    (20, lockup_txid, option),
+    (22, lockup_vout, option),
+    (24, sweep_address, required),
+    (26, sweep_txid, option),
+    (28, created_at, required),
+    (30, updated_at, required),
+    (32, error, option),
+});
+
+impl SubmarineSwapInfo {
+    fn our_pubkey(&self) -> PublicKey {
+        self.secret_key.public_key(&Secp256k1::signing_only())
+    }
+
+    /// Scripts of the claim and refund leaves, the provider claiming swaps in and us claiming
+    /// swaps out
+    fn scripts(&self) -> (ScriptBuf, ScriptBuf) {
+        let our_key = self.our_pubkey().x_only_public_key().0;
+        let provider_key = self.provider_pubkey.x_only_public_key().0;
+        match self.kind {
+            SubmarineSwapKind::In => (
+                claim_script(&self.payment_hash, provider_key, false),
+                refund_script(our_key, self.timeout_block_height),
+            ),
+            SubmarineSwapKind::Out => (
+                claim_script(&self.payment_hash, our_key, true),
+                refund_script(provider_key, self.timeout_block_height),
+            ),
+        }
+    }
+
+    fn spend_info(&self) -> Result<TaprootSpendInfo, String> {
+        let (claim_script, refund_script) = self.scripts();
+        swap_spend_info(
+            claim_script,
+            refund_script,
+            &self.provider_pubkey,
+            &self.our_pubkey(),
+        )
+    }
+
+    fn lockup_script(&self, network: Network) -> Result<ScriptBuf, String> {
+        address_script(&self.lockup_address, network)
+    }
+
+    pub(crate) fn to_submarine_swap(&self, id: String) -> SubmarineSwap {
+        SubmarineSwap {
+            id,
+            kind: self.kind,
+            status: self.status,
+            amt_sat: self.amt_sat,
+            onchain_amt_sat: self.onchain_amt_sat,
+            payment_hash: hex_str(&self.payment_hash.0),
+            timeout_block_height: self.timeout_block_height,
+            lockup_address: self.lockup_address.clone(),
+            lockup_txid: self.lockup_txid.clone(),
+            sweep_address: self.sweep_address.clone(),
+            sweep_txid: self.sweep_txid.clone(),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            error: self.error.clone(),
+        }
+    }
+}
+
+fn address_script(address: &str, network: Network) -> Result<ScriptBuf, String> {
+    Ok(Address::from_str(address)
+        .map_err(|e| e.to_string())?
+        .require_network(network)
+        .map_err(|e| e.to_string())?
+        .script_pubkey())
+}
+
+pub(crate) struct SubmarineSwapMap {
+    pub(crate) swaps: LdkHashMap<String, SubmarineSwapInfo>,
+}
+
+impl_writeable_tlv_based!(SubmarineSwapMap, {
+    (0, swaps, required),
+});
+
+impl UnlockedAppState {
+    pub(crate) fn add_submarine_swap(&self, swap_id: String, swap: SubmarineSwapInfo) {
+        let mut submarine_swaps = self.get_submarine_swaps();
+        submarine_swaps.swaps.insert(swap_id, swap);
+        self.save_submarine_swaps(submarine_swaps);
+    }
+
+    pub(crate) fn submarine_swaps(&self) -> LdkHashMap<String, SubmarineSwapInfo> {
+        self.get_submarine_swaps().swaps.clone()
+    }
+
+    fn update_submarine_swap(&self, swap_id: &str, update: impl FnOnce(&mut SubmarineSwapInfo)) {
+        let mut submarine_swaps = self.get_submarine_swaps();
+        if let Some(swap) = submarine_swaps.swaps.get_mut(swap_id) {
+            update(swap);
+            swap.updated_at = get_current_timestamp();
+        }
+        self.save_submarine_swaps(submarine_swaps);
+    }
+
+    fn save_submarine_swaps(&self, submarine_swaps: MutexGuard<SubmarineSwapMap>) {
+        self.kv_store
+            .write("", "", SUBMARINE_SWAPS_FNAME, submarine_swaps.encode())
+            .unwrap();
+    }
+
+    fn fail_submarine_swap(&self, swap_id: &str, error: String) {
+        tracing::error!("Submarine swap {swap_id} has failed: {error}");
+        self.update_submarine_swap(swap_id, |s| {
+            s.status = SubmarineSwapStatus::Failed;
+            s.error = Some(error);
+        });
+    }
+
+    /// Spend the lockup output of the swap to its sweep address, through the claim leaf
+    /// (revealing the preimage) for swaps out or the refund leaf for swaps in
+    fn sweep_submarine_swap(
+        &self,
+        swap: &SubmarineSwapInfo,
+        network: Network,
+    ) -> Result<Txid, String> {
+        let (Some(lockup_txid), Some(lockup_vout)) = (&swap.lockup_txid, swap.lockup_vout) else {
+            return Err(s!("missing lockup output"));
+        };
+        let lockup_txout = TxOut {
+            value: Amount::from_sat(swap.onchain_amt_sat),
+            script_pubkey: swap.lockup_script(network)?,
+        };
+        let destination = address_script(&swap.sweep_address, network)?;
+        let feerate_sat_per_kw = self
+            .bitcoind_client
+            .get_est_sat_per_1000_weight(ConfirmationTarget::UrgentOnChainSweep);
+        let fee_sat = feerate_sat_per_kw as u64 * SWEEP_TX_WEIGHT / 1000;
+        let output_value = Amount::from_sat(swap.onchain_amt_sat.saturating_sub(fee_sat));
+        if output_value < destination.minimal_non_dust() {
+            return Err(format!(
+                "the lockup output cannot pay the sweep fee of {fee_sat} sats"
+            ));
+        }
+
+        let (claim_script, refund_script) = swap.scripts();
+        let (script, lock_time, preimage) = match swap.kind {
+            SubmarineSwapKind::In => (
+                refund_script,
+                LockTime::from_height(swap.timeout_block_height).map_err(|e| e.to_string())?,
+                None,
+            ),
+            SubmarineSwapKind::Out => (claim_script, LockTime::ZERO, swap.preimage),
+        };
+        let mut tx = Transaction {
+            version: Version::TWO,
+            lock_time,
+            input: vec![TxIn {
+                previous_output: OutPoint {
+                    txid: Txid::from_str(lockup_txid).map_err(|e| e.to_string())?,
+                    vout: lockup_vout,
+                },
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_LOCKTIME_NO_RBF,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: output_value,
+                script_pubkey: destination,
+            }],
+        };
+
+        let leaf_hash = TapLeafHash::from_script(&script, LeafVersion::TapScript);
+        let sighash = SighashCache::new(&tx)
+            .taproot_script_spend_signature_hash(
+                0,
+                &Prevouts::All(&[lockup_txout]),
+                leaf_hash,
+                TapSighashType::Default,
+            )
+            .map_err(|e| e.to_string())?;
+        let secp = Secp256k1::new();
+        let keypair = Keypair::from_secret_key(&secp, &swap.secret_key);
+        let signature =
+            secp.sign_schnorr_no_aux_rand(&Message::from_digest(sighash.to_byte_array()), &keypair);
+        let control_block = swap
+            .spend_info()?
+            .control_block(&(script.clone(), LeafVersion::TapScript))
+            .ok_or_else(|| s!("missing control block"))?;
+        let mut witness = Witness::new();
+        witness.push(signature.serialize());
+        if let Some(preimage) = preimage {
+            witness.push(preimage.0);
+        }
+        witness.push(script.as_bytes());
+        witness.push(control_block.serialize());
+        tx.input[0].witness = witness;
+
+        self.bitcoind_client.broadcast_transactions(&[&tx]);
+        Ok(tx.compute_txid())
+    }
+
+    /// Claim the lockup outputs of the swaps out, refund the ones of the expired swaps in and
+    /// complete the swaps whose LN payment has succeeded
+    pub(crate) async fn process_submarine_swaps(
+        &self,
+        provider: &SwapProviderClient,
+        network: Network,
+    ) {
+        let height = self.channel_manager.current_best_block().height;
+        for (swap_id, swap) in self.submarine_swaps() {
+            match (swap.kind, swap.status) {
+                (SubmarineSwapKind::In, SubmarineSwapStatus::Pending) => {
+                    let payment_status = self
+                        .inbound_payments()
+                        .get(&swap.payment_hash)
+                        .map(|p| p.status);
+                    if payment_status == Some(HTLCStatus::Succeeded) {
+                        tracing::info!("Submarine swap {swap_id} has succeeded");
+                        self.update_submarine_swap(&swap_id, |s| {
+                            s.status = SubmarineSwapStatus::Succeeded
+                        });
+                        continue;
+                    }
+                    if height < swap.timeout_block_height {
+                        continue;
+                    }
+                    match self.sweep_submarine_swap(&swap, network) {
+                        Ok(txid) => {
+                            tracing::info!("Refunded submarine swap {swap_id} with TX {txid}");
+                            self.update_submarine_swap(&swap_id, |s| {
+                                s.status = SubmarineSwapStatus::Refunded;
+                                s.sweep_txid = Some(txid.to_string());
+                            });
+                        }
+                        Err(e) => self.fail_submarine_swap(&swap_id, format!("refund failed: {e}")),
+                    }
+                }
+                (SubmarineSwapKind::Out, SubmarineSwapStatus::Pending) => {
+                    let payment_id = PaymentId(swap.payment_hash.0);
+                    let payment_status =
+                        self.outbound_payments().get(&payment_id).map(|p| p.status);
+                    if payment_status == Some(HTLCStatus::Failed) {
+                        self.fail_submarine_swap(&swap_id, s!("LN payment failed"));
+                        continue;
+                    }
+                    if height >= swap.timeout_block_height {
+                        self.fail_submarine_swap(&swap_id, s!("timed out"));
+                        continue;
+                    }
+                    if swap.lockup_txid.is_none() {
+                        let Ok(lockup_tx) = provider.reverse_lockup_transaction(&swap_id).await
+                        else {
+                            continue;
+                        };
+                        let Ok(lockup_script) = swap.lockup_script(network) else {
+                            continue;
+                        };
+                        let Some((vout, txout)) = lockup_tx
+                            .output
+                            .iter()
+                            .enumerate()
+                            .find(|(_, o)| o.script_pubkey == lockup_script)
+                        else {
+                            continue;
+                        };
+                        if txout.value.to_sat() < swap.onchain_amt_sat {
+                            self.fail_submarine_swap(
+                                &swap_id,
+                                format!("lockup of {} sats is too low", txout.value.to_sat()),
+                            );
+                            continue;
+                        }
+                        let lockup_txid = lockup_tx.compute_txid().to_string();
+                        let lockup_value = txout.value.to_sat();
+                        self.update_submarine_swap(&swap_id, |s| {
+                            s.lockup_txid = Some(lockup_txid);
+                            s.lockup_vout = Some(vout as u32);
+                            s.onchain_amt_sat = lockup_value;
+                        });
+                        continue;
+                    }
+                    let (Some(lockup_txid), Some(lockup_vout)) =
+                        (&swap.lockup_txid, swap.lockup_vout)
+                    else {
+                        continue;
+                    };
+                    let confirmations = self
+                        .bitcoind_client
+                        .get_txout_confirmations(&Txid::from_str(lockup_txid).unwrap(), lockup_vout)
+                        .await
+                        .ok()
+                        .flatten();
+                    if !confirmations.is_some_and(|c| c >= LOCKUP_MIN_CONFIRMATIONS) {
+                        continue;
+                    }
+                    match self.sweep_submarine_swap(&swap, network) {
+                        Ok(txid) => {
+                            tracing::info!("Claimed submarine swap {swap_id} with TX {txid}");
+                            self.update_submarine_swap(&swap_id, |s| {
+                                s.status = SubmarineSwapStatus::Claimed;
+                                s.sweep_txid = Some(txid.to_string());
+                            });
+                        }
+                        Err(e) => self.fail_submarine_swap(&swap_id, format!("claim failed: {e}")),
+                    }
+                }
+                (SubmarineSwapKind::Out, SubmarineSwapStatus::Claimed) => {
+                    let payment_id = PaymentId(swap.payment_hash.0);
+                    let payment_status =
+                        self.outbound_payments().get(&payment_id).map(|p| p.status);
+                    if payment_status == Some(HTLCStatus::Succeeded) {
+                        tracing::info!("Submarine swap {swap_id} has succeeded");
+                        self.update_submarine_swap(&swap_id, |s| {
+                            s.status = SubmarineSwapStatus::Succeeded
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/abandon_payment/";
+
+async fn abandon_payment_res(node_address: SocketAddr, payment_hash: &str) -> Response {
+    let payload = AbandonPaymentRequest {
+        payment_hash: payment_hash.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/abandonpayment"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn abandon_payment() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(100000),
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    let res = abandon_payment_res(node1_addr, &"00".repeat(32)).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Payment not found",
+        "PaymentNotFound",
+    )
+    .await;
+
+    // the HTLC is held by the payee, so the payment stays pending after being abandoned
+    let HodlInvoiceResponse {
+        invoice,
+        payment_hash,
+        ..
+    } = hodl_invoice(node2_addr, Some(5000000), None, None, None, false).await;
+    send_payment_raw(node1_addr, invoice.clone()).await;
+    wait_for_invoice_status(node2_addr, &invoice, InvoiceStatus::Held).await;
+    let res = abandon_payment_res(node1_addr, &payment_hash).await;
+    _check_response_is_ok(res).await;
+    assert_eq!(
+        get_payment(node1_addr, &payment_hash).await.status,
+        HTLCStatus::Pending
+    );
+
+    // it fails once the HTLC gets resolved
+    cancel_invoice(node2_addr, &payment_hash).await;
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Failed).await;
+    let res = abandon_payment_res(node1_addr, &payment_hash).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Payment is not pending",
+        "PaymentNotPending",
+    )
+    .await;
+
+    shutdown(&[node1_addr, node2_addr]).await;
+}
@@ -0,0 +1,128 @@
+use crate::utils::get_current_timestamp;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/accounting_export/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn accounting_export() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+    let funding_txid = channel.funding_txid.unwrap();
+
+    let LNInvoiceResponse { invoice } = ln_invoice(node2_addr, None, None, None, 900).await;
+    let payment_hash = send_payment(node1_addr, invoice).await.payment_hash;
+
+    let entries = export_accounting(node1_addr, &ExportAccountingRequest::default())
+        .await
+        .entries;
+    assert!(entries.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+    let open = entries
+        .iter()
+        .find(|e| e.id == format!("onchain:{funding_txid}"))
+        .unwrap();
+    assert_eq!(open.kind, AccountingEntryKind::ChannelOpen);
+    assert_eq!(open.reference, funding_txid);
+    assert!(open.amount_msat < 0);
+    assert!(open.fee_msat > 0);
+    assert!(entries
+        .iter()
+        .any(|e| e.kind == AccountingEntryKind::OnchainTx && e.amount_msat > 0));
+    let sent = entries
+        .iter()
+        .find(|e| e.id == format!("payment_sent:{payment_hash}"))
+        .unwrap();
+    assert_eq!(sent.kind, AccountingEntryKind::PaymentSent);
+    assert_eq!(sent.amount_msat, -3000000);
+    assert_eq!(sent.fee_msat, 0);
+    assert_eq!(sent.reference, payment_hash);
+    let issuance = entries
+        .iter()
+        .find(|e| e.kind == AccountingEntryKind::AssetTransfer)
+        .unwrap();
+    assert_eq!(issuance.asset_id, Some(asset_id));
+    assert_eq!(issuance.asset_amount, Some(1000));
+    assert_eq!(issuance.amount_msat, 0);
+
+    let entries = export_accounting(node2_addr, &ExportAccountingRequest::default())
+        .await
+        .entries;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].id, format!("payment_received:{payment_hash}"));
+    assert_eq!(entries[0].kind, AccountingEntryKind::PaymentReceived);
+    assert_eq!(entries[0].amount_msat, 3000000);
+
+    // time range
+    let payload = ExportAccountingRequest {
+        start_time: Some(get_current_timestamp() + 3600),
+        ..Default::default()
+    };
+    assert!(export_accounting(node1_addr, &payload)
+        .await
+        .entries
+        .is_empty());
+    let payload = ExportAccountingRequest {
+        start_time: Some(2),
+        end_time: Some(1),
+        ..Default::default()
+    };
+    let res = reqwest::Client::new()
+        .get(format!("http://{node1_addr}/export/accounting"))
+        .query(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "start_time cannot be after end_time",
+        "InvalidPagination",
+    )
+    .await;
+
+    // CSV
+    let payload = ExportAccountingRequest {
+        format: Some(ExportFormat::Csv),
+        ..Default::default()
+    };
+    let res = reqwest::Client::new()
+        .get(format!("http://{node2_addr}/export/accounting"))
+        .query(&payload)
+        .send()
+        .await
+        .unwrap();
+    let csv = _check_response_is_ok(res).await.text().await.unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        lines,
+        vec![
+            "id,timestamp,kind,amount_msat,fee_msat,asset_id,asset_amount,reference",
+            &format!(
+                "payment_received:{payment_hash},{},PaymentReceived,3000000,0,,,{payment_hash}",
+                entries[0].timestamp
+            ),
+        ]
+    );
+}
@@ -0,0 +1,53 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/address_gap_limit/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn address_gap_limit() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    // issue addresses without using them until the gap limit is reached
+    let mut addresses = vec![];
+    for _ in 0..DEFAULT_ADDRESS_GAP_LIMIT {
+        addresses.push(address(node1_addr).await);
+    }
+    let stats = address_stats(node1_addr).await;
+    assert_eq!(stats.issued, DEFAULT_ADDRESS_GAP_LIMIT);
+    assert_eq!(stats.used, 0);
+    assert_eq!(stats.gap, DEFAULT_ADDRESS_GAP_LIMIT);
+    assert_eq!(stats.gap_limit, DEFAULT_ADDRESS_GAP_LIMIT);
+    assert_eq!(stats.unused_addresses, addresses);
+
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/address"))
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        &format!("Address gap limit ({DEFAULT_ADDRESS_GAP_LIMIT}) reached"),
+        "AddressGapLimitReached",
+    )
+    .await;
+
+    // using an address resets the gap for the ones issued before it
+    let used_address = addresses[4].clone();
+    _fund_wallet(used_address.clone());
+    mine(false);
+    let stats = address_stats(node1_addr).await;
+    assert_eq!(stats.used, 1);
+    assert_eq!(stats.gap, DEFAULT_ADDRESS_GAP_LIMIT - 5);
+    assert!(!stats.unused_addresses.contains(&used_address));
+
+    let new_address = address(node1_addr).await;
+    assert!(!addresses.contains(&new_address));
+    let stats = address_stats(node1_addr).await;
+    assert_eq!(stats.issued, DEFAULT_ADDRESS_GAP_LIMIT + 1);
+    assert_eq!(stats.gap, DEFAULT_ADDRESS_GAP_LIMIT - 4);
+}
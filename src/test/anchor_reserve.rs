@@ -0,0 +1,96 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/anchor_reserve/";
+
+async fn wait_for_anchor_reserve_utxos(
+    node_address: SocketAddr,
+    num: usize,
+) -> AnchorReserveResponse {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let reserve = anchor_reserve(node_address).await;
+        if reserve.utxos.len() == num {
+            return reserve;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 70.0 {
+            panic!("anchor reserve UTXOs have not been created")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn anchor_reserve_maintenance() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let args = UserArgs {
+        storage_dir_path: test_dir_node1.clone().into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        anchor_reserve_utxos: 2,
+        ..Default::default()
+    };
+    let (node1_addr, _) = start_node_with_args(args, false).await;
+
+    // the reserve cannot be created without funds
+    let t_0 = OffsetDateTime::now_utc();
+    let reserve = loop {
+        let reserve = anchor_reserve(node1_addr).await;
+        if reserve.depleted {
+            break reserve;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("anchor reserve has not been reported as depleted")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+    assert_eq!(reserve.target_utxos, 2);
+    assert_eq!(reserve.utxo_size_sat, DEFAULT_ANCHOR_RESERVE_UTXO_SIZE_SAT);
+    assert!(reserve.utxos.is_empty());
+    assert!(reserve
+        .events
+        .iter()
+        .any(|e| e.kind == AnchorReserveEventKind::Depleted));
+
+    // once funded, the missing UTXOs are created
+    fund_and_create_utxos(node1_addr, None).await;
+    let reserve = wait_for_anchor_reserve_utxos(node1_addr, 2).await;
+    assert!(!reserve.depleted);
+    assert!(reserve
+        .utxos
+        .iter()
+        .all(|u| u.amount_sat == DEFAULT_ANCHOR_RESERVE_UTXO_SIZE_SAT));
+    let created = reserve
+        .events
+        .iter()
+        .filter(|e| e.kind == AnchorReserveEventKind::Created)
+        .count();
+    assert_eq!(created, 2);
+    assert_eq!(
+        reserve.events.last().unwrap().kind,
+        AnchorReserveEventKind::Replenished
+    );
+
+    mine(false);
+    let reserve = anchor_reserve(node1_addr).await;
+    let events_len = reserve.events.len();
+    assert!(reserve
+        .utxos
+        .iter()
+        .all(|u| u.confirmations.is_some_and(|c| c > 0)));
+
+    // the reserve is persisted across restarts
+    shutdown(&[node1_addr]).await;
+    let args = UserArgs {
+        storage_dir_path: test_dir_node1.into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        anchor_reserve_utxos: 2,
+        ..Default::default()
+    };
+    let (node1_addr, _) = start_node_with_args(args, true).await;
+    let reserve = anchor_reserve(node1_addr).await;
+    assert_eq!(reserve.utxos.len(), 2);
+    assert_eq!(reserve.events.len(), events_len);
+}
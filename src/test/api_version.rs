@@ -0,0 +1,100 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/api_version/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn api_version() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    // versioned and unversioned paths are served by the same APIs
+    let res = reqwest::Client::new()
+        .get(format!("http://{node1_addr}/v1/nodeinfo"))
+        .send()
+        .await
+        .unwrap();
+    let versioned_node_info = _check_response_is_ok(res)
+        .await
+        .json::<NodeInfoResponse>()
+        .await
+        .unwrap();
+    assert_eq!(
+        versioned_node_info.pubkey,
+        node_info(node1_addr).await.pubkey
+    );
+
+    // /v2 serves the same APIs, with paginated lists and structured errors
+    let res = reqwest::Client::new()
+        .get(format!("http://{node1_addr}/v2/nodeinfo"))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+    let res = reqwest::Client::new()
+        .get(format!("http://{node1_addr}/v2/listchannels"))
+        .send()
+        .await
+        .unwrap();
+    let channels = _check_response_is_ok(res)
+        .await
+        .json::<Page<Channel>>()
+        .await
+        .unwrap();
+    assert!(channels.items.is_empty());
+    assert_eq!(channels.total, 0);
+    assert!(channels.next_cursor.is_none());
+    for (param, value, message) in [
+        ("cursor", "abc", "invalid cursor abc"),
+        ("offset", "2", "offset is only accepted by the /v1 API"),
+    ] {
+        let res = reqwest::Client::new()
+            .get(format!("http://{node1_addr}/v2/listpayments"))
+            .query(&[(param, value)])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+        let api_error_response = res.json::<APIErrorResponseV2>().await.unwrap();
+        assert_eq!(api_error_response.code, "InvalidPagination");
+        assert!(api_error_response.message.contains(message));
+        assert!(api_error_response.details.is_none());
+    }
+
+    // /v1 keeps the error shape it was released with
+    let res = reqwest::Client::new()
+        .get(format!("http://{node1_addr}/v1/listpayments"))
+        .query(&[("limit", "0")])
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "limit must be between 1 and 1000",
+        "InvalidPagination",
+    )
+    .await;
+
+    // the spec documents the served APIs
+    let res = reqwest::Client::new()
+        .get(format!("http://{node1_addr}/v2/spec"))
+        .send()
+        .await
+        .unwrap();
+    let res = _check_response_is_ok(res).await;
+    assert_eq!(
+        res.headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok()),
+        Some("application/yaml")
+    );
+    let spec = res.text().await.unwrap();
+    assert!(spec.starts_with("openapi: 3"));
+    for path in ["/nodeinfo:", "/sendpayment:", "/hodlinvoice:", "/spec:"] {
+        assert!(spec.contains(path), "{path} missing from the spec");
+    }
+}
@@ -0,0 +1,106 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/asset_audit/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn asset_audit_supply() {
+    initialize();
+
+    let test_dir_base = format!("{TEST_DIR_BASE}supply/");
+    let test_dir_node1 = format!("{test_dir_base}node1");
+    let test_dir_node2 = format!("{test_dir_base}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let audit = asset_audit(node1_addr, &asset_id).await;
+    assert_eq!(audit.asset_id, asset_id);
+    assert_eq!(audit.issued_supply, 1000);
+    assert_eq!(audit.issued_locally, 1000);
+    assert_eq!(audit.received, 0);
+    assert_eq!(audit.sent, 0);
+    assert_eq!(audit.held_onchain, 1000);
+    assert_eq!(audit.held_offchain, 0);
+    assert_eq!(audit.burned, 0);
+    assert!(audit.anomalies.is_empty());
+
+    // on-chain send
+    let recipient_id = rgb_invoice(node2_addr, None, false).await.recipient_id;
+    send_asset(
+        node1_addr,
+        &asset_id,
+        Assignment::Fungible(400),
+        recipient_id,
+        None,
+    )
+    .await;
+    mine(false);
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node1_addr).await;
+
+    let audit = asset_audit(node1_addr, &asset_id).await;
+    assert_eq!(audit.sent, 400);
+    assert_eq!(audit.held_onchain, 600);
+    assert_eq!(audit.burned, 0);
+    assert!(audit.anomalies.is_empty());
+    let audit = asset_audit(node2_addr, &asset_id).await;
+    assert_eq!(audit.issued_supply, 1000);
+    assert_eq!(audit.issued_locally, 0);
+    assert_eq!(audit.received, 400);
+    assert_eq!(audit.held_onchain, 400);
+    assert_eq!(audit.burned, 0);
+
+    // channel funding is reported as held offchain, not as sent
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(500),
+        Some(&asset_id),
+    )
+    .await;
+    let audit = asset_audit(node1_addr, &asset_id).await;
+    assert_eq!(audit.sent, 400);
+    assert_eq!(audit.held_onchain, 100);
+    assert_eq!(audit.held_offchain, 500);
+    assert_eq!(audit.burned, 0);
+    assert!(audit.anomalies.is_empty());
+
+    // unknown and invalid asset IDs
+    let res = reqwest::Client::new()
+        .get(format!(
+            "http://{node1_addr}/assetaudit/rgb:CJkb4YZw-jRiz2sk-~PARPio-wtVYI1c-XAEYCqO-wTfvRZ8"
+        ))
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown RGB contract ID",
+        "UnknownContractId",
+    )
+    .await;
+    let res = reqwest::Client::new()
+        .get(format!("http://{node1_addr}/assetaudit/invalid"))
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid asset ID: invalid",
+        "InvalidAssetID",
+    )
+    .await;
+}
@@ -0,0 +1,222 @@
+use lightning::ln::types::ChannelId;
+
+use crate::disk::{read_asset_htlc_minimums_info, ASSET_HTLC_MINIMUMS_FNAME};
+use crate::routes::HTLC_MIN_MSAT;
+use crate::utils::LDK_DIR;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/asset_htlc_min/";
+
+fn remote_htlc_min_msat(test_dir: &str, channel_id: &str) -> Option<u64> {
+    let channel_id = ChannelId(hex_str_to_vec(channel_id).unwrap().try_into().unwrap());
+    let path = PathBuf::from(test_dir)
+        .join(LDK_DIR)
+        .join(ASSET_HTLC_MINIMUMS_FNAME);
+    read_asset_htlc_minimums_info(&path)
+        .channels
+        .get(&channel_id)
+        .and_then(|info| info.remote_msat)
+}
+
+async fn wait_for_remote_htlc_min_msat(test_dir: &str, channel_id: &str, expected: Option<u64>) {
+    let t_0 = OffsetDateTime::now_utc();
+    while remote_htlc_min_msat(test_dir, channel_id) != expected {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 20.0 {
+            panic!("remote asset HTLC minimum is not {expected:?}")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn asset_htlc_min() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+
+    // both sides announce their minimum when the channel gets opened
+    wait_for_remote_htlc_min_msat(&test_dir_node1, &channel.channel_id, Some(HTLC_MIN_MSAT)).await;
+    wait_for_remote_htlc_min_msat(&test_dir_node2, &channel.channel_id, Some(HTLC_MIN_MSAT)).await;
+    for node_addr in [node1_addr, node2_addr] {
+        let channels = list_channels(node_addr).await;
+        let chan = channels
+            .iter()
+            .find(|c| c.channel_id == channel.channel_id)
+            .unwrap();
+        assert_eq!(chan.asset_htlc_min_msat, Some(HTLC_MIN_MSAT));
+    }
+
+    let LNInvoiceResponse { invoice } = ln_invoice(
+        node2_addr,
+        Some(HTLC_MIN_MSAT),
+        Some(&asset_id),
+        Some(100),
+        900,
+    )
+    .await;
+    let payment = send_payment(node1_addr, invoice).await;
+    wait_for_ln_payment(node2_addr, &payment.payment_hash, HTLCStatus::Succeeded).await;
+
+    // vanilla channels carry no asset HTLC minimum
+    let vanilla_channel = open_channel(
+        node2_addr,
+        &node1_pubkey,
+        Some(NODE1_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+    let channels = list_channels(node2_addr).await;
+    let chan = channels
+        .iter()
+        .find(|c| c.channel_id == vanilla_channel.channel_id)
+        .unwrap();
+    assert!(chan.asset_htlc_min_msat.is_none());
+
+    // the agreement is dropped with the channel
+    close_channel(node1_addr, &channel.channel_id, &node2_pubkey, false).await;
+    wait_for_remote_htlc_min_msat(&test_dir_node1, &channel.channel_id, None).await;
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn asset_htlc_min_configured() {
+    initialize();
+
+    let asset_htlc_min_msat = HTLC_MIN_MSAT * 2;
+    let test_dir_node1 = format!("{TEST_DIR_BASE}configured_node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}configured_node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node_with_args(
+        UserArgs {
+            storage_dir_path: test_dir_node2.clone().into(),
+            ldk_peer_listening_port: NODE2_PEER_PORT,
+            asset_htlc_min_msat,
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+
+    // the higher minimum announced by node 2 applies to the channel
+    wait_for_remote_htlc_min_msat(
+        &test_dir_node1,
+        &channel.channel_id,
+        Some(asset_htlc_min_msat),
+    )
+    .await;
+    let channels = list_channels(node1_addr).await;
+    let chan = channels
+        .iter()
+        .find(|c| c.channel_id == channel.channel_id)
+        .unwrap();
+    assert_eq!(chan.asset_htlc_min_msat, Some(asset_htlc_min_msat));
+
+    // asset invoices can't carry less than the minimum
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(HTLC_MIN_MSAT),
+        expiry_sec: 900,
+        asset_id: Some(asset_id.clone()),
+        asset_amount: Some(100),
+        ..Default::default()
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/lninvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        &format!("amt_msat cannot be less than {asset_htlc_min_msat}"),
+        "InvalidAmount",
+    )
+    .await;
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(asset_htlc_min_msat),
+        ..payload
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/lninvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let LNInvoiceResponse { invoice } = _check_response_is_ok(res)
+        .await
+        .json::<LNInvoiceResponse>()
+        .await
+        .unwrap();
+    assert_eq!(
+        decode_ln_invoice(node1_addr, &invoice).await.amt_msat,
+        Some(asset_htlc_min_msat)
+    );
+    let payment = send_payment(node1_addr, invoice).await;
+    wait_for_ln_payment(node2_addr, &payment.payment_hash, HTLCStatus::Succeeded).await;
+
+    // a changed minimum is announced again to the peer on reconnection
+    shutdown(&[node1_addr]).await;
+    let (node1_addr, _) = start_node_with_args(
+        UserArgs {
+            storage_dir_path: test_dir_node1.clone().into(),
+            ldk_peer_listening_port: NODE1_PEER_PORT,
+            asset_htlc_min_msat: asset_htlc_min_msat * 2,
+            ..Default::default()
+        },
+        true,
+    )
+    .await;
+    wait_for_remote_htlc_min_msat(
+        &test_dir_node2,
+        &channel.channel_id,
+        Some(asset_htlc_min_msat * 2),
+    )
+    .await;
+
+    shutdown(&[node1_addr, node2_addr]).await;
+}
@@ -0,0 +1,209 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/async_payments/";
+
+fn parse_scid(scid: &str) -> u64 {
+    let parts: Vec<u64> = scid.split('x').map(|p| p.parse().unwrap()).collect();
+    parts[0] << 40 | parts[1] << 16 | parts[2]
+}
+
+async fn async_recipient_scope_res(
+    node_address: SocketAddr,
+    peer_pubkey: &str,
+    hold_timeout_sec: Option<u64>,
+) -> Response {
+    let payload = InterceptScopesRequest {
+        kind: InterceptScopeKind::AsyncRecipient,
+        peer_pubkey: Some(peer_pubkey.to_string()),
+        payment_hash: None,
+        hold_timeout_sec,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/interceptscopes"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn async_recipient_scope(
+    node_address: SocketAddr,
+    peer_pubkey: &str,
+    hold_timeout_sec: Option<u64>,
+) -> InterceptScopesResponse {
+    let res = async_recipient_scope_res(node_address, peer_pubkey, hold_timeout_sec).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<InterceptScopesResponse>()
+        .await
+        .unwrap()
+}
+
+async fn hinted_invoice(node_address: SocketAddr, hub_pubkey: &str, scid: &str) -> String {
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(5_000_000),
+        min_amt_msat: None,
+        max_amt_msat: None,
+        expiry_sec: 900,
+        asset_id: None,
+        asset_amount: None,
+        route_hints: Some(vec![InvoiceRouteHint {
+            hops: vec![InvoiceRouteHintHop {
+                src_node_id: hub_pubkey.to_string(),
+                short_channel_id: parse_scid(scid),
+                fee_base_msat: 0,
+                fee_proportional_millionths: 0,
+                cltv_expiry_delta: DEFAULT_CLTV_EXPIRY_DELTA,
+                htlc_minimum_msat: None,
+                htlc_maximum_msat: None,
+            }],
+        }]),
+        lsp: None,
+        phantom: None,
+        label: None,
+        fallback_address_type: None,
+        ..Default::default()
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/lninvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<LNInvoiceResponse>()
+        .await
+        .unwrap()
+        .invoice
+}
+
+async fn wait_for_held_payment(node_address: SocketAddr, scope_id: &str) -> InterceptedHtlc {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        if let Some(htlc) = intercepted_htlcs(node_address, scope_id).await.pop() {
+            return htlc;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 20.0 {
+            panic!("no payment has been held")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn async_payments() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+
+    check_response_is_nok(
+        async_recipient_scope_res(node2_addr, &node3_pubkey, Some(0)).await,
+        reqwest::StatusCode::BAD_REQUEST,
+        "hold timeout must be between 1 and",
+        "InvalidInterceptScope",
+    )
+    .await;
+    let payload = InterceptScopesRequest {
+        kind: InterceptScopeKind::Forward,
+        peer_pubkey: Some(node3_pubkey.clone()),
+        payment_hash: None,
+        hold_timeout_sec: Some(60),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/interceptscopes"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "only async recipient scopes have a hold timeout",
+        "InvalidInterceptScope",
+    )
+    .await;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(500_000),
+        None,
+        None,
+        None,
+    )
+    .await;
+    open_channel(
+        node2_addr,
+        &node3_pubkey,
+        Some(NODE3_PEER_PORT),
+        Some(500_000),
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    let scope = async_recipient_scope(node2_addr, &node3_pubkey, None).await;
+    let scid = scope.intercept_scid.unwrap();
+    let expiring_scope = async_recipient_scope(node2_addr, &node3_pubkey, Some(5)).await;
+    let expiring_scid = expiring_scope.intercept_scid.unwrap();
+    let invoice_online = hinted_invoice(node3_addr, &node2_pubkey, &scid).await;
+    let invoice_offline = hinted_invoice(node3_addr, &node2_pubkey, &scid).await;
+    let invoice_expiring = hinted_invoice(node3_addr, &node2_pubkey, &expiring_scid).await;
+
+    // payments to an online recipient are forwarded right away
+    let payment_hash = decode_ln_invoice(node3_addr, &invoice_online)
+        .await
+        .payment_hash;
+    send_payment_raw(node1_addr, invoice_online).await;
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Succeeded).await;
+    wait_for_ln_payment(node3_addr, &payment_hash, HTLCStatus::Succeeded).await;
+    assert!(intercepted_htlcs(node2_addr, &scope.scope_id)
+        .await
+        .is_empty());
+
+    // payments to an offline recipient are held until it reconnects
+    shutdown(&[node3_addr]).await;
+    let payment_hash = decode_ln_invoice(node2_addr, &invoice_offline)
+        .await
+        .payment_hash;
+    send_payment_raw(node1_addr, invoice_offline).await;
+    let htlc = wait_for_held_payment(node2_addr, &scope.scope_id).await;
+    assert_eq!(htlc.kind, InterceptScopeKind::AsyncRecipient);
+    assert_eq!(htlc.payment_hash, payment_hash);
+    assert_eq!(htlc.outbound_amt_msat, Some(5_000_000));
+
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, true).await;
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Succeeded).await;
+    wait_for_ln_payment(node3_addr, &payment_hash, HTLCStatus::Succeeded).await;
+    assert!(intercepted_htlcs(node2_addr, &scope.scope_id)
+        .await
+        .is_empty());
+
+    // payments are failed back when the recipient doesn't reconnect in time
+    shutdown(&[node3_addr]).await;
+    let payment_hash = decode_ln_invoice(node2_addr, &invoice_expiring)
+        .await
+        .payment_hash;
+    send_payment_raw(node1_addr, invoice_expiring).await;
+    wait_for_held_payment(node2_addr, &expiring_scope.scope_id).await;
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Failed).await;
+    assert!(intercepted_htlcs(node2_addr, &expiring_scope.scope_id)
+        .await
+        .is_empty());
+}
@@ -0,0 +1,136 @@
+use bitcoin::secp256k1::{PublicKey, Secp256k1, SecretKey};
+
+use crate::audit::audit_storage_dir;
+use crate::error::AppError;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/audit/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn audit() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(node2_addr, None, Some(&asset_id), Some(100), 900).await;
+    send_payment(node1_addr, invoice).await;
+
+    shutdown(&[node1_addr, node2_addr]).await;
+
+    let auditor_key = SecretKey::from_slice(&[7; 32]).unwrap();
+    let auditor_pubkey = PublicKey::from_secret_key(&Secp256k1::new(), &auditor_key);
+    let auditor_key_path = Path::new(TEST_DIR_BASE).join("auditor_key");
+    std::fs::write(&auditor_key_path, auditor_key.display_secret().to_string()).unwrap();
+
+    // both sides of the RGB channel can be audited without their password
+    let report = audit_storage_dir(
+        Path::new(&test_dir_node1),
+        BitcoinNetwork::Regtest,
+        &auditor_key_path,
+    )
+    .await
+    .unwrap();
+    let summary = &report.summary;
+    assert!(summary.anomalies.is_empty());
+    assert_eq!(summary.channels.len(), 1);
+    let audited_channel = summary.channels.first().unwrap();
+    assert_eq!(audited_channel.channel_id, channel.channel_id);
+    assert_eq!(audited_channel.peer_pubkey, node2_pubkey);
+    assert_eq!(audited_channel.asset_id, Some(asset_id.clone()));
+    assert_eq!(audited_channel.asset_local_amount, Some(500));
+    assert_eq!(audited_channel.asset_remote_amount, Some(100));
+    assert_eq!(audited_channel.consignment_verified, Some(true));
+    assert!(audited_channel.claimable_balance_sat > 0);
+    assert_eq!(
+        summary.offchain_btc_claimable_sat,
+        audited_channel.claimable_balance_sat
+    );
+    let audited_asset = summary
+        .assets
+        .iter()
+        .find(|a| a.asset_id == asset_id)
+        .unwrap();
+    assert_eq!(audited_asset.settled, 400);
+    assert_eq!(audited_asset.offchain_outbound, 500);
+    assert_eq!(audited_asset.offchain_inbound, 100);
+    assert!(summary.onchain_btc_settled_sat > 0);
+    assert_eq!(report.auditor_pubkey, auditor_pubkey.to_string());
+    assert!(report
+        .message
+        .ends_with(&serde_json::to_string(&report.summary).unwrap()));
+    assert!(lightning::util::message_signing::verify(
+        report.message.as_bytes(),
+        &report.signature,
+        &auditor_pubkey,
+    ));
+
+    let report = audit_storage_dir(
+        Path::new(&test_dir_node2),
+        BitcoinNetwork::Regtest,
+        &auditor_key_path,
+    )
+    .await
+    .unwrap();
+    assert!(report.summary.anomalies.is_empty());
+    let audited_channel = report.summary.channels.first().unwrap();
+    assert_eq!(audited_channel.asset_local_amount, Some(100));
+    assert_eq!(audited_channel.consignment_verified, Some(true));
+
+    // failures
+    assert!(matches!(
+        audit_storage_dir(
+            Path::new(&test_dir_node1),
+            BitcoinNetwork::Signet,
+            &auditor_key_path,
+        )
+        .await,
+        Err(AppError::NetworkMismatch(_, BitcoinNetwork::Signet))
+    ));
+    let invalid_key_path = Path::new(TEST_DIR_BASE).join("invalid_key");
+    std::fs::write(&invalid_key_path, "invalid").unwrap();
+    assert!(matches!(
+        audit_storage_dir(
+            Path::new(&test_dir_node1),
+            BitcoinNetwork::Regtest,
+            &invalid_key_path,
+        )
+        .await,
+        Err(AppError::InvalidAuditorKey)
+    ));
+    let test_dir_empty = format!("{TEST_DIR_BASE}empty");
+    std::fs::create_dir_all(&test_dir_empty).unwrap();
+    assert!(matches!(
+        audit_storage_dir(
+            Path::new(&test_dir_empty),
+            BitcoinNetwork::Regtest,
+            &auditor_key_path,
+        )
+        .await,
+        Err(AppError::FailedAudit(_))
+    ));
+}
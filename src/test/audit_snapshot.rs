@@ -0,0 +1,190 @@
+use bitcoin::secp256k1::PublicKey;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/audit_snapshot/";
+
+async fn audit_res(
+    node_address: SocketAddr,
+    block_height: Option<u32>,
+    nonce: Option<&str>,
+) -> Response {
+    let mut query = vec![];
+    if let Some(block_height) = block_height {
+        query.push(("block_height", block_height.to_string()));
+    }
+    if let Some(nonce) = nonce {
+        query.push(("nonce", nonce.to_string()));
+    }
+    reqwest::Client::new()
+        .get(format!("http://{node_address}/audit"))
+        .query(&query)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn verify_message(
+    node_address: SocketAddr,
+    message: &str,
+    signature: &str,
+    pubkey: Option<&str>,
+) -> VerifyMessageResponse {
+    let payload = VerifyMessageRequest {
+        message: message.to_string(),
+        signature: signature.to_string(),
+        pubkey: pubkey.map(|p| p.to_string()),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/verifymessage"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<VerifyMessageResponse>()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn audit_snapshot() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+
+    let res = audit_res(node1_addr, None, Some("audit-2024-q3")).await;
+    let audit = _check_response_is_ok(res)
+        .await
+        .json::<AuditResponse>()
+        .await
+        .unwrap();
+    let snapshot = &audit.snapshot;
+    assert_eq!(snapshot.pubkey, node1_pubkey);
+    assert_eq!(snapshot.nonce, Some(s!("audit-2024-q3")));
+    assert!(snapshot.onchain_vanilla_sat > 0);
+    assert!(snapshot.onchain_colored_sat > 0);
+    assert_eq!(snapshot.channels.len(), 1);
+    let audit_channel = &snapshot.channels[0];
+    assert_eq!(audit_channel.channel_id, channel.channel_id);
+    assert_eq!(audit_channel.peer_pubkey, node2_pubkey);
+    assert!(audit_channel.ready);
+    assert!(audit_channel.claimable_balance_sat > 0);
+    assert_eq!(audit_channel.asset_id, Some(asset_id.clone()));
+    assert_eq!(audit_channel.asset_local_amount, Some(600));
+    assert_eq!(audit_channel.asset_remote_amount, Some(0));
+    assert_eq!(snapshot.assets.len(), 1);
+    let audit_asset = &snapshot.assets[0];
+    assert_eq!(audit_asset.asset_id, asset_id);
+    assert_eq!(audit_asset.settled, 400);
+    assert_eq!(audit_asset.offchain_outbound, 600);
+    assert_eq!(audit_asset.offchain_inbound, 0);
+
+    // the signed message is the snapshot
+    let (header, signed_snapshot) = audit.message.split_once('\n').unwrap();
+    assert_eq!(header, "rgb-lightning-node audit snapshot");
+    let signed_snapshot: AuditSnapshot = serde_json::from_str(signed_snapshot).unwrap();
+    assert_eq!(signed_snapshot.block_height, snapshot.block_height);
+    assert_eq!(signed_snapshot.block_hash, snapshot.block_hash);
+    assert_eq!(signed_snapshot.assets.len(), 1);
+    assert!(lightning::util::message_signing::verify(
+        audit.message.as_bytes(),
+        &audit.signature,
+        &PublicKey::from_str(&node1_pubkey).unwrap(),
+    ));
+
+    // the signature can be checked by the node itself and by other nodes
+    let verification = verify_message(node1_addr, &audit.message, &audit.signature, None).await;
+    assert!(verification.valid);
+    assert_eq!(verification.pubkey, Some(node1_pubkey.clone()));
+    let verification = verify_message(
+        node2_addr,
+        &audit.message,
+        &audit.signature,
+        Some(&node1_pubkey),
+    )
+    .await;
+    assert!(verification.valid);
+    let verification = verify_message(node2_addr, &audit.message, &audit.signature, None).await;
+    assert!(!verification.valid);
+    let tampered = audit.message.replace("\"settled\":400", "\"settled\":4000");
+    assert_ne!(tampered, audit.message);
+    let verification =
+        verify_message(node1_addr, &tampered, &audit.signature, Some(&node1_pubkey)).await;
+    assert!(!verification.valid);
+    let verification = verify_message(node1_addr, &audit.message, "invalid", None).await;
+    assert!(!verification.valid);
+    assert_eq!(verification.pubkey, None);
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/verifymessage"))
+        .json(&VerifyMessageRequest {
+            message: audit.message.clone(),
+            signature: audit.signature.clone(),
+            pubkey: Some(s!("invalid")),
+        })
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid pubkey",
+        "InvalidPubkey",
+    )
+    .await;
+
+    // snapshots can be pinned to the current block height only
+    let block_height = snapshot.block_height;
+    let res = audit_res(node1_addr, Some(block_height), None).await;
+    let audit = _check_response_is_ok(res)
+        .await
+        .json::<AuditResponse>()
+        .await
+        .unwrap();
+    assert_eq!(audit.snapshot.block_height, block_height);
+    assert_eq!(audit.snapshot.nonce, None);
+    let res = audit_res(node1_addr, Some(block_height - 1), None).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        &format!(
+            "Invalid block height: requested {} but the node is at height {block_height}",
+            block_height - 1
+        ),
+        "InvalidBlockHeight",
+    )
+    .await;
+    let res = audit_res(node1_addr, None, Some("")).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid nonce: cannot be empty",
+        "InvalidNonce",
+    )
+    .await;
+}
@@ -41,6 +41,11 @@ async fn authentication() {
     let password = "a_password";
     let payload = InitRequest {
         password: password.to_string(),
+        network: None,
+        mnemonic: None,
+        passphrase: None,
+        account_index: None,
+        ldk_keys_only: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node_address}/init"))
@@ -211,3 +216,101 @@ async fn authentication() {
         .unwrap();
     assert_eq!(res.status(), reqwest::StatusCode::UNAUTHORIZED);
 }
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn authentication_generated_root_key() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}generated_node1");
+
+    let _ = std::fs::remove_dir_all(&test_dir_node1);
+    let args = UserArgs {
+        storage_dir_path: test_dir_node1.clone().into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        generate_root_key: true,
+        ..Default::default()
+    };
+    let node_address = start_daemon_with_args(args).await;
+
+    // before init only init can be called
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/nodeinfo"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    // init generates the root key and returns the tokens
+    let password = "a_password";
+    let payload = InitRequest {
+        password: password.to_string(),
+        network: None,
+        mnemonic: None,
+        passphrase: None,
+        account_index: None,
+        ldk_keys_only: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/init"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let init_res = _check_response_is_ok(res)
+        .await
+        .json::<InitResponse>()
+        .await
+        .unwrap();
+    let admin_token = init_res.admin_token.unwrap();
+    let read_only_token = init_res.read_only_token.unwrap();
+
+    // after init a token is required
+    let payload = unlock_req(password);
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/unlock"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::UNAUTHORIZED);
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/unlock"))
+        .json(&payload)
+        .bearer_auth(&admin_token)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
+
+    // the read-only token can only call read-only operations
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/nodeinfo"))
+        .bearer_auth(&read_only_token)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<NodeInfoResponse>()
+        .await
+        .unwrap();
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/address"))
+        .bearer_auth(&read_only_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+
+    // the root public key is persisted, while the private key is not
+    let root_key_hex =
+        std::fs::read_to_string(PathBuf::from(&test_dir_node1).join("root_public_key.txt"))
+            .unwrap();
+    assert_eq!(hex_str_to_vec(&root_key_hex).unwrap().len(), 32);
+}
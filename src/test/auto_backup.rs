@@ -0,0 +1,185 @@
+use crate::auto_backup::{
+    AutoBackupManifest, SnapshotName, AUTO_BACKUP_CHANNELS_DIR, AUTO_BACKUP_MANIFEST_FNAME,
+    AUTO_BACKUP_RGB_DIR,
+};
+use crate::backup::restore_backup;
+use crate::disk::{CHANNEL_MONITORS_DIR, EMERGENCY_KIT_CONTENTS_FNAME, EMERGENCY_KIT_FNAME};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/auto_backup/";
+
+fn list_snapshots(backup_dir: &Path) -> Vec<(SnapshotName, PathBuf)> {
+    let mut snapshots = std::fs::read_dir(backup_dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter_map(|p| {
+            let name = SnapshotName::parse(&p.file_name().unwrap().to_string_lossy())?;
+            Some((name, p))
+        })
+        .collect::<Vec<_>>();
+    snapshots.sort();
+    snapshots
+}
+
+async fn wait_for_snapshot(
+    backup_dir: &Path,
+    previous: Option<&SnapshotName>,
+) -> (SnapshotName, PathBuf) {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        if let Some(latest) = list_snapshots(backup_dir).pop() {
+            if Some(&latest.0) != previous {
+                return latest;
+            }
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 60.0 {
+            panic!("automatic backup has not been saved")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+fn restore_snapshot(snapshot_path: &Path, password: &str) -> (PathBuf, AutoBackupManifest) {
+    let snapshot_name = snapshot_path.file_name().unwrap().to_string_lossy();
+    let restore_dir = PathBuf::from(format!("{TEST_DIR_BASE}restored/{snapshot_name}"));
+    if restore_dir.exists() {
+        std::fs::remove_dir_all(&restore_dir).unwrap();
+    }
+    restore_backup(snapshot_path, password, &restore_dir).unwrap();
+    let manifest = serde_json::from_str(
+        &std::fs::read_to_string(restore_dir.join(AUTO_BACKUP_MANIFEST_FNAME)).unwrap(),
+    )
+    .unwrap();
+    (restore_dir, manifest)
+}
+
+/// Restore the given file from the snapshot holding its content
+fn restore_file(
+    backup_dir: &Path,
+    manifest: &AutoBackupManifest,
+    file: &str,
+    password: &str,
+) -> PathBuf {
+    let snapshot_path = backup_dir.join(&manifest.files[file].snapshot);
+    let (restore_dir, _) = restore_snapshot(&snapshot_path, password);
+    restore_dir.join(file)
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn auto_backup() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let backup_dir = PathBuf::from(format!("{TEST_DIR_BASE}backups"));
+    if backup_dir.exists() {
+        std::fs::remove_dir_all(&backup_dir).unwrap();
+    }
+
+    let args = UserArgs {
+        storage_dir_path: test_dir_node1.clone().into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        auto_backup_target: Some(backup_dir.to_string_lossy().to_string()),
+        auto_backup_interval_sec: 10,
+        auto_backup_keep: 1,
+        auto_backup_full_every: 2,
+        ..Default::default()
+    };
+    let (node1_addr, node1_password) = start_node_with_args(args, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    // a full snapshot is saved after unlocking, including the RGB wallet data
+    let (first_snapshot, first_path) = wait_for_snapshot(&backup_dir, None).await;
+    assert!(first_snapshot.full);
+    let (first_dir, first_manifest) = restore_snapshot(&first_path, &node1_password);
+    assert!(first_manifest
+        .files
+        .keys()
+        .any(|f| f.starts_with(&format!("{AUTO_BACKUP_RGB_DIR}/"))));
+    for (file, entry) in &first_manifest.files {
+        assert_eq!(entry.snapshot, first_snapshot.to_string());
+        assert!(first_dir.join(file).exists());
+    }
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // the channel state changes trigger new snapshots, incremental ones only holding the files
+    // changed since the previous one
+    let monitors_prefix = format!("{AUTO_BACKUP_CHANNELS_DIR}/{CHANNEL_MONITORS_DIR}/");
+    let mut previous = first_snapshot.clone();
+    let mut incremental_checked = false;
+    let mut kit_checked = false;
+    for _ in 0..10 {
+        let (snapshot, path) = wait_for_snapshot(&backup_dir, Some(&previous)).await;
+        let (dir, manifest) = restore_snapshot(&path, &node1_password);
+        for (file, entry) in &manifest.files {
+            if entry.snapshot == snapshot.to_string() {
+                assert!(dir.join(file).exists());
+            } else {
+                assert!(!snapshot.full);
+                assert!(!dir.join(file).exists());
+                assert!(backup_dir.join(&entry.snapshot).exists());
+                incremental_checked = true;
+            }
+        }
+        previous = snapshot;
+        if !manifest.files.contains_key(EMERGENCY_KIT_FNAME)
+            || !manifest
+                .files
+                .keys()
+                .any(|f| f.starts_with(&monitors_prefix))
+        {
+            continue;
+        }
+        let kit_path = restore_file(&backup_dir, &manifest, EMERGENCY_KIT_FNAME, &node1_password);
+        let kit_dir = PathBuf::from(format!("{TEST_DIR_BASE}kit_decrypted"));
+        if kit_dir.exists() {
+            std::fs::remove_dir_all(&kit_dir).unwrap();
+        }
+        restore_backup(&kit_path, &node1_password, &kit_dir).unwrap();
+        let contents: EmergencyKitContents = serde_json::from_str(
+            &std::fs::read_to_string(kit_dir.join(EMERGENCY_KIT_CONTENTS_FNAME)).unwrap(),
+        )
+        .unwrap();
+        if contents.channels.len() == 1 {
+            assert_eq!(contents.channels[0].channel_id, channel.channel_id);
+            kit_checked = true;
+            break;
+        }
+    }
+    assert!(kit_checked);
+    assert!(incremental_checked);
+
+    // only the latest full snapshot is kept, along with the incremental ones following it
+    keysend(node1_addr, &node2_pubkey, Some(3000000), None, None).await;
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let snapshots = list_snapshots(&backup_dir);
+        if snapshots[0].0 > previous
+            && snapshots[0].0.full
+            && snapshots.iter().filter(|(s, _)| s.full).count() == 1
+        {
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 60.0 {
+            panic!("old automatic backups have not been deleted")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
@@ -0,0 +1,93 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/backup_export_import/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn backup_export_import() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, node1_password) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+
+    // check the node needs to be locked
+    let payload = ExportBackupRequest {
+        password: node1_password.clone(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/exportbackup"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Node is unlocked (hint: call lock)",
+        "UnlockedNode",
+    )
+    .await;
+
+    lock(node1_addr).await;
+
+    // check the password is verified
+    let payload = ExportBackupRequest {
+        password: s!("wrong password"),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/exportbackup"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::UNAUTHORIZED,
+        "The provided password is incorrect",
+        "WrongPassword",
+    )
+    .await;
+
+    let backup = export_backup(node1_addr, &node1_password).await;
+    assert!(!backup.is_empty());
+
+    shutdown(&[node1_addr]).await;
+
+    let old_test_dir_node1 = format!("{test_dir_node1}_old");
+    if Path::new(&old_test_dir_node1).exists() {
+        std::fs::remove_dir_all(&old_test_dir_node1).unwrap();
+    }
+    std::fs::rename(&test_dir_node1, &old_test_dir_node1).unwrap();
+
+    let node1_addr = start_daemon(&test_dir_node1, NODE1_PEER_PORT, None).await;
+
+    // check the upload needs a password
+    let form = reqwest::multipart::Form::new()
+        .part("file", reqwest::multipart::Part::bytes(backup.clone()));
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/importbackup"))
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid backup upload: password not provided",
+        "InvalidBackupUpload",
+    )
+    .await;
+
+    import_backup(node1_addr, backup, &node1_password).await;
+
+    unlock(node1_addr, &node1_password).await;
+
+    let node1_info = node_info(node1_addr).await;
+    assert_eq!(node1_pubkey, node1_info.pubkey);
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 1000);
+}
@@ -0,0 +1,152 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/backup_restore_channels/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn backup_restore_channels() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, node1_password) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    // full backup taken before the channel exists
+    lock(node1_addr).await;
+    let node1_backup_path = format!("{TEST_DIR_BASE}node1_backup");
+    if Path::new(&node1_backup_path).exists() {
+        std::fs::remove_file(&node1_backup_path).unwrap();
+    }
+    backup(node1_addr, &node1_backup_path, &node1_password).await;
+    unlock(node1_addr, &node1_password).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(100_000),
+        None,
+        None,
+        None,
+    )
+    .await;
+    keysend(node1_addr, &node2_pubkey, Some(3000000), None, None).await;
+    let balance_after_open = btc_balance(node1_addr).await.vanilla.spendable;
+
+    let node1_channel_backup_path = format!("{TEST_DIR_BASE}node1_channel_backup");
+    if Path::new(&node1_channel_backup_path).exists() {
+        std::fs::remove_file(&node1_channel_backup_path).unwrap();
+    }
+    assert_eq!(
+        backup_channels(node1_addr, &node1_channel_backup_path, &node1_password).await,
+        1
+    );
+
+    // check InvalidBackupPath error
+    let payload = BackupChannelsRequest {
+        backup_path: node1_channel_backup_path.clone(),
+        password: node1_password.clone(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/backupchannels"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid backup path",
+        "InvalidBackupPath",
+    )
+    .await;
+
+    // lose the disk of node1
+    shutdown(&[node1_addr]).await;
+    let old_test_dir_node1 = format!("{test_dir_node1}_old");
+    if Path::new(&old_test_dir_node1).exists() {
+        std::fs::remove_dir_all(&old_test_dir_node1).unwrap();
+    }
+    std::fs::rename(test_dir_node1.clone(), old_test_dir_node1).unwrap();
+
+    let node1_addr = start_daemon(&test_dir_node1, NODE1_PEER_PORT, None).await;
+    restore(node1_addr, &node1_backup_path, &node1_password).await;
+
+    // a full wallet backup is not a channel backup
+    let payload = RestoreChannelsRequest {
+        backup_path: node1_backup_path.clone(),
+        password: node1_password.clone(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/restorechannels"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid channel backup",
+        "InvalidChannelBackup",
+    )
+    .await;
+
+    assert_eq!(
+        restore_channels(node1_addr, &node1_channel_backup_path, &node1_password).await,
+        1
+    );
+
+    // restoring twice is not allowed
+    let payload = RestoreChannelsRequest {
+        backup_path: node1_channel_backup_path.clone(),
+        password: node1_password.clone(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/restorechannels"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot restore channels: the node already has channels",
+        "CannotRestoreChannels",
+    )
+    .await;
+
+    // once unlocked, the node reconnects to the peer and the channel gets force-closed
+    unlock(node1_addr, &node1_password).await;
+    let t_0 = OffsetDateTime::now_utc();
+    while list_channels(node2_addr)
+        .await
+        .iter()
+        .any(|c| c.channel_id == channel.channel_id)
+    {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 60.0 {
+            panic!("restored channel has not been closed")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+
+    // the channel funds get swept back to node1
+    mine_n_blocks(true, 144);
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        if btc_balance(node1_addr).await.vanilla.spendable > balance_after_open + 90_000 {
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 60.0 {
+            panic!("channel funds have not been recovered")
+        }
+        mine_n_blocks(true, 1);
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
@@ -0,0 +1,137 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/balance_sheet/";
+
+async fn balance_sheet(node_address: SocketAddr) -> BalanceSheetResponse {
+    let payload = BalanceSheetRequest { skip_sync: false };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/balancesheet"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<BalanceSheetResponse>()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn balance_sheet_channel_lifecycle() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    // on-chain funds only
+    let sheet = balance_sheet(node1_addr).await;
+    let btc_balance = btc_balance(node1_addr).await;
+    assert_eq!(
+        sheet.onchain.confirmed_sat,
+        btc_balance.vanilla.settled + btc_balance.colored.settled
+    );
+    assert_eq!(sheet.channels.local_sat, 0);
+    assert_eq!(sheet.pending_close_sat, 0);
+    let asset = sheet
+        .assets
+        .iter()
+        .find(|a| a.asset_id == asset_id)
+        .unwrap();
+    assert_eq!(asset.settled, 1000);
+    assert_eq!(asset.offchain_outbound, 0);
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        Some(3000000),
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+    keysend(node1_addr, &node2_pubkey, None, Some(&asset_id), Some(150)).await;
+
+    // the channel balances are reported for BTC and the asset
+    let sheet = balance_sheet(node1_addr).await;
+    assert!(sheet.channels.local_sat > 0);
+    assert!(sheet.channels.remote_sat > 0);
+    assert_eq!(sheet.channels.pending_open_sat, 0);
+    assert_eq!(sheet.htlcs.outbound_msat, 0);
+    let asset = sheet
+        .assets
+        .iter()
+        .find(|a| a.asset_id == asset_id)
+        .unwrap();
+    assert_eq!(asset.future, 400);
+    assert_eq!(asset.offchain_outbound, 450);
+    assert_eq!(asset.offchain_inbound, 150);
+
+    // this sleep prevents non-deterministic issue where force close broadcasts an old commitment
+    // TX (one that still has an HTLC output)
+    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+
+    // the funds of the force-closed channel are pending until they're claimed
+    stop_mining();
+    let payload = CloseChannelRequest {
+        channel_id: channel.channel_id.clone(),
+        peer_pubkey: node2_pubkey.clone(),
+        force: true,
+        close_address: None,
+        fee_rate: None,
+        confirmation_target: None,
+        totp_code: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/closechannel"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+    let t_0 = OffsetDateTime::now_utc();
+    while list_channels(node1_addr)
+        .await
+        .iter()
+        .any(|c| c.channel_id == channel.channel_id)
+    {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("channel is taking too long to close")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+    let sheet = balance_sheet(node1_addr).await;
+    assert_eq!(sheet.channels.local_sat, 0);
+    assert!(sheet.pending_close_sat > 0);
+    let asset = sheet
+        .assets
+        .iter()
+        .find(|a| a.asset_id == asset_id)
+        .unwrap();
+    assert_eq!(asset.offchain_outbound, 0);
+    assert_eq!(asset.pending_close, 450);
+
+    mine_n_blocks(true, 144);
+    wait_for_balance(node1_addr, &asset_id, 850).await;
+    let sheet = balance_sheet(node1_addr).await;
+    assert_eq!(sheet.pending_close_sat, 0);
+    assert_eq!(sheet.pending_sweep_sat, 0);
+    let asset = sheet
+        .assets
+        .iter()
+        .find(|a| a.asset_id == asset_id)
+        .unwrap();
+    assert_eq!(asset.pending_close, 0);
+    assert_eq!(asset.pending_sweep, 0);
+}
@@ -0,0 +1,112 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/bump_close_fee/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn bump_close_fee_after_force_close() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(100_000),
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // the channel is still open
+    let payload = BumpCloseFeeRequest {
+        channel_id: channel.channel_id.clone(),
+        confirmation_target: 2,
+        totp_code: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/bumpclosefee"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "channel has not been force-closed",
+        "CannotBumpCloseFee",
+    )
+    .await;
+
+    // force-close without mining, so the commitment transaction stays unconfirmed
+    stop_mining();
+    let payload = CloseChannelRequest {
+        channel_id: channel.channel_id.clone(),
+        peer_pubkey: node2_pubkey.clone(),
+        force: true,
+        close_address: None,
+        fee_rate: None,
+        confirmation_target: None,
+        totp_code: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/closechannel"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+    let t_0 = OffsetDateTime::now_utc();
+    while list_channels(node1_addr)
+        .await
+        .iter()
+        .any(|c| c.channel_id == channel.channel_id)
+    {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("channel is taking too long to close")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+
+    let BumpCloseFeeResponse {
+        fee_rate_sat_per_kw,
+    } = bump_close_fee(node1_addr, &channel.channel_id, 2).await;
+    assert!(fee_rate_sat_per_kw >= 253);
+    let events = channel_events(node1_addr, &channel.channel_id).await.events;
+    assert!(events
+        .iter()
+        .any(|e| e.kind == ChannelEventKind::CloseFeeBumped));
+
+    // unknown channel
+    let payload = BumpCloseFeeRequest {
+        channel_id: s!("0000000000000000000000000000000000000000000000000000000000000000"),
+        confirmation_target: 2,
+        totp_code: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/bumpclosefee"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown channel ID",
+        "UnknownChannelId",
+    )
+    .await;
+
+    mine_n_blocks(true, 144);
+}
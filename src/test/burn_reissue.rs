@@ -0,0 +1,159 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/burn_reissue/";
+
+async fn burn_asset_res(node_address: SocketAddr, asset_id: &str, amount: u64) -> Response {
+    let payload = BurnAssetRequest {
+        asset_id: asset_id.to_string(),
+        amount,
+        fee_rate: FEE_RATE,
+        min_confirmations: 1,
+        skip_sync: false,
+        totp_code: None,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/burnasset"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn burn_asset(node_address: SocketAddr, asset_id: &str, amount: u64) -> BurnAssetResponse {
+    println!("burning {amount} of asset {asset_id} on node {node_address}");
+    let res = burn_asset_res(node_address, asset_id, amount).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<BurnAssetResponse>()
+        .await
+        .unwrap()
+}
+
+async fn issue_asset_ifa(node_address: SocketAddr) -> AssetIFA {
+    println!("issuing IFA asset on node {node_address}");
+    let payload = IssueAssetIFARequest {
+        amounts: vec![1000],
+        inflation_amounts: vec![500],
+        ticker: s!("USDT"),
+        name: s!("Tether"),
+        precision: 0,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/issueassetifa"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<IssueAssetIFAResponse>()
+        .await
+        .unwrap()
+        .asset
+}
+
+async fn reissue_asset_res(
+    node_address: SocketAddr,
+    asset_id: &str,
+    amounts: Vec<u64>,
+) -> Response {
+    let payload = ReissueAssetRequest {
+        asset_id: asset_id.to_string(),
+        amounts,
+        fee_rate: FEE_RATE,
+        min_confirmations: 1,
+        totp_code: None,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/reissueasset"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn burn_reissue() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    // burn part of an asset
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let BurnAssetResponse {
+        txid,
+        witness_script,
+    } = burn_asset(node1_addr, &asset_id, 300).await;
+    assert_eq!(witness_script, "6a");
+    mine(false);
+    wait_for_balance(node1_addr, &asset_id, 700).await;
+    let audit = asset_audit(node1_addr, &asset_id).await;
+    assert_eq!(audit.sent, 0);
+    assert_eq!(audit.held_onchain, 700);
+    assert_eq!(audit.burned, 300);
+    assert!(!export_consignment(node1_addr, &asset_id, &txid)
+        .await
+        .is_empty());
+
+    // burn failures
+    let res = burn_asset_res(node1_addr, &asset_id, 0).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "amount must be greater than 0",
+        "InvalidAmount",
+    )
+    .await;
+    let res = burn_asset_res(node1_addr, &asset_id, 2000).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Not enough assets",
+        "InsufficientAssets",
+    )
+    .await;
+
+    // only assets with inflation rights can be reissued
+    let res = reissue_asset_res(node1_addr, &asset_id, vec![100]).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "cannot be reissued, as it has no inflation rights",
+        "AssetNotInflatable",
+    )
+    .await;
+
+    // reissue an asset up to its max supply
+    let asset_id = issue_asset_ifa(node1_addr).await.asset_id;
+    let assets_ifa = list_assets(node1_addr).await.ifa.unwrap();
+    let asset_ifa = assets_ifa.iter().find(|a| a.asset_id == asset_id).unwrap();
+    assert_eq!(asset_ifa.known_circulating_supply, 1000);
+    assert_eq!(asset_ifa.max_supply, 1500);
+    let res = reissue_asset_res(node1_addr, &asset_id, vec![600]).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "the reissued amount would exceed the max supply of 1500",
+        "InvalidAmount",
+    )
+    .await;
+    let res = reissue_asset_res(node1_addr, &asset_id, vec![200]).await;
+    let ReissueAssetResponse { txid } = _check_response_is_ok(res)
+        .await
+        .json::<ReissueAssetResponse>()
+        .await
+        .unwrap();
+    mine(false);
+    wait_for_balance(node1_addr, &asset_id, 1200).await;
+    assert!(!export_consignment(node1_addr, &asset_id, &txid)
+        .await
+        .is_empty());
+    let audit = asset_audit(node1_addr, &asset_id).await;
+    assert_eq!(audit.issued_locally, 1200);
+    assert_eq!(audit.burned, 0);
+}
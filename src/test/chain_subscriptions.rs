@@ -0,0 +1,103 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/chain_subscriptions/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn chain_subscriptions() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    // node2 watches one of its addresses, then the transaction funding it
+    let node2_address = address(node2_addr).await;
+    let address_subscription = chain_subscription(node2_addr, None, Some(&node2_address)).await;
+    let txid = send_btc(node1_addr, 100000, &node2_address).await;
+    let txid_subscription = chain_subscription(node2_addr, Some(&txid), None).await;
+    assert!(chain_events(node2_addr, &address_subscription)
+        .await
+        .is_empty());
+    mine(false);
+
+    let events = wait_for_chain_events(node2_addr, &address_subscription, 1).await;
+    let received = events.first().unwrap();
+    assert_eq!(received.kind, ChainEventKind::Received);
+    assert_eq!(received.txid, txid);
+    assert_eq!(received.amount_sat, Some(100000));
+    let received_outpoint = received.outpoint.clone().unwrap();
+    let events = wait_for_chain_events(node2_addr, &txid_subscription, 1).await;
+    let confirmed = events.first().unwrap();
+    assert_eq!(confirmed.kind, ChainEventKind::Confirmed);
+    assert_eq!(confirmed.txid, txid);
+    assert_eq!(confirmed.block_height, received.block_height);
+
+    // spending the received output is reported to both subscriptions
+    create_utxos(node2_addr, false, Some(1), None).await;
+    mine(false);
+    let events = wait_for_chain_events(node2_addr, &address_subscription, 2).await;
+    let spent = events.last().unwrap();
+    assert_eq!(spent.kind, ChainEventKind::Spent);
+    assert_eq!(spent.outpoint, Some(received_outpoint.clone()));
+    assert_ne!(spent.txid, txid);
+    let events = wait_for_chain_events(node2_addr, &txid_subscription, 2).await;
+    let spent_txid = events.last().unwrap();
+    assert_eq!(spent_txid.kind, ChainEventKind::Spent);
+    assert_eq!(spent_txid.outpoint, Some(received_outpoint));
+    assert_eq!(spent_txid.txid, spent.txid);
+
+    // failures
+    let payload = ChainSubscriptionsRequest {
+        txid: Some(txid),
+        script: None,
+        address: Some(node2_address),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/chainsubscriptions"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "exactly one of txid, script and address must be provided",
+        "InvalidChainSubscription",
+    )
+    .await;
+    let payload = ChainSubscriptionsRequest {
+        txid: Some(s!("invalid")),
+        script: None,
+        address: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/chainsubscriptions"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "invalid txid",
+        "InvalidChainSubscription",
+    )
+    .await;
+    let res = reqwest::Client::new()
+        .get(format!("http://{node2_addr}/chainevents/unknown"))
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown chain subscription",
+        "UnknownChainSubscription",
+    )
+    .await;
+}
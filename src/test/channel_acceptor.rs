@@ -0,0 +1,243 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/channel_acceptor/";
+
+async fn request_channel(
+    node_address: SocketAddr,
+    dest_peer_pubkey: &str,
+    capacity_sat: u64,
+    temporary_channel_id: &str,
+) {
+    println!("requesting channel {temporary_channel_id} from node {node_address}");
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_opt_addr: format!("{dest_peer_pubkey}@127.0.0.1:{NODE2_PEER_PORT}"),
+        capacity_sat,
+        push_msat: 0,
+        asset_amount: None,
+        asset_id: None,
+        public: true,
+        with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        temporary_channel_id: Some(temporary_channel_id.to_string()),
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
+    };
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let res = reqwest::Client::new()
+            .post(format!("http://{node_address}/openchannel"))
+            .json(&payload)
+            .send()
+            .await
+            .unwrap();
+        // a previous rejected opening may still be holding the lock
+        if res.status() != reqwest::StatusCode::FORBIDDEN {
+            _check_response_is_ok(res).await;
+            return;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("cannot request channel")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+async fn wait_for_rejection(node_address: SocketAddr, temporary_channel_id: &str) -> String {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let res = reqwest::Client::new()
+            .get(format!(
+                "http://{node_address}/channels/{temporary_channel_id}/events"
+            ))
+            .send()
+            .await
+            .unwrap();
+        if res.status().is_success() {
+            let events = res.json::<ChannelEventsResponse>().await.unwrap();
+            if let Some(event) = events
+                .events
+                .iter()
+                .find(|e| e.details.starts_with("rejected request"))
+            {
+                return event.details.clone();
+            }
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 20.0 {
+            panic!("channel has not been rejected")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+async fn wait_for_pending_channels(
+    node_address: SocketAddr,
+    expected: usize,
+) -> Vec<PendingChannel> {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let channels = pending_channels(node_address).await;
+        if channels.len() == expected {
+            return channels;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 20.0 {
+            panic!("pending channels are not {expected}")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn channel_acceptor() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    // every channel is accepted by default
+    let res = reqwest::Client::new()
+        .get(format!("http://{node2_addr}/channelacceptor"))
+        .send()
+        .await
+        .unwrap();
+    let policy = _check_response_is_ok(res)
+        .await
+        .json::<ChannelAcceptorResponse>()
+        .await
+        .unwrap();
+    assert!(!policy.manual_approval);
+    assert!(policy.min_channel_size_sat.is_none());
+
+    let mut payload = ChannelAcceptorRequest {
+        min_channel_size_sat: Some(200_000),
+        max_channel_size_sat: Some(100_000),
+        require_anchors: true,
+        max_channels_per_peer: Some(1),
+        reject_rgb_channels: false,
+        allowed_assets: vec![],
+        denied_assets: vec![],
+        manual_approval: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/channelacceptor"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "min_channel_size_sat cannot exceed max_channel_size_sat",
+        "InvalidChannelAcceptor",
+    )
+    .await;
+    payload.max_channel_size_sat = None;
+    payload.denied_assets = vec![s!("invalid")];
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/channelacceptor"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid asset ID",
+        "InvalidAssetID",
+    )
+    .await;
+    payload.denied_assets = vec![];
+    let policy = update_channel_acceptor(node2_addr, &payload).await;
+    assert_eq!(policy.min_channel_size_sat, Some(200_000));
+    assert!(policy.require_anchors);
+
+    // channels below the min size are rejected by the static policy
+    let temporary_channel_id =
+        s!("0011223344556677889900112233445566778899001122334455667788990031");
+    request_channel(node1_addr, &node2_pubkey, 100_000, &temporary_channel_id).await;
+    let details = wait_for_rejection(node2_addr, &temporary_channel_id).await;
+    assert!(details.contains("channel size of 100000 sat is below the min of 200000 sat"));
+    assert!(pending_channels(node2_addr).await.is_empty());
+
+    // requests passing the policy are held for a decision
+    payload.manual_approval = true;
+    update_channel_acceptor(node2_addr, &payload).await;
+
+    let temporary_channel_id =
+        s!("0011223344556677889900112233445566778899001122334455667788990032");
+    request_channel(node1_addr, &node2_pubkey, 250_000, &temporary_channel_id).await;
+    let channels = wait_for_pending_channels(node2_addr, 1).await;
+    assert_eq!(channels[0].temporary_channel_id, temporary_channel_id);
+    assert_eq!(channels[0].peer_pubkey, node1_pubkey);
+    assert_eq!(channels[0].capacity_sat, 250_000);
+    assert!(channels[0].public);
+    assert!(channels[0].with_anchors);
+
+    reject_channel(node2_addr, &temporary_channel_id, Some("not now")).await;
+    assert!(pending_channels(node2_addr).await.is_empty());
+    let details = wait_for_rejection(node2_addr, &temporary_channel_id).await;
+    assert!(details.ends_with("not now"));
+    let payload_reject = RejectChannelRequest {
+        temporary_channel_id: temporary_channel_id.clone(),
+        reason: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/rejectchannel"))
+        .json(&payload_reject)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown temporary channel ID",
+        "UnknownTemporaryChannelId",
+    )
+    .await;
+
+    let temporary_channel_id =
+        s!("0011223344556677889900112233445566778899001122334455667788990033");
+    request_channel(node1_addr, &node2_pubkey, 250_000, &temporary_channel_id).await;
+    wait_for_pending_channels(node2_addr, 1).await;
+    accept_channel(node2_addr, &temporary_channel_id).await;
+    assert!(pending_channels(node2_addr).await.is_empty());
+
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let channels = list_channels(node1_addr).await;
+        if channels.iter().any(|c| c.funding_txid.is_some()) {
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("cannot find funding TX")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+    mine_n_blocks(false, 6);
+    wait_for_usable_channels(node1_addr, 1).await;
+
+    // the peer has reached the max number of channels
+    let temporary_channel_id =
+        s!("0011223344556677889900112233445566778899001122334455667788990034");
+    request_channel(node1_addr, &node2_pubkey, 250_000, &temporary_channel_id).await;
+    let details = wait_for_rejection(node2_addr, &temporary_channel_id).await;
+    assert!(details.contains("the peer already has 1 channels, the max is 1"));
+    assert_eq!(list_channels(node2_addr).await.len(), 1);
+}
@@ -0,0 +1,240 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/channel_depths/";
+
+async fn channel_depths(node_address: SocketAddr) -> ChannelDepthsResponse {
+    println!("getting channel depths for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/channeldepths"))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ChannelDepthsResponse>()
+        .await
+        .unwrap()
+}
+
+async fn update_channel_depths_res(node_address: SocketAddr, peers: &[(&str, u32)]) -> Response {
+    let payload = ChannelDepthsRequest {
+        peers: peers
+            .iter()
+            .map(|(peer_pubkey, min_depth)| ChannelDepthOverride {
+                peer_pubkey: peer_pubkey.to_string(),
+                min_depth: *min_depth,
+            })
+            .collect(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/channeldepths"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn update_channel_depths(
+    node_address: SocketAddr,
+    peers: &[(&str, u32)],
+) -> ChannelDepthsResponse {
+    println!("setting channel depths {peers:?} on node {node_address}");
+    let res = update_channel_depths_res(node_address, peers).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<ChannelDepthsResponse>()
+        .await
+        .unwrap()
+}
+
+async fn open_vanilla_channel(
+    node_address: SocketAddr,
+    peer_pubkey: &str,
+    peer_port: u16,
+    temporary_channel_id: &str,
+) {
+    println!("opening channel {temporary_channel_id} from node {node_address}");
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_opt_addr: format!("{peer_pubkey}@127.0.0.1:{peer_port}"),
+        capacity_sat: 100_000,
+        push_msat: 0,
+        asset_amount: None,
+        asset_id: None,
+        public: true,
+        with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        temporary_channel_id: Some(temporary_channel_id.to_string()),
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/openchannel"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+}
+
+async fn wait_for_open_stage(
+    node_address: SocketAddr,
+    temporary_channel_id: &str,
+    stage: ChannelOpenStage,
+) -> ChannelStatusResponse {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let res = reqwest::Client::new()
+            .get(format!("http://{node_address}/channelstatus"))
+            .query(&[("temporary_channel_id", temporary_channel_id)])
+            .send()
+            .await
+            .unwrap();
+        if res.status().is_success() {
+            let status = res.json::<ChannelStatusResponse>().await.unwrap();
+            if status.stage == stage {
+                return status;
+            }
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("channel {temporary_channel_id} has not reached stage {stage:?}")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn channel_depths() {
+    initialize();
+
+    let test_dir_base = format!("{TEST_DIR_BASE}success/");
+    let test_dir_node1 = format!("{test_dir_base}node1");
+    let test_dir_node2 = format!("{test_dir_base}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    // the global depth is reported without overrides
+    let depths = channel_depths(node1_addr).await;
+    assert_eq!(depths.min_depth, DEFAULT_MIN_CHANNEL_DEPTH);
+    assert!(depths.peers.is_empty());
+
+    // the acceptor requires the confirmations set for the peer
+    update_channel_depths(node2_addr, &[(&node1_pubkey, 3)]).await;
+    let depths = update_channel_depths(node1_addr, &[(&node2_pubkey, 3)]).await;
+    assert_eq!(depths.peers.len(), 1);
+    assert_eq!(depths.peers[0].peer_pubkey, node2_pubkey);
+    assert_eq!(depths.peers[0].min_depth, 3);
+    let temporary_channel_id =
+        s!("0011223344556677889900112233445566778899001122334455667788990044");
+    open_channel_with_custom_data(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        Some(&temporary_channel_id),
+        true,
+    )
+    .await;
+    let status =
+        wait_for_open_stage(node1_addr, &temporary_channel_id, ChannelOpenStage::Ready).await;
+    assert_eq!(status.required_confirmations, Some(3));
+
+    // the funding gets refused when the acceptor requires fewer confirmations than the opener
+    update_channel_depths(node1_addr, &[]).await;
+    let temporary_channel_id =
+        s!("0011223344556677889900112233445566778899001122334455667788990055");
+    open_vanilla_channel(
+        node1_addr,
+        &node2_pubkey,
+        NODE2_PEER_PORT,
+        &temporary_channel_id,
+    )
+    .await;
+    let status =
+        wait_for_open_stage(node1_addr, &temporary_channel_id, ChannelOpenStage::Failed).await;
+    assert!(status.funding_txid.is_none());
+
+    // trusted peers get zero-conf channels, ready without mining
+    update_channel_depths(node1_addr, &[(&node2_pubkey, 0)]).await;
+    update_channel_depths(node2_addr, &[(&node1_pubkey, 0)]).await;
+    let temporary_channel_id =
+        s!("0011223344556677889900112233445566778899001122334455667788990066");
+    open_vanilla_channel(
+        node1_addr,
+        &node2_pubkey,
+        NODE2_PEER_PORT,
+        &temporary_channel_id,
+    )
+    .await;
+    let status =
+        wait_for_open_stage(node1_addr, &temporary_channel_id, ChannelOpenStage::Ready).await;
+    assert_eq!(status.required_confirmations, Some(0));
+    assert_eq!(status.confirmations, Some(0));
+
+    // overrides survive a restart
+    shutdown(&[node2_addr]).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, true).await;
+    let depths = channel_depths(node2_addr).await;
+    assert_eq!(depths.peers.len(), 1);
+    assert_eq!(depths.peers[0].min_depth, 0);
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn channel_depths_fail() {
+    initialize();
+
+    let test_dir_base = format!("{TEST_DIR_BASE}fail/");
+    let test_dir_node1 = format!("{test_dir_base}node1");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    let res = update_channel_depths_res(node1_addr, &[("invalid", 1)]).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid channel depths: invalid pubkey invalid",
+        "InvalidChannelDepths",
+    )
+    .await;
+
+    let pubkey = "03b79a4bc1ec365524b4fab9a39eb133753646babb5a1da5c4bc94c53110b7795d";
+    let res = update_channel_depths_res(node1_addr, &[(pubkey, 145)]).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid channel depths: min_depth cannot be more than 144",
+        "InvalidChannelDepths",
+    )
+    .await;
+
+    let res = update_channel_depths_res(node1_addr, &[(pubkey, 1), (pubkey, 2)]).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        &format!("Invalid channel depths: duplicate pubkey {pubkey}"),
+        "InvalidChannelDepths",
+    )
+    .await;
+}
@@ -0,0 +1,117 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/channel_events/";
+
+fn event_kinds(events: &ChannelEventsResponse) -> Vec<ChannelEventKind> {
+    events.events.iter().map(|e| e.kind).collect()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn channel_events_lifecycle() {
+    initialize();
+
+    let test_dir_base = format!("{TEST_DIR_BASE}lifecycle/");
+    let test_dir_node1 = format!("{test_dir_base}node1");
+    let test_dir_node2 = format!("{test_dir_base}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let temporary_channel_id =
+        s!("0011223344556677889900112233445566778899001122334455667788990022");
+    let channel = open_channel_with_custom_data(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        Some(&temporary_channel_id),
+        true,
+    )
+    .await;
+
+    let events = channel_events(node1_addr, &channel.channel_id).await;
+    assert_eq!(events.channel_id, channel.channel_id);
+    assert_eq!(
+        event_kinds(&events),
+        vec![
+            ChannelEventKind::OpenRequested,
+            ChannelEventKind::FundingGenerated,
+            ChannelEventKind::Pending,
+            ChannelEventKind::FundingBroadcast,
+            ChannelEventKind::Ready,
+        ]
+    );
+    assert!(events
+        .events
+        .windows(2)
+        .all(|w| w[0].timestamp <= w[1].timestamp));
+    // the temporary channel ID resolves to the same log
+    let events_tmp = channel_events(node1_addr, &temporary_channel_id).await;
+    assert_eq!(events_tmp.channel_id, channel.channel_id);
+    assert_eq!(events_tmp.events.len(), events.events.len());
+
+    let events = channel_events(node2_addr, &channel.channel_id).await;
+    assert_eq!(
+        event_kinds(&events),
+        vec![
+            ChannelEventKind::OpenReceived,
+            ChannelEventKind::Pending,
+            ChannelEventKind::Ready,
+        ]
+    );
+
+    close_channel(node1_addr, &channel.channel_id, &node2_pubkey, false).await;
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let kinds = event_kinds(&channel_events(node1_addr, &channel.channel_id).await);
+        if kinds.last() == Some(&ChannelEventKind::Closed) {
+            assert!(kinds.contains(&ChannelEventKind::CloseRequested));
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("close event has not been recorded")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+    let kinds = event_kinds(&channel_events(node2_addr, &channel.channel_id).await);
+    assert!(!kinds.contains(&ChannelEventKind::CloseRequested));
+    assert_eq!(kinds.first(), Some(&ChannelEventKind::OpenReceived));
+
+    // failures
+    let unknown_id = "ff".repeat(32);
+    let res = reqwest::Client::new()
+        .get(format!("http://{node1_addr}/channels/{unknown_id}/events"))
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown channel ID",
+        "UnknownChannelId",
+    )
+    .await;
+    let res = reqwest::Client::new()
+        .get(format!("http://{node1_addr}/channels/invalid/events"))
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid channel ID",
+        "InvalidChannelID",
+    )
+    .await;
+}
@@ -0,0 +1,167 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/channel_limits/";
+
+async fn open_channel_limits_res(
+    node_address: SocketAddr,
+    dest_peer_pubkey: &str,
+    asset_id: Option<&str>,
+    htlc_minimum_msat: Option<u64>,
+    max_htlc_value_in_flight_percent: Option<u8>,
+    max_accepted_htlcs: Option<u16>,
+    channel_reserve_proportional_millionths: Option<u32>,
+) -> Response {
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_opt_addr: format!("{dest_peer_pubkey}@127.0.0.1:{NODE2_PEER_PORT}"),
+        capacity_sat: 100_000,
+        push_msat: 0,
+        asset_amount: asset_id.map(|_| 100),
+        asset_id: asset_id.map(|id| id.to_string()),
+        public: true,
+        with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat,
+        max_htlc_value_in_flight_percent,
+        max_accepted_htlcs,
+        channel_reserve_proportional_millionths,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        temporary_channel_id: None,
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/openchannel"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn channel_limits() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let res = open_channel_limits_res(
+        node1_addr,
+        &node2_pubkey,
+        Some(&asset_id),
+        Some(1000),
+        None,
+        None,
+        None,
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "htlc_minimum_msat of RGB channels cannot be less than 3000000",
+        "InvalidChannelLimits",
+    )
+    .await;
+    let res = open_channel_limits_res(
+        node1_addr,
+        &node2_pubkey,
+        None,
+        Some(100_000_000),
+        None,
+        None,
+        None,
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "htlc_minimum_msat must be positive and lower than the capacity",
+        "InvalidChannelLimits",
+    )
+    .await;
+    let res =
+        open_channel_limits_res(node1_addr, &node2_pubkey, None, None, Some(101), None, None).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "max_htlc_value_in_flight_percent must be between 1 and 100",
+        "InvalidChannelLimits",
+    )
+    .await;
+    let res =
+        open_channel_limits_res(node1_addr, &node2_pubkey, None, None, None, Some(484), None).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "max_accepted_htlcs must be between 1 and 483",
+        "InvalidChannelLimits",
+    )
+    .await;
+    let res = open_channel_limits_res(
+        node1_addr,
+        &node2_pubkey,
+        None,
+        None,
+        None,
+        None,
+        Some(1_000_001),
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "channel_reserve_proportional_millionths cannot be more than 1000000",
+        "InvalidChannelLimits",
+    )
+    .await;
+
+    let res = open_channel_limits_res(
+        node1_addr,
+        &node2_pubkey,
+        None,
+        Some(1000),
+        Some(50),
+        Some(30),
+        Some(20_000),
+    )
+    .await;
+    _check_response_is_ok(res).await;
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let channels = list_channels(node1_addr).await;
+        if let Some(funding_txid) = channels.first().and_then(|c| c.funding_txid.as_ref()) {
+            if !_get_txout(funding_txid).is_empty() {
+                break;
+            }
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("cannot find funding TX")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+    mine_n_blocks(false, 6);
+    wait_for_usable_channels(node1_addr, 1).await;
+
+    // the limits apply to what the peer can send to us
+    let channels = list_channels(node1_addr).await;
+    assert_eq!(channels[0].inbound_htlc_minimum_msat, Some(1000));
+    assert!(channels[0].inbound_htlc_maximum_msat.unwrap() <= 50_000_000);
+    assert_eq!(channels[0].remote_reserve_sat, 2000);
+    let channels = list_channels(node2_addr).await;
+    assert_eq!(channels[0].local_reserve_sat, Some(2000));
+    assert_eq!(channels[0].next_outbound_htlc_minimum_msat, 1000);
+
+    shutdown(&[node1_addr, node2_addr]).await;
+}
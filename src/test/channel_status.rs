@@ -0,0 +1,128 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/channel_status/";
+
+async fn channel_status_res(node_address: SocketAddr, temporary_channel_id: &str) -> Response {
+    let payload = ChannelStatusRequest {
+        temporary_channel_id: temporary_channel_id.to_string(),
+    };
+    reqwest::Client::new()
+        .get(format!("http://{node_address}/channelstatus"))
+        .query(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn channel_status(
+    node_address: SocketAddr,
+    temporary_channel_id: &str,
+) -> ChannelStatusResponse {
+    println!("getting status for channel {temporary_channel_id} on node {node_address}");
+    let res = channel_status_res(node_address, temporary_channel_id).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<ChannelStatusResponse>()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn channel_status_success() {
+    initialize();
+
+    let test_dir_base = format!("{TEST_DIR_BASE}success/");
+    let test_dir_node1 = format!("{test_dir_base}node1");
+    let test_dir_node2 = format!("{test_dir_base}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let temporary_channel_id =
+        s!("0011223344556677889900112233445566778899001122334455667788990033");
+    let channel = open_channel_with_custom_data(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+        None,
+        None,
+        Some(&temporary_channel_id),
+        true,
+    )
+    .await;
+
+    let status = channel_status(node1_addr, &temporary_channel_id).await;
+    assert_eq!(status.temporary_channel_id, temporary_channel_id);
+    assert_eq!(status.channel_id, Some(channel.channel_id.clone()));
+    assert_eq!(status.stage, ChannelOpenStage::Ready);
+    assert_eq!(status.asset_id, Some(asset_id.clone()));
+    assert_eq!(status.funding_txid, channel.funding_txid);
+    assert!(status.confirmations.unwrap() >= status.required_confirmations.unwrap());
+    assert!(status.updated_at.is_some());
+
+    // the consignment and broadcast steps have been recorded along the way
+    let events = channel_events(node1_addr, &temporary_channel_id).await;
+    let kinds: Vec<ChannelEventKind> = events.events.iter().map(|e| e.kind).collect();
+    let sent_idx = kinds
+        .iter()
+        .position(|k| *k == ChannelEventKind::ConsignmentSent)
+        .unwrap();
+    let broadcast_idx = kinds
+        .iter()
+        .position(|k| *k == ChannelEventKind::FundingBroadcast)
+        .unwrap();
+    assert!(sent_idx < broadcast_idx);
+
+    // the status follows the channel once it is closed
+    close_channel(node1_addr, &channel.channel_id, &node2_pubkey, false).await;
+    wait_for_balance(node1_addr, &asset_id, 1000).await;
+    let status = channel_status(node1_addr, &temporary_channel_id).await;
+    assert_eq!(status.stage, ChannelOpenStage::Closed);
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn channel_status_fail() {
+    initialize();
+
+    let test_dir_base = format!("{TEST_DIR_BASE}fail/");
+    let test_dir_node1 = format!("{test_dir_base}node1");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    // invalid temporary channel ID
+    let res = channel_status_res(node1_addr, "odd").await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid channel ID",
+        "InvalidChannelID",
+    )
+    .await;
+
+    // unknown temporary channel ID
+    let res = channel_status_res(
+        node1_addr,
+        "0011223344556677889900112233445566778899001122334455667788990033",
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown temporary channel ID",
+        "UnknownTemporaryChannelId",
+    )
+    .await;
+}
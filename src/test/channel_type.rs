@@ -0,0 +1,112 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/channel_type/";
+
+async fn open_channel_scid_alias_res(
+    node_address: SocketAddr,
+    dest_peer_pubkey: &str,
+    public: bool,
+) -> Response {
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_opt_addr: format!("{dest_peer_pubkey}@127.0.0.1:{NODE3_PEER_PORT}"),
+        capacity_sat: 100_000,
+        push_msat: 0,
+        asset_amount: None,
+        asset_id: None,
+        public,
+        with_anchors: true,
+        scid_alias: Some(true),
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        temporary_channel_id: None,
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/openchannel"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn channel_type() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+
+    // a channel without anchors uses static remote keys
+    let channel = open_channel_with_custom_data(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        false,
+    )
+    .await;
+    assert!(!channel.with_anchors);
+    assert!(!channel.scid_alias);
+    let channels = list_channels(node2_addr).await;
+    assert!(!channels[0].with_anchors);
+
+    // SCID aliases hide the funding outpoint of private channels only
+    let res = open_channel_scid_alias_res(node1_addr, &node3_pubkey, true).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid channel type: scid_alias is only supported by private channels",
+        "InvalidChannelType",
+    )
+    .await;
+
+    let res = open_channel_scid_alias_res(node1_addr, &node3_pubkey, false).await;
+    _check_response_is_ok(res).await;
+    let t_0 = OffsetDateTime::now_utc();
+    let channel = loop {
+        let channels = list_channels(node1_addr).await;
+        if let Some(channel) = channels
+            .into_iter()
+            .find(|c| c.peer_pubkey == node3_pubkey && c.funding_txid.is_some())
+        {
+            break channel;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("channel has not been funded")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+    assert!(channel.with_anchors);
+    assert!(channel.scid_alias);
+    assert!(!channel.public);
+    let channels = list_channels(node3_addr).await;
+    assert!(channels[0].with_anchors);
+    assert!(channels[0].scid_alias);
+
+    shutdown(&[node1_addr, node2_addr, node3_addr]).await;
+}
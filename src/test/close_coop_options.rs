@@ -0,0 +1,136 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/close_coop_options/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn close_coop_options() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(100_000),
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // failures
+    let payload = CloseChannelRequest {
+        channel_id: channel.channel_id.clone(),
+        peer_pubkey: node2_pubkey.clone(),
+        force: true,
+        close_address: None,
+        fee_rate: Some(5),
+        confirmation_target: None,
+        totp_code: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/closechannel"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "close_address and fee_rate are only supported by cooperative closes",
+        "InvalidCloseOptions",
+    )
+    .await;
+    let payload = CloseChannelRequest {
+        force: false,
+        fee_rate: None,
+        confirmation_target: Some(6),
+        ..payload
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/closechannel"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "confirmation_target is only supported by force closes",
+        "InvalidCloseOptions",
+    )
+    .await;
+    let payload = CloseChannelRequest {
+        fee_rate: Some(0),
+        confirmation_target: None,
+        ..payload
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/closechannel"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "fee_rate cannot be less than 1",
+        "InvalidFeeRate",
+    )
+    .await;
+    let payload = CloseChannelRequest {
+        close_address: Some(s!("invalid")),
+        fee_rate: None,
+        ..payload
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/closechannel"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid address",
+        "InvalidAddress",
+    )
+    .await;
+
+    // close sending the funds to an address of another node
+    assert_eq!(btc_balance(node3_addr).await.vanilla.settled, 0);
+    let close_address = address(node3_addr).await;
+    let payload = CloseChannelRequest {
+        close_address: Some(close_address),
+        fee_rate: Some(5),
+        ..payload
+    };
+    close_channel_with_options(node1_addr, &payload).await;
+
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        if btc_balance(node3_addr).await.vanilla.settled > 0 {
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 20.0 {
+            panic!("closing funds not received")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+    let settled = btc_balance(node3_addr).await.vanilla.settled;
+    assert!(settled < 100_000);
+    assert!(settled > 90_000);
+}
@@ -0,0 +1,115 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/close_detail/";
+
+async fn close_detail_res(node_address: SocketAddr, channel_id: &str) -> Response {
+    let payload = CloseDetailRequest {
+        channel_id: channel_id.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/closedetail"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn close_detail(node_address: SocketAddr, channel_id: &str) -> CloseDetailResponse {
+    let res = close_detail_res(node_address, channel_id).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<CloseDetailResponse>()
+        .await
+        .unwrap()
+}
+
+async fn wait_for_recovery(node_address: SocketAddr, channel_id: &str) -> CloseDetailResponse {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let detail = close_detail(node_address, channel_id).await;
+        if detail.recovered {
+            return detail;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 70.0 {
+            panic!("channel outputs are taking too long to be recovered");
+        }
+        mine(false);
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn close_detail_force() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        Some(3000000),
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+
+    keysend(node1_addr, &node2_pubkey, None, Some(&asset_id), Some(150)).await;
+
+    // only closed channels can be reported
+    let res = close_detail_res(node1_addr, &channel.channel_id).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Channel has not been closed",
+        "ChannelNotClosed",
+    )
+    .await;
+    let unknown_channel_id = "00".repeat(32);
+    let res = close_detail_res(node1_addr, &unknown_channel_id).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown channel ID",
+        "UnknownChannelId",
+    )
+    .await;
+
+    // this sleep prevents non-deterministic issue where force close broadcasts an old commitment
+    // TX (one that still has an HTLC output)
+    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+
+    close_channel(node1_addr, &channel.channel_id, &node2_pubkey, true).await;
+    wait_for_balance(node1_addr, &asset_id, 850).await;
+    wait_for_balance(node2_addr, &asset_id, 150).await;
+
+    // each side reports the output carrying its assets and the TX that swept it
+    for (node_addr, asset_amount) in [(node1_addr, 450), (node2_addr, 150)] {
+        let detail = wait_for_recovery(node_addr, &channel.channel_id).await;
+        assert_eq!(detail.channel_id, channel.channel_id);
+        assert_eq!(detail.asset_id, Some(asset_id.clone()));
+        assert!(detail.pending_claims.is_empty());
+        let colored_output = detail
+            .outputs
+            .iter()
+            .find(|o| o.asset_amount.is_some())
+            .unwrap();
+        assert_eq!(colored_output.asset_id, Some(asset_id.clone()));
+        assert_eq!(colored_output.asset_amount, Some(asset_amount));
+        assert_eq!(colored_output.status, CloseOutputStatus::Swept);
+        assert!(colored_output.sweep_txid.is_some());
+    }
+}
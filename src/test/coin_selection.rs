@@ -0,0 +1,190 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/coin_selection/";
+
+async fn send_btc_with_coin_selection(
+    node_address: SocketAddr,
+    amount: u64,
+    address: &str,
+    coin_selection: CoinSelection,
+) -> Response {
+    let payload = SendBtcRequest {
+        amount,
+        address: address.to_string(),
+        fee_rate: FEE_RATE,
+        skip_sync: false,
+        broadcast_at: None,
+        hold_for_approval: false,
+        coin_selection: Some(coin_selection),
+        totp_code: None,
+        dry_run: false,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/sendbtc"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn coin_selection() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+    let node2_address = address(node2_addr).await;
+
+    // invalid selections are refused
+    let res = send_btc_with_coin_selection(
+        node1_addr,
+        1000,
+        &node2_address,
+        CoinSelection {
+            strategy: CoinSelectionStrategy::LargestFirst,
+            outpoints: Some(vec![]),
+            excluded_outpoints: Some(vec![]),
+        },
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "outpoints and excluded_outpoints cannot be both set",
+        "InvalidCoinSelection",
+    )
+    .await;
+    // colored UTXOs cannot be selected
+    let colored_outpoint = list_unspents(node1_addr).await[0].utxo.outpoint.clone();
+    let res = send_btc_with_coin_selection(
+        node1_addr,
+        1000,
+        &node2_address,
+        CoinSelection {
+            strategy: CoinSelectionStrategy::LargestFirst,
+            outpoints: Some(vec![colored_outpoint.clone()]),
+            excluded_outpoints: None,
+        },
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        &format!("{colored_outpoint} is not a spendable vanilla UTXO"),
+        "InvalidCoinSelection",
+    )
+    .await;
+
+    // every strategy is able to fund a send
+    let balance_before = btc_balance(node2_addr).await.vanilla.future;
+    for strategy in [
+        CoinSelectionStrategy::LargestFirst,
+        CoinSelectionStrategy::OldestFirst,
+        CoinSelectionStrategy::Privacy,
+    ] {
+        let res = send_btc_with_coin_selection(
+            node1_addr,
+            10_000,
+            &node2_address,
+            CoinSelection {
+                strategy,
+                outpoints: None,
+                excluded_outpoints: None,
+            },
+        )
+        .await;
+        _check_response_is_ok(res).await;
+        mine(false);
+    }
+    assert_eq!(
+        btc_balance(node2_addr).await.vanilla.future,
+        balance_before + 30_000
+    );
+
+    // colored and multisig channels cannot use a coin selection
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_opt_addr: format!("{node2_pubkey}@127.0.0.1:{NODE2_PEER_PORT}"),
+        capacity_sat: 100_000,
+        push_msat: 0,
+        asset_amount: Some(100),
+        asset_id: Some(asset_id),
+        public: true,
+        with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        temporary_channel_id: None,
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: Some(CoinSelection {
+            strategy: CoinSelectionStrategy::LargestFirst,
+            outpoints: None,
+            excluded_outpoints: None,
+        }),
+        totp_code: None,
+        dry_run: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/openchannel"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "only supported for vanilla channels funded by the node wallet",
+        "InvalidCoinSelection",
+    )
+    .await;
+
+    // a vanilla channel is funded with the selected coins
+    let payload = OpenChannelRequest {
+        asset_amount: None,
+        asset_id: None,
+        coin_selection: Some(CoinSelection {
+            strategy: CoinSelectionStrategy::Privacy,
+            outpoints: None,
+            excluded_outpoints: None,
+        }),
+        ..payload
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/openchannel"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node1_addr).await;
+        if let Some(funding_txid) = channels.first().and_then(|c| c.funding_txid.clone()) {
+            if !_get_txout(&funding_txid).is_empty() {
+                break;
+            }
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 50.0 {
+            panic!("cannot find funding TX")
+        }
+    }
+    mine_n_blocks(false, 6);
+    wait_for_usable_channels(node1_addr, 1).await;
+    wait_for_usable_channels(node2_addr, 1).await;
+
+    shutdown(&[node1_addr, node2_addr]).await;
+}
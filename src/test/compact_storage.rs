@@ -0,0 +1,88 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/compact_storage/";
+
+async fn compact_storage(
+    node_address: SocketAddr,
+    payment_retention_sec: Option<u64>,
+) -> CompactStorageResponse {
+    println!("compacting storage of node {node_address}");
+    let payload = CompactStorageRequest {
+        payment_retention_sec,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/maintenance/compact"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<CompactStorageResponse>()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn compact_storage_prune() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(100000),
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    keysend(node1_addr, &node2_pubkey, Some(3000000), None, None).await;
+    keysend(node1_addr, &node2_pubkey, Some(4000000), None, None).await;
+    let invoice = ln_invoice(node2_addr, Some(5000000), None, None, 3600)
+        .await
+        .invoice;
+    assert_eq!(list_payments(node1_addr).await.len(), 2);
+    assert_eq!(list_payments(node2_addr).await.len(), 3);
+
+    // recent payments are kept with the default retention
+    let summary = compact_storage(node1_addr, None).await;
+    assert_eq!(summary.pruned_payments, 0);
+    assert_eq!(summary.removed_monitors, 0);
+    assert_eq!(list_payments(node1_addr).await.len(), 2);
+
+    // resolved payments older than the retention get pruned, pending ones are kept
+    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    let summary = compact_storage(node1_addr, Some(1)).await;
+    assert_eq!(summary.pruned_payments, 2);
+    assert!(summary.reclaimed_bytes > 0);
+    assert!(list_payments(node1_addr).await.is_empty());
+    let summary = compact_storage(node2_addr, Some(1)).await;
+    assert_eq!(summary.pruned_payments, 2);
+    let payments = list_payments(node2_addr).await;
+    assert_eq!(payments.len(), 1);
+    assert_eq!(payments[0].status, HTLCStatus::Pending);
+
+    // a retention of 0 keeps all payments
+    let summary = compact_storage(node2_addr, Some(0)).await;
+    assert_eq!(summary.pruned_payments, 0);
+
+    // the node keeps working after the compaction
+    send_payment(node1_addr, invoice).await;
+    assert_eq!(list_payments(node1_addr).await.len(), 1);
+
+    shutdown(&[node1_addr, node2_addr]).await;
+}
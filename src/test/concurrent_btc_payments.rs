@@ -73,6 +73,15 @@ async fn concurrent_btc_payments() {
     let payload_1 = SendPaymentRequest {
         invoice: invoice_1.clone(),
         amt_msat: None,
+        timeout_sec: None,
+        max_retries: None,
+        max_fee_msat: None,
+        max_fee_ppm: None,
+        max_cltv_expiry_delta: None,
+        max_parts: None,
+        max_shard_amt_msat: None,
+        totp_code: None,
+        dry_run: false,
     };
     let res_1 = reqwest::Client::new()
         .post(format!("http://{node3_addr}/sendpayment"))
@@ -86,6 +95,15 @@ async fn concurrent_btc_payments() {
     let payload_2 = SendPaymentRequest {
         invoice: invoice_2.clone(),
         amt_msat: None,
+        timeout_sec: None,
+        max_retries: None,
+        max_fee_msat: None,
+        max_fee_ppm: None,
+        max_cltv_expiry_delta: None,
+        max_parts: None,
+        max_shard_amt_msat: None,
+        totp_code: None,
+        dry_run: false,
     };
     let res_2 = reqwest::Client::new()
         .post(format!("http://{node4_addr}/sendpayment"))
@@ -0,0 +1,93 @@
+use futures::future::join_all;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/concurrent_ln_payments/";
+
+const NUM_PAYMENTS: usize = 10;
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+#[traced_test]
+async fn concurrent_ln_payments() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(1_000_000),
+        Some(0),
+        None,
+        None,
+    )
+    .await;
+
+    // a request stuck on a peer that never answers the handshake
+    let silent_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let silent_port = silent_listener.local_addr().unwrap().port();
+    let silent_task = tokio::spawn(async move {
+        let mut streams = vec![];
+        while let Ok((stream, _)) = silent_listener.accept().await {
+            streams.push(stream);
+        }
+    });
+    let payload = ConnectPeerRequest {
+        peer_pubkey_and_addr: format!("{node2_pubkey}@127.0.0.1:{silent_port}"),
+        timeout_sec: Some(60),
+        retries: None,
+    };
+    let stuck_request = tokio::spawn(
+        reqwest::Client::new()
+            .post(format!("http://{node1_addr}/connectpeer"))
+            .json(&payload)
+            .send(),
+    );
+
+    // invoices are created in parallel
+    let amt_msat = 3_000_000;
+    let invoices = join_all(
+        (0..NUM_PAYMENTS).map(|_| ln_invoice(node2_addr, Some(amt_msat), None, None, 900)),
+    )
+    .await;
+
+    // payments are dispatched in parallel, none of them waiting for the others to complete
+    let responses = join_all(
+        invoices
+            .into_iter()
+            .map(|LNInvoiceResponse { invoice }| send_payment_raw(node1_addr, invoice)),
+    )
+    .await;
+    assert!(responses.iter().all(|r| r.status == HTLCStatus::Pending));
+
+    join_all(responses.iter().map(|r| {
+        wait_for_ln_payment(
+            node1_addr,
+            r.payment_hash.as_ref().unwrap(),
+            HTLCStatus::Succeeded,
+        )
+    }))
+    .await;
+    // none of the requests waited for the stuck one to complete
+    assert!(!stuck_request.is_finished());
+    stuck_request.abort();
+    silent_task.abort();
+
+    let channels = list_channels(node2_addr).await;
+    assert_eq!(channels.len(), 1);
+    assert_eq!(
+        channels[0].local_balance_sat * 1000,
+        NUM_PAYMENTS as u64 * amt_msat
+    );
+
+    shutdown(&[node1_addr, node2_addr]).await;
+}
@@ -0,0 +1,98 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/concurrent_rgb_spends/";
+
+async fn send_asset_res(node_address: SocketAddr, asset_id: &str, recipient_id: &str) -> Response {
+    let payload = SendBtcManyRequest {
+        recipients: vec![],
+        asset_recipients: vec![AssetRecipient {
+            asset_id: asset_id.to_string(),
+            assignment: Assignment::Fungible(100),
+            recipient_id: recipient_id.to_string(),
+            witness_data: None,
+            transport_endpoints: vec![PROXY_ENDPOINT_LOCAL.to_string()],
+        }],
+        fee_rate: FEE_RATE,
+        donation: true,
+        min_confirmations: 1,
+        skip_sync: false,
+        coin_selection: None,
+        totp_code: None,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/sendbtcmany"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+#[traced_test]
+async fn concurrent_rgb_spends() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let send_asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let channel_asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let recipient_id = rgb_invoice(node2_addr, None, false).await.recipient_id;
+
+    // an RGB send and a channel open dispatched together take turns on the RGB wallet: the send
+    // either completes before the open starts or is refused until the open completes
+    let (send_res, open_res) = tokio::join!(
+        send_asset_res(node1_addr, &send_asset_id, &recipient_id),
+        open_channel_raw(
+            node1_addr,
+            &node2_pubkey,
+            Some(NODE2_PEER_PORT),
+            None,
+            None,
+            Some(600),
+            Some(&channel_asset_id),
+            None,
+            None,
+            None,
+            true,
+            true,
+        ),
+    );
+    let channel = open_res.unwrap();
+    if send_res.status() != reqwest::StatusCode::OK {
+        check_response_is_nok(
+            send_res,
+            reqwest::StatusCode::FORBIDDEN,
+            "Cannot perform this operation while an open channel operation is in progress",
+            "OpenChannelInProgress",
+        )
+        .await;
+        let res = send_asset_res(node1_addr, &send_asset_id, &recipient_id).await;
+        _check_response_is_ok(res).await;
+    }
+
+    // neither spend invalidated the other one
+    mine(false);
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node1_addr).await;
+    assert_eq!(
+        asset_balance_spendable(node1_addr, &send_asset_id).await,
+        900
+    );
+    assert_eq!(
+        asset_balance_spendable(node2_addr, &send_asset_id).await,
+        100
+    );
+    assert_eq!(channel.asset_local_amount, Some(600));
+    assert_eq!(list_channels(node1_addr).await.len(), 1);
+
+    shutdown(&[node1_addr, node2_addr]).await;
+}
@@ -0,0 +1,70 @@
+use std::ffi::OsString;
+
+use crate::args::parse_startup_args_from;
+use crate::error::AppError;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/config_file/";
+
+fn argv(config_path: &Path, extra: &[&str]) -> Vec<OsString> {
+    let mut argv = vec![
+        OsString::from("rgb-lightning-node"),
+        OsString::from(format!("{TEST_DIR_BASE}node1")),
+        OsString::from("--config"),
+        config_path.as_os_str().to_owned(),
+    ];
+    argv.extend(extra.iter().map(OsString::from));
+    argv
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn config_file() {
+    initialize();
+
+    let _ = std::fs::remove_dir_all(TEST_DIR_BASE);
+    std::fs::create_dir_all(TEST_DIR_BASE).unwrap();
+    let config_path = PathBuf::from(format!("{TEST_DIR_BASE}rln.toml"));
+    std::fs::write(
+        &config_path,
+        r#"
+daemon_listening_port = 3011
+ldk_peer_listening_port = 9760
+max_route_hints = 5
+network = "regtest"
+disable_authentication = true
+"#,
+    )
+    .unwrap();
+
+    // options not set via CLI or environment are taken from the file
+    let args = parse_startup_args_from(argv(&config_path, &[])).unwrap();
+    assert_eq!(args.daemon_listening_port, 3011);
+    assert_eq!(args.ldk_peer_listening_port, 9760);
+    assert_eq!(args.max_route_hints, 5);
+    assert!(matches!(args.network, BitcoinNetwork::Regtest));
+    assert!(args.root_public_key.is_none());
+
+    // CLI args take precedence over environment variables, which take precedence over the file
+    std::env::set_var("RLN_LDK_PEER_LISTENING_PORT", "9761");
+    std::env::set_var("RLN_MAX_ROUTE_HINTS", "7");
+    let args = parse_startup_args_from(argv(&config_path, &["--max-route-hints", "6"])).unwrap();
+    std::env::remove_var("RLN_LDK_PEER_LISTENING_PORT");
+    std::env::remove_var("RLN_MAX_ROUTE_HINTS");
+    assert_eq!(args.daemon_listening_port, 3011);
+    assert_eq!(args.ldk_peer_listening_port, 9761);
+    assert_eq!(args.max_route_hints, 6);
+
+    // unknown options and values of the wrong type are rejected
+    std::fs::write(&config_path, "unknown_option = 1\n").unwrap();
+    let res = parse_startup_args_from(argv(&config_path, &["--disable-authentication"]));
+    assert!(matches!(res, Err(AppError::InvalidConfig(_))));
+    std::fs::write(&config_path, "max_route_hints = true\n").unwrap();
+    let res = parse_startup_args_from(argv(&config_path, &["--disable-authentication"]));
+    assert!(matches!(res, Err(AppError::InvalidConfig(_))));
+    std::fs::write(&config_path, "storage_directory_path = \"other\"\n").unwrap();
+    let res = parse_startup_args_from(argv(&config_path, &["--disable-authentication"]));
+    assert!(matches!(res, Err(AppError::InvalidConfig(_))));
+}
@@ -0,0 +1,143 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/connect_peer/";
+
+async fn connect_peer_res(
+    node_address: SocketAddr,
+    peer_pubkey_and_addr: &str,
+    timeout_sec: Option<u64>,
+    retries: Option<u8>,
+) -> Response {
+    let payload = ConnectPeerRequest {
+        peer_pubkey_and_addr: peer_pubkey_and_addr.to_string(),
+        timeout_sec,
+        retries,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/connectpeer"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn connect_peer() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+    let node2_peer = format!("{node2_pubkey}@127.0.0.1:{NODE2_PEER_PORT}");
+
+    let res = connect_peer_res(node1_addr, &node2_peer, Some(0), None).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "timeout_sec must be between 1 and 300",
+        "InvalidConnectOptions",
+    )
+    .await;
+    let res = connect_peer_res(node1_addr, &node2_peer, None, Some(11)).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "retries cannot be more than 10",
+        "InvalidConnectOptions",
+    )
+    .await;
+
+    // each failure is reported with its own reason
+    let res = connect_peer_res(
+        node1_addr,
+        &format!("{node2_pubkey}@unknown-host.invalid:{NODE2_PEER_PORT}"),
+        None,
+        None,
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Cannot resolve the peer address",
+        "PeerAddressUnresolved",
+    )
+    .await;
+
+    let closed_port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let res = connect_peer_res(
+        node1_addr,
+        &format!("{node2_pubkey}@127.0.0.1:{closed_port}"),
+        None,
+        Some(1),
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Peer is unreachable",
+        "PeerUnreachable",
+    )
+    .await;
+
+    // a listener that never answers the handshake
+    let silent_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let silent_port = silent_listener.local_addr().unwrap().port();
+    let silent_task = tokio::spawn(async move {
+        let mut streams = vec![];
+        while let Ok((stream, _)) = silent_listener.accept().await {
+            streams.push(stream);
+        }
+    });
+    let res = connect_peer_res(
+        node1_addr,
+        &format!("{node2_pubkey}@127.0.0.1:{silent_port}"),
+        Some(1),
+        None,
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Handshake with the peer timed out after 1s",
+        "PeerHandshakeTimeout",
+    )
+    .await;
+    silent_task.abort();
+
+    // node 2 drops a handshake addressed to another pubkey
+    let res = connect_peer_res(
+        node1_addr,
+        &format!("{node3_pubkey}@127.0.0.1:{NODE2_PEER_PORT}"),
+        None,
+        None,
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "The peer closed the connection during the handshake",
+        "PeerConnectionRejected",
+    )
+    .await;
+
+    let res = connect_peer_res(node1_addr, &node2_peer, Some(30), Some(2)).await;
+    _check_response_is_ok(res).await;
+    assert!(list_peers(node1_addr)
+        .await
+        .iter()
+        .any(|p| p.pubkey == node2_pubkey));
+
+    shutdown(&[node1_addr, node2_addr, node3_addr]).await;
+}
@@ -0,0 +1,97 @@
+use crate::routes::TransferKind;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/consignment_export_import/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn consignment_export_import() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+    fund_and_create_utxos(node3_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let recipient_id = rgb_invoice(node2_addr, None, false).await.recipient_id;
+    send_asset(
+        node1_addr,
+        &asset_id,
+        Assignment::Fungible(400),
+        recipient_id,
+        None,
+    )
+    .await;
+    mine(false);
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node1_addr).await;
+
+    let txid = list_transfers(node1_addr, &asset_id)
+        .await
+        .into_iter()
+        .find(|t| t.kind == TransferKind::Send)
+        .unwrap()
+        .txid
+        .unwrap();
+    let consignment = export_consignment(node1_addr, &asset_id, &txid).await;
+    assert!(!consignment.is_empty());
+
+    // the asset becomes known to a node it has never been sent to
+    assert!(list_assets(node3_addr).await.nia.unwrap().is_empty());
+    let imported_asset_id = import_consignment(node3_addr, consignment.clone(), &txid).await;
+    assert_eq!(imported_asset_id, asset_id);
+    let assets_nia = list_assets(node3_addr).await.nia.unwrap();
+    assert_eq!(assets_nia.len(), 1);
+    assert_eq!(assets_nia[0].asset_id, asset_id);
+
+    // importing a known asset again is fine
+    let imported_asset_id = import_consignment(node2_addr, consignment, &txid).await;
+    assert_eq!(imported_asset_id, asset_id);
+
+    // failures
+    let payload = ExportConsignmentRequest {
+        asset_id: asset_id.clone(),
+        txid: "0".repeat(64),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/exportconsignment"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown consignment",
+        "UnknownConsignment",
+    )
+    .await;
+
+    let form = reqwest::multipart::Form::new()
+        .part("file", reqwest::multipart::Part::bytes(vec![1, 2, 3]))
+        .text("txid", txid.clone());
+    let res = reqwest::Client::new()
+        .post(format!("http://{node3_addr}/importconsignment"))
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid consignment: cannot be decoded",
+        "InvalidConsignment",
+    )
+    .await;
+}
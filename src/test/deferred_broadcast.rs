@@ -0,0 +1,158 @@
+use crate::utils::get_current_timestamp;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/deferred_broadcast/";
+
+async fn send_btc_deferred_raw(
+    node_address: SocketAddr,
+    amount: u64,
+    address: &str,
+    broadcast_at: Option<u64>,
+    hold_for_approval: bool,
+) -> Response {
+    let payload = SendBtcRequest {
+        amount,
+        address: address.to_string(),
+        fee_rate: FEE_RATE,
+        skip_sync: false,
+        broadcast_at,
+        hold_for_approval,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/sendbtc"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn wait_for_no_pending_broadcasts(node_address: SocketAddr) {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        if pending_broadcasts(node_address).await.is_empty() {
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("deferred TXs have not been broadcast")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn deferred_broadcast() {
+    initialize();
+
+    let test_dir_base = format!("{TEST_DIR_BASE}deferred_broadcast/");
+    let test_dir_node1 = format!("{test_dir_base}node1");
+    let test_dir_node2 = format!("{test_dir_base}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    // held BTC send, broadcast only after approval
+    let node2_balance = btc_balance(node2_addr).await.vanilla.settled;
+    let address = address(node2_addr).await;
+    let res = send_btc_deferred_raw(node1_addr, 10000, &address, None, true).await;
+    let txid = _check_response_is_ok(res)
+        .await
+        .json::<SendBtcResponse>()
+        .await
+        .unwrap()
+        .txid;
+    let pending = pending_broadcasts(node1_addr).await;
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].txid, txid);
+    assert_eq!(pending[0].kind, PendingBroadcastKind::Btc);
+    assert!(pending[0].hold_for_approval);
+    assert!(pending[0].approved_at.is_none());
+    tokio::time::sleep(std::time::Duration::from_secs(6)).await;
+    assert_eq!(pending_broadcasts(node1_addr).await.len(), 1);
+    assert!(approve_broadcast(node1_addr, &txid).await);
+    assert!(pending_broadcasts(node1_addr).await.is_empty());
+    mine(false);
+    assert_eq!(
+        btc_balance(node2_addr).await.vanilla.settled,
+        node2_balance + 10000
+    );
+
+    // scheduled asset send, broadcast by the node when due
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let recipient_id = rgb_invoice(node2_addr, None, false).await.recipient_id;
+    let payload = SendAssetRequest {
+        asset_id: asset_id.clone(),
+        assignment: Assignment::Fungible(400),
+        recipient_id,
+        witness_data: None,
+        donation: true,
+        fee_rate: FEE_RATE,
+        min_confirmations: 1,
+        transport_endpoints: vec![PROXY_ENDPOINT_LOCAL.to_string()],
+        skip_sync: false,
+        broadcast_at: Some(get_current_timestamp() + 5),
+        hold_for_approval: false,
+        totp_code: None,
+        dry_run: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/sendasset"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let txid = _check_response_is_ok(res)
+        .await
+        .json::<SendAssetResponse>()
+        .await
+        .unwrap()
+        .txid;
+    let pending = pending_broadcasts(node1_addr).await;
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].txid, txid);
+    assert_eq!(pending[0].kind, PendingBroadcastKind::Asset);
+    wait_for_no_pending_broadcasts(node1_addr).await;
+    mine(false);
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node1_addr).await;
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 600);
+    assert_eq!(asset_balance_spendable(node2_addr, &asset_id).await, 400);
+
+    // failures
+    let res = send_btc_deferred_raw(
+        node1_addr,
+        10000,
+        &address,
+        Some(get_current_timestamp() - 1),
+        false,
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "broadcast_at must be in the future",
+        "InvalidBroadcastSchedule",
+    )
+    .await;
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/approvebroadcast/{txid}"))
+        .json(&ApproveBroadcastRequest { totp_code: None })
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown pending broadcast",
+        "UnknownPendingBroadcast",
+    )
+    .await;
+}
@@ -0,0 +1,61 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/descriptors/";
+
+async fn address_res(node_address: SocketAddr, address_type: &str) -> Response {
+    reqwest::Client::new()
+        .post(format!(
+            "http://{node_address}/address?address_type={address_type}"
+        ))
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn descriptors() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    // wallet addresses are taproot ones
+    assert!(address(node1_addr).await.starts_with("bcrt1p"));
+    let res = address_res(node1_addr, "P2tr").await;
+    let address = _check_response_is_ok(res)
+        .await
+        .json::<AddressResponse>()
+        .await
+        .unwrap();
+    assert!(address.address.starts_with("bcrt1p"));
+    assert_eq!(address.address_type, AddressType::P2tr);
+    let res = address_res(node1_addr, "P2wpkh").await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unsupported address type: P2wpkh, the wallet only derives P2tr addresses",
+        "UnsupportedAddressType",
+    )
+    .await;
+
+    // the exported descriptors are checksummed taproot ones
+    let res = reqwest::Client::new()
+        .get(format!("http://{node1_addr}/descriptors"))
+        .send()
+        .await
+        .unwrap();
+    let descriptors = _check_response_is_ok(res)
+        .await
+        .json::<DescriptorsResponse>()
+        .await
+        .unwrap();
+    for descriptor in [&descriptors.vanilla, &descriptors.colored] {
+        assert!(descriptor.starts_with("tr("));
+        assert!(descriptor.contains("/0/*)#"));
+    }
+    assert_ne!(descriptors.vanilla, descriptors.colored);
+
+    shutdown(&[node1_addr]).await;
+}
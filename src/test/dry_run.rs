@@ -0,0 +1,149 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/dry_run/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn dry_run() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let unspents_before = list_unspents(node1_addr).await.len();
+
+    // an on-chain send reports its inputs and fee without broadcasting
+    let node2_address = address(node2_addr).await;
+    let payload = SendBtcRequest {
+        amount: 10_000,
+        address: node2_address,
+        fee_rate: FEE_RATE,
+        skip_sync: false,
+        broadcast_at: None,
+        hold_for_approval: false,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: true,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/sendbtc"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let response = _check_response_is_ok(res)
+        .await
+        .json::<SendBtcResponse>()
+        .await
+        .unwrap();
+    let dry_run = response.dry_run.unwrap();
+    assert!(dry_run.fee_sat.unwrap() > 0);
+    assert!(!dry_run.inputs.is_empty());
+    let balance_before = btc_balance(node2_addr).await.vanilla.future;
+    mine(false);
+    assert_eq!(btc_balance(node2_addr).await.vanilla.future, balance_before);
+
+    // an asset send reports the allocations it would move, leaving no transfer behind
+    let recipient_id = rgb_invoice(node2_addr, None, false).await.recipient_id;
+    let payload = SendAssetRequest {
+        asset_id: asset_id.clone(),
+        assignment: Assignment::Fungible(100),
+        recipient_id,
+        witness_data: None,
+        donation: true,
+        fee_rate: FEE_RATE,
+        min_confirmations: 1,
+        transport_endpoints: vec![PROXY_ENDPOINT_LOCAL.to_string()],
+        skip_sync: false,
+        broadcast_at: None,
+        hold_for_approval: false,
+        totp_code: None,
+        dry_run: true,
+    };
+    let transfers_before = list_transfers(node1_addr, &asset_id).await.len();
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/sendasset"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let response = _check_response_is_ok(res)
+        .await
+        .json::<SendAssetResponse>()
+        .await
+        .unwrap();
+    let dry_run = response.dry_run.unwrap();
+    assert!(dry_run.inputs.iter().any(|i| i
+        .rgb_allocations
+        .iter()
+        .any(|a| a.asset_id.as_deref() == Some(asset_id.as_str()))));
+    assert_eq!(
+        list_transfers(node1_addr, &asset_id).await.len(),
+        transfers_before
+    );
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 1000);
+
+    // a channel open goes through the checks without contacting the peer
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_opt_addr: format!("{node2_pubkey}@127.0.0.1:{NODE2_PEER_PORT}"),
+        capacity_sat: 100_000,
+        push_msat: 0,
+        asset_amount: Some(100),
+        asset_id: Some(asset_id.clone()),
+        public: true,
+        with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        temporary_channel_id: None,
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: true,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/openchannel"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let response = _check_response_is_ok(res)
+        .await
+        .json::<OpenChannelResponse>()
+        .await
+        .unwrap();
+    assert!(response.dry_run.unwrap().fee_sat.unwrap() > 0);
+    assert!(list_channels(node1_addr).await.is_empty());
+    assert!(list_peers(node1_addr).await.is_empty());
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/openchannel"))
+        .json(&OpenChannelRequest {
+            asset_amount: Some(1_000_000),
+            ..payload
+        })
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Not enough assets",
+        "InsufficientAssets",
+    )
+    .await;
+
+    assert_eq!(list_unspents(node1_addr).await.len(), unspents_before);
+}
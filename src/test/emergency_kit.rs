@@ -0,0 +1,103 @@
+use crate::backup::restore_backup;
+use crate::disk::{
+    CHANNEL_MONITORS_DIR, EMERGENCY_KIT_CONTENTS_FNAME, EMERGENCY_KIT_INSTRUCTIONS_FNAME,
+};
+use crate::error::APIError;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/emergency_kit/";
+
+fn decrypt_kit(kit: &EmergencyKitResponse, password: &str, name: &str) -> PathBuf {
+    let kit_path = PathBuf::from(format!("{TEST_DIR_BASE}{name}"));
+    let kit_dir = PathBuf::from(format!("{TEST_DIR_BASE}{name}_decrypted"));
+    if kit_dir.exists() {
+        std::fs::remove_dir_all(&kit_dir).unwrap();
+    }
+    std::fs::write(&kit_path, hex_str_to_vec(&kit.kit_hex).unwrap()).unwrap();
+    restore_backup(&kit_path, password, &kit_dir).unwrap();
+    kit_dir
+}
+
+fn read_kit_contents(kit_dir: &Path) -> EmergencyKitContents {
+    let contents = std::fs::read_to_string(kit_dir.join(EMERGENCY_KIT_CONTENTS_FNAME)).unwrap();
+    serde_json::from_str(&contents).unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn emergency_kit() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, node1_password) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let kit = super::emergency_kit(node1_addr).await;
+    assert_eq!(kit.num_channels, 0);
+    let kit_dir = decrypt_kit(&kit, &node1_password, "kit_empty");
+    let contents = read_kit_contents(&kit_dir);
+    assert!(contents.channels.is_empty());
+    assert_eq!(contents.node_pubkey, node_info(node1_addr).await.pubkey);
+    assert_eq!(contents.ldk_seed_derivation_path, "m/535'");
+    assert!(!contents.account_xpub_vanilla.is_empty());
+    assert!(!contents.account_xpub_colored.is_empty());
+    assert!(kit_dir.join(EMERGENCY_KIT_INSTRUCTIONS_FNAME).exists());
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(100_000),
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    let kit = super::emergency_kit(node1_addr).await;
+    assert_eq!(kit.num_channels, 1);
+    let kit_dir = decrypt_kit(&kit, &node1_password, "kit_open");
+    let contents = read_kit_contents(&kit_dir);
+    assert_eq!(contents.channels.len(), 1);
+    let kit_channel = &contents.channels[0];
+    assert_eq!(kit_channel.channel_id, channel.channel_id);
+    assert_eq!(kit_channel.peer_pubkey, node2_pubkey);
+    assert_eq!(
+        Some(&kit_channel.funding_txid),
+        channel.funding_txid.as_ref()
+    );
+    assert_eq!(kit_channel.capacity_sat, 100_000);
+    assert!(kit_channel.asset_id.is_none());
+    assert_eq!(
+        kit_dir
+            .join(CHANNEL_MONITORS_DIR)
+            .read_dir()
+            .unwrap()
+            .count(),
+        1
+    );
+    let update_id_after_open = kit_channel.latest_update_id;
+
+    // a payment changes the channel state, so the kit gets refreshed
+    keysend(node1_addr, &node2_pubkey, Some(3000000), None, None).await;
+    let kit_after_payment = super::emergency_kit(node1_addr).await;
+    assert!(kit_after_payment.updated_at >= kit.updated_at);
+    let kit_dir = decrypt_kit(&kit_after_payment, &node1_password, "kit_payment");
+    let contents = read_kit_contents(&kit_dir);
+    assert!(contents.channels[0].latest_update_id > update_id_after_open);
+
+    // the kit can only be decrypted with the node password
+    let kit_path = PathBuf::from(format!("{TEST_DIR_BASE}kit_payment"));
+    let wrong_dir = PathBuf::from(format!("{TEST_DIR_BASE}kit_wrong_password"));
+    assert!(matches!(
+        restore_backup(&kit_path, "wrong password", &wrong_dir),
+        Err(APIError::WrongPassword)
+    ));
+}
@@ -0,0 +1,175 @@
+use lightning::util::persist::{
+    KVStoreSync, CHANNEL_MANAGER_PERSISTENCE_KEY, CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE,
+};
+use rgb_lib::bdk_wallet::keys::bip39::Mnemonic;
+
+use crate::disk::INBOUND_PAYMENTS_FNAME;
+use crate::error::AppError;
+use crate::store::{check_storage_encryption, storage_cipher, NodeStore, ENCRYPTED_VALUE_MAGIC};
+use crate::utils::LDK_DIR;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/encrypted_storage/";
+
+fn encrypted_args(node_test_dir: &str, node_peer_port: u16) -> UserArgs {
+    UserArgs {
+        storage_dir_path: node_test_dir.into(),
+        ldk_peer_listening_port: node_peer_port,
+        encrypt_storage: true,
+        ..Default::default()
+    }
+}
+
+fn is_encrypted(path: &Path) -> bool {
+    std::fs::read(path)
+        .unwrap()
+        .starts_with(ENCRYPTED_VALUE_MAGIC)
+}
+
+fn monitor_paths(node_test_dir: &str) -> Vec<PathBuf> {
+    std::fs::read_dir(
+        Path::new(node_test_dir)
+            .join(LDK_DIR)
+            .join(CHANNEL_MONITOR_PERSISTENCE_PRIMARY_NAMESPACE),
+    )
+    .unwrap()
+    .map(|e| e.unwrap().path())
+    .filter(|p| p.is_file())
+    .collect()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn encrypted_storage() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) =
+        start_node_with_args(encrypted_args(&test_dir_node1, NODE1_PEER_PORT), false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(node2_addr, None, Some(&asset_id), Some(100), 900).await;
+    let payment = send_payment(node1_addr, invoice).await;
+
+    shutdown(&[node1_addr, node2_addr]).await;
+
+    // node 1 data can't be read without its seed, so it refuses to start without encryption
+    let node1_ldk_dir = Path::new(&test_dir_node1).join(LDK_DIR);
+    assert!(is_encrypted(
+        &node1_ldk_dir.join(CHANNEL_MANAGER_PERSISTENCE_KEY)
+    ));
+    assert!(monitor_paths(&test_dir_node1)
+        .iter()
+        .all(|p| is_encrypted(p)));
+    let err = check_storage_encryption(
+        Path::new(&test_dir_node1),
+        StorageBackend::Filesystem,
+        false,
+    )
+    .unwrap_err();
+    assert!(matches!(err, AppError::InvalidStorageEncryption(_)));
+
+    // node 2 data is in clear until the node gets unlocked with encryption enabled
+    let node2_ldk_dir = Path::new(&test_dir_node2).join(LDK_DIR);
+    assert!(!is_encrypted(
+        &node2_ldk_dir.join(CHANNEL_MANAGER_PERSISTENCE_KEY)
+    ));
+    assert!(monitor_paths(&test_dir_node2)
+        .iter()
+        .all(|p| !is_encrypted(p)));
+
+    // both nodes get their channel and payment back
+    let (node1_addr, _) =
+        start_node_with_args(encrypted_args(&test_dir_node1, NODE1_PEER_PORT), true).await;
+    let (node2_addr, _) =
+        start_node_with_args(encrypted_args(&test_dir_node2, NODE2_PEER_PORT), true).await;
+    assert!(monitor_paths(&test_dir_node2)
+        .iter()
+        .all(|p| is_encrypted(p)));
+    for node_addr in [node1_addr, node2_addr] {
+        let channels = list_channels(node_addr).await;
+        assert_eq!(channels.len(), 1);
+        assert_eq!(channels[0].channel_id, channel.channel_id);
+        assert!(list_payments(node_addr)
+            .await
+            .iter()
+            .any(|p| p.payment_hash == payment.payment_hash));
+    }
+
+    close_channel(node1_addr, &channel.channel_id, &node2_pubkey, false).await;
+    wait_for_balance(node1_addr, &asset_id, 900).await;
+    wait_for_balance(node2_addr, &asset_id, 100).await;
+
+    shutdown(&[node1_addr, node2_addr]).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn plaintext_rejected_once_encrypted() {
+    let ldk_data_dir = Path::new(TEST_DIR_BASE).join("plaintext").join(LDK_DIR);
+    let _ = std::fs::remove_dir_all(&ldk_data_dir);
+    std::fs::create_dir_all(&ldk_data_dir).unwrap();
+    let mnemonic = Mnemonic::from_str(
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+         about",
+    )
+    .unwrap();
+
+    // data written in clear gets encrypted once the store is opened with a cipher
+    let store = NodeStore::new(&ldk_data_dir, StorageBackend::Filesystem).unwrap();
+    KVStoreSync::write(&store, "", "", CHANNEL_MANAGER_PERSISTENCE_KEY, vec![1]).unwrap();
+    KVStoreSync::write(&store, "", "", INBOUND_PAYMENTS_FNAME, vec![2]).unwrap();
+    let store = NodeStore::open(
+        &ldk_data_dir,
+        StorageBackend::Filesystem,
+        None,
+        Some(storage_cipher(&mnemonic)),
+    )
+    .await
+    .unwrap();
+    assert!(is_encrypted(&ldk_data_dir.join(INBOUND_PAYMENTS_FNAME)));
+    assert_eq!(
+        KVStoreSync::read(&store, "", "", INBOUND_PAYMENTS_FNAME).unwrap(),
+        vec![2]
+    );
+
+    // from then on a value in clear is rejected, even after reopening the store
+    std::fs::write(ldk_data_dir.join(INBOUND_PAYMENTS_FNAME), [3]).unwrap();
+    let store = NodeStore::open(
+        &ldk_data_dir,
+        StorageBackend::Filesystem,
+        None,
+        Some(storage_cipher(&mnemonic)),
+    )
+    .await
+    .unwrap();
+    let err = KVStoreSync::read(&store, "", "", INBOUND_PAYMENTS_FNAME).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(!is_encrypted(&ldk_data_dir.join(INBOUND_PAYMENTS_FNAME)));
+    assert!(matches!(
+        check_storage_encryption(
+            ldk_data_dir.parent().unwrap(),
+            StorageBackend::Filesystem,
+            false,
+        ),
+        Err(AppError::InvalidStorageEncryption(_))
+    ));
+}
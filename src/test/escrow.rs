@@ -0,0 +1,197 @@
+use bitcoin::hashes::{sha256::Hash as Sha256, Hash};
+use bitcoin::secp256k1::{PublicKey, Secp256k1, SecretKey};
+
+use crate::utils::hex_str;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/escrow/";
+
+async fn escrow_create(node_address: SocketAddr, payment_hash: &str, arbiter: &str) -> Escrow {
+    let payload = EscrowCreateRequest {
+        payment_hash: payment_hash.to_string(),
+        arbiter_pubkey: arbiter.to_string(),
+        description: Some(s!("second-hand bike")),
+        amt_msat: Some(5000000),
+        asset_id: None,
+        asset_amount: None,
+        expiry_sec: 900,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/escrowcreate"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EscrowCreateResponse>()
+        .await
+        .unwrap()
+        .escrow
+}
+
+async fn escrow_resolve_res(
+    node_address: SocketAddr,
+    escrow_id: &str,
+    decision: EscrowDecision,
+    payment_preimage: Option<String>,
+    signature: String,
+) -> Response {
+    let payload = EscrowResolveRequest {
+        escrow_id: escrow_id.to_string(),
+        decision,
+        payment_preimage,
+        signature,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/escrowresolve"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn list_escrows(node_address: SocketAddr) -> Vec<Escrow> {
+    let res = reqwest::get(format!("http://{node_address}/listescrows"))
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListEscrowsResponse>()
+        .await
+        .unwrap()
+        .escrows
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn escrow() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(100000),
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // the arbiter holds the preimages and signs its decisions with its key
+    let arbiter_key = SecretKey::from_slice(&[7; 32]).unwrap();
+    let arbiter_pubkey = PublicKey::from_secret_key(&Secp256k1::new(), &arbiter_key).to_string();
+    let other_key = SecretKey::from_slice(&[8; 32]).unwrap();
+    let sign = |message: &str, key: &SecretKey| {
+        lightning::util::message_signing::sign(message.as_bytes(), key)
+    };
+
+    // settled on the arbiter decision
+    let preimage = [1; 32];
+    let payment_hash = Sha256::hash(&preimage).to_string();
+    let escrow = escrow_create(node2_addr, &payment_hash, &arbiter_pubkey).await;
+    assert_eq!(escrow.status, EscrowStatus::AwaitingPayment);
+    send_payment_raw(node1_addr, escrow.invoice.clone()).await;
+    wait_for_invoice_status(node2_addr, &escrow.invoice, InvoiceStatus::Held).await;
+    let escrows = list_escrows(node2_addr).await;
+    assert_eq!(escrows[0].status, EscrowStatus::Funded);
+
+    let res = escrow_resolve_res(
+        node2_addr,
+        &escrow.escrow_id,
+        EscrowDecision::Settle,
+        Some(hex_str(&preimage)),
+        sign(&escrow.settle_message, &other_key),
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid escrow: the decision is not signed by the arbiter",
+        "InvalidEscrow",
+    )
+    .await;
+    // a signature for the other decision cannot be reused
+    let res = escrow_resolve_res(
+        node2_addr,
+        &escrow.escrow_id,
+        EscrowDecision::Settle,
+        Some(hex_str(&preimage)),
+        sign(&escrow.refund_message, &arbiter_key),
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid escrow: the decision is not signed by the arbiter",
+        "InvalidEscrow",
+    )
+    .await;
+
+    let res = escrow_resolve_res(
+        node2_addr,
+        &escrow.escrow_id,
+        EscrowDecision::Settle,
+        Some(hex_str(&preimage)),
+        sign(&escrow.settle_message, &arbiter_key),
+    )
+    .await;
+    let resolved = _check_response_is_ok(res)
+        .await
+        .json::<EscrowResolveResponse>()
+        .await
+        .unwrap()
+        .escrow;
+    assert_eq!(resolved.status, EscrowStatus::Settled);
+    assert!(resolved.resolved_at.is_some());
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Succeeded).await;
+
+    let res = escrow_resolve_res(
+        node2_addr,
+        &escrow.escrow_id,
+        EscrowDecision::Refund,
+        None,
+        sign(&escrow.refund_message, &arbiter_key),
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "The escrow has already been resolved",
+        "EscrowAlreadyResolved",
+    )
+    .await;
+
+    // refunded on the arbiter decision
+    let payment_hash = Sha256::hash(&[2; 32]).to_string();
+    let escrow = escrow_create(node2_addr, &payment_hash, &arbiter_pubkey).await;
+    send_payment_raw(node1_addr, escrow.invoice.clone()).await;
+    wait_for_invoice_status(node2_addr, &escrow.invoice, InvoiceStatus::Held).await;
+    let res = escrow_resolve_res(
+        node2_addr,
+        &escrow.escrow_id,
+        EscrowDecision::Refund,
+        None,
+        sign(&escrow.refund_message, &arbiter_key),
+    )
+    .await;
+    _check_response_is_ok(res).await;
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Failed).await;
+    let escrows = list_escrows(node2_addr).await;
+    let refunded = escrows
+        .iter()
+        .find(|e| e.escrow_id == escrow.escrow_id)
+        .unwrap();
+    assert_eq!(refunded.status, EscrowStatus::Refunded);
+}
@@ -0,0 +1,250 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/external_funding/";
+
+async fn fund_channel_complete_res(
+    node_address: SocketAddr,
+    temporary_channel_id: &str,
+    signed_psbt: &str,
+) -> Response {
+    let payload = FundChannelCompleteRequest {
+        temporary_channel_id: temporary_channel_id.to_string(),
+        signed_psbt: signed_psbt.to_string(),
+        totp_code: None,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/fundchannelcomplete"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+/// Build and sign, with the wallet of another node, a PSBT paying the address
+async fn external_psbt(wallet_address: SocketAddr, address: &str, amount: u64) -> (String, String) {
+    let payload = FundPsbtRequest {
+        recipients: vec![BtcRecipient {
+            address: address.to_string(),
+            amount,
+        }],
+        opret_commitment: false,
+        fee_rate: FEE_RATE,
+        coin_selection: None,
+        skip_sync: false,
+        totp_code: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{wallet_address}/fundpsbt"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let unsigned_psbt = _check_response_is_ok(res)
+        .await
+        .json::<FundPsbtResponse>()
+        .await
+        .unwrap()
+        .psbt;
+    let payload = SignPsbtRequest {
+        psbt: unsigned_psbt.clone(),
+        totp_code: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{wallet_address}/signpsbt"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let signed_psbt = _check_response_is_ok(res)
+        .await
+        .json::<SignPsbtResponse>()
+        .await
+        .unwrap()
+        .psbt;
+    (unsigned_psbt, signed_psbt)
+}
+
+async fn open_channel_external_res(
+    node_address: SocketAddr,
+    dest_peer_pubkey: &str,
+    asset: Option<(&str, u64)>,
+) -> Response {
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_opt_addr: format!("{dest_peer_pubkey}@127.0.0.1:{NODE2_PEER_PORT}"),
+        capacity_sat: 100_000,
+        push_msat: 0,
+        asset_amount: asset.map(|(_, amt)| amt),
+        asset_id: asset.map(|(id, _)| id.to_string()),
+        public: true,
+        with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        temporary_channel_id: None,
+        multisig_funding: None,
+        external_funding: Some(ExternalFunding { timeout_sec: None }),
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/openchannel"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn pending_external_fundings(node_address: SocketAddr) -> Vec<PendingExternalFunding> {
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/pendingfundings"))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<PendingFundingsResponse>()
+        .await
+        .unwrap()
+        .external_fundings
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn external_funding() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+    // node 3 plays the external wallet
+    fund_and_create_utxos(node3_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let btc_balance_before = btc_balance(node1_addr).await.vanilla.spendable;
+
+    let res = open_channel_external_res(node1_addr, &node2_pubkey, Some((&asset_id, 100))).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid external funding: RGB channels cannot be funded by an external PSBT",
+        "InvalidExternalFunding",
+    )
+    .await;
+
+    let res = open_channel_external_res(node1_addr, &node2_pubkey, None).await;
+    let temporary_channel_id = _check_response_is_ok(res)
+        .await
+        .json::<OpenChannelResponse>()
+        .await
+        .unwrap()
+        .temporary_channel_id;
+
+    // the funding output is known once the peer accepts the channel
+    let t_0 = OffsetDateTime::now_utc();
+    let funding = loop {
+        let fundings = pending_external_fundings(node1_addr).await;
+        assert_eq!(fundings.len(), 1);
+        if fundings[0].funding_address.is_some() {
+            break fundings.into_iter().next().unwrap();
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("funding output is not known")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+    assert_eq!(funding.temporary_channel_id, temporary_channel_id);
+    assert_eq!(funding.peer_pubkey, node2_pubkey);
+    assert_eq!(funding.capacity_sat, 100_000);
+    assert_eq!(funding.expires_at - funding.created_at, 1800);
+    assert!(pending_fundings(node1_addr).await.is_empty());
+    let events = channel_events(node1_addr, &temporary_channel_id)
+        .await
+        .events;
+    assert!(events
+        .iter()
+        .any(|e| e.kind == ChannelEventKind::FundingOutputReady));
+    let funding_address = funding.funding_address.unwrap();
+
+    // the PSBT must be signed and pay the channel capacity to the funding output
+    let (unsigned_psbt, _) = external_psbt(node3_addr, &funding_address, 100_000).await;
+    let res = fund_channel_complete_res(node1_addr, &temporary_channel_id, &unsigned_psbt).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid funding PSBT: input 0 is not finalized",
+        "InvalidFundingPsbt",
+    )
+    .await;
+    let (_, wrong_amount_psbt) = external_psbt(node3_addr, &funding_address, 90_000).await;
+    let res =
+        fund_channel_complete_res(node1_addr, &temporary_channel_id, &wrong_amount_psbt).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid funding PSBT: the funding output must pay 100000 sats",
+        "InvalidFundingPsbt",
+    )
+    .await;
+
+    let (_, signed_psbt) = external_psbt(node3_addr, &funding_address, 100_000).await;
+    let res = fund_channel_complete_res(node1_addr, &temporary_channel_id, &signed_psbt).await;
+    let completed = _check_response_is_ok(res)
+        .await
+        .json::<FundChannelCompleteResponse>()
+        .await
+        .unwrap();
+    assert!(pending_external_fundings(node1_addr).await.is_empty());
+
+    // the node broadcasts the funding TX once the peer has signed
+    let t_0 = OffsetDateTime::now_utc();
+    while _get_txout(&completed.funding_txid).is_empty() {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("funding TX has not been broadcast")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+    mine_n_blocks(false, 6);
+    wait_for_usable_channels(node1_addr, 1).await;
+    let channels = list_channels(node1_addr).await;
+    assert_eq!(channels.len(), 1);
+    assert_eq!(channels[0].channel_id, completed.channel_id);
+    assert_eq!(channels[0].capacity_sat, 100_000);
+
+    // the node wallet is not used to fund the channel
+    assert_eq!(
+        btc_balance(node1_addr).await.vanilla.spendable,
+        btc_balance_before
+    );
+
+    // an external funding can be aborted before the PSBT is provided
+    let res = open_channel_external_res(node1_addr, &node2_pubkey, None).await;
+    let temporary_channel_id = _check_response_is_ok(res)
+        .await
+        .json::<OpenChannelResponse>()
+        .await
+        .unwrap()
+        .temporary_channel_id;
+    fund_channel_abort(node1_addr, &temporary_channel_id).await;
+    assert!(pending_external_fundings(node1_addr).await.is_empty());
+    let events = channel_events(node1_addr, &temporary_channel_id)
+        .await
+        .events;
+    assert!(events
+        .iter()
+        .any(|e| e.kind == ChannelEventKind::FundingFailed));
+
+    shutdown(&[node1_addr, node2_addr, node3_addr]).await;
+}
@@ -1,3 +1,5 @@
+use crate::routes::{TransferKind, TransferStatus};
+
 use super::*;
 
 const TEST_DIR_BASE: &str = "tmp/fail_transfers/";
@@ -21,3 +23,70 @@ async fn success() {
     let transfers_changed = fail_transfers(node1_addr, Some(batch_transfer_idx)).await;
     assert!(transfers_changed);
 }
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn fail_single_transfer() {
+    initialize();
+
+    let test_dir_base = format!("{TEST_DIR_BASE}fail_single_transfer/");
+    let test_dir_node1 = format!("{test_dir_base}node1");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    rgb_invoice(node1_addr, Some(asset_id.clone()), false).await;
+
+    let transfers = list_transfers(node1_addr, &asset_id).await;
+    let issuance_idx = transfers
+        .iter()
+        .find(|t| t.kind == TransferKind::Issuance)
+        .unwrap()
+        .idx;
+    let receive_idx = transfers
+        .iter()
+        .find(|t| t.kind == TransferKind::ReceiveBlind)
+        .unwrap()
+        .idx;
+
+    fail_transfer(node1_addr, receive_idx).await;
+    let transfer = transfer_detail(node1_addr, receive_idx).await.transfer;
+    assert_eq!(transfer.status, TransferStatus::Failed);
+
+    // failures
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/failtransfer"))
+        .json(&FailTransferRequest {
+            idx: issuance_idx,
+            skip_sync: false,
+        })
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Batch transfer cannot be set to failed status",
+        "CannotFailBatchTransfer",
+    )
+    .await;
+
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/failtransfer"))
+        .json(&FailTransferRequest {
+            idx: 9999,
+            skip_sync: false,
+        })
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Transfer not found: 9999",
+        "TransferNotFound",
+    )
+    .await;
+}
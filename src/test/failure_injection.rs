@@ -0,0 +1,98 @@
+use super::*;
+
+use crate::routes::{InjectFailureRequest, InjectedFailure};
+
+const TEST_DIR_BASE: &str = "tmp/failure_injection/";
+
+async fn inject_failure(
+    node_address: SocketAddr,
+    failure: Option<InjectedFailure>,
+    proxy_delay_ms: Option<u64>,
+) {
+    println!("injecting failure {failure:?} on node {node_address}");
+    let payload = InjectFailureRequest {
+        failure,
+        proxy_delay_ms,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/testing/injectfailure"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn failure_injection() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(100000),
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // the sender finds no route, only for the next payment
+    inject_failure(node1_addr, Some(InjectedFailure::NoRoute), None).await;
+    let keysend_res = _keysend_raw(node1_addr, &node2_pubkey, None, None, None).await;
+    wait_for_ln_payment(node1_addr, &keysend_res.payment_hash, HTLCStatus::Failed).await;
+    keysend(node1_addr, &node2_pubkey, None, None, None).await;
+
+    // the recipient fails the payment back
+    inject_failure(
+        node2_addr,
+        Some(InjectedFailure::IncorrectPaymentDetails),
+        None,
+    )
+    .await;
+    let invoice = ln_invoice(node2_addr, Some(5000000), None, None, 3600)
+        .await
+        .invoice;
+    send_payment_with_status(node1_addr, invoice, HTLCStatus::Failed).await;
+    inject_failure(
+        node2_addr,
+        Some(InjectedFailure::TemporaryNodeFailure),
+        None,
+    )
+    .await;
+    let keysend_res = _keysend_raw(node1_addr, &node2_pubkey, None, None, None).await;
+    wait_for_ln_payment(node1_addr, &keysend_res.payment_hash, HTLCStatus::Failed).await;
+    let invoice = ln_invoice(node2_addr, Some(5000000), None, None, 3600)
+        .await
+        .invoice;
+    send_payment(node1_addr, invoice).await;
+
+    // RGB proxy requests get delayed until disarmed
+    inject_failure(node1_addr, None, Some(3000)).await;
+    let t_0 = OffsetDateTime::now_utc();
+    refresh_transfers(node1_addr).await;
+    assert!((OffsetDateTime::now_utc() - t_0).as_seconds_f32() >= 3.0);
+    inject_failure(node1_addr, None, None).await;
+    let t_0 = OffsetDateTime::now_utc();
+    refresh_transfers(node1_addr).await;
+    assert!((OffsetDateTime::now_utc() - t_0).as_seconds_f32() < 3.0);
+
+    shutdown(&[node1_addr, node2_addr]).await;
+}
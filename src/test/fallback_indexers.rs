@@ -0,0 +1,66 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/fallback_indexers/";
+
+const UNREACHABLE_INDEXER_URL: &str = "tcp://127.0.0.1:50099";
+
+async fn unlock_res(node_address: SocketAddr, payload: &UnlockRequest) -> Response {
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/unlock"))
+        .json(payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn fallback_indexers() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, password) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    assert_eq!(
+        network_info(node1_addr).await.indexer_url,
+        ELECTRUM_URL_REGTEST
+    );
+
+    lock(node1_addr).await;
+
+    // failures
+    let payload = UnlockRequest {
+        fallback_indexer_urls: Some(vec![
+            s!("tcp://127.0.0.1:50097"),
+            s!("tcp://127.0.0.1:50098"),
+            s!(UNREACHABLE_INDEXER_URL),
+        ]),
+        ..unlock_req(&password)
+    };
+    let res = unlock_res(node1_addr, &payload).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "at most 3 indexers are supported",
+        "InvalidIndexer",
+    )
+    .await;
+
+    // an unreachable indexer is replaced by the first fallback that answers
+    let payload = UnlockRequest {
+        indexer_url: Some(s!(UNREACHABLE_INDEXER_URL)),
+        fallback_indexer_urls: Some(vec![ELECTRUM_URL_REGTEST.to_string()]),
+        ..unlock_req(&password)
+    };
+    let res = unlock_res(node1_addr, &payload).await;
+    _check_response_is_ok(res).await;
+    assert_eq!(
+        network_info(node1_addr).await.indexer_url,
+        ELECTRUM_URL_REGTEST
+    );
+    assert!(btc_balance(node1_addr).await.vanilla.settled > 0);
+
+    shutdown(&[node1_addr]).await;
+}
@@ -0,0 +1,88 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/fallback_proxies/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn fallback_proxies() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, password) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    lock(node1_addr).await;
+
+    // failures
+    let payload = UnlockRequest {
+        fallback_proxy_endpoints: Some(vec![s!("invalid")]),
+        ..unlock_req(&password)
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/unlock"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Invalid proxy endpoint",
+        "InvalidProxyEndpoint",
+    )
+    .await;
+
+    let payload = UnlockRequest {
+        fallback_proxy_endpoints: Some(vec![
+            s!("rpc://127.0.0.1:3001/json-rpc"),
+            s!("rpc://127.0.0.1:3002/json-rpc"),
+            s!("rpc://127.0.0.1:3003/json-rpc"),
+        ]),
+        ..unlock_req(&password)
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/unlock"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "at most 3 proxy endpoints are supported",
+        "InvalidTransportEndpoints",
+    )
+    .await;
+
+    // an unreachable fallback doesn't prevent unlocking and a duplicate one is ignored
+    let fallback_proxy_endpoint = s!("rpc://127.0.0.1:3001/json-rpc");
+    let payload = UnlockRequest {
+        fallback_proxy_endpoints: Some(vec![
+            PROXY_ENDPOINT_LOCAL.to_string(),
+            fallback_proxy_endpoint.clone(),
+        ]),
+        ..unlock_req(&password)
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/unlock"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+
+    // invoices embed all the proxies, in order of priority
+    for witness in [false, true] {
+        let invoice = rgb_invoice(node1_addr, None, witness).await.invoice;
+        let decoded = decode_rgb_invoice(node1_addr, &invoice).await;
+        assert_eq!(
+            decoded.transport_endpoints,
+            vec![
+                PROXY_ENDPOINT_LOCAL.to_string(),
+                fallback_proxy_endpoint.clone()
+            ]
+        );
+    }
+}
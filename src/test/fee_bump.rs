@@ -0,0 +1,211 @@
+use crate::routes::TransactionType;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/fee_bump/";
+
+async fn bump_fee_res(node_address: SocketAddr, txid: &str, fee_rate: u64) -> Response {
+    let payload = BumpFeeRequest {
+        txid: txid.to_string(),
+        fee_rate,
+        skip_sync: false,
+        totp_code: None,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/bumpfee"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn accelerate_incoming_res(
+    node_address: SocketAddr,
+    idx: i32,
+    fee_rate: u64,
+    max_fee_sat: u64,
+) -> Response {
+    let payload = AccelerateIncomingRequest {
+        idx,
+        fee_rate,
+        max_fee_sat,
+        totp_code: None,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/accelerateincoming"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn fee_bump() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let res = bump_fee_res(node1_addr, "invalid", FEE_RATE * 2).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown transaction",
+        "UnknownTransaction",
+    )
+    .await;
+
+    // a BTC send is replaced, still paying the recipient
+    let node2_address = address(node2_addr).await;
+    let txid = send_btc(node1_addr, 50_000, &node2_address).await;
+    let res = bump_fee_res(node1_addr, &txid, FEE_RATE).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "fee rate must be higher than the current",
+        "CannotBumpFee",
+    )
+    .await;
+    let res = bump_fee_res(node1_addr, &txid, FEE_RATE * 3).await;
+    let bumped = _check_response_is_ok(res)
+        .await
+        .json::<BumpFeeResponse>()
+        .await
+        .unwrap();
+    assert_eq!(bumped.method, FeeBumpMethod::Rbf);
+    assert_ne!(bumped.txid, txid);
+    mine(false);
+    let transactions = list_transactions(node1_addr).await;
+    assert!(transactions
+        .iter()
+        .any(|t| t.txid == bumped.txid && t.confirmation_time.is_some()));
+    assert!(!transactions.iter().any(|t| t.txid == txid));
+    assert_eq!(btc_balance(node2_addr).await.vanilla.settled, 50_000);
+
+    let res = bump_fee_res(node1_addr, &bumped.txid, FEE_RATE * 4).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "transaction is already confirmed",
+        "CannotBumpFee",
+    )
+    .await;
+
+    // the UTXOs created for RGB allocations are kept, paying for a child instead
+    create_utxos(node1_addr, false, Some(1), None).await;
+    let txid = list_transactions(node1_addr)
+        .await
+        .into_iter()
+        .find(|t| {
+            t.transaction_type == TransactionType::CreateUtxos && t.confirmation_time.is_none()
+        })
+        .unwrap()
+        .txid;
+    let res = bump_fee_res(node1_addr, &txid, FEE_RATE * 3).await;
+    let bumped = _check_response_is_ok(res)
+        .await
+        .json::<BumpFeeResponse>()
+        .await
+        .unwrap();
+    assert_eq!(bumped.method, FeeBumpMethod::Cpfp);
+    mine(false);
+    let transactions = list_transactions(node1_addr).await;
+    for txid in [txid, bumped.txid] {
+        assert!(transactions
+            .iter()
+            .any(|t| t.txid == txid && t.confirmation_time.is_some()));
+    }
+
+    shutdown(&[node1_addr, node2_addr]).await;
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn accelerate_incoming() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}accelerate_node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}accelerate_node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let recipient_id = rgb_invoice_with_assignment(
+        node2_addr,
+        Some(asset_id.clone()),
+        Some(Assignment::Fungible(300)),
+        true,
+    )
+    .await
+    .recipient_id;
+    let witness_data = WitnessData {
+        amount_sat: 1200,
+        blinding: None,
+    };
+    send_asset(
+        node1_addr,
+        &asset_id,
+        Assignment::Fungible(300),
+        recipient_id,
+        Some(witness_data),
+    )
+    .await;
+    refresh_transfers(node2_addr).await;
+    let incoming = list_transfers(node2_addr, &asset_id)
+        .await
+        .into_iter()
+        .find(|t| t.kind == TransferKind::ReceiveWitness)
+        .unwrap();
+    assert_eq!(incoming.status, TransferStatus::WaitingConfirmations);
+
+    // only incoming transfers can be accelerated, within the fee budget
+    let outgoing = list_transfers(node1_addr, &asset_id)
+        .await
+        .into_iter()
+        .find(|t| t.kind == TransferKind::Send)
+        .unwrap();
+    let res = accelerate_incoming_res(node1_addr, outgoing.idx, FEE_RATE * 3, 10_000).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "only incoming witness transfers waiting for confirmations can be accelerated",
+        "CannotBumpFee",
+    )
+    .await;
+    let res = accelerate_incoming_res(node2_addr, incoming.idx, FEE_RATE * 3, 1).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "exceeds max_fee_sat",
+        "MaxFeeExceeded",
+    )
+    .await;
+
+    let res = accelerate_incoming_res(node2_addr, incoming.idx, FEE_RATE * 3, 10_000).await;
+    let accelerated = _check_response_is_ok(res)
+        .await
+        .json::<AccelerateIncomingResponse>()
+        .await
+        .unwrap();
+    assert!(accelerated.fee_sat > 0);
+    mine(false);
+    refresh_transfers(node1_addr).await;
+    // the received assets have been moved to a new output of the receiver
+    wait_for_balance(node2_addr, &asset_id, 300).await;
+    let transactions = list_transactions(node2_addr).await;
+    assert!(transactions
+        .iter()
+        .any(|t| t.txid == accelerated.txid && t.confirmation_time.is_some()));
+
+    shutdown(&[node1_addr, node2_addr]).await;
+}
@@ -0,0 +1,142 @@
+use crate::utils::get_current_timestamp;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/forwarding_history/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn forwarding_history_and_fee_report() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+
+    let channel_12 = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+    let channel_23 = open_channel(
+        node2_addr,
+        &node3_pubkey,
+        Some(NODE3_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    let history = forwarding_history(node2_addr, &ForwardingHistoryRequest::default()).await;
+    assert_eq!(history.total, 0);
+    let report = fee_report(node2_addr).await;
+    assert!(report.channels.is_empty());
+    assert_eq!(report.total_fee_msat, 0);
+
+    // settled forward
+    let t_start = get_current_timestamp();
+    let LNInvoiceResponse { invoice } = ln_invoice(node3_addr, None, None, None, 900).await;
+    send_payment(node1_addr, invoice).await;
+
+    // failed forward, the payee cancels the HTLC
+    let HodlInvoiceResponse {
+        invoice,
+        payment_hash,
+        ..
+    } = hodl_invoice(node3_addr, Some(3000000), None, None, None, false).await;
+    send_payment_raw(node1_addr, invoice.clone()).await;
+    wait_for_invoice_status(node3_addr, &invoice, InvoiceStatus::Held).await;
+    cancel_invoice(node3_addr, &payment_hash).await;
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Failed).await;
+
+    let history = forwarding_history(node2_addr, &ForwardingHistoryRequest::default()).await;
+    assert_eq!(history.total, 2);
+    let settled = &history.forwards[0];
+    assert_eq!(settled.resolution, ForwardResolution::Settled);
+    assert!(settled.timestamp >= t_start);
+    assert_eq!(settled.prev_channel_id, channel_12.channel_id);
+    assert_eq!(settled.next_channel_id, Some(channel_23.channel_id.clone()));
+    assert_eq!(settled.amt_out_msat, Some(3000000));
+    let fee_earned_msat = settled.fee_earned_msat.unwrap();
+    assert!(fee_earned_msat > 0);
+    assert_eq!(settled.amt_in_msat, Some(3000000 + fee_earned_msat));
+    assert_eq!(settled.asset_id, None);
+    let failed = &history.forwards[1];
+    assert_eq!(failed.resolution, ForwardResolution::Failed);
+    assert_eq!(failed.prev_channel_id, channel_12.channel_id);
+    assert_eq!(failed.next_channel_id, Some(channel_23.channel_id.clone()));
+    assert_eq!(failed.fee_earned_msat, None);
+
+    // pagination and time range
+    let payload = ForwardingHistoryRequest {
+        limit: Some(1),
+        offset: Some(1),
+        ..Default::default()
+    };
+    let history = forwarding_history(node2_addr, &payload).await;
+    assert_eq!(history.total, 2);
+    assert_eq!(history.forwards.len(), 1);
+    assert_eq!(history.forwards[0].resolution, ForwardResolution::Failed);
+    let payload = ForwardingHistoryRequest {
+        start_time: Some(get_current_timestamp() + 3600),
+        ..Default::default()
+    };
+    let history = forwarding_history(node2_addr, &payload).await;
+    assert_eq!(history.total, 0);
+    let payload = ForwardingHistoryRequest {
+        start_time: Some(t_start),
+        end_time: Some(t_start - 1),
+        ..Default::default()
+    };
+    let res = reqwest::Client::new()
+        .get(format!("http://{node2_addr}/forwardinghistory"))
+        .query(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "start_time cannot be after end_time",
+        "InvalidPagination",
+    )
+    .await;
+
+    // the fee goes to the outbound channel, the failure to the inbound one
+    let report = fee_report(node2_addr).await;
+    assert_eq!(report.channels.len(), 2);
+    assert_eq!(report.total_fee_msat, fee_earned_msat);
+    assert_eq!(report.day_fee_msat, fee_earned_msat);
+    assert_eq!(report.month_fee_msat, fee_earned_msat);
+    let outbound = &report.channels[0];
+    assert_eq!(outbound.channel_id, channel_23.channel_id);
+    assert_eq!(outbound.forwards_out, 1);
+    assert_eq!(outbound.amt_out_msat, 3000000);
+    assert_eq!(outbound.fee_earned_msat, fee_earned_msat);
+    let inbound = &report.channels[1];
+    assert_eq!(inbound.channel_id, channel_12.channel_id);
+    assert_eq!(inbound.forwards_in, 1);
+    assert_eq!(inbound.failed_forwards, 1);
+    assert_eq!(inbound.fee_earned_msat, 0);
+
+    // nodes not routing have no history
+    let history = forwarding_history(node1_addr, &ForwardingHistoryRequest::default()).await;
+    assert_eq!(history.total, 0);
+}
@@ -0,0 +1,86 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/gossip_bandwidth/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn gossip_bandwidth() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // the channel update sent by node1 is accounted as gossip
+    let t_0 = OffsetDateTime::now_utc();
+    let peer = loop {
+        let peers = list_peers(node2_addr).await;
+        let peer = peers
+            .into_iter()
+            .find(|p| p.pubkey == node1_pubkey)
+            .unwrap();
+        if peer.gossip_msgs_received > 0 {
+            break peer;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("no gossip received from node1")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+    assert!(peer.gossip_bytes_received > 0);
+    assert_eq!(peer.gossip_msgs_dropped, 0);
+    assert_eq!(peer.gossip_limit_bytes_per_min, None);
+
+    // limits can be set and removed, surviving a restart
+    throttle_gossip(node2_addr, &node1_pubkey, Some(1024)).await;
+    let peers = list_peers(node2_addr).await;
+    let peer = peers.iter().find(|p| p.pubkey == node1_pubkey).unwrap();
+    assert_eq!(peer.gossip_limit_bytes_per_min, Some(1024));
+    shutdown(&[node2_addr]).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, true).await;
+    wait_for_usable_channels(node2_addr, 1).await;
+    let peers = list_peers(node2_addr).await;
+    let peer = peers.iter().find(|p| p.pubkey == node1_pubkey).unwrap();
+    assert_eq!(peer.gossip_limit_bytes_per_min, Some(1024));
+    throttle_gossip(node2_addr, &node1_pubkey, None).await;
+    let peers = list_peers(node2_addr).await;
+    let peer = peers.iter().find(|p| p.pubkey == node1_pubkey).unwrap();
+    assert_eq!(peer.gossip_limit_bytes_per_min, None);
+
+    // failures
+    let payload = ThrottleGossipRequest {
+        peer_pubkey: s!("invalid"),
+        limit_bytes_per_min: Some(1024),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/throttlegossip"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid pubkey",
+        "InvalidPubkey",
+    )
+    .await;
+}
@@ -0,0 +1,74 @@
+use tonic::Code;
+
+use crate::grpc::proto::{
+    rgb_lightning_node_client::RgbLightningNodeClient, Empty, InvoiceStatusRequest,
+    LnInvoiceRequest, PageRequest,
+};
+use crate::grpc::ERROR_NAME_METADATA_KEY;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/grpc/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn grpc() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    let mut client = RgbLightningNodeClient::connect(format!("http://{node1_addr}"))
+        .await
+        .unwrap();
+
+    let grpc_node_info = client.node_info(Empty {}).await.unwrap().into_inner();
+    assert_eq!(grpc_node_info.pubkey, node_info(node1_addr).await.pubkey);
+
+    let invoice = client
+        .ln_invoice(LnInvoiceRequest {
+            amt_msat: Some(3000000),
+            expiry_sec: 900,
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .invoice;
+    let status = client
+        .invoice_status(InvoiceStatusRequest {
+            invoice: Some(invoice.clone()),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .status;
+    assert_eq!(status, "Pending");
+    assert_eq!(
+        invoice_status(node1_addr, &invoice).await,
+        InvoiceStatus::Pending
+    );
+
+    let err = client
+        .invoice_status(InvoiceStatusRequest {
+            invoice: Some(s!("invalid")),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::InvalidArgument);
+    assert_eq!(
+        err.metadata().get(ERROR_NAME_METADATA_KEY).unwrap(),
+        "InvalidInvoice"
+    );
+
+    let channels = client
+        .list_channels(PageRequest::default())
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(channels.items.is_empty());
+    assert_eq!(channels.total, 0);
+}
@@ -0,0 +1,226 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/hedging/";
+
+async fn set_hedging_policy_res(
+    node_address: SocketAddr,
+    payload: &SetHedgingPolicyRequest,
+) -> Response {
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/sethedgingpolicy"))
+        .json(payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn remove_hedging_policy_res(node_address: SocketAddr, asset_id: &str) -> Response {
+    let payload = RemoveHedgingPolicyRequest {
+        asset_id: asset_id.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/removehedgingpolicy"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn list_hedges(node_address: SocketAddr) -> Vec<Hedge> {
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/listhedges"))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListHedgesResponse>()
+        .await
+        .unwrap()
+        .hedges
+}
+
+async fn wait_for_hedge_status(
+    node_address: SocketAddr,
+    payment_hash: &str,
+    expected_status: HedgeStatus,
+) -> Hedge {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        if let Some(hedge) = list_hedges(node_address)
+            .await
+            .into_iter()
+            .find(|h| h.payment_hash == payment_hash)
+        {
+            if hedge.status == expected_status {
+                return hedge;
+            }
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 40.0 {
+            panic!("hedge is not becoming {expected_status:?}")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+async fn receive_asset(
+    payee_addr: SocketAddr,
+    payer_addr: SocketAddr,
+    asset_id: &str,
+    asset_amount: u64,
+) -> String {
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(payee_addr, None, Some(asset_id), Some(asset_amount), 900).await;
+    send_payment(payer_addr, invoice).await.payment_hash
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn hedging() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node2_addr).await.asset_id;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    // node2 pays node1 in the asset and acts as the maker of its conversions to BTC
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(5000000),
+        Some(546000),
+        None,
+        None,
+    )
+    .await;
+    open_channel(
+        node2_addr,
+        &node1_pubkey,
+        Some(NODE1_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+    wait_for_usable_channels(node1_addr, 2).await;
+    wait_for_usable_channels(node2_addr, 2).await;
+
+    let mut payload = SetHedgingPolicyRequest {
+        asset_id: asset_id.clone(),
+        target_asset_id: asset_id.clone(),
+        min_amount: 50,
+        max_amount: Some(80),
+        max_daily_amount: Some(120),
+        rate_from: 1,
+        rate_to: 1000,
+        maker_url: format!("http://{node2_addr}"),
+        maker_token: None,
+        totp_code: None,
+        dry_run: true,
+    };
+    let res = set_hedging_policy_res(node1_addr, &payload).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "asset_id and target_asset_id need to be different",
+        "InvalidHedgingPolicy",
+    )
+    .await;
+    payload.target_asset_id = s!("btc");
+    payload.maker_url = s!("ftp://maker");
+    let res = set_hedging_policy_res(node1_addr, &payload).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "maker_url must be an HTTP(S) URL",
+        "InvalidHedgingPolicy",
+    )
+    .await;
+    payload.maker_url = format!("http://{node2_addr}");
+    let res = set_hedging_policy_res(node1_addr, &payload).await;
+    let policy = _check_response_is_ok(res)
+        .await
+        .json::<HedgingPolicyEntry>()
+        .await
+        .unwrap();
+    assert_eq!(policy.asset_id, asset_id);
+    assert_eq!(policy.target_asset_id, None);
+    assert!(policy.dry_run);
+
+    // payments below the threshold are kept
+    receive_asset(node1_addr, node2_addr, &asset_id, 10).await;
+    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+    assert!(list_hedges(node1_addr).await.is_empty());
+
+    // in dry-run mode the conversion is only recorded, capped at max_amount
+    let payment_hash = receive_asset(node1_addr, node2_addr, &asset_id, 100).await;
+    let hedge = wait_for_hedge_status(node1_addr, &payment_hash, HedgeStatus::DryRun).await;
+    assert_eq!(hedge.asset_id, Some(asset_id.clone()));
+    assert_eq!(hedge.target_asset_id, None);
+    assert_eq!(hedge.received_amount, 100);
+    assert_eq!(hedge.qty_from, Some(80));
+    assert_eq!(hedge.qty_to, Some(80000));
+    assert!(list_swaps(node1_addr).await.taker.is_empty());
+    wait_for_ln_balance(node1_addr, &asset_id, 110).await;
+
+    // conversions are executed as swaps taken from the maker, within the daily limit
+    payload.dry_run = false;
+    let res = set_hedging_policy_res(node1_addr, &payload).await;
+    _check_response_is_ok(res).await;
+    let payment_hash = receive_asset(node1_addr, node2_addr, &asset_id, 60).await;
+    let hedge = wait_for_hedge_status(node1_addr, &payment_hash, HedgeStatus::Succeeded).await;
+    assert_eq!(hedge.qty_from, Some(40));
+    assert_eq!(hedge.qty_to, Some(40000));
+    let swap_payment_hash = hedge.swap_payment_hash.unwrap();
+    let swaps = list_swaps(node1_addr).await;
+    assert_eq!(swaps.taker.len(), 1);
+    assert_eq!(swaps.taker[0].payment_hash, swap_payment_hash);
+    assert_eq!(swaps.taker[0].status, SwapStatus::Succeeded);
+    wait_for_swap_status(node2_addr, &swap_payment_hash, SwapStatus::Succeeded).await;
+    wait_for_ln_balance(node1_addr, &asset_id, 130).await;
+
+    let payment_hash = receive_asset(node1_addr, node2_addr, &asset_id, 60).await;
+    let hedge = wait_for_hedge_status(node1_addr, &payment_hash, HedgeStatus::Skipped).await;
+    assert!(hedge.error.is_some());
+    assert_eq!(list_swaps(node1_addr).await.taker.len(), 1);
+
+    let res = reqwest::Client::new()
+        .get(format!("http://{node1_addr}/hedgingpolicies"))
+        .send()
+        .await
+        .unwrap();
+    let policies = _check_response_is_ok(res)
+        .await
+        .json::<HedgingPoliciesResponse>()
+        .await
+        .unwrap()
+        .policies;
+    assert_eq!(policies.len(), 1);
+    assert!(!policies[0].dry_run);
+
+    let res = remove_hedging_policy_res(node1_addr, &asset_id).await;
+    _check_response_is_ok(res).await;
+    let res = remove_hedging_policy_res(node1_addr, &asset_id).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown hedging policy",
+        "UnknownHedgingPolicy",
+    )
+    .await;
+    receive_asset(node1_addr, node2_addr, &asset_id, 60).await;
+    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+    assert_eq!(list_hedges(node1_addr).await.len(), 3);
+}
@@ -0,0 +1,474 @@
+use bitcoin::hashes::{sha256::Hash as Sha256, Hash};
+use std::os::unix::fs::PermissionsExt;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/hodl_invoice/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn hodl_invoice_rgb() {
+    initialize();
+
+    let test_dir_base = format!("{TEST_DIR_BASE}rgb/");
+    let test_dir_node1 = format!("{test_dir_base}node1");
+    let test_dir_node2 = format!("{test_dir_base}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        Some(3500000),
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+
+    // cancelled, the asset amount goes back to the payer
+    let HodlInvoiceResponse {
+        invoice,
+        payment_hash,
+        ..
+    } = hodl_invoice(
+        node2_addr,
+        Some(3000000),
+        Some(&asset_id),
+        Some(100),
+        None,
+        false,
+    )
+    .await;
+    send_payment_raw(node1_addr, invoice.clone()).await;
+    wait_for_invoice_status(node2_addr, &invoice, InvoiceStatus::Held).await;
+    cancel_invoice(node2_addr, &payment_hash).await;
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Failed).await;
+    wait_for_ln_balance(node1_addr, &asset_id, 600).await;
+    let payment = get_payment(node2_addr, &payment_hash).await;
+    assert_eq!(payment.asset_id, Some(asset_id.clone()));
+    assert_eq!(payment.asset_amount, Some(100));
+    assert_eq!(payment.status, HTLCStatus::Failed);
+
+    // settled, the asset amount moves to the payee
+    let HodlInvoiceResponse {
+        invoice,
+        payment_hash,
+        payment_preimage,
+    } = hodl_invoice(
+        node2_addr,
+        Some(3000000),
+        Some(&asset_id),
+        Some(100),
+        None,
+        true,
+    )
+    .await;
+    send_payment_raw(node1_addr, invoice.clone()).await;
+    wait_for_invoice_status(node2_addr, &invoice, InvoiceStatus::Held).await;
+    settle_invoice(node2_addr, &payment_hash, &payment_preimage.unwrap()).await;
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Succeeded).await;
+    wait_for_ln_balance(node1_addr, &asset_id, 500).await;
+    wait_for_ln_balance(node2_addr, &asset_id, 100).await;
+    let payment = get_payment(node2_addr, &payment_hash).await;
+    assert_eq!(payment.asset_id, Some(asset_id.clone()));
+    assert_eq!(payment.asset_amount, Some(100));
+    assert_eq!(payment.status, HTLCStatus::Succeeded);
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn hodl_invoice_settle_and_cancel() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(100000),
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // the preimage is not revealed unless requested
+    let HodlInvoiceResponse {
+        payment_preimage, ..
+    } = hodl_invoice(node2_addr, Some(5000000), None, None, None, false).await;
+    assert!(payment_preimage.is_none());
+
+    // node-generated preimage, settled
+    let HodlInvoiceResponse {
+        invoice,
+        payment_hash,
+        payment_preimage,
+    } = hodl_invoice(node2_addr, Some(5000000), None, None, None, true).await;
+    let payment_preimage = payment_preimage.unwrap();
+    let preimage_hash = Sha256::hash(&hex_str_to_vec(&payment_preimage).unwrap());
+    assert_eq!(preimage_hash.to_string(), payment_hash);
+    assert_eq!(
+        invoice_status(node2_addr, &invoice).await,
+        InvoiceStatus::Pending
+    );
+
+    // the status can be looked up by invoice or by payment hash, not both
+    let payload = InvoiceStatusRequest {
+        invoice: Some(invoice.clone()),
+        payment_hash: Some(payment_hash.clone()),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/invoicestatus"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "exactly one of invoice and payment_hash must be provided",
+        "InvalidInvoiceStatusRequest",
+    )
+    .await;
+    let status = invoice_status_by_hash(node2_addr, &payment_hash).await;
+    assert_eq!(status.status, InvoiceStatus::Pending);
+    assert_eq!(status.amt_msat, Some(5000000));
+    assert!(status.expires_at.is_some());
+    assert!(status.settled_at.is_none());
+    assert!(status.blocks_to_cancel.is_none());
+
+    // settling before the payment has been received should fail
+    let payload = SettleInvoiceRequest {
+        payment_hash: payment_hash.clone(),
+        payment_preimage: payment_preimage.clone(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/settleinvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Invoice is not claimable",
+        "InvoiceNotClaimable",
+    )
+    .await;
+
+    send_payment_raw(node1_addr, invoice.clone()).await;
+    wait_for_invoice_status(node2_addr, &invoice, InvoiceStatus::Held).await;
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Pending).await;
+
+    // held payments report how long they can be held for
+    let status = invoice_status_by_hash(node2_addr, &payment_hash).await;
+    assert_eq!(status.status, InvoiceStatus::Held);
+    let cltv_expiry = status.cltv_expiry.unwrap();
+    let current_height = status.current_height.unwrap();
+    assert!(cltv_expiry >= current_height + MIN_FINAL_CLTV_EXPIRY_DELTA as u32);
+    assert!(status.blocks_to_cancel.unwrap() < cltv_expiry - current_height);
+
+    // settling with a wrong preimage should fail
+    let payload = SettleInvoiceRequest {
+        payment_hash: payment_hash.clone(),
+        payment_preimage: "00".repeat(32),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/settleinvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid payment preimage",
+        "InvalidPaymentPreimage",
+    )
+    .await;
+
+    settle_invoice(node2_addr, &payment_hash, &payment_preimage).await;
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Succeeded).await;
+    wait_for_ln_payment(node2_addr, &payment_hash, HTLCStatus::Succeeded).await;
+    assert_eq!(
+        invoice_status(node2_addr, &invoice).await,
+        InvoiceStatus::Succeeded
+    );
+    let status = invoice_status_by_hash(node2_addr, &payment_hash).await;
+    assert_eq!(status.status, InvoiceStatus::Succeeded);
+    assert!(status.settled_at.is_some());
+
+    // caller-provided payment hash, cancelled
+    let preimage = "42".repeat(32);
+    let payment_hash = Sha256::hash(&hex_str_to_vec(&preimage).unwrap()).to_string();
+    let HodlInvoiceResponse {
+        invoice,
+        payment_preimage,
+        ..
+    } = hodl_invoice(
+        node2_addr,
+        Some(5000000),
+        None,
+        None,
+        Some(&payment_hash),
+        true,
+    )
+    .await;
+    assert!(payment_preimage.is_none());
+
+    // reusing the same payment hash should fail
+    let payload = HodlInvoiceRequest {
+        amt_msat: Some(5000000),
+        min_amt_msat: None,
+        max_amt_msat: None,
+        expiry_sec: 900,
+        asset_id: None,
+        asset_amount: None,
+        payment_hash: Some(payment_hash.clone()),
+        reveal_preimage: false,
+        route_hints: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/hodlinvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Payment hash already used",
+        "PaymentHashAlreadyUsed",
+    )
+    .await;
+
+    send_payment_raw(node1_addr, invoice.clone()).await;
+    wait_for_invoice_status(node2_addr, &invoice, InvoiceStatus::Held).await;
+
+    cancel_invoice(node2_addr, &payment_hash).await;
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Failed).await;
+    assert_eq!(
+        invoice_status(node2_addr, &invoice).await,
+        InvoiceStatus::Failed
+    );
+    let status = invoice_status_by_hash(node2_addr, &payment_hash).await;
+    assert_eq!(status.status, InvoiceStatus::Failed);
+    assert!(status.settled_at.is_none());
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn hodl_invoice_batch() {
+    initialize();
+
+    let test_dir_base = format!("{TEST_DIR_BASE}batch/");
+    let test_dir_node1 = format!("{test_dir_base}node1");
+    let test_dir_node2 = format!("{test_dir_base}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(100000),
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    let mut hodl_invoices = vec![];
+    for _ in 0..4 {
+        let HodlInvoiceResponse {
+            invoice,
+            payment_hash,
+            payment_preimage,
+        } = hodl_invoice(node2_addr, Some(3000000), None, None, None, true).await;
+        send_payment_raw(node1_addr, invoice.clone()).await;
+        wait_for_invoice_status(node2_addr, &invoice, InvoiceStatus::Held).await;
+        hodl_invoices.push((payment_hash, payment_preimage.unwrap()));
+    }
+    let HodlInvoiceResponse {
+        payment_hash: unpaid_payment_hash,
+        payment_preimage: unpaid_payment_preimage,
+        ..
+    } = hodl_invoice(node2_addr, Some(3000000), None, None, None, true).await;
+    let unpaid_payment_preimage = unpaid_payment_preimage.unwrap();
+
+    // settle two held invoices, an unpaid one and one with a wrong preimage
+    let zero_preimage = "00".repeat(32);
+    let results = settle_invoices(
+        node2_addr,
+        &[
+            (&hodl_invoices[0].0, &hodl_invoices[0].1),
+            (&hodl_invoices[1].0, &hodl_invoices[1].1),
+            (&unpaid_payment_hash, &unpaid_payment_preimage),
+            (&hodl_invoices[2].0, &zero_preimage),
+        ],
+    )
+    .await;
+    assert_eq!(results.len(), 4);
+    assert!(results[0].error.is_none());
+    assert!(results[1].error.is_none());
+    assert_eq!(results[2].payment_hash, unpaid_payment_hash);
+    assert!(results[2]
+        .error
+        .as_ref()
+        .unwrap()
+        .starts_with("Invoice is not claimable"));
+    assert_eq!(results[3].error, Some(s!("Invalid payment preimage")));
+    for (payment_hash, _) in &hodl_invoices[..2] {
+        wait_for_ln_payment(node1_addr, payment_hash, HTLCStatus::Succeeded).await;
+    }
+
+    // cancel the remaining held invoices plus an already settled one
+    let results = cancel_invoices(
+        node2_addr,
+        &[
+            &hodl_invoices[2].0,
+            &hodl_invoices[3].0,
+            &unpaid_payment_hash,
+            &hodl_invoices[0].0,
+        ],
+    )
+    .await;
+    assert_eq!(results.len(), 4);
+    assert!(results[..3].iter().all(|r| r.error.is_none()));
+    assert_eq!(results[3].error, Some(s!("Unknown LN invoice")));
+    for (payment_hash, _) in &hodl_invoices[2..] {
+        wait_for_ln_payment(node1_addr, payment_hash, HTLCStatus::Failed).await;
+    }
+
+    // empty batches are rejected
+    let payload = CancelInvoicesRequest {
+        payment_hashes: vec![],
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/cancelinvoices"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid invoice batch: no invoice has been provided",
+        "InvalidInvoiceBatch",
+    )
+    .await;
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn hodl_invoice_deadline() {
+    initialize();
+
+    let test_dir_base = format!("{TEST_DIR_BASE}deadline/");
+    let test_dir_node1 = format!("{test_dir_base}node1");
+    let test_dir_node2 = format!("{test_dir_base}node2");
+
+    // the hook of the payee appends the received events to a file in its working directory
+    std::fs::create_dir_all(&test_dir_base).unwrap();
+    let hook_path = PathBuf::from(format!("{test_dir_base}hook.sh"));
+    std::fs::write(
+        &hook_path,
+        "#!/bin/sh\ncat >> events.jsonl\necho >> events.jsonl\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&hook_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let args = UserArgs {
+        storage_dir_path: test_dir_node2.clone().into(),
+        ldk_peer_listening_port: NODE2_PEER_PORT,
+        hook_command: Some(std::fs::canonicalize(&hook_path).unwrap()),
+        ..Default::default()
+    };
+    let (node2_addr, _) = start_node_with_args(args, false).await;
+    let events_path = PathBuf::from(format!("{test_dir_node2}/hooks/events.jsonl"));
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(100000),
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    let HodlInvoiceResponse {
+        invoice,
+        payment_hash,
+        payment_preimage,
+    } = hodl_invoice(node2_addr, Some(5000000), None, None, None, true).await;
+    send_payment_raw(node1_addr, invoice.clone()).await;
+    wait_for_invoice_status(node2_addr, &invoice, InvoiceStatus::Held).await;
+    let blocks_to_cancel = invoice_status_by_hash(node2_addr, &payment_hash)
+        .await
+        .blocks_to_cancel
+        .unwrap();
+
+    // a warning event is sent on each new block once the deadline gets close
+    let num_blocks = blocks_to_cancel
+        .saturating_sub(HTLC_RISK_WINDOW_BLOCKS)
+        .max(1);
+    mine_n_blocks(false, num_blocks as u16);
+    let t_0 = OffsetDateTime::now_utc();
+    let event = loop {
+        let events = std::fs::read_to_string(&events_path).unwrap_or_default();
+        if let Some(event) = events
+            .lines()
+            .find(|l| l.contains(r#""event":"hodl_invoice_expiring""#))
+        {
+            break serde_json::from_str::<serde_json::Value>(event).unwrap();
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 60.0 {
+            panic!("no warning event has been sent")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+    let status = invoice_status_by_hash(node2_addr, &payment_hash).await;
+    assert_eq!(event["payment_hash"], payment_hash);
+    assert_eq!(event["cltv_expiry"], status.cltv_expiry.unwrap());
+    assert_eq!(event["current_height"], status.current_height.unwrap());
+    assert_eq!(event["blocks_to_cancel"], status.blocks_to_cancel.unwrap());
+    assert!(status.blocks_to_cancel.unwrap() <= HTLC_RISK_WINDOW_BLOCKS);
+
+    // the payment can still be settled before the deadline
+    settle_invoice(node2_addr, &payment_hash, &payment_preimage.unwrap()).await;
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Succeeded).await;
+}
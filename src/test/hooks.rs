@@ -0,0 +1,102 @@
+use std::os::unix::fs::PermissionsExt;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/hooks/";
+
+async fn wait_for_hook_events(events_path: &Path, expected_num_events: usize) -> Vec<String> {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let events = std::fs::read_to_string(events_path).unwrap_or_default();
+        let events: Vec<String> = events.lines().map(|l| l.to_string()).collect();
+        if events.len() >= expected_num_events {
+            return events;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("hook has not been run")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn hooks() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+
+    // the hook appends the received events to a file in its working directory
+    std::fs::create_dir_all(TEST_DIR_BASE).unwrap();
+    let hook_path = PathBuf::from(format!("{TEST_DIR_BASE}hook.sh"));
+    std::fs::write(
+        &hook_path,
+        "#!/bin/sh\nif [ -n \"$HOME\" ]; then exit 1; fi\ncat >> events.jsonl\necho >> events.jsonl\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&hook_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let args = UserArgs {
+        storage_dir_path: test_dir_node1.clone().into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        hook_command: Some(std::fs::canonicalize(&hook_path).unwrap()),
+        ..Default::default()
+    };
+    let (node1_addr, _) = start_node_with_args(args, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let events_path = PathBuf::from(format!("{test_dir_node1}/hooks/events.jsonl"));
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+
+    open_channel(
+        node2_addr,
+        &node1_pubkey,
+        Some(NODE1_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // invoice settled
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(node1_addr, Some(3000000), None, None, 900).await;
+    let decoded = decode_ln_invoice(node1_addr, &invoice).await;
+    send_payment(node2_addr, invoice).await;
+    let events = wait_for_hook_events(&events_path, 1).await;
+    let event: serde_json::Value = serde_json::from_str(&events[0]).unwrap();
+    assert_eq!(event["event"], "invoice_settled");
+    assert_eq!(event["payment_hash"], decoded.payment_hash);
+    assert_eq!(event["amt_msat"], 3000000);
+    assert!(event["asset_id"].is_null());
+    assert!(event["timestamp"].as_u64().unwrap() > 0);
+
+    // asset transfer settled
+    let asset_id = issue_asset_nia(node2_addr).await.asset_id;
+    let recipient_id = rgb_invoice(node1_addr, None, false).await.recipient_id;
+    send_asset(
+        node2_addr,
+        &asset_id,
+        Assignment::Fungible(400),
+        recipient_id,
+        None,
+    )
+    .await;
+    mine(false);
+    refresh_transfers(node1_addr).await;
+    refresh_transfers(node1_addr).await;
+    refresh_transfers(node2_addr).await;
+    let events = wait_for_hook_events(&events_path, 2).await;
+    let event: serde_json::Value = serde_json::from_str(&events[1]).unwrap();
+    assert_eq!(event["event"], "transfer_settled");
+    assert!(event["batch_transfer_idx"].as_i64().is_some());
+
+    // node2 has no hook
+    assert!(!Path::new(&format!("{test_dir_node2}/hooks")).exists());
+}
@@ -0,0 +1,263 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/htlc_interceptor/";
+
+fn parse_scid(scid: &str) -> u64 {
+    let parts: Vec<u64> = scid.split('x').map(|p| p.parse().unwrap()).collect();
+    parts[0] << 40 | parts[1] << 16 | parts[2]
+}
+
+async fn wait_for_intercepted_htlc(node_address: SocketAddr, scope_id: &str) -> InterceptedHtlc {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        if let Some(htlc) = intercepted_htlcs(node_address, scope_id).await.pop() {
+            return htlc;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 20.0 {
+            panic!("no HTLC has been intercepted")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn htlc_interceptor() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+
+    let payload = InterceptScopesRequest {
+        kind: InterceptScopeKind::Forward,
+        peer_pubkey: None,
+        payment_hash: None,
+        hold_timeout_sec: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/interceptscopes"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "forward scopes need only the peer_pubkey",
+        "InvalidInterceptScope",
+    )
+    .await;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(500_000),
+        None,
+        None,
+        None,
+    )
+    .await;
+    open_channel(
+        node2_addr,
+        &node3_pubkey,
+        Some(NODE3_PEER_PORT),
+        Some(500_000),
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // forwards to the intercept SCID are held until resolved
+    let forward_scope = intercept_scopes(
+        node2_addr,
+        InterceptScopeKind::Forward,
+        Some(&node3_pubkey),
+        None,
+    )
+    .await;
+    let intercept_scid = parse_scid(&forward_scope.intercept_scid.unwrap());
+    let route_hints = Some(vec![InvoiceRouteHint {
+        hops: vec![InvoiceRouteHintHop {
+            src_node_id: node2_pubkey.clone(),
+            short_channel_id: intercept_scid,
+            fee_base_msat: 0,
+            fee_proportional_millionths: 0,
+            cltv_expiry_delta: DEFAULT_CLTV_EXPIRY_DELTA,
+            htlc_minimum_msat: None,
+            htlc_maximum_msat: None,
+        }],
+    }]);
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(5_000_000),
+        min_amt_msat: None,
+        max_amt_msat: None,
+        expiry_sec: 900,
+        asset_id: None,
+        asset_amount: None,
+        route_hints,
+        lsp: None,
+        phantom: None,
+        label: None,
+        fallback_address_type: None,
+        ..Default::default()
+    };
+    let mut invoices = vec![];
+    for _ in 0..2 {
+        let res = reqwest::Client::new()
+            .post(format!("http://{node3_addr}/lninvoice"))
+            .json(&payload)
+            .send()
+            .await
+            .unwrap();
+        let invoice = _check_response_is_ok(res)
+            .await
+            .json::<LNInvoiceResponse>()
+            .await
+            .unwrap()
+            .invoice;
+        invoices.push(invoice);
+    }
+
+    let payment_hash = decode_ln_invoice(node3_addr, &invoices[0])
+        .await
+        .payment_hash;
+    send_payment_raw(node1_addr, invoices[0].clone()).await;
+    let htlc = wait_for_intercepted_htlc(node2_addr, &forward_scope.scope_id).await;
+    assert_eq!(htlc.kind, InterceptScopeKind::Forward);
+    assert_eq!(htlc.payment_hash, payment_hash);
+    assert_eq!(htlc.amt_msat, 5_000_000);
+    assert_eq!(htlc.outbound_amt_msat, Some(5_000_000));
+
+    let payload = ResolveHtlcRequest {
+        htlc_id: htlc.htlc_id.clone(),
+        resolution: HtlcResolution::Settle,
+        payment_preimage: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/resolvehtlc"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "forwarded HTLCs can only be resumed or failed",
+        "InvalidHtlcResolution",
+    )
+    .await;
+
+    resolve_htlc(node2_addr, &htlc.htlc_id, HtlcResolution::Resume, None).await;
+    assert!(intercepted_htlcs(node2_addr, &forward_scope.scope_id)
+        .await
+        .is_empty());
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Succeeded).await;
+    wait_for_ln_payment(node3_addr, &payment_hash, HTLCStatus::Succeeded).await;
+
+    let payment_hash = decode_ln_invoice(node3_addr, &invoices[1])
+        .await
+        .payment_hash;
+    send_payment_raw(node1_addr, invoices[1].clone()).await;
+    let htlc = wait_for_intercepted_htlc(node2_addr, &forward_scope.scope_id).await;
+    resolve_htlc(node2_addr, &htlc.htlc_id, HtlcResolution::Fail, None).await;
+    // the payer may retry through the same hint
+    let t_0 = OffsetDateTime::now_utc();
+    while check_payment_status(node1_addr, &payment_hash, HTLCStatus::Failed)
+        .await
+        .is_none()
+    {
+        if let Some(retry) = intercepted_htlcs(node2_addr, &forward_scope.scope_id)
+            .await
+            .pop()
+        {
+            resolve_htlc(node2_addr, &retry.htlc_id, HtlcResolution::Fail, None).await;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 40.0 {
+            panic!("payment has not failed")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+
+    let payload = ResolveHtlcRequest {
+        htlc_id: htlc.htlc_id,
+        resolution: HtlcResolution::Fail,
+        payment_preimage: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/resolvehtlc"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown intercepted HTLC",
+        "UnknownInterceptedHtlc",
+    )
+    .await;
+
+    // receives for the payment hash are held until resolved
+    let HodlInvoiceResponse {
+        invoice,
+        payment_hash,
+        payment_preimage,
+    } = hodl_invoice(node2_addr, Some(5_000_000), None, None, None, true).await;
+    let payment_preimage = payment_preimage.unwrap();
+    let receive_scope = intercept_scopes(
+        node2_addr,
+        InterceptScopeKind::Receive,
+        None,
+        Some(&payment_hash),
+    )
+    .await;
+    assert!(receive_scope.intercept_scid.is_none());
+
+    send_payment_raw(node1_addr, invoice).await;
+    let htlc = wait_for_intercepted_htlc(node2_addr, &receive_scope.scope_id).await;
+    assert_eq!(htlc.kind, InterceptScopeKind::Receive);
+    assert_eq!(htlc.htlc_id, payment_hash);
+    assert!(htlc.outbound_amt_msat.is_none());
+
+    let payload = ResolveHtlcRequest {
+        htlc_id: htlc.htlc_id.clone(),
+        resolution: HtlcResolution::Settle,
+        payment_preimage: Some(payment_hash.clone()),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/resolvehtlc"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid payment preimage",
+        "InvalidPaymentPreimage",
+    )
+    .await;
+
+    resolve_htlc(
+        node2_addr,
+        &htlc.htlc_id,
+        HtlcResolution::Settle,
+        Some(&payment_preimage),
+    )
+    .await;
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Succeeded).await;
+    wait_for_ln_payment(node2_addr, &payment_hash, HTLCStatus::Succeeded).await;
+}
@@ -0,0 +1,104 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/htlcs/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn htlcs_at_risk() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let args = UserArgs {
+        storage_dir_path: test_dir_node2.into(),
+        ldk_peer_listening_port: NODE2_PEER_PORT,
+        htlc_risk_threshold_sat: 1000,
+        ..Default::default()
+    };
+    let (node2_addr, _) = start_node_with_args(args, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        Some(3500000),
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+
+    assert!(htlcs(node2_addr).await.htlcs.is_empty());
+
+    // a held HTLC stays pending on both sides
+    let HodlInvoiceResponse {
+        invoice,
+        payment_hash,
+        ..
+    } = hodl_invoice(
+        node2_addr,
+        Some(3000000),
+        Some(&asset_id),
+        Some(100),
+        None,
+        false,
+    )
+    .await;
+    send_payment_raw(node1_addr, invoice.clone()).await;
+    wait_for_invoice_status(node2_addr, &invoice, InvoiceStatus::Held).await;
+
+    let res = htlcs(node2_addr).await;
+    assert_eq!(res.htlcs.len(), 1);
+    let htlc = &res.htlcs[0];
+    assert_eq!(htlc.channel_id, channel.channel_id);
+    assert_eq!(htlc.direction, HtlcDirection::Inbound);
+    assert_eq!(htlc.kind, HtlcKind::Invoice);
+    assert_eq!(htlc.payment_hash, payment_hash);
+    assert_eq!(htlc.amt_msat, 3000000);
+    assert_eq!(htlc.asset_id, Some(asset_id.clone()));
+    assert_eq!(htlc.asset_amount, Some(100));
+    assert_eq!(
+        htlc.blocks_to_deadline,
+        htlc.cltv_expiry - res.current_height
+    );
+    assert_eq!(res.risk_threshold_sat, 1000);
+    let blocks_to_deadline = htlc.blocks_to_deadline;
+
+    let res = htlcs(node1_addr).await;
+    assert_eq!(res.htlcs.len(), 1);
+    assert_eq!(res.htlcs[0].direction, HtlcDirection::Outbound);
+    assert_eq!(res.htlcs[0].kind, HtlcKind::Payment);
+    assert_eq!(res.risk_threshold_sat, 0);
+    assert!(!res.risk_threshold_exceeded);
+
+    // getting close to the deadline puts the HTLC at risk
+    if blocks_to_deadline > 30 {
+        mine_n_blocks(false, (blocks_to_deadline - 30) as u16);
+    }
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let res = htlcs(node2_addr).await;
+        if res.htlcs[0].at_risk {
+            assert_eq!(res.at_risk_amt_msat, 3000000);
+            assert!(res.risk_threshold_exceeded);
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("HTLC is not at risk")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+
+    cancel_invoice(node2_addr, &payment_hash).await;
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Failed).await;
+    assert!(htlcs(node2_addr).await.htlcs.is_empty());
+}
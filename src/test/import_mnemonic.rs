@@ -0,0 +1,106 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/import_mnemonic/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn import_mnemonic() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let _ = std::fs::remove_dir_all(&test_dir_node1);
+    let node1_addr = start_daemon(&test_dir_node1, NODE1_PEER_PORT, None).await;
+    let password = "a_password";
+
+    // an invalid mnemonic is rejected
+    let payload = InitRequest {
+        password: password.to_string(),
+        network: None,
+        mnemonic: Some(s!("not a valid mnemonic")),
+        passphrase: None,
+        account_index: None,
+        ldk_keys_only: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/init"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid mnemonic",
+        "InvalidMnemonic",
+    )
+    .await;
+
+    // the node gets the keys of the imported mnemonic
+    let keys = rgb_lib::generate_keys(BitcoinNetwork::Regtest);
+    let payload = InitRequest {
+        password: password.to_string(),
+        network: None,
+        mnemonic: Some(keys.mnemonic.clone()),
+        passphrase: None,
+        account_index: None,
+        ldk_keys_only: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/init"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let init_res = _check_response_is_ok(res)
+        .await
+        .json::<InitResponse>()
+        .await
+        .unwrap();
+    assert_eq!(init_res.mnemonic, keys.mnemonic);
+
+    unlock(node1_addr, password).await;
+    let node_info = node_info(node1_addr).await;
+    assert_eq!(node_info.account_xpub_vanilla, keys.account_xpub_vanilla);
+    assert_eq!(node_info.account_xpub_colored, keys.account_xpub_colored);
+
+    // a passphrase and an account index only apply to the LDK keys, once acknowledged
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let _ = std::fs::remove_dir_all(&test_dir_node2);
+    let node2_addr = start_daemon(&test_dir_node2, NODE2_PEER_PORT, None).await;
+    let mut payload = InitRequest {
+        password: password.to_string(),
+        network: None,
+        mnemonic: Some(keys.mnemonic.clone()),
+        passphrase: Some(s!("a passphrase")),
+        account_index: Some(1),
+        ldk_keys_only: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/init"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid key derivation: rgb-lib does not support BIP39 passphrases and account indexes",
+        "InvalidKeyDerivation",
+    )
+    .await;
+    payload.ldk_keys_only = true;
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/init"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+
+    unlock(node2_addr, password).await;
+    let node2_info = node_info(node2_addr).await;
+    assert_ne!(node2_info.pubkey, node_info.pubkey);
+    assert_eq!(node2_info.account_xpub_vanilla, keys.account_xpub_vanilla);
+    assert_eq!(node2_info.account_xpub_colored, keys.account_xpub_colored);
+}
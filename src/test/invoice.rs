@@ -17,10 +17,10 @@ async fn invoice() {
 
     // an invoice with RGB data and no amt_msat should fail
     let payload = LNInvoiceRequest {
-        amt_msat: None,
         expiry_sec: 900,
         asset_id: Some(asset_id.clone()),
         asset_amount: Some(1),
+        ..Default::default()
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node1_addr}/lninvoice"))
@@ -36,6 +36,7 @@ async fn invoice() {
         expiry_sec: 900,
         asset_id: Some(asset_id.clone()),
         asset_amount: Some(1),
+        ..Default::default()
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node1_addr}/lninvoice"))
@@ -47,10 +48,8 @@ async fn invoice() {
 
     // an invoice with no RGB data and no amt_msat should succeed
     let payload = LNInvoiceRequest {
-        amt_msat: None,
         expiry_sec: 900,
-        asset_id: None,
-        asset_amount: None,
+        ..Default::default()
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node1_addr}/lninvoice"))
@@ -61,4 +60,32 @@ async fn invoice() {
         .json::<LNInvoiceResponse>()
         .await;
     assert!(res.is_ok());
+    // a witness RGB invoice with no asset can be decoded
+    let invoice = rgb_invoice(node1_addr, None, true).await;
+    let decoded = decode_rgb_invoice(node1_addr, &invoice.invoice).await;
+    assert_eq!(decoded.recipient_id, invoice.recipient_id);
+    assert!(matches!(decoded.recipient_type, RecipientType::Witness));
+    assert!(decoded.asset_schema.is_none());
+    assert!(decoded.asset_id.is_none());
+    assert_eq!(decoded.assignment, Assignment::Any);
+    assert!(matches!(decoded.network, BitcoinNetwork::Regtest));
+    assert_eq!(decoded.transport_endpoints, vec![PROXY_ENDPOINT_LOCAL]);
+
+    // an invalid RGB invoice should fail
+    let payload = DecodeRGBInvoiceRequest {
+        invoice: s!("invalid"),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/decodergbinvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid invoice",
+        "InvalidInvoice",
+    )
+    .await;
 }
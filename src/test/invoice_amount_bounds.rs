@@ -0,0 +1,219 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/invoice_amount_bounds/";
+
+async fn bounded_invoice(node_address: SocketAddr, min_amt_msat: u64, max_amt_msat: u64) -> String {
+    let payload = LNInvoiceRequest {
+        amt_msat: None,
+        min_amt_msat: Some(min_amt_msat),
+        max_amt_msat: Some(max_amt_msat),
+        expiry_sec: 900,
+        asset_id: None,
+        asset_amount: None,
+        route_hints: None,
+        lsp: None,
+        phantom: None,
+        label: None,
+        fallback_address_type: None,
+        ..Default::default()
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/lninvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<LNInvoiceResponse>()
+        .await
+        .unwrap()
+        .invoice
+}
+
+async fn send_payment_with_amount_res(
+    node_address: SocketAddr,
+    invoice: &str,
+    amt_msat: Option<u64>,
+) -> Response {
+    let payload = SendPaymentRequest {
+        invoice: invoice.to_string(),
+        amt_msat,
+        timeout_sec: None,
+        max_retries: None,
+        max_fee_msat: None,
+        max_fee_ppm: None,
+        max_cltv_expiry_delta: None,
+        max_parts: None,
+        max_shard_amt_msat: None,
+        totp_code: None,
+        dry_run: false,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/sendpayment"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn send_payment_with_amount(
+    node_address: SocketAddr,
+    invoice: &str,
+    amt_msat: u64,
+) -> String {
+    let res = send_payment_with_amount_res(node_address, invoice, Some(amt_msat)).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<SendPaymentResponse>()
+        .await
+        .unwrap()
+        .payment_hash
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn invoice_amount_bounds() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(100000),
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // bounds are only accepted when the payer chooses the amount
+    let payload = HodlInvoiceRequest {
+        amt_msat: Some(5000000),
+        min_amt_msat: Some(1000000),
+        max_amt_msat: None,
+        expiry_sec: 900,
+        asset_id: None,
+        asset_amount: None,
+        payment_hash: None,
+        reveal_preimage: false,
+        route_hints: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/hodlinvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "min_amt_msat and max_amt_msat cannot be provided along with amt_msat",
+        "InvalidAmount",
+    )
+    .await;
+    let payload = LNInvoiceRequest {
+        amt_msat: None,
+        min_amt_msat: Some(5000000),
+        max_amt_msat: Some(1000000),
+        expiry_sec: 900,
+        asset_id: None,
+        asset_amount: None,
+        route_hints: None,
+        lsp: None,
+        phantom: None,
+        label: None,
+        fallback_address_type: None,
+        ..Default::default()
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/lninvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "min_amt_msat of 5000000 cannot be more than max_amt_msat of 1000000",
+        "InvalidAmount",
+    )
+    .await;
+
+    // the payer needs to choose a positive amount
+    let invoice = bounded_invoice(node2_addr, 1000000, 5000000).await;
+    for (amt_msat, message) in [
+        (None, "need an amount for the given 0-value invoice"),
+        (
+            Some(0),
+            "amount for the given 0-value invoice must be greater than 0",
+        ),
+    ] {
+        let res = send_payment_with_amount_res(node1_addr, &invoice, amt_msat).await;
+        check_response_is_nok(
+            res,
+            reqwest::StatusCode::BAD_REQUEST,
+            message,
+            "InvalidAmount",
+        )
+        .await;
+    }
+
+    // amounts outside of the bounds are failed back by the payee
+    let payment_hash = send_payment_with_amount(node1_addr, &invoice, 500000).await;
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Failed).await;
+    let invoice = bounded_invoice(node2_addr, 1000000, 5000000).await;
+    let payment_hash = send_payment_with_amount(node1_addr, &invoice, 6000000).await;
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Failed).await;
+
+    // amounts within the bounds are claimed
+    let invoice = bounded_invoice(node2_addr, 1000000, 5000000).await;
+    let payment_hash = send_payment_with_amount(node1_addr, &invoice, 3000000).await;
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Succeeded).await;
+    let payment = get_payment(node2_addr, &payment_hash).await;
+    assert_eq!(payment.amt_msat, Some(3000000));
+    assert_eq!(payment.status, HTLCStatus::Succeeded);
+
+    // HODL invoices enforce the bounds before holding the payment
+    let payload = HodlInvoiceRequest {
+        amt_msat: None,
+        min_amt_msat: Some(1000000),
+        max_amt_msat: None,
+        expiry_sec: 900,
+        asset_id: None,
+        asset_amount: None,
+        payment_hash: None,
+        reveal_preimage: true,
+        route_hints: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/hodlinvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let HodlInvoiceResponse {
+        invoice,
+        payment_hash,
+        payment_preimage,
+    } = _check_response_is_ok(res)
+        .await
+        .json::<HodlInvoiceResponse>()
+        .await
+        .unwrap();
+    send_payment_with_amount(node1_addr, &invoice, 2000000).await;
+    wait_for_invoice_status(node2_addr, &invoice, InvoiceStatus::Held).await;
+    settle_invoice(node2_addr, &payment_hash, &payment_preimage.unwrap()).await;
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Succeeded).await;
+}
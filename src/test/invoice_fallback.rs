@@ -0,0 +1,122 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/invoice_fallback/";
+
+async fn fallback_invoice_res(
+    node_address: SocketAddr,
+    amt_msat: Option<u64>,
+    asset_id: Option<&str>,
+    fallback_address_type: AddressType,
+) -> Response {
+    let payload = LNInvoiceRequest {
+        amt_msat,
+        min_amt_msat: None,
+        max_amt_msat: None,
+        expiry_sec: 900,
+        asset_id: asset_id.map(|a| a.to_string()),
+        asset_amount: asset_id.map(|_| 10),
+        route_hints: None,
+        lsp: None,
+        phantom: None,
+        label: None,
+        fallback_address_type: Some(fallback_address_type),
+        ..Default::default()
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/lninvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn invoice_fallback() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    // fallbacks need a fixed BTC amount and a taproot address
+    let res = fallback_invoice_res(node1_addr, None, None, AddressType::P2tr).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "amt_msat is required to add an on-chain fallback",
+        "InvalidFallbackOptions",
+    )
+    .await;
+    let res = fallback_invoice_res(
+        node1_addr,
+        Some(3000000),
+        Some(&asset_id),
+        AddressType::P2tr,
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "invoices receiving RGB assets cannot have an on-chain fallback",
+        "InvalidFallbackOptions",
+    )
+    .await;
+    let res = fallback_invoice_res(node1_addr, Some(3000000), None, AddressType::P2wpkh).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unsupported address type: P2wpkh, the wallet only derives P2tr addresses",
+        "UnsupportedAddressType",
+    )
+    .await;
+
+    // the invoice embeds a fresh wallet address
+    let res = fallback_invoice_res(node1_addr, Some(3000000), None, AddressType::P2tr).await;
+    let invoice = _check_response_is_ok(res)
+        .await
+        .json::<LNInvoiceResponse>()
+        .await
+        .unwrap()
+        .invoice;
+    let bolt11_invoice = Bolt11Invoice::from_str(&invoice).unwrap();
+    let fallback_addresses = bolt11_invoice.fallback_addresses();
+    assert_eq!(fallback_addresses.len(), 1);
+    let fallback_address = fallback_addresses[0].to_string();
+    assert!(fallback_address.starts_with("bcrt1p"));
+    let payment_hash = bolt11_invoice.payment_hash().to_string();
+    assert_eq!(
+        invoice_status(node1_addr, &invoice).await,
+        InvoiceStatus::Pending
+    );
+
+    // an underpayment leaves the invoice pending
+    send_btc(node2_addr, 2000, &fallback_address).await;
+    mine(false);
+    tokio::time::sleep(std::time::Duration::from_secs(15)).await;
+    let status = invoice_status_by_hash(node1_addr, &payment_hash).await;
+    assert_eq!(status.status, InvoiceStatus::Pending);
+    assert_eq!(status.fallback_txid, None);
+
+    // paying the fallback on-chain settles the invoice
+    let txid = send_btc(node2_addr, 3000, &fallback_address).await;
+    mine(false);
+    wait_for_invoice_status(node1_addr, &invoice, InvoiceStatus::Succeeded).await;
+    let status = invoice_status_by_hash(node1_addr, &payment_hash).await;
+    assert_eq!(status.fallback_txid, Some(txid));
+
+    // invoices without a fallback don't report a TX
+    let LNInvoiceResponse { invoice } = ln_invoice(node1_addr, None, None, None, 900).await;
+    let bolt11_invoice = Bolt11Invoice::from_str(&invoice).unwrap();
+    assert!(bolt11_invoice.fallback_addresses().is_empty());
+    let status =
+        invoice_status_by_hash(node1_addr, &bolt11_invoice.payment_hash().to_string()).await;
+    assert_eq!(status.fallback_txid, None);
+}
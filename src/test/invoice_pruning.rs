@@ -0,0 +1,69 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/invoice_pruning/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn invoice_pruning() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let args = UserArgs {
+        storage_dir_path: test_dir_node1.into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        invoice_retention_sec: 1,
+        ..Default::default()
+    };
+    let (node1_addr, _) = start_node_with_args(args, false).await;
+
+    let expiring_invoice = ln_invoice(node1_addr, None, None, None, 1).await.invoice;
+    let expiring_hash = decode_ln_invoice(node1_addr, &expiring_invoice)
+        .await
+        .payment_hash;
+    let invoice = ln_invoice(node1_addr, None, None, None, 3600).await.invoice;
+    let payment_hash = decode_ln_invoice(node1_addr, &invoice).await.payment_hash;
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(3000000),
+        expiry_sec: 1,
+        settle_after_expiry: true,
+        ..Default::default()
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/lninvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let late_invoice = _check_response_is_ok(res)
+        .await
+        .json::<LNInvoiceResponse>()
+        .await
+        .unwrap()
+        .invoice;
+    let late_hash = decode_ln_invoice(node1_addr, &late_invoice)
+        .await
+        .payment_hash;
+    assert_eq!(list_payments(node1_addr).await.len(), 3);
+
+    // only the invoice expired for longer than the retention gets pruned, the one that can be
+    // settled after its expiry is kept
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let payments = list_payments(node1_addr).await;
+        if !payments.iter().any(|p| p.payment_hash == expiring_hash) {
+            assert_eq!(payments.len(), 2);
+            assert!(payments.iter().any(|p| p.payment_hash == payment_hash));
+            assert!(payments.iter().any(|p| p.payment_hash == late_hash));
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 90.0 {
+            panic!("expired invoice has not been pruned");
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    }
+    assert_eq!(
+        invoice_status(node1_addr, &invoice).await,
+        InvoiceStatus::Pending
+    );
+}
@@ -0,0 +1,149 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/invoice_quotas/";
+
+async fn ln_invoice_res(node_address: SocketAddr, token: &str) -> Response {
+    println!("generating invoice for node {node_address}");
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(3000000),
+        min_amt_msat: None,
+        max_amt_msat: None,
+        expiry_sec: 900,
+        asset_id: None,
+        asset_amount: None,
+        route_hints: None,
+        lsp: None,
+        phantom: None,
+        label: None,
+        fallback_address_type: None,
+        ..Default::default()
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/lninvoice"))
+        .json(&payload)
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn invoice_quotas() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+
+    let root_keypair = KeyPair::new();
+    let admin_token = biscuit!(r#"role("admin");"#)
+        .build(&root_keypair)
+        .unwrap()
+        .to_base64()
+        .unwrap();
+    let invoice_token = biscuit!(r#"role("custom"); right("api", "/lninvoice");"#)
+        .build(&root_keypair)
+        .unwrap()
+        .to_base64()
+        .unwrap();
+
+    let _ = std::fs::remove_dir_all(&test_dir_node1);
+    let args = UserArgs {
+        storage_dir_path: test_dir_node1.clone().into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        root_public_key: Some(root_keypair.public()),
+        max_invoices_per_minute: 4,
+        max_token_unpaid_invoices: 2,
+        ..Default::default()
+    };
+    let node_address = start_daemon_with_args(args).await;
+
+    let password = "a_password";
+    let payload = InitRequest {
+        password: password.to_string(),
+        network: None,
+        mnemonic: None,
+        passphrase: None,
+        account_index: None,
+        ldk_keys_only: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/init"))
+        .json(&payload)
+        .bearer_auth(&admin_token)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/unlock"))
+        .json(&unlock_req(password))
+        .bearer_auth(&admin_token)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+
+    // each token can only keep a few unpaid invoices
+    for _ in 0..2 {
+        let res = ln_invoice_res(node_address, &invoice_token).await;
+        _check_response_is_ok(res).await;
+    }
+    let res = ln_invoice_res(node_address, &invoice_token).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::TOO_MANY_REQUESTS,
+        "token reached the max of 2 unpaid invoices",
+        "InvoiceQuotaExceeded",
+    )
+    .await;
+
+    // the node quotas count the invoices issued to every token
+    for _ in 0..2 {
+        let res = ln_invoice_res(node_address, &admin_token).await;
+        _check_response_is_ok(res).await;
+    }
+    let res = ln_invoice_res(node_address, &admin_token).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::TOO_MANY_REQUESTS,
+        "node reached the max of 4 invoices per minute",
+        "InvoiceQuotaExceeded",
+    )
+    .await;
+
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/invoicequotas"))
+        .bearer_auth(&admin_token)
+        .send()
+        .await
+        .unwrap();
+    let quotas = _check_response_is_ok(res)
+        .await
+        .json::<InvoiceQuotasResponse>()
+        .await
+        .unwrap();
+    assert_eq!(quotas.max_invoices_per_minute, 4);
+    assert_eq!(quotas.max_unpaid_invoices, 0);
+    assert_eq!(quotas.max_token_invoices_per_minute, 0);
+    assert_eq!(quotas.max_token_unpaid_invoices, 2);
+    assert_eq!(quotas.node.issued_last_minute, 4);
+    assert_eq!(quotas.node.unpaid, 4);
+    assert_eq!(quotas.node.issued, 4);
+    assert_eq!(quotas.node.rejected, 2);
+    assert_eq!(quotas.tokens.len(), 2);
+    for token in &quotas.tokens {
+        assert_eq!(token.usage.unpaid, 2);
+        assert_eq!(token.usage.issued, 2);
+        assert_eq!(token.usage.rejected, 1);
+    }
+
+    // the token is not allowed to read the quotas
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/invoicequotas"))
+        .bearer_auth(&invoice_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+}
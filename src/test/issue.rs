@@ -91,9 +91,70 @@ async fn issue() {
         .unwrap();
     check_response_is_nok(
         res,
-        reqwest::StatusCode::INTERNAL_SERVER_ERROR,
-        "IO error: Is a directory (os error 21)",
-        "IO",
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid media digest",
+        "InvalidMediaDigest",
+    )
+    .await;
+
+    let payload = GetAssetMediaRequest {
+        digest: s!("../../../.."),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/getassetmedia"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid media digest",
+        "InvalidMediaDigest",
+    )
+    .await;
+
+    // check issuance with a media that has not been uploaded errors
+    let payload = IssueAssetCFARequest {
+        amounts: vec![1000],
+        name: s!("Collectible"),
+        details: None,
+        precision: 0,
+        file_digest: Some("0".repeat(64)),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/issueassetcfa"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid media digest",
+        "InvalidMediaDigest",
+    )
+    .await;
+
+    let payload = IssueAssetUDARequest {
+        ticker: s!("UNI"),
+        name: s!("Unique"),
+        details: None,
+        precision: 0,
+        media_file_digest: Some(cfa_digest.clone()),
+        attachments_file_digests: vec![s!("../media")],
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/issueassetuda"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid media digest",
+        "InvalidMediaDigest",
     )
     .await;
 }
@@ -0,0 +1,107 @@
+use bitcoin::secp256k1::PublicKey;
+
+use crate::ldk::journal_entry_hash;
+use crate::utils::hex_str;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/journal/";
+
+fn hash_from_hex(hex: &str) -> [u8; 32] {
+    hex_str_to_vec(hex).unwrap().try_into().unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn journal() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let pubkey = PublicKey::from_str(&node1_pubkey).unwrap();
+
+    let res = reqwest::Client::new()
+        .get(format!("http://{node1_addr}/journalproof/0-0"))
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "the journal is empty",
+        "InvalidJournalRange",
+    )
+    .await;
+
+    let HodlInvoiceResponse { payment_hash, .. } =
+        hodl_invoice(node1_addr, Some(5_000_000), None, None, None, true).await;
+    cancel_invoice(node1_addr, &payment_hash).await;
+
+    let proof = journal_proof(node1_addr, "0-1").await;
+    assert_eq!(proof.pubkey, node1_pubkey);
+    assert_eq!(proof.entries.len(), 2);
+    assert_eq!(proof.entries[0].kind, JournalEventKind::InvoiceCreated);
+    assert_eq!(proof.entries[1].kind, JournalEventKind::InvoiceCancelled);
+    assert!(proof
+        .entries
+        .iter()
+        .all(|e| e.details.contains(&payment_hash)));
+    assert_eq!(proof.entries[0].prev_hash, hex_str(&[0; 32]));
+
+    // each entry commits to the previous one and is signed by the node
+    for (i, entry) in proof.entries.iter().enumerate() {
+        assert_eq!(entry.seq, i as u64);
+        if i > 0 {
+            assert_eq!(entry.prev_hash, proof.entries[i - 1].hash);
+        }
+        let hash = journal_entry_hash(
+            &hash_from_hex(&entry.prev_hash),
+            entry.seq,
+            entry.timestamp,
+            entry.kind,
+            &entry.details,
+        );
+        assert_eq!(hex_str(&hash), entry.hash);
+        assert!(lightning::util::message_signing::verify(
+            entry.hash.as_bytes(),
+            &entry.signature,
+            &pubkey
+        ));
+    }
+    let tampered = journal_entry_hash(
+        &hash_from_hex(&proof.entries[1].prev_hash),
+        proof.entries[1].seq,
+        proof.entries[1].timestamp,
+        JournalEventKind::InvoiceSettled,
+        &proof.entries[1].details,
+    );
+    assert_ne!(hex_str(&tampered), proof.entries[1].hash);
+
+    let proof = journal_proof(node1_addr, "1-1").await;
+    assert_eq!(proof.entries.len(), 1);
+    assert_eq!(proof.entries[0].kind, JournalEventKind::InvoiceCancelled);
+
+    for (range, msg) in [
+        ("1", "expected START-END"),
+        ("a-1", "expected START-END"),
+        ("1-0", "start cannot be greater than end"),
+        ("0-1000", "cannot export more than 1000 entries"),
+        ("0-2", "the last entry is 1"),
+    ] {
+        let res = reqwest::Client::new()
+            .get(format!("http://{node1_addr}/journalproof/{range}"))
+            .send()
+            .await
+            .unwrap();
+        check_response_is_nok(
+            res,
+            reqwest::StatusCode::BAD_REQUEST,
+            msg,
+            "InvalidJournalRange",
+        )
+        .await;
+    }
+}
@@ -0,0 +1,214 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/lease/";
+
+async fn buy_inbound_res(
+    node_address: SocketAddr,
+    seller: &str,
+    capacity_sat: u64,
+    max_fee_sat: u64,
+) -> Response {
+    let payload = BuyInboundRequest {
+        peer_pubkey_and_opt_addr: seller.to_string(),
+        capacity_sat,
+        asset_id: None,
+        asset_amount: None,
+        max_fee_sat,
+        totp_code: None,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/buyinbound"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn lease_config_res(node_address: SocketAddr, payload: &LeaseConfigRequest) -> Response {
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/lease/config"))
+        .json(payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn lease_orders(node_address: SocketAddr) -> Vec<LeaseOrder> {
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/lease/orders"))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<LeaseOrdersResponse>()
+        .await
+        .unwrap()
+        .orders
+}
+
+async fn lease_rates_res(node_address: SocketAddr, seller: &str) -> Response {
+    let payload = LeaseRatesRequest {
+        peer_pubkey_and_opt_addr: seller.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/leaserates"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn lease() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let seller = format!("{node1_pubkey}@127.0.0.1:{NODE1_PEER_PORT}");
+
+    // lease mode is disabled by default
+    check_response_is_nok(
+        lease_rates_res(node2_addr, &seller).await,
+        reqwest::StatusCode::FORBIDDEN,
+        "lease.get_rates: method not found",
+        "FailedLspRequest",
+    )
+    .await;
+
+    let mut config = LeaseConfigRequest {
+        enabled: true,
+        min_channel_size_sat: 50_000,
+        max_channel_size_sat: 1_000_000,
+        fee_base_sat: 0,
+        fee_proportional_millionths: 0,
+        lease_duration_blocks: 4032,
+        order_expiry_sec: 600,
+    };
+    check_response_is_nok(
+        lease_config_res(node1_addr, &config).await,
+        reqwest::StatusCode::BAD_REQUEST,
+        "fee_base_sat and fee_proportional_millionths cannot both be 0",
+        "InvalidLeaseConfig",
+    )
+    .await;
+    config.fee_base_sat = 1_000;
+    config.fee_proportional_millionths = 10_000;
+    let res = lease_config_res(node1_addr, &config).await;
+    let lease_config = _check_response_is_ok(res)
+        .await
+        .json::<LeaseConfigResponse>()
+        .await
+        .unwrap();
+    assert!(lease_config.enabled);
+    assert!(lease_orders(node1_addr).await.is_empty());
+
+    let res = lease_rates_res(node2_addr, &seller).await;
+    let rates = _check_response_is_ok(res)
+        .await
+        .json::<LeaseRatesResponse>()
+        .await
+        .unwrap();
+    assert_eq!(rates.min_channel_size_sat, 50_000);
+    assert_eq!(rates.max_channel_size_sat, 1_000_000);
+    assert_eq!(rates.fee_base_sat, 1_000);
+    assert_eq!(rates.fee_proportional_millionths, 10_000);
+    assert_eq!(rates.lease_duration_blocks, 4032);
+
+    // the buyer pays the fee through its own channel to the seller
+    open_channel(
+        node2_addr,
+        &node1_pubkey,
+        Some(NODE1_PEER_PORT),
+        Some(500_000),
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    check_response_is_nok(
+        buy_inbound_res(node2_addr, &seller, 10_000, 10_000).await,
+        reqwest::StatusCode::FORBIDDEN,
+        "capacity_sat must be between 50000 and 1000000",
+        "FailedLspRequest",
+    )
+    .await;
+    check_response_is_nok(
+        buy_inbound_res(node2_addr, &seller, 200_000, 2_000).await,
+        reqwest::StatusCode::FORBIDDEN,
+        "lease fee of 3000 sat exceeds the max of 2000 sat",
+        "CannotBuyInbound",
+    )
+    .await;
+
+    let res = buy_inbound_res(node2_addr, &seller, 200_000, 3_000).await;
+    let lease = _check_response_is_ok(res)
+        .await
+        .json::<BuyInboundResponse>()
+        .await
+        .unwrap();
+    assert_eq!(lease.fee_sat, 3_000);
+    assert_eq!(lease.lease_duration_blocks, 4032);
+    wait_for_ln_payment(node2_addr, &lease.payment_hash, HTLCStatus::Succeeded).await;
+
+    // the seller opens the channel once the fee has been paid
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let orders = lease_orders(node1_addr).await;
+        let order = orders
+            .iter()
+            .find(|o| o.order_id == lease.order_id)
+            .unwrap();
+        if order.status == LeaseOrderStatus::ChannelOpened {
+            assert!(order.temporary_channel_id.is_some());
+            assert_eq!(order.capacity_sat, 200_000);
+            assert_eq!(order.fee_sat, 3_000);
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("the leased channel has not been opened")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+    // the unpaid order is still waiting for its fee
+    let orders = lease_orders(node1_addr).await;
+    assert_eq!(orders.len(), 2);
+    assert!(orders
+        .iter()
+        .any(|o| o.status == LeaseOrderStatus::AwaitingPayment));
+
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let channels = list_channels(node2_addr).await;
+        if let Some(channel) = channels
+            .iter()
+            .find(|c| c.peer_pubkey == node1_pubkey && c.capacity_sat == 200_000)
+        {
+            if channel
+                .funding_txid
+                .as_ref()
+                .is_some_and(|txid| !_get_txout(txid).is_empty())
+            {
+                assert!(!channel.public);
+                assert_eq!(channel.local_balance_sat, 0);
+                break;
+            }
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 50.0 {
+            panic!("cannot find the funding TX of the leased channel")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+    mine_n_blocks(false, 6);
+    wait_for_usable_channels(node2_addr, 2).await;
+}
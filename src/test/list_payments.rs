@@ -0,0 +1,188 @@
+use crate::utils::get_current_timestamp;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/list_payments/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn list_payments_filters() {
+    initialize();
+
+    let test_dir_base = format!("{TEST_DIR_BASE}list_payments_filters/");
+    let test_dir_node1 = format!("{test_dir_base}node1");
+    let test_dir_node2 = format!("{test_dir_base}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    for _ in 0..3 {
+        let LNInvoiceResponse { invoice } = ln_invoice(node2_addr, None, None, None, 900).await;
+        send_payment(node1_addr, invoice).await;
+    }
+    let LNInvoiceResponse { invoice } = ln_invoice(node2_addr, None, None, None, 900).await;
+    let pending_hash = decode_ln_invoice(node2_addr, &invoice).await.payment_hash;
+
+    // no filter returns everything
+    let all = list_payments_filtered(node2_addr, &ListPaymentsRequest::default()).await;
+    assert_eq!(all.total, 4);
+    assert_eq!(all.payments.len(), 4);
+    assert!(all
+        .payments
+        .windows(2)
+        .all(|w| w[0].created_at >= w[1].created_at));
+
+    // filters
+    let req = ListPaymentsRequest {
+        status: Some(HTLCStatus::Succeeded),
+        ..Default::default()
+    };
+    assert_eq!(list_payments_filtered(node2_addr, &req).await.total, 3);
+    let req = ListPaymentsRequest {
+        status: Some(HTLCStatus::Pending),
+        ..Default::default()
+    };
+    let pending = list_payments_filtered(node2_addr, &req).await;
+    assert_eq!(pending.total, 1);
+    assert_eq!(pending.payments[0].payment_hash, pending_hash);
+    let req = ListPaymentsRequest {
+        inbound: Some(false),
+        ..Default::default()
+    };
+    assert_eq!(list_payments_filtered(node2_addr, &req).await.total, 0);
+    assert_eq!(list_payments_filtered(node1_addr, &req).await.total, 3);
+    let req = ListPaymentsRequest {
+        asset_id: Some(s!("rgb:CJkb4YZw-jRiz2sk-~PARPio-wtVYI1c-XAEYCqO-wTfvRZ8")),
+        ..Default::default()
+    };
+    assert_eq!(list_payments_filtered(node2_addr, &req).await.total, 0);
+    let req = ListPaymentsRequest {
+        created_from: Some(get_current_timestamp() + 3600),
+        ..Default::default()
+    };
+    assert_eq!(list_payments_filtered(node2_addr, &req).await.total, 0);
+    let req = ListPaymentsRequest {
+        created_to: Some(get_current_timestamp()),
+        ..Default::default()
+    };
+    assert_eq!(list_payments_filtered(node2_addr, &req).await.total, 4);
+    let req = ListPaymentsRequest {
+        payment_hash_prefix: Some(pending_hash[..16].to_uppercase()),
+        ..Default::default()
+    };
+    let found = list_payments_filtered(node2_addr, &req).await;
+    assert_eq!(found.total, 1);
+    assert_eq!(found.payments[0].payment_hash, pending_hash);
+
+    // pagination
+    let req = ListPaymentsRequest {
+        limit: Some(2),
+        ..Default::default()
+    };
+    let page1 = list_payments_filtered(node2_addr, &req).await;
+    assert_eq!(page1.total, 4);
+    assert_eq!(page1.payments.len(), 2);
+    let req = ListPaymentsRequest {
+        limit: Some(2),
+        offset: Some(2),
+        ..Default::default()
+    };
+    let page2 = list_payments_filtered(node2_addr, &req).await;
+    assert_eq!(page2.total, 4);
+    assert_eq!(page2.payments.len(), 2);
+    let mut hashes: Vec<_> = page1
+        .payments
+        .iter()
+        .chain(page2.payments.iter())
+        .map(|p| p.payment_hash.clone())
+        .collect();
+    hashes.sort();
+    hashes.dedup();
+    assert_eq!(hashes.len(), 4);
+    let req = ListPaymentsRequest {
+        offset: Some(4),
+        ..Default::default()
+    };
+    assert!(list_payments_filtered(node2_addr, &req)
+        .await
+        .payments
+        .is_empty());
+
+    // /v2 pagination, with cursors
+    let res = reqwest::Client::new()
+        .get(format!("http://{node2_addr}/v2/listpayments"))
+        .query(&[("limit", "3")])
+        .send()
+        .await
+        .unwrap();
+    let page1 = _check_response_is_ok(res)
+        .await
+        .json::<Page<Payment>>()
+        .await
+        .unwrap();
+    assert_eq!(page1.total, 4);
+    assert_eq!(page1.items.len(), 3);
+    let cursor = page1.next_cursor.unwrap();
+    let res = reqwest::Client::new()
+        .get(format!("http://{node2_addr}/v2/listpayments"))
+        .query(&[("limit", "3"), ("cursor", &cursor)])
+        .send()
+        .await
+        .unwrap();
+    let page2 = _check_response_is_ok(res)
+        .await
+        .json::<Page<Payment>>()
+        .await
+        .unwrap();
+    assert_eq!(page2.total, 4);
+    assert_eq!(page2.items.len(), 1);
+    assert!(page2.next_cursor.is_none());
+    assert!(page1
+        .items
+        .iter()
+        .all(|p| p.payment_hash != page2.items[0].payment_hash));
+
+    // failures
+    let res = reqwest::Client::new()
+        .get(format!("http://{node2_addr}/listpayments"))
+        .query(&[("limit", "0")])
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "limit must be between 1 and 1000",
+        "InvalidPagination",
+    )
+    .await;
+    let res = reqwest::Client::new()
+        .get(format!("http://{node2_addr}/listpayments"))
+        .query(&[("payment_hash_prefix", "xyz")])
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid payment hash: xyz",
+        "InvalidPaymentHash",
+    )
+    .await;
+}
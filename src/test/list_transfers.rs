@@ -0,0 +1,161 @@
+use crate::routes::{TransferKind, TransferStatus};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/list_transfers/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn list_transfers_filters() {
+    initialize();
+
+    let test_dir_base = format!("{TEST_DIR_BASE}list_transfers_filters/");
+    let test_dir_node1 = format!("{test_dir_base}node1");
+    let test_dir_node2 = format!("{test_dir_base}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let recipient_id = rgb_invoice(node2_addr, None, false).await.recipient_id;
+    send_asset(
+        node1_addr,
+        &asset_id,
+        Assignment::Fungible(100),
+        recipient_id,
+        None,
+    )
+    .await;
+    rgb_invoice(node1_addr, Some(asset_id.clone()), false).await;
+
+    // no filter returns everything
+    let all = list_transfers_filtered(
+        node1_addr,
+        &ListTransfersRequest {
+            asset_id: asset_id.clone(),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(all.total, 3);
+    assert_eq!(all.transfers.len(), 3);
+    assert!(all
+        .transfers
+        .windows(2)
+        .all(|w| w[0].created_at >= w[1].created_at));
+
+    // filters
+    let req = ListTransfersRequest {
+        asset_id: asset_id.clone(),
+        inbound: Some(false),
+        ..Default::default()
+    };
+    let res = list_transfers_filtered(node1_addr, &req).await;
+    assert_eq!(res.total, 1);
+    assert_eq!(res.transfers[0].kind, TransferKind::Send);
+    let req = ListTransfersRequest {
+        asset_id: asset_id.clone(),
+        inbound: Some(true),
+        ..Default::default()
+    };
+    assert_eq!(list_transfers_filtered(node1_addr, &req).await.total, 2);
+    let req = ListTransfersRequest {
+        asset_id: asset_id.clone(),
+        status: Some(TransferStatus::Settled),
+        ..Default::default()
+    };
+    let res = list_transfers_filtered(node1_addr, &req).await;
+    assert_eq!(res.total, 1);
+    assert_eq!(res.transfers[0].kind, TransferKind::Issuance);
+    let req = ListTransfersRequest {
+        asset_id: asset_id.clone(),
+        status: Some(TransferStatus::WaitingCounterparty),
+        ..Default::default()
+    };
+    assert_eq!(list_transfers_filtered(node1_addr, &req).await.total, 2);
+    let req = ListTransfersRequest {
+        asset_id: asset_id.clone(),
+        created_from: Some(all.transfers[0].created_at + 1),
+        ..Default::default()
+    };
+    assert_eq!(list_transfers_filtered(node1_addr, &req).await.total, 0);
+    let req = ListTransfersRequest {
+        asset_id: asset_id.clone(),
+        created_to: Some(all.transfers[2].created_at - 1),
+        ..Default::default()
+    };
+    assert_eq!(list_transfers_filtered(node1_addr, &req).await.total, 0);
+
+    // pagination
+    let req = ListTransfersRequest {
+        asset_id: asset_id.clone(),
+        limit: Some(2),
+        ..Default::default()
+    };
+    let page1 = list_transfers_filtered(node1_addr, &req).await;
+    assert_eq!(page1.total, 3);
+    assert_eq!(page1.transfers.len(), 2);
+    let req = ListTransfersRequest {
+        asset_id: asset_id.clone(),
+        limit: Some(2),
+        offset: Some(2),
+        ..Default::default()
+    };
+    let page2 = list_transfers_filtered(node1_addr, &req).await;
+    assert_eq!(page2.total, 3);
+    assert_eq!(page2.transfers.len(), 1);
+    assert!(page1
+        .transfers
+        .iter()
+        .all(|t| t.idx != page2.transfers[0].idx));
+
+    // detail lookup
+    let send_idx = all
+        .transfers
+        .iter()
+        .find(|t| t.kind == TransferKind::Send)
+        .unwrap()
+        .idx;
+    let detail = transfer_detail(node1_addr, send_idx).await;
+    assert_eq!(detail.asset_id, asset_id);
+    assert_eq!(detail.transfer.idx, send_idx);
+    assert_eq!(detail.transfer.kind, TransferKind::Send);
+
+    // failures
+    let req = ListTransfersRequest {
+        asset_id: asset_id.clone(),
+        limit: Some(0),
+        ..Default::default()
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/listtransfers"))
+        .json(&req)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "limit must be between 1 and 1000",
+        "InvalidPagination",
+    )
+    .await;
+
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/transferdetail"))
+        .json(&TransferDetailRequest { idx: 9999 })
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Transfer not found: 9999",
+        "TransferNotFound",
+    )
+    .await;
+}
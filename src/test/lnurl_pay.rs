@@ -0,0 +1,163 @@
+use bitcoin::hashes::{sha256, Hash};
+use lightning_invoice::Bolt11InvoiceDescriptionRef;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/lnurl_pay/";
+
+async fn check_lnurl_error(res: Response, expected_status: reqwest::StatusCode, reason: &str) {
+    assert_eq!(res.status(), expected_status);
+    let lnurl_error = res.json::<LnurlErrorResponse>().await.unwrap();
+    assert_eq!(lnurl_error.status, "ERROR");
+    assert!(lnurl_error.reason.contains(reason));
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn lnurl_pay() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // no address is served before configuring one
+    let res = reqwest::Client::new()
+        .get(format!("http://{node2_addr}/.well-known/lnurlp/alice"))
+        .send()
+        .await
+        .unwrap();
+    check_lnurl_error(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown lightning address",
+    )
+    .await;
+
+    let address = LightningAddress {
+        name: s!("alice"),
+        description: s!("Tips for Alice"),
+        min_sendable_msat: 1000,
+        max_sendable_msat: 10000000,
+        asset_id: None,
+    };
+    let config = update_lnurl_pay(node2_addr, "Example.com", vec![address]).await;
+    assert_eq!(config.domain, Some(s!("example.com")));
+    assert_eq!(config.addresses.len(), 1);
+
+    let metadata = lnurl_pay_metadata(node2_addr, "alice").await;
+    assert_eq!(metadata.tag, "payRequest");
+    assert_eq!(
+        metadata.callback,
+        "https://example.com/lnurlp/alice/callback"
+    );
+    assert_eq!(metadata.min_sendable, 1000);
+    assert_eq!(metadata.max_sendable, 10000000);
+    assert!(metadata.metadata.contains("Tips for Alice"));
+    assert!(metadata.metadata.contains("alice@example.com"));
+
+    // the invoice commits to the metadata
+    let LnurlPayCallbackResponse { pr, routes } =
+        lnurl_pay_callback(node2_addr, "alice", 3000000, None).await;
+    assert!(routes.is_empty());
+    let invoice = Bolt11Invoice::from_str(&pr).unwrap();
+    assert_eq!(invoice.amount_milli_satoshis(), Some(3000000));
+    match invoice.description() {
+        Bolt11InvoiceDescriptionRef::Hash(hash) => {
+            assert_eq!(hash.0, sha256::Hash::hash(metadata.metadata.as_bytes()))
+        }
+        Bolt11InvoiceDescriptionRef::Direct(_) => panic!("expected a description hash"),
+    }
+    let payment_hash = send_payment(node1_addr, pr).await.payment_hash;
+    let payment = get_payment(node2_addr, &payment_hash).await;
+    assert_eq!(payment.status, HTLCStatus::Succeeded);
+
+    // callback errors
+    let res = reqwest::Client::new()
+        .get(format!("http://{node2_addr}/lnurlp/bob/callback"))
+        .query(&LnurlPayCallbackRequest {
+            amount: 3000000,
+            asset_amount: None,
+        })
+        .send()
+        .await
+        .unwrap();
+    check_lnurl_error(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown lightning address",
+    )
+    .await;
+    let res = reqwest::Client::new()
+        .get(format!("http://{node2_addr}/lnurlp/alice/callback"))
+        .query(&LnurlPayCallbackRequest {
+            amount: 20000000,
+            asset_amount: None,
+        })
+        .send()
+        .await
+        .unwrap();
+    check_lnurl_error(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "amount must be between 1000 and 10000000 msat",
+    )
+    .await;
+
+    // invalid config
+    let payload = LnurlPayRequest {
+        domain: s!("example.com"),
+        addresses: vec![LightningAddress {
+            name: s!("Alice!"),
+            description: s!("Tips"),
+            min_sendable_msat: 1000,
+            max_sendable_msat: 10000000,
+            asset_id: None,
+        }],
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/lnurlpay"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "invalid name Alice!",
+        "InvalidLnurlPay",
+    )
+    .await;
+
+    // the config survives a restart
+    shutdown(&[node2_addr]).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, true).await;
+    let res = reqwest::Client::new()
+        .get(format!("http://{node2_addr}/lnurlpay"))
+        .send()
+        .await
+        .unwrap();
+    let config = _check_response_is_ok(res)
+        .await
+        .json::<LnurlPayResponse>()
+        .await
+        .unwrap();
+    assert_eq!(config.domain, Some(s!("example.com")));
+    assert_eq!(config.addresses[0].name, "alice");
+}
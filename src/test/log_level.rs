@@ -0,0 +1,52 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/log_level/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn log_level_change() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    assert_eq!(log_level(node1_addr).await, DEFAULT_LOG_LEVEL);
+
+    // per-module directives are accepted
+    let directives = "info,rgb_lightning_node::ldk=trace";
+    assert_eq!(update_log_level(node1_addr, directives).await, directives);
+    assert_eq!(log_level(node1_addr).await, directives);
+
+    // the log level can be changed on a locked node
+    lock(node1_addr).await;
+    assert_eq!(update_log_level(node1_addr, "debug").await, "debug");
+
+    // invalid log levels are rejected without changes
+    let payload = LogLevelRequest {
+        log_level: s!("info,rgb_lightning_node=verbose"),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/loglevel"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid log level",
+        "InvalidLogLevel",
+    )
+    .await;
+    assert_eq!(log_level(node1_addr).await, "debug");
+
+    // reloading the config file replaces the log level
+    std::fs::write(
+        Path::new(&test_dir_node1).join("config.json"),
+        r#"{"log_level": "warn"}"#,
+    )
+    .unwrap();
+    reload_config(node1_addr).await;
+    assert_eq!(log_level(node1_addr).await, "warn");
+}
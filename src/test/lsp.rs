@@ -0,0 +1,145 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/lsp/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn lsp_fail() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let lsp = InvoiceLsp {
+        peer_pubkey_and_opt_addr: format!("{node2_pubkey}@127.0.0.1:{NODE2_PEER_PORT}"),
+        token: None,
+        max_fee_msat: None,
+    };
+
+    // invalid invoice options
+    let payload = LNInvoiceRequest {
+        amt_msat: None,
+        min_amt_msat: None,
+        max_amt_msat: None,
+        expiry_sec: 900,
+        asset_id: None,
+        asset_amount: None,
+        route_hints: None,
+        lsp: Some(lsp.clone()),
+        phantom: None,
+        label: None,
+        fallback_address_type: None,
+        ..Default::default()
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/lninvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "amt_msat is required to receive through a JIT channel",
+        "InvalidLspOptions",
+    )
+    .await;
+
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(3000000),
+        min_amt_msat: None,
+        max_amt_msat: None,
+        expiry_sec: 900,
+        asset_id: None,
+        asset_amount: None,
+        route_hints: Some(vec![]),
+        lsp: Some(lsp.clone()),
+        phantom: None,
+        label: None,
+        fallback_address_type: None,
+        ..Default::default()
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/lninvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "route_hints cannot be provided along with an LSP",
+        "InvalidLspOptions",
+    )
+    .await;
+
+    // unknown LSP address
+    let payload = LspFeeMenuRequest {
+        peer_pubkey_and_opt_addr: node2_pubkey.clone(),
+        token: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/lspfeemenu"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "cannot find the address for the provided pubkey",
+        "InvalidPeerInfo",
+    )
+    .await;
+
+    // a node that doesn't act as LSP never answers
+    let payload = LspFeeMenuRequest {
+        peer_pubkey_and_opt_addr: lsp.peer_pubkey_and_opt_addr.clone(),
+        token: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/lspfeemenu"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "lsps2.get_info: no response from the LSP",
+        "FailedLspRequest",
+    )
+    .await;
+
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(3000000),
+        min_amt_msat: None,
+        max_amt_msat: None,
+        expiry_sec: 900,
+        asset_id: None,
+        asset_amount: None,
+        route_hints: None,
+        lsp: Some(lsp),
+        phantom: None,
+        label: None,
+        fallback_address_type: None,
+        ..Default::default()
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/lninvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "lsps2.get_info: no response from the LSP",
+        "FailedLspRequest",
+    )
+    .await;
+}
@@ -0,0 +1,155 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/lsp_server/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn lsp_server() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node2_addr, None).await;
+    fund_and_create_utxos(node3_addr, None).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let lsp = InvoiceLsp {
+        peer_pubkey_and_opt_addr: format!("{node2_pubkey}@127.0.0.1:{NODE2_PEER_PORT}"),
+        token: None,
+        max_fee_msat: None,
+    };
+
+    // LSP mode is disabled by default
+    let payload = LspFeeMenuRequest {
+        peer_pubkey_and_opt_addr: lsp.peer_pubkey_and_opt_addr.clone(),
+        token: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/lspfeemenu"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "lsps2.get_info: method not found",
+        "FailedLspRequest",
+    )
+    .await;
+
+    let mut config = LspConfigRequest {
+        enabled: true,
+        min_fee_msat: 2_000_000,
+        fee_proportional_millionths: 10_000,
+        min_payment_size_msat: 60_000_000,
+        max_payment_size_msat: 50_000_000,
+        min_channel_size_sat: 100_000,
+        fee_params_validity_sec: 600,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/lsp/config"))
+        .json(&config)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "min_payment_size_msat cannot exceed max_payment_size_msat",
+        "InvalidLspConfig",
+    )
+    .await;
+    config.min_payment_size_msat = 10_000_000;
+    let lsp_config = update_lsp_config(node2_addr, &config).await;
+    assert!(lsp_config.enabled);
+    assert_eq!(lsp_config.min_payment_size_msat, 10_000_000);
+    assert!(lsp_clients(node2_addr).await.is_empty());
+
+    open_channel(
+        node3_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(500_000),
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // payment sizes out of the configured range are refused
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(60_000_000),
+        min_amt_msat: None,
+        max_amt_msat: None,
+        expiry_sec: 900,
+        asset_id: None,
+        asset_amount: None,
+        route_hints: None,
+        lsp: Some(lsp.clone()),
+        phantom: None,
+        label: None,
+        fallback_address_type: None,
+        ..Default::default()
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/lninvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "no fee parameters offered for a payment of 60000000 msat",
+        "CannotUseLsp",
+    )
+    .await;
+
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(30_000_000),
+        min_amt_msat: None,
+        max_amt_msat: None,
+        lsp: Some(lsp),
+        phantom: None,
+        ..payload
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/lninvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let invoice = _check_response_is_ok(res)
+        .await
+        .json::<LNInvoiceResponse>()
+        .await
+        .unwrap()
+        .invoice;
+    let clients = lsp_clients(node2_addr).await;
+    assert_eq!(clients.len(), 1);
+    assert_eq!(clients[0].client_pubkey, node1_pubkey);
+    assert_eq!(clients[0].payment_size_msat, 30_000_000);
+    assert_eq!(clients[0].opening_fee_msat, 2_000_000);
+    assert_eq!(clients[0].status, LspClientStatus::Waiting);
+    assert!(clients[0].channel_id.is_none());
+
+    // the payment opens the JIT channel to the client
+    let payment_hash = decode_ln_invoice(node1_addr, &invoice).await.payment_hash;
+    send_payment(node3_addr, invoice).await;
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Succeeded).await;
+
+    let clients = lsp_clients(node2_addr).await;
+    assert_eq!(clients[0].status, LspClientStatus::Forwarded);
+    let channels = list_channels(node1_addr).await;
+    assert_eq!(channels.len(), 1);
+    assert_eq!(clients[0].channel_id, Some(channels[0].channel_id.clone()));
+    assert!(!channels[0].public);
+}
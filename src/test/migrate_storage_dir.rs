@@ -0,0 +1,91 @@
+use crate::error::AppError;
+use crate::store::{migrate_storage, migrate_storage_dir, SQLITE_STORE_FNAME};
+use crate::utils::LDK_DIR;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/migrate_storage_dir/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn migrate_storage_dir_with_channel() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node1_moved = format!("{TEST_DIR_BASE}node1_moved");
+    let _ = std::fs::remove_dir_all(&test_dir_node1_moved);
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(node2_addr, None, Some(&asset_id), Some(100), 900).await;
+    let payment = send_payment(node1_addr, invoice).await;
+
+    shutdown(&[node1_addr]).await;
+
+    // a directory already holding files is refused
+    std::fs::create_dir_all(&test_dir_node1_moved).unwrap();
+    std::fs::write(Path::new(&test_dir_node1_moved).join("other"), "data").unwrap();
+    let err = migrate_storage_dir(Path::new(&test_dir_node1), Path::new(&test_dir_node1_moved))
+        .unwrap_err();
+    assert!(matches!(err, AppError::InvalidStorageDir(_)));
+    std::fs::remove_dir_all(&test_dir_node1_moved).unwrap();
+
+    // the whole node is moved, then its data is moved to a database in the new directory
+    let migrated =
+        migrate_storage_dir(Path::new(&test_dir_node1), Path::new(&test_dir_node1_moved)).unwrap();
+    assert!(migrated > 0);
+    assert!(!Path::new(&test_dir_node1).exists());
+    let err = migrate_storage_dir(Path::new(&test_dir_node1), Path::new(&test_dir_node1_moved))
+        .unwrap_err();
+    assert!(matches!(err, AppError::InvalidStorageDir(_)));
+    migrate_storage(Path::new(&test_dir_node1_moved), StorageBackend::Sqlite).unwrap();
+    assert!(Path::new(&test_dir_node1_moved)
+        .join(LDK_DIR)
+        .join(SQLITE_STORE_FNAME)
+        .is_file());
+
+    // the node keeps its identity, channel, payments and assets in the new directory
+    let (node1_addr, _) = start_node_with_args(
+        UserArgs {
+            storage_dir_path: test_dir_node1_moved.clone().into(),
+            ldk_peer_listening_port: NODE1_PEER_PORT,
+            storage_backend: StorageBackend::Sqlite,
+            ..Default::default()
+        },
+        true,
+    )
+    .await;
+    assert_eq!(node_info(node1_addr).await.pubkey, node1_pubkey);
+    let channels = list_channels(node1_addr).await;
+    assert_eq!(channels.len(), 1);
+    assert_eq!(channels[0].channel_id, channel.channel_id);
+    assert!(list_payments(node1_addr)
+        .await
+        .iter()
+        .any(|p| p.payment_hash == payment.payment_hash));
+
+    close_channel(node1_addr, &channel.channel_id, &node2_pubkey, false).await;
+    wait_for_balance(node1_addr, &asset_id, 900).await;
+    wait_for_balance(node2_addr, &asset_id, 100).await;
+
+    shutdown(&[node1_addr, node2_addr]).await;
+}
@@ -1,50 +1,117 @@
 use amplify::s;
-use biscuit_auth::{builder::date, macros::*, KeyPair};
+use biscuit_auth::{
+    builder::{date, BiscuitBuilder},
+    macros::*,
+    Biscuit, KeyPair,
+};
 use chrono::{DateTime, Local, Utc};
-use electrum_client::ElectrumApi;
 use lazy_static::lazy_static;
+use lightning::ln::channelmanager::MIN_FINAL_CLTV_EXPIRY_DELTA;
 use lightning_invoice::Bolt11Invoice;
-use once_cell::sync::Lazy;
 use reqwest::Response;
 use rgb_lib::BitcoinNetwork;
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::Command;
 use std::str::FromStr;
-use std::sync::{Mutex, Once, RwLock};
+use std::sync::{Mutex, Once};
 use time::OffsetDateTime;
 use tokio::io::AsyncReadExt;
 use tokio::net::TcpListener;
 use tracing_test::traced_test;
 
-use crate::error::APIErrorResponse;
-use crate::ldk::FEE_RATE;
+use crate::args::{
+    StorageBackend, DEFAULT_ADDRESS_GAP_LIMIT, DEFAULT_CLTV_EXPIRY_DELTA, DEFAULT_MIN_CHANNEL_DEPTH,
+};
+use crate::config::DEFAULT_LOG_LEVEL;
+use crate::error::{APIErrorResponse, APIErrorResponseV2};
+use crate::ldk::{FEE_RATE, HTLC_RISK_WINDOW_BLOCKS};
 use crate::routes::{
-    AddressResponse, AssetBalanceRequest, AssetBalanceResponse, AssetCFA, AssetNIA, AssetUDA,
-    Assignment, BackupRequest, BtcBalanceRequest, BtcBalanceResponse, ChangePasswordRequest,
-    Channel, CloseChannelRequest, ConnectPeerRequest, CreateUtxosRequest, DecodeLNInvoiceRequest,
-    DecodeLNInvoiceResponse, DecodeRGBInvoiceRequest, DecodeRGBInvoiceResponse,
-    DisconnectPeerRequest, EmptyResponse, FailTransfersRequest, FailTransfersResponse,
-    GetAssetMediaRequest, GetAssetMediaResponse, GetChannelIdRequest, GetChannelIdResponse,
-    GetPaymentRequest, GetPaymentResponse, GetSwapRequest, GetSwapResponse, HTLCStatus,
-    InitRequest, InitResponse, InvoiceStatus, InvoiceStatusRequest, InvoiceStatusResponse,
-    IssueAssetCFARequest, IssueAssetCFAResponse, IssueAssetNIARequest, IssueAssetNIAResponse,
-    IssueAssetUDARequest, IssueAssetUDAResponse, KeysendRequest, KeysendResponse, LNInvoiceRequest,
-    LNInvoiceResponse, ListAssetsRequest, ListAssetsResponse, ListChannelsResponse,
-    ListPaymentsResponse, ListPeersResponse, ListSwapsResponse, ListTransactionsRequest,
+    AbandonPaymentRequest, AccelerateIncomingRequest, AccelerateIncomingResponse,
+    AcceptChannelRequest, AccountingEntryKind, AddressResponse, AddressStatsResponse, AddressType,
+    AnchorReserveEventKind, AnchorReserveResponse, ApproveBroadcastRequest,
+    ApproveBroadcastResponse, AssetAuditResponse, AssetBalanceRequest, AssetBalanceResponse,
+    AssetCFA, AssetIFA, AssetNIA, AssetRecipient, AssetUDA, Assignment, AuditResponse,
+    AuditSnapshot, BackupChannelsRequest, BackupChannelsResponse, BackupRequest,
+    BalanceSheetRequest, BalanceSheetResponse, Bolt12Offer, BroadcastPsbtRequest,
+    BroadcastPsbtResponse, BtcBalanceRequest, BtcBalanceResponse, BtcRecipient,
+    BumpCloseFeeRequest, BumpCloseFeeResponse, BumpFeeRequest, BumpFeeResponse, BurnAssetRequest,
+    BurnAssetResponse, BuyInboundRequest, BuyInboundResponse, CancelInvoiceRequest,
+    CancelInvoicesRequest, CancelInvoicesResponse, ChainEvent, ChainEventKind, ChainEventsResponse,
+    ChainSubscriptionsRequest, ChainSubscriptionsResponse, ChangePasswordRequest, Channel,
+    ChannelAcceptorRequest, ChannelAcceptorResponse, ChannelDepthOverride, ChannelDepthsRequest,
+    ChannelDepthsResponse, ChannelEventKind, ChannelEventsResponse, ChannelOpenStage,
+    ChannelStatusRequest, ChannelStatusResponse, CloseChannelRequest, CloseDetailRequest,
+    CloseDetailResponse, CloseOutputStatus, CoinSelection, CoinSelectionStrategy,
+    CompactStorageRequest, CompactStorageResponse, ConfirmTotpRequest, ConnectPeerRequest,
+    CreateOrderRequest, CreateOrderResponse, DecodeLNInvoiceRequest, DecodeLNInvoiceResponse,
+    DecodeRGBInvoiceRequest, DecodeRGBInvoiceResponse, DescriptorsResponse, DisableTotpRequest,
+    DisconnectPeerRequest, EmergencyKitContents, EmergencyKitResponse, EmptyResponse,
+    EnrollTotpRequest, EnrollTotpResponse, Escrow, EscrowCreateRequest, EscrowCreateResponse,
+    EscrowDecision, EscrowResolveRequest, EscrowResolveResponse, EscrowStatus,
+    ExportAccountingRequest, ExportAccountingResponse, ExportBackupRequest,
+    ExportConsignmentRequest, ExportFormat, ExternalFunding, FailTransferRequest,
+    FailTransfersRequest, FailTransfersResponse, FeeBumpMethod, FeeReportResponse,
+    ForwardResolution, ForwardingHistoryRequest, ForwardingHistoryResponse,
+    FundChannelAbortRequest, FundChannelCompleteRequest, FundChannelCompleteResponse,
+    FundPsbtRequest, FundPsbtResponse, GetAssetMediaRequest, GetAssetMediaResponse,
+    GetChanInfoResponse, GetChannelIdRequest, GetChannelIdResponse, GetNodeInfoResponse,
+    GetOrderRequest, GetOrderResponse, GetPaymentRequest, GetPaymentResponse, GetSwapRequest,
+    GetSwapResponse, HTLCStatus, Hedge, HedgeStatus, HedgingPoliciesResponse, HedgingPolicyEntry,
+    HodlInvoiceRequest, HodlInvoiceResponse, HtlcDirection, HtlcKind, HtlcResolution,
+    HtlcsResponse, ImportConsignmentResponse, InitRequest, InitResponse, InterceptScopeKind,
+    InterceptScopesRequest, InterceptScopesResponse, InterceptedHtlc, InterceptedHtlcsResponse,
+    InvoiceLsp, InvoicePhantom, InvoiceQuotasResponse, InvoiceResult, InvoiceRouteHint,
+    InvoiceRouteHintHop, InvoiceStatus, InvoiceStatusRequest, InvoiceStatusResponse,
+    IssueAssetCFARequest, IssueAssetCFAResponse, IssueAssetIFARequest, IssueAssetIFAResponse,
+    IssueAssetNIARequest, IssueAssetNIAResponse, IssueAssetUDARequest, IssueAssetUDAResponse,
+    JournalEventKind, JournalProofResponse, KeysendRequest, KeysendResponse, LNInvoiceRequest,
+    LNInvoiceResponse, LeaseConfigRequest, LeaseConfigResponse, LeaseOrder, LeaseOrderStatus,
+    LeaseOrdersResponse, LeaseRatesRequest, LeaseRatesResponse, LightningAddress,
+    ListAssetsRequest, ListAssetsResponse, ListEscrowsResponse, ListHedgesResponse,
+    ListOffersResponse, ListPaymentsRequest, ListPaymentsResponse, ListPeersResponse,
+    ListSubmarineSwapsResponse, ListSwapsResponse, ListSweepsResponse, ListTransactionsRequest,
     ListTransactionsResponse, ListTransfersRequest, ListTransfersResponse, ListUnspentsRequest,
-    ListUnspentsResponse, MakerExecuteRequest, MakerInitRequest, MakerInitResponse,
-    NetworkInfoResponse, NodeInfoResponse, OpenChannelRequest, OpenChannelResponse, Payment, Peer,
-    PostAssetMediaResponse, RefreshRequest, RestoreRequest, RevokeTokenRequest, RgbInvoiceRequest,
-    RgbInvoiceResponse, SendAssetRequest, SendAssetResponse, SendBtcRequest, SendBtcResponse,
-    SendPaymentRequest, SendPaymentResponse, Swap, SwapStatus, TakerRequest, Transaction, Transfer,
-    UnlockRequest, Unspent, WitnessData,
+    ListUnspentsResponse, LnurlErrorResponse, LnurlPayCallbackRequest, LnurlPayCallbackResponse,
+    LnurlPayMetadataResponse, LnurlPayRequest, LnurlPayResponse, LockUtxoRequest, LogLevelRequest,
+    LogLevelResponse, LspClient, LspClientStatus, LspClientsResponse, LspConfigRequest,
+    LspConfigResponse, LspFeeMenuRequest, MakerExecuteRequest, MakerInitRequest, MakerInitResponse,
+    MultisigFunding, MultisigInput, NetworkInfoResponse, NodeInfoResponse, NwcBudgetRenewal,
+    NwcConnectRequest, NwcConnectResponse, NwcConnection, NwcConnectionsResponse, NwcRevokeRequest,
+    OfferRequest, OfferResponse, OpenChannelRequest, OpenChannelResponse, Order, OrderItem,
+    OrderStatus, OwnershipProofResponse, Page, PayOfferRequest, PayOfferResponse, Payment, Peer,
+    PeerCompatResponse, PeerFilterRequest, PeerFilterResponse, PeerSuggestion, PeerSuggestionGoal,
+    PeerSuggestionsRequest, PeerSuggestionsResponse, PeerTransport, PendingBroadcast,
+    PendingBroadcastKind, PendingBroadcastsResponse, PendingChannel, PendingChannelsResponse,
+    PendingExternalFunding, PendingFunding, PendingFundingsResponse, PhantomRouteHintsResponse,
+    PostAssetMediaResponse, ProbePaymentRequest, ProbePaymentResponse, QueryRoutesRequest,
+    QueryRoutesResponse, RebalanceRequest, RebalanceResponse, RecipientType, RefreshRequest,
+    ReissueAssetRequest, ReissueAssetResponse, RejectChannelRequest, ReloadConfigResponse,
+    RemoveHedgingPolicyRequest, ResolveHtlcRequest, RestoreChannelsRequest,
+    RestoreChannelsResponse, RestoreRequest, RetrySweepRequest, RetryTransferRequest,
+    RevokeTokenRequest, RgbInvoiceRequest, RgbInvoiceResponse, SendAssetRequest, SendAssetResponse,
+    SendBtcManyRequest, SendBtcManyResponse, SendBtcRequest, SendBtcResponse, SendPaymentRequest,
+    SendPaymentResponse, SendToRouteRequest, SetAliasRequest, SetHedgingPolicyRequest,
+    SetViewPasswordRequest, SettleInvoiceRequest, SettleInvoicesRequest, SettleInvoicesResponse,
+    SettlementsRequest, SettlementsResponse, SignPsbtRequest, SignPsbtResponse, SubmarineSwap,
+    Swap, SwapInRequest, SwapOutRequest, SwapStatus, Sweep, SweepConfigRequest,
+    SweepConfigResponse, SweepStatus, SyncStage, SyncStatusResponse, TakerRequest,
+    ThrottleGossipRequest, TorStatusResponse, Transaction, Transfer, TransferDetailRequest,
+    TransferDetailResponse, UnlockRequest, UnlockUtxoRequest, Unspent, UpdateChannelPolicyRequest,
+    UpdateChannelPolicyResponse, VerifyMessageRequest, VerifyMessageResponse, WitnessData,
+    HTLC_MIN_MSAT,
 };
+use crate::testkit::{
+    _check_response_is_ok, _fund_wallet, _get_txout, address, create_utxos, fund_and_create_utxos,
+    get_block_count, list_channels, mine, mine_n_blocks, network_info, node_info, open_channel,
+    open_channel_raw, open_channel_with_custom_data, start_daemon_with_args, start_node,
+    start_node_with_args, stop_mining, unlock, unlock_req, unlock_res, wait_for_usable_channels,
+};
+use crate::tor::OnionServiceConfig;
 use crate::utils::{hex_str_to_vec, ELECTRUM_URL_REGTEST, PROXY_ENDPOINT_LOCAL};
 
 use super::*;
 
-const ELECTRUM_URL: &str = "127.0.0.1:50001";
 const NODE1_PEER_PORT: u16 = 9801;
 const NODE2_PEER_PORT: u16 = 9802;
 const NODE3_PEER_PORT: u16 = 9803;
@@ -54,41 +121,6 @@ const NODE6_PEER_PORT: u16 = 9806;
 
 static INIT: Once = Once::new();
 
-static MINER: Lazy<RwLock<Miner>> = Lazy::new(|| RwLock::new(Miner { no_mine_count: 0 }));
-
-#[cfg(test)]
-impl Default for UserArgs {
-    fn default() -> Self {
-        Self {
-            network: BitcoinNetwork::Regtest,
-            storage_dir_path: PathBuf::from("tmp/test_name/nodeN"),
-            daemon_listening_port: 3001,
-            ldk_peer_listening_port: 9735,
-            max_media_upload_size_mb: 3,
-            root_public_key: None,
-        }
-    }
-}
-
-fn _bitcoin_cli() -> [String; 7] {
-    [
-        s!("exec"),
-        s!("-T"),
-        s!("-u"),
-        s!("blits"),
-        s!("bitcoind"),
-        s!("bitcoin-cli"),
-        s!("-regtest"),
-    ]
-}
-
-async fn _check_response_is_ok(res: Response) -> Response {
-    if res.status() != reqwest::StatusCode::OK {
-        panic!("reqwest response is not OK: {:?}", res.text().await);
-    }
-    res
-}
-
 async fn check_response_is_nok(
     res: Response,
     expected_status: reqwest::StatusCode,
@@ -102,112 +134,95 @@ async fn check_response_is_nok(
     assert_eq!(api_error_response.name, expected_name);
 }
 
-fn _fund_wallet(address: String) {
-    let status = Command::new("docker")
-        .stdin(Stdio::null())
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .arg("compose")
-        .args(_bitcoin_cli())
-        .arg("-rpcwallet=miner")
-        .arg("sendtoaddress")
-        .arg(address)
-        .arg("1")
-        .status()
-        .expect("failed to fund wallet");
-    assert!(status.success());
-}
-
-fn _get_txout(txid: &str) -> String {
-    String::from_utf8(
-        Command::new("docker")
-            .stdin(Stdio::null())
-            .arg("compose")
-            .args(_bitcoin_cli())
-            .arg("-rpcwallet=miner")
-            .arg("gettxout")
-            .arg(txid)
-            .arg("0")
-            .output()
-            .expect("failed get txout")
-            .stdout,
-    )
-    .unwrap()
-}
-
 async fn start_daemon(
     node_test_dir: &str,
     node_peer_port: u16,
     root_public_key: Option<biscuit_auth::PublicKey>,
 ) -> SocketAddr {
-    let listener = TcpListener::bind("0.0.0.0:0").await.unwrap();
-    let node_address = listener.local_addr().unwrap();
-    std::fs::create_dir_all(node_test_dir).unwrap();
     let args = UserArgs {
         storage_dir_path: node_test_dir.into(),
         ldk_peer_listening_port: node_peer_port,
         root_public_key,
         ..Default::default()
     };
-    tokio::spawn(async move {
-        let (router, app_state) = app(args).await.unwrap();
-        axum::serve(listener, router)
-            .with_graceful_shutdown(shutdown_signal(app_state))
-            .await
-            .unwrap();
-    });
-    node_address
+    start_daemon_with_args(args).await
 }
 
-async fn start_node(
-    node_test_dir: &str,
-    node_peer_port: u16,
-    keep_node_dir: bool,
-) -> (SocketAddr, String) {
-    println!("starting node with peer port {node_peer_port}");
-    if !keep_node_dir && Path::new(&node_test_dir).is_dir() {
-        std::fs::remove_dir_all(node_test_dir).unwrap();
-    }
-    let node_address = start_daemon(node_test_dir, node_peer_port, None).await;
-
-    let password = format!("{node_test_dir}.{node_peer_port}");
+async fn accept_channel(node_address: SocketAddr, temporary_channel_id: &str) {
+    println!("accepting channel {temporary_channel_id} on node {node_address}");
+    let payload = AcceptChannelRequest {
+        temporary_channel_id: temporary_channel_id.to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/acceptchannel"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
+}
 
-    if !keep_node_dir {
-        let payload = InitRequest {
-            password: password.clone(),
-        };
-        let res = reqwest::Client::new()
-            .post(format!("http://{node_address}/init"))
-            .json(&payload)
-            .send()
-            .await
-            .unwrap();
-        _check_response_is_ok(res)
-            .await
-            .json::<InitResponse>()
-            .await
-            .unwrap();
-    }
+async fn address_stats(node_address: SocketAddr) -> AddressStatsResponse {
+    println!("getting address stats for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/addressstats"))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<AddressStatsResponse>()
+        .await
+        .unwrap()
+}
 
-    unlock(node_address, &password).await;
+async fn anchor_reserve(node_address: SocketAddr) -> AnchorReserveResponse {
+    println!("getting anchor reserve for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/anchorreserve"))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<AnchorReserveResponse>()
+        .await
+        .unwrap()
+}
 
-    println!("node on peer port {node_peer_port} started with address {node_address:?}");
-    (node_address, password)
+async fn approve_broadcast(node_address: SocketAddr, txid: &str) -> bool {
+    println!("approving broadcast of TX {txid} on node {node_address}");
+    let payload = ApproveBroadcastRequest { totp_code: None };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/approvebroadcast/{txid}"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ApproveBroadcastResponse>()
+        .await
+        .unwrap()
+        .broadcast
 }
 
-async fn address(node_address: SocketAddr) -> String {
-    println!("getting address for node {node_address}");
+async fn asset_audit(node_address: SocketAddr, asset_id: &str) -> AssetAuditResponse {
+    println!("auditing asset {asset_id} on node {node_address}");
     let res = reqwest::Client::new()
-        .post(format!("http://{node_address}/address"))
+        .get(format!("http://{node_address}/assetaudit/{asset_id}"))
         .send()
         .await
         .unwrap();
     _check_response_is_ok(res)
         .await
-        .json::<AddressResponse>()
+        .json::<AssetAuditResponse>()
         .await
         .unwrap()
-        .address
 }
 
 async fn asset_balance(node_address: SocketAddr, asset_id: &str) -> AssetBalanceResponse {
@@ -257,6 +272,26 @@ async fn backup(node_address: SocketAddr, backup_path: &str, password: &str) {
         .unwrap();
 }
 
+async fn backup_channels(node_address: SocketAddr, backup_path: &str, password: &str) -> usize {
+    println!("performing channel backup for node {node_address} on {backup_path}");
+    let payload = BackupChannelsRequest {
+        backup_path: backup_path.to_string(),
+        password: password.to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/backupchannels"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<BackupChannelsResponse>()
+        .await
+        .unwrap()
+        .num_channels
+}
+
 async fn btc_balance(node_address: SocketAddr) -> BtcBalanceResponse {
     println!("getting BTC balance for node {node_address}");
     let payload = BtcBalanceRequest { skip_sync: false };
@@ -273,6 +308,70 @@ async fn btc_balance(node_address: SocketAddr) -> BtcBalanceResponse {
         .unwrap()
 }
 
+async fn bump_close_fee(
+    node_address: SocketAddr,
+    channel_id: &str,
+    confirmation_target: u16,
+) -> BumpCloseFeeResponse {
+    println!("bumping close fee of channel {channel_id} on node {node_address}");
+    let payload = BumpCloseFeeRequest {
+        channel_id: channel_id.to_string(),
+        confirmation_target,
+        totp_code: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/bumpclosefee"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<BumpCloseFeeResponse>()
+        .await
+        .unwrap()
+}
+
+async fn cancel_invoice(node_address: SocketAddr, payment_hash: &str) {
+    println!("cancelling HODL invoice with payment hash {payment_hash} on node {node_address}");
+    let payload = CancelInvoiceRequest {
+        payment_hash: payment_hash.to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/cancelinvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
+}
+
+async fn cancel_invoices(node_address: SocketAddr, payment_hashes: &[&str]) -> Vec<InvoiceResult> {
+    println!(
+        "cancelling {} HODL invoices on node {node_address}",
+        payment_hashes.len()
+    );
+    let payload = CancelInvoicesRequest {
+        payment_hashes: payment_hashes.iter().map(|h| h.to_string()).collect(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/cancelinvoices"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<CancelInvoicesResponse>()
+        .await
+        .unwrap()
+        .results
+}
+
 async fn change_password(node_address: SocketAddr, old_password: &str, new_password: &str) {
     println!("changing password for node {node_address}");
     let payload = ChangePasswordRequest {
@@ -292,6 +391,64 @@ async fn change_password(node_address: SocketAddr, old_password: &str, new_passw
         .unwrap();
 }
 
+async fn chain_events(node_address: SocketAddr, subscription_id: &str) -> Vec<ChainEvent> {
+    println!("getting events for chain subscription {subscription_id} on node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!(
+            "http://{node_address}/chainevents/{subscription_id}"
+        ))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ChainEventsResponse>()
+        .await
+        .unwrap()
+        .events
+}
+
+async fn chain_subscription(
+    node_address: SocketAddr,
+    txid: Option<&str>,
+    address: Option<&str>,
+) -> String {
+    println!("subscribing to txid {txid:?} and address {address:?} on node {node_address}");
+    let payload = ChainSubscriptionsRequest {
+        txid: txid.map(|t| t.to_string()),
+        script: None,
+        address: address.map(|a| a.to_string()),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/chainsubscriptions"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ChainSubscriptionsResponse>()
+        .await
+        .unwrap()
+        .subscription_id
+}
+
+async fn channel_events(node_address: SocketAddr, channel_id: &str) -> ChannelEventsResponse {
+    println!("getting events for channel {channel_id} on node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!(
+            "http://{node_address}/channels/{channel_id}/events"
+        ))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ChannelEventsResponse>()
+        .await
+        .unwrap()
+}
+
 async fn check_payment_status(
     node_address: SocketAddr,
     payment_hash: &str,
@@ -309,19 +466,29 @@ async fn check_payment_status(
 }
 
 async fn close_channel(node_address: SocketAddr, channel_id: &str, peer_pubkey: &str, force: bool) {
-    println!(
-        "{}closing channel {channel_id} from node {node_address}",
-        if force { "force-" } else { "cooperatively " }
-    );
-    stop_mining();
     let payload = CloseChannelRequest {
         channel_id: channel_id.to_string(),
         peer_pubkey: peer_pubkey.to_string(),
         force,
+        close_address: None,
+        fee_rate: None,
+        confirmation_target: None,
+        totp_code: None,
     };
+    close_channel_with_options(node_address, &payload).await;
+}
+
+async fn close_channel_with_options(node_address: SocketAddr, payload: &CloseChannelRequest) {
+    let channel_id = payload.channel_id.as_str();
+    let force = payload.force;
+    println!(
+        "{}closing channel {channel_id} from node {node_address}",
+        if force { "force-" } else { "cooperatively " }
+    );
+    stop_mining();
     let res = reqwest::Client::new()
         .post(format!("http://{node_address}/closechannel"))
-        .json(&payload)
+        .json(payload)
         .send()
         .await
         .unwrap();
@@ -349,10 +516,30 @@ async fn close_channel(node_address: SocketAddr, channel_id: &str, peer_pubkey:
     }
 }
 
+async fn confirm_totp(node_address: SocketAddr, code: &str) {
+    println!("confirming TOTP enrollment for node {node_address}");
+    let payload = ConfirmTotpRequest {
+        code: code.to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/confirmtotp"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
+}
+
 async fn connect_peer(node_address: SocketAddr, peer_pubkey: &str, peer_addr: &str) {
     println!("connecting peer {peer_pubkey} from node {node_address}");
     let payload = ConnectPeerRequest {
         peer_pubkey_and_addr: format!("{peer_pubkey}@{peer_addr}"),
+        timeout_sec: None,
+        retries: None,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node_address}/connectpeer"))
@@ -367,41 +554,24 @@ async fn connect_peer(node_address: SocketAddr, peer_pubkey: &str, peer_addr: &s
         .unwrap();
 }
 
-async fn create_utxos(node_address: SocketAddr, up_to: bool, num: Option<u8>, size: Option<u32>) {
+async fn create_order(node_address: SocketAddr, items: Vec<OrderItem>, expiry_sec: u32) -> Order {
     println!(
-        "creating{}{} UTXOs{} for node {node_address}",
-        if up_to { " up to" } else { "" },
-        if let Some(num) = num {
-            format!(" {}", num)
-        } else {
-            s!("")
-        },
-        if let Some(size) = size {
-            format!(" of size {}", size)
-        } else {
-            s!("")
-        },
+        "creating order with {} items on node {node_address}",
+        items.len()
     );
-
-    let num = if num.is_some() { num } else { Some(10) };
-    let payload = CreateUtxosRequest {
-        up_to,
-        num,
-        size,
-        fee_rate: FEE_RATE,
-        skip_sync: false,
-    };
+    let payload = CreateOrderRequest { items, expiry_sec };
     let res = reqwest::Client::new()
-        .post(format!("http://{node_address}/createutxos"))
+        .post(format!("http://{node_address}/orders"))
         .json(&payload)
         .send()
         .await
         .unwrap();
     _check_response_is_ok(res)
         .await
-        .json::<EmptyResponse>()
+        .json::<CreateOrderResponse>()
         .await
-        .unwrap();
+        .unwrap()
+        .order
 }
 
 async fn decode_ln_invoice(node_address: SocketAddr, invoice: &str) -> DecodeLNInvoiceResponse {
@@ -458,126 +628,419 @@ async fn disconnect_peer(node_address: SocketAddr, peer_pubkey: &str) {
         .unwrap();
 }
 
-async fn fail_transfers(node_address: SocketAddr, batch_transfer_idx: Option<i32>) -> bool {
-    println!(
-        "failing transfers, batch_transfer_idx {batch_transfer_idx:?} from node {node_address}"
-    );
-    let payload = FailTransfersRequest {
-        batch_transfer_idx,
-        no_asset_only: false,
-        skip_sync: false,
-    };
+async fn emergency_kit(node_address: SocketAddr) -> EmergencyKitResponse {
+    println!("getting emergency kit for node {node_address}");
     let res = reqwest::Client::new()
-        .post(format!("http://{node_address}/failtransfers"))
-        .json(&payload)
+        .get(format!("http://{node_address}/emergencykit"))
         .send()
         .await
         .unwrap();
     _check_response_is_ok(res)
         .await
-        .json::<FailTransfersResponse>()
+        .json::<EmergencyKitResponse>()
         .await
         .unwrap()
-        .transfers_changed
-}
-
-async fn fund_and_create_utxos(node_address: SocketAddr, num: Option<u8>) {
-    println!("funding wallet for node {node_address}");
-    let addr = address(node_address).await;
-
-    _fund_wallet(addr);
-    mine(false);
-
-    create_utxos(node_address, false, Some(num.unwrap_or(10)), None).await;
-    mine(false);
 }
 
-async fn get_asset_media(node_address: SocketAddr, digest: &str) -> String {
-    println!("requesting media for digest {digest} from node {node_address}");
-    let payload = GetAssetMediaRequest {
-        digest: digest.to_string(),
+async fn enroll_totp(node_address: SocketAddr, payment_threshold_msat: u64) -> EnrollTotpResponse {
+    println!("enrolling TOTP for node {node_address}");
+    let payload = EnrollTotpRequest {
+        payment_threshold_msat,
     };
     let res = reqwest::Client::new()
-        .post(format!("http://{node_address}/getassetmedia"))
+        .post(format!("http://{node_address}/enrolltotp"))
         .json(&payload)
         .send()
         .await
         .unwrap();
     _check_response_is_ok(res)
         .await
-        .json::<GetAssetMediaResponse>()
+        .json::<EnrollTotpResponse>()
         .await
         .unwrap()
-        .bytes_hex
 }
 
-async fn get_channel_id(node_address: SocketAddr, temp_chan_id: &str) -> String {
-    println!("requesting channel ID for temporary ID {temp_chan_id} from node {node_address}");
-    let payload = GetChannelIdRequest {
-        temporary_channel_id: temp_chan_id.to_string(),
-    };
+async fn export_accounting(
+    node_address: SocketAddr,
+    payload: &ExportAccountingRequest,
+) -> ExportAccountingResponse {
+    println!("exporting accounting entries for node {node_address}");
     let res = reqwest::Client::new()
-        .post(format!("http://{node_address}/getchannelid"))
-        .json(&payload)
+        .get(format!("http://{node_address}/export/accounting"))
+        .query(payload)
         .send()
         .await
         .unwrap();
     _check_response_is_ok(res)
         .await
-        .json::<GetChannelIdResponse>()
+        .json::<ExportAccountingResponse>()
         .await
         .unwrap()
-        .channel_id
 }
 
-async fn invoice_status(node_address: SocketAddr, invoice: &str) -> InvoiceStatus {
-    println!("getting status of invoice {invoice} for node {node_address}");
-    let payload = InvoiceStatusRequest {
-        invoice: invoice.to_string(),
+async fn export_backup(node_address: SocketAddr, password: &str) -> Vec<u8> {
+    println!("exporting backup for node {node_address}");
+    let payload = ExportBackupRequest {
+        password: password.to_string(),
     };
     let res = reqwest::Client::new()
-        .post(format!("http://{node_address}/invoicestatus"))
+        .post(format!("http://{node_address}/exportbackup"))
         .json(&payload)
         .send()
         .await
         .unwrap();
     _check_response_is_ok(res)
         .await
-        .json::<InvoiceStatusResponse>()
+        .bytes()
         .await
         .unwrap()
-        .status
+        .to_vec()
 }
 
-async fn issue_asset_cfa(node_address: SocketAddr, file_path: Option<&str>) -> AssetCFA {
-    println!("issuing CFA asset on node {node_address}");
-    let mut file_digest = None;
-    if let Some(fp) = file_path {
-        file_digest = Some(post_asset_media(node_address, fp).await);
-    }
-    let payload = IssueAssetCFARequest {
-        amounts: vec![2000],
-        name: s!("Collectible"),
-        details: None,
-        precision: 0,
-        file_digest,
+async fn export_consignment(node_address: SocketAddr, asset_id: &str, txid: &str) -> Vec<u8> {
+    println!("exporting consignment for asset {asset_id} and txid {txid} from node {node_address}");
+    let payload = ExportConsignmentRequest {
+        asset_id: asset_id.to_string(),
+        txid: txid.to_string(),
     };
     let res = reqwest::Client::new()
-        .post(format!("http://{node_address}/issueassetcfa"))
+        .post(format!("http://{node_address}/exportconsignment"))
         .json(&payload)
         .send()
         .await
         .unwrap();
     _check_response_is_ok(res)
         .await
-        .json::<IssueAssetCFAResponse>()
+        .bytes()
         .await
         .unwrap()
-        .asset
+        .to_vec()
 }
 
-async fn issue_asset_nia(node_address: SocketAddr) -> AssetNIA {
-    println!("issuing NIA asset on node {node_address}");
+async fn fail_transfer(node_address: SocketAddr, idx: i32) {
+    println!("failing transfer {idx} on node {node_address}");
+    let payload = FailTransferRequest {
+        idx,
+        skip_sync: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/failtransfer"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+}
+
+async fn fail_transfers(node_address: SocketAddr, batch_transfer_idx: Option<i32>) -> bool {
+    println!(
+        "failing transfers, batch_transfer_idx {batch_transfer_idx:?} from node {node_address}"
+    );
+    let payload = FailTransfersRequest {
+        batch_transfer_idx,
+        no_asset_only: false,
+        skip_sync: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/failtransfers"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<FailTransfersResponse>()
+        .await
+        .unwrap()
+        .transfers_changed
+}
+
+async fn fee_report(node_address: SocketAddr) -> FeeReportResponse {
+    println!("getting fee report for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/feereport"))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<FeeReportResponse>()
+        .await
+        .unwrap()
+}
+
+async fn forwarding_history(
+    node_address: SocketAddr,
+    payload: &ForwardingHistoryRequest,
+) -> ForwardingHistoryResponse {
+    println!("getting forwarding history for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/forwardinghistory"))
+        .query(payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ForwardingHistoryResponse>()
+        .await
+        .unwrap()
+}
+
+async fn fund_channel_abort(node_address: SocketAddr, temporary_channel_id: &str) {
+    println!("aborting funding of channel {temporary_channel_id} on node {node_address}");
+    let payload = FundChannelAbortRequest {
+        temporary_channel_id: temporary_channel_id.to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/fundchannelabort"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
+}
+
+async fn get_asset_media(node_address: SocketAddr, digest: &str) -> String {
+    println!("requesting media for digest {digest} from node {node_address}");
+    let payload = GetAssetMediaRequest {
+        digest: digest.to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/getassetmedia"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<GetAssetMediaResponse>()
+        .await
+        .unwrap()
+        .bytes_hex
+}
+
+async fn get_channel_id(node_address: SocketAddr, temp_chan_id: &str) -> String {
+    println!("requesting channel ID for temporary ID {temp_chan_id} from node {node_address}");
+    let payload = GetChannelIdRequest {
+        temporary_channel_id: temp_chan_id.to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/getchannelid"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<GetChannelIdResponse>()
+        .await
+        .unwrap()
+        .channel_id
+}
+
+async fn hodl_invoice(
+    node_address: SocketAddr,
+    amt_msat: Option<u64>,
+    asset_id: Option<&str>,
+    asset_amount: Option<u64>,
+    payment_hash: Option<&str>,
+    reveal_preimage: bool,
+) -> HodlInvoiceResponse {
+    println!(
+        "generating HODL invoice for {amt_msat:?} msat and {asset_amount:?} of asset {asset_id:?} for node {node_address}"
+    );
+    let payload = HodlInvoiceRequest {
+        amt_msat,
+        min_amt_msat: None,
+        max_amt_msat: None,
+        expiry_sec: 900,
+        asset_id: asset_id.map(|a| a.to_string()),
+        asset_amount,
+        payment_hash: payment_hash.map(|h| h.to_string()),
+        reveal_preimage,
+        route_hints: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/hodlinvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<HodlInvoiceResponse>()
+        .await
+        .unwrap()
+}
+
+async fn htlcs(node_address: SocketAddr) -> HtlcsResponse {
+    println!("listing HTLCs for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/htlcs"))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<HtlcsResponse>()
+        .await
+        .unwrap()
+}
+
+async fn import_backup(node_address: SocketAddr, backup: Vec<u8>, password: &str) {
+    println!("importing backup for node {node_address}");
+    let form = reqwest::multipart::Form::new()
+        .part("file", reqwest::multipart::Part::bytes(backup))
+        .text("password", password.to_string());
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/importbackup"))
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
+}
+
+async fn import_consignment(node_address: SocketAddr, consignment: Vec<u8>, txid: &str) -> String {
+    println!("importing consignment with txid {txid} on node {node_address}");
+    let form = reqwest::multipart::Form::new()
+        .part("file", reqwest::multipart::Part::bytes(consignment))
+        .text("txid", txid.to_string());
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/importconsignment"))
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ImportConsignmentResponse>()
+        .await
+        .unwrap()
+        .asset_id
+}
+
+async fn intercept_scopes(
+    node_address: SocketAddr,
+    kind: InterceptScopeKind,
+    peer_pubkey: Option<&str>,
+    payment_hash: Option<&str>,
+) -> InterceptScopesResponse {
+    println!("adding {kind:?} intercept scope for node {node_address}");
+    let payload = InterceptScopesRequest {
+        kind,
+        peer_pubkey: peer_pubkey.map(|p| p.to_string()),
+        payment_hash: payment_hash.map(|h| h.to_string()),
+        hold_timeout_sec: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/interceptscopes"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<InterceptScopesResponse>()
+        .await
+        .unwrap()
+}
+
+async fn intercepted_htlcs(node_address: SocketAddr, scope_id: &str) -> Vec<InterceptedHtlc> {
+    println!("listing HTLCs intercepted by scope {scope_id} for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/interceptedhtlcs/{scope_id}"))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<InterceptedHtlcsResponse>()
+        .await
+        .unwrap()
+        .htlcs
+}
+
+async fn invoice_status(node_address: SocketAddr, invoice: &str) -> InvoiceStatus {
+    println!("getting status of invoice {invoice} for node {node_address}");
+    let payload = InvoiceStatusRequest {
+        invoice: Some(invoice.to_string()),
+        payment_hash: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/invoicestatus"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<InvoiceStatusResponse>()
+        .await
+        .unwrap()
+        .status
+}
+
+async fn invoice_status_by_hash(
+    node_address: SocketAddr,
+    payment_hash: &str,
+) -> InvoiceStatusResponse {
+    println!("getting status of invoice with payment hash {payment_hash} for node {node_address}");
+    let payload = InvoiceStatusRequest {
+        invoice: None,
+        payment_hash: Some(payment_hash.to_string()),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/invoicestatus"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<InvoiceStatusResponse>()
+        .await
+        .unwrap()
+}
+
+async fn issue_asset_cfa(node_address: SocketAddr, file_path: Option<&str>) -> AssetCFA {
+    println!("issuing CFA asset on node {node_address}");
+    let mut file_digest = None;
+    if let Some(fp) = file_path {
+        file_digest = Some(post_asset_media(node_address, fp).await);
+    }
+    let payload = IssueAssetCFARequest {
+        amounts: vec![2000],
+        name: s!("Collectible"),
+        details: None,
+        precision: 0,
+        file_digest,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/issueassetcfa"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<IssueAssetCFAResponse>()
+        .await
+        .unwrap()
+        .asset
+}
+
+async fn issue_asset_nia(node_address: SocketAddr) -> AssetNIA {
+    println!("issuing NIA asset on node {node_address}");
     let payload = IssueAssetNIARequest {
         amounts: vec![1000],
         ticker: s!("USDT"),
@@ -679,6 +1142,7 @@ async fn _keysend_raw(
         amt_msat,
         asset_id: asset_id.map(|a| a.to_string()),
         asset_amount,
+        totp_code: None,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node_address}/keysend"))
@@ -693,6 +1157,20 @@ async fn _keysend_raw(
         .unwrap()
 }
 
+async fn journal_proof(node_address: SocketAddr, range: &str) -> JournalProofResponse {
+    println!("exporting journal range {range} for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/journalproof/{range}"))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<JournalProofResponse>()
+        .await
+        .unwrap()
+}
+
 async fn keysend(
     node_address: SocketAddr,
     dest_pubkey: &str,
@@ -733,6 +1211,7 @@ async fn list_assets(node_address: SocketAddr) -> ListAssetsResponse {
     println!("listing assets for node {node_address}");
     let payload = ListAssetsRequest {
         filter_asset_schemas: vec![],
+        ..Default::default()
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node_address}/listassets"))
@@ -747,19 +1226,19 @@ async fn list_assets(node_address: SocketAddr) -> ListAssetsResponse {
         .unwrap()
 }
 
-async fn list_channels(node_address: SocketAddr) -> Vec<Channel> {
-    println!("listing channels for node {node_address}");
+async fn list_offers(node_address: SocketAddr) -> Vec<Bolt12Offer> {
+    println!("listing offers for node {node_address}");
     let res = reqwest::Client::new()
-        .get(format!("http://{node_address}/listchannels"))
+        .get(format!("http://{node_address}/listoffers"))
         .send()
         .await
         .unwrap();
     _check_response_is_ok(res)
         .await
-        .json::<ListChannelsResponse>()
+        .json::<ListOffersResponse>()
         .await
         .unwrap()
-        .channels
+        .offers
 }
 
 async fn list_payments(node_address: SocketAddr) -> Vec<Payment> {
@@ -776,7 +1255,51 @@ async fn list_payments(node_address: SocketAddr) -> Vec<Payment> {
         .unwrap()
         .payments
 }
+
+async fn list_payments_filtered(
+    node_address: SocketAddr,
+    payload: &ListPaymentsRequest,
+) -> ListPaymentsResponse {
+    println!("listing filtered payments for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/listpayments"))
+        .query(payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListPaymentsResponse>()
+        .await
+        .unwrap()
+}
+
+async fn get_order(node_address: SocketAddr, order_id: &str) -> Order {
+    println!("getting order {order_id} for node {node_address}");
+    let payload = GetOrderRequest {
+        order_id: order_id.to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/getorder"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<GetOrderResponse>()
+        .await
+        .unwrap()
+        .order
+}
+
 async fn get_payment(node_address: SocketAddr, payment_hash: &str) -> Payment {
+    get_payment_details(node_address, payment_hash)
+        .await
+        .payment
+}
+
+async fn get_payment_details(node_address: SocketAddr, payment_hash: &str) -> GetPaymentResponse {
     println!("getting payment for node {node_address}");
     let payload = GetPaymentRequest {
         payment_hash: payment_hash.to_string(),
@@ -792,7 +1315,6 @@ async fn get_payment(node_address: SocketAddr, payment_hash: &str) -> Payment {
         .json::<GetPaymentResponse>()
         .await
         .unwrap()
-        .payment
 }
 
 async fn list_peers(node_address: SocketAddr) -> Vec<Peer> {
@@ -810,6 +1332,21 @@ async fn list_peers(node_address: SocketAddr) -> Vec<Peer> {
         .peers
 }
 
+async fn list_submarine_swaps(node_address: SocketAddr) -> Vec<SubmarineSwap> {
+    println!("listing submarine swaps for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/listsubmarineswaps"))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListSubmarineSwapsResponse>()
+        .await
+        .unwrap()
+        .swaps
+}
+
 async fn list_swaps(node_address: SocketAddr) -> ListSwapsResponse {
     println!("listing swaps for node {node_address}");
     let res = reqwest::Client::new()
@@ -861,79 +1398,187 @@ async fn list_transfers(node_address: SocketAddr, asset_id: &str) -> Vec<Transfe
     println!("listing transfers for asset {asset_id} on node {node_address}");
     let payload = ListTransfersRequest {
         asset_id: asset_id.to_string(),
+        ..Default::default()
+    };
+    list_transfers_filtered(node_address, &payload)
+        .await
+        .transfers
+}
+
+async fn list_transfers_filtered(
+    node_address: SocketAddr,
+    payload: &ListTransfersRequest,
+) -> ListTransfersResponse {
+    println!(
+        "listing filtered transfers for asset {} on node {node_address}",
+        payload.asset_id
+    );
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/listtransfers"))
+        .json(payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListTransfersResponse>()
+        .await
+        .unwrap()
+}
+
+async fn list_unspents(node_address: SocketAddr) -> Vec<Unspent> {
+    println!("listing unspents for node {node_address}");
+    let payload = ListUnspentsRequest { skip_sync: false };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/listunspents"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListUnspentsResponse>()
+        .await
+        .unwrap()
+        .unspents
+}
+
+async fn ln_invoice(
+    node_address: SocketAddr,
+    amt_msat: Option<u64>,
+    asset_id: Option<&str>,
+    asset_amount: Option<u64>,
+    expiry_sec: u32,
+) -> LNInvoiceResponse {
+    println!(
+        "generating invoice for {asset_amount:?} of asset {asset_id:?} for node {node_address}"
+    );
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(amt_msat.unwrap_or(3000000)),
+        min_amt_msat: None,
+        max_amt_msat: None,
+        expiry_sec,
+        asset_id: asset_id.map(|a| a.to_string()),
+        asset_amount,
+        route_hints: None,
+        lsp: None,
+        phantom: None,
+        label: None,
+        fallback_address_type: None,
+        ..Default::default()
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/lninvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<LNInvoiceResponse>()
+        .await
+        .unwrap()
+}
+
+async fn lnurl_pay_callback(
+    node_address: SocketAddr,
+    name: &str,
+    amount: u64,
+    asset_amount: Option<u64>,
+) -> LnurlPayCallbackResponse {
+    println!("requesting LNURL-pay invoice of {amount} msat for {name} from node {node_address}");
+    let payload = LnurlPayCallbackRequest {
+        amount,
+        asset_amount,
     };
     let res = reqwest::Client::new()
-        .post(format!("http://{node_address}/listtransfers"))
-        .json(&payload)
+        .get(format!("http://{node_address}/lnurlp/{name}/callback"))
+        .query(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<LnurlPayCallbackResponse>()
+        .await
+        .unwrap()
+}
+
+async fn lnurl_pay_metadata(node_address: SocketAddr, name: &str) -> LnurlPayMetadataResponse {
+    println!("getting LNURL-pay metadata for {name} from node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/.well-known/lnurlp/{name}"))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<LnurlPayMetadataResponse>()
+        .await
+        .unwrap()
+}
+
+async fn lock(node_address: SocketAddr) {
+    println!("locking node {node_address}");
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/lock"))
         .send()
         .await
         .unwrap();
     _check_response_is_ok(res)
         .await
-        .json::<ListTransfersResponse>()
+        .json::<EmptyResponse>()
         .await
-        .unwrap()
-        .transfers
+        .unwrap();
 }
 
-async fn list_unspents(node_address: SocketAddr) -> Vec<Unspent> {
-    println!("listing unspents for node {node_address}");
-    let payload = ListUnspentsRequest { skip_sync: false };
+async fn lock_utxo(node_address: SocketAddr, outpoint: &str, label: Option<String>) {
+    println!("locking UTXO {outpoint} on node {node_address}");
+    let payload = LockUtxoRequest {
+        outpoint: outpoint.to_string(),
+        label,
+    };
     let res = reqwest::Client::new()
-        .post(format!("http://{node_address}/listunspents"))
+        .post(format!("http://{node_address}/lockutxo"))
         .json(&payload)
         .send()
         .await
         .unwrap();
     _check_response_is_ok(res)
         .await
-        .json::<ListUnspentsResponse>()
+        .json::<EmptyResponse>()
         .await
-        .unwrap()
-        .unspents
+        .unwrap();
 }
 
-async fn ln_invoice(
-    node_address: SocketAddr,
-    amt_msat: Option<u64>,
-    asset_id: Option<&str>,
-    asset_amount: Option<u64>,
-    expiry_sec: u32,
-) -> LNInvoiceResponse {
-    println!(
-        "generating invoice for {asset_amount:?} of asset {asset_id:?} for node {node_address}"
-    );
-    let payload = LNInvoiceRequest {
-        amt_msat: Some(amt_msat.unwrap_or(3000000)),
-        expiry_sec,
-        asset_id: asset_id.map(|a| a.to_string()),
-        asset_amount,
-    };
+async fn log_level(node_address: SocketAddr) -> String {
+    println!("getting log level for node {node_address}");
     let res = reqwest::Client::new()
-        .post(format!("http://{node_address}/lninvoice"))
-        .json(&payload)
+        .get(format!("http://{node_address}/loglevel"))
         .send()
         .await
         .unwrap();
     _check_response_is_ok(res)
         .await
-        .json::<LNInvoiceResponse>()
+        .json::<LogLevelResponse>()
         .await
         .unwrap()
+        .log_level
 }
 
-async fn lock(node_address: SocketAddr) {
-    println!("locking node {node_address}");
+async fn lsp_clients(node_address: SocketAddr) -> Vec<LspClient> {
+    println!("listing LSP clients for node {node_address}");
     let res = reqwest::Client::new()
-        .post(format!("http://{node_address}/lock"))
+        .get(format!("http://{node_address}/lsp/clients"))
         .send()
         .await
         .unwrap();
     _check_response_is_ok(res)
         .await
-        .json::<EmptyResponse>()
+        .json::<LspClientsResponse>()
         .await
-        .unwrap();
+        .unwrap()
+        .clients
 }
 
 async fn maker_execute(
@@ -960,6 +1605,7 @@ async fn maker_execute_raw(
         swapstring,
         payment_secret,
         taker_pubkey,
+        totp_code: None,
     };
     reqwest::Client::new()
         .post(format!("http://{node_address}/makerexecute"))
@@ -1001,57 +1647,92 @@ async fn maker_init(
         .unwrap()
 }
 
-async fn network_info(node_address: SocketAddr) -> NetworkInfoResponse {
-    println!("getting network info for node {node_address}");
+async fn nwc_connect(
+    node_address: SocketAddr,
+    name: &str,
+    relay: &str,
+    budget_msat: Option<u64>,
+    budget_renewal: Option<NwcBudgetRenewal>,
+) -> NwcConnectResponse {
+    println!("creating NWC connection {name} for node {node_address}");
+    let payload = NwcConnectRequest {
+        name: name.to_string(),
+        relay: relay.to_string(),
+        budget_msat,
+        budget_renewal,
+        totp_code: None,
+    };
     let res = reqwest::Client::new()
-        .get(format!("http://{node_address}/networkinfo"))
+        .post(format!("http://{node_address}/nwc/connect"))
+        .json(&payload)
         .send()
         .await
         .unwrap();
     _check_response_is_ok(res)
         .await
-        .json::<NetworkInfoResponse>()
+        .json::<NwcConnectResponse>()
         .await
         .unwrap()
 }
 
-async fn node_info(node_address: SocketAddr) -> NodeInfoResponse {
-    println!("getting node info for {node_address}");
+async fn nwc_connections(node_address: SocketAddr) -> Vec<NwcConnection> {
+    println!("listing NWC connections for node {node_address}");
     let res = reqwest::Client::new()
-        .get(format!("http://{node_address}/nodeinfo"))
+        .get(format!("http://{node_address}/nwc/connections"))
         .send()
         .await
         .unwrap();
     _check_response_is_ok(res)
         .await
-        .json::<NodeInfoResponse>()
+        .json::<NwcConnectionsResponse>()
         .await
         .unwrap()
+        .connections
+}
+
+async fn nwc_revoke(node_address: SocketAddr, connection_id: &str) {
+    println!("revoking NWC connection {connection_id} for node {node_address}");
+    let payload = NwcRevokeRequest {
+        connection_id: connection_id.to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/nwc/revoke"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
 }
 
-async fn open_channel(
+async fn offer(
     node_address: SocketAddr,
-    dest_peer_pubkey: &str,
-    dest_peer_port: Option<u16>,
-    capacity_sat: Option<u64>,
-    push_msat: Option<u64>,
-    asset_amount: Option<u64>,
-    asset_id: Option<&str>,
-) -> Channel {
-    open_channel_with_custom_data(
-        node_address,
-        dest_peer_pubkey,
-        dest_peer_port,
-        capacity_sat,
-        push_msat,
-        asset_amount,
-        asset_id,
-        None,
-        None,
-        None,
-        true,
-    )
-    .await
+    amt_msat: Option<u64>,
+    description: &str,
+    expiry_sec: Option<u32>,
+) -> OfferResponse {
+    println!("creating offer for {amt_msat:?} msat on node {node_address}");
+    let payload = OfferRequest {
+        amt_msat,
+        description: description.to_string(),
+        expiry_sec,
+        asset_id: None,
+        asset_amount: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/offer"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<OfferResponse>()
+        .await
+        .unwrap()
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -1080,6 +1761,7 @@ async fn open_channel_with_retry(
             None,
             None,
             true,
+            true,
         )
         .await;
 
@@ -1102,169 +1784,232 @@ async fn open_channel_with_retry(
     }
 }
 
-#[allow(clippy::too_many_arguments)]
-async fn open_channel_raw(
-    node_address: SocketAddr,
-    dest_peer_pubkey: &str,
-    dest_peer_port: Option<u16>,
-    capacity_sat: Option<u64>,
-    push_msat: Option<u64>,
-    asset_amount: Option<u64>,
-    asset_id: Option<&str>,
-    fee_base_msat: Option<u32>,
-    fee_proportional_millionths: Option<u32>,
-    temporary_channel_id: Option<&str>,
-    with_anchors: bool,
-) -> Result<Channel, reqwest::StatusCode> {
-    println!(
-        "opening channel with {asset_amount:?} of asset {asset_id:?} from node {node_address} \
-              to {dest_peer_pubkey}"
-    );
-
-    let blockcount = get_block_count();
-    let t_0 = OffsetDateTime::now_utc();
-    loop {
-        let net_info = network_info(node_address).await;
-        if net_info.height == blockcount {
-            break;
-        }
-        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
-            panic!("height is not syncing");
-        }
-    }
+async fn ownership_proof(node_address: SocketAddr, nonce: &str) -> OwnershipProofResponse {
+    println!("getting ownership proof with nonce {nonce} for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/ownershipproof"))
+        .query(&[("nonce", nonce)])
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<OwnershipProofResponse>()
+        .await
+        .unwrap()
+}
 
-    let peer_pubkey_and_opt_addr = if let Some(p) = dest_peer_port {
-        format!("{dest_peer_pubkey}@127.0.0.1:{p}")
-    } else {
-        dest_peer_pubkey.to_string()
-    };
-    let payload = OpenChannelRequest {
-        peer_pubkey_and_opt_addr,
-        capacity_sat: capacity_sat.unwrap_or(100_000),
-        push_msat: push_msat.unwrap_or(0),
-        asset_amount,
-        asset_id: asset_id.map(|a| a.to_string()),
-        public: true,
-        with_anchors,
-        fee_base_msat,
-        fee_proportional_millionths,
-        temporary_channel_id: temporary_channel_id.map(|t| t.to_string()),
+async fn pay_offer(
+    node_address: SocketAddr,
+    offer: &str,
+    amt_msat: Option<u64>,
+) -> PayOfferResponse {
+    println!("paying offer {offer} from node {node_address}");
+    let payload = PayOfferRequest {
+        offer: offer.to_string(),
+        amt_msat,
+        payer_note: None,
+        totp_code: None,
     };
     let res = reqwest::Client::new()
-        .post(format!("http://{node_address}/openchannel"))
+        .post(format!("http://{node_address}/payoffer"))
         .json(&payload)
         .send()
         .await
         .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<PayOfferResponse>()
+        .await
+        .unwrap()
+}
+
+async fn peer_suggestions(
+    node_address: SocketAddr,
+    payload: &PeerSuggestionsRequest,
+) -> Vec<PeerSuggestion> {
+    println!("getting peer suggestions for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/peersuggestions"))
+        .query(payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<PeerSuggestionsResponse>()
+        .await
+        .unwrap()
+        .suggestions
+}
 
-    let status = res.status();
-    if !status.is_success() {
-        return Err(status);
-    }
+async fn pending_broadcasts(node_address: SocketAddr) -> Vec<PendingBroadcast> {
+    println!("listing pending broadcasts for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/pendingbroadcasts"))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<PendingBroadcastsResponse>()
+        .await
+        .unwrap()
+        .broadcasts
+}
 
-    res.json::<OpenChannelResponse>().await.unwrap();
+async fn pending_channels(node_address: SocketAddr) -> Vec<PendingChannel> {
+    println!("listing pending channels for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/pendingchannels"))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<PendingChannelsResponse>()
+        .await
+        .unwrap()
+        .channels
+}
 
-    let t_0 = OffsetDateTime::now_utc();
-    let mut channel_id = None;
-    let mut channel_funded = false;
-    while !channel_funded {
-        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-        let channels = list_channels(node_address).await;
-        if let Some(channel) = channels.iter().find(|c| {
-            !c.ready
-                && c.peer_pubkey == dest_peer_pubkey
-                && c.asset_id == asset_id.map(|id| id.to_string())
-                && c.asset_local_amount == asset_amount
-        }) {
-            if channel.funding_txid.is_some() {
-                let txout = _get_txout(channel.funding_txid.as_ref().unwrap());
-                if !txout.is_empty() {
-                    mine_n_blocks(false, 6);
-                    channel_id = Some(channel.channel_id.clone());
-                    channel_funded = true;
-                    continue;
-                }
-            }
-        }
-        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 50.0 {
-            panic!("cannot find funding TX")
-        }
-    }
-    let channel_id = channel_id.unwrap();
+async fn pending_fundings(node_address: SocketAddr) -> Vec<PendingFunding> {
+    println!("listing pending fundings for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/pendingfundings"))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<PendingFundingsResponse>()
+        .await
+        .unwrap()
+        .fundings
+}
 
-    let t_0 = OffsetDateTime::now_utc();
-    loop {
-        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-        let channels = list_channels(node_address).await;
-        let channel = channels
-            .iter()
-            .find(|c| c.channel_id == channel_id)
-            .unwrap();
-        if channel.ready {
-            return Ok(channel.clone());
-        }
-        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
-            panic!("channel is taking too long to be ready")
-        }
-    }
+async fn post_asset_media(node_address: SocketAddr, file_path: &str) -> String {
+    println!("posting asset media on node {node_address}");
+    let file_bytes = tokio::fs::read(file_path).await.unwrap();
+    let form =
+        reqwest::multipart::Form::new().part("file", reqwest::multipart::Part::bytes(file_bytes));
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/postassetmedia"))
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<PostAssetMediaResponse>()
+        .await
+        .unwrap()
+        .digest
 }
 
-#[allow(clippy::too_many_arguments)]
-async fn open_channel_with_custom_data(
+async fn probe_payment(
     node_address: SocketAddr,
-    dest_peer_pubkey: &str,
-    dest_peer_port: Option<u16>,
-    capacity_sat: Option<u64>,
-    push_msat: Option<u64>,
-    asset_amount: Option<u64>,
-    asset_id: Option<&str>,
-    fee_base_msat: Option<u32>,
-    fee_proportional_millionths: Option<u32>,
-    temporary_channel_id: Option<&str>,
-    with_anchors: bool,
-) -> Channel {
-    open_channel_raw(
-        node_address,
-        dest_peer_pubkey,
-        dest_peer_port,
-        capacity_sat,
-        push_msat,
-        asset_amount,
-        asset_id,
-        fee_base_msat,
-        fee_proportional_millionths,
-        temporary_channel_id,
-        with_anchors,
-    )
-    .await
-    .expect("channel opening should succeed")
+    payload: &ProbePaymentRequest,
+) -> ProbePaymentResponse {
+    println!("probing payment from node {node_address}");
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/probepayment"))
+        .json(payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ProbePaymentResponse>()
+        .await
+        .unwrap()
+}
+
+async fn query_routes(
+    node_address: SocketAddr,
+    payload: &QueryRoutesRequest,
+) -> QueryRoutesResponse {
+    println!("querying routes from node {node_address}");
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/queryroutes"))
+        .json(payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<QueryRoutesResponse>()
+        .await
+        .unwrap()
+}
+
+async fn refresh_transfers(node_address: SocketAddr) {
+    println!("refreshing transfers for node {node_address}");
+    let payload = RefreshRequest { skip_sync: false };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/refreshtransfers"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
+}
+
+async fn reject_channel(
+    node_address: SocketAddr,
+    temporary_channel_id: &str,
+    reason: Option<&str>,
+) {
+    println!("rejecting channel {temporary_channel_id} on node {node_address}");
+    let payload = RejectChannelRequest {
+        temporary_channel_id: temporary_channel_id.to_string(),
+        reason: reason.map(|r| r.to_string()),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/rejectchannel"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
 }
 
-async fn post_asset_media(node_address: SocketAddr, file_path: &str) -> String {
-    println!("posting asset media on node {node_address}");
-    let file_bytes = tokio::fs::read(file_path).await.unwrap();
-    let form =
-        reqwest::multipart::Form::new().part("file", reqwest::multipart::Part::bytes(file_bytes));
+async fn reload_config(node_address: SocketAddr) -> ReloadConfigResponse {
+    println!("reloading config for node {node_address}");
     let res = reqwest::Client::new()
-        .post(format!("http://{node_address}/postassetmedia"))
-        .multipart(form)
+        .post(format!("http://{node_address}/reloadconfig"))
         .send()
         .await
         .unwrap();
     _check_response_is_ok(res)
         .await
-        .json::<PostAssetMediaResponse>()
+        .json::<ReloadConfigResponse>()
         .await
         .unwrap()
-        .digest
 }
 
-async fn refresh_transfers(node_address: SocketAddr) {
-    println!("refreshing transfers for node {node_address}");
-    let payload = RefreshRequest { skip_sync: false };
+async fn resolve_htlc(
+    node_address: SocketAddr,
+    htlc_id: &str,
+    resolution: HtlcResolution,
+    payment_preimage: Option<&str>,
+) {
+    println!("resolving intercepted HTLC {htlc_id} with {resolution:?} on node {node_address}");
+    let payload = ResolveHtlcRequest {
+        htlc_id: htlc_id.to_string(),
+        resolution,
+        payment_preimage: payment_preimage.map(|p| p.to_string()),
+    };
     let res = reqwest::Client::new()
-        .post(format!("http://{node_address}/refreshtransfers"))
+        .post(format!("http://{node_address}/resolvehtlc"))
         .json(&payload)
         .send()
         .await
@@ -1295,6 +2040,38 @@ async fn restore(node_address: SocketAddr, backup_path: &str, password: &str) {
         .unwrap();
 }
 
+async fn restore_channels(node_address: SocketAddr, backup_path: &str, password: &str) -> usize {
+    println!("restoring channel backup for node {node_address} from {backup_path}");
+    let payload = RestoreChannelsRequest {
+        backup_path: backup_path.to_string(),
+        password: password.to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/restorechannels"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<RestoreChannelsResponse>()
+        .await
+        .unwrap()
+        .num_channels
+}
+
+async fn retry_transfer(node_address: SocketAddr, idx: i32) {
+    println!("retrying transfer {idx} on node {node_address}");
+    let payload = RetryTransferRequest { idx };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/retrytransfer"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+}
+
 async fn rgb_invoice(
     node_address: SocketAddr,
     asset_id: Option<String>,
@@ -1323,6 +2100,7 @@ async fn rgb_invoice_with_assignment(
         assignment,
         duration_seconds: None,
         witness,
+        label: None,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node_address}/rgbinvoice"))
@@ -1357,6 +2135,10 @@ async fn send_asset(
         min_confirmations: 1,
         transport_endpoints: vec![PROXY_ENDPOINT_LOCAL.to_string()],
         skip_sync: false,
+        broadcast_at: None,
+        hold_for_approval: false,
+        totp_code: None,
+        dry_run: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node_address}/sendasset"))
@@ -1378,6 +2160,11 @@ async fn send_btc(node_address: SocketAddr, amount: u64, address: &str) -> Strin
         address: address.to_string(),
         fee_rate: FEE_RATE,
         skip_sync: false,
+        broadcast_at: None,
+        hold_for_approval: false,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node_address}/sendbtc"))
@@ -1398,6 +2185,15 @@ async fn send_payment_raw(node_address: SocketAddr, invoice: String) -> SendPaym
     let payload = SendPaymentRequest {
         invoice,
         amt_msat: None,
+        timeout_sec: None,
+        max_retries: None,
+        max_fee_msat: None,
+        max_fee_ppm: None,
+        max_cltv_expiry_delta: None,
+        max_parts: None,
+        max_shard_amt_msat: None,
+        totp_code: None,
+        dry_run: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node_address}/sendpayment"))
@@ -1455,6 +2251,99 @@ async fn send_payment_with_status(
     .await
 }
 
+async fn send_to_route(
+    node_address: SocketAddr,
+    invoice: String,
+    hops: Vec<String>,
+) -> SendPaymentResponse {
+    println!("sending LN payment for invoice {invoice} on route {hops:?} from node {node_address}");
+    let payload = SendToRouteRequest {
+        invoice,
+        amt_msat: None,
+        hops,
+        totp_code: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/sendtoroute"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<SendPaymentResponse>()
+        .await
+        .unwrap()
+}
+
+async fn settle_invoice(node_address: SocketAddr, payment_hash: &str, payment_preimage: &str) {
+    println!("settling HODL invoice with payment hash {payment_hash} on node {node_address}");
+    let payload = SettleInvoiceRequest {
+        payment_hash: payment_hash.to_string(),
+        payment_preimage: payment_preimage.to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/settleinvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
+}
+
+async fn settle_invoices(
+    node_address: SocketAddr,
+    invoices: &[(&str, &str)],
+) -> Vec<InvoiceResult> {
+    println!(
+        "settling {} HODL invoices on node {node_address}",
+        invoices.len()
+    );
+    let payload = SettleInvoicesRequest {
+        invoices: invoices
+            .iter()
+            .map(|(payment_hash, payment_preimage)| SettleInvoiceRequest {
+                payment_hash: payment_hash.to_string(),
+                payment_preimage: payment_preimage.to_string(),
+            })
+            .collect(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/settleinvoices"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<SettleInvoicesResponse>()
+        .await
+        .unwrap()
+        .results
+}
+
+async fn settlements(node_address: SocketAddr, period: Option<&str>) -> SettlementsResponse {
+    println!("getting settlements of period {period:?} for node {node_address}");
+    let payload = SettlementsRequest {
+        period: period.map(|p| p.to_string()),
+    };
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/settlements"))
+        .query(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<SettlementsResponse>()
+        .await
+        .unwrap()
+}
+
 async fn shutdown(node_sockets: &[SocketAddr]) {
     // shutdown nodes
     for node_address in node_sockets {
@@ -1489,7 +2378,10 @@ async fn shutdown(node_sockets: &[SocketAddr]) {
 
 async fn taker(node_address: SocketAddr, swapstring: String) -> EmptyResponse {
     println!("taking swap {swapstring} on node {node_address}");
-    let payload = TakerRequest { swapstring };
+    let payload = TakerRequest {
+        swapstring,
+        totp_code: None,
+    };
     let res = reqwest::Client::new()
         .post(format!("http://{node_address}/taker"))
         .json(&payload)
@@ -1503,34 +2395,52 @@ async fn taker(node_address: SocketAddr, swapstring: String) -> EmptyResponse {
         .unwrap()
 }
 
-fn unlock_req(password: &str) -> UnlockRequest {
-    UnlockRequest {
-        password: password.to_string(),
-        bitcoind_rpc_username: s!("user"),
-        bitcoind_rpc_password: s!("password"),
-        bitcoind_rpc_host: s!("localhost"),
-        bitcoind_rpc_port: 18443,
-        indexer_url: Some(ELECTRUM_URL_REGTEST.to_string()),
-        proxy_endpoint: Some(PROXY_ENDPOINT_LOCAL.to_string()),
-        announce_addresses: vec![],
-        announce_alias: Some(s!("RLN_alias")),
-    }
+async fn throttle_gossip(node_address: SocketAddr, peer_pubkey: &str, limit: Option<u64>) {
+    println!("setting gossip limit {limit:?} for peer {peer_pubkey} on node {node_address}");
+    let payload = ThrottleGossipRequest {
+        peer_pubkey: peer_pubkey.to_string(),
+        limit_bytes_per_min: limit,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/throttlegossip"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
 }
 
-async fn unlock_res(node_address: SocketAddr, password: &str) -> Response {
-    println!("unlocking node {node_address}");
-    let payload = unlock_req(password);
-    reqwest::Client::new()
-        .post(format!("http://{node_address}/unlock"))
+async fn transfer_detail(node_address: SocketAddr, idx: i32) -> TransferDetailResponse {
+    println!("getting detail of transfer {idx} on node {node_address}");
+    let payload = TransferDetailRequest { idx };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/transferdetail"))
         .json(&payload)
         .send()
         .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<TransferDetailResponse>()
+        .await
         .unwrap()
 }
 
-async fn unlock(node_address: SocketAddr, password: &str) {
-    println!("unlocking node {node_address}");
-    let res = unlock_res(node_address, password).await;
+async fn unlock_utxo(node_address: SocketAddr, outpoint: &str) {
+    println!("unlocking UTXO {outpoint} on node {node_address}");
+    let payload = UnlockUtxoRequest {
+        outpoint: outpoint.to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/unlockutxo"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
     _check_response_is_ok(res)
         .await
         .json::<EmptyResponse>()
@@ -1538,6 +2448,102 @@ async fn unlock(node_address: SocketAddr, password: &str) {
         .unwrap();
 }
 
+async fn update_channel_acceptor(
+    node_address: SocketAddr,
+    payload: &ChannelAcceptorRequest,
+) -> ChannelAcceptorResponse {
+    println!("updating channel acceptor on node {node_address}");
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/channelacceptor"))
+        .json(payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ChannelAcceptorResponse>()
+        .await
+        .unwrap()
+}
+
+async fn update_channel_policy(
+    node_address: SocketAddr,
+    payload: &UpdateChannelPolicyRequest,
+) -> UpdateChannelPolicyResponse {
+    println!("updating channel policy on node {node_address}");
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/updatechannelpolicy"))
+        .json(payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<UpdateChannelPolicyResponse>()
+        .await
+        .unwrap()
+}
+
+async fn update_lnurl_pay(
+    node_address: SocketAddr,
+    domain: &str,
+    addresses: Vec<LightningAddress>,
+) -> LnurlPayResponse {
+    println!("setting LNURL-pay addresses for node {node_address}");
+    let payload = LnurlPayRequest {
+        domain: domain.to_string(),
+        addresses,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/lnurlpay"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<LnurlPayResponse>()
+        .await
+        .unwrap()
+}
+
+async fn update_log_level(node_address: SocketAddr, log_level: &str) -> String {
+    println!("setting log level {log_level} for node {node_address}");
+    let payload = LogLevelRequest {
+        log_level: log_level.to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/loglevel"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<LogLevelResponse>()
+        .await
+        .unwrap()
+        .log_level
+}
+
+async fn update_lsp_config(
+    node_address: SocketAddr,
+    payload: &LspConfigRequest,
+) -> LspConfigResponse {
+    println!("updating LSP config on node {node_address}");
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/lsp/config"))
+        .json(payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<LspConfigResponse>()
+        .await
+        .unwrap()
+}
+
 async fn wait_for_balance(node_address: SocketAddr, asset_id: &str, expected_balance: u64) {
     println!(
         "waiting for balance of asset {asset_id} to become {expected_balance} \
@@ -1575,21 +2581,39 @@ async fn wait_for_ln_balance(node_address: SocketAddr, asset_id: &str, expected_
     }
 }
 
-async fn wait_for_usable_channels(node_address: SocketAddr, expected_num_usable_channels: usize) {
+async fn wait_for_chain_events(
+    node_address: SocketAddr,
+    subscription_id: &str,
+    expected_num_events: usize,
+) -> Vec<ChainEvent> {
     let t_0 = OffsetDateTime::now_utc();
     loop {
-        let node_info = node_info(node_address).await;
-        let num_usable_channels = node_info.num_usable_channels;
-        if num_usable_channels == expected_num_usable_channels {
-            break;
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let events = chain_events(node_address, subscription_id).await;
+        if events.len() >= expected_num_events {
+            return events;
         }
-        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
-            panic!(
-                "num of usable channels ({num_usable_channels:?}) is not becoming the expected \
-                one ({expected_num_usable_channels:?})"
-            );
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("chain subscription {subscription_id} didn't get {expected_num_events} events")
         }
+    }
+}
+
+async fn wait_for_invoice_status(
+    node_address: SocketAddr,
+    invoice: &str,
+    expected_status: InvoiceStatus,
+) {
+    println!("waiting for invoice {invoice} to become {expected_status:?} on node {node_address}");
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
         tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        if invoice_status(node_address, invoice).await == expected_status {
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 40.0 {
+            panic!("invoice is not becoming {expected_status:?}")
+        }
     }
 }
 
@@ -1615,6 +2639,43 @@ async fn wait_for_ln_payment(
     }
 }
 
+async fn wait_for_order_status(
+    node_address: SocketAddr,
+    order_id: &str,
+    expected_status: OrderStatus,
+) -> Order {
+    println!("waiting for order {order_id} to become {expected_status:?} on node {node_address}");
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let order = get_order(node_address, order_id).await;
+        if order.status == expected_status {
+            return order;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 40.0 {
+            panic!("order {order_id} didn't become {expected_status:?}")
+        }
+    }
+}
+
+async fn wait_for_payment_parts(
+    node_address: SocketAddr,
+    payment_hash: &str,
+    amt_msat: u64,
+) -> GetPaymentResponse {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let details = get_payment_details(node_address, payment_hash).await;
+        if details.fees.iter().map(|f| f.amt_msat).sum::<u64>() == amt_msat {
+            return details;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("payment parts have not been recorded")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
 async fn wait_for_swap_status(
     node_address: SocketAddr,
     payment_hash: &str,
@@ -1644,122 +2705,6 @@ async fn wait_for_swap_status(
     }
 }
 
-#[derive(Clone, Debug)]
-struct Miner {
-    no_mine_count: u32,
-}
-
-impl Miner {
-    fn mine(&self, num_blocks: u16) -> bool {
-        if self.no_mine_count > 0 {
-            return false;
-        }
-        let status = Command::new("docker")
-            .stdin(Stdio::null())
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .arg("compose")
-            .args(_bitcoin_cli())
-            .arg("-rpcwallet=miner")
-            .arg("-generate")
-            .arg(num_blocks.to_string())
-            .status()
-            .expect("failed to mine");
-        assert!(status.success());
-        true
-    }
-
-    fn stop_mining(&mut self) {
-        self.no_mine_count += 1;
-    }
-
-    fn resume_mining(&mut self) {
-        if self.no_mine_count > 0 {
-            self.no_mine_count -= 1;
-        }
-    }
-}
-
-fn mine(resume: bool) {
-    mine_n_blocks(resume, 1)
-}
-
-fn mine_n_blocks(resume: bool, num_blocks: u16) {
-    let t_0 = OffsetDateTime::now_utc();
-    if resume {
-        resume_mining();
-    }
-    let mut last_result = false;
-    while !last_result {
-        let miner = MINER.read();
-        last_result = miner
-            .as_ref()
-            .expect("MINER has been initialized")
-            .mine(num_blocks);
-        drop(miner);
-        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 120.0 {
-            eprintln!("forcibly breaking mining wait");
-            resume_mining();
-        }
-        if !last_result {
-            std::thread::sleep(std::time::Duration::from_millis(500));
-        }
-    }
-    wait_electrs_sync();
-}
-
-fn stop_mining() {
-    MINER
-        .write()
-        .expect("MINER has been initialized")
-        .stop_mining()
-}
-
-fn resume_mining() {
-    MINER
-        .write()
-        .expect("MINER has been initialized")
-        .resume_mining()
-}
-
-fn get_block_count() -> u32 {
-    let output = Command::new("docker")
-        .stdin(Stdio::null())
-        .stderr(Stdio::null())
-        .arg("compose")
-        .args(_bitcoin_cli())
-        .arg("getblockcount")
-        .output()
-        .expect("failed to call getblockcount");
-    assert!(output.status.success());
-    let blockcount_str =
-        std::str::from_utf8(&output.stdout).expect("could not parse blockcount output");
-    blockcount_str
-        .trim()
-        .parse::<u32>()
-        .expect("could not parse blockcount")
-}
-
-fn wait_electrs_sync() {
-    let t_0 = OffsetDateTime::now_utc();
-    let blockcount = get_block_count();
-    loop {
-        std::thread::sleep(std::time::Duration::from_millis(100));
-        let mut all_synced = true;
-        let electrum =
-            electrum_client::Client::new(ELECTRUM_URL).expect("cannot get electrum client");
-        if electrum.block_header(blockcount as usize).is_err() {
-            all_synced = false;
-        }
-        if all_synced {
-            break;
-        };
-        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
-            panic!("electrs not syncing with bitcoind");
-        }
-    }
-}
-
 pub(crate) fn initialize() {
     INIT.call_once(|| {
         if std::env::var("SKIP_INIT").is_ok() {
@@ -1792,33 +2737,132 @@ pub fn mock_fee(fee: u32) -> u32 {
     }
 }
 
+mod abandon_payment;
+mod accounting_export;
+mod address_gap_limit;
+mod anchor_reserve;
+mod api_version;
+mod asset_audit;
+mod asset_htlc_min;
+mod async_payments;
+mod audit;
+mod audit_snapshot;
 mod authentication;
+mod auto_backup;
 mod backup_and_restore;
+mod backup_export_import;
+mod backup_restore_channels;
+mod balance_sheet;
+mod bump_close_fee;
+mod burn_reissue;
+mod chain_subscriptions;
+mod channel_acceptor;
+mod channel_depths;
+mod channel_events;
+mod channel_limits;
+mod channel_status;
+mod channel_type;
 mod close_coop_nobtc_acceptor;
+mod close_coop_options;
 mod close_coop_other_side;
 mod close_coop_standard;
 mod close_coop_vanilla;
 mod close_coop_zero_balance;
+mod close_detail;
 mod close_force_nobtc_acceptor;
 mod close_force_other_side;
 mod close_force_standard;
+mod coin_selection;
+mod compact_storage;
 mod concurrent_btc_payments;
+mod concurrent_ln_payments;
 mod concurrent_openchannel;
+mod concurrent_rgb_spends;
+mod config_file;
+mod connect_peer;
+mod consignment_export_import;
+mod deferred_broadcast;
+mod descriptors;
+mod dry_run;
+mod emergency_kit;
+mod encrypted_storage;
+mod escrow;
+mod external_funding;
 mod fail_transfers;
+#[cfg(feature = "failure-injection")]
+mod failure_injection;
+mod fallback_indexers;
+mod fallback_proxies;
+mod fee_bump;
+mod forwarding_history;
 mod getchannelid;
+mod gossip_bandwidth;
+mod grpc;
+mod hedging;
+mod hodl_invoice;
+mod hooks;
 mod htlc_amount_checks;
+mod htlc_interceptor;
+mod htlcs;
+mod import_mnemonic;
 mod invoice;
+mod invoice_amount_bounds;
+mod invoice_fallback;
+mod invoice_pruning;
+mod invoice_quotas;
 mod issue;
+mod journal;
+mod lease;
+mod list_payments;
+mod list_transfers;
+mod lnurl_pay;
 mod lock_unlock_changepassword;
+mod log_level;
+mod lsp;
+mod lsp_server;
+mod migrate_storage_dir;
 mod multi_hop;
 mod multi_open_close;
+mod multi_part_payment;
+mod multisig_funding;
+mod network;
+mod network_graph;
+mod node_alias;
+mod notifications;
+mod nwc;
+mod offers;
 mod open_after_double_send;
+mod openapi;
 mod openchannel_fail;
 mod openchannel_optional_addr;
+mod orders;
+mod ownership_proof;
 mod payment;
+mod payment_limits;
+mod peer_compat;
+mod peer_filter;
+mod peer_metadata;
+mod peer_suggestions;
+mod phantom_invoice;
+mod probe_payment;
+mod psbt;
+mod read_after_write;
+mod rebalance;
 mod refuse_high_fees;
+mod reload_config;
 mod restart;
+mod retry_transfer;
+mod reverse_proxy;
+mod route_hints;
+mod send_btc_many;
 mod send_receive;
+mod send_to_route;
+mod settlements;
+mod shutdown_drain;
+#[cfg(feature = "simulation")]
+mod simulation;
+mod sqlite_store;
+mod submarine_swaps;
 mod swap_assets_liquidity_both_ways;
 mod swap_reverse_same_channel;
 mod swap_roundtrip_assets;
@@ -1836,5 +2880,16 @@ mod swap_roundtrip_multihop_asset_asset;
 mod swap_roundtrip_multihop_buy;
 mod swap_roundtrip_multihop_sell;
 mod swap_roundtrip_sell;
+mod sweeps;
+mod sync_status;
+mod tls;
+mod token_caveats;
+mod tor_onion_service;
+mod tor_proxy;
+mod totp;
+mod update_channel_policy;
 mod upload_asset_media;
+mod utxo_locks;
+mod utxo_pool;
 mod vanilla_payment_on_rgb_channel;
+mod watch_only;
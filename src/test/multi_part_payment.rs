@@ -0,0 +1,141 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/multi_part_payment/";
+
+async fn send_payment_in_parts(
+    node_address: SocketAddr,
+    invoice: String,
+    max_parts: Option<u8>,
+    max_shard_amt_msat: Option<u64>,
+) -> Response {
+    let payload = SendPaymentRequest {
+        invoice,
+        amt_msat: None,
+        timeout_sec: None,
+        max_retries: None,
+        max_fee_msat: None,
+        max_fee_ppm: None,
+        max_cltv_expiry_delta: None,
+        max_parts,
+        max_shard_amt_msat,
+        totp_code: None,
+        dry_run: false,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/sendpayment"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn multi_part_payment() {
+    initialize();
+
+    let test_dir_base = format!("{TEST_DIR_BASE}multi_part_payment/");
+    let test_dir_node1 = format!("{test_dir_base}node1");
+    let test_dir_node2 = format!("{test_dir_base}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    for _ in 0..2 {
+        open_channel(
+            node1_addr,
+            &node2_pubkey,
+            Some(NODE2_PEER_PORT),
+            Some(100_000),
+            None,
+            None,
+            None,
+        )
+        .await;
+    }
+
+    // a payment larger than any channel is split by the router
+    let amt_msat = 150_000_000;
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(node2_addr, Some(amt_msat), None, None, 900).await;
+    let res = send_payment_in_parts(node1_addr, invoice, Some(4), None).await;
+    let payment_hash = _check_response_is_ok(res)
+        .await
+        .json::<SendPaymentResponse>()
+        .await
+        .unwrap()
+        .payment_hash
+        .unwrap();
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Succeeded).await;
+    let parts = wait_for_payment_parts(node1_addr, &payment_hash, amt_msat)
+        .await
+        .parts;
+    let succeeded: Vec<_> = parts
+        .iter()
+        .filter(|p| p.status == HTLCStatus::Succeeded)
+        .collect();
+    assert!(succeeded.len() > 1);
+    assert_eq!(succeeded.iter().map(|p| p.amt_msat).sum::<u64>(), amt_msat);
+
+    // a max shard amount splits the payment in equal parts
+    let amt_msat = 30_000_000;
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(node2_addr, Some(amt_msat), None, None, 900).await;
+    let res = send_payment_in_parts(node1_addr, invoice, None, Some(10_000_000)).await;
+    let payment_hash = _check_response_is_ok(res)
+        .await
+        .json::<SendPaymentResponse>()
+        .await
+        .unwrap()
+        .payment_hash
+        .unwrap();
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Succeeded).await;
+    let details = wait_for_payment_parts(node1_addr, &payment_hash, amt_msat).await;
+    assert_eq!(details.parts.len(), 3);
+    assert!(details
+        .parts
+        .iter()
+        .all(|p| p.status == HTLCStatus::Succeeded && p.amt_msat == 10_000_000));
+    // direct channels charge no routing fees
+    assert_eq!(details.fees.len(), 1);
+    assert_eq!(details.fees[0].asset_id, None);
+    assert_eq!(details.fees[0].amt_msat, amt_msat);
+    assert_eq!(details.fees[0].fee_msat, 0);
+    assert_eq!(details.fees[0].num_parts, 3);
+    // each part reports the route it took
+    assert!(details.parts.iter().all(|p| p.route.len() == 1
+        && p.route[0].pubkey == node2_pubkey
+        && p.route[0].fee_msat == 0));
+    // the preimage is known to both sides once the payment has been claimed
+    let preimage = details.preimage.unwrap();
+    assert_eq!(preimage.len(), 64);
+    assert!(details.resolved_at.unwrap() >= details.payment.created_at);
+    let details_2 = get_payment_details(node2_addr, &payment_hash).await;
+    assert_eq!(details_2.preimage, Some(preimage));
+    assert!(details_2.resolved_at.is_some());
+
+    // failures
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(node2_addr, Some(amt_msat), None, None, 900).await;
+    let res = send_payment_in_parts(node1_addr, invoice.clone(), Some(0), None).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "max_parts must be between 1 and 16",
+        "InvalidPaymentLimits",
+    )
+    .await;
+    let res = send_payment_in_parts(node1_addr, invoice, Some(2), Some(10_000_000)).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "30000000msat cannot be split in 2 parts of at most 10000000msat",
+        "InvalidPaymentLimits",
+    )
+    .await;
+}
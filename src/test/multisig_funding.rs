@@ -0,0 +1,224 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/multisig_funding/";
+
+// 2-of-3 multisig of the pubkeys G, 2G and 3G
+const WITNESS_SCRIPT: &str =
+    "52210279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f8179821\
+02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee52102f9308a019258c31049344f85f8\
+9d5229b531c845836f99b08601f113bce036f953ae";
+const TREASURY_OUTPOINT: &str =
+    "1111111111111111111111111111111111111111111111111111111111111111:0";
+
+async fn open_channel_multisig_res(
+    node_address: SocketAddr,
+    dest_peer_pubkey: &str,
+    multisig_funding: MultisigFunding,
+    asset: Option<(&str, u64)>,
+) -> Response {
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_opt_addr: format!("{dest_peer_pubkey}@127.0.0.1:{NODE2_PEER_PORT}"),
+        capacity_sat: 100_000,
+        push_msat: 0,
+        asset_amount: asset.map(|(_, amt)| amt),
+        asset_id: asset.map(|(id, _)| id.to_string()),
+        public: true,
+        with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        temporary_channel_id: None,
+        multisig_funding: Some(multisig_funding),
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/openchannel"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+fn treasury_funding(
+    amount_sat: u64,
+    witness_script: &str,
+    change_address: &str,
+) -> MultisigFunding {
+    MultisigFunding {
+        inputs: vec![MultisigInput {
+            outpoint: s!(TREASURY_OUTPOINT),
+            amount_sat,
+            witness_script: witness_script.to_string(),
+        }],
+        change_address: change_address.to_string(),
+        fee_rate: 2,
+        timeout_sec: None,
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn multisig_funding() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let change_address = address(node1_addr).await;
+    let btc_balance_before = btc_balance(node1_addr).await.vanilla.spendable;
+
+    let res = open_channel_multisig_res(
+        node1_addr,
+        &node2_pubkey,
+        treasury_funding(150_000, WITNESS_SCRIPT, &change_address),
+        None,
+    )
+    .await;
+    let temporary_channel_id = _check_response_is_ok(res)
+        .await
+        .json::<OpenChannelResponse>()
+        .await
+        .unwrap()
+        .temporary_channel_id;
+
+    // the funding PSBT is created once the peer accepts the channel
+    let t_0 = OffsetDateTime::now_utc();
+    let funding = loop {
+        let fundings = pending_fundings(node1_addr).await;
+        assert_eq!(fundings.len(), 1);
+        if fundings[0].unsigned_psbt.is_some() {
+            break fundings.into_iter().next().unwrap();
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("funding PSBT has not been created")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+    assert_eq!(funding.temporary_channel_id, temporary_channel_id);
+    assert_eq!(funding.peer_pubkey, node2_pubkey);
+    assert_eq!(funding.capacity_sat, 100_000);
+    assert!(funding.fee_sat > 0);
+    assert_eq!(funding.expires_at - funding.created_at, 1800);
+    let events = channel_events(node1_addr, &temporary_channel_id)
+        .await
+        .events;
+    assert!(events
+        .iter()
+        .any(|e| e.kind == ChannelEventKind::FundingPsbtCreated));
+
+    // the node wallet is not used to fund the channel
+    assert_eq!(
+        btc_balance(node1_addr).await.vanilla.spendable,
+        btc_balance_before
+    );
+
+    // the PSBT still lacks the treasury signatures
+    let payload = FundChannelCompleteRequest {
+        temporary_channel_id: temporary_channel_id.clone(),
+        signed_psbt: funding.unsigned_psbt.unwrap(),
+        totp_code: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/fundchannelcomplete"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid funding PSBT: input 0 has 0 of the 2 required signatures",
+        "InvalidFundingPsbt",
+    )
+    .await;
+
+    fund_channel_abort(node1_addr, &temporary_channel_id).await;
+    assert!(pending_fundings(node1_addr).await.is_empty());
+    let events = channel_events(node1_addr, &temporary_channel_id)
+        .await
+        .events;
+    assert!(events
+        .iter()
+        .any(|e| e.kind == ChannelEventKind::FundingFailed));
+
+    let payload = FundChannelAbortRequest {
+        temporary_channel_id: temporary_channel_id.clone(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/fundchannelabort"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown temporary channel ID",
+        "UnknownTemporaryChannelId",
+    )
+    .await;
+
+    // failures
+    let res = open_channel_multisig_res(
+        node1_addr,
+        &node2_pubkey,
+        treasury_funding(150_000, WITNESS_SCRIPT, &change_address),
+        Some((&asset_id, 100)),
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid multisig funding: RGB channels cannot be funded by a multisig treasury",
+        "InvalidMultisigFunding",
+    )
+    .await;
+
+    let res = open_channel_multisig_res(
+        node1_addr,
+        &node2_pubkey,
+        treasury_funding(150_000, "0014deadbeef", &change_address),
+        None,
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        &format!(
+            "Invalid multisig funding: witness script of {TREASURY_OUTPOINT} is not a 2-of-3 multisig"
+        ),
+        "InvalidMultisigFunding",
+    )
+    .await;
+
+    let res = open_channel_multisig_res(
+        node1_addr,
+        &node2_pubkey,
+        treasury_funding(100_000, WITNESS_SCRIPT, &change_address),
+        None,
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid multisig funding: inputs amount (100000 sats) is lower than the capacity plus fees",
+        "InvalidMultisigFunding",
+    )
+    .await;
+    assert!(pending_fundings(node1_addr).await.is_empty());
+}
@@ -0,0 +1,153 @@
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::secp256k1::{Secp256k1, SecretKey};
+use lightning::rgb_utils::BITCOIN_NETWORK_FNAME;
+use lightning_invoice::{Currency, InvoiceBuilder, PaymentSecret};
+
+use crate::error::AppError;
+use crate::routes::BitcoinNetwork;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/network/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn network() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let _ = std::fs::remove_dir_all(&test_dir_node1);
+    let _ = std::fs::remove_dir_all(&test_dir_node2);
+
+    // the network selected at init needs to match the one of the daemon
+    let node1_addr = start_daemon(&test_dir_node1, NODE1_PEER_PORT, None).await;
+    let password = "a_password";
+    let payload = InitRequest {
+        password: password.to_string(),
+        network: Some(BitcoinNetwork::Signet),
+        mnemonic: None,
+        passphrase: None,
+        account_index: None,
+        ldk_keys_only: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/init"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "the node has been started on",
+        "InvalidNetwork",
+    )
+    .await;
+    let payload = InitRequest {
+        password: password.to_string(),
+        network: Some(BitcoinNetwork::Regtest),
+        mnemonic: None,
+        passphrase: None,
+        account_index: None,
+        ldk_keys_only: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/init"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<InitResponse>()
+        .await
+        .unwrap();
+    unlock(node1_addr, password).await;
+
+    let node_info = node_info(node1_addr).await;
+    assert_eq!(node_info.network, BitcoinNetwork::Regtest);
+
+    // addresses and invoices for other networks are refused
+    let payload = SendBtcRequest {
+        amount: 1000,
+        address: s!("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq"),
+        fee_rate: FEE_RATE,
+        skip_sync: false,
+        broadcast_at: None,
+        hold_for_approval: false,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/sendbtc"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "address is not valid on",
+        "InvalidNetwork",
+    )
+    .await;
+    let node_key = SecretKey::from_slice(&[42; 32]).unwrap();
+    let mainnet_invoice = InvoiceBuilder::new(Currency::Bitcoin)
+        .description(s!("mainnet"))
+        .payment_hash(sha256::Hash::from_slice(&[1; 32]).unwrap())
+        .payment_secret(PaymentSecret([2; 32]))
+        .current_timestamp()
+        .min_final_cltv_expiry_delta(144)
+        .amount_milli_satoshis(3000000)
+        .build_signed(|hash| Secp256k1::new().sign_ecdsa_recoverable(hash, &node_key))
+        .unwrap();
+    let payload = SendPaymentRequest {
+        invoice: mainnet_invoice.to_string(),
+        amt_msat: None,
+        timeout_sec: None,
+        max_retries: None,
+        max_fee_msat: None,
+        max_fee_ppm: None,
+        max_cltv_expiry_delta: None,
+        max_parts: None,
+        max_shard_amt_msat: None,
+        totp_code: None,
+        dry_run: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/sendpayment"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "invoice is not valid on",
+        "InvalidNetwork",
+    )
+    .await;
+
+    // a node initialized on another network cannot be started
+    std::fs::create_dir_all(&test_dir_node2).unwrap();
+    std::fs::write(
+        Path::new(&test_dir_node2).join(BITCOIN_NETWORK_FNAME),
+        rgb_lib::BitcoinNetwork::Signet.to_string(),
+    )
+    .unwrap();
+    let args = UserArgs {
+        storage_dir_path: test_dir_node2.into(),
+        ldk_peer_listening_port: NODE2_PEER_PORT,
+        ..Default::default()
+    };
+    assert!(matches!(
+        app(args).await,
+        Err(AppError::NetworkMismatch(
+            _,
+            rgb_lib::BitcoinNetwork::Regtest
+        ))
+    ));
+}
@@ -0,0 +1,141 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/network_graph/";
+
+async fn get_chan_info_res(node_address: SocketAddr, chan_id: u64) -> Response {
+    reqwest::Client::new()
+        .get(format!(
+            "http://{node_address}/getchaninfo?chan_id={chan_id}"
+        ))
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn get_node_info_res(node_address: SocketAddr, pubkey: &str) -> Response {
+    reqwest::Client::new()
+        .get(format!("http://{node_address}/getnodeinfo?pubkey={pubkey}"))
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn network_graph() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node3_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+
+    let net_info = network_info(node1_addr).await;
+    assert!(net_info.synced_to_chain);
+    assert_eq!(net_info.num_channels, 0);
+
+    let res = get_node_info_res(node1_addr, "invalid").await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid pubkey",
+        "InvalidPubkey",
+    )
+    .await;
+    let res = get_node_info_res(node1_addr, &node3_pubkey).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown node",
+        "UnknownNode",
+    )
+    .await;
+    let res = get_chan_info_res(node1_addr, 42).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown channel ID",
+        "UnknownChannelId",
+    )
+    .await;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+    let channel = open_channel(
+        node3_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(200_000),
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // wait for the channel between node2 and node3 to be gossiped to node1
+    let chan_id = list_channels(node3_addr)
+        .await
+        .into_iter()
+        .find(|c| c.channel_id == channel.channel_id)
+        .unwrap()
+        .short_channel_id
+        .unwrap();
+    let t_0 = OffsetDateTime::now_utc();
+    let chan_info = loop {
+        let res = get_chan_info_res(node1_addr, chan_id).await;
+        if res.status() == reqwest::StatusCode::OK {
+            let chan_info = res.json::<GetChanInfoResponse>().await.unwrap();
+            if chan_info.node1_policy.is_some() && chan_info.node2_policy.is_some() {
+                break chan_info;
+            }
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 60.0 {
+            panic!("channel has not been gossiped")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+    assert_eq!(chan_info.chan_id, chan_id);
+    assert_eq!(chan_info.capacity_sat, Some(200_000));
+    let mut chan_nodes = [chan_info.node1_pubkey, chan_info.node2_pubkey];
+    chan_nodes.sort();
+    let mut expected_nodes = [node2_pubkey.clone(), node3_pubkey.clone()];
+    expected_nodes.sort();
+    assert_eq!(chan_nodes, expected_nodes);
+    assert!(chan_info.node1_policy.unwrap().enabled);
+
+    let res = get_node_info_res(node1_addr, &node3_pubkey).await;
+    let node3_info = _check_response_is_ok(res)
+        .await
+        .json::<GetNodeInfoResponse>()
+        .await
+        .unwrap();
+    assert_eq!(node3_info.pubkey, node3_pubkey);
+    assert_eq!(node3_info.num_channels, 1);
+    assert_eq!(node3_info.total_capacity_sat, 200_000);
+    assert_eq!(node3_info.chan_ids, vec![chan_id]);
+
+    let net_info = network_info(node1_addr).await;
+    assert_eq!(net_info.num_nodes, 3);
+    assert_eq!(net_info.num_channels, 2);
+    assert!(net_info.num_channels_with_updates >= 1);
+    assert!(net_info.total_capacity_sat >= 200_000);
+
+    shutdown(&[node1_addr, node2_addr, node3_addr]).await;
+}
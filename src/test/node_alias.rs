@@ -0,0 +1,109 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/node_alias/";
+
+async fn set_alias_res(
+    node_address: SocketAddr,
+    alias: Option<&str>,
+    color: Option<&str>,
+) -> Response {
+    let payload = SetAliasRequest {
+        alias: alias.map(|a| a.to_string()),
+        color: color.map(|c| c.to_string()),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/setalias"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn node_alias() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let node1_args = || UserArgs {
+        storage_dir_path: test_dir_node1.clone().into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        announce_alias: Some(s!("cli_alias")),
+        announce_color: Some([0x33, 0x99, 0xff]),
+        ..Default::default()
+    };
+    let (node1_addr, _) = start_node_with_args(node1_args(), false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    // the unlock request alias prevails over the option one
+    let node1_info = node_info(node1_addr).await;
+    assert_eq!(node1_info.alias, Some(s!("RLN_alias")));
+    assert_eq!(node1_info.color, Some(s!("3399ff")));
+
+    check_response_is_nok(
+        set_alias_res(node1_addr, Some(&"a".repeat(33)), None).await,
+        reqwest::StatusCode::BAD_REQUEST,
+        "cannot be longer than 32 bytes",
+        "InvalidAnnounceAlias",
+    )
+    .await;
+    check_response_is_nok(
+        set_alias_res(node1_addr, None, Some("zz0000")).await,
+        reqwest::StatusCode::BAD_REQUEST,
+        "expected 3 hex-encoded bytes",
+        "InvalidAnnounceColor",
+    )
+    .await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    let node1_pubkey = node1_info.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // the new identity is announced right away
+    _check_response_is_ok(set_alias_res(node1_addr, Some("my node"), Some("#ff8800")).await).await;
+    let node1_info = node_info(node1_addr).await;
+    assert_eq!(node1_info.alias, Some(s!("my node")));
+    assert_eq!(node1_info.color, Some(s!("ff8800")));
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let res = reqwest::Client::new()
+            .get(format!(
+                "http://{node2_addr}/getnodeinfo?pubkey={node1_pubkey}"
+            ))
+            .send()
+            .await
+            .unwrap();
+        if res.status() == reqwest::StatusCode::OK {
+            let info = res.json::<GetNodeInfoResponse>().await.unwrap();
+            if info.alias.as_deref() == Some("my node") {
+                assert_eq!(info.color, Some(s!("ff8800")));
+                break;
+            }
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 60.0 {
+            panic!("node announcement has not been received")
+        }
+        // the announcement may have been sent before the channel one
+        let _ = set_alias_res(node1_addr, None, None).await;
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+
+    // the identity set at runtime survives restarts
+    shutdown(&[node1_addr]).await;
+    let (node1_addr, _) = start_node_with_args(node1_args(), true).await;
+    let node1_info = node_info(node1_addr).await;
+    assert_eq!(node1_info.alias, Some(s!("my node")));
+    assert_eq!(node1_info.color, Some(s!("ff8800")));
+}
@@ -0,0 +1,60 @@
+use zeromq::{Socket, SocketRecv, SubSocket};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/notifications/";
+const ZMQ_ENDPOINT: &str = "tcp://127.0.0.1:28400";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn notifications() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+
+    let args = UserArgs {
+        storage_dir_path: test_dir_node1.clone().into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        notify_zmq_endpoint: Some(ZMQ_ENDPOINT.to_string()),
+        ..Default::default()
+    };
+    let (node1_addr, _) = start_node_with_args(args, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    let mut subscriber = SubSocket::new();
+    subscriber.connect(ZMQ_ENDPOINT).await.unwrap();
+    subscriber.subscribe("invoice_settled").await.unwrap();
+
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    open_channel(
+        node2_addr,
+        &node1_pubkey,
+        Some(NODE1_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // the event is published with its name as topic, followed by the hook payload
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(node1_addr, Some(3000000), None, None, 900).await;
+    let decoded = decode_ln_invoice(node1_addr, &invoice).await;
+    send_payment(node2_addr, invoice).await;
+    let msg = tokio::time::timeout(std::time::Duration::from_secs(30), subscriber.recv())
+        .await
+        .expect("notification has not been published")
+        .unwrap();
+    assert_eq!(msg.len(), 2);
+    assert_eq!(msg.get(0).unwrap().as_ref(), b"invoice_settled");
+    let event: serde_json::Value = serde_json::from_slice(msg.get(1).unwrap()).unwrap();
+    assert_eq!(event["event"], "invoice_settled");
+    assert_eq!(event["payment_hash"], decoded.payment_hash);
+    assert_eq!(event["amt_msat"], 3000000);
+    assert!(event["timestamp"].as_u64().unwrap() > 0);
+}
@@ -0,0 +1,338 @@
+use bitcoin::key::Keypair;
+use bitcoin::secp256k1::{Secp256k1, SecretKey};
+use futures::{SinkExt, StreamExt};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::nwc::{nip04_decrypt, nip04_encrypt, NostrEvent, NwcResponse};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/nwc/";
+
+#[derive(Default)]
+struct Relay {
+    events: Vec<NostrEvent>,
+    subscriptions: Vec<(String, Value, mpsc::UnboundedSender<String>)>,
+}
+
+fn filter_matches(filter: &Value, event: &NostrEvent) -> bool {
+    let kind_matches = filter["kinds"]
+        .as_array()
+        .is_none_or(|kinds| kinds.iter().any(|k| k.as_u64() == Some(event.kind as u64)));
+    let p_matches = filter["#p"]
+        .as_array()
+        .is_none_or(|pubkeys| pubkeys.iter().any(|p| p.as_str() == event.tag("p")));
+    kind_matches && p_matches
+}
+
+/// Serve a relay client, storing the published events and forwarding them to the subscriptions
+async fn serve_relay_client(stream: TcpStream, relay: Arc<Mutex<Relay>>) {
+    let ws_stream = tokio_tungstenite::accept_async(stream).await.unwrap();
+    let (mut sink, mut stream) = ws_stream.split();
+    let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+    loop {
+        tokio::select! {
+            Some(msg) = rx.recv() => {
+                if sink.send(WsMessage::Text(msg)).await.is_err() {
+                    return;
+                }
+            }
+            msg = stream.next() => {
+                let Some(Ok(WsMessage::Text(text))) = msg else {
+                    return;
+                };
+                let msg: Vec<Value> = serde_json::from_str(&text).unwrap();
+                let mut state = relay.lock().unwrap();
+                match msg[0].as_str().unwrap() {
+                    "REQ" => {
+                        let sub_id = msg[1].as_str().unwrap().to_string();
+                        let filter = msg[2].clone();
+                        for event in state.events.iter().filter(|e| filter_matches(&filter, e)) {
+                            let _ = tx.send(serde_json::json!(["EVENT", sub_id, event]).to_string());
+                        }
+                        state
+                            .subscriptions
+                            .retain(|(id, _, sub_tx)| !(id == &sub_id && sub_tx.same_channel(&tx)));
+                        state.subscriptions.push((sub_id, filter, tx.clone()));
+                    }
+                    "EVENT" => {
+                        let event: NostrEvent = serde_json::from_value(msg[1].clone()).unwrap();
+                        assert!(event.verify());
+                        for (sub_id, filter, sub_tx) in &state.subscriptions {
+                            if filter_matches(filter, &event) {
+                                let _ = sub_tx
+                                    .send(serde_json::json!(["EVENT", sub_id, event]).to_string());
+                            }
+                        }
+                        let _ = tx.send(serde_json::json!(["OK", event.id, true, ""]).to_string());
+                        state.events.push(event);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+async fn start_relay() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let relay_addr = listener.local_addr().unwrap();
+    let relay = Arc::new(Mutex::new(Relay::default()));
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            tokio::spawn(serve_relay_client(stream, Arc::clone(&relay)));
+        }
+    });
+    format!("ws://{relay_addr}")
+}
+
+/// App using an NWC connection
+struct NwcClient {
+    ws_stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    keypair: Keypair,
+    wallet_pubkey: String,
+}
+
+impl NwcClient {
+    async fn connect(uri: &str) -> Self {
+        let (wallet_pubkey, query) = uri
+            .strip_prefix("nostr+walletconnect://")
+            .unwrap()
+            .split_once('?')
+            .unwrap();
+        let params: HashMap<String, String> = serde_urlencoded::from_str(query).unwrap();
+        let secret = SecretKey::from_str(&params["secret"]).unwrap();
+        let keypair = Keypair::from_secret_key(&Secp256k1::new(), &secret);
+        let (mut ws_stream, _) = tokio_tungstenite::connect_async(&params["relay"])
+            .await
+            .unwrap();
+        let pubkey = keypair.x_only_public_key().0.to_string();
+        let filter = serde_json::json!({"kinds": [23195], "#p": [pubkey]});
+        ws_stream
+            .send(WsMessage::Text(
+                serde_json::json!(["REQ", "client", filter]).to_string(),
+            ))
+            .await
+            .unwrap();
+        Self {
+            ws_stream,
+            keypair,
+            wallet_pubkey: wallet_pubkey.to_string(),
+        }
+    }
+
+    async fn request(&mut self, method: &str, params: Value) -> NwcResponse {
+        println!("sending NWC request for method {method}");
+        let request = serde_json::json!({"method": method, "params": params}).to_string();
+        let content =
+            nip04_encrypt(&self.keypair.secret_key(), &self.wallet_pubkey, &request).unwrap();
+        let event = NostrEvent::new(
+            &self.keypair,
+            23194,
+            vec![vec![s!("p"), self.wallet_pubkey.clone()]],
+            content,
+        );
+        self.ws_stream
+            .send(WsMessage::Text(
+                serde_json::json!(["EVENT", event]).to_string(),
+            ))
+            .await
+            .unwrap();
+        let response = tokio::time::timeout(Duration::from_secs(180), async {
+            loop {
+                let Some(Ok(WsMessage::Text(text))) = self.ws_stream.next().await else {
+                    panic!("relay connection closed");
+                };
+                let msg: Vec<Value> = serde_json::from_str(&text).unwrap();
+                if msg[0] != "EVENT" {
+                    continue;
+                }
+                let response: NostrEvent = serde_json::from_value(msg[2].clone()).unwrap();
+                if response.tag("e") == Some(event.id.as_str()) {
+                    assert!(response.verify());
+                    assert_eq!(response.pubkey, self.wallet_pubkey);
+                    return response;
+                }
+            }
+        })
+        .await
+        .expect("no NWC response");
+        let plaintext = nip04_decrypt(
+            &self.keypair.secret_key(),
+            &self.wallet_pubkey,
+            &response.content,
+        )
+        .unwrap();
+        serde_json::from_str(&plaintext).unwrap()
+    }
+}
+
+#[test]
+fn nip04_roundtrip() {
+    let secp = Secp256k1::new();
+    let alice = SecretKey::from_slice(&[1; 32]).unwrap();
+    let bob = SecretKey::from_slice(&[2; 32]).unwrap();
+    let alice_pubkey = alice.x_only_public_key(&secp).0.to_string();
+    let bob_pubkey = bob.x_only_public_key(&secp).0.to_string();
+    let content = nip04_encrypt(&alice, &bob_pubkey, "hello").unwrap();
+    assert_eq!(
+        nip04_decrypt(&bob, &alice_pubkey, &content).unwrap(),
+        "hello"
+    );
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn nwc() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    let relay = start_relay().await;
+    let NwcConnectResponse { connection_id, uri } = nwc_connect(
+        node1_addr,
+        "test app",
+        &relay,
+        Some(5000000),
+        Some(NwcBudgetRenewal::Daily),
+    )
+    .await;
+    let mut client = NwcClient::connect(&uri).await;
+    let connections = nwc_connections(node1_addr).await;
+    assert_eq!(connections.len(), 1);
+    assert_eq!(connections[0].connection_id, connection_id);
+    assert_eq!(connections[0].relay, relay);
+    assert_eq!(connections[0].wallet_pubkey, client.wallet_pubkey);
+    assert_eq!(connections[0].spent_msat, 0);
+
+    let response = client.request("get_balance", serde_json::json!({})).await;
+    assert_eq!(response.result_type, "get_balance");
+    assert!(response.error.is_none());
+    let balance = response.result.unwrap()["balance"].as_u64().unwrap();
+    assert!(balance > 0);
+
+    let response = client
+        .request("make_invoice", serde_json::json!({"amount": 1000000}))
+        .await;
+    let result = response.result.unwrap();
+    let invoice = result["invoice"].as_str().unwrap();
+    let decoded = decode_ln_invoice(node1_addr, invoice).await;
+    assert_eq!(decoded.amt_msat, Some(1000000));
+    assert_eq!(result["payment_hash"], decoded.payment_hash);
+
+    let LNInvoiceResponse { invoice } = ln_invoice(node2_addr, None, None, None, 900).await;
+    let response = client
+        .request("pay_invoice", serde_json::json!({"invoice": invoice}))
+        .await;
+    assert!(response.error.is_none());
+    let result = response.result.unwrap();
+    assert_eq!(result["preimage"].as_str().unwrap().len(), 64);
+    assert_eq!(result["fees_paid"], 0);
+    let connections = nwc_connections(node1_addr).await;
+    assert_eq!(connections[0].spent_msat, 3000000);
+    assert!(connections[0].last_used_at.is_some());
+
+    // budget exceeded
+    let LNInvoiceResponse { invoice } = ln_invoice(node2_addr, None, None, None, 900).await;
+    let response = client
+        .request("pay_invoice", serde_json::json!({"invoice": invoice}))
+        .await;
+    let error = response.error.unwrap();
+    assert_eq!(error.code, "QUOTA_EXCEEDED");
+    assert_eq!(nwc_connections(node1_addr).await[0].spent_msat, 3000000);
+    // an amount that would overflow the spent one is refused as well
+    let payload = LNInvoiceRequest {
+        expiry_sec: 900,
+        ..Default::default()
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/lninvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let LNInvoiceResponse { invoice } = _check_response_is_ok(res)
+        .await
+        .json::<LNInvoiceResponse>()
+        .await
+        .unwrap();
+    let response = client
+        .request(
+            "pay_invoice",
+            serde_json::json!({"invoice": invoice, "amount": u64::MAX}),
+        )
+        .await;
+    assert_eq!(response.error.unwrap().code, "QUOTA_EXCEEDED");
+    assert_eq!(nwc_connections(node1_addr).await[0].spent_msat, 3000000);
+
+    let response = client
+        .request("list_transactions", serde_json::json!({}))
+        .await;
+    assert_eq!(response.error.unwrap().code, "NOT_IMPLEMENTED");
+
+    // revocation
+    nwc_revoke(node1_addr, &connection_id).await;
+    assert!(nwc_connections(node1_addr).await.is_empty());
+    let payload = NwcRevokeRequest { connection_id };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/nwc/revoke"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown NWC connection",
+        "UnknownNwcConnection",
+    )
+    .await;
+
+    // invalid relay
+    let payload = NwcConnectRequest {
+        name: s!("test app"),
+        relay: s!("https://relay.example.com"),
+        budget_msat: None,
+        budget_renewal: None,
+        totp_code: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/nwc/connect"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "expected a ws:// or wss:// URL",
+        "InvalidNwcConnection",
+    )
+    .await;
+}
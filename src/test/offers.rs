@@ -0,0 +1,146 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/offers/";
+
+async fn wait_for_offer_payment(
+    node_address: SocketAddr,
+    offer_id: &str,
+    inbound: bool,
+) -> Payment {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        if let Some(payment) = list_payments(node_address).await.into_iter().find(|p| {
+            p.inbound == inbound
+                && p.offer_id.as_deref() == Some(offer_id)
+                && p.status == HTLCStatus::Succeeded
+        }) {
+            return payment;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 40.0 {
+            panic!("cannot find succeeded payment for offer {offer_id}")
+        }
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn offers() {
+    initialize();
+
+    let test_dir_base = format!("{TEST_DIR_BASE}offers/");
+    let test_dir_node1 = format!("{test_dir_base}node1");
+    let test_dir_node2 = format!("{test_dir_base}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // fixed amount offer, paid twice
+    let OfferResponse {
+        offer: offer_fixed,
+        offer_id,
+    } = offer(node2_addr, Some(3000000), "fixed", Some(3600)).await;
+    pay_offer(node1_addr, &offer_fixed, None).await;
+    let payment = wait_for_offer_payment(node1_addr, &offer_id, false).await;
+    assert_eq!(payment.amt_msat, Some(3000000));
+    assert_eq!(payment.payee_pubkey, node2_pubkey);
+    let payment = wait_for_offer_payment(node2_addr, &offer_id, true).await;
+    assert_eq!(payment.amt_msat, Some(3000000));
+    pay_offer(node1_addr, &offer_fixed, None).await;
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let paid = list_payments(node2_addr)
+            .await
+            .into_iter()
+            .filter(|p| {
+                p.offer_id.as_deref() == Some(&offer_id) && p.status == HTLCStatus::Succeeded
+            })
+            .count();
+        if paid == 2 {
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 40.0 {
+            panic!("second offer payment not received")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+
+    // amount-less offer, the payer chooses the amount
+    let OfferResponse {
+        offer: offer_any,
+        offer_id: offer_any_id,
+    } = offer(node2_addr, None, "any", None).await;
+    let payload = PayOfferRequest {
+        offer: offer_any.clone(),
+        amt_msat: None,
+        payer_note: None,
+        totp_code: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/payoffer"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "cannot process non-Bitcoin-denominated offer value",
+        "InvalidAmount",
+    )
+    .await;
+    pay_offer(node1_addr, &offer_any, Some(5000000)).await;
+    let payment = wait_for_offer_payment(node2_addr, &offer_any_id, true).await;
+    assert_eq!(payment.amt_msat, Some(5000000));
+
+    // RGB offers are not supported yet
+    let payload = OfferRequest {
+        amt_msat: Some(3000000),
+        description: s!("rgb"),
+        expiry_sec: None,
+        asset_id: Some(s!("rgb:CJkb4YZw-jRiz2sk-~PARPio-wtVYI1c-XAEYCqO-wTfvRZ8")),
+        asset_amount: Some(10),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/offer"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "RGB assets are not supported in BOLT12 offers",
+        "UnsupportedRgbOffer",
+    )
+    .await;
+
+    let offers = list_offers(node2_addr).await;
+    assert_eq!(offers.len(), 2);
+    let fixed = offers.iter().find(|o| o.offer_id == offer_id).unwrap();
+    assert_eq!(fixed.offer, offer_fixed);
+    assert_eq!(fixed.description, "fixed");
+    assert_eq!(fixed.amt_msat, Some(3000000));
+    assert!(fixed.expires_at.is_some());
+    let any = offers.iter().find(|o| o.offer_id == offer_any_id).unwrap();
+    assert_eq!(any.description, "any");
+    assert_eq!(any.amt_msat, None);
+    assert_eq!(any.expires_at, None);
+    assert!(list_offers(node1_addr).await.is_empty());
+}
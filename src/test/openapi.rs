@@ -0,0 +1,305 @@
+use std::collections::BTreeSet;
+
+use serde::de::{DeserializeOwned, Error, Visitor};
+use serde::Deserializer;
+use serde_yaml::{Mapping, Value as YamlValue};
+
+use crate::routes::{Page, OPENAPI_SPEC};
+
+use super::*;
+
+const HTTP_METHODS: [&str; 5] = ["delete", "get", "patch", "post", "put"];
+
+const SCHEMA_REF_PREFIX: &str = "#/components/schemas/";
+
+/// Schemas not matching a single Rust type, as they describe tagged enums (with allOf and oneOf)
+/// or multipart forms
+const UNCHECKED_SCHEMAS: [&str; 10] = [
+    "Asset",
+    "Assignment",
+    "AssignmentAny",
+    "AssignmentFungible",
+    "AssignmentInflationRight",
+    "AssignmentNonFungible",
+    "AssignmentReplaceRight",
+    "ImportBackupRequest",
+    "ImportConsignmentRequest",
+    "PostAssetMediaRequest",
+];
+
+/// Deserializer recording the field names of the struct or the variant names of the enum
+/// deserialized with it, as serde sees them (i.e. after renames)
+struct Introspector<'a>(&'a mut Option<&'static [&'static str]>);
+
+impl<'de> Deserializer<'de> for Introspector<'_> {
+    type Error = serde::de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(Error::custom("not a struct nor an enum"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.0 = Some(fields);
+        Err(Error::custom("introspection only"))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.0 = Some(variants);
+        Err(Error::custom("introspection only"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct newtype_struct seq tuple tuple_struct map identifier ignored_any
+    }
+}
+
+fn serde_names<T: DeserializeOwned>() -> Option<BTreeSet<String>> {
+    let mut names = None;
+    let _ = T::deserialize(Introspector(&mut names));
+    names.map(|n| n.iter().map(|s| s.to_string()).collect())
+}
+
+macro_rules! schema_types {
+    ($($name:ident),* $(,)?) => {
+        vec![$((stringify!($name), serde_names::<crate::routes::$name>())),*]
+    };
+}
+
+/// Paths and methods of the routes declared in lib.rs, with path parameters in the OpenAPI form
+fn declared_routes() -> BTreeSet<(String, String)> {
+    let src = include_str!("../lib.rs");
+    let mut routes = BTreeSet::new();
+    for (idx, call) in src.match_indices(".route(") {
+        let args = &src[idx + call.len()..];
+        let path_start = args.find('"').unwrap() + 1;
+        let path_end = path_start + args[path_start..].find('"').unwrap();
+        let path = args[path_start..path_end]
+            .split('/')
+            .map(|s| match s.strip_prefix(':') {
+                Some(param) => format!("{{{param}}}"),
+                None => s.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        // method routers chained at the top level of the call, e.g. get(a).post(b)
+        let mut depth = 0;
+        let mut ident = String::new();
+        for c in args[path_end + 1..].chars() {
+            match c {
+                '(' => {
+                    if depth == 0 && HTTP_METHODS.contains(&ident.as_str()) {
+                        routes.insert((path.clone(), ident.clone()));
+                    }
+                    depth += 1;
+                }
+                ')' if depth == 0 => break,
+                ')' => depth -= 1,
+                _ => {}
+            }
+            if c.is_alphanumeric() || c == '_' {
+                ident.push(c);
+            } else {
+                ident.clear();
+            }
+        }
+    }
+    routes
+}
+
+fn check_refs(value: &YamlValue, schemas: &Mapping) {
+    match value {
+        YamlValue::Mapping(mapping) => {
+            for (key, value) in mapping {
+                if key.as_str() == Some("$ref") {
+                    let reference = value.as_str().unwrap();
+                    let name = reference.strip_prefix(SCHEMA_REF_PREFIX).unwrap();
+                    assert!(schemas.contains_key(name), "unknown schema {reference}");
+                }
+                check_refs(value, schemas);
+            }
+        }
+        YamlValue::Sequence(sequence) => sequence.iter().for_each(|v| check_refs(v, schemas)),
+        _ => {}
+    }
+}
+
+fn keys(value: Option<&YamlValue>) -> BTreeSet<String> {
+    value
+        .and_then(|v| v.as_mapping())
+        .map(|m| m.keys().map(|k| k.as_str().unwrap().to_string()).collect())
+        .unwrap_or_default()
+}
+
+#[test]
+fn openapi() {
+    let spec: YamlValue = serde_yaml::from_str(OPENAPI_SPEC).unwrap();
+    let schemas = spec["components"]["schemas"].as_mapping().unwrap();
+
+    // the spec describes all the routes, and only them
+    let mut spec_routes = BTreeSet::new();
+    for (path, item) in spec["paths"].as_mapping().unwrap() {
+        for method in keys(Some(item)) {
+            if HTTP_METHODS.contains(&method.as_str()) {
+                spec_routes.insert((path.as_str().unwrap().to_string(), method));
+            }
+        }
+    }
+    let routes = declared_routes();
+    assert_eq!(
+        routes.difference(&spec_routes).collect::<Vec<_>>(),
+        Vec::<&(String, String)>::new(),
+        "routes missing from the spec"
+    );
+    assert_eq!(
+        spec_routes.difference(&routes).collect::<Vec<_>>(),
+        Vec::<&(String, String)>::new(),
+        "spec paths with no route"
+    );
+
+    check_refs(&spec, schemas);
+
+    // the schemas have the fields and variants of the request and response types
+    let mut types = schema_types! {
+        AbandonPaymentRequest, AccelerateIncomingRequest, AccelerateIncomingResponse,
+        AcceptChannelRequest, AccountingEntry, AccountingEntryKind, AddressResponse,
+        AddressStatsResponse, AddressType, AnchorReserveEvent, AnchorReserveEventKind,
+        AnchorReserveResponse, AnchorReserveUtxo, ApproveBroadcastResponse, AssetAuditResponse,
+        AssetBalanceRequest, AssetBalanceResponse, AssetCFA, AssetIFA, AssetMetadataRequest,
+        AssetMetadataResponse, AssetNIA, AssetRecipient, AssetSchema, AssetUDA, AuditResponse,
+        AuditSnapshot, AuditSnapshotAsset, AuditSnapshotChannel, BackupChannelsRequest,
+        BackupChannelsResponse, BackupRequest, BalanceSheetAsset, BalanceSheetChannels,
+        BalanceSheetHtlcs, BalanceSheetOnchain, BalanceSheetRequest, BalanceSheetResponse,
+        BitcoinNetwork, BlockTime, Bolt12Offer, BroadcastPsbtRequest, BroadcastPsbtResponse,
+        BtcBalance, BtcBalanceRequest, BtcBalanceResponse, BtcRecipient, BumpCloseFeeRequest,
+        BumpCloseFeeResponse, BumpFeeRequest, BumpFeeResponse, BurnAssetRequest, BurnAssetResponse,
+        BuyInboundRequest, BuyInboundResponse, CancelInvoiceRequest, CancelInvoicesRequest,
+        CancelInvoicesResponse, ChainEvent, ChainEventKind, ChainEventsResponse,
+        ChainSubscriptionsRequest, ChainSubscriptionsResponse, ChangePasswordRequest, Channel,
+        ChannelAcceptorRequest, ChannelAcceptorResponse, ChannelDepthOverride, ChannelDepthsRequest,
+        ChannelDepthsResponse, ChannelEvent, ChannelEventKind, ChannelEventsResponse,
+        ChannelFeeReport, ChannelOpenStage, ChannelOperation, ChannelStatus, ChannelStatusResponse,
+        CheckIndexerUrlRequest, CheckIndexerUrlResponse, CheckProxyEndpointRequest,
+        CloseChannelRequest, CloseClaim, CloseClaimKind, CloseDetailRequest, CloseDetailResponse,
+        CloseOutput, CloseOutputStatus, CoinSelection, CoinSelectionStrategy, CompactStorageRequest,
+        CompactStorageResponse, ConfirmTotpRequest, ConnectPeerRequest, CreateOrderRequest,
+        CreateOrderResponse, CreateUtxosRequest, DecodeLNInvoiceRequest, DecodeLNInvoiceResponse,
+        DecodeRGBInvoiceRequest, DecodeRGBInvoiceResponse, DescriptorsResponse, DisableTotpRequest,
+        DisconnectPeerRequest, DryRunInput, DryRunResult, EmbeddedMedia, EmergencyKitResponse,
+        EmptyResponse, EnrollTotpRequest, EnrollTotpResponse, Escrow, EscrowCreateRequest,
+        EscrowCreateResponse, EscrowDecision, EscrowResolveRequest, EscrowResolveResponse,
+        EscrowStatus, EstimateFeeRequest, EstimateFeeResponse, ExportAccountingResponse,
+        ExportBackupRequest, ExportConsignmentRequest, ExportFormat, ExternalFunding,
+        FailTransferRequest, FailTransfersRequest, FailTransfersResponse, FeeBumpMethod,
+        FeeReportResponse, Forward, ForwardResolution, ForwardingHistoryResponse,
+        FundChannelAbortRequest, FundChannelCompleteRequest, FundChannelCompleteResponse,
+        FundPsbtRequest, FundPsbtResponse, GetAssetMediaRequest, GetAssetMediaResponse,
+        GetChanInfoResponse, GetChannelIdRequest, GetChannelIdResponse, GetNodeInfoResponse,
+        GetOrderRequest, GetOrderResponse, GetPaymentRequest, GetPaymentResponse, GetSwapRequest,
+        GetSwapResponse, GraphChannelPolicy, HTLCStatus, Hedge, HedgeStatus,
+        HedgingPoliciesResponse, HedgingPolicyEntry, HodlInvoiceRequest, HodlInvoiceResponse, Htlc,
+        HtlcDirection, HtlcKind, HtlcResolution, HtlcsResponse, ImportConsignmentResponse,
+        IndexerProtocol, InitRequest, InitResponse, InterceptScopeKind, InterceptScopesRequest,
+        InterceptScopesResponse, InterceptedHtlc, InterceptedHtlcsResponse, InvoiceLsp,
+        InvoicePhantom, InvoiceQuotaUsage, InvoiceQuotasResponse, InvoiceResult, InvoiceRouteHint,
+        InvoiceRouteHintHop, InvoiceStatus, InvoiceStatusRequest, InvoiceStatusResponse,
+        IssueAssetCFARequest, IssueAssetCFAResponse, IssueAssetIFARequest, IssueAssetIFAResponse,
+        IssueAssetNIARequest, IssueAssetNIAResponse, IssueAssetUDARequest, IssueAssetUDAResponse,
+        JournalEntry, JournalEventKind, JournalProofResponse, KeysendRequest, KeysendResponse,
+        LNInvoiceRequest, LNInvoiceResponse, LeaseConfigRequest, LeaseConfigResponse, LeaseOrder,
+        LeaseOrderStatus, LeaseOrdersResponse, LeaseRatesRequest, LeaseRatesResponse,
+        LightningAddress, ListAssetsRequest, ListEscrowsResponse, ListHedgesResponse,
+        ListOffersResponse, ListSubmarineSwapsResponse, ListSwapsResponse, ListSweepsResponse,
+        ListTransactionsRequest, ListTransactionsResponse, ListTransfersRequest,
+        ListUnspentsRequest, ListUnspentsResponse, LnurlPayCallbackResponse,
+        LnurlPayMetadataResponse, LnurlPayRequest, LnurlPayResponse, LockUtxoRequest,
+        LogLevelRequest, LogLevelResponse, LspClient, LspClientStatus, LspClientsResponse,
+        LspConfigRequest, LspConfigResponse, LspFeeMenuRequest, LspFeeMenuResponse, LspFeeParams,
+        MakerExecuteRequest, MakerInitRequest, MakerInitResponse, Media, MultisigFunding,
+        MultisigInput, NetworkInfoResponse, NodeInfoResponse, NwcBudgetRenewal, NwcConnectRequest,
+        NwcConnectResponse, NwcConnection, NwcConnectionsResponse, NwcRevokeRequest, OfferRequest,
+        OfferResponse, OnionServiceStatus, OpenChannelRequest, OpenChannelResponse, Order,
+        OrderItem, OrderLeg, OrderStatus, OwnershipProofResponse, PayOfferRequest, PayOfferResponse,
+        Payment, PaymentFee, PaymentHop, PaymentPart, Peer, PeerCompatResponse, PeerFeatures,
+        PeerFilterRequest, PeerFilterResponse, PeerSuggestion, PeerSuggestionGoal,
+        PeerSuggestionsResponse, PeerTransport, PendingBroadcast, PendingBroadcastKind,
+        PendingBroadcastsResponse, PendingChannel, PendingChannelsResponse, PendingExternalFunding,
+        PendingFunding, PendingFundingsResponse, PhantomRouteHintsResponse, PostAssetMediaResponse,
+        ProbePaymentRequest, ProbePaymentResponse, ProofOfReserves, QueriedRoute, QueriedRouteHop,
+        QueryRoutesRequest, QueryRoutesResponse, RebalanceRequest, RebalanceResponse, RecipientType,
+        RefreshRequest, ReissueAssetRequest, ReissueAssetResponse, RejectChannelRequest,
+        ReloadConfigResponse, RemoveHedgingPolicyRequest, ResolveHtlcRequest,
+        RestoreChannelsRequest, RestoreChannelsResponse, RestoreRequest, RetrySweepRequest,
+        RetryTransferRequest, RevokeTokenRequest, RgbAllocation, RgbInvoiceRequest,
+        RgbInvoiceResponse, SendAssetRequest, SendAssetResponse, SendBtcManyRequest,
+        SendBtcManyResponse, SendBtcRequest, SendBtcResponse, SendOnionMessageRequest,
+        SendPaymentRequest, SendPaymentResponse, SendToRouteRequest, SetAliasRequest,
+        SetHedgingPolicyRequest, SetViewPasswordRequest, SettleInvoiceRequest,
+        SettleInvoicesRequest, SettleInvoicesResponse, SettlementTotal, SettlementsResponse,
+        SignMessageRequest, SignMessageResponse, SignPsbtRequest, SignPsbtResponse, SubmarineSwap,
+        SubmarineSwapKind, SubmarineSwapStatus, Swap, SwapInRequest, SwapInResponse, SwapOutRequest,
+        SwapOutResponse, SwapStatus, Sweep, SweepConfigRequest, SweepConfigResponse, SweepStatus,
+        SyncStage, SyncStatusResponse, TakerRequest, ThrottleGossipRequest, Token,
+        TokenInvoiceQuotaUsage, TokenLight, TorStatusResponse, Transaction, TransactionDirection,
+        TransactionType, Transfer, TransferDetailRequest, TransferDetailResponse, TransferKind,
+        TransferStatus, TransferTransportEndpoint, TransportType, UnlockRequest, UnlockUtxoRequest,
+        Unspent, UpdateChannelPolicyRequest, UpdateChannelPolicyResponse, Utxo,
+        VerifyMessageRequest, VerifyMessageResponse, WitnessData,
+    };
+    // the spec describes the /v2 APIs
+    types.extend([
+        ("APIErrorResponse", serde_names::<APIErrorResponseV2>()),
+        ("AssetPage", serde_names::<Page<serde_json::Value>>()),
+        ("ChannelPage", serde_names::<Page<serde_json::Value>>()),
+        ("PaymentPage", serde_names::<Page<serde_json::Value>>()),
+        ("PeerPage", serde_names::<Page<serde_json::Value>>()),
+        ("TransferPage", serde_names::<Page<serde_json::Value>>()),
+    ]);
+    #[cfg(feature = "failure-injection")]
+    types.extend(schema_types!(InjectFailureRequest, InjectedFailure));
+    for (name, names) in &types {
+        let names = names
+            .as_ref()
+            .unwrap_or_else(|| panic!("{name} is not a struct nor an enum"));
+        let schema = schemas
+            .get(*name)
+            .unwrap_or_else(|| panic!("schema {name} missing from the spec"));
+        let spec_names = match schema.get("enum") {
+            Some(variants) => variants
+                .as_sequence()
+                .unwrap()
+                .iter()
+                .map(|v| v.as_str().unwrap().to_string())
+                .collect(),
+            None => keys(schema.get("properties")),
+        };
+        assert_eq!(
+            &spec_names, names,
+            "schema {name} doesn't match the Rust type"
+        );
+    }
+
+    // every schema is checked, unless it can't map to a single type
+    for name in keys(Some(&spec["components"]["schemas"])) {
+        let feature_gated = ["InjectFailureRequest", "InjectedFailure"].contains(&name.as_str());
+        if UNCHECKED_SCHEMAS.contains(&name.as_str())
+            || (feature_gated && !cfg!(feature = "failure-injection"))
+        {
+            continue;
+        }
+        assert!(
+            types.iter().any(|(n, _)| *n == name),
+            "schema {name} not checked against a Rust type"
+        );
+    }
+}
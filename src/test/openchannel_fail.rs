@@ -31,9 +31,19 @@ async fn open_fail() {
         asset_id: Some(asset_id.clone()),
         public: true,
         with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node1_addr}/openchannel"))
@@ -65,9 +75,19 @@ async fn open_fail() {
         asset_id: Some(s!("rgb:EIkAVQvq-WbAb5JG-CYxbUER-oqDNwne-ZNxBDID-p0cpf9U")),
         public: true,
         with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node1_addr}/openchannel"))
@@ -92,9 +112,19 @@ async fn open_fail() {
         asset_id: Some(asset_id.clone()),
         public: true,
         with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node1_addr}/openchannel"))
@@ -124,9 +154,19 @@ async fn open_fail() {
         asset_id: Some(s!("bad asset ID")),
         public: true,
         with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node1_addr}/openchannel"))
@@ -156,9 +196,19 @@ async fn open_fail() {
         asset_id: None,
         public: true,
         with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node1_addr}/openchannel"))
@@ -188,9 +238,19 @@ async fn open_fail() {
         asset_id: Some(asset_id.clone()),
         public: true,
         with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node1_addr}/openchannel"))
@@ -222,9 +282,19 @@ async fn open_fail() {
         asset_id: Some(asset_id.clone()),
         public: true,
         with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node1_addr}/openchannel"))
@@ -254,9 +324,19 @@ async fn open_fail() {
         asset_id: Some(asset_id.clone()),
         public: true,
         with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node1_addr}/openchannel"))
@@ -286,9 +366,19 @@ async fn open_fail() {
         asset_id: Some(asset_id.clone()),
         public: true,
         with_anchors: false,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node1_addr}/openchannel"))
@@ -320,9 +410,19 @@ async fn open_fail() {
         asset_id: None,
         public: true,
         with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node1_addr}/openchannel"))
@@ -352,9 +452,19 @@ async fn open_fail() {
         asset_id: Some(asset_id.clone()),
         public: true,
         with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node1_addr}/openchannel"))
@@ -384,9 +494,19 @@ async fn open_fail() {
         asset_id: Some(asset_id.clone()),
         public: true,
         with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: Some(s!("ttoooosshhoorrtt")),
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node1_addr}/openchannel"))
@@ -416,9 +536,19 @@ async fn open_fail() {
         asset_id: Some(asset_id.clone()),
         public: true,
         with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node1_addr}/openchannel"))
@@ -436,9 +566,19 @@ async fn open_fail() {
         asset_id: Some(asset_id),
         public: true,
         with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node1_addr}/openchannel"))
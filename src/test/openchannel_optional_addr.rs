@@ -32,9 +32,19 @@ async fn openchannel_optional_addr_forward() {
         asset_id: Some(asset_id.clone()),
         public: true,
         with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node1_addr}/openchannel"))
@@ -115,9 +125,19 @@ async fn openchannel_optional_addr_reverse() {
         asset_id: Some(asset_id.clone()),
         public: true,
         with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
         fee_base_msat: None,
         fee_proportional_millionths: None,
         temporary_channel_id: None,
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node2_addr}/openchannel"))
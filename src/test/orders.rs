@@ -0,0 +1,164 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/orders/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn orders() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+
+    let btc_item = |description: &str, amt_msat| OrderItem {
+        description: description.to_string(),
+        amt_msat: Some(amt_msat),
+        asset_id: None,
+        asset_amount: None,
+    };
+    let asset_item = |description: &str, asset_amount| OrderItem {
+        description: description.to_string(),
+        amt_msat: None,
+        asset_id: Some(asset_id.clone()),
+        asset_amount: Some(asset_amount),
+    };
+
+    // sat items share an invoice, asset items get one invoice per asset
+    let order = create_order(
+        node2_addr,
+        vec![
+            btc_item("coffee", 2000000),
+            btc_item("cake", 3000000),
+            asset_item("ticket", 40),
+            asset_item("parking", 60),
+        ],
+        900,
+    )
+    .await;
+    assert_eq!(order.items.len(), 4);
+    assert_eq!(order.legs.len(), 2);
+    assert_eq!(order.status, OrderStatus::Pending);
+    let btc_leg = order.legs.iter().find(|l| l.asset_id.is_none()).unwrap();
+    assert_eq!(btc_leg.amt_msat, 5000000);
+    let asset_leg = order.legs.iter().find(|l| l.asset_id.is_some()).unwrap();
+    assert_eq!(asset_leg.asset_id, Some(asset_id.clone()));
+    assert_eq!(asset_leg.asset_amount, Some(100));
+    assert!(order.legs.iter().all(|l| !l.received));
+
+    // the first payment is held until the second one arrives
+    send_payment_raw(node1_addr, btc_leg.invoice.clone()).await;
+    wait_for_invoice_status(node2_addr, &btc_leg.invoice, InvoiceStatus::Held).await;
+    let pending = get_order(node2_addr, &order.order_id).await;
+    assert_eq!(pending.status, OrderStatus::Pending);
+    assert!(
+        pending
+            .legs
+            .iter()
+            .find(|l| l.payment_hash == btc_leg.payment_hash)
+            .unwrap()
+            .received
+    );
+    send_payment_raw(node1_addr, asset_leg.invoice.clone()).await;
+    let paid = wait_for_order_status(node2_addr, &order.order_id, OrderStatus::Paid).await;
+    assert!(paid
+        .legs
+        .iter()
+        .all(|l| l.received && l.status == HTLCStatus::Succeeded));
+    wait_for_ln_payment(node1_addr, &btc_leg.payment_hash, HTLCStatus::Succeeded).await;
+    wait_for_ln_payment(node1_addr, &asset_leg.payment_hash, HTLCStatus::Succeeded).await;
+    wait_for_ln_balance(node1_addr, &asset_id, 500).await;
+    wait_for_ln_balance(node2_addr, &asset_id, 100).await;
+
+    // an expired order gives the received payments back
+    let order = create_order(
+        node2_addr,
+        vec![btc_item("coffee", 3000000), asset_item("ticket", 50)],
+        10,
+    )
+    .await;
+    let btc_leg = order.legs.iter().find(|l| l.asset_id.is_none()).unwrap();
+    send_payment_raw(node1_addr, btc_leg.invoice.clone()).await;
+    wait_for_invoice_status(node2_addr, &btc_leg.invoice, InvoiceStatus::Held).await;
+    let expired = wait_for_order_status(node2_addr, &order.order_id, OrderStatus::Expired).await;
+    assert!(expired.legs.iter().all(|l| !l.received));
+    wait_for_ln_payment(node1_addr, &btc_leg.payment_hash, HTLCStatus::Failed).await;
+    wait_for_ln_balance(node1_addr, &asset_id, 500).await;
+
+    // failures
+    let payload = CreateOrderRequest {
+        items: vec![],
+        expiry_sec: 900,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/orders"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "the number of items must be between 1 and",
+        "InvalidOrder",
+    )
+    .await;
+    let payload = CreateOrderRequest {
+        items: vec![OrderItem {
+            description: "mixed".to_string(),
+            amt_msat: Some(3000000),
+            asset_id: Some(asset_id.clone()),
+            asset_amount: Some(10),
+        }],
+        expiry_sec: 900,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/orders"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "each item needs either a positive amt_msat",
+        "InvalidOrder",
+    )
+    .await;
+    let payload = GetOrderRequest {
+        order_id: "unknown".to_string(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/getorder"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown order",
+        "UnknownOrder",
+    )
+    .await;
+}
@@ -0,0 +1,81 @@
+use bitcoin::secp256k1::PublicKey;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/ownership_proof/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn ownership_proof_signature() {
+    initialize();
+
+    let test_dir_base = format!("{TEST_DIR_BASE}signature/");
+    let test_dir_node1 = format!("{test_dir_base}node1");
+    let (node1_addr, node1_password) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let pubkey = PublicKey::from_str(&node1_pubkey).unwrap();
+
+    // no announced addresses
+    let nonce = "2b5c0f3e9a1d4c7b";
+    let proof = ownership_proof(node1_addr, nonce).await;
+    assert_eq!(proof.pubkey, node1_pubkey);
+    assert!(proof.addresses.is_empty());
+    assert_eq!(proof.nonce, nonce);
+    assert!(proof.message.contains(&format!("pubkey: {node1_pubkey}")));
+    assert!(proof.message.contains(&format!("nonce: {nonce}")));
+    assert!(lightning::util::message_signing::verify(
+        proof.message.as_bytes(),
+        &proof.signature,
+        &pubkey
+    ));
+    assert!(!lightning::util::message_signing::verify(
+        proof.message.replace(nonce, "another nonce").as_bytes(),
+        &proof.signature,
+        &pubkey
+    ));
+
+    // announced addresses are included in the signed statement
+    lock(node1_addr).await;
+    let mut payload = unlock_req(&node1_password);
+    payload.announce_addresses = vec![s!("1.2.3.4:9735"), s!("example.com:9736")];
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/unlock"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+    let proof = ownership_proof(node1_addr, nonce).await;
+    assert_eq!(proof.addresses, vec!["1.2.3.4:9735", "example.com:9736"]);
+    assert!(proof
+        .message
+        .contains("addresses: 1.2.3.4:9735,example.com:9736"));
+    assert!(lightning::util::message_signing::verify(
+        proof.message.as_bytes(),
+        &proof.signature,
+        &pubkey
+    ));
+
+    // invalid nonces
+    for (nonce, msg) in [
+        (s!(""), "cannot be empty"),
+        ("a".repeat(129), "cannot be longer than 128 bytes"),
+        (s!("a\nb"), "cannot contain line breaks"),
+    ] {
+        let res = reqwest::Client::new()
+            .get(format!("http://{node1_addr}/ownershipproof"))
+            .query(&[("nonce", &nonce)])
+            .send()
+            .await
+            .unwrap();
+        check_response_is_nok(res, reqwest::StatusCode::BAD_REQUEST, msg, "InvalidNonce").await;
+    }
+    let res = reqwest::Client::new()
+        .get(format!("http://{node1_addr}/ownershipproof"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+}
@@ -1,4 +1,7 @@
-use crate::routes::{BitcoinNetwork, TransactionType, TransferKind, TransferStatus};
+use crate::routes::{
+    BitcoinNetwork, ChannelOperation, TransactionDirection, TransactionType, TransferKind,
+    TransferStatus,
+};
 
 use super::*;
 
@@ -63,6 +66,14 @@ async fn success() {
     assert_eq!(payment.asset_id, Some(asset_id.clone()));
     assert_eq!(payment.asset_amount, asset_amount);
     assert_eq!(payment.status, HTLCStatus::Succeeded);
+    let fees = wait_for_payment_parts(node1_addr, &decoded.payment_hash, 3000000)
+        .await
+        .fees;
+    assert_eq!(fees.len(), 1);
+    assert_eq!(fees[0].asset_id, Some(asset_id.clone()));
+    assert_eq!(fees[0].asset_amount, asset_amount);
+    assert_eq!(fees[0].fee_msat, 0);
+    assert_eq!(fees[0].num_parts, 1);
     let payment = get_payment(node2_addr, &decoded.payment_hash).await;
     assert_eq!(payment.asset_id, Some(asset_id.clone()));
     assert_eq!(payment.asset_amount, asset_amount);
@@ -174,6 +185,27 @@ async fn success() {
     assert_eq!(tx_utxos.transaction_type, TransactionType::CreateUtxos);
     assert_eq!(tx_send.transaction_type, TransactionType::RgbSend);
     assert!(tx_utxos.confirmation_time.is_some());
+    assert!(tx_utxos.confirmations > 0);
+    assert_eq!(tx_user.direction, TransactionDirection::Incoming);
+    assert_eq!(tx_utxos.direction, TransactionDirection::SelfTransfer);
+    assert_eq!(tx_send.direction, TransactionDirection::Outgoing);
+    assert!(!tx_user.rgb_commitment);
+    assert!(tx_send.rgb_commitment);
+    assert_eq!(tx_send.asset_ids, vec![asset_id.clone()]);
+    let tx_funding = transactions
+        .iter()
+        .find(|t| Some(&t.txid) == channel.funding_txid.as_ref())
+        .unwrap();
+    assert_eq!(
+        tx_funding.channel_operation,
+        Some(ChannelOperation::Funding)
+    );
+    assert!(tx_funding.rgb_commitment);
+    assert_eq!(tx_funding.asset_ids, vec![asset_id.clone()]);
+    assert!(transactions
+        .iter()
+        .any(|t| t.channel_operation == Some(ChannelOperation::Close)));
+    assert!(tx_send.channel_operation.is_none());
 
     let transfers = list_transfers(node1_addr, &asset_id).await;
     let xfer_1 = transfers.iter().find(|t| t.idx == 1).unwrap();
@@ -256,6 +288,15 @@ async fn same_invoice_twice() {
     let payload = SendPaymentRequest {
         invoice: invoice.clone(),
         amt_msat: None,
+        timeout_sec: None,
+        max_retries: None,
+        max_fee_msat: None,
+        max_fee_ppm: None,
+        max_cltv_expiry_delta: None,
+        max_parts: None,
+        max_shard_amt_msat: None,
+        totp_code: None,
+        dry_run: false,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{node1_addr}/sendpayment"))
@@ -0,0 +1,160 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/payment_limits/";
+
+async fn send_payment_with_limits(
+    node_address: SocketAddr,
+    payload: &SendPaymentRequest,
+) -> Response {
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/sendpayment"))
+        .json(payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn payment_limits() {
+    initialize();
+
+    let test_dir_base = format!("{TEST_DIR_BASE}payment_limits/");
+    let test_dir_node1 = format!("{test_dir_base}node1");
+    let test_dir_node2 = format!("{test_dir_base}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // a payment within the limits succeeds
+    let LNInvoiceResponse { invoice } = ln_invoice(node2_addr, None, None, None, 900).await;
+    let payload = SendPaymentRequest {
+        invoice,
+        amt_msat: None,
+        timeout_sec: Some(60),
+        max_retries: Some(2),
+        max_fee_msat: Some(1000),
+        max_fee_ppm: Some(5000),
+        max_cltv_expiry_delta: Some(500),
+        max_parts: None,
+        max_shard_amt_msat: None,
+        totp_code: None,
+        dry_run: false,
+    };
+    let res = send_payment_with_limits(node1_addr, &payload).await;
+    let payment_hash = _check_response_is_ok(res)
+        .await
+        .json::<SendPaymentResponse>()
+        .await
+        .unwrap()
+        .payment_hash
+        .unwrap();
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Succeeded).await;
+
+    // a CLTV limit lower than the one required by the invoice makes the payment fail
+    let LNInvoiceResponse { invoice } = ln_invoice(node2_addr, None, None, None, 900).await;
+    let payload = SendPaymentRequest {
+        invoice,
+        amt_msat: None,
+        timeout_sec: None,
+        max_retries: Some(0),
+        max_fee_msat: None,
+        max_fee_ppm: None,
+        max_cltv_expiry_delta: Some(1),
+        max_parts: None,
+        max_shard_amt_msat: None,
+        totp_code: None,
+        dry_run: false,
+    };
+    let res = send_payment_with_limits(node1_addr, &payload).await;
+    let payment_hash = _check_response_is_ok(res)
+        .await
+        .json::<SendPaymentResponse>()
+        .await
+        .unwrap()
+        .payment_hash
+        .unwrap();
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Failed).await;
+
+    // failures
+    let LNInvoiceResponse { invoice } = ln_invoice(node2_addr, None, None, None, 900).await;
+    let payload = SendPaymentRequest {
+        invoice: invoice.clone(),
+        amt_msat: None,
+        timeout_sec: Some(0),
+        max_retries: None,
+        max_fee_msat: None,
+        max_fee_ppm: None,
+        max_cltv_expiry_delta: None,
+        max_parts: None,
+        max_shard_amt_msat: None,
+        totp_code: None,
+        dry_run: false,
+    };
+    let res = send_payment_with_limits(node1_addr, &payload).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "timeout_sec must be between 1 and 3600",
+        "InvalidPaymentLimits",
+    )
+    .await;
+    let payload = SendPaymentRequest {
+        invoice: invoice.clone(),
+        amt_msat: None,
+        timeout_sec: None,
+        max_retries: None,
+        max_fee_msat: None,
+        max_fee_ppm: Some(1_000_001),
+        max_cltv_expiry_delta: None,
+        max_parts: None,
+        max_shard_amt_msat: None,
+        totp_code: None,
+        dry_run: false,
+    };
+    let res = send_payment_with_limits(node1_addr, &payload).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "max_fee_ppm cannot be more than 1000000",
+        "InvalidPaymentLimits",
+    )
+    .await;
+    let payload = SendPaymentRequest {
+        invoice,
+        amt_msat: None,
+        timeout_sec: None,
+        max_retries: None,
+        max_fee_msat: None,
+        max_fee_ppm: None,
+        max_cltv_expiry_delta: Some(5000),
+        max_parts: None,
+        max_shard_amt_msat: None,
+        totp_code: None,
+        dry_run: false,
+    };
+    let res = send_payment_with_limits(node1_addr, &payload).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "max_cltv_expiry_delta cannot be more than",
+        "InvalidPaymentLimits",
+    )
+    .await;
+}
@@ -0,0 +1,98 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/peer_compat/";
+
+async fn peer_compat(node_address: SocketAddr, query: &str) -> reqwest::Response {
+    println!("checking peer compatibility from node {node_address}");
+    reqwest::Client::new()
+        .get(format!("http://{node_address}/peercompat?{query}"))
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn peer_compat_ok(node_address: SocketAddr, query: &str) -> PeerCompatResponse {
+    let res = peer_compat(node_address, query).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<PeerCompatResponse>()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn peer_compat() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node2_peer_addr = format!("127.0.0.1:{NODE2_PEER_PORT}");
+
+    // the peer gets connected when needed
+    assert!(list_peers(node1_addr).await.is_empty());
+    let compat = peer_compat_ok(
+        node1_addr,
+        &format!("pubkey={node2_pubkey}@{node2_peer_addr}&asset_id={asset_id}"),
+    )
+    .await;
+    assert_eq!(compat.pubkey, node2_pubkey);
+    assert!(compat.features.anchors);
+    assert!(compat.features.rgb);
+    assert!(compat.features.scid_alias);
+    assert!(!compat.init_features.is_empty());
+    assert!(compat.open_compatible);
+    assert!(compat.issues.is_empty());
+    assert!(list_peers(node1_addr)
+        .await
+        .iter()
+        .any(|p| p.pubkey == node2_pubkey));
+
+    // the existing connection is used when no address is given
+    let compat = peer_compat_ok(
+        node1_addr,
+        &format!("pubkey={node2_pubkey}&scid_alias=true&public=true"),
+    )
+    .await;
+    assert!(!compat.open_compatible);
+    assert_eq!(
+        compat.issues,
+        vec![s!("scid_alias is only supported by private channels")]
+    );
+
+    let compat = peer_compat_ok(
+        node1_addr,
+        &format!("pubkey={node2_pubkey}&asset_id={asset_id}&with_anchors=false"),
+    )
+    .await;
+    assert!(!compat.open_compatible);
+    assert_eq!(compat.issues, vec![s!("RGB channels require anchors")]);
+
+    // failures
+    let unknown_pubkey = "03b79a4bc1ec365524b4fab9a39eb133753646babb5a1da5c4bc94c53110b7795d";
+    let res = peer_compat(node1_addr, &format!("pubkey={unknown_pubkey}")).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "cannot find the address for the provided pubkey",
+        "InvalidPeerInfo",
+    )
+    .await;
+    let res = peer_compat(node1_addr, "pubkey=invalid").await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid peer info",
+        "InvalidPeerInfo",
+    )
+    .await;
+}
@@ -0,0 +1,220 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/peer_filter/";
+
+fn filter_req(
+    allowed_pubkeys: &[&str],
+    denied_pubkeys: &[&str],
+    allowed_addresses: &[&str],
+    denied_addresses: &[&str],
+) -> PeerFilterRequest {
+    let to_vec = |items: &[&str]| items.iter().map(|i| i.to_string()).collect();
+    PeerFilterRequest {
+        allowed_pubkeys: to_vec(allowed_pubkeys),
+        denied_pubkeys: to_vec(denied_pubkeys),
+        allowed_addresses: to_vec(allowed_addresses),
+        denied_addresses: to_vec(denied_addresses),
+    }
+}
+
+async fn update_peer_filter(
+    node_address: SocketAddr,
+    payload: &PeerFilterRequest,
+) -> reqwest::Response {
+    println!("updating peer filter of node {node_address}");
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/peerfilter"))
+        .json(payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn is_peer_connected(node_address: SocketAddr, peer_pubkey: &str) -> bool {
+    list_peers(node_address)
+        .await
+        .iter()
+        .any(|p| p.pubkey == peer_pubkey)
+}
+
+async fn wait_for_disconnection(node_address: SocketAddr, peer_pubkey: &str) {
+    let t_0 = OffsetDateTime::now_utc();
+    while is_peer_connected(node_address, peer_pubkey).await {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 20.0 {
+            panic!("peer {peer_pubkey} is still connected")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+/// Try to connect to the peer, which may refuse the connection
+async fn try_connect_peer(node_address: SocketAddr, peer_pubkey: &str, peer_addr: &str) {
+    println!("trying to connect peer {peer_pubkey} from node {node_address}");
+    let payload = ConnectPeerRequest {
+        peer_pubkey_and_addr: format!("{peer_pubkey}@{peer_addr}"),
+        timeout_sec: None,
+        retries: None,
+    };
+    let _ = reqwest::Client::new()
+        .post(format!("http://{node_address}/connectpeer"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+}
+
+async fn wait_for_rejection(node_address: SocketAddr, temporary_channel_id: &str) -> String {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let res = reqwest::Client::new()
+            .get(format!(
+                "http://{node_address}/channels/{temporary_channel_id}/events"
+            ))
+            .send()
+            .await
+            .unwrap();
+        if res.status().is_success() {
+            let events = res.json::<ChannelEventsResponse>().await.unwrap();
+            if let Some(event) = events
+                .events
+                .iter()
+                .find(|e| e.details.starts_with("rejected request"))
+            {
+                return event.details.clone();
+            }
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 20.0 {
+            panic!("channel has not been rejected")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn peer_filter() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node1_peer_addr = format!("127.0.0.1:{NODE1_PEER_PORT}");
+    let node2_peer_addr = format!("127.0.0.1:{NODE2_PEER_PORT}");
+
+    // every peer is accepted by default
+    let res = reqwest::Client::new()
+        .get(format!("http://{node2_addr}/peerfilter"))
+        .send()
+        .await
+        .unwrap();
+    let filter = _check_response_is_ok(res)
+        .await
+        .json::<PeerFilterResponse>()
+        .await
+        .unwrap();
+    assert!(filter.allowed_pubkeys.is_empty());
+    assert!(filter.denied_pubkeys.is_empty());
+    assert!(filter.allowed_addresses.is_empty());
+    assert!(filter.denied_addresses.is_empty());
+
+    for (payload, msg) in [
+        (filter_req(&[], &["nope"], &[], &[]), "invalid pubkey nope"),
+        (
+            filter_req(&[], &[], &[], &["example.com"]),
+            "invalid address example.com",
+        ),
+        (
+            filter_req(&[&node1_pubkey], &[&node1_pubkey], &[], &[]),
+            "a pubkey cannot be both allowed and denied",
+        ),
+        (
+            filter_req(&[], &[], &["10.1.2.3"], &["10.1.2.3"]),
+            "an address cannot be both allowed and denied",
+        ),
+    ] {
+        let res = update_peer_filter(node2_addr, &payload).await;
+        check_response_is_nok(
+            res,
+            reqwest::StatusCode::BAD_REQUEST,
+            msg,
+            "InvalidPeerFilter",
+        )
+        .await;
+    }
+
+    // denied peers are refused at the handshake
+    let res = update_peer_filter(node2_addr, &filter_req(&[], &[&node1_pubkey], &[], &[])).await;
+    let filter = _check_response_is_ok(res)
+        .await
+        .json::<PeerFilterResponse>()
+        .await
+        .unwrap();
+    assert_eq!(filter.denied_pubkeys, vec![node1_pubkey.clone()]);
+    try_connect_peer(node1_addr, &node2_pubkey, &node2_peer_addr).await;
+    assert!(!is_peer_connected(node2_addr, &node1_pubkey).await);
+
+    // connections from denied IPs are dropped
+    let res = update_peer_filter(
+        node2_addr,
+        &filter_req(&[], &[], &[], &["127.0.0.1", "abcdefghij234567.onion"]),
+    )
+    .await;
+    _check_response_is_ok(res).await;
+    try_connect_peer(node1_addr, &node2_pubkey, &node2_peer_addr).await;
+    assert!(!is_peer_connected(node2_addr, &node1_pubkey).await);
+
+    // allowed peers can connect
+    let res = update_peer_filter(node2_addr, &filter_req(&[&node1_pubkey], &[], &[], &[])).await;
+    _check_response_is_ok(res).await;
+    connect_peer(node1_addr, &node2_pubkey, &node2_peer_addr).await;
+    assert!(is_peer_connected(node2_addr, &node1_pubkey).await);
+
+    // inbound peers not allowed anymore get disconnected
+    let res = update_peer_filter(node2_addr, &filter_req(&[], &[], &["10.1.2.3"], &[])).await;
+    _check_response_is_ok(res).await;
+    wait_for_disconnection(node2_addr, &node1_pubkey).await;
+
+    // the filter also applies to channel requests over outbound connections
+    connect_peer(node2_addr, &node1_pubkey, &node1_peer_addr).await;
+    let temporary_channel_id =
+        s!("0011223344556677889900112233445566778899001122334455667788990033");
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_opt_addr: format!("{node2_pubkey}@{node2_peer_addr}"),
+        capacity_sat: 100_000,
+        push_msat: 0,
+        asset_amount: None,
+        asset_id: None,
+        public: true,
+        with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        temporary_channel_id: Some(temporary_channel_id.clone()),
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/openchannel"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+    let details = wait_for_rejection(node2_addr, &temporary_channel_id).await;
+    assert!(details.contains(&format!("peer {node1_pubkey} is not allowed")));
+}
@@ -0,0 +1,64 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/peer_metadata/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn peer_metadata() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // the outbound connection goes to the announced clearnet address
+    let peers = list_peers(node1_addr).await;
+    let peer = peers.iter().find(|p| p.pubkey == node2_pubkey).unwrap();
+    assert_eq!(peer.transport, PeerTransport::Clearnet);
+    assert!(!peer.inbound);
+    assert_eq!(
+        peer.address.as_deref(),
+        Some(format!("127.0.0.1:{NODE2_PEER_PORT}").as_str())
+    );
+    assert_eq!(peer.num_channels, 1);
+    assert!(peer.connected_at.is_some());
+    assert!(!peer.init_features.is_empty());
+
+    // inbound connections from loopback are reported as coming from the onion service
+    let peers = list_peers(node2_addr).await;
+    let peer = peers.iter().find(|p| p.pubkey == node1_pubkey).unwrap();
+    assert_eq!(peer.transport, PeerTransport::OnionServiceInbound);
+    assert!(peer.inbound);
+    assert_eq!(peer.num_channels, 1);
+
+    // the uptime restarts on reconnection
+    disconnect_peer(node1_addr, &node2_pubkey).await;
+    connect_peer(
+        node1_addr,
+        &node2_pubkey,
+        &format!("127.0.0.1:{NODE2_PEER_PORT}"),
+    )
+    .await;
+    let peers = list_peers(node1_addr).await;
+    let peer = peers.iter().find(|p| p.pubkey == node2_pubkey).unwrap();
+    assert!(peer.uptime_sec.unwrap() < 10);
+
+    shutdown(&[node1_addr, node2_addr]).await;
+}
@@ -0,0 +1,109 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/peer_suggestions/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn peer_suggestions_ranking() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+    fund_and_create_utxos(node3_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+    open_channel(
+        node3_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    let payload = ProbePaymentRequest {
+        dest_pubkey: node2_pubkey.clone(),
+        amt_msat: 5000000,
+        asset_id: None,
+        asset_amount: None,
+    };
+    assert!(probe_payment(node1_addr, &payload).await.reachable);
+
+    // wait for the channel between node2 and node3 to be gossiped to node1
+    let payload = PeerSuggestionsRequest::default();
+    let t_0 = OffsetDateTime::now_utc();
+    let suggestions = loop {
+        let suggestions = peer_suggestions(node1_addr, &payload).await;
+        if suggestions.iter().any(|s| s.pubkey == node3_pubkey) {
+            break suggestions;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 60.0 {
+            panic!("node3 has not been gossiped")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    };
+
+    // node2 is the most central node and the only one that has been probed
+    assert_eq!(suggestions.len(), 2);
+    let node2 = &suggestions[0];
+    assert_eq!(node2.pubkey, node2_pubkey);
+    assert_eq!(node2.num_channels, 2);
+    assert_eq!(node2.probe_success_rate, Some(1.0));
+    assert!(node2.has_channel);
+    assert!(!node2.supports_assets);
+    let node3 = &suggestions[1];
+    assert_eq!(node3.pubkey, node3_pubkey);
+    assert_eq!(node3.num_channels, 1);
+    assert_eq!(node3.probe_success_rate, None);
+    assert!(!node3.has_channel);
+    assert!(node2.score > node3.score);
+
+    let payload = PeerSuggestionsRequest {
+        goal: Some(PeerSuggestionGoal::RoutingRevenue),
+        limit: Some(1),
+    };
+    let suggestions = peer_suggestions(node1_addr, &payload).await;
+    assert_eq!(suggestions.len(), 1);
+    assert_eq!(suggestions[0].pubkey, node2_pubkey);
+
+    // failures
+    let payload = PeerSuggestionsRequest {
+        goal: None,
+        limit: Some(0),
+    };
+    let res = reqwest::Client::new()
+        .get(format!("http://{node1_addr}/peersuggestions"))
+        .query(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "limit must be between 1 and 100",
+        "InvalidPagination",
+    )
+    .await;
+}
@@ -0,0 +1,147 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/phantom_invoice/";
+
+async fn phantom_route_hints(node_address: SocketAddr) -> Response {
+    reqwest::Client::new()
+        .get(format!("http://{node_address}/phantomroutehints"))
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn phantom_ln_invoice(
+    node_address: SocketAddr,
+    asset_id: Option<&str>,
+    route_hints: Vec<String>,
+) -> Response {
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(3000000),
+        min_amt_msat: None,
+        max_amt_msat: None,
+        expiry_sec: 900,
+        asset_id: asset_id.map(|a| a.to_string()),
+        asset_amount: asset_id.map(|_| 10),
+        route_hints: None,
+        lsp: None,
+        phantom: Some(InvoicePhantom { route_hints }),
+        label: None,
+        fallback_address_type: None,
+        ..Default::default()
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/lninvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn phantom_invoice() {
+    initialize();
+
+    let phantom_secret = [7; 32];
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let args = UserArgs {
+        storage_dir_path: test_dir_node1.clone().into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        phantom_secret: Some(phantom_secret),
+        ..Default::default()
+    };
+    let (node1_addr, _) = start_node_with_args(args, false).await;
+    let args = UserArgs {
+        storage_dir_path: test_dir_node2.clone().into(),
+        ldk_peer_listening_port: NODE2_PEER_PORT,
+        phantom_secret: Some(phantom_secret),
+        ..Default::default()
+    };
+    let (node2_addr, _) = start_node_with_args(args, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node3_addr, None).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    // node3 can only reach node2
+    open_channel(
+        node3_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    let node2_hints = _check_response_is_ok(phantom_route_hints(node2_addr).await)
+        .await
+        .json::<PhantomRouteHintsResponse>()
+        .await
+        .unwrap()
+        .route_hints;
+
+    // node1 creates an invoice that node2 can settle
+    let res = phantom_ln_invoice(node1_addr, None, vec![node2_hints.clone()]).await;
+    let invoice = _check_response_is_ok(res)
+        .await
+        .json::<LNInvoiceResponse>()
+        .await
+        .unwrap()
+        .invoice;
+    let decoded = Bolt11Invoice::from_str(&invoice).unwrap();
+    let payee_pubkey = decoded.recover_payee_pub_key().to_string();
+    assert_ne!(payee_pubkey, node1_pubkey);
+    assert_ne!(payee_pubkey, node2_pubkey);
+    assert!(decoded
+        .route_hints()
+        .iter()
+        .any(|h| h.0[0].src_node_id.to_string() == node2_pubkey));
+
+    let payment = send_payment(node3_addr, invoice).await;
+    let payment =
+        wait_for_ln_payment(node2_addr, &payment.payment_hash, HTLCStatus::Succeeded).await;
+    assert!(payment.inbound);
+    assert_eq!(payment.amt_msat, Some(3000000));
+
+    // failures
+    check_response_is_nok(
+        phantom_route_hints(node3_addr).await,
+        reqwest::StatusCode::FORBIDDEN,
+        "No phantom secret configured",
+        "PhantomNotConfigured",
+    )
+    .await;
+    check_response_is_nok(
+        phantom_ln_invoice(node3_addr, None, vec![]).await,
+        reqwest::StatusCode::FORBIDDEN,
+        "No phantom secret configured",
+        "PhantomNotConfigured",
+    )
+    .await;
+    check_response_is_nok(
+        phantom_ln_invoice(node1_addr, None, vec![s!("zz")]).await,
+        reqwest::StatusCode::BAD_REQUEST,
+        "invalid route hints zz",
+        "InvalidPhantomOptions",
+    )
+    .await;
+    check_response_is_nok(
+        phantom_ln_invoice(
+            node1_addr,
+            Some("rgb:CJkb4YZw-jRiz2sk-~PARPio-wtVYI1c-XAEYCqO-wTfvRZ8"),
+            vec![node2_hints],
+        )
+        .await,
+        reqwest::StatusCode::BAD_REQUEST,
+        "phantom invoices cannot receive RGB assets",
+        "InvalidPhantomOptions",
+    )
+    .await;
+}
@@ -0,0 +1,118 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/probe_payment/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn probe_payment_reachability() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+    fund_and_create_utxos(node3_addr, None).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+
+    let channel_12 = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+    // node2 has almost no liquidity towards node3
+    let channel_32 = open_channel(
+        node3_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        Some(2000000),
+        None,
+        None,
+    )
+    .await;
+
+    let channel_12_balance = |channels: Vec<Channel>| {
+        channels
+            .into_iter()
+            .find(|c| c.channel_id == channel_12.channel_id)
+            .unwrap()
+            .local_balance_sat
+    };
+    let initial_balance = channel_12_balance(list_channels(node1_addr).await);
+
+    // a direct peer can be reached
+    let payload = ProbePaymentRequest {
+        dest_pubkey: node2_pubkey.clone(),
+        amt_msat: 5000000,
+        asset_id: None,
+        asset_amount: None,
+    };
+    let res = probe_payment(node1_addr, &payload).await;
+    assert!(res.reachable);
+    assert_eq!(res.hops.len(), 1);
+    assert_eq!(
+        res.hops[0].short_channel_id,
+        channel_12.short_channel_id.unwrap()
+    );
+    assert_eq!(res.total_fee_msat, 0);
+    assert!(res.failed_short_channel_id.is_none());
+    assert!(res.failure.is_none());
+
+    // the probe fails at the hop lacking liquidity
+    let payload = ProbePaymentRequest {
+        dest_pubkey: node3_pubkey.clone(),
+        ..payload
+    };
+    let res = probe_payment(node1_addr, &payload).await;
+    assert!(!res.reachable);
+    assert_eq!(res.hops.len(), 2);
+    assert_eq!(res.hops[1].pubkey, node3_pubkey);
+    assert_eq!(
+        res.failed_short_channel_id,
+        Some(channel_32.short_channel_id.unwrap())
+    );
+    assert!(res.failure.is_some());
+
+    // probes don't move funds and are not recorded as payments
+    assert!(list_payments(node1_addr).await.is_empty());
+    assert!(list_payments(node2_addr).await.is_empty());
+    assert_eq!(
+        channel_12_balance(list_channels(node1_addr).await),
+        initial_balance
+    );
+
+    // failures
+    let payload = ProbePaymentRequest {
+        dest_pubkey: node1_pubkey,
+        amt_msat: 5000000,
+        asset_id: Some(s!("rgb:invalid")),
+        asset_amount: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/probepayment"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "For an RGB operation both asset_id and asset_amount must be set",
+        "IncompleteRGBInfo",
+    )
+    .await;
+}
@@ -0,0 +1,153 @@
+use bitcoin::psbt::Psbt;
+use bitcoin::OutPoint;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/psbt/";
+
+async fn broadcast_psbt_res(node_address: SocketAddr, psbt: &str) -> Response {
+    let payload = BroadcastPsbtRequest {
+        psbt: psbt.to_string(),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/broadcastpsbt"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn fund_psbt(node_address: SocketAddr, recipients: Vec<BtcRecipient>) -> String {
+    let payload = FundPsbtRequest {
+        recipients,
+        opret_commitment: false,
+        fee_rate: FEE_RATE,
+        coin_selection: None,
+        skip_sync: false,
+        totp_code: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/fundpsbt"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<FundPsbtResponse>()
+        .await
+        .unwrap()
+        .psbt
+}
+
+async fn sign_psbt_res(node_address: SocketAddr, psbt: &str) -> Response {
+    let payload = SignPsbtRequest {
+        psbt: psbt.to_string(),
+        totp_code: None,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/signpsbt"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn psbt() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    let recipients = vec![BtcRecipient {
+        address: address(node2_addr).await,
+        amount: 10_000,
+    }];
+
+    let res = broadcast_psbt_res(node1_addr, "invalid").await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid PSBT",
+        "InvalidPsbt",
+    )
+    .await;
+
+    // an unsigned PSBT cannot be broadcast
+    let unsigned_psbt = fund_psbt(node1_addr, recipients).await;
+    let res = broadcast_psbt_res(node1_addr, &unsigned_psbt).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "input 0 is not finalized",
+        "InvalidPsbt",
+    )
+    .await;
+
+    // a PSBT spending a locked UTXO is not signed
+    let mut psbt = Psbt::from_str(&unsigned_psbt).unwrap();
+    let outpoint = psbt.unsigned_tx.input[0].previous_output.to_string();
+    lock_utxo(node1_addr, &outpoint, None).await;
+    let res = sign_psbt_res(node1_addr, &unsigned_psbt).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        &format!("UTXO {outpoint} is locked"),
+        "LockedUtxo",
+    )
+    .await;
+    unlock_utxo(node1_addr, &outpoint).await;
+
+    // a PSBT spending RGB allocations without a commitment is not signed
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let colored_outpoint = list_unspents(node1_addr)
+        .await
+        .into_iter()
+        .find(|u| {
+            u.rgb_allocations
+                .iter()
+                .any(|a| a.asset_id.as_ref() == Some(&asset_id))
+        })
+        .unwrap()
+        .utxo
+        .outpoint;
+    psbt.unsigned_tx.input[0].previous_output = OutPoint::from_str(&colored_outpoint).unwrap();
+    let res = sign_psbt_res(node1_addr, &psbt.to_string()).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "holds RGB allocations but the PSBT carries no RGB commitment",
+        "CannotSignPsbt",
+    )
+    .await;
+
+    // a funded PSBT is signed and broadcast
+    let res = sign_psbt_res(node1_addr, &unsigned_psbt).await;
+    let signed_psbt = _check_response_is_ok(res)
+        .await
+        .json::<SignPsbtResponse>()
+        .await
+        .unwrap()
+        .psbt;
+    let res = broadcast_psbt_res(node2_addr, &signed_psbt).await;
+    let txid = _check_response_is_ok(res)
+        .await
+        .json::<BroadcastPsbtResponse>()
+        .await
+        .unwrap()
+        .txid;
+    mine(false);
+    assert_eq!(btc_balance(node2_addr).await.vanilla.settled, 10_000);
+    assert!(list_transactions(node2_addr)
+        .await
+        .iter()
+        .any(|t| t.txid == txid));
+
+    shutdown(&[node1_addr, node2_addr]).await;
+}
@@ -0,0 +1,117 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/read_after_write/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn read_after_write() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let blockcount = get_block_count();
+    let t_0 = OffsetDateTime::now_utc();
+    while network_info(node1_addr).await.height != blockcount {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("height is not syncing");
+        }
+    }
+
+    // a newly opened channel is immediately listed and its temporary ID is never reported as
+    // unknown while funding is in progress
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_opt_addr: format!("{node2_pubkey}@127.0.0.1:{NODE2_PEER_PORT}"),
+        capacity_sat: 100000,
+        push_msat: 0,
+        asset_amount: None,
+        asset_id: None,
+        public: true,
+        with_anchors: true,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        temporary_channel_id: None,
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/openchannel"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let temporary_channel_id = _check_response_is_ok(res)
+        .await
+        .json::<OpenChannelResponse>()
+        .await
+        .unwrap()
+        .temporary_channel_id;
+    let channels = list_channels(node1_addr).await;
+    assert!(channels.iter().any(|c| c.peer_pubkey == node2_pubkey));
+    let channel_id = loop {
+        let payload = GetChannelIdRequest {
+            temporary_channel_id: temporary_channel_id.clone(),
+        };
+        let res = reqwest::Client::new()
+            .post(format!("http://{node1_addr}/getchannelid"))
+            .json(&payload)
+            .send()
+            .await
+            .unwrap();
+        if res.status() == reqwest::StatusCode::OK {
+            break res.json::<GetChannelIdResponse>().await.unwrap().channel_id;
+        }
+        let api_error_response = res.json::<APIErrorResponse>().await.unwrap();
+        assert_eq!(api_error_response.name, "ChannelFundingInProgress");
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    };
+    mine_n_blocks(false, 6);
+    wait_for_usable_channels(node1_addr, 1).await;
+    let channels = list_channels(node1_addr).await;
+    assert!(channels.iter().any(|c| c.channel_id == channel_id));
+
+    // a newly created invoice is immediately visible to the payee
+    let LNInvoiceResponse { invoice } = ln_invoice(node2_addr, None, None, None, 900).await;
+    let decoded = decode_ln_invoice(node2_addr, &invoice).await;
+    let payment = get_payment(node2_addr, &decoded.payment_hash).await;
+    assert!(payment.inbound);
+    assert_eq!(payment.status, HTLCStatus::Pending);
+    assert!(list_payments(node2_addr)
+        .await
+        .iter()
+        .any(|p| p.payment_hash == decoded.payment_hash));
+    assert_eq!(
+        invoice_status(node2_addr, &invoice).await,
+        InvoiceStatus::Pending
+    );
+
+    // a newly sent payment is immediately visible to the payer
+    let payment_hash = send_payment_raw(node1_addr, invoice)
+        .await
+        .payment_hash
+        .unwrap();
+    let payment = get_payment(node1_addr, &payment_hash).await;
+    assert!(!payment.inbound);
+    assert!(list_payments(node1_addr)
+        .await
+        .iter()
+        .any(|p| p.payment_hash == payment_hash));
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Succeeded).await;
+}
@@ -0,0 +1,183 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/rebalance/";
+
+async fn rebalance_raw(node_address: SocketAddr, payload: &RebalanceRequest) -> Response {
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/rebalance"))
+        .json(payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+fn local_balance_sat(channels: &[Channel], channel_id: &str) -> u64 {
+    channels
+        .iter()
+        .find(|c| c.channel_id == channel_id)
+        .unwrap()
+        .local_balance_sat
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn rebalance() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+    fund_and_create_utxos(node3_addr, None).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+
+    let channel_12 = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+    let channel_23 = open_channel(
+        node2_addr,
+        &node3_pubkey,
+        Some(NODE3_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+    let channel_31 = open_channel(
+        node3_addr,
+        &node1_pubkey,
+        Some(NODE1_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // move liquidity from the channel with node2 to the one with node3
+    let channels_before = list_channels(node1_addr).await;
+    let amt_msat = 3000000;
+    let payload = RebalanceRequest {
+        amt_msat,
+        outgoing_channel_id: channel_12.channel_id.clone(),
+        incoming_channel_id: channel_31.channel_id.clone(),
+        max_fee_msat: 10000,
+        totp_code: None,
+    };
+    let res = _check_response_is_ok(rebalance_raw(node1_addr, &payload).await)
+        .await
+        .json::<RebalanceResponse>()
+        .await
+        .unwrap();
+    assert_eq!(res.status, HTLCStatus::Pending);
+    let hop_scids: Vec<u64> = res.hops.iter().map(|h| h.short_channel_id).collect();
+    assert_eq!(
+        hop_scids,
+        vec![
+            channel_12.short_channel_id.unwrap(),
+            channel_23.short_channel_id.unwrap(),
+            channel_31.short_channel_id.unwrap(),
+        ]
+    );
+    assert_eq!(res.hops.last().unwrap().pubkey, node1_pubkey);
+    assert_eq!(res.hops.last().unwrap().fee_msat, amt_msat);
+    assert!(res.total_fee_msat > 0);
+    assert!(res.total_fee_msat <= payload.max_fee_msat);
+
+    // both the sent and the received payment succeed
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let payments: Vec<Payment> = list_payments(node1_addr)
+            .await
+            .into_iter()
+            .filter(|p| p.payment_hash == res.payment_hash)
+            .collect();
+        if payments.len() == 2 && payments.iter().all(|p| p.status == HTLCStatus::Succeeded) {
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 40.0 {
+            panic!("rebalance payment has not succeeded")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+    let channels_after = list_channels(node1_addr).await;
+    let moved_sat = amt_msat / 1000;
+    assert!(
+        local_balance_sat(&channels_after, &channel_12.channel_id)
+            <= local_balance_sat(&channels_before, &channel_12.channel_id) - moved_sat
+    );
+    assert_eq!(
+        local_balance_sat(&channels_after, &channel_31.channel_id),
+        local_balance_sat(&channels_before, &channel_31.channel_id) + moved_sat
+    );
+
+    // failures
+    let res = rebalance_raw(
+        node1_addr,
+        &RebalanceRequest {
+            incoming_channel_id: channel_12.channel_id.clone(),
+            ..payload
+        },
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "the outgoing and incoming channels need to be different",
+        "InvalidRebalance",
+    )
+    .await;
+    let res = rebalance_raw(
+        node1_addr,
+        &RebalanceRequest {
+            amt_msat,
+            outgoing_channel_id: channel_12.channel_id.clone(),
+            incoming_channel_id: channel_23.channel_id.clone(),
+            max_fee_msat: 10000,
+            totp_code: None,
+        },
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown channel ID",
+        "UnknownChannelId",
+    )
+    .await;
+    let res = rebalance_raw(
+        node1_addr,
+        &RebalanceRequest {
+            amt_msat,
+            outgoing_channel_id: channel_12.channel_id.clone(),
+            incoming_channel_id: channel_31.channel_id.clone(),
+            max_fee_msat: 0,
+            totp_code: None,
+        },
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "exceeds max_fee_msat",
+        "InvalidRebalance",
+    )
+    .await;
+}
@@ -0,0 +1,106 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/reload_config/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn reload_config_settings() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let config_path = Path::new(&test_dir_node1).join("config.json");
+
+    // no config file
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/reloadconfig"))
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "no config file found",
+        "InvalidConfig",
+    )
+    .await;
+
+    // hot-reloadable settings are applied, the others are skipped
+    std::fs::write(
+        &config_path,
+        r#"{"max_route_hints": 0, "gossip_bandwidth_cap": 100000, "log_level": "debug", "network": "signet"}"#,
+    )
+    .unwrap();
+    let ReloadConfigResponse {
+        mut applied,
+        skipped,
+    } = reload_config(node1_addr).await;
+    applied.sort();
+    assert_eq!(
+        applied,
+        vec![
+            s!("gossip_bandwidth_cap"),
+            s!("log_level"),
+            s!("max_route_hints")
+        ]
+    );
+    assert_eq!(skipped, vec![s!("network")]);
+    assert_eq!(node_info(node1_addr).await.network, BitcoinNetwork::Regtest);
+
+    // the hook command can be set and removed
+    let hook_path = PathBuf::from(format!("{TEST_DIR_BASE}hook.sh"));
+    std::fs::write(&hook_path, "#!/bin/sh\ncat > /dev/null\n").unwrap();
+    let hook_path = std::fs::canonicalize(&hook_path).unwrap();
+    std::fs::write(
+        &config_path,
+        format!(r#"{{"hook_command": "{}"}}"#, hook_path.display()),
+    )
+    .unwrap();
+    let ReloadConfigResponse { applied, .. } = reload_config(node1_addr).await;
+    assert_eq!(applied, vec![s!("hook_command")]);
+    std::fs::write(&config_path, r#"{"hook_command": null}"#).unwrap();
+    let ReloadConfigResponse { applied, .. } = reload_config(node1_addr).await;
+    assert_eq!(applied, vec![s!("hook_command")]);
+
+    // failures
+    std::fs::write(&config_path, r#"{"max_route_hints": 1, "unknown": true}"#).unwrap();
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/reloadconfig"))
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "unknown setting unknown",
+        "InvalidConfig",
+    )
+    .await;
+    std::fs::write(&config_path, r#"{"max_route_hints": 300}"#).unwrap();
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/reloadconfig"))
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "invalid value for max_route_hints",
+        "InvalidConfig",
+    )
+    .await;
+    std::fs::write(&config_path, r#"{"hook_command": "/nonexistent/hook"}"#).unwrap();
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/reloadconfig"))
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "/nonexistent/hook is not a file",
+        "InvalidConfig",
+    )
+    .await;
+}
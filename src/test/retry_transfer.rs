@@ -0,0 +1,98 @@
+use crate::routes::{TransferKind, TransferStatus};
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/retry_transfer/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn retry_transfer_success() {
+    initialize();
+
+    let test_dir_base = format!("{TEST_DIR_BASE}retry_transfer_success/");
+    let test_dir_node1 = format!("{test_dir_base}node1");
+    let test_dir_node2 = format!("{test_dir_base}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let recipient_id = rgb_invoice(node2_addr, None, false).await.recipient_id;
+    send_asset(
+        node1_addr,
+        &asset_id,
+        Assignment::Fungible(100),
+        recipient_id,
+        None,
+    )
+    .await;
+
+    let transfers = list_transfers(node1_addr, &asset_id).await;
+    let send_idx = transfers
+        .iter()
+        .find(|t| t.kind == TransferKind::Send)
+        .unwrap()
+        .idx;
+    let issuance_idx = transfers
+        .iter()
+        .find(|t| t.kind == TransferKind::Issuance)
+        .unwrap()
+        .idx;
+
+    // posting the consignment again doesn't disturb the transfer
+    retry_transfer(node1_addr, send_idx).await;
+    refresh_transfers(node2_addr).await;
+    mine(false);
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node1_addr).await;
+    wait_for_balance(node2_addr, &asset_id, 100).await;
+    let transfer = transfer_detail(node1_addr, send_idx).await.transfer;
+    assert_eq!(transfer.status, TransferStatus::Settled);
+
+    // failures
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/retrytransfer"))
+        .json(&RetryTransferRequest { idx: send_idx })
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "only sends waiting for the counterparty can be retried",
+        "CannotRetryTransfer",
+    )
+    .await;
+
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/retrytransfer"))
+        .json(&RetryTransferRequest { idx: issuance_idx })
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "only sends waiting for the counterparty can be retried",
+        "CannotRetryTransfer",
+    )
+    .await;
+
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/retrytransfer"))
+        .json(&RetryTransferRequest { idx: 9999 })
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Transfer not found: 9999",
+        "TransferNotFound",
+    )
+    .await;
+}
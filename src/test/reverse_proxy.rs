@@ -0,0 +1,81 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/reverse_proxy/";
+
+const ORIGIN: &str = "https://wallet.example.com";
+
+async fn node_info_res(node_address: SocketAddr, path: &str, client_ip: &str) -> Response {
+    reqwest::Client::new()
+        .get(format!("http://{node_address}{path}"))
+        .header("X-Forwarded-For", format!("203.0.113.1, {client_ip}"))
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn preflight_res(node_address: SocketAddr, origin: &str) -> Response {
+    reqwest::Client::new()
+        .request(
+            reqwest::Method::OPTIONS,
+            format!("http://{node_address}/api/v1/nodeinfo"),
+        )
+        .header("Origin", origin)
+        .header("Access-Control-Request-Method", "GET")
+        .header("Access-Control-Request-Headers", "authorization")
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn reverse_proxy() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+
+    let _ = std::fs::remove_dir_all(&test_dir_node1);
+    let args = UserArgs {
+        storage_dir_path: test_dir_node1.clone().into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        cors_allowed_origins: Some(vec![ORIGIN.parse().unwrap()]),
+        url_prefix: Some(s!("/api/v1")),
+        trust_forwarded_for: true,
+        max_requests_per_minute: 2,
+        ..Default::default()
+    };
+    let node_address = start_daemon_with_args(args).await;
+
+    // only the configured origins can call the APIs from a browser
+    let res = preflight_res(node_address, ORIGIN).await;
+    assert_eq!(
+        res.headers()
+            .get("access-control-allow-origin")
+            .and_then(|v| v.to_str().ok()),
+        Some(ORIGIN)
+    );
+    let res = preflight_res(node_address, "https://other.example.com").await;
+    assert!(res.headers().get("access-control-allow-origin").is_none());
+
+    // the APIs are only served under the URL prefix
+    let res = node_info_res(node_address, "/nodeinfo", "198.51.100.1").await;
+    assert_eq!(res.status(), reqwest::StatusCode::NOT_FOUND);
+
+    // requests are rate limited by the client address reported by the proxy
+    for _ in 0..2 {
+        let res = node_info_res(node_address, "/api/v1/nodeinfo", "198.51.100.2").await;
+        assert_ne!(res.status(), reqwest::StatusCode::NOT_FOUND);
+        assert_ne!(res.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+    }
+    let res = node_info_res(node_address, "/api/v1/nodeinfo", "198.51.100.2").await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::TOO_MANY_REQUESTS,
+        "Too many requests",
+        "RateLimited",
+    )
+    .await;
+    let res = node_info_res(node_address, "/api/v1/nodeinfo", "198.51.100.3").await;
+    assert_ne!(res.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+}
@@ -0,0 +1,148 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/route_hints/";
+
+async fn ln_invoice_with_route_hints(
+    node_address: SocketAddr,
+    route_hints: Option<Vec<InvoiceRouteHint>>,
+) -> Response {
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(3000000),
+        min_amt_msat: None,
+        max_amt_msat: None,
+        expiry_sec: 900,
+        asset_id: None,
+        asset_amount: None,
+        route_hints,
+        lsp: None,
+        phantom: None,
+        label: None,
+        fallback_address_type: None,
+        ..Default::default()
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/lninvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn route_hints() {
+    initialize();
+
+    let test_dir_base = format!("{TEST_DIR_BASE}route_hints/");
+    let test_dir_node1 = format!("{test_dir_base}node1");
+    let test_dir_node2 = format!("{test_dir_base}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel_raw(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        Some(50_000_000),
+        None,
+        None,
+        None,
+        None,
+        None,
+        true,
+        false,
+    )
+    .await
+    .unwrap();
+    assert!(!channel.public);
+
+    // node2 only has a private channel, so a hint for it gets added automatically
+    let LNInvoiceResponse { invoice } = ln_invoice(node2_addr, None, None, None, 900).await;
+    let decoded = Bolt11Invoice::from_str(&invoice).unwrap();
+    let hints = decoded.route_hints();
+    assert_eq!(hints.len(), 1);
+    assert_eq!(hints[0].0.len(), 1);
+    assert_eq!(hints[0].0[0].src_node_id.to_string(), node1_pubkey);
+    assert_eq!(decoded.recover_payee_pub_key().to_string(), node2_pubkey);
+    let decoded_res = decode_ln_invoice(node1_addr, &invoice).await;
+    assert_eq!(decoded_res.amt_msat, Some(3000000));
+    send_payment(node1_addr, invoice).await;
+
+    // explicit hints override the automatic ones
+    let hint = InvoiceRouteHint {
+        hops: vec![InvoiceRouteHintHop {
+            src_node_id: node1_pubkey.clone(),
+            short_channel_id: 42,
+            fee_base_msat: 1000,
+            fee_proportional_millionths: 100,
+            cltv_expiry_delta: 144,
+            htlc_minimum_msat: None,
+            htlc_maximum_msat: Some(10_000_000),
+        }],
+    };
+    let res = ln_invoice_with_route_hints(node2_addr, Some(vec![hint])).await;
+    let invoice = _check_response_is_ok(res)
+        .await
+        .json::<LNInvoiceResponse>()
+        .await
+        .unwrap()
+        .invoice;
+    let decoded = Bolt11Invoice::from_str(&invoice).unwrap();
+    let hints = decoded.route_hints();
+    assert_eq!(hints.len(), 1);
+    assert_eq!(hints[0].0[0].short_channel_id, 42);
+    assert_eq!(hints[0].0[0].fees.base_msat, 1000);
+    assert_eq!(hints[0].0[0].htlc_maximum_msat, Some(10_000_000));
+
+    // an empty override disables hints
+    let res = ln_invoice_with_route_hints(node2_addr, Some(vec![])).await;
+    let invoice = _check_response_is_ok(res)
+        .await
+        .json::<LNInvoiceResponse>()
+        .await
+        .unwrap()
+        .invoice;
+    assert!(Bolt11Invoice::from_str(&invoice)
+        .unwrap()
+        .route_hints()
+        .is_empty());
+
+    // failures
+    let res =
+        ln_invoice_with_route_hints(node2_addr, Some(vec![InvoiceRouteHint { hops: vec![] }]))
+            .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "a route hint needs at least a hop",
+        "InvalidRouteHint",
+    )
+    .await;
+    let hint = InvoiceRouteHint {
+        hops: vec![InvoiceRouteHintHop {
+            src_node_id: s!("invalid"),
+            short_channel_id: 42,
+            fee_base_msat: 0,
+            fee_proportional_millionths: 0,
+            cltv_expiry_delta: 144,
+            htlc_minimum_msat: None,
+            htlc_maximum_msat: None,
+        }],
+    };
+    let res = ln_invoice_with_route_hints(node2_addr, Some(vec![hint])).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "invalid pubkey invalid",
+        "InvalidRouteHint",
+    )
+    .await;
+}
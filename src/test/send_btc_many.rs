@@ -0,0 +1,141 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/send_btc_many/";
+
+async fn send_btc_many_res(
+    node_address: SocketAddr,
+    recipients: Vec<BtcRecipient>,
+    asset_recipients: Vec<AssetRecipient>,
+) -> Response {
+    let payload = SendBtcManyRequest {
+        recipients,
+        asset_recipients,
+        fee_rate: FEE_RATE,
+        donation: true,
+        min_confirmations: 1,
+        skip_sync: false,
+        coin_selection: None,
+        totp_code: None,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/sendbtcmany"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn send_btc_many() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+    fund_and_create_utxos(node3_addr, None).await;
+
+    let res = send_btc_many_res(node1_addr, vec![], vec![]).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "no recipient provided",
+        "InvalidRecipients",
+    )
+    .await;
+
+    // BTC recipients are paid by a single transaction
+    let node2_balance = btc_balance(node2_addr).await.vanilla.settled;
+    let node3_balance = btc_balance(node3_addr).await.vanilla.settled;
+    let recipients = vec![
+        BtcRecipient {
+            address: address(node2_addr).await,
+            amount: 10_000,
+        },
+        BtcRecipient {
+            address: address(node3_addr).await,
+            amount: 20_000,
+        },
+    ];
+    let res = send_btc_many_res(node1_addr, recipients, vec![]).await;
+    let txid = _check_response_is_ok(res)
+        .await
+        .json::<SendBtcManyResponse>()
+        .await
+        .unwrap()
+        .txid;
+    mine(false);
+    assert_eq!(
+        btc_balance(node2_addr).await.vanilla.settled,
+        node2_balance + 10_000
+    );
+    assert_eq!(
+        btc_balance(node3_addr).await.vanilla.settled,
+        node3_balance + 20_000
+    );
+    assert!(list_transactions(node2_addr)
+        .await
+        .iter()
+        .any(|t| t.txid == txid));
+    assert!(list_transactions(node3_addr)
+        .await
+        .iter()
+        .any(|t| t.txid == txid));
+
+    // BTC and asset recipients cannot be mixed
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let res = send_btc_many_res(
+        node1_addr,
+        vec![BtcRecipient {
+            address: address(node2_addr).await,
+            amount: 10_000,
+        }],
+        vec![AssetRecipient {
+            asset_id: asset_id.clone(),
+            assignment: Assignment::Fungible(100),
+            recipient_id: rgb_invoice(node2_addr, None, false).await.recipient_id,
+            witness_data: None,
+            transport_endpoints: vec![PROXY_ENDPOINT_LOCAL.to_string()],
+        }],
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "BTC and asset recipients cannot be paid in the same transaction",
+        "InvalidRecipients",
+    )
+    .await;
+
+    // asset recipients are paid by a single transaction as well
+    let mut asset_recipients = vec![];
+    for (node_addr, amount) in [(node2_addr, 100), (node3_addr, 200)] {
+        asset_recipients.push(AssetRecipient {
+            asset_id: asset_id.clone(),
+            assignment: Assignment::Fungible(amount),
+            recipient_id: rgb_invoice(node_addr, None, false).await.recipient_id,
+            witness_data: None,
+            transport_endpoints: vec![PROXY_ENDPOINT_LOCAL.to_string()],
+        });
+    }
+    let res = send_btc_many_res(node1_addr, vec![], asset_recipients).await;
+    _check_response_is_ok(res).await;
+    mine(false);
+    for node_addr in [node2_addr, node3_addr] {
+        refresh_transfers(node_addr).await;
+        refresh_transfers(node_addr).await;
+    }
+    refresh_transfers(node1_addr).await;
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 700);
+    assert_eq!(asset_balance_spendable(node2_addr, &asset_id).await, 100);
+    assert_eq!(asset_balance_spendable(node3_addr, &asset_id).await, 200);
+
+    shutdown(&[node1_addr, node2_addr, node3_addr]).await;
+}
@@ -0,0 +1,120 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/send_to_route/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn send_to_route_queried() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+    fund_and_create_utxos(node3_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+
+    let channel_12 = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+    let channel_23 = open_channel(
+        node2_addr,
+        &node3_pubkey,
+        Some(NODE3_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // routes to node3 go through node2
+    let amt_msat = 3000000;
+    let payload = QueryRoutesRequest {
+        dest_pubkey: node3_pubkey.clone(),
+        amt_msat,
+        asset_id: None,
+        asset_amount: None,
+        max_fee_msat: None,
+        max_cltv_expiry_delta: None,
+        max_routes: None,
+    };
+    let QueryRoutesResponse { routes } = query_routes(node1_addr, &payload).await;
+    assert_eq!(routes.len(), 1);
+    let route = routes.first().unwrap();
+    assert_eq!(route.hops.len(), 2);
+    assert_eq!(route.hops[0].pubkey, node2_pubkey);
+    assert_eq!(
+        route.hops[0].short_channel_id,
+        channel_12.short_channel_id.unwrap()
+    );
+    assert_eq!(route.hops[1].pubkey, node3_pubkey);
+    assert_eq!(
+        route.hops[1].short_channel_id,
+        channel_23.short_channel_id.unwrap()
+    );
+    assert_eq!(route.hops[1].fee_msat, amt_msat);
+    assert_eq!(route.total_amt_msat, amt_msat + route.total_fee_msat);
+
+    // pay an invoice on the queried route
+    let LNInvoiceResponse { invoice } = ln_invoice(node3_addr, None, None, None, 900).await;
+    let hops = route.hops.iter().map(|h| h.pubkey.clone()).collect();
+    let res = send_to_route(node1_addr, invoice, hops).await;
+    let payment_hash = res.payment_hash.unwrap();
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Succeeded).await;
+    wait_for_ln_payment(node3_addr, &payment_hash, HTLCStatus::Succeeded).await;
+
+    // failures
+    let payload = QueryRoutesRequest {
+        max_routes: Some(0),
+        ..payload
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/queryroutes"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "max_routes must be between 1 and",
+        "InvalidPaymentLimits",
+    )
+    .await;
+    let LNInvoiceResponse { invoice } = ln_invoice(node3_addr, None, None, None, 900).await;
+    let payload = SendToRouteRequest {
+        invoice,
+        amt_msat: None,
+        hops: vec![node2_pubkey],
+        totp_code: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/sendtoroute"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "the last hop needs to be the invoice payee",
+        "InvalidRoute",
+    )
+    .await;
+}
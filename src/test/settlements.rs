@@ -0,0 +1,190 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/settlements/";
+
+async fn labeled_ln_invoice(node_address: SocketAddr, amt_msat: u64, label: &str) -> String {
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(amt_msat),
+        min_amt_msat: None,
+        max_amt_msat: None,
+        expiry_sec: 900,
+        asset_id: None,
+        asset_amount: None,
+        route_hints: None,
+        lsp: None,
+        phantom: None,
+        label: Some(label.to_string()),
+        fallback_address_type: None,
+        ..Default::default()
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/lninvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<LNInvoiceResponse>()
+        .await
+        .unwrap()
+        .invoice
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn settlements_report() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let report = settlements(node2_addr, None).await;
+    assert!(report.totals.is_empty());
+    let period = report.period;
+    assert_eq!(period.len(), 7);
+
+    let res = reqwest::Client::new()
+        .get(format!("http://{node2_addr}/settlements?period=2024-13"))
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "2024-13 is not a month in the YYYY-MM format",
+        "InvalidPeriod",
+    )
+    .await;
+
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(3000000),
+        min_amt_msat: None,
+        max_amt_msat: None,
+        expiry_sec: 900,
+        asset_id: None,
+        asset_amount: None,
+        route_hints: None,
+        lsp: None,
+        phantom: None,
+        label: Some(" ".to_string()),
+        fallback_address_type: None,
+        ..Default::default()
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/lninvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "label cannot be empty",
+        "InvalidLabel",
+    )
+    .await;
+
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // settled invoices get grouped by label
+    for (amt_msat, label) in [(3000000, "store-a"), (4000000, "store-a"), (5000000, "")] {
+        let invoice = if label.is_empty() {
+            ln_invoice(node2_addr, Some(amt_msat), None, None, 900)
+                .await
+                .invoice
+        } else {
+            labeled_ln_invoice(node2_addr, amt_msat, label).await
+        };
+        let payment_hash = decode_ln_invoice(node2_addr, &invoice).await.payment_hash;
+        send_payment(node1_addr, invoice).await;
+        wait_for_ln_payment(node2_addr, &payment_hash, HTLCStatus::Succeeded).await;
+    }
+
+    // received asset transfers get grouped by label and asset
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let payload = RgbInvoiceRequest {
+        min_confirmations: 1,
+        asset_id: None,
+        assignment: None,
+        duration_seconds: None,
+        witness: false,
+        label: Some(s!("store-b")),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/rgbinvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    let recipient_id = _check_response_is_ok(res)
+        .await
+        .json::<RgbInvoiceResponse>()
+        .await
+        .unwrap()
+        .recipient_id;
+    send_asset(
+        node1_addr,
+        &asset_id,
+        Assignment::Fungible(400),
+        recipient_id,
+        None,
+    )
+    .await;
+    mine(false);
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node2_addr).await;
+    refresh_transfers(node1_addr).await;
+
+    let report = settlements(node2_addr, Some(&period)).await;
+    assert_eq!(report.period, period);
+    assert_eq!(report.totals.len(), 3);
+    let unlabeled = &report.totals[0];
+    assert!(unlabeled.label.is_none());
+    assert!(unlabeled.asset_id.is_none());
+    assert_eq!(unlabeled.settled_invoices, 1);
+    assert_eq!(unlabeled.amt_msat, 5000000);
+    let store_a = &report.totals[1];
+    assert_eq!(store_a.label, Some(s!("store-a")));
+    assert_eq!(store_a.settled_invoices, 2);
+    assert_eq!(store_a.amt_msat, 7000000);
+    assert_eq!(store_a.received_transfers, 0);
+    let store_b = &report.totals[2];
+    assert_eq!(store_b.label, Some(s!("store-b")));
+    assert_eq!(store_b.asset_id, Some(asset_id));
+    assert_eq!(store_b.settled_invoices, 0);
+    assert_eq!(store_b.received_transfers, 1);
+    assert_eq!(store_b.asset_amount, 400);
+
+    // the payer has nothing settled and other periods are empty
+    assert!(settlements(node1_addr, Some(&period))
+        .await
+        .totals
+        .is_empty());
+    assert!(settlements(node2_addr, Some("2020-01"))
+        .await
+        .totals
+        .is_empty());
+
+    // the rollups are persisted
+    shutdown(&[node2_addr]).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, true).await;
+    assert_eq!(settlements(node2_addr, Some(&period)).await.totals.len(), 3);
+}
@@ -0,0 +1,92 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/shutdown_drain/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn shutdown_drain() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let args = UserArgs {
+        storage_dir_path: test_dir_node2.clone().into(),
+        ldk_peer_listening_port: NODE2_PEER_PORT,
+        shutdown_drain_timeout_sec: 60,
+        ..Default::default()
+    };
+    let (node2_addr, _) = start_node_with_args(args, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        Some(100000),
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // node2 holds an incoming HTLC when asked to shut down
+    let HodlInvoiceResponse {
+        invoice,
+        payment_hash,
+        payment_preimage,
+    } = hodl_invoice(node2_addr, Some(5000000), None, None, None, true).await;
+    send_payment_raw(node1_addr, invoice.clone()).await;
+    wait_for_invoice_status(node2_addr, &invoice, InvoiceStatus::Held).await;
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/shutdown"))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+
+    // new payments are refused while draining
+    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    let payload = LNInvoiceRequest {
+        amt_msat: Some(3000000),
+        min_amt_msat: None,
+        max_amt_msat: None,
+        expiry_sec: 900,
+        asset_id: None,
+        asset_amount: None,
+        route_hints: None,
+        lsp: None,
+        phantom: None,
+        label: None,
+        fallback_address_type: None,
+        ..Default::default()
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/lninvoice"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::SERVICE_UNAVAILABLE,
+        "The node is shutting down",
+        "ShuttingDown",
+    )
+    .await;
+
+    // the held HTLC can still be settled, after which the node shuts down
+    settle_invoice(node2_addr, &payment_hash, &payment_preimage.unwrap()).await;
+    wait_for_ln_payment(node1_addr, &payment_hash, HTLCStatus::Succeeded).await;
+    let t_0 = OffsetDateTime::now_utc();
+    while TcpListener::bind(node2_addr).await.is_err() {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("node has not shut down after draining")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
@@ -0,0 +1,43 @@
+use super::*;
+
+use crate::error::AppError;
+use crate::simulation::{run_simulation, SimulationConfig};
+
+#[test]
+#[traced_test]
+fn simulation() {
+    let config = SimulationConfig {
+        nodes: 10,
+        channels_per_node: 2,
+        payments: 100,
+        ..Default::default()
+    };
+    let report = run_simulation(config.clone()).unwrap();
+    assert_eq!(report.nodes, 10);
+    assert!(report.channels >= 10);
+    assert_eq!(report.payments, 100);
+    assert_eq!(report.succeeded + report.failed, 100);
+    assert!(report.succeeded > 0);
+    assert!(report.avg_attempts >= 1.0);
+    assert!(report.latency_ms.p50 <= report.latency_ms.p90);
+    assert!(report.latency_ms.p99 <= report.latency_ms.max);
+
+    // the same seed gives the same results
+    let rerun = run_simulation(config.clone()).unwrap();
+    assert_eq!(rerun.succeeded, report.succeeded);
+    assert_eq!(rerun.latency_ms.max, report.latency_ms.max);
+
+    // payments exceeding the asset liquidity of the channels always fail
+    let report = run_simulation(SimulationConfig {
+        payment_asset_amount: Some(1000),
+        ..config.clone()
+    })
+    .unwrap();
+    assert_eq!(report.succeeded, 0);
+
+    let res = run_simulation(SimulationConfig {
+        channels_per_node: 10,
+        ..config
+    });
+    assert!(matches!(res, Err(AppError::InvalidSimulationConfig(_))));
+}
@@ -0,0 +1,107 @@
+use lightning::util::persist::CHANNEL_MANAGER_PERSISTENCE_KEY;
+
+use crate::error::AppError;
+use crate::store::{check_storage_backend, migrate_storage, SQLITE_STORE_FNAME};
+use crate::utils::LDK_DIR;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/sqlite_store/";
+
+fn sqlite_args(node_test_dir: &str, node_peer_port: u16) -> UserArgs {
+    UserArgs {
+        storage_dir_path: node_test_dir.into(),
+        ldk_peer_listening_port: node_peer_port,
+        storage_backend: StorageBackend::Sqlite,
+        ..Default::default()
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn sqlite_store() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) =
+        start_node_with_args(sqlite_args(&test_dir_node1, NODE1_PEER_PORT), false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+    let LNInvoiceResponse { invoice } =
+        ln_invoice(node2_addr, None, Some(&asset_id), Some(100), 900).await;
+    let payment = send_payment(node1_addr, invoice).await;
+
+    shutdown(&[node1_addr, node2_addr]).await;
+
+    // the node data is kept in the database, so the flat-file backend would start from scratch
+    let node1_ldk_dir = Path::new(&test_dir_node1).join(LDK_DIR);
+    assert!(node1_ldk_dir.join(SQLITE_STORE_FNAME).is_file());
+    assert!(!node1_ldk_dir.join(CHANNEL_MANAGER_PERSISTENCE_KEY).exists());
+    let err =
+        check_storage_backend(Path::new(&test_dir_node1), StorageBackend::Filesystem).unwrap_err();
+    assert!(matches!(err, AppError::InvalidStorageBackend(_)));
+
+    // the flat files of node 2 are moved to a database
+    let node2_ldk_dir = Path::new(&test_dir_node2).join(LDK_DIR);
+    assert!(node2_ldk_dir
+        .join(CHANNEL_MANAGER_PERSISTENCE_KEY)
+        .is_file());
+    let migrated = migrate_storage(Path::new(&test_dir_node2), StorageBackend::Sqlite).unwrap();
+    assert!(migrated > 0);
+    assert!(node2_ldk_dir.join(SQLITE_STORE_FNAME).is_file());
+    assert!(!node2_ldk_dir.join(CHANNEL_MANAGER_PERSISTENCE_KEY).exists());
+    let err = migrate_storage(Path::new(&test_dir_node2), StorageBackend::Sqlite).unwrap_err();
+    assert!(matches!(err, AppError::InvalidStorageBackend(_)));
+
+    // both nodes get their channel and payment back
+    let (node1_addr, _) =
+        start_node_with_args(sqlite_args(&test_dir_node1, NODE1_PEER_PORT), true).await;
+    let (node2_addr, _) =
+        start_node_with_args(sqlite_args(&test_dir_node2, NODE2_PEER_PORT), true).await;
+    for node_addr in [node1_addr, node2_addr] {
+        let channels = list_channels(node_addr).await;
+        assert_eq!(channels.len(), 1);
+        assert_eq!(channels[0].channel_id, channel.channel_id);
+        assert!(list_payments(node_addr)
+            .await
+            .iter()
+            .any(|p| p.payment_hash == payment.payment_hash));
+    }
+
+    close_channel(node1_addr, &channel.channel_id, &node2_pubkey, false).await;
+    wait_for_balance(node1_addr, &asset_id, 900).await;
+    wait_for_balance(node2_addr, &asset_id, 100).await;
+
+    shutdown(&[node1_addr, node2_addr]).await;
+
+    // node 1 goes back to flat files
+    migrate_storage(Path::new(&test_dir_node1), StorageBackend::Filesystem).unwrap();
+    assert!(!node1_ldk_dir.join(SQLITE_STORE_FNAME).exists());
+    assert!(node1_ldk_dir
+        .join(CHANNEL_MANAGER_PERSISTENCE_KEY)
+        .is_file());
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, true).await;
+    assert!(list_payments(node1_addr)
+        .await
+        .iter()
+        .any(|p| p.payment_hash == payment.payment_hash));
+
+    shutdown(&[node1_addr]).await;
+}
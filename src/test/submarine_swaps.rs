@@ -0,0 +1,155 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/submarine_swaps/";
+
+/// Start a swap provider answering submarine swaps with a swap tree that doesn't match the swap
+/// terms and refusing reverse swaps
+async fn start_swap_provider() -> SocketAddr {
+    let router = axum::Router::new()
+        .route(
+            "/v2/swap/submarine",
+            axum::routing::post(|| async {
+                axum::Json(serde_json::json!({
+                    "id": "submarine1",
+                    "address": "bcrt1p2ds5d9g5jt4jcu0hzt3yxy36q5xxsclw2cnw8d4ymz7s6hmsnyjsyjyqfn",
+                    "swapTree": {
+                        "claimLeaf": {"version": 192, "output": "51"},
+                        "refundLeaf": {"version": 192, "output": "51"},
+                    },
+                    "claimPublicKey":
+                        "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+                    "timeoutBlockHeight": 1000,
+                    "expectedAmount": 50100,
+                }))
+            }),
+        )
+        .route(
+            "/v2/swap/reverse",
+            axum::routing::post(|| async {
+                (
+                    axum::http::StatusCode::BAD_REQUEST,
+                    axum::Json(serde_json::json!({"error": "invoice amount too low"})),
+                )
+            }),
+        );
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let provider_addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    provider_addr
+}
+
+async fn swap_in_nok(
+    node_address: SocketAddr,
+    max_fee_sat: Option<u64>,
+    expected_status: reqwest::StatusCode,
+    expected_message: &str,
+    expected_name: &str,
+) {
+    let payload = SwapInRequest {
+        amt_sat: 50000,
+        fee_rate: FEE_RATE,
+        max_fee_sat,
+        skip_sync: false,
+        totp_code: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/swapin"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(res, expected_status, expected_message, expected_name).await;
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn submarine_swaps() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let provider_addr = start_swap_provider().await;
+    let args = UserArgs {
+        storage_dir_path: test_dir_node2.clone().into(),
+        ldk_peer_listening_port: NODE2_PEER_PORT,
+        swap_provider_url: Some(format!("http://{provider_addr}/")),
+        ..Default::default()
+    };
+    let (node2_addr, _) = start_node_with_args(args, false).await;
+
+    // swaps need a provider
+    swap_in_nok(
+        node1_addr,
+        None,
+        reqwest::StatusCode::FORBIDDEN,
+        "No swap provider configured",
+        "SwapProviderNotConfigured",
+    )
+    .await;
+    assert!(list_submarine_swaps(node1_addr).await.is_empty());
+
+    // the provider fee is checked before locking any funds
+    swap_in_nok(
+        node2_addr,
+        Some(50),
+        reqwest::StatusCode::FORBIDDEN,
+        "the swap fee of 100 sats exceeds max_fee_sat",
+        "MaxFeeExceeded",
+    )
+    .await;
+
+    // a swap tree not matching the swap terms is refused
+    swap_in_nok(
+        node2_addr,
+        Some(100),
+        reqwest::StatusCode::FORBIDDEN,
+        "unexpected swap tree",
+        "FailedSwapProviderRequest",
+    )
+    .await;
+
+    // provider errors are reported
+    let payload = SwapOutRequest {
+        amt_sat: 50000,
+        address: None,
+        max_fee_sat: None,
+        totp_code: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/swapout"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "invoice amount too low",
+        "FailedSwapProviderRequest",
+    )
+    .await;
+
+    let payload = SwapOutRequest {
+        amt_sat: 50000,
+        address: Some(s!("invalid")),
+        max_fee_sat: None,
+        totp_code: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node2_addr}/swapout"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid address",
+        "InvalidAddress",
+    )
+    .await;
+
+    assert!(list_submarine_swaps(node2_addr).await.is_empty());
+}
@@ -57,6 +57,7 @@ async fn swap_roundtrip_fail_timeout() {
     // try adding an expired swap, which should fail
     let payload = TakerRequest {
         swapstring: maker_init_response_1.swapstring.clone(),
+        totp_code: None,
     };
     let res = reqwest::Client::new()
         .post(format!("http://{taker_addr}/taker"))
@@ -0,0 +1,256 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/sweeps/";
+
+async fn list_sweeps(node_address: SocketAddr) -> Vec<Sweep> {
+    println!("listing sweeps for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/listsweeps"))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListSweepsResponse>()
+        .await
+        .unwrap()
+        .sweeps
+}
+
+async fn retry_sweep(node_address: SocketAddr, outpoint: &str) -> reqwest::Response {
+    println!("retrying sweep of {outpoint} for node {node_address}");
+    let payload = RetrySweepRequest {
+        outpoint: outpoint.to_string(),
+        totp_code: None,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/retrysweep"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn update_sweep_config(
+    node_address: SocketAddr,
+    payload: &SweepConfigRequest,
+) -> reqwest::Response {
+    println!("updating sweep config of node {node_address}");
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/sweepconfig"))
+        .json(payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn wait_for_sweep_status(node_address: SocketAddr, expected_status: SweepStatus) -> Sweep {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        if let Some(sweep) = list_sweeps(node_address)
+            .await
+            .into_iter()
+            .find(|s| s.status == expected_status)
+        {
+            return sweep;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 60.0 {
+            panic!("no sweep reached status {expected_status:?}")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn sweeps() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+
+    let channel = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        Some(600),
+        Some(&asset_id),
+    )
+    .await;
+
+    // the node's own fee estimation and addresses are used by default
+    let res = reqwest::Client::new()
+        .get(format!("http://{node1_addr}/sweepconfig"))
+        .send()
+        .await
+        .unwrap();
+    let config = _check_response_is_ok(res)
+        .await
+        .json::<SweepConfigResponse>()
+        .await
+        .unwrap();
+    assert_eq!(config.fee_rate, None);
+    assert_eq!(config.destination_address, None);
+
+    let res = update_sweep_config(
+        node1_addr,
+        &SweepConfigRequest {
+            fee_rate: Some(0),
+            destination_address: None,
+            totp_code: None,
+        },
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "fee_rate cannot be less than 1",
+        "InvalidFeeRate",
+    )
+    .await;
+    let res = update_sweep_config(
+        node1_addr,
+        &SweepConfigRequest {
+            fee_rate: Some(u64::MAX / 250),
+            destination_address: None,
+            totp_code: None,
+        },
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "fee_rate is too high",
+        "InvalidFeeRate",
+    )
+    .await;
+    let res = update_sweep_config(
+        node1_addr,
+        &SweepConfigRequest {
+            fee_rate: None,
+            destination_address: Some(s!("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq")),
+            totp_code: None,
+        },
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "address is not valid on regtest",
+        "InvalidNetwork",
+    )
+    .await;
+
+    let destination_address = address(node1_addr).await;
+    let res = update_sweep_config(
+        node1_addr,
+        &SweepConfigRequest {
+            fee_rate: Some(3),
+            destination_address: Some(destination_address.clone()),
+            totp_code: None,
+        },
+    )
+    .await;
+    let config = _check_response_is_ok(res)
+        .await
+        .json::<SweepConfigResponse>()
+        .await
+        .unwrap();
+    assert_eq!(config.fee_rate, Some(3));
+    assert_eq!(config.destination_address, Some(destination_address));
+
+    assert!(list_sweeps(node1_addr).await.is_empty());
+    let res = retry_sweep(
+        node1_addr,
+        "0000000000000000000000000000000000000000000000000000000000000000:0",
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown sweep",
+        "UnknownSweep",
+    )
+    .await;
+
+    // force-close and only confirm the commitment transaction, so the local output is timelocked
+    stop_mining();
+    let payload = CloseChannelRequest {
+        channel_id: channel.channel_id.clone(),
+        peer_pubkey: node2_pubkey.clone(),
+        force: true,
+        close_address: None,
+        fee_rate: None,
+        confirmation_target: None,
+        totp_code: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/closechannel"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res).await;
+    let t_0 = OffsetDateTime::now_utc();
+    while list_channels(node1_addr)
+        .await
+        .iter()
+        .any(|c| c.channel_id == channel.channel_id)
+    {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 30.0 {
+            panic!("channel is taking too long to close")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+    mine_n_blocks(true, 1);
+
+    let sweep = wait_for_sweep_status(node1_addr, SweepStatus::Timelocked).await;
+    assert_eq!(sweep.channel_id, Some(channel.channel_id.clone()));
+    assert_eq!(sweep.asset_id, Some(asset_id.clone()));
+    assert!(sweep.amount_sat > 0);
+    assert!(sweep.spendable_height.unwrap() > get_block_count());
+    assert!(sweep.sweep_txid.is_none());
+
+    // once the timelock expires the output gets swept
+    mine_n_blocks(false, 144);
+    let sweep = wait_for_sweep_status(node1_addr, SweepStatus::Confirming).await;
+    assert_eq!(sweep.channel_id, Some(channel.channel_id.clone()));
+    assert_eq!(sweep.asset_id, Some(asset_id.clone()));
+    assert!(sweep.sweep_txid.is_some());
+    assert!(sweep.confirmation_height.is_some());
+    let res = retry_sweep(node1_addr, sweep.outpoint.as_ref().unwrap()).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "sweep is already confirmed",
+        "CannotRetrySweep",
+    )
+    .await;
+
+    // the sweeper forgets outputs with enough confirmations
+    mine_n_blocks(false, 6);
+    let t_0 = OffsetDateTime::now_utc();
+    while list_sweeps(node1_addr)
+        .await
+        .iter()
+        .any(|s| s.outpoint == sweep.outpoint)
+    {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 60.0 {
+            panic!("sweep is still tracked")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+    wait_for_balance(node1_addr, &asset_id, 1000).await;
+}
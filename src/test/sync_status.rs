@@ -0,0 +1,107 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/sync_status/";
+
+const UNREACHABLE_INDEXER_URL: &str = "tcp://127.0.0.1:50099";
+
+async fn background_unlock(node_address: SocketAddr, payload: UnlockRequest) {
+    let payload = UnlockRequest {
+        background: Some(true),
+        ..payload
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/unlock"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
+}
+
+async fn sync_status(node_address: SocketAddr) -> SyncStatusResponse {
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/syncstatus"))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<SyncStatusResponse>()
+        .await
+        .unwrap()
+}
+
+async fn wait_for_sync_stage(node_address: SocketAddr, stage: SyncStage) -> SyncStatusResponse {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let status = sync_status(node_address).await;
+        if status.stage == stage {
+            return status;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 60.0 {
+            panic!("sync stage {stage:?} not reached, still {:?}", status.stage)
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn sync_status() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, password) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    let status = sync_status(node1_addr).await;
+    assert_eq!(status.stage, SyncStage::Ready);
+    assert!(status.error.is_none());
+    assert_eq!(status.chain_tip_lag, Some(0));
+    assert!(status.graph_num_nodes.is_some());
+
+    lock(node1_addr).await;
+    let status = sync_status(node1_addr).await;
+    assert_eq!(status.stage, SyncStage::Locked);
+    assert!(status.height.is_none());
+
+    // a background unlock returns before the node is ready, reporting its progress
+    background_unlock(node1_addr, unlock_req(&password)).await;
+    let status = sync_status(node1_addr).await;
+    assert_ne!(status.stage, SyncStage::Locked);
+    let status = wait_for_sync_stage(node1_addr, SyncStage::Ready).await;
+    assert!(status.started_at.unwrap() <= status.updated_at.unwrap());
+    assert_eq!(status.chain_tip_lag, Some(0));
+    assert!(btc_balance(node1_addr).await.vanilla.settled == 0);
+
+    // the failure of a background unlock is reported too
+    lock(node1_addr).await;
+    let payload = UnlockRequest {
+        indexer_url: Some(s!(UNREACHABLE_INDEXER_URL)),
+        ..unlock_req(&password)
+    };
+    background_unlock(node1_addr, payload).await;
+    let status = wait_for_sync_stage(node1_addr, SyncStage::Failed).await;
+    assert!(status.error.is_some());
+    let res = reqwest::Client::new()
+        .get(format!("http://{node1_addr}/networkinfo"))
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Node is locked (hint: call unlock)",
+        "LockedNode",
+    )
+    .await;
+
+    unlock(node1_addr, &password).await;
+    assert_eq!(sync_status(node1_addr).await.stage, SyncStage::Ready);
+
+    shutdown(&[node1_addr]).await;
+}
@@ -0,0 +1,107 @@
+use rcgen::{
+    BasicConstraints, CertificateParams, ExtendedKeyUsagePurpose, IsCa, KeyPair as CertKeyPair,
+};
+
+use crate::tls::check_tls_args;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/tls/";
+
+fn https_client(identity: Option<reqwest::Identity>) -> reqwest::Client {
+    let mut builder = reqwest::Client::builder().danger_accept_invalid_certs(true);
+    if let Some(identity) = identity {
+        builder = builder.identity(identity);
+    }
+    builder.build().unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn tls() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let _ = std::fs::remove_dir_all(TEST_DIR_BASE);
+    std::fs::create_dir_all(TEST_DIR_BASE).unwrap();
+
+    // a self-signed certificate is generated on first run and reused afterwards
+    let tls_config = check_tls_args(true, None, None, None, Path::new(&test_dir_node1)).unwrap();
+    let cert_path = PathBuf::from(&test_dir_node1).join("tls").join("cert.pem");
+    let cert = std::fs::read_to_string(&cert_path).unwrap();
+    check_tls_args(true, None, None, None, Path::new(&test_dir_node1)).unwrap();
+    assert_eq!(std::fs::read_to_string(&cert_path).unwrap(), cert);
+
+    let args = UserArgs {
+        storage_dir_path: test_dir_node1.clone().into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        tls_config,
+        ..Default::default()
+    };
+    let node1_port = start_daemon_with_args(args).await.port();
+
+    let res = https_client(None)
+        .get(format!("https://127.0.0.1:{node1_port}/nodeinfo"))
+        .send()
+        .await;
+    assert!(res.is_ok());
+    // cleartext requests are not served
+    let res = reqwest::Client::new()
+        .get(format!("http://127.0.0.1:{node1_port}/nodeinfo"))
+        .send()
+        .await;
+    assert!(res.is_err() || !res.unwrap().status().is_success());
+
+    // TLS files require TLS to be enabled
+    let res = check_tls_args(
+        false,
+        None,
+        None,
+        Some(cert_path),
+        Path::new(&test_dir_node1),
+    );
+    assert!(res.is_err());
+
+    // client certificates signed by the configured CA are required
+    let ca_key = CertKeyPair::generate().unwrap();
+    let mut ca_params = CertificateParams::new(vec![]).unwrap();
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    let ca_cert = ca_params.self_signed(&ca_key).unwrap();
+    let ca_path = PathBuf::from(format!("{TEST_DIR_BASE}client_ca.pem"));
+    std::fs::write(&ca_path, ca_cert.pem()).unwrap();
+
+    let client_key = CertKeyPair::generate().unwrap();
+    let mut client_params = CertificateParams::new(vec![s!("client")]).unwrap();
+    client_params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+    let client_cert = client_params
+        .signed_by(&client_key, &ca_cert, &ca_key)
+        .unwrap();
+    let identity = reqwest::Identity::from_pkcs8_pem(
+        client_cert.pem().as_bytes(),
+        client_key.serialize_pem().as_bytes(),
+    )
+    .unwrap();
+
+    let tls_config =
+        check_tls_args(true, None, None, Some(ca_path), Path::new(&test_dir_node2)).unwrap();
+    let args = UserArgs {
+        storage_dir_path: test_dir_node2.clone().into(),
+        ldk_peer_listening_port: NODE2_PEER_PORT,
+        tls_config,
+        ..Default::default()
+    };
+    let node2_port = start_daemon_with_args(args).await.port();
+
+    let res = https_client(None)
+        .get(format!("https://127.0.0.1:{node2_port}/nodeinfo"))
+        .send()
+        .await;
+    assert!(res.is_err());
+    let res = https_client(Some(identity))
+        .get(format!("https://127.0.0.1:{node2_port}/nodeinfo"))
+        .send()
+        .await;
+    assert!(res.is_ok());
+}
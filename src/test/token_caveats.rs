@@ -0,0 +1,252 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/token_caveats/";
+
+fn to_token(authority: BiscuitBuilder, root: &KeyPair) -> String {
+    authority.build(root).unwrap().to_base64().unwrap()
+}
+
+async fn send_btc_res(
+    node_address: SocketAddr,
+    token: &str,
+    amount: u64,
+    address: &str,
+) -> Response {
+    let payload = SendBtcRequest {
+        amount,
+        address: address.to_string(),
+        fee_rate: FEE_RATE,
+        skip_sync: false,
+        broadcast_at: None,
+        hold_for_approval: false,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/sendbtc"))
+        .json(&payload)
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn token_caveats() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+
+    let root_keypair = KeyPair::new();
+    let root_public_key = root_keypair.public();
+
+    let _ = std::fs::remove_dir_all(&test_dir_node1);
+    let node_address = start_daemon(&test_dir_node1, NODE1_PEER_PORT, Some(root_public_key)).await;
+
+    let admin_token = to_token(biscuit!(r#"role("admin");"#), &root_keypair);
+    let password = "a_password";
+    let payload = InitRequest {
+        password: password.to_string(),
+        network: None,
+        mnemonic: None,
+        passphrase: None,
+        account_index: None,
+        ldk_keys_only: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/init"))
+        .json(&payload)
+        .bearer_auth(&admin_token)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<InitResponse>()
+        .await
+        .unwrap();
+    let payload = unlock_req(password);
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/unlock"))
+        .json(&payload)
+        .bearer_auth(&admin_token)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/address"))
+        .bearer_auth(&admin_token)
+        .send()
+        .await
+        .unwrap();
+    let addr = _check_response_is_ok(res)
+        .await
+        .json::<AddressResponse>()
+        .await
+        .unwrap()
+        .address;
+    _fund_wallet(addr.clone());
+    mine(false);
+
+    // endpoints can be restricted even for the admin role
+    let token = to_token(
+        biscuit!(
+            r#"
+                role("admin");
+                allowed_endpoint("/networkinfo");
+            "#
+        ),
+        &root_keypair,
+    );
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/networkinfo"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<NetworkInfoResponse>()
+        .await
+        .unwrap();
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/nodeinfo"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::FORBIDDEN);
+
+    // an attenuated token is only valid for the operations it has been restricted to
+    let attenuated_token = Biscuit::from_base64(&admin_token, root_public_key)
+        .unwrap()
+        .append(block!(
+            r#"check if operation($op), ["/nodeinfo"].contains($op);"#
+        ))
+        .unwrap()
+        .to_base64()
+        .unwrap();
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/nodeinfo"))
+        .bearer_auth(&attenuated_token)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<NodeInfoResponse>()
+        .await
+        .unwrap();
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/networkinfo"))
+        .bearer_auth(&attenuated_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    // spending caveats limit each payment and the daily spending
+    let token = to_token(
+        biscuit!(
+            r#"
+                role("admin");
+                max_payment_msat(60000000);
+                daily_budget_msat(100000000);
+                allowed_asset("rgb:allowed");
+            "#
+        ),
+        &root_keypair,
+    );
+    check_response_is_nok(
+        send_btc_res(node_address, &token, 70_000, &addr).await,
+        reqwest::StatusCode::FORBIDDEN,
+        "payment of 70000000 msat exceeds the max of 60000000 msat",
+        "TokenLimitExceeded",
+    )
+    .await;
+    let payload = KeysendRequest {
+        dest_pubkey: "03b79a4bc1ec365524b4fab9a39eb133753646babb5a1da5c4bc94c53110b7795d"
+            .to_string(),
+        amt_msat: 3_000_000,
+        asset_id: Some("rgb:other".to_string()),
+        asset_amount: Some(10),
+        totp_code: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/keysend"))
+        .json(&payload)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "asset rgb:other is not allowed",
+        "TokenLimitExceeded",
+    )
+    .await;
+    for op in ["/openchannel", "/closechannel", "/sweepconfig"] {
+        let res = reqwest::Client::new()
+            .post(format!("http://{node_address}{op}"))
+            .json(&serde_json::json!({}))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap();
+        check_response_is_nok(
+            res,
+            reqwest::StatusCode::FORBIDDEN,
+            &format!("{op} is not allowed to tokens with spending limits"),
+            "TokenLimitExceeded",
+        )
+        .await;
+    }
+
+    let res = send_btc_res(node_address, &token, 60_000, &addr).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<SendBtcResponse>()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        send_btc_res(node_address, &token, 50_000, &addr).await,
+        reqwest::StatusCode::FORBIDDEN,
+        "payment of 50000000 msat exceeds the remaining daily budget of 40000000 msat",
+        "TokenLimitExceeded",
+    )
+    .await;
+
+    // failed requests don't consume the budget
+    let res = send_btc_res(node_address, &token, 40_000, "invalid").await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    let res = send_btc_res(node_address, &token, 40_000, &addr).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<SendBtcResponse>()
+        .await
+        .unwrap();
+
+    // attenuated tokens share the budget of the token they derive from
+    let attenuated_token = Biscuit::from_base64(&token, root_public_key)
+        .unwrap()
+        .append(block!(r#"check if operation("/sendbtc");"#))
+        .unwrap()
+        .to_base64()
+        .unwrap();
+    check_response_is_nok(
+        send_btc_res(node_address, &attenuated_token, 1_000, &addr).await,
+        reqwest::StatusCode::FORBIDDEN,
+        "exceeds the remaining daily budget of 0 msat",
+        "TokenLimitExceeded",
+    )
+    .await;
+}
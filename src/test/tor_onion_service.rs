@@ -0,0 +1,138 @@
+use crate::tor::OnionServiceConfig;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/tor_onion_service/";
+const CLIENT_KEY: &str = "N2NU7BSRL6YODZCYPN4CREB54TYLKGIE2KYOQWLFYC23ZJVCE5DQ";
+
+async fn tor_status(node_address: SocketAddr) -> TorStatusResponse {
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/torstatus"))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<TorStatusResponse>()
+        .await
+        .unwrap()
+}
+
+async fn wait_for_active_inbound(node_address: SocketAddr, active: u16) {
+    let t_0 = OffsetDateTime::now_utc();
+    while tor_status(node_address)
+        .await
+        .onion_service
+        .active_inbound_connections
+        != active
+    {
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("onion service connections are not {active}")
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+}
+
+async fn connect_peer_res(node_address: SocketAddr, peer_pubkey_and_addr: String) -> Response {
+    let payload = ConnectPeerRequest {
+        peer_pubkey_and_addr,
+        timeout_sec: None,
+        retries: None,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/connectpeer"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn tor_onion_service() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let service_dir = PathBuf::from(format!("{TEST_DIR_BASE}hidden_service"));
+    let clients_dir = service_dir.join("authorized_clients");
+    std::fs::create_dir_all(&clients_dir).unwrap();
+    std::fs::write(clients_dir.join("rln-7.auth"), "stale").unwrap();
+    std::fs::write(clients_dir.join("operator.auth"), "kept").unwrap();
+    let args = UserArgs {
+        storage_dir_path: test_dir_node1.into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        onion_service: OnionServiceConfig {
+            service_dir: Some(service_dir.clone()),
+            authorized_clients: vec![s!(CLIENT_KEY)],
+            max_inbound_connections: 1,
+            max_inbound_per_minute: 2,
+        },
+        ..Default::default()
+    };
+    let (node1_addr, _) = start_node_with_args(args, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    // the node replaces its own authorized client files only
+    assert_eq!(
+        std::fs::read_to_string(clients_dir.join("rln-0.auth")).unwrap(),
+        format!("descriptor:x25519:{CLIENT_KEY}\n")
+    );
+    assert!(!clients_dir.join("rln-7.auth").exists());
+    assert!(clients_dir.join("operator.auth").exists());
+
+    let status = tor_status(node1_addr).await;
+    assert!(status.socks_proxy.is_none());
+    assert!(status.onion_service.client_authorization);
+    assert_eq!(status.onion_service.authorized_clients, 1);
+    assert_eq!(status.onion_service.active_inbound_connections, 0);
+
+    // a second concurrent connection is dropped
+    let node1_pubkey = node_info(node1_addr).await.pubkey;
+    let node1_peer = format!("{node1_pubkey}@127.0.0.1:{NODE1_PEER_PORT}");
+    connect_peer(
+        node2_addr,
+        &node1_pubkey,
+        &format!("127.0.0.1:{NODE1_PEER_PORT}"),
+    )
+    .await;
+    wait_for_active_inbound(node1_addr, 1).await;
+    let res = connect_peer_res(node3_addr, node1_peer.clone()).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "The peer closed the connection during the handshake",
+        "PeerConnectionRejected",
+    )
+    .await;
+    let status = tor_status(node1_addr).await;
+    assert_eq!(status.onion_service.accepted_inbound_connections, 1);
+    assert_eq!(status.onion_service.rejected_max_connections, 1);
+
+    // the slot is freed on disconnection
+    disconnect_peer(node2_addr, &node1_pubkey).await;
+    wait_for_active_inbound(node1_addr, 0).await;
+    let res = connect_peer_res(node3_addr, node1_peer.clone()).await;
+    _check_response_is_ok(res).await;
+    wait_for_active_inbound(node1_addr, 1).await;
+
+    // a third connection within the minute is throttled
+    disconnect_peer(node3_addr, &node1_pubkey).await;
+    wait_for_active_inbound(node1_addr, 0).await;
+    let res = connect_peer_res(node2_addr, node1_peer).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "The peer closed the connection during the handshake",
+        "PeerConnectionRejected",
+    )
+    .await;
+    let status = tor_status(node1_addr).await;
+    assert_eq!(status.onion_service.accepted_inbound_connections, 2);
+    assert_eq!(status.onion_service.rejected_throttled, 1);
+
+    shutdown(&[node1_addr, node2_addr, node3_addr]).await;
+}
@@ -0,0 +1,321 @@
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::tor::TorProxy;
+
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/tor_proxy/";
+const SOCKS_USERNAME: &str = "rln";
+const SOCKS_PASSWORD: &str = "isolation-1";
+
+async fn unlock_res(node_address: SocketAddr, payload: &UnlockRequest) -> Response {
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/unlock"))
+        .json(payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+/// Handle a SOCKS5 CONNECT to an IPv4 or domain target, requiring username/password
+/// authentication, and return the target (host:port) the client asked for with the password it
+/// used
+async fn socks_handshake(stream: &mut tokio::net::TcpStream) -> Option<(String, String)> {
+    let mut header = [0u8; 2];
+    stream.read_exact(&mut header).await.ok()?;
+    let mut methods = vec![0u8; header[1] as usize];
+    stream.read_exact(&mut methods).await.ok()?;
+    if !methods.contains(&0x02) {
+        stream.write_all(&[0x05, 0xff]).await.ok()?;
+        return None;
+    }
+    stream.write_all(&[0x05, 0x02]).await.ok()?;
+
+    let mut ulen = [0u8; 2];
+    stream.read_exact(&mut ulen).await.ok()?;
+    let mut username = vec![0u8; ulen[1] as usize];
+    stream.read_exact(&mut username).await.ok()?;
+    let plen = stream.read_u8().await.ok()?;
+    let mut password = vec![0u8; plen as usize];
+    stream.read_exact(&mut password).await.ok()?;
+    // isolated circuits append the circuit group to the password
+    let password = String::from_utf8(password).ok()?;
+    if username != SOCKS_USERNAME.as_bytes() || !password.starts_with(SOCKS_PASSWORD) {
+        stream.write_all(&[0x01, 0x01]).await.ok()?;
+        return None;
+    }
+    stream.write_all(&[0x01, 0x00]).await.ok()?;
+
+    let mut request = [0u8; 4];
+    stream.read_exact(&mut request).await.ok()?;
+    let host = match request[3] {
+        0x01 => {
+            let mut ip = [0u8; 4];
+            stream.read_exact(&mut ip).await.ok()?;
+            std::net::Ipv4Addr::from(ip).to_string()
+        }
+        0x03 => {
+            let len = stream.read_u8().await.ok()?;
+            let mut domain = vec![0u8; len as usize];
+            stream.read_exact(&mut domain).await.ok()?;
+            String::from_utf8(domain).ok()?
+        }
+        _ => {
+            stream
+                .write_all(&[0x05, 0x08, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+                .await
+                .ok()?;
+            return None;
+        }
+    };
+    let port = stream.read_u16().await.ok()?;
+    Some((format!("{host}:{port}"), password))
+}
+
+/// Start a SOCKS5 proxy that forwards the authenticated connections, recording their targets
+/// and the passwords they used
+async fn start_socks_proxy() -> (SocketAddr, Arc<Mutex<Vec<String>>>, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy_addr = listener.local_addr().unwrap();
+    let targets = Arc::new(Mutex::new(vec![]));
+    let passwords = Arc::new(Mutex::new(vec![]));
+    let targets_copy = Arc::clone(&targets);
+    let passwords_copy = Arc::clone(&passwords);
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let targets = Arc::clone(&targets_copy);
+            let passwords = Arc::clone(&passwords_copy);
+            tokio::spawn(async move {
+                let Some((target, password)) = socks_handshake(&mut stream).await else {
+                    return;
+                };
+                let Ok(mut upstream) = tokio::net::TcpStream::connect(&target).await else {
+                    let _ = stream
+                        .write_all(&[0x05, 0x05, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+                        .await;
+                    return;
+                };
+                stream
+                    .write_all(&[0x05, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+                    .await
+                    .unwrap();
+                targets.lock().unwrap().push(target);
+                passwords.lock().unwrap().push(password);
+                let _ = tokio::io::copy_bidirectional(&mut stream, &mut upstream).await;
+            });
+        }
+    });
+    (proxy_addr, targets, passwords)
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn tor_proxy() {
+    initialize();
+
+    let (proxy_addr, targets, _) = start_socks_proxy().await;
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let args = UserArgs {
+        storage_dir_path: test_dir_node1.into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        tor_proxy: Some(TorProxy {
+            addr: proxy_addr.to_string(),
+            credentials: Some((s!(SOCKS_USERNAME), s!(SOCKS_PASSWORD))),
+            no_clearnet_dns: false,
+            isolate_circuits: false,
+        }),
+        ..Default::default()
+    };
+    let (node1_addr, _) = start_node_with_args(args, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    // the connection goes through the proxy, which receives the peer address
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node2_peer_addr = format!("127.0.0.1:{NODE2_PEER_PORT}");
+    connect_peer(node1_addr, &node2_pubkey, &node2_peer_addr).await;
+    assert_eq!(*targets.lock().unwrap(), vec![node2_peer_addr.clone()]);
+    let peers = list_peers(node1_addr).await;
+    let peer = peers.iter().find(|p| p.pubkey == node2_pubkey).unwrap();
+    assert_eq!(peer.transport, PeerTransport::Tor);
+    assert_eq!(
+        peer.address.as_deref(),
+        Some(proxy_addr.to_string().as_str())
+    );
+
+    // hostnames are left for the proxy to resolve
+    disconnect_peer(node1_addr, &node2_pubkey).await;
+    let node2_peer_host = format!("localhost:{NODE2_PEER_PORT}");
+    connect_peer(node1_addr, &node2_pubkey, &node2_peer_host).await;
+    assert_eq!(targets.lock().unwrap().last(), Some(&node2_peer_host));
+
+    // a node with the wrong credentials is refused by the proxy
+    let args = UserArgs {
+        storage_dir_path: test_dir_node3.into(),
+        ldk_peer_listening_port: NODE3_PEER_PORT,
+        tor_proxy: Some(TorProxy {
+            addr: proxy_addr.to_string(),
+            credentials: Some((s!(SOCKS_USERNAME), s!("wrong"))),
+            no_clearnet_dns: false,
+            isolate_circuits: false,
+        }),
+        ..Default::default()
+    };
+    let (node3_addr, _) = start_node_with_args(args, false).await;
+    let payload = ConnectPeerRequest {
+        peer_pubkey_and_addr: format!("{node2_pubkey}@{node2_peer_addr}"),
+        timeout_sec: None,
+        retries: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node3_addr}/connectpeer"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "the proxy rejected the SOCKS credentials",
+        "PeerUnreachable",
+    )
+    .await;
+    assert_eq!(targets.lock().unwrap().len(), 2);
+
+    shutdown(&[node1_addr, node2_addr, node3_addr]).await;
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn tor_no_clearnet_dns() {
+    initialize();
+
+    let (proxy_addr, _, _) = start_socks_proxy().await;
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}no_clearnet_dns_node1");
+    let args = UserArgs {
+        storage_dir_path: test_dir_node1.into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        tor_proxy: Some(TorProxy {
+            addr: proxy_addr.to_string(),
+            credentials: Some((s!(SOCKS_USERNAME), s!(SOCKS_PASSWORD))),
+            no_clearnet_dns: true,
+            isolate_circuits: false,
+        }),
+        ..Default::default()
+    };
+    let (node1_addr, password) = start_node_with_args(args, false).await;
+    lock(node1_addr).await;
+
+    // endpoints with a hostname would be resolved outside of Tor
+    let payload = UnlockRequest {
+        indexer_url: Some(s!("ssl://electrum.example.com:50002")),
+        ..unlock_req(&password)
+    };
+    let res = unlock_res(node1_addr, &payload).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "ssl://electrum.example.com:50002 would be resolved with clearnet DNS",
+        "ClearnetDnsRequired",
+    )
+    .await;
+    let payload = UnlockRequest {
+        fallback_proxy_endpoints: Some(vec![s!("rpcs://proxy.example.com/json-rpc")]),
+        ..unlock_req(&password)
+    };
+    let res = unlock_res(node1_addr, &payload).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "rpcs://proxy.example.com/json-rpc would be resolved with clearnet DNS",
+        "ClearnetDnsRequired",
+    )
+    .await;
+
+    // IP addresses (and localhost) need no resolution
+    let res = unlock_res(node1_addr, &unlock_req(&password)).await;
+    _check_response_is_ok(res).await;
+
+    shutdown(&[node1_addr]).await;
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn tor_isolate_circuits() {
+    initialize();
+
+    let (proxy_addr, targets, passwords) = start_socks_proxy().await;
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}isolate_circuits_node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}isolate_circuits_node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}isolate_circuits_node3");
+    let args = UserArgs {
+        storage_dir_path: test_dir_node1.into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        tor_proxy: Some(TorProxy {
+            addr: proxy_addr.to_string(),
+            credentials: Some((s!(SOCKS_USERNAME), s!(SOCKS_PASSWORD))),
+            no_clearnet_dns: false,
+            isolate_circuits: true,
+        }),
+        ..Default::default()
+    };
+    let (node1_addr, _) = start_node_with_args(args, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+
+    // connecting to a peer without channels goes through the gossip circuit
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node2_peer_addr = format!("127.0.0.1:{NODE2_PEER_PORT}");
+    connect_peer(node1_addr, &node2_pubkey, &node2_peer_addr).await;
+    assert_eq!(
+        *passwords.lock().unwrap(),
+        vec![format!("{SOCKS_PASSWORD}-gossip")]
+    );
+
+    // opening a channel goes through the payments circuit
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+    open_channel(
+        node1_addr,
+        &node3_pubkey,
+        Some(NODE3_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+    assert_eq!(
+        targets.lock().unwrap().last(),
+        Some(&format!("127.0.0.1:{NODE3_PEER_PORT}"))
+    );
+    assert_eq!(
+        passwords.lock().unwrap().last(),
+        Some(&format!("{SOCKS_PASSWORD}-payments"))
+    );
+
+    // reconnecting to a channel peer also uses the payments circuit
+    disconnect_peer(node1_addr, &node3_pubkey).await;
+    connect_peer(
+        node1_addr,
+        &node3_pubkey,
+        &format!("127.0.0.1:{NODE3_PEER_PORT}"),
+    )
+    .await;
+    assert_eq!(
+        passwords.lock().unwrap().last(),
+        Some(&format!("{SOCKS_PASSWORD}-payments"))
+    );
+
+    shutdown(&[node1_addr, node2_addr, node3_addr]).await;
+}
@@ -0,0 +1,220 @@
+use super::*;
+
+use crate::totp::{totp_code, totp_step};
+use crate::utils::get_current_timestamp;
+
+const TEST_DIR_BASE: &str = "tmp/totp/";
+
+fn base32_decode(data: &str) -> Vec<u8> {
+    let alphabet = "ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+    let mut bits = 0u64;
+    let mut num_bits = 0;
+    let mut decoded = vec![];
+    for c in data.chars() {
+        bits = bits << 5 | alphabet.find(c).unwrap() as u64;
+        num_bits += 5;
+        if num_bits >= 8 {
+            num_bits -= 8;
+            decoded.push((bits >> num_bits) as u8);
+        }
+    }
+    decoded
+}
+
+/// TOTP code of the given number of steps after the current one
+fn code(secret: &str, steps_ahead: u64) -> String {
+    let step = totp_step(get_current_timestamp()) + steps_ahead;
+    totp_code(&base32_decode(secret), step)
+}
+
+async fn send_btc_with_totp(
+    node_address: SocketAddr,
+    address: &str,
+    totp_code: Option<String>,
+) -> Response {
+    let payload = SendBtcRequest {
+        amount: 1000,
+        address: address.to_string(),
+        fee_rate: FEE_RATE,
+        skip_sync: false,
+        broadcast_at: None,
+        hold_for_approval: false,
+        coin_selection: None,
+        totp_code,
+        dry_run: false,
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/sendbtc"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn totp() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    let address = address(node1_addr).await;
+
+    // a pending enrollment doesn't require codes
+    let enrollment = enroll_totp(node1_addr, 0).await;
+    assert_eq!(enrollment.recovery_codes.len(), 8);
+    assert!(enrollment.uri.starts_with("otpauth://totp/"));
+    assert!(enrollment.uri.contains(&enrollment.secret));
+    let res = send_btc_with_totp(node1_addr, &address, None).await;
+    _check_response_is_ok(res).await;
+
+    // a new enrollment replaces the pending one
+    let old_secret = enrollment.secret;
+    let enrollment = enroll_totp(node1_addr, 0).await;
+    let secret = enrollment.secret;
+    let payload = ConfirmTotpRequest {
+        code: code(&old_secret, 0),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/confirmtotp"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::UNAUTHORIZED,
+        "The provided TOTP code is incorrect",
+        "WrongTotpCode",
+    )
+    .await;
+    confirm_totp(node1_addr, &code(&secret, 0)).await;
+
+    let payload = EnrollTotpRequest {
+        payment_threshold_msat: 0,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/enrolltotp"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "TOTP is already enabled",
+        "TotpAlreadyEnabled",
+    )
+    .await;
+
+    // spending requires a code
+    let res = send_btc_with_totp(node1_addr, &address, None).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::UNAUTHORIZED,
+        "A TOTP code is required for this operation",
+        "MissingTotpCode",
+    )
+    .await;
+    let payload = KeysendRequest {
+        dest_pubkey: s!("02270dadcd6e7ba0ef707dac72acccae1a3607453a8dd2aef36ff3be4e0d31f043"),
+        amt_msat: 3000000,
+        asset_id: None,
+        asset_amount: None,
+        totp_code: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/keysend"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::UNAUTHORIZED,
+        "A TOTP code is required for this operation",
+        "MissingTotpCode",
+    )
+    .await;
+    // redirecting the swept funds requires a code as well
+    let payload = SweepConfigRequest {
+        fee_rate: None,
+        destination_address: Some(address.clone()),
+        totp_code: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/sweepconfig"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::UNAUTHORIZED,
+        "A TOTP code is required for this operation",
+        "MissingTotpCode",
+    )
+    .await;
+    let res = send_btc_with_totp(node1_addr, &address, Some(code(&secret, 1))).await;
+    _check_response_is_ok(res).await;
+    // a code cannot be reused
+    let res = send_btc_with_totp(node1_addr, &address, Some(code(&secret, 1))).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::UNAUTHORIZED,
+        "The provided TOTP code is incorrect",
+        "WrongTotpCode",
+    )
+    .await;
+
+    // recovery codes can be used once
+    let recovery_code = enrollment.recovery_codes[0].clone();
+    let res = send_btc_with_totp(node1_addr, &address, Some(recovery_code.clone())).await;
+    _check_response_is_ok(res).await;
+    let res = send_btc_with_totp(node1_addr, &address, Some(recovery_code)).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::UNAUTHORIZED,
+        "The provided TOTP code is incorrect",
+        "WrongTotpCode",
+    )
+    .await;
+
+    // too many wrong codes lock the verification
+    for _ in 0..3 {
+        let res = send_btc_with_totp(node1_addr, &address, Some(s!("000000"))).await;
+        assert_eq!(res.status(), reqwest::StatusCode::UNAUTHORIZED);
+    }
+    let res = send_btc_with_totp(
+        node1_addr,
+        &address,
+        Some(enrollment.recovery_codes[1].clone()),
+    )
+    .await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::TOO_MANY_REQUESTS,
+        "Too many wrong TOTP codes",
+        "TotpRateLimited",
+    )
+    .await;
+    let payload = DisableTotpRequest {
+        code: enrollment.recovery_codes[1].clone(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/disabletotp"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::TOO_MANY_REQUESTS,
+        "Too many wrong TOTP codes",
+        "TotpRateLimited",
+    )
+    .await;
+}
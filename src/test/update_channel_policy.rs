@@ -0,0 +1,312 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/update_channel_policy/";
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn update_channel_policy_fees() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+    fund_and_create_utxos(node3_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+
+    let channel_12 = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+    let channel_13 = open_channel(
+        node1_addr,
+        &node3_pubkey,
+        Some(NODE3_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // update a single channel
+    let payload = UpdateChannelPolicyRequest {
+        channel_id: Some(channel_12.channel_id.clone()),
+        asset_id: None,
+        fee_base_msat: Some(2000),
+        fee_proportional_millionths: Some(150),
+        cltv_expiry_delta: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+    };
+    let UpdateChannelPolicyResponse { channel_ids } =
+        update_channel_policy(node1_addr, &payload).await;
+    assert_eq!(channel_ids, vec![channel_12.channel_id.clone()]);
+    let channels = list_channels(node1_addr).await;
+    let chan_12 = channels
+        .iter()
+        .find(|c| c.channel_id == channel_12.channel_id)
+        .unwrap();
+    assert_eq!(chan_12.fee_base_msat, Some(2000));
+    assert_eq!(chan_12.fee_proportional_millionths, Some(150));
+    let chan_13 = channels
+        .iter()
+        .find(|c| c.channel_id == channel_13.channel_id)
+        .unwrap();
+    assert_ne!(chan_13.fee_base_msat, Some(2000));
+    let default_cltv_expiry_delta = chan_13.cltv_expiry_delta;
+    assert_eq!(chan_12.cltv_expiry_delta, default_cltv_expiry_delta);
+
+    // update all channels
+    let payload = UpdateChannelPolicyRequest {
+        channel_id: None,
+        asset_id: None,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        cltv_expiry_delta: Some(100),
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+    };
+    let UpdateChannelPolicyResponse { channel_ids } =
+        update_channel_policy(node1_addr, &payload).await;
+    assert_eq!(channel_ids.len(), 2);
+    let channels = list_channels(node1_addr).await;
+    assert!(channels.iter().all(|c| c.cltv_expiry_delta == Some(100)));
+    let chan_12 = channels
+        .iter()
+        .find(|c| c.channel_id == channel_12.channel_id)
+        .unwrap();
+    assert_eq!(chan_12.fee_base_msat, Some(2000));
+
+    // failures
+    let payload = UpdateChannelPolicyRequest {
+        channel_id: None,
+        asset_id: None,
+        fee_base_msat: None,
+        fee_proportional_millionths: None,
+        cltv_expiry_delta: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/updatechannelpolicy"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "at least one policy field needs to be set",
+        "InvalidChannelPolicy",
+    )
+    .await;
+    let payload = UpdateChannelPolicyRequest {
+        cltv_expiry_delta: Some(10),
+        ..payload
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/updatechannelpolicy"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "cltv_expiry_delta cannot be less than",
+        "InvalidChannelPolicy",
+    )
+    .await;
+    let payload = UpdateChannelPolicyRequest {
+        channel_id: Some(s!(
+            "0000000000000000000000000000000000000000000000000000000000000000"
+        )),
+        fee_base_msat: Some(1000),
+        ..payload
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/updatechannelpolicy"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown channel ID",
+        "UnknownChannelId",
+    )
+    .await;
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn update_channel_policy_assets() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}assets_node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}assets_node2");
+    let test_dir_node3 = format!("{TEST_DIR_BASE}assets_node3");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+    let (node3_addr, _) = start_node(&test_dir_node3, NODE3_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node3_addr, None).await;
+
+    let node2_pubkey = node_info(node2_addr).await.pubkey;
+    let node3_pubkey = node_info(node3_addr).await.pubkey;
+
+    let channel_12 = open_channel(
+        node1_addr,
+        &node2_pubkey,
+        Some(NODE2_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+
+    // the BTC policy applies to the open vanilla channels
+    let payload = UpdateChannelPolicyRequest {
+        channel_id: None,
+        asset_id: Some(s!("btc")),
+        fee_base_msat: Some(3000),
+        fee_proportional_millionths: Some(250),
+        cltv_expiry_delta: None,
+        htlc_minimum_msat: Some(5000),
+        max_htlc_value_in_flight_percent: None,
+    };
+    let UpdateChannelPolicyResponse { channel_ids } =
+        update_channel_policy(node1_addr, &payload).await;
+    assert_eq!(channel_ids, vec![channel_12.channel_id.clone()]);
+    let channels = list_channels(node1_addr).await;
+    let chan_12 = channels
+        .iter()
+        .find(|c| c.channel_id == channel_12.channel_id)
+        .unwrap();
+    assert_eq!(chan_12.fee_base_msat, Some(3000));
+    assert_eq!(chan_12.fee_proportional_millionths, Some(250));
+
+    // and to the vanilla channels opened afterwards, by us or by a peer
+    let payload = UpdateChannelPolicyRequest {
+        fee_base_msat: Some(4000),
+        htlc_minimum_msat: None,
+        ..payload
+    };
+    update_channel_policy(node3_addr, &payload).await;
+    let channel_13 = open_channel(
+        node1_addr,
+        &node3_pubkey,
+        Some(NODE3_PEER_PORT),
+        None,
+        None,
+        None,
+        None,
+    )
+    .await;
+    let channels = list_channels(node1_addr).await;
+    let chan_13 = channels
+        .iter()
+        .find(|c| c.channel_id == channel_13.channel_id)
+        .unwrap();
+    assert_eq!(chan_13.fee_base_msat, Some(3000));
+    assert_eq!(chan_13.fee_proportional_millionths, Some(250));
+    assert_eq!(chan_13.inbound_htlc_minimum_msat, Some(5000));
+    let channels = list_channels(node3_addr).await;
+    let chan_31 = channels
+        .iter()
+        .find(|c| c.channel_id == channel_13.channel_id)
+        .unwrap();
+    assert_eq!(chan_31.fee_base_msat, Some(4000));
+
+    // failures
+    let payload = UpdateChannelPolicyRequest {
+        channel_id: Some(channel_12.channel_id.clone()),
+        ..payload
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/updatechannelpolicy"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "channel_id and asset_id cannot be provided together",
+        "InvalidChannelPolicy",
+    )
+    .await;
+    let payload = UpdateChannelPolicyRequest {
+        asset_id: None,
+        htlc_minimum_msat: Some(5000),
+        ..payload
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/updatechannelpolicy"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "HTLC limits can only be set for an asset_id",
+        "InvalidChannelPolicy",
+    )
+    .await;
+    let payload = UpdateChannelPolicyRequest {
+        channel_id: None,
+        asset_id: Some(s!("invalid")),
+        ..payload
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/updatechannelpolicy"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid asset ID: invalid",
+        "InvalidAssetID",
+    )
+    .await;
+    let payload = UpdateChannelPolicyRequest {
+        asset_id: Some(s!("btc")),
+        max_htlc_value_in_flight_percent: Some(0),
+        ..payload
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/updatechannelpolicy"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "max_htlc_value_in_flight_percent must be between 1 and 100",
+        "InvalidChannelPolicy",
+    )
+    .await;
+}
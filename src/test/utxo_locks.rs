@@ -0,0 +1,142 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/utxo_locks/";
+
+async fn lock_utxo_nok(
+    node_address: SocketAddr,
+    outpoint: &str,
+    label: Option<String>,
+    expected_status: reqwest::StatusCode,
+    expected_message: &str,
+    expected_name: &str,
+) {
+    let payload = LockUtxoRequest {
+        outpoint: outpoint.to_string(),
+        label,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/lockutxo"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(res, expected_status, expected_message, expected_name).await;
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn utxo_locks() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let test_dir_node2 = format!("{TEST_DIR_BASE}node2");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+    let (node2_addr, _) = start_node(&test_dir_node2, NODE2_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    fund_and_create_utxos(node2_addr, None).await;
+
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+
+    let unspents = list_unspents(node1_addr).await;
+    assert!(unspents.iter().all(|u| !u.locked && u.label.is_none()));
+    assert!(unspents.iter().all(|u| u.confirmations > 0));
+    assert!(unspents.iter().all(|u| !u.reserved_for_funding));
+    let outpoint = unspents
+        .iter()
+        .find(|u| {
+            u.rgb_allocations
+                .iter()
+                .any(|a| a.asset_id.as_ref() == Some(&asset_id))
+        })
+        .unwrap()
+        .utxo
+        .outpoint
+        .clone();
+
+    // lock the UTXO holding the issued asset
+    lock_utxo_nok(
+        node1_addr,
+        &outpoint,
+        Some(s!(" ")),
+        reqwest::StatusCode::BAD_REQUEST,
+        "Invalid label: label cannot be empty",
+        "InvalidLabel",
+    )
+    .await;
+    lock_utxo_nok(
+        node1_addr,
+        &format!("{}:999", outpoint.split(':').next().unwrap()),
+        None,
+        reqwest::StatusCode::FORBIDDEN,
+        "Unknown UTXO",
+        "UnknownUtxo",
+    )
+    .await;
+    lock_utxo(node1_addr, &outpoint, Some(s!("cold storage"))).await;
+    let unspents = list_unspents(node1_addr).await;
+    let locked: Vec<_> = unspents.iter().filter(|u| u.locked).collect();
+    assert_eq!(locked.len(), 1);
+    assert_eq!(locked[0].utxo.outpoint, outpoint);
+    assert_eq!(locked[0].label, Some(s!("cold storage")));
+
+    // the locked UTXO cannot be spent
+    let recipient_id = rgb_invoice(node2_addr, None, false).await.recipient_id;
+    let payload = SendAssetRequest {
+        asset_id: asset_id.clone(),
+        assignment: Assignment::Fungible(100),
+        recipient_id: recipient_id.clone(),
+        witness_data: None,
+        donation: true,
+        fee_rate: FEE_RATE,
+        min_confirmations: 1,
+        transport_endpoints: vec![PROXY_ENDPOINT_LOCAL.to_string()],
+        skip_sync: false,
+        broadcast_at: None,
+        hold_for_approval: false,
+        totp_code: None,
+        dry_run: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/sendasset"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        &format!("UTXO {outpoint} is locked"),
+        "LockedUtxo",
+    )
+    .await;
+
+    // once unlocked the UTXO can be spent again
+    unlock_utxo(node1_addr, &outpoint).await;
+    let payload = UnlockUtxoRequest {
+        outpoint: outpoint.clone(),
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node1_addr}/unlockutxo"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::FORBIDDEN,
+        "UTXO is not locked",
+        "UtxoNotLocked",
+    )
+    .await;
+    assert!(list_unspents(node1_addr).await.iter().all(|u| !u.locked));
+    send_asset(
+        node1_addr,
+        &asset_id,
+        Assignment::Fungible(100),
+        recipient_id,
+        None,
+    )
+    .await;
+}
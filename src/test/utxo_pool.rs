@@ -0,0 +1,79 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/utxo_pool/";
+
+fn free_colorable_utxos(unspents: &[Unspent]) -> usize {
+    unspents
+        .iter()
+        .filter(|u| u.utxo.colorable && u.rgb_allocations.is_empty() && !u.locked)
+        .count()
+}
+
+async fn wait_for_free_colorable_utxos(node_address: SocketAddr, num: usize) -> Vec<Unspent> {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let unspents = list_unspents(node_address).await;
+        if free_colorable_utxos(&unspents) >= num {
+            return unspents;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 70.0 {
+            panic!("UTXO pool has not been topped up")
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn utxo_pool_maintenance() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let args = UserArgs {
+        storage_dir_path: test_dir_node1.clone().into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        utxo_pool_target: 3,
+        utxo_pool_min: 2,
+        utxo_pool_utxo_size_sat: 20000,
+        ..Default::default()
+    };
+    let (node1_addr, _) = start_node_with_args(args, false).await;
+
+    // once funded, the pool is filled without calling /createutxos
+    let addr = address(node1_addr).await;
+    _fund_wallet(addr);
+    mine(false);
+    let unspents = wait_for_free_colorable_utxos(node1_addr, 3).await;
+    assert_eq!(free_colorable_utxos(&unspents), 3);
+    assert!(unspents
+        .iter()
+        .filter(|u| u.utxo.colorable)
+        .all(|u| u.utxo.btc_amount == 20000));
+    mine(false);
+
+    // using UTXOs for RGB operations makes the pool drop below its threshold and get topped up
+    issue_asset_nia(node1_addr).await;
+    issue_asset_cfa(node1_addr, None).await;
+    let unspents = wait_for_free_colorable_utxos(node1_addr, 3).await;
+    assert_eq!(free_colorable_utxos(&unspents), 3);
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn utxo_pool_disabled() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}disabled/node1");
+    let (node1_addr, _) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    let addr = address(node1_addr).await;
+    _fund_wallet(addr);
+    mine(false);
+
+    // no UTXO gets created when the pool is disabled
+    tokio::time::sleep(std::time::Duration::from_secs(35)).await;
+    let unspents = list_unspents(node1_addr).await;
+    assert_eq!(free_colorable_utxos(&unspents), 0);
+}
@@ -0,0 +1,115 @@
+use super::*;
+
+const TEST_DIR_BASE: &str = "tmp/watch_only/";
+
+async fn set_view_password_res(
+    node_address: SocketAddr,
+    password: &str,
+    view_password: Option<&str>,
+) -> Response {
+    let payload = SetViewPasswordRequest {
+        password: password.to_string(),
+        view_password: view_password.map(|p| p.to_string()),
+    };
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/setviewpassword"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn unlock_view_res(node_address: SocketAddr, view_password: &str) -> Response {
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/unlockview"))
+        .json(&unlock_req(view_password))
+        .send()
+        .await
+        .unwrap()
+}
+
+#[serial_test::serial]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+#[traced_test]
+async fn watch_only() {
+    initialize();
+
+    let test_dir_node1 = format!("{TEST_DIR_BASE}node1");
+    let (node1_addr, node1_password) = start_node(&test_dir_node1, NODE1_PEER_PORT, false).await;
+
+    fund_and_create_utxos(node1_addr, None).await;
+    let asset_id = issue_asset_nia(node1_addr).await.asset_id;
+    let pubkey = node_info(node1_addr).await.pubkey;
+    let btc_vanilla = btc_balance(node1_addr).await.vanilla;
+
+    // the view password needs the node one and must differ from it
+    let view_password = format!("{node1_password}.view");
+    let res = set_view_password_res(node1_addr, "!nc0rr3ct", Some(&view_password)).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::UNAUTHORIZED,
+        "The provided password is incorrect",
+        "WrongPassword",
+    )
+    .await;
+    let res = set_view_password_res(node1_addr, &node1_password, Some(&node1_password)).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::BAD_REQUEST,
+        "the view password must differ from the node one",
+        "InvalidPassword",
+    )
+    .await;
+    let res = set_view_password_res(node1_addr, &node1_password, Some(&view_password)).await;
+    _check_response_is_ok(res).await;
+
+    shutdown(&[node1_addr]).await;
+
+    // the watch-only node is unlocked with the view password only
+    let node1_addr = start_daemon_with_args(UserArgs {
+        storage_dir_path: test_dir_node1.clone().into(),
+        ldk_peer_listening_port: NODE1_PEER_PORT,
+        watch_only: true,
+        ..Default::default()
+    })
+    .await;
+    let res = unlock_res(node1_addr, &node1_password).await;
+    assert_eq!(res.status(), reqwest::StatusCode::NOT_FOUND);
+    let res = unlock_view_res(node1_addr, &node1_password).await;
+    check_response_is_nok(
+        res,
+        reqwest::StatusCode::UNAUTHORIZED,
+        "The provided password is incorrect",
+        "WrongPassword",
+    )
+    .await;
+    let res = unlock_view_res(node1_addr, &view_password).await;
+    _check_response_is_ok(res).await;
+
+    // balances and assets are reported
+    assert_eq!(node_info(node1_addr).await.pubkey, pubkey);
+    assert_eq!(btc_balance(node1_addr).await.vanilla, btc_vanilla);
+    assert_eq!(asset_balance_spendable(node1_addr, &asset_id).await, 1000);
+    let assets_nia = list_assets(node1_addr).await.nia.unwrap();
+    assert_eq!(assets_nia.len(), 1);
+    assert_eq!(assets_nia[0].asset_id, asset_id);
+
+    // no API moving funds is served
+    for path in [
+        "/openchannel",
+        "/sendasset",
+        "/sendbtc",
+        "/sendpayment",
+        "/setviewpassword",
+    ] {
+        let res = reqwest::Client::new()
+            .post(format!("http://{node1_addr}{path}"))
+            .json(&serde_json::json!({}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+
+    shutdown(&[node1_addr]).await;
+}
@@ -0,0 +1,706 @@
+//! Helpers to run clusters of regtest nodes in-process, for integration tests.
+//!
+//! The regtest services defined in `compose.yaml` (bitcoind, electrs and the RGB proxy) need to be
+//! running, e.g. with `./regtest.sh start`, and the helpers need to be called from the directory
+//! containing `compose.yaml`, as blocks are mined through `docker compose`.
+
+use amplify::s;
+use electrum_client::ElectrumApi;
+use lightning::ln::channelmanager::MIN_FINAL_CLTV_EXPIRY_DELTA;
+use once_cell::sync::Lazy;
+use reqwest::Response;
+use rgb_lib::BitcoinNetwork;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::RwLock;
+use time::OffsetDateTime;
+use tokio::net::TcpListener;
+use zeroize::Zeroizing;
+
+use crate::args::{
+    LogFormat, StorageBackend, UserArgs, DEFAULT_ADDRESS_GAP_LIMIT, DEFAULT_ANCHOR_RESERVE_UTXOS,
+    DEFAULT_ANCHOR_RESERVE_UTXO_SIZE_SAT, DEFAULT_AUTO_BACKUP_FULL_EVERY,
+    DEFAULT_AUTO_BACKUP_INTERVAL_SEC, DEFAULT_AUTO_BACKUP_KEEP, DEFAULT_CLTV_EXPIRY_DELTA,
+    DEFAULT_GOSSIP_BANDWIDTH_CAP, DEFAULT_HOOK_MAX_CONCURRENCY, DEFAULT_HOOK_TIMEOUT_SEC,
+    DEFAULT_HTLC_RISK_THRESHOLD_SAT, DEFAULT_INVOICE_RETENTION_SEC,
+    DEFAULT_MAX_REQUESTS_PER_MINUTE, DEFAULT_MAX_ROUTE_HINTS, DEFAULT_MAX_TOTAL_CLTV_EXPIRY_DELTA,
+    DEFAULT_MIN_CHANNEL_DEPTH, DEFAULT_NOTIFY_MQTT_TOPIC, DEFAULT_PAYMENT_RETENTION_SEC,
+    DEFAULT_SHUTDOWN_DRAIN_TIMEOUT_SEC,
+};
+use crate::config::DEFAULT_LOG_LEVEL;
+use crate::ldk::{FEE_RATE, UTXO_SIZE_SAT};
+use crate::routes::{
+    AddressResponse, CreateUtxosRequest, EmptyResponse, InitRequest, InitResponse,
+    ListChannelsResponse, NetworkInfoResponse, NodeInfoResponse, OpenChannelRequest,
+    OpenChannelResponse, UnlockRequest, HTLC_MIN_MSAT,
+};
+use crate::tor::OnionServiceConfig;
+use crate::utils::{ELECTRUM_URL_REGTEST, PROXY_ENDPOINT_LOCAL};
+use crate::{app, shutdown_signal};
+
+pub use crate::routes::{Channel, ChannelStatus};
+
+const ELECTRUM_URL: &str = "127.0.0.1:50001";
+
+static MINER: Lazy<RwLock<Miner>> = Lazy::new(|| RwLock::new(Miner { no_mine_count: 0 }));
+
+impl Default for UserArgs {
+    fn default() -> Self {
+        Self {
+            network: BitcoinNetwork::Regtest,
+            storage_dir_path: PathBuf::from("tmp/test_name/nodeN"),
+            daemon_listening_port: 3001,
+            ldk_peer_listening_port: 9735,
+            grpc_listening_port: None,
+            max_media_upload_size_mb: 3,
+            root_public_key: None,
+            generate_root_key: false,
+            tls_config: None,
+            cors_allowed_origins: None,
+            url_prefix: None,
+            trust_forwarded_for: false,
+            max_requests_per_minute: DEFAULT_MAX_REQUESTS_PER_MINUTE,
+            max_invoices_per_minute: 0,
+            max_unpaid_invoices: 0,
+            max_token_invoices_per_minute: 0,
+            max_token_unpaid_invoices: 0,
+            watch_only: false,
+            cltv_expiry_delta: DEFAULT_CLTV_EXPIRY_DELTA,
+            max_total_cltv_expiry_delta: DEFAULT_MAX_TOTAL_CLTV_EXPIRY_DELTA,
+            min_final_cltv_expiry_delta: MIN_FINAL_CLTV_EXPIRY_DELTA,
+            address_gap_limit: DEFAULT_ADDRESS_GAP_LIMIT,
+            max_route_hints: DEFAULT_MAX_ROUTE_HINTS,
+            anchor_reserve_utxos: DEFAULT_ANCHOR_RESERVE_UTXOS,
+            anchor_reserve_utxo_size_sat: DEFAULT_ANCHOR_RESERVE_UTXO_SIZE_SAT,
+            min_channel_depth: DEFAULT_MIN_CHANNEL_DEPTH,
+            asset_htlc_min_msat: HTLC_MIN_MSAT,
+            // tests create the UTXOs they need explicitly
+            utxo_pool_target: 0,
+            utxo_pool_min: 0,
+            utxo_pool_utxo_size_sat: UTXO_SIZE_SAT,
+            gossip_bandwidth_cap: DEFAULT_GOSSIP_BANDWIDTH_CAP,
+            rgs_url: None,
+            htlc_risk_threshold_sat: DEFAULT_HTLC_RISK_THRESHOLD_SAT,
+            invoice_retention_sec: DEFAULT_INVOICE_RETENTION_SEC,
+            payment_retention_sec: DEFAULT_PAYMENT_RETENTION_SEC,
+            compact_storage_on_startup: false,
+            hook_command: None,
+            hook_timeout_sec: DEFAULT_HOOK_TIMEOUT_SEC,
+            hook_max_concurrency: DEFAULT_HOOK_MAX_CONCURRENCY,
+            notify_zmq_endpoint: None,
+            notify_mqtt_url: None,
+            notify_mqtt_topic: DEFAULT_NOTIFY_MQTT_TOPIC.to_string(),
+            auto_backup_target: None,
+            auto_backup_interval_sec: DEFAULT_AUTO_BACKUP_INTERVAL_SEC,
+            auto_backup_keep: DEFAULT_AUTO_BACKUP_KEEP,
+            auto_backup_full_every: DEFAULT_AUTO_BACKUP_FULL_EVERY,
+            shutdown_drain_timeout_sec: DEFAULT_SHUTDOWN_DRAIN_TIMEOUT_SEC,
+            swap_provider_url: None,
+            log_level: DEFAULT_LOG_LEVEL.to_string(),
+            log_format: LogFormat::Text,
+            storage_backend: StorageBackend::Filesystem,
+            migrate_storage_to: None,
+            migrate_storage_dir: None,
+            remote_storage_url: None,
+            encrypt_storage: false,
+            announce_alias: None,
+            announce_color: None,
+            phantom_secret: None,
+            auditor_key_path: None,
+            tor_proxy: None,
+            onion_service: OnionServiceConfig::default(),
+            log_level_handle: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Miner {
+    no_mine_count: u32,
+}
+
+impl Miner {
+    fn mine(&self, num_blocks: u16) -> bool {
+        if self.no_mine_count > 0 {
+            return false;
+        }
+        let status = Command::new("docker")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .arg("compose")
+            .args(_bitcoin_cli())
+            .arg("-rpcwallet=miner")
+            .arg("-generate")
+            .arg(num_blocks.to_string())
+            .status()
+            .expect("failed to mine");
+        assert!(status.success());
+        true
+    }
+
+    #[cfg(test)]
+    fn stop_mining(&mut self) {
+        self.no_mine_count += 1;
+    }
+
+    fn resume_mining(&mut self) {
+        if self.no_mine_count > 0 {
+            self.no_mine_count -= 1;
+        }
+    }
+}
+
+/// Start a regtest node serving its APIs on a random local port, initializing (unless
+/// `keep_node_dir` is set) and unlocking it, returning its address and password
+pub async fn start_node(
+    node_test_dir: &str,
+    node_peer_port: u16,
+    keep_node_dir: bool,
+) -> (SocketAddr, String) {
+    let args = UserArgs {
+        storage_dir_path: node_test_dir.into(),
+        ldk_peer_listening_port: node_peer_port,
+        ..Default::default()
+    };
+    start_node_with_args(args, keep_node_dir).await
+}
+
+/// Fund the node wallet from the regtest miner and create `num` (default 10) UTXOs
+pub async fn fund_and_create_utxos(node_address: SocketAddr, num: Option<u8>) {
+    println!("funding wallet for node {node_address}");
+    let addr = address(node_address).await;
+
+    _fund_wallet(addr);
+    mine(false);
+
+    create_utxos(node_address, false, Some(num.unwrap_or(10)), None).await;
+    mine(false);
+}
+
+/// Open a channel to the given peer, mining its funding TX and waiting for it to be ready
+pub async fn open_channel(
+    node_address: SocketAddr,
+    dest_peer_pubkey: &str,
+    dest_peer_port: Option<u16>,
+    capacity_sat: Option<u64>,
+    push_msat: Option<u64>,
+    asset_amount: Option<u64>,
+    asset_id: Option<&str>,
+) -> Channel {
+    open_channel_with_custom_data(
+        node_address,
+        dest_peer_pubkey,
+        dest_peer_port,
+        capacity_sat,
+        push_msat,
+        asset_amount,
+        asset_id,
+        None,
+        None,
+        None,
+        true,
+    )
+    .await
+}
+
+/// Wait for the node to report the expected number of usable channels
+pub async fn wait_for_usable_channels(
+    node_address: SocketAddr,
+    expected_num_usable_channels: usize,
+) {
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let node_info = node_info(node_address).await;
+        let num_usable_channels = node_info.num_usable_channels;
+        if num_usable_channels == expected_num_usable_channels {
+            break;
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!(
+                "num of usable channels ({num_usable_channels:?}) is not becoming the expected \
+                one ({expected_num_usable_channels:?})"
+            );
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
+
+fn _bitcoin_cli() -> [String; 7] {
+    [
+        s!("exec"),
+        s!("-T"),
+        s!("-u"),
+        s!("blits"),
+        s!("bitcoind"),
+        s!("bitcoin-cli"),
+        s!("-regtest"),
+    ]
+}
+
+pub(crate) async fn _check_response_is_ok(res: Response) -> Response {
+    if res.status() != reqwest::StatusCode::OK {
+        panic!("reqwest response is not OK: {:?}", res.text().await);
+    }
+    res
+}
+
+pub(crate) fn _fund_wallet(address: String) {
+    let status = Command::new("docker")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .arg("compose")
+        .args(_bitcoin_cli())
+        .arg("-rpcwallet=miner")
+        .arg("sendtoaddress")
+        .arg(address)
+        .arg("1")
+        .status()
+        .expect("failed to fund wallet");
+    assert!(status.success());
+}
+
+pub(crate) fn _get_txout(txid: &str) -> String {
+    String::from_utf8(
+        Command::new("docker")
+            .stdin(Stdio::null())
+            .arg("compose")
+            .args(_bitcoin_cli())
+            .arg("-rpcwallet=miner")
+            .arg("gettxout")
+            .arg(txid)
+            .arg("0")
+            .output()
+            .expect("failed get txout")
+            .stdout,
+    )
+    .unwrap()
+}
+
+pub(crate) async fn address(node_address: SocketAddr) -> String {
+    println!("getting address for node {node_address}");
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/address"))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<AddressResponse>()
+        .await
+        .unwrap()
+        .address
+}
+
+pub(crate) async fn create_utxos(
+    node_address: SocketAddr,
+    up_to: bool,
+    num: Option<u8>,
+    size: Option<u32>,
+) {
+    println!(
+        "creating{}{} UTXOs{} for node {node_address}",
+        if up_to { " up to" } else { "" },
+        if let Some(num) = num {
+            format!(" {}", num)
+        } else {
+            s!("")
+        },
+        if let Some(size) = size {
+            format!(" of size {}", size)
+        } else {
+            s!("")
+        },
+    );
+
+    let num = if num.is_some() { num } else { Some(10) };
+    let payload = CreateUtxosRequest {
+        up_to,
+        num,
+        size,
+        fee_rate: FEE_RATE,
+        skip_sync: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/createutxos"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
+}
+
+pub(crate) fn get_block_count() -> u32 {
+    let output = Command::new("docker")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .arg("compose")
+        .args(_bitcoin_cli())
+        .arg("getblockcount")
+        .output()
+        .expect("failed to call getblockcount");
+    assert!(output.status.success());
+    let blockcount_str =
+        std::str::from_utf8(&output.stdout).expect("could not parse blockcount output");
+    blockcount_str
+        .trim()
+        .parse::<u32>()
+        .expect("could not parse blockcount")
+}
+
+pub(crate) async fn list_channels(node_address: SocketAddr) -> Vec<Channel> {
+    println!("listing channels for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/listchannels"))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<ListChannelsResponse>()
+        .await
+        .unwrap()
+        .channels
+}
+
+pub(crate) fn mine(resume: bool) {
+    mine_n_blocks(resume, 1)
+}
+
+pub(crate) fn mine_n_blocks(resume: bool, num_blocks: u16) {
+    let t_0 = OffsetDateTime::now_utc();
+    if resume {
+        resume_mining();
+    }
+    let mut last_result = false;
+    while !last_result {
+        let miner = MINER.read();
+        last_result = miner
+            .as_ref()
+            .expect("MINER has been initialized")
+            .mine(num_blocks);
+        drop(miner);
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 120.0 {
+            eprintln!("forcibly breaking mining wait");
+            resume_mining();
+        }
+        if !last_result {
+            std::thread::sleep(std::time::Duration::from_millis(500));
+        }
+    }
+    wait_electrs_sync();
+}
+
+pub(crate) async fn network_info(node_address: SocketAddr) -> NetworkInfoResponse {
+    println!("getting network info for node {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/networkinfo"))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<NetworkInfoResponse>()
+        .await
+        .unwrap()
+}
+
+pub(crate) async fn node_info(node_address: SocketAddr) -> NodeInfoResponse {
+    println!("getting node info for {node_address}");
+    let res = reqwest::Client::new()
+        .get(format!("http://{node_address}/nodeinfo"))
+        .send()
+        .await
+        .unwrap();
+    _check_response_is_ok(res)
+        .await
+        .json::<NodeInfoResponse>()
+        .await
+        .unwrap()
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn open_channel_raw(
+    node_address: SocketAddr,
+    dest_peer_pubkey: &str,
+    dest_peer_port: Option<u16>,
+    capacity_sat: Option<u64>,
+    push_msat: Option<u64>,
+    asset_amount: Option<u64>,
+    asset_id: Option<&str>,
+    fee_base_msat: Option<u32>,
+    fee_proportional_millionths: Option<u32>,
+    temporary_channel_id: Option<&str>,
+    with_anchors: bool,
+    public: bool,
+) -> Result<Channel, reqwest::StatusCode> {
+    println!(
+        "opening channel with {asset_amount:?} of asset {asset_id:?} from node {node_address} \
+              to {dest_peer_pubkey}"
+    );
+
+    let blockcount = get_block_count();
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        let net_info = network_info(node_address).await;
+        if net_info.height == blockcount {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("height is not syncing");
+        }
+    }
+
+    let peer_pubkey_and_opt_addr = if let Some(p) = dest_peer_port {
+        format!("{dest_peer_pubkey}@127.0.0.1:{p}")
+    } else {
+        dest_peer_pubkey.to_string()
+    };
+    let payload = OpenChannelRequest {
+        peer_pubkey_and_opt_addr,
+        capacity_sat: capacity_sat.unwrap_or(100_000),
+        push_msat: push_msat.unwrap_or(0),
+        asset_amount,
+        asset_id: asset_id.map(|a| a.to_string()),
+        public,
+        with_anchors,
+        scid_alias: None,
+        htlc_minimum_msat: None,
+        max_htlc_value_in_flight_percent: None,
+        max_accepted_htlcs: None,
+        channel_reserve_proportional_millionths: None,
+        fee_base_msat,
+        fee_proportional_millionths,
+        temporary_channel_id: temporary_channel_id.map(|t| t.to_string()),
+        multisig_funding: None,
+        external_funding: None,
+        coin_selection: None,
+        totp_code: None,
+        dry_run: false,
+    };
+    let res = reqwest::Client::new()
+        .post(format!("http://{node_address}/openchannel"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap();
+
+    let status = res.status();
+    if !status.is_success() {
+        return Err(status);
+    }
+
+    res.json::<OpenChannelResponse>().await.unwrap();
+
+    let t_0 = OffsetDateTime::now_utc();
+    let mut channel_id = None;
+    let mut channel_funded = false;
+    while !channel_funded {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node_address).await;
+        if let Some(channel) = channels.iter().find(|c| {
+            !c.ready
+                && c.peer_pubkey == dest_peer_pubkey
+                && c.asset_id == asset_id.map(|id| id.to_string())
+                && c.asset_local_amount == asset_amount
+        }) {
+            if channel.funding_txid.is_some() {
+                let txout = _get_txout(channel.funding_txid.as_ref().unwrap());
+                if !txout.is_empty() {
+                    mine_n_blocks(false, 6);
+                    channel_id = Some(channel.channel_id.clone());
+                    channel_funded = true;
+                    continue;
+                }
+            }
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 50.0 {
+            panic!("cannot find funding TX")
+        }
+    }
+    let channel_id = channel_id.unwrap();
+
+    let t_0 = OffsetDateTime::now_utc();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        let channels = list_channels(node_address).await;
+        let channel = channels
+            .iter()
+            .find(|c| c.channel_id == channel_id)
+            .unwrap();
+        if channel.ready {
+            return Ok(channel.clone());
+        }
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("channel is taking too long to be ready")
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn open_channel_with_custom_data(
+    node_address: SocketAddr,
+    dest_peer_pubkey: &str,
+    dest_peer_port: Option<u16>,
+    capacity_sat: Option<u64>,
+    push_msat: Option<u64>,
+    asset_amount: Option<u64>,
+    asset_id: Option<&str>,
+    fee_base_msat: Option<u32>,
+    fee_proportional_millionths: Option<u32>,
+    temporary_channel_id: Option<&str>,
+    with_anchors: bool,
+) -> Channel {
+    open_channel_raw(
+        node_address,
+        dest_peer_pubkey,
+        dest_peer_port,
+        capacity_sat,
+        push_msat,
+        asset_amount,
+        asset_id,
+        fee_base_msat,
+        fee_proportional_millionths,
+        temporary_channel_id,
+        with_anchors,
+        true,
+    )
+    .await
+    .expect("channel opening should succeed")
+}
+
+fn resume_mining() {
+    MINER
+        .write()
+        .expect("MINER has been initialized")
+        .resume_mining()
+}
+
+pub(crate) async fn start_daemon_with_args(args: UserArgs) -> SocketAddr {
+    let listener = TcpListener::bind("0.0.0.0:0").await.unwrap();
+    let node_address = listener.local_addr().unwrap();
+    std::fs::create_dir_all(&args.storage_dir_path).unwrap();
+    let tls_config = args.tls_config.clone();
+    tokio::spawn(async move {
+        let (router, app_state) = app(args).await.unwrap();
+        crate::tls::serve(listener, router, tls_config, shutdown_signal(app_state))
+            .await
+            .unwrap();
+    });
+    node_address
+}
+
+pub(crate) async fn start_node_with_args(
+    args: UserArgs,
+    keep_node_dir: bool,
+) -> (SocketAddr, String) {
+    let node_test_dir = args.storage_dir_path.clone();
+    let node_peer_port = args.ldk_peer_listening_port;
+    println!("starting node with peer port {node_peer_port}");
+    if !keep_node_dir && node_test_dir.is_dir() {
+        std::fs::remove_dir_all(&node_test_dir).unwrap();
+    }
+    let node_address = start_daemon_with_args(args).await;
+
+    let password = format!("{}.{node_peer_port}", node_test_dir.display());
+
+    if !keep_node_dir {
+        let payload = InitRequest {
+            password: password.clone(),
+            network: None,
+            mnemonic: None,
+            passphrase: None,
+            account_index: None,
+            ldk_keys_only: false,
+        };
+        let res = reqwest::Client::new()
+            .post(format!("http://{node_address}/init"))
+            .json(&payload)
+            .send()
+            .await
+            .unwrap();
+        _check_response_is_ok(res)
+            .await
+            .json::<InitResponse>()
+            .await
+            .unwrap();
+    }
+
+    unlock(node_address, &password).await;
+
+    println!("node on peer port {node_peer_port} started with address {node_address:?}");
+    (node_address, password)
+}
+
+#[cfg(test)]
+pub(crate) fn stop_mining() {
+    MINER
+        .write()
+        .expect("MINER has been initialized")
+        .stop_mining()
+}
+
+pub(crate) async fn unlock(node_address: SocketAddr, password: &str) {
+    println!("unlocking node {node_address}");
+    let res = unlock_res(node_address, password).await;
+    _check_response_is_ok(res)
+        .await
+        .json::<EmptyResponse>()
+        .await
+        .unwrap();
+}
+
+pub(crate) fn unlock_req(password: &str) -> UnlockRequest {
+    UnlockRequest {
+        password: Zeroizing::new(password.to_string()),
+        bitcoind_rpc_username: s!("user"),
+        bitcoind_rpc_password: s!("password"),
+        bitcoind_rpc_host: s!("localhost"),
+        bitcoind_rpc_port: None,
+        indexer_url: Some(ELECTRUM_URL_REGTEST.to_string()),
+        fallback_indexer_urls: None,
+        proxy_endpoint: Some(PROXY_ENDPOINT_LOCAL.to_string()),
+        fallback_proxy_endpoints: None,
+        announce_addresses: vec![],
+        announce_alias: Some(s!("RLN_alias")),
+        background: None,
+    }
+}
+
+pub(crate) async fn unlock_res(node_address: SocketAddr, password: &str) -> Response {
+    println!("unlocking node {node_address}");
+    let payload = unlock_req(password);
+    reqwest::Client::new()
+        .post(format!("http://{node_address}/unlock"))
+        .json(&payload)
+        .send()
+        .await
+        .unwrap()
+}
+
+fn wait_electrs_sync() {
+    let t_0 = OffsetDateTime::now_utc();
+    let blockcount = get_block_count();
+    loop {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        let mut all_synced = true;
+        let electrum =
+            electrum_client::Client::new(ELECTRUM_URL).expect("cannot get electrum client");
+        if electrum.block_header(blockcount as usize).is_err() {
+            all_synced = false;
+        }
+        if all_synced {
+            break;
+        };
+        if (OffsetDateTime::now_utc() - t_0).as_seconds_f32() > 10.0 {
+            panic!("electrs not syncing with bitcoind");
+        }
+    }
+}
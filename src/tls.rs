@@ -0,0 +1,156 @@
+use amplify::s;
+use axum::Router;
+use axum_server::{tls_rustls::RustlsConfig, Handle};
+use rustls::{
+    pki_types::{CertificateDer, PrivateKeyDer},
+    server::WebPkiClientVerifier,
+    RootCertStore, ServerConfig,
+};
+use std::{
+    fs,
+    future::Future,
+    io::BufReader,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::net::TcpListener;
+
+use crate::error::AppError;
+
+const TLS_DIR: &str = "tls";
+const TLS_CERT_FNAME: &str = "cert.pem";
+const TLS_KEY_FNAME: &str = "key.pem";
+
+pub(crate) fn check_tls_args(
+    tls: bool,
+    cert_path: Option<PathBuf>,
+    key_path: Option<PathBuf>,
+    client_ca_path: Option<PathBuf>,
+    storage_dir_path: &Path,
+) -> Result<Option<Arc<ServerConfig>>, AppError> {
+    if !tls {
+        if cert_path.is_some() || key_path.is_some() || client_ca_path.is_some() {
+            tracing::error!("TLS files provided but TLS not enabled");
+            return Err(AppError::InvalidTlsConfig(s!("TLS is not enabled")));
+        }
+        return Ok(None);
+    }
+
+    let (cert_path, key_path) = match (cert_path, key_path) {
+        (Some(cert_path), Some(key_path)) => (cert_path, key_path),
+        (None, None) => generate_self_signed_cert(storage_dir_path)?,
+        _ => {
+            return Err(AppError::InvalidTlsConfig(s!(
+                "both the certificate and the key need to be provided"
+            )))
+        }
+    };
+    tracing::info!(
+        "Serving the APIs over TLS with certificate {}",
+        cert_path.display()
+    );
+
+    let certs = load_certs(&cert_path)?;
+    let key = load_key(&key_path)?;
+
+    let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| AppError::InvalidTlsConfig(e.to_string()))?;
+    let builder = if let Some(client_ca_path) = client_ca_path {
+        let mut roots = RootCertStore::empty();
+        for cert in load_certs(&client_ca_path)? {
+            roots
+                .add(cert)
+                .map_err(|e| AppError::InvalidTlsConfig(format!("invalid client CA: {e}")))?;
+        }
+        let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+            .build()
+            .map_err(|e| AppError::InvalidTlsConfig(format!("invalid client CA: {e}")))?;
+        tracing::info!(
+            "Requiring client certificates signed by {}",
+            client_ca_path.display()
+        );
+        builder.with_client_cert_verifier(verifier)
+    } else {
+        builder.with_no_client_auth()
+    };
+    let mut config = builder
+        .with_single_cert(certs, key)
+        .map_err(|e| AppError::InvalidTlsConfig(e.to_string()))?;
+    // the gRPC service requires HTTP/2
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    Ok(Some(Arc::new(config)))
+}
+
+/// Generate a self-signed certificate on first run, reusing it afterwards
+fn generate_self_signed_cert(storage_dir_path: &Path) -> Result<(PathBuf, PathBuf), AppError> {
+    let tls_dir = storage_dir_path.join(TLS_DIR);
+    let cert_path = tls_dir.join(TLS_CERT_FNAME);
+    let key_path = tls_dir.join(TLS_KEY_FNAME);
+    if cert_path.exists() && key_path.exists() {
+        return Ok((cert_path, key_path));
+    }
+
+    tracing::info!("Generating a self-signed TLS certificate");
+    let subject_alt_names = vec![s!("localhost"), s!("127.0.0.1"), s!("::1")];
+    let rcgen::CertifiedKey { cert, key_pair } =
+        rcgen::generate_simple_self_signed(subject_alt_names)
+            .map_err(|e| AppError::InvalidTlsConfig(e.to_string()))?;
+    fs::create_dir_all(&tls_dir)?;
+    fs::write(&cert_path, cert.pem())?;
+    fs::write(&key_path, key_pair.serialize_pem())?;
+    Ok((cert_path, key_path))
+}
+
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, AppError> {
+    let mut reader = BufReader::new(fs::File::open(path)?);
+    let certs = rustls_pemfile::certs(&mut reader).collect::<Result<Vec<_>, _>>()?;
+    if certs.is_empty() {
+        return Err(AppError::InvalidTlsConfig(format!(
+            "no certificate found in {}",
+            path.display()
+        )));
+    }
+    Ok(certs)
+}
+
+fn load_key(path: &Path) -> Result<PrivateKeyDer<'static>, AppError> {
+    let mut reader = BufReader::new(fs::File::open(path)?);
+    rustls_pemfile::private_key(&mut reader)?.ok_or_else(|| {
+        AppError::InvalidTlsConfig(format!("no private key found in {}", path.display()))
+    })
+}
+
+/// Serve the router on the listener, over TLS if configured, until shutdown completes
+pub(crate) async fn serve<F>(
+    listener: TcpListener,
+    router: Router,
+    tls_config: Option<Arc<ServerConfig>>,
+    shutdown: F,
+) -> std::io::Result<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let Some(tls_config) = tls_config else {
+        return axum::serve(
+            listener,
+            router.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(shutdown)
+        .await;
+    };
+
+    let handle = Handle::new();
+    let shutdown_handle = handle.clone();
+    tokio::spawn(async move {
+        shutdown.await;
+        shutdown_handle.graceful_shutdown(None);
+    });
+    axum_server::from_tcp_rustls(listener.into_std()?, RustlsConfig::from_config(tls_config))
+        .handle(handle)
+        .serve(router.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+}
@@ -0,0 +1,469 @@
+use amplify::s;
+use lightning::ln::msgs::SocketAddress;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::error::AppError;
+use crate::utils::get_current_timestamp;
+
+const SOCKS_VERSION: u8 = 0x05;
+const SOCKS_AUTH_NONE: u8 = 0x00;
+const SOCKS_AUTH_USERNAME_PASSWORD: u8 = 0x02;
+/// Version of the username/password subnegotiation (RFC 1929)
+const SOCKS_USERNAME_PASSWORD_VERSION: u8 = 0x01;
+const SOCKS_CMD_CONNECT: u8 = 0x01;
+const SOCKS_ATYP_IPV4: u8 = 0x01;
+const SOCKS_ATYP_DOMAIN: u8 = 0x03;
+const SOCKS_ATYP_IPV6: u8 = 0x04;
+const SOCKS_REPLY_SUCCEEDED: u8 = 0x00;
+
+/// Directory of the onion service where Tor looks for the authorized clients
+const AUTHORIZED_CLIENTS_DIR: &str = "authorized_clients";
+/// Prefix of the authorized client files written by the node, which are the only ones it replaces
+const AUTHORIZED_CLIENT_FILE_PREFIX: &str = "rln-";
+/// Length of a base32-encoded x25519 public key
+const X25519_BASE32_LEN: usize = 52;
+const INBOUND_WINDOW_SEC: u64 = 60;
+/// SOCKS credentials used to isolate the circuits when none are configured
+const ISOLATION_USERNAME: &str = "rln";
+const ISOLATION_PASSWORD: &str = "rln";
+
+/// SOCKS5 proxy (e.g. a Tor client, possibly on another machine) the outbound peer connections
+/// go through
+#[derive(Clone, Debug)]
+pub(crate) struct TorProxy {
+    pub(crate) addr: String,
+    pub(crate) credentials: Option<(String, String)>,
+    /// Refuse to use endpoints that would need a DNS resolution outside of Tor
+    pub(crate) no_clearnet_dns: bool,
+    /// Keep each circuit group on its own Tor circuits
+    pub(crate) isolate_circuits: bool,
+}
+
+/// Group of the connections made through the SOCKS proxy. Tor (with IsolateSOCKSAuth, its
+/// default) never shares a circuit between streams authenticated with different credentials, so
+/// each group gets its own password when the circuits are isolated.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum CircuitGroup {
+    /// Connections to peers we have no channel with, mostly exchanging gossip, and the downloads
+    /// of the gossip snapshots
+    Gossip,
+    /// Connections to the channel peers, which payments depend on
+    Payments,
+}
+
+impl fmt::Display for CircuitGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Gossip => write!(f, "gossip"),
+            Self::Payments => write!(f, "payments"),
+        }
+    }
+}
+
+impl TorProxy {
+    /// Credentials to authenticate the connections of the group with
+    fn group_credentials(&self, group: CircuitGroup) -> Option<(String, String)> {
+        if !self.isolate_circuits {
+            return self.credentials.clone();
+        }
+        let (username, password) = self
+            .credentials
+            .clone()
+            .unwrap_or((s!(ISOLATION_USERNAME), s!(ISOLATION_PASSWORD)));
+        Some((username, format!("{password}-{group}")))
+    }
+}
+
+pub(crate) fn check_tor_args(
+    socks_addr: Option<String>,
+    username: Option<String>,
+    password: Option<String>,
+    no_clearnet_dns: bool,
+    isolate_circuits: bool,
+) -> Result<Option<TorProxy>, AppError> {
+    let Some(addr) = socks_addr else {
+        if username.is_some() || password.is_some() || no_clearnet_dns || isolate_circuits {
+            return Err(AppError::InvalidTorConfig(s!(
+                "SOCKS credentials, --tor-no-clearnet-dns and --tor-isolate-circuits require \
+                 --tor-socks-addr"
+            )));
+        }
+        return Ok(None);
+    };
+    match addr.rsplit_once(':') {
+        Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {
+            if no_clearnet_dns && host_requires_dns(host) {
+                return Err(AppError::InvalidTorConfig(format!(
+                    "SOCKS proxy host {host} would be resolved with clearnet DNS, use its IP address"
+                )));
+            }
+        }
+        _ => {
+            return Err(AppError::InvalidTorConfig(format!(
+                "SOCKS proxy address {addr} is not in the host:port format"
+            )))
+        }
+    }
+    // isolated circuits append the group to the password
+    let max_password_len = if isolate_circuits {
+        255 - format!("-{}", CircuitGroup::Payments).len()
+    } else {
+        255
+    };
+    let credentials = match (username, password) {
+        (Some(username), Some(password)) => {
+            // RFC 1929 encodes each of them with a single length byte
+            if username.is_empty() || username.len() > 255 || password.len() > max_password_len {
+                return Err(AppError::InvalidTorConfig(format!(
+                    "SOCKS username must be 1 to 255 bytes long and password at most \
+                     {max_password_len} bytes long"
+                )));
+            }
+            Some((username, password))
+        }
+        (None, None) => None,
+        _ => {
+            return Err(AppError::InvalidTorConfig(s!(
+                "SOCKS username and password must be set together"
+            )))
+        }
+    };
+    Ok(Some(TorProxy {
+        addr,
+        credentials,
+        no_clearnet_dns,
+        isolate_circuits,
+    }))
+}
+
+/// Whether reaching the host needs a DNS resolution (IP addresses and localhost don't)
+fn host_requires_dns(host: &str) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    host.parse::<IpAddr>().is_err() && !host.eq_ignore_ascii_case("localhost")
+}
+
+/// Check that the endpoint (a URL, a host:port or a bare host) the node connects to without
+/// going through the SOCKS proxy can be reached without resolving its host with clearnet DNS
+pub(crate) fn check_no_clearnet_dns(endpoint: &str) -> Result<(), String> {
+    let host = if endpoint.contains("://") {
+        reqwest::Url::parse(endpoint)
+            .map_err(|e| format!("invalid endpoint {endpoint}: {e}"))?
+            .host_str()
+            .map(|h| h.to_string())
+            .unwrap_or_default()
+    } else if endpoint.starts_with('[') {
+        endpoint
+            .split_once(']')
+            .map(|(h, _)| h.to_string())
+            .unwrap_or_default()
+    } else {
+        match endpoint.rsplit_once(':') {
+            Some((host, _)) if !host.contains(':') => host.to_string(),
+            _ => endpoint.to_string(),
+        }
+    };
+    if host_requires_dns(&host) {
+        return Err(format!(
+            "{endpoint} would be resolved with clearnet DNS, use its IP address"
+        ));
+    }
+    Ok(())
+}
+
+fn socks_error(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::ConnectionRefused, msg.into())
+}
+
+fn socks_reply_error(reply: u8) -> &'static str {
+    match reply {
+        0x01 => "general SOCKS server failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown SOCKS error",
+    }
+}
+
+/// Open a TCP connection to the target through the SOCKS5 proxy, authenticating with username
+/// and password when credentials are configured or the circuits are isolated. Hostnames and
+/// onion addresses are passed to the proxy unresolved (as SOCKS5h does), so they never reach the
+/// local resolver.
+pub(crate) async fn connect_via_socks(
+    proxy: &TorProxy,
+    target: &SocketAddress,
+    group: CircuitGroup,
+) -> io::Result<TcpStream> {
+    let mut stream = TcpStream::connect(&proxy.addr).await?;
+
+    let credentials = proxy.group_credentials(group);
+    let method = if credentials.is_some() {
+        SOCKS_AUTH_USERNAME_PASSWORD
+    } else {
+        SOCKS_AUTH_NONE
+    };
+    stream.write_all(&[SOCKS_VERSION, 1, method]).await?;
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply).await?;
+    if reply[0] != SOCKS_VERSION {
+        return Err(socks_error("the proxy is not a SOCKS5 server"));
+    }
+    if reply[1] != method {
+        return Err(socks_error(
+            "the proxy doesn't accept the configured authentication method",
+        ));
+    }
+
+    if let Some((username, password)) = &credentials {
+        let mut auth = vec![SOCKS_USERNAME_PASSWORD_VERSION, username.len() as u8];
+        auth.extend_from_slice(username.as_bytes());
+        auth.push(password.len() as u8);
+        auth.extend_from_slice(password.as_bytes());
+        stream.write_all(&auth).await?;
+        let mut reply = [0u8; 2];
+        stream.read_exact(&mut reply).await?;
+        if reply[1] != 0x00 {
+            return Err(socks_error("the proxy rejected the SOCKS credentials"));
+        }
+    }
+
+    let mut request = vec![SOCKS_VERSION, SOCKS_CMD_CONNECT, 0x00];
+    match target {
+        SocketAddress::TcpIpV4 { addr, port } => {
+            request.push(SOCKS_ATYP_IPV4);
+            request.extend_from_slice(addr);
+            request.extend_from_slice(&port.to_be_bytes());
+        }
+        SocketAddress::TcpIpV6 { addr, port } => {
+            request.push(SOCKS_ATYP_IPV6);
+            request.extend_from_slice(addr);
+            request.extend_from_slice(&port.to_be_bytes());
+        }
+        _ => {
+            let target = target.to_string();
+            let Some((host, port)) = target
+                .rsplit_once(':')
+                .and_then(|(host, port)| Some((host, port.parse::<u16>().ok()?)))
+            else {
+                return Err(socks_error(format!("invalid target address {target}")));
+            };
+            request.push(SOCKS_ATYP_DOMAIN);
+            request.push(host.len() as u8);
+            request.extend_from_slice(host.as_bytes());
+            request.extend_from_slice(&port.to_be_bytes());
+        }
+    }
+    stream.write_all(&request).await?;
+
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply).await?;
+    if reply[1] != SOCKS_REPLY_SUCCEEDED {
+        return Err(socks_error(socks_reply_error(reply[1])));
+    }
+    // skip the address the proxy bound, which is of no use
+    let bound_addr_len = match reply[3] {
+        SOCKS_ATYP_IPV4 => 4,
+        SOCKS_ATYP_IPV6 => 16,
+        SOCKS_ATYP_DOMAIN => stream.read_u8().await? as usize,
+        _ => return Err(socks_error("invalid SOCKS reply")),
+    };
+    let mut bound_addr = vec![0u8; bound_addr_len + 2];
+    stream.read_exact(&mut bound_addr).await?;
+
+    Ok(stream)
+}
+
+/// Download the resource at the plain HTTP URL (e.g. of an onion service, whose transport is
+/// already encrypted) through the SOCKS5 proxy
+pub(crate) async fn http_get_via_socks(
+    proxy: &TorProxy,
+    url: &reqwest::Url,
+    group: CircuitGroup,
+) -> io::Result<Vec<u8>> {
+    let http_error = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+    if url.scheme() != "http" {
+        return Err(http_error(format!("{url} is not a plain HTTP URL")));
+    }
+    let host = url.host_str().unwrap_or_default();
+    let port = url.port_or_known_default().unwrap_or(80);
+    let target = SocketAddress::from_str(&format!("{host}:{port}"))
+        .map_err(|_| http_error(format!("invalid host in {url}")))?;
+    let mut stream = connect_via_socks(proxy, &target, group).await?;
+
+    let mut path = url.path().to_string();
+    if let Some(query) = url.query() {
+        path = format!("{path}?{query}");
+    }
+    // HTTP/1.0 responses are never chunked and end with the connection
+    let request = format!("GET {path} HTTP/1.0\r\nHost: {host}\r\nConnection: close\r\n\r\n");
+    stream.write_all(request.as_bytes()).await?;
+    let mut response = vec![];
+    stream.read_to_end(&mut response).await?;
+
+    let Some(headers_len) = response.windows(4).position(|w| w == b"\r\n\r\n") else {
+        return Err(http_error(format!("invalid HTTP response from {url}")));
+    };
+    let headers = String::from_utf8_lossy(&response[..headers_len]);
+    let status = headers.split_whitespace().nth(1).unwrap_or_default();
+    if status != "200" {
+        return Err(http_error(format!("{url} returned HTTP status {status}")));
+    }
+    Ok(response.split_off(headers_len + 4))
+}
+
+/// Settings of the onion service that forwards inbound peer connections to the LDK peer port
+#[derive(Clone, Debug, Default)]
+pub(crate) struct OnionServiceConfig {
+    pub(crate) service_dir: Option<PathBuf>,
+    pub(crate) authorized_clients: Vec<String>,
+    pub(crate) max_inbound_connections: u16,
+    pub(crate) max_inbound_per_minute: u16,
+}
+
+pub(crate) fn check_onion_service_args(
+    service_dir: Option<PathBuf>,
+    authorized_clients: Option<String>,
+    max_inbound_connections: u16,
+    max_inbound_per_minute: u16,
+) -> Result<OnionServiceConfig, AppError> {
+    let mut clients = vec![];
+    for client in authorized_clients.iter().flat_map(|c| c.split(',')) {
+        let client = client.trim().to_ascii_uppercase();
+        if client.len() != X25519_BASE32_LEN
+            || !client.chars().all(|c| matches!(c, 'A'..='Z' | '2'..='7'))
+        {
+            return Err(AppError::InvalidTorConfig(format!(
+                "authorized client {client} is not a base32-encoded x25519 public key"
+            )));
+        }
+        clients.push(client);
+    }
+    if !clients.is_empty() && service_dir.is_none() {
+        return Err(AppError::InvalidTorConfig(s!(
+            "authorized clients require --tor-onion-service-dir"
+        )));
+    }
+    Ok(OnionServiceConfig {
+        service_dir,
+        authorized_clients: clients,
+        max_inbound_connections,
+        max_inbound_per_minute,
+    })
+}
+
+/// Replace the authorized client files written by the node with the configured ones (Tor needs
+/// to be reloaded to pick them up). With no authorized clients, any client can reach the onion
+/// service, unless the operator configured some.
+fn write_authorized_clients(service_dir: &Path, clients: &[String]) -> io::Result<()> {
+    let clients_dir = service_dir.join(AUTHORIZED_CLIENTS_DIR);
+    fs::create_dir_all(&clients_dir)?;
+    for entry in fs::read_dir(&clients_dir)? {
+        let path = entry?.path();
+        let fname = path.file_name().unwrap_or_default().to_string_lossy();
+        if fname.starts_with(AUTHORIZED_CLIENT_FILE_PREFIX) && fname.ends_with(".auth") {
+            fs::remove_file(&path)?;
+        }
+    }
+    for (idx, client) in clients.iter().enumerate() {
+        fs::write(
+            clients_dir.join(format!("{AUTHORIZED_CLIENT_FILE_PREFIX}{idx}.auth")),
+            format!("descriptor:x25519:{client}\n"),
+        )?;
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct InboundStats {
+    pub(crate) active: u16,
+    pub(crate) accepted: u64,
+    pub(crate) rejected_max_connections: u64,
+    pub(crate) rejected_throttled: u64,
+    window_start: u64,
+    window_count: u16,
+}
+
+/// Keeps the inbound connections coming through the onion service (which Tor forwards from
+/// loopback) within the configured limits, to make probing and scanning the node expensive
+pub(crate) struct TorConnectionManager {
+    pub(crate) config: OnionServiceConfig,
+    inbound: Mutex<InboundStats>,
+}
+
+/// Slot of an accepted onion service connection, freed when dropped
+pub(crate) struct InboundPermit {
+    manager: Arc<TorConnectionManager>,
+}
+
+impl Drop for InboundPermit {
+    fn drop(&mut self) {
+        let mut inbound = self.manager.inbound.lock().unwrap();
+        inbound.active = inbound.active.saturating_sub(1);
+    }
+}
+
+impl TorConnectionManager {
+    pub(crate) fn new(config: OnionServiceConfig) -> Result<Self, AppError> {
+        if let Some(service_dir) = &config.service_dir {
+            write_authorized_clients(service_dir, &config.authorized_clients).map_err(|e| {
+                AppError::InvalidTorConfig(format!("cannot write the authorized clients: {e}"))
+            })?;
+            if !config.authorized_clients.is_empty() {
+                tracing::info!(
+                    "Authorized {} clients to reach the onion service (Tor needs a reload)",
+                    config.authorized_clients.len()
+                );
+            }
+        }
+        Ok(Self {
+            config,
+            inbound: Mutex::new(InboundStats::default()),
+        })
+    }
+
+    /// Admit an inbound connection coming through the onion service, unless the max number of
+    /// connections is reached or too many were opened in the last minute
+    pub(crate) fn admit_inbound(self: &Arc<Self>) -> Option<InboundPermit> {
+        let now = get_current_timestamp();
+        let mut inbound = self.inbound.lock().unwrap();
+        if now >= inbound.window_start + INBOUND_WINDOW_SEC {
+            inbound.window_start = now;
+            inbound.window_count = 0;
+        }
+        let max_connections = self.config.max_inbound_connections;
+        if max_connections > 0 && inbound.active >= max_connections {
+            inbound.rejected_max_connections += 1;
+            tracing::info!("Dropping onion service connection: {max_connections} already open");
+            return None;
+        }
+        let max_per_minute = self.config.max_inbound_per_minute;
+        if max_per_minute > 0 && inbound.window_count >= max_per_minute {
+            inbound.rejected_throttled += 1;
+            tracing::info!(
+                "Dropping onion service connection: {max_per_minute} in the last minute"
+            );
+            return None;
+        }
+        inbound.active += 1;
+        inbound.accepted += 1;
+        inbound.window_count += 1;
+        Some(InboundPermit {
+            manager: Arc::clone(self),
+        })
+    }
+
+    pub(crate) fn inbound_stats(&self) -> InboundStats {
+        self.inbound.lock().unwrap().clone()
+    }
+}
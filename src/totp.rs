@@ -0,0 +1,263 @@
+use bitcoin::hashes::hmac::{Hmac, HmacEngine};
+use bitcoin::hashes::{sha1, sha256, Hash, HashEngine};
+use lightning::impl_writeable_tlv_based;
+use lightning::util::ser::Writeable;
+use rand::RngCore;
+use std::sync::Mutex;
+
+use crate::error::APIError;
+use crate::utils::{get_current_timestamp, hex_str};
+
+const TOTP_ISSUER: &str = "RGB Lightning Node";
+const TOTP_STEP_SEC: u64 = 30;
+const TOTP_DIGITS: u32 = 6;
+const TOTP_SECRET_LEN: usize = 20;
+/// Steps before and after the current one accepted to tolerate clock drift
+const TOTP_SKEW_STEPS: u64 = 1;
+const RECOVERY_CODES_NUM: usize = 8;
+const RECOVERY_CODE_LEN: usize = 5;
+/// Wrong codes accepted before verification gets locked for TOTP_LOCKOUT_SEC
+const MAX_FAILED_ATTEMPTS: u8 = 5;
+const TOTP_LOCKOUT_SEC: u64 = 300;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+pub(crate) struct TotpInfo {
+    secret: Vec<u8>,
+    recovery_code_hashes: Vec<String>,
+    payment_threshold_msat: u64,
+    enabled: bool,
+    last_used_step: u64,
+}
+
+impl_writeable_tlv_based!(TotpInfo, {
+    (0, secret, required),
+    (2, recovery_code_hashes, required_vec),
+    (4, payment_threshold_msat, required),
+    (6, enabled, required),
+    (8, last_used_step, required),
+});
+
+pub(crate) struct TotpState {
+    pub(crate) totp: Option<TotpInfo>,
+}
+
+impl_writeable_tlv_based!(TotpState, {
+    (0, totp, option),
+});
+
+/// Data to be shown to the user once, when enrolling
+pub(crate) struct TotpEnrollment {
+    pub(crate) secret: String,
+    pub(crate) uri: String,
+    pub(crate) recovery_codes: Vec<String>,
+}
+
+/// Operation that may require a TOTP code
+pub(crate) enum TotpOperation {
+    /// On-chain spends and channel closes, always requiring a code
+    Spend,
+    /// LN payment of the given amount, requiring a code above the enrolled threshold
+    Payment(u64),
+}
+
+#[derive(Default)]
+struct TotpAttempts {
+    failures: u8,
+    locked_until: u64,
+}
+
+fn base32_encode(data: &[u8]) -> String {
+    let mut encoded = String::new();
+    for chunk in data.chunks(5) {
+        let mut buf = [0u8; 5];
+        buf[..chunk.len()].copy_from_slice(chunk);
+        let bits = buf.iter().fold(0u64, |acc, b| acc << 8 | *b as u64);
+        let chars = (chunk.len() * 8).div_ceil(5);
+        for i in 0..chars {
+            let index = (bits >> (35 - i * 5)) & 0x1f;
+            encoded.push(BASE32_ALPHABET[index as usize] as char);
+        }
+    }
+    encoded
+}
+
+/// Compute the RFC 6238 code (HMAC-SHA1, 6 digits) of the given time step
+pub(crate) fn totp_code(secret: &[u8], step: u64) -> String {
+    let mut engine = HmacEngine::<sha1::Hash>::new(secret);
+    engine.input(&step.to_be_bytes());
+    let hmac = Hmac::<sha1::Hash>::from_engine(engine).to_byte_array();
+    let offset = (hmac[hmac.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes(hmac[offset..offset + 4].try_into().unwrap()) & 0x7fffffff;
+    format!(
+        "{:0width$}",
+        binary % 10u32.pow(TOTP_DIGITS),
+        width = TOTP_DIGITS as usize
+    )
+}
+
+pub(crate) fn totp_step(timestamp: u64) -> u64 {
+    timestamp / TOTP_STEP_SEC
+}
+
+fn hash_recovery_code(code: &str) -> String {
+    sha256::Hash::hash(code.trim().to_lowercase().as_bytes()).to_string()
+}
+
+fn generate_recovery_code() -> String {
+    let mut bytes = [0u8; RECOVERY_CODE_LEN];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let code = hex_str(&bytes);
+    format!(
+        "{}-{}",
+        &code[..RECOVERY_CODE_LEN],
+        &code[RECOVERY_CODE_LEN..]
+    )
+}
+
+impl TotpInfo {
+    /// Check a TOTP code, refusing the ones of steps that have already been used
+    fn verify_code(&mut self, code: &str) -> bool {
+        let current_step = totp_step(get_current_timestamp());
+        let code = code.trim();
+        let matching_step = (current_step.saturating_sub(TOTP_SKEW_STEPS)
+            ..=current_step + TOTP_SKEW_STEPS)
+            .filter(|s| *s > self.last_used_step)
+            .find(|s| totp_code(&self.secret, *s) == code);
+        if let Some(step) = matching_step {
+            self.last_used_step = step;
+            return true;
+        }
+        false
+    }
+
+    /// Check a recovery code, which can be used only once
+    fn use_recovery_code(&mut self, code: &str) -> bool {
+        let hash = hash_recovery_code(code);
+        let len_before = self.recovery_code_hashes.len();
+        self.recovery_code_hashes.retain(|h| *h != hash);
+        self.recovery_code_hashes.len() < len_before
+    }
+}
+
+/// Second factor required by spending operations once enrolled, with a lockout after too many
+/// wrong codes
+pub(crate) struct TotpVerifier {
+    state: Mutex<TotpState>,
+    attempts: Mutex<TotpAttempts>,
+}
+
+impl TotpVerifier {
+    pub(crate) fn new(state: TotpState) -> Self {
+        Self {
+            state: Mutex::new(state),
+            attempts: Mutex::new(TotpAttempts::default()),
+        }
+    }
+
+    /// Run the check of a code, accounting failures and refusing checks while locked
+    fn attempt(&self, check: impl FnOnce() -> bool) -> Result<(), APIError> {
+        let now = get_current_timestamp();
+        let mut attempts = self.attempts.lock().unwrap();
+        if attempts.locked_until > now {
+            return Err(APIError::TotpRateLimited(attempts.locked_until - now));
+        }
+        if check() {
+            attempts.failures = 0;
+            return Ok(());
+        }
+        attempts.failures += 1;
+        if attempts.failures >= MAX_FAILED_ATTEMPTS {
+            tracing::warn!("Too many wrong TOTP codes, locking verification");
+            attempts.failures = 0;
+            attempts.locked_until = now + TOTP_LOCKOUT_SEC;
+        }
+        Err(APIError::WrongTotpCode)
+    }
+
+    /// Start a new enrollment, to be confirmed with a valid code before it gets enabled
+    pub(crate) fn enroll(
+        &self,
+        payment_threshold_msat: u64,
+        account: &str,
+    ) -> Result<(TotpEnrollment, Vec<u8>), APIError> {
+        let mut state = self.state.lock().unwrap();
+        if state.totp.as_ref().is_some_and(|t| t.enabled) {
+            return Err(APIError::TotpAlreadyEnabled);
+        }
+
+        let mut secret = vec![0u8; TOTP_SECRET_LEN];
+        rand::thread_rng().fill_bytes(&mut secret);
+        let recovery_codes: Vec<String> = (0..RECOVERY_CODES_NUM)
+            .map(|_| generate_recovery_code())
+            .collect();
+        let secret_base32 = base32_encode(&secret);
+        let issuer = TOTP_ISSUER.replace(' ', "%20");
+        let uri = format!(
+            "otpauth://totp/{issuer}:{account}?secret={secret_base32}&issuer={issuer}&algorithm=SHA1&digits={TOTP_DIGITS}&period={TOTP_STEP_SEC}"
+        );
+
+        state.totp = Some(TotpInfo {
+            secret,
+            recovery_code_hashes: recovery_codes
+                .iter()
+                .map(|c| hash_recovery_code(c))
+                .collect(),
+            payment_threshold_msat,
+            enabled: false,
+            last_used_step: 0,
+        });
+        let enrollment = TotpEnrollment {
+            secret: secret_base32,
+            uri,
+            recovery_codes,
+        };
+        Ok((enrollment, state.encode()))
+    }
+
+    /// Enable the pending enrollment, proving the authenticator app has been set up
+    pub(crate) fn confirm(&self, code: &str) -> Result<Vec<u8>, APIError> {
+        let mut state = self.state.lock().unwrap();
+        let totp = state.totp.as_mut().ok_or(APIError::TotpNotEnrolled)?;
+        if totp.enabled {
+            return Err(APIError::TotpAlreadyEnabled);
+        }
+        self.attempt(|| totp.verify_code(code))?;
+        totp.enabled = true;
+        Ok(state.encode())
+    }
+
+    /// Disable TOTP, given a valid TOTP or recovery code
+    pub(crate) fn disable(&self, code: &str) -> Result<Vec<u8>, APIError> {
+        let mut state = self.state.lock().unwrap();
+        let totp = match state.totp.as_mut() {
+            Some(totp) if totp.enabled => totp,
+            _ => return Err(APIError::TotpNotEnrolled),
+        };
+        self.attempt(|| totp.verify_code(code) || totp.use_recovery_code(code))?;
+        state.totp = None;
+        Ok(state.encode())
+    }
+
+    /// Check the code required by the operation, if any. A TOTP or recovery code is accepted.
+    /// Returns the state to be persisted when a code has been used.
+    pub(crate) fn check(
+        &self,
+        code: Option<&str>,
+        operation: TotpOperation,
+    ) -> Result<Option<Vec<u8>>, APIError> {
+        let mut state = self.state.lock().unwrap();
+        let totp = match state.totp.as_mut() {
+            Some(totp) if totp.enabled => totp,
+            _ => return Ok(None),
+        };
+        if let TotpOperation::Payment(amt_msat) = operation {
+            if amt_msat <= totp.payment_threshold_msat {
+                return Ok(None);
+            }
+        }
+        let code = code.ok_or(APIError::MissingTotpCode)?;
+        self.attempt(|| totp.verify_code(code) || totp.use_recovery_code(code))?;
+        Ok(Some(state.encode()))
+    }
+}
@@ -3,38 +3,70 @@ use bitcoin::io;
 use bitcoin::secp256k1::PublicKey;
 use futures::Future;
 use lightning::ln::channel_state::ChannelDetails;
+use lightning::ln::msgs::SocketAddress;
 use lightning::ln::types::ChannelId;
 use lightning::routing::router::{
     Payee, PaymentParameters, Route, RouteHint, RouteParameters, Router as _,
-    DEFAULT_MAX_TOTAL_CLTV_EXPIRY_DELTA, MAX_PATH_LENGTH_ESTIMATE,
+    MAX_PATH_LENGTH_ESTIMATE,
 };
 use lightning::{
     onion_message::packet::OnionMessageContents,
-    sign::KeysManager,
+    rgb_utils::BITCOIN_NETWORK_FNAME,
+    sign::PhantomKeysManager,
+    types::payment::{PaymentHash, PaymentPreimage},
     util::ser::{Writeable, Writer},
 };
-use lightning_persister::fs_store::FilesystemStore;
 use magic_crypt::{new_magic_crypt, MagicCryptTrait};
 use rgb_lib::{bdk_wallet::keys::bip39::Mnemonic, BitcoinNetwork, ContractId};
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     fmt::Write,
     fs,
-    net::{SocketAddr, TcpStream, ToSocketAddrs},
+    net::{SocketAddr, TcpStream},
     path::Path,
     path::PathBuf,
     str::FromStr,
     sync::{Arc, Mutex, MutexGuard},
     time::{Duration, SystemTime},
 };
-use tokio::sync::{Mutex as TokioMutex, MutexGuard as TokioMutexGuard};
+use tokio::sync::{
+    Mutex as TokioMutex, RwLock as TokioRwLock, RwLockReadGuard as TokioRwLockReadGuard,
+};
 use tokio_util::sync::CancellationToken;
+use zeroize::Zeroizing;
 
-use crate::ldk::{ChannelIdsMap, Router};
+use crate::auth::TokenSpendingMap;
+use crate::auto_backup::AutoBackupTarget;
+#[cfg(feature = "failure-injection")]
+use crate::failure_injection::FailureInjector;
+use crate::gossip::GossipHandler;
+use crate::hedging::{HedgeMap, HedgingPolicyMap};
+use crate::hooks::HookRunner;
+use crate::ldk::{
+    AmountBoundsMap, AnchorReserveMap, AssetPolicyMap, ChainSubscriptionMap, ChannelAcceptorPolicy,
+    ChannelDepthOverrides, ChannelEventMap, ChannelIdsMap, EmergencyKitState, EscrowMap,
+    ExternalFundingMap, FallbackAddressMap, ForceCloseFeerateMap, ForwardMap,
+    FundingCoinSelectionMap, HodlInvoiceMap, InterceptScopeMap, InterceptedHtlcMap,
+    IssuedAddressMap, JitChannelMap, JournalMap, LnurlPayConfig, LockedUtxoMap, MultisigFundingMap,
+    NodeAnnouncementConfig, OfferMap, OrderMap, PeerFilter, PendingBroadcastMap, PendingChannelMap,
+    ProbeMap, ProbeStatsMap, Router, SettlementMap, SweepConfig, LDK_SEED_DERIVATION_PATH,
+};
+use crate::lsps::LspsMessageHandler;
+use crate::notify::NotificationPublisher;
+use crate::nwc::NwcConnectionMap;
+use crate::peer_messages::AssetHtlcMinHandler;
+use crate::proxy::RequestWindowMap;
+use crate::quota::InvoiceQuotas;
 use crate::rgb::{get_rgb_channel_info_optional, RgbLibWalletWrapper};
-use crate::routes::{DEFAULT_FINAL_CLTV_EXPIRY_DELTA, HTLC_MIN_MSAT};
+use crate::routes::{SyncStage, DEFAULT_FINAL_CLTV_EXPIRY_DELTA, HTLC_MIN_MSAT};
+use crate::store::NodeStore;
+use crate::submarine::SubmarineSwapMap;
+use crate::tor::{connect_via_socks, CircuitGroup, TorConnectionManager, TorProxy};
+use crate::totp::TotpVerifier;
 use crate::{
-    args::UserArgs,
+    args::{StorageBackend, UserArgs},
+    bitcoind::BitcoindClient,
+    config::{LogLevelHandle, RuntimeConfig},
     disk::FilesystemLogger,
     error::{APIError, AppError},
     ldk::{
@@ -54,15 +86,26 @@ pub(crate) const ELECTRUM_URL_MAINNET: &str = "ssl://electrum.iriswallet.com:500
 pub(crate) const PROXY_ENDPOINT_LOCAL: &str = "rpc://127.0.0.1:3000/json-rpc";
 pub(crate) const PROXY_ENDPOINT_PUBLIC: &str = "rpcs://proxy.iriswallet.com/0.2/json-rpc";
 const PASSWORD_MIN_LENGTH: u8 = 8;
+pub(crate) const PEER_CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+const PEER_CONNECTION_RETRY_DELAY: Duration = Duration::from_secs(1);
 
 pub(crate) struct AppState {
     pub(crate) static_state: Arc<StaticState>,
     pub(crate) cancel_token: CancellationToken,
-    pub(crate) unlocked_app_state: Arc<TokioMutex<Option<Arc<UnlockedAppState>>>>,
+    pub(crate) unlocked_app_state: Arc<TokioRwLock<Option<Arc<UnlockedAppState>>>>,
     pub(crate) ldk_background_services: Arc<Mutex<Option<LdkBackgroundServices>>>,
     pub(crate) changing_state: Mutex<bool>,
-    pub(crate) root_public_key: Option<biscuit_auth::PublicKey>,
+    pub(crate) draining: Mutex<bool>,
+    pub(crate) root_public_key: Mutex<Option<biscuit_auth::PublicKey>>,
+    pub(crate) generate_root_key: bool,
     pub(crate) revoked_tokens: Arc<Mutex<HashSet<Vec<u8>>>>,
+    pub(crate) runtime_config: Mutex<RuntimeConfig>,
+    pub(crate) log_level_handle: Option<LogLevelHandle>,
+    pub(crate) sync_progress: Mutex<SyncProgress>,
+    pub(crate) request_windows: Mutex<RequestWindowMap>,
+    pub(crate) invoice_quotas: Mutex<InvoiceQuotas>,
+    pub(crate) notification_publisher: Option<Arc<NotificationPublisher>>,
+    pub(crate) tor_connection_manager: Arc<TorConnectionManager>,
 }
 
 impl AppState {
@@ -70,17 +113,72 @@ impl AppState {
         self.changing_state.lock().unwrap()
     }
 
+    pub(crate) fn get_draining(&self) -> MutexGuard<'_, bool> {
+        self.draining.lock().unwrap()
+    }
+
     pub(crate) fn get_ldk_background_services(
         &self,
     ) -> MutexGuard<'_, Option<LdkBackgroundServices>> {
         self.ldk_background_services.lock().unwrap()
     }
 
+    pub(crate) fn get_root_public_key(&self) -> MutexGuard<'_, Option<biscuit_auth::PublicKey>> {
+        self.root_public_key.lock().unwrap()
+    }
+
+    pub(crate) fn get_sync_progress(&self) -> MutexGuard<'_, SyncProgress> {
+        self.sync_progress.lock().unwrap()
+    }
+
     pub(crate) async fn get_unlocked_app_state(
         &self,
-    ) -> TokioMutexGuard<'_, Option<Arc<UnlockedAppState>>> {
-        self.unlocked_app_state.lock().await
+    ) -> TokioRwLockReadGuard<'_, Option<Arc<UnlockedAppState>>> {
+        self.unlocked_app_state.read().await
+    }
+
+    /// Record the failure of the unlock in progress
+    pub(crate) fn fail_sync_progress(&self, error: &APIError) {
+        let mut sync_progress = self.get_sync_progress();
+        sync_progress.stage = SyncStage::Failed;
+        sync_progress.error = Some(error.to_string());
+        sync_progress.updated_at = Some(get_current_timestamp());
+    }
+
+    /// Record the beginning of an unlock
+    pub(crate) fn start_sync_progress(&self) {
+        let now = get_current_timestamp();
+        *self.get_sync_progress() = SyncProgress {
+            stage: SyncStage::Unlocking,
+            error: None,
+            started_at: Some(now),
+            updated_at: Some(now),
+        };
     }
+
+    pub(crate) fn update_sync_stage(&self, stage: SyncStage) {
+        let mut sync_progress = self.get_sync_progress();
+        sync_progress.stage = stage;
+        sync_progress.updated_at = Some(get_current_timestamp());
+    }
+}
+
+/// Progress of the unlock of the node, which can continue in the background
+#[derive(Clone, Default)]
+pub(crate) struct SyncProgress {
+    pub(crate) stage: SyncStage,
+    pub(crate) error: Option<String>,
+    pub(crate) started_at: Option<u64>,
+    pub(crate) updated_at: Option<u64>,
+}
+
+/// Defaults bundled with each supported network
+pub(crate) struct NetworkParams {
+    /// Chain name reported by bitcoind
+    pub(crate) bitcoind_chain: &'static str,
+    pub(crate) bitcoind_rpc_port: u16,
+    pub(crate) indexer_url: &'static str,
+    pub(crate) proxy_endpoint: &'static str,
 }
 
 pub(crate) struct StaticState {
@@ -90,18 +188,49 @@ pub(crate) struct StaticState {
     pub(crate) ldk_data_dir: PathBuf,
     pub(crate) logger: Arc<FilesystemLogger>,
     pub(crate) max_media_upload_size_mb: u16,
+    pub(crate) watch_only: bool,
+    pub(crate) cltv_expiry_delta: u16,
+    pub(crate) max_total_cltv_expiry_delta: u32,
+    pub(crate) min_final_cltv_expiry_delta: u16,
+    pub(crate) address_gap_limit: u32,
+    pub(crate) anchor_reserve_utxos: u8,
+    pub(crate) anchor_reserve_utxo_size_sat: u64,
+    pub(crate) min_channel_depth: u32,
+    pub(crate) asset_htlc_min_msat: u64,
+    pub(crate) utxo_pool_target: u8,
+    pub(crate) utxo_pool_min: u8,
+    pub(crate) utxo_pool_utxo_size_sat: u32,
+    pub(crate) compact_storage_on_startup: bool,
+    pub(crate) hook_timeout_sec: u64,
+    pub(crate) hook_max_concurrency: u8,
+    pub(crate) auto_backup_target: Option<AutoBackupTarget>,
+    pub(crate) auto_backup_interval_sec: u64,
+    pub(crate) auto_backup_keep: u16,
+    pub(crate) auto_backup_full_every: u16,
+    pub(crate) shutdown_drain_timeout_sec: u64,
+    pub(crate) swap_provider_url: Option<String>,
+    pub(crate) storage_backend: StorageBackend,
+    pub(crate) remote_storage_url: Option<String>,
+    pub(crate) encrypt_storage: bool,
+    pub(crate) phantom_secret: Option<[u8; 32]>,
+    pub(crate) announce_alias: Option<String>,
+    pub(crate) announce_color: Option<[u8; 3]>,
+    pub(crate) url_prefix: Option<String>,
+    pub(crate) trust_forwarded_for: bool,
+    pub(crate) tor_proxy: Option<TorProxy>,
+    pub(crate) rgs_url: Option<String>,
 }
 
 pub(crate) struct UnlockedAppState {
     pub(crate) channel_manager: Arc<ChannelManager>,
     pub(crate) inbound_payments: Arc<Mutex<InboundPaymentInfoStorage>>,
-    pub(crate) keys_manager: Arc<KeysManager>,
+    pub(crate) keys_manager: Arc<PhantomKeysManager>,
     pub(crate) network_graph: Arc<NetworkGraph>,
     pub(crate) chain_monitor: Arc<ChainMonitor>,
     pub(crate) onion_messenger: Arc<OnionMessenger>,
     pub(crate) outbound_payments: Arc<Mutex<OutboundPaymentInfoStorage>>,
     pub(crate) peer_manager: Arc<PeerManager>,
-    pub(crate) fs_store: Arc<FilesystemStore>,
+    pub(crate) kv_store: Arc<NodeStore>,
     pub(crate) bump_tx_event_handler: Arc<BumpTxEventHandler>,
     pub(crate) maker_swaps: Arc<Mutex<SwapMap>>,
     pub(crate) taker_swaps: Arc<Mutex<SwapMap>>,
@@ -109,8 +238,59 @@ pub(crate) struct UnlockedAppState {
     pub(crate) router: Arc<Router>,
     pub(crate) output_sweeper: Arc<OutputSweeper>,
     pub(crate) rgb_send_lock: Arc<Mutex<bool>>,
+    pub(crate) rgb_spend_lock: Arc<TokioMutex<()>>,
     pub(crate) channel_ids_map: Arc<Mutex<ChannelIdsMap>>,
+    pub(crate) channel_events: Arc<Mutex<ChannelEventMap>>,
+    pub(crate) forwards: Arc<Mutex<ForwardMap>>,
+    pub(crate) hodl_invoices: Arc<Mutex<HodlInvoiceMap>>,
+    pub(crate) jit_channels: Arc<Mutex<JitChannelMap>>,
+    pub(crate) amount_bounds: Arc<Mutex<AmountBoundsMap>>,
+    pub(crate) fallback_addresses: Arc<Mutex<FallbackAddressMap>>,
+    pub(crate) asset_policies: Arc<Mutex<AssetPolicyMap>>,
+    pub(crate) lnurl_pay: Arc<Mutex<LnurlPayConfig>>,
+    pub(crate) node_announcement: Arc<Mutex<NodeAnnouncementConfig>>,
+    pub(crate) nwc_connections: Arc<Mutex<NwcConnectionMap>>,
+    pub(crate) token_spending: Arc<Mutex<TokenSpendingMap>>,
+    pub(crate) channel_acceptor: Arc<Mutex<ChannelAcceptorPolicy>>,
+    pub(crate) channel_depths: Arc<Mutex<ChannelDepthOverrides>>,
+    pub(crate) peer_filter: Arc<Mutex<PeerFilter>>,
+    pub(crate) sweep_config: Arc<Mutex<SweepConfig>>,
+    pub(crate) pending_channels: Arc<Mutex<PendingChannelMap>>,
+    pub(crate) offers: Arc<Mutex<OfferMap>>,
+    pub(crate) orders: Arc<Mutex<OrderMap>>,
+    pub(crate) escrows: Arc<Mutex<EscrowMap>>,
+    pub(crate) settlements: Arc<Mutex<SettlementMap>>,
+    pub(crate) submarine_swaps: Arc<Mutex<SubmarineSwapMap>>,
+    pub(crate) hedging_policies: Arc<Mutex<HedgingPolicyMap>>,
+    pub(crate) hedges: Arc<Mutex<HedgeMap>>,
+    pub(crate) issued_addresses: Arc<Mutex<IssuedAddressMap>>,
+    pub(crate) pending_broadcasts: Arc<Mutex<PendingBroadcastMap>>,
+    pub(crate) locked_utxos: Arc<Mutex<LockedUtxoMap>>,
+    pub(crate) anchor_reserve: Arc<Mutex<AnchorReserveMap>>,
+    pub(crate) chain_subscriptions: Arc<Mutex<ChainSubscriptionMap>>,
+    pub(crate) intercept_scopes: Arc<Mutex<InterceptScopeMap>>,
+    pub(crate) intercepted_htlcs: Arc<Mutex<InterceptedHtlcMap>>,
+    pub(crate) journal: Arc<Mutex<JournalMap>>,
+    pub(crate) gossip_handler: Arc<GossipHandler>,
+    pub(crate) lsps_handler: Arc<LspsMessageHandler>,
+    pub(crate) asset_htlc_min_handler: Arc<AssetHtlcMinHandler>,
+    pub(crate) peer_connected_at: Arc<Mutex<HashMap<PublicKey, u64>>>,
+    pub(crate) totp_verifier: Arc<TotpVerifier>,
+    pub(crate) hook_runner: Arc<HookRunner>,
+    pub(crate) probes: Arc<Mutex<ProbeMap>>,
+    pub(crate) probe_stats: Arc<Mutex<ProbeStatsMap>>,
+    pub(crate) force_close_feerates: Arc<Mutex<ForceCloseFeerateMap>>,
+    pub(crate) multisig_fundings: Arc<Mutex<MultisigFundingMap>>,
+    pub(crate) external_fundings: Arc<Mutex<ExternalFundingMap>>,
+    pub(crate) funding_coin_selections: Arc<Mutex<FundingCoinSelectionMap>>,
+    pub(crate) emergency_kit: Arc<Mutex<EmergencyKitState>>,
+    #[cfg(feature = "failure-injection")]
+    pub(crate) failure_injector: Arc<FailureInjector>,
+    pub(crate) bitcoind_client: Arc<BitcoindClient>,
+    pub(crate) announced_addresses: Vec<SocketAddress>,
     pub(crate) proxy_endpoint: String,
+    pub(crate) proxy_endpoints: Vec<String>,
+    pub(crate) ldk_seed_derivation_path: String,
 }
 
 impl UnlockedAppState {
@@ -133,6 +313,158 @@ impl UnlockedAppState {
     pub(crate) fn get_channel_ids_map(&self) -> MutexGuard<'_, ChannelIdsMap> {
         self.channel_ids_map.lock().unwrap()
     }
+
+    pub(crate) fn get_channel_events(&self) -> MutexGuard<'_, ChannelEventMap> {
+        self.channel_events.lock().unwrap()
+    }
+
+    pub(crate) fn get_forwards(&self) -> MutexGuard<'_, ForwardMap> {
+        self.forwards.lock().unwrap()
+    }
+
+    pub(crate) fn get_hodl_invoices(&self) -> MutexGuard<'_, HodlInvoiceMap> {
+        self.hodl_invoices.lock().unwrap()
+    }
+
+    pub(crate) fn get_jit_channels(&self) -> MutexGuard<'_, JitChannelMap> {
+        self.jit_channels.lock().unwrap()
+    }
+
+    pub(crate) fn get_amount_bounds(&self) -> MutexGuard<'_, AmountBoundsMap> {
+        self.amount_bounds.lock().unwrap()
+    }
+
+    pub(crate) fn get_fallback_addresses(&self) -> MutexGuard<'_, FallbackAddressMap> {
+        self.fallback_addresses.lock().unwrap()
+    }
+
+    pub(crate) fn get_asset_policies(&self) -> MutexGuard<'_, AssetPolicyMap> {
+        self.asset_policies.lock().unwrap()
+    }
+
+    pub(crate) fn get_lnurl_pay(&self) -> MutexGuard<'_, LnurlPayConfig> {
+        self.lnurl_pay.lock().unwrap()
+    }
+
+    pub(crate) fn get_node_announcement(&self) -> MutexGuard<'_, NodeAnnouncementConfig> {
+        self.node_announcement.lock().unwrap()
+    }
+
+    pub(crate) fn get_nwc_connections(&self) -> MutexGuard<'_, NwcConnectionMap> {
+        self.nwc_connections.lock().unwrap()
+    }
+
+    pub(crate) fn get_token_spending(&self) -> MutexGuard<'_, TokenSpendingMap> {
+        self.token_spending.lock().unwrap()
+    }
+
+    pub(crate) fn get_channel_acceptor(&self) -> MutexGuard<'_, ChannelAcceptorPolicy> {
+        self.channel_acceptor.lock().unwrap()
+    }
+
+    pub(crate) fn get_channel_depths(&self) -> MutexGuard<'_, ChannelDepthOverrides> {
+        self.channel_depths.lock().unwrap()
+    }
+
+    pub(crate) fn get_peer_filter(&self) -> MutexGuard<'_, PeerFilter> {
+        self.peer_filter.lock().unwrap()
+    }
+
+    pub(crate) fn get_sweep_config(&self) -> MutexGuard<'_, SweepConfig> {
+        self.sweep_config.lock().unwrap()
+    }
+
+    pub(crate) fn get_pending_channels(&self) -> MutexGuard<'_, PendingChannelMap> {
+        self.pending_channels.lock().unwrap()
+    }
+
+    pub(crate) fn get_offers(&self) -> MutexGuard<'_, OfferMap> {
+        self.offers.lock().unwrap()
+    }
+
+    pub(crate) fn get_orders(&self) -> MutexGuard<'_, OrderMap> {
+        self.orders.lock().unwrap()
+    }
+
+    pub(crate) fn get_escrows(&self) -> MutexGuard<'_, EscrowMap> {
+        self.escrows.lock().unwrap()
+    }
+
+    pub(crate) fn get_settlements(&self) -> MutexGuard<'_, SettlementMap> {
+        self.settlements.lock().unwrap()
+    }
+
+    pub(crate) fn get_submarine_swaps(&self) -> MutexGuard<'_, SubmarineSwapMap> {
+        self.submarine_swaps.lock().unwrap()
+    }
+
+    pub(crate) fn get_hedging_policies(&self) -> MutexGuard<'_, HedgingPolicyMap> {
+        self.hedging_policies.lock().unwrap()
+    }
+
+    pub(crate) fn get_hedges(&self) -> MutexGuard<'_, HedgeMap> {
+        self.hedges.lock().unwrap()
+    }
+
+    pub(crate) fn get_issued_addresses(&self) -> MutexGuard<'_, IssuedAddressMap> {
+        self.issued_addresses.lock().unwrap()
+    }
+
+    pub(crate) fn get_pending_broadcasts(&self) -> MutexGuard<'_, PendingBroadcastMap> {
+        self.pending_broadcasts.lock().unwrap()
+    }
+
+    pub(crate) fn get_locked_utxos(&self) -> MutexGuard<'_, LockedUtxoMap> {
+        self.locked_utxos.lock().unwrap()
+    }
+
+    pub(crate) fn get_anchor_reserve(&self) -> MutexGuard<'_, AnchorReserveMap> {
+        self.anchor_reserve.lock().unwrap()
+    }
+
+    pub(crate) fn get_chain_subscriptions(&self) -> MutexGuard<'_, ChainSubscriptionMap> {
+        self.chain_subscriptions.lock().unwrap()
+    }
+
+    pub(crate) fn get_intercept_scopes(&self) -> MutexGuard<'_, InterceptScopeMap> {
+        self.intercept_scopes.lock().unwrap()
+    }
+
+    pub(crate) fn get_intercepted_htlcs(&self) -> MutexGuard<'_, InterceptedHtlcMap> {
+        self.intercepted_htlcs.lock().unwrap()
+    }
+
+    pub(crate) fn get_journal(&self) -> MutexGuard<'_, JournalMap> {
+        self.journal.lock().unwrap()
+    }
+
+    pub(crate) fn get_probes(&self) -> MutexGuard<'_, ProbeMap> {
+        self.probes.lock().unwrap()
+    }
+
+    pub(crate) fn get_probe_stats(&self) -> MutexGuard<'_, ProbeStatsMap> {
+        self.probe_stats.lock().unwrap()
+    }
+
+    pub(crate) fn get_force_close_feerates(&self) -> MutexGuard<'_, ForceCloseFeerateMap> {
+        self.force_close_feerates.lock().unwrap()
+    }
+
+    pub(crate) fn get_multisig_fundings(&self) -> MutexGuard<'_, MultisigFundingMap> {
+        self.multisig_fundings.lock().unwrap()
+    }
+
+    pub(crate) fn get_external_fundings(&self) -> MutexGuard<'_, ExternalFundingMap> {
+        self.external_fundings.lock().unwrap()
+    }
+
+    pub(crate) fn get_funding_coin_selections(&self) -> MutexGuard<'_, FundingCoinSelectionMap> {
+        self.funding_coin_selections.lock().unwrap()
+    }
+
+    pub(crate) fn get_emergency_kit(&self) -> MutexGuard<'_, EmergencyKitState> {
+        self.emergency_kit.lock().unwrap()
+    }
 }
 
 #[derive(Debug)]
@@ -178,16 +510,53 @@ pub(crate) fn check_password_validity(
 ) -> Result<Mnemonic, APIError> {
     let mnemonic_path = get_mnemonic_path(storage_dir_path);
     if let Ok(encrypted_mnemonic) = fs::read_to_string(mnemonic_path) {
-        let mcrypt = new_magic_crypt!(password, 256);
-        let mnemonic_str = mcrypt
-            .decrypt_base64_to_string(encrypted_mnemonic)
-            .map_err(|_| APIError::WrongPassword)?;
-        Ok(Mnemonic::from_str(&mnemonic_str).expect("valid mnemonic"))
+        decrypt_mnemonic(password, encrypted_mnemonic)
     } else {
         Err(APIError::NotInitialized)
     }
 }
 
+pub(crate) fn check_view_password_validity(
+    view_password: &str,
+    storage_dir_path: &Path,
+) -> Result<Mnemonic, APIError> {
+    if !get_mnemonic_path(storage_dir_path).exists() {
+        return Err(APIError::NotInitialized);
+    }
+    let view_mnemonic_path = get_view_mnemonic_path(storage_dir_path);
+    if let Ok(encrypted_mnemonic) = fs::read_to_string(view_mnemonic_path) {
+        decrypt_mnemonic(view_password, encrypted_mnemonic)
+    } else {
+        Err(APIError::NoViewPassword)
+    }
+}
+
+fn decrypt_mnemonic(password: &str, encrypted_mnemonic: String) -> Result<Mnemonic, APIError> {
+    let mcrypt = new_magic_crypt!(password, 256);
+    let mnemonic_str = Zeroizing::new(
+        mcrypt
+            .decrypt_base64_to_string(encrypted_mnemonic)
+            .map_err(|_| APIError::WrongPassword)?,
+    );
+    Ok(Mnemonic::from_str(&mnemonic_str).expect("valid mnemonic"))
+}
+
+pub(crate) fn check_payment_hash(payment_hash_str: &str) -> Result<PaymentHash, APIError> {
+    match hex_str_to_vec(payment_hash_str) {
+        Some(bytes) if bytes.len() == 32 => Ok(PaymentHash(bytes.try_into().unwrap())),
+        _ => Err(APIError::InvalidPaymentHash(payment_hash_str.to_string())),
+    }
+}
+
+pub(crate) fn check_payment_preimage(
+    payment_preimage_str: &str,
+) -> Result<PaymentPreimage, APIError> {
+    match hex_str_to_vec(payment_preimage_str) {
+        Some(bytes) if bytes.len() == 32 => Ok(PaymentPreimage(bytes.try_into().unwrap())),
+        _ => Err(APIError::InvalidPaymentPreimage),
+    }
+}
+
 pub(crate) fn check_channel_id(channel_id_str: &str) -> Result<ChannelId, APIError> {
     if let Some(channel_id_bytes) = hex_str_to_vec(channel_id_str) {
         if channel_id_bytes.len() != 32 {
@@ -210,6 +579,12 @@ pub(crate) fn get_mnemonic_path(storage_dir_path: &Path) -> PathBuf {
     storage_dir_path.join("mnemonic")
 }
 
+/// Path of the mnemonic encrypted with the view password, which unlocks the node in watch-only
+/// mode
+pub(crate) fn get_view_mnemonic_path(storage_dir_path: &Path) -> PathBuf {
+    storage_dir_path.join("view_mnemonic")
+}
+
 pub(crate) fn encrypt_and_save_mnemonic(
     password: String,
     mnemonic: String,
@@ -229,41 +604,211 @@ pub(crate) fn encrypt_and_save_mnemonic(
     }
 }
 
+/// Path of the derivation of the LDK keys set on init, encrypted with the mnemonic, which is
+/// missing when the default one is used
+pub(crate) fn get_ldk_key_derivation_path(storage_dir_path: &Path) -> PathBuf {
+    storage_dir_path.join("ldk_key_derivation")
+}
+
+/// BIP39 passphrase and account index from which the LDK keys are derived
+#[derive(Default, PartialEq)]
+pub(crate) struct LdkKeyDerivation {
+    pub(crate) passphrase: Zeroizing<String>,
+    pub(crate) account_index: u32,
+}
+
+impl LdkKeyDerivation {
+    /// Path of the LDK seed from the master key, with the default account keeping the path of the
+    /// nodes created before accounts were supported
+    pub(crate) fn seed_derivation_path(&self) -> String {
+        match self.account_index {
+            0 => s!(LDK_SEED_DERIVATION_PATH),
+            account_index => format!("{LDK_SEED_DERIVATION_PATH}/{account_index}'"),
+        }
+    }
+}
+
+pub(crate) fn save_ldk_key_derivation(
+    storage_dir_path: &Path,
+    mnemonic: &str,
+    ldk_key_derivation: &LdkKeyDerivation,
+) -> Result<(), APIError> {
+    let ldk_key_derivation_path = get_ldk_key_derivation_path(storage_dir_path);
+    if *ldk_key_derivation == LdkKeyDerivation::default() {
+        if ldk_key_derivation_path.exists() {
+            fs::remove_file(ldk_key_derivation_path)?;
+        }
+        return Ok(());
+    }
+    let mcrypt = new_magic_crypt!(mnemonic, 256);
+    let contents = Zeroizing::new(format!(
+        "{}\n{}",
+        ldk_key_derivation.account_index, *ldk_key_derivation.passphrase
+    ));
+    fs::write(
+        ldk_key_derivation_path,
+        mcrypt.encrypt_str_to_base64(contents.as_str()),
+    )?;
+    Ok(())
+}
+
+pub(crate) fn read_ldk_key_derivation(
+    storage_dir_path: &Path,
+    mnemonic: &Mnemonic,
+) -> Result<LdkKeyDerivation, APIError> {
+    let ldk_key_derivation_path = get_ldk_key_derivation_path(storage_dir_path);
+    if !ldk_key_derivation_path.exists() {
+        return Ok(LdkKeyDerivation::default());
+    }
+    let mcrypt = new_magic_crypt!(Zeroizing::new(mnemonic.to_string()).as_str(), 256);
+    let contents = Zeroizing::new(
+        mcrypt
+            .decrypt_base64_to_string(fs::read_to_string(ldk_key_derivation_path)?)
+            .map_err(|e| {
+                APIError::Unexpected(format!("Failed to decrypt the LDK key derivation: {e}"))
+            })?,
+    );
+    let (account_index, passphrase) = contents.split_once('\n').expect("valid LDK key derivation");
+    Ok(LdkKeyDerivation {
+        passphrase: Zeroizing::new(passphrase.to_string()),
+        account_index: account_index.parse().expect("valid LDK account index"),
+    })
+}
+
 pub(crate) async fn connect_peer_if_necessary(
     pubkey: PublicKey,
-    address: SocketAddr,
+    address: &SocketAddress,
+    peer_manager: Arc<PeerManager>,
+    tor_proxy: Option<&TorProxy>,
+    circuit_group: CircuitGroup,
+) -> Result<(), APIError> {
+    connect_peer_with_retries(
+        pubkey,
+        address,
+        peer_manager,
+        tor_proxy,
+        circuit_group,
+        PEER_CONNECTION_TIMEOUT,
+        0,
+    )
+    .await
+}
+
+/// Connect to the peer unless already connected, trying again up to retries times when an
+/// attempt fails
+pub(crate) async fn connect_peer_with_retries(
+    pubkey: PublicKey,
+    address: &SocketAddress,
     peer_manager: Arc<PeerManager>,
+    tor_proxy: Option<&TorProxy>,
+    circuit_group: CircuitGroup,
+    timeout: Duration,
+    retries: u8,
 ) -> Result<(), APIError> {
     for peer_details in peer_manager.list_peers() {
         if peer_details.counterparty_node_id == pubkey {
             return Ok(());
         }
     }
-    do_connect_peer(pubkey, address, peer_manager).await?;
+    let mut attempt = 0;
+    loop {
+        match do_connect_peer(
+            pubkey,
+            address,
+            Arc::clone(&peer_manager),
+            tor_proxy,
+            circuit_group,
+            timeout,
+        )
+        .await
+        {
+            Ok(()) => break,
+            Err(e) if attempt < retries => {
+                attempt += 1;
+                tracing::info!(
+                    "retrying connection to peer {pubkey} ({attempt}/{retries}) after error: {e}"
+                );
+                tokio::time::sleep(PEER_CONNECTION_RETRY_DELAY).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
     tracing::info!("connected to peer (pubkey: {pubkey}, addr: {address})");
     Ok(())
 }
 
+/// Resolve the peer address to connect to it directly, as onion addresses can only be reached
+/// through the SOCKS proxy
+async fn resolve_peer_address(address: &SocketAddress) -> Result<Vec<SocketAddr>, APIError> {
+    if matches!(
+        address,
+        SocketAddress::OnionV2(_) | SocketAddress::OnionV3 { .. }
+    ) {
+        return Err(APIError::PeerAddressUnresolved(s!(
+            "onion addresses can only be reached through a SOCKS proxy (--tor-socks-addr)"
+        )));
+    }
+    let socket_addrs = tokio::net::lookup_host(address.to_string())
+        .await
+        .map_err(|e| APIError::PeerAddressUnresolved(e.to_string()))?
+        .collect::<Vec<_>>();
+    if socket_addrs.is_empty() {
+        return Err(APIError::PeerAddressUnresolved(format!(
+            "no IP address found for {address}"
+        )));
+    }
+    Ok(socket_addrs)
+}
+
+/// Open a TCP connection to the peer (through the SOCKS proxy, if one is configured, on the
+/// circuits of the group) and wait for the handshake to complete, giving up on each of the two
+/// steps after the timeout
 pub(crate) async fn do_connect_peer(
     pubkey: PublicKey,
-    address: SocketAddr,
+    address: &SocketAddress,
     peer_manager: Arc<PeerManager>,
+    tor_proxy: Option<&TorProxy>,
+    circuit_group: CircuitGroup,
+    timeout: Duration,
 ) -> Result<(), APIError> {
-    match lightning_net_tokio::connect_outbound(Arc::clone(&peer_manager), pubkey, address).await {
-        Some(connection_closed_future) => {
-            let mut connection_closed_future = Box::pin(connection_closed_future);
-            loop {
-                tokio::select! {
-                    _ = &mut connection_closed_future => return Err(APIError::FailedPeerConnection),
-                    _ = tokio::time::sleep(Duration::from_millis(10)) => {},
-                };
-                if peer_manager.peer_by_node_id(&pubkey).is_some() {
-                    return Ok(());
-                }
+    // the proxy resolves the host itself, so that no DNS query leaves the node in clear
+    let socket_addrs = match tor_proxy {
+        Some(_) => vec![],
+        None => resolve_peer_address(address).await?,
+    };
+    let connect = async {
+        match tor_proxy {
+            Some(proxy) => connect_via_socks(proxy, address, circuit_group).await,
+            None => tokio::net::TcpStream::connect(&socket_addrs[..]).await,
+        }
+    };
+    let stream = match tokio::time::timeout(timeout, connect).await {
+        Ok(Ok(stream)) => stream,
+        Ok(Err(e)) => return Err(APIError::PeerUnreachable(e.to_string())),
+        Err(_) => return Err(APIError::PeerConnectionTimeout(timeout.as_secs())),
+    };
+    let stream = stream
+        .into_std()
+        .map_err(|e| APIError::PeerUnreachable(e.to_string()))?;
+    let mut connection_closed_future = Box::pin(lightning_net_tokio::setup_outbound(
+        Arc::clone(&peer_manager),
+        pubkey,
+        stream,
+    ));
+    let handshake = async {
+        loop {
+            tokio::select! {
+                _ = &mut connection_closed_future => return Err(APIError::PeerConnectionRejected),
+                _ = tokio::time::sleep(Duration::from_millis(10)) => {},
+            };
+            if peer_manager.peer_by_node_id(&pubkey).is_some() {
+                return Ok(());
             }
         }
-        None => Err(APIError::FailedPeerConnection),
-    }
+    };
+    tokio::time::timeout(timeout, handshake)
+        .await
+        .map_err(|_| APIError::PeerHandshakeTimeout(timeout.as_secs()))?
 }
 
 #[inline]
@@ -304,6 +849,18 @@ pub(crate) fn hex_str_to_vec(hex: &str) -> Option<Vec<u8>> {
     Some(out)
 }
 
+/// Node announcements carry the alias in a 32-byte field
+pub(crate) fn check_announce_alias(alias: &str) -> Result<(), String> {
+    if alias.len() > 32 {
+        return Err(s!("cannot be longer than 32 bytes"));
+    }
+    Ok(())
+}
+
+pub(crate) fn parse_announce_color(color: &str) -> Option<[u8; 3]> {
+    hex_str_to_vec(color.trim_start_matches('#')).and_then(|v| v.try_into().ok())
+}
+
 pub(crate) async fn no_cancel<Fut>(fut: Fut) -> Fut::Output
 where
     Fut: 'static + Future + Send,
@@ -317,20 +874,23 @@ where
     rx.await.unwrap()
 }
 
+/// Parse pubkey[@host:port], leaving the host unresolved until the connection is made (by the
+/// SOCKS proxy, when one is configured)
 pub(crate) fn parse_peer_info(
     peer_pubkey_and_ip_addr: String,
-) -> Result<(PublicKey, Option<SocketAddr>), APIError> {
+) -> Result<(PublicKey, Option<SocketAddress>), APIError> {
     let mut pubkey_and_addr = peer_pubkey_and_ip_addr.split('@');
     let pubkey = pubkey_and_addr.next();
 
     let peer_addr = if let Some(peer_addr_str) = pubkey_and_addr.next() {
-        let peer_addr = peer_addr_str.to_socket_addrs().map(|mut r| r.next());
-        if peer_addr.is_err() || peer_addr.as_ref().unwrap().is_none() {
-            return Err(APIError::InvalidPeerInfo(s!(
-                "couldn't parse pubkey@host:port into a socket address"
-            )));
+        match SocketAddress::from_str(peer_addr_str) {
+            Ok(peer_addr) => Some(peer_addr),
+            Err(_) => {
+                return Err(APIError::InvalidPeerInfo(s!(
+                    "couldn't parse pubkey@host:port into a peer address"
+                )))
+            }
         }
-        peer_addr.unwrap()
     } else {
         None
     };
@@ -346,6 +906,15 @@ pub(crate) fn parse_peer_info(
 }
 
 pub(crate) async fn start_daemon(args: &UserArgs) -> Result<Arc<AppState>, AppError> {
+    // The network is selected when the node gets initialized and cannot change afterwards
+    let network_path = args.storage_dir_path.join(BITCOIN_NETWORK_FNAME);
+    if network_path.exists() {
+        let stored_network = fs::read_to_string(&network_path)?;
+        if BitcoinNetwork::from_str(stored_network.trim()).ok() != Some(args.network) {
+            return Err(AppError::NetworkMismatch(stored_network, args.network));
+        }
+    }
+
     // Initialize the Logger (creates ldk_data_dir and its logs directory)
     let ldk_data_dir = args.storage_dir_path.join(LDK_DIR);
     let logger = Arc::new(FilesystemLogger::new(ldk_data_dir.clone()));
@@ -359,20 +928,98 @@ pub(crate) async fn start_daemon(args: &UserArgs) -> Result<Arc<AppState>, AppEr
         ldk_data_dir,
         logger,
         max_media_upload_size_mb: args.max_media_upload_size_mb,
+        watch_only: args.watch_only,
+        cltv_expiry_delta: args.cltv_expiry_delta,
+        max_total_cltv_expiry_delta: args.max_total_cltv_expiry_delta,
+        min_final_cltv_expiry_delta: args.min_final_cltv_expiry_delta,
+        address_gap_limit: args.address_gap_limit,
+        anchor_reserve_utxos: args.anchor_reserve_utxos,
+        anchor_reserve_utxo_size_sat: args.anchor_reserve_utxo_size_sat,
+        min_channel_depth: args.min_channel_depth,
+        asset_htlc_min_msat: args.asset_htlc_min_msat,
+        utxo_pool_target: args.utxo_pool_target,
+        utxo_pool_min: args.utxo_pool_min,
+        utxo_pool_utxo_size_sat: args.utxo_pool_utxo_size_sat,
+        compact_storage_on_startup: args.compact_storage_on_startup,
+        hook_timeout_sec: args.hook_timeout_sec,
+        hook_max_concurrency: args.hook_max_concurrency,
+        auto_backup_target: args.auto_backup_target.clone(),
+        auto_backup_interval_sec: args.auto_backup_interval_sec,
+        auto_backup_keep: args.auto_backup_keep,
+        auto_backup_full_every: args.auto_backup_full_every,
+        shutdown_drain_timeout_sec: args.shutdown_drain_timeout_sec,
+        swap_provider_url: args.swap_provider_url.clone(),
+        storage_backend: args.storage_backend,
+        remote_storage_url: args.remote_storage_url.clone(),
+        encrypt_storage: args.encrypt_storage,
+        phantom_secret: args.phantom_secret,
+        announce_alias: args.announce_alias.clone(),
+        announce_color: args.announce_color,
+        url_prefix: args.url_prefix.clone(),
+        trust_forwarded_for: args.trust_forwarded_for,
+        tor_proxy: args.tor_proxy.clone(),
+        rgs_url: args.rgs_url.clone(),
     });
 
+    let runtime_config = RuntimeConfig {
+        gossip_bandwidth_cap: args.gossip_bandwidth_cap,
+        hook_command: args.hook_command.clone(),
+        htlc_risk_threshold_sat: args.htlc_risk_threshold_sat,
+        invoice_retention_sec: args.invoice_retention_sec,
+        log_level: args.log_level.clone(),
+        max_invoices_per_minute: args.max_invoices_per_minute,
+        max_requests_per_minute: args.max_requests_per_minute,
+        max_route_hints: args.max_route_hints,
+        max_token_invoices_per_minute: args.max_token_invoices_per_minute,
+        max_token_unpaid_invoices: args.max_token_unpaid_invoices,
+        max_unpaid_invoices: args.max_unpaid_invoices,
+        payment_retention_sec: args.payment_retention_sec,
+    };
+
+    // Publish the hook events on the configured message buses
+    let notification_publisher =
+        if args.notify_zmq_endpoint.is_some() || args.notify_mqtt_url.is_some() {
+            Some(Arc::new(
+                NotificationPublisher::start(
+                    args.notify_zmq_endpoint.as_deref(),
+                    args.notify_mqtt_url.as_deref(),
+                    &args.notify_mqtt_topic,
+                )
+                .await?,
+            ))
+        } else {
+            None
+        };
+
+    let tor_connection_manager = Arc::new(TorConnectionManager::new(args.onion_service.clone())?);
+
     let app_state = Arc::new(AppState {
         static_state,
         cancel_token,
-        unlocked_app_state: Arc::new(TokioMutex::new(None)),
+        unlocked_app_state: Arc::new(TokioRwLock::new(None)),
         ldk_background_services: Arc::new(Mutex::new(None)),
         changing_state: Mutex::new(false),
-        root_public_key: args.root_public_key,
+        draining: Mutex::new(false),
+        root_public_key: Mutex::new(args.root_public_key),
+        generate_root_key: args.generate_root_key,
         revoked_tokens: Arc::new(Mutex::new(HashSet::new())),
+        runtime_config: Mutex::new(runtime_config),
+        log_level_handle: args.log_level_handle.clone(),
+        sync_progress: Mutex::new(SyncProgress::default()),
+        request_windows: Mutex::new(HashMap::new()),
+        invoice_quotas: Mutex::new(InvoiceQuotas::default()),
+        notification_publisher,
+        tor_connection_manager,
     });
 
+    app_state.load_config()?;
+
+    if app_state.generate_root_key {
+        app_state.load_root_public_key()?;
+    }
+
     // Load revoked tokens from file if authentication is enabled
-    if app_state.root_public_key.is_some() {
+    if app_state.get_root_public_key().is_some() || app_state.generate_root_key {
         let loaded_tokens = app_state.load_revoked_tokens()?;
         *app_state.revoked_tokens.lock().unwrap() = loaded_tokens;
     }
@@ -406,6 +1053,41 @@ pub(crate) fn get_max_local_rgb_amount<'r>(
     max_balance
 }
 
+pub(crate) fn get_network_params(network: BitcoinNetwork) -> NetworkParams {
+    match network {
+        BitcoinNetwork::Mainnet => NetworkParams {
+            bitcoind_chain: "main",
+            bitcoind_rpc_port: 8332,
+            indexer_url: ELECTRUM_URL_MAINNET,
+            proxy_endpoint: PROXY_ENDPOINT_PUBLIC,
+        },
+        BitcoinNetwork::Testnet => NetworkParams {
+            bitcoind_chain: "test",
+            bitcoind_rpc_port: 18332,
+            indexer_url: ELECTRUM_URL_TESTNET,
+            proxy_endpoint: PROXY_ENDPOINT_PUBLIC,
+        },
+        BitcoinNetwork::Testnet4 => NetworkParams {
+            bitcoind_chain: "testnet4",
+            bitcoind_rpc_port: 48332,
+            indexer_url: ELECTRUM_URL_TESTNET4,
+            proxy_endpoint: PROXY_ENDPOINT_PUBLIC,
+        },
+        BitcoinNetwork::Signet => NetworkParams {
+            bitcoind_chain: "signet",
+            bitcoind_rpc_port: 38332,
+            indexer_url: ELECTRUM_URL_SIGNET,
+            proxy_endpoint: PROXY_ENDPOINT_PUBLIC,
+        },
+        BitcoinNetwork::Regtest => NetworkParams {
+            bitcoind_chain: "regtest",
+            bitcoind_rpc_port: 18443,
+            indexer_url: ELECTRUM_URL_REGTEST,
+            proxy_endpoint: PROXY_ENDPOINT_LOCAL,
+        },
+    }
+}
+
 pub(crate) fn get_route(
     channel_manager: &crate::ldk::ChannelManager,
     router: &crate::ldk::Router,
@@ -414,6 +1096,7 @@ pub(crate) fn get_route(
     final_value_msat: Option<u64>,
     rgb_payment: Option<(ContractId, u64)>,
     hints: Vec<RouteHint>,
+    max_total_cltv_expiry_delta: u32,
 ) -> Option<Route> {
     let inflight_htlcs = channel_manager.compute_inflight_htlcs();
     let payment_params = PaymentParameters {
@@ -424,7 +1107,7 @@ pub(crate) fn get_route(
             final_cltv_expiry_delta: DEFAULT_FINAL_CLTV_EXPIRY_DELTA,
         },
         expiry_time: None,
-        max_total_cltv_expiry_delta: DEFAULT_MAX_TOTAL_CLTV_EXPIRY_DELTA,
+        max_total_cltv_expiry_delta,
         max_path_count: 1,
         max_path_length: MAX_PATH_LENGTH_ESTIMATE,
         max_channel_saturation_power_of_half: 2,